@@ -9,6 +9,7 @@ import type {
   ModelsQuery,
   GroupsQuery,
   UsersQuery,
+  UserDeletionReport,
   UserCreateRequest,
   GroupCreateRequest,
   ApiKeyCreateRequest,
@@ -22,6 +23,7 @@ import type {
   EndpointSyncResponse,
   ConfigResponse,
   ListRequestsResponse,
+  RequestDetailResponse,
   ListRequestsQuery,
   RequestsAggregateResponse,
   ModelUserUsageResponse,
@@ -46,6 +48,15 @@ const userApi = {
   async list(options?: UsersQuery): Promise<User[]> {
     const params = new URLSearchParams();
     if (options?.include) params.set("include", options.include);
+    if (options?.q) params.set("q", options.q);
+    if (options?.role) params.set("role", options.role);
+    if (options?.group_id) params.set("group_id", options.group_id);
+    if (options?.is_admin !== undefined)
+      params.set("is_admin", options.is_admin.toString());
+    if (options?.attributes) params.set("attributes", options.attributes);
+    if (options?.sort) params.set("sort", options.sort);
+    if (options?.order) params.set("order", options.order);
+    if (options?.cursor) params.set("cursor", options.cursor);
 
     const url = `/admin/api/v1/users${params.toString() ? "?" + params.toString() : ""}`;
     const response = await fetch(url);
@@ -96,6 +107,17 @@ const userApi = {
     }
   },
 
+  // Deletes the user and erases their personal data (logged requests, analytics user details)
+  async purge(id: string): Promise<UserDeletionReport> {
+    const response = await fetch(`/admin/api/v1/users/${id}?purge=true`, {
+      method: "DELETE",
+    });
+    if (!response.ok) {
+      throw new Error(`Failed to purge user: ${response.status}`);
+    }
+    return response.json();
+  },
+
   // Nested API keys under users
   apiKeys: {
     async getAll(userId: string = "current"): Promise<ApiKey[]> {
@@ -152,6 +174,13 @@ const modelApi = {
     if (options?.include) params.set("include", options.include);
     if (options?.accessible !== undefined)
       params.set("accessible", options.accessible.toString());
+    if (options?.skip !== undefined)
+      params.set("skip", options.skip.toString());
+    if (options?.limit !== undefined)
+      params.set("limit", options.limit.toString());
+    if (options?.sort) params.set("sort", options.sort);
+    if (options?.order) params.set("order", options.order);
+    if (options?.cursor) params.set("cursor", options.cursor);
 
     const url = `/admin/api/v1/models${params.toString() ? "?" + params.toString() : ""}`;
     const response = await fetch(url);
@@ -380,6 +409,9 @@ const groupApi = {
   async list(options?: GroupsQuery): Promise<Group[]> {
     const params = new URLSearchParams();
     if (options?.include) params.set("include", options.include);
+    if (options?.sort) params.set("sort", options.sort);
+    if (options?.order) params.set("order", options.order);
+    if (options?.cursor) params.set("cursor", options.cursor);
 
     const url = `/admin/api/v1/groups${params.toString() ? "?" + params.toString() : ""}`;
     const response = await fetch(url);
@@ -515,6 +547,7 @@ const requestsApi = {
       params.set("timestamp_before", options.timestamp_before);
     if (options?.order_desc !== undefined)
       params.set("order_desc", options.order_desc.toString());
+    if (options?.cursor) params.set("cursor", options.cursor);
 
     const url = `/admin/api/v1/requests${params.toString() ? "?" + params.toString() : ""}`;
     const response = await fetch(url);
@@ -524,6 +557,14 @@ const requestsApi = {
     return response.json();
   },
 
+  async get(id: number): Promise<RequestDetailResponse> {
+    const response = await fetch(`/admin/api/v1/requests/${id}`);
+    if (!response.ok) {
+      throw new Error(`Failed to fetch request: ${response.status}`);
+    }
+    return response.json();
+  },
+
   async aggregate(
     model?: string,
     timestampAfter?: string,
@@ -677,6 +677,7 @@ export const handlers = [
       models_reactivated: 1,
       models_deactivated: 0,
       models_deleted: 1,
+      queued_models: [],
       total_models_fetched: 5,
       filtered_models_count: 5,
       synced_at: new Date().toISOString(),
@@ -732,6 +733,7 @@ export const handlers = [
       created_at: new Date().toISOString(),
       updated_at: new Date().toISOString(),
       source: "native",
+      priority: "normal",
     };
     return HttpResponse.json(newGroup, { status: 201 });
   }),
@@ -3,6 +3,7 @@ import { z } from "zod";
 export type ModelType = "CHAT" | "EMBEDDINGS" | "RERANKER";
 export type AuthSource = "vouch" | "native" | "system" | "proxy-header";
 export type Role = "PlatformManager" | "RequestViewer" | "StandardUser";
+export type PriorityTier = "high" | "normal" | "low";
 
 // Config/Metadata types
 export interface ConfigResponse {
@@ -36,6 +37,21 @@ export interface ModelProbeStatus {
   uptime_percentage?: number; // Last 24h uptime
 }
 
+// Health derived from recent probe results
+export type HealthStatus = "healthy" | "degraded" | "down";
+
+export interface Health {
+  status: HealthStatus;
+  last_checked: string; // ISO 8601 timestamp
+}
+
+export interface ModelDeprecation {
+  deprecated_at: string; // ISO 8601 timestamp
+  sunset_at?: string | null; // requests are refused after this, null if not scheduled
+  replacement_deployment_id?: string | null;
+  replacement_alias?: string | null; // alias clients should move to
+}
+
 // Base model types
 export interface Model {
   id: string;
@@ -47,9 +63,15 @@ export interface Model {
   hosted_on: string; // endpoint ID (UUID)
   requests_per_second?: number | null; // Global rate limiting: requests per second
   burst_size?: number | null; // Global rate limiting: burst capacity
+  max_context_tokens?: number | null; // null if unknown
+  supports_tools?: boolean | null; // null if unknown
+  supports_vision?: boolean | null; // null if unknown
+  tags?: string[]; // admin-managed labels, e.g. "vision" or "eu-hosted"
   groups?: Group[]; // array of group IDs - only present when include=groups
   metrics?: ModelMetrics; // only present when include=metrics
   status?: ModelProbeStatus; // only present when include=status
+  health?: Health | null; // null if the model hasn't been probed
+  deprecation?: ModelDeprecation; // only present when the model is deprecated
 }
 
 export interface Endpoint {
@@ -62,8 +84,18 @@ export interface Endpoint {
   updated_at: string; // ISO 8601 timestamp
   requires_api_key: boolean; // Whether this endpoint requires an API key
   model_filter?: string[] | null; // Optional list of models to sync
+  health?: Health | null; // combined health of its models, null if none have been probed
+  tls_ca_pem?: string | null; // PEM bundle of CA certificates trusted in addition to the built-in roots
+  tls_skip_verify?: boolean; // Accept any certificate. Only meant for lab environments
+  tls_min_version?: TlsVersion | null;
+  sync_interval_seconds?: number | null; // null if the endpoint only syncs on demand
+  last_sync_at?: string | null; // ISO 8601 timestamp of the last sync, automatic or on demand
+  last_sync_error?: string | null; // why the last sync failed, if it did
+  enabled?: boolean; // false while the endpoint's models are removed from routing
 }
 
+export type TlsVersion = "1.2" | "1.3";
+
 export interface EndpointSyncResponse {
   endpoint_id: string; // UUID
   changes_made: number;
@@ -86,6 +118,9 @@ export interface Group {
   users?: User[]; // List of IDs, only present when include contains 'users'
   models?: Model[]; // List of IDs, only present when include contains 'models'
   source: string;
+  priority: PriorityTier; // Admission priority of members' AI requests when saturated
+  parent_id?: string | null; // Group whose members and models this group inherits
+  is_default?: boolean; // Newly provisioned users join this group automatically
 }
 
 export interface User {
@@ -100,6 +135,21 @@ export interface User {
   created_at: string; // ISO 8601 timestamp
   updated_at: string; // ISO 8601 timestamp
   auth_source: AuthSource;
+  default_model_id?: string | null; // Model offered to the user's clients by default
+  attributes?: Record<string, string>; // Custom key/value metadata, e.g. team or cost center
+}
+
+// Returned when a user is deleted with purge=true
+export interface UserDeletionReport {
+  user_id: string;
+  api_keys_deleted: number;
+  requests_deleted: number;
+  responses_deleted: number;
+  analytics_anonymized: number; // Usage rows kept, with the user's details cleared
+  stored_bodies_deleted: number;
+  stored_bodies_remaining: string[]; // Object storage keys that still need removing
+  archived_requests_deleted: number; // Removed from request log exports
+  archives_remaining: string[]; // Request log exports that still need redacting
 }
 
 export interface ApiKey {
@@ -135,27 +185,48 @@ export type GroupsInclude = "users" | "models" | "users,models";
 export type UsersInclude = "groups";
 
 // List endpoint query parameters
+export type SortOrder = "asc" | "desc";
+
 export interface ModelsQuery {
   endpoint?: string;
   include?: ModelsInclude;
   accessible?: boolean; // Filter to only models the current user can access
+  sort?: "alias" | "model_name" | "created_at" | "updated_at";
+  order?: SortOrder;
+  skip?: number;
+  limit?: number;
+  cursor?: string; // From the x-next-cursor header of the previous page
 }
 
 export interface EndpointsQuery {
   skip?: number;
   limit?: number;
+  sort?: "name" | "url" | "created_at" | "updated_at";
+  order?: SortOrder;
+  cursor?: string; // From the x-next-cursor header of the previous page
 }
 
 export interface GroupsQuery {
   skip?: number;
   limit?: number;
   include?: GroupsInclude;
+  sort?: "name" | "created_at" | "updated_at";
+  order?: SortOrder;
+  cursor?: string; // From the x-next-cursor header of the previous page
 }
 
 export interface UsersQuery {
   skip?: number;
   limit?: number;
   include?: UsersInclude;
+  q?: string; // Case-insensitive search of email, username and display name
+  role?: Role;
+  group_id?: string;
+  is_admin?: boolean;
+  attributes?: string; // Comma-separated key=value pairs users must all have
+  sort?: "created_at" | "email" | "username" | "display_name" | "last_login";
+  order?: SortOrder;
+  cursor?: string; // From the x-next-cursor header of the previous page
 }
 
 // Create endpoint bodies
@@ -166,11 +237,14 @@ export interface UserCreateRequest {
   display_name?: string;
   avatar_url?: string;
   roles: Role[];
+  attributes?: Record<string, string>;
 }
 
 export interface GroupCreateRequest {
   name: string;
   description?: string;
+  parent_id?: string;
+  is_default?: boolean;
 }
 
 export interface ApiKeyCreateRequest {
@@ -185,11 +259,16 @@ export interface UserUpdateRequest {
   display_name?: string;
   avatar_url?: string;
   roles?: Role[];
+  default_model_id?: string | null; // null clears the default model
+  attributes?: Record<string, string>; // Replaces all of the user's attributes
 }
 
 export interface GroupUpdateRequest {
   name?: string;
   description?: string;
+  priority?: PriorityTier;
+  parent_id?: string | null; // null moves the group to the top level
+  is_default?: boolean;
 }
 
 export interface ModelUpdateRequest {
@@ -199,6 +278,10 @@ export interface ModelUpdateRequest {
   capabilities?: string[] | null;
   requests_per_second?: number | null;
   burst_size?: number | null;
+  max_context_tokens?: number | null;
+  supports_tools?: boolean | null;
+  supports_vision?: boolean | null;
+  tags?: string[];
 }
 
 // Endpoint-specific types
@@ -209,10 +292,14 @@ export interface EndpointCreateRequest {
   api_key?: string;
   model_filter?: string[]; // Array of model IDs to sync, or null for all models
   alias_mapping?: Record<string, string>; // model_name -> custom_alias
-  auth_header_name?: string; // Header name for authorization (defaults to "Authorization")
-  auth_header_prefix?: string; // Prefix for authorization header value (defaults to "Bearer ")
+  auth_header_name?: string; // Header name for authorization (defaults to "Authorization", or "api-key" for Azure OpenAI)
+  auth_header_prefix?: string; // Prefix for authorization header value (defaults to "Bearer ", or none for Azure OpenAI)
   sync?: boolean; // Whether to sync models during creation (defaults to true)
   skip_fetch?: boolean; // Create deployments directly from model_filter without fetching (defaults to false)
+  tls_ca_pem?: string;
+  tls_skip_verify?: boolean;
+  tls_min_version?: TlsVersion;
+  sync_interval_seconds?: number; // Sync models automatically every this many seconds (at least 60)
 }
 
 export interface EndpointUpdateRequest {
@@ -224,6 +311,11 @@ export interface EndpointUpdateRequest {
   alias_mapping?: Record<string, string>;
   auth_header_name?: string;
   auth_header_prefix?: string;
+  tls_ca_pem?: string | null;
+  tls_skip_verify?: boolean;
+  tls_min_version?: TlsVersion | null;
+  sync_interval_seconds?: number | null; // null to only sync on demand
+  enabled?: boolean;
 }
 
 export type EndpointValidateRequest =
@@ -233,6 +325,9 @@ export type EndpointValidateRequest =
       api_key?: string;
       auth_header_name?: string;
       auth_header_prefix?: string;
+      tls_ca_pem?: string;
+      tls_skip_verify?: boolean;
+      tls_min_version?: TlsVersion;
     }
   | {
       type: "existing";
@@ -287,6 +382,15 @@ export interface RequestResponsePair {
 
 export interface ListRequestsResponse {
   requests: RequestResponsePair[];
+  next_cursor?: string | null;
+}
+
+// Where a request's bodies were read from: the request logging tables, or object storage
+export type BodySource = "database" | "archive";
+
+export interface RequestDetailResponse extends RequestResponsePair {
+  body_source: BodySource;
+  archive_fetch_ms?: number; // Only set when bodies were fetched from the archive
 }
 
 // AI request/response types (matching Control Layer's tagged ApiAiRequest/ApiAiResponse enums)
@@ -432,6 +536,7 @@ export interface ListRequestsQuery {
   timestamp_after?: string;
   timestamp_before?: string;
   order_desc?: boolean;
+  cursor?: string; // next_cursor of the previous page, used instead of offset
 }
 
 // Validation schemas
@@ -448,6 +553,7 @@ export const listRequestsQuerySchema = z.object({
   timestamp_after: z.string().optional(),
   timestamp_before: z.string().optional(),
   order_desc: z.boolean().optional(),
+  cursor: z.string().optional(),
 });
 
 export type ListRequestsQueryValidated = z.infer<
@@ -561,6 +667,31 @@ export interface ChangePasswordRequest {
 export type UserResponse = User;
 
 // Probe types
+export type ProbeType =
+  | "http"
+  | "embeddings"
+  | "tool_call"
+  | "conversation"
+  | "semantic_similarity"
+  | "load";
+
+export interface ConversationTurn {
+  content: string;
+  expect_contains?: string[];
+}
+
+export type ProbeAssertion =
+  | { type: "json_path_exists"; path: string }
+  | { type: "regex"; pattern: string }
+  | { type: "max_latency_ms"; max_ms: number }
+  | { type: "min_tokens"; min_tokens: number };
+
+export interface AssertionResult {
+  assertion: ProbeAssertion;
+  passed: boolean;
+  message: string | null;
+}
+
 export interface Probe {
   id: string;
   name: string;
@@ -570,6 +701,18 @@ export interface Probe {
   http_method: string;
   request_path?: string | null;
   request_body?: Record<string, any> | null;
+  probe_type: ProbeType;
+  expected_dimensions?: number | null;
+  max_latency_ms?: number | null;
+  conversation?: ConversationTurn[] | null;
+  jitter_seconds?: number | null;
+  timeout_seconds?: number | null;
+  assertions?: ProbeAssertion[] | null;
+  golden_answer?: string | null;
+  embeddings_deployment_id?: string | null;
+  similarity_threshold?: number | null;
+  load_concurrency?: number | null;
+  load_duration_seconds?: number | null;
   created_at: string;
   updated_at: string;
 }
@@ -581,6 +724,18 @@ export interface CreateProbeRequest {
   http_method?: string;
   request_path?: string | null;
   request_body?: Record<string, any> | null;
+  probe_type?: ProbeType;
+  expected_dimensions?: number | null;
+  max_latency_ms?: number | null;
+  conversation?: ConversationTurn[] | null;
+  jitter_seconds?: number | null;
+  timeout_seconds?: number | null;
+  assertions?: ProbeAssertion[] | null;
+  golden_answer?: string | null;
+  embeddings_deployment_id?: string | null;
+  similarity_threshold?: number | null;
+  load_concurrency?: number | null;
+  load_duration_seconds?: number | null;
 }
 
 export interface ProbeResult {
@@ -593,6 +748,10 @@ export interface ProbeResult {
   error_message: string | null;
   response_data: any | null;
   metadata: any | null;
+  assertion_results?: AssertionResult[] | null;
+  in_maintenance: boolean;
+  time_to_first_token_ms: number | null;
+  tokens_per_second: number | null;
 }
 
 export interface ProbeStatistics {
@@ -606,6 +765,10 @@ export interface ProbeStatistics {
   p50_response_time_ms: number | null;
   p95_response_time_ms: number | null;
   p99_response_time_ms: number | null;
+  avg_time_to_first_token_ms: number | null;
+  p50_time_to_first_token_ms: number | null;
+  p95_time_to_first_token_ms: number | null;
+  avg_tokens_per_second: number | null;
   last_execution: string | null;
   last_success: string | null;
   last_failure: string | null;
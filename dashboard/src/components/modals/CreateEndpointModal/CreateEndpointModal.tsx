@@ -1228,7 +1228,8 @@ export const CreateEndpointModal: React.FC<CreateEndpointModalProps> = ({
                               </FormControl>
                               <FormDescription>
                                 The HTTP header name provided with upstream
-                                requests to this endpoint.
+                                requests to this endpoint. Azure OpenAI
+                                endpoints default to "api-key".
                               </FormDescription>
                             </FormItem>
                           )}
@@ -0,0 +1,19 @@
+-- Create endpoint_validation_results table
+-- Stores the outcome of each scheduled re-validation of an inference endpoint
+CREATE TABLE IF NOT EXISTS endpoint_validation_results (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    endpoint_id UUID NOT NULL REFERENCES inference_endpoints(id) ON DELETE CASCADE,
+    validated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    success BOOLEAN NOT NULL,
+    status_code INTEGER,
+    error_message TEXT,
+    model_count INTEGER,
+    credentials_expired BOOLEAN NOT NULL DEFAULT false
+);
+
+CREATE INDEX IF NOT EXISTS idx_endpoint_validation_results_endpoint_validated_at
+    ON endpoint_validation_results(endpoint_id, validated_at DESC);
+CREATE INDEX IF NOT EXISTS idx_endpoint_validation_results_validated_at
+    ON endpoint_validation_results(validated_at);
+
+COMMENT ON COLUMN endpoint_validation_results.credentials_expired IS 'True when the endpoint rejected the stored credentials (HTTP 401/403)';
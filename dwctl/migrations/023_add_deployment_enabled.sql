@@ -0,0 +1,7 @@
+-- Add an enabled flag to deployed_models
+-- Disabled deployments keep their configuration, history and group assignments,
+-- but are removed from routing and from /v1/models
+ALTER TABLE deployed_models
+ADD COLUMN enabled BOOLEAN NOT NULL DEFAULT true;
+
+COMMENT ON COLUMN deployed_models.enabled IS 'Whether the deployment is routable (false = temporarily disabled, e.g. during an incident)';
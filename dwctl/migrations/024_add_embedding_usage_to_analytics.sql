@@ -0,0 +1,16 @@
+-- Track embedding workload size in http_analytics
+-- Token counts underrepresent embedding traffic, so record how many vectors were
+-- produced and their dimensionality for each embeddings request
+
+ALTER TABLE http_analytics
+ADD COLUMN embedding_inputs INTEGER,
+ADD COLUMN embedding_dimensions INTEGER;
+
+-- Index for embeddings aggregation queries
+CREATE INDEX idx_analytics_embeddings ON http_analytics (timestamp, model)
+WHERE embedding_inputs IS NOT NULL;
+
+COMMENT ON COLUMN http_analytics.embedding_inputs IS
+'Number of embedding vectors returned (embeddings requests only)';
+COMMENT ON COLUMN http_analytics.embedding_dimensions IS
+'Dimensionality of the returned embedding vectors (embeddings requests only)';
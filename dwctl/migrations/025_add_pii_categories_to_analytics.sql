@@ -0,0 +1,13 @@
+-- Record coarse PII categories detected in logged prompts
+-- Only the category names are stored ('email', 'phone', 'financial'), never the matched values.
+-- NULL means the request was not classified; an empty array means no PII was found.
+
+ALTER TABLE http_analytics
+ADD COLUMN pii_categories TEXT[];
+
+-- Index for PII rate aggregation queries
+CREATE INDEX idx_analytics_pii_classified ON http_analytics (timestamp)
+WHERE pii_categories IS NOT NULL;
+
+COMMENT ON COLUMN http_analytics.pii_categories IS
+'Coarse PII categories detected in the request (NULL when classification is disabled)';
@@ -0,0 +1,18 @@
+-- Ordered fallback endpoints for a deployment alias.
+-- The deployment's own endpoint (hosted_on) is the primary; when it returns 5xx/429 or times out,
+-- the proxy retries the request against each fallback in ascending priority order.
+CREATE TABLE IF NOT EXISTS deployment_fallbacks (
+    deployment_id UUID NOT NULL REFERENCES deployed_models(id) ON DELETE CASCADE,
+    priority INTEGER NOT NULL CHECK (priority > 0),
+    endpoint_id UUID NOT NULL REFERENCES inference_endpoints(id) ON DELETE CASCADE,
+    model_name VARCHAR NOT NULL CHECK (trim(model_name) <> ''),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (deployment_id, priority)
+);
+
+CREATE INDEX IF NOT EXISTS idx_deployment_fallbacks_endpoint_id ON deployment_fallbacks(endpoint_id);
+
+-- Reload the proxy configuration when fallbacks change
+CREATE TRIGGER deployment_fallbacks_notify
+    AFTER INSERT OR UPDATE OR DELETE ON deployment_fallbacks
+    EXECUTE FUNCTION notify_config_change();
@@ -0,0 +1,23 @@
+-- Create load_tests table
+-- Each row is one admin-triggered run of synthetic traffic against a deployment, with its report
+CREATE TABLE IF NOT EXISTS load_tests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    deployment_id UUID NOT NULL REFERENCES deployed_models(id) ON DELETE CASCADE,
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status VARCHAR NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'stopped', 'failed')),
+    stop_reason TEXT,
+    stop_requested BOOLEAN NOT NULL DEFAULT false,
+    total_requests INTEGER NOT NULL CHECK (total_requests > 0),
+    concurrency INTEGER NOT NULL CHECK (concurrency > 0),
+    requests_per_second INTEGER NOT NULL CHECK (requests_per_second > 0),
+    max_duration_seconds INTEGER NOT NULL CHECK (max_duration_seconds > 0),
+    request_body JSONB,
+    report JSONB,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_load_tests_started_at ON load_tests(started_at DESC);
+
+-- Only one load test may run at a time, across all replicas
+CREATE UNIQUE INDEX IF NOT EXISTS idx_load_tests_single_running ON load_tests((true)) WHERE status = 'running';
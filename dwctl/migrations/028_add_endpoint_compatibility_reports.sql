@@ -0,0 +1,18 @@
+-- Create endpoint_compatibility_reports table
+-- Stores the outcome of each admin-triggered OpenAI compatibility suite run against an endpoint
+CREATE TABLE IF NOT EXISTS endpoint_compatibility_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    endpoint_id UUID NOT NULL REFERENCES inference_endpoints(id) ON DELETE CASCADE,
+    model VARCHAR NOT NULL,
+    passed INTEGER NOT NULL CHECK (passed >= 0),
+    total INTEGER NOT NULL CHECK (total >= passed),
+    score DOUBLE PRECISION GENERATED ALWAYS AS (CASE WHEN total = 0 THEN 0 ELSE passed::DOUBLE PRECISION / total END) STORED,
+    checks JSONB NOT NULL,
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_endpoint_compatibility_reports_endpoint_created_at
+    ON endpoint_compatibility_reports(endpoint_id, created_at DESC);
+
+COMMENT ON COLUMN endpoint_compatibility_reports.checks IS 'Outcome of each check in the suite, e.g. streaming or tool calls';
@@ -0,0 +1,20 @@
+-- Weighted targets for a deployment alias.
+-- When a deployment has traffic splits, each request is sent to one of them, chosen at random in
+-- proportion to its weight, instead of the deployment's own endpoint (hosted_on). To keep some
+-- traffic on that endpoint, list it as one of the targets.
+CREATE TABLE IF NOT EXISTS deployment_traffic_splits (
+    deployment_id UUID NOT NULL REFERENCES deployed_models(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL CHECK (position > 0),
+    endpoint_id UUID NOT NULL REFERENCES inference_endpoints(id) ON DELETE CASCADE,
+    model_name VARCHAR NOT NULL CHECK (trim(model_name) <> ''),
+    weight INTEGER NOT NULL CHECK (weight > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (deployment_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_deployment_traffic_splits_endpoint_id ON deployment_traffic_splits(endpoint_id);
+
+-- Reload the proxy configuration when traffic splits change
+CREATE TRIGGER deployment_traffic_splits_notify
+    AFTER INSERT OR UPDATE OR DELETE ON deployment_traffic_splits
+    EXECUTE FUNCTION notify_config_change();
@@ -0,0 +1,34 @@
+-- Create regression_suites and regression_runs tables
+-- A regression suite is a set of golden prompts with assertions on their responses, attached to a
+-- deployment. Every run is kept, so quality regressions after a backend upgrade show up in the history.
+CREATE TABLE IF NOT EXISTS regression_suites (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    deployment_id UUID NOT NULL REFERENCES deployed_models(id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    description TEXT,
+    cases JSONB NOT NULL,
+    schedule_interval_seconds INTEGER CHECK (schedule_interval_seconds >= 60),
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_run_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_regression_suites_deployment_id ON regression_suites(deployment_id);
+
+COMMENT ON COLUMN regression_suites.cases IS 'Prompts to send, each with the assertions its response must satisfy';
+COMMENT ON COLUMN regression_suites.schedule_interval_seconds IS 'Run the suite automatically this often; NULL to only run on demand';
+
+CREATE TABLE IF NOT EXISTS regression_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    suite_id UUID NOT NULL REFERENCES regression_suites(id) ON DELETE CASCADE,
+    trigger VARCHAR NOT NULL CHECK (trigger IN ('manual', 'scheduled')),
+    passed INTEGER NOT NULL CHECK (passed >= 0),
+    total INTEGER NOT NULL CHECK (total >= passed),
+    score DOUBLE PRECISION GENERATED ALWAYS AS (CASE WHEN total = 0 THEN 0 ELSE passed::DOUBLE PRECISION / total END) STORED,
+    results JSONB NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_regression_runs_suite_started_at ON regression_runs(suite_id, started_at DESC);
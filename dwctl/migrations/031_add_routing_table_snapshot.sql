@@ -0,0 +1,10 @@
+-- Create routing_table_snapshot table
+-- Holds the routing table as of the last change notification, so every replica can tell whether a
+-- reload actually changed routing and only one of them sends the notification for each change
+CREATE TABLE IF NOT EXISTS routing_table_snapshot (
+    id BOOLEAN PRIMARY KEY DEFAULT true CHECK (id),
+    snapshot JSONB NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO routing_table_snapshot (snapshot) VALUES ('{}'::jsonb) ON CONFLICT DO NOTHING;
@@ -0,0 +1,20 @@
+-- Create group_moderation_policies table
+-- Requests from members of a group with a policy are checked before being forwarded: matches
+-- are either annotated or blocked. Policies of the Everyone group apply to every user.
+CREATE TABLE IF NOT EXISTS group_moderation_policies (
+    group_id UUID PRIMARY KEY REFERENCES groups(id) ON DELETE CASCADE,
+    mode VARCHAR NOT NULL DEFAULT 'off' CHECK (mode IN ('off', 'annotate', 'block')),
+    blocked_patterns TEXT[] NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN group_moderation_policies.blocked_patterns IS
+'Regular expressions matched against the text of each request, in addition to the moderation endpoint (if configured)';
+
+-- Record the moderation decision for each request a policy applied to
+ALTER TABLE http_analytics
+ADD COLUMN moderation_decision VARCHAR,
+ADD COLUMN moderation_categories TEXT[];
+
+COMMENT ON COLUMN http_analytics.moderation_decision IS
+'allowed, flagged or blocked (NULL when no moderation policy applied to the request)';
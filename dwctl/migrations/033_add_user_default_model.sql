@@ -0,0 +1,7 @@
+-- Add a default model to users
+-- Returned by GET /ai/v1/bootstrap so client SDKs can pick a model without hardcoding one.
+ALTER TABLE users
+ADD COLUMN default_model_id UUID REFERENCES deployed_models(id) ON DELETE SET NULL;
+
+COMMENT ON COLUMN users.default_model_id IS
+'Model offered to the user''s clients by default. Ignored if the user no longer has access to it.';
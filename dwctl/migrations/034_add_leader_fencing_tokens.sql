@@ -0,0 +1,10 @@
+-- Create leader_fencing_tokens table
+-- Each time a replica becomes leader it increments the token for the leader lock. Leader-run
+-- jobs check that their token is still the newest before doing any work, so a replica that
+-- lost the advisory lock without noticing (e.g. its connection was dropped) stops instead of
+-- running the jobs alongside the new leader.
+CREATE TABLE IF NOT EXISTS leader_fencing_tokens (
+    lock_id BIGINT PRIMARY KEY,
+    token BIGINT NOT NULL,
+    acquired_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,23 @@
+-- Canary deployments for model rollouts
+-- A canary takes a percentage of the traffic sent to another deployment's alias (the stable
+-- deployment), so a new model or endpoint can be compared with the current one before full
+-- cutover. Each alias has at most one canary.
+CREATE TABLE IF NOT EXISTS deployment_canaries (
+    deployment_id UUID PRIMARY KEY REFERENCES deployed_models(id) ON DELETE CASCADE,
+    stable_deployment_id UUID NOT NULL UNIQUE REFERENCES deployed_models(id) ON DELETE CASCADE,
+    percent INTEGER NOT NULL CHECK (percent BETWEEN 1 AND 100),
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (deployment_id <> stable_deployment_id)
+);
+
+-- Reload the proxy configuration when canaries change
+CREATE TRIGGER deployment_canaries_notify
+    AFTER INSERT OR UPDATE OR DELETE ON deployment_canaries
+    EXECUTE FUNCTION notify_config_change();
+
+-- Record which variant served each request to an alias with a canary
+ALTER TABLE http_analytics
+ADD COLUMN variant VARCHAR;
+
+COMMENT ON COLUMN http_analytics.variant IS
+'stable or canary (NULL when the requested alias had no canary)';
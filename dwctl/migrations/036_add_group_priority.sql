@@ -0,0 +1,5 @@
+-- Admission priority of each group's AI requests
+-- When the proxy is saturated, queued requests are admitted highest tier first. A user's tier is
+-- the highest of their groups' tiers.
+ALTER TABLE groups
+ADD COLUMN priority VARCHAR NOT NULL DEFAULT 'normal' CHECK (priority IN ('high', 'normal', 'low'));
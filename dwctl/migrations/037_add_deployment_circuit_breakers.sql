@@ -0,0 +1,14 @@
+-- Open circuit breakers of deployments whose probes keep failing
+-- A row is added once a deployment's probe has failed a configured number of times in a row,
+-- and removed when the probe next succeeds. While a deployment has a row, the proxy sends its
+-- requests straight to its fallbacks, or refuses them if it has none.
+CREATE TABLE IF NOT EXISTS deployment_circuit_breakers (
+    deployment_id UUID PRIMARY KEY REFERENCES deployed_models(id) ON DELETE CASCADE,
+    opened_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Reload the proxy configuration when a circuit opens or closes. This is a row trigger so that
+-- probe results that leave a circuit as it was don't cause reloads.
+CREATE TRIGGER deployment_circuit_breakers_notify
+    AFTER INSERT OR DELETE ON deployment_circuit_breakers
+    FOR EACH ROW EXECUTE FUNCTION notify_config_change();
@@ -0,0 +1,24 @@
+-- Create endpoint_redaction_policies table
+-- Requests routed to an endpoint with a policy have PII masked in their prompts before being
+-- forwarded, e.g. for endpoints run by external providers.
+CREATE TABLE IF NOT EXISTS endpoint_redaction_policies (
+    endpoint_id UUID PRIMARY KEY REFERENCES inference_endpoints(id) ON DELETE CASCADE,
+    entities TEXT[] NOT NULL DEFAULT '{}',
+    patterns TEXT[] NOT NULL DEFAULT '{}',
+    log_original BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN endpoint_redaction_policies.entities IS
+'Categories of PII to mask: email, phone and/or financial';
+
+COMMENT ON COLUMN endpoint_redaction_policies.patterns IS
+'Regular expressions whose matches are masked, in addition to the entities';
+
+COMMENT ON COLUMN endpoint_redaction_policies.log_original IS
+'Whether the request log may keep the original, unredacted request';
+
+-- Reload the proxy configuration when redaction policies change
+CREATE TRIGGER endpoint_redaction_policies_notify
+    AFTER INSERT OR UPDATE OR DELETE ON endpoint_redaction_policies
+    EXECUTE FUNCTION notify_config_change();
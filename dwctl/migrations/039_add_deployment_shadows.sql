@@ -0,0 +1,16 @@
+-- Shadow deployments for evaluating models on production traffic
+-- A shadow receives a copy of a percentage of the requests sent to another deployment's alias
+-- (the source deployment). Users only ever see the source's responses; the shadow's are
+-- discarded after being logged. Each alias has at most one shadow.
+CREATE TABLE IF NOT EXISTS deployment_shadows (
+    deployment_id UUID PRIMARY KEY REFERENCES deployed_models(id) ON DELETE CASCADE,
+    source_deployment_id UUID NOT NULL UNIQUE REFERENCES deployed_models(id) ON DELETE CASCADE,
+    percent INTEGER NOT NULL CHECK (percent BETWEEN 1 AND 100),
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (deployment_id <> source_deployment_id)
+);
+
+-- Reload the proxy configuration when shadows change
+CREATE TRIGGER deployment_shadows_notify
+    AFTER INSERT OR UPDATE OR DELETE ON deployment_shadows
+    EXECUTE FUNCTION notify_config_change();
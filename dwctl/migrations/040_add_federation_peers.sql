@@ -0,0 +1,17 @@
+-- Create federation_peers table
+-- Other dwctl instances (e.g. in other regions) whose read-only summaries are aggregated with
+-- this instance's, for a single view of analytics and health across instances.
+CREATE TABLE IF NOT EXISTS federation_peers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR NOT NULL UNIQUE,
+    url VARCHAR NOT NULL,
+    token VARCHAR NOT NULL,
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN federation_peers.url IS
+'Base URL of the peer instance, e.g. https://eu.example.com';
+
+COMMENT ON COLUMN federation_peers.token IS
+'The federation token configured on the peer, sent as a bearer token';
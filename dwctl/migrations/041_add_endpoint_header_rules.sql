@@ -0,0 +1,21 @@
+-- Create endpoint_header_rules table
+-- Requests routed to an endpoint with rules get the injected headers added before being
+-- forwarded, e.g. organization IDs or routing hints required by the upstream, and only carry
+-- the client headers on the passthrough allowlist, if one is set.
+CREATE TABLE IF NOT EXISTS endpoint_header_rules (
+    endpoint_id UUID PRIMARY KEY REFERENCES inference_endpoints(id) ON DELETE CASCADE,
+    inject JSONB NOT NULL DEFAULT '{}',
+    passthrough TEXT[],
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN endpoint_header_rules.inject IS
+'Headers added to every forwarded request, as an object of lowercase header names to values';
+
+COMMENT ON COLUMN endpoint_header_rules.passthrough IS
+'Lowercase names of the client headers forwarded to the endpoint. NULL forwards all client headers';
+
+-- Reload the proxy configuration when header rules change
+CREATE TRIGGER endpoint_header_rules_notify
+    AFTER INSERT OR UPDATE OR DELETE ON endpoint_header_rules
+    EXECUTE FUNCTION notify_config_change();
@@ -0,0 +1,19 @@
+-- Create group_request_limits table
+-- Requests from members of a group with limits are refused before being forwarded when they
+-- exceed any of them. Limits of the Everyone group apply to every user.
+CREATE TABLE IF NOT EXISTS group_request_limits (
+    group_id UUID PRIMARY KEY REFERENCES groups(id) ON DELETE CASCADE,
+    max_body_bytes BIGINT CHECK (max_body_bytes > 0),
+    max_messages INTEGER CHECK (max_messages > 0),
+    max_tokens INTEGER CHECK (max_tokens > 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN group_request_limits.max_body_bytes IS
+'Maximum size of a request body in bytes (NULL for no limit)';
+
+COMMENT ON COLUMN group_request_limits.max_messages IS
+'Maximum number of messages in a chat or Messages API request (NULL for no limit)';
+
+COMMENT ON COLUMN group_request_limits.max_tokens IS
+'Maximum max_tokens (or max_completion_tokens) a request may ask for (NULL for no limit)';
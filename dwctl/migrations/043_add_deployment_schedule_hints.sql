@@ -0,0 +1,15 @@
+-- Schedule hints of deployments
+-- Admins record the hours in which a deployment is expected to be busy, so that backend
+-- operators and autoscalers can provision capacity ahead of known traffic peaks.
+CREATE TABLE IF NOT EXISTS deployment_schedule_hints (
+    deployment_id UUID PRIMARY KEY REFERENCES deployed_models(id) ON DELETE CASCADE,
+    busy_windows JSONB NOT NULL DEFAULT '[]',
+    lead_time_minutes INTEGER NOT NULL DEFAULT 30 CHECK (lead_time_minutes >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN deployment_schedule_hints.busy_windows IS
+'Windows of expected traffic peaks, as [{"days": ["mon", ...], "start_hour": 8, "end_hour": 18}] in UTC';
+
+COMMENT ON COLUMN deployment_schedule_hints.lead_time_minutes IS
+'How long before a busy window starts capacity should be provisioned';
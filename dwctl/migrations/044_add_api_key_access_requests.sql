@@ -0,0 +1,39 @@
+-- Key-level approval for sensitive deployments
+-- Deployments requiring key approval are only served to API keys that have been granted access
+-- individually, on top of the usual group access. Users request access for one of their keys,
+-- and an approver approves or denies the request.
+CREATE TABLE IF NOT EXISTS deployment_access_policies (
+    deployment_id UUID PRIMARY KEY REFERENCES deployed_models(id) ON DELETE CASCADE,
+    requires_key_approval BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS api_key_access_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    api_key_id UUID NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    deployment_id UUID NOT NULL REFERENCES deployed_models(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'approved', 'denied')),
+    reason TEXT,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    decided_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    decided_at TIMESTAMPTZ,
+    decision_note TEXT,
+    UNIQUE (api_key_id, deployment_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_key_access_requests_status ON api_key_access_requests(status);
+
+COMMENT ON COLUMN api_key_access_requests.reason IS
+'Why the requester needs access, shown to approvers';
+
+COMMENT ON COLUMN api_key_access_requests.decision_note IS
+'Optional note from the approver explaining their decision';
+
+-- Reload the proxy configuration when policies or grants change
+CREATE TRIGGER deployment_access_policies_notify
+    AFTER INSERT OR UPDATE OR DELETE ON deployment_access_policies
+    EXECUTE FUNCTION notify_config_change();
+
+CREATE TRIGGER api_key_access_requests_notify
+    AFTER INSERT OR UPDATE OR DELETE ON api_key_access_requests
+    EXECUTE FUNCTION notify_config_change();
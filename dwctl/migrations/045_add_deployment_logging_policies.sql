@@ -0,0 +1,20 @@
+-- Create deployment_logging_policies table
+-- Controls what the request log keeps of the bodies of requests to a deployment, for deployments
+-- whose prompts may not be stored. Deployments without a policy are logged in full.
+CREATE TABLE IF NOT EXISTS deployment_logging_policies (
+    deployment_id UUID PRIMARY KEY REFERENCES deployed_models(id) ON DELETE CASCADE,
+    mode TEXT NOT NULL DEFAULT 'full' CHECK (mode IN ('full', 'metadata_only', 'hash_only', 'redact_fields')),
+    redacted_fields TEXT[] NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN deployment_logging_policies.mode IS
+'full keeps bodies as sent, metadata_only drops them, hash_only keeps a SHA-256 of each and redact_fields masks redacted_fields';
+
+COMMENT ON COLUMN deployment_logging_policies.redacted_fields IS
+'Dot-separated paths of the body fields masked in redact_fields mode, e.g. messages.content';
+
+-- Reload the proxy configuration when logging policies change
+CREATE TRIGGER deployment_logging_policies_notify
+    AFTER INSERT OR UPDATE OR DELETE ON deployment_logging_policies
+    EXECUTE FUNCTION notify_config_change();
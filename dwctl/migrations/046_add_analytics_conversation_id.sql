@@ -0,0 +1,9 @@
+-- Add conversation_id to http_analytics
+-- Requests are threaded into conversations by a client-supplied conversation ID header, or by an
+-- ID derived from the caller and the opening messages of the conversation, so usage can be
+-- analysed per conversation.
+ALTER TABLE http_analytics ADD COLUMN IF NOT EXISTS conversation_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_analytics_conversation
+    ON http_analytics (conversation_id, timestamp)
+    WHERE conversation_id IS NOT NULL;
@@ -0,0 +1,16 @@
+-- Manifest of request log ranges exported to object storage
+-- With the "export" retention action, the leader uploads each expired day of the request log to
+-- object storage as JSON Lines objects before deleting it. Each object is recorded here, so the
+-- archived ranges can still be found from the API once the rows are gone.
+CREATE TABLE request_log_archives (
+    id BIGSERIAL PRIMARY KEY,
+    range_start TIMESTAMPTZ NOT NULL,
+    range_end TIMESTAMPTZ NOT NULL,
+    object_key TEXT NOT NULL UNIQUE,
+    format TEXT NOT NULL DEFAULT 'jsonl',
+    request_count BIGINT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_request_log_archives_range ON request_log_archives (range_start, range_end);
@@ -0,0 +1,8 @@
+-- Add tags to http_analytics
+-- Clients attach key=value tags to AI requests with the x-doubleword-tags header, so usage can be
+-- attributed to the features and projects that made it rather than only to users.
+ALTER TABLE http_analytics ADD COLUMN IF NOT EXISTS tags JSONB;
+
+CREATE INDEX IF NOT EXISTS idx_analytics_tags
+    ON http_analytics USING GIN (tags jsonb_path_ops)
+    WHERE tags IS NOT NULL;
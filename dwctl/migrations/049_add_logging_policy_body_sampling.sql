@@ -0,0 +1,10 @@
+-- Add body sampling to deployment logging policies
+-- Capturing every body of a busy deployment is expensive, so a policy can keep the bodies of only a
+-- percentage of its requests. The rest are logged as metadata only; usage analytics are recorded for
+-- every request either way.
+ALTER TABLE deployment_logging_policies
+    ADD COLUMN IF NOT EXISTS body_sample_percent INTEGER NOT NULL DEFAULT 100
+    CHECK (body_sample_percent BETWEEN 0 AND 100);
+
+COMMENT ON COLUMN deployment_logging_policies.body_sample_percent IS
+'Percentage of requests whose bodies are logged under the policy''s mode; the others are logged as metadata only';
@@ -0,0 +1,25 @@
+-- Create token_backfills table
+-- Each row is one admin-triggered run filling in the token counts of logged requests whose
+-- analytics were recorded without them, from the bodies kept in the request log.
+CREATE TABLE IF NOT EXISTS token_backfills (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status VARCHAR NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'stopped', 'failed')),
+    stop_reason TEXT,
+    stop_requested BOOLEAN NOT NULL DEFAULT false,
+    -- Only requests logged in this range are backfilled (unbounded if NULL)
+    range_start TIMESTAMPTZ,
+    range_end TIMESTAMPTZ,
+    total_requests BIGINT NOT NULL DEFAULT 0,
+    processed_requests BIGINT NOT NULL DEFAULT 0,
+    updated_requests BIGINT NOT NULL DEFAULT 0,
+    skipped_requests BIGINT NOT NULL DEFAULT 0,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_token_backfills_started_at ON token_backfills(started_at DESC);
+
+-- Only one backfill may run at a time, across all replicas
+CREATE UNIQUE INDEX IF NOT EXISTS idx_token_backfills_single_running ON token_backfills((true)) WHERE status = 'running';
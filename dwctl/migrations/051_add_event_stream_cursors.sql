@@ -0,0 +1,10 @@
+-- Create event_stream_cursors table
+-- Records how far the event stream publisher has got through http_analytics for each
+-- destination, so records are published at least once across restarts and leader changes.
+CREATE TABLE IF NOT EXISTS event_stream_cursors (
+    -- Destination the cursor belongs to, e.g. 'kafka:<topic>' or 'nats:<subject>'
+    destination VARCHAR PRIMARY KEY,
+    -- ID of the last http_analytics row published (or dead-lettered)
+    last_id BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,12 @@
+-- Add embeddings probes
+-- An embeddings probe calls /v1/embeddings on its deployment and checks the returned vectors, not
+-- just the status code. Any probe can also fail responses slower than a latency limit.
+ALTER TABLE probes
+    ADD COLUMN IF NOT EXISTS probe_type VARCHAR NOT NULL DEFAULT 'http'
+        CHECK (probe_type IN ('http', 'embeddings')),
+    ADD COLUMN IF NOT EXISTS expected_dimensions INTEGER CHECK (expected_dimensions > 0),
+    ADD COLUMN IF NOT EXISTS max_latency_ms INTEGER CHECK (max_latency_ms > 0);
+
+COMMENT ON COLUMN probes.probe_type IS 'http to check the response status, embeddings to also validate the returned vectors';
+COMMENT ON COLUMN probes.expected_dimensions IS 'Number of dimensions each embedding must have (embeddings probes only)';
+COMMENT ON COLUMN probes.max_latency_ms IS 'Responses slower than this many milliseconds count as failures';
@@ -0,0 +1,8 @@
+-- Add tool call probes
+-- A tool call probe sends a chat completion with a tool schema and fails unless the model answers
+-- with a well-formed call to one of the tools, catching upstream regressions in function calling.
+ALTER TABLE probes DROP CONSTRAINT IF EXISTS probes_probe_type_check;
+ALTER TABLE probes ADD CONSTRAINT probes_probe_type_check
+    CHECK (probe_type IN ('http', 'embeddings', 'tool_call'));
+
+COMMENT ON COLUMN probes.probe_type IS 'http to check the response status, embeddings to also validate the returned vectors, tool_call to also validate the returned tool calls';
@@ -0,0 +1,44 @@
+-- Create deployment_slos and slo_alerts tables
+-- An SLO sets a target share of a deployment's probe results that must be good over a rolling
+-- window: successful for availability SLOs, successful and under a latency threshold for latency
+-- SLOs. The leader evaluates every SLO periodically, keeps the latest evaluation on the SLO and
+-- records an alert whenever an SLO's error budget status changes.
+CREATE TABLE IF NOT EXISTS deployment_slos (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    deployment_id UUID NOT NULL REFERENCES deployed_models(id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    objective VARCHAR NOT NULL CHECK (objective IN ('availability', 'latency')),
+    target_percent DOUBLE PRECISION NOT NULL CHECK (target_percent > 0 AND target_percent < 100),
+    latency_threshold_ms INTEGER CHECK (latency_threshold_ms > 0),
+    window_days INTEGER NOT NULL DEFAULT 30 CHECK (window_days BETWEEN 1 AND 90),
+    status VARCHAR NOT NULL DEFAULT 'ok' CHECK (status IN ('ok', 'at_risk', 'exhausted')),
+    total_results BIGINT NOT NULL DEFAULT 0,
+    good_results BIGINT NOT NULL DEFAULT 0,
+    error_budget_remaining_percent DOUBLE PRECISION NOT NULL DEFAULT 100,
+    burn_rate DOUBLE PRECISION NOT NULL DEFAULT 0,
+    evaluated_at TIMESTAMPTZ,
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (objective <> 'latency' OR latency_threshold_ms IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_deployment_slos_deployment_id ON deployment_slos(deployment_id);
+
+COMMENT ON COLUMN deployment_slos.target_percent IS 'Share of probe results in the window that must be good, e.g. 99.9';
+COMMENT ON COLUMN deployment_slos.latency_threshold_ms IS 'Probe results slower than this are bad (latency SLOs only)';
+COMMENT ON COLUMN deployment_slos.error_budget_remaining_percent IS 'Share of the error budget left at the last evaluation; negative once overspent';
+COMMENT ON COLUMN deployment_slos.burn_rate IS 'Rate the error budget was spent at over the recent burn window at the last evaluation, where 1 spends it exactly over the SLO window';
+
+CREATE TABLE IF NOT EXISTS slo_alerts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    slo_id UUID NOT NULL REFERENCES deployment_slos(id) ON DELETE CASCADE,
+    status VARCHAR NOT NULL CHECK (status IN ('ok', 'at_risk', 'exhausted')),
+    previous_status VARCHAR NOT NULL CHECK (previous_status IN ('ok', 'at_risk', 'exhausted')),
+    error_budget_remaining_percent DOUBLE PRECISION NOT NULL,
+    burn_rate DOUBLE PRECISION NOT NULL,
+    message TEXT NOT NULL,
+    fired_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_slo_alerts_slo_fired_at ON slo_alerts(slo_id, fired_at DESC);
@@ -0,0 +1,30 @@
+-- Create notification_channels and probe_alert_states tables
+-- Notification channels are where probe alerts are sent: a JSON webhook, a Slack incoming webhook
+-- or an email address. Every enabled channel is notified when a probe starts failing (a run of
+-- consecutive failures) or recovers.
+CREATE TABLE IF NOT EXISTS notification_channels (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR NOT NULL,
+    channel_type VARCHAR NOT NULL CHECK (channel_type IN ('webhook', 'slack', 'email')),
+    destination TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    last_delivery_at TIMESTAMPTZ,
+    last_delivery_error TEXT,
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN notification_channels.destination IS 'Webhook URL for webhook and slack channels, email address for email channels';
+COMMENT ON COLUMN notification_channels.last_delivery_error IS 'Error of the last delivery attempt, NULL if it succeeded';
+
+-- Alerting state of each probe. An alert is only sent when a probe's status differs from the one
+-- last notified, and at most once per cooldown, so flapping probes don't flood channels.
+CREATE TABLE IF NOT EXISTS probe_alert_states (
+    probe_id UUID PRIMARY KEY REFERENCES probes(id) ON DELETE CASCADE,
+    status VARCHAR NOT NULL DEFAULT 'ok' CHECK (status IN ('ok', 'failing')),
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    notified_status VARCHAR NOT NULL DEFAULT 'ok' CHECK (notified_status IN ('ok', 'failing')),
+    notified_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
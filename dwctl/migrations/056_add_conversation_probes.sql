@@ -0,0 +1,12 @@
+-- Add conversation probes
+-- A conversation probe plays a scripted multi-turn chat against its deployment, sending the whole
+-- history with each user message, and fails unless every reply contains the expected content. This
+-- catches upstreams that answer single prompts but mishandle context.
+ALTER TABLE probes DROP CONSTRAINT IF EXISTS probes_probe_type_check;
+ALTER TABLE probes ADD CONSTRAINT probes_probe_type_check
+    CHECK (probe_type IN ('http', 'embeddings', 'tool_call', 'conversation'));
+
+ALTER TABLE probes ADD COLUMN IF NOT EXISTS conversation JSONB;
+
+COMMENT ON COLUMN probes.probe_type IS 'http to check the response status, embeddings to also validate the returned vectors, tool_call to also validate the returned tool calls, conversation to play a scripted multi-turn chat';
+COMMENT ON COLUMN probes.conversation IS 'Turns of a conversation probe: [{"content": "...", "expect_contains": ["..."]}]';
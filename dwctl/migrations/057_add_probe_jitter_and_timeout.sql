@@ -0,0 +1,10 @@
+-- Add per-probe jitter and timeout
+-- Each execution of a probe starts after a random delay of up to its jitter, so probes sharing an
+-- interval don't hit upstreams in lockstep, and gives up on requests slower than its timeout. When
+-- unset, the probes.jitter and probes.request_timeout settings apply.
+ALTER TABLE probes
+    ADD COLUMN IF NOT EXISTS jitter_seconds INTEGER CHECK (jitter_seconds >= 0),
+    ADD COLUMN IF NOT EXISTS timeout_seconds INTEGER CHECK (timeout_seconds > 0);
+
+COMMENT ON COLUMN probes.jitter_seconds IS 'Maximum random delay before each execution, in seconds (defaults to probes.jitter)';
+COMMENT ON COLUMN probes.timeout_seconds IS 'Timeout for each probe request, in seconds (defaults to probes.request_timeout)';
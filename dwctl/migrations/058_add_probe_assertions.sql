@@ -0,0 +1,9 @@
+-- Add custom assertions to probes
+-- Assertions are extra checks evaluated against a probe's response (a JSON path exists, the
+-- content matches a regex, the latency or completion token count is within bounds). The outcome
+-- of each is stored with the result, so a failure says which check broke.
+ALTER TABLE probes ADD COLUMN IF NOT EXISTS assertions JSONB;
+ALTER TABLE probe_results ADD COLUMN IF NOT EXISTS assertion_results JSONB;
+
+COMMENT ON COLUMN probes.assertions IS 'Checks on the response: [{"type": "json_path_exists", "path": "$.usage"}, {"type": "regex", "pattern": "..."}, {"type": "max_latency_ms", "max_ms": 2000}, {"type": "min_tokens", "min_tokens": 1}]';
+COMMENT ON COLUMN probe_results.assertion_results IS 'Outcome of each of the probe''s assertions: [{"assertion": {...}, "passed": true, "message": null}]';
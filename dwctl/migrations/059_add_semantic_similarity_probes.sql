@@ -0,0 +1,17 @@
+-- Add semantic similarity probes
+-- A semantic similarity probe asks its deployment a question and compares the answer to a stored
+-- golden answer, embedding both with another (embeddings) deployment. The probe fails when the
+-- cosine similarity drops below a threshold, catching silent regressions in answer quality.
+ALTER TABLE probes DROP CONSTRAINT IF EXISTS probes_probe_type_check;
+ALTER TABLE probes ADD CONSTRAINT probes_probe_type_check
+    CHECK (probe_type IN ('http', 'embeddings', 'tool_call', 'conversation', 'semantic_similarity'));
+
+ALTER TABLE probes ADD COLUMN IF NOT EXISTS golden_answer TEXT;
+ALTER TABLE probes ADD COLUMN IF NOT EXISTS embeddings_deployment_id UUID REFERENCES deployed_models(id) ON DELETE SET NULL;
+ALTER TABLE probes ADD COLUMN IF NOT EXISTS similarity_threshold DOUBLE PRECISION
+    CHECK (similarity_threshold > 0 AND similarity_threshold <= 1);
+
+COMMENT ON COLUMN probes.probe_type IS 'http to check the response status, embeddings to also validate the returned vectors, tool_call to also validate the returned tool calls, conversation to play a scripted multi-turn chat, semantic_similarity to compare the answer to a golden answer';
+COMMENT ON COLUMN probes.golden_answer IS 'Expected answer of a semantic similarity probe';
+COMMENT ON COLUMN probes.embeddings_deployment_id IS 'Deployment used to embed the answer and golden answer of a semantic similarity probe';
+COMMENT ON COLUMN probes.similarity_threshold IS 'Minimum cosine similarity between the answer and the golden answer';
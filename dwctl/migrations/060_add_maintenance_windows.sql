@@ -0,0 +1,26 @@
+-- Add maintenance windows
+-- A maintenance window covers an endpoint (and so every deployment hosted on it) or a single
+-- deployment for a period of time. Probes keep running during the window, but their results are
+-- marked as taken in maintenance: they don't trigger probe alerts and aren't counted by SLOs.
+CREATE TABLE maintenance_windows (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR NOT NULL,
+    endpoint_id UUID REFERENCES inference_endpoints(id) ON DELETE CASCADE,
+    deployment_id UUID REFERENCES deployed_models(id) ON DELETE CASCADE,
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    reason TEXT,
+    created_by UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT maintenance_windows_target_check CHECK (num_nonnulls(endpoint_id, deployment_id) = 1),
+    CONSTRAINT maintenance_windows_period_check CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX idx_maintenance_windows_endpoint_id ON maintenance_windows(endpoint_id, ends_at);
+CREATE INDEX idx_maintenance_windows_deployment_id ON maintenance_windows(deployment_id, ends_at);
+
+ALTER TABLE probe_results ADD COLUMN IF NOT EXISTS in_maintenance BOOLEAN NOT NULL DEFAULT false;
+
+COMMENT ON TABLE maintenance_windows IS 'Periods during which failures of an endpoint''s or deployment''s probes don''t alert or count against SLOs';
+COMMENT ON COLUMN probe_results.in_maintenance IS 'Whether the result was taken during a maintenance window of its deployment or endpoint';
@@ -0,0 +1,5 @@
+-- Record streaming timings of probe results
+-- Probes of chat deployments stream their completion, measuring the time to the first token (the
+-- latency users actually feel) and the rate of the tokens that follow, besides the total latency.
+ALTER TABLE probe_results ADD COLUMN IF NOT EXISTS time_to_first_token_ms INTEGER;
+ALTER TABLE probe_results ADD COLUMN IF NOT EXISTS tokens_per_second DOUBLE PRECISION;
@@ -0,0 +1,15 @@
+-- Add load probes
+-- A load probe keeps a number of concurrent requests in flight against its deployment for a bounded
+-- duration and reports the throughput and latency distribution. Load probes only run on demand,
+-- within the server's load testing limits, so they are never active (scheduled).
+ALTER TABLE probes DROP CONSTRAINT IF EXISTS probes_probe_type_check;
+ALTER TABLE probes ADD CONSTRAINT probes_probe_type_check
+    CHECK (probe_type IN ('http', 'embeddings', 'tool_call', 'conversation', 'semantic_similarity', 'load'));
+
+ALTER TABLE probes ADD COLUMN IF NOT EXISTS load_concurrency INTEGER CHECK (load_concurrency > 0);
+ALTER TABLE probes ADD COLUMN IF NOT EXISTS load_duration_seconds INTEGER CHECK (load_duration_seconds > 0);
+ALTER TABLE probes ADD CONSTRAINT probes_load_not_active CHECK (probe_type <> 'load' OR NOT active);
+
+COMMENT ON COLUMN probes.probe_type IS 'http to check the response status, embeddings to also validate the returned vectors, tool_call to also validate the returned tool calls, conversation to play a scripted multi-turn chat, semantic_similarity to compare the answer to a golden answer, load to measure throughput and latency under concurrent requests';
+COMMENT ON COLUMN probes.load_concurrency IS 'Requests a load probe keeps in flight';
+COMMENT ON COLUMN probes.load_duration_seconds IS 'How long a load probe sends requests for';
@@ -0,0 +1,21 @@
+-- Add TLS settings to inference endpoints
+-- Endpoints in private networks often serve certificates signed by an internal CA. These are
+-- applied both when fetching an endpoint's models and when the AI proxy forwards requests to it.
+ALTER TABLE inference_endpoints
+    ADD COLUMN tls_ca_pem TEXT,
+    ADD COLUMN tls_skip_verify BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN tls_min_version TEXT CHECK (tls_min_version IN ('1.2', '1.3'));
+
+COMMENT ON COLUMN inference_endpoints.tls_ca_pem IS
+'PEM bundle of CA certificates trusted for the endpoint, in addition to the built-in roots';
+
+COMMENT ON COLUMN inference_endpoints.tls_skip_verify IS
+'Whether to accept any certificate from the endpoint. Only meant for lab environments';
+
+COMMENT ON COLUMN inference_endpoints.tls_min_version IS
+'Minimum TLS version to negotiate with the endpoint. NULL uses the client default';
+
+-- Reload the proxy configuration when TLS settings change
+CREATE TRIGGER inference_endpoints_tls_notify
+    AFTER UPDATE OF tls_ca_pem, tls_skip_verify, tls_min_version ON inference_endpoints
+    EXECUTE FUNCTION notify_config_change();
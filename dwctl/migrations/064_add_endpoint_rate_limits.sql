@@ -0,0 +1,26 @@
+-- Create endpoint_rate_limits table
+-- Providers rate limit a whole organization's key, so requests forwarded to an endpoint with
+-- limits are refused by the proxy once they'd exceed its request rate or concurrency cap,
+-- rather than letting one team's burst get every deployment on the endpoint refused upstream.
+CREATE TABLE IF NOT EXISTS endpoint_rate_limits (
+    endpoint_id UUID PRIMARY KEY REFERENCES inference_endpoints(id) ON DELETE CASCADE,
+    requests_per_second REAL CHECK (requests_per_second > 0),
+    burst_size INTEGER CHECK (burst_size > 0),
+    max_concurrent INTEGER CHECK (max_concurrent > 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (burst_size IS NULL OR requests_per_second IS NOT NULL)
+);
+
+COMMENT ON COLUMN endpoint_rate_limits.requests_per_second IS
+'Requests per second forwarded to the endpoint across all its deployments. NULL leaves the rate unlimited';
+
+COMMENT ON COLUMN endpoint_rate_limits.burst_size IS
+'Requests that may be forwarded at once before the rate applies. NULL uses the rate, rounded up';
+
+COMMENT ON COLUMN endpoint_rate_limits.max_concurrent IS
+'Requests that may be in flight to the endpoint at once. NULL leaves concurrency unlimited';
+
+-- Reload the proxy configuration when rate limits change
+CREATE TRIGGER endpoint_rate_limits_notify
+    AFTER INSERT OR UPDATE OR DELETE ON endpoint_rate_limits
+    EXECUTE FUNCTION notify_config_change();
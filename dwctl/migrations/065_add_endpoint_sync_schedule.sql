@@ -0,0 +1,17 @@
+-- Add automatic synchronization schedules to inference endpoints
+-- Endpoints with a sync interval have their model list synchronized by the leader replica once
+-- the interval has passed since their last synchronization. The outcome of the latest
+-- synchronization, scheduled or on demand, is kept on the endpoint.
+ALTER TABLE inference_endpoints
+    ADD COLUMN sync_interval_seconds INTEGER CHECK (sync_interval_seconds > 0),
+    ADD COLUMN last_sync_at TIMESTAMPTZ,
+    ADD COLUMN last_sync_error TEXT;
+
+COMMENT ON COLUMN inference_endpoints.sync_interval_seconds IS
+'Seconds between automatic synchronizations of the endpoint. NULL only synchronizes on demand';
+
+COMMENT ON COLUMN inference_endpoints.last_sync_at IS
+'When the endpoint was last synchronized, successfully or not';
+
+COMMENT ON COLUMN inference_endpoints.last_sync_error IS
+'Why the last synchronization failed. NULL if it succeeded';
@@ -0,0 +1,12 @@
+-- Add an enabled flag to inference endpoints
+-- Disabling an endpoint removes all of its deployments from routing and pauses their probes,
+-- while keeping the deployments, aliases and group assignments so it can be turned back on.
+ALTER TABLE inference_endpoints
+ADD COLUMN enabled BOOLEAN NOT NULL DEFAULT true;
+
+COMMENT ON COLUMN inference_endpoints.enabled IS 'Whether the endpoint''s deployments are routable (false = temporarily disabled)';
+
+-- Reload the proxy configuration when an endpoint is disabled or enabled
+CREATE TRIGGER inference_endpoints_enabled_notify
+    AFTER UPDATE OF enabled ON inference_endpoints
+    EXECUTE FUNCTION notify_config_change();
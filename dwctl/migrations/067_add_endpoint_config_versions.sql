@@ -0,0 +1,45 @@
+-- Create endpoint_config_versions table
+-- Every change to an endpoint's configuration or to the aliases of its deployments is kept as a
+-- new version, recording who made it, so a bad change can be rolled back to an earlier version.
+-- API keys aren't versioned.
+CREATE TABLE IF NOT EXISTS endpoint_config_versions (
+    endpoint_id UUID NOT NULL REFERENCES inference_endpoints(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL CHECK (version > 0),
+    config JSONB NOT NULL,
+    changed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (endpoint_id, version)
+);
+
+COMMENT ON COLUMN endpoint_config_versions.config IS
+'The endpoint''s configuration and the alias of each of its deployments, by model name';
+
+COMMENT ON COLUMN endpoint_config_versions.changed_by IS
+'User who made the change. NULL if the user has since been deleted';
+
+-- Start the history of existing endpoints from their current configuration
+INSERT INTO endpoint_config_versions (endpoint_id, version, config, changed_by, created_at)
+SELECT
+    e.id,
+    1,
+    jsonb_build_object(
+        'name', e.name,
+        'description', e.description,
+        'url', e.url,
+        'model_filter', to_jsonb(e.model_filter),
+        'auth_header_name', e.auth_header_name,
+        'auth_header_prefix', e.auth_header_prefix,
+        'tls_ca_pem', e.tls_ca_pem,
+        'tls_skip_verify', e.tls_skip_verify,
+        'tls_min_version', e.tls_min_version,
+        'sync_interval_seconds', e.sync_interval_seconds,
+        'enabled', e.enabled,
+        'aliases', COALESCE(
+            (SELECT jsonb_object_agg(d.model_name, d.alias) FROM deployed_models d WHERE d.hosted_on = e.id AND NOT d.deleted),
+            '{}'::jsonb
+        )
+    ),
+    e.created_by,
+    e.updated_at
+FROM inference_endpoints e
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,10 @@
+-- Add structured metadata to deployed_models, returned in /v1/models so clients can choose
+-- models by context window and supported features. Null means unknown.
+ALTER TABLE deployed_models
+ADD COLUMN max_context_tokens INTEGER CHECK (max_context_tokens > 0),
+ADD COLUMN supports_tools BOOLEAN,
+ADD COLUMN supports_vision BOOLEAN;
+
+COMMENT ON COLUMN deployed_models.max_context_tokens IS 'Maximum number of tokens in the context window (input plus output), if known';
+COMMENT ON COLUMN deployed_models.supports_tools IS 'Whether the model supports tool/function calling, if known';
+COMMENT ON COLUMN deployed_models.supports_vision IS 'Whether the model accepts image inputs, if known';
@@ -0,0 +1,6 @@
+-- Add admin-managed tags to deployed_models (e.g. "vision", "code", "eu-hosted"), returned in
+-- /v1/models so clients can build model pickers from the gateway alone
+ALTER TABLE deployed_models
+ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN deployed_models.tags IS 'Lowercase labels describing the model, set by admins';
@@ -0,0 +1,15 @@
+-- Deprecation of deployments. A deprecated deployment keeps serving requests, with a warning in
+-- each response, until its sunset date. After it, the proxy rejects requests to its alias and
+-- points clients to the alias of its replacement.
+CREATE TABLE IF NOT EXISTS deployment_deprecations (
+    deployment_id UUID PRIMARY KEY REFERENCES deployed_models(id) ON DELETE CASCADE,
+    sunset_at TIMESTAMPTZ,
+    replacement_deployment_id UUID REFERENCES deployed_models(id) ON DELETE SET NULL,
+    deprecated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (deployment_id <> replacement_deployment_id)
+);
+
+-- Reload the proxy configuration when deprecations change
+CREATE TRIGGER deployment_deprecations_notify
+    AFTER INSERT OR UPDATE OR DELETE ON deployment_deprecations
+    EXECUTE FUNCTION notify_config_change();
@@ -0,0 +1,26 @@
+-- API key secrets can be stored encrypted (see `crypto`), with a random nonce, so they can no
+-- longer be looked up by value. Keys are looked up by a SHA-256 hash of their secret instead.
+CREATE OR REPLACE FUNCTION api_key_secret_hash(secret TEXT) RETURNS TEXT AS $$
+    SELECT encode(sha256(convert_to(secret, 'UTF8')), 'hex')
+$$ LANGUAGE SQL IMMUTABLE STRICT;
+
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS secret_hash TEXT;
+UPDATE api_keys SET secret_hash = api_key_secret_hash(secret) WHERE secret_hash IS NULL;
+ALTER TABLE api_keys ALTER COLUMN secret_hash SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_api_keys_secret_hash ON api_keys(secret_hash);
+
+-- Hash plaintext secrets as they're written. Writers of encrypted secrets set the hash of the
+-- plaintext themselves.
+CREATE OR REPLACE FUNCTION hash_api_key_secret() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.secret NOT LIKE 'enc:%' THEN
+        NEW.secret_hash := api_key_secret_hash(NEW.secret);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER api_keys_hash_secret
+    BEFORE INSERT OR UPDATE OF secret ON api_keys
+    FOR EACH ROW
+    EXECUTE FUNCTION hash_api_key_secret();
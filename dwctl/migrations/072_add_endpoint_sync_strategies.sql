@@ -0,0 +1,27 @@
+-- How synchronizing an endpoint treats changes to its upstream model list. Endpoints without a
+-- strategy disable removed models and deploy new ones.
+CREATE TABLE IF NOT EXISTS endpoint_sync_strategies (
+    endpoint_id UUID PRIMARY KEY REFERENCES inference_endpoints(id) ON DELETE CASCADE,
+    removed_models TEXT NOT NULL DEFAULT 'disable' CHECK (removed_models IN ('disable', 'delete', 'keep')),
+    new_models TEXT NOT NULL DEFAULT 'auto_add' CHECK (new_models IN ('auto_add', 'queue')),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN endpoint_sync_strategies.removed_models IS
+'What happens to deployments of models no longer listed upstream: disable (marked inactive until they return), delete, or keep (left as they are)';
+
+COMMENT ON COLUMN endpoint_sync_strategies.new_models IS
+'What happens to models newly listed upstream: auto_add (deployed) or queue (await approval in endpoint_pending_models)';
+
+-- Models found upstream by syncs of endpoints that queue new models, awaiting an admin's
+-- decision. Approved models are deployed and leave the queue; rejected ones stay, so later syncs
+-- don't queue them again.
+CREATE TABLE IF NOT EXISTS endpoint_pending_models (
+    endpoint_id UUID NOT NULL REFERENCES inference_endpoints(id) ON DELETE CASCADE,
+    model_name TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'rejected')),
+    discovered_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    rejected_at TIMESTAMPTZ,
+    rejected_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    PRIMARY KEY (endpoint_id, model_name)
+);
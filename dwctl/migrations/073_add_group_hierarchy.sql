@@ -0,0 +1,37 @@
+-- Groups can be nested under a parent group. Members of a group are also members of its
+-- descendants, and deployments granted to a group are also granted to its descendants.
+ALTER TABLE groups ADD COLUMN IF NOT EXISTS parent_id UUID REFERENCES groups(id) ON DELETE SET NULL;
+
+ALTER TABLE groups ADD CONSTRAINT groups_parent_not_self CHECK (parent_id IS NULL OR parent_id <> id);
+
+-- The Everyone group already covers every user, so it stays outside of any hierarchy
+ALTER TABLE groups ADD CONSTRAINT groups_everyone_not_nested CHECK (
+    (id <> '00000000-0000-0000-0000-000000000000' OR parent_id IS NULL)
+    AND (parent_id IS NULL OR parent_id <> '00000000-0000-0000-0000-000000000000')
+);
+
+CREATE INDEX IF NOT EXISTS idx_groups_parent_id ON groups(parent_id) WHERE parent_id IS NOT NULL;
+
+-- Every (ancestor, descendant) pair of groups, including each group paired with itself. Cycles
+-- are rejected when a parent is set, but UNION keeps the recursion finite regardless.
+CREATE OR REPLACE VIEW group_ancestry AS
+WITH RECURSIVE ancestry(ancestor_id, descendant_id) AS (
+    SELECT id, id FROM groups
+    UNION
+    SELECT a.ancestor_id, g.id
+    FROM ancestry a
+    JOIN groups g ON g.parent_id = a.descendant_id
+)
+SELECT ancestor_id, descendant_id FROM ancestry;
+
+-- Group memberships including those inherited from parent groups
+CREATE OR REPLACE VIEW effective_user_groups AS
+SELECT DISTINCT ug.user_id, ga.descendant_id AS group_id
+FROM user_groups ug
+JOIN group_ancestry ga ON ga.ancestor_id = ug.group_id;
+
+-- Deployment grants including those inherited from parent groups
+CREATE OR REPLACE VIEW effective_deployment_groups AS
+SELECT DISTINCT dg.deployment_id, ga.descendant_id AS group_id
+FROM deployment_groups dg
+JOIN group_ancestry ga ON ga.ancestor_id = dg.group_id;
@@ -0,0 +1,10 @@
+-- Free-form attributes of users (cost center, department, external ID...), as an object of
+-- string keys to string values, for filtering users and allocating spend
+ALTER TABLE users ADD COLUMN IF NOT EXISTS attributes JSONB NOT NULL DEFAULT '{}';
+
+ALTER TABLE users ADD CONSTRAINT users_attributes_strings CHECK (
+    jsonb_typeof(attributes) = 'object'
+    AND NOT jsonb_path_exists(attributes, '$.* ? (@.type() != "string")')
+);
+
+CREATE INDEX IF NOT EXISTS idx_users_attributes ON users USING GIN (attributes jsonb_path_ops);
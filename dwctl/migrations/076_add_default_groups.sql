@@ -0,0 +1,4 @@
+-- Groups that newly provisioned users join automatically
+ALTER TABLE groups ADD COLUMN IF NOT EXISTS is_default BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX IF NOT EXISTS idx_groups_is_default ON groups(is_default) WHERE is_default;
@@ -0,0 +1,7 @@
+-- Replicas forwarding AI proxy requests
+-- Each replica records itself here periodically, and counts the replicas heard from recently to
+-- enforce its share of the endpoints' upstream rate limits.
+CREATE TABLE replica_heartbeats (
+    id UUID PRIMARY KEY,
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
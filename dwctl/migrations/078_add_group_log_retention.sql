@@ -0,0 +1,9 @@
+-- Create group_log_retention table
+-- Overrides of the request log retention window for the requests of a group's members. When
+-- several apply to a user the shortest wins, and the override of the Everyone group applies to
+-- every request.
+CREATE TABLE IF NOT EXISTS group_log_retention (
+    group_id UUID PRIMARY KEY REFERENCES groups(id) ON DELETE CASCADE,
+    retention_days INTEGER NOT NULL CHECK (retention_days > 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
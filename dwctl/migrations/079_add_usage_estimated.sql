@@ -0,0 +1,3 @@
+-- Flag analytics whose token counts were estimated from the request and response text rather
+-- than reported by the upstream, so billing can tell them apart.
+ALTER TABLE http_analytics ADD COLUMN IF NOT EXISTS usage_estimated BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,4 @@
+-- Whether a group's request log retention override is a maximum (e.g. a privacy limit, after
+-- which requests are purged) or a minimum (e.g. a legal hold, before which they're kept).
+ALTER TABLE group_log_retention
+    ADD COLUMN IF NOT EXISTS kind TEXT NOT NULL DEFAULT 'maximum' CHECK (kind IN ('maximum', 'minimum'));
@@ -0,0 +1,553 @@
+//! Priority admission of AI requests when upstreams are saturated.
+//!
+//! Admins give each group a priority tier (`high`, `normal` or `low`) and users get the highest
+//! tier of their groups. When admission is enabled, the [`admit`] middleware lets up to
+//! `max_in_flight` requests through to the proxy at once; a request counts as in flight until
+//! its response body has been fully sent, so streaming completions hold their slot throughout.
+//!
+//! Requests arriving beyond that limit wait in a bounded queue. Whenever a slot frees up it is
+//! handed to the oldest waiting request of the highest tier. Requests are refused with a `503`
+//! when the queue is already full, or when they have waited longer than their tier's timeout.
+
+use crate::api::models::groups::PriorityTier;
+use crate::config::AdmissionConfig;
+use crate::db::health::timed;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry};
+use serde_json::json;
+use sqlx::PgPool;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{debug, error};
+
+/// Seconds clients are asked to wait before retrying a refused request
+const RETRY_AFTER_SECS: u64 = 5;
+
+/// Why a request was not admitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// The queue already held `max_queued` requests
+    QueueFull,
+    /// The request waited longer than its tier's timeout
+    Timeout,
+}
+
+impl Rejection {
+    fn code(&self) -> &'static str {
+        match self {
+            Rejection::QueueFull => "queue_full",
+            Rejection::Timeout => "queue_timeout",
+        }
+    }
+}
+
+/// Prometheus instruments describing the admission queue
+#[derive(Clone)]
+struct AdmissionMetrics {
+    /// Requests waiting for admission, by tier
+    queue_depth: IntGaugeVec,
+    /// Requests currently admitted
+    in_flight: IntGauge,
+    /// Requests by outcome: immediate, queued (admitted after waiting), queue_full or timeout
+    requests: IntCounterVec,
+    /// Seconds queued requests waited before being admitted or timing out, by tier
+    wait_seconds: HistogramVec,
+}
+
+impl AdmissionMetrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            queue_depth: IntGaugeVec::new(
+                Opts::new("dwctl_admission_queue_depth", "AI requests waiting for admission"),
+                &["tier"],
+            )?,
+            in_flight: IntGauge::new("dwctl_admission_in_flight", "AI requests currently admitted to the proxy")?,
+            requests: IntCounterVec::new(
+                Opts::new("dwctl_admission_requests_total", "AI requests handled by the admission queue"),
+                &["outcome"],
+            )?,
+            wait_seconds: HistogramVec::new(
+                HistogramOpts::new("dwctl_admission_wait_seconds", "Time AI requests spent waiting for admission")
+                    .buckets(vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0]),
+                &["tier"],
+            )?,
+        })
+    }
+
+    fn register(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        registry.register(Box::new(self.queue_depth.clone()))?;
+        registry.register(Box::new(self.in_flight.clone()))?;
+        registry.register(Box::new(self.requests.clone()))?;
+        registry.register(Box::new(self.wait_seconds.clone()))?;
+        Ok(())
+    }
+
+    fn observe(&self, state: &QueueState) {
+        self.in_flight.set(state.in_flight as i64);
+        for tier in PriorityTier::ALL {
+            self.queue_depth
+                .with_label_values(&[tier.as_str()])
+                .set(state.waiting[slot(tier)].len() as i64);
+        }
+    }
+}
+
+/// Index of a tier's queue in [`QueueState::waiting`]
+fn slot(tier: PriorityTier) -> usize {
+    match tier {
+        PriorityTier::High => 0,
+        PriorityTier::Normal => 1,
+        PriorityTier::Low => 2,
+    }
+}
+
+struct Waiter {
+    id: u64,
+    grant: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    in_flight: usize,
+    next_id: u64,
+    /// Waiting requests of each tier, highest tier first and oldest first within a tier
+    waiting: [VecDeque<Waiter>; 3],
+}
+
+impl QueueState {
+    fn queued(&self) -> usize {
+        self.waiting.iter().map(VecDeque::len).sum()
+    }
+}
+
+struct AdmissionQueue {
+    max_in_flight: usize,
+    max_queued: usize,
+    state: Mutex<QueueState>,
+    metrics: AdmissionMetrics,
+}
+
+impl AdmissionQueue {
+    fn new(max_in_flight: usize, max_queued: usize, metrics: AdmissionMetrics) -> Self {
+        Self {
+            max_in_flight,
+            max_queued,
+            state: Mutex::new(QueueState::default()),
+            metrics,
+        }
+    }
+
+    /// Take a slot if one is free and nobody is waiting for it
+    fn try_acquire(self: &Arc<Self>) -> Option<Permit> {
+        let mut state = self.state.lock().unwrap();
+        if state.in_flight >= self.max_in_flight || state.queued() > 0 {
+            return None;
+        }
+        state.in_flight += 1;
+        self.metrics.observe(&state);
+        Some(Permit { queue: self.clone() })
+    }
+
+    /// Take a slot, waiting up to `timeout` behind requests of the same or a higher tier
+    async fn acquire(self: &Arc<Self>, tier: PriorityTier, timeout: Duration) -> Result<Permit, Rejection> {
+        let (id, rx) = {
+            let mut state = self.state.lock().unwrap();
+            if state.in_flight < self.max_in_flight && state.queued() == 0 {
+                state.in_flight += 1;
+                self.metrics.observe(&state);
+                return Ok(Permit { queue: self.clone() });
+            }
+            if state.queued() >= self.max_queued {
+                return Err(Rejection::QueueFull);
+            }
+            let (grant, rx) = oneshot::channel();
+            let id = state.next_id;
+            state.next_id += 1;
+            state.waiting[slot(tier)].push_back(Waiter { id, grant });
+            self.metrics.observe(&state);
+            (id, rx)
+        };
+
+        let mut waiting = Waiting {
+            queue: self.clone(),
+            tier,
+            id,
+            rx,
+            admitted: false,
+        };
+        match tokio::time::timeout(timeout, &mut waiting.rx).await {
+            Ok(Ok(())) => {
+                waiting.admitted = true;
+                Ok(Permit { queue: self.clone() })
+            }
+            // The sender is only dropped after being removed from the queue without a grant,
+            // which can't happen while we're still waiting
+            Ok(Err(_)) | Err(_) => Err(Rejection::Timeout),
+        }
+    }
+
+    /// Hand a slot that is being given up to the next waiting request, or free it
+    fn release(&self, state: &mut QueueState) {
+        let handed_over = state.waiting.iter_mut().any(|waiting| {
+            while let Some(waiter) = waiting.pop_front() {
+                if waiter.grant.send(()).is_ok() {
+                    return true;
+                }
+            }
+            false
+        });
+        if !handed_over {
+            state.in_flight -= 1;
+        }
+        self.metrics.observe(state);
+    }
+}
+
+/// A request's place in the queue; leaves the queue when dropped without being admitted
+struct Waiting {
+    queue: Arc<AdmissionQueue>,
+    tier: PriorityTier,
+    id: u64,
+    rx: oneshot::Receiver<()>,
+    admitted: bool,
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        if self.admitted {
+            return;
+        }
+        let mut state = self.queue.state.lock().unwrap();
+        let waiting = &mut state.waiting[slot(self.tier)];
+        if let Some(position) = waiting.iter().position(|waiter| waiter.id == self.id) {
+            waiting.remove(position);
+            self.queue.metrics.observe(&state);
+        } else if self.rx.try_recv().is_ok() {
+            // A slot was granted just as we gave up waiting; pass it on
+            self.queue.release(&mut state);
+        }
+    }
+}
+
+/// A slot in the proxy, given up when dropped
+pub struct Permit {
+    queue: Arc<AdmissionQueue>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap();
+        self.queue.release(&mut state);
+    }
+}
+
+/// State for the [`admit`] middleware
+#[derive(Clone)]
+pub struct Admission {
+    pool: PgPool,
+    config: AdmissionConfig,
+    queue: Arc<AdmissionQueue>,
+}
+
+impl std::fmt::Debug for Admission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Admission").field("config", &self.config).finish_non_exhaustive()
+    }
+}
+
+impl Admission {
+    pub fn new(pool: PgPool, config: AdmissionConfig) -> Result<Self, prometheus::Error> {
+        let queue = AdmissionQueue::new(config.max_in_flight, config.max_queued, AdmissionMetrics::new()?);
+        Ok(Self {
+            pool,
+            config,
+            queue: Arc::new(queue),
+        })
+    }
+
+    /// Register the queue's metrics with `registry`
+    pub fn register_metrics(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        self.queue.metrics.register(registry)
+    }
+
+    /// The highest priority tier of the groups of the API key's owner, `normal` if they have none
+    async fn tier_for_key(&self, api_key: &str) -> anyhow::Result<PriorityTier> {
+        let query = sqlx::query_scalar!(
+            r#"
+            SELECT g.priority
+            FROM groups g
+            JOIN effective_user_groups ug ON ug.group_id = g.id
+            JOIN api_keys ak ON ak.user_id = ug.user_id
+            WHERE ak.secret_hash = api_key_secret_hash($1)
+            "#,
+            api_key
+        )
+        .fetch_all(&self.pool);
+        let tiers = timed("admission.tier_for_key", query).await?;
+
+        Ok(tiers.iter().map(|tier| PriorityTier::parse(tier)).max().unwrap_or_default())
+    }
+
+    fn timeout(&self, tier: PriorityTier) -> Duration {
+        let timeouts = &self.config.queue_timeout;
+        match tier {
+            PriorityTier::High => timeouts.high,
+            PriorityTier::Normal => timeouts.normal,
+            PriorityTier::Low => timeouts.low,
+        }
+    }
+
+    /// Admit a request of the caller's tier, queueing it if the proxy is saturated
+    async fn admit(&self, api_key: Option<&str>) -> Result<Permit, Rejection> {
+        let metrics = &self.queue.metrics;
+        if let Some(permit) = self.queue.try_acquire() {
+            metrics.requests.with_label_values(&["immediate"]).inc();
+            return Ok(permit);
+        }
+
+        // Only look up the caller's tier once we know they'll have to wait
+        let tier = match api_key {
+            Some(api_key) => self.tier_for_key(api_key).await.unwrap_or_else(|e| {
+                error!("Failed to look up admission priority, queueing as normal: {:#}", e);
+                PriorityTier::Normal
+            }),
+            None => PriorityTier::Normal,
+        };
+
+        let started = Instant::now();
+        let result = self.queue.acquire(tier, self.timeout(tier)).await;
+        let outcome = match &result {
+            Ok(_) => "queued",
+            Err(Rejection::QueueFull) => "queue_full",
+            Err(Rejection::Timeout) => "timeout",
+        };
+        metrics.requests.with_label_values(&[outcome]).inc();
+        if !matches!(result, Err(Rejection::QueueFull)) {
+            metrics
+                .wait_seconds
+                .with_label_values(&[tier.as_str()])
+                .observe(started.elapsed().as_secs_f64());
+        }
+        debug!("Admission of {} priority request: {}", tier.as_str(), outcome);
+        result
+    }
+}
+
+impl std::fmt::Debug for Permit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Permit").finish_non_exhaustive()
+    }
+}
+
+/// Middleware that limits the AI requests in flight, admitting queued requests by priority
+pub async fn admit(State(admission): State<Admission>, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let api_key = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+
+    let permit = match admission.admit(api_key.as_deref()).await {
+        Ok(permit) => permit,
+        Err(rejection) => return rejected_response(rejection),
+    };
+
+    // Hold the slot until the response body has been sent, so streams count as in flight
+    let (parts, body) = next.run(request).await.into_parts();
+    let body = body.into_data_stream().map(move |chunk| {
+        let _permit = &permit;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+fn rejected_response(rejection: Rejection) -> Response {
+    let message = match rejection {
+        Rejection::QueueFull => "The service is at capacity and the request queue is full, please retry later",
+        Rejection::Timeout => "The service is at capacity and the request timed out waiting to be processed, please retry later",
+    };
+    let error = json!({"message": message, "type": "server_error", "param": null, "code": rejection.code()});
+    let mut response = (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": error }))).into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SECS));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::users::Role;
+    use crate::db::handlers::{Groups, Repository};
+    use crate::db::models::groups::GroupUpdateDBRequest;
+    use crate::test_utils::*;
+    use axum::middleware::from_fn_with_state;
+    use axum::{routing::post, Router};
+    use axum_test::TestServer;
+    use serde_json::Value;
+
+    fn queue(max_in_flight: usize, max_queued: usize) -> Arc<AdmissionQueue> {
+        Arc::new(AdmissionQueue::new(max_in_flight, max_queued, AdmissionMetrics::new().unwrap()))
+    }
+
+    async fn wait_for_queued(queue: &AdmissionQueue, queued: usize) {
+        while queue.state.lock().unwrap().queued() != queued {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slots_go_to_highest_tier_first() {
+        let queue = queue(1, 10);
+        let held = queue.try_acquire().unwrap();
+        assert!(queue.try_acquire().is_none());
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        for (queued, tier) in [PriorityTier::Low, PriorityTier::Normal, PriorityTier::Low, PriorityTier::High]
+            .into_iter()
+            .enumerate()
+        {
+            tokio::spawn({
+                let (queue, tx) = (queue.clone(), tx.clone());
+                async move {
+                    let _permit = queue.acquire(tier, Duration::from_secs(10)).await.unwrap();
+                    tx.send(tier).unwrap();
+                }
+            });
+            wait_for_queued(&queue, queued + 1).await;
+        }
+        assert_eq!(queue.metrics.queue_depth.with_label_values(&["low"]).get(), 2);
+
+        drop(held);
+        let mut order = Vec::new();
+        for _ in 0..4 {
+            order.push(rx.recv().await.unwrap());
+        }
+        assert_eq!(
+            order,
+            [PriorityTier::High, PriorityTier::Normal, PriorityTier::Low, PriorityTier::Low]
+        );
+
+        // Every slot has been given back
+        wait_for_queued(&queue, 0).await;
+        assert_eq!(queue.state.lock().unwrap().in_flight, 0);
+        assert_eq!(queue.metrics.in_flight.get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_rejects_immediately() {
+        let queue = queue(1, 1);
+        let _held = queue.try_acquire().unwrap();
+
+        let waiter = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(PriorityTier::Low, Duration::from_secs(10)).await.map(drop) }
+        });
+        wait_for_queued(&queue, 1).await;
+
+        let rejection = queue.acquire(PriorityTier::High, Duration::from_secs(10)).await.unwrap_err();
+        assert_eq!(rejection, Rejection::QueueFull);
+        waiter.abort();
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_requests_leave_the_queue() {
+        let queue = queue(1, 10);
+        let held = queue.try_acquire().unwrap();
+
+        let rejection = queue.acquire(PriorityTier::Normal, Duration::from_millis(20)).await.unwrap_err();
+        assert_eq!(rejection, Rejection::Timeout);
+        assert_eq!(queue.state.lock().unwrap().queued(), 0);
+
+        // Cancelled waits leave the queue too
+        let waiter = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(PriorityTier::High, Duration::from_secs(10)).await.map(drop) }
+        });
+        wait_for_queued(&queue, 1).await;
+        waiter.abort();
+        let _ = waiter.await;
+        assert_eq!(queue.state.lock().unwrap().queued(), 0);
+
+        drop(held);
+        assert_eq!(queue.state.lock().unwrap().in_flight, 0);
+        assert!(queue.try_acquire().is_some());
+    }
+
+    #[sqlx::test]
+    async fn test_tier_is_highest_of_users_groups(pool: PgPool) {
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let ungrouped = create_test_user(&pool, Role::StandardUser).await;
+        let low = create_test_group(&pool).await;
+        let high = create_test_group(&pool).await;
+        add_user_to_group(&pool, user.id, low.id).await;
+        let key = create_test_api_key_for_user(&pool, user.id).await;
+        let ungrouped_key = create_test_api_key_for_user(&pool, ungrouped.id).await;
+
+        let mut conn = pool.acquire().await.unwrap();
+        let mut groups = Groups::new(&mut conn);
+        for (group, tier) in [(low.id, "low"), (high.id, "high")] {
+            let update = GroupUpdateDBRequest {
+                name: None,
+                description: None,
+                priority: Some(tier.to_string()),
+                parent_id: None,
+                is_default: None,
+            };
+            groups.update(group, &update).await.unwrap();
+        }
+
+        let admission = Admission::new(pool.clone(), AdmissionConfig::default()).unwrap();
+        assert_eq!(admission.tier_for_key(&key.secret).await.unwrap(), PriorityTier::Low);
+        assert_eq!(admission.tier_for_key(&ungrouped_key.secret).await.unwrap(), PriorityTier::Normal);
+        assert_eq!(admission.tier_for_key("not-a-key").await.unwrap(), PriorityTier::Normal);
+
+        add_user_to_group(&pool, user.id, high.id).await;
+        assert_eq!(admission.tier_for_key(&key.secret).await.unwrap(), PriorityTier::High);
+    }
+
+    #[sqlx::test]
+    async fn test_middleware_rejects_when_saturated(pool: PgPool) {
+        let config = AdmissionConfig {
+            enabled: true,
+            max_in_flight: 1,
+            max_queued: 0,
+            ..Default::default()
+        };
+        let admission = Admission::new(pool, config).unwrap();
+        let app = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|| async { Json(json!({"object": "chat.completion"})) }),
+            )
+            .layer(from_fn_with_state(admission.clone(), admit));
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.post("/v1/chat/completions").json(&json!({"model": "m"})).await;
+        response.assert_status_ok();
+        // The slot is given back once the response body has been sent
+        assert_eq!(admission.queue.state.lock().unwrap().in_flight, 0);
+
+        let _held = admission.queue.try_acquire().unwrap();
+        let response = server.post("/v1/chat/completions").json(&json!({"model": "m"})).await;
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.header(header::RETRY_AFTER), "5");
+        let error: Value = response.json();
+        assert_eq!(error["error"]["code"], "queue_full");
+        assert_eq!(admission.queue.metrics.requests.with_label_values(&["queue_full"]).get(), 1);
+    }
+}
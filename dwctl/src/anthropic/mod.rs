@@ -0,0 +1,314 @@
+//! The Anthropic Messages API, served on `/ai/v1/messages`.
+//!
+//! Clients using the Anthropic SDK send `POST /v1/messages` with their key in the `x-api-key`
+//! header rather than as a bearer token. The [`accept_api_key`] middleware moves it into the
+//! `Authorization` header, so these requests go through the same access control, admission and
+//! moderation as every other AI request, and the request logger attributes them to the key's user.
+//!
+//! Requests for models served by an Anthropic endpoint (recognised by their host, as model sync
+//! does) are passed through unchanged. Requests for any other model are rewritten by
+//! [`translate_messages`] as chat completions, and their responses rewritten back into messages,
+//! with streamed chat completion chunks mapped onto Messages API stream events (see [`translate`]).
+
+pub mod translate;
+
+use crate::routing::RoutingTable;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use onwards::target::ConfigFile;
+use serde_json::{json, Value};
+use tokio::sync::watch;
+use tracing::debug;
+use url::Url;
+
+/// Path of the Messages API, relative to `/ai/v1`
+pub const MESSAGES_PATH: &str = "/messages";
+
+/// Header the Anthropic SDK sends API keys in
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+const ANTHROPIC_HOST: &str = "api.anthropic.com";
+
+/// Path chat completions are sent to, relative to `/ai/v1`
+const CHAT_COMPLETIONS_PATH: &str = "/chat/completions";
+
+/// Whether `url` points at the Anthropic API
+pub fn is_anthropic_url(url: &Url) -> bool {
+    url.host_str() == Some(ANTHROPIC_HOST)
+}
+
+/// Aliases of the targets in `config` served by an Anthropic endpoint
+pub fn anthropic_targets(config: &ConfigFile) -> Vec<String> {
+    config
+        .targets
+        .iter()
+        .filter(|(_, target)| is_anthropic_url(&target.url))
+        .map(|(alias, _)| alias.clone())
+        .collect()
+}
+
+/// Middleware accepting API keys sent in the `x-api-key` header, by moving them into
+/// `Authorization: Bearer` for requests that don't already carry one
+pub async fn accept_api_key(mut request: Request, next: Next) -> Response {
+    if !request.headers().contains_key(header::AUTHORIZATION) {
+        let bearer = request
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|key| HeaderValue::from_str(&format!("Bearer {key}")).ok());
+        if let Some(bearer) = bearer {
+            request.headers_mut().insert(header::AUTHORIZATION, bearer);
+        }
+    }
+    next.run(request).await
+}
+
+/// Middleware rewriting Messages API requests for models not served by an Anthropic endpoint as
+/// chat completions, and their responses back into messages.
+///
+/// Runs inside `routing::fallback_routing`, so each attempt is translated for the target it is
+/// actually sent to.
+pub async fn translate_messages(State(table): State<watch::Receiver<RoutingTable>>, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST || request.uri().path() != MESSAGES_PATH {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => return error_response(StatusCode::BAD_REQUEST, "Failed to read request body"),
+    };
+    let Ok(model) = onwards::extract_model_from_request(&parts.headers, &body) else {
+        // Let onwards produce its usual error for requests without a model
+        return next.run(Request::from_parts(parts, Body::from(body))).await;
+    };
+    if table.borrow().is_anthropic(&model) {
+        return next.run(Request::from_parts(parts, Body::from(body))).await;
+    }
+
+    let chat_request = match serde_json::from_slice::<Value>(&body)
+        .map_err(|_| "Request body is not valid JSON".to_string())
+        .and_then(|message_request| translate::chat_request(&message_request))
+    {
+        Ok(chat_request) => chat_request,
+        Err(message) => return error_response(StatusCode::BAD_REQUEST, &message),
+    };
+    debug!("Translating Messages API request for '{}' to a chat completion", model);
+
+    let stream = chat_request["stream"].as_bool().unwrap_or(false);
+    let Some(uri) = with_path(&parts.uri, CHAT_COMPLETIONS_PATH) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid request URI");
+    };
+    parts.uri = uri;
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let body = Body::from(serde_json::to_vec(&chat_request).unwrap_or_default());
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+    message_response(response, stream).await
+}
+
+/// Rewrite the chat completion `response` as a Messages API response
+async fn message_response(response: Response, stream: bool) -> Response {
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    if stream && parts.status.is_success() {
+        parts
+            .headers
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+        return Response::from_parts(parts, translate::message_stream(body));
+    }
+
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => return error_response(StatusCode::BAD_GATEWAY, "Failed to read upstream response"),
+    };
+    let Ok(chat_response) = serde_json::from_slice::<Value>(&body) else {
+        // Not JSON, so nothing to translate
+        return Response::from_parts(parts, Body::from(body));
+    };
+    let message = if parts.status.is_success() {
+        translate::message(&chat_response)
+    } else {
+        let message = chat_response["error"]["message"].as_str().unwrap_or("Upstream request failed");
+        error_body(parts.status, message)
+    };
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Response::from_parts(parts, Body::from(message.to_string()))
+}
+
+/// `uri` with its path replaced by `path`, keeping its query
+fn with_path(uri: &Uri, path: &str) -> Option<Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+/// Messages API error type for a response status
+fn error_type(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "invalid_request_error",
+        StatusCode::UNAUTHORIZED => "authentication_error",
+        StatusCode::FORBIDDEN => "permission_error",
+        StatusCode::NOT_FOUND => "not_found_error",
+        StatusCode::PAYLOAD_TOO_LARGE => "request_too_large",
+        StatusCode::TOO_MANY_REQUESTS => "rate_limit_error",
+        StatusCode::SERVICE_UNAVAILABLE => "overloaded_error",
+        status if status.is_client_error() => "invalid_request_error",
+        _ => "api_error",
+    }
+}
+
+fn error_body(status: StatusCode, message: &str) -> Value {
+    json!({"type": "error", "error": {"type": error_type(status), "message": message}})
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(error_body(status, message))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::HeaderMap,
+        middleware::{from_fn, from_fn_with_state},
+        routing::post,
+        Router,
+    };
+    use axum_test::TestServer;
+
+    /// An upstream answering chat completions, and echoing Messages API requests with the
+    /// `Authorization` header they arrived with. Like onwards, it serves every path from one
+    /// route, so the path it sees is the one the middleware rewrote the request to.
+    fn upstream() -> Router {
+        Router::new().route(
+            "/{*path}",
+            post(|uri: Uri, headers: HeaderMap, Json(request): Json<Value>| async move {
+                if uri.path() == MESSAGES_PATH {
+                    let authorization = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()).map(str::to_string);
+                    return Json(json!({"passed_through": request, "authorization": authorization})).into_response();
+                }
+                if request["stream"] == json!(true) {
+                    let chunks = [
+                        json!({"id": "chatcmpl-1", "model": request["model"], "choices": [{"index": 0, "delta": {"role": "assistant", "content": "Hel"}}]}),
+                        json!({"id": "chatcmpl-1", "model": request["model"], "choices": [{"index": 0, "delta": {"content": "lo"}, "finish_reason": "stop"}]}),
+                        json!({"id": "chatcmpl-1", "model": request["model"], "choices": [], "usage": {"prompt_tokens": 12, "completion_tokens": 2}}),
+                    ];
+                    let body: String = chunks.iter().map(|chunk| format!("data: {chunk}\n\n")).collect();
+                    return ([(header::CONTENT_TYPE, "text/event-stream")], format!("{body}data: [DONE]\n\n")).into_response();
+                }
+                if request["max_tokens"] == json!(0) {
+                    let error = json!({"error": {"message": "max_tokens must be positive", "type": "invalid_request_error"}});
+                    return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+                }
+                Json(json!({
+                    "id": "chatcmpl-1",
+                    "object": "chat.completion",
+                    "model": request["model"],
+                    "choices": [{"index": 0, "message": {"role": "assistant", "content": request["messages"][0]["content"]}, "finish_reason": "length"}],
+                    "usage": {"prompt_tokens": 12, "completion_tokens": 5, "total_tokens": 17},
+                }))
+                .into_response()
+            }),
+        )
+    }
+
+    fn server() -> TestServer {
+        let mut table = RoutingTable::default();
+        table.set_anthropic("claude".to_string());
+        let (_, receiver) = watch::channel(table);
+        let router = upstream()
+            .layer(from_fn_with_state(receiver, translate_messages))
+            .layer(from_fn(accept_api_key));
+        TestServer::new(router).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_targets_are_passed_through() {
+        let request = json!({"model": "claude", "max_tokens": 16, "messages": [{"role": "user", "content": "Hi"}]});
+        let response = server()
+            .post("/messages")
+            .add_header(API_KEY_HEADER, "sk-test")
+            .json(&request)
+            .await;
+
+        let body: Value = response.json();
+        assert_eq!(body["passed_through"], request);
+        assert_eq!(body["authorization"], "Bearer sk-test");
+    }
+
+    #[tokio::test]
+    async fn test_other_targets_are_translated() {
+        let response = server()
+            .post("/messages")
+            .json(&json!({"model": "gpt-4o", "max_tokens": 16, "system": "Be brief", "messages": [{"role": "user", "content": "Hi"}]}))
+            .await;
+
+        let body: Value = response.json();
+        assert_eq!(body["type"], "message");
+        assert_eq!(body["model"], "gpt-4o");
+        // The upstream echoes the first message, which is the system prompt
+        assert_eq!(body["content"], json!([{"type": "text", "text": "Be brief"}]));
+        assert_eq!(body["stop_reason"], "max_tokens");
+        assert_eq!(body["usage"], json!({"input_tokens": 12, "output_tokens": 5}));
+
+        let response = server()
+            .post("/messages")
+            .json(&json!({"model": "gpt-4o", "max_tokens": 0, "messages": [{"role": "user", "content": "Hi"}]}))
+            .await;
+        response.assert_status_bad_request();
+        assert_eq!(
+            response.json::<Value>(),
+            json!({"type": "error", "error": {"type": "invalid_request_error", "message": "max_tokens must be positive"}})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streamed_responses_are_mapped_to_message_events() {
+        let response = server()
+            .post("/messages")
+            .json(&json!({"model": "gpt-4o", "max_tokens": 16, "stream": true, "messages": [{"role": "user", "content": "Hi"}]}))
+            .await;
+
+        assert_eq!(response.header(header::CONTENT_TYPE), "text/event-stream");
+        let events: Vec<String> = response
+            .text()
+            .lines()
+            .filter_map(|line| line.strip_prefix("event: ").map(str::to_string))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop"
+            ]
+        );
+        let message_delta = response
+            .text()
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str::<Value>(data).unwrap())
+            .find(|event| event["type"] == "message_delta")
+            .unwrap();
+        assert_eq!(message_delta["delta"]["stop_reason"], "end_turn");
+        assert_eq!(message_delta["usage"], json!({"input_tokens": 12, "output_tokens": 2}));
+    }
+}
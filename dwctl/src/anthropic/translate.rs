@@ -0,0 +1,516 @@
+//! Translation between Messages API and chat completion requests and responses.
+//!
+//! Covers text, images, tool use and tool results. Content the chat completions API has no
+//! equivalent for, such as thinking blocks, is dropped from requests.
+//!
+//! Streamed chat completions are mapped onto Messages API stream events as they arrive: the
+//! first chunk opens the message (`message_start`), content and tool call deltas open, fill and
+//! close content blocks (`content_block_start`, `content_block_delta`, `content_block_stop`), and
+//! the end of the stream closes the message with its stop reason and usage (`message_delta`,
+//! `message_stop`). The request asks the upstream to include usage in its last chunk, so that
+//! `message_delta` can report it.
+
+use axum::body::{Body, Bytes};
+use futures::StreamExt;
+use serde_json::{json, Map, Value};
+
+/// Rewrite a Messages API request as a chat completion request
+pub fn chat_request(request: &Value) -> Result<Value, String> {
+    let request = request.as_object().ok_or("Request body must be a JSON object")?;
+
+    let mut messages = Vec::new();
+    match request.get("system") {
+        Some(Value::String(system)) => messages.push(json!({"role": "system", "content": system})),
+        Some(Value::Array(blocks)) => messages.push(json!({"role": "system", "content": block_text(blocks)})),
+        _ => {}
+    }
+    let input = request
+        .get("messages")
+        .and_then(Value::as_array)
+        .ok_or("`messages` must be an array of messages")?;
+    for message in input {
+        let role = message["role"].as_str().unwrap_or("user");
+        match &message["content"] {
+            Value::String(content) => messages.push(json!({"role": role, "content": content})),
+            Value::Array(blocks) => messages.extend(chat_messages(role, blocks)),
+            _ => return Err("Message `content` must be a string or an array of content blocks".to_string()),
+        }
+    }
+
+    let mut chat = Map::new();
+    chat.insert("model".to_string(), request.get("model").cloned().unwrap_or_default());
+    chat.insert("messages".to_string(), Value::Array(messages));
+    for (from, to) in [
+        ("max_tokens", "max_tokens"),
+        ("temperature", "temperature"),
+        ("top_p", "top_p"),
+        ("stop_sequences", "stop"),
+    ] {
+        if let Some(value) = request.get(from) {
+            chat.insert(to.to_string(), value.clone());
+        }
+    }
+    if let Some(user) = request.get("metadata").and_then(|metadata| metadata.get("user_id")) {
+        chat.insert("user".to_string(), user.clone());
+    }
+    if request.get("stream").and_then(Value::as_bool) == Some(true) {
+        chat.insert("stream".to_string(), Value::Bool(true));
+        chat.insert("stream_options".to_string(), json!({"include_usage": true}));
+    }
+    if let Some(tools) = request.get("tools").and_then(Value::as_array) {
+        let tools = tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "type": "function",
+                    "function": {"name": tool["name"], "description": tool["description"], "parameters": tool["input_schema"]},
+                })
+            })
+            .collect();
+        chat.insert("tools".to_string(), Value::Array(tools));
+    }
+    if let Some(tool_choice) = request.get("tool_choice") {
+        let choice = match tool_choice["type"].as_str() {
+            Some("any") => json!("required"),
+            Some("none") => json!("none"),
+            Some("tool") => json!({"type": "function", "function": {"name": tool_choice["name"]}}),
+            _ => json!("auto"),
+        };
+        chat.insert("tool_choice".to_string(), choice);
+        if tool_choice["disable_parallel_tool_use"].as_bool() == Some(true) {
+            chat.insert("parallel_tool_calls".to_string(), Value::Bool(false));
+        }
+    }
+
+    Ok(Value::Object(chat))
+}
+
+/// The chat messages for a message with content blocks. Tool results become messages of their
+/// own, ahead of the rest of the content, as they must directly follow the assistant message
+/// that made the tool calls.
+fn chat_messages(role: &str, blocks: &[Value]) -> Vec<Value> {
+    let mut messages = Vec::new();
+    let mut parts = Vec::new();
+    let mut tool_calls = Vec::new();
+
+    for block in blocks {
+        match block["type"].as_str() {
+            Some("text") => parts.push(json!({"type": "text", "text": block["text"]})),
+            Some("image") => {
+                let source = &block["source"];
+                let url = match source["type"].as_str() {
+                    Some("url") => source["url"].as_str().unwrap_or_default().to_string(),
+                    _ => format!(
+                        "data:{};base64,{}",
+                        source["media_type"].as_str().unwrap_or_default(),
+                        source["data"].as_str().unwrap_or_default()
+                    ),
+                };
+                parts.push(json!({"type": "image_url", "image_url": {"url": url}}));
+            }
+            Some("tool_use") => tool_calls.push(json!({
+                "id": block["id"],
+                "type": "function",
+                "function": {"name": block["name"], "arguments": block["input"].to_string()},
+            })),
+            Some("tool_result") => {
+                let content = match &block["content"] {
+                    Value::String(content) => content.clone(),
+                    Value::Array(blocks) => block_text(blocks),
+                    _ => String::new(),
+                };
+                messages.push(json!({"role": "tool", "tool_call_id": block["tool_use_id"], "content": content}));
+            }
+            _ => {}
+        }
+    }
+
+    if role == "assistant" {
+        let text = block_text(&parts);
+        let mut message = json!({"role": "assistant", "content": if text.is_empty() { Value::Null } else { Value::String(text) }});
+        if !tool_calls.is_empty() {
+            message["tool_calls"] = Value::Array(tool_calls);
+        }
+        messages.push(message);
+    } else if !parts.is_empty() {
+        messages.push(json!({"role": role, "content": parts}));
+    }
+    messages
+}
+
+/// The text of the text blocks in `blocks`
+fn block_text(blocks: &[Value]) -> String {
+    blocks
+        .iter()
+        .filter(|block| block["type"] == "text")
+        .filter_map(|block| block["text"].as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Messages API stop reason for a chat completion finish reason
+fn stop_reason(finish_reason: &str) -> &'static str {
+    match finish_reason {
+        "length" => "max_tokens",
+        "tool_calls" | "function_call" => "tool_use",
+        "content_filter" => "refusal",
+        _ => "end_turn",
+    }
+}
+
+/// Tool call arguments as a JSON object, or an empty object if the model produced invalid JSON
+fn tool_input(arguments: &Value) -> Value {
+    arguments
+        .as_str()
+        .and_then(|arguments| serde_json::from_str(arguments).ok())
+        .unwrap_or_else(|| json!({}))
+}
+
+/// Rewrite a chat completion as a Messages API response
+pub fn message(chat: &Value) -> Value {
+    let choice = &chat["choices"][0];
+    let mut content = Vec::new();
+    if let Some(text) = choice["message"]["content"].as_str().filter(|text| !text.is_empty()) {
+        content.push(json!({"type": "text", "text": text}));
+    }
+    for call in choice["message"]["tool_calls"].as_array().into_iter().flatten() {
+        content.push(json!({
+            "type": "tool_use",
+            "id": call["id"],
+            "name": call["function"]["name"],
+            "input": tool_input(&call["function"]["arguments"]),
+        }));
+    }
+
+    json!({
+        "id": chat["id"],
+        "type": "message",
+        "role": "assistant",
+        "model": chat["model"],
+        "content": content,
+        "stop_reason": stop_reason(choice["finish_reason"].as_str().unwrap_or_default()),
+        "stop_sequence": null,
+        "usage": {
+            "input_tokens": chat["usage"]["prompt_tokens"].as_u64().unwrap_or_default(),
+            "output_tokens": chat["usage"]["completion_tokens"].as_u64().unwrap_or_default(),
+        },
+    })
+}
+
+/// Map a streamed chat completion body onto a stream of Messages API events
+pub fn message_stream(body: Body) -> Body {
+    let events = futures::stream::unfold(
+        (body.into_data_stream(), MessageStream::default(), false),
+        |(mut upstream, mut stream, done)| async move {
+            if done {
+                return None;
+            }
+            match upstream.next().await {
+                Some(Ok(bytes)) => {
+                    let events = stream.push(&bytes);
+                    Some((Ok(Bytes::from(events)), (upstream, stream, false)))
+                }
+                Some(Err(e)) => Some((Err(e), (upstream, stream, true))),
+                None => {
+                    let mut events = String::new();
+                    stream.finish(&mut events);
+                    Some((Ok(Bytes::from(events)), (upstream, stream, true)))
+                }
+            }
+        },
+    );
+    Body::from_stream(events)
+}
+
+/// The content block of a streamed message currently being filled
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OpenBlock {
+    Text,
+    /// A tool use block, filled by the tool call with this index
+    ToolUse(u64),
+}
+
+/// State of the mapping of one streamed chat completion onto Messages API events
+#[derive(Debug, Default)]
+struct MessageStream {
+    /// Bytes of an incomplete line of the chat completion stream
+    buffer: Vec<u8>,
+    started: bool,
+    finished: bool,
+    open_block: Option<OpenBlock>,
+    /// Index of the next content block
+    next_block: usize,
+    stop_reason: Option<&'static str>,
+    usage: Option<Value>,
+}
+
+impl MessageStream {
+    /// The events for the next bytes of the chat completion stream
+    fn push(&mut self, bytes: &[u8]) -> String {
+        self.buffer.extend_from_slice(bytes);
+        let mut events = String::new();
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                continue;
+            };
+            if data == "[DONE]" {
+                self.finish(&mut events);
+            } else if let Ok(chunk) = serde_json::from_str::<Value>(data) {
+                self.chunk(&chunk, &mut events);
+            }
+        }
+        events
+    }
+
+    fn chunk(&mut self, chunk: &Value, events: &mut String) {
+        if self.finished {
+            return;
+        }
+        if !self.started {
+            self.started = true;
+            let message = json!({
+                "id": chunk["id"],
+                "type": "message",
+                "role": "assistant",
+                "model": chunk["model"],
+                "content": [],
+                "stop_reason": null,
+                "stop_sequence": null,
+                "usage": {"input_tokens": 0, "output_tokens": 0},
+            });
+            event(events, json!({"type": "message_start", "message": message}));
+        }
+        if let Some(usage) = chunk.get("usage").filter(|usage| usage.is_object()) {
+            self.usage = Some(usage.clone());
+        }
+
+        let Some(choice) = chunk["choices"].as_array().and_then(|choices| choices.first()) else {
+            return;
+        };
+        let delta = &choice["delta"];
+        if let Some(text) = delta["content"].as_str().filter(|text| !text.is_empty()) {
+            if self.open_block != Some(OpenBlock::Text) {
+                self.open(OpenBlock::Text, json!({"type": "text", "text": ""}), events);
+            }
+            self.delta(json!({"type": "text_delta", "text": text}), events);
+        }
+        for call in delta["tool_calls"].as_array().into_iter().flatten() {
+            let index = call["index"].as_u64().unwrap_or_default();
+            if call.get("id").is_some_and(|id| id.is_string()) || self.open_block != Some(OpenBlock::ToolUse(index)) {
+                let block = json!({"type": "tool_use", "id": call["id"], "name": call["function"]["name"], "input": {}});
+                self.open(OpenBlock::ToolUse(index), block, events);
+            }
+            if let Some(arguments) = call["function"]["arguments"].as_str().filter(|arguments| !arguments.is_empty()) {
+                self.delta(json!({"type": "input_json_delta", "partial_json": arguments}), events);
+            }
+        }
+        if let Some(finish_reason) = choice["finish_reason"].as_str() {
+            self.stop_reason = Some(stop_reason(finish_reason));
+        }
+    }
+
+    /// Close the open content block, if any, and open `block`
+    fn open(&mut self, open_block: OpenBlock, block: Value, events: &mut String) {
+        self.close(events);
+        event(
+            events,
+            json!({"type": "content_block_start", "index": self.next_block, "content_block": block}),
+        );
+        self.open_block = Some(open_block);
+        self.next_block += 1;
+    }
+
+    fn delta(&self, delta: Value, events: &mut String) {
+        event(
+            events,
+            json!({"type": "content_block_delta", "index": self.next_block - 1, "delta": delta}),
+        );
+    }
+
+    fn close(&mut self, events: &mut String) {
+        if self.open_block.take().is_some() {
+            event(events, json!({"type": "content_block_stop", "index": self.next_block - 1}));
+        }
+    }
+
+    /// The events ending the message, once the chat completion stream has ended
+    fn finish(&mut self, events: &mut String) {
+        if self.finished || !self.started {
+            return;
+        }
+        self.finished = true;
+        self.close(events);
+
+        let usage = self.usage.take().unwrap_or_default();
+        event(
+            events,
+            json!({
+                "type": "message_delta",
+                "delta": {"stop_reason": self.stop_reason.unwrap_or("end_turn"), "stop_sequence": null},
+                "usage": {
+                    "input_tokens": usage["prompt_tokens"].as_u64().unwrap_or_default(),
+                    "output_tokens": usage["completion_tokens"].as_u64().unwrap_or_default(),
+                },
+            }),
+        );
+        event(events, json!({"type": "message_stop"}));
+    }
+}
+
+/// Append `data` to `events` as a server-sent event named after its type
+fn event(events: &mut String, data: Value) {
+    let name = data["type"].as_str().unwrap_or_default();
+    events.push_str(&format!("event: {name}\ndata: {data}\n\n"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The events in a stream of server-sent events
+    fn parse_events(events: &str) -> Vec<Value> {
+        events
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_chat_request() {
+        let request = json!({
+            "model": "gpt-4o",
+            "max_tokens": 256,
+            "system": [{"type": "text", "text": "You are terse."}],
+            "stop_sequences": ["END"],
+            "stream": true,
+            "metadata": {"user_id": "user-1"},
+            "tools": [{"name": "weather", "description": "Get the weather", "input_schema": {"type": "object"}}],
+            "tool_choice": {"type": "tool", "name": "weather", "disable_parallel_tool_use": true},
+            "messages": [
+                {"role": "user", "content": [
+                    {"type": "text", "text": "What's the weather here?"},
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBOR"}},
+                ]},
+                {"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "I should call the tool"},
+                    {"type": "tool_use", "id": "toolu_1", "name": "weather", "input": {"city": "Paris"}},
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": [{"type": "text", "text": "Sunny"}]},
+                    {"type": "text", "text": "Thanks"},
+                ]},
+            ],
+        });
+
+        assert_eq!(
+            chat_request(&request).unwrap(),
+            json!({
+                "model": "gpt-4o",
+                "max_tokens": 256,
+                "stop": ["END"],
+                "user": "user-1",
+                "stream": true,
+                "stream_options": {"include_usage": true},
+                "tools": [{"type": "function", "function": {"name": "weather", "description": "Get the weather", "parameters": {"type": "object"}}}],
+                "tool_choice": {"type": "function", "function": {"name": "weather"}},
+                "parallel_tool_calls": false,
+                "messages": [
+                    {"role": "system", "content": "You are terse."},
+                    {"role": "user", "content": [
+                        {"type": "text", "text": "What's the weather here?"},
+                        {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBOR"}},
+                    ]},
+                    {"role": "assistant", "content": null, "tool_calls": [
+                        {"id": "toolu_1", "type": "function", "function": {"name": "weather", "arguments": "{\"city\":\"Paris\"}"}},
+                    ]},
+                    {"role": "tool", "tool_call_id": "toolu_1", "content": "Sunny"},
+                    {"role": "user", "content": [{"type": "text", "text": "Thanks"}]},
+                ],
+            })
+        );
+
+        assert!(chat_request(&json!({"model": "gpt-4o"})).is_err());
+        assert!(chat_request(&json!({"model": "gpt-4o", "messages": [{"role": "user", "content": 1}]})).is_err());
+    }
+
+    #[test]
+    fn test_message() {
+        let chat = json!({
+            "id": "chatcmpl-1",
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Let me check.", "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "weather", "arguments": "{\"city\":\"Paris\"}"}},
+                ]},
+                "finish_reason": "tool_calls",
+            }],
+            "usage": {"prompt_tokens": 20, "completion_tokens": 8, "total_tokens": 28},
+        });
+
+        assert_eq!(
+            message(&chat),
+            json!({
+                "id": "chatcmpl-1",
+                "type": "message",
+                "role": "assistant",
+                "model": "gpt-4o",
+                "content": [
+                    {"type": "text", "text": "Let me check."},
+                    {"type": "tool_use", "id": "call_1", "name": "weather", "input": {"city": "Paris"}},
+                ],
+                "stop_reason": "tool_use",
+                "stop_sequence": null,
+                "usage": {"input_tokens": 20, "output_tokens": 8},
+            })
+        );
+    }
+
+    #[test]
+    fn test_message_stream_maps_text_and_tool_calls() {
+        let chunks = [
+            json!({"id": "chatcmpl-1", "model": "gpt-4o", "choices": [{"index": 0, "delta": {"role": "assistant", "content": "Checking"}}]}),
+            json!({"id": "chatcmpl-1", "model": "gpt-4o", "choices": [{"index": 0, "delta": {"tool_calls": [
+                {"index": 0, "id": "call_1", "type": "function", "function": {"name": "weather", "arguments": ""}},
+            ]}}]}),
+            json!({"id": "chatcmpl-1", "model": "gpt-4o", "choices": [{"index": 0, "delta": {"tool_calls": [
+                {"index": 0, "function": {"arguments": "{\"city\":"}},
+            ]}}]}),
+            json!({"id": "chatcmpl-1", "model": "gpt-4o", "choices": [{"index": 0, "delta": {"tool_calls": [
+                {"index": 0, "function": {"arguments": "\"Paris\"}"}},
+            ]}, "finish_reason": "tool_calls"}]}),
+            json!({"id": "chatcmpl-1", "model": "gpt-4o", "choices": [], "usage": {"prompt_tokens": 20, "completion_tokens": 8}}),
+        ];
+        let body: String = chunks.iter().map(|chunk| format!("data: {chunk}\n\n")).collect();
+
+        // Feed the stream in pieces that split lines, to check they are buffered
+        let mut stream = MessageStream::default();
+        let mut events = String::new();
+        for piece in format!("{body}data: [DONE]\n\n").as_bytes().chunks(7) {
+            events.push_str(&stream.push(piece));
+        }
+        stream.finish(&mut events);
+
+        assert_eq!(
+            parse_events(&events),
+            vec![
+                json!({"type": "message_start", "message": {
+                    "id": "chatcmpl-1", "type": "message", "role": "assistant", "model": "gpt-4o", "content": [],
+                    "stop_reason": null, "stop_sequence": null, "usage": {"input_tokens": 0, "output_tokens": 0},
+                }}),
+                json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+                json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Checking"}}),
+                json!({"type": "content_block_stop", "index": 0}),
+                json!({"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "call_1", "name": "weather", "input": {}}}),
+                json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"city\":"}}),
+                json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "\"Paris\"}"}}),
+                json!({"type": "content_block_stop", "index": 1}),
+                json!({"type": "message_delta", "delta": {"stop_reason": "tool_use", "stop_sequence": null}, "usage": {"input_tokens": 20, "output_tokens": 8}}),
+                json!({"type": "message_stop"}),
+            ]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Adoption metrics handlers
+//!
+//! Endpoints reporting on how widely the platform is used, computed from request logs.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use tracing::{debug, instrument};
+
+use crate::{
+    api::models::adoption::{AdoptionQuery, AdoptionResponse},
+    auth::permissions::{operation, resource, RequiresPermission},
+    db::handlers::analytics::get_adoption_metrics,
+    errors::Error,
+    AppState,
+};
+
+/// Get platform adoption metrics
+///
+/// Returns daily/weekly/monthly active users, new users, users who have never made a request,
+/// and weekly active user trends overall and per group.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/adoption",
+    params(AdoptionQuery),
+    responses(
+        (status = 200, description = "Adoption metrics", body = AdoptionResponse),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn get_adoption(
+    Query(query): Query<AdoptionQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<AdoptionResponse>, Error> {
+    // Adoption is computed from request logs, so return 404 if they aren't being collected
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    let as_of = query.as_of.unwrap_or_else(Utc::now);
+    let weeks = query.weeks.unwrap_or(12).clamp(1, 52);
+
+    let adoption = get_adoption_metrics(&state.db, as_of, weeks).await?;
+
+    Ok(Json(adoption))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{api::models::users::Role, test_utils::*};
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_adoption_permissions(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await; // Request logging disabled
+        let viewer = create_test_user(&pool, Role::RequestViewer).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+
+        let response = app
+            .get("/admin/api/v1/adoption")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::FORBIDDEN);
+
+        // Should return 404 since request logging is disabled
+        let response = app
+            .get("/admin/api/v1/adoption")
+            .add_header(add_auth_headers(&viewer).0, add_auth_headers(&viewer).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+    }
+}
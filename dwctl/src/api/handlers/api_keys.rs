@@ -1,17 +1,20 @@
 use crate::api::models::api_keys::ListApiKeysQuery;
 use crate::{
     api::models::{
-        api_keys::{ApiKeyCreate, ApiKeyInfoResponse, ApiKeyResponse},
+        api_keys::{
+            AccessRequestDecision, AccessRequestStatus, ApiKeyAccessRequestCreate, ApiKeyAccessRequestResponse, ApiKeyCreate,
+            ApiKeyInfoResponse, ApiKeyResponse, ListAccessRequestsQuery,
+        },
         users::CurrentUser,
     },
     auth::permissions::{
         can_create_all_resources, can_create_own_resource, can_delete_all_resources, can_delete_own_resource, can_read_all_resources,
-        can_read_own_resource,
+        can_read_own_resource, has_permission, operation, resource, RequiresPermission,
     },
-    db::handlers::{api_keys::ApiKeyFilter, api_keys::ApiKeys, Repository},
+    db::handlers::{api_keys::ApiKeyFilter, api_keys::ApiKeys, Deployments, Repository},
     db::models::api_keys::ApiKeyCreateDBRequest,
     errors::{Error, Result},
-    types::{ApiKeyId, Operation, Permission, Resource, UserIdOrCurrent},
+    types::{ApiKeyId, Operation, Permission, Resource, UserId, UserIdOrCurrent},
     AppState,
 };
 use axum::{
@@ -327,6 +330,219 @@ pub async fn delete_user_api_key(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// The owner of the API keys under `/users/{user_id}`, if the current user may perform `own` on
+/// their own keys or `all` on everyone's
+fn key_owner(current_user: &CurrentUser, user_id: UserIdOrCurrent, own: Operation, all: Operation) -> Result<UserId> {
+    let target_user_id = match user_id {
+        UserIdOrCurrent::Current(_) => current_user.id,
+        UserIdOrCurrent::Id(uuid) => uuid,
+    };
+    let is_own = target_user_id == current_user.id && has_permission(current_user, Resource::ApiKeys, own);
+    if !is_own && !has_permission(current_user, Resource::ApiKeys, all) {
+        return Err(Error::InsufficientPermissions {
+            required: Permission::Any(vec![
+                Permission::Allow(Resource::ApiKeys, all),
+                Permission::Allow(Resource::ApiKeys, own),
+            ]),
+            action: own,
+            resource: format!("API keys for user {target_user_id}"),
+        });
+    }
+    Ok(target_user_id)
+}
+
+/// Request access to a deployment that requires key approval for one of a user's API keys.
+#[utoipa::path(
+    post,
+    path = "/users/{user_id}/api-keys/{id}/access-requests",
+    tag = "api_keys",
+    summary = "Request deployment access for API key",
+    description = "Ask for an API key to be granted access to a deployment that requires key approval. The key also needs \
+                   group access to the deployment. A denied request can be made again.",
+    params(
+        ("user_id" = String, Path, description = "User ID (UUID) or 'current' for current user"),
+        ("id" = uuid::Uuid, Path, description = "API key ID"),
+    ),
+    request_body = ApiKeyAccessRequestCreate,
+    responses(
+        (status = 201, description = "Access requested", body = ApiKeyAccessRequestResponse),
+        (status = 400, description = "The deployment doesn't require key approval"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - can only request access for own API keys unless admin"),
+        (status = 404, description = "API key or deployment not found"),
+        (status = 409, description = "A request is already pending or approved"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn request_api_key_access(
+    State(state): State<AppState>,
+    Path((user_id, api_key_id)): Path<(UserIdOrCurrent, ApiKeyId)>,
+    current_user: CurrentUser,
+    Json(data): Json<ApiKeyAccessRequestCreate>,
+) -> Result<(StatusCode, Json<ApiKeyAccessRequestResponse>)> {
+    let target_user_id = key_owner(&current_user, user_id, Operation::CreateOwn, Operation::CreateAll)?;
+
+    let mut pool_conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    ApiKeys::new(&mut pool_conn)
+        .get_by_id(api_key_id)
+        .await?
+        .filter(|key| key.user_id == target_user_id)
+        .ok_or_else(|| Error::NotFound {
+            resource: "API key".to_string(),
+            id: api_key_id.to_string(),
+        })?;
+
+    let mut deployments = Deployments::new(&mut pool_conn);
+    let deployment = deployments
+        .get_by_id(data.deployment_id)
+        .await?
+        .filter(|deployment| !deployment.deleted)
+        .ok_or_else(|| Error::NotFound {
+            resource: "Deployment".to_string(),
+            id: data.deployment_id.to_string(),
+        })?;
+    if !deployments.requires_key_approval(deployment.id).await? {
+        return Err(Error::BadRequest {
+            message: format!("Deployment '{}' doesn't require key approval", deployment.alias),
+        });
+    }
+
+    let request = ApiKeys::new(&mut pool_conn)
+        .request_access(api_key_id, deployment.id, data.reason.as_deref())
+        .await?
+        .ok_or_else(|| Error::Conflict {
+            message: format!(
+                "Access to '{}' has already been requested or approved for this API key",
+                deployment.alias
+            ),
+            conflicts: None,
+        })?;
+    Ok((StatusCode::CREATED, Json(request.into())))
+}
+
+/// List the deployment access requests made for one of a user's API keys.
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/api-keys/{id}/access-requests",
+    tag = "api_keys",
+    summary = "List API key access requests",
+    description = "List the requests made for an API key to access deployments that require key approval",
+    params(
+        ("user_id" = String, Path, description = "User ID (UUID) or 'current' for current user"),
+        ("id" = uuid::Uuid, Path, description = "API key ID"),
+    ),
+    responses(
+        (status = 200, description = "Access requests, most recent first", body = [ApiKeyAccessRequestResponse]),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - can only view own API keys unless admin"),
+        (status = 404, description = "API key not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_api_key_access_requests(
+    State(state): State<AppState>,
+    Path((user_id, api_key_id)): Path<(UserIdOrCurrent, ApiKeyId)>,
+    current_user: CurrentUser,
+) -> Result<Json<Vec<ApiKeyAccessRequestResponse>>> {
+    let target_user_id = key_owner(&current_user, user_id, Operation::ReadOwn, Operation::ReadAll)?;
+
+    let mut pool_conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = ApiKeys::new(&mut pool_conn);
+    repo.get_by_id(api_key_id)
+        .await?
+        .filter(|key| key.user_id == target_user_id)
+        .ok_or_else(|| Error::NotFound {
+            resource: "API key".to_string(),
+            id: api_key_id.to_string(),
+        })?;
+
+    let requests = repo.list_access_requests(Some(api_key_id), None).await?;
+    Ok(Json(requests.into_iter().map(Into::into).collect()))
+}
+
+/// List deployment access requests across all API keys, for approvers.
+#[utoipa::path(
+    get,
+    path = "/access-requests",
+    tag = "api_keys",
+    summary = "List access requests",
+    description = "List the requests for API keys to access deployments that require key approval",
+    params(ListAccessRequestsQuery),
+    responses(
+        (status = 200, description = "Access requests, most recent first", body = [ApiKeyAccessRequestResponse]),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_access_requests(
+    State(state): State<AppState>,
+    Query(query): Query<ListAccessRequestsQuery>,
+    _: RequiresPermission<resource::ApiKeys, operation::ReadAll>,
+) -> Result<Json<Vec<ApiKeyAccessRequestResponse>>> {
+    let mut pool_conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let requests = ApiKeys::new(&mut pool_conn)
+        .list_access_requests(None, query.status.as_ref().map(AccessRequestStatus::as_str))
+        .await?;
+    Ok(Json(requests.into_iter().map(Into::into).collect()))
+}
+
+/// Approve or deny a deployment access request.
+#[utoipa::path(
+    patch,
+    path = "/access-requests/{id}",
+    tag = "api_keys",
+    summary = "Decide access request",
+    description = "Approve or deny a request for an API key to access a deployment that requires key approval. Denying an \
+                   approved request revokes the key's access.",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Access request ID"),
+    ),
+    request_body = AccessRequestDecision,
+    responses(
+        (status = 200, description = "Decision recorded", body = ApiKeyAccessRequestResponse),
+        (status = 400, description = "The status isn't approved or denied"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Access request not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn decide_access_request(
+    State(state): State<AppState>,
+    Path(id): Path<uuid::Uuid>,
+    current_user: RequiresPermission<resource::ApiKeys, operation::UpdateAll>,
+    Json(decision): Json<AccessRequestDecision>,
+) -> Result<Json<ApiKeyAccessRequestResponse>> {
+    if decision.status == AccessRequestStatus::Pending {
+        return Err(Error::BadRequest {
+            message: "An access request can only be approved or denied".to_string(),
+        });
+    }
+
+    let mut pool_conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let request = ApiKeys::new(&mut pool_conn)
+        .decide_access_request(id, decision.status.as_str(), current_user.id, decision.note.as_deref())
+        .await?
+        .ok_or_else(|| Error::NotFound {
+            resource: "Access request".to_string(),
+            id: id.to_string(),
+        })?;
+    Ok(Json(request.into()))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::api::models::api_keys::{ApiKeyInfoResponse, ApiKeyResponse};
@@ -972,4 +1188,120 @@ mod tests {
         assert_eq!(retrieved_key.id, created_key.id);
         assert_eq!(retrieved_key.name, created_key.name);
     }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_api_key_access_approval(pool: PgPool) {
+        use crate::api::models::api_keys::{AccessRequestStatus, ApiKeyAccessRequestResponse};
+        use crate::db::handlers::api_keys::ApiKeys;
+
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let group = create_test_group(&pool).await;
+        add_user_to_group(&pool, user.id, group.id).await;
+        let deployment = create_test_deployment(&pool, admin.id, "frontier", "frontier").await;
+        add_deployment_to_group(&pool, deployment.id, group.id, admin.id).await;
+        let api_key = create_test_api_key_for_user(&pool, user.id).await;
+        let admin_headers = add_auth_headers(&admin);
+        let user_headers = add_auth_headers(&user);
+
+        let served_keys = || async {
+            let mut conn = pool.acquire().await.unwrap();
+            let keys = ApiKeys::new(&mut conn).get_api_keys_for_deployment(deployment.id).await.unwrap();
+            keys.iter().any(|key| key.id == api_key.id)
+        };
+        assert!(served_keys().await);
+
+        // Requests are only accepted for deployments requiring key approval
+        let requests_path = format!("/admin/api/v1/users/current/api-keys/{}/access-requests", api_key.id);
+        let request_body = json!({"deployment_id": deployment.id, "reason": "Evaluating the model"});
+        let response = app
+            .post(&requests_path)
+            .add_header(user_headers.0.clone(), user_headers.1.clone())
+            .json(&request_body)
+            .await;
+        response.assert_status_bad_request();
+
+        let response = app
+            .put(&format!("/admin/api/v1/models/{}/access-policy", deployment.id))
+            .add_header(admin_headers.0.clone(), admin_headers.1.clone())
+            .json(&json!({"requires_key_approval": true}))
+            .await;
+        response.assert_status_ok();
+        assert!(!served_keys().await);
+
+        let response = app
+            .post(&requests_path)
+            .add_header(user_headers.0.clone(), user_headers.1.clone())
+            .json(&request_body)
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let request: ApiKeyAccessRequestResponse = response.json();
+        assert_eq!(request.status, AccessRequestStatus::Pending);
+
+        let response = app
+            .post(&requests_path)
+            .add_header(user_headers.0.clone(), user_headers.1.clone())
+            .json(&request_body)
+            .await;
+        response.assert_status(axum::http::StatusCode::CONFLICT);
+
+        // Only approvers can see and decide requests
+        let response = app
+            .get("/admin/api/v1/access-requests?status=pending")
+            .add_header(user_headers.0.clone(), user_headers.1.clone())
+            .await;
+        response.assert_status_forbidden();
+        let response = app
+            .get("/admin/api/v1/access-requests?status=pending")
+            .add_header(admin_headers.0.clone(), admin_headers.1.clone())
+            .await;
+        response.assert_status_ok();
+        let pending: Vec<ApiKeyAccessRequestResponse> = response.json();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].reason.as_deref(), Some("Evaluating the model"));
+
+        let decision_path = format!("/admin/api/v1/access-requests/{}", request.id);
+        let response = app
+            .patch(&decision_path)
+            .add_header(user_headers.0.clone(), user_headers.1.clone())
+            .json(&json!({"status": "approved"}))
+            .await;
+        response.assert_status_forbidden();
+        let response = app
+            .patch(&decision_path)
+            .add_header(admin_headers.0.clone(), admin_headers.1.clone())
+            .json(&json!({"status": "approved", "note": "Go ahead"}))
+            .await;
+        response.assert_status_ok();
+        let request: ApiKeyAccessRequestResponse = response.json();
+        assert_eq!(request.status, AccessRequestStatus::Approved);
+        assert_eq!(request.decided_by, Some(admin.id));
+        assert!(served_keys().await);
+
+        // Denying an approved request revokes access, after which access can be requested again
+        let response = app
+            .patch(&decision_path)
+            .add_header(admin_headers.0.clone(), admin_headers.1.clone())
+            .json(&json!({"status": "denied"}))
+            .await;
+        response.assert_status_ok();
+        assert!(!served_keys().await);
+
+        let response = app
+            .get(&requests_path)
+            .add_header(user_headers.0.clone(), user_headers.1.clone())
+            .await;
+        response.assert_status_ok();
+        let requests: Vec<ApiKeyAccessRequestResponse> = response.json();
+        assert_eq!(requests[0].status, AccessRequestStatus::Denied);
+
+        let response = app
+            .post(&requests_path)
+            .add_header(user_headers.0.clone(), user_headers.1.clone())
+            .json(&request_body)
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+    }
 }
@@ -14,7 +14,7 @@ use crate::{
     },
     auth::{password, session},
     db::{
-        handlers::{PasswordResetTokens, Repository, Users},
+        handlers::{Groups, PasswordResetTokens, Repository, Users},
         models::users::UserCreateDBRequest,
     },
     email::EmailService,
@@ -108,9 +108,13 @@ pub async fn register(State(state): State<AppState>, Json(request): Json<Registe
         roles: vec![Role::StandardUser],
         auth_source: "native".to_string(),
         password_hash: Some(password_hash),
+        attributes: Default::default(),
     };
 
     let created_user = user_repo.create(&create_request).await?;
+    Groups::new(&mut tx)
+        .add_user_to_default_groups(created_user.id, &state.config.auth.default_groups)
+        .await?;
     tx.commit().await.map_err(|e| Error::Database(e.into()))?;
     let user_response = UserResponse::from(created_user);
 
@@ -341,6 +345,8 @@ pub async fn confirm_password_reset(
         avatar_url: None,
         roles: None,
         password_hash: Some(new_password_hash),
+        default_model_id: None,
+        attributes: None,
     };
 
     let mut tx = state.db.begin().await.unwrap();
@@ -465,6 +471,8 @@ pub async fn change_password(
         avatar_url: None,
         roles: None,
         password_hash: Some(new_password_hash),
+        default_model_id: None,
+        attributes: None,
     };
 
     user_repo.update(current_user.id, &update_request).await?;
@@ -488,6 +496,7 @@ fn create_session_cookie(token: &str, config: &crate::config::Config) -> String
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::models::groups::GroupCreateDBRequest;
     use crate::test_utils::create_test_config;
     use axum_test::TestServer;
     use sqlx::PgPool;
@@ -523,6 +532,54 @@ mod tests {
         assert_eq!(body.message, "Registration successful");
     }
 
+    #[sqlx::test]
+    async fn test_register_joins_default_groups(pool: PgPool) {
+        let mut conn = pool.acquire().await.unwrap();
+        let mut group_repo = Groups::new(&mut conn);
+        let create_group = |name: &str, is_default: bool| GroupCreateDBRequest {
+            name: name.to_string(),
+            description: None,
+            created_by: Uuid::nil(),
+            parent_id: None,
+            is_default,
+        };
+        let flagged = group_repo.create(&create_group("flagged", true)).await.unwrap();
+        let configured = group_repo.create(&create_group("configured", false)).await.unwrap();
+        let other = group_repo.create(&create_group("other", false)).await.unwrap();
+
+        let mut config = create_test_config();
+        config.auth.native.enabled = true;
+        config.auth.native.allow_registration = true;
+        config.auth.default_groups = vec!["configured".to_string(), "missing".to_string()];
+
+        let state = AppState::builder().db(pool).config(config).build();
+        let app = axum::Router::new()
+            .route("/auth/register", axum::routing::post(register))
+            .with_state(state);
+        let server = TestServer::new(app).unwrap();
+
+        let request = RegisterRequest {
+            username: "testuser".to_string(),
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            display_name: None,
+        };
+        let response = server.post("/auth/register").json(&request).await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let body: AuthResponse = response.json();
+
+        let group_ids: Vec<_> = Groups::new(&mut conn)
+            .get_user_groups(body.user.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|g| g.id)
+            .collect();
+        assert!(group_ids.contains(&flagged.id));
+        assert!(group_ids.contains(&configured.id));
+        assert!(!group_ids.contains(&other.id));
+    }
+
     #[sqlx::test]
     async fn test_register_disabled(pool: PgPool) {
         let mut config = create_test_config();
@@ -0,0 +1,118 @@
+use crate::{
+    api::models::bootstrap::{BootstrapModel, BootstrapResponse, RateLimit},
+    db::handlers::{api_keys::ApiKeys, deployments::DeploymentFilter, Deployments, Repository, Users},
+    errors::{Error, Result},
+    AppState,
+};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    Json,
+};
+use uuid::Uuid;
+
+/// Get the configuration a client SDK needs at startup.
+///
+/// Authenticated with an API key, like the rest of the AI proxy. Returns the models the key can
+/// use, the user's default model and the rate limits that apply, so clients don't need to
+/// hardcode any of them.
+pub async fn bootstrap(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<BootstrapResponse>> {
+    let secret = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| Error::Unauthenticated {
+            message: Some("Missing API key".to_string()),
+        })?;
+
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let api_key = ApiKeys::new(&mut conn)
+        .get_by_secret(secret)
+        .await?
+        .ok_or_else(|| Error::Unauthenticated {
+            message: Some("Invalid API key".to_string()),
+        })?;
+
+    // The system key can use every model; other keys get the models shared with their groups
+    let mut filter = DeploymentFilter::new(0, i64::MAX).with_deleted(false).with_enabled(true);
+    if api_key.user_id != Uuid::nil() {
+        filter = filter.with_accessible_to(api_key.user_id);
+    }
+    let deployments = Deployments::new(&mut conn).list(&filter).await?;
+
+    let default_model_id = Users::new(&mut conn)
+        .get_by_id(api_key.user_id)
+        .await?
+        .and_then(|user| user.default_model_id);
+    let default_model = deployments.iter().find(|d| Some(d.id) == default_model_id).map(|d| d.alias.clone());
+
+    Ok(Json(BootstrapResponse {
+        user_id: api_key.user_id,
+        models: deployments.into_iter().map(BootstrapModel::from).collect(),
+        default_model,
+        rate_limit: RateLimit {
+            requests_per_second: api_key.requests_per_second,
+            burst_size: api_key.burst_size,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::bootstrap::BootstrapResponse;
+    use crate::api::models::users::Role;
+    use crate::test_utils::*;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_bootstrap_returns_accessible_models_and_default(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let group = create_test_group(&pool).await;
+        add_user_to_group(&pool, user.id, group.id).await;
+
+        let shared = create_test_deployment(&pool, admin.id, "shared-model", "shared").await;
+        let other = create_test_deployment(&pool, admin.id, "other-model", "other").await;
+        add_deployment_to_group(&pool, shared.id, group.id, admin.id).await;
+        let api_key = create_test_api_key_for_user(&pool, user.id).await;
+
+        let response = app.get("/ai/v1/bootstrap").authorization_bearer(&api_key.secret).await;
+        response.assert_status_ok();
+        let bootstrap: BootstrapResponse = response.json();
+        assert_eq!(bootstrap.user_id, user.id);
+        assert_eq!(bootstrap.models.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), vec!["shared"]);
+        assert_eq!(bootstrap.default_model, None);
+
+        // A default model the user can't access isn't offered
+        app.patch(&format!("/admin/api/v1/users/{}", user.id))
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"default_model_id": other.id}))
+            .await
+            .assert_status_ok();
+        let bootstrap: BootstrapResponse = app.get("/ai/v1/bootstrap").authorization_bearer(&api_key.secret).await.json();
+        assert_eq!(bootstrap.default_model, None);
+
+        app.patch(&format!("/admin/api/v1/users/{}", user.id))
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"default_model_id": shared.id}))
+            .await
+            .assert_status_ok();
+        let bootstrap: BootstrapResponse = app.get("/ai/v1/bootstrap").authorization_bearer(&api_key.secret).await.json();
+        assert_eq!(bootstrap.default_model.as_deref(), Some("shared"));
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_bootstrap_requires_valid_api_key(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+
+        app.get("/ai/v1/bootstrap").await.assert_status_unauthorized();
+        app.get("/ai/v1/bootstrap")
+            .authorization_bearer("not-a-real-key")
+            .await
+            .assert_status_unauthorized();
+    }
+}
@@ -0,0 +1,429 @@
+use crate::api::models::federation::{
+    FederationOverview, FederationPeerCreate, FederationPeerResponse, FederationSummaryQuery, InstanceSummary,
+};
+use crate::auth::permissions::{operation, resource, RequiresPermission};
+use crate::errors::Error;
+use crate::federation::{
+    self,
+    db::{FederationPeerManager, NewFederationPeer},
+};
+use crate::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use url::Url;
+use uuid::Uuid;
+
+fn require_enabled(state: &AppState) -> Result<(), Error> {
+    if !state.config.federation.enabled {
+        return Err(Error::BadRequest {
+            message: "Federation is not enabled on this server".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// The window of a summary query, defaulting to the last 24 hours
+fn window(query: &FederationSummaryQuery) -> (DateTime<Utc>, DateTime<Utc>) {
+    let now = Utc::now();
+    let start = query.timestamp_after.unwrap_or_else(|| now - chrono::Duration::hours(24));
+    (start, query.timestamp_before.unwrap_or(now))
+}
+
+#[utoipa::path(
+    get,
+    path = "/federation/peers",
+    tag = "federation",
+    summary = "List federation peers",
+    description = "List the peer instances whose summaries are aggregated with this instance's",
+    responses(
+        (status = 200, description = "List of peers", body = Vec<FederationPeerResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_peers(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::SystemAccess>,
+) -> Result<Json<Vec<FederationPeerResponse>>, Error> {
+    let peers = FederationPeerManager::list(&state.db).await?;
+    Ok(Json(peers.into_iter().map(Into::into).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/federation/peers",
+    tag = "federation",
+    summary = "Register a federation peer",
+    description = "Register another dwctl instance as a peer. The token must match the peer's `federation.token`; \
+                   it's stored to authenticate requests for the peer's summary and never returned.",
+    request_body = FederationPeerCreate,
+    responses(
+        (status = 201, description = "Peer registered", body = FederationPeerResponse),
+        (status = 400, description = "Bad request - federation is disabled, or the name, URL or token is invalid"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 409, description = "A peer with this name already exists"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn create_peer(
+    State(state): State<AppState>,
+    permission: RequiresPermission<resource::Analytics, operation::SystemAccess>,
+    Json(request): Json<FederationPeerCreate>,
+) -> Result<(StatusCode, Json<FederationPeerResponse>), Error> {
+    require_enabled(&state)?;
+
+    let name = request.name.trim();
+    if name.is_empty() || name == "local" {
+        return Err(Error::BadRequest {
+            message: "name must be non-empty and not 'local'".to_string(),
+        });
+    }
+    if !Url::parse(&request.url).is_ok_and(|url| matches!(url.scheme(), "http" | "https")) {
+        return Err(Error::BadRequest {
+            message: "url must be an http or https URL".to_string(),
+        });
+    }
+    if request.token.trim().is_empty() {
+        return Err(Error::BadRequest {
+            message: "token cannot be empty".to_string(),
+        });
+    }
+
+    let peer = FederationPeerManager::create(
+        &state.db,
+        NewFederationPeer {
+            name: name.to_string(),
+            url: request.url,
+            token: request.token,
+            created_by: permission.current_user.id,
+        },
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(peer.into())))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/federation/peers/{id}",
+    tag = "federation",
+    summary = "Remove a federation peer",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Peer ID to remove"),
+    ),
+    responses(
+        (status = 204, description = "Peer removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Peer not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn delete_peer(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::SystemAccess>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    FederationPeerManager::delete(&state.db, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/federation/overview",
+    tag = "federation",
+    summary = "Get federation overview",
+    description = "Get request analytics and deployment health for this instance and every peer, with totals across \
+                   them. Peers are queried concurrently; those that can't be reached are listed with an error and left \
+                   out of the totals.",
+    params(FederationSummaryQuery),
+    responses(
+        (status = 200, description = "Overview across instances", body = FederationOverview),
+        (status = 400, description = "Federation is not enabled"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - analytics access required"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_overview(
+    State(state): State<AppState>,
+    Query(query): Query<FederationSummaryQuery>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<FederationOverview>, Error> {
+    require_enabled(&state)?;
+    let (start, end) = window(&query);
+    Ok(Json(federation::overview(&state, start, end).await?))
+}
+
+/// Get the read-only summary of this instance, for its federation peers.
+///
+/// Authenticated with the `federation.token` of this instance rather than a user session, and
+/// not found unless a token is configured.
+pub async fn get_summary(
+    State(state): State<AppState>,
+    Query(query): Query<FederationSummaryQuery>,
+    headers: HeaderMap,
+) -> Result<Json<InstanceSummary>, Error> {
+    let Some(token) = state.config.federation.token.as_deref() else {
+        return Err(Error::NotFound {
+            resource: "Federation summary".to_string(),
+            id: federation::SUMMARY_PATH.to_string(),
+        });
+    };
+    let presented = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if presented != Some(token) {
+        return Err(Error::Unauthenticated {
+            message: Some("Invalid federation token".to_string()),
+        });
+    }
+
+    let (start, end) = window(&query);
+    Ok(Json(federation::local_summary(&state, start, end).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::federation::{FederationOverview, FederationPeerResponse, FederationTotals, InstanceHealth, InstanceSummary};
+    use crate::api::models::requests::{ModelUsage, RequestsAggregateResponse, StatusCodeBreakdown};
+    use crate::api::models::users::Role;
+    use crate::test_utils::*;
+    use axum::{http::HeaderMap, routing::get, Json, Router};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    async fn create_app(pool: PgPool, token: Option<&str>) -> (axum_test::TestServer, tokio_util::sync::DropGuard) {
+        let mut config = create_test_config();
+        config.federation.enabled = true;
+        config.federation.token = token.map(str::to_string);
+        let (router, _, drop_guard) = crate::setup_app(pool, config, true).await.unwrap();
+        (axum_test::TestServer::new(router).unwrap(), drop_guard)
+    }
+
+    fn summary(deployments: i64, requests: Option<RequestsAggregateResponse>) -> InstanceSummary {
+        InstanceSummary {
+            health: InstanceHealth {
+                deployments,
+                open_circuits: 1,
+                failing_probes: 0,
+            },
+            requests,
+        }
+    }
+
+    fn requests(total: i64, errors: i64, model: &str, avg_latency_ms: f64) -> RequestsAggregateResponse {
+        RequestsAggregateResponse {
+            total_requests: total,
+            total_cost: Some(total as f64 * 0.5),
+            model: None,
+            status_codes: vec![
+                StatusCodeBreakdown {
+                    status: "200".to_string(),
+                    count: total - errors,
+                    percentage: 0.0,
+                },
+                StatusCodeBreakdown {
+                    status: "500".to_string(),
+                    count: errors,
+                    percentage: 0.0,
+                },
+            ],
+            models: Some(vec![ModelUsage {
+                model: model.to_string(),
+                count: total,
+                percentage: 100.0,
+                avg_latency_ms,
+                total_cost: Some(total as f64 * 0.5),
+            }]),
+            time_series: Vec::new(),
+        }
+    }
+
+    /// A peer that serves `summary` to callers presenting `peer-token`
+    async fn spawn_peer(summary: InstanceSummary) -> String {
+        let app = Router::new().route(
+            crate::federation::SUMMARY_PATH,
+            get(move |headers: HeaderMap| {
+                let summary = summary.clone();
+                async move {
+                    match headers.get("authorization").and_then(|v| v.to_str().ok()) {
+                        Some("Bearer peer-token") => Ok(Json(summary)),
+                        _ => Err(axum::http::StatusCode::UNAUTHORIZED),
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    #[test]
+    fn test_totals_add_up_reachable_instances() {
+        let eu = summary(2, Some(requests(30, 3, "llama", 100.0)));
+        let us = summary(3, Some(requests(10, 0, "llama", 200.0)));
+        let no_logging = summary(1, None);
+        let totals = FederationTotals::new([Some(&eu), Some(&us), Some(&no_logging), None]);
+
+        assert_eq!(totals.reachable_instances, 3);
+        assert_eq!(totals.unreachable_instances, 1);
+        assert_eq!(
+            totals.health,
+            InstanceHealth {
+                deployments: 6,
+                open_circuits: 3,
+                failing_probes: 0
+            }
+        );
+        assert_eq!(totals.total_requests, 40);
+        assert_eq!(totals.total_cost, Some(20.0));
+        let errors = totals.status_codes.iter().find(|s| s.status == "500").unwrap();
+        assert_eq!(errors.count, 3);
+        assert_eq!(errors.percentage, 7.5);
+        assert_eq!(totals.models.len(), 1);
+        assert_eq!(totals.models[0].count, 40);
+        assert_eq!(totals.models[0].avg_latency_ms, 125.0);
+        assert_eq!(totals.models[0].total_cost, Some(20.0));
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_federation_overview_across_peers(pool: PgPool) {
+        let (server, _drop_guard) = create_app(pool.clone(), None).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let headers = add_auth_headers(&admin);
+        create_test_deployment(&pool, admin.id, "local-model", "local-alias").await;
+
+        let peer_url = spawn_peer(summary(4, Some(requests(10, 1, "llama", 50.0)))).await;
+        for (name, url) in [("eu", peer_url.as_str()), ("offline", "http://127.0.0.1:1")] {
+            let response = server
+                .post("/admin/api/v1/federation/peers")
+                .add_header(headers.0.clone(), headers.1.clone())
+                .json(&json!({"name": name, "url": url, "token": "peer-token"}))
+                .await;
+            response.assert_status(axum::http::StatusCode::CREATED);
+        }
+
+        for (body, status) in [
+            (
+                json!({"name": "eu", "url": peer_url, "token": "t"}),
+                axum::http::StatusCode::CONFLICT,
+            ),
+            (
+                json!({"name": "local", "url": peer_url, "token": "t"}),
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+            (
+                json!({"name": "us", "url": "ftp://us.example.com", "token": "t"}),
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+            (
+                json!({"name": "us", "url": "https://us.example.com", "token": " "}),
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+        ] {
+            server
+                .post("/admin/api/v1/federation/peers")
+                .add_header(headers.0.clone(), headers.1.clone())
+                .json(&body)
+                .await
+                .assert_status(status);
+        }
+
+        let peers: Vec<FederationPeerResponse> = server
+            .get("/admin/api/v1/federation/peers")
+            .add_header(headers.0.clone(), headers.1.clone())
+            .await
+            .json();
+        assert_eq!(peers.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["eu", "offline"]);
+
+        let response = server
+            .get("/admin/api/v1/federation/overview")
+            .add_header(headers.0.clone(), headers.1.clone())
+            .await;
+        response.assert_status_ok();
+        let overview: FederationOverview = response.json();
+        assert_eq!(
+            overview.instances.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(),
+            vec!["local", "eu", "offline"]
+        );
+        let local = overview.instances[0].summary.as_ref().unwrap();
+        assert_eq!(local.health.deployments, 1);
+        assert!(local.requests.is_none());
+        assert!(overview.instances[2].summary.is_none());
+        assert!(overview.instances[2].error.is_some());
+        assert_eq!(overview.totals.reachable_instances, 2);
+        assert_eq!(overview.totals.unreachable_instances, 1);
+        assert_eq!(overview.totals.health.deployments, 5);
+        assert_eq!(overview.totals.total_requests, 10);
+
+        let path = format!("/admin/api/v1/federation/peers/{}", peers[1].id);
+        server
+            .delete(&path)
+            .add_header(headers.0.clone(), headers.1.clone())
+            .await
+            .assert_status(axum::http::StatusCode::NO_CONTENT);
+        server
+            .delete(&path)
+            .add_header(headers.0.clone(), headers.1.clone())
+            .await
+            .assert_status_not_found();
+
+        // Standard users can't see peers
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        server
+            .get("/admin/api/v1/federation/peers")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await
+            .assert_status_forbidden();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_summary_requires_federation_token(pool: PgPool) {
+        let (server, _drop_guard) = create_app(pool.clone(), Some("secret")).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        create_test_deployment(&pool, admin.id, "local-model", "local-alias").await;
+
+        server.get("/federation/v1/summary").await.assert_status_unauthorized();
+        server
+            .get("/federation/v1/summary")
+            .authorization_bearer("wrong")
+            .await
+            .assert_status_unauthorized();
+
+        let response = server.get("/federation/v1/summary").authorization_bearer("secret").await;
+        response.assert_status_ok();
+        let summary: InstanceSummary = response.json();
+        assert_eq!(summary.health.deployments, 1);
+        assert_eq!(summary.health.open_circuits, 0);
+
+        // Without a token, instances don't serve their summary at all
+        let (server, _drop_guard) = create_app(pool, None).await;
+        server
+            .get("/federation/v1/summary")
+            .authorization_bearer("secret")
+            .await
+            .assert_status_not_found();
+    }
+}
@@ -1,10 +1,16 @@
 use crate::api::models::deployments::DeployedModelResponse;
-use crate::api::models::groups::{GroupCreate, GroupResponse, GroupUpdate, ListGroupsQuery};
+use crate::api::models::groups::{
+    GroupCreate, GroupLogRetention, GroupModerationPolicy, GroupRequestLimits, GroupResponse, GroupSortField, GroupUpdate, ListGroupsQuery,
+    LogRetentionKind,
+};
+use crate::api::models::pagination::{next_cursor_headers, timestamp_value, ListCursor};
+use crate::api::models::sorting::SortOrder;
 use crate::api::models::users::{CurrentUser, UserResponse};
 use crate::auth::permissions::{can_read_all_resources, can_read_own_resource, operation, resource, RequiresPermission};
 use crate::db::handlers::{groups::GroupFilter, Deployments, Groups, Repository, Users};
-use crate::db::models::groups::{GroupCreateDBRequest, GroupUpdateDBRequest};
+use crate::db::models::groups::{GroupCreateDBRequest, GroupLogRetentionDBResponse, GroupUpdateDBRequest};
 use crate::errors::{Error, Result};
+use crate::request_logging::retention::effective_retention;
 use crate::types::{Operation, Permission, Resource};
 use crate::{
     types::{DeploymentId, GroupId, UserId},
@@ -12,7 +18,7 @@ use crate::{
 };
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Json,
 };
 use sqlx::Acquire;
@@ -23,13 +29,18 @@ use sqlx::Acquire;
     tag = "groups",
     summary = "List groups",
     responses(
-        (status = 200, description = "List of groups", body = Vec<GroupResponse>),
+        (status = 200, description = "List of groups", body = Vec<GroupResponse>, headers(
+            ("x-next-cursor" = String, description = "Cursor of the next page, absent on the last page")
+        )),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
     ),
     params(
         ("skip" = Option<i64>, Query, description = "Number of groups to skip"),
         ("limit" = Option<i64>, Query, description = "Maximum number of groups to return"),
+        ("sort" = Option<GroupSortField>, Query, description = "Column to sort by (default: name)"),
+        ("order" = Option<SortOrder>, Query, description = "Sort direction (default: asc)"),
+        ("cursor" = Option<String>, Query, description = "Cursor from the x-next-cursor header of the previous page, used instead of skip"),
     ),
     security(
         ("X-Doubleword-User" = [])
@@ -39,16 +50,33 @@ pub async fn list_groups(
     State(state): State<AppState>,
     Query(query): Query<ListGroupsQuery>,
     _: RequiresPermission<resource::Groups, operation::ReadAll>,
-) -> Result<Json<Vec<GroupResponse>>> {
+) -> Result<(HeaderMap, Json<Vec<GroupResponse>>)> {
     let mut tx = state.db.begin().await.map_err(|e| Error::Database(e.into()))?;
 
     let groups;
+    let next_cursor;
     {
         let mut repo = Groups::new(tx.acquire().await.map_err(|e| Error::Database(e.into()))?);
         let skip = query.skip.unwrap_or(0);
         let limit = query.limit.unwrap_or(100).min(1000);
 
-        groups = repo.list(&GroupFilter::new(skip, limit)).await?;
+        let mut filter = GroupFilter::new(skip, limit);
+        if query.sort.is_some() || query.order.is_some() {
+            filter = filter.with_sort(query.sort.unwrap_or(GroupSortField::Name), query.order.unwrap_or_default());
+        }
+        if let Some(cursor) = query.cursor.as_deref() {
+            filter = filter.with_cursor(ListCursor::decode(cursor)?);
+        }
+
+        groups = repo.list(&filter).await?;
+        next_cursor = ListCursor::next(&groups, limit, filter.sort, |group| {
+            let value = match filter.sort.0 {
+                GroupSortField::Name => group.name.clone(),
+                GroupSortField::CreatedAt => timestamp_value(group.created_at),
+                GroupSortField::UpdatedAt => timestamp_value(group.updated_at),
+            };
+            (Some(value), group.id)
+        });
     }
 
     // Parse include parameter
@@ -154,7 +182,7 @@ pub async fn list_groups(
     // Commit the transaction to ensure all reads were atomic
     tx.commit().await.map_err(|e| Error::Database(e.into()))?;
 
-    Ok(Json(response_groups))
+    Ok((next_cursor_headers(next_cursor), Json(response_groups)))
 }
 
 #[utoipa::path(
@@ -291,6 +319,306 @@ pub async fn delete_group(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/groups/{group_id}/moderation",
+    tag = "groups",
+    summary = "Get group moderation policy",
+    description = "Get the content moderation policy applied to requests from members of a group",
+    responses(
+        (status = 200, description = "Moderation policy (mode off if none has been set)", body = GroupModerationPolicy),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("group_id" = uuid::Uuid, Path, description = "Group ID")
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_group_moderation(
+    State(state): State<AppState>,
+    Path(group_id): Path<GroupId>,
+    _: RequiresPermission<resource::Groups, operation::ReadAll>,
+) -> Result<Json<GroupModerationPolicy>> {
+    let mut pool_conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = Groups::new(&mut pool_conn);
+
+    if repo.get_by_id(group_id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Group".to_string(),
+            id: group_id.to_string(),
+        });
+    }
+
+    let policy = repo.get_moderation_policy(group_id).await?;
+    Ok(Json(policy.map(GroupModerationPolicy::from).unwrap_or_default()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/groups/{group_id}/moderation",
+    tag = "groups",
+    summary = "Set group moderation policy",
+    description = "Replace the content moderation policy of a group. Requests from its members are checked against \
+                   the blocked patterns and the configured moderation endpoint, and flagged requests are annotated \
+                   or blocked. Policies of the Everyone group apply to all users.",
+    request_body = GroupModerationPolicy,
+    responses(
+        (status = 200, description = "Moderation policy updated", body = GroupModerationPolicy),
+        (status = 400, description = "Invalid regular expression"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("group_id" = uuid::Uuid, Path, description = "Group ID")
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn set_group_moderation(
+    State(state): State<AppState>,
+    Path(group_id): Path<GroupId>,
+    _: RequiresPermission<resource::Groups, operation::UpdateAll>,
+    Json(policy): Json<GroupModerationPolicy>,
+) -> Result<Json<GroupModerationPolicy>> {
+    for pattern in &policy.blocked_patterns {
+        if let Err(e) = regex::Regex::new(pattern) {
+            return Err(Error::BadRequest {
+                message: format!("Invalid blocked pattern '{pattern}': {e}"),
+            });
+        }
+    }
+
+    let mut pool_conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = Groups::new(&mut pool_conn);
+
+    if repo.get_by_id(group_id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Group".to_string(),
+            id: group_id.to_string(),
+        });
+    }
+
+    let policy = repo.set_moderation_policy(group_id, &policy.into()).await?;
+    Ok(Json(policy.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/groups/{group_id}/limits",
+    tag = "groups",
+    summary = "Get group request limits",
+    description = "Get the caps on the size of requests from members of a group",
+    responses(
+        (status = 200, description = "Request limits (all unset if none have been set)", body = GroupRequestLimits),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("group_id" = uuid::Uuid, Path, description = "Group ID")
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_group_limits(
+    State(state): State<AppState>,
+    Path(group_id): Path<GroupId>,
+    _: RequiresPermission<resource::Groups, operation::ReadAll>,
+) -> Result<Json<GroupRequestLimits>> {
+    let mut pool_conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = Groups::new(&mut pool_conn);
+
+    if repo.get_by_id(group_id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Group".to_string(),
+            id: group_id.to_string(),
+        });
+    }
+
+    let limits = repo.get_request_limits(group_id).await?;
+    Ok(Json(limits.map(GroupRequestLimits::from).unwrap_or_default()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/groups/{group_id}/limits",
+    tag = "groups",
+    summary = "Set group request limits",
+    description = "Replace the request limits of a group. Requests from its members with a larger body, more messages \
+                   or a higher max_tokens are refused with a 400 before being forwarded. When a user is in several \
+                   groups the lowest limit applies, and limits of the Everyone group apply to all users.",
+    request_body = GroupRequestLimits,
+    responses(
+        (status = 200, description = "Request limits updated", body = GroupRequestLimits),
+        (status = 400, description = "Limits must be positive"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("group_id" = uuid::Uuid, Path, description = "Group ID")
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn set_group_limits(
+    State(state): State<AppState>,
+    Path(group_id): Path<GroupId>,
+    _: RequiresPermission<resource::Groups, operation::UpdateAll>,
+    Json(limits): Json<GroupRequestLimits>,
+) -> Result<Json<GroupRequestLimits>> {
+    let positive =
+        limits.max_body_bytes.is_none_or(|n| n > 0) && limits.max_messages.is_none_or(|n| n > 0) && limits.max_tokens.is_none_or(|n| n > 0);
+    if !positive {
+        return Err(Error::BadRequest {
+            message: "Request limits must be positive".to_string(),
+        });
+    }
+
+    let mut pool_conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = Groups::new(&mut pool_conn);
+
+    if repo.get_by_id(group_id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Group".to_string(),
+            id: group_id.to_string(),
+        });
+    }
+
+    let limits = repo.set_request_limits(group_id, &limits.into()).await?;
+    Ok(Json(limits.into()))
+}
+
+/// The retention of a group with the override `retention`, and its effective retention
+async fn log_retention(
+    state: &AppState,
+    repo: &mut Groups<'_>,
+    group_id: GroupId,
+    retention: Option<GroupLogRetentionDBResponse>,
+) -> Result<GroupLogRetention> {
+    let everyone = repo
+        .get_log_retention(GroupId::nil())
+        .await?
+        .map(|everyone| everyone.retention_days);
+    let overrides = repo.get_member_log_retention(group_id).await?;
+    let effective = effective_retention(
+        &state.config.request_log_retention,
+        everyone,
+        overrides
+            .iter()
+            .map(|retention| (retention.retention_days, LogRetentionKind::parse(&retention.kind))),
+    );
+    Ok(GroupLogRetention {
+        retention_days: retention.as_ref().map(|retention| retention.retention_days),
+        kind: retention
+            .map(|retention| LogRetentionKind::parse(&retention.kind))
+            .unwrap_or_default(),
+        effective_retention_days: effective.map(|retention| retention.as_secs_f64() / (24.0 * 60.0 * 60.0)),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/groups/{group_id}/log-retention",
+    tag = "groups",
+    summary = "Get group log retention",
+    description = "Get how long the requests of a group's members are kept in the request log: the group's override, if \
+                   any, and the retention that applies to members in no other group with an override",
+    responses(
+        (status = 200, description = "Log retention", body = GroupLogRetention),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("group_id" = uuid::Uuid, Path, description = "Group ID")
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_group_log_retention(
+    State(state): State<AppState>,
+    Path(group_id): Path<GroupId>,
+    _: RequiresPermission<resource::Groups, operation::ReadAll>,
+) -> Result<Json<GroupLogRetention>> {
+    let mut pool_conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = Groups::new(&mut pool_conn);
+
+    if repo.get_by_id(group_id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Group".to_string(),
+            id: group_id.to_string(),
+        });
+    }
+
+    let retention = repo.get_log_retention(group_id).await?;
+    Ok(Json(log_retention(&state, &mut repo, group_id, retention).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/groups/{group_id}/log-retention",
+    tag = "groups",
+    summary = "Set group log retention",
+    description = "Override how long the requests of a group's members are kept in the request log. A maximum (e.g. a \
+                   privacy limit) purges them sooner or later than the default, a minimum (e.g. a legal hold) keeps them \
+                   for at least that long. A user's requests are kept for the shortest maximum of their groups, extended \
+                   to their longest minimum, so holds always win. The override of the Everyone group replaces the default \
+                   retention instead. A null retention_days removes the override.",
+    request_body = GroupLogRetention,
+    responses(
+        (status = 200, description = "Log retention updated", body = GroupLogRetention),
+        (status = 400, description = "Retention must be positive"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    params(
+        ("group_id" = uuid::Uuid, Path, description = "Group ID")
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn set_group_log_retention(
+    State(state): State<AppState>,
+    Path(group_id): Path<GroupId>,
+    _: RequiresPermission<resource::Groups, operation::UpdateAll>,
+    Json(retention): Json<GroupLogRetention>,
+) -> Result<Json<GroupLogRetention>> {
+    if retention.retention_days.is_some_and(|days| days <= 0) {
+        return Err(Error::BadRequest {
+            message: "Log retention must be positive".to_string(),
+        });
+    }
+
+    let mut pool_conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = Groups::new(&mut pool_conn);
+
+    if repo.get_by_id(group_id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Group".to_string(),
+            id: group_id.to_string(),
+        });
+    }
+
+    let retention = retention.retention_days.map(|retention_days| GroupLogRetentionDBResponse {
+        retention_days,
+        kind: retention.kind.as_str().to_string(),
+    });
+    let retention = repo.set_log_retention(group_id, retention).await?;
+    Ok(Json(log_retention(&state, &mut repo, group_id, retention).await?))
+}
+
 #[utoipa::path(
     post,
     path = "/groups/{group_id}/users/{user_id}",
@@ -606,7 +934,12 @@ mod tests {
     use std::collections::HashSet;
 
     use crate::{
-        api::models::{groups::GroupResponse, users::Role},
+        api::models::{
+            groups::{
+                GroupLogRetention, GroupModerationPolicy, GroupRequestLimits, GroupResponse, LogRetentionKind, ModerationMode, PriorityTier,
+            },
+            users::Role,
+        },
         db::{
             handlers::{Deployments, Groups, Repository},
             models::{deployments::DeploymentCreateDBRequest, groups::GroupCreateDBRequest},
@@ -632,6 +965,8 @@ mod tests {
                 name: format!("Test Group {i}"),
                 description: Some(format!("Description for group {i}")),
                 created_by: user.id,
+                parent_id: None,
+                is_default: false,
             };
             group_repo.create(&group_create).await.expect("Failed to create test group");
         }
@@ -677,6 +1012,317 @@ mod tests {
         assert_eq!(groups.len(), 6); // Should return all 6 groups (5 test groups + Everyone group)
     }
 
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_list_groups_sorted(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let mut pool_conn = pool.acquire().await.unwrap();
+        let mut group_repo = Groups::new(&mut pool_conn);
+        for name in ["Bravo", "Alpha", "Charlie"] {
+            let group_create = GroupCreateDBRequest {
+                name: name.to_string(),
+                description: None,
+                created_by: user.id,
+                parent_id: None,
+                is_default: false,
+            };
+            group_repo.create(&group_create).await.expect("Failed to create test group");
+        }
+
+        let names = |response: axum_test::TestResponse| {
+            response.assert_status_ok();
+            response
+                .json::<Vec<GroupResponse>>()
+                .into_iter()
+                .map(|g| g.name)
+                .filter(|name| name != "Everyone")
+                .collect::<Vec<_>>()
+        };
+
+        // Sorted by name by default
+        let response = app
+            .get("/admin/api/v1/groups")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        assert_eq!(names(response), ["Alpha", "Bravo", "Charlie"]);
+
+        let response = app
+            .get("/admin/api/v1/groups?sort=name&order=desc")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        assert_eq!(names(response), ["Charlie", "Bravo", "Alpha"]);
+
+        let response = app
+            .get("/admin/api/v1/groups?sort=created_at")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        assert_eq!(names(response), ["Bravo", "Alpha", "Charlie"]);
+
+        // Only whitelisted columns can be sorted by
+        let response = app
+            .get("/admin/api/v1/groups?sort=description")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_group_moderation_policy(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let group = create_test_group(&pool).await;
+        let url = format!("/admin/api/v1/groups/{}/moderation", group.id);
+
+        // Groups without a policy aren't moderated
+        let response = app
+            .get(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await;
+        response.assert_status_ok();
+        let policy: GroupModerationPolicy = response.json();
+        assert_eq!(policy.mode, ModerationMode::Off);
+        assert!(policy.blocked_patterns.is_empty());
+
+        let response = app
+            .put(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"mode": "block", "blocked_patterns": ["(?i)secret project"]}))
+            .await;
+        response.assert_status_ok();
+
+        let response = app
+            .get(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await;
+        let policy: GroupModerationPolicy = response.json();
+        assert_eq!(policy.mode, ModerationMode::Block);
+        assert_eq!(policy.blocked_patterns, vec!["(?i)secret project".to_string()]);
+
+        // Invalid patterns are rejected, leaving the policy unchanged
+        let response = app
+            .put(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"mode": "annotate", "blocked_patterns": ["(unclosed"]}))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        let response = app
+            .get(&format!("/admin/api/v1/groups/{}/moderation", uuid::Uuid::new_v4()))
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await;
+        response.assert_status_not_found();
+
+        // Standard users can't change policies
+        let response = app
+            .put(&url)
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&json!({"mode": "off"}))
+            .await;
+        response.assert_status_forbidden();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_group_log_retention(pool: PgPool) {
+        let mut config = create_test_config();
+        config.request_log_retention.enabled = true;
+        let (router, _, _drop_guard) = crate::setup_app(pool.clone(), config, true).await.unwrap();
+        let app = axum_test::TestServer::new(router).unwrap();
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let group = create_test_group(&pool).await;
+        let url = format!("/admin/api/v1/groups/{}/log-retention", group.id);
+        let retention = |retention_days, kind, effective_retention_days| GroupLogRetention {
+            retention_days,
+            kind,
+            effective_retention_days,
+        };
+        let put = |url: String, body: serde_json::Value| {
+            let request = app
+                .put(&url)
+                .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+                .json(&body);
+            async move {
+                let response = request.await;
+                response.assert_status_ok();
+                response.json::<GroupLogRetention>()
+            }
+        };
+
+        // Without an override the default applies
+        let response = app
+            .get(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<GroupLogRetention>(),
+            retention(None, LogRetentionKind::Maximum, Some(90.0))
+        );
+
+        assert_eq!(
+            put(url.clone(), json!({"retention_days": 365})).await,
+            retention(Some(365), LogRetentionKind::Maximum, Some(365.0))
+        );
+
+        // The override of the Everyone group replaces the default, not the group's override
+        let everyone_url = format!("/admin/api/v1/groups/{}/log-retention", GroupId::nil());
+        assert_eq!(
+            put(everyone_url, json!({"retention_days": 180})).await,
+            retention(Some(180), LogRetentionKind::Maximum, Some(180.0))
+        );
+        let response = app
+            .get(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await;
+        assert_eq!(
+            response.json::<GroupLogRetention>(),
+            retention(Some(365), LogRetentionKind::Maximum, Some(365.0))
+        );
+
+        // A minimum never shortens the retention
+        assert_eq!(
+            put(url.clone(), json!({"retention_days": 30, "kind": "minimum"})).await,
+            retention(Some(30), LogRetentionKind::Minimum, Some(180.0))
+        );
+
+        // Members inherit the membership, and so the overrides, of nested groups, but a minimum
+        // still wins over their maximums
+        let child = create_test_group(&pool).await;
+        sqlx::query("UPDATE groups SET parent_id = $1 WHERE id = $2")
+            .bind(group.id)
+            .bind(child.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let child_url = format!("/admin/api/v1/groups/{}/log-retention", child.id);
+        assert_eq!(
+            put(child_url, json!({"retention_days": 7})).await,
+            retention(Some(7), LogRetentionKind::Maximum, Some(7.0))
+        );
+        let response = app
+            .get(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await;
+        assert_eq!(
+            response.json::<GroupLogRetention>(),
+            retention(Some(30), LogRetentionKind::Minimum, Some(30.0))
+        );
+
+        assert_eq!(
+            put(url.clone(), json!({"retention_days": null})).await,
+            retention(None, LogRetentionKind::Maximum, Some(7.0))
+        );
+
+        let response = app
+            .put(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"retention_days": 0}))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        let response = app
+            .put(&url)
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&json!({"retention_days": 30}))
+            .await;
+        response.assert_status_forbidden();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_group_request_limits(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let group = create_test_group(&pool).await;
+        let url = format!("/admin/api/v1/groups/{}/limits", group.id);
+
+        // Groups without limits don't cap requests
+        let response = app
+            .get(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<GroupRequestLimits>(), GroupRequestLimits::default());
+
+        let response = app
+            .put(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"max_body_bytes": 65536, "max_tokens": 4096}))
+            .await;
+        response.assert_status_ok();
+
+        let response = app
+            .get(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await;
+        let limits: GroupRequestLimits = response.json();
+        assert_eq!(limits.max_body_bytes, Some(65536));
+        assert_eq!(limits.max_messages, None);
+        assert_eq!(limits.max_tokens, Some(4096));
+
+        let response = app
+            .put(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"max_messages": 0}))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        let response = app
+            .put(&url)
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&json!({}))
+            .await;
+        response.assert_status_forbidden();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_update_group_priority(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let group = create_test_group(&pool).await;
+        let url = format!("/admin/api/v1/groups/{}", group.id);
+
+        let response = app
+            .get(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await;
+        let fetched: GroupResponse = response.json();
+        assert_eq!(fetched.priority, PriorityTier::Normal);
+
+        let response = app
+            .patch(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"priority": "high"}))
+            .await;
+        response.assert_status_ok();
+        let updated: GroupResponse = response.json();
+        assert_eq!(updated.priority, PriorityTier::High);
+        assert_eq!(updated.name, group.name);
+
+        // Updating other fields leaves the priority alone
+        let response = app
+            .patch(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"description": "Batch jobs"}))
+            .await;
+        let updated: GroupResponse = response.json();
+        assert_eq!(updated.priority, PriorityTier::High);
+
+        let response = app
+            .patch(&url)
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"priority": "urgent"}))
+            .await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
     #[sqlx::test]
     #[test_log::test]
     async fn test_add_user_to_group(pool: PgPool) {
@@ -691,6 +1337,8 @@ mod tests {
             name: "Test Group".to_string(),
             description: Some("Test group for membership".to_string()),
             created_by: user1.id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -727,6 +1375,8 @@ mod tests {
             name: "Test Group".to_string(),
             description: Some("Test group for membership".to_string()),
             created_by: user1.id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -771,6 +1421,8 @@ mod tests {
             name: "Test Group".to_string(),
             description: Some("Test group for listing users".to_string()),
             created_by: user1.id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -814,6 +1466,8 @@ mod tests {
                 name: format!("Test Group {i}"),
                 description: Some(format!("Test group {i} for user membership")),
                 created_by: user.id,
+                parent_id: None,
+                is_default: false,
             };
             let group = group_repo.create(&group_create).await.expect("Failed to create test group");
             group_ids.push(group.id);
@@ -855,6 +1509,8 @@ mod tests {
             name: "Test Group".to_string(),
             description: Some("Test group for duplicate prevention".to_string()),
             created_by: user1.id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -897,6 +1553,8 @@ mod tests {
             name: "Test Group".to_string(),
             description: Some("Test symmetric endpoints".to_string()),
             created_by: user.id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -1281,6 +1939,8 @@ mod tests {
             name: "User Group 1".to_string(),
             description: Some("First group for standard user".to_string()),
             created_by: platform_manager.id,
+            parent_id: None,
+            is_default: false,
         };
         let group1 = group_repo.create(&group1_create).await.expect("Failed to create test group");
 
@@ -1288,6 +1948,8 @@ mod tests {
             name: "User Group 2".to_string(),
             description: Some("Second group for standard user".to_string()),
             created_by: platform_manager.id,
+            parent_id: None,
+            is_default: false,
         };
         let group2 = group_repo.create(&group2_create).await.expect("Failed to create test group");
 
@@ -1382,6 +2044,8 @@ mod tests {
             name: "Multi Role Test Group".to_string(),
             description: Some("Group for multi-role user test".to_string()),
             created_by: multi_role_user.id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
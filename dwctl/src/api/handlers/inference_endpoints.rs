@@ -1,31 +1,54 @@
 use crate::{
+    api::models::deployments::DeployedModelResponse,
     api::models::inference_endpoints::{
-        InferenceEndpointCreate, InferenceEndpointResponse, InferenceEndpointUpdate, InferenceEndpointValidate,
-        InferenceEndpointValidateResponse, ListEndpointsQuery, OpenAIModelsResponse,
+        EndpointAliases, EndpointCompatibilityRun, EndpointConfigVersion, EndpointHeaderRules, EndpointHistoryQuery, EndpointImportAction,
+        EndpointImportChange, EndpointImportQuery, EndpointImportResult, EndpointManifest, EndpointManifestEntry, EndpointPendingModel,
+        EndpointRateLimits, EndpointRedactionPolicy, EndpointSettingChange, EndpointSortField, EndpointSyncStrategy,
+        EndpointValidationReport, InferenceEndpointCreate, InferenceEndpointResponse, InferenceEndpointUpdate, InferenceEndpointValidate,
+        InferenceEndpointValidateResponse, ListEndpointsQuery, PendingModelsDecision, PendingModelsQuery,
     },
+    api::models::pagination::{next_cursor_headers, timestamp_value, ListCursor},
+    api::models::sorting::SortOrder,
     auth::permissions::{operation, resource, RequiresPermission},
+    azure,
     db::{
-        handlers::{inference_endpoints::InferenceEndpointFilter, Deployments, InferenceEndpoints, Repository},
-        models::inference_endpoints::{InferenceEndpointCreateDBRequest, InferenceEndpointUpdateDBRequest},
+        handlers::{
+            deployments::DeploymentFilter, inference_endpoints::InferenceEndpointFilter, Deployments, InferenceEndpoints, Repository,
+        },
+        models::{
+            deployments::{DeploymentCreateDBRequest, DeploymentDBResponse, ModelType},
+            endpoint_compatibility::EndpointCompatibilityReport,
+            endpoint_history::EndpointConfig,
+            inference_endpoints::{InferenceEndpointCreateDBRequest, InferenceEndpointUpdateDBRequest},
+        },
     },
-    errors::{Error, Result},
+    errors::{AliasConflict, Error, Result},
+    header_rules::HeaderRules,
+    probes::db::ProbeManager,
     sync::{
-        deployments::fetch_models::{FetchModels, FetchModelsReqwest, StaticModelsFetcher, SyncConfig},
+        deployments::fetch_models::{FetchModelsReqwest, StaticModelsFetcher, SyncConfig},
+        endpoint_compatibility,
         endpoint_sync::{self, sync_endpoint_models_with_aliases, update_endpoint_aliases},
+        endpoint_validation::{self, validate_endpoint_connection},
     },
-    types::InferenceEndpointId,
+    tls::EndpointTls,
+    types::{DeploymentId, InferenceEndpointId, UserId},
     AppState,
 };
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
+use sqlx::PgConnection;
+use std::collections::{BTreeSet, HashMap, HashSet};
 #[cfg(test)]
 struct MockFetchModels;
 
 #[cfg(test)]
-use crate::api::models::inference_endpoints::OpenAIModel;
+use crate::api::models::inference_endpoints::{OpenAIModel, OpenAIModelsResponse};
+#[cfg(test)]
+use crate::sync::deployments::fetch_models::FetchModels;
 
 #[cfg(test)]
 #[async_trait::async_trait]
@@ -51,6 +74,27 @@ impl FetchModels for MockFetchModels {
     }
 }
 
+/// Refuse CA bundles that can't be used to connect to the endpoint
+fn validate_ca_pem(ca_pem: Option<&str>) -> Result<()> {
+    let tls = EndpointTls {
+        ca_pem: ca_pem.map(str::to_string),
+        ..Default::default()
+    };
+    tls.validate().map_err(|message| Error::BadRequest { message })
+}
+
+/// Sync intervals shorter than this would have the scheduler hammering the endpoint
+const MIN_SYNC_INTERVAL_SECONDS: i32 = 60;
+
+fn validate_sync_interval(sync_interval_seconds: Option<i32>) -> Result<()> {
+    match sync_interval_seconds {
+        Some(seconds) if seconds < MIN_SYNC_INTERVAL_SECONDS => Err(Error::BadRequest {
+            message: format!("sync_interval_seconds must be at least {MIN_SYNC_INTERVAL_SECONDS}"),
+        }),
+        _ => Ok(()),
+    }
+}
+
 // GET /endpoints - List endpoints
 #[utoipa::path(
     get,
@@ -61,9 +105,14 @@ impl FetchModels for MockFetchModels {
     params(
         ("skip" = Option<i64>, Query, description = "Number of endpoints to skip"),
         ("limit" = Option<i64>, Query, description = "Maximum number of endpoints to return"),
+        ("sort" = Option<EndpointSortField>, Query, description = "Column to sort by (default: created_at)"),
+        ("order" = Option<SortOrder>, Query, description = "Sort direction (default: asc, or desc when neither sort nor order is given)"),
+        ("cursor" = Option<String>, Query, description = "Cursor from the x-next-cursor header of the previous page, used instead of skip"),
     ),
     responses(
-        (status = 200, description = "List of endpoints", body = [InferenceEndpointResponse]),
+        (status = 200, description = "List of endpoints", body = [InferenceEndpointResponse], headers(
+            ("x-next-cursor" = String, description = "Cursor of the next page, absent on the last page")
+        )),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error"),
     ),
@@ -75,14 +124,45 @@ pub async fn list_inference_endpoints(
     State(state): State<AppState>,
     Query(query): Query<ListEndpointsQuery>,
     _: RequiresPermission<resource::Endpoints, operation::ReadAll>, // Need at least read-own, users with ReadAll can see more
-) -> Result<Json<Vec<InferenceEndpointResponse>>> {
+) -> Result<(HeaderMap, Json<Vec<InferenceEndpointResponse>>)> {
     let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
     let mut repo = InferenceEndpoints::new(&mut conn);
     let skip = query.skip.unwrap_or(0);
     let limit = query.limit.unwrap_or(100).min(1000);
 
-    let endpoints = repo.list(&InferenceEndpointFilter::new(skip, limit)).await?;
-    Ok(Json(endpoints.into_iter().map(Into::into).collect()))
+    let mut filter = InferenceEndpointFilter::new(skip, limit);
+    if query.sort.is_some() || query.order.is_some() {
+        filter = filter.with_sort(query.sort.unwrap_or(EndpointSortField::CreatedAt), query.order.unwrap_or_default());
+    }
+    if let Some(cursor) = query.cursor.as_deref() {
+        filter = filter.with_cursor(ListCursor::decode(cursor)?);
+    }
+
+    let endpoints = repo.list(&filter).await?;
+    let next_cursor = ListCursor::next(&endpoints, limit, filter.sort, |endpoint| {
+        let value = match filter.sort.0 {
+            EndpointSortField::Name => endpoint.name.clone(),
+            EndpointSortField::Url => endpoint.url.to_string(),
+            EndpointSortField::CreatedAt => timestamp_value(endpoint.created_at),
+            EndpointSortField::UpdatedAt => timestamp_value(endpoint.updated_at),
+        };
+        (Some(value), endpoint.id)
+    });
+    let endpoint_ids: Vec<_> = endpoints.iter().map(|endpoint| endpoint.id).collect();
+    let mut health = ProbeManager::get_endpoint_health(&state.db, &endpoint_ids).await?;
+
+    Ok((
+        next_cursor_headers(next_cursor),
+        Json(
+            endpoints
+                .into_iter()
+                .map(|endpoint| {
+                    let endpoint_health = health.remove(&endpoint.id);
+                    InferenceEndpointResponse::from(endpoint).with_health(endpoint_health)
+                })
+                .collect(),
+        ),
+    ))
 }
 
 // GET /endpoints/:id - Get a specific endpoint
@@ -113,7 +193,10 @@ pub async fn get_inference_endpoint(
     let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
     let mut repo = InferenceEndpoints::new(&mut conn);
     match repo.get_by_id(id).await? {
-        Some(endpoint) => Ok(Json(endpoint.into())),
+        Some(endpoint) => {
+            let health = ProbeManager::get_endpoint_health(&state.db, &[id]).await?.remove(&id);
+            Ok(Json(InferenceEndpointResponse::from(endpoint).with_health(health)))
+        }
         None => Err(Error::NotFound {
             resource: "Endpoint".to_string(),
             id: id.to_string(),
@@ -146,9 +229,12 @@ pub async fn get_inference_endpoint(
 pub async fn update_inference_endpoint(
     State(state): State<AppState>,
     Path(id): Path<InferenceEndpointId>,
-    _: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+    current_user: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
     Json(update): Json<InferenceEndpointUpdate>,
 ) -> Result<Json<InferenceEndpointResponse>> {
+    validate_ca_pem(update.tls_ca_pem.as_ref().and_then(|pem| pem.as_deref()))?;
+    validate_sync_interval(update.sync_interval_seconds.flatten())?;
+
     // Use a transaction if alias mapping is being updated
     if update.alias_mapping.is_some() {
         let mut tx = state.db.begin().await.map_err(|e| Error::Database(e.into()))?;
@@ -166,6 +252,13 @@ pub async fn update_inference_endpoint(
             model_filter: update.model_filter.clone(),
             auth_header_name: update.auth_header_name.clone(),
             auth_header_prefix: update.auth_header_prefix.clone(),
+            tls_ca_pem: update.tls_ca_pem.clone(),
+            tls_skip_verify: update.tls_skip_verify,
+            tls_min_version: update
+                .tls_min_version
+                .map(|version| version.map(|version| version.as_str().to_string())),
+            sync_interval_seconds: update.sync_interval_seconds,
+            enabled: update.enabled,
         };
 
         let endpoint = repo.update(id, &db_request).await?;
@@ -197,6 +290,9 @@ pub async fn update_inference_endpoint(
                 return Err(converted_error);
             }
         }
+        InferenceEndpoints::new(&mut tx)
+            .record_version(endpoint.id, current_user.id)
+            .await?;
         tx.commit().await.map_err(|e| Error::Database(e.into()))?;
         Ok(Json(endpoint.into()))
     } else {
@@ -216,22 +312,34 @@ pub async fn update_inference_endpoint(
             model_filter: update.model_filter,
             auth_header_name: update.auth_header_name,
             auth_header_prefix: update.auth_header_prefix,
+            tls_ca_pem: update.tls_ca_pem,
+            tls_skip_verify: update.tls_skip_verify,
+            tls_min_version: update
+                .tls_min_version
+                .map(|version| version.map(|version| version.as_str().to_string())),
+            sync_interval_seconds: update.sync_interval_seconds,
+            enabled: update.enabled,
         };
 
         let endpoint = repo.update(id, &db_request).await?;
+        repo.record_version(endpoint.id, current_user.id).await?;
 
-        // Perform background sync after successful update
-        match endpoint_sync::synchronize_endpoint(endpoint.id, state.db.clone()).await {
-            Ok(sync_result) => {
-                tracing::info!(
-                    "Auto-sync after endpoint {} update: {} changes made",
-                    endpoint.id,
-                    sync_result.changes_made
-                );
-            }
-            Err(e) => {
-                tracing::warn!("Auto-sync failed after endpoint {} update: {}", endpoint.id, e);
-                // Continue anyway - update succeeded even if sync failed
+        // Perform background sync after successful update, unless the endpoint is disabled
+        if !endpoint.enabled {
+            tracing::info!("Skipped auto-sync after endpoint {} update (endpoint disabled)", endpoint.id);
+        } else {
+            match endpoint_sync::synchronize_endpoint(endpoint.id, state.db.clone()).await {
+                Ok(sync_result) => {
+                    tracing::info!(
+                        "Auto-sync after endpoint {} update: {} changes made",
+                        endpoint.id,
+                        sync_result.changes_made
+                    );
+                }
+                Err(e) => {
+                    tracing::warn!("Auto-sync failed after endpoint {} update: {}", endpoint.id, e);
+                    // Continue anyway - update succeeded even if sync failed
+                }
             }
         }
         Ok(Json(endpoint.into()))
@@ -261,17 +369,27 @@ pub async fn validate_inference_endpoint(
     _: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
     Json(validate_request): Json<InferenceEndpointValidate>,
 ) -> Result<Json<InferenceEndpointValidateResponse>> {
-    let (url, api_key, auth_header_name, auth_header_prefix) = match validate_request {
+    let (url, api_key, auth_header_name, auth_header_prefix, tls) = match validate_request {
         InferenceEndpointValidate::New {
             url,
             api_key,
             auth_header_name,
             auth_header_prefix,
+            tls_ca_pem,
+            tls_skip_verify,
+            tls_min_version,
         } => {
             let parsed_url = url.parse::<url::Url>().map_err(|_| Error::BadRequest {
                 message: "Invalid URL format".to_string(),
             })?;
-            (parsed_url, api_key, auth_header_name, auth_header_prefix)
+            let (auth_header_name, auth_header_prefix) = azure::default_auth_header(&parsed_url, auth_header_name, auth_header_prefix);
+            validate_ca_pem(tls_ca_pem.as_deref())?;
+            let tls = EndpointTls {
+                ca_pem: tls_ca_pem,
+                skip_verify: tls_skip_verify,
+                min_version: tls_min_version,
+            };
+            (parsed_url, api_key, auth_header_name, auth_header_prefix, tls)
         }
         InferenceEndpointValidate::Existing { endpoint_id } => {
             let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
@@ -281,11 +399,13 @@ pub async fn validate_inference_endpoint(
                 resource: "Endpoint".to_string(),
                 id: endpoint_id.to_string(),
             })?;
+            let tls = EndpointTls::from_endpoint(&endpoint);
             (
                 endpoint.url,
                 endpoint.api_key,
                 Some(endpoint.auth_header_name),
                 Some(endpoint.auth_header_prefix),
+                tls,
             )
         }
     };
@@ -298,7 +418,7 @@ pub async fn validate_inference_endpoint(
         auth_header_prefix
     );
 
-    let models = validate_endpoint_connection(&url, api_key.as_deref(), auth_header_name, auth_header_prefix).await?;
+    let models = validate_endpoint_connection(&url, api_key.as_deref(), auth_header_name, auth_header_prefix, &tls).await?;
     Ok(Json(InferenceEndpointValidateResponse {
         status: "success".to_string(),
         models: Some(models),
@@ -333,6 +453,10 @@ pub async fn create_inference_endpoint(
     let url = create_request.url.parse().map_err(|_| Error::BadRequest {
         message: "Invalid URL format".to_string(),
     })?;
+    let (auth_header_name, auth_header_prefix) =
+        azure::default_auth_header(&url, create_request.auth_header_name, create_request.auth_header_prefix);
+    validate_ca_pem(create_request.tls_ca_pem.as_deref())?;
+    validate_sync_interval(create_request.sync_interval_seconds)?;
 
     // Start transaction for atomic endpoint creation + sync
     let mut tx = state.db.begin().await.map_err(|e| Error::Database(e.into()))?;
@@ -346,11 +470,15 @@ pub async fn create_inference_endpoint(
         url,
         api_key: create_request.api_key,
         model_filter: create_request.model_filter.clone(),
-        auth_header_name: create_request.auth_header_name,
-        auth_header_prefix: create_request.auth_header_prefix,
+        auth_header_name,
+        auth_header_prefix,
+        tls_ca_pem: create_request.tls_ca_pem,
+        tls_skip_verify: create_request.tls_skip_verify,
+        tls_min_version: create_request.tls_min_version.map(|version| version.as_str().to_string()),
+        sync_interval_seconds: create_request.sync_interval_seconds,
     };
 
-    let endpoint = repo.create(&db_request).await?;
+    let mut endpoint = repo.create(&db_request).await?;
 
     // Optionally sync models during creation
     if create_request.sync {
@@ -375,13 +503,20 @@ pub async fn create_inference_endpoint(
             sync_endpoint_models_with_aliases(endpoint.clone(), &mut deployments_repo, fetcher, &create_request.alias_mapping).await
         } else {
             // Fetch models from endpoint
-            let fetcher = FetchModelsReqwest::new(SyncConfig::from_endpoint(&endpoint));
+            let fetcher = FetchModelsReqwest::new(SyncConfig::from_endpoint(&endpoint).await.map_err(|e| Error::BadRequest {
+                message: format!("Failed to resolve the endpoint's API key: {e:#}"),
+            })?);
             sync_endpoint_models_with_aliases(endpoint.clone(), &mut deployments_repo, fetcher, &create_request.alias_mapping).await
         };
 
         match sync_result {
             Ok(result) => {
                 tracing::info!("Sync succeeded: {:?}", result);
+                let mut repo = InferenceEndpoints::new(&mut tx);
+                repo.record_sync(endpoint.id, None).await?;
+                endpoint = repo.get_by_id(endpoint.id).await?.ok_or_else(|| Error::Internal {
+                    operation: "reload endpoint after sync".to_string(),
+                })?;
             }
             Err(sync_error) => {
                 tracing::error!("Sync failed with error: {:?}", sync_error);
@@ -404,6 +539,9 @@ pub async fn create_inference_endpoint(
     } else {
         tracing::info!("Skipped sync during endpoint {} creation (sync=false)", endpoint.id);
     }
+    InferenceEndpoints::new(&mut tx)
+        .record_version(endpoint.id, current_user.id)
+        .await?;
 
     tx.commit().await.map_err(|e| Error::Database(e.into()))?;
     Ok((StatusCode::CREATED, Json(endpoint.into())))
@@ -447,62 +585,29 @@ pub async fn delete_inference_endpoint(
     }
 }
 
-// Helper: Validate endpoint connection and fetch models
-async fn validate_endpoint_connection(
-    url: &url::Url,
-    api_key: Option<&str>,
-    auth_header_name: Option<String>,
-    auth_header_prefix: Option<String>,
-) -> Result<OpenAIModelsResponse> {
-    use std::time::Duration;
-
-    let auth_header_name = auth_header_name.unwrap_or_else(|| "Authorization".to_string());
-    let auth_header_prefix = auth_header_prefix.unwrap_or_else(|| "Bearer ".to_string());
-
-    tracing::debug!(
-        "Creating SyncConfig for validation: url={}, auth_header_name={}, auth_header_prefix={:?}, has_api_key={}",
-        url,
-        auth_header_name,
-        auth_header_prefix,
-        api_key.is_some()
-    );
-
-    let sync_config = SyncConfig {
-        openai_api_key: api_key.map(|s| s.to_string()),
-        openai_base_url: url.clone(),
-        auth_header_name,
-        auth_header_prefix,
-        request_timeout: Duration::from_secs(10),
-    };
-
-    // Use the existing FetchModelsReqwest implementation
-    let fetcher = FetchModelsReqwest::new(sync_config);
-
-    tracing::debug!("Fetching models from endpoint...");
-    let models_response = fetcher.fetch().await.map_err(|e| {
-        tracing::error!("Failed to fetch models: {:#}", e);
-        e
-    })?;
-
-    tracing::debug!(
-        "Received models response: object={}, model_count={}",
-        models_response.object,
-        models_response.data.len()
-    );
-
-    if models_response.object != "list" {
-        return Err(Error::BadRequest {
-            message: "Invalid response format - expected 'list' object".to_string(),
-        });
-    }
-    if models_response.data.is_empty() {
-        return Err(Error::BadRequest {
-            message: "No models found at this endpoint".to_string(),
-        });
-    }
-
-    // The OpenAIModelsResponse is already in the right format
-    Ok(models_response)
+// GET /endpoints/validation-report - Latest scheduled validation results (admin only)
+#[utoipa::path(
+    get,
+    path = "/endpoints/validation-report",
+    tag = "endpoints",
+    summary = "Endpoint validation report",
+    description = "Get the most recent scheduled validation outcome for every endpoint, including endpoints whose credentials have been rejected (admin only)",
+    responses(
+        (status = 200, description = "Validation report", body = EndpointValidationReport),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_validation_report(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+) -> Result<Json<EndpointValidationReport>> {
+    let statuses = endpoint_validation::get_validation_report(&state.db).await?;
+    Ok(Json(statuses.into()))
 }
 
 // POST /endpoints/:id/synchronize - Synchronize endpoint deployments (admin only)
@@ -538,100 +643,1355 @@ pub async fn synchronize_endpoint(
     Ok(Json(response))
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::api::models::deployments::DeployedModelResponse;
-    use crate::api::models::inference_endpoints::InferenceEndpointResponse;
-    use crate::api::models::users::Role;
-    use crate::test_utils::*;
-    use serde_json::json;
-    use sqlx::PgPool;
+/// Number of compatibility reports returned per endpoint
+const COMPATIBILITY_REPORT_LIMIT: i64 = 50;
 
-    #[sqlx::test]
-    #[test_log::test]
-    async fn test_list_inference_endpoints(pool: PgPool) {
-        let (app, _) = create_test_app(pool.clone(), false).await;
-        let user = create_test_user(&pool, Role::StandardUser).await;
+// POST /endpoints/:id/compatibility - Run the OpenAI compatibility suite (admin only)
+#[utoipa::path(
+    post,
+    path = "/endpoints/{id}/compatibility",
+    tag = "endpoints",
+    summary = "Run compatibility suite",
+    description = "Run the OpenAI compatibility suite (chat completions, streaming, tool calls, JSON mode, stop sequences and error shapes) \
+                   directly against an endpoint, and store the scored report (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID to test"),
+    ),
+    request_body = EndpointCompatibilityRun,
+    responses(
+        (status = 201, description = "Suite completed and report stored", body = EndpointCompatibilityReport),
+        (status = 400, description = "Bad request - the endpoint lists no models to test with"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn run_compatibility_suite(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    current_user: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+    Json(request): Json<EndpointCompatibilityRun>,
+) -> Result<(StatusCode, Json<EndpointCompatibilityReport>)> {
+    let endpoint = {
+        let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+        InferenceEndpoints::new(&mut conn)
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| Error::NotFound {
+                resource: "Endpoint".to_string(),
+                id: id.to_string(),
+            })?
+    };
 
-        let response = app
-            .get("/admin/api/v1/endpoints")
-            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
-            .await;
+    let report = endpoint_compatibility::run_compatibility_suite(&state.db, &endpoint, request.model, current_user.current_user.id).await?;
+    Ok((StatusCode::CREATED, Json(report)))
+}
 
-        response.assert_status_ok();
-        let endpoints: Vec<InferenceEndpointResponse> = response.json();
-        // Should have at least the default endpoint
-        assert!(!endpoints.is_empty());
-        assert!(endpoints.iter().any(|e| e.name == "test"));
+// GET /endpoints/:id/compatibility - Stored compatibility reports (admin only)
+#[utoipa::path(
+    get,
+    path = "/endpoints/{id}/compatibility",
+    tag = "endpoints",
+    summary = "List compatibility reports",
+    description = "Get the stored OpenAI compatibility reports for an endpoint, most recent first (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    responses(
+        (status = 200, description = "Compatibility reports", body = Vec<EndpointCompatibilityReport>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_compatibility_reports(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    _: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+) -> Result<Json<Vec<EndpointCompatibilityReport>>> {
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    if InferenceEndpoints::new(&mut conn).get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
     }
 
-    // Helper function to get the test endpoint ID
-    async fn get_test_endpoint_id(
-        app: &axum_test::TestServer,
-        user: &crate::api::models::users::UserResponse,
-    ) -> crate::types::InferenceEndpointId {
-        let response = app
-            .get("/admin/api/v1/endpoints")
-            .add_header(add_auth_headers(user).0, add_auth_headers(user).1)
-            .await;
+    let reports = endpoint_compatibility::list_compatibility_reports(&state.db, id, COMPATIBILITY_REPORT_LIMIT).await?;
+    Ok(Json(reports))
+}
 
-        response.assert_status_ok();
-        let endpoints: Vec<InferenceEndpointResponse> = response.json();
-        endpoints.iter().find(|e| e.name == "test").expect("Test endpoint should exist").id
+// GET /endpoints/:id/redaction - Get the endpoint's redaction policy (admin only)
+#[utoipa::path(
+    get,
+    path = "/endpoints/{id}/redaction",
+    tag = "endpoints",
+    summary = "Get endpoint redaction policy",
+    description = "Get the PII redaction policy applied to requests forwarded to an endpoint (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    responses(
+        (status = 200, description = "Redaction policy (empty if none has been set)", body = EndpointRedactionPolicy),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_endpoint_redaction(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    _: RequiresPermission<resource::Endpoints, operation::ReadAll>,
+) -> Result<Json<EndpointRedactionPolicy>> {
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut conn);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
     }
 
-    #[sqlx::test]
-    #[test_log::test]
-    async fn test_get_inference_endpoint(pool: PgPool) {
-        let (app, _) = create_test_app(pool.clone(), false).await;
-        let user = create_test_user(&pool, Role::StandardUser).await;
-        let test_endpoint_id = get_test_endpoint_id(&app, &user).await;
-
-        let response = app
-            .get(&format!("/admin/api/v1/endpoints/{test_endpoint_id}"))
-            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
-            .await;
+    let policy = repo.get_redaction_policy(id).await?;
+    Ok(Json(policy.map(EndpointRedactionPolicy::from).unwrap_or_default()))
+}
 
-        response.assert_status_ok();
-        let endpoint: InferenceEndpointResponse = response.json();
-        assert_eq!(endpoint.id, test_endpoint_id);
-        assert_eq!(endpoint.name, "test");
+// PUT /endpoints/:id/redaction - Set the endpoint's redaction policy (admin only)
+#[utoipa::path(
+    put,
+    path = "/endpoints/{id}/redaction",
+    tag = "endpoints",
+    summary = "Set endpoint redaction policy",
+    description = "Replace the PII redaction policy of an endpoint. Emails, phone numbers and financial details (as selected) and \
+                   matches of the patterns are masked in the prompts of requests before they are forwarded to the endpoint, \
+                   including when it serves a fallback, weighted target or canary. The request log keeps the original request \
+                   only if log_original is set (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    request_body = EndpointRedactionPolicy,
+    responses(
+        (status = 200, description = "Redaction policy updated", body = EndpointRedactionPolicy),
+        (status = 400, description = "Invalid regular expression"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn set_endpoint_redaction(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    _: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+    Json(policy): Json<EndpointRedactionPolicy>,
+) -> Result<Json<EndpointRedactionPolicy>> {
+    for pattern in &policy.patterns {
+        if let Err(e) = regex::Regex::new(pattern) {
+            return Err(Error::BadRequest {
+                message: format!("Invalid redaction pattern '{pattern}': {e}"),
+            });
+        }
     }
 
-    #[sqlx::test]
-    #[test_log::test]
-    async fn test_get_nonexistent_inference_endpoint(pool: PgPool) {
-        let (app, _) = create_test_app(pool.clone(), false).await;
-        let user = create_test_user(&pool, Role::StandardUser).await;
-        let non_existent_id = uuid::Uuid::new_v4();
-
-        let response = app
-            .get(&format!("/admin/api/v1/endpoints/{non_existent_id}"))
-            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
-            .await;
-
-        response.assert_status_not_found();
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut conn);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
     }
 
-    #[sqlx::test]
-    #[test_log::test]
-    async fn test_update_inference_endpoint_as_admin(pool: PgPool) {
-        let (app, _) = create_test_app(pool.clone(), false).await;
-        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
-        let test_endpoint_id = get_test_endpoint_id(&app, &admin_user).await;
+    let policy = repo.set_redaction_policy(id, &policy.into()).await?;
+    Ok(Json(policy.into()))
+}
 
-        let update = json!({
-            "name": "Updated Default"
+// GET /endpoints/:id/headers - Get the endpoint's header rules (admin only)
+#[utoipa::path(
+    get,
+    path = "/endpoints/{id}/headers",
+    tag = "endpoints",
+    summary = "Get endpoint header rules",
+    description = "Get the headers injected into requests forwarded to an endpoint and the client headers passed through (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    responses(
+        (status = 200, description = "Header rules (empty if none have been set)", body = EndpointHeaderRules),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_endpoint_headers(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    _: RequiresPermission<resource::Endpoints, operation::ReadAll>,
+) -> Result<Json<EndpointHeaderRules>> {
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut conn);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
         });
+    }
 
-        let response = app
-            .patch(&format!("/admin/api/v1/endpoints/{test_endpoint_id}"))
-            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
-            .json(&update)
-            .await;
+    let rules = repo.get_header_rules(id).await?;
+    Ok(Json(rules.map(EndpointHeaderRules::from).unwrap_or_default()))
+}
 
-        response.assert_status_ok();
-        let updated_endpoint: InferenceEndpointResponse = response.json();
+// PUT /endpoints/:id/headers - Set the endpoint's header rules (admin only)
+#[utoipa::path(
+    put,
+    path = "/endpoints/{id}/headers",
+    tag = "endpoints",
+    summary = "Set endpoint header rules",
+    description = "Replace the header rules of an endpoint. The inject headers are added to requests before they are forwarded to \
+                   the endpoint, including when it serves a fallback, weighted target, canary or shadow. If passthrough is set, \
+                   only the listed client headers are forwarded along with those the proxy needs. Headers the proxy sets itself, \
+                   such as authorization and host, can't be injected (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    request_body = EndpointHeaderRules,
+    responses(
+        (status = 200, description = "Header rules updated", body = EndpointHeaderRules),
+        (status = 400, description = "Invalid or reserved header"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn set_endpoint_headers(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    _: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+    Json(rules): Json<EndpointHeaderRules>,
+) -> Result<Json<EndpointHeaderRules>> {
+    let rules = rules.normalized();
+    let inject = rules.inject.iter().map(|(name, value)| (name.as_str(), value.as_str()));
+    if let Err(e) = HeaderRules::new(inject, rules.passthrough.as_deref()) {
+        return Err(Error::BadRequest {
+            message: format!("Invalid header rules: {e}"),
+        });
+    }
+
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut conn);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    let rules = repo.set_header_rules(id, &rules.into()).await?;
+    Ok(Json(rules.into()))
+}
+
+// GET /endpoints/:id/rate-limits - Get the endpoint's upstream rate limits (admin only)
+#[utoipa::path(
+    get,
+    path = "/endpoints/{id}/rate-limits",
+    tag = "endpoints",
+    summary = "Get endpoint rate limits",
+    description = "Get the request rate and concurrency limits on requests forwarded to an endpoint (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    responses(
+        (status = 200, description = "Rate limits (empty if none have been set)", body = EndpointRateLimits),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_endpoint_rate_limits(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    _: RequiresPermission<resource::Endpoints, operation::ReadAll>,
+) -> Result<Json<EndpointRateLimits>> {
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut conn);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    let limits = repo.get_rate_limits(id).await?;
+    Ok(Json(limits.map(EndpointRateLimits::from).unwrap_or_default()))
+}
+
+// PUT /endpoints/:id/rate-limits - Set the endpoint's upstream rate limits (admin only)
+#[utoipa::path(
+    put,
+    path = "/endpoints/{id}/rate-limits",
+    tag = "endpoints",
+    summary = "Set endpoint rate limits",
+    description = "Replace the rate limits of an endpoint. They apply to all requests the proxy forwards to the endpoint, \
+                   whichever deployment, fallback, weighted target, canary or shadow they are for. Requests that would exceed \
+                   requests_per_second (with up to burst_size at once) or max_concurrent requests in flight are refused with a \
+                   429 without reaching the endpoint, and retried against the deployment's fallbacks if it has any. The \
+                   limits are totals across replicas: each enforces an even share of them, never below one request. Omitted \
+                   limits aren't enforced (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    request_body = EndpointRateLimits,
+    responses(
+        (status = 200, description = "Rate limits updated", body = EndpointRateLimits),
+        (status = 400, description = "Invalid rate limits"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn set_endpoint_rate_limits(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    _: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+    Json(limits): Json<EndpointRateLimits>,
+) -> Result<Json<EndpointRateLimits>> {
+    if let Err(message) = limits.validate() {
+        return Err(Error::BadRequest {
+            message: format!("Invalid rate limits: {message}"),
+        });
+    }
+
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut conn);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    let limits = repo.set_rate_limits(id, &limits.into()).await?;
+    Ok(Json(limits.into()))
+}
+
+// GET /endpoints/:id/sync-strategy - Get the endpoint's sync strategy (admin only)
+#[utoipa::path(
+    get,
+    path = "/endpoints/{id}/sync-strategy",
+    tag = "endpoints",
+    summary = "Get endpoint sync strategy",
+    description = "Get how syncing an endpoint treats models removed from and added to its upstream model list (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    responses(
+        (status = 200, description = "Sync strategy (the default if none has been set)", body = EndpointSyncStrategy),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_endpoint_sync_strategy(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    _: RequiresPermission<resource::Endpoints, operation::ReadAll>,
+) -> Result<Json<EndpointSyncStrategy>> {
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut conn);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    let strategy = repo.get_sync_strategy(id).await?;
+    Ok(Json(strategy.map(EndpointSyncStrategy::from).unwrap_or_default()))
+}
+
+// PUT /endpoints/:id/sync-strategy - Set the endpoint's sync strategy (admin only)
+#[utoipa::path(
+    put,
+    path = "/endpoints/{id}/sync-strategy",
+    tag = "endpoints",
+    summary = "Set endpoint sync strategy",
+    description = "Choose what syncing an endpoint does with deployments of models no longer listed upstream (disable, delete \
+                   or keep them) and with models newly listed (deploy them, or queue them for approval). Applies from the next \
+                   sync; the models deployed when the endpoint is created are chosen by the admin creating it (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    request_body = EndpointSyncStrategy,
+    responses(
+        (status = 200, description = "Sync strategy updated", body = EndpointSyncStrategy),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn set_endpoint_sync_strategy(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    _: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+    Json(strategy): Json<EndpointSyncStrategy>,
+) -> Result<Json<EndpointSyncStrategy>> {
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut conn);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    let strategy = repo.set_sync_strategy(id, &strategy.into()).await?;
+    Ok(Json(strategy.into()))
+}
+
+// GET /endpoints/:id/pending-models - List the models awaiting approval (admin only)
+#[utoipa::path(
+    get,
+    path = "/endpoints/{id}/pending-models",
+    tag = "endpoints",
+    summary = "List pending models",
+    description = "List the models found upstream by syncs of an endpoint that queues new models, awaiting approval (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+        PendingModelsQuery,
+    ),
+    responses(
+        (status = 200, description = "Queued models, oldest first", body = [EndpointPendingModel]),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_endpoint_pending_models(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    Query(query): Query<PendingModelsQuery>,
+    _: RequiresPermission<resource::Endpoints, operation::ReadAll>,
+) -> Result<Json<Vec<EndpointPendingModel>>> {
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut conn);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    let models = repo.list_pending_models(id, query.include_rejected.unwrap_or(false)).await?;
+    Ok(Json(models.into_iter().map(EndpointPendingModel::from).collect()))
+}
+
+// POST /endpoints/:id/pending-models/approve - Deploy queued models (admin only)
+#[utoipa::path(
+    post,
+    path = "/endpoints/{id}/pending-models/approve",
+    tag = "endpoints",
+    summary = "Approve pending models",
+    description = "Deploy queued models, pending or rejected, under their own name. Models are deployed all at once or not at \
+                   all: if any isn't queued or its name is already used as an alias, nothing is deployed (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    request_body = PendingModelsDecision,
+    responses(
+        (status = 200, description = "Deployments created", body = [DeployedModelResponse]),
+        (status = 400, description = "Models that aren't queued"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 409, description = "Alias conflicts"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn approve_endpoint_pending_models(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    current_user: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+    Json(decision): Json<PendingModelsDecision>,
+) -> Result<Json<Vec<DeployedModelResponse>>> {
+    let mut tx = state.db.begin().await.map_err(|e| Error::Database(e.into()))?;
+    let mut endpoints_repo = InferenceEndpoints::new(&mut tx);
+    if endpoints_repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    let approved = endpoints_repo.approve_pending_models(id, &decision.model_names).await?;
+    let unknown: Vec<&str> = decision
+        .model_names
+        .iter()
+        .filter(|model_name| !approved.contains(model_name))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(Error::BadRequest {
+            message: format!("Models not queued on the endpoint: {}", unknown.join(", ")),
+        });
+    }
+
+    let mut repo = Deployments::new(&mut tx);
+    let taken = repo
+        .list(&DeploymentFilter::new(0, i64::MAX).with_aliases(approved.clone()))
+        .await?;
+    if !taken.is_empty() {
+        return Err(Error::Conflict {
+            message: "Alias conflicts detected, no models were deployed".to_string(),
+            conflicts: Some(
+                taken
+                    .into_iter()
+                    .map(|other| AliasConflict {
+                        model_name: other.alias.clone(),
+                        attempted_alias: other.alias,
+                    })
+                    .collect(),
+            ),
+        });
+    }
+
+    let mut deployments = Vec::with_capacity(approved.len());
+    for model_name in &approved {
+        let request = DeploymentCreateDBRequest::builder()
+            .created_by(current_user.id)
+            .model_name(model_name.clone())
+            .alias(model_name.clone())
+            .maybe_model_type(Some(ModelType::detect_from_name(model_name)))
+            .hosted_on(id)
+            .build();
+        deployments.push(DeployedModelResponse::from(repo.create(&request).await?));
+    }
+    InferenceEndpoints::new(&mut tx).record_version(id, current_user.id).await?;
+    tx.commit().await.map_err(|e| Error::Database(e.into()))?;
+    tracing::info!("Deployed {} approved models on endpoint {}", deployments.len(), id);
+
+    Ok(Json(deployments))
+}
+
+// POST /endpoints/:id/pending-models/reject - Reject queued models (admin only)
+#[utoipa::path(
+    post,
+    path = "/endpoints/{id}/pending-models/reject",
+    tag = "endpoints",
+    summary = "Reject pending models",
+    description = "Reject queued models. They stay in the queue as rejected, so later syncs don't queue them again, and can still \
+                   be approved (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    request_body = PendingModelsDecision,
+    responses(
+        (status = 200, description = "Models rejected", body = [EndpointPendingModel]),
+        (status = 400, description = "Models that aren't queued"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn reject_endpoint_pending_models(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    current_user: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+    Json(decision): Json<PendingModelsDecision>,
+) -> Result<Json<Vec<EndpointPendingModel>>> {
+    let mut tx = state.db.begin().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut tx);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    let rejected = repo.reject_pending_models(id, &decision.model_names, current_user.id).await?;
+    let unknown: Vec<&str> = decision
+        .model_names
+        .iter()
+        .filter(|model_name| !rejected.iter().any(|model| model.model_name == **model_name))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(Error::BadRequest {
+            message: format!("Models not queued on the endpoint: {}", unknown.join(", ")),
+        });
+    }
+    tx.commit().await.map_err(|e| Error::Database(e.into()))?;
+
+    Ok(Json(rejected.into_iter().map(EndpointPendingModel::from).collect()))
+}
+
+// GET /endpoints/:id/history - List versions of the endpoint's configuration (admin only)
+#[utoipa::path(
+    get,
+    path = "/endpoints/{id}/history",
+    tag = "endpoints",
+    summary = "Get endpoint history",
+    description = "List the versions of an endpoint's configuration and of its deployments' aliases, newest first, \
+                   with who made each change and what it changed. API keys aren't versioned (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+        EndpointHistoryQuery
+    ),
+    responses(
+        (status = 200, description = "Configuration versions", body = [EndpointConfigVersion]),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_endpoint_history(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    Query(query): Query<EndpointHistoryQuery>,
+    _: RequiresPermission<resource::Endpoints, operation::ReadAll>,
+) -> Result<Json<Vec<EndpointConfigVersion>>> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut conn);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    // Fetch one more version than returned, to work out what the oldest one changed
+    let versions = repo.list_versions(id, limit + 1).await?;
+    Ok(Json(EndpointConfigVersion::from_versions(versions, limit as usize)))
+}
+
+// POST /endpoints/:id/rollback/:version - Restore a version of the endpoint's configuration (admin only)
+#[utoipa::path(
+    post,
+    path = "/endpoints/{id}/rollback/{version}",
+    tag = "endpoints",
+    summary = "Roll back endpoint",
+    description = "Restore the endpoint's configuration and its deployments' aliases as of an earlier version, recording \
+                   the result as a new version. The endpoint's API key is left as it is, and models added since keep their \
+                   aliases (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+        ("version" = i32, Path, description = "Version to restore"),
+    ),
+    responses(
+        (status = 200, description = "Endpoint rolled back", body = InferenceEndpointResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint or version not found"),
+        (status = 409, description = "An alias of the version is now used by another deployment"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn rollback_inference_endpoint(
+    State(state): State<AppState>,
+    Path((id, version)): Path<(InferenceEndpointId, i32)>,
+    current_user: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+) -> Result<Json<InferenceEndpointResponse>> {
+    let mut tx = state.db.begin().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut tx);
+    if repo.get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+    let config = repo
+        .get_version(id, version)
+        .await?
+        .ok_or_else(|| Error::NotFound {
+            resource: "Endpoint version".to_string(),
+            id: version.to_string(),
+        })?
+        .config
+        .0;
+
+    let endpoint = repo.restore_config(id, &config).await?;
+    let alias_mapping: std::collections::HashMap<String, String> = config.aliases.into_iter().collect();
+    let mut deployments_repo = Deployments::new(&mut tx);
+    if let Err(sync_error) = update_endpoint_aliases(endpoint.clone(), &mut deployments_repo, &alias_mapping).await {
+        tracing::error!("Failed to restore aliases of endpoint {}: {}", endpoint.id, sync_error);
+        return Err(match sync_error {
+            crate::sync::endpoint_sync::SyncError::AliasConflicts { conflicts } => Error::Conflict {
+                message: "Alias conflicts detected during endpoint rollback".to_string(),
+                conflicts: Some(conflicts),
+            },
+            crate::sync::endpoint_sync::SyncError::Other(_) => Error::Internal {
+                operation: "restore endpoint aliases".to_string(),
+            },
+        });
+    }
+
+    InferenceEndpoints::new(&mut tx)
+        .record_version(endpoint.id, current_user.id)
+        .await?;
+    tx.commit().await.map_err(|e| Error::Database(e.into()))?;
+    tracing::info!("Rolled back endpoint {} to version {}", endpoint.id, version);
+    Ok(Json(endpoint.into()))
+}
+
+// GET /endpoints/:id/aliases - Get the aliases of the endpoint's deployments (admin only)
+#[utoipa::path(
+    get,
+    path = "/endpoints/{id}/aliases",
+    tag = "endpoints",
+    summary = "Get endpoint aliases",
+    description = "Get the alias of each model deployed on an endpoint (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    responses(
+        (status = 200, description = "Aliases of the endpoint's deployments", body = EndpointAliases),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_endpoint_aliases(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    _: RequiresPermission<resource::Endpoints, operation::ReadAll>,
+) -> Result<Json<EndpointAliases>> {
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    if InferenceEndpoints::new(&mut conn).get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    let deployments = Deployments::new(&mut conn)
+        .list(&DeploymentFilter::new(0, i64::MAX).with_endpoint(id).with_deleted(false))
+        .await?;
+    Ok(Json(EndpointAliases {
+        aliases: deployments.into_iter().map(|d| (d.model_name, d.alias)).collect(),
+    }))
+}
+
+// PUT /endpoints/:id/aliases - Replace the aliases of the endpoint's deployments (admin only)
+#[utoipa::path(
+    put,
+    path = "/endpoints/{id}/aliases",
+    tag = "endpoints",
+    summary = "Set endpoint aliases",
+    description = "Replace the aliases of the models deployed on an endpoint. Models left out of the map are served under \
+                   their own name. Aliases are changed all at once, so they can be swapped between models, or not at all: if \
+                   any alias is used by another deployment or given to several models, nothing is changed and every conflict \
+                   is returned. Deployments aren't created or removed (admin only)",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Endpoint ID"),
+    ),
+    request_body = EndpointAliases,
+    responses(
+        (status = 200, description = "Aliases updated", body = EndpointAliases),
+        (status = 400, description = "Empty alias, or a model that isn't deployed on the endpoint"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint not found"),
+        (status = 409, description = "Alias conflicts"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn set_endpoint_aliases(
+    State(state): State<AppState>,
+    Path(id): Path<InferenceEndpointId>,
+    current_user: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+    Json(request): Json<EndpointAliases>,
+) -> Result<Json<EndpointAliases>> {
+    let mut tx = state.db.begin().await.map_err(|e| Error::Database(e.into()))?;
+    if InferenceEndpoints::new(&mut tx).get_by_id(id).await?.is_none() {
+        return Err(Error::NotFound {
+            resource: "Endpoint".to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    let mut repo = Deployments::new(&mut tx);
+    let deployments = repo
+        .list(&DeploymentFilter::new(0, i64::MAX).with_endpoint(id).with_deleted(false))
+        .await?;
+    let unknown: Vec<&str> = request
+        .aliases
+        .keys()
+        .filter(|model_name| !deployments.iter().any(|d| d.model_name == **model_name))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(Error::BadRequest {
+            message: format!("Models not deployed on the endpoint: {}", unknown.join(", ")),
+        });
+    }
+    if request.aliases.values().any(|alias| alias.trim().is_empty()) {
+        return Err(Error::BadRequest {
+            message: "Aliases must not be empty or whitespace".to_string(),
+        });
+    }
+
+    let aliases: Vec<(&DeploymentDBResponse, String)> = deployments
+        .iter()
+        .map(|d| {
+            let alias = request.aliases.get(&d.model_name).unwrap_or(&d.model_name);
+            (d, alias.trim().to_string())
+        })
+        .collect();
+
+    // Aliases given to several of the endpoint's models, or used by deployments elsewhere
+    let mut conflicts = Vec::new();
+    for (deployment, alias) in &aliases {
+        if aliases
+            .iter()
+            .any(|(other, other_alias)| other.id != deployment.id && other_alias == alias)
+        {
+            conflicts.push(AliasConflict {
+                model_name: deployment.model_name.clone(),
+                attempted_alias: alias.clone(),
+            });
+        }
+    }
+    let taken = repo
+        .list(&DeploymentFilter::new(0, i64::MAX).with_aliases(aliases.iter().map(|(_, alias)| alias.clone()).collect()))
+        .await?;
+    for other in taken.iter().filter(|other| !deployments.iter().any(|d| d.id == other.id)) {
+        conflicts.extend(
+            aliases
+                .iter()
+                .filter(|(_, alias)| *alias == other.alias)
+                .map(|(deployment, alias)| AliasConflict {
+                    model_name: deployment.model_name.clone(),
+                    attempted_alias: alias.clone(),
+                }),
+        );
+    }
+    if !conflicts.is_empty() {
+        return Err(Error::Conflict {
+            message: "Alias conflicts detected, no aliases were changed".to_string(),
+            conflicts: Some(conflicts),
+        });
+    }
+
+    let changed: Vec<(DeploymentId, String)> = aliases
+        .iter()
+        .filter(|(deployment, alias)| deployment.alias != *alias)
+        .map(|(deployment, alias)| (deployment.id, alias.clone()))
+        .collect();
+    repo.set_aliases(&changed).await?;
+    InferenceEndpoints::new(&mut tx).record_version(id, current_user.id).await?;
+    tx.commit().await.map_err(|e| Error::Database(e.into()))?;
+    tracing::info!("Set the aliases of endpoint {}: {} changed", id, changed.len());
+
+    Ok(Json(EndpointAliases {
+        aliases: aliases
+            .into_iter()
+            .map(|(deployment, alias)| (deployment.model_name.clone(), alias))
+            .collect(),
+    }))
+}
+
+/// The configuration an endpoint of an imported manifest is given, from its entry and its current
+/// configuration, if it exists
+fn manifest_config(entry: &EndpointManifestEntry, current: Option<&EndpointConfig>) -> Result<EndpointConfig> {
+    let url: url::Url = entry.url.parse().map_err(|_| Error::BadRequest {
+        message: format!("Invalid URL format for endpoint {}", entry.name),
+    })?;
+    validate_ca_pem(entry.tls_ca_pem.as_deref())?;
+    validate_sync_interval(entry.sync_interval_seconds)?;
+    if entry.aliases.values().any(|alias| alias.trim().is_empty()) {
+        return Err(Error::BadRequest {
+            message: format!("Aliases of endpoint {} must not be empty or whitespace", entry.name),
+        });
+    }
+    if let Some(model_filter) = &entry.model_filter {
+        let unknown: Vec<&str> = entry
+            .aliases
+            .keys()
+            .filter(|model_name| !model_filter.contains(model_name))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            return Err(Error::BadRequest {
+                message: format!(
+                    "Models aliased outside the model filter of endpoint {}: {}",
+                    entry.name,
+                    unknown.join(", ")
+                ),
+            });
+        }
+    }
+    let (auth_header_name, auth_header_prefix) =
+        azure::default_auth_header(&url, entry.auth_header_name.clone(), entry.auth_header_prefix.clone());
+
+    // Deployments follow the model filter. Without one, an endpoint keeps the models it has, and a
+    // new endpoint's models are only known once it is synchronized.
+    let current_aliases = current.map(|config| config.aliases.clone()).unwrap_or_default();
+    let models: BTreeSet<&String> = match (&entry.model_filter, current) {
+        (Some(model_filter), _) => model_filter.iter().collect(),
+        (None, Some(current)) => current.aliases.keys().collect(),
+        (None, None) => entry.aliases.keys().collect(),
+    };
+    let aliases = models
+        .into_iter()
+        .map(|model_name| {
+            let alias = entry
+                .aliases
+                .get(model_name)
+                .map(|alias| alias.trim())
+                .or(current_aliases.get(model_name).map(String::as_str))
+                .unwrap_or(model_name);
+            (model_name.clone(), alias.to_string())
+        })
+        .collect();
+
+    Ok(EndpointConfig {
+        name: entry.name.clone(),
+        description: entry.description.clone(),
+        url: url.to_string(),
+        model_filter: entry.model_filter.clone(),
+        auth_header_name: auth_header_name.unwrap_or_else(|| "Authorization".to_string()),
+        auth_header_prefix: auth_header_prefix.unwrap_or_else(|| "Bearer ".to_string()),
+        tls_ca_pem: entry.tls_ca_pem.clone(),
+        tls_skip_verify: entry.tls_skip_verify,
+        tls_min_version: entry.tls_min_version.map(|version| version.as_str().to_string()),
+        sync_interval_seconds: entry.sync_interval_seconds,
+        enabled: entry.enabled,
+        aliases,
+    })
+}
+
+/// Give an endpoint the configuration of its manifest entry, creating it if `id` is `None`
+async fn apply_manifest_entry(
+    conn: &mut PgConnection,
+    id: Option<InferenceEndpointId>,
+    entry: &EndpointManifestEntry,
+    config: &EndpointConfig,
+    user_id: UserId,
+) -> Result<()> {
+    let is_new = id.is_none();
+    let mut repo = InferenceEndpoints::new(&mut *conn);
+    let id = match id {
+        Some(id) => id,
+        None => {
+            repo.create(&InferenceEndpointCreateDBRequest {
+                created_by: user_id,
+                name: config.name.clone(),
+                description: config.description.clone(),
+                url: config.url.parse().map_err(|_| Error::BadRequest {
+                    message: format!("Invalid URL format for endpoint {}", config.name),
+                })?,
+                api_key: None,
+                model_filter: config.model_filter.clone(),
+                auth_header_name: Some(config.auth_header_name.clone()),
+                auth_header_prefix: Some(config.auth_header_prefix.clone()),
+                tls_ca_pem: config.tls_ca_pem.clone(),
+                tls_skip_verify: config.tls_skip_verify,
+                tls_min_version: config.tls_min_version.clone(),
+                sync_interval_seconds: config.sync_interval_seconds,
+            })
+            .await?
+            .id
+        }
+    };
+    // Keys are never exported, so an endpoint's key is only replaced when one is given
+    if let Some(api_key) = &entry.api_key {
+        let db_request = InferenceEndpointUpdateDBRequest {
+            name: None,
+            description: None,
+            url: None,
+            api_key: Some(Some(api_key.clone())),
+            model_filter: None,
+            auth_header_name: None,
+            auth_header_prefix: None,
+            tls_ca_pem: None,
+            tls_skip_verify: None,
+            tls_min_version: None,
+            sync_interval_seconds: None,
+            enabled: None,
+        };
+        repo.update(id, &db_request).await?;
+    }
+    let endpoint = repo.restore_config(id, config).await?;
+
+    // A new endpoint without a model filter deploys every model it serves, as on creation
+    let alias_mapping: HashMap<String, String> = config.aliases.clone().into_iter().collect();
+    let sync_result = if !is_new || endpoint.model_filter.is_some() {
+        Some(update_endpoint_aliases(endpoint.clone(), &mut Deployments::new(&mut *conn), &alias_mapping).await)
+    } else if endpoint.enabled {
+        #[cfg(test)]
+        let fetcher = MockFetchModels;
+        #[cfg(not(test))]
+        let fetcher = FetchModelsReqwest::new(SyncConfig::from_endpoint(&endpoint).await.map_err(|e| Error::BadRequest {
+            message: format!("Failed to resolve the endpoint's API key: {e:#}"),
+        })?);
+        let sync_result =
+            sync_endpoint_models_with_aliases(endpoint.clone(), &mut Deployments::new(&mut *conn), fetcher, &Some(alias_mapping)).await;
+        if sync_result.is_ok() {
+            InferenceEndpoints::new(&mut *conn).record_sync(endpoint.id, None).await?;
+        }
+        Some(sync_result)
+    } else {
+        tracing::info!("Skipped sync of imported endpoint {} (endpoint disabled)", endpoint.id);
+        None
+    };
+    if let Some(Err(sync_error)) = sync_result {
+        tracing::error!("Failed to import endpoint {}: {}", endpoint.name, sync_error);
+        return Err(match sync_error {
+            crate::sync::endpoint_sync::SyncError::AliasConflicts { conflicts } => Error::Conflict {
+                message: format!("Alias conflicts detected importing endpoint {}", endpoint.name),
+                conflicts: Some(conflicts),
+            },
+            crate::sync::endpoint_sync::SyncError::Other(_) => Error::Internal {
+                operation: format!("sync models of imported endpoint {}", endpoint.name),
+            },
+        });
+    }
+
+    InferenceEndpoints::new(&mut *conn).record_version(endpoint.id, user_id).await?;
+    Ok(())
+}
+
+// GET /endpoints/export - Export endpoints as a YAML manifest
+#[utoipa::path(
+    get,
+    path = "/endpoints/export",
+    tag = "endpoints",
+    summary = "Export endpoints",
+    description = "Export every endpoint, with its model filter and the aliases of its deployments, as a YAML manifest \
+                   that can be kept in version control and imported again. API keys are not exported",
+    responses(
+        (status = 200, description = "Endpoint manifest", body = EndpointManifest, content_type = "application/yaml"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn export_endpoints(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Endpoints, operation::ReadAll>,
+) -> Result<Response> {
+    let mut conn = state.db.acquire().await.map_err(|e| Error::Database(e.into()))?;
+    let mut repo = InferenceEndpoints::new(&mut conn);
+    let mut endpoints: Vec<EndpointManifestEntry> = Vec::new();
+    for endpoint in repo.list(&InferenceEndpointFilter::new(0, i64::MAX)).await? {
+        if let Some(config) = repo.get_config(endpoint.id).await? {
+            endpoints.push(config.into());
+        }
+    }
+    // Keep the order stable, so that manifests diff cleanly
+    endpoints.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let manifest = serde_yaml::to_string(&EndpointManifest { endpoints }).map_err(|e| {
+        tracing::error!("Failed to serialize endpoint manifest: {}", e);
+        Error::Internal {
+            operation: "serialize endpoint manifest".to_string(),
+        }
+    })?;
+    Ok(([(header::CONTENT_TYPE, "application/yaml")], manifest).into_response())
+}
+
+// POST /endpoints/import - Create and update endpoints from a YAML manifest (admin only)
+#[utoipa::path(
+    post,
+    path = "/endpoints/import",
+    tag = "endpoints",
+    summary = "Import endpoints",
+    description = "Create or update the endpoints of a YAML manifest, as exported, matching them to existing endpoints \
+                   by name. Endpoints missing from the manifest are left as they are. All endpoints are imported, or \
+                   none if any fails. With `dry_run`, only report the changes the import would make (admin only)",
+    params(EndpointImportQuery),
+    request_body(content = EndpointManifest, content_type = "application/yaml"),
+    responses(
+        (status = 200, description = "Changes made, or that would be made on a dry run", body = EndpointImportResult),
+        (status = 400, description = "Invalid manifest"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 409, description = "An imported alias is used by another deployment"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn import_endpoints(
+    State(state): State<AppState>,
+    Query(query): Query<EndpointImportQuery>,
+    _: RequiresPermission<resource::Endpoints, operation::CreateAll>,
+    current_user: RequiresPermission<resource::Endpoints, operation::UpdateAll>,
+    body: String,
+) -> Result<Json<EndpointImportResult>> {
+    let manifest: EndpointManifest = serde_yaml::from_str(&body).map_err(|e| Error::BadRequest {
+        message: format!("Invalid endpoint manifest: {e}"),
+    })?;
+    let mut names = HashSet::new();
+    if let Some(entry) = manifest.endpoints.iter().find(|entry| !names.insert(entry.name.as_str())) {
+        return Err(Error::BadRequest {
+            message: format!("Endpoint {} is declared more than once", entry.name),
+        });
+    }
+
+    let mut tx = state.db.begin().await.map_err(|e| Error::Database(e.into()))?;
+    let existing: HashMap<String, InferenceEndpointId> = InferenceEndpoints::new(&mut tx)
+        .list(&InferenceEndpointFilter::new(0, i64::MAX))
+        .await?
+        .into_iter()
+        .map(|endpoint| (endpoint.name, endpoint.id))
+        .collect();
+
+    let mut endpoints = Vec::new();
+    for entry in &manifest.endpoints {
+        let id = existing.get(&entry.name).copied();
+        let current = match id {
+            Some(id) => InferenceEndpoints::new(&mut tx).get_config(id).await?,
+            None => None,
+        };
+        let config = manifest_config(entry, current.as_ref())?;
+        let changes = EndpointSettingChange::between(current.as_ref(), &config);
+        let action = match &current {
+            None => EndpointImportAction::Create,
+            Some(_) if changes.is_empty() => EndpointImportAction::Unchanged,
+            Some(_) => EndpointImportAction::Update,
+        };
+        if !query.dry_run && (action != EndpointImportAction::Unchanged || entry.api_key.is_some()) {
+            apply_manifest_entry(&mut tx, id, entry, &config, current_user.id).await?;
+        }
+        endpoints.push(EndpointImportChange {
+            name: entry.name.clone(),
+            action,
+            changes,
+        });
+    }
+
+    if !query.dry_run {
+        tx.commit().await.map_err(|e| Error::Database(e.into()))?;
+        tracing::info!("Imported {} endpoints", endpoints.len());
+    }
+    Ok(Json(EndpointImportResult {
+        dry_run: query.dry_run,
+        endpoints,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::deployments::DeployedModelResponse;
+    use crate::api::models::inference_endpoints::{
+        EndpointConfigVersion, EndpointHeaderRules, EndpointImportAction, EndpointImportResult, EndpointManifest, EndpointPendingModel,
+        EndpointRateLimits, EndpointRedactionPolicy, EndpointSettingChange, EndpointSyncStrategy, InferenceEndpointResponse,
+        NewModelAction, RedactionEntity, RemovedModelAction,
+    };
+    use crate::api::models::probes::HealthStatus;
+    use crate::api::models::users::Role;
+    use crate::db::handlers::InferenceEndpoints;
+    use crate::db::models::endpoint_compatibility::EndpointCompatibilityReport;
+    use crate::test_utils::*;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_list_inference_endpoints(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+
+        let response = app
+            .get("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+
+        response.assert_status_ok();
+        let endpoints: Vec<InferenceEndpointResponse> = response.json();
+        // Should have at least the default endpoint
+        assert!(!endpoints.is_empty());
+        assert!(endpoints.iter().any(|e| e.name == "test"));
+    }
+
+    // Helper function to get the test endpoint ID
+    async fn get_test_endpoint_id(
+        app: &axum_test::TestServer,
+        user: &crate::api::models::users::UserResponse,
+    ) -> crate::types::InferenceEndpointId {
+        let response = app
+            .get("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(user).0, add_auth_headers(user).1)
+            .await;
+
+        response.assert_status_ok();
+        let endpoints: Vec<InferenceEndpointResponse> = response.json();
+        endpoints.iter().find(|e| e.name == "test").expect("Test endpoint should exist").id
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_get_inference_endpoint(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let test_endpoint_id = get_test_endpoint_id(&app, &user).await;
+
+        let response = app
+            .get(&format!("/admin/api/v1/endpoints/{test_endpoint_id}"))
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+
+        response.assert_status_ok();
+        let endpoint: InferenceEndpointResponse = response.json();
+        assert_eq!(endpoint.id, test_endpoint_id);
+        assert_eq!(endpoint.name, "test");
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_endpoint_and_model_health(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, admin.id, "health-model", "health-alias").await;
+        let auth = add_auth_headers(&admin);
+
+        // Nothing probed yet
+        let endpoint: InferenceEndpointResponse = app
+            .get(&format!("/admin/api/v1/endpoints/{}", deployment.hosted_on))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .json();
+        assert_eq!(endpoint.health, None);
+
+        let probe: serde_json::Value = app
+            .post("/admin/api/v1/probes")
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({"name": "health", "deployment_id": deployment.id, "interval_seconds": 60}))
+            .await
+            .json();
+        let probe_id: uuid::Uuid = probe["id"].as_str().unwrap().parse().unwrap();
+        for success in [true, false] {
+            sqlx::query("INSERT INTO probe_results (probe_id, success) VALUES ($1, $2)")
+                .bind(probe_id)
+                .bind(success)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let endpoint: InferenceEndpointResponse = app
+            .get(&format!("/admin/api/v1/endpoints/{}", deployment.hosted_on))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .json();
+        assert_eq!(endpoint.health.unwrap().status, HealthStatus::Degraded);
+
+        let model: DeployedModelResponse = app
+            .get(&format!("/admin/api/v1/models/{}", deployment.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .json();
+        assert_eq!(model.health, endpoint.health);
+
+        let models: Vec<DeployedModelResponse> = app.get("/admin/api/v1/models").add_header(auth.0, auth.1).await.json();
+        let listed = models.iter().find(|model| model.id == deployment.id).unwrap();
+        assert_eq!(listed.health, endpoint.health);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_get_nonexistent_inference_endpoint(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let non_existent_id = uuid::Uuid::new_v4();
+
+        let response = app
+            .get(&format!("/admin/api/v1/endpoints/{non_existent_id}"))
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+
+        response.assert_status_not_found();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_update_inference_endpoint_as_admin(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let test_endpoint_id = get_test_endpoint_id(&app, &admin_user).await;
+
+        let update = json!({
+            "name": "Updated Default"
+        });
+
+        let response = app
+            .patch(&format!("/admin/api/v1/endpoints/{test_endpoint_id}"))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&update)
+            .await;
+
+        response.assert_status_ok();
+        let updated_endpoint: InferenceEndpointResponse = response.json();
         assert_eq!(updated_endpoint.id, test_endpoint_id);
         assert_eq!(updated_endpoint.name, "Updated Default");
     }
@@ -937,19 +2297,378 @@ mod tests {
         });
 
         let response = app
-            .post("/admin/api/v1/endpoints")
+            .post("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&create_request)
+            .await;
+
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let endpoint: InferenceEndpointResponse = response.json();
+        assert_eq!(endpoint.name, "Minimal Endpoint");
+        assert_eq!(endpoint.description, None);
+        assert_eq!(endpoint.url, "https://api.minimal.com/v1");
+        assert!(!endpoint.requires_api_key);
+        assert_eq!(endpoint.model_filter, None);
+        assert_eq!(endpoint.created_by, admin_user.id);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_create_azure_endpoint_defaults_to_api_key_header(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let response = app
+            .post("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({
+                "name": "Azure Endpoint",
+                "url": "https://my-resource.openai.azure.com/?api-version=2024-10-21",
+                "api_key": "azure-key"
+            }))
+            .await;
+
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let endpoint: InferenceEndpointResponse = response.json();
+        assert_eq!(endpoint.auth_header_name, "api-key");
+        assert_eq!(endpoint.auth_header_prefix, "");
+
+        // An explicit header is kept, e.g. for Entra ID tokens
+        let response = app
+            .post("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({
+                "name": "Azure Entra Endpoint",
+                "url": "https://my-resource.openai.azure.com",
+                "auth_header_name": "Authorization",
+                "auth_header_prefix": "Bearer ",
+                "sync": false
+            }))
+            .await;
+
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let endpoint: InferenceEndpointResponse = response.json();
+        assert_eq!(endpoint.auth_header_name, "Authorization");
+        assert_eq!(endpoint.auth_header_prefix, "Bearer ");
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_endpoint_tls_settings(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let response = app
+            .post("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({
+                "name": "Bad CA Endpoint",
+                "url": "https://models.internal/v1",
+                "tls_ca_pem": "not a certificate",
+                "sync": false
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        let response = app
+            .post("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({
+                "name": "Internal Endpoint",
+                "url": "https://models.internal/v1",
+                "tls_ca_pem": TEST_CA_PEM,
+                "tls_min_version": "1.3",
+                "sync": false
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let endpoint: InferenceEndpointResponse = response.json();
+        assert_eq!(endpoint.tls_ca_pem.as_deref(), Some(TEST_CA_PEM));
+        assert_eq!(endpoint.tls_min_version, Some(crate::tls::TlsVersion::Tls13));
+        assert!(!endpoint.tls_skip_verify);
+
+        // Clearing the CA bundle and version, and skipping verification instead
+        let response = app
+            .patch(&format!("/admin/api/v1/endpoints/{}", endpoint.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"tls_ca_pem": null, "tls_min_version": null, "tls_skip_verify": true}))
+            .await;
+        response.assert_status_ok();
+        let endpoint: InferenceEndpointResponse = response.json();
+        assert_eq!(endpoint.tls_ca_pem, None);
+        assert_eq!(endpoint.tls_min_version, None);
+        assert!(endpoint.tls_skip_verify);
+
+        let response = app
+            .patch(&format!("/admin/api/v1/endpoints/{}", endpoint.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"tls_min_version": "1.1"}))
+            .await;
+        response.assert_status(axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_disable_and_reenable_endpoint(pool: PgPool) {
+        use crate::db::handlers::{deployments::DeploymentFilter, Deployments, Repository};
+
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, admin_user.id, "toggle-model", "toggle-alias").await;
+        let group = create_test_group(&pool).await;
+        add_deployment_to_group(&pool, deployment.id, group.id, admin_user.id).await;
+        let routable = |pool: PgPool| async move {
+            let mut conn = pool.acquire().await.unwrap();
+            Deployments::new(&mut conn)
+                .list(&DeploymentFilter::new(0, 100).with_enabled(true))
+                .await
+                .unwrap()
+                .iter()
+                .any(|model| model.id == deployment.id)
+        };
+        assert!(routable(pool.clone()).await);
+
+        let response = app
+            .patch(&format!("/admin/api/v1/endpoints/{}", deployment.hosted_on))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"enabled": false}))
+            .await;
+        response.assert_status_ok();
+        let endpoint: InferenceEndpointResponse = response.json();
+        assert!(!endpoint.enabled);
+        assert!(!routable(pool.clone()).await);
+
+        // The deployment and its group assignments are preserved
+        let response = app
+            .get(&format!("/admin/api/v1/models/{}/groups", deployment.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let groups: Vec<crate::types::GroupId> = response.json();
+        assert!(groups.contains(&group.id));
+        let response = app
+            .get(&format!("/admin/api/v1/models/{}", deployment.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let model: DeployedModelResponse = response.json();
+        assert_eq!(model.alias, "toggle-alias");
+
+        let response = app
+            .patch(&format!("/admin/api/v1/endpoints/{}", deployment.hosted_on))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"enabled": true}))
+            .await;
+        response.assert_status_ok();
+        let endpoint: InferenceEndpointResponse = response.json();
+        assert!(endpoint.enabled);
+        assert!(routable(pool.clone()).await);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_endpoint_sync_schedule(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let response = app
+            .post("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({
+                "name": "Too Frequent",
+                "url": "https://api.example.com/v1",
+                "sync_interval_seconds": 5,
+                "sync": false
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        // Syncing during creation is recorded as the endpoint's last sync
+        let response = app
+            .post("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({
+                "name": "Scheduled Endpoint",
+                "url": "https://api.example.com/v1",
+                "sync_interval_seconds": 3600
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let endpoint: InferenceEndpointResponse = response.json();
+        assert_eq!(endpoint.sync_interval_seconds, Some(3600));
+        assert!(endpoint.last_sync_at.is_some());
+        assert_eq!(endpoint.last_sync_error, None);
+
+        // Leaving other fields alone keeps the interval, and null turns the schedule off
+        let response = app
+            .patch(&format!("/admin/api/v1/endpoints/{}", endpoint.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"description": "Synced hourly"}))
+            .await;
+        response.assert_status_ok();
+        let endpoint: InferenceEndpointResponse = response.json();
+        assert_eq!(endpoint.sync_interval_seconds, Some(3600));
+
+        let response = app
+            .patch(&format!("/admin/api/v1/endpoints/{}", endpoint.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"sync_interval_seconds": null}))
+            .await;
+        response.assert_status_ok();
+        let endpoint: InferenceEndpointResponse = response.json();
+        assert_eq!(endpoint.sync_interval_seconds, None);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_set_endpoint_aliases(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        create_test_deployment(&pool, admin_user.id, "other-model", "taken").await;
+
+        let response = app
+            .post("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({
+                "name": "Aliased Endpoint",
+                "url": "https://api.example.com/v1",
+                "alias_mapping": { "openai/gpt-4": "gpt-4" }
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let endpoint: InferenceEndpointResponse = response.json();
+        let aliases_url = format!("/admin/api/v1/endpoints/{}/aliases", endpoint.id);
+
+        // Aliases can be swapped, and models left out go back to their own name
+        let response = app
+            .put(&aliases_url)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"aliases": {"google/gemma-3-12b-it": "gpt-4"}}))
+            .await;
+        response.assert_status_ok();
+        let expected = json!({"aliases": {"google/gemma-3-12b-it": "gpt-4", "openai/gpt-4": "openai/gpt-4"}});
+        assert_eq!(response.json::<serde_json::Value>(), expected);
+        let response = app
+            .get(&aliases_url)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<serde_json::Value>(), expected);
+
+        // Every conflict is reported, and nothing is changed
+        let response = app
+            .put(&aliases_url)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"aliases": {"google/gemma-3-12b-it": "taken", "openai/gpt-4": "gpt-4"}}))
+            .await;
+        response.assert_status(axum::http::StatusCode::CONFLICT);
+        let conflicts = response.json::<serde_json::Value>()["conflicts"].clone();
+        assert_eq!(
+            conflicts,
+            json!([{"model_name": "google/gemma-3-12b-it", "attempted_alias": "taken"}])
+        );
+        let response = app
+            .put(&aliases_url)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"aliases": {"google/gemma-3-12b-it": "same", "openai/gpt-4": "same"}}))
+            .await;
+        response.assert_status(axum::http::StatusCode::CONFLICT);
+        assert_eq!(response.json::<serde_json::Value>()["conflicts"].as_array().unwrap().len(), 2);
+        let response = app
+            .get(&aliases_url)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        assert_eq!(response.json::<serde_json::Value>(), expected);
+
+        let response = app
+            .put(&aliases_url)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"aliases": {"not-deployed": "alias"}}))
+            .await;
+        response.assert_status_bad_request();
+
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let response = app
+            .put(&aliases_url)
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&json!({"aliases": {}}))
+            .await;
+        response.assert_status_forbidden();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_endpoint_history_and_rollback(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let response = app
+            .post("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({
+                "name": "Versioned Endpoint",
+                "url": "https://api.example.com/v1"
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let endpoint: InferenceEndpointResponse = response.json();
+
+        let response = app
+            .patch(&format!("/admin/api/v1/endpoints/{}", endpoint.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({
+                "url": "https://api.other.com/v1",
+                "alias_mapping": { "openai/gpt-4": "versioned-gpt-4" }
+            }))
+            .await;
+        response.assert_status_ok();
+
+        let response = app
+            .get(&format!("/admin/api/v1/endpoints/{}/history", endpoint.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let history: Vec<EndpointConfigVersion> = response.json();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].version, 2);
+        assert_eq!(history[0].changes, vec!["url", "aliases.openai/gpt-4"]);
+        assert_eq!(history[0].changed_by, Some(admin_user.id));
+        assert_eq!(history[0].config.aliases["openai/gpt-4"], "versioned-gpt-4");
+        assert_eq!(history[1].version, 1);
+        assert!(history[1].changes.contains(&"url".to_string()));
+        assert_eq!(history[1].config.url, "https://api.example.com/v1");
+
+        let response = app
+            .post(&format!("/admin/api/v1/endpoints/{}/rollback/1", endpoint.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let rolled_back: InferenceEndpointResponse = response.json();
+        assert_eq!(rolled_back.url, "https://api.example.com/v1");
+
+        let response = app
+            .get(&format!("/admin/api/v1/endpoints/{}/history?limit=1", endpoint.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let history: Vec<EndpointConfigVersion> = response.json();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].version, 3);
+        assert_eq!(history[0].changes, vec!["url", "aliases.openai/gpt-4"]);
+        assert_eq!(history[0].config.aliases["openai/gpt-4"], "openai/gpt-4");
+
+        let response = app
+            .post(&format!("/admin/api/v1/endpoints/{}/rollback/42", endpoint.id))
             .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
-            .json(&create_request)
             .await;
+        response.assert_status_not_found();
 
-        response.assert_status(axum::http::StatusCode::CREATED);
-        let endpoint: InferenceEndpointResponse = response.json();
-        assert_eq!(endpoint.name, "Minimal Endpoint");
-        assert_eq!(endpoint.description, None);
-        assert_eq!(endpoint.url, "https://api.minimal.com/v1");
-        assert!(!endpoint.requires_api_key);
-        assert_eq!(endpoint.model_filter, None);
-        assert_eq!(endpoint.created_by, admin_user.id);
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let response = app
+            .post(&format!("/admin/api/v1/endpoints/{}/rollback/1", endpoint.id))
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        response.assert_status_forbidden();
     }
 
     #[sqlx::test]
@@ -1134,6 +2853,41 @@ mod tests {
         response.assert_status_forbidden();
     }
 
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_validation_report_as_admin(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let test_endpoint_id = get_test_endpoint_id(&app, &admin_user).await;
+
+        let response = app
+            .get("/admin/api/v1/endpoints/validation-report")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+
+        response.assert_status_ok();
+        let report: crate::api::models::inference_endpoints::EndpointValidationReport = response.json();
+        assert_eq!(report.total_endpoints, report.endpoints.len());
+        // Scheduled validation is disabled in tests, so nothing has been validated yet
+        let status = report.endpoints.iter().find(|e| e.endpoint_id == test_endpoint_id).unwrap();
+        assert!(status.last_validated_at.is_none());
+        assert_eq!(report.never_validated, report.total_endpoints);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_validation_report_as_non_admin_forbidden(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+
+        let response = app
+            .get("/admin/api/v1/endpoints/validation-report")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+
+        response.assert_status_forbidden();
+    }
+
     #[sqlx::test]
     #[test_log::test]
     async fn test_standard_user_can_read_endpoints_only(pool: PgPool) {
@@ -2047,4 +3801,469 @@ mod tests {
         assert!(deployments.iter().any(|d| d.alias == "google/gemma-3-12b-it"));
         assert!(deployments.iter().any(|d| d.alias == "openai/gpt-4"));
     }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_compatibility_reports(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let standard_user = create_test_user(&pool, Role::StandardUser).await;
+        let endpoint_id = get_test_endpoint_id(&app, &admin_user).await;
+        let path = format!("/admin/api/v1/endpoints/{endpoint_id}/compatibility");
+
+        // Nothing is listening on the test endpoint, so every check fails but the report is still stored
+        let response = app
+            .post(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"model": "test-model"}))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let report: EndpointCompatibilityReport = response.json();
+        assert_eq!(report.model, "test-model");
+        assert_eq!(report.passed, 0);
+        assert_eq!(report.total, report.checks.len() as i32);
+        assert!(report.checks.iter().all(|c| !c.passed && c.detail.is_some()));
+
+        let response = app
+            .get(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let reports: Vec<EndpointCompatibilityReport> = response.json();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].id, report.id);
+
+        app.get(&path)
+            .add_header(add_auth_headers(&standard_user).0, add_auth_headers(&standard_user).1)
+            .await
+            .assert_status_forbidden();
+        app.post(&path)
+            .add_header(add_auth_headers(&standard_user).0, add_auth_headers(&standard_user).1)
+            .json(&json!({}))
+            .await
+            .assert_status_forbidden();
+        app.get(&format!("/admin/api/v1/endpoints/{}/compatibility", uuid::Uuid::new_v4()))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await
+            .assert_status_not_found();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_endpoint_redaction_policy(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let standard_user = create_test_user(&pool, Role::StandardUser).await;
+        let endpoint_id = get_test_endpoint_id(&app, &admin_user).await;
+        let path = format!("/admin/api/v1/endpoints/{endpoint_id}/redaction");
+
+        // Endpoints without a policy aren't redacted
+        let response = app
+            .get(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let policy: EndpointRedactionPolicy = response.json();
+        assert!(policy.entities.is_empty() && policy.patterns.is_empty());
+        assert!(!policy.log_original);
+
+        let response = app
+            .put(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"entities": ["email", "financial"], "patterns": [r"ACME-\d+"], "log_original": true}))
+            .await;
+        response.assert_status_ok();
+
+        let response = app
+            .get(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        let policy: EndpointRedactionPolicy = response.json();
+        assert_eq!(policy.entities, vec![RedactionEntity::Email, RedactionEntity::Financial]);
+        assert_eq!(policy.patterns, vec![r"ACME-\d+".to_string()]);
+        assert!(policy.log_original);
+
+        // Invalid patterns and unknown entities are rejected
+        app.put(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"patterns": ["(unclosed"]}))
+            .await
+            .assert_status(axum::http::StatusCode::BAD_REQUEST);
+        app.put(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"entities": ["address"]}))
+            .await
+            .assert_status(axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+
+        app.put(&path)
+            .add_header(add_auth_headers(&standard_user).0, add_auth_headers(&standard_user).1)
+            .json(&json!({}))
+            .await
+            .assert_status_forbidden();
+        app.get(&format!("/admin/api/v1/endpoints/{}/redaction", uuid::Uuid::new_v4()))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await
+            .assert_status_not_found();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_endpoint_header_rules(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let standard_user = create_test_user(&pool, Role::StandardUser).await;
+        let endpoint_id = get_test_endpoint_id(&app, &admin_user).await;
+        let path = format!("/admin/api/v1/endpoints/{endpoint_id}/headers");
+
+        // Endpoints without rules forward all client headers and inject none
+        let response = app
+            .get(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let rules: EndpointHeaderRules = response.json();
+        assert!(rules.inject.is_empty());
+        assert!(rules.passthrough.is_none());
+
+        let response = app
+            .put(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"inject": {"OpenAI-Organization": "org-123"}, "passthrough": ["X-Trace-Id"]}))
+            .await;
+        response.assert_status_ok();
+
+        // Header names are stored lowercased
+        let response = app
+            .get(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        let rules: EndpointHeaderRules = response.json();
+        assert_eq!(rules.inject.get("openai-organization").map(String::as_str), Some("org-123"));
+        assert_eq!(rules.passthrough, Some(vec!["x-trace-id".to_string()]));
+
+        // Invalid and reserved headers are rejected
+        app.put(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"inject": {"authorization": "Bearer other"}}))
+            .await
+            .assert_status(axum::http::StatusCode::BAD_REQUEST);
+        app.put(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"passthrough": ["not a header"]}))
+            .await
+            .assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        app.put(&path)
+            .add_header(add_auth_headers(&standard_user).0, add_auth_headers(&standard_user).1)
+            .json(&json!({}))
+            .await
+            .assert_status_forbidden();
+        app.get(&format!("/admin/api/v1/endpoints/{}/headers", uuid::Uuid::new_v4()))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await
+            .assert_status_not_found();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_endpoint_rate_limits(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let standard_user = create_test_user(&pool, Role::StandardUser).await;
+        let endpoint_id = get_test_endpoint_id(&app, &admin_user).await;
+        let path = format!("/admin/api/v1/endpoints/{endpoint_id}/rate-limits");
+
+        // Endpoints without limits aren't limited
+        let response = app
+            .get(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let limits: EndpointRateLimits = response.json();
+        assert!(limits.requests_per_second.is_none());
+        assert!(limits.max_concurrent.is_none());
+
+        let response = app
+            .put(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"requests_per_second": 5.0, "burst_size": 10, "max_concurrent": 20}))
+            .await;
+        response.assert_status_ok();
+
+        let response = app
+            .get(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        let limits: EndpointRateLimits = response.json();
+        assert_eq!(limits.requests_per_second, Some(5.0));
+        assert_eq!(limits.burst_size, Some(10));
+        assert_eq!(limits.max_concurrent, Some(20));
+
+        // Non-positive limits and a burst size without a rate are rejected
+        for invalid in [
+            json!({"requests_per_second": 0.0}),
+            json!({"max_concurrent": -1}),
+            json!({"burst_size": 10}),
+        ] {
+            app.put(&path)
+                .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+                .json(&invalid)
+                .await
+                .assert_status(axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        app.put(&path)
+            .add_header(add_auth_headers(&standard_user).0, add_auth_headers(&standard_user).1)
+            .json(&json!({}))
+            .await
+            .assert_status_forbidden();
+        app.get(&format!("/admin/api/v1/endpoints/{}/rate-limits", uuid::Uuid::new_v4()))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await
+            .assert_status_not_found();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_endpoint_sync_strategy_and_pending_models(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let standard_user = create_test_user(&pool, Role::StandardUser).await;
+        let endpoint_id = get_test_endpoint_id(&app, &admin_user).await;
+        let path = format!("/admin/api/v1/endpoints/{endpoint_id}/sync-strategy");
+
+        // Endpoints without a strategy disable removed models and deploy new ones
+        let response = app
+            .get(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<EndpointSyncStrategy>(), EndpointSyncStrategy::default());
+
+        let response = app
+            .put(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"removed_models": "keep", "new_models": "queue"}))
+            .await;
+        response.assert_status_ok();
+        let strategy: EndpointSyncStrategy = response.json();
+        assert_eq!(strategy.removed_models, RemovedModelAction::Keep);
+        assert_eq!(strategy.new_models, NewModelAction::Queue);
+        app.put(&path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"removed_models": "archive"}))
+            .await
+            .assert_status(axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+        app.put(&path)
+            .add_header(add_auth_headers(&standard_user).0, add_auth_headers(&standard_user).1)
+            .json(&json!({}))
+            .await
+            .assert_status_forbidden();
+
+        // A sync queues the new models it finds
+        let queued = ["queued-a", "queued-b", "queued-c"].map(String::from);
+        let mut conn = pool.acquire().await.unwrap();
+        let pending = InferenceEndpoints::new(&mut conn)
+            .sync_pending_models(endpoint_id, &queued)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 3);
+
+        let pending_path = format!("/admin/api/v1/endpoints/{endpoint_id}/pending-models");
+        let response = app
+            .post(&format!("{pending_path}/reject"))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"model_names": ["queued-c"]}))
+            .await;
+        response.assert_status_ok();
+        let rejected: Vec<EndpointPendingModel> = response.json();
+        assert!(rejected[0].rejected);
+        assert_eq!(rejected[0].rejected_by, Some(admin_user.id));
+
+        let response = app
+            .post(&format!("{pending_path}/approve"))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"model_names": ["queued-a"]}))
+            .await;
+        response.assert_status_ok();
+        let deployed: Vec<DeployedModelResponse> = response.json();
+        assert_eq!(deployed.len(), 1);
+        assert_eq!(deployed[0].alias, "queued-a");
+        assert_eq!(deployed[0].hosted_on, endpoint_id);
+
+        // Models that aren't queued can't be approved; nothing else is deployed
+        app.post(&format!("{pending_path}/approve"))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"model_names": ["queued-b", "queued-a"]}))
+            .await
+            .assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        let response = app
+            .get(&pending_path)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let pending: Vec<EndpointPendingModel> = response.json();
+        assert_eq!(pending.iter().map(|m| m.model_name.as_str()).collect::<Vec<_>>(), vec!["queued-b"]);
+        let response = app
+            .get(&format!("{pending_path}?include_rejected=true"))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        assert_eq!(response.json::<Vec<EndpointPendingModel>>().len(), 2);
+
+        // Rejected models aren't queued again, and models gone upstream leave the queue
+        let pending = InferenceEndpoints::new(&mut conn)
+            .sync_pending_models(endpoint_id, &["queued-c".to_string(), "queued-d".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(pending, vec!["queued-d".to_string()]);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_export_and_import_endpoints(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let response = app
+            .post("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({
+                "name": "Exported Endpoint",
+                "url": "https://api.example.com/v1",
+                "api_key": "secret-key",
+                "alias_mapping": { "openai/gpt-4": "gpt-4" }
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+
+        let response = app
+            .get("/admin/api/v1/endpoints/export")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header("content-type"), "application/yaml");
+        let exported = response.text();
+        assert!(!exported.contains("secret-key"));
+        let mut manifest: EndpointManifest = serde_yaml::from_str(&exported).unwrap();
+        let entry = manifest.endpoints.iter().find(|entry| entry.name == "Exported Endpoint").unwrap();
+        assert_eq!(entry.aliases["openai/gpt-4"], "gpt-4");
+        assert_eq!(entry.aliases["google/gemma-3-12b-it"], "google/gemma-3-12b-it");
+
+        // Importing an export changes nothing
+        let response = app
+            .post("/admin/api/v1/endpoints/import")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .text(exported)
+            .await;
+        response.assert_status_ok();
+        let result: EndpointImportResult = response.json();
+        assert!(!result.dry_run);
+        assert!(result
+            .endpoints
+            .iter()
+            .all(|endpoint| endpoint.action == EndpointImportAction::Unchanged && endpoint.changes.is_empty()));
+
+        // A dry run reports the changes without making them
+        let entry = manifest
+            .endpoints
+            .iter_mut()
+            .find(|entry| entry.name == "Exported Endpoint")
+            .unwrap();
+        entry.aliases.insert("google/gemma-3-12b-it".to_string(), "gemma".to_string());
+        entry.sync_interval_seconds = Some(3600);
+        manifest.endpoints.push(
+            serde_yaml::from_str(
+                "name: Imported Endpoint\nurl: https://api.imported.com/v1\nmodel_filter: [model-a]\naliases:\n  model-a: imported-a\n",
+            )
+            .unwrap(),
+        );
+        let manifest = serde_yaml::to_string(&manifest).unwrap();
+        let response = app
+            .post("/admin/api/v1/endpoints/import?dry_run=true")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .text(manifest.clone())
+            .await;
+        response.assert_status_ok();
+        let result: EndpointImportResult = response.json();
+        assert!(result.dry_run);
+        let updated = result.endpoints.iter().find(|e| e.name == "Exported Endpoint").unwrap();
+        assert_eq!(updated.action, EndpointImportAction::Update);
+        assert_eq!(
+            updated.changes,
+            vec![
+                EndpointSettingChange {
+                    setting: "sync_interval_seconds".to_string(),
+                    from: None,
+                    to: Some(json!(3600)),
+                },
+                EndpointSettingChange {
+                    setting: "aliases.google/gemma-3-12b-it".to_string(),
+                    from: Some(json!("google/gemma-3-12b-it")),
+                    to: Some(json!("gemma")),
+                },
+            ]
+        );
+        let created = result.endpoints.iter().find(|e| e.name == "Imported Endpoint").unwrap();
+        assert_eq!(created.action, EndpointImportAction::Create);
+        assert!(created
+            .changes
+            .iter()
+            .any(|change| change.setting == "aliases.model-a" && change.from.is_none() && change.to == Some(json!("imported-a"))));
+        let response = app
+            .get("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        assert!(!response
+            .json::<Vec<InferenceEndpointResponse>>()
+            .iter()
+            .any(|e| e.name == "Imported Endpoint"));
+
+        // Without the dry run, the changes are made
+        let response = app
+            .post("/admin/api/v1/endpoints/import")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .text(manifest)
+            .await;
+        response.assert_status_ok();
+        let response = app
+            .get("/admin/api/v1/endpoints")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        let endpoints: Vec<InferenceEndpointResponse> = response.json();
+        let exported_endpoint = endpoints.iter().find(|e| e.name == "Exported Endpoint").unwrap();
+        assert_eq!(exported_endpoint.sync_interval_seconds, Some(3600));
+        // The API key is kept, as it wasn't exported
+        assert!(exported_endpoint.requires_api_key);
+        let imported_endpoint = endpoints.iter().find(|e| e.name == "Imported Endpoint").unwrap();
+        assert_eq!(imported_endpoint.model_filter, Some(vec!["model-a".to_string()]));
+        for (endpoint, expected) in [
+            (
+                exported_endpoint,
+                json!({"google/gemma-3-12b-it": "gemma", "openai/gpt-4": "gpt-4"}),
+            ),
+            (imported_endpoint, json!({"model-a": "imported-a"})),
+        ] {
+            let response = app
+                .get(&format!("/admin/api/v1/endpoints/{}/aliases", endpoint.id))
+                .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+                .await;
+            assert_eq!(response.json::<serde_json::Value>()["aliases"], expected);
+        }
+
+        // Invalid manifests are rejected
+        let response = app
+            .post("/admin/api/v1/endpoints/import")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .text("endpoints:\n  - name: missing-url\n")
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let response = app
+            .post("/admin/api/v1/endpoints/import")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .text("endpoints: []\n")
+            .await;
+        response.assert_status_forbidden();
+    }
 }
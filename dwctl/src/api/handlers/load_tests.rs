@@ -0,0 +1,327 @@
+use crate::api::models::load_tests::LoadTestCreate;
+use crate::auth::permissions::{operation, resource, RequiresPermission};
+use crate::db::models::deployments::ModelType;
+use crate::db::models::load_tests::LoadTest;
+use crate::errors::Error;
+use crate::load_tests::db::{LoadTestManager, NewLoadTest};
+use crate::load_tests::runner::{self, LoadTestPlan};
+use crate::probes::executor::ProbeExecutor;
+use crate::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Number of load tests returned when listing
+const LIST_LIMIT: i64 = 100;
+
+fn check_cap(field: &str, value: u32, cap: u32) -> Result<(), Error> {
+    if value == 0 || value > cap {
+        return Err(Error::BadRequest {
+            message: format!("{field} must be between 1 and {cap}"),
+        });
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/loadtest",
+    tag = "load_tests",
+    summary = "Start a load test",
+    description = "Send synthetic traffic to a deployment through the AI proxy and record a report. \
+                   Limits are capped by the server's load_testing configuration, and the load test stops \
+                   early if it reaches its maximum duration or too many requests fail. Only one load test \
+                   can run at a time.",
+    request_body = LoadTestCreate,
+    responses(
+        (status = 202, description = "Load test started", body = LoadTest),
+        (status = 400, description = "Bad request - load testing disabled or limits exceed the configured caps"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Deployment not found"),
+        (status = 409, description = "Another load test is already running"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn create_load_test(
+    State(state): State<AppState>,
+    permission: RequiresPermission<resource::Models, operation::SystemAccess>,
+    Json(request): Json<LoadTestCreate>,
+) -> Result<(StatusCode, Json<LoadTest>), Error> {
+    let caps = &state.config.load_testing;
+    if !caps.enabled {
+        return Err(Error::BadRequest {
+            message: "Load testing is not enabled on this server".to_string(),
+        });
+    }
+
+    let concurrency = request.concurrency.unwrap_or(1);
+    let requests_per_second = request.requests_per_second.unwrap_or(caps.max_requests_per_second);
+    let max_duration_cap = u32::try_from(caps.max_duration.as_secs()).unwrap_or(u32::MAX);
+    let max_duration_seconds = request.max_duration_seconds.unwrap_or(max_duration_cap);
+    check_cap("total_requests", request.total_requests, caps.max_requests)?;
+    check_cap("concurrency", concurrency, caps.max_concurrency)?;
+    check_cap("requests_per_second", requests_per_second, caps.max_requests_per_second)?;
+    check_cap("max_duration_seconds", max_duration_seconds, max_duration_cap)?;
+
+    let target = LoadTestManager::get_target(&state.db, request.deployment_id).await?;
+    let model_type = match target.model_type.as_deref() {
+        Some(t) => match t.to_uppercase().as_str() {
+            "CHAT" => ModelType::Chat,
+            "EMBEDDINGS" => ModelType::Embeddings,
+            "RERANKER" => ModelType::Reranker,
+            _ => {
+                return Err(Error::BadRequest {
+                    message: format!("Unknown model type: {}", t),
+                });
+            }
+        },
+        None => ModelType::detect_from_name(&target.alias),
+    };
+
+    // Route through the normal AI proxy, so the load test sees what real users would
+    let endpoint_url = format!("http://localhost:{}/ai", state.config.port);
+    let (url, default_body) = ProbeExecutor::get_default_config(&model_type, &target.alias, &endpoint_url);
+    let mut body = request.request_body.clone().unwrap_or(default_body);
+    let Some(fields) = body.as_object_mut() else {
+        return Err(Error::BadRequest {
+            message: "request_body must be a JSON object".to_string(),
+        });
+    };
+    fields.insert("model".to_string(), target.alias.clone().into());
+
+    // All values are within caps that fit comfortably in an i32
+    let load_test = LoadTestManager::create(
+        &state.db,
+        NewLoadTest {
+            deployment_id: request.deployment_id,
+            created_by: permission.current_user.id,
+            total_requests: request.total_requests as i32,
+            concurrency: concurrency as i32,
+            requests_per_second: requests_per_second as i32,
+            max_duration_seconds: max_duration_seconds as i32,
+            request_body: request.request_body,
+        },
+    )
+    .await?;
+
+    let plan = LoadTestPlan {
+        url,
+        api_key: target.system_api_key,
+        body,
+        total_requests: request.total_requests,
+        concurrency,
+        requests_per_second,
+        max_duration: Duration::from_secs(max_duration_seconds as u64),
+        max_error_rate: caps.max_error_rate,
+    };
+    tokio::spawn(runner::run(state.db.clone(), load_test.id, plan));
+
+    Ok((StatusCode::ACCEPTED, Json(load_test)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/loadtest",
+    tag = "load_tests",
+    summary = "List load tests",
+    description = "List the most recent load tests, newest first",
+    responses(
+        (status = 200, description = "List of load tests", body = Vec<LoadTest>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_load_tests(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Models, operation::SystemAccess>,
+) -> Result<Json<Vec<LoadTest>>, Error> {
+    let load_tests = LoadTestManager::list(&state.db, LIST_LIMIT).await?;
+    Ok(Json(load_tests))
+}
+
+#[utoipa::path(
+    get,
+    path = "/loadtest/{id}",
+    tag = "load_tests",
+    summary = "Get a load test",
+    description = "Get a load test and its report. The report of a running load test is updated every second.",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Load test ID to retrieve"),
+    ),
+    responses(
+        (status = 200, description = "Load test details", body = LoadTest),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Load test not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_load_test(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Models, operation::SystemAccess>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LoadTest>, Error> {
+    let load_test = LoadTestManager::get(&state.db, id).await?;
+    Ok(Json(load_test))
+}
+
+#[utoipa::path(
+    post,
+    path = "/loadtest/{id}/stop",
+    tag = "load_tests",
+    summary = "Stop a load test",
+    description = "Ask a running load test to stop. It stops within about a second; requests still in flight are not counted.",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Load test ID to stop"),
+    ),
+    responses(
+        (status = 202, description = "Stop requested", body = LoadTest),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Load test not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn stop_load_test(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Models, operation::SystemAccess>,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<LoadTest>), Error> {
+    let load_test = LoadTestManager::request_stop(&state.db, id).await?;
+    Ok((StatusCode::ACCEPTED, Json(load_test)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::users::Role;
+    use crate::db::models::load_tests::LoadTest;
+    use crate::test_utils::*;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    async fn create_app(pool: PgPool) -> (axum_test::TestServer, tokio_util::sync::DropGuard) {
+        let mut config = create_test_config();
+        config.load_testing.enabled = true;
+        config.load_testing.max_requests = 100;
+        config.load_testing.max_concurrency = 4;
+        let (router, _, drop_guard) = crate::setup_app(pool, config, true).await.unwrap();
+        (axum_test::TestServer::new(router).unwrap(), drop_guard)
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_load_test_caps_are_enforced(pool: PgPool) {
+        let (server, _drop_guard) = create_app(pool.clone()).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, admin.id, "load-model", "load-alias").await;
+
+        for body in [
+            json!({"deployment_id": deployment.id, "total_requests": 101}),
+            json!({"deployment_id": deployment.id, "total_requests": 0}),
+            json!({"deployment_id": deployment.id, "total_requests": 10, "concurrency": 5}),
+            json!({"deployment_id": deployment.id, "total_requests": 10, "max_duration_seconds": 100000}),
+            json!({"deployment_id": deployment.id, "total_requests": 10, "request_body": [1, 2]}),
+        ] {
+            server
+                .post("/admin/api/v1/loadtest")
+                .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+                .json(&body)
+                .await
+                .assert_status_bad_request();
+        }
+
+        server
+            .post("/admin/api/v1/loadtest")
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"deployment_id": uuid::Uuid::new_v4(), "total_requests": 10}))
+            .await
+            .assert_status_not_found();
+
+        let load_tests: Vec<LoadTest> = server
+            .get("/admin/api/v1/loadtest")
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await
+            .json();
+        assert!(load_tests.is_empty());
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_load_test_lifecycle(pool: PgPool) {
+        let (server, _drop_guard) = create_app(pool.clone()).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, admin.id, "load-model", "load-alias").await;
+
+        let response = server
+            .post("/admin/api/v1/loadtest")
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"deployment_id": deployment.id, "total_requests": 100, "requests_per_second": 1}))
+            .await;
+        response.assert_status(axum::http::StatusCode::ACCEPTED);
+        let load_test: LoadTest = response.json();
+        assert_eq!(load_test.status, "running");
+        assert_eq!(load_test.concurrency, 1);
+        assert_eq!(load_test.created_by, admin.id);
+
+        // Only one load test at a time
+        server
+            .post("/admin/api/v1/loadtest")
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .json(&json!({"deployment_id": deployment.id, "total_requests": 1}))
+            .await
+            .assert_status(axum::http::StatusCode::CONFLICT);
+
+        let stopped: LoadTest = server
+            .post(&format!("/admin/api/v1/loadtest/{}/stop", load_test.id))
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await
+            .json();
+        assert!(stopped.stop_requested);
+
+        let fetched: LoadTest = server
+            .get(&format!("/admin/api/v1/loadtest/{}", load_test.id))
+            .add_header(add_auth_headers(&admin).0, add_auth_headers(&admin).1)
+            .await
+            .json();
+        assert_eq!(fetched.id, load_test.id);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_load_tests_require_admin(pool: PgPool) {
+        let (server, _drop_guard) = create_app(pool.clone()).await;
+        let manager = create_test_user(&pool, Role::PlatformManager).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, admin.id, "load-model", "load-alias").await;
+
+        server
+            .post("/admin/api/v1/loadtest")
+            .add_header(add_auth_headers(&manager).0, add_auth_headers(&manager).1)
+            .json(&json!({"deployment_id": deployment.id, "total_requests": 1}))
+            .await
+            .assert_status_forbidden();
+        server
+            .get("/admin/api/v1/loadtest")
+            .add_header(add_auth_headers(&manager).0, add_auth_headers(&manager).1)
+            .await
+            .assert_status_forbidden();
+    }
+}
@@ -0,0 +1,111 @@
+use crate::api::models::logging::{LogLevelsResponse, LogLevelsUpdate};
+use crate::auth::permissions::{operation, resource, RequiresPermission};
+use crate::errors::Error;
+use crate::logging::{self, LogLevels};
+use axum::Json;
+use tracing::info;
+
+fn log_levels() -> Result<&'static LogLevels, Error> {
+    logging::levels().ok_or_else(|| Error::BadRequest {
+        message: "Log levels can't be changed on this server".to_string(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/log-levels",
+    tag = "logging",
+    summary = "Get log levels",
+    description = "Get the log levels in effect, as `tracing` directives",
+    responses(
+        (status = 200, description = "Log levels", body = LogLevelsResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_log_levels(_: RequiresPermission<resource::Analytics, operation::SystemAccess>) -> Result<Json<LogLevelsResponse>, Error> {
+    let levels = log_levels()?;
+    Ok(Json(LogLevelsResponse {
+        directives: levels.directives(),
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/log-levels",
+    tag = "logging",
+    summary = "Set log levels",
+    description = "Replace the log levels of this instance until it restarts, e.g. `info,dwctl::auth=debug` to debug \
+                   authentication. The levels aren't persisted or shared with other instances.",
+    request_body = LogLevelsUpdate,
+    responses(
+        (status = 200, description = "Log levels updated", body = LogLevelsResponse),
+        (status = 400, description = "Bad request - invalid directives"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn update_log_levels(
+    permission: RequiresPermission<resource::Analytics, operation::SystemAccess>,
+    Json(update): Json<LogLevelsUpdate>,
+) -> Result<Json<LogLevelsResponse>, Error> {
+    let levels = log_levels()?;
+    levels.set(&update.directives).map_err(|e| Error::BadRequest {
+        message: format!("Invalid log directives: {e}"),
+    })?;
+    info!(user_id = %permission.id, directives = %update.directives, "Log levels changed");
+    Ok(Json(LogLevelsResponse {
+        directives: levels.directives(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::logging::LogLevelsResponse;
+    use crate::api::models::users::Role;
+    use crate::test_utils::*;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_update_log_levels(pool: PgPool) {
+        crate::logging::install_for_tests();
+        let (server, _drop_guard) = create_test_app(pool.clone(), false).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+
+        let response = server
+            .put("/admin/api/v1/log-levels")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&json!({"directives": "debug"}))
+            .await;
+        response.assert_status_forbidden();
+
+        let headers = add_auth_headers(&admin);
+        let response = server
+            .put("/admin/api/v1/log-levels")
+            .add_header(headers.0.clone(), headers.1.clone())
+            .json(&json!({"directives": "info,dwctl::auth=debug"}))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<LogLevelsResponse>().directives, "info,dwctl::auth=debug");
+
+        let response = server
+            .put("/admin/api/v1/log-levels")
+            .add_header(headers.0.clone(), headers.1.clone())
+            .json(&json!({"directives": "dwctl=loud"}))
+            .await;
+        response.assert_status_bad_request();
+
+        let response = server.get("/admin/api/v1/log-levels").add_header(headers.0, headers.1).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<LogLevelsResponse>().directives, "info,dwctl::auth=debug");
+    }
+}
@@ -0,0 +1,337 @@
+use crate::api::models::maintenance_windows::{MaintenanceWindowCreate, MaintenanceWindowUpdate, MaintenanceWindowsQuery};
+use crate::auth::permissions::{operation, resource, RequiresPermission};
+use crate::db::models::maintenance_windows::MaintenanceWindow;
+use crate::errors::Error;
+use crate::probes::maintenance::{MaintenanceManager, MaintenanceWindowChanges, NewMaintenanceWindow};
+use crate::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Reject windows that don't cover any time
+fn validate_period(starts_at: DateTime<Utc>, ends_at: DateTime<Utc>) -> Result<(), Error> {
+    if ends_at <= starts_at {
+        return Err(Error::BadRequest {
+            message: "ends_at must be after starts_at".to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/maintenance-windows",
+    tag = "maintenance_windows",
+    summary = "Schedule a maintenance window",
+    description = "Schedule maintenance of an endpoint (covering every deployment hosted on it) or of a single \
+                   deployment. Probes keep running during the window, but their failures don't trigger alerts and \
+                   their results aren't counted by SLOs.",
+    request_body = MaintenanceWindowCreate,
+    responses(
+        (status = 201, description = "Maintenance window scheduled", body = MaintenanceWindow),
+        (status = 400, description = "Bad request - invalid period, or not exactly one of endpoint and deployment"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Endpoint or deployment not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn create_maintenance_window(
+    State(state): State<AppState>,
+    permission: RequiresPermission<resource::Probes, operation::CreateAll>,
+    Json(request): Json<MaintenanceWindowCreate>,
+) -> Result<(StatusCode, Json<MaintenanceWindow>), Error> {
+    if request.endpoint_id.is_some() == request.deployment_id.is_some() {
+        return Err(Error::BadRequest {
+            message: "exactly one of endpoint_id and deployment_id must be set".to_string(),
+        });
+    }
+    validate_period(request.starts_at, request.ends_at)?;
+
+    let window = MaintenanceManager::create(
+        &state.db,
+        NewMaintenanceWindow {
+            name: request.name,
+            endpoint_id: request.endpoint_id,
+            deployment_id: request.deployment_id,
+            starts_at: request.starts_at,
+            ends_at: request.ends_at,
+            reason: request.reason,
+            created_by: permission.current_user.id,
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(window)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/maintenance-windows",
+    tag = "maintenance_windows",
+    summary = "List maintenance windows",
+    description = "List maintenance windows, soonest first, optionally only those of one endpoint or deployment or \
+                   those that haven't ended",
+    params(
+        MaintenanceWindowsQuery
+    ),
+    responses(
+        (status = 200, description = "List of maintenance windows", body = Vec<MaintenanceWindow>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_maintenance_windows(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::ReadAll>,
+    Query(query): Query<MaintenanceWindowsQuery>,
+) -> Result<Json<Vec<MaintenanceWindow>>, Error> {
+    let windows = MaintenanceManager::list(&state.db, &query).await?;
+    Ok(Json(windows))
+}
+
+#[utoipa::path(
+    get,
+    path = "/maintenance-windows/{id}",
+    tag = "maintenance_windows",
+    summary = "Get a maintenance window",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Maintenance window ID to retrieve"),
+    ),
+    responses(
+        (status = 200, description = "Maintenance window details", body = MaintenanceWindow),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Maintenance window not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_maintenance_window(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::ReadAll>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<MaintenanceWindow>, Error> {
+    let window = MaintenanceManager::get(&state.db, id).await?;
+    Ok(Json(window))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/maintenance-windows/{id}",
+    tag = "maintenance_windows",
+    summary = "Update a maintenance window",
+    description = "Rename or reschedule a maintenance window, e.g. to extend it or to end it early. Results already \
+                   taken keep whether they were taken in maintenance.",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Maintenance window ID to update"),
+    ),
+    request_body = MaintenanceWindowUpdate,
+    responses(
+        (status = 200, description = "Maintenance window updated", body = MaintenanceWindow),
+        (status = 400, description = "Bad request - invalid period"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Maintenance window not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn update_maintenance_window(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::UpdateAll>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<MaintenanceWindowUpdate>,
+) -> Result<Json<MaintenanceWindow>, Error> {
+    if request.starts_at.is_some() || request.ends_at.is_some() {
+        let current = MaintenanceManager::get(&state.db, id).await?;
+        validate_period(
+            request.starts_at.unwrap_or(current.starts_at),
+            request.ends_at.unwrap_or(current.ends_at),
+        )?;
+    }
+
+    let window = MaintenanceManager::update(
+        &state.db,
+        id,
+        MaintenanceWindowChanges {
+            name: request.name,
+            starts_at: request.starts_at,
+            ends_at: request.ends_at,
+            reason: request.reason,
+        },
+    )
+    .await?;
+
+    Ok(Json(window))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/maintenance-windows/{id}",
+    tag = "maintenance_windows",
+    summary = "Delete a maintenance window",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Maintenance window ID to delete"),
+    ),
+    responses(
+        (status = 204, description = "Maintenance window deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Maintenance window not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn delete_maintenance_window(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::DeleteAll>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    MaintenanceManager::delete(&state.db, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::users::Role;
+    use crate::db::models::maintenance_windows::MaintenanceWindow;
+    use crate::test_utils::*;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_maintenance_window_crud(pool: PgPool) {
+        let (server, _drop_guard) = create_test_app(pool.clone(), false).await;
+        let manager = create_test_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, manager.id, "maintained-model", "maintained-alias").await;
+        let auth = add_auth_headers(&manager);
+        let now = Utc::now();
+
+        let response = server
+            .post("/admin/api/v1/maintenance-windows")
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({
+                "name": "GPU driver upgrade",
+                "deployment_id": deployment.id,
+                "starts_at": now + Duration::hours(1),
+                "ends_at": now + Duration::hours(2),
+                "reason": "Upgrading to the latest CUDA driver"
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let window: MaintenanceWindow = response.json();
+        assert_eq!(window.deployment_id, Some(deployment.id));
+        assert_eq!(window.endpoint_id, None);
+
+        // Extending the window is fine, ending it before it starts isn't
+        let updated: MaintenanceWindow = server
+            .patch(&format!("/admin/api/v1/maintenance-windows/{}", window.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({"ends_at": now + Duration::hours(3)}))
+            .await
+            .json();
+        assert_eq!(updated.starts_at, window.starts_at);
+        server
+            .patch(&format!("/admin/api/v1/maintenance-windows/{}", window.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({"ends_at": now}))
+            .await
+            .assert_status_bad_request();
+
+        let listed: Vec<MaintenanceWindow> = server
+            .get(&format!(
+                "/admin/api/v1/maintenance-windows?deployment_id={}&upcoming=true",
+                deployment.id
+            ))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .json();
+        assert_eq!(listed.len(), 1);
+
+        server
+            .delete(&format!("/admin/api/v1/maintenance-windows/{}", window.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .assert_status(axum::http::StatusCode::NO_CONTENT);
+        server
+            .get(&format!("/admin/api/v1/maintenance-windows/{}", window.id))
+            .add_header(auth.0, auth.1)
+            .await
+            .assert_status_not_found();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_maintenance_window_validation(pool: PgPool) {
+        let (server, _drop_guard) = create_test_app(pool.clone(), false).await;
+        let manager = create_test_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, manager.id, "maintained-model", "maintained-alias").await;
+        let now = Utc::now();
+        let body = |target: serde_json::Value, hours: i64| {
+            let mut body = json!({
+                "name": "maintenance",
+                "starts_at": now,
+                "ends_at": now + Duration::hours(hours)
+            });
+            body.as_object_mut().unwrap().extend(target.as_object().unwrap().clone());
+            body
+        };
+
+        for (invalid, status) in [
+            (body(json!({}), 1), axum::http::StatusCode::BAD_REQUEST),
+            (
+                body(json!({"deployment_id": deployment.id, "endpoint_id": deployment.hosted_on}), 1),
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+            (
+                body(json!({"deployment_id": deployment.id}), -1),
+                axum::http::StatusCode::BAD_REQUEST,
+            ),
+            (
+                body(json!({"endpoint_id": uuid::Uuid::new_v4()}), 1),
+                axum::http::StatusCode::NOT_FOUND,
+            ),
+        ] {
+            server
+                .post("/admin/api/v1/maintenance-windows")
+                .add_header(add_auth_headers(&manager).0, add_auth_headers(&manager).1)
+                .json(&invalid)
+                .await
+                .assert_status(status);
+        }
+
+        server
+            .post("/admin/api/v1/maintenance-windows")
+            .add_header(add_auth_headers(&manager).0, add_auth_headers(&manager).1)
+            .json(&body(json!({"endpoint_id": deployment.hosted_on}), 1))
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        server
+            .get("/admin/api/v1/maintenance-windows")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await
+            .assert_status_forbidden();
+    }
+}
@@ -1,9 +1,18 @@
+pub mod adoption;
 pub mod api_keys;
 pub mod auth;
+pub mod bootstrap;
 pub mod config;
 pub mod deployments;
+pub mod federation;
 pub mod groups;
 pub mod inference_endpoints;
+pub mod load_tests;
+pub mod logging;
+pub mod maintenance_windows;
+pub mod notification_channels;
 pub mod probes;
+pub mod regression_suites;
 pub mod requests;
+pub mod slos;
 pub mod users;
@@ -0,0 +1,312 @@
+use crate::api::models::notification_channels::{ChannelType, NotificationChannelCreate, NotificationChannelUpdate};
+use crate::auth::permissions::{operation, resource, RequiresPermission};
+use crate::db::models::notification_channels::NotificationChannel;
+use crate::errors::Error;
+use crate::probes::channels::{ChannelChanges, ChannelManager, NewChannel};
+use crate::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use url::Url;
+use uuid::Uuid;
+
+/// Reject destinations alerts can't be delivered to
+fn validate_destination(channel_type: ChannelType, destination: &str) -> Result<(), Error> {
+    let valid = match channel_type {
+        ChannelType::Webhook | ChannelType::Slack => Url::parse(destination).is_ok_and(|url| url.scheme() == "https"),
+        ChannelType::Email => destination.parse::<lettre::Address>().is_ok(),
+    };
+    if valid {
+        return Ok(());
+    }
+
+    let expected = match channel_type {
+        ChannelType::Webhook | ChannelType::Slack => "an https URL",
+        ChannelType::Email => "an email address",
+    };
+    Err(Error::BadRequest {
+        message: format!("destination of {} channels must be {expected}", channel_type.as_str()),
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/notification-channels",
+    tag = "notification_channels",
+    summary = "Create a notification channel",
+    description = "Add a webhook, Slack incoming webhook or email address that's alerted whenever a probe starts failing \
+                   or recovers. Webhooks receive the alert as JSON, Slack and email channels a message describing it.",
+    request_body = NotificationChannelCreate,
+    responses(
+        (status = 201, description = "Notification channel created", body = NotificationChannel),
+        (status = 400, description = "Bad request - invalid destination for the channel type"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn create_notification_channel(
+    State(state): State<AppState>,
+    permission: RequiresPermission<resource::Probes, operation::CreateAll>,
+    Json(request): Json<NotificationChannelCreate>,
+) -> Result<(StatusCode, Json<NotificationChannel>), Error> {
+    validate_destination(request.channel_type, &request.destination)?;
+
+    let channel = ChannelManager::create(
+        &state.db,
+        NewChannel {
+            name: request.name,
+            channel_type: request.channel_type.as_str().to_string(),
+            destination: request.destination,
+            enabled: request.enabled,
+            created_by: permission.current_user.id,
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(channel)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/notification-channels",
+    tag = "notification_channels",
+    summary = "List notification channels",
+    description = "List notification channels along with the outcome of their last delivery",
+    responses(
+        (status = 200, description = "List of notification channels", body = Vec<NotificationChannel>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_notification_channels(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::ReadAll>,
+) -> Result<Json<Vec<NotificationChannel>>, Error> {
+    let channels = ChannelManager::list(&state.db, false).await?;
+    Ok(Json(channels))
+}
+
+#[utoipa::path(
+    get,
+    path = "/notification-channels/{id}",
+    tag = "notification_channels",
+    summary = "Get a notification channel",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Notification channel ID to retrieve"),
+    ),
+    responses(
+        (status = 200, description = "Notification channel details", body = NotificationChannel),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Notification channel not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_notification_channel(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::ReadAll>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<NotificationChannel>, Error> {
+    let channel = ChannelManager::get(&state.db, id).await?;
+    Ok(Json(channel))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/notification-channels/{id}",
+    tag = "notification_channels",
+    summary = "Update a notification channel",
+    description = "Rename a notification channel, change its destination, or enable or disable it",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Notification channel ID to update"),
+    ),
+    request_body = NotificationChannelUpdate,
+    responses(
+        (status = 200, description = "Notification channel updated", body = NotificationChannel),
+        (status = 400, description = "Bad request - invalid destination for the channel type"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Notification channel not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn update_notification_channel(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::UpdateAll>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<NotificationChannelUpdate>,
+) -> Result<Json<NotificationChannel>, Error> {
+    if let Some(destination) = &request.destination {
+        let current = ChannelManager::get(&state.db, id).await?;
+        let channel_type = ChannelType::parse(&current.channel_type).ok_or_else(|| Error::Internal {
+            operation: format!("parse channel type '{}'", current.channel_type),
+        })?;
+        validate_destination(channel_type, destination)?;
+    }
+
+    let channel = ChannelManager::update(
+        &state.db,
+        id,
+        ChannelChanges {
+            name: request.name,
+            destination: request.destination,
+            enabled: request.enabled,
+        },
+    )
+    .await?;
+
+    Ok(Json(channel))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/notification-channels/{id}",
+    tag = "notification_channels",
+    summary = "Delete a notification channel",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Notification channel ID to delete"),
+    ),
+    responses(
+        (status = 204, description = "Notification channel deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Notification channel not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn delete_notification_channel(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::DeleteAll>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    ChannelManager::delete(&state.db, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::users::Role;
+    use crate::db::models::notification_channels::NotificationChannel;
+    use crate::test_utils::*;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_notification_channel_crud(pool: PgPool) {
+        let (server, _drop_guard) = create_test_app(pool.clone(), false).await;
+        let manager = create_test_user(&pool, Role::PlatformManager).await;
+        let auth = add_auth_headers(&manager);
+
+        let response = server
+            .post("/admin/api/v1/notification-channels")
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({
+                "name": "on-call",
+                "channel_type": "slack",
+                "destination": "https://hooks.slack.com/services/T000/B000/XXXX"
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let channel: NotificationChannel = response.json();
+        assert_eq!(channel.channel_type, "slack");
+        assert!(channel.enabled);
+        assert!(channel.last_delivery_at.is_none());
+
+        let updated: NotificationChannel = server
+            .patch(&format!("/admin/api/v1/notification-channels/{}", channel.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({"enabled": false}))
+            .await
+            .json();
+        assert!(!updated.enabled);
+        assert_eq!(updated.destination, channel.destination);
+
+        // Destinations are checked against the channel's type
+        server
+            .patch(&format!("/admin/api/v1/notification-channels/{}", channel.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({"destination": "ops@example.com"}))
+            .await
+            .assert_status_bad_request();
+
+        let listed: Vec<NotificationChannel> = server
+            .get("/admin/api/v1/notification-channels")
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .json();
+        assert_eq!(listed.len(), 1);
+
+        server
+            .delete(&format!("/admin/api/v1/notification-channels/{}", channel.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .assert_status(axum::http::StatusCode::NO_CONTENT);
+        server
+            .get(&format!("/admin/api/v1/notification-channels/{}", channel.id))
+            .add_header(auth.0, auth.1)
+            .await
+            .assert_status_not_found();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_notification_channel_validation(pool: PgPool) {
+        let (server, _drop_guard) = create_test_app(pool.clone(), false).await;
+        let manager = create_test_user(&pool, Role::PlatformManager).await;
+        let body = |channel_type: &str, destination: &str| {
+            json!({
+                "name": "alerts",
+                "channel_type": channel_type,
+                "destination": destination
+            })
+        };
+
+        for invalid in [
+            body("webhook", "not a url"),
+            body("webhook", "http://alerts.example.com/probes"),
+            body("slack", "ops@example.com"),
+            body("email", "https://alerts.example.com"),
+        ] {
+            server
+                .post("/admin/api/v1/notification-channels")
+                .add_header(add_auth_headers(&manager).0, add_auth_headers(&manager).1)
+                .json(&invalid)
+                .await
+                .assert_status_bad_request();
+        }
+
+        server
+            .post("/admin/api/v1/notification-channels")
+            .add_header(add_auth_headers(&manager).0, add_auth_headers(&manager).1)
+            .json(&body("email", "ops@example.com"))
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        server
+            .get("/admin/api/v1/notification-channels")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await
+            .assert_status_forbidden();
+    }
+}
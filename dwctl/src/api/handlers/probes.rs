@@ -1,10 +1,13 @@
 use crate::api::models::probes::{
-    CreateProbe, ProbeStatistics, ProbesQuery, ResultsQuery, StatsQuery, TestProbeRequest, UpdateProbeRequest,
+    ConversationTurn, CreateProbe, ProbeAssertion, ProbeStatistics, ProbeType, ProbesQuery, ResultsQuery, StatsQuery, TestProbeRequest,
+    UpdateProbeRequest,
 };
 use crate::auth::permissions::{operation, resource, RequiresPermission};
+use crate::config::LoadTestingConfig;
 use crate::db::models::probes::{Probe, ProbeResult};
 use crate::errors::Error;
 use crate::probes::db::ProbeManager;
+use crate::probes::executor::LoadSettings;
 use crate::AppState;
 use axum::{
     extract::{Path, Query, State},
@@ -13,12 +16,142 @@ use axum::{
 };
 use uuid::Uuid;
 
+/// Reject probe limits that can never be met
+fn validate_limits(probe_type: Option<ProbeType>, expected_dimensions: Option<i32>, max_latency_ms: Option<i32>) -> Result<(), Error> {
+    if expected_dimensions.is_some_and(|dimensions| dimensions <= 0) {
+        return Err(Error::BadRequest {
+            message: "expected_dimensions must be positive".to_string(),
+        });
+    }
+    if expected_dimensions.is_some() && probe_type.is_some_and(|probe_type| probe_type != ProbeType::Embeddings) {
+        return Err(Error::BadRequest {
+            message: "expected_dimensions only applies to embeddings probes".to_string(),
+        });
+    }
+    if max_latency_ms.is_some_and(|latency| latency <= 0) {
+        return Err(Error::BadRequest {
+            message: "max_latency_ms must be positive".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Reject jitter and timeouts probes can't run with
+fn validate_timing(jitter_seconds: Option<i32>, timeout_seconds: Option<i32>) -> Result<(), Error> {
+    if jitter_seconds.is_some_and(|jitter| jitter < 0) {
+        return Err(Error::BadRequest {
+            message: "jitter_seconds cannot be negative".to_string(),
+        });
+    }
+    if timeout_seconds.is_some_and(|timeout| timeout <= 0) {
+        return Err(Error::BadRequest {
+            message: "timeout_seconds must be positive".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Longest allowed conversation probe script, in turns
+const MAX_CONVERSATION_TURNS: usize = 20;
+
+/// Reject conversation scripts that can't be played
+fn validate_conversation(probe_type: Option<ProbeType>, conversation: Option<&[ConversationTurn]>) -> Result<(), Error> {
+    let Some(conversation) = conversation else {
+        return Ok(());
+    };
+    let message = if probe_type.is_some_and(|probe_type| probe_type != ProbeType::Conversation) {
+        "conversation only applies to conversation probes".to_string()
+    } else if conversation.is_empty() || conversation.len() > MAX_CONVERSATION_TURNS {
+        format!("conversation must have between 1 and {MAX_CONVERSATION_TURNS} turns")
+    } else if conversation.iter().any(|turn| turn.content.trim().is_empty()) {
+        "every turn of a conversation needs content".to_string()
+    } else if conversation
+        .iter()
+        .flat_map(|turn| &turn.expect_contains)
+        .any(|expected| expected.is_empty())
+    {
+        "expect_contains cannot contain empty strings".to_string()
+    } else {
+        return Ok(());
+    };
+    Err(Error::BadRequest { message })
+}
+
+/// Most assertions a probe can have
+const MAX_ASSERTIONS: usize = 20;
+
+/// Reject assertions that can't be evaluated
+fn validate_assertions(assertions: Option<&[ProbeAssertion]>) -> Result<(), Error> {
+    let Some(assertions) = assertions else {
+        return Ok(());
+    };
+    if assertions.len() > MAX_ASSERTIONS {
+        return Err(Error::BadRequest {
+            message: format!("a probe can have at most {MAX_ASSERTIONS} assertions"),
+        });
+    }
+    for assertion in assertions {
+        crate::probes::assertions::validate(assertion).map_err(|message| Error::BadRequest { message })?;
+    }
+    Ok(())
+}
+
+/// Reject semantic similarity settings that can't be evaluated. `complete` requires everything a
+/// semantic similarity probe needs to run, for probes being created or tested rather than updated.
+fn validate_similarity(
+    probe_type: Option<ProbeType>,
+    golden_answer: Option<&str>,
+    embeddings_deployment_id: Option<Uuid>,
+    similarity_threshold: Option<f64>,
+    request_body: Option<&serde_json::Value>,
+    complete: bool,
+) -> Result<(), Error> {
+    let is_set = golden_answer.is_some() || embeddings_deployment_id.is_some() || similarity_threshold.is_some();
+    let message = if is_set && probe_type.is_some_and(|probe_type| probe_type != ProbeType::SemanticSimilarity) {
+        "golden_answer, embeddings_deployment_id and similarity_threshold only apply to semantic_similarity probes"
+    } else if similarity_threshold.is_some_and(|threshold| !(threshold > 0.0 && threshold <= 1.0)) {
+        "similarity_threshold must be greater than 0 and at most 1"
+    } else if golden_answer.is_some_and(|answer| answer.trim().is_empty()) {
+        "golden_answer cannot be empty"
+    } else if complete
+        && probe_type == Some(ProbeType::SemanticSimilarity)
+        && (golden_answer.is_none() || embeddings_deployment_id.is_none() || request_body.is_none())
+    {
+        "semantic_similarity probes need a request_body asking a question, its golden_answer and an embeddings_deployment_id"
+    } else {
+        return Ok(());
+    };
+    Err(Error::BadRequest {
+        message: message.to_string(),
+    })
+}
+
+/// Reject load settings on other probe types, and load probes beyond the load testing limits
+fn validate_load(
+    probe_type: Option<ProbeType>,
+    load_concurrency: Option<i32>,
+    load_duration_seconds: Option<i32>,
+    limits: &LoadTestingConfig,
+) -> Result<(), Error> {
+    let is_set = load_concurrency.is_some() || load_duration_seconds.is_some();
+    if is_set && probe_type.is_some_and(|probe_type| probe_type != ProbeType::Load) {
+        return Err(Error::BadRequest {
+            message: "load_concurrency and load_duration_seconds only apply to load probes".to_string(),
+        });
+    }
+    if is_set || probe_type == Some(ProbeType::Load) {
+        LoadSettings::new(load_concurrency, load_duration_seconds, limits).map_err(|message| Error::BadRequest { message })?;
+    }
+    Ok(())
+}
+
 #[utoipa::path(
     post,
     path = "/probes",
     tag = "probes",
     summary = "Create a new probe",
-    description = "Create a new probe to monitor a deployed model. The probe is automatically activated and starts executing on its configured interval.",
+    description = "Create a new probe to monitor a deployed model. The probe is automatically activated and starts executing on its configured interval, \
+                   except for load probes, which only run on demand.",
     request_body = CreateProbe,
     responses(
         (status = 201, description = "Probe created successfully", body = Probe),
@@ -37,6 +170,24 @@ pub async fn create_probe(
     _: RequiresPermission<resource::Probes, operation::CreateAll>,
     Json(probe): Json<CreateProbe>,
 ) -> Result<(StatusCode, Json<Probe>), Error> {
+    validate_limits(Some(probe.probe_type), probe.expected_dimensions, probe.max_latency_ms)?;
+    validate_conversation(Some(probe.probe_type), probe.conversation.as_deref())?;
+    validate_timing(probe.jitter_seconds, probe.timeout_seconds)?;
+    validate_assertions(probe.assertions.as_deref())?;
+    validate_similarity(
+        Some(probe.probe_type),
+        probe.golden_answer.as_deref(),
+        probe.embeddings_deployment_id,
+        probe.similarity_threshold,
+        probe.request_body.as_ref(),
+        true,
+    )?;
+    validate_load(
+        Some(probe.probe_type),
+        probe.load_concurrency,
+        probe.load_duration_seconds,
+        &state.config.load_testing,
+    )?;
     let created = ProbeManager::create_probe(&state.db, probe).await?;
     Ok((StatusCode::CREATED, Json(created)))
 }
@@ -141,6 +292,7 @@ pub async fn delete_probe(
     ),
     responses(
         (status = 200, description = "Probe activated successfully", body = Probe),
+        (status = 400, description = "Bad request - load probes only run on demand"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden - admin access required"),
         (status = 404, description = "Probe not found"),
@@ -216,6 +368,24 @@ pub async fn update_probe(
     Path(id): Path<Uuid>,
     Json(update): Json<UpdateProbeRequest>,
 ) -> Result<Json<Probe>, Error> {
+    validate_limits(update.probe_type, update.expected_dimensions, update.max_latency_ms)?;
+    validate_conversation(update.probe_type, update.conversation.as_deref())?;
+    validate_timing(update.jitter_seconds, update.timeout_seconds)?;
+    validate_assertions(update.assertions.as_deref())?;
+    validate_similarity(
+        update.probe_type,
+        update.golden_answer.as_deref(),
+        update.embeddings_deployment_id,
+        update.similarity_threshold,
+        update.request_body.as_ref(),
+        false,
+    )?;
+    validate_load(
+        update.probe_type,
+        update.load_concurrency,
+        update.load_duration_seconds,
+        &state.config.load_testing,
+    )?;
     let probe = ProbeManager::update_probe(&state.db, id, update).await?;
     Ok(Json(probe))
 }
@@ -225,12 +395,15 @@ pub async fn update_probe(
     path = "/probes/{id}/execute",
     tag = "probes",
     summary = "Execute a probe immediately",
-    description = "Manually trigger a probe execution without waiting for the scheduled interval",
+    description = "Manually trigger a probe execution without waiting for the scheduled interval. This is the only way \
+                   to run a load probe, which responds once its duration has passed, with the throughput and latency \
+                   distribution in the result's metadata.",
     params(
         ("id" = uuid::Uuid, Path, description = "Probe ID to execute"),
     ),
     responses(
         (status = 201, description = "Probe executed successfully", body = ProbeResult),
+        (status = 400, description = "Bad request - load testing disabled or the load probe exceeds its limits"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden - admin access required"),
         (status = 404, description = "Probe not found"),
@@ -275,13 +448,31 @@ pub async fn test_probe(
     Path(deployment_id): Path<Uuid>,
     Json(request): Json<Option<TestProbeRequest>>,
 ) -> Result<(StatusCode, Json<ProbeResult>), Error> {
-    let (http_method, request_path, request_body) = if let Some(req) = request {
-        (req.http_method, req.request_path, req.request_body)
-    } else {
-        (None, None, None)
-    };
-
-    let result = ProbeManager::test_probe(&state.db, deployment_id, &state.config, http_method, request_path, request_body).await?;
+    let request = request.unwrap_or_default();
+    validate_limits(
+        Some(request.probe_type.unwrap_or_default()),
+        request.expected_dimensions,
+        request.max_latency_ms,
+    )?;
+    validate_conversation(Some(request.probe_type.unwrap_or_default()), request.conversation.as_deref())?;
+    validate_timing(None, request.timeout_seconds)?;
+    validate_assertions(request.assertions.as_deref())?;
+    validate_similarity(
+        Some(request.probe_type.unwrap_or_default()),
+        request.golden_answer.as_deref(),
+        request.embeddings_deployment_id,
+        request.similarity_threshold,
+        request.request_body.as_ref(),
+        true,
+    )?;
+    validate_load(
+        Some(request.probe_type.unwrap_or_default()),
+        request.load_concurrency,
+        request.load_duration_seconds,
+        &state.config.load_testing,
+    )?;
+
+    let result = ProbeManager::test_probe(&state.db, deployment_id, &state.config, request).await?;
     Ok((StatusCode::OK, Json(result)))
 }
 
@@ -321,7 +512,8 @@ pub async fn get_probe_results(
     path = "/probes/{id}/statistics",
     tag = "probes",
     summary = "Get probe statistics",
-    description = "Get aggregated statistics for a probe including success rates, response times, and percentiles",
+    description = "Get aggregated statistics for a probe including success rates, response times, and percentiles, and for \
+                   streamed completions the time to the first token and tokens per second",
     params(
         ("id" = uuid::Uuid, Path, description = "Probe ID to get statistics for"),
         StatsQuery
@@ -353,7 +545,7 @@ mod tests {
     use crate::{
         api::models::users::Role,
         db::models::probes::Probe,
-        test_utils::{add_auth_headers, create_test_admin_user, create_test_app, create_test_user},
+        test_utils::{add_auth_headers, create_test_admin_user, create_test_app, create_test_config, create_test_user},
     };
     use sqlx::PgPool;
 
@@ -412,6 +604,285 @@ mod tests {
         assert!(probe.active);
     }
 
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_create_embeddings_probe(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let deployment_id = setup_test_deployment(&pool, user.id).await;
+
+        let mut payload = serde_json::json!({
+            "name": "Embeddings Probe",
+            "deployment_id": deployment_id,
+            "interval_seconds": 60,
+            "expected_dimensions": 1536,
+            "max_latency_ms": 500
+        });
+
+        // Only embeddings probes return vectors to check
+        let response = app
+            .post("/admin/api/v1/probes")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&payload)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        payload["probe_type"] = "embeddings".into();
+        let response = app
+            .post("/admin/api/v1/probes")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&payload)
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let probe: Probe = response.json();
+        assert_eq!(probe.probe_type, "embeddings");
+        assert_eq!(probe.expected_dimensions, Some(1536));
+        assert_eq!(probe.max_latency_ms, Some(500));
+
+        let response = app
+            .patch(&format!("/admin/api/v1/probes/{}", probe.id))
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&serde_json::json!({"max_latency_ms": 0}))
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        for invalid in [serde_json::json!({"jitter_seconds": -1}), serde_json::json!({"timeout_seconds": 0})] {
+            let response = app
+                .patch(&format!("/admin/api/v1/probes/{}", probe.id))
+                .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+                .json(&invalid)
+                .await;
+            response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        let probe: Probe = app
+            .patch(&format!("/admin/api/v1/probes/{}", probe.id))
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&serde_json::json!({"jitter_seconds": 15, "timeout_seconds": 5}))
+            .await
+            .json();
+        assert_eq!((probe.jitter_seconds, probe.timeout_seconds), (Some(15), Some(5)));
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_create_conversation_probe(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let deployment_id = setup_test_deployment(&pool, user.id).await;
+
+        let conversation = serde_json::json!([
+            {"content": "My name is Ada."},
+            {"content": "What is my name?", "expect_contains": ["Ada"]}
+        ]);
+        let mut payload = serde_json::json!({
+            "name": "Conversation Probe",
+            "deployment_id": deployment_id,
+            "interval_seconds": 60,
+            "conversation": conversation
+        });
+
+        // Only conversation probes play a script
+        let response = app
+            .post("/admin/api/v1/probes")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&payload)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        payload["probe_type"] = "conversation".into();
+        let response = app
+            .post("/admin/api/v1/probes")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&payload)
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let probe: Probe = response.json();
+        assert_eq!(probe.probe_type, "conversation");
+        assert_eq!(probe.conversation.unwrap()[1]["expect_contains"], serde_json::json!(["Ada"]));
+
+        for conversation in [serde_json::json!([]), serde_json::json!([{"content": " "}])] {
+            let response = app
+                .patch(&format!("/admin/api/v1/probes/{}", probe.id))
+                .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+                .json(&serde_json::json!({"conversation": conversation}))
+                .await;
+            response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_create_semantic_similarity_probe(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let deployment_id = setup_test_deployment(&pool, user.id).await;
+        let embeddings_deployment_id = setup_test_deployment(&pool, user.id).await;
+
+        let mut payload = serde_json::json!({
+            "name": "Golden Answer Probe",
+            "deployment_id": deployment_id,
+            "interval_seconds": 300,
+            "probe_type": "semantic_similarity",
+            "request_body": {"messages": [{"role": "user", "content": "What is the capital of France?"}]},
+            "golden_answer": "The capital of France is Paris.",
+            "embeddings_deployment_id": embeddings_deployment_id,
+            "similarity_threshold": 1.5
+        });
+
+        let response = app
+            .post("/admin/api/v1/probes")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&payload)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        // A golden answer is meaningless without an embeddings deployment to compare it with
+        payload["similarity_threshold"] = 0.9.into();
+        let mut incomplete = payload.clone();
+        incomplete.as_object_mut().unwrap().remove("embeddings_deployment_id");
+        let response = app
+            .post("/admin/api/v1/probes")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&incomplete)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        let response = app
+            .post("/admin/api/v1/probes")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&payload)
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let probe: Probe = response.json();
+        assert_eq!(probe.probe_type, "semantic_similarity");
+        assert_eq!(probe.embeddings_deployment_id, Some(embeddings_deployment_id));
+        assert_eq!(probe.similarity_threshold, Some(0.9));
+
+        let response = app
+            .patch(&format!("/admin/api/v1/probes/{}", probe.id))
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&serde_json::json!({"probe_type": "http", "golden_answer": "Paris"}))
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        let response = app
+            .patch(&format!("/admin/api/v1/probes/{}", probe.id))
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&serde_json::json!({"golden_answer": "Paris"}))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<Probe>().golden_answer.as_deref(), Some("Paris"));
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_create_load_probe(pool: PgPool) {
+        let user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let deployment_id = setup_test_deployment(&pool, user.id).await;
+        let payload = serde_json::json!({
+            "name": "Load Probe",
+            "deployment_id": deployment_id,
+            "interval_seconds": 300,
+            "probe_type": "load",
+            "load_concurrency": 4,
+            "load_duration_seconds": 30
+        });
+
+        // Load probes need load testing to be enabled
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let response = app
+            .post("/admin/api/v1/probes")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&payload)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        let mut config = create_test_config();
+        config.load_testing.enabled = true;
+        config.load_testing.max_concurrency = 4;
+        let (router, _, _drop_guard) = crate::setup_app(pool.clone(), config, true).await.unwrap();
+        let app = axum_test::TestServer::new(router).unwrap();
+
+        let mut too_many = payload.clone();
+        too_many["load_concurrency"] = 5.into();
+        let mut not_load = payload.clone();
+        not_load["probe_type"] = "http".into();
+        for invalid in [too_many, not_load] {
+            let response = app
+                .post("/admin/api/v1/probes")
+                .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+                .json(&invalid)
+                .await;
+            response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        let response = app
+            .post("/admin/api/v1/probes")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&payload)
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let probe: Probe = response.json();
+        assert_eq!(probe.probe_type, "load");
+        assert_eq!(probe.load_concurrency, Some(4));
+        // Load probes are never scheduled
+        assert!(!probe.active);
+
+        let response = app
+            .patch(&format!("/admin/api/v1/probes/{}/activate", probe.id))
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_create_probe_with_assertions(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let deployment_id = setup_test_deployment(&pool, user.id).await;
+
+        let assertions = serde_json::json!([
+            {"type": "json_path_exists", "path": "$.choices[0].message.content"},
+            {"type": "regex", "pattern": "(?i)hello"},
+            {"type": "max_latency_ms", "max_ms": 2000},
+            {"type": "min_tokens", "min_tokens": 1}
+        ]);
+        let response = app
+            .post("/admin/api/v1/probes")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&serde_json::json!({
+                "name": "Asserting Probe",
+                "deployment_id": deployment_id,
+                "interval_seconds": 60,
+                "assertions": assertions
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let probe: Probe = response.json();
+        assert_eq!(probe.assertions, Some(assertions));
+
+        for assertions in [
+            serde_json::json!([{"type": "regex", "pattern": "("}]),
+            serde_json::json!([{"type": "json_path_exists", "path": "choices"}]),
+        ] {
+            let response = app
+                .patch(&format!("/admin/api/v1/probes/{}", probe.id))
+                .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+                .json(&serde_json::json!({"assertions": assertions}))
+                .await;
+            response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        let response = app
+            .patch(&format!("/admin/api/v1/probes/{}", probe.id))
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&serde_json::json!({"assertions": []}))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<Probe>().assertions, Some(serde_json::json!([])));
+    }
+
     #[sqlx::test]
     #[test_log::test]
     async fn test_create_probe_unauthorized(pool: PgPool) {
@@ -453,6 +924,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -467,6 +950,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -498,6 +993,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -545,6 +1052,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -582,6 +1101,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -616,6 +1147,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -647,6 +1190,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -684,6 +1239,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -715,6 +1282,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -0,0 +1,411 @@
+use crate::api::models::regression_suites::{RegressionSuiteCreate, RegressionSuiteUpdate, RegressionSuitesQuery};
+use crate::auth::permissions::{operation, resource, RequiresPermission};
+use crate::db::models::regression_suites::{RegressionCase, RegressionRun, RegressionSuite};
+use crate::errors::Error;
+use crate::regression_suites::db::{NewRegressionSuite, RegressionSuiteChanges, RegressionSuiteManager};
+use crate::regression_suites::runner;
+use crate::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+/// Number of runs returned when listing a suite's history
+const RUNS_LIMIT: i64 = 100;
+
+/// Shortest allowed schedule, in seconds
+const MIN_SCHEDULE_INTERVAL: u32 = 60;
+
+fn validate_cases(cases: &[RegressionCase]) -> Result<(), Error> {
+    runner::validate_cases(cases).map_err(|message| Error::BadRequest { message })
+}
+
+fn validate_schedule(interval: Option<u32>) -> Result<Option<i32>, Error> {
+    interval
+        .map(|seconds| {
+            if seconds < MIN_SCHEDULE_INTERVAL {
+                return Err(Error::BadRequest {
+                    message: format!("schedule_interval_seconds must be at least {MIN_SCHEDULE_INTERVAL}"),
+                });
+            }
+            i32::try_from(seconds).map_err(|_| Error::BadRequest {
+                message: "schedule_interval_seconds is too large".to_string(),
+            })
+        })
+        .transpose()
+}
+
+#[utoipa::path(
+    post,
+    path = "/regression-suites",
+    tag = "regression_suites",
+    summary = "Create a regression suite",
+    description = "Attach a suite of golden prompts to a deployment. Each case sends a chat completion request \
+                   and asserts properties of the response (regex, JSON schema, max latency). Set a schedule \
+                   to run the suite automatically.",
+    request_body = RegressionSuiteCreate,
+    responses(
+        (status = 201, description = "Regression suite created", body = RegressionSuite),
+        (status = 400, description = "Bad request - no cases, invalid regex or schedule too short"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Deployment not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn create_regression_suite(
+    State(state): State<AppState>,
+    permission: RequiresPermission<resource::Probes, operation::CreateAll>,
+    Json(request): Json<RegressionSuiteCreate>,
+) -> Result<(StatusCode, Json<RegressionSuite>), Error> {
+    validate_cases(&request.cases)?;
+    let schedule_interval_seconds = validate_schedule(request.schedule_interval_seconds)?;
+
+    let suite = RegressionSuiteManager::create(
+        &state.db,
+        NewRegressionSuite {
+            deployment_id: request.deployment_id,
+            name: request.name,
+            description: request.description,
+            cases: request.cases,
+            schedule_interval_seconds,
+            created_by: permission.current_user.id,
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(suite)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/regression-suites",
+    tag = "regression_suites",
+    summary = "List regression suites",
+    description = "List regression suites, optionally only those attached to one deployment",
+    params(
+        RegressionSuitesQuery
+    ),
+    responses(
+        (status = 200, description = "List of regression suites", body = Vec<RegressionSuite>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_regression_suites(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::ReadAll>,
+    Query(query): Query<RegressionSuitesQuery>,
+) -> Result<Json<Vec<RegressionSuite>>, Error> {
+    let suites = RegressionSuiteManager::list(&state.db, query.deployment_id).await?;
+    Ok(Json(suites))
+}
+
+#[utoipa::path(
+    get,
+    path = "/regression-suites/{id}",
+    tag = "regression_suites",
+    summary = "Get a regression suite",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Regression suite ID to retrieve"),
+    ),
+    responses(
+        (status = 200, description = "Regression suite details", body = RegressionSuite),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Regression suite not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_regression_suite(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::ReadAll>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RegressionSuite>, Error> {
+    let suite = RegressionSuiteManager::get(&state.db, id).await?;
+    Ok(Json(suite))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/regression-suites/{id}",
+    tag = "regression_suites",
+    summary = "Update a regression suite",
+    description = "Update a regression suite's cases, schedule or details. Stored runs are kept.",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Regression suite ID to update"),
+    ),
+    request_body = RegressionSuiteUpdate,
+    responses(
+        (status = 200, description = "Regression suite updated", body = RegressionSuite),
+        (status = 400, description = "Bad request - no cases, invalid regex or schedule too short"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Regression suite not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn update_regression_suite(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::UpdateAll>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<RegressionSuiteUpdate>,
+) -> Result<Json<RegressionSuite>, Error> {
+    if let Some(cases) = &request.cases {
+        validate_cases(cases)?;
+    }
+    let schedule_interval_seconds = request.schedule_interval_seconds.map(validate_schedule).transpose()?;
+
+    let suite = RegressionSuiteManager::update(
+        &state.db,
+        id,
+        RegressionSuiteChanges {
+            name: request.name,
+            description: request.description,
+            cases: request.cases,
+            schedule_interval_seconds,
+        },
+    )
+    .await?;
+
+    Ok(Json(suite))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/regression-suites/{id}",
+    tag = "regression_suites",
+    summary = "Delete a regression suite",
+    description = "Delete a regression suite along with its run history",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Regression suite ID to delete"),
+    ),
+    responses(
+        (status = 204, description = "Regression suite deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Regression suite not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn delete_regression_suite(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::DeleteAll>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    RegressionSuiteManager::delete(&state.db, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/regression-suites/{id}/run",
+    tag = "regression_suites",
+    summary = "Run a regression suite",
+    description = "Run every case of a regression suite against its deployment through the AI proxy, \
+                   store the results and return them. Cases run one at a time.",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Regression suite ID to run"),
+    ),
+    responses(
+        (status = 201, description = "Suite run and results stored", body = RegressionRun),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Regression suite or deployment not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn run_regression_suite(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::UpdateAll>,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<RegressionRun>), Error> {
+    let suite = RegressionSuiteManager::get(&state.db, id).await?;
+    let ai_base_url = format!("http://localhost:{}/ai", state.config.port);
+    let run = runner::run_suite(&state.db, &ai_base_url, &suite, "manual").await?;
+    Ok((StatusCode::CREATED, Json(run)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/regression-suites/{id}/runs",
+    tag = "regression_suites",
+    summary = "List runs of a regression suite",
+    description = "List the most recent runs of a regression suite, newest first, to track its score over time",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Regression suite ID"),
+    ),
+    responses(
+        (status = 200, description = "Runs of the suite", body = Vec<RegressionRun>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Regression suite not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_regression_runs(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::ReadAll>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<RegressionRun>>, Error> {
+    // 404 for unknown suites rather than an empty history
+    RegressionSuiteManager::get(&state.db, id).await?;
+    let runs = RegressionSuiteManager::list_runs(&state.db, id, RUNS_LIMIT).await?;
+    Ok(Json(runs))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::users::Role;
+    use crate::db::models::regression_suites::{RegressionRun, RegressionSuite};
+    use crate::test_utils::*;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    fn suite_body(deployment_id: uuid::Uuid) -> serde_json::Value {
+        json!({
+            "deployment_id": deployment_id,
+            "name": "golden prompts",
+            "cases": [{
+                "name": "capital",
+                "request": {"messages": [{"role": "user", "content": "What is the capital of France?"}]},
+                "assertions": [
+                    {"type": "regex", "pattern": "(?i)paris"},
+                    {"type": "max_latency_ms", "max_ms": 5000}
+                ]
+            }],
+            "schedule_interval_seconds": 3600
+        })
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_regression_suite_crud(pool: PgPool) {
+        let (server, _drop_guard) = create_test_app(pool.clone(), false).await;
+        let manager = create_test_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, manager.id, "regression-model", "regression-alias").await;
+        let auth = add_auth_headers(&manager);
+
+        let response = server
+            .post("/admin/api/v1/regression-suites")
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&suite_body(deployment.id))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let suite: RegressionSuite = response.json();
+        assert_eq!(suite.cases.len(), 1);
+        assert_eq!(suite.schedule_interval_seconds, Some(3600));
+        assert_eq!(suite.created_by, manager.id);
+
+        let listed: Vec<RegressionSuite> = server
+            .get(&format!("/admin/api/v1/regression-suites?deployment_id={}", deployment.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .json();
+        assert_eq!(listed.len(), 1);
+
+        let updated: RegressionSuite = server
+            .patch(&format!("/admin/api/v1/regression-suites/{}", suite.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({"name": "renamed", "schedule_interval_seconds": null}))
+            .await
+            .json();
+        assert_eq!(updated.name, "renamed");
+        assert_eq!(updated.schedule_interval_seconds, None);
+        assert_eq!(updated.cases.len(), 1);
+
+        let runs: Vec<RegressionRun> = server
+            .get(&format!("/admin/api/v1/regression-suites/{}/runs", suite.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .json();
+        assert!(runs.is_empty());
+
+        server
+            .delete(&format!("/admin/api/v1/regression-suites/{}", suite.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .assert_status(axum::http::StatusCode::NO_CONTENT);
+        server
+            .get(&format!("/admin/api/v1/regression-suites/{}", suite.id))
+            .add_header(auth.0, auth.1)
+            .await
+            .assert_status_not_found();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_regression_suite_validation(pool: PgPool) {
+        let (server, _drop_guard) = create_test_app(pool.clone(), false).await;
+        let manager = create_test_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, manager.id, "regression-model", "regression-alias").await;
+
+        let mut no_cases = suite_body(deployment.id);
+        no_cases["cases"] = json!([]);
+        let mut bad_regex = suite_body(deployment.id);
+        bad_regex["cases"][0]["assertions"][0]["pattern"] = json!("(unclosed");
+        let mut short_schedule = suite_body(deployment.id);
+        short_schedule["schedule_interval_seconds"] = json!(30);
+
+        for body in [no_cases, bad_regex, short_schedule] {
+            server
+                .post("/admin/api/v1/regression-suites")
+                .add_header(add_auth_headers(&manager).0, add_auth_headers(&manager).1)
+                .json(&body)
+                .await
+                .assert_status_bad_request();
+        }
+
+        server
+            .post("/admin/api/v1/regression-suites")
+            .add_header(add_auth_headers(&manager).0, add_auth_headers(&manager).1)
+            .json(&suite_body(uuid::Uuid::new_v4()))
+            .await
+            .assert_status_not_found();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_regression_suites_require_probes_permission(pool: PgPool) {
+        let (server, _drop_guard) = create_test_app(pool.clone(), false).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, admin.id, "regression-model", "regression-alias").await;
+
+        server
+            .post("/admin/api/v1/regression-suites")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&suite_body(deployment.id))
+            .await
+            .assert_status_forbidden();
+        server
+            .get("/admin/api/v1/regression-suites")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await
+            .assert_status_forbidden();
+    }
+}
@@ -3,27 +3,54 @@
 //! Endpoints for querying HTTP requests logged by the outlet-postgres middleware.
 
 use axum::{
-    extract::{Query, State},
-    response::Json,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
 // Remove unused chrono imports
+use bytes::Bytes;
+use futures::StreamExt;
 use outlet_postgres::{RequestFilter, RequestRepository};
-use tracing::{debug, error, instrument};
+use tracing::{debug, error, instrument, warn};
 
 use crate::{
     api::models::requests::{
-        AggregateRequestsQuery, ApiAiRequest, ApiAiResponse, HttpRequest, HttpResponse, ListRequestsQuery, ListRequestsResponse,
-        ModelUserUsageResponse, RequestResponsePair, RequestsAggregateResponse,
+        AggregateRequestsQuery, ApiAiRequest, ApiAiResponse, AttributeUsageResponse, BodySource, ConversationUsageResponse,
+        EmbeddingUsageQuery, EmbeddingUsageResponse, ErrorBreakdownResponse, ExportRequestsQuery, GroupUsageResponse, HttpRequest,
+        HttpResponse, ListArchivesQuery, ListRequestsQuery, ListRequestsResponse, ModelUsageResponse, ModelUserUsageResponse,
+        PiiStatsQuery, PiiStatsResponse, RequestCursor, RequestDetailResponse, RequestLogArchive, RequestResponsePair, RequestSortField,
+        RequestsAggregateResponse, TagUsageResponse, TokenBackfillCreate, UsageTimeSeriesQuery, UsageTimeSeriesResponse,
     },
+    api::models::{pagination::timestamp_value, sorting::SortOrder},
     auth::permissions::{operation, resource, RequiresPermission},
-    db::handlers::analytics::{get_model_user_usage, get_requests_aggregate},
+    db::{
+        errors::DbError,
+        handlers::analytics::{
+            get_attribute_model_usage, get_conversation_usage, get_embedding_usage_by_group, get_embedding_usage_by_model,
+            get_error_breakdown, get_group_model_usage, get_model_endpoint_usage, get_model_user_usage, get_pii_stats_by_group,
+            get_request_costs, get_requests_aggregate, get_tag_usage, get_usage_time_series, stream_requests_for_export,
+        },
+        models::token_backfills::TokenBackfill,
+    },
     errors::Error,
-    request_logging::{AiRequest, AiResponse},
+    request_export::ExportEncoder,
+    request_logging::{
+        backfill::{self, TokenBackfillManager},
+        conversations::conversation_requests,
+        retention::list_archives,
+        search::{requests_after, search_requests},
+        storage::BodyStorage,
+        tags::{parse_tag_filter, tagged_requests},
+        AiRequest, AiResponse,
+    },
     AppState,
 };
 use chrono::{DateTime, Duration, Utc};
 use serde::Deserialize;
+use tokio::sync::mpsc;
 use utoipa::IntoParams;
+use uuid::Uuid;
 
 /// Convert outlet-postgres request/response pairs to API types
 ///
@@ -92,16 +119,90 @@ fn convert_outlet_pairs_to_api(outlet_pairs: Vec<outlet_postgres::RequestRespons
             RequestResponsePair {
                 request: api_request,
                 response: api_response,
+                total_cost: None,
             }
         })
         .collect()
 }
 
-/// List HTTP requests with filtering and pagination
+/// Convert outlet-postgres request/response pairs to API types, with the cost recorded in the
+/// analytics table for each request
+async fn convert_pairs_with_costs(
+    db: &sqlx::PgPool,
+    outlet_pairs: Vec<outlet_postgres::RequestResponsePair<AiRequest, AiResponse>>,
+) -> Result<Vec<RequestResponsePair>, Error> {
+    let keys: Vec<_> = outlet_pairs
+        .iter()
+        .map(|pair| (pair.request.instance_id, pair.request.correlation_id))
+        .collect();
+    let costs = get_request_costs(db, &keys).await?;
+    let mut pairs = convert_outlet_pairs_to_api(outlet_pairs);
+    for (pair, key) in pairs.iter_mut().zip(&keys) {
+        pair.total_cost = costs.get(key).copied();
+    }
+    Ok(pairs)
+}
+
+/// Number of bodies fetched from object storage at once when listing requests
+const BODY_LOAD_CONCURRENCY: usize = 32;
+
+/// Replace pointers to bodies kept in object storage with the bodies themselves. Bodies that
+/// can't be fetched are left as pointers.
+async fn load_stored_bodies(outlet_pairs: &mut [outlet_postgres::RequestResponsePair<AiRequest, AiResponse>], body_storage: &BodyStorage) {
+    futures::stream::iter(outlet_pairs.iter_mut())
+        .for_each_concurrent(BODY_LOAD_CONCURRENCY, |pair| async move {
+            if let Some(Ok(body)) = pair.request.body.as_mut() {
+                if let AiRequest::Stored { stored_body } = body {
+                    match body_storage.load(stored_body).await {
+                        Ok(loaded) => *body = loaded,
+                        Err(e) => warn!(key = %stored_body.key, error = %e, "Failed to load stored request body"),
+                    }
+                }
+            }
+            if let Some(Ok(body)) = pair.response.as_mut().and_then(|response| response.body.as_mut()) {
+                if let AiResponse::Stored { stored_body } = body {
+                    match body_storage.load(stored_body).await {
+                        Ok(loaded) => *body = loaded,
+                        Err(e) => warn!(key = %stored_body.key, error = %e, "Failed to load stored response body"),
+                    }
+                }
+            }
+        })
+        .await;
+}
+
+/// Fetch the logged requests with the given `(instance_id, correlation_id)` keys, in order
+async fn fetch_pairs(
+    repository: &RequestRepository<AiRequest, AiResponse>,
+    keys: Vec<(Uuid, i64)>,
+) -> Result<Vec<outlet_postgres::RequestResponsePair<AiRequest, AiResponse>>, outlet_postgres::PostgresHandlerError> {
+    let pairs = futures::future::try_join_all(keys.into_iter().map(|(instance_id, correlation_id)| {
+        repository.query(RequestFilter {
+            instance_id: Some(instance_id),
+            correlation_id: Some(correlation_id),
+            ..Default::default()
+        })
+    }))
+    .await?;
+    Ok(pairs.into_iter().flatten().collect())
+}
+
+/// Whether either body of `pair` is a pointer into object storage
+fn has_stored_bodies(pair: &outlet_postgres::RequestResponsePair<AiRequest, AiResponse>) -> bool {
+    matches!(pair.request.body, Some(Ok(AiRequest::Stored { .. })))
+        || pair
+            .response
+            .as_ref()
+            .is_some_and(|response| matches!(response.body, Some(Ok(AiResponse::Stored { .. }))))
+}
+
 ///
 /// Returns a paginated list of HTTP requests logged by the system, with optional filtering
 /// by user, endpoint type, time range, and other criteria. Only requests to AI endpoints
-/// (/ai/* paths) are included.
+/// (/ai/* paths) are included. `search` narrows the list to requests whose request or
+/// response body matches a full-text search, `conversation_id` to the turns of a
+/// conversation and `tags` to requests carrying the given tags; a search can't be combined
+/// with the other two.
 #[utoipa::path(
     get,
     path = "/admin/api/v1/requests",
@@ -135,12 +236,26 @@ pub async fn list_requests(
 
     let repository: RequestRepository<AiRequest, AiResponse> = RequestRepository::new(outlet_pool.clone());
 
+    // outlet-postgres only orders requests by timestamp
+    let RequestSortField::Timestamp = query.sort.unwrap_or(RequestSortField::Timestamp);
+    let mut order = query.order.unwrap_or(if query.order_desc.unwrap_or(true) {
+        SortOrder::Desc
+    } else {
+        SortOrder::Asc
+    });
+
+    // A cursor replaces the offset, and keeps the order it was issued for
+    let cursor = query.cursor.as_deref().map(RequestCursor::decode).transpose()?;
+    if let Some(cursor) = &cursor {
+        order = cursor.order;
+    }
+
     // Build filter for outlet-postgres - always filter to /ai/ paths only
     let mut filter = RequestFilter {
         uri_pattern: Some("/ai/%".to_string()), // Only AI endpoint requests
         limit: Some(limit),
-        offset: Some(offset),
-        order_by_timestamp_desc: query.order_desc.unwrap_or(true),
+        offset: Some(if cursor.is_some() { 0 } else { offset }),
+        order_by_timestamp_desc: order == SortOrder::Desc,
         ..Default::default()
     };
 
@@ -183,18 +298,147 @@ pub async fn list_requests(
         filter.uri_pattern = Some(format!("/ai/{uri_pattern}"));
     }
 
-    // Query the outlet-postgres repository
-    let outlet_pairs = repository.query(filter).await.map_err(|e| {
+    // Query the outlet-postgres repository, narrowed to the requests matching a body search, in
+    // a conversation or carrying tags
+    let query_failed = |e: &dyn std::fmt::Display| {
         error!("Failed to query requests: {}", e);
         Error::Internal {
             operation: "Failed to query requests".to_string(),
         }
-    })?;
+    };
+    let search = query.search.as_deref().map(str::trim).filter(|search| !search.is_empty());
+    let tags = query
+        .tags
+        .as_deref()
+        .map(parse_tag_filter)
+        .transpose()
+        .map_err(|message| Error::BadRequest { message })?;
+    let mut outlet_pairs = match (search, query.conversation_id.as_deref(), &tags) {
+        (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+            return Err(Error::BadRequest {
+                message: "search cannot be combined with conversation_id or tags".to_string(),
+            });
+        }
+        (Some(search), None, None) => {
+            let keys = search_requests(outlet_pool, search, &filter, cursor.as_ref())
+                .await
+                .map_err(|e| query_failed(&e))?;
+            fetch_pairs(&repository, keys).await.map_err(|e| query_failed(&e))?
+        }
+        (None, conversation_id, Some(tags)) => {
+            let keys = tagged_requests(&state.db, tags, conversation_id, &filter, cursor.as_ref())
+                .await
+                .map_err(|e| query_failed(&e))?;
+            fetch_pairs(&repository, keys).await.map_err(|e| query_failed(&e))?
+        }
+        (None, Some(conversation_id), None) => {
+            let keys = conversation_requests(&state.db, conversation_id, &filter, cursor.as_ref())
+                .await
+                .map_err(|e| query_failed(&e))?;
+            fetch_pairs(&repository, keys).await.map_err(|e| query_failed(&e))?
+        }
+        (None, None, None) => match &cursor {
+            Some(cursor) => {
+                let keys = requests_after(outlet_pool, &filter, cursor).await.map_err(|e| query_failed(&e))?;
+                fetch_pairs(&repository, keys).await.map_err(|e| query_failed(&e))?
+            }
+            None => repository.query(filter).await.map_err(|e| query_failed(&e))?,
+        },
+    };
+    if let Some(body_storage) = &state.body_storage {
+        load_stored_bodies(&mut outlet_pairs, body_storage).await;
+    }
+
+    let next_cursor = RequestCursor::next(&outlet_pairs, limit, (RequestSortField::Timestamp, order), |pair| {
+        (Some(timestamp_value(pair.request.timestamp)), pair.request.id)
+    });
 
     // Convert outlet-postgres types to API types
-    let api_pairs = convert_outlet_pairs_to_api(outlet_pairs);
+    let api_pairs = convert_pairs_with_costs(&state.db, outlet_pairs).await?;
+
+    Ok(Json(ListRequestsResponse {
+        requests: api_pairs,
+        next_cursor,
+    }))
+}
+
+/// Get a single HTTP request and its response
+///
+/// Bodies archived to object storage are fetched from the archive, so the request looks the same
+/// wherever its bodies are kept. `body_source` says where they were read from, and
+/// `archive_fetch_ms` how long the archive took to return them.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/{id}",
+    params(
+        ("id" = i64, Path, description = "ID of the logged request"),
+    ),
+    responses(
+        (status = 200, description = "The request and its response", body = RequestDetailResponse),
+        (status = 404, description = "Request not found, or request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state), err)]
+pub async fn get_request(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Requests, operation::ReadAll>,
+) -> Result<Json<RequestDetailResponse>, Error> {
+    let outlet_pool = state.outlet_db.as_ref().ok_or_else(|| {
+        debug!("Request logging is not enabled");
+        Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        }
+    })?;
+    let not_found = || Error::NotFound {
+        resource: "Request".to_string(),
+        id: id.to_string(),
+    };
+
+    // outlet-postgres filters by correlation ID rather than row ID, so look that up first
+    let correlation_id: Option<i64> = sqlx::query_scalar("SELECT correlation_id FROM http_requests WHERE id = $1 AND uri LIKE '/ai/%'")
+        .bind(id)
+        .fetch_optional(outlet_pool)
+        .await
+        .map_err(DbError::from)?;
+    let correlation_id = correlation_id.ok_or_else(not_found)?;
+
+    let repository: RequestRepository<AiRequest, AiResponse> = RequestRepository::new(outlet_pool.clone());
+    let filter = RequestFilter {
+        correlation_id: Some(correlation_id),
+        uri_pattern: Some("/ai/%".to_string()),
+        ..Default::default()
+    };
+    let outlet_pairs = repository.query(filter).await.map_err(|e| {
+        error!("Failed to query request {}: {}", id, e);
+        Error::Internal {
+            operation: "Failed to query request".to_string(),
+        }
+    })?;
+    // Correlation IDs are only unique per instance
+    let mut outlet_pairs: Vec<_> = outlet_pairs.into_iter().filter(|pair| pair.request.id == id).collect();
+
+    let (body_source, archive_fetch_ms) = match (&state.body_storage, outlet_pairs.first()) {
+        (Some(body_storage), Some(pair)) if has_stored_bodies(pair) => {
+            let started = std::time::Instant::now();
+            load_stored_bodies(&mut outlet_pairs, body_storage).await;
+            (BodySource::Archive, Some(started.elapsed().as_millis() as u64))
+        }
+        _ => (BodySource::Database, None),
+    };
 
-    Ok(Json(ListRequestsResponse { requests: api_pairs }))
+    let pair = convert_pairs_with_costs(&state.db, outlet_pairs)
+        .await?
+        .pop()
+        .ok_or_else(not_found)?;
+    Ok(Json(RequestDetailResponse {
+        pair,
+        body_source,
+        archive_fetch_ms,
+    }))
 }
 
 /// Get aggregated request metrics and analytics
@@ -239,6 +483,124 @@ pub async fn aggregate_requests(
     Ok(Json(response))
 }
 
+/// Number of encoded chunks buffered ahead of a slow export download
+const EXPORT_BUFFER_CHUNKS: usize = 256;
+
+/// Export request logs
+///
+/// Streams the requests in the time range, optionally filtered by user and model, as CSV (with
+/// a header row), JSON Lines or Parquet, oldest first, for ingestion into a data warehouse. Rows
+/// are read from the database as the download progresses, so exports of any size can be taken.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/export",
+    params(ExportRequestsQuery),
+    responses(
+        (status = 200, description = "Request log export", content_type = "text/csv"),
+        (status = 400, description = "Invalid query parameters, or Parquet requested from a build without the parquet feature"),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn export_requests(
+    Query(query): Query<ExportRequestsQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Requests, operation::ReadAll>,
+) -> Result<Response, Error> {
+    // If request logging is not enabled, return 404
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    let mut encoder = ExportEncoder::new(query.format).map_err(|e| Error::BadRequest { message: e.to_string() })?;
+    let (content_type, extension) = encoder.content_type();
+
+    // Encode rows in a background task, which stops once the client disconnects
+    let (tx, rx) = mpsc::channel::<anyhow::Result<Bytes>>(EXPORT_BUFFER_CHUNKS);
+    tokio::spawn(async move {
+        if let Some(start) = encoder.start() {
+            if tx.send(Ok(start)).await.is_err() {
+                return;
+            }
+        }
+        let mut rows = stream_requests_for_export(
+            &state.db,
+            query.timestamp_after,
+            query.timestamp_before,
+            query.user_id,
+            query.model.as_deref(),
+        );
+        while let Some(row) = rows.next().await {
+            let chunk = row.map_err(anyhow::Error::from).and_then(|record| encoder.encode(record));
+            if let Err(e) = &chunk {
+                error!("Failed to export requests: {}", e);
+            }
+            let failed = chunk.is_err();
+            let Some(chunk) = chunk.transpose() else { continue };
+            if tx.send(chunk).await.is_err() || failed {
+                return;
+            }
+        }
+        match encoder.finish() {
+            Ok(Some(end)) => {
+                let _ = tx.send(Ok(end)).await;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Failed to export requests: {}", e);
+                let _ = tx.send(Err(e)).await;
+            }
+        }
+    });
+    let body = Body::from_stream(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }));
+
+    let filename = format!("requests-{}.{extension}", Utc::now().format("%Y%m%dT%H%M%SZ"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// List exported ranges of the request log
+///
+/// With the export retention action, days past the retention window are uploaded to object
+/// storage and deleted from the request log. This lists the exported objects and the range of
+/// the log each covers, oldest first.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/archives",
+    params(ListArchivesQuery),
+    responses(
+        (status = 200, description = "Exported request log ranges", body = [RequestLogArchive]),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn list_request_archives(
+    Query(query): Query<ListArchivesQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Requests, operation::ReadAll>,
+) -> Result<Json<Vec<RequestLogArchive>>, Error> {
+    let archives = list_archives(&state.db, query.start_date, query.end_date)
+        .await
+        .map_err(DbError::from)?;
+
+    Ok(Json(archives))
+}
+
 /// Query parameters for aggregate by user
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct AggregateByUserQuery {
@@ -294,126 +656,1238 @@ pub async fn aggregate_by_user(
     Ok(Json(usage_data))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{api::models::users::Role, test_utils::*};
-    use chrono::{Duration, Utc};
-    use serde_json::json;
-    use sqlx::{ConnectOptions, PgPool};
+/// Query parameters for aggregate by group
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AggregateByGroupQuery {
+    /// Filter by specific model alias
+    pub model: Option<String>,
+    /// Start date for usage data (defaults to 30 days ago)
+    pub start_date: Option<DateTime<Utc>>,
+    /// End date for usage data (defaults to now)
+    pub end_date: Option<DateTime<Utc>>,
+}
 
-    // Helper function to insert test analytics data for aggregate tests
-    async fn insert_test_analytics_data(
-        pool: &PgPool,
-        timestamp: chrono::DateTime<chrono::Utc>,
-        model: &str,
-        status_code: i32,
-        duration_ms: f64,
-        prompt_tokens: i64,
-        completion_tokens: i64,
-    ) {
-        use uuid::Uuid;
+/// Get spend grouped by group and model
+///
+/// Returns a chargeback report allocating request cost to groups for the specified time range.
+/// A user's spend is split evenly between the groups they belong to.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/aggregate-by-group",
+    params(AggregateByGroupQuery),
+    responses(
+        (status = 200, description = "Spend per group per model", body = GroupUsageResponse),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn aggregate_by_group(
+    Query(query): Query<AggregateByGroupQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<GroupUsageResponse>, Error> {
+    // If request logging is not enabled, return 404
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
 
-        sqlx::query!(
-            r#"
-            INSERT INTO http_analytics (
-                instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms, 
-                model, prompt_tokens, completion_tokens, total_tokens
-            ) VALUES ($1, $2, $3, '/ai/chat/completions', 'POST', $4, $5, $6, $7, $8, $9)
-            "#,
-            Uuid::new_v4(),
-            1i64,
-            timestamp,
-            status_code,
-            duration_ms as i64,
-            model,
-            prompt_tokens,
-            completion_tokens,
-            prompt_tokens + completion_tokens
-        )
-        .execute(pool)
-        .await
-        .expect("Failed to insert test analytics data");
+    // Set default date range
+    let end_date = query.end_date.unwrap_or_else(Utc::now);
+    let start_date = query.start_date.unwrap_or_else(|| end_date - Duration::days(30));
+
+    let usage = get_group_model_usage(&state.db, start_date, end_date, query.model.as_deref()).await?;
+
+    Ok(Json(usage))
+}
+
+/// Query parameters for aggregate by user attribute
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AggregateByAttributeQuery {
+    /// User attribute to allocate spend by, e.g. `cost_center`
+    pub attribute: String,
+    /// Filter by specific model alias
+    pub model: Option<String>,
+    /// Start date for usage data (defaults to 30 days ago)
+    pub start_date: Option<DateTime<Utc>>,
+    /// End date for usage data (defaults to now)
+    pub end_date: Option<DateTime<Utc>>,
+}
+
+/// Get spend grouped by user attribute and model
+///
+/// Returns a chargeback report allocating request cost to the values of a user attribute (e.g.
+/// cost center) for the specified time range.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/aggregate-by-attribute",
+    params(AggregateByAttributeQuery),
+    responses(
+        (status = 200, description = "Spend per attribute value per model", body = AttributeUsageResponse),
+        (status = 400, description = "Invalid query parameters"),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn aggregate_by_attribute(
+    Query(query): Query<AggregateByAttributeQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<AttributeUsageResponse>, Error> {
+    // If request logging is not enabled, return 404
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    let attribute = query.attribute.trim();
+    if attribute.is_empty() {
+        return Err(Error::BadRequest {
+            message: "attribute must not be empty".to_string(),
+        });
+    }
+
+    // Set default date range
+    let end_date = query.end_date.unwrap_or_else(Utc::now);
+    let start_date = query.start_date.unwrap_or_else(|| end_date - Duration::days(30));
+
+    let usage = get_attribute_model_usage(&state.db, attribute, start_date, end_date, query.model.as_deref()).await?;
+
+    Ok(Json(usage))
+}
+
+/// Query parameters for the error breakdown
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ErrorBreakdownQuery {
+    /// Filter by specific model alias
+    pub model: Option<String>,
+    /// Start date (defaults to 24 hours ago)
+    pub start_date: Option<DateTime<Utc>>,
+    /// End date (defaults to now)
+    pub end_date: Option<DateTime<Utc>>,
+    /// Maximum number of users to return (default: 20, max: 1000)
+    pub user_limit: Option<i64>,
+}
+
+/// Get failed requests broken down for incident triage
+///
+/// Breaks the 4xx and 5xx responses in the specified time range (the last 24 hours by default)
+/// down by status code, kind of failure (timeout, connect, rate limited, other server or client
+/// errors), deployment and user.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/errors",
+    params(ErrorBreakdownQuery),
+    responses(
+        (status = 200, description = "Breakdown of failed requests", body = ErrorBreakdownResponse),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn error_breakdown(
+    Query(query): Query<ErrorBreakdownQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<ErrorBreakdownResponse>, Error> {
+    // If request logging is not enabled, return 404
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    let end_date = query.end_date.unwrap_or_else(Utc::now);
+    let start_date = query.start_date.unwrap_or_else(|| end_date - Duration::hours(24));
+    let user_limit = query.user_limit.unwrap_or(20).clamp(1, 1000);
+
+    let breakdown = get_error_breakdown(&state.db, start_date, end_date, query.model.as_deref(), user_limit).await?;
+
+    Ok(Json(breakdown))
+}
+
+/// Most buckets a usage time series can have
+const MAX_TIME_SERIES_POINTS: i64 = 10_000;
+
+/// Get usage over time
+///
+/// Returns requests, tokens or cost in fixed-width buckets over the specified time range (the
+/// last 7 days by default), with empty buckets included, ready to chart.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/timeseries",
+    params(UsageTimeSeriesQuery),
+    responses(
+        (status = 200, description = "Usage per bucket", body = UsageTimeSeriesResponse),
+        (status = 400, description = "Invalid bucket or time range"),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn usage_time_series(
+    Query(query): Query<UsageTimeSeriesQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<UsageTimeSeriesResponse>, Error> {
+    // If request logging is not enabled, return 404
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    let bucket = match query.bucket.as_deref() {
+        Some(bucket) => humantime::parse_duration(bucket)
+            .ok()
+            .and_then(|bucket| Duration::from_std(bucket).ok())
+            .filter(|bucket| bucket.num_seconds() >= 60 && bucket.subsec_nanos() == 0)
+            .ok_or_else(|| Error::BadRequest {
+                message: format!("Invalid bucket '{bucket}': expected a whole number of seconds of at least a minute, e.g. 10m or 1h"),
+            })?,
+        None => Duration::hours(1),
+    };
+    let end_date = query.end_date.unwrap_or_else(Utc::now);
+    let start_date = query.start_date.unwrap_or_else(|| end_date - Duration::days(7));
+    if start_date > end_date {
+        return Err(Error::BadRequest {
+            message: "start_date must be before end_date".to_string(),
+        });
+    }
+    if (end_date - start_date).num_seconds() / bucket.num_seconds() >= MAX_TIME_SERIES_POINTS {
+        return Err(Error::BadRequest {
+            message: format!("The time range spans more than {MAX_TIME_SERIES_POINTS} buckets; use a wider bucket"),
+        });
+    }
+
+    let series = get_usage_time_series(&state.db, start_date, end_date, bucket, query.metric, query.model.as_deref()).await?;
+
+    Ok(Json(series))
+}
+
+/// Query parameters for aggregate by model
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AggregateByModelQuery {
+    /// Start date for usage data (defaults to 30 days ago)
+    pub start_date: Option<DateTime<Utc>>,
+    /// End date for usage data (defaults to now)
+    pub end_date: Option<DateTime<Utc>>,
+}
+
+/// Get utilization grouped by deployment and by endpoint
+///
+/// Returns request counts, tokens, error rates and latency for each deployment and for each
+/// inference endpoint in the specified time range, to compare model and provider utilization.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/aggregate-by-model",
+    params(AggregateByModelQuery),
+    responses(
+        (status = 200, description = "Utilization per deployment and per endpoint", body = ModelUsageResponse),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn aggregate_by_model(
+    Query(query): Query<AggregateByModelQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<ModelUsageResponse>, Error> {
+    // If request logging is not enabled, return 404
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    let end_date = query.end_date.unwrap_or_else(Utc::now);
+    let start_date = query.start_date.unwrap_or_else(|| end_date - Duration::days(30));
+
+    let usage = get_model_endpoint_usage(&state.db, start_date, end_date).await?;
+
+    Ok(Json(usage))
+}
+
+/// Query parameters for aggregate by conversation
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AggregateByConversationQuery {
+    /// Filter by specific model alias
+    pub model: Option<String>,
+    /// Start date for usage data (defaults to 30 days ago)
+    pub start_date: Option<DateTime<Utc>>,
+    /// End date for usage data (defaults to now)
+    pub end_date: Option<DateTime<Utc>>,
+    /// Maximum number of conversations to return (default: 100, max: 1000)
+    pub limit: Option<i64>,
+}
+
+/// Get usage grouped by conversation
+///
+/// Returns the turns, tokens and cost of each conversation in the specified time range, most
+/// turns first. Requests are threaded into conversations by the `x-doubleword-conversation-id`
+/// request header, or for chat requests without one, by their opening messages.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/aggregate-by-conversation",
+    params(AggregateByConversationQuery),
+    responses(
+        (status = 200, description = "Usage per conversation", body = ConversationUsageResponse),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn aggregate_by_conversation(
+    Query(query): Query<AggregateByConversationQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<ConversationUsageResponse>, Error> {
+    // If request logging is not enabled, return 404
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    let end_date = query.end_date.unwrap_or_else(Utc::now);
+    let start_date = query.start_date.unwrap_or_else(|| end_date - Duration::days(30));
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+    let usage = get_conversation_usage(&state.db, start_date, end_date, query.model.as_deref(), limit).await?;
+
+    Ok(Json(usage))
+}
+
+/// Query parameters for aggregate by tag
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AggregateByTagQuery {
+    /// Tag key to group usage by
+    pub key: String,
+    /// Filter by specific model alias
+    pub model: Option<String>,
+    /// Start date for usage data (defaults to 30 days ago)
+    pub start_date: Option<DateTime<Utc>>,
+    /// End date for usage data (defaults to now)
+    pub end_date: Option<DateTime<Utc>>,
+    /// Maximum number of tag values to return (default: 100, max: 1000)
+    pub limit: Option<i64>,
+}
+
+/// Get usage grouped by the values of a tag
+///
+/// Returns the requests, users, tokens and cost for each value of the tag `key` in the specified
+/// time range, busiest values first. Requests are tagged with the `x-doubleword-tags` request
+/// header, e.g. `x-doubleword-tags: feature=search, project=atlas`.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/aggregate-by-tag",
+    params(AggregateByTagQuery),
+    responses(
+        (status = 200, description = "Usage per tag value", body = TagUsageResponse),
+        (status = 400, description = "Invalid tag key"),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn aggregate_by_tag(
+    Query(query): Query<AggregateByTagQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<TagUsageResponse>, Error> {
+    // If request logging is not enabled, return 404
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    let key = query.key.trim();
+    if key.is_empty() {
+        return Err(Error::BadRequest {
+            message: "key must not be empty".to_string(),
+        });
+    }
+
+    let end_date = query.end_date.unwrap_or_else(Utc::now);
+    let start_date = query.start_date.unwrap_or_else(|| end_date - Duration::days(30));
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+    let usage = get_tag_usage(&state.db, key, start_date, end_date, query.model.as_deref(), limit).await?;
+
+    Ok(Json(usage))
+}
+
+/// Resolve the date range for embedding usage queries (defaults to the last 30 days)
+fn embedding_usage_range(query: &EmbeddingUsageQuery) -> (DateTime<Utc>, DateTime<Utc>) {
+    let end_date = query.end_date.unwrap_or_else(Utc::now);
+    let start_date = query.start_date.unwrap_or_else(|| end_date - Duration::days(30));
+    (start_date, end_date)
+}
+
+/// Get embedding vectors per day for each model
+///
+/// Token counts underrepresent embedding workloads, so this reports the number of vectors
+/// produced per model per day along with their average dimensionality.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/embeddings/aggregate-by-model",
+    params(EmbeddingUsageQuery),
+    responses(
+        (status = 200, description = "Daily embedding usage per model", body = EmbeddingUsageResponse),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn aggregate_embeddings_by_model(
+    Query(query): Query<EmbeddingUsageQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<EmbeddingUsageResponse>, Error> {
+    // If request logging is not enabled, return 404
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    let (start_date, end_date) = embedding_usage_range(&query);
+    let usage = get_embedding_usage_by_model(&state.db, start_date, end_date, query.model.as_deref()).await?;
+
+    Ok(Json(usage))
+}
+
+/// Get embedding vectors per day for each group
+///
+/// Requests are attributed to every group their user belongs to (including Everyone), so
+/// per-group figures can add up to more than the totals.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/embeddings/aggregate-by-group",
+    params(EmbeddingUsageQuery),
+    responses(
+        (status = 200, description = "Daily embedding usage per group", body = EmbeddingUsageResponse),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn aggregate_embeddings_by_group(
+    Query(query): Query<EmbeddingUsageQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<EmbeddingUsageResponse>, Error> {
+    // If request logging is not enabled, return 404
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    let (start_date, end_date) = embedding_usage_range(&query);
+    let usage = get_embedding_usage_by_group(&state.db, start_date, end_date, query.model.as_deref()).await?;
+
+    Ok(Json(usage))
+}
+
+/// Get PII classification rates per group
+///
+/// Returns how often logged prompts from each group contained coarse PII categories
+/// (email, phone, financial). Requires `enable_pii_classification`; only classified
+/// requests are counted.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/pii-by-group",
+    params(PiiStatsQuery),
+    responses(
+        (status = 200, description = "PII classification rates per group", body = PiiStatsResponse),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, query), err)]
+pub async fn pii_stats_by_group(
+    Query(query): Query<PiiStatsQuery>,
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::ReadAll>,
+) -> Result<Json<PiiStatsResponse>, Error> {
+    // If request logging is not enabled, return 404
+    if state.outlet_db.is_none() {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    let end_date = query.end_date.unwrap_or_else(Utc::now);
+    let start_date = query.start_date.unwrap_or_else(|| end_date - Duration::days(30));
+
+    let stats = get_pii_stats_by_group(&state.db, start_date, end_date).await?;
+
+    Ok(Json(stats))
+}
+
+/// Number of token backfills returned when listing
+const BACKFILL_LIST_LIMIT: i64 = 100;
+
+/// Start a token backfill
+///
+/// Fills in the token counts (and so the costs) of successful AI requests whose analytics were
+/// recorded without them, by parsing their logged bodies again. Usage reported by the upstream is
+/// used as is; otherwise tokens are estimated from the text of the request and response. Requests
+/// whose bodies are no longer in the log are skipped. The backfill runs in the background; poll it
+/// for progress. Only one backfill can run at a time.
+#[utoipa::path(
+    post,
+    path = "/admin/api/v1/requests/token-backfills",
+    request_body = TokenBackfillCreate,
+    responses(
+        (status = 202, description = "Backfill started", body = TokenBackfill),
+        (status = 400, description = "Invalid date range"),
+        (status = 404, description = "Request logging not enabled"),
+        (status = 409, description = "Another token backfill is already running"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state, permission, request), err)]
+pub async fn create_token_backfill(
+    State(state): State<AppState>,
+    permission: RequiresPermission<resource::Analytics, operation::SystemAccess>,
+    Json(request): Json<TokenBackfillCreate>,
+) -> Result<(StatusCode, Json<TokenBackfill>), Error> {
+    // If request logging is not enabled, return 404
+    let Some(outlet_pool) = state.outlet_db.clone() else {
+        debug!("Request logging is not enabled");
+        return Err(Error::NotFound {
+            id: "request_logging".to_string(),
+            resource: "Request logging is not enabled".to_string(),
+        });
+    };
+
+    if let (Some(start_date), Some(end_date)) = (request.start_date, request.end_date) {
+        if start_date > end_date {
+            return Err(Error::BadRequest {
+                message: "start_date must not be after end_date".to_string(),
+            });
+        }
+    }
+
+    let token_backfill = TokenBackfillManager::create(&state.db, permission.current_user.id, request.start_date, request.end_date).await?;
+    tokio::spawn(backfill::run(
+        state.db.clone(),
+        outlet_pool,
+        state.body_storage.clone(),
+        token_backfill.clone(),
+    ));
+
+    Ok((StatusCode::ACCEPTED, Json(token_backfill)))
+}
+
+/// List token backfills
+///
+/// Returns the most recent token backfills, newest first.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/token-backfills",
+    responses(
+        (status = 200, description = "List of token backfills", body = Vec<TokenBackfill>),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state), err)]
+pub async fn list_token_backfills(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::SystemAccess>,
+) -> Result<Json<Vec<TokenBackfill>>, Error> {
+    let backfills = TokenBackfillManager::list(&state.db, BACKFILL_LIST_LIMIT).await?;
+    Ok(Json(backfills))
+}
+
+/// Get a token backfill
+///
+/// Returns a token backfill and its progress. The progress of a running backfill is saved after
+/// every batch of requests.
+#[utoipa::path(
+    get,
+    path = "/admin/api/v1/requests/token-backfills/{id}",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Token backfill ID to retrieve"),
+    ),
+    responses(
+        (status = 200, description = "Token backfill details", body = TokenBackfill),
+        (status = 404, description = "Token backfill not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state), err)]
+pub async fn get_token_backfill(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::SystemAccess>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<TokenBackfill>, Error> {
+    let token_backfill = TokenBackfillManager::get(&state.db, id).await?;
+    Ok(Json(token_backfill))
+}
+
+/// Stop a token backfill
+///
+/// Asks a running token backfill to stop after its current batch. Token counts already filled in
+/// are kept.
+#[utoipa::path(
+    post,
+    path = "/admin/api/v1/requests/token-backfills/{id}/stop",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Token backfill ID to stop"),
+    ),
+    responses(
+        (status = 202, description = "Stop requested", body = TokenBackfill),
+        (status = 404, description = "Token backfill not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    tag = "requests",
+)]
+#[instrument(skip(state), err)]
+pub async fn stop_token_backfill(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Analytics, operation::SystemAccess>,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<TokenBackfill>), Error> {
+    let token_backfill = TokenBackfillManager::request_stop(&state.db, id).await?;
+    Ok((StatusCode::ACCEPTED, Json(token_backfill)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::models::{requests::RequestExportRecord, users::Role},
+        test_utils::*,
+    };
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+    use sqlx::{ConnectOptions, PgPool};
+
+    // Helper function to insert test analytics data for aggregate tests
+    async fn insert_test_analytics_data(
+        pool: &PgPool,
+        timestamp: chrono::DateTime<chrono::Utc>,
+        model: &str,
+        status_code: i32,
+        duration_ms: f64,
+        prompt_tokens: i64,
+        completion_tokens: i64,
+    ) {
+        use uuid::Uuid;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO http_analytics (
+                instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms, 
+                model, prompt_tokens, completion_tokens, total_tokens
+            ) VALUES ($1, $2, $3, '/ai/chat/completions', 'POST', $4, $5, $6, $7, $8, $9)
+            "#,
+            Uuid::new_v4(),
+            1i64,
+            timestamp,
+            status_code,
+            duration_ms as i64,
+            model,
+            prompt_tokens,
+            completion_tokens,
+            prompt_tokens + completion_tokens
+        )
+        .execute(pool)
+        .await
+        .expect("Failed to insert test analytics data");
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_list_requests_outlet_disabled(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await; // Request logging disabled
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let response = app
+            .get("/admin/api/v1/requests")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+
+        // Should return 404 since request logging is disabled
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_list_requests_unauthorized(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), true).await; // Request logging enabled
+        let user = create_test_user(&pool, Role::StandardUser).await; // Non-admin user
+
+        let response = app
+            .get("/admin/api/v1/requests")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+
+        // Should be forbidden since user doesn't have Requests:Read permission
+        response.assert_status(axum::http::StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_get_request_outlet_disabled(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let response = app
+            .get("/admin/api/v1/requests/1")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+
+        // Non-admins can't read individual requests either
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let response = app
+            .get("/admin/api/v1/requests/1")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_aggregate_requests_outlet_disabled(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await; // Request logging disabled
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let response = app
+            .get("/admin/api/v1/requests/aggregate")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+
+        // Should return 404 since request logging is disabled
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_aggregate_requests_success(pool: PgPool) {
+        // Insert analytics data to test aggregate functionality
+        let base_time = Utc::now() - Duration::hours(1);
+        insert_test_analytics_data(&pool, base_time, "gpt-4", 200, 100.0, 50, 25).await;
+
+        // Create config with request logging enabled (like in main.rs test)
+        let mut config = create_test_config();
+        config.enable_request_logging = true;
+        config.database = crate::config::DatabaseConfig::External {
+            url: pool.connect_options().to_url_lossy().to_string(),
+        };
+
+        // Build router with request logging enabled
+        let mut app_state = crate::AppState::builder().db(pool.clone()).config(config).build();
+        let onwards_router = axum::Router::new();
+        let router = crate::build_router(&mut app_state, onwards_router)
+            .await
+            .expect("Failed to build router");
+
+        let server = axum_test::TestServer::new(router).expect("Failed to create test server");
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let response = server
+            .get("/admin/api/v1/requests/aggregate")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+
+        response.assert_status_ok();
+        let aggregate_response: RequestsAggregateResponse = response.json();
+        assert_eq!(aggregate_response.total_requests, 1);
+        assert!(aggregate_response.model.is_none()); // No model filter applied
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_list_requests_search(pool: PgPool) {
+        let mut config = create_test_config();
+        config.enable_request_logging = true;
+        let mut app_state = crate::AppState::builder().db(pool.clone()).config(config).build();
+        let router = crate::build_router(&mut app_state, axum::Router::new())
+            .await
+            .expect("Failed to build router");
+        let server = axum_test::TestServer::new(router).expect("Failed to create test server");
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let bodies = [
+            (
+                "Summarise the Falcon incident report",
+                "The Falcon outage was caused by a config push",
+            ),
+            ("Write a haiku about autumn", "Leaves fall on the pond"),
+            ("Translate falcon into French", "Faucon"),
+        ];
+        let instance_id = uuid::Uuid::new_v4();
+        for (correlation_id, (request, response)) in bodies.into_iter().enumerate() {
+            let timestamp = Utc::now() - Duration::minutes(10 - correlation_id as i64);
+            sqlx::query(
+                "INSERT INTO outlet.http_requests (instance_id, correlation_id, timestamp, method, uri, headers, body, body_parsed)
+                 VALUES ($1, $2, $3, 'POST', '/ai/v1/chat/completions', '{}', to_jsonb($4::text), false)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id as i64)
+            .bind(timestamp)
+            .bind(request)
+            .execute(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO outlet.http_responses
+                     (instance_id, correlation_id, timestamp, status_code, headers, body, body_parsed, duration_ms, duration_to_first_byte_ms)
+                 VALUES ($1, $2, $3, 200, '{}', to_jsonb($4::text), false, 100, 10)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id as i64)
+            .bind(timestamp)
+            .bind(response)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let search = |query: &'static str| {
+            server
+                .get("/admin/api/v1/requests")
+                .add_query_param("search", query)
+                .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+        };
+        let request_texts = |response: axum_test::TestResponse| -> Vec<String> {
+            response.assert_status_ok();
+            let list: ListRequestsResponse = response.json();
+            list.requests
+                .into_iter()
+                .map(|pair| match pair.request.body {
+                    Some(ApiAiRequest::Other(serde_json::Value::String(text))) => text,
+                    body => panic!("Unexpected request body: {body:?}"),
+                })
+                .collect()
+        };
+
+        // Matches in request and response bodies are found regardless of case, newest first
+        assert_eq!(
+            request_texts(search("falcon").await),
+            vec!["Translate falcon into French", "Summarise the Falcon incident report"]
+        );
+        assert_eq!(request_texts(search("pond").await), vec!["Write a haiku about autumn"]);
+        assert_eq!(
+            request_texts(search("falcon -french").await),
+            vec!["Summarise the Falcon incident report"]
+        );
+        assert!(request_texts(search("\"config falcon\"").await).is_empty());
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_list_requests_with_cursor(pool: PgPool) {
+        let mut config = create_test_config();
+        config.enable_request_logging = true;
+        let mut app_state = crate::AppState::builder().db(pool.clone()).config(config).build();
+        let router = crate::build_router(&mut app_state, axum::Router::new())
+            .await
+            .expect("Failed to build router");
+        let server = axum_test::TestServer::new(router).expect("Failed to create test server");
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let instance_id = uuid::Uuid::new_v4();
+        let base_time = Utc::now() - Duration::minutes(10);
+        let insert = |correlation_id: i64, timestamp: DateTime<Utc>| {
+            sqlx::query(
+                "INSERT INTO outlet.http_requests (instance_id, correlation_id, timestamp, method, uri, headers, body, body_parsed)
+                 VALUES ($1, $2, $3, 'POST', '/ai/v1/chat/completions', '{}', to_jsonb($4::text), false)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id)
+            .bind(timestamp)
+            .bind(format!("request {correlation_id}"))
+            .execute(&pool)
+        };
+        // Requests 1 and 2 share a timestamp, so only their IDs order them
+        for (correlation_id, minutes) in [(0, 0), (1, 1), (2, 1), (3, 2), (4, 3)] {
+            insert(correlation_id, base_time + Duration::minutes(minutes)).await.unwrap();
+        }
+
+        let mut texts = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut request = server
+                .get("/admin/api/v1/requests")
+                .add_query_param("limit", 2)
+                .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1);
+            if let Some(cursor) = &cursor {
+                request = request.add_query_param("cursor", cursor);
+            }
+            let response = request.await;
+            response.assert_status_ok();
+            let list: ListRequestsResponse = response.json();
+            texts.extend(list.requests.into_iter().map(|pair| match pair.request.body {
+                Some(ApiAiRequest::Other(serde_json::Value::String(text))) => text,
+                body => panic!("Unexpected request body: {body:?}"),
+            }));
+
+            // Requests logged while paging newest first don't shift later pages
+            if cursor.is_none() {
+                insert(5, Utc::now()).await.unwrap();
+            }
+            match list.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(texts, vec!["request 4", "request 3", "request 2", "request 1", "request 0"]);
+
+        let response = server
+            .get("/admin/api/v1/requests")
+            .add_query_param("cursor", "garbage")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
     }
 
     #[sqlx::test]
     #[test_log::test]
-    async fn test_list_requests_outlet_disabled(pool: PgPool) {
-        let (app, _) = create_test_app(pool.clone(), false).await; // Request logging disabled
+    async fn test_conversations(pool: PgPool) {
+        let mut config = create_test_config();
+        config.enable_request_logging = true;
+        let mut app_state = crate::AppState::builder().db(pool.clone()).config(config).build();
+        let router = crate::build_router(&mut app_state, axum::Router::new())
+            .await
+            .expect("Failed to build router");
+        let server = axum_test::TestServer::new(router).expect("Failed to create test server");
         let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
 
-        let response = app
+        let turns = [
+            ("support-1", "gpt-4", "My order is late"),
+            ("support-2", "gpt-4", "How do I reset my password?"),
+            ("support-1", "gpt-4", "It was due on Monday"),
+            ("support-1", "claude-3", "Thanks"),
+        ];
+        let instance_id = uuid::Uuid::new_v4();
+        for (correlation_id, (conversation_id, model, request)) in turns.into_iter().enumerate() {
+            let timestamp = Utc::now() - Duration::minutes(10 - correlation_id as i64);
+            sqlx::query(
+                "INSERT INTO outlet.http_requests (instance_id, correlation_id, timestamp, method, uri, headers, body, body_parsed)
+                 VALUES ($1, $2, $3, 'POST', '/ai/v1/chat/completions', '{}', to_jsonb($4::text), false)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id as i64)
+            .bind(timestamp)
+            .bind(request)
+            .execute(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO http_analytics (
+                     instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms,
+                     model, prompt_tokens, completion_tokens, total_tokens, conversation_id
+                 ) VALUES ($1, $2, $3, '/ai/v1/chat/completions', 'POST', 200, 100, $4, 10, 5, 15, $5)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id as i64)
+            .bind(timestamp)
+            .bind(model)
+            .bind(conversation_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        // Listing a conversation returns its turns in order
+        let response = server
             .get("/admin/api/v1/requests")
+            .add_query_param("conversation_id", "support-1")
+            .add_query_param("order_desc", "false")
             .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
             .await;
+        response.assert_status_ok();
+        let list: ListRequestsResponse = response.json();
+        let requests: Vec<_> = list
+            .requests
+            .into_iter()
+            .map(|pair| match pair.request.body {
+                Some(ApiAiRequest::Other(serde_json::Value::String(text))) => text,
+                body => panic!("Unexpected request body: {body:?}"),
+            })
+            .collect();
+        assert_eq!(requests, vec!["My order is late", "It was due on Monday", "Thanks"]);
 
-        // Should return 404 since request logging is disabled
-        response.assert_status(axum::http::StatusCode::NOT_FOUND);
-    }
-
-    #[sqlx::test]
-    #[test_log::test]
-    async fn test_list_requests_unauthorized(pool: PgPool) {
-        let (app, _) = create_test_app(pool.clone(), true).await; // Request logging enabled
-        let user = create_test_user(&pool, Role::StandardUser).await; // Non-admin user
-
-        let response = app
+        let response = server
             .get("/admin/api/v1/requests")
-            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .add_query_param("conversation_id", "support-1")
+            .add_query_param("search", "order")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
             .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
 
-        // Should be forbidden since user doesn't have Requests:Read permission
-        response.assert_status(axum::http::StatusCode::FORBIDDEN);
+        // The aggregate counts turns per conversation, busiest first
+        let response = server
+            .get("/admin/api/v1/requests/aggregate-by-conversation")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let usage: ConversationUsageResponse = response.json();
+        assert_eq!(usage.total_conversations, 2);
+        assert_eq!(usage.total_turns, 4);
+        assert_eq!(usage.avg_turns, 2.0);
+        let busiest = &usage.conversations[0];
+        assert_eq!(busiest.conversation_id, "support-1");
+        assert_eq!(busiest.turns, 3);
+        assert_eq!(busiest.models, vec!["claude-3", "gpt-4"]);
+        assert_eq!(busiest.input_tokens, 30);
+        assert_eq!(usage.conversations[1].turns, 1);
+
+        let response = server
+            .get("/admin/api/v1/requests/aggregate-by-conversation")
+            .add_query_param("model", "claude-3")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        let usage: ConversationUsageResponse = response.json();
+        assert_eq!(usage.total_turns, 1);
+        assert_eq!(usage.conversations[0].conversation_id, "support-1");
     }
 
     #[sqlx::test]
     #[test_log::test]
-    async fn test_aggregate_requests_outlet_disabled(pool: PgPool) {
-        let (app, _) = create_test_app(pool.clone(), false).await; // Request logging disabled
+    async fn test_tags(pool: PgPool) {
+        let mut config = create_test_config();
+        config.enable_request_logging = true;
+        let mut app_state = crate::AppState::builder().db(pool.clone()).config(config).build();
+        let router = crate::build_router(&mut app_state, axum::Router::new())
+            .await
+            .expect("Failed to build router");
+        let server = axum_test::TestServer::new(router).expect("Failed to create test server");
         let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
 
-        let response = app
-            .get("/admin/api/v1/requests/aggregate")
+        let requests = [
+            (Some(serde_json::json!({"feature": "search", "project": "atlas"})), "Find flights"),
+            (Some(serde_json::json!({"feature": "search", "project": "borealis"})), "Find hotels"),
+            (Some(serde_json::json!({"feature": "summaries"})), "Summarise this"),
+            (None, "Untagged"),
+        ];
+        let instance_id = uuid::Uuid::new_v4();
+        for (correlation_id, (tags, request)) in requests.into_iter().enumerate() {
+            let timestamp = Utc::now() - Duration::minutes(10 - correlation_id as i64);
+            sqlx::query(
+                "INSERT INTO outlet.http_requests (instance_id, correlation_id, timestamp, method, uri, headers, body, body_parsed)
+                 VALUES ($1, $2, $3, 'POST', '/ai/v1/chat/completions', '{}', to_jsonb($4::text), false)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id as i64)
+            .bind(timestamp)
+            .bind(request)
+            .execute(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO http_analytics (
+                     instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms,
+                     model, prompt_tokens, completion_tokens, total_tokens, tags
+                 ) VALUES ($1, $2, $3, '/ai/v1/chat/completions', 'POST', 200, 100, 'gpt-4', 10, 5, 15, $4)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id as i64)
+            .bind(timestamp)
+            .bind(tags)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let list = |tags: &'static str| {
+            server
+                .get("/admin/api/v1/requests")
+                .add_query_param("tags", tags)
+                .add_query_param("order_desc", "false")
+                .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+        };
+        let request_texts = |list: ListRequestsResponse| -> Vec<String> {
+            list.requests
+                .into_iter()
+                .map(|pair| match pair.request.body {
+                    Some(ApiAiRequest::Other(serde_json::Value::String(text))) => text,
+                    body => panic!("Unexpected request body: {body:?}"),
+                })
+                .collect()
+        };
+
+        // Requests must carry every tag in the filter
+        let response = list("feature=search").await;
+        response.assert_status_ok();
+        assert_eq!(request_texts(response.json()), vec!["Find flights", "Find hotels"]);
+        assert_eq!(
+            request_texts(list("feature=search,project=borealis").await.json()),
+            vec!["Find hotels"]
+        );
+        assert!(request_texts(list("feature=chat").await.json()).is_empty());
+        list("feature").await.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        // Usage is grouped by the values of a tag key
+        let response = server
+            .get("/admin/api/v1/requests/aggregate-by-tag")
+            .add_query_param("key", "feature")
             .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
             .await;
+        response.assert_status_ok();
+        let usage: TagUsageResponse = response.json();
+        assert_eq!(usage.key, "feature");
+        assert_eq!(usage.tagged_requests, 3);
+        assert_eq!(usage.untagged_requests, 1);
+        let values: Vec<_> = usage.values.iter().map(|value| (value.value.as_str(), value.requests)).collect();
+        assert_eq!(values, vec![("search", 2), ("summaries", 1)]);
+        assert_eq!(usage.values[0].input_tokens, 20);
 
-        // Should return 404 since request logging is disabled
-        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+        let response = server
+            .get("/admin/api/v1/requests/aggregate-by-tag")
+            .add_query_param("key", "project")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        let usage: TagUsageResponse = response.json();
+        assert_eq!(usage.tagged_requests, 2);
+        assert_eq!(usage.untagged_requests, 2);
     }
 
     #[sqlx::test]
     #[test_log::test]
-    async fn test_aggregate_requests_success(pool: PgPool) {
-        // Insert analytics data to test aggregate functionality
+    async fn test_export_requests(pool: PgPool) {
+        let user = create_test_user(&pool, Role::StandardUser).await;
         let base_time = Utc::now() - Duration::hours(1);
-        insert_test_analytics_data(&pool, base_time, "gpt-4", 200, 100.0, 50, 25).await;
+        insert_test_analytics_data(&pool, base_time - Duration::days(2), "gpt-4", 200, 90.0, 10, 5).await;
+        insert_test_analytics_data(&pool, base_time, "claude-3", 200, 150.0, 75, 35).await;
+        for (minutes, model) in [(1, "gpt-4"), (2, "gpt-4, \"turbo\"")] {
+            insert_test_analytics_data(&pool, base_time + Duration::minutes(minutes), model, 200, 100.0, 50, 25).await;
+        }
+        sqlx::query("UPDATE http_analytics SET user_id = $1, user_email = $2 WHERE model LIKE 'gpt-4%'")
+            .bind(user.id)
+            .bind(&user.email)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE users SET attributes = '{\"cost_center\": \"CC-100\"}' WHERE id = $1")
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
 
-        // Create config with request logging enabled (like in main.rs test)
         let mut config = create_test_config();
         config.enable_request_logging = true;
         config.database = crate::config::DatabaseConfig::External {
             url: pool.connect_options().to_url_lossy().to_string(),
         };
-
-        // Build router with request logging enabled
         let mut app_state = crate::AppState::builder().db(pool.clone()).config(config).build();
-        let onwards_router = axum::Router::new();
-        let router = crate::build_router(&mut app_state, onwards_router)
+        let router = crate::build_router(&mut app_state, axum::Router::new())
             .await
             .expect("Failed to build router");
-
         let server = axum_test::TestServer::new(router).expect("Failed to create test server");
         let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let after = (base_time - Duration::hours(1)).to_rfc3339().replace('+', "%2B");
 
         let response = server
-            .get("/admin/api/v1/requests/aggregate")
+            .get(&format!(
+                "/admin/api/v1/requests/export?user_id={}&timestamp_after={after}",
+                user.id
+            ))
             .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
             .await;
+        response.assert_status_ok();
+        response.assert_header("content-type", "text/csv");
+        let csv = response.text();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], RequestExportRecord::CSV_HEADER.trim_end());
+        let fields: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(fields[4], "gpt-4");
+        assert_eq!(fields[11], "false");
+        assert_eq!(fields[13], user.id.to_string());
+        assert!(lines[1].ends_with(",\"{\"\"cost_center\"\":\"\"CC-100\"\"}\""), "{}", lines[1]);
+        assert!(lines[2].contains(",\"gpt-4, \"\"turbo\"\"\","), "{}", lines[2]);
 
+        let response = server
+            .get("/admin/api/v1/requests/export?format=jsonl&model=claude-3")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
         response.assert_status_ok();
-        let aggregate_response: RequestsAggregateResponse = response.json();
-        assert_eq!(aggregate_response.total_requests, 1);
-        assert!(aggregate_response.model.is_none()); // No model filter applied
+        let records: Vec<serde_json::Value> = response.text().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["model"], "claude-3");
+        assert_eq!(records[0]["total_tokens"], 110);
+
+        let response = server
+            .get("/admin/api/v1/requests/export?format=parquet&model=claude-3")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        #[cfg(not(feature = "parquet"))]
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        #[cfg(feature = "parquet")]
+        {
+            use arrow_array::{Array, Int64Array, StringArray};
+            use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+            response.assert_status_ok();
+            response.assert_header("content-type", "application/vnd.apache.parquet");
+            let batches: Vec<_> = ParquetRecordBatchReaderBuilder::try_new(response.into_bytes())
+                .unwrap()
+                .build()
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 1);
+            let column = |name| batches[0].column_by_name(name).unwrap().clone();
+            let models = column("model");
+            let models = models.as_any().downcast_ref::<StringArray>().unwrap();
+            assert_eq!(models.value(0), "claude-3");
+            let total_tokens = column("total_tokens");
+            let total_tokens = total_tokens.as_any().downcast_ref::<Int64Array>().unwrap();
+            assert_eq!(total_tokens.value(0), 110);
+        }
+
+        // Exports contain request data, so they need request viewing permissions
+        let response = server
+            .get("/admin/api/v1/requests/export")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::FORBIDDEN);
     }
 
     #[sqlx::test]
@@ -467,6 +1941,145 @@ mod tests {
         response.assert_status(axum::http::StatusCode::FORBIDDEN);
     }
 
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_aggregate_by_attribute_permissions(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await; // Request logging disabled
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+
+        let response = app
+            .get("/admin/api/v1/requests/aggregate-by-attribute?attribute=cost_center")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::FORBIDDEN);
+
+        // Should return 404 since request logging is disabled
+        let response = app
+            .get("/admin/api/v1/requests/aggregate-by-attribute?attribute=cost_center")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_aggregate_by_group_permissions(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await; // Request logging disabled
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+
+        let response = app
+            .get("/admin/api/v1/requests/aggregate-by-group")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::FORBIDDEN);
+
+        // Should return 404 since request logging is disabled
+        let response = app
+            .get("/admin/api/v1/requests/aggregate-by-group")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_usage_time_series(pool: PgPool) {
+        let mut config = create_test_config();
+        config.enable_request_logging = true;
+        let mut app_state = crate::AppState::builder().db(pool.clone()).config(config).build();
+        let router = crate::build_router(&mut app_state, axum::Router::new())
+            .await
+            .expect("Failed to build router");
+        let server = axum_test::TestServer::new(router).expect("Failed to create test server");
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        insert_test_analytics_data(&pool, Utc::now() - Duration::minutes(30), "gpt-4", 200, 100.0, 50, 25).await;
+
+        let response = server
+            .get("/admin/api/v1/requests/timeseries")
+            .add_query_param("bucket", "10m")
+            .add_query_param("metric", "tokens")
+            .add_query_param("start_date", (Utc::now() - Duration::hours(2)).to_rfc3339())
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let series: UsageTimeSeriesResponse = response.json();
+        assert_eq!(series.bucket_seconds, 600);
+        assert!((12..=13).contains(&series.points.len()));
+        assert_eq!(series.points.iter().map(|p| p.value).sum::<f64>(), 75.0);
+
+        for bucket in ["soon", "30s"] {
+            let response = server
+                .get("/admin/api/v1/requests/timeseries")
+                .add_query_param("bucket", bucket)
+                .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+                .await;
+            response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        // 7 days of minutes is too many points
+        let response = server
+            .get("/admin/api/v1/requests/timeseries")
+            .add_query_param("bucket", "1m")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_pii_stats_by_group_permissions(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await; // Request logging disabled
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+
+        let response = app
+            .get("/admin/api/v1/requests/pii-by-group")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::FORBIDDEN);
+
+        let response = app
+            .get("/admin/api/v1/requests/pii-by-group")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_aggregate_embeddings_outlet_disabled(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await; // Request logging disabled
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        for path in [
+            "/admin/api/v1/requests/embeddings/aggregate-by-model",
+            "/admin/api/v1/requests/embeddings/aggregate-by-group",
+        ] {
+            let response = app
+                .get(path)
+                .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+                .await;
+            response.assert_status(axum::http::StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_aggregate_embeddings_unauthorized(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), true).await; // Request logging enabled
+        let user = create_test_user(&pool, Role::StandardUser).await; // Non-admin user
+
+        for path in [
+            "/admin/api/v1/requests/embeddings/aggregate-by-model",
+            "/admin/api/v1/requests/embeddings/aggregate-by-group",
+        ] {
+            let response = app.get(path).add_header(add_auth_headers(&user).0, add_auth_headers(&user).1).await;
+            response.assert_status(axum::http::StatusCode::FORBIDDEN);
+        }
+    }
+
     #[sqlx::test]
     #[test_log::test]
     async fn test_list_requests_success_empty(pool: PgPool) {
@@ -748,6 +2361,88 @@ mod tests {
         assert!(pair.response.is_none());
     }
 
+    #[tokio::test]
+    async fn test_stored_bodies_are_loaded() {
+        use crate::request_logging::{models::StoredBody, storage::BodyStore};
+        use bytes::Bytes;
+        use std::{collections::HashMap, sync::Arc};
+        use uuid::Uuid;
+
+        #[derive(Debug, Default)]
+        struct MemoryStore(HashMap<String, Bytes>);
+
+        #[async_trait::async_trait]
+        impl BodyStore for MemoryStore {
+            async fn put(&self, _key: &str, _body: Bytes) -> anyhow::Result<()> {
+                unimplemented!()
+            }
+
+            async fn get(&self, key: &str) -> anyhow::Result<Bytes> {
+                self.0.get(key).cloned().ok_or_else(|| anyhow::anyhow!("no such key"))
+            }
+
+            async fn delete(&self, _key: &str) -> anyhow::Result<()> {
+                unimplemented!()
+            }
+        }
+
+        let request_body = json!({"model": "gpt-4", "input": "hello"});
+        let store = MemoryStore(HashMap::from([(
+            "bodies/request.json".to_string(),
+            Bytes::from(request_body.to_string()),
+        )]));
+        let body_storage = BodyStorage::new(Arc::new(store), "bodies/".to_string());
+
+        let stored = |key: &str| StoredBody {
+            key: key.to_string(),
+            size_bytes: 0,
+        };
+        let timestamp = Utc::now();
+        let mut outlet_pairs = vec![outlet_postgres::RequestResponsePair {
+            request: outlet_postgres::HttpRequest {
+                id: 1,
+                instance_id: Uuid::new_v4(),
+                correlation_id: 1,
+                timestamp,
+                method: "POST".to_string(),
+                uri: "/ai/v1/embeddings".to_string(),
+                headers: json!({}),
+                body: Some(Ok(AiRequest::Stored {
+                    stored_body: stored("bodies/request.json"),
+                })),
+                created_at: timestamp,
+            },
+            response: Some(outlet_postgres::HttpResponse {
+                id: 1,
+                instance_id: Uuid::new_v4(),
+                correlation_id: 1,
+                timestamp,
+                status_code: 200,
+                headers: json!({}),
+                body: Some(Ok(AiResponse::Stored {
+                    stored_body: stored("bodies/missing.json"),
+                })),
+                duration_ms: 10,
+                duration_to_first_byte_ms: 5,
+                created_at: timestamp,
+            }),
+        }];
+
+        assert!(super::has_stored_bodies(&outlet_pairs[0]));
+        super::load_stored_bodies(&mut outlet_pairs, &body_storage).await;
+        let api_pairs = super::convert_outlet_pairs_to_api(outlet_pairs);
+
+        match &api_pairs[0].request.body {
+            Some(ApiAiRequest::Embeddings(value)) => assert_eq!(value, &request_body),
+            other => panic!("Expected the stored embeddings request, got {other:?}"),
+        }
+        // Bodies missing from the store are returned as their pointer
+        match &api_pairs[0].response.as_ref().unwrap().body {
+            Some(ApiAiResponse::Other(value)) => assert_eq!(value["stored_body"]["key"], "bodies/missing.json"),
+            other => panic!("Expected the stored body pointer, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_convert_outlet_pairs_mixed_scenarios() {
         // Test conversion with multiple pairs in different states
@@ -1256,4 +2951,149 @@ mod tests {
         assert!(pair.request.headers.get("authorization").is_some());
         assert!(pair.request.body.is_some());
     }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_token_backfill(pool: PgPool) {
+        let mut config = create_test_config();
+        config.enable_request_logging = true;
+        let mut app_state = crate::AppState::builder().db(pool.clone()).config(config).build();
+        let router = crate::build_router(&mut app_state, axum::Router::new())
+            .await
+            .expect("Failed to build router");
+        let server = axum_test::TestServer::new(router).expect("Failed to create test server");
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+
+        let request = json!({"model": "gpt-4", "messages": [{"role": "user", "content": "12345678"}]});
+        let response = json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1,
+            "model": "gpt-4",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hello there"}, "finish_reason": "stop", "logprobs": null}]
+        });
+        let mut reported = response.clone();
+        reported["usage"] = json!({"prompt_tokens": 20, "completion_tokens": 7, "total_tokens": 27});
+
+        // Usage reported, usage estimated from the bodies, no response logged, already counted
+        let logged = [(Some(reported), 0), (Some(response), 0), (None, 0), (None, 15)];
+        let instance_id = uuid::Uuid::new_v4();
+        for (correlation_id, (response, total_tokens)) in logged.into_iter().enumerate() {
+            let timestamp = Utc::now() - Duration::minutes(10 - correlation_id as i64);
+            sqlx::query(
+                "INSERT INTO outlet.http_requests (instance_id, correlation_id, timestamp, method, uri, headers, body, body_parsed)
+                 VALUES ($1, $2, $3, 'POST', '/ai/v1/chat/completions', '{}', $4, true)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id as i64)
+            .bind(timestamp)
+            .bind(&request)
+            .execute(&pool)
+            .await
+            .unwrap();
+            if let Some(response) = response {
+                sqlx::query(
+                    "INSERT INTO outlet.http_responses
+                         (instance_id, correlation_id, timestamp, status_code, headers, body, body_parsed, duration_ms, duration_to_first_byte_ms)
+                     VALUES ($1, $2, $3, 200, '{}', $4, true, 100, 10)",
+                )
+                .bind(instance_id)
+                .bind(correlation_id as i64)
+                .bind(timestamp)
+                .bind(response)
+                .execute(&pool)
+                .await
+                .unwrap();
+            }
+            sqlx::query(
+                "INSERT INTO http_analytics (
+                     instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms, model,
+                     prompt_tokens, completion_tokens, total_tokens, input_price_per_token, output_price_per_token
+                 ) VALUES ($1, $2, $3, '/ai/v1/chat/completions', 'POST', 200, 100, 'gpt-4', 0, 0, $4, 0.001, 0.002)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id as i64)
+            .bind(timestamp)
+            .bind(total_tokens)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let response = server
+            .post("/admin/api/v1/requests/token-backfills")
+            .json(&json!({"start_date": Utc::now(), "end_date": Utc::now() - Duration::days(1)}))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        let response = server
+            .post("/admin/api/v1/requests/token-backfills")
+            .json(&json!({}))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::ACCEPTED);
+        let started: TokenBackfill = response.json();
+        assert_eq!(started.total_requests, 3);
+
+        let mut backfill = started.clone();
+        for _ in 0..100 {
+            let response = server
+                .get(&format!("/admin/api/v1/requests/token-backfills/{}", started.id))
+                .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+                .await;
+            response.assert_status_ok();
+            backfill = response.json();
+            if backfill.status != "running" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(backfill.status, "completed");
+        assert_eq!(
+            (backfill.processed_requests, backfill.updated_requests, backfill.skipped_requests),
+            (3, 2, 1)
+        );
+        assert!(backfill.finished_at.is_some());
+
+        let tokens: Vec<(i64, i64, i64, Option<f64>, bool)> = sqlx::query_as(
+            "SELECT prompt_tokens, completion_tokens, total_tokens, total_cost::float8, usage_estimated FROM http_analytics ORDER BY correlation_id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!((tokens[0].0, tokens[0].1, tokens[0].2), (20, 7, 27));
+        assert!((tokens[0].3.unwrap() - 0.034).abs() < 1e-9);
+        assert!(!tokens[0].4);
+        // Counted with the tokenizer of gpt-4, and flagged as estimated
+        assert_eq!((tokens[1].0, tokens[1].1, tokens[1].2), (3, 2, 5));
+        assert!(tokens[1].4);
+        assert_eq!(tokens[2].2, 0);
+        assert_eq!(tokens[3].2, 15);
+
+        let response = server
+            .get("/admin/api/v1/requests/token-backfills")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let backfills: Vec<TokenBackfill> = response.json();
+        assert_eq!(backfills.len(), 1);
+
+        // Stopping a finished backfill leaves it as it was
+        let response = server
+            .post(&format!("/admin/api/v1/requests/token-backfills/{}/stop", started.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::ACCEPTED);
+        let stopped: TokenBackfill = response.json();
+        assert_eq!(stopped.status, "completed");
+        assert!(!stopped.stop_requested);
+
+        let viewer = create_test_user(&pool, Role::RequestViewer).await;
+        let response = server
+            .get("/admin/api/v1/requests/token-backfills")
+            .add_header(add_auth_headers(&viewer).0, add_auth_headers(&viewer).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::FORBIDDEN);
+    }
 }
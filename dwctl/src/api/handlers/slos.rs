@@ -0,0 +1,371 @@
+use crate::api::models::slos::{SloCreate, SloObjective, SloUpdate, SlosQuery};
+use crate::auth::permissions::{operation, resource, RequiresPermission};
+use crate::db::models::slos::{DeploymentSlo, SloAlert};
+use crate::errors::Error;
+use crate::slos::db::{NewSlo, SloChanges, SloManager};
+use crate::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+/// Number of alerts returned when listing an SLO's history
+const ALERTS_LIMIT: i64 = 100;
+
+/// Longest allowed SLO window, in days
+const MAX_WINDOW_DAYS: i32 = 90;
+
+/// Reject objectives that can't be evaluated
+fn validate_objective(objective: &str, target_percent: f64, latency_threshold_ms: Option<i32>, window_days: i32) -> Result<(), Error> {
+    let message = if !(target_percent > 0.0 && target_percent < 100.0) {
+        "target_percent must be between 0 and 100 (exclusive)".to_string()
+    } else if !(1..=MAX_WINDOW_DAYS).contains(&window_days) {
+        format!("window_days must be between 1 and {MAX_WINDOW_DAYS}")
+    } else if latency_threshold_ms.is_some_and(|threshold| threshold <= 0) {
+        "latency_threshold_ms must be positive".to_string()
+    } else if objective == SloObjective::Latency.as_str() && latency_threshold_ms.is_none() {
+        "latency SLOs need a latency_threshold_ms".to_string()
+    } else if objective != SloObjective::Latency.as_str() && latency_threshold_ms.is_some() {
+        "latency_threshold_ms only applies to latency SLOs".to_string()
+    } else {
+        return Ok(());
+    };
+    Err(Error::BadRequest { message })
+}
+
+#[utoipa::path(
+    post,
+    path = "/slos",
+    tag = "slos",
+    summary = "Create an SLO",
+    description = "Define an availability or latency objective for a deployment, measured on its probe results over a \
+                   rolling window (e.g. 99% of probes succeed over 30 days). SLOs are evaluated periodically, and an \
+                   alert is fired whenever their error budget becomes at risk, is exhausted or recovers.",
+    request_body = SloCreate,
+    responses(
+        (status = 201, description = "SLO created", body = DeploymentSlo),
+        (status = 400, description = "Bad request - invalid target, window or latency threshold"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "Deployment not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn create_slo(
+    State(state): State<AppState>,
+    permission: RequiresPermission<resource::Probes, operation::CreateAll>,
+    Json(request): Json<SloCreate>,
+) -> Result<(StatusCode, Json<DeploymentSlo>), Error> {
+    validate_objective(
+        request.objective.as_str(),
+        request.target_percent,
+        request.latency_threshold_ms,
+        request.window_days,
+    )?;
+
+    let slo = SloManager::create(
+        &state.db,
+        NewSlo {
+            deployment_id: request.deployment_id,
+            name: request.name,
+            objective: request.objective.as_str().to_string(),
+            target_percent: request.target_percent,
+            latency_threshold_ms: request.latency_threshold_ms,
+            window_days: request.window_days,
+            created_by: permission.current_user.id,
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(slo)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/slos",
+    tag = "slos",
+    summary = "List SLOs",
+    description = "List SLOs along with their latest evaluation, optionally only those of one deployment",
+    params(
+        SlosQuery
+    ),
+    responses(
+        (status = 200, description = "List of SLOs", body = Vec<DeploymentSlo>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_slos(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::ReadAll>,
+    Query(query): Query<SlosQuery>,
+) -> Result<Json<Vec<DeploymentSlo>>, Error> {
+    let slos = SloManager::list(&state.db, query.deployment_id).await?;
+    Ok(Json(slos))
+}
+
+#[utoipa::path(
+    get,
+    path = "/slos/{id}",
+    tag = "slos",
+    summary = "Get an SLO",
+    params(
+        ("id" = uuid::Uuid, Path, description = "SLO ID to retrieve"),
+    ),
+    responses(
+        (status = 200, description = "SLO details and latest evaluation", body = DeploymentSlo),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "SLO not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn get_slo(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::ReadAll>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DeploymentSlo>, Error> {
+    let slo = SloManager::get(&state.db, id).await?;
+    Ok(Json(slo))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/slos/{id}",
+    tag = "slos",
+    summary = "Update an SLO",
+    description = "Update an SLO's name, target, window or latency threshold. Changes take effect at the next evaluation.",
+    params(
+        ("id" = uuid::Uuid, Path, description = "SLO ID to update"),
+    ),
+    request_body = SloUpdate,
+    responses(
+        (status = 200, description = "SLO updated", body = DeploymentSlo),
+        (status = 400, description = "Bad request - invalid target, window or latency threshold"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "SLO not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn update_slo(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::UpdateAll>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SloUpdate>,
+) -> Result<Json<DeploymentSlo>, Error> {
+    let current = SloManager::get(&state.db, id).await?;
+    validate_objective(
+        &current.objective,
+        request.target_percent.unwrap_or(current.target_percent),
+        request.latency_threshold_ms.or(current.latency_threshold_ms),
+        request.window_days.unwrap_or(current.window_days),
+    )?;
+
+    let slo = SloManager::update(
+        &state.db,
+        id,
+        SloChanges {
+            name: request.name,
+            target_percent: request.target_percent,
+            latency_threshold_ms: request.latency_threshold_ms,
+            window_days: request.window_days,
+        },
+    )
+    .await?;
+
+    Ok(Json(slo))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/slos/{id}",
+    tag = "slos",
+    summary = "Delete an SLO",
+    description = "Delete an SLO along with its alert history",
+    params(
+        ("id" = uuid::Uuid, Path, description = "SLO ID to delete"),
+    ),
+    responses(
+        (status = 204, description = "SLO deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "SLO not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn delete_slo(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::DeleteAll>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    SloManager::delete(&state.db, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/slos/{id}/alerts",
+    tag = "slos",
+    summary = "List alerts of an SLO",
+    description = "List the most recent changes in an SLO's error budget status, newest first",
+    params(
+        ("id" = uuid::Uuid, Path, description = "SLO ID"),
+    ),
+    responses(
+        (status = 200, description = "Alerts of the SLO", body = Vec<SloAlert>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin access required"),
+        (status = 404, description = "SLO not found"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(
+        ("X-Doubleword-User" = [])
+    )
+)]
+pub async fn list_slo_alerts(
+    State(state): State<AppState>,
+    _: RequiresPermission<resource::Probes, operation::ReadAll>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<SloAlert>>, Error> {
+    // 404 for unknown SLOs rather than an empty history
+    SloManager::get(&state.db, id).await?;
+    let alerts = SloManager::list_alerts(&state.db, id, ALERTS_LIMIT).await?;
+    Ok(Json(alerts))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::users::Role;
+    use crate::db::models::slos::{DeploymentSlo, SloAlert};
+    use crate::test_utils::*;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_slo_crud(pool: PgPool) {
+        let (server, _drop_guard) = create_test_app(pool.clone(), false).await;
+        let manager = create_test_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, manager.id, "slo-model", "slo-alias").await;
+        let auth = add_auth_headers(&manager);
+
+        let response = server
+            .post("/admin/api/v1/slos")
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({
+                "deployment_id": deployment.id,
+                "name": "p99 under a second",
+                "objective": "latency",
+                "target_percent": 99.0,
+                "latency_threshold_ms": 1000
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let slo: DeploymentSlo = response.json();
+        assert_eq!(slo.window_days, 30);
+        assert_eq!(slo.status, "ok");
+        assert_eq!(slo.created_by, manager.id);
+
+        let listed: Vec<DeploymentSlo> = server
+            .get(&format!("/admin/api/v1/slos?deployment_id={}", deployment.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .json();
+        assert_eq!(listed.len(), 1);
+
+        let updated: DeploymentSlo = server
+            .patch(&format!("/admin/api/v1/slos/{}", slo.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({"target_percent": 99.5, "window_days": 7}))
+            .await
+            .json();
+        assert_eq!((updated.target_percent, updated.window_days), (99.5, 7));
+        assert_eq!(updated.latency_threshold_ms, Some(1000));
+
+        let alerts: Vec<SloAlert> = server
+            .get(&format!("/admin/api/v1/slos/{}/alerts", slo.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .json();
+        assert!(alerts.is_empty());
+
+        server
+            .delete(&format!("/admin/api/v1/slos/{}", slo.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await
+            .assert_status(axum::http::StatusCode::NO_CONTENT);
+        server
+            .get(&format!("/admin/api/v1/slos/{}/alerts", slo.id))
+            .add_header(auth.0, auth.1)
+            .await
+            .assert_status_not_found();
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_slo_validation(pool: PgPool) {
+        let (server, _drop_guard) = create_test_app(pool.clone(), false).await;
+        let manager = create_test_user(&pool, Role::PlatformManager).await;
+        let deployment = create_test_deployment(&pool, manager.id, "slo-model", "slo-alias").await;
+        let body = |objective: &str, target_percent: f64, latency_threshold_ms: Option<i32>, window_days: i32| {
+            json!({
+                "deployment_id": deployment.id,
+                "name": "slo",
+                "objective": objective,
+                "target_percent": target_percent,
+                "latency_threshold_ms": latency_threshold_ms,
+                "window_days": window_days
+            })
+        };
+
+        for invalid in [
+            body("availability", 100.0, None, 30),
+            body("availability", 99.0, None, 365),
+            body("availability", 99.0, Some(500), 30),
+            body("latency", 99.0, None, 30),
+        ] {
+            server
+                .post("/admin/api/v1/slos")
+                .add_header(add_auth_headers(&manager).0, add_auth_headers(&manager).1)
+                .json(&invalid)
+                .await
+                .assert_status_bad_request();
+        }
+
+        let mut unknown_deployment = body("availability", 99.0, None, 30);
+        unknown_deployment["deployment_id"] = json!(uuid::Uuid::new_v4());
+        server
+            .post("/admin/api/v1/slos")
+            .add_header(add_auth_headers(&manager).0, add_auth_headers(&manager).1)
+            .json(&unknown_deployment)
+            .await
+            .assert_status_not_found();
+
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        server
+            .post("/admin/api/v1/slos")
+            .add_header(add_auth_headers(&user).0, add_auth_headers(&user).1)
+            .json(&body("availability", 99.0, None, 30))
+            .await
+            .assert_status_forbidden();
+    }
+}
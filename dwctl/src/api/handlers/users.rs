@@ -1,22 +1,77 @@
 use crate::{
     api::models::{
         groups::GroupResponse,
-        users::{CurrentUser, ListUsersQuery, UserCreate, UserResponse, UserUpdate},
+        pagination::{next_cursor_headers, timestamp_value, ListCursor},
+        sorting::SortOrder,
+        users::{
+            CurrentUser, DeleteUserQuery, ListUsersQuery, Role, UserCreate, UserDeletionReport, UserResponse, UserSortField, UserUpdate,
+        },
     },
     auth::permissions::{can_read_all_resources, can_read_own_resource, operation, resource, RequiresPermission},
     db::{
-        handlers::{users::UserFilter, Groups, Repository, Users},
+        handlers::{users::UserFilter, Deployments, Groups, Repository, Users},
         models::users::{UserCreateDBRequest, UserUpdateDBRequest},
     },
     errors::Error,
+    request_logging::retention::{export_storage, redact_archive},
     types::{GroupId, Operation, Permission, Resource, UserId, UserIdOrCurrent},
     AppState,
 };
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+
+/// Most attributes a user can have
+const MAX_ATTRIBUTES: usize = 32;
+
+/// Longest attribute key
+const MAX_ATTRIBUTE_KEY_LENGTH: usize = 64;
+
+/// Longest attribute value
+const MAX_ATTRIBUTE_VALUE_LENGTH: usize = 256;
+
+/// Reject attributes with too many entries, or empty or overlong keys or values
+fn validate_attributes(attributes: &BTreeMap<String, String>) -> Result<(), Error> {
+    if attributes.len() > MAX_ATTRIBUTES {
+        return Err(Error::BadRequest {
+            message: format!("Users can have at most {MAX_ATTRIBUTES} attributes"),
+        });
+    }
+    for (key, value) in attributes {
+        if key.trim().is_empty() || key.len() > MAX_ATTRIBUTE_KEY_LENGTH {
+            return Err(Error::BadRequest {
+                message: format!("Attribute keys must be 1 to {MAX_ATTRIBUTE_KEY_LENGTH} characters long: '{key}'"),
+            });
+        }
+        if value.trim().is_empty() || value.len() > MAX_ATTRIBUTE_VALUE_LENGTH {
+            return Err(Error::BadRequest {
+                message: format!("Attribute values must be 1 to {MAX_ATTRIBUTE_VALUE_LENGTH} characters long: '{key}'"),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Parse an attribute filter of comma-separated `key=value` pairs, failing on malformed pairs
+fn parse_attribute_filter(filter: &str) -> Result<BTreeMap<String, String>, Error> {
+    filter
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            let (key, value) = (key.trim(), value.trim());
+            (!key.is_empty() && !value.is_empty()).then(|| (key.to_string(), value.to_string()))
+        })
+        .collect::<Option<BTreeMap<_, _>>>()
+        .filter(|attributes| !attributes.is_empty())
+        .ok_or_else(|| Error::BadRequest {
+            message: format!("attributes must be a comma-separated list of key=value pairs, got '{filter}'"),
+        })
+}
 
 // GET /user - List users (admin only)
 #[utoipa::path(
@@ -28,9 +83,19 @@ use axum::{
     params(
         ("skip" = Option<i64>, Query, description = "Number of users to skip"),
         ("limit" = Option<i64>, Query, description = "Maximum number of users to return"),
+        ("q" = Option<String>, Query, description = "Only users whose email, username or display name contains this (case-insensitive)"),
+        ("role" = Option<Role>, Query, description = "Only users with this role"),
+        ("group_id" = Option<String>, Query, description = "Only direct members of this group"),
+        ("is_admin" = Option<bool>, Query, description = "Only admins (true) or non-admins (false)"),
+        ("attributes" = Option<String>, Query, description = "Only users with all of these attributes, as comma-separated key=value pairs"),
+        ("sort" = Option<UserSortField>, Query, description = "Column to sort by (default: created_at)"),
+        ("order" = Option<SortOrder>, Query, description = "Sort direction (default: asc, or desc when neither sort nor order is given)"),
+        ("cursor" = Option<String>, Query, description = "Cursor from the x-next-cursor header of the previous page, used instead of skip"),
     ),
     responses(
-        (status = 200, description = "List of users", body = [UserResponse]),
+        (status = 200, description = "List of users", body = [UserResponse], headers(
+            ("x-next-cursor" = String, description = "Cursor of the next page, absent on the last page")
+        )),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden - admin access required"),
         (status = 500, description = "Internal server error"),
@@ -43,16 +108,49 @@ pub async fn list_users(
     State(state): State<AppState>,
     Query(query): Query<ListUsersQuery>,
     _: RequiresPermission<resource::Users, operation::ReadAll>,
-) -> Result<Json<Vec<UserResponse>>, Error> {
+) -> Result<(HeaderMap, Json<Vec<UserResponse>>), Error> {
     let mut tx = state.db.begin().await.map_err(|e| Error::Database(e.into()))?;
     let skip = query.skip.unwrap_or(0);
     let limit = query.limit.unwrap_or(100).min(1000);
 
+    let mut filter = UserFilter::new(skip, limit);
+    if let Some(q) = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        filter = filter.with_search(q.to_string());
+    }
+    if let Some(role) = query.role.clone() {
+        filter = filter.with_role(role);
+    }
+    if let Some(group_id) = query.group_id {
+        filter = filter.with_group(group_id);
+    }
+    if let Some(is_admin) = query.is_admin {
+        filter = filter.with_is_admin(is_admin);
+    }
+    if let Some(attributes) = query.attributes.as_deref() {
+        filter = filter.with_attributes(parse_attribute_filter(attributes)?);
+    }
+    if query.sort.is_some() || query.order.is_some() {
+        filter = filter.with_sort(query.sort.unwrap_or(UserSortField::CreatedAt), query.order.unwrap_or_default());
+    }
+    if let Some(cursor) = query.cursor.as_deref() {
+        filter = filter.with_cursor(ListCursor::decode(cursor)?);
+    }
+
     let users;
     {
         let mut repo = Users::new(&mut tx);
-        users = repo.list(&UserFilter::new(skip, limit)).await?;
+        users = repo.list(&filter).await?;
     }
+    let next_cursor = ListCursor::next(&users, limit, filter.sort, |user| {
+        let value = match filter.sort.0 {
+            UserSortField::CreatedAt => Some(timestamp_value(user.created_at)),
+            UserSortField::Email => Some(user.email.clone()),
+            UserSortField::Username => Some(user.username.clone()),
+            UserSortField::DisplayName => user.display_name.clone(),
+            UserSortField::LastLogin => user.last_login.map(timestamp_value),
+        };
+        (value, user.id)
+    });
     // Parse include parameter
     let includes: Vec<&str> = query
         .include
@@ -99,7 +197,7 @@ pub async fn list_users(
     }
 
     tx.commit().await.map_err(|e| Error::Database(e.into()))?;
-    Ok(Json(response_users))
+    Ok((next_cursor_headers(next_cursor), Json(response_users)))
 }
 
 // GET /users/{user_id} - Get specific user (admin only) or current user
@@ -196,6 +294,8 @@ pub async fn create_user(
 ) -> Result<(StatusCode, Json<UserResponse>), Error> {
     // Check admin role
 
+    validate_attributes(&user_data.attributes)?;
+
     let mut conn = state.db.acquire().await.expect("Failed to acquire database connection");
     let mut repo = Users::new(&mut conn);
     let db_request = UserCreateDBRequest::from(user_data);
@@ -235,6 +335,21 @@ pub async fn update_user(
     // Check admin role
     let mut conn = state.db.acquire().await.expect("Failed to acquire database connection");
 
+    if let Some(attributes) = &user_data.attributes {
+        validate_attributes(attributes)?;
+    }
+
+    if let Some(Some(model_id)) = user_data.default_model_id {
+        match Deployments::new(&mut conn).get_by_id(model_id).await? {
+            Some(model) if !model.deleted => {}
+            _ => {
+                return Err(Error::BadRequest {
+                    message: format!("Model {model_id} does not exist"),
+                })
+            }
+        }
+    }
+
     let mut repo = Users::new(&mut conn);
     let db_request = UserUpdateDBRequest::new(user_data);
 
@@ -248,11 +363,13 @@ pub async fn update_user(
     path = "/users/{user_id}",
     tag = "users",
     summary = "Delete user",
-    description = "Delete a user (admin only)",
+    description = "Delete a user (admin only). With `purge=true` the user's personal data is erased too, and a report of what was erased is returned.",
     params(
         ("user_id" = uuid::Uuid, Path, description = "User ID to delete"),
+        DeleteUserQuery,
     ),
     responses(
+        (status = 200, description = "User and their personal data erased", body = UserDeletionReport),
         (status = 204, description = "User deleted successfully"),
         (status = 400, description = "Bad request - cannot delete yourself"),
         (status = 401, description = "Unauthorized"),
@@ -267,31 +384,96 @@ pub async fn update_user(
 pub async fn delete_user(
     State(state): State<AppState>,
     Path(user_id): Path<UserId>,
+    Query(query): Query<DeleteUserQuery>,
     current_user: RequiresPermission<resource::Users, operation::DeleteAll>,
-) -> Result<StatusCode, Error> {
+) -> Result<Response, Error> {
     // Prevent self-deletion
     if user_id == current_user.id {
         return Err(Error::BadRequest {
             message: "You cannot delete your own account".to_string(),
         });
     }
+    let not_found = || Error::NotFound {
+        resource: "User".to_string(),
+        id: user_id.to_string(),
+    };
     let mut conn = state.db.acquire().await.expect("Failed to acquire database connection");
     let mut repo = Users::new(&mut conn);
 
-    match repo.delete(user_id).await? {
-        true => Ok(StatusCode::NO_CONTENT),
-        false => Err(Error::NotFound {
-            resource: "User".to_string(),
-            id: user_id.to_string(),
-        }),
+    if !query.purge.unwrap_or(false) {
+        return match repo.delete(user_id).await? {
+            true => Ok(StatusCode::NO_CONTENT.into_response()),
+            false => Err(not_found()),
+        };
     }
+
+    let purged = repo.purge(user_id).await?.ok_or_else(not_found)?;
+
+    // Bodies in object storage go once the rows pointing at them are gone, so a failure here
+    // leaves orphaned objects rather than dangling pointers
+    let mut stored_bodies_deleted = 0;
+    let mut stored_bodies_remaining = Vec::new();
+    for key in purged.stored_body_keys {
+        let deleted = match &state.body_storage {
+            Some(body_storage) => body_storage.delete(&key).await,
+            None => Err(anyhow::anyhow!("object storage is not configured")),
+        };
+        match deleted {
+            Ok(()) => stored_bodies_deleted += 1,
+            Err(e) => {
+                warn!(key = %key, error = %e, "Failed to delete stored body of purged user");
+                stored_bodies_remaining.push(key);
+            }
+        }
+    }
+
+    // Likewise, exported archives are rewritten without the user's requests once the purge has
+    // committed
+    let mut archived_requests_deleted = 0;
+    let mut archives_remaining = Vec::new();
+    if !purged.archived_requests.is_empty() {
+        let archive = export_storage(&state.config.request_log_retention);
+        for (key, requests) in purged.archived_requests {
+            let redacted = match &archive {
+                Ok(Some(archive)) => redact_archive(&state.db, archive, &key, &requests).await,
+                Ok(None) => Err(anyhow::anyhow!("the request log export bucket is not configured")),
+                Err(e) => Err(anyhow::anyhow!("failed to open the request log export bucket: {e}")),
+            };
+            match redacted {
+                Ok(removed) => archived_requests_deleted += removed,
+                Err(e) => {
+                    warn!(key = %key, error = %e, "Failed to remove purged user's requests from request log archive");
+                    archives_remaining.push(key);
+                }
+            }
+        }
+    }
+
+    let report = UserDeletionReport {
+        user_id,
+        api_keys_deleted: purged.api_keys_deleted,
+        requests_deleted: purged.requests_deleted,
+        responses_deleted: purged.responses_deleted,
+        analytics_anonymized: purged.analytics_anonymized,
+        stored_bodies_deleted,
+        stored_bodies_remaining,
+        archived_requests_deleted,
+        archives_remaining,
+    };
+    info!(
+        user_id = %user_id,
+        requests_deleted = report.requests_deleted,
+        analytics_anonymized = report.analytics_anonymized,
+        "Purged user data"
+    );
+    Ok(Json(report).into_response())
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
 
-    use crate::api::models::users::{Role, UserResponse};
+    use crate::api::models::users::{Role, UserDeletionReport, UserResponse};
     use crate::db::handlers::{Groups, Repository};
     use crate::db::models::groups::GroupCreateDBRequest;
     use crate::test_utils::*;
@@ -372,6 +554,73 @@ mod tests {
         assert_eq!(created_user.email, "newuser@example.com");
     }
 
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_user_attributes(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let auth = add_auth_headers(&admin_user);
+
+        let response = app
+            .post("/admin/api/v1/users")
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({
+                "username": "analyst",
+                "email": "analyst@example.com",
+                "roles": ["StandardUser"],
+                "attributes": {"cost_center": "CC-100", "department": "finance"}
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::CREATED);
+        let analyst: UserResponse = response.json();
+        assert_eq!(analyst.attributes["cost_center"], "CC-100");
+
+        // Updates replace all attributes
+        let response = app
+            .patch(&format!("/admin/api/v1/users/{}", analyst.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({"attributes": {"cost_center": "CC-200"}}))
+            .await;
+        response.assert_status_ok();
+        let analyst: UserResponse = response.json();
+        assert_eq!(analyst.attributes.len(), 1);
+        assert_eq!(analyst.attributes["cost_center"], "CC-200");
+
+        // Updates without attributes leave them alone
+        let response = app
+            .patch(&format!("/admin/api/v1/users/{}", analyst.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({"display_name": "Analyst"}))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<UserResponse>().attributes["cost_center"], "CC-200");
+
+        let listed = |query: &'static str| {
+            let app = &app;
+            let auth = auth.clone();
+            async move {
+                let response = app.get(&format!("/admin/api/v1/users?{query}")).add_header(auth.0, auth.1).await;
+                response.assert_status_ok();
+                response.json::<Vec<UserResponse>>().into_iter().map(|u| u.id).collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(listed("attributes=cost_center=CC-200").await, vec![analyst.id]);
+        assert!(listed("attributes=cost_center=CC-200,department=finance").await.is_empty());
+        assert!(listed("attributes=cost_center=CC-100").await.is_empty());
+
+        let response = app
+            .get("/admin/api/v1/users?attributes=cost_center")
+            .add_header(auth.0.clone(), auth.1.clone())
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let response = app
+            .patch(&format!("/admin/api/v1/users/{}", analyst.id))
+            .add_header(auth.0.clone(), auth.1.clone())
+            .json(&json!({"attributes": {"cost_center": ""}}))
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    }
+
     #[sqlx::test]
     #[test_log::test]
     async fn test_unauthenticated_request(pool: PgPool) {
@@ -495,6 +744,8 @@ mod tests {
             name: "Test Group".to_string(),
             description: Some("Test group for user include".to_string()),
             created_by: admin_user.id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
         group_repo
@@ -553,6 +804,175 @@ mod tests {
         assert!(groups.contains(&group.id));
     }
 
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_list_users_with_filters(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let viewer = create_test_user(&pool, Role::RequestViewer).await;
+        let standard_user = create_test_user(&pool, Role::StandardUser).await;
+        let group = create_test_group(&pool).await;
+        add_user_to_group(&pool, standard_user.id, group.id).await;
+
+        let list = |query: String| {
+            let app = &app;
+            let admin_user = &admin_user;
+            async move {
+                let response = app
+                    .get(&format!("/admin/api/v1/users?{query}"))
+                    .add_header(add_auth_headers(admin_user).0, add_auth_headers(admin_user).1)
+                    .await;
+                response.assert_status_ok();
+                response
+                    .json::<Vec<UserResponse>>()
+                    .into_iter()
+                    .map(|u| u.id)
+                    .collect::<HashSet<_>>()
+            }
+        };
+
+        // Search is a case-insensitive substring match
+        let users = list(format!("q={}", viewer.username.to_uppercase())).await;
+        assert_eq!(users, HashSet::from([viewer.id]));
+        let users = list(format!("q={}", &standard_user.email[9..25])).await;
+        assert_eq!(users, HashSet::from([standard_user.id]));
+        // LIKE wildcards match literally
+        assert!(list("q=%25".to_string()).await.is_empty());
+
+        let users = list("role=RequestViewer".to_string()).await;
+        assert!(users.contains(&viewer.id));
+        assert!(!users.contains(&standard_user.id));
+
+        let users = list(format!("group_id={}", group.id)).await;
+        assert_eq!(users, HashSet::from([standard_user.id]));
+
+        let users = list("is_admin=true".to_string()).await;
+        assert!(users.contains(&admin_user.id));
+        assert!(!users.contains(&viewer.id));
+
+        // Filters combine
+        let users = list(format!("is_admin=false&role=StandardUser&group_id={}", group.id)).await;
+        assert_eq!(users, HashSet::from([standard_user.id]));
+        let users = list(format!("role=RequestViewer&group_id={}", group.id)).await;
+        assert!(users.is_empty());
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_list_users_sorted(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        for _ in 0..3 {
+            create_test_user(&pool, Role::StandardUser).await;
+        }
+
+        let emails = |response: axum_test::TestResponse| {
+            response.assert_status_ok();
+            response
+                .json::<Vec<UserResponse>>()
+                .into_iter()
+                .map(|u| u.email)
+                .collect::<Vec<_>>()
+        };
+
+        let response = app
+            .get("/admin/api/v1/users?sort=email")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        let ascending = emails(response);
+        assert_eq!(ascending.len(), 4);
+        assert!(ascending.is_sorted());
+
+        let response = app
+            .get("/admin/api/v1/users?sort=email&order=desc")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        let descending = emails(response);
+        assert_eq!(descending, ascending.into_iter().rev().collect::<Vec<_>>());
+
+        // Paging through a sorted list returns each user once
+        let mut paged = Vec::new();
+        for skip in 0..4 {
+            let response = app
+                .get(&format!("/admin/api/v1/users?sort=display_name&skip={skip}&limit=1"))
+                .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+                .await;
+            paged.extend(emails(response));
+        }
+        paged.sort();
+        paged.dedup();
+        assert_eq!(paged.len(), 4);
+
+        let response = app
+            .get("/admin/api/v1/users?sort=password_hash")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        let response = app
+            .get("/admin/api/v1/users?order=sideways")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_list_users_with_cursor(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        for _ in 0..4 {
+            create_test_user(&pool, Role::StandardUser).await;
+        }
+
+        // Follows cursors from the first page to the last, returning the emails seen on the way
+        let page_through = |first: String, created_after_first_page: bool| {
+            let (app, admin_user, pool) = (&app, &admin_user, &pool);
+            async move {
+                let mut emails = Vec::new();
+                let mut url = first;
+                loop {
+                    let response = app
+                        .get(&url)
+                        .add_header(add_auth_headers(admin_user).0, add_auth_headers(admin_user).1)
+                        .await;
+                    response.assert_status_ok();
+                    let users = response.json::<Vec<UserResponse>>();
+                    assert!(users.len() <= 2);
+                    emails.extend(users.into_iter().map(|u| u.email));
+                    if created_after_first_page && emails.len() == 2 {
+                        create_test_user(pool, Role::StandardUser).await;
+                    }
+                    match response.maybe_header("x-next-cursor") {
+                        Some(cursor) => url = format!("/admin/api/v1/users?limit=2&cursor={}", cursor.to_str().unwrap()),
+                        None => return emails,
+                    }
+                }
+            }
+        };
+
+        // Users created while paging newest first don't shift later pages
+        let emails = page_through("/admin/api/v1/users?limit=2".to_string(), true).await;
+        assert_eq!(emails.len(), 5);
+        let mut unique = emails.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 5);
+
+        // No user has logged in, so the cursors page through users by ID alone
+        let emails = page_through("/admin/api/v1/users?limit=2&sort=last_login".to_string(), false).await;
+        let mut unique = emails.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 6);
+        assert_eq!(emails.len(), 6);
+
+        let response = app
+            .get("/admin/api/v1/users?cursor=garbage")
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    }
+
     #[sqlx::test]
     #[test_log::test]
     async fn test_update_user_as_admin(pool: PgPool) {
@@ -578,6 +998,50 @@ mod tests {
         assert_eq!(updated_user.avatar_url.as_deref(), Some("https://example.com/new-avatar.jpg"));
     }
 
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_update_user_default_model(pool: PgPool) {
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let regular_user = create_test_user(&pool, Role::StandardUser).await;
+        let deployment = create_test_deployment(&pool, admin_user.id, "default-model", "default-alias").await;
+        let url = format!("/admin/api/v1/users/{}", regular_user.id);
+
+        let response = app
+            .patch(&url)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"default_model_id": deployment.id}))
+            .await;
+        response.assert_status_ok();
+        let updated_user: UserResponse = response.json();
+        assert_eq!(updated_user.default_model_id, Some(deployment.id));
+
+        // Other updates leave the default model alone
+        let response = app
+            .patch(&url)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"display_name": "Renamed"}))
+            .await;
+        let updated_user: UserResponse = response.json();
+        assert_eq!(updated_user.default_model_id, Some(deployment.id));
+
+        let response = app
+            .patch(&url)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"default_model_id": uuid::Uuid::new_v4()}))
+            .await;
+        response.assert_status_bad_request();
+
+        let response = app
+            .patch(&url)
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .json(&json!({"default_model_id": null}))
+            .await;
+        response.assert_status_ok();
+        let updated_user: UserResponse = response.json();
+        assert_eq!(updated_user.default_model_id, None);
+    }
+
     #[sqlx::test]
     #[test_log::test]
     async fn test_update_user_as_non_admin_forbidden(pool: PgPool) {
@@ -641,6 +1105,125 @@ mod tests {
         get_response.assert_status_not_found();
     }
 
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_delete_user_with_purge(pool: PgPool) {
+        use sqlx::Executor;
+
+        let (app, _) = create_test_app(pool.clone(), false).await;
+        let admin_user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let other_user = create_test_user(&pool, Role::StandardUser).await;
+        create_test_api_key_for_user(&pool, user.id).await;
+
+        pool.execute("CREATE SCHEMA outlet").await.unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+        conn.execute("SET search_path = 'outlet'").await.unwrap();
+        outlet_postgres::migrator().run(&mut *conn).await.unwrap();
+        conn.execute("SET search_path = 'public'").await.unwrap();
+
+        // Two requests by the user, one with its body in object storage, and one by another user
+        let instance_id = uuid::Uuid::new_v4();
+        for (correlation_id, owner, body) in [
+            (1_i64, &user, json!({"model": "gpt-4"})),
+            (2, &user, json!({"stored_body": {"key": "bodies/request.json", "size_bytes": 10}})),
+            (3, &other_user, json!({"model": "gpt-4"})),
+        ] {
+            sqlx::query(
+                "INSERT INTO outlet.http_requests (instance_id, correlation_id, timestamp, method, uri, headers, body)
+                 VALUES ($1, $2, NOW(), 'POST', '/ai/v1/chat/completions', '{}', $3)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id)
+            .bind(body)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO outlet.http_responses
+                     (instance_id, correlation_id, timestamp, status_code, headers, duration_ms, duration_to_first_byte_ms)
+                 VALUES ($1, $2, NOW(), 200, '{}', 100, 10)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO http_analytics
+                     (instance_id, correlation_id, timestamp, method, uri, model, prompt_tokens, user_id, user_email, conversation_id)
+                 VALUES ($1, $2, NOW(), 'POST', '/ai/v1/chat/completions', 'gpt-4', 100, $3, $4, 'conversation')",
+            )
+            .bind(instance_id)
+            .bind(correlation_id)
+            .bind(owner.id)
+            .bind(&owner.email)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        }
+
+        // An export of today's log, which would hold the user's requests, and one of last year's
+        sqlx::query(
+            "INSERT INTO request_log_archives (range_start, range_end, object_key, request_count, size_bytes)
+             VALUES (date_trunc('day', NOW()), date_trunc('day', NOW()) + INTERVAL '1 day', 'archive/request-log/today.jsonl', 3, 100),
+                    (NOW() - INTERVAL '1 year', NOW() - INTERVAL '1 year' + INTERVAL '1 day', 'archive/request-log/old.jsonl', 1, 10)",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        let response = app
+            .delete(&format!("/admin/api/v1/users/{}?purge=true", user.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_ok();
+        let report: UserDeletionReport = response.json();
+        assert_eq!(report.user_id, user.id);
+        assert_eq!(report.api_keys_deleted, 1);
+        assert_eq!(report.requests_deleted, 2);
+        assert_eq!(report.responses_deleted, 2);
+        assert_eq!(report.analytics_anonymized, 2);
+        // No object storage is configured, so the stored body is left for an operator to remove
+        assert_eq!(report.stored_bodies_deleted, 0);
+        assert_eq!(report.stored_bodies_remaining, vec!["bodies/request.json".to_string()]);
+        // Nor is an export bucket, so the archive holding the user's requests is reported too
+        assert_eq!(report.archived_requests_deleted, 0);
+        assert_eq!(report.archives_remaining, vec!["archive/request-log/today.jsonl".to_string()]);
+
+        // Only the other user's request is left in the log
+        let logged: Vec<i64> = sqlx::query_scalar("SELECT correlation_id FROM outlet.http_requests")
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(logged, vec![3]);
+
+        // Usage is kept, without anything tying it to the user
+        let analytics: Vec<(i64, Option<uuid::Uuid>, bool)> = sqlx::query_as(
+            "SELECT prompt_tokens, user_id, user_email IS NULL AND conversation_id IS NULL FROM http_analytics ORDER BY correlation_id",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap();
+        assert_eq!(
+            analytics,
+            vec![(100, None, true), (100, None, true), (100, Some(other_user.id), false)]
+        );
+
+        let get_response = app
+            .get(&format!("/admin/api/v1/users/{}", user.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        get_response.assert_status_not_found();
+
+        // Purging a user that doesn't exist is a 404, like a plain delete
+        let response = app
+            .delete(&format!("/admin/api/v1/users/{}?purge=true", user.id))
+            .add_header(add_auth_headers(&admin_user).0, add_auth_headers(&admin_user).1)
+            .await;
+        response.assert_status_not_found();
+    }
+
     #[sqlx::test]
     #[test_log::test]
     async fn test_delete_user_as_non_admin_forbidden(pool: PgPool) {
@@ -0,0 +1,56 @@
+//! API request/response models for adoption metrics.
+
+use crate::types::GroupId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Query parameters for adoption metrics
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AdoptionQuery {
+    /// Point in time to report on (defaults to now)
+    pub as_of: Option<DateTime<Utc>>,
+    /// Number of weeks of trend data to return (default: 12, max: 52)
+    pub weeks: Option<i32>,
+}
+
+/// Number of active users in a week
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AdoptionTrendPoint {
+    /// Start of the week (Monday, UTC)
+    pub week_start: DateTime<Utc>,
+    pub active_users: i64,
+}
+
+/// Adoption of the platform within a single group
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GroupAdoption {
+    #[schema(value_type = String, format = "uuid")]
+    pub group_id: GroupId,
+    pub group_name: String,
+    pub member_count: i64,
+    /// Members who made a request in the 30 days before `as_of`
+    pub monthly_active_users: i64,
+    /// Percentage of members who are monthly active
+    pub adoption_percentage: f64,
+    pub weekly_active: Vec<AdoptionTrendPoint>,
+}
+
+/// Platform adoption metrics, computed from request logs
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AdoptionResponse {
+    pub as_of: DateTime<Utc>,
+    pub total_users: i64,
+    /// Users who made a request in the day before `as_of`
+    pub daily_active_users: i64,
+    /// Users who made a request in the 7 days before `as_of`
+    pub weekly_active_users: i64,
+    /// Users who made a request in the 30 days before `as_of`
+    pub monthly_active_users: i64,
+    /// Users created in the 30 days before `as_of`
+    pub new_users: i64,
+    /// Users who have never made a request
+    pub zero_usage_users: i64,
+    pub weekly_active: Vec<AdoptionTrendPoint>,
+    pub groups: Vec<GroupAdoption>,
+}
@@ -1,8 +1,9 @@
-use crate::db::models::api_keys::ApiKeyDBResponse;
+use crate::db::models::api_keys::{ApiKeyAccessRequestDBResponse, ApiKeyDBResponse};
 use crate::types::{ApiKeyId, DeploymentId, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
 
 // API Key request models.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -107,3 +108,86 @@ impl From<ApiKeyDBResponse> for ApiKeyInfoResponse {
         }
     }
 }
+
+/// Status of a request for an API key to access a deployment requiring key approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessRequestStatus {
+    Pending,
+    Approved,
+    Denied,
+}
+
+impl AccessRequestStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessRequestStatus::Pending => "pending",
+            AccessRequestStatus::Approved => "approved",
+            AccessRequestStatus::Denied => "denied",
+        }
+    }
+
+    fn parse(status: &str) -> Self {
+        match status {
+            "approved" => AccessRequestStatus::Approved,
+            "denied" => AccessRequestStatus::Denied,
+            _ => AccessRequestStatus::Pending,
+        }
+    }
+}
+
+/// Request for an API key to access a deployment requiring key approval
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeyAccessRequestCreate {
+    #[schema(value_type = String, format = "uuid")]
+    pub deployment_id: DeploymentId,
+    /// Why access is needed, shown to approvers
+    pub reason: Option<String>,
+}
+
+/// An approver's decision on an access request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccessRequestDecision {
+    /// `approved` or `denied` (denying an approved request revokes access)
+    pub status: AccessRequestStatus,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct ListAccessRequestsQuery {
+    /// Only list requests with this status
+    pub status: Option<AccessRequestStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeyAccessRequestResponse {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub api_key_id: ApiKeyId,
+    #[schema(value_type = String, format = "uuid")]
+    pub deployment_id: DeploymentId,
+    pub status: AccessRequestStatus,
+    pub reason: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub decided_by: Option<UserId>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub decision_note: Option<String>,
+}
+
+impl From<ApiKeyAccessRequestDBResponse> for ApiKeyAccessRequestResponse {
+    fn from(db: ApiKeyAccessRequestDBResponse) -> Self {
+        Self {
+            id: db.id,
+            api_key_id: db.api_key_id,
+            deployment_id: db.deployment_id,
+            status: AccessRequestStatus::parse(&db.status),
+            reason: db.reason,
+            requested_at: db.requested_at,
+            decided_by: db.decided_by,
+            decided_at: db.decided_at,
+            decision_note: db.decision_note,
+        }
+    }
+}
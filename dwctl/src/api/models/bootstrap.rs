@@ -0,0 +1,51 @@
+//! API response models for client bootstrap.
+
+use crate::db::models::deployments::{DeploymentDBResponse, ModelType};
+use crate::types::UserId;
+use serde::{Deserialize, Serialize};
+
+/// Requests-per-second limit, with the burst allowed above it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Sustained requests per second (null = no limit)
+    pub requests_per_second: Option<f32>,
+    /// Maximum burst size (null = no limit)
+    pub burst_size: Option<i32>,
+}
+
+/// A model the caller's API key can use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootstrapModel {
+    /// Model name to send in requests
+    pub id: String,
+    pub model_type: Option<ModelType>,
+    pub capabilities: Option<Vec<String>>,
+    /// Global rate limit of the model, shared by all callers
+    pub rate_limit: RateLimit,
+}
+
+impl From<DeploymentDBResponse> for BootstrapModel {
+    fn from(db: DeploymentDBResponse) -> Self {
+        Self {
+            id: db.alias,
+            model_type: db.model_type,
+            capabilities: db.capabilities,
+            rate_limit: RateLimit {
+                requests_per_second: db.requests_per_second,
+                burst_size: db.burst_size,
+            },
+        }
+    }
+}
+
+/// Everything a client SDK needs to configure itself, returned by `GET /ai/v1/bootstrap`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootstrapResponse {
+    pub user_id: UserId,
+    /// Models the API key can use
+    pub models: Vec<BootstrapModel>,
+    /// The user's default model, if set and still accessible
+    pub default_model: Option<String>,
+    /// Rate limit of the API key itself, applied across all models
+    pub rate_limit: RateLimit,
+}
@@ -1,9 +1,15 @@
 use crate::api::models::groups::GroupResponse;
+use crate::api::models::probes::Health;
+use crate::api::models::sorting::{SortField, SortOrder};
 use crate::db::models::deployments::{
-    DeploymentDBResponse, ModelType, ProviderPricing, ProviderPricingUpdate, TokenPricing, TokenPricingUpdate,
+    DeploymentCanaryDBResponse, DeploymentDBResponse, DeploymentDeprecationDBResponse, DeploymentFallbackCreateDBRequest,
+    DeploymentFallbackDBResponse, DeploymentLoggingPolicyDBResponse, DeploymentLoggingPolicyUpdateDBRequest, DeploymentScheduleDBResponse,
+    DeploymentScheduleUpdateDBRequest, DeploymentShadowDBResponse, DeploymentTrafficSplitCreateDBRequest, DeploymentTrafficSplitDBResponse,
+    ModelType, ProviderPricing, ProviderPricingUpdate, TokenPricing, TokenPricingUpdate,
 };
-use crate::types::{DeploymentId, InferenceEndpointId, UserId};
-use chrono::{DateTime, Utc};
+use crate::rate_limits::{LimitOutcome, RateLimit, SimulationOutcome};
+use crate::types::{ApiKeyId, DeploymentId, InferenceEndpointId, UserId};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use serde_with::rust::double_option;
 use utoipa::{IntoParams, ToSchema};
@@ -25,6 +31,49 @@ pub struct ListModelsQuery {
     pub inactive: Option<bool>,
     /// Filter to only models the current user can access (defaults to false for admins, true for users)
     pub accessible: Option<bool>,
+    /// Column to sort by (default: created_at)
+    pub sort: Option<ModelSortField>,
+    /// Sort direction (default: asc, or desc when neither sort nor order is given)
+    pub order: Option<SortOrder>,
+    /// Number of items to skip
+    #[param(default = 0, minimum = 0)]
+    pub skip: Option<i64>,
+    /// Maximum number of items to return (default: all)
+    #[param(minimum = 1, maximum = 1000)]
+    pub limit: Option<i64>,
+    /// Cursor from the `x-next-cursor` header of the previous page. Replaces skip, and keeps the
+    /// sort the cursor was issued for
+    pub cursor: Option<String>,
+}
+
+/// Columns the deployed models list can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelSortField {
+    Alias,
+    ModelName,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl SortField for ModelSortField {
+    fn column(&self) -> &'static str {
+        match self {
+            ModelSortField::Alias => "alias",
+            ModelSortField::ModelName => "model_name",
+            ModelSortField::CreatedAt => "created_at",
+            ModelSortField::UpdatedAt => "updated_at",
+        }
+    }
+
+    fn column_type(&self) -> &'static str {
+        match self {
+            ModelSortField::Alias => "text",
+            ModelSortField::ModelName => "text",
+            ModelSortField::CreatedAt => "timestamptz",
+            ModelSortField::UpdatedAt => "timestamptz",
+        }
+    }
 }
 
 /// Query parameters for getting a single deployed model
@@ -84,6 +133,15 @@ pub struct DeployedModelCreate {
     pub pricing: Option<TokenPricing>,
     /// Provider/downstream pricing details (admin only)
     pub downstream_pricing: Option<ProviderPricing>,
+    /// Maximum context window in tokens, input plus output (null = unknown)
+    pub max_context_tokens: Option<i32>,
+    /// Whether the model supports tool/function calling (null = unknown)
+    pub supports_tools: Option<bool>,
+    /// Whether the model accepts image inputs (null = unknown)
+    pub supports_vision: Option<bool>,
+    /// Labels describing the model, e.g. "vision", "code" or "eu-hosted"
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 /// The data required to update a specific model.
@@ -105,6 +163,21 @@ pub struct DeployedModelUpdate {
     /// Provider/downstream pricing details partial updates (null = no change, Some(pricing_update) = partial update)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub downstream_pricing: Option<ProviderPricingUpdate>,
+    /// Enable or disable routing to this model without deleting it (null = no change)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Maximum context window in tokens (null = no change, Some(None) = unknown)
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "double_option")]
+    pub max_context_tokens: Option<Option<i32>>,
+    /// Whether the model supports tool calling (null = no change, Some(None) = unknown)
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "double_option")]
+    pub supports_tools: Option<Option<bool>>,
+    /// Whether the model accepts image inputs (null = no change, Some(None) = unknown)
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "double_option")]
+    pub supports_vision: Option<Option<bool>>,
+    /// Replace the model's labels (null = no change)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
 }
 
 /// A request to update a specific model (i.e. bundle a `DeployedModelUpdate` with a model id).
@@ -143,12 +216,23 @@ pub struct DeployedModelResponse {
     pub hosted_on: InferenceEndpointId,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Whether the model is currently routable. Disabled models keep their configuration,
+    /// but are removed from the AI proxy and `/v1/models`.
+    pub enabled: bool,
     /// Global per-model rate limit: requests per second (null = no limit)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub requests_per_second: Option<f32>,
     /// Global per-model rate limit: maximum burst size (null = no limit)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub burst_size: Option<i32>,
+    /// Maximum context window in tokens, input plus output (null = unknown)
+    pub max_context_tokens: Option<i32>,
+    /// Whether the model supports tool/function calling (null = unknown)
+    pub supports_tools: Option<bool>,
+    /// Whether the model accepts image inputs (null = unknown)
+    pub supports_vision: Option<bool>,
+    /// Labels describing the model, e.g. "vision", "code" or "eu-hosted"
+    pub tags: Vec<String>,
     /// Groups that have access to this model (only included if requested)
     /// Note: no_recursion is important! utoipa will panic at runtime, because it overflows the
     /// stack trying to follow the relationship.
@@ -161,12 +245,17 @@ pub struct DeployedModelResponse {
     /// Probe status (only included if requested)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<ModelProbeStatus>,
+    /// Health from recent probe results (null if the model hasn't been probed)
+    pub health: Option<Health>,
     /// Customer-facing pricing rates (only included if requested)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pricing: Option<TokenPricing>,
     /// Provider/downstream pricing details (only included if requested and user has Pricing::ReadAll)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub downstream_pricing: Option<ProviderPricing>,
+    /// Deprecation of the model (only included if it is deprecated)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecation: Option<DeploymentDeprecation>,
 }
 
 impl From<DeploymentDBResponse> for DeployedModelResponse {
@@ -182,13 +271,20 @@ impl From<DeploymentDBResponse> for DeployedModelResponse {
             hosted_on: db.hosted_on,
             created_at: db.created_at,
             updated_at: db.updated_at,
+            enabled: db.enabled,
             requests_per_second: db.requests_per_second,
             burst_size: db.burst_size,
-            groups: None,             // By default, relationships are not included
-            metrics: None,            // By default, metrics are not included
-            status: None,             // By default, probe status is not included
+            max_context_tokens: db.max_context_tokens,
+            supports_tools: db.supports_tools,
+            supports_vision: db.supports_vision,
+            tags: db.tags,
+            groups: None,  // By default, relationships are not included
+            metrics: None, // By default, metrics are not included
+            status: None,  // By default, probe status is not included
+            health: None,
             pricing: None,            // By default, pricing is not included (opt-in via include)
             downstream_pricing: None, // By default, downstream pricing is not included
+            deprecation: None,
         }
     }
 }
@@ -212,6 +308,12 @@ impl DeployedModelResponse {
         self
     }
 
+    /// Create a response with health included
+    pub fn with_health(mut self, health: Option<Health>) -> Self {
+        self.health = health;
+        self
+    }
+
     /// Create a response with customer pricing included
     pub fn with_pricing(mut self, pricing: Option<TokenPricing>) -> Self {
         self.pricing = pricing;
@@ -224,6 +326,12 @@ impl DeployedModelResponse {
         self
     }
 
+    /// Create a response with the model's deprecation included
+    pub fn with_deprecation(mut self, deprecation: Option<DeploymentDeprecation>) -> Self {
+        self.deprecation = deprecation;
+        self
+    }
+
     /// Mask rate limiting information (sets to None for users without permission)
     pub fn mask_rate_limiting(mut self) -> Self {
         self.requests_per_second = None;
@@ -231,3 +339,614 @@ impl DeployedModelResponse {
         self
     }
 }
+
+/// An endpoint that serves a deployment when its primary endpoint fails
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentFallback {
+    /// Inference endpoint to fall back to
+    #[schema(value_type = String, format = "uuid")]
+    pub endpoint_id: InferenceEndpointId,
+    /// Name of the model on the fallback endpoint
+    pub model_name: String,
+}
+
+/// Ordered fallbacks for a deployment. When the deployment's endpoint returns a 5xx or 429, or
+/// times out, the request is retried against each fallback in turn.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentFallbacks {
+    pub fallbacks: Vec<DeploymentFallback>,
+}
+
+impl From<DeploymentFallbackDBResponse> for DeploymentFallback {
+    fn from(db: DeploymentFallbackDBResponse) -> Self {
+        Self {
+            endpoint_id: db.endpoint_id,
+            model_name: db.model_name,
+        }
+    }
+}
+
+impl From<DeploymentFallback> for DeploymentFallbackCreateDBRequest {
+    fn from(fallback: DeploymentFallback) -> Self {
+        Self {
+            endpoint_id: fallback.endpoint_id,
+            model_name: fallback.model_name,
+        }
+    }
+}
+
+/// An endpoint that receives a share of a deployment's traffic
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentTrafficSplit {
+    /// Inference endpoint to send traffic to
+    #[schema(value_type = String, format = "uuid")]
+    pub endpoint_id: InferenceEndpointId,
+    /// Name of the model on the endpoint
+    pub model_name: String,
+    /// Share of traffic relative to the other targets' weights, e.g. 80 and 20. Must be positive.
+    pub weight: i32,
+}
+
+/// Weighted targets for a deployment. Each request is sent to one target, chosen in proportion
+/// to its weight; fallbacks still apply if that target fails. An empty list sends all traffic to
+/// the deployment's own endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentTrafficSplits {
+    pub targets: Vec<DeploymentTrafficSplit>,
+}
+
+impl From<DeploymentTrafficSplitDBResponse> for DeploymentTrafficSplit {
+    fn from(db: DeploymentTrafficSplitDBResponse) -> Self {
+        Self {
+            endpoint_id: db.endpoint_id,
+            model_name: db.model_name,
+            weight: db.weight,
+        }
+    }
+}
+
+impl From<DeploymentTrafficSplit> for DeploymentTrafficSplitCreateDBRequest {
+    fn from(split: DeploymentTrafficSplit) -> Self {
+        Self {
+            endpoint_id: split.endpoint_id,
+            model_name: split.model_name,
+            weight: split.weight,
+        }
+    }
+}
+
+/// Make a deployment a canary of an existing alias
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentCanaryUpdate {
+    /// Alias of the deployment whose traffic the canary takes a share of
+    pub alias: String,
+    /// Percentage of the alias' requests sent to the canary, from 1 to 100
+    pub percent: i32,
+}
+
+/// Requests routed to one side of a canary rollout or shadow evaluation
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CanaryVariantMetrics {
+    /// `stable`, `canary` or `shadow`
+    pub variant: String,
+    pub requests: i64,
+    /// Percentage of requests that failed with a server error
+    pub error_rate: f64,
+    pub avg_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+}
+
+/// A canary deployment: it takes a percentage of the requests to another deployment's alias, in
+/// place of that deployment's own endpoint, until it is promoted or removed
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentCanary {
+    /// Alias whose traffic the canary takes a share of
+    pub alias: String,
+    /// Deployment currently serving the alias
+    #[schema(value_type = String, format = "uuid")]
+    pub stable_deployment_id: DeploymentId,
+    /// Percentage of the alias' requests sent to the canary
+    pub percent: i32,
+    /// When the canary started taking traffic from this alias
+    pub started_at: DateTime<Utc>,
+    /// Requests to the alias since the canary started, by variant, for comparing the two.
+    /// All zero unless request logging is enabled.
+    pub variants: Vec<CanaryVariantMetrics>,
+}
+
+impl DeploymentCanary {
+    pub fn new(db: DeploymentCanaryDBResponse, alias: String, variants: Vec<CanaryVariantMetrics>) -> Self {
+        Self {
+            alias,
+            stable_deployment_id: db.stable_deployment_id,
+            percent: db.percent,
+            started_at: db.started_at,
+            variants,
+        }
+    }
+}
+
+/// Make a deployment a shadow of an existing alias
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentShadowUpdate {
+    /// Alias of the deployment whose requests are mirrored to the shadow
+    pub alias: String,
+    /// Percentage of the alias' requests mirrored to the shadow, from 1 to 100
+    pub percent: i32,
+}
+
+/// A shadow deployment: it receives a copy of a percentage of the requests to another
+/// deployment's alias. Its responses are logged but never returned to users.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentShadow {
+    /// Alias whose requests are mirrored to the shadow
+    pub alias: String,
+    /// Deployment currently serving the alias
+    #[schema(value_type = String, format = "uuid")]
+    pub source_deployment_id: DeploymentId,
+    /// Percentage of the alias' requests mirrored to the shadow
+    pub percent: i32,
+    /// When the shadow started receiving requests from this alias
+    pub started_at: DateTime<Utc>,
+    /// Mirrored requests since the shadow started, as served by the alias (`stable`) and by the
+    /// shadow. All zero unless request logging is enabled.
+    pub variants: Vec<CanaryVariantMetrics>,
+}
+
+impl DeploymentShadow {
+    pub fn new(db: DeploymentShadowDBResponse, alias: String, variants: Vec<CanaryVariantMetrics>) -> Self {
+        Self {
+            alias,
+            source_deployment_id: db.source_deployment_id,
+            percent: db.percent,
+            started_at: db.started_at,
+            variants,
+        }
+    }
+}
+
+/// Deprecate a deployment. It keeps serving requests, with a warning, until its sunset date.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentDeprecationUpdate {
+    /// When the deployment stops serving requests (null = no sunset date yet)
+    pub sunset_at: Option<DateTime<Utc>>,
+    /// Alias of the deployment clients should move to
+    pub replacement_alias: Option<String>,
+}
+
+/// A deprecated deployment. Responses to its requests carry `Deprecation`, `Sunset` and
+/// `Warning` headers, and after its sunset date requests are refused with a 410.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentDeprecation {
+    pub deprecated_at: DateTime<Utc>,
+    pub sunset_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub replacement_deployment_id: Option<DeploymentId>,
+    /// Alias of the deployment clients should move to
+    pub replacement_alias: Option<String>,
+}
+
+impl From<DeploymentDeprecationDBResponse> for DeploymentDeprecation {
+    fn from(db: DeploymentDeprecationDBResponse) -> Self {
+        Self {
+            deprecated_at: db.deprecated_at,
+            sunset_at: db.sunset_at,
+            replacement_deployment_id: db.replacement_deployment_id,
+            replacement_alias: db.replacement_alias,
+        }
+    }
+}
+
+/// Hypothetical traffic from one API key to a deployment
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RateLimitSimulationRequest {
+    /// API key sending the traffic
+    #[schema(value_type = String, format = "uuid")]
+    pub api_key_id: ApiKeyId,
+    /// Steady rate at which requests are sent
+    pub requests_per_second: f64,
+    /// How long the steady traffic lasts
+    pub duration_seconds: u32,
+    /// Requests sent all at once before the steady traffic starts (defaults to 0)
+    pub initial_burst: Option<u32>,
+}
+
+/// How one rate limit would treat the simulated traffic
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SimulatedRateLimit {
+    /// Rate the proxy enforces, rounded down to a whole number of at least one
+    pub requests_per_second: u32,
+    /// Requests the limit allows at once (the rate itself when no burst size is configured)
+    pub burst_size: u32,
+    /// Requests refused with 429 by this limit
+    pub limited_requests: u64,
+    /// Seconds into the traffic at which this limit first refuses a request
+    pub first_limited_after_seconds: Option<f64>,
+}
+
+impl SimulatedRateLimit {
+    fn new(limit: RateLimit, outcome: LimitOutcome) -> Self {
+        Self {
+            requests_per_second: limit.requests_per_second,
+            burst_size: limit.burst_size,
+            limited_requests: outcome.limited_requests,
+            first_limited_after_seconds: outcome.first_limited_after_seconds,
+        }
+    }
+}
+
+/// Which of the live rate limits a traffic profile would trip, and when
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RateLimitSimulation {
+    /// Whether the key's owner can use the deployment at all. If not, every request would be
+    /// refused regardless of the limits.
+    pub has_access: bool,
+    pub total_requests: u64,
+    pub allowed_requests: u64,
+    /// The API key's limit, if it has one. Checked before the deployment's; assumes the key sends
+    /// no other traffic.
+    pub api_key_limit: Option<SimulatedRateLimit>,
+    /// The deployment's limit, if it has one; assumes no other traffic to the deployment
+    pub model_limit: Option<SimulatedRateLimit>,
+}
+
+impl RateLimitSimulation {
+    pub fn new(has_access: bool, api_key_limit: Option<RateLimit>, model_limit: Option<RateLimit>, outcome: SimulationOutcome) -> Self {
+        Self {
+            has_access,
+            total_requests: outcome.total_requests,
+            allowed_requests: outcome.allowed_requests,
+            api_key_limit: api_key_limit.map(|limit| SimulatedRateLimit::new(limit, outcome.api_key)),
+            model_limit: model_limit.map(|limit| SimulatedRateLimit::new(limit, outcome.model)),
+        }
+    }
+}
+
+/// A chat request sent straight to a deployment, to smoke test it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentTestRequest {
+    /// Chat messages, as in the OpenAI chat completions API
+    #[schema(value_type = Vec<Object>)]
+    pub messages: Vec<serde_json::Value>,
+    /// Upper bound on the tokens generated (defaults to 64)
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f64>,
+}
+
+/// What the deployment's endpoint answered to a test request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentTestResponse {
+    /// HTTP status of the response, or null if no response arrived
+    pub status: Option<u16>,
+    /// Time until the whole response was received
+    pub latency_ms: u64,
+    /// Response body as returned by the endpoint: JSON if it parses, a string otherwise
+    #[schema(value_type = Option<Object>)]
+    pub response: Option<serde_json::Value>,
+    /// Why the request failed without a response, e.g. the endpoint couldn't be reached
+    pub error: Option<String>,
+}
+
+/// Day of the week, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DayOfWeek {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl DayOfWeek {
+    pub const ALL: [DayOfWeek; 7] = [
+        DayOfWeek::Mon,
+        DayOfWeek::Tue,
+        DayOfWeek::Wed,
+        DayOfWeek::Thu,
+        DayOfWeek::Fri,
+        DayOfWeek::Sat,
+        DayOfWeek::Sun,
+    ];
+
+    /// The day numbered `day` by ISO 8601, from 1 (Monday) to 7 (Sunday)
+    pub fn from_iso(day: i32) -> Option<Self> {
+        Self::ALL.get(usize::try_from(day).ok()?.checked_sub(1)?).copied()
+    }
+}
+
+impl From<Weekday> for DayOfWeek {
+    fn from(weekday: Weekday) -> Self {
+        Self::ALL[weekday.num_days_from_monday() as usize]
+    }
+}
+
+/// Hours of the given days in which a deployment is expected to be busy, in UTC
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BusyWindow {
+    pub days: Vec<DayOfWeek>,
+    /// First busy hour, from 0 to 23
+    pub start_hour: u32,
+    /// Hour the window ends (exclusive), from 1 to 24
+    pub end_hour: u32,
+}
+
+impl BusyWindow {
+    pub fn is_valid(&self) -> bool {
+        !self.days.is_empty() && self.start_hour < self.end_hour && self.end_hour <= 24
+    }
+
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        self.days.contains(&time.weekday().into()) && (self.start_hour..self.end_hour).contains(&time.hour())
+    }
+
+    /// When the window next starts after `time`
+    pub fn next_start(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (0..=7)
+            .map(|days| time.date_naive() + Duration::days(days))
+            .filter(|date| self.days.contains(&date.weekday().into()))
+            .filter_map(|date| date.and_hms_opt(self.start_hour, 0, 0))
+            .map(|start| start.and_utc())
+            .find(|start| *start > time)
+    }
+}
+
+/// Schedule hints of a deployment: when it is expected to be busy, so capacity can be
+/// provisioned ahead of its traffic peaks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentSchedule {
+    #[serde(default)]
+    pub busy_windows: Vec<BusyWindow>,
+    /// How long before a busy window starts capacity should be provisioned
+    #[serde(default = "default_lead_time_minutes")]
+    pub lead_time_minutes: i32,
+}
+
+fn default_lead_time_minutes() -> i32 {
+    30
+}
+
+impl Default for DeploymentSchedule {
+    fn default() -> Self {
+        Self {
+            busy_windows: Vec::new(),
+            lead_time_minutes: default_lead_time_minutes(),
+        }
+    }
+}
+
+impl From<DeploymentScheduleDBResponse> for DeploymentSchedule {
+    fn from(db: DeploymentScheduleDBResponse) -> Self {
+        Self {
+            busy_windows: serde_json::from_value(db.busy_windows).unwrap_or_default(),
+            lead_time_minutes: db.lead_time_minutes,
+        }
+    }
+}
+
+impl From<DeploymentSchedule> for DeploymentScheduleUpdateDBRequest {
+    fn from(schedule: DeploymentSchedule) -> Self {
+        Self {
+            busy_windows: serde_json::to_value(schedule.busy_windows).unwrap_or_default(),
+            lead_time_minutes: schedule.lead_time_minutes,
+        }
+    }
+}
+
+/// Schedule hints of a deployment as exported to backend operators and autoscalers
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScheduleHint {
+    #[schema(value_type = String, format = "uuid")]
+    pub deployment_id: DeploymentId,
+    pub alias: String,
+    /// Model name on the endpoint serving the deployment
+    pub model_name: String,
+    #[schema(value_type = String, format = "uuid")]
+    pub hosted_on: InferenceEndpointId,
+    pub busy_windows: Vec<BusyWindow>,
+    pub lead_time_minutes: i32,
+    /// Whether one of the busy windows is in progress
+    pub busy_now: bool,
+    /// When the next busy window starts
+    pub next_busy_at: Option<DateTime<Utc>>,
+    /// When capacity should be provisioned for the next busy window
+    pub provision_at: Option<DateTime<Utc>>,
+}
+
+impl ScheduleHint {
+    pub fn new(deployment: &DeploymentDBResponse, schedule: DeploymentSchedule, now: DateTime<Utc>) -> Self {
+        let next_busy_at = schedule.busy_windows.iter().filter_map(|window| window.next_start(now)).min();
+        Self {
+            deployment_id: deployment.id,
+            alias: deployment.alias.clone(),
+            model_name: deployment.model_name.clone(),
+            hosted_on: deployment.hosted_on,
+            busy_now: schedule.busy_windows.iter().any(|window| window.contains(now)),
+            next_busy_at,
+            provision_at: next_busy_at.map(|start| start - Duration::minutes(schedule.lead_time_minutes.into())),
+            busy_windows: schedule.busy_windows,
+            lead_time_minutes: schedule.lead_time_minutes,
+        }
+    }
+}
+
+/// Query parameters for deriving busy windows from a deployment's traffic
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct ObservedScheduleQuery {
+    /// Number of past weeks of traffic to look at (defaults to 4)
+    pub weeks: Option<i32>,
+    /// Hours with at least this percentage of the busiest hour's traffic count as busy (defaults to 50)
+    pub threshold_percent: Option<u32>,
+}
+
+/// Average traffic of a deployment in one hour of the week
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HourlyTraffic {
+    pub day: DayOfWeek,
+    pub hour: u32,
+    /// Requests per week in this hour
+    pub avg_requests: f64,
+}
+
+/// Busy windows observed in a deployment's logged traffic, in the form taken by its schedule
+/// hints. Empty unless request logging is enabled.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ObservedSchedule {
+    pub weeks: i32,
+    pub busy_windows: Vec<BusyWindow>,
+    /// Hours of the week with any traffic
+    pub hourly: Vec<HourlyTraffic>,
+}
+
+impl ObservedSchedule {
+    /// Find the busy hours among the request counts of each hour of the week, joining
+    /// consecutive busy hours into windows and windows with the same hours across days
+    pub fn new(counts: &[(DayOfWeek, u32, i64)], weeks: i32, threshold_percent: u32) -> Self {
+        let peak = counts.iter().map(|(_, _, requests)| *requests).max().unwrap_or(0);
+        let busy = |day: DayOfWeek, hour: u32| {
+            counts
+                .iter()
+                .any(|&(d, h, requests)| d == day && h == hour && requests > 0 && requests * 100 >= peak * i64::from(threshold_percent))
+        };
+
+        let mut busy_windows: Vec<BusyWindow> = Vec::new();
+        for day in DayOfWeek::ALL {
+            let mut hour = 0;
+            while hour < 24 {
+                if !busy(day, hour) {
+                    hour += 1;
+                    continue;
+                }
+                let start_hour = hour;
+                while hour < 24 && busy(day, hour) {
+                    hour += 1;
+                }
+                match busy_windows
+                    .iter_mut()
+                    .find(|window| window.start_hour == start_hour && window.end_hour == hour)
+                {
+                    Some(window) => window.days.push(day),
+                    None => busy_windows.push(BusyWindow {
+                        days: vec![day],
+                        start_hour,
+                        end_hour: hour,
+                    }),
+                }
+            }
+        }
+
+        let mut hourly: Vec<HourlyTraffic> = counts
+            .iter()
+            .map(|&(day, hour, requests)| HourlyTraffic {
+                day,
+                hour,
+                avg_requests: requests as f64 / f64::from(weeks.max(1)),
+            })
+            .collect();
+        hourly.sort_by_key(|traffic| (traffic.day, traffic.hour));
+
+        Self {
+            weeks,
+            busy_windows,
+            hourly,
+        }
+    }
+}
+
+/// Access controls of a deployment, on top of the groups it is assigned to
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentAccessPolicy {
+    /// Whether API keys need an approved access request to use the deployment, in addition to
+    /// group access
+    pub requires_key_approval: bool,
+}
+
+/// What the request log keeps of the bodies of a deployment's requests and responses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LoggingMode {
+    /// Bodies are logged as sent
+    #[default]
+    Full,
+    /// Bodies are dropped; the method, URI, headers, status, timings and usage are kept
+    MetadataOnly,
+    /// Bodies are replaced with their SHA-256 digest
+    HashOnly,
+    /// The values of `redacted_fields` are masked
+    RedactFields,
+}
+
+impl LoggingMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoggingMode::Full => "full",
+            LoggingMode::MetadataOnly => "metadata_only",
+            LoggingMode::HashOnly => "hash_only",
+            LoggingMode::RedactFields => "redact_fields",
+        }
+    }
+
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "full" => Some(LoggingMode::Full),
+            "metadata_only" => Some(LoggingMode::MetadataOnly),
+            "hash_only" => Some(LoggingMode::HashOnly),
+            "redact_fields" => Some(LoggingMode::RedactFields),
+            _ => None,
+        }
+    }
+}
+
+/// Logging policy of a deployment.
+///
+/// Applied to the request and response bodies of requests addressed to the deployment before
+/// they are written to the request log. Usage analytics are recorded in every mode.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentLoggingPolicy {
+    #[serde(default)]
+    pub mode: LoggingMode,
+    /// Dot-separated paths of the fields to mask in `redact_fields` mode, e.g. `messages.content`
+    /// or `choices.message.content`. Arrays along a path are traversed element by element.
+    #[serde(default)]
+    pub redacted_fields: Vec<String>,
+    /// Percentage of requests (0-100) whose bodies are logged according to `mode`. The bodies of
+    /// the other requests are dropped, as in `metadata_only` mode.
+    #[serde(default = "default_body_sample_percent")]
+    pub body_sample_percent: u32,
+}
+
+fn default_body_sample_percent() -> u32 {
+    100
+}
+
+impl Default for DeploymentLoggingPolicy {
+    fn default() -> Self {
+        Self {
+            mode: LoggingMode::default(),
+            redacted_fields: Vec::new(),
+            body_sample_percent: default_body_sample_percent(),
+        }
+    }
+}
+
+impl From<DeploymentLoggingPolicyDBResponse> for DeploymentLoggingPolicy {
+    fn from(db: DeploymentLoggingPolicyDBResponse) -> Self {
+        Self {
+            mode: LoggingMode::parse(&db.mode).unwrap_or_default(),
+            redacted_fields: db.redacted_fields,
+            body_sample_percent: db.body_sample_percent as u32,
+        }
+    }
+}
+
+impl From<DeploymentLoggingPolicy> for DeploymentLoggingPolicyUpdateDBRequest {
+    fn from(policy: DeploymentLoggingPolicy) -> Self {
+        Self {
+            mode: policy.mode.as_str().to_string(),
+            redacted_fields: policy.redacted_fields,
+            body_sample_percent: policy.body_sample_percent as i32,
+        }
+    }
+}
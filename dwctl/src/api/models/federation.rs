@@ -0,0 +1,184 @@
+use crate::api::models::requests::{ModelUsage, RequestsAggregateResponse, StatusCodeBreakdown};
+use crate::db::models::federation::FederationPeer;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Request payload for registering a peer instance
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FederationPeerCreate {
+    /// Unique display name, e.g. the peer's region
+    pub name: String,
+    /// Base URL of the peer instance, e.g. `https://eu.example.com`
+    pub url: String,
+    /// The `federation.token` configured on the peer
+    pub token: String,
+}
+
+/// A registered peer instance. Its token is never returned.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FederationPeerResponse {
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub name: String,
+    pub url: String,
+    #[schema(value_type = String, format = "uuid")]
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<FederationPeer> for FederationPeerResponse {
+    fn from(peer: FederationPeer) -> Self {
+        Self {
+            id: peer.id,
+            name: peer.name,
+            url: peer.url,
+            created_by: peer.created_by,
+            created_at: peer.created_at,
+        }
+    }
+}
+
+/// Time window of the request analytics in summaries
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+pub struct FederationSummaryQuery {
+    /// Start of the window (defaults to 24 hours ago)
+    pub timestamp_after: Option<DateTime<Utc>>,
+    /// End of the window (defaults to now)
+    pub timestamp_before: Option<DateTime<Utc>>,
+}
+
+/// Health of the deployments of one instance
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct InstanceHealth {
+    /// Enabled deployments
+    pub deployments: i64,
+    /// Deployments taken out of rotation by their circuit breaker
+    pub open_circuits: i64,
+    /// Active probes whose most recent check failed
+    pub failing_probes: i64,
+}
+
+/// Read-only summary of one instance, as served to its peers
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InstanceSummary {
+    pub health: InstanceHealth,
+    /// Request analytics for the window, absent if the instance doesn't log requests
+    pub requests: Option<RequestsAggregateResponse>,
+}
+
+/// The summary of one instance in a federation overview, or why it couldn't be fetched
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InstanceOverview {
+    /// Peer name, or `local` for the instance serving the overview
+    pub name: String,
+    /// Registered peer ID (absent for the local instance)
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub peer_id: Option<Uuid>,
+    pub summary: Option<InstanceSummary>,
+    /// Why the peer's summary is missing, e.g. it timed out or rejected the token
+    pub error: Option<String>,
+}
+
+/// Totals across every instance whose summary could be fetched
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct FederationTotals {
+    pub reachable_instances: i64,
+    pub unreachable_instances: i64,
+    pub health: InstanceHealth,
+    pub total_requests: i64,
+    /// Cost of the requests, across the instances that reported one
+    pub total_cost: Option<f64>,
+    pub status_codes: Vec<StatusCodeBreakdown>,
+    /// Requests per model alias, with latency averaged over every instance's requests
+    pub models: Vec<ModelUsage>,
+}
+
+impl FederationTotals {
+    /// Add up the summaries of several instances. Summaries are `None` for unreachable instances.
+    pub fn new<'a>(summaries: impl IntoIterator<Item = Option<&'a InstanceSummary>>) -> Self {
+        let mut totals = Self::default();
+        let mut status_codes: BTreeMap<String, i64> = BTreeMap::new();
+        // Request count, summed latency and cost of each model
+        let mut models: BTreeMap<String, (i64, f64, Option<f64>)> = BTreeMap::new();
+
+        for summary in summaries {
+            let Some(summary) = summary else {
+                totals.unreachable_instances += 1;
+                continue;
+            };
+            totals.reachable_instances += 1;
+            totals.health.deployments += summary.health.deployments;
+            totals.health.open_circuits += summary.health.open_circuits;
+            totals.health.failing_probes += summary.health.failing_probes;
+
+            let Some(requests) = &summary.requests else {
+                continue;
+            };
+            totals.total_requests += requests.total_requests;
+            totals.total_cost = add_costs(totals.total_cost, requests.total_cost);
+            for status in &requests.status_codes {
+                *status_codes.entry(status.status.clone()).or_default() += status.count;
+            }
+            for model in requests.models.iter().flatten() {
+                let (count, latency, cost) = models.entry(model.model.clone()).or_default();
+                *count += model.count;
+                *latency += model.avg_latency_ms * model.count as f64;
+                *cost = add_costs(*cost, model.total_cost);
+            }
+        }
+
+        let percentage = |count: i64| {
+            if totals.total_requests > 0 {
+                (count as f64 * 100.0) / totals.total_requests as f64
+            } else {
+                0.0
+            }
+        };
+        let status_codes = status_codes
+            .into_iter()
+            .map(|(status, count)| StatusCodeBreakdown {
+                status,
+                count,
+                percentage: percentage(count),
+            })
+            .collect();
+        let mut models: Vec<ModelUsage> = models
+            .into_iter()
+            .map(|(model, (count, latency, total_cost))| ModelUsage {
+                model,
+                count,
+                percentage: percentage(count),
+                avg_latency_ms: if count > 0 { latency / count as f64 } else { 0.0 },
+                total_cost,
+            })
+            .collect();
+        models.sort_by_key(|model| std::cmp::Reverse(model.count));
+
+        Self {
+            status_codes,
+            models,
+            ..totals
+        }
+    }
+}
+
+/// Sum two costs, either of which may be unknown
+fn add_costs(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Analytics and health across this instance and its peers
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FederationOverview {
+    pub timestamp_after: DateTime<Utc>,
+    pub timestamp_before: DateTime<Utc>,
+    pub totals: FederationTotals,
+    /// The local instance first, then each peer by name
+    pub instances: Vec<InstanceOverview>,
+}
@@ -1,9 +1,14 @@
 use crate::api::models::deployments::DeployedModelResponse;
+use crate::api::models::sorting::{SortField, SortOrder};
 use crate::api::models::users::UserResponse;
-use crate::db::models::groups::GroupDBResponse;
+use crate::db::models::groups::{
+    GroupDBResponse, GroupModerationDBResponse, GroupModerationUpdateDBRequest, GroupRequestLimitsDBResponse,
+    GroupRequestLimitsUpdateDBRequest,
+};
 use crate::types::{GroupId, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_with::rust::double_option;
 use utoipa::{IntoParams, ToSchema};
 
 /// Query parameters for listing groups
@@ -19,6 +24,43 @@ pub struct ListGroupsQuery {
 
     /// Include related data (comma-separated: "users", "models")
     pub include: Option<String>,
+
+    /// Column to sort by (default: name)
+    pub sort: Option<GroupSortField>,
+
+    /// Sort direction (default: asc)
+    pub order: Option<SortOrder>,
+
+    /// Cursor from the `x-next-cursor` header of the previous page. Replaces skip, and keeps the
+    /// sort the cursor was issued for
+    pub cursor: Option<String>,
+}
+
+/// Columns the groups list can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupSortField {
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl SortField for GroupSortField {
+    fn column(&self) -> &'static str {
+        match self {
+            GroupSortField::Name => "name",
+            GroupSortField::CreatedAt => "created_at",
+            GroupSortField::UpdatedAt => "updated_at",
+        }
+    }
+
+    fn column_type(&self) -> &'static str {
+        match self {
+            GroupSortField::Name => "text",
+            GroupSortField::CreatedAt => "timestamptz",
+            GroupSortField::UpdatedAt => "timestamptz",
+        }
+    }
 }
 
 // Request models
@@ -26,12 +68,30 @@ pub struct ListGroupsQuery {
 pub struct GroupCreate {
     pub name: String,
     pub description: Option<String>,
+    /// Parent group whose members and deployments this group inherits
+    #[serde(default)]
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub parent_id: Option<GroupId>,
+    /// Whether users join the group automatically when they register or are provisioned from
+    /// SSO headers
+    #[serde(default)]
+    pub is_default: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct GroupUpdate {
     pub name: Option<String>,
     pub description: Option<String>,
+    /// Admission priority of the group's members' AI requests
+    #[serde(default)]
+    pub priority: Option<PriorityTier>,
+    /// Parent group (null = no change, Some(None) = move to the top level)
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "double_option")]
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub parent_id: Option<Option<GroupId>>,
+    /// Whether newly provisioned users join the group
+    #[serde(default)]
+    pub is_default: Option<bool>,
 }
 
 // Response model
@@ -55,6 +115,13 @@ pub struct GroupResponse {
     #[schema(no_recursion)]
     pub models: Option<Vec<DeployedModelResponse>>,
     pub source: String,
+    /// Admission priority of the group's members' AI requests when upstreams are saturated
+    pub priority: PriorityTier,
+    /// Parent group whose members and deployments this group inherits
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub parent_id: Option<GroupId>,
+    /// Whether newly provisioned users join this group automatically
+    pub is_default: bool,
 }
 
 impl From<GroupDBResponse> for GroupResponse {
@@ -67,6 +134,9 @@ impl From<GroupDBResponse> for GroupResponse {
             created_at: db.created_at,
             updated_at: db.updated_at,
             source: db.source,
+            priority: PriorityTier::parse(&db.priority),
+            parent_id: db.parent_id,
+            is_default: db.is_default,
             users: None, // By default, relationships are not included
             models: None,
         }
@@ -85,3 +155,183 @@ impl GroupResponse {
         self
     }
 }
+
+/// Priority with which AI requests are admitted when upstreams are saturated. Users get the
+/// highest priority of their groups.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PriorityTier {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl PriorityTier {
+    /// All tiers, highest first
+    pub const ALL: [PriorityTier; 3] = [PriorityTier::High, PriorityTier::Normal, PriorityTier::Low];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriorityTier::High => "high",
+            PriorityTier::Normal => "normal",
+            PriorityTier::Low => "low",
+        }
+    }
+
+    pub fn parse(tier: &str) -> Self {
+        match tier {
+            "high" => PriorityTier::High,
+            "low" => PriorityTier::Low,
+            _ => PriorityTier::Normal,
+        }
+    }
+}
+
+/// What happens to requests that the moderation policy of a group flags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationMode {
+    /// Requests are not moderated
+    #[default]
+    Off,
+    /// Flagged requests are forwarded, with the decision in the response headers
+    Annotate,
+    /// Flagged requests are refused
+    Block,
+}
+
+impl ModerationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationMode::Off => "off",
+            ModerationMode::Annotate => "annotate",
+            ModerationMode::Block => "block",
+        }
+    }
+
+    pub fn parse(mode: &str) -> Self {
+        match mode {
+            "annotate" => ModerationMode::Annotate,
+            "block" => ModerationMode::Block,
+            _ => ModerationMode::Off,
+        }
+    }
+}
+
+/// Content moderation policy of a group.
+///
+/// Requests from members of the group are checked against `blocked_patterns` and the
+/// server's moderation endpoint (if configured) before being forwarded. When a user is in
+/// several groups, the strictest applicable policy wins.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct GroupModerationPolicy {
+    pub mode: ModerationMode,
+    /// Regular expressions matched against the text of each request
+    #[serde(default)]
+    pub blocked_patterns: Vec<String>,
+}
+
+impl From<GroupModerationDBResponse> for GroupModerationPolicy {
+    fn from(db: GroupModerationDBResponse) -> Self {
+        Self {
+            mode: ModerationMode::parse(&db.mode),
+            blocked_patterns: db.blocked_patterns,
+        }
+    }
+}
+
+impl From<GroupModerationPolicy> for GroupModerationUpdateDBRequest {
+    fn from(policy: GroupModerationPolicy) -> Self {
+        Self {
+            mode: policy.mode.as_str().to_string(),
+            blocked_patterns: policy.blocked_patterns,
+        }
+    }
+}
+
+/// Request limits of a group.
+///
+/// Requests from members of the group that exceed any of the limits are refused with a `400`
+/// before being forwarded. When a user is in several groups, the lowest applicable limit wins.
+/// Unset limits don't apply.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GroupRequestLimits {
+    /// Maximum size of the request body, in bytes
+    #[serde(default)]
+    pub max_body_bytes: Option<i64>,
+    /// Maximum number of messages in a chat completion or Messages API request
+    #[serde(default)]
+    pub max_messages: Option<i32>,
+    /// Maximum `max_tokens` (or `max_completion_tokens`) a request may ask for
+    #[serde(default)]
+    pub max_tokens: Option<i32>,
+}
+
+impl From<GroupRequestLimitsDBResponse> for GroupRequestLimits {
+    fn from(db: GroupRequestLimitsDBResponse) -> Self {
+        Self {
+            max_body_bytes: db.max_body_bytes,
+            max_messages: db.max_messages,
+            max_tokens: db.max_tokens,
+        }
+    }
+}
+
+/// How a group's request log retention override combines with the others that apply to a user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRetentionKind {
+    /// Requests are purged after at most this many days, e.g. for privacy
+    #[default]
+    Maximum,
+    /// Requests are kept for at least this many days, e.g. for a legal hold
+    Minimum,
+}
+
+impl LogRetentionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogRetentionKind::Maximum => "maximum",
+            LogRetentionKind::Minimum => "minimum",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Self {
+        match kind {
+            "minimum" => LogRetentionKind::Minimum,
+            _ => LogRetentionKind::Maximum,
+        }
+    }
+}
+
+/// Request log retention of a group.
+///
+/// The override of the Everyone group replaces the default retention window. The overrides of
+/// other groups replace it for their members: the shortest maximum applies, unless a minimum is
+/// longer, so legal holds always win over privacy limits.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GroupLogRetention {
+    /// Days the requests of the group's members are kept for, if overridden
+    #[serde(default)]
+    pub retention_days: Option<i32>,
+    /// Whether the override is a maximum or a minimum. Ignored for the Everyone group.
+    #[serde(default)]
+    pub kind: LogRetentionKind,
+    /// Days the requests of a member in no other group with an override (besides the groups
+    /// nested under this one) are kept for, or null if the retention policy is disabled and
+    /// they're kept indefinitely. Ignored when setting the retention.
+    #[serde(default)]
+    #[schema(read_only)]
+    pub effective_retention_days: Option<f64>,
+}
+
+impl From<GroupRequestLimits> for GroupRequestLimitsUpdateDBRequest {
+    fn from(limits: GroupRequestLimits) -> Self {
+        Self {
+            max_body_bytes: limits.max_body_bytes,
+            max_messages: limits.max_messages,
+            max_tokens: limits.max_tokens,
+        }
+    }
+}
@@ -1,8 +1,18 @@
-use crate::db::models::inference_endpoints::InferenceEndpointDBResponse;
+use crate::api::models::probes::Health;
+use crate::api::models::sorting::{SortField, SortOrder};
+use crate::db::models::endpoint_history::{EndpointConfig, EndpointConfigVersionDBResponse};
+use crate::db::models::inference_endpoints::{
+    EndpointHeaderRulesDBResponse, EndpointHeaderRulesUpdateDBRequest, EndpointPendingModelDBResponse, EndpointRateLimitsDBResponse,
+    EndpointRateLimitsUpdateDBRequest, EndpointRedactionDBResponse, EndpointRedactionUpdateDBRequest, EndpointSyncStrategyDBResponse,
+    EndpointSyncStrategyUpdateDBRequest, InferenceEndpointDBResponse,
+};
+use crate::request_logging::pii::PiiCategory;
+use crate::tls::TlsVersion;
 use crate::types::{InferenceEndpointId, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_with::rust::double_option;
+use std::collections::{BTreeMap, HashMap};
 use utoipa::{IntoParams, ToSchema};
 
 /// A model from an OpenAI-compatible API
@@ -58,6 +68,41 @@ impl From<AnthropicModelsResponse> for OpenAIModelsResponse {
     }
 }
 
+/// A deployment of a model on an Azure OpenAI resource
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AzureDeployment {
+    /// Deployment name, used in place of the model name in requests
+    pub id: String,
+    /// The model deployed
+    pub model: String,
+    pub created_at: Option<i64>,
+}
+
+/// Response from the /openai/deployments endpoint of the Azure OpenAI API
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AzureDeploymentsResponse {
+    pub data: Vec<AzureDeployment>,
+}
+
+impl From<AzureDeploymentsResponse> for OpenAIModelsResponse {
+    fn from(azure: AzureDeploymentsResponse) -> Self {
+        let data = azure
+            .data
+            .into_iter()
+            .map(|deployment| OpenAIModel {
+                id: deployment.id,
+                object: "model".to_string(),
+                created: deployment.created_at,
+                owned_by: "azure".to_string(),
+            })
+            .collect();
+        Self {
+            object: "list".to_string(),
+            data,
+        }
+    }
+}
+
 /// Query parameters for listing inference endpoints
 #[derive(Debug, Deserialize, IntoParams, ToSchema)]
 pub struct ListEndpointsQuery {
@@ -68,6 +113,46 @@ pub struct ListEndpointsQuery {
     /// Maximum number of items to return
     #[param(default = 100, minimum = 1, maximum = 1000)]
     pub limit: Option<i64>,
+
+    /// Column to sort by (default: created_at)
+    pub sort: Option<EndpointSortField>,
+
+    /// Sort direction (default: asc, or desc when neither sort nor order is given)
+    pub order: Option<SortOrder>,
+
+    /// Cursor from the `x-next-cursor` header of the previous page. Replaces skip, and keeps the
+    /// sort the cursor was issued for
+    pub cursor: Option<String>,
+}
+
+/// Columns the endpoints list can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointSortField {
+    Name,
+    Url,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl SortField for EndpointSortField {
+    fn column(&self) -> &'static str {
+        match self {
+            EndpointSortField::Name => "name",
+            EndpointSortField::Url => "url",
+            EndpointSortField::CreatedAt => "created_at",
+            EndpointSortField::UpdatedAt => "updated_at",
+        }
+    }
+
+    fn column_type(&self) -> &'static str {
+        match self {
+            EndpointSortField::Name => "text",
+            EndpointSortField::Url => "text",
+            EndpointSortField::CreatedAt => "timestamptz",
+            EndpointSortField::UpdatedAt => "timestamptz",
+        }
+    }
 }
 
 // Request models
@@ -84,10 +169,19 @@ pub struct InferenceEndpointCreate {
     /// If a model is not in this map, its alias will default to the model_name
     #[serde(default)]
     pub alias_mapping: Option<HashMap<String, String>>,
-    /// The name of the authorization header (defaults to "Authorization")
+    /// The name of the authorization header (defaults to "Authorization", or "api-key" for Azure OpenAI)
     pub auth_header_name: Option<String>,
-    /// The prefix for the authorization header value (defaults to "Bearer " with trailing space)
+    /// The prefix for the authorization header value (defaults to "Bearer " with trailing space, or none for Azure OpenAI)
     pub auth_header_prefix: Option<String>,
+    /// PEM bundle of CA certificates to trust for the endpoint, in addition to the built-in roots
+    pub tls_ca_pem: Option<String>,
+    /// Accept any certificate from the endpoint, e.g. a self-signed one. Only meant for lab environments
+    #[serde(default)]
+    pub tls_skip_verify: bool,
+    /// Minimum TLS version to negotiate with the endpoint
+    pub tls_min_version: Option<TlsVersion>,
+    /// Synchronize the endpoint's models automatically every this many seconds (if None, only on demand)
+    pub sync_interval_seconds: Option<i32>,
     /// Whether to automatically synchronize models after creation (defaults to true)
     #[serde(default = "default_sync")]
     pub sync: bool,
@@ -113,6 +207,20 @@ pub struct InferenceEndpointUpdate {
     pub auth_header_name: Option<String>,
     /// The prefix for the authorization header value (include trailing space if needed)
     pub auth_header_prefix: Option<String>,
+    /// PEM bundle of CA certificates to trust for the endpoint (null = no change, Some(None) = remove)
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "double_option")]
+    pub tls_ca_pem: Option<Option<String>>,
+    /// Accept any certificate from the endpoint. Only meant for lab environments
+    pub tls_skip_verify: Option<bool>,
+    /// Minimum TLS version to negotiate with the endpoint (null = no change, Some(None) = remove)
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "double_option")]
+    pub tls_min_version: Option<Option<TlsVersion>>,
+    /// Seconds between automatic model synchronizations (null = no change, Some(None) = only sync on demand)
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "double_option")]
+    pub sync_interval_seconds: Option<Option<i32>>,
+    /// Enable or disable routing to all of the endpoint's models without deleting them (null = no change)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -121,10 +229,19 @@ pub enum InferenceEndpointValidate {
     New {
         url: String,
         api_key: Option<String>,
-        /// The name of the authorization header (defaults to "Authorization")
+        /// The name of the authorization header (defaults to "Authorization", or "api-key" for Azure OpenAI)
         auth_header_name: Option<String>,
-        /// The prefix for the authorization header value (defaults to "Bearer " with trailing space)
+        /// The prefix for the authorization header value (defaults to "Bearer " with trailing space, or none for Azure OpenAI)
         auth_header_prefix: Option<String>,
+        /// PEM bundle of CA certificates to trust for the endpoint
+        #[serde(default)]
+        tls_ca_pem: Option<String>,
+        /// Accept any certificate from the endpoint
+        #[serde(default)]
+        tls_skip_verify: bool,
+        /// Minimum TLS version to negotiate with the endpoint
+        #[serde(default)]
+        tls_min_version: Option<TlsVersion>,
     },
     Existing {
         #[schema(value_type = String, format = "uuid")]
@@ -139,6 +256,591 @@ pub struct InferenceEndpointValidateResponse {
     pub error: Option<String>,
 }
 
+/// Request to run the OpenAI compatibility suite against an endpoint
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct EndpointCompatibilityRun {
+    /// Chat model to run the suite with (defaults to the first model the endpoint lists)
+    pub model: Option<String>,
+}
+
+/// Latest scheduled validation outcome for a single endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EndpointValidationStatus {
+    #[schema(value_type = String, format = "uuid")]
+    pub endpoint_id: InferenceEndpointId,
+    pub endpoint_name: String,
+    pub url: String,
+    /// When the endpoint was last validated (None if it has never been validated)
+    pub last_validated_at: Option<DateTime<Utc>>,
+    /// When the endpoint last validated successfully
+    pub last_success_at: Option<DateTime<Utc>>,
+    /// Outcome of the most recent validation
+    pub success: Option<bool>,
+    pub status_code: Option<i32>,
+    pub error_message: Option<String>,
+    pub model_count: Option<i32>,
+    /// Whether the most recent validation was rejected with HTTP 401/403
+    pub credentials_expired: bool,
+}
+
+/// Summary of the latest scheduled validation across all endpoints
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EndpointValidationReport {
+    pub generated_at: DateTime<Utc>,
+    pub total_endpoints: usize,
+    pub healthy: usize,
+    pub failing: usize,
+    pub credentials_expired: usize,
+    pub never_validated: usize,
+    pub endpoints: Vec<EndpointValidationStatus>,
+}
+
+impl From<Vec<EndpointValidationStatus>> for EndpointValidationReport {
+    fn from(endpoints: Vec<EndpointValidationStatus>) -> Self {
+        Self {
+            generated_at: Utc::now(),
+            total_endpoints: endpoints.len(),
+            healthy: endpoints.iter().filter(|e| e.success == Some(true)).count(),
+            failing: endpoints.iter().filter(|e| e.success == Some(false)).count(),
+            credentials_expired: endpoints.iter().filter(|e| e.credentials_expired).count(),
+            never_validated: endpoints.iter().filter(|e| e.success.is_none()).count(),
+            endpoints,
+        }
+    }
+}
+
+/// Category of personally identifiable information masked by a redaction policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionEntity {
+    Email,
+    Phone,
+    /// Payment card numbers and IBANs
+    Financial,
+}
+
+impl From<RedactionEntity> for PiiCategory {
+    fn from(entity: RedactionEntity) -> Self {
+        match entity {
+            RedactionEntity::Email => PiiCategory::Email,
+            RedactionEntity::Phone => PiiCategory::Phone,
+            RedactionEntity::Financial => PiiCategory::Financial,
+        }
+    }
+}
+
+impl From<PiiCategory> for RedactionEntity {
+    fn from(category: PiiCategory) -> Self {
+        match category {
+            PiiCategory::Email => RedactionEntity::Email,
+            PiiCategory::Phone => RedactionEntity::Phone,
+            PiiCategory::Financial => RedactionEntity::Financial,
+        }
+    }
+}
+
+/// Redaction policy of an endpoint.
+///
+/// Before a request is forwarded to the endpoint, the entities and the matches of `patterns`
+/// in its prompts are replaced with placeholders such as `[REDACTED_EMAIL]`. The request log
+/// keeps the original request only if `log_original` is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct EndpointRedactionPolicy {
+    #[serde(default)]
+    pub entities: Vec<RedactionEntity>,
+    /// Regular expressions whose matches are masked
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Whether the request log may keep the original, unredacted request
+    #[serde(default)]
+    pub log_original: bool,
+}
+
+impl From<EndpointRedactionDBResponse> for EndpointRedactionPolicy {
+    fn from(db: EndpointRedactionDBResponse) -> Self {
+        Self {
+            entities: db
+                .entities
+                .iter()
+                .filter_map(|entity| PiiCategory::parse(entity))
+                .map(RedactionEntity::from)
+                .collect(),
+            patterns: db.patterns,
+            log_original: db.log_original,
+        }
+    }
+}
+
+impl From<EndpointRedactionPolicy> for EndpointRedactionUpdateDBRequest {
+    fn from(policy: EndpointRedactionPolicy) -> Self {
+        Self {
+            entities: policy
+                .entities
+                .into_iter()
+                .map(|entity| PiiCategory::from(entity).as_str().to_string())
+                .collect(),
+            patterns: policy.patterns,
+            log_original: policy.log_original,
+        }
+    }
+}
+
+/// Header rules of an endpoint.
+///
+/// The `inject` headers are added to every request forwarded to the endpoint, replacing any
+/// client header of the same name. If `passthrough` is set, only the listed client headers (and
+/// those the proxy needs, such as `content-type`) are forwarded; otherwise all of them are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct EndpointHeaderRules {
+    /// Headers to inject, by name
+    #[serde(default)]
+    pub inject: BTreeMap<String, String>,
+    /// Client headers to forward, or null to forward all of them
+    #[serde(default)]
+    pub passthrough: Option<Vec<String>>,
+}
+
+impl EndpointHeaderRules {
+    /// The rules with header names lowercased, as they're matched and stored
+    pub fn normalized(self) -> Self {
+        Self {
+            inject: self
+                .inject
+                .into_iter()
+                .map(|(name, value)| (name.to_ascii_lowercase(), value))
+                .collect(),
+            passthrough: self
+                .passthrough
+                .map(|names| names.iter().map(|name| name.to_ascii_lowercase()).collect()),
+        }
+    }
+}
+
+impl From<EndpointHeaderRulesDBResponse> for EndpointHeaderRules {
+    fn from(db: EndpointHeaderRulesDBResponse) -> Self {
+        Self {
+            inject: serde_json::from_value(db.inject).unwrap_or_default(),
+            passthrough: db.passthrough,
+        }
+    }
+}
+
+impl From<EndpointHeaderRules> for EndpointHeaderRulesUpdateDBRequest {
+    fn from(rules: EndpointHeaderRules) -> Self {
+        Self {
+            inject: serde_json::to_value(rules.inject).unwrap_or_default(),
+            passthrough: rules.passthrough,
+        }
+    }
+}
+
+/// Upstream rate limits of an endpoint.
+///
+/// Requests forwarded to the endpoint, across all the deployments it serves, are refused with a
+/// 429 once they'd exceed `requests_per_second` (allowing `burst_size` at once) or while
+/// `max_concurrent` requests are already in flight. The limits are totals across replicas, each
+/// of which enforces an even share of them. Unset limits aren't enforced.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct EndpointRateLimits {
+    /// Requests per second forwarded to the endpoint
+    #[serde(default)]
+    pub requests_per_second: Option<f32>,
+    /// Requests that may be forwarded at once before the rate applies, defaulting to the rate
+    #[serde(default)]
+    pub burst_size: Option<i32>,
+    /// Requests that may be in flight to the endpoint at once
+    #[serde(default)]
+    pub max_concurrent: Option<i32>,
+}
+
+impl EndpointRateLimits {
+    /// Why the limits can't be enforced, if they can't
+    pub fn validate(&self) -> Result<(), String> {
+        if self.requests_per_second.is_some_and(|rps| !rps.is_finite() || rps <= 0.0) {
+            return Err("requests_per_second must be greater than 0".to_string());
+        }
+        if self.burst_size.is_some_and(|burst| burst <= 0) {
+            return Err("burst_size must be greater than 0".to_string());
+        }
+        if self.burst_size.is_some() && self.requests_per_second.is_none() {
+            return Err("burst_size requires requests_per_second".to_string());
+        }
+        if self.max_concurrent.is_some_and(|max| max <= 0) {
+            return Err("max_concurrent must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl From<EndpointRateLimitsDBResponse> for EndpointRateLimits {
+    fn from(db: EndpointRateLimitsDBResponse) -> Self {
+        Self {
+            requests_per_second: db.requests_per_second,
+            burst_size: db.burst_size,
+            max_concurrent: db.max_concurrent,
+        }
+    }
+}
+
+impl From<EndpointRateLimits> for EndpointRateLimitsUpdateDBRequest {
+    fn from(limits: EndpointRateLimits) -> Self {
+        Self {
+            requests_per_second: limits.requests_per_second,
+            burst_size: limits.burst_size,
+            max_concurrent: limits.max_concurrent,
+        }
+    }
+}
+
+/// What syncing an endpoint does with deployments of models no longer listed upstream
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RemovedModelAction {
+    /// Marked inactive, and reactivated if the model is listed again
+    #[default]
+    Disable,
+    /// Deleted, as if by an admin
+    Delete,
+    /// Left as they are, still routable
+    Keep,
+}
+
+impl RemovedModelAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RemovedModelAction::Disable => "disable",
+            RemovedModelAction::Delete => "delete",
+            RemovedModelAction::Keep => "keep",
+        }
+    }
+
+    pub fn parse(action: &str) -> Option<Self> {
+        match action {
+            "disable" => Some(RemovedModelAction::Disable),
+            "delete" => Some(RemovedModelAction::Delete),
+            "keep" => Some(RemovedModelAction::Keep),
+            _ => None,
+        }
+    }
+}
+
+/// What syncing an endpoint does with models newly listed upstream
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NewModelAction {
+    /// Deployed under their own name
+    #[default]
+    AutoAdd,
+    /// Queued until an admin approves or rejects them
+    Queue,
+}
+
+impl NewModelAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NewModelAction::AutoAdd => "auto_add",
+            NewModelAction::Queue => "queue",
+        }
+    }
+
+    pub fn parse(action: &str) -> Option<Self> {
+        match action {
+            "auto_add" => Some(NewModelAction::AutoAdd),
+            "queue" => Some(NewModelAction::Queue),
+            _ => None,
+        }
+    }
+}
+
+/// How syncing an endpoint treats changes to its upstream model list. Models left out by the
+/// endpoint's model filter are deleted regardless.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct EndpointSyncStrategy {
+    #[serde(default)]
+    pub removed_models: RemovedModelAction,
+    #[serde(default)]
+    pub new_models: NewModelAction,
+}
+
+impl From<EndpointSyncStrategyDBResponse> for EndpointSyncStrategy {
+    fn from(db: EndpointSyncStrategyDBResponse) -> Self {
+        Self {
+            removed_models: RemovedModelAction::parse(&db.removed_models).unwrap_or_default(),
+            new_models: NewModelAction::parse(&db.new_models).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<EndpointSyncStrategy> for EndpointSyncStrategyUpdateDBRequest {
+    fn from(strategy: EndpointSyncStrategy) -> Self {
+        Self {
+            removed_models: strategy.removed_models.as_str().to_string(),
+            new_models: strategy.new_models.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+pub struct PendingModelsQuery {
+    /// Also list rejected models
+    #[param(default = false)]
+    pub include_rejected: Option<bool>,
+}
+
+/// A model found upstream by a sync of an endpoint that queues new models
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EndpointPendingModel {
+    pub model_name: String,
+    /// Whether the model awaits approval, or was rejected
+    pub rejected: bool,
+    pub discovered_at: DateTime<Utc>,
+    pub rejected_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub rejected_by: Option<UserId>,
+}
+
+impl From<EndpointPendingModelDBResponse> for EndpointPendingModel {
+    fn from(db: EndpointPendingModelDBResponse) -> Self {
+        Self {
+            model_name: db.model_name,
+            rejected: db.status == "rejected",
+            discovered_at: db.discovered_at,
+            rejected_at: db.rejected_at,
+            rejected_by: db.rejected_by,
+        }
+    }
+}
+
+/// Queued models to approve or reject, by name
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PendingModelsDecision {
+    pub model_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+pub struct EndpointHistoryQuery {
+    /// Maximum number of versions to return, newest first
+    #[param(default = 100, minimum = 1, maximum = 1000)]
+    pub limit: Option<i64>,
+}
+
+/// The alias of each of an endpoint's deployments, by model name
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct EndpointAliases {
+    /// Alias of each model deployed on the endpoint. When setting aliases, models left out are
+    /// served under their own name.
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// A version of an endpoint's configuration: its settings, except its API key, and the aliases
+/// of its deployments
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EndpointConfigVersion {
+    #[schema(value_type = String, format = "uuid")]
+    pub endpoint_id: InferenceEndpointId,
+    pub version: i32,
+    pub config: EndpointConfig,
+    /// Settings changed since the previous version, with alias changes named `aliases.<model name>`
+    /// (every setting, for the first version)
+    pub changes: Vec<String>,
+    /// User who made the change (null if they have since been deleted)
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub changed_by: Option<UserId>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl EndpointConfigVersion {
+    /// Convert up to `limit` versions ordered newest first, each one's changes being worked out
+    /// from the next. Pass one more version than `limit` to have the changes of the oldest one.
+    pub fn from_versions(versions: Vec<EndpointConfigVersionDBResponse>, limit: usize) -> Vec<Self> {
+        let previous: Vec<Option<EndpointConfig>> = versions.iter().skip(1).map(|v| Some(v.config.0.clone())).chain([None]).collect();
+        versions
+            .into_iter()
+            .zip(previous)
+            .take(limit)
+            .map(|(version, previous)| {
+                let config = version.config.0;
+                let changes = match &previous {
+                    Some(previous) => config.changes_since(previous),
+                    None => {
+                        let mut fields: Vec<String> = serde_json::to_value(&config)
+                            .ok()
+                            .and_then(|value| value.as_object().map(|fields| fields.keys().cloned().collect()))
+                            .unwrap_or_default();
+                        fields.sort();
+                        fields
+                    }
+                };
+                Self {
+                    endpoint_id: version.endpoint_id,
+                    version: version.version,
+                    config,
+                    changes,
+                    changed_by: version.changed_by,
+                    created_at: version.created_at,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Endpoints declared as a document, exported and imported as YAML to manage them from version
+/// control
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EndpointManifest {
+    pub endpoints: Vec<EndpointManifestEntry>,
+}
+
+/// An endpoint in a manifest, identified by its name. Settings left out take their default value,
+/// except for the API key: it is never exported, and an endpoint's key is kept when left out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EndpointManifestEntry {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub url: String,
+    /// API key to set for the endpoint (only read on import)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Models to deploy from the endpoint (if left out, every model it serves)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_filter: Option<Vec<String>>,
+    /// The name of the authorization header (defaults to "Authorization", or "api-key" for Azure OpenAI)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_header_name: Option<String>,
+    /// The prefix for the authorization header value (defaults to "Bearer ", or none for Azure OpenAI)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_header_prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_ca_pem: Option<String>,
+    #[serde(default)]
+    pub tls_skip_verify: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_min_version: Option<TlsVersion>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_interval_seconds: Option<i32>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Alias of the endpoint's models, by model name. Models left out keep their current alias,
+    /// or are served under their own name when first deployed.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl From<EndpointConfig> for EndpointManifestEntry {
+    fn from(config: EndpointConfig) -> Self {
+        Self {
+            name: config.name,
+            description: config.description,
+            url: config.url,
+            api_key: None,
+            model_filter: config.model_filter,
+            auth_header_name: Some(config.auth_header_name),
+            auth_header_prefix: Some(config.auth_header_prefix),
+            tls_ca_pem: config.tls_ca_pem,
+            tls_skip_verify: config.tls_skip_verify,
+            tls_min_version: config.tls_min_version.as_deref().and_then(TlsVersion::parse),
+            sync_interval_seconds: config.sync_interval_seconds,
+            enabled: config.enabled,
+            aliases: config.aliases,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EndpointImportQuery {
+    /// Report the changes the import would make without making them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// What an import does to an endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointImportAction {
+    Create,
+    Update,
+    Unchanged,
+}
+
+/// A setting changed by an import, with alias changes named `aliases.<model name>`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EndpointSettingChange {
+    pub setting: String,
+    /// Current value (null if unset)
+    #[schema(value_type = Option<Object>)]
+    pub from: Option<serde_json::Value>,
+    /// Imported value (null if unset)
+    #[schema(value_type = Option<Object>)]
+    pub to: Option<serde_json::Value>,
+}
+
+impl EndpointSettingChange {
+    /// The settings of `imported` that differ from `current`, or all of its settings for a new endpoint
+    pub fn between(current: Option<&EndpointConfig>, imported: &EndpointConfig) -> Vec<Self> {
+        let settings = match current {
+            Some(current) => imported.changes_since(current),
+            None => {
+                let mut settings: Vec<String> = serde_json::to_value(imported)
+                    .ok()
+                    .and_then(|value| {
+                        value.as_object().map(|fields| {
+                            fields
+                                .iter()
+                                .filter(|(field, value)| *field != "aliases" && !value.is_null())
+                                .map(|(field, _)| field.clone())
+                                .collect()
+                        })
+                    })
+                    .unwrap_or_default();
+                settings.sort();
+                settings.extend(imported.aliases.keys().map(|model| format!("aliases.{model}")));
+                settings
+            }
+        };
+        settings
+            .into_iter()
+            .map(|setting| Self {
+                from: current.and_then(|current| setting_value(current, &setting)),
+                to: setting_value(imported, &setting),
+                setting,
+            })
+            .collect()
+    }
+}
+
+fn setting_value(config: &EndpointConfig, setting: &str) -> Option<serde_json::Value> {
+    match setting.strip_prefix("aliases.") {
+        Some(model) => config.aliases.get(model).cloned().map(serde_json::Value::String),
+        None => serde_json::to_value(config)
+            .ok()?
+            .get(setting)
+            .filter(|value| !value.is_null())
+            .cloned(),
+    }
+}
+
+/// The changes an import makes to an endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EndpointImportChange {
+    pub name: String,
+    pub action: EndpointImportAction,
+    pub changes: Vec<EndpointSettingChange>,
+}
+
+/// The outcome of an import, in the order of the manifest. Endpoints missing from the manifest are
+/// left as they are.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EndpointImportResult {
+    /// Whether the changes were only reported, rather than made
+    pub dry_run: bool,
+    pub endpoints: Vec<EndpointImportChange>,
+}
+
 // Response model
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct InferenceEndpointResponse {
@@ -151,10 +853,28 @@ pub struct InferenceEndpointResponse {
     pub requires_api_key: bool,
     pub auth_header_name: String,
     pub auth_header_prefix: String,
+    /// PEM bundle of CA certificates trusted for the endpoint, in addition to the built-in roots
+    pub tls_ca_pem: Option<String>,
+    /// Whether any certificate from the endpoint is accepted
+    pub tls_skip_verify: bool,
+    /// Minimum TLS version negotiated with the endpoint
+    pub tls_min_version: Option<TlsVersion>,
+    /// Seconds between automatic model synchronizations (null if the endpoint only syncs on demand)
+    pub sync_interval_seconds: Option<i32>,
+    /// When the endpoint's models were last synchronized, automatically or on demand
+    pub last_sync_at: Option<DateTime<Utc>>,
+    /// Error from the last synchronization, if it failed
+    pub last_sync_error: Option<String>,
+    /// Whether the endpoint's models are routable. Disabling an endpoint keeps its models, aliases
+    /// and group assignments, but removes them from the AI proxy and pauses their probes.
+    pub enabled: bool,
     #[schema(value_type = String, format = "uuid")]
     pub created_by: UserId,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Combined health of the endpoint's deployments, from their recent probe results (null if
+    /// none of them have been probed)
+    pub health: Option<Health>,
 }
 
 impl From<InferenceEndpointDBResponse> for InferenceEndpointResponse {
@@ -168,9 +888,25 @@ impl From<InferenceEndpointDBResponse> for InferenceEndpointResponse {
             requires_api_key: db.api_key.is_some() && !db.api_key.as_ref().unwrap().is_empty(),
             auth_header_name: db.auth_header_name,
             auth_header_prefix: db.auth_header_prefix,
+            tls_min_version: db.tls_min_version.as_deref().and_then(TlsVersion::parse),
+            tls_ca_pem: db.tls_ca_pem,
+            tls_skip_verify: db.tls_skip_verify,
+            sync_interval_seconds: db.sync_interval_seconds,
+            last_sync_at: db.last_sync_at,
+            last_sync_error: db.last_sync_error,
+            enabled: db.enabled,
             created_by: db.created_by,
             created_at: db.created_at,
             updated_at: db.updated_at,
+            health: None,
         }
     }
 }
+
+impl InferenceEndpointResponse {
+    /// Create a response with health included
+    pub fn with_health(mut self, health: Option<Health>) -> Self {
+        self.health = health;
+        self
+    }
+}
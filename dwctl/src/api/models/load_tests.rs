@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Request payload for starting a load test.
+///
+/// Every limit is checked against the server's `load_testing` caps; requests
+/// that exceed them are rejected rather than clamped.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LoadTestCreate {
+    /// Deployment (model) to send traffic to
+    #[schema(value_type = String, format = "uuid")]
+    pub deployment_id: Uuid,
+    /// Number of requests to send
+    pub total_requests: u32,
+    /// Maximum number of requests in flight at once (defaults to 1)
+    pub concurrency: Option<u32>,
+    /// Maximum rate at which requests are started (defaults to the server's cap)
+    pub requests_per_second: Option<u32>,
+    /// Stop the load test after this many seconds (defaults to the server's cap)
+    pub max_duration_seconds: Option<u32>,
+    /// Custom JSON request body. The model field is always set to the deployment's alias.
+    /// Defaults to a minimal request for the deployment's model type.
+    pub request_body: Option<serde_json::Value>,
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The log levels in effect
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LogLevelsResponse {
+    /// `tracing` directives, e.g. `info,dwctl::auth=debug`
+    pub directives: String,
+}
+
+/// Request payload for replacing the log levels
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LogLevelsUpdate {
+    /// `tracing` directives, e.g. `info,dwctl::auth=debug`
+    pub directives: String,
+}
@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Request payload for scheduling a maintenance window. Exactly one of `endpoint_id` and
+/// `deployment_id` must be set.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MaintenanceWindowCreate {
+    pub name: String,
+    /// Endpoint under maintenance, covering every deployment hosted on it
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub endpoint_id: Option<Uuid>,
+    /// Deployment under maintenance
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub deployment_id: Option<Uuid>,
+    #[schema(value_type = String, format = "date-time")]
+    pub starts_at: DateTime<Utc>,
+    /// When the window ends, after `starts_at`
+    #[schema(value_type = String, format = "date-time")]
+    pub ends_at: DateTime<Utc>,
+    pub reason: Option<String>,
+}
+
+/// Request payload for updating a maintenance window, e.g. to extend or end it early
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct MaintenanceWindowUpdate {
+    pub name: Option<String>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub starts_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub ends_at: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+/// Query parameters for listing maintenance windows
+#[derive(Debug, Default, Deserialize, IntoParams, ToSchema)]
+pub struct MaintenanceWindowsQuery {
+    /// Only list windows of this endpoint
+    pub endpoint_id: Option<Uuid>,
+    /// Only list windows of this deployment
+    pub deployment_id: Option<Uuid>,
+    /// Only list windows that haven't ended yet
+    #[serde(default)]
+    pub upcoming: bool,
+}
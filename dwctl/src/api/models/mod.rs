@@ -1,8 +1,19 @@
+pub mod adoption;
 pub mod api_keys;
 pub mod auth;
+pub mod bootstrap;
 pub mod deployments;
+pub mod federation;
 pub mod groups;
 pub mod inference_endpoints;
+pub mod load_tests;
+pub mod logging;
+pub mod maintenance_windows;
+pub mod notification_channels;
+pub mod pagination;
 pub mod probes;
+pub mod regression_suites;
 pub mod requests;
+pub mod slos;
+pub mod sorting;
 pub mod users;
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// How a notification channel delivers alerts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelType {
+    /// POST the alert as JSON to a URL
+    Webhook,
+    /// Post the alert's message to a Slack incoming webhook URL
+    Slack,
+    /// Email the alert to an address
+    Email,
+}
+
+impl ChannelType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelType::Webhook => "webhook",
+            ChannelType::Slack => "slack",
+            ChannelType::Email => "email",
+        }
+    }
+
+    pub fn parse(channel_type: &str) -> Option<Self> {
+        match channel_type {
+            "webhook" => Some(ChannelType::Webhook),
+            "slack" => Some(ChannelType::Slack),
+            "email" => Some(ChannelType::Email),
+            _ => None,
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Request payload for creating a notification channel
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NotificationChannelCreate {
+    pub name: String,
+    pub channel_type: ChannelType,
+    /// Webhook URL for webhook and Slack channels, email address for email channels
+    pub destination: String,
+    /// Whether alerts are sent to the channel (defaults to true)
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// Request payload for updating a notification channel
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct NotificationChannelUpdate {
+    pub name: Option<String>,
+    /// New destination, valid for the channel's type
+    pub destination: Option<String>,
+    pub enabled: Option<bool>,
+}
@@ -0,0 +1,96 @@
+use axum::http::{HeaderMap, HeaderValue};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::models::sorting::{SortField, SortOrder};
+use crate::errors::Error;
+
+/// Response header carrying the cursor of the next page of lists returned as bare arrays
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+/// Position in a sorted list after which the next page starts.
+///
+/// Cursors are handed to clients as opaque strings. Besides the last item's sort value and ID,
+/// they carry the sort they were issued for, so following a cursor keeps the list's order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListCursor<F, K = Uuid> {
+    pub sort: F,
+    pub order: SortOrder,
+    /// Sort column value of the last item, as text (None if it was null)
+    pub value: Option<String>,
+    /// ID of the last item, which breaks ties between equal sort values
+    pub id: K,
+}
+
+impl<F: SortField, K: Serialize + DeserializeOwned> ListCursor<F, K> {
+    pub fn encode(&self) -> String {
+        general_purpose::URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("cursors serialize to JSON"))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, Error> {
+        general_purpose::URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(|| Error::BadRequest {
+                message: "Invalid cursor".to_string(),
+            })
+    }
+
+    /// Cursor of the page after `items`, or None if `items` is the last page
+    pub fn next<T>(items: &[T], limit: i64, (sort, order): (F, SortOrder), key: impl Fn(&T) -> (Option<String>, K)) -> Option<String> {
+        if (items.len() as i64) < limit {
+            return None;
+        }
+        let (value, id) = key(items.last()?);
+        Some(Self { sort, order, value, id }.encode())
+    }
+}
+
+/// Cursor value of a timestamp column, at the microsecond precision Postgres stores
+pub fn timestamp_value(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Headers of a list response returned as a bare array, pointing at the next page if there is one
+pub fn next_cursor_headers(next_cursor: Option<String>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(value) = next_cursor.and_then(|cursor| HeaderValue::from_str(&cursor).ok()) {
+        headers.insert(NEXT_CURSOR_HEADER, value);
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::{groups::GroupSortField, users::UserSortField};
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = ListCursor {
+            sort: UserSortField::Email,
+            order: SortOrder::Desc,
+            value: Some("alice@example.com".to_string()),
+            id: Uuid::new_v4(),
+        };
+        assert_eq!(ListCursor::decode(&cursor.encode()).unwrap(), cursor);
+
+        // Cursors only decode for lists with the same sort columns
+        assert!(ListCursor::<GroupSortField>::decode(&cursor.encode()).is_err());
+        assert!(ListCursor::<UserSortField>::decode("not a cursor").is_err());
+    }
+
+    #[test]
+    fn test_next_cursor_only_for_full_pages() {
+        let items = [1, 2, 3];
+        let key = |item: &i32| (Some(item.to_string()), Uuid::nil());
+        let sort = (UserSortField::CreatedAt, SortOrder::Asc);
+        assert!(ListCursor::next(&items, 4, sort, key).is_none());
+
+        let next = ListCursor::<UserSortField>::decode(&ListCursor::next(&items, 3, sort, key).unwrap()).unwrap();
+        assert_eq!(next.value.as_deref(), Some("3"));
+    }
+}
@@ -23,6 +23,109 @@ pub struct CreateProbe {
     pub request_path: Option<String>,
     /// JSON body to send with the probe request
     pub request_body: Option<serde_json::Value>,
+    /// What the probe checks (defaults to `http`)
+    #[serde(default)]
+    pub probe_type: ProbeType,
+    /// Number of dimensions each embedding must have (embeddings probes only)
+    pub expected_dimensions: Option<i32>,
+    /// Fail responses slower than this many milliseconds
+    pub max_latency_ms: Option<i32>,
+    /// Turns to play (conversation probes only, defaults to a two-turn recall check)
+    pub conversation: Option<Vec<ConversationTurn>>,
+    /// Maximum random delay before each execution, in seconds (defaults to `probes.jitter`)
+    pub jitter_seconds: Option<i32>,
+    /// Timeout for each request, in seconds (defaults to `probes.request_timeout`)
+    pub timeout_seconds: Option<i32>,
+    /// Extra checks on the response, each recorded with the result
+    pub assertions: Option<Vec<ProbeAssertion>>,
+    /// Answer the deployment's reply is compared to (semantic similarity probes only)
+    pub golden_answer: Option<String>,
+    /// Deployment that embeds the reply and the golden answer (semantic similarity probes only)
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub embeddings_deployment_id: Option<Uuid>,
+    /// Minimum cosine similarity between the reply and the golden answer (defaults to 0.8)
+    pub similarity_threshold: Option<f64>,
+    /// Requests kept in flight (load probes only, defaults to 4)
+    pub load_concurrency: Option<i32>,
+    /// Seconds to send requests for (load probes only, defaults to 10)
+    pub load_duration_seconds: Option<i32>,
+}
+
+/// What a probe checks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeType {
+    /// The request succeeds with a 2xx status and a JSON body without an error. The payload is
+    /// chosen by the deployment's model type.
+    #[default]
+    Http,
+    /// `/v1/embeddings` returns one non-empty numeric vector per input, all of the same (and, if
+    /// set, the expected) number of dimensions
+    Embeddings,
+    /// A chat completion offered a tool schema answers with a call to one of the tools, whose
+    /// arguments are a JSON object with the tool's required parameters
+    ToolCall,
+    /// A scripted multi-turn chat, sending the whole history with each user message, gets replies
+    /// that contain the expected content on every turn
+    Conversation,
+    /// A chat completion's reply is semantically close to a golden answer: the cosine similarity of
+    /// their embeddings, from another deployment, is at least a threshold
+    SemanticSimilarity,
+    /// Run on demand only: a number of concurrent requests are kept in flight for a bounded
+    /// duration, and the throughput and latency distribution are reported. Fails if too many
+    /// requests fail, or if the 95th percentile latency is over the latency limit.
+    Load,
+}
+
+impl ProbeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProbeType::Http => "http",
+            ProbeType::Embeddings => "embeddings",
+            ProbeType::ToolCall => "tool_call",
+            ProbeType::Conversation => "conversation",
+            ProbeType::SemanticSimilarity => "semantic_similarity",
+            ProbeType::Load => "load",
+        }
+    }
+
+    pub fn parse(probe_type: &str) -> Option<Self> {
+        match probe_type {
+            "http" => Some(ProbeType::Http),
+            "embeddings" => Some(ProbeType::Embeddings),
+            "tool_call" => Some(ProbeType::ToolCall),
+            "conversation" => Some(ProbeType::Conversation),
+            "semantic_similarity" => Some(ProbeType::SemanticSimilarity),
+            "load" => Some(ProbeType::Load),
+            _ => None,
+        }
+    }
+}
+
+/// One user message of a conversation probe, and what the reply to it must contain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ConversationTurn {
+    /// Message sent as the user, after the previous turns and their replies
+    pub content: String,
+    /// Strings the assistant's reply must contain, ignoring case
+    #[serde(default)]
+    pub expect_contains: Vec<String>,
+}
+
+/// A check on a probe's response. Content is the first choice's message content (or text, for
+/// completions); for conversation probes, assertions apply to the last turn's response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProbeAssertion {
+    /// The response body has a value at a JSON path such as `$.choices[0].message.content`. Supports
+    /// dotted keys, `['key']` and array indexes.
+    JsonPathExists { path: String },
+    /// The content matches a regular expression
+    Regex { pattern: String },
+    /// The response arrived within this many milliseconds
+    MaxLatencyMs { max_ms: u64 },
+    /// The response's `usage.completion_tokens` is at least this many
+    MinTokens { min_tokens: u64 },
 }
 
 fn default_http_method() -> String {
@@ -30,7 +133,7 @@ fn default_http_method() -> String {
 }
 
 /// Request payload for testing a probe configuration
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct TestProbeRequest {
     /// HTTP method to use for the test request
     pub http_method: Option<String>,
@@ -38,6 +141,29 @@ pub struct TestProbeRequest {
     pub request_path: Option<String>,
     /// JSON body to send with the test request
     pub request_body: Option<serde_json::Value>,
+    /// What the test checks (defaults to `http`)
+    pub probe_type: Option<ProbeType>,
+    /// Number of dimensions each embedding must have (embeddings probes only)
+    pub expected_dimensions: Option<i32>,
+    /// Fail responses slower than this many milliseconds
+    pub max_latency_ms: Option<i32>,
+    /// Turns to play (conversation probes only)
+    pub conversation: Option<Vec<ConversationTurn>>,
+    /// Timeout for each request, in seconds (defaults to `probes.request_timeout`)
+    pub timeout_seconds: Option<i32>,
+    /// Extra checks on the response
+    pub assertions: Option<Vec<ProbeAssertion>>,
+    /// Answer the deployment's reply is compared to (semantic similarity probes only)
+    pub golden_answer: Option<String>,
+    /// Deployment that embeds the reply and the golden answer (semantic similarity probes only)
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub embeddings_deployment_id: Option<Uuid>,
+    /// Minimum cosine similarity between the reply and the golden answer (defaults to 0.8)
+    pub similarity_threshold: Option<f64>,
+    /// Requests kept in flight (load probes only, defaults to 4)
+    pub load_concurrency: Option<i32>,
+    /// Seconds to send requests for (load probes only, defaults to 10)
+    pub load_duration_seconds: Option<i32>,
 }
 
 /// Query parameters for filtering probes
@@ -86,6 +212,31 @@ pub struct UpdateProbeRequest {
     pub request_path: Option<String>,
     /// Update the request body
     pub request_body: Option<serde_json::Value>,
+    /// Update what the probe checks
+    pub probe_type: Option<ProbeType>,
+    /// Update the number of dimensions each embedding must have
+    pub expected_dimensions: Option<i32>,
+    /// Update the latency limit in milliseconds
+    pub max_latency_ms: Option<i32>,
+    /// Update the turns of a conversation probe
+    pub conversation: Option<Vec<ConversationTurn>>,
+    /// Update the maximum random delay before each execution, in seconds
+    pub jitter_seconds: Option<i32>,
+    /// Update the request timeout, in seconds
+    pub timeout_seconds: Option<i32>,
+    /// Replace the probe's assertions
+    pub assertions: Option<Vec<ProbeAssertion>>,
+    /// Update the golden answer of a semantic similarity probe
+    pub golden_answer: Option<String>,
+    /// Update the deployment that embeds the reply and the golden answer
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub embeddings_deployment_id: Option<Uuid>,
+    /// Update the minimum cosine similarity
+    pub similarity_threshold: Option<f64>,
+    /// Update the requests a load probe keeps in flight
+    pub load_concurrency: Option<i32>,
+    /// Update how long a load probe sends requests for
+    pub load_duration_seconds: Option<i32>,
 }
 
 /// Aggregated statistics for a probe over a time period.
@@ -114,6 +265,14 @@ pub struct ProbeStatistics {
     pub p95_response_time_ms: Option<f64>,
     /// 99th percentile response time
     pub p99_response_time_ms: Option<f64>,
+    /// Average time to the first token of streamed completions, in milliseconds
+    pub avg_time_to_first_token_ms: Option<f64>,
+    /// 50th percentile (median) time to the first token
+    pub p50_time_to_first_token_ms: Option<f64>,
+    /// 95th percentile time to the first token
+    pub p95_time_to_first_token_ms: Option<f64>,
+    /// Average tokens per second generated after the first, for streamed completions
+    pub avg_tokens_per_second: Option<f64>,
     /// Timestamp of the most recent execution
     #[schema(value_type = Option<String>, format = "date-time")]
     pub last_execution: Option<DateTime<Utc>>,
@@ -138,9 +297,45 @@ impl Default for ProbeStatistics {
             p50_response_time_ms: None,
             p95_response_time_ms: None,
             p99_response_time_ms: None,
+            avg_time_to_first_token_ms: None,
+            p50_time_to_first_token_ms: None,
+            p95_time_to_first_token_ms: None,
+            avg_tokens_per_second: None,
             last_execution: None,
             last_success: None,
             last_failure: None,
         }
     }
 }
+
+/// How a deployment or endpoint has fared in its recent probe results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// None of the recent results failed
+    Healthy,
+    /// Some, but not all, of the recent results failed
+    Degraded,
+    /// All of the recent results failed
+    Down,
+}
+
+/// Health derived from recent probe results. Load probes, which only run on demand, aren't counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Health {
+    pub status: HealthStatus,
+    /// When the most recent of the results was taken
+    #[schema(value_type = String, format = "date-time")]
+    pub last_checked: DateTime<Utc>,
+}
+
+impl Health {
+    /// Combine the health of several deployments, e.g. those hosted on an endpoint: down if all are
+    /// down, healthy if all are healthy, and degraded otherwise. `None` if there's nothing to combine.
+    pub fn combine(healths: impl IntoIterator<Item = Health>) -> Option<Health> {
+        healths.into_iter().reduce(|a, b| Health {
+            status: if a.status == b.status { a.status } else { HealthStatus::Degraded },
+            last_checked: a.last_checked.max(b.last_checked),
+        })
+    }
+}
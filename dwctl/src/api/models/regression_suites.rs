@@ -0,0 +1,38 @@
+use crate::db::models::regression_suites::RegressionCase;
+use serde::{Deserialize, Serialize};
+use serde_with::rust::double_option;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Request payload for creating a regression suite
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RegressionSuiteCreate {
+    /// Deployment (model) to run the suite against
+    #[schema(value_type = String, format = "uuid")]
+    pub deployment_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    /// Prompts to send and the assertions their responses must satisfy
+    pub cases: Vec<RegressionCase>,
+    /// Run the suite automatically this often (at least 60). Leave unset to only run on demand.
+    pub schedule_interval_seconds: Option<u32>,
+}
+
+/// Request payload for updating a regression suite. Runs already stored are not affected.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RegressionSuiteUpdate {
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "double_option")]
+    pub description: Option<Option<String>>,
+    pub cases: Option<Vec<RegressionCase>>,
+    /// New schedule (null = no change, Some(None) = only run on demand)
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "double_option")]
+    pub schedule_interval_seconds: Option<Option<u32>>,
+}
+
+/// Query parameters for listing regression suites
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct RegressionSuitesQuery {
+    /// Only list the suites attached to this deployment
+    pub deployment_id: Option<Uuid>,
+}
@@ -8,7 +8,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use utoipa::{IntoParams, ToSchema};
 
+use crate::api::models::pagination::ListCursor;
+use crate::api::models::sorting::{SortField, SortOrder};
 use crate::request_logging::{AiRequest, AiResponse};
+use crate::types::{GroupId, InferenceEndpointId, UserId};
 
 /// Tagged AI request types for API serialization - provides type discrimination for frontend
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -37,6 +40,9 @@ impl From<&AiRequest> for ApiAiRequest {
             AiRequest::ChatCompletions(req) => ApiAiRequest::ChatCompletions(serde_json::to_value(req).unwrap_or_default()),
             AiRequest::Completions(req) => ApiAiRequest::Completions(serde_json::to_value(req).unwrap_or_default()),
             AiRequest::Embeddings(req) => ApiAiRequest::Embeddings(serde_json::to_value(req).unwrap_or_default()),
+            AiRequest::Messages(req) => ApiAiRequest::Other(serde_json::to_value(req).unwrap_or_default()),
+            // A body that couldn't be fetched from object storage
+            AiRequest::Stored { .. } => ApiAiRequest::Other(serde_json::to_value(ai_request).unwrap_or_default()),
             AiRequest::Other(val) => ApiAiRequest::Other(val.clone()),
         }
     }
@@ -52,6 +58,10 @@ impl From<&AiResponse> for ApiAiResponse {
             AiResponse::Completions(resp) => ApiAiResponse::Completions(serde_json::to_value(resp).unwrap_or_default()),
             AiResponse::Embeddings(resp) => ApiAiResponse::Embeddings(serde_json::to_value(resp).unwrap_or_default()),
             AiResponse::Base64Embeddings(resp) => ApiAiResponse::Embeddings(serde_json::to_value(resp).unwrap_or_default()),
+            AiResponse::Messages(resp) => ApiAiResponse::Other(serde_json::to_value(resp).unwrap_or_default()),
+            AiResponse::MessagesStream(events) => ApiAiResponse::Other(serde_json::to_value(events).unwrap_or_default()),
+            // A body that couldn't be fetched from object storage
+            AiResponse::Stored { .. } => ApiAiResponse::Other(serde_json::to_value(ai_response).unwrap_or_default()),
             AiResponse::Other(val) => ApiAiResponse::Other(val.clone()),
         }
     }
@@ -85,6 +95,16 @@ pub struct ListRequestsQuery {
     /// Filter by URI pattern (supports SQL LIKE patterns with %)
     pub uri_pattern: Option<String>,
 
+    /// Full-text search over request and response bodies, e.g. `"connection reset" -retry`
+    pub search: Option<String>,
+
+    /// Filter by conversation ID (see the `x-doubleword-conversation-id` request header)
+    pub conversation_id: Option<String>,
+
+    /// Filter by tags, as comma-separated `key=value` pairs that requests must all carry (see the
+    /// `x-doubleword-tags` request header)
+    pub tags: Option<String>,
+
     /// Filter by exact status code
     pub status_code: Option<i32>,
 
@@ -108,6 +128,148 @@ pub struct ListRequestsQuery {
 
     /// Order by timestamp descending (newest first) - default: true
     pub order_desc: Option<bool>,
+
+    /// Column to sort by. Requests can only be sorted by timestamp
+    pub sort: Option<RequestSortField>,
+
+    /// Sort direction, overriding `order_desc` (default: desc)
+    pub order: Option<SortOrder>,
+
+    /// Cursor from `next_cursor` of the previous page. Replaces offset, and keeps the order the
+    /// cursor was issued for
+    pub cursor: Option<String>,
+}
+
+/// Columns the requests list can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestSortField {
+    Timestamp,
+}
+
+impl SortField for RequestSortField {
+    fn column(&self) -> &'static str {
+        match self {
+            RequestSortField::Timestamp => "r.timestamp",
+        }
+    }
+
+    fn column_type(&self) -> &'static str {
+        match self {
+            RequestSortField::Timestamp => "timestamptz",
+        }
+    }
+}
+
+/// Position in the requests list, after a request with a given timestamp and ID
+pub type RequestCursor = ListCursor<RequestSortField, i64>;
+
+/// File format of a request log export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row
+    #[default]
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+    /// Apache Parquet, with the columns of CSV exports (requires the `parquet` feature)
+    Parquet,
+}
+
+/// Query parameters for exporting request logs
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ExportRequestsQuery {
+    /// File format (default: csv)
+    #[serde(default)]
+    pub format: ExportFormat,
+
+    /// Filter by user ID
+    #[schema(value_type = Option<String>, format = "uuid")]
+    #[param(value_type = Option<String>, format = "uuid")]
+    pub user_id: Option<UserId>,
+
+    /// Filter by specific model name
+    pub model: Option<String>,
+
+    /// Export requests after this timestamp
+    pub timestamp_after: Option<DateTime<Utc>>,
+
+    /// Export requests before this timestamp
+    pub timestamp_before: Option<DateTime<Utc>>,
+}
+
+/// A row of a request log export, one per request that transited the gateway
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestExportRecord {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub uri: String,
+    pub model: Option<String>,
+    pub status_code: Option<i32>,
+    pub duration_ms: Option<i64>,
+    pub duration_to_first_byte_ms: Option<i64>,
+    pub prompt_tokens: Option<i64>,
+    pub completion_tokens: Option<i64>,
+    pub total_tokens: Option<i64>,
+    /// Whether the token counts were estimated rather than reported by the upstream
+    pub usage_estimated: bool,
+    pub total_cost: Option<f64>,
+    pub user_id: Option<UserId>,
+    pub user_email: Option<String>,
+    pub access_source: Option<String>,
+    pub response_type: Option<String>,
+    pub variant: Option<String>,
+    /// Current attributes of the user, e.g. cost center, as a JSON object
+    pub user_attributes: Option<Value>,
+}
+
+impl RequestExportRecord {
+    /// Header row of CSV exports, in the order of [`RequestExportRecord::to_csv_row`]
+    pub const CSV_HEADER: &'static str = "id,timestamp,method,uri,model,status_code,duration_ms,duration_to_first_byte_ms,\
+prompt_tokens,completion_tokens,total_tokens,usage_estimated,total_cost,user_id,user_email,access_source,response_type,variant,user_attributes\n";
+
+    /// The record as a CSV row (RFC 4180), with empty fields for missing values
+    pub fn to_csv_row(&self) -> String {
+        fn field<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map(|v| csv_escape(&v.to_string())).unwrap_or_default()
+        }
+
+        let fields = [
+            self.id.to_string(),
+            self.timestamp.to_rfc3339(),
+            csv_escape(&self.method),
+            csv_escape(&self.uri),
+            field(&self.model),
+            field(&self.status_code),
+            field(&self.duration_ms),
+            field(&self.duration_to_first_byte_ms),
+            field(&self.prompt_tokens),
+            field(&self.completion_tokens),
+            field(&self.total_tokens),
+            self.usage_estimated.to_string(),
+            field(&self.total_cost),
+            field(&self.user_id),
+            field(&self.user_email),
+            field(&self.access_source),
+            field(&self.response_type),
+            field(&self.variant),
+            field(&self.user_attributes),
+        ];
+        let mut row = fields.join(",");
+        row.push('\n');
+        row
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
 }
 
 /// API-compatible HTTP request representation
@@ -139,6 +301,8 @@ pub struct HttpResponse {
 pub struct RequestResponsePair {
     pub request: HttpRequest,
     pub response: Option<HttpResponse>,
+    /// Cost of the request at the model's prices when it was made, if priced
+    pub total_cost: Option<f64>,
 }
 
 /// Response containing a list of requests and pagination metadata
@@ -146,6 +310,28 @@ pub struct RequestResponsePair {
 pub struct ListRequestsResponse {
     /// List of HTTP requests
     pub requests: Vec<RequestResponsePair>,
+    /// Cursor of the next page, if there may be one
+    pub next_cursor: Option<String>,
+}
+
+/// Where the bodies of a logged request were read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BodySource {
+    /// The request logging tables
+    Database,
+    /// Object storage, see `body_storage` in the config
+    Archive,
+}
+
+/// A single logged request, with its bodies fetched from wherever they are kept
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RequestDetailResponse {
+    #[serde(flatten)]
+    pub pair: RequestResponsePair,
+    pub body_source: BodySource,
+    /// Time taken to fetch the bodies from the archive, when they were read from it
+    pub archive_fetch_ms: Option<u64>,
 }
 
 impl Default for ListRequestsQuery {
@@ -155,6 +341,9 @@ impl Default for ListRequestsQuery {
             offset: Some(0),
             method: None,
             uri_pattern: None,
+            search: None,
+            conversation_id: None,
+            tags: None,
             status_code: None,
             status_code_min: None,
             status_code_max: None,
@@ -163,6 +352,9 @@ impl Default for ListRequestsQuery {
             timestamp_after: None,
             timestamp_before: None,
             order_desc: Some(true),
+            sort: None,
+            order: None,
+            cursor: None,
         }
     }
 }
@@ -184,6 +376,61 @@ pub struct ModelUsage {
     pub count: i64,
     pub percentage: f64,
     pub avg_latency_ms: f64,
+    pub total_cost: Option<f64>,
+}
+
+/// Utilization of one deployment
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentUsage {
+    /// Deployment alias the requests were made to
+    pub model: String,
+    /// Endpoint currently hosting the deployment (absent if the alias no longer exists)
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub endpoint_id: Option<InferenceEndpointId>,
+    pub endpoint_name: Option<String>,
+    pub request_count: i64,
+    /// Requests answered with a 4xx or 5xx status
+    pub error_count: i64,
+    /// Percentage of requests that errored
+    pub error_rate: f64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_cost: Option<f64>,
+    pub avg_latency_ms: f64,
+    pub p95_latency_ms: f64,
+}
+
+/// Utilization of one inference endpoint, across the deployments it hosts
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EndpointUsage {
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub endpoint_id: Option<InferenceEndpointId>,
+    pub endpoint_name: Option<String>,
+    pub request_count: i64,
+    /// Requests answered with a 4xx or 5xx status
+    pub error_count: i64,
+    /// Percentage of requests that errored
+    pub error_rate: f64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_cost: Option<f64>,
+    pub avg_latency_ms: f64,
+    pub p95_latency_ms: f64,
+}
+
+/// Request volume, tokens, errors and latency per deployment and per endpoint
+///
+/// Requests are attributed to the endpoint hosting their deployment now, so a deployment moved
+/// between endpoints is reported against its current one.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ModelUsageResponse {
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub total_requests: i64,
+    /// Busiest deployments first
+    pub models: Vec<DeploymentUsage>,
+    /// Busiest endpoints first; requests to unknown aliases are grouped under no endpoint
+    pub endpoints: Vec<EndpointUsage>,
 }
 
 /// User usage statistics for a specific model
@@ -211,6 +458,144 @@ pub struct ModelUserUsageResponse {
     pub users: Vec<UserUsage>,
 }
 
+/// Spend for one group on one model
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GroupModelUsage {
+    /// Group the spend is allocated to (absent for requests with no known user)
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub group_id: Option<GroupId>,
+    pub group_name: Option<String>,
+    pub model: String,
+    /// Requests allocated to the group; fractional when members belong to several groups
+    pub request_count: f64,
+    /// Input tokens allocated to the group
+    pub input_tokens: f64,
+    /// Output tokens allocated to the group
+    pub output_tokens: f64,
+    /// Cost allocated to the group
+    pub total_cost: Option<f64>,
+}
+
+/// Chargeback report: spend per group per model
+///
+/// A user's requests, with their tokens and cost, are split evenly between the groups they
+/// belong to, so group requests and costs add up to the overall totals. Users in no explicit group are charged to Everyone.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GroupUsageResponse {
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub total_requests: i64,
+    pub total_cost: Option<f64>,
+    pub groups: Vec<GroupModelUsage>,
+}
+
+/// Spend of the users sharing a value of an attribute, on a model
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttributeModelUsage {
+    /// Value of the attribute (absent for requests with no known user, or whose user doesn't
+    /// have the attribute)
+    pub value: Option<String>,
+    pub model: String,
+    pub request_count: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_cost: Option<f64>,
+}
+
+/// Chargeback report: spend per value of a user attribute (e.g. cost center) per model
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttributeUsageResponse {
+    pub attribute: String,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub total_requests: i64,
+    pub total_cost: Option<f64>,
+    pub values: Vec<AttributeModelUsage>,
+}
+
+/// A range of the request log exported to object storage by the retention policy
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RequestLogArchive {
+    pub id: i64,
+    /// Start of the exported day (inclusive)
+    pub range_start: DateTime<Utc>,
+    /// End of the exported day (exclusive)
+    pub range_end: DateTime<Utc>,
+    /// Key of the object in the export bucket
+    pub object_key: String,
+    /// Object format (`jsonl`: one request and its response per line)
+    pub format: String,
+    pub request_count: i64,
+    pub size_bytes: i64,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// Query parameters for listing exported request log ranges
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListArchivesQuery {
+    /// Only ranges ending after this time
+    pub start_date: Option<DateTime<Utc>>,
+    /// Only ranges starting before this time
+    pub end_date: Option<DateTime<Utc>>,
+}
+
+/// Usage of one conversation
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConversationUsage {
+    /// Client-supplied conversation ID, or one derived from the conversation's opening messages
+    pub conversation_id: String,
+    pub user_email: Option<String>,
+    /// Requests made in the conversation
+    pub turns: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Models used in the conversation
+    pub models: Vec<String>,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_cost: Option<f64>,
+}
+
+/// Usage per conversation, busiest conversations first
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConversationUsageResponse {
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    /// Conversations in the range, including any beyond the returned limit
+    pub total_conversations: i64,
+    /// Requests made in those conversations
+    pub total_turns: i64,
+    pub avg_turns: f64,
+    pub conversations: Vec<ConversationUsage>,
+}
+
+/// Usage of requests carrying one value of a tag
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TagValueUsage {
+    pub value: String,
+    pub requests: i64,
+    /// Distinct users that made the requests
+    pub users: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_cost: Option<f64>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Usage per value of a tag, busiest values first
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TagUsageResponse {
+    /// The tag key usage is grouped by
+    pub key: String,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    /// Requests in the range carrying the tag, including those beyond the returned limit
+    pub tagged_requests: i64,
+    /// Requests in the range without the tag
+    pub untagged_requests: i64,
+    pub values: Vec<TagValueUsage>,
+}
+
 /// Time series data point with combined metrics
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TimeSeriesPoint {
@@ -224,10 +609,150 @@ pub struct TimeSeriesPoint {
     pub p99_latency_ms: Option<f64>,
 }
 
+/// Kind of failure, as told by the status code a request was answered with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorType {
+    /// The upstream didn't answer in time (408, 504)
+    Timeout,
+    /// The upstream couldn't be reached or sent an invalid response (502)
+    Connect,
+    /// Rate limited by the gateway or the upstream (429)
+    RateLimited,
+    /// Other 5xx responses
+    ServerError,
+    /// Other 4xx responses
+    ClientError,
+}
+
+impl ErrorType {
+    /// The kind of failure a status code reports, or `None` for successful responses
+    pub fn from_status(status_code: i32) -> Option<Self> {
+        match status_code {
+            408 | 504 => Some(Self::Timeout),
+            502 => Some(Self::Connect),
+            429 => Some(Self::RateLimited),
+            500..=599 => Some(Self::ServerError),
+            400..=499 => Some(Self::ClientError),
+            _ => None,
+        }
+    }
+}
+
+/// Failures with one status code
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorStatusBreakdown {
+    pub status_code: i32,
+    pub error_type: ErrorType,
+    pub count: i64,
+    /// Percentage of all failures
+    pub percentage: f64,
+}
+
+/// Failures of one kind
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorTypeBreakdown {
+    pub error_type: ErrorType,
+    pub count: i64,
+    /// Percentage of all failures
+    pub percentage: f64,
+}
+
+/// Failures of requests to one deployment
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ModelErrorBreakdown {
+    pub model: String,
+    pub requests: i64,
+    pub errors: i64,
+    /// Percentage of the deployment's requests that failed
+    pub error_rate: f64,
+}
+
+/// Failures of one user's requests
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserErrorBreakdown {
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub user_id: Option<UserId>,
+    pub user_email: Option<String>,
+    pub requests: i64,
+    pub errors: i64,
+    /// Percentage of the user's requests that failed
+    pub error_rate: f64,
+}
+
+/// Breakdown of failed requests (4xx and 5xx responses) for incident triage
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorBreakdownResponse {
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub total_requests: i64,
+    pub total_errors: i64,
+    pub error_rate: f64,
+    /// Most frequent first
+    pub by_status: Vec<ErrorStatusBreakdown>,
+    /// Most frequent first
+    pub by_type: Vec<ErrorTypeBreakdown>,
+    /// Deployments with failures, most failures first
+    pub by_model: Vec<ModelErrorBreakdown>,
+    /// Users with failures, most failures first (at most `user_limit`)
+    pub by_user: Vec<UserErrorBreakdown>,
+}
+
+/// Measure plotted by a usage time series
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageMetric {
+    /// Number of requests
+    #[default]
+    Requests,
+    /// Input and output tokens
+    Tokens,
+    InputTokens,
+    OutputTokens,
+    /// Cost at the prices in effect when each request was made
+    Cost,
+}
+
+/// Query parameters for a usage time series
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct UsageTimeSeriesQuery {
+    /// Width of each bucket, e.g. `10m`, `1h` or `1d` (default: 1h). Buckets are aligned to
+    /// midnight UTC, and weekly buckets to Mondays.
+    pub bucket: Option<String>,
+    /// What to measure (default: requests)
+    #[serde(default)]
+    pub metric: UsageMetric,
+    /// Filter by specific model alias
+    pub model: Option<String>,
+    /// Start date for usage data (defaults to 7 days ago)
+    pub start_date: Option<DateTime<Utc>>,
+    /// End date for usage data (defaults to now)
+    pub end_date: Option<DateTime<Utc>>,
+}
+
+/// One bucket of a usage time series
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UsageTimeSeriesPoint {
+    /// Start of the bucket
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// Usage over time in fixed-width buckets, with empty buckets included as zero
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UsageTimeSeriesResponse {
+    pub metric: UsageMetric,
+    pub bucket_seconds: i64,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub points: Vec<UsageTimeSeriesPoint>,
+}
+
 /// Aggregated request analytics response
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct RequestsAggregateResponse {
     pub total_requests: i64,
+    pub total_cost: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
     pub status_codes: Vec<StatusCodeBreakdown>,
@@ -235,3 +760,100 @@ pub struct RequestsAggregateResponse {
     pub models: Option<Vec<ModelUsage>>,
     pub time_series: Vec<TimeSeriesPoint>,
 }
+
+/// Query parameters for embedding usage analytics
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EmbeddingUsageQuery {
+    /// Filter by specific model alias
+    pub model: Option<String>,
+    /// Start date for usage data (defaults to 30 days ago)
+    pub start_date: Option<DateTime<Utc>>,
+    /// End date for usage data (defaults to now)
+    pub end_date: Option<DateTime<Utc>>,
+}
+
+/// Embedding volume for one model or group on one day
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EmbeddingUsagePoint {
+    /// Start of the day (UTC)
+    pub date: DateTime<Utc>,
+    /// Model alias (when grouped by model)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Group ID (when grouped by group)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub group_id: Option<GroupId>,
+    /// Group name (when grouped by group)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_name: Option<String>,
+    /// Number of embeddings requests
+    pub requests: i64,
+    /// Number of vectors produced
+    pub vectors: i64,
+    /// Input tokens consumed
+    pub input_tokens: i64,
+    /// Average vector dimensionality
+    pub avg_dimensions: Option<f64>,
+}
+
+/// Daily embedding usage, grouped by model or group
+///
+/// Totals count each request once, even when its user belongs to several groups.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EmbeddingUsageResponse {
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub total_requests: i64,
+    pub total_vectors: i64,
+    pub daily: Vec<EmbeddingUsagePoint>,
+}
+
+/// Request payload for starting a token backfill
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct TokenBackfillCreate {
+    /// Only backfill requests logged at or after this time (defaults to the oldest logged request)
+    pub start_date: Option<DateTime<Utc>>,
+    /// Only backfill requests logged at or before this time (defaults to now)
+    pub end_date: Option<DateTime<Utc>>,
+}
+
+/// Query parameters for PII classification statistics
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PiiStatsQuery {
+    /// Start date (defaults to 30 days ago)
+    pub start_date: Option<DateTime<Utc>>,
+    /// End date (defaults to now)
+    pub end_date: Option<DateTime<Utc>>,
+}
+
+/// How often a PII category appeared in a group's requests
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PiiCategoryBreakdown {
+    /// PII category (email, phone or financial)
+    pub category: String,
+    pub count: i64,
+    pub percentage: f64,
+}
+
+/// PII rates for the requests made by members of a group
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GroupPiiStats {
+    #[schema(value_type = String, format = "uuid")]
+    pub group_id: GroupId,
+    pub group_name: String,
+    /// Requests that were classified
+    pub classified_requests: i64,
+    /// Requests containing at least one PII category
+    pub requests_with_pii: i64,
+    pub pii_percentage: f64,
+    pub categories: Vec<PiiCategoryBreakdown>,
+}
+
+/// PII classification statistics per group
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PiiStatsResponse {
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub groups: Vec<GroupPiiStats>,
+}
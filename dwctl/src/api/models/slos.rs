@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// What makes a probe result good
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SloObjective {
+    /// The probe succeeded
+    Availability,
+    /// The probe succeeded within `latency_threshold_ms`
+    Latency,
+}
+
+impl SloObjective {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SloObjective::Availability => "availability",
+            SloObjective::Latency => "latency",
+        }
+    }
+}
+
+fn default_window_days() -> i32 {
+    30
+}
+
+/// Request payload for creating an SLO
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SloCreate {
+    /// Deployment (model) whose probe results the SLO is measured on
+    #[schema(value_type = String, format = "uuid")]
+    pub deployment_id: Uuid,
+    pub name: String,
+    pub objective: SloObjective,
+    /// Share of probe results in the window that must be good, strictly between 0 and 100
+    pub target_percent: f64,
+    /// Probe results slower than this are bad (required for latency SLOs, not allowed otherwise)
+    pub latency_threshold_ms: Option<i32>,
+    /// Length of the rolling window, in days (1-90, defaults to 30)
+    #[serde(default = "default_window_days")]
+    pub window_days: i32,
+}
+
+/// Request payload for updating an SLO. Changes take effect at the next evaluation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct SloUpdate {
+    pub name: Option<String>,
+    pub target_percent: Option<f64>,
+    /// New latency threshold (latency SLOs only)
+    pub latency_threshold_ms: Option<i32>,
+    pub window_days: Option<i32>,
+}
+
+/// Query parameters for listing SLOs
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct SlosQuery {
+    /// Only list the SLOs of this deployment
+    pub deployment_id: Option<Uuid>,
+}
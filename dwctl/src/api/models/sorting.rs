@@ -0,0 +1,29 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Direction in which a list is sorted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// Column a list can be sorted by
+pub trait SortField: Copy + Serialize + DeserializeOwned {
+    /// Column to order by
+    fn column(&self) -> &'static str;
+
+    /// Postgres type of the column, to compare it with the value of a cursor
+    fn column_type(&self) -> &'static str;
+}
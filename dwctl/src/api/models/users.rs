@@ -1,8 +1,11 @@
 use crate::api::models::groups::GroupResponse;
+use crate::api::models::sorting::{SortField, SortOrder};
 use crate::db::models::users::UserDBResponse;
-use crate::types::UserId;
+use crate::types::{DeploymentId, GroupId, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_with::rust::double_option;
+use std::collections::BTreeMap;
 use utoipa::{IntoParams, ToSchema};
 
 // Role enum for different job functions
@@ -22,6 +25,9 @@ pub struct UserCreate {
     pub display_name: Option<String>,
     pub avatar_url: Option<String>,
     pub roles: Vec<Role>,
+    /// Attributes of the user, e.g. cost center, department or external ID
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -29,6 +35,12 @@ pub struct UserUpdate {
     pub display_name: Option<String>,
     pub avatar_url: Option<String>,
     pub roles: Option<Vec<Role>>,
+    /// Model offered to the user's clients by default (null = no change, Some(None) = clear)
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "double_option")]
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub default_model_id: Option<Option<DeploymentId>>,
+    /// Attributes replacing all of the user's attributes (null = no change)
+    pub attributes: Option<BTreeMap<String, String>>,
 }
 
 // User response models
@@ -46,6 +58,11 @@ pub struct UserResponse {
     pub updated_at: DateTime<Utc>,
     pub last_login: Option<DateTime<Utc>>,
     pub auth_source: String,
+    /// Model offered to the user's clients by default
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub default_model_id: Option<DeploymentId>,
+    /// Attributes of the user, e.g. cost center, department or external ID
+    pub attributes: BTreeMap<String, String>,
     /// Groups this user belongs to (only included if requested)
     /// Note: no_recursion is important! utoipa will panic at runtime, because it overflows the
     /// stack trying to follow the relationship.
@@ -67,6 +84,97 @@ pub struct ListUsersQuery {
 
     /// Include related data (comma-separated: "groups")
     pub include: Option<String>,
+
+    /// Only users whose email, username or display name contains this (case-insensitive)
+    pub q: Option<String>,
+
+    /// Only users with this role
+    pub role: Option<Role>,
+
+    /// Only direct members of this group
+    #[param(value_type = Option<String>, format = "uuid")]
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub group_id: Option<GroupId>,
+
+    /// Only admins (true) or non-admins (false)
+    pub is_admin: Option<bool>,
+
+    /// Only users with all of these attributes, as comma-separated `key=value` pairs
+    pub attributes: Option<String>,
+
+    /// Column to sort by (default: created_at)
+    pub sort: Option<UserSortField>,
+
+    /// Sort direction (default: asc, or desc when neither sort nor order is given)
+    pub order: Option<SortOrder>,
+
+    /// Cursor from the `x-next-cursor` header of the previous page. Replaces skip, and keeps the
+    /// sort the cursor was issued for
+    pub cursor: Option<String>,
+}
+
+/// Query parameters for deleting a user
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct DeleteUserQuery {
+    /// Also erase the user's personal data: their logged requests and responses are deleted, and
+    /// their analytics anonymized (default: false)
+    pub purge: Option<bool>,
+}
+
+/// What was erased when a user was deleted with `purge=true`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserDeletionReport {
+    #[schema(value_type = String, format = "uuid")]
+    pub user_id: UserId,
+    pub api_keys_deleted: u64,
+    /// Logged requests deleted, including ones archived by the retention policy
+    pub requests_deleted: u64,
+    /// Logged responses deleted, including ones archived by the retention policy
+    pub responses_deleted: u64,
+    /// Analytics rows kept for usage totals, with the user's details cleared
+    pub analytics_anonymized: u64,
+    /// Captured bodies deleted from object storage
+    pub stored_bodies_deleted: u64,
+    /// Captured bodies that couldn't be deleted from object storage, and have to be removed by hand
+    pub stored_bodies_remaining: Vec<String>,
+    /// Requests removed from the request log archives exported by the retention policy
+    pub archived_requests_deleted: u64,
+    /// Request log archives that couldn't be rewritten without the user's requests, and have to
+    /// be redacted by hand
+    pub archives_remaining: Vec<String>,
+}
+
+/// Columns the users list can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSortField {
+    CreatedAt,
+    Email,
+    Username,
+    DisplayName,
+    LastLogin,
+}
+
+impl SortField for UserSortField {
+    fn column(&self) -> &'static str {
+        match self {
+            UserSortField::CreatedAt => "created_at",
+            UserSortField::Email => "email",
+            UserSortField::Username => "username",
+            UserSortField::DisplayName => "display_name",
+            UserSortField::LastLogin => "last_login",
+        }
+    }
+
+    fn column_type(&self) -> &'static str {
+        match self {
+            UserSortField::CreatedAt => "timestamptz",
+            UserSortField::Email => "text",
+            UserSortField::Username => "text",
+            UserSortField::DisplayName => "text",
+            UserSortField::LastLogin => "timestamptz",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -115,8 +223,10 @@ impl From<UserDBResponse> for UserResponse {
             created_at: db.created_at,
             updated_at: db.updated_at,
             auth_source: db.auth_source,
-            last_login: None, // UserDBResponse doesn't have last_login
-            groups: None,     // By default, relationships are not included
+            default_model_id: db.default_model_id,
+            attributes: db.attributes,
+            last_login: db.last_login,
+            groups: None, // By default, relationships are not included
         }
     }
 }
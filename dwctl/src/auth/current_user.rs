@@ -1,17 +1,19 @@
 use crate::db::errors::DbError;
 use crate::db::handlers::Groups;
+use crate::db::health::timed;
 use crate::{
     api::models::users::{CurrentUser, Role},
     auth::session,
     db::{
         handlers::{Repository, Users},
-        models::users::UserCreateDBRequest,
+        models::users::{UserCreateDBRequest, UserDBResponse},
     },
     errors::{Error, Result},
     AppState,
 };
 use axum::{extract::FromRequestParts, http::request::Parts};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
+use tracing::debug;
 
 /// Extract user from JWT session cookie if present and valid
 fn try_jwt_session_auth(parts: &axum::http::request::Parts, config: &crate::config::Config) -> Result<Option<CurrentUser>> {
@@ -44,6 +46,46 @@ fn try_jwt_session_auth(parts: &axum::http::request::Parts, config: &crate::conf
     Ok(None)
 }
 
+/// Create a user seen for the first time in the proxy header, with the configured default roles and
+/// groups. A new user's first page load sends several requests at once, which race to create them:
+/// the losers pick up the user the winner created.
+async fn provision_user(conn: &mut PgConnection, email: &str, config: &crate::config::Config) -> Result<UserDBResponse> {
+    let mut roles = vec![Role::StandardUser];
+    for role in &config.auth.proxy_header.default_roles {
+        if !roles.contains(role) {
+            roles.push(role.clone());
+        }
+    }
+
+    let create_request = UserCreateDBRequest {
+        username: email.to_string(),
+        email: email.to_string(),
+        display_name: None,
+        avatar_url: None,
+        is_admin: false,
+        roles,
+        auth_source: "proxy-header".to_string(),
+        password_hash: None,
+        attributes: Default::default(),
+    };
+
+    // The insert runs in a savepoint, so the transaction survives losing the race
+    let created = Users::new(&mut *conn).create(&create_request).await;
+    match created {
+        Ok(user) => {
+            Groups::new(&mut *conn)
+                .add_user_to_default_groups(user.id, &config.auth.default_groups)
+                .await?;
+            Ok(user)
+        }
+        Err(DbError::UniqueViolation { .. }) => {
+            debug!("User {email} was provisioned by a concurrent request");
+            Ok(Users::new(&mut *conn).get_user_by_email(email).await?.ok_or(DbError::NotFound)?)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
 /// Extract user from proxy header if present and valid
 async fn try_proxy_header_auth(
     parts: &axum::http::request::Parts,
@@ -62,7 +104,7 @@ async fn try_proxy_header_auth(
     let mut tx = db.begin().await.unwrap();
     let mut user_repo = Users::new(&mut tx);
 
-    let user_result = match user_repo.get_user_by_email(user_email).await? {
+    let user_result = match timed("auth.user_by_email", user_repo.get_user_by_email(user_email)).await? {
         Some(user) => Some(CurrentUser {
             id: user.id,
             username: user.username,
@@ -74,18 +116,7 @@ async fn try_proxy_header_auth(
         }),
         None => {
             if config.auth.proxy_header.auto_create_users {
-                let create_request = UserCreateDBRequest {
-                    username: user_email.to_string(),
-                    email: user_email.to_string(),
-                    display_name: None,
-                    avatar_url: None,
-                    is_admin: false,
-                    roles: vec![Role::StandardUser],
-                    auth_source: "proxy-header".to_string(),
-                    password_hash: None,
-                };
-
-                let new_user = user_repo.create(&create_request).await?;
+                let new_user = provision_user(&mut tx, user_email, config).await?;
                 Some(CurrentUser {
                     id: new_user.id,
                     username: new_user.username,
@@ -156,14 +187,14 @@ impl FromRequestParts<AppState> for CurrentUser {
         // Native authentication first (JWT sessions)
         if state.config.auth.native.enabled {
             if let Some(user) = try_jwt_session_auth(parts, &state.config)? {
-                return Ok(user);
+                return Ok(record_user(user));
             }
         }
 
         // Fall back to proxy header authentication
         if state.config.auth.proxy_header.enabled {
             if let Some(user) = try_proxy_header_auth(parts, &state.config, &state.db).await? {
-                return Ok(user);
+                return Ok(record_user(user));
             }
         }
 
@@ -171,6 +202,14 @@ impl FromRequestParts<AppState> for CurrentUser {
     }
 }
 
+/// Record the authenticated user on the request span, so it's on every log line and error report
+/// of the request
+fn record_user(user: CurrentUser) -> CurrentUser {
+    tracing::Span::current().record("user_id", tracing::field::display(user.id));
+    crate::error_tracking::set_user(&user);
+    user
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -244,6 +283,51 @@ mod tests {
         assert_eq!(db_user.auth_source, "proxy-header");
     }
 
+    #[sqlx::test]
+    async fn test_auto_create_with_default_roles(pool: PgPool) {
+        let mut config = create_test_config();
+        config.auth.proxy_header.default_roles = vec![Role::RequestViewer];
+        let state = AppState::builder().db(pool.clone()).config(config).build();
+
+        let mut parts = create_test_parts_with_header("x-doubleword-user", "viewer@example.com");
+        let current_user = CurrentUser::from_request_parts(&mut parts, &state).await.unwrap();
+
+        // StandardUser is kept even when the configured roles leave it out
+        assert_eq!(current_user.roles.len(), 2);
+        assert!(current_user.roles.contains(&Role::StandardUser));
+        assert!(current_user.roles.contains(&Role::RequestViewer));
+    }
+
+    #[sqlx::test]
+    async fn test_concurrent_first_requests_create_one_user(pool: PgPool) {
+        let config = create_test_config();
+        let state = AppState::builder().db(pool.clone()).config(config).build();
+
+        let new_email = "burst@example.com";
+        let extract = || {
+            let state = state.clone();
+            async move {
+                let mut parts = create_test_parts_with_header("x-doubleword-user", new_email);
+                CurrentUser::from_request_parts(&mut parts, &state).await
+            }
+        };
+        let results = tokio::join!(extract(), extract(), extract(), extract());
+
+        let ids = [
+            results.0.unwrap().id,
+            results.1.unwrap().id,
+            results.2.unwrap().id,
+            results.3.unwrap().id,
+        ];
+        assert!(ids.iter().all(|id| *id == ids[0]));
+
+        let count = sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE email = $1", new_email)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, Some(1));
+    }
+
     #[sqlx::test]
     async fn test_missing_header_returns_unauthorized(pool: PgPool) {
         let config = create_test_config();
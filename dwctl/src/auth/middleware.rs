@@ -148,6 +148,10 @@ mod tests {
                 model_filter: None,
                 auth_header_name: None,
                 auth_header_prefix: None,
+                tls_ca_pem: None,
+                tls_skip_verify: false,
+                tls_min_version: None,
+                sync_interval_seconds: None,
                 created_by: user.id,
             })
             .await
@@ -199,6 +203,10 @@ mod tests {
                 model_filter: None,
                 auth_header_name: None,
                 auth_header_prefix: None,
+                tls_ca_pem: None,
+                tls_skip_verify: false,
+                tls_min_version: None,
+                sync_interval_seconds: None,
                 created_by: user.id,
             })
             .await
@@ -227,6 +235,8 @@ mod tests {
                 GroupCreate {
                     name: "a group".to_string(),
                     description: Some("A test group".to_string()),
+                    parent_id: None,
+                    is_default: false,
                 },
             ))
             .await
@@ -358,6 +368,10 @@ mod tests {
                 model_filter: None,
                 auth_header_name: None,
                 auth_header_prefix: None,
+                tls_ca_pem: None,
+                tls_skip_verify: false,
+                tls_min_version: None,
+                sync_interval_seconds: None,
                 created_by: user.id,
             })
             .await
@@ -424,6 +438,10 @@ mod tests {
                 model_filter: None,
                 auth_header_name: None,
                 auth_header_prefix: None,
+                tls_ca_pem: None,
+                tls_skip_verify: false,
+                tls_min_version: None,
+                sync_interval_seconds: None,
                 created_by: user.id,
             })
             .await
@@ -452,6 +470,8 @@ mod tests {
                 GroupCreate {
                     name: "jwt group".to_string(),
                     description: Some("A test group for JWT".to_string()),
+                    parent_id: None,
+                    is_default: false,
                 },
             ))
             .await
@@ -482,8 +502,15 @@ mod tests {
             db: pool.clone(),
             config: config.clone(),
             outlet_db: None,
+            body_storage: None,
             metrics_recorder: None,
             is_leader: false,
+            admission: None,
+            log_retention: None,
+            event_stream: None,
+            probe_scheduler: None,
+            leader_fence: None,
+            routing_table: None,
         };
 
         let request = axum::http::Request::builder()
@@ -523,6 +550,10 @@ mod tests {
                 model_filter: None,
                 auth_header_name: None,
                 auth_header_prefix: None,
+                tls_ca_pem: None,
+                tls_skip_verify: false,
+                tls_min_version: None,
+                sync_interval_seconds: None,
                 created_by: jwt_user.id,
             })
             .await
@@ -551,6 +582,8 @@ mod tests {
                 GroupCreate {
                     name: "priority group".to_string(),
                     description: Some("A test group for auth priority".to_string()),
+                    parent_id: None,
+                    is_default: false,
                 },
             ))
             .await
@@ -582,8 +615,15 @@ mod tests {
             db: pool.clone(),
             config: config.clone(),
             outlet_db: None,
+            body_storage: None,
             metrics_recorder: None,
             is_leader: false,
+            admission: None,
+            log_retention: None,
+            event_stream: None,
+            probe_scheduler: None,
+            leader_fence: None,
+            routing_table: None,
         };
 
         let request = axum::http::Request::builder()
@@ -624,6 +664,10 @@ mod tests {
                 model_filter: None,
                 auth_header_name: None,
                 auth_header_prefix: None,
+                tls_ca_pem: None,
+                tls_skip_verify: false,
+                tls_min_version: None,
+                sync_interval_seconds: None,
                 created_by: user.id,
             })
             .await
@@ -652,6 +696,8 @@ mod tests {
                 GroupCreate {
                     name: "disabled auth group".to_string(),
                     description: Some("A test group for disabled auth".to_string()),
+                    parent_id: None,
+                    is_default: false,
                 },
             ))
             .await
@@ -682,8 +728,15 @@ mod tests {
             db: pool.clone(),
             config: config.clone(),
             outlet_db: None,
+            body_storage: None,
             metrics_recorder: None,
             is_leader: false,
+            admission: None,
+            log_retention: None,
+            event_stream: None,
+            probe_scheduler: None,
+            leader_fence: None,
+            routing_table: None,
         };
 
         // Request with JWT cookie - should be ignored since native auth is disabled
@@ -723,6 +776,10 @@ mod tests {
                 model_filter: None,
                 auth_header_name: None,
                 auth_header_prefix: None,
+                tls_ca_pem: None,
+                tls_skip_verify: false,
+                tls_min_version: None,
+                sync_interval_seconds: None,
                 created_by: Uuid::nil(), // Use nil for system creation
             })
             .await
@@ -805,6 +862,10 @@ mod tests {
                 model_filter: None,
                 auth_header_name: None,
                 auth_header_prefix: None,
+                tls_ca_pem: None,
+                tls_skip_verify: false,
+                tls_min_version: None,
+                sync_interval_seconds: None,
                 created_by: user.id,
             })
             .await
@@ -831,6 +892,8 @@ mod tests {
                 GroupCreate {
                     name: "fallback group".to_string(),
                     description: Some("A test group for auth fallback".to_string()),
+                    parent_id: None,
+                    is_default: false,
                 },
             ))
             .await
@@ -851,8 +914,15 @@ mod tests {
             db: pool.clone(),
             config: config.clone(),
             outlet_db: None,
+            body_storage: None,
             metrics_recorder: None,
             is_leader: false,
+            admission: None,
+            log_retention: None,
+            event_stream: None,
+            probe_scheduler: None,
+            leader_fence: None,
+            routing_table: None,
         };
 
         let request = axum::http::Request::builder()
@@ -0,0 +1,208 @@
+//! Support for Azure OpenAI endpoints.
+//!
+//! Azure OpenAI serves the OpenAI API with three differences: every request needs an
+//! `api-version` query parameter, keys are sent in an `api-key` header rather than as a bearer
+//! token, and models are addressed by deployment name in the path
+//! (`/openai/deployments/{deployment}/chat/completions`) rather than by the `model` field.
+//!
+//! Endpoints are recognised as Azure by their host, and take their API version from the
+//! `api-version` query parameter of the endpoint URL (defaulting to [`DEFAULT_API_VERSION`]).
+//! Model sync lists the resource's deployments, so each deployment name becomes a model name,
+//! and `sync::onwards_config` points each Azure target at its deployment. Upstream URLs are built
+//! from the target URL and the path and query of the request, so the API version is moved off
+//! each Azure target's URL into the [`RoutingTable`], and the [`add_api_version`] middleware adds
+//! it to every request for the target.
+
+use crate::routing::RoutingTable;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use onwards::target::ConfigFile;
+use std::collections::HashMap;
+use tokio::sync::watch;
+use url::Url;
+
+/// Query parameter carrying the API version of every Azure OpenAI request
+pub const API_VERSION_PARAM: &str = "api-version";
+
+/// API version used when the endpoint URL doesn't specify one
+pub const DEFAULT_API_VERSION: &str = "2024-10-21";
+
+/// Header Azure OpenAI reads API keys from, with no prefix
+pub const API_KEY_HEADER: &str = "api-key";
+
+/// Listing deployments was dropped from the data plane API after this version, so it is always
+/// used for model sync whatever the endpoint's own API version
+const LIST_DEPLOYMENTS_API_VERSION: &str = "2022-12-01";
+
+const AZURE_HOST_SUFFIXES: &[&str] = &[".openai.azure.com", ".cognitiveservices.azure.com"];
+
+/// Whether `url` points at an Azure OpenAI resource
+pub fn is_azure_url(url: &Url) -> bool {
+    url.host_str()
+        .is_some_and(|host| AZURE_HOST_SUFFIXES.iter().any(|suffix| host.ends_with(suffix)))
+}
+
+/// The API version requests to an Azure endpoint at `url` are made with
+pub fn api_version(url: &Url) -> String {
+    url.query_pairs()
+        .find(|(name, _)| name == API_VERSION_PARAM)
+        .map(|(_, version)| version.into_owned())
+        .filter(|version| !version.is_empty())
+        .unwrap_or_else(|| DEFAULT_API_VERSION.to_string())
+}
+
+/// URL listing the deployments of the Azure resource at `url`
+pub fn deployments_url(url: &Url) -> Url {
+    let mut deployments = url.clone();
+    deployments.set_path("/openai/deployments");
+    deployments.set_query(None);
+    deployments
+        .query_pairs_mut()
+        .append_pair(API_VERSION_PARAM, LIST_DEPLOYMENTS_API_VERSION);
+    deployments
+}
+
+/// Base URL of `deployment` on the Azure resource at `url`, keeping the endpoint's API version
+pub fn deployment_url(url: &Url, deployment: &str) -> Url {
+    let mut target = url.clone();
+    target.set_path("/openai/deployments");
+    if let Ok(mut segments) = target.path_segments_mut() {
+        segments.push(deployment);
+    }
+    target.set_query(None);
+    target.query_pairs_mut().append_pair(API_VERSION_PARAM, &api_version(url));
+    target
+}
+
+/// Fill in Azure's `api-key` header for an endpoint at `url` whose auth header isn't given
+/// explicitly. Endpoints can still opt into `Authorization: Bearer` for Entra ID tokens.
+pub fn default_auth_header(url: &Url, name: Option<String>, prefix: Option<String>) -> (Option<String>, Option<String>) {
+    if !is_azure_url(url) || name.is_some() {
+        return (name, prefix);
+    }
+    (Some(API_KEY_HEADER.to_string()), Some(prefix.unwrap_or_default()))
+}
+
+/// Strip the API version from the URL of every Azure target in `config`, returning the version
+/// of each target alias
+pub fn take_api_versions(config: &mut ConfigFile) -> HashMap<String, String> {
+    let mut versions = HashMap::new();
+    for (alias, target) in config.targets.iter_mut() {
+        if !is_azure_url(&target.url) {
+            continue;
+        }
+        versions.insert(alias.clone(), api_version(&target.url));
+        target.url.set_query(None);
+    }
+    versions
+}
+
+/// Middleware adding the API version of the target a request is sent to to its query string.
+///
+/// Runs inside `routing::fallback_routing`, so it sees the internal target each attempt is
+/// rewritten to, rather than the alias the client asked for.
+pub async fn add_api_version(State(table): State<watch::Receiver<RoutingTable>>, request: Request, next: Next) -> Response {
+    if !table.borrow().has_api_versions() {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Failed to read request body").into_response(),
+    };
+
+    if let Ok(model) = onwards::extract_model_from_request(&parts.headers, &body) {
+        let version = table.borrow().api_version(&model).map(str::to_string);
+        if let Some(uri) = version.and_then(|version| with_api_version(&parts.uri, &version)) {
+            parts.uri = uri;
+        }
+    }
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
+
+/// `uri` with its `api-version` query parameter set to `version`, replacing any the client sent
+fn with_api_version(uri: &Uri, version: &str) -> Option<Uri> {
+    let pairs = url::form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+        .filter(|(name, _)| name != API_VERSION_PARAM)
+        .chain(std::iter::once((API_VERSION_PARAM.into(), version.into())));
+    let query = url::form_urlencoded::Serializer::new(String::new()).extend_pairs(pairs).finish();
+
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(format!("{}?{}", uri.path(), query).parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware::from_fn_with_state, routing::post, Router};
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    #[test]
+    fn test_azure_urls() {
+        let url = Url::parse("https://my-resource.openai.azure.com/").unwrap();
+        assert!(is_azure_url(&url));
+        assert!(is_azure_url(
+            &Url::parse("https://my-resource.cognitiveservices.azure.com").unwrap()
+        ));
+        assert!(!is_azure_url(&Url::parse("https://api.openai.com/v1").unwrap()));
+        assert!(!is_azure_url(&Url::parse("https://openai.azure.com.example.com").unwrap()));
+
+        assert_eq!(api_version(&url), DEFAULT_API_VERSION);
+        assert_eq!(
+            deployments_url(&url).as_str(),
+            "https://my-resource.openai.azure.com/openai/deployments?api-version=2022-12-01"
+        );
+
+        let url = Url::parse("https://my-resource.openai.azure.com/openai?api-version=2025-01-01-preview").unwrap();
+        assert_eq!(api_version(&url), "2025-01-01-preview");
+        assert_eq!(
+            deployment_url(&url, "gpt-4o prod").as_str(),
+            "https://my-resource.openai.azure.com/openai/deployments/gpt-4o%20prod?api-version=2025-01-01-preview"
+        );
+    }
+
+    #[test]
+    fn test_default_auth_header() {
+        let azure = Url::parse("https://my-resource.openai.azure.com").unwrap();
+        assert_eq!(
+            default_auth_header(&azure, None, None),
+            (Some("api-key".to_string()), Some(String::new()))
+        );
+        let bearer = (Some("Authorization".to_string()), Some("Bearer ".to_string()));
+        assert_eq!(default_auth_header(&azure, bearer.0.clone(), bearer.1.clone()), bearer);
+
+        let openai = Url::parse("https://api.openai.com/v1").unwrap();
+        assert_eq!(default_auth_header(&openai, None, None), (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_api_version_is_added_for_azure_targets() {
+        let mut table = RoutingTable::default();
+        table.set_api_version("azure".to_string(), "2024-10-21".to_string());
+        let (_, receiver) = watch::channel(table);
+
+        // Echo the query string the upstream receives
+        let upstream = Router::new().route(
+            "/chat/completions",
+            post(|uri: Uri| async move { uri.query().unwrap_or_default().to_string() }),
+        );
+        let server = TestServer::new(upstream.layer(from_fn_with_state(receiver, add_api_version))).unwrap();
+
+        let response = server
+            .post("/chat/completions?api-version=2023-05-15&foo=bar")
+            .json(&json!({"model": "azure"}))
+            .await;
+        assert_eq!(response.text(), "foo=bar&api-version=2024-10-21");
+
+        let response = server.post("/chat/completions").json(&json!({"model": "other"})).await;
+        assert_eq!(response.text(), "");
+    }
+}
@@ -1,13 +1,15 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use figment::{
     providers::{Env, Format, Yaml},
     Figment,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 use url::Url;
 
+use crate::api::models::users::Role;
 use crate::errors::Error;
 
 /// Simple CLI args - just for specifying config file
@@ -17,6 +19,29 @@ pub struct Args {
     /// Path to configuration file
     #[arg(short = 'f', long, env = "DWCTL_CONFIG", default_value = "config.yaml")]
     pub config: String,
+    /// Run a maintenance command instead of the server
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Write the gateway's state (users, groups, endpoints, deployments, API keys, probes and
+    /// policies, but no request logs) to an archive. The archive contains secrets.
+    ExportState {
+        /// Path of the archive to write
+        output: PathBuf,
+    },
+    /// Replace the gateway's state with an archive written by export-state. The database must
+    /// be at the same schema version as the one the archive was exported from.
+    ImportState {
+        /// Path of the archive to read
+        input: PathBuf,
+    },
+    /// Encrypt stored API key secrets and endpoint API keys with the configured secret encryption
+    /// key. Run after enabling encryption, to encrypt existing secrets, or after rotating the key,
+    /// with the old key in `secret_encryption.previous_keys`.
+    EncryptSecrets,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -33,6 +58,10 @@ pub struct Config {
     pub admin_password: Option<String>,
     // Global secret key for encryption/signing
     pub secret_key: Option<String>,
+    // Encryption at rest of API key secrets and endpoint API keys
+    pub secret_encryption: SecretEncryptionConfig,
+    // External secret managers that endpoint API keys can reference instead of being stored
+    pub secret_managers: SecretManagersConfig,
     // Model sources are now properly plural
     pub model_sources: Vec<ModelSource>,
     // Frontend metadata
@@ -41,8 +70,50 @@ pub struct Config {
     pub auth: AuthConfig,
     // Metrics configuration
     pub enable_metrics: bool,
+    // Which metric families and label dimensions /internal/metrics exposes
+    pub metrics: MetricsConfig,
     // Request logging configuration
     pub enable_request_logging: bool,
+    // Tag logged prompts with coarse PII categories (requires request logging)
+    pub enable_pii_classification: bool,
+    // Scheduled endpoint re-validation configuration
+    pub endpoint_validation: EndpointValidationConfig,
+    // Scheduled synchronization of endpoints with a sync interval
+    pub endpoint_sync: EndpointSyncConfig,
+    // Forwarding of completed request records to an external webhook
+    pub request_mirroring: RequestMirroringConfig,
+    // Per-request webhook events for completed AI requests matching a filter
+    pub request_webhook: RequestWebhookConfig,
+    // Where captured request and response bodies are kept (requires request logging)
+    pub body_storage: BodyStorageConfig,
+    // Purging of request log rows older than the retention window (requires request logging)
+    pub request_log_retention: RequestLogRetentionConfig,
+    // Publishing of request analytics records to Kafka or NATS JetStream (requires request logging)
+    pub event_stream: EventStreamConfig,
+    // Built-in mock inference backend for development and CI
+    pub sandbox: SandboxConfig,
+    // Routing of deployment aliases across fallback endpoints
+    pub routing: RoutingConfig,
+    // Safety caps for admin-triggered load tests
+    pub load_testing: LoadTestingConfig,
+    // Evaluation of deployment SLOs and error budget alerts
+    pub slos: SloConfig,
+    // Load the scheduled probes put on upstreams
+    pub probes: ProbesConfig,
+    // Alerts sent to notification channels when probes start failing or recover
+    pub probe_alerts: ProbeAlertsConfig,
+    // Content moderation of AI requests, per group policy
+    pub moderation: ModerationConfig,
+    // Per-group caps on the size of AI requests
+    pub request_limits: RequestLimitsConfig,
+    // Priority admission queue for AI requests when upstreams are saturated
+    pub admission: AdmissionConfig,
+    // Read-only aggregation of analytics and health across peer instances
+    pub federation: FederationConfig,
+    // Log output format and levels
+    pub logging: LoggingConfig,
+    // Reporting of panics and errors to Sentry
+    pub error_tracking: ErrorTrackingConfig,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -140,6 +211,9 @@ pub struct AuthConfig {
     pub native: NativeAuthConfig,
     pub proxy_header: ProxyHeaderAuthConfig,
     pub security: SecurityConfig,
+    /// Names of groups that users join when they register or are provisioned from a proxy
+    /// header, on top of the groups marked as default through the API
+    pub default_groups: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -159,6 +233,8 @@ pub struct ProxyHeaderAuthConfig {
     pub header_name: String,
     pub groups_field_name: String,
     pub auto_create_users: bool,
+    /// Roles given to users created from the header. StandardUser is always included.
+    pub default_roles: Vec<Role>,
     pub blacklisted_sso_groups: Vec<String>,
     pub provider_field_name: String,
     pub import_idp_groups: bool,
@@ -223,6 +299,634 @@ pub struct PasswordResetEmailConfig {
     pub base_url: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EndpointValidationConfig {
+    /// Whether the leader replica periodically re-validates every inference endpoint
+    pub enabled: bool,
+    /// How often to re-validate endpoints
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EndpointSyncConfig {
+    /// Whether the leader replica synchronizes endpoints that have a sync interval
+    pub enabled: bool,
+    /// How often to check for endpoints due to be synchronized
+    #[serde(with = "humantime_serde")]
+    pub check_interval: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Metric families left out of `/internal/metrics`, by name. A trailing `*` matches every
+    /// family with that prefix, e.g. `dwctl_admission_*`.
+    pub disabled_families: Vec<String>,
+    /// Labels dropped from the GenAI metrics, e.g. `server_address` and `server_port`
+    pub dropped_labels: Vec<String>,
+    /// Label the GenAI metrics with the ID of the user making each request
+    pub user_labels: bool,
+    /// Number of distinct `user_id` label values
+    pub max_user_labels: usize,
+    /// Label the GenAI metrics with the groups of the user making each request
+    pub group_labels: bool,
+    /// Number of distinct `group` label values
+    pub max_group_labels: usize,
+    /// How user and group label values are kept within their limits
+    pub label_overflow: LabelOverflow,
+    /// Push export of the metrics to an OpenTelemetry collector
+    pub otlp: OtlpMetricsConfig,
+    /// Database connection pool and query metrics
+    pub database: DatabaseMetricsConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Format of log lines on stdout
+    pub format: LogFormat,
+    /// Levels to log at, as `tracing` directives, e.g. `info,dwctl::auth=debug`. `RUST_LOG`
+    /// takes precedence when set, and admins can change the levels at runtime.
+    pub level: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, with the fields of the enclosing spans (such as the request ID
+    /// and user of admin API requests), for log pipelines
+    Json,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Text,
+            level: "info,onwards_pilot::sync=warn".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ErrorTrackingConfig {
+    /// DSN of the Sentry (or Sentry-compatible) project panics and `error` logs are reported
+    /// to. Nothing is reported when unset.
+    pub dsn: Option<String>,
+    /// Environment the reports are tagged with, e.g. `production`
+    pub environment: Option<String>,
+    /// Fraction of errors reported, between 0 and 1
+    pub sample_rate: f32,
+}
+
+impl Default for ErrorTrackingConfig {
+    fn default() -> Self {
+        Self {
+            dsn: None,
+            environment: None,
+            sample_rate: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OtlpMetricsConfig {
+    /// Whether the metrics on `/internal/metrics` are also pushed to `endpoint` over OTLP/HTTP
+    /// (requires metrics)
+    pub enabled: bool,
+    /// URL the metrics are POSTed to as OTLP JSON, e.g. `http://otel-collector:4318/v1/metrics`
+    pub endpoint: Option<Url>,
+    /// Headers sent with every export, e.g. for authenticating to a hosted collector
+    pub headers: HashMap<String, String>,
+    /// How often the metrics are pushed
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    /// Timeout for each push
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+    /// `service.name` resource attribute of the exported metrics
+    pub service_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DatabaseMetricsConfig {
+    /// How often the connection pool is sampled, including the time taken to acquire a
+    /// connection from it
+    #[serde(with = "humantime_serde")]
+    pub sample_interval: Duration,
+    /// Named queries taking at least this long are counted as slow
+    #[serde(with = "humantime_serde")]
+    pub slow_query_threshold: Duration,
+}
+
+/// How the GenAI metrics keep user and group labels within `metrics.max_user_labels` and
+/// `metrics.max_group_labels`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelOverflow {
+    /// The first users or groups seen keep their own label, and any further ones share `other`
+    #[default]
+    Other,
+    /// Every user or group is hashed into one of a fixed number of `bucket-N` labels, which
+    /// stay the same across replicas and restarts
+    Hash,
+}
+
+impl MetricsConfig {
+    /// Whether the metric family `name` is exposed
+    pub fn family_enabled(&self, name: &str) -> bool {
+        !self.disabled_families.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RequestMirroringConfig {
+    /// Whether completed request records are forwarded to `url` (requires request logging)
+    pub enabled: bool,
+    /// HTTPS endpoint that receives batches of request records as JSON
+    pub url: Option<Url>,
+    /// Optional value sent in the `Authorization` header of each batch
+    pub authorization: Option<String>,
+    /// Maximum number of records sent in a single batch
+    pub batch_size: usize,
+    /// Maximum time a record waits before its batch is sent
+    #[serde(with = "humantime_serde")]
+    pub flush_interval: Duration,
+    /// Number of records buffered in memory
+    pub buffer_size: usize,
+    /// How long a record waits for space in a full buffer before it is dropped
+    #[serde(with = "humantime_serde")]
+    pub enqueue_timeout: Duration,
+    /// Number of times a failed batch is retried before it is dropped
+    pub max_retries: u32,
+    /// Timeout for each delivery attempt
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RequestWebhookConfig {
+    /// Whether an event is POSTed to `url` for each completed AI request matching `filter`
+    /// (requires request logging)
+    pub enabled: bool,
+    /// HTTPS endpoint that receives one JSON event per request
+    pub url: Option<Url>,
+    /// Optional value sent in the `Authorization` header of each event
+    pub authorization: Option<String>,
+    /// Which requests produce events
+    pub filter: RequestWebhookFilter,
+    /// Maximum number of events being delivered at once
+    pub max_concurrency: usize,
+    /// Number of events buffered in memory; events are dropped when the buffer is full
+    pub buffer_size: usize,
+    /// Number of times a failed event is retried before it is dropped
+    pub max_retries: u32,
+    /// Timeout for each delivery attempt
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+}
+
+/// Which completed requests produce webhook events. Every condition must hold.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RequestWebhookFilter {
+    /// Only requests to these model aliases (all models if empty)
+    pub models: Vec<String>,
+    /// Only requests made by these users, given by email or ID (all users if empty)
+    pub users: Vec<String>,
+    /// Only requests with this outcome
+    pub status: RequestWebhookStatus,
+    /// Only requests using at least this many tokens
+    pub min_total_tokens: i64,
+}
+
+/// Outcome of the requests that produce webhook events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestWebhookStatus {
+    #[default]
+    All,
+    /// 2xx responses
+    Success,
+    /// 4xx and 5xx responses
+    Error,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BodyStorageConfig {
+    /// Keep bodies in the request logging tables in PostgreSQL
+    #[default]
+    Postgres,
+    /// Keep bodies in an S3-compatible bucket, with only a pointer to each in PostgreSQL
+    S3(Box<S3StorageConfig>),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct S3StorageConfig {
+    /// S3 API endpoint, e.g. a MinIO server (defaults to AWS S3 in `region`)
+    pub endpoint: Option<Url>,
+    pub region: String,
+    pub bucket: String,
+    /// Prefix of the key of every stored body
+    pub prefix: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Address the bucket in the path rather than the host name (needed by most MinIO setups)
+    pub path_style: bool,
+    /// Timeout for each upload and download
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionAction {
+    /// Delete expired rows
+    #[default]
+    Delete,
+    /// Move expired rows to the `http_requests_archive` and `http_responses_archive` tables
+    Archive,
+    /// Export expired days to the `export` bucket as JSON Lines, then delete them
+    Export,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RequestLogRetentionConfig {
+    /// Whether the leader replica periodically purges expired request log rows
+    pub enabled: bool,
+    /// How long logged requests and responses are kept
+    #[serde(with = "humantime_serde")]
+    pub retention: Duration,
+    /// What to do with expired rows
+    pub action: RetentionAction,
+    /// How often to purge expired rows
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    /// Maximum number of rows purged per statement, to keep locks and transactions short
+    pub batch_size: i64,
+    /// Only count the rows that would be purged, without changing anything
+    pub dry_run: bool,
+    /// Bucket expired days are exported to (required by the export action)
+    pub export: Option<S3StorageConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventStreamConfig {
+    /// Whether the leader replica publishes each request analytics record to `sink`
+    pub enabled: bool,
+    /// Where records are published (required when enabled)
+    pub sink: Option<EventSinkConfig>,
+    /// How often new records are looked for
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    /// Maximum number of records read from the database at once
+    pub batch_size: i64,
+    /// Number of times a record that fails to publish is retried before it's sent to the
+    /// dead-letter destination
+    pub max_retries: u32,
+    /// Timeout for each publish
+    #[serde(with = "humantime_serde")]
+    pub publish_timeout: Duration,
+}
+
+/// Streaming platform request analytics records are published to
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum EventSinkConfig {
+    /// A Kafka topic (requires the `kafka` feature)
+    Kafka(KafkaSinkConfig),
+    /// A NATS JetStream subject (requires the `nats` feature)
+    Nats(NatsSinkConfig),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KafkaSinkConfig {
+    /// Comma-separated list of bootstrap brokers
+    pub brokers: String,
+    pub topic: String,
+    /// Topic records that can't be published are sent to
+    pub dead_letter_topic: String,
+    /// Additional librdkafka settings, e.g. `security.protocol` or `sasl.username`
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NatsSinkConfig {
+    /// Server URL, e.g. `nats://nats:4222`
+    pub url: String,
+    /// Subject records are published to; it must be bound to a JetStream stream
+    pub subject: String,
+    /// Subject records that can't be published are sent to
+    pub dead_letter_subject: String,
+    /// Optional authentication token
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// Whether the mock backend is served at `/sandbox/v1` and registered as an inference endpoint
+    pub enabled: bool,
+    /// Name of the inference endpoint registered for the sandbox
+    pub endpoint_name: String,
+    /// Models offered by the sandbox. Models with `echo` in their name repeat the last user
+    /// message; all others respond with lorem ipsum.
+    pub models: Vec<String>,
+    /// Delay between streamed tokens
+    #[serde(with = "humantime_serde")]
+    pub token_delay: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RoutingConfig {
+    /// How long to wait for an endpoint to start responding before trying the alias' next
+    /// fallback. Only applies to aliases that have fallbacks.
+    #[serde(with = "humantime_serde")]
+    pub fallback_timeout: Duration,
+    /// Notification sent whenever the routing table changes
+    pub change_webhook: RoutingChangeWebhookConfig,
+    /// Short-circuiting of deployments whose probes keep failing
+    pub circuit_breaker: CircuitBreakerConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    /// Whether deployments are taken out of rotation when their probe keeps failing
+    pub enabled: bool,
+    /// Consecutive probe failures after which a deployment's requests go straight to its
+    /// fallbacks, or are refused with a 503 if it has none, until its probe succeeds again
+    pub failure_threshold: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RoutingChangeWebhookConfig {
+    /// Whether a diff of the routing table is POSTed to `url` whenever it changes
+    pub enabled: bool,
+    /// HTTPS endpoint that receives routing table changes as JSON
+    pub url: Option<Url>,
+    /// Optional value sent in the `Authorization` header of each notification
+    pub authorization: Option<String>,
+    /// Number of times a failed notification is retried before it is dropped
+    pub max_retries: u32,
+    /// Timeout for each delivery attempt
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SloConfig {
+    /// Whether the leader replica evaluates deployment SLOs and fires error budget alerts
+    pub enabled: bool,
+    /// How often to evaluate every SLO
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    /// SLOs with less than this percentage of their error budget left are at risk
+    pub at_risk_budget_percent: f64,
+    /// SLOs spending their error budget at least this fast over `burn_window` are at risk,
+    /// however much budget is left. A burn rate of 1 spends the budget exactly over the SLO
+    /// window; 14.4 spends 2% of a 30-day budget in an hour.
+    pub fast_burn_rate: f64,
+    /// Recent period the burn rate is measured over
+    #[serde(with = "humantime_serde")]
+    pub burn_window: Duration,
+    /// Where alerts are sent when an SLO's status changes
+    pub alert_webhook: SloAlertWebhookConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SloAlertWebhookConfig {
+    /// Whether SLO alerts are POSTed to `url`
+    pub enabled: bool,
+    /// HTTPS endpoint that receives SLO alerts as JSON
+    pub url: Option<Url>,
+    /// Optional value sent in the `Authorization` header of each alert
+    pub authorization: Option<String>,
+    /// Number of times a failed alert is retried before it is dropped
+    pub max_retries: u32,
+    /// Timeout for each delivery attempt
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProbesConfig {
+    /// Maximum number of probe executions in flight at once across all probes; further
+    /// executions wait for one to finish. 0 for no limit.
+    pub max_concurrent_executions: u32,
+    /// Maximum random delay before each execution, so probes sharing an interval don't hit
+    /// upstreams in lockstep. Probes can set their own; it's capped at the probe's interval.
+    #[serde(with = "humantime_serde")]
+    pub jitter: Duration,
+    /// Timeout for each probe request. Probes can set their own.
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProbeAlertsConfig {
+    /// Whether probe failures and recoveries are sent to the notification channels
+    pub enabled: bool,
+    /// Consecutive failures after which a probe is failing
+    pub failure_threshold: u32,
+    /// Minimum time between two alerts for the same probe. A change of status during the
+    /// cooldown is sent once it's over, if the probe hasn't changed back by then.
+    #[serde(with = "humantime_serde")]
+    pub cooldown: Duration,
+    /// Number of times a failed delivery to a channel is retried before it is dropped
+    pub max_retries: u32,
+    /// Timeout for each webhook and Slack delivery attempt
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LoadTestingConfig {
+    /// Whether admins can start load tests
+    pub enabled: bool,
+    /// Maximum number of requests a single load test may send
+    pub max_requests: u32,
+    /// Maximum number of requests a load test may have in flight at once
+    pub max_concurrency: u32,
+    /// Maximum rate at which a load test may start requests
+    pub max_requests_per_second: u32,
+    /// Load tests are stopped after this long, however many requests remain
+    #[serde(with = "humantime_serde")]
+    pub max_duration: Duration,
+    /// Load tests are stopped once more than this fraction of requests fail (429s excluded)
+    pub max_error_rate: f64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ModerationConfig {
+    /// Whether AI requests are checked against the moderation policies of their user's groups
+    pub enabled: bool,
+    /// OpenAI-compatible moderation endpoint (e.g. `https://api.openai.com/v1/moderations`). When
+    /// unset, only the blocked patterns of each policy are checked.
+    pub endpoint: Option<Url>,
+    /// Bearer token sent to the moderation endpoint
+    pub api_key: Option<String>,
+    /// Model requested from the moderation endpoint, if it needs one
+    pub model: Option<String>,
+    /// Timeout for each call to the moderation endpoint
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    /// Forward requests unmoderated when the moderation endpoint fails, rather than refusing them
+    pub fail_open: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SecretEncryptionConfig {
+    /// Base64-encoded 256-bit key that the data key of each stored secret is encrypted with.
+    /// Secrets are stored in plaintext when neither this nor `key_file` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// File to read the key from instead, e.g. one written by a KMS or secrets store agent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<PathBuf>,
+    /// Keys used before the current one, still accepted to decrypt secrets until
+    /// `dwctl encrypt-secrets` has re-encrypted them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub previous_keys: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SecretManagersConfig {
+    /// How often referenced secrets are fetched again. The AI proxy picks up changed values
+    /// without a restart.
+    #[serde(with = "humantime_serde")]
+    pub refresh_interval: Duration,
+    /// Timeout for each request to a secret manager
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+    /// HashiCorp Vault, for `vault://<path>#<field>` references
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault: Option<VaultConfig>,
+    /// AWS Secrets Manager, for `arn:aws:secretsmanager:...` references, optionally followed by
+    /// `#<field>` to read a field of a JSON secret
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aws: Option<AwsSecretsManagerConfig>,
+}
+
+impl Default for SecretManagersConfig {
+    fn default() -> Self {
+        Self {
+            refresh_interval: Duration::from_secs(300),
+            request_timeout: Duration::from_secs(10),
+            vault: None,
+            aws: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VaultConfig {
+    /// Vault server, e.g. `https://vault.internal:8200`
+    pub address: Url,
+    /// Token to authenticate with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// File to read the token from instead, before each request, e.g. a Vault agent sink
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_file: Option<PathBuf>,
+    /// Vault Enterprise namespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Version of the KV secrets engine references point into (1 or 2)
+    #[serde(default = "default_kv_version")]
+    pub kv_version: u8,
+}
+
+fn default_kv_version() -> u8 {
+    2
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AwsSecretsManagerConfig {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Session token of temporary credentials
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_token: Option<String>,
+    /// Secrets Manager API endpoint (defaults to AWS in the region of each secret's ARN)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<Url>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RequestLimitsConfig {
+    /// Whether AI requests are checked against the request limits of their user's groups
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AdmissionConfig {
+    /// Whether AI requests beyond `max_in_flight` are queued and admitted by group priority
+    pub enabled: bool,
+    /// Maximum AI requests forwarded upstream at once (including streaming responses in progress)
+    pub max_in_flight: usize,
+    /// Maximum requests waiting for admission; further requests are refused straight away
+    pub max_queued: usize,
+    /// How long a request of each priority tier may wait for admission before being refused
+    pub queue_timeout: TierTimeouts,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TierTimeouts {
+    #[serde(with = "humantime_serde")]
+    pub high: Duration,
+    #[serde(with = "humantime_serde")]
+    pub normal: Duration,
+    #[serde(with = "humantime_serde")]
+    pub low: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FederationConfig {
+    /// Whether admins can register peer instances and query aggregate analytics and health
+    /// across them
+    pub enabled: bool,
+    /// Bearer token peers must present to read this instance's summary. The summary endpoint
+    /// is disabled when unset.
+    pub token: Option<String>,
+    /// Timeout for each request to a peer; peers that don't answer in time are reported as
+    /// unreachable
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum CorsOrigin {
@@ -262,78 +966,377 @@ impl Default for Config {
             admin_email: "test@doubleword.ai".to_string(),
             admin_password: Some("hunter2".to_string()),
             secret_key: None,
+            secret_encryption: SecretEncryptionConfig::default(),
+            secret_managers: SecretManagersConfig::default(),
             model_sources: vec![],
             metadata: Metadata::default(),
             auth: AuthConfig::default(),
             enable_metrics: true,
+            metrics: MetricsConfig::default(),
             enable_request_logging: true,
+            enable_pii_classification: false,
+            endpoint_validation: EndpointValidationConfig::default(),
+            endpoint_sync: EndpointSyncConfig::default(),
+            request_mirroring: RequestMirroringConfig::default(),
+            request_webhook: RequestWebhookConfig::default(),
+            body_storage: BodyStorageConfig::default(),
+            request_log_retention: RequestLogRetentionConfig::default(),
+            event_stream: EventStreamConfig::default(),
+            sandbox: SandboxConfig::default(),
+            routing: RoutingConfig::default(),
+            load_testing: LoadTestingConfig::default(),
+            slos: SloConfig::default(),
+            probes: ProbesConfig::default(),
+            probe_alerts: ProbeAlertsConfig::default(),
+            moderation: ModerationConfig::default(),
+            request_limits: RequestLimitsConfig::default(),
+            admission: AdmissionConfig::default(),
+            federation: FederationConfig::default(),
+            logging: LoggingConfig::default(),
+            error_tracking: ErrorTrackingConfig::default(),
         }
     }
 }
 
-impl Default for Metadata {
+impl Default for RequestLogRetentionConfig {
     fn default() -> Self {
         Self {
-            region: "UK South".to_string(),
-            organization: "ACME Corp".to_string(),
-            registration_enabled: true,
+            enabled: false,
+            retention: Duration::from_secs(90 * 24 * 60 * 60),
+            action: RetentionAction::default(),
+            interval: Duration::from_secs(60 * 60),
+            batch_size: 10_000,
+            dry_run: false,
+            export: None,
         }
     }
 }
 
-impl Default for ModelSource {
+impl Default for EndpointValidationConfig {
     fn default() -> Self {
         Self {
-            name: String::new(),
-            url: Url::parse("http://localhost:8080").unwrap(),
-            api_key: None,
-            sync_interval: Duration::from_secs(10),
+            enabled: true,
+            interval: Duration::from_secs(24 * 60 * 60), // Nightly
         }
     }
 }
 
-impl Default for NativeAuthConfig {
+impl Default for EndpointSyncConfig {
     fn default() -> Self {
         Self {
             enabled: true,
-            allow_registration: false,
-            password: PasswordConfig::default(),
-            session: SessionConfig::default(),
-            email: EmailConfig::default(),
+            check_interval: Duration::from_secs(30),
         }
     }
 }
 
-impl Default for ProxyHeaderAuthConfig {
+impl Default for MetricsConfig {
     fn default() -> Self {
         Self {
-            enabled: false,
-            header_name: "x-doubleword-user".to_string(),
-            groups_field_name: "x-doubleword-user-groups".to_string(),
-            provider_field_name: "x-doubleword-sso-provider".to_string(),
-            auto_create_users: true,
-            blacklisted_sso_groups: Vec::new(),
-            import_idp_groups: false,
+            disabled_families: vec![],
+            dropped_labels: vec![],
+            user_labels: false,
+            max_user_labels: 100,
+            group_labels: false,
+            max_group_labels: 50,
+            label_overflow: LabelOverflow::Other,
+            otlp: OtlpMetricsConfig::default(),
+            database: DatabaseMetricsConfig::default(),
         }
     }
 }
 
-impl Default for SessionConfig {
+impl Default for DatabaseMetricsConfig {
     fn default() -> Self {
         Self {
-            timeout: Duration::from_secs(24 * 60 * 60), // 24 hours
-            cookie_name: "dwctl_session".to_string(),
-            cookie_secure: true,
-            cookie_same_site: "strict".to_string(),
+            sample_interval: Duration::from_secs(10),
+            slow_query_threshold: Duration::from_millis(500),
         }
     }
 }
 
-impl Default for PasswordConfig {
+impl Default for OtlpMetricsConfig {
     fn default() -> Self {
         Self {
-            min_length: 8,
-            max_length: 64,
+            enabled: false,
+            endpoint: None,
+            headers: HashMap::new(),
+            interval: Duration::from_secs(60),
+            request_timeout: Duration::from_secs(10),
+            service_name: "dwctl".to_string(),
+        }
+    }
+}
+
+impl Default for RequestMirroringConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: None,
+            authorization: None,
+            batch_size: 500,
+            flush_interval: Duration::from_secs(2),
+            buffer_size: 10_000,
+            enqueue_timeout: Duration::from_secs(30),
+            max_retries: 5,
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Default for RequestWebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: None,
+            authorization: None,
+            filter: RequestWebhookFilter::default(),
+            max_concurrency: 8,
+            buffer_size: 10_000,
+            max_retries: 3,
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Default for EventStreamConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sink: None,
+            interval: Duration::from_secs(5),
+            batch_size: 500,
+            max_retries: 3,
+            publish_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Default for S3StorageConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            region: "us-east-1".to_string(),
+            bucket: String::new(),
+            prefix: "bodies/".to_string(),
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            path_style: false,
+            request_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint_name: "sandbox".to_string(),
+            models: vec!["sandbox-echo".to_string(), "sandbox-lorem".to_string()],
+            token_delay: Duration::from_millis(20),
+        }
+    }
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            fallback_timeout: Duration::from_secs(30),
+            change_webhook: RoutingChangeWebhookConfig::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+        }
+    }
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            failure_threshold: 3,
+        }
+    }
+}
+
+impl Default for RoutingChangeWebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: None,
+            authorization: None,
+            max_retries: 5,
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: Duration::from_secs(5 * 60),
+            at_risk_budget_percent: 25.0,
+            fast_burn_rate: 14.4,
+            burn_window: Duration::from_secs(60 * 60),
+            alert_webhook: SloAlertWebhookConfig::default(),
+        }
+    }
+}
+
+impl Default for SloAlertWebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: None,
+            authorization: None,
+            max_retries: 5,
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Default for ProbesConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_executions: 16,
+            jitter: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Default for ProbeAlertsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(15 * 60),
+            max_retries: 3,
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Default for LoadTestingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_requests: 10_000,
+            max_concurrency: 32,
+            max_requests_per_second: 50,
+            max_duration: Duration::from_secs(10 * 60),
+            max_error_rate: 0.5,
+        }
+    }
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            api_key: None,
+            model: None,
+            timeout: Duration::from_secs(5),
+            fail_open: true,
+        }
+    }
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_in_flight: 256,
+            max_queued: 1024,
+            queue_timeout: TierTimeouts::default(),
+        }
+    }
+}
+
+impl Default for FederationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token: None,
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Default for TierTimeouts {
+    fn default() -> Self {
+        Self {
+            high: Duration::from_secs(60),
+            normal: Duration::from_secs(30),
+            low: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Default for Metadata {
+    fn default() -> Self {
+        Self {
+            region: "UK South".to_string(),
+            organization: "ACME Corp".to_string(),
+            registration_enabled: true,
+        }
+    }
+}
+
+impl Default for ModelSource {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            url: Url::parse("http://localhost:8080").unwrap(),
+            api_key: None,
+            sync_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Default for NativeAuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allow_registration: false,
+            password: PasswordConfig::default(),
+            session: SessionConfig::default(),
+            email: EmailConfig::default(),
+        }
+    }
+}
+
+impl Default for ProxyHeaderAuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            header_name: "x-doubleword-user".to_string(),
+            groups_field_name: "x-doubleword-user-groups".to_string(),
+            provider_field_name: "x-doubleword-sso-provider".to_string(),
+            auto_create_users: true,
+            default_roles: vec![Role::StandardUser],
+            blacklisted_sso_groups: Vec::new(),
+            import_idp_groups: false,
+        }
+    }
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(24 * 60 * 60), // 24 hours
+            cookie_name: "dwctl_session".to_string(),
+            cookie_secure: true,
+            cookie_same_site: "strict".to_string(),
+        }
+    }
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 64,
         }
     }
 }
@@ -480,6 +1483,270 @@ impl Config {
             });
         }
 
+        // Validate endpoint re-validation interval
+        if self.endpoint_validation.enabled && self.endpoint_validation.interval.as_secs() < 60 {
+            return Err(Error::Internal {
+                operation: "Config validation: endpoint_validation.interval is too short (minimum 1 minute)".to_string(),
+            });
+        }
+
+        // Validate endpoint sync check interval
+        if self.endpoint_sync.enabled && self.endpoint_sync.check_interval.is_zero() {
+            return Err(Error::Internal {
+                operation: "Config validation: endpoint_sync.check_interval must be greater than 0".to_string(),
+            });
+        }
+
+        // Validate request mirroring target
+        if self.request_mirroring.enabled {
+            match &self.request_mirroring.url {
+                None => {
+                    return Err(Error::Internal {
+                        operation: "Config validation: request_mirroring is enabled but request_mirroring.url is not configured"
+                            .to_string(),
+                    });
+                }
+                Some(url) if url.scheme() != "https" => {
+                    return Err(Error::Internal {
+                        operation: "Config validation: request_mirroring.url must use https".to_string(),
+                    });
+                }
+                Some(_) => {}
+            }
+
+            if self.request_mirroring.batch_size == 0 || self.request_mirroring.buffer_size < self.request_mirroring.batch_size {
+                return Err(Error::Internal {
+                    operation:
+                        "Config validation: request_mirroring.buffer_size must be at least request_mirroring.batch_size (and both non-zero)"
+                            .to_string(),
+                });
+            }
+        }
+
+        // Validate request webhook target
+        if self.request_webhook.enabled {
+            match &self.request_webhook.url {
+                None => {
+                    return Err(Error::Internal {
+                        operation: "Config validation: request_webhook is enabled but request_webhook.url is not configured".to_string(),
+                    });
+                }
+                Some(url) if url.scheme() != "https" => {
+                    return Err(Error::Internal {
+                        operation: "Config validation: request_webhook.url must use https".to_string(),
+                    });
+                }
+                Some(_) => {}
+            }
+
+            if self.request_webhook.max_concurrency == 0 || self.request_webhook.buffer_size == 0 {
+                return Err(Error::Internal {
+                    operation: "Config validation: request_webhook.max_concurrency and request_webhook.buffer_size must be non-zero"
+                        .to_string(),
+                });
+            }
+        }
+
+        // Validate metric label dimensions
+        if let Some(label) = self
+            .metrics
+            .dropped_labels
+            .iter()
+            .find(|label| !crate::metrics::GEN_AI_LABELS.contains(&label.as_str()))
+        {
+            return Err(Error::Internal {
+                operation: format!(
+                    "Config validation: metrics.dropped_labels contains unknown label '{label}' (expected one of {})",
+                    crate::metrics::GEN_AI_LABELS.join(", ")
+                ),
+            });
+        }
+
+        if (self.metrics.user_labels && self.metrics.max_user_labels == 0)
+            || (self.metrics.group_labels && self.metrics.max_group_labels == 0)
+        {
+            return Err(Error::Internal {
+                operation:
+                    "Config validation: metrics.max_user_labels and metrics.max_group_labels must be non-zero when their labels are enabled"
+                        .to_string(),
+            });
+        }
+
+        // Validate the OTLP metrics exporter
+        if self.metrics.otlp.enabled {
+            if !self.enable_metrics {
+                return Err(Error::Internal {
+                    operation: "Config validation: metrics.otlp requires enable_metrics".to_string(),
+                });
+            }
+            if self.metrics.otlp.endpoint.is_none() {
+                return Err(Error::Internal {
+                    operation: "Config validation: metrics.otlp is enabled but metrics.otlp.endpoint is not configured".to_string(),
+                });
+            }
+            if self.metrics.otlp.interval.is_zero() {
+                return Err(Error::Internal {
+                    operation: "Config validation: metrics.otlp.interval must be non-zero".to_string(),
+                });
+            }
+        }
+
+        // Validate the database metrics
+        if self.enable_metrics && self.metrics.database.sample_interval.is_zero() {
+            return Err(Error::Internal {
+                operation: "Config validation: metrics.database.sample_interval must be non-zero".to_string(),
+            });
+        }
+
+        // Validate the log levels
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.logging.level) {
+            return Err(Error::Internal {
+                operation: format!("Config validation: invalid logging.level '{}': {e}", self.logging.level),
+            });
+        }
+
+        // Validate error tracking
+        if let Some(dsn) = &self.error_tracking.dsn {
+            if let Err(e) = dsn.parse::<sentry::types::Dsn>() {
+                return Err(Error::Internal {
+                    operation: format!("Config validation: invalid error_tracking.dsn: {e}"),
+                });
+            }
+        }
+        if !(0.0..=1.0).contains(&self.error_tracking.sample_rate) {
+            return Err(Error::Internal {
+                operation: "Config validation: error_tracking.sample_rate must be between 0 and 1".to_string(),
+            });
+        }
+
+        // Validate object storage for captured bodies
+        if let BodyStorageConfig::S3(s3) = &self.body_storage {
+            if s3.bucket.is_empty() || s3.access_key_id.is_empty() || s3.secret_access_key.is_empty() {
+                return Err(Error::Internal {
+                    operation: "Config validation: body_storage.bucket, body_storage.access_key_id and body_storage.secret_access_key \
+                                are required for S3 body storage"
+                        .to_string(),
+                });
+            }
+        }
+
+        // Validate the request log retention policy
+        if self.request_log_retention.enabled {
+            if self.request_log_retention.retention.is_zero() {
+                return Err(Error::Internal {
+                    operation: "Config validation: request_log_retention.retention must be greater than zero".to_string(),
+                });
+            }
+            if self.request_log_retention.batch_size < 1 {
+                return Err(Error::Internal {
+                    operation: "Config validation: request_log_retention.batch_size must be at least 1".to_string(),
+                });
+            }
+            if self.request_log_retention.action == RetentionAction::Export {
+                let configured = self
+                    .request_log_retention
+                    .export
+                    .as_ref()
+                    .is_some_and(|s3| !s3.bucket.is_empty() && !s3.access_key_id.is_empty() && !s3.secret_access_key.is_empty());
+                if !configured {
+                    return Err(Error::Internal {
+                        operation: "Config validation: request_log_retention.export with a bucket, access_key_id and secret_access_key \
+                                    is required by the export action"
+                            .to_string(),
+                    });
+                }
+            }
+        }
+
+        // Validate the event stream publisher
+        if self.event_stream.enabled {
+            let compiled_in = match &self.event_stream.sink {
+                None => {
+                    return Err(Error::Internal {
+                        operation: "Config validation: event_stream is enabled but event_stream.sink is not configured".to_string(),
+                    });
+                }
+                Some(EventSinkConfig::Kafka(_)) => cfg!(feature = "kafka"),
+                Some(EventSinkConfig::Nats(_)) => cfg!(feature = "nats"),
+            };
+            if !compiled_in {
+                return Err(Error::Internal {
+                    operation: "Config validation: event_stream.sink type requires dwctl to be built with the matching feature \
+                                (kafka or nats)"
+                        .to_string(),
+                });
+            }
+            if self.event_stream.batch_size < 1 {
+                return Err(Error::Internal {
+                    operation: "Config validation: event_stream.batch_size must be at least 1".to_string(),
+                });
+            }
+        }
+
+        // Validate routing change notifications target
+        if self.routing.change_webhook.enabled {
+            match &self.routing.change_webhook.url {
+                None => {
+                    return Err(Error::Internal {
+                        operation: "Config validation: routing.change_webhook is enabled but routing.change_webhook.url is not configured"
+                            .to_string(),
+                    });
+                }
+                Some(url) if url.scheme() != "https" => {
+                    return Err(Error::Internal {
+                        operation: "Config validation: routing.change_webhook.url must use https".to_string(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+
+        // Validate SLO evaluation and its alert target
+        if !(0.0..=100.0).contains(&self.slos.at_risk_budget_percent) {
+            return Err(Error::Internal {
+                operation: "Config validation: slos.at_risk_budget_percent must be between 0 and 100".to_string(),
+            });
+        }
+        if self.slos.burn_window.is_zero() || self.slos.interval.is_zero() {
+            return Err(Error::Internal {
+                operation: "Config validation: slos.interval and slos.burn_window must be non-zero".to_string(),
+            });
+        }
+        if self.slos.alert_webhook.enabled {
+            match &self.slos.alert_webhook.url {
+                None => {
+                    return Err(Error::Internal {
+                        operation: "Config validation: slos.alert_webhook is enabled but slos.alert_webhook.url is not configured"
+                            .to_string(),
+                    });
+                }
+                Some(url) if url.scheme() != "https" => {
+                    return Err(Error::Internal {
+                        operation: "Config validation: slos.alert_webhook.url must use https".to_string(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+
+        if self.probes.request_timeout.is_zero() {
+            return Err(Error::Internal {
+                operation: "Config validation: probes.request_timeout must be non-zero".to_string(),
+            });
+        }
+        if self.probe_alerts.failure_threshold == 0 {
+            return Err(Error::Internal {
+                operation: "Config validation: probe_alerts.failure_threshold must be at least 1".to_string(),
+            });
+        }
+
+        // An empty federation token would let anyone read this instance's summary
+        if self.federation.token.as_deref().is_some_and(|token| token.trim().is_empty()) {
+            return Err(Error::Internal {
+                operation: "Config validation: federation.token cannot be empty".to_string(),
+            });
+        }
+
         Ok(())
     }
 
@@ -522,6 +1789,7 @@ model_sources:
 
             let args = Args {
                 config: "test.yaml".to_string(),
+                command: None,
             };
 
             let config = Config::load(&args)?;
@@ -560,6 +1828,7 @@ metadata:
 
             let args = Args {
                 config: "test.yaml".to_string(),
+                command: None,
             };
 
             let config = Config::load(&args)?;
@@ -599,6 +1868,7 @@ auth:
 
             let args = Args {
                 config: "test.yaml".to_string(),
+                command: None,
             };
 
             let config = Config::load(&args)?;
@@ -653,6 +1923,266 @@ auth:
         assert!(result.unwrap_err().to_string().contains("No authentication methods"));
     }
 
+    #[test]
+    fn test_config_validation_endpoint_validation_interval_too_short() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.endpoint_validation.interval = Duration::from_secs(5);
+
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("endpoint_validation.interval"));
+    }
+
+    #[test]
+    fn test_config_validation_endpoint_sync_check_interval_zero() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.endpoint_sync.check_interval = Duration::ZERO;
+
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("endpoint_sync.check_interval"));
+    }
+
+    #[test]
+    fn test_config_validation_request_mirroring_requires_https() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.request_mirroring.enabled = true;
+
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("request_mirroring.url"));
+
+        config.request_mirroring.url = Some("http://lake.example.com/ingest".parse().unwrap());
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("https"));
+
+        config.request_mirroring.url = Some("https://lake.example.com/ingest".parse().unwrap());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_request_webhook_requires_https() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.request_webhook.enabled = true;
+
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("request_webhook.url"));
+
+        config.request_webhook.url = Some("http://siem.example.com/events".parse().unwrap());
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("https"));
+
+        config.request_webhook.url = Some("https://siem.example.com/events".parse().unwrap());
+        assert!(config.validate().is_ok());
+
+        config.request_webhook.max_concurrency = 0;
+        assert!(config.validate().unwrap_err().to_string().contains("max_concurrency"));
+    }
+
+    #[test]
+    fn test_config_validation_slos() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.slos.alert_webhook.enabled = true;
+
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("slos.alert_webhook.url"));
+
+        config.slos.alert_webhook.url = Some("https://alerts.example.com/slo".parse().unwrap());
+        assert!(config.validate().is_ok());
+
+        config.slos.at_risk_budget_percent = 150.0;
+        assert!(config.validate().unwrap_err().to_string().contains("at_risk_budget_percent"));
+    }
+
+    #[test]
+    fn test_config_validation_event_stream() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.event_stream.enabled = true;
+
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("event_stream.sink"));
+
+        config.event_stream.sink = Some(EventSinkConfig::Nats(NatsSinkConfig {
+            url: "nats://nats:4222".to_string(),
+            subject: "dwctl.analytics".to_string(),
+            dead_letter_subject: "dwctl.analytics.dlq".to_string(),
+            token: None,
+        }));
+        assert_eq!(config.validate().is_ok(), cfg!(feature = "nats"));
+    }
+
+    #[test]
+    fn test_metrics_config() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.metrics.disabled_families = vec!["gen_ai_client_token_usage".to_string(), "dwctl_admission_*".to_string()];
+
+        assert!(!config.metrics.family_enabled("gen_ai_client_token_usage"));
+        assert!(!config.metrics.family_enabled("dwctl_admission_queue_depth"));
+        assert!(config.metrics.family_enabled("gen_ai_server_request_duration_seconds"));
+
+        config.metrics.dropped_labels = vec!["server_port".to_string(), "user".to_string()];
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("unknown label 'user'"));
+
+        config.metrics.dropped_labels.pop();
+        assert!(config.validate().is_ok());
+
+        config.metrics.group_labels = true;
+        config.metrics.max_group_labels = 0;
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("max_group_labels must be non-zero"));
+
+        config.metrics.max_group_labels = 10;
+        config.metrics.otlp.enabled = true;
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("metrics.otlp.endpoint"));
+
+        config.metrics.otlp.endpoint = Some("http://otel-collector:4318/v1/metrics".parse().unwrap());
+        assert!(config.validate().is_ok());
+
+        config.metrics.database.sample_interval = Duration::ZERO;
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("metrics.database.sample_interval"));
+    }
+
+    #[test]
+    fn test_s3_body_storage_config() {
+        Jail::expect_with(|jail| {
+            jail.create_file(
+                "test.yaml",
+                r#"
+secret_key: hello
+body_storage:
+  type: s3
+  endpoint: http://minio:9000
+  bucket: dwctl-bodies
+  access_key_id: minio
+  path_style: true
+"#,
+            )?;
+            let args = Args {
+                config: "test.yaml".to_string(),
+                command: None,
+            };
+
+            // The secret is required
+            assert!(Config::load(&args).unwrap_err().to_string().contains("body_storage"));
+
+            jail.set_env("DWCTL_BODY_STORAGE__SECRET_ACCESS_KEY", "minio-secret");
+            let config = Config::load(&args)?;
+            let BodyStorageConfig::S3(s3) = config.body_storage else {
+                panic!("expected S3 body storage");
+            };
+            assert_eq!(s3.endpoint.unwrap().as_str(), "http://minio:9000/");
+            assert_eq!(s3.bucket, "dwctl-bodies");
+            assert_eq!(s3.secret_access_key, "minio-secret");
+            assert!(s3.path_style);
+            // Unset fields keep their defaults
+            assert_eq!(s3.region, "us-east-1");
+            assert_eq!(s3.prefix, "bodies/");
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_config_validation_retention_export_requires_bucket() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.request_log_retention.enabled = true;
+        config.request_log_retention.action = RetentionAction::Export;
+
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("request_log_retention.export"));
+
+        config.request_log_retention.export = Some(S3StorageConfig {
+            bucket: "dwctl-archive".to_string(),
+            access_key_id: "key".to_string(),
+            secret_access_key: "secret".to_string(),
+            ..Default::default()
+        });
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_routing_change_webhook_requires_https() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.routing.change_webhook.enabled = true;
+
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("routing.change_webhook.url"));
+
+        config.routing.change_webhook.url = Some("http://changes.example.com/routing".parse().unwrap());
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("https"));
+
+        config.routing.change_webhook.url = Some("https://changes.example.com/routing".parse().unwrap());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_federation_token_not_empty() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.federation.token = Some("  ".to_string());
+
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("federation.token"));
+
+        config.federation.token = Some("shared-secret".to_string());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_logging_level() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.logging.level = "info,dwctl=loud".to_string();
+
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("logging.level"));
+
+        config.logging.level = "warn,dwctl::auth=debug".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_error_tracking() {
+        let mut config = Config::default();
+        config.auth.native.enabled = true;
+        config.secret_key = Some("test-secret-key".to_string());
+        config.error_tracking.dsn = Some("not a dsn".to_string());
+
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("error_tracking.dsn"));
+
+        config.error_tracking.dsn = Some("https://public@sentry.example.com/1".to_string());
+        config.error_tracking.sample_rate = 1.5;
+        let result = config.validate();
+        assert!(result.unwrap_err().to_string().contains("error_tracking.sample_rate"));
+
+        config.error_tracking.sample_rate = 0.25;
+        assert!(config.validate().is_ok());
+    }
+
     #[test]
     fn test_config_validation_valid_config() {
         let mut config = Config::default();
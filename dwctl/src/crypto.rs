@@ -1,5 +1,32 @@
+//! API key generation, and encryption of the secrets stored in the database.
+//!
+//! API key secrets and inference endpoint API keys are encrypted at rest when a key is configured
+//! (`secret_encryption` in the config). Each value is encrypted with its own random data key, and
+//! that data key is stored alongside it, encrypted with the configured key (envelope encryption).
+//! Values are stored as `enc:v1:<key id>:<encrypted data key>:<ciphertext>`, so rotating the
+//! configured key only means re-encrypting data keys, which `dwctl encrypt-secrets` does, along
+//! with encrypting any values still stored in plaintext. Repositories encrypt values as they write
+//! them and decrypt them as they read them, passing plaintext values through unchanged.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, bail, Context};
 use base64::{engine::general_purpose, Engine as _};
 use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::OnceLock;
+
+use crate::config::SecretEncryptionConfig;
+
+/// Prefix of encrypted values, which plaintext API keys never start with
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const NONCE_LENGTH: usize = 12;
+
+/// The cipher repositories encrypt secrets with, when encryption is configured
+static SECRET_CIPHER: OnceLock<SecretCipher> = OnceLock::new();
 
 /// Generates a cryptographically secure API key with 256 bits of entropy.
 ///
@@ -27,11 +54,302 @@ pub fn generate_api_key() -> String {
     format!("sk-{}", general_purpose::URL_SAFE_NO_PAD.encode(key_bytes))
 }
 
+/// Encrypts and decrypts stored secrets with a key encryption key, falling back to previous keys
+/// to decrypt values written before the key was rotated
+pub struct SecretCipher {
+    current: (String, Aes256Gcm),
+    previous: Vec<(String, Aes256Gcm)>,
+}
+
+impl SecretCipher {
+    /// A cipher from base64-encoded 256-bit keys
+    pub fn new(key: &str, previous_keys: &[String]) -> anyhow::Result<Self> {
+        Ok(Self {
+            current: Self::key_encryption_key(key)?,
+            previous: previous_keys
+                .iter()
+                .map(|key| Self::key_encryption_key(key))
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+
+    /// The cipher described by the config, or `None` if no key is configured
+    pub fn from_config(config: &SecretEncryptionConfig) -> anyhow::Result<Option<Self>> {
+        let key = match (&config.key, &config.key_file) {
+            (Some(_), Some(_)) => bail!("secret_encryption.key and secret_encryption.key_file can't both be set"),
+            (Some(key), None) => key.clone(),
+            (None, Some(path)) => std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read the secret encryption key from {}", path.display()))?
+                .trim()
+                .to_string(),
+            (None, None) => return Ok(None),
+        };
+        Self::new(&key, &config.previous_keys).map(Some)
+    }
+
+    fn key_encryption_key(key: &str) -> anyhow::Result<(String, Aes256Gcm)> {
+        let key = general_purpose::STANDARD
+            .decode(key.trim())
+            .context("Secret encryption keys must be base64-encoded")?;
+        if key.len() != 32 {
+            bail!("Secret encryption keys must be 32 bytes long, got {}", key.len());
+        }
+        // Identifies the key a value's data key was encrypted with, without revealing it
+        let id = hex::encode(&Sha256::digest(&key)[..4]);
+        Ok((id, Aes256Gcm::new_from_slice(&key)?))
+    }
+
+    /// Encrypt `plaintext` with a new data key
+    pub fn encrypt(&self, plaintext: &str) -> anyhow::Result<String> {
+        let data_key = Aes256Gcm::generate_key(&mut thread_rng());
+        let ciphertext = seal(&Aes256Gcm::new(&data_key), plaintext.as_bytes())?;
+        let (key_id, key) = &self.current;
+        let encrypted_data_key = seal(key, &data_key)?;
+        Ok(format!(
+            "{ENCRYPTED_PREFIX}{key_id}:{}:{}",
+            general_purpose::STANDARD.encode(encrypted_data_key),
+            general_purpose::STANDARD.encode(ciphertext)
+        ))
+    }
+
+    /// Decrypt a stored value, passing plaintext values through
+    pub fn decrypt(&self, stored: String) -> anyhow::Result<String> {
+        let Some(encrypted) = EncryptedSecret::parse(&stored)? else {
+            return Ok(stored);
+        };
+        let data_key = open(self.key(encrypted.key_id)?, &encrypted.encrypted_data_key)?;
+        let plaintext = open(&Aes256Gcm::new_from_slice(&data_key)?, &encrypted.ciphertext)?;
+        String::from_utf8(plaintext).context("Decrypted secret isn't valid UTF-8")
+    }
+
+    /// Bring a stored value up to date with the current key: encrypt it if it's plaintext, or
+    /// re-encrypt its data key if that was encrypted with a previous key. Returns `None` if it's
+    /// already encrypted with the current key.
+    pub fn reencrypt(&self, stored: &str) -> anyhow::Result<Option<String>> {
+        let Some(encrypted) = EncryptedSecret::parse(stored)? else {
+            return self.encrypt(stored).map(Some);
+        };
+        let (current_id, current_key) = &self.current;
+        if encrypted.key_id == current_id {
+            return Ok(None);
+        }
+        let data_key = open(self.key(encrypted.key_id)?, &encrypted.encrypted_data_key)?;
+        Ok(Some(format!(
+            "{ENCRYPTED_PREFIX}{current_id}:{}:{}",
+            general_purpose::STANDARD.encode(seal(current_key, &data_key)?),
+            general_purpose::STANDARD.encode(encrypted.ciphertext)
+        )))
+    }
+
+    fn key(&self, key_id: &str) -> anyhow::Result<&Aes256Gcm> {
+        std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|(id, _)| id == key_id)
+            .map(|(_, key)| key)
+            .ok_or_else(|| anyhow!("Secret was encrypted with an unknown key ({key_id})"))
+    }
+}
+
+/// Encrypt with a random nonce, which is prepended to the ciphertext
+fn seal(key: &Aes256Gcm, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let nonce: [u8; NONCE_LENGTH] = thread_rng().gen();
+    let ciphertext = key
+        .encrypt(&Nonce::from(nonce), plaintext)
+        .map_err(|_| anyhow!("Failed to encrypt secret"))?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+fn open(key: &Aes256Gcm, sealed: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if sealed.len() < NONCE_LENGTH {
+        bail!("Encrypted secret is truncated");
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+    let nonce: [u8; NONCE_LENGTH] = nonce.try_into()?;
+    key.decrypt(&Nonce::from(nonce), ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt secret"))
+}
+
+/// The parts of an encrypted value
+struct EncryptedSecret<'a> {
+    key_id: &'a str,
+    encrypted_data_key: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl<'a> EncryptedSecret<'a> {
+    /// Split a stored value into its parts, or `None` if it's plaintext
+    fn parse(stored: &'a str) -> anyhow::Result<Option<Self>> {
+        let Some(encrypted) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(None);
+        };
+        let mut parts = encrypted.splitn(3, ':');
+        let (Some(key_id), Some(encrypted_data_key), Some(ciphertext)) = (parts.next(), parts.next(), parts.next()) else {
+            bail!("Malformed encrypted secret");
+        };
+        Ok(Some(Self {
+            key_id,
+            encrypted_data_key: general_purpose::STANDARD.decode(encrypted_data_key)?,
+            ciphertext: general_purpose::STANDARD.decode(ciphertext)?,
+        }))
+    }
+}
+
+/// Set the cipher used by repositories. Called once at startup; without it, secrets are stored
+/// in plaintext.
+pub fn init_secret_encryption(cipher: SecretCipher) -> anyhow::Result<()> {
+    SECRET_CIPHER
+        .set(cipher)
+        .map_err(|_| anyhow!("Secret encryption is already initialized"))
+}
+
+/// Encrypt a secret for storage, if encryption is configured
+pub fn encrypt_secret(plaintext: &str) -> anyhow::Result<String> {
+    match SECRET_CIPHER.get() {
+        Some(cipher) => cipher.encrypt(plaintext),
+        None => Ok(plaintext.to_string()),
+    }
+}
+
+/// Decrypt a stored secret. Plaintext values are passed through.
+pub fn decrypt_secret(stored: String) -> anyhow::Result<String> {
+    match SECRET_CIPHER.get() {
+        Some(cipher) => cipher.decrypt(stored),
+        None if stored.starts_with(ENCRYPTED_PREFIX) => bail!("Secret is encrypted, but no secret encryption key is configured"),
+        None => Ok(stored),
+    }
+}
+
+/// Encrypt the API key secrets and endpoint API keys still stored in plaintext, and re-encrypt
+/// the data keys of those encrypted with a previous key. Returns the number of values rewritten.
+pub async fn encrypt_stored_secrets(pool: &PgPool, cipher: &SecretCipher) -> anyhow::Result<u64> {
+    let mut tx = pool.begin().await?;
+    let mut rewritten = 0;
+
+    // The hashes API keys are looked up by are of the plaintext, so they're unchanged
+    let api_keys = sqlx::query!("SELECT id, secret FROM api_keys FOR UPDATE")
+        .fetch_all(&mut *tx)
+        .await?;
+    for api_key in api_keys {
+        if let Some(secret) = cipher.reencrypt(&api_key.secret)? {
+            sqlx::query!("UPDATE api_keys SET secret = $1 WHERE id = $2", secret, api_key.id)
+                .execute(&mut *tx)
+                .await?;
+            rewritten += 1;
+        }
+    }
+
+    let endpoints = sqlx::query!("SELECT id, api_key as \"api_key!\" FROM inference_endpoints WHERE api_key IS NOT NULL FOR UPDATE")
+        .fetch_all(&mut *tx)
+        .await?;
+    for endpoint in endpoints {
+        if let Some(api_key) = cipher.reencrypt(&endpoint.api_key)? {
+            sqlx::query!("UPDATE inference_endpoints SET api_key = $1 WHERE id = $2", api_key, endpoint.id)
+                .execute(&mut *tx)
+                .await?;
+            rewritten += 1;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(rewritten)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::collections::HashSet;
 
+    fn key() -> String {
+        let mut key = [0u8; 32];
+        thread_rng().fill(&mut key);
+        general_purpose::STANDARD.encode(key)
+    }
+
+    #[test]
+    fn test_secret_round_trip() {
+        let cipher = SecretCipher::new(&key(), &[]).unwrap();
+        let encrypted = cipher.encrypt("sk-secret").unwrap();
+        assert!(encrypted.starts_with(ENCRYPTED_PREFIX));
+        assert!(!encrypted.contains("sk-secret"));
+        // Each value gets its own data key and nonce
+        assert_ne!(cipher.encrypt("sk-secret").unwrap(), encrypted);
+        assert_eq!(cipher.decrypt(encrypted).unwrap(), "sk-secret");
+
+        // Plaintext values are passed through
+        assert_eq!(cipher.decrypt("sk-plain".to_string()).unwrap(), "sk-plain");
+    }
+
+    #[test]
+    fn test_secret_key_rotation() {
+        let (old_key, new_key) = (key(), key());
+        let old_cipher = SecretCipher::new(&old_key, &[]).unwrap();
+        let encrypted = old_cipher.encrypt("sk-secret").unwrap();
+
+        // A cipher without the old key can't decrypt the value
+        assert!(SecretCipher::new(&new_key, &[]).unwrap().decrypt(encrypted.clone()).is_err());
+
+        let cipher = SecretCipher::new(&new_key, std::slice::from_ref(&old_key)).unwrap();
+        assert_eq!(cipher.decrypt(encrypted.clone()).unwrap(), "sk-secret");
+        let reencrypted = cipher.reencrypt(&encrypted).unwrap().unwrap();
+        assert_eq!(cipher.reencrypt(&reencrypted).unwrap(), None);
+        assert_eq!(SecretCipher::new(&new_key, &[]).unwrap().decrypt(reencrypted).unwrap(), "sk-secret");
+
+        let encrypted = cipher.reencrypt("sk-plain").unwrap().unwrap();
+        assert_eq!(cipher.decrypt(encrypted).unwrap(), "sk-plain");
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_encrypt_stored_secrets(pool: PgPool) {
+        use crate::{api::models::users::Role, test_utils::*};
+
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let api_key = create_test_api_key_for_user(&pool, user.id).await;
+        let endpoint_id = sqlx::query_scalar!(
+            "INSERT INTO inference_endpoints (name, url, api_key, created_by) VALUES ('upstream', 'https://api.example.com/v1', 'upstream-key', $1) RETURNING id",
+            user.id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let old_key = key();
+        let cipher = SecretCipher::new(&old_key, &[]).unwrap();
+        let rewritten = encrypt_stored_secrets(&pool, &cipher).await.unwrap();
+        // The user's key, the system key and the endpoint's key
+        assert_eq!(rewritten, 3);
+        assert_eq!(encrypt_stored_secrets(&pool, &cipher).await.unwrap(), 0);
+
+        // Keys are still found by their plaintext secret
+        let secret = sqlx::query_scalar!(
+            "SELECT secret FROM api_keys WHERE secret_hash = api_key_secret_hash($1)",
+            api_key.secret
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(secret.starts_with(ENCRYPTED_PREFIX));
+        assert_eq!(cipher.decrypt(secret).unwrap(), api_key.secret);
+
+        // After rotating the key, data keys are re-encrypted with the new one
+        let cipher = SecretCipher::new(&key(), &[old_key]).unwrap();
+        assert_eq!(encrypt_stored_secrets(&pool, &cipher).await.unwrap(), 3);
+        let endpoint_key = sqlx::query_scalar!("SELECT api_key FROM inference_endpoints WHERE id = $1", endpoint_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .unwrap();
+        let (current_id, _) = &cipher.current;
+        assert!(endpoint_key.starts_with(&format!("{ENCRYPTED_PREFIX}{current_id}:")));
+        assert_eq!(cipher.decrypt(endpoint_key).unwrap(), "upstream-key");
+    }
+
+    #[test]
+    fn test_secret_cipher_rejects_invalid_keys() {
+        assert!(SecretCipher::new("not base64!", &[]).is_err());
+        assert!(SecretCipher::new(&general_purpose::STANDARD.encode([0u8; 16]), &[]).is_err());
+    }
+
     #[test]
     fn test_generate_api_key_format() {
         let key = generate_api_key();
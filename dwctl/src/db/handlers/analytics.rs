@@ -3,16 +3,27 @@
 //! Provides functions for generating analytics reports from logged HTTP requests.
 
 use chrono::{DateTime, Duration, Timelike, Utc};
+use futures::stream::BoxStream;
 use sqlx::{FromRow, PgPool};
 use std::collections::HashMap;
 use tracing::instrument;
 
 use crate::{
     api::models::{
-        deployments::{ModelMetrics, ModelTimeSeriesPoint},
-        requests::{ModelUsage, ModelUserUsageResponse, RequestsAggregateResponse, StatusCodeBreakdown, TimeSeriesPoint, UserUsage},
+        adoption::{AdoptionResponse, AdoptionTrendPoint, GroupAdoption},
+        deployments::{CanaryVariantMetrics, DayOfWeek, ModelMetrics, ModelTimeSeriesPoint},
+        requests::{
+            AttributeModelUsage, AttributeUsageResponse, ConversationUsage, ConversationUsageResponse, DeploymentUsage,
+            EmbeddingUsagePoint, EmbeddingUsageResponse, EndpointUsage, ErrorBreakdownResponse, ErrorStatusBreakdown, ErrorType,
+            ErrorTypeBreakdown, GroupModelUsage, GroupPiiStats, GroupUsageResponse, ModelErrorBreakdown, ModelUsage, ModelUsageResponse,
+            ModelUserUsageResponse, PiiCategoryBreakdown, PiiStatsResponse, RequestExportRecord, RequestsAggregateResponse,
+            StatusCodeBreakdown, TagUsageResponse, TagValueUsage, TimeSeriesPoint, UsageMetric, UsageTimeSeriesPoint,
+            UsageTimeSeriesResponse, UserErrorBreakdown, UserUsage,
+        },
     },
-    db::errors::Result,
+    db::{errors::Result, health::timed},
+    request_logging::pii::PiiCategory,
+    types::UserId,
 };
 
 /// Time granularity for analytics queries
@@ -49,12 +60,14 @@ struct ModelUsageRow {
     pub model_name: Option<String>,
     pub model_count: Option<i64>,
     pub model_avg_latency_ms: Option<f64>,
+    pub model_total_cost: Option<f64>,
 }
 
-/// Total requests count
+/// Total request count and cost
 #[derive(FromRow)]
 struct TotalRequestsRow {
     pub total_requests: Option<i64>,
+    pub total_cost: Option<f64>,
 }
 
 /// Model metrics aggregation from analytics query
@@ -67,39 +80,35 @@ struct ModelMetricsRow {
     pub last_active_at: Option<DateTime<Utc>>,
 }
 
-/// Get total request count
+/// Get total request count and the cost of those requests
 #[instrument(skip(db), err)]
 async fn get_total_requests(
     db: &PgPool,
     time_range_start: DateTime<Utc>,
     time_range_end: DateTime<Utc>,
     model_filter: Option<&str>,
-) -> Result<i64> {
-    let total_requests = if let Some(model) = model_filter {
+) -> Result<(i64, Option<f64>)> {
+    let row = if let Some(model) = model_filter {
         sqlx::query_as!(
             TotalRequestsRow,
-            "SELECT COUNT(*) as total_requests FROM http_analytics WHERE uri LIKE '/ai/%' AND timestamp >= $1 AND timestamp <= $2 AND model = $3",
+            "SELECT COUNT(*) as total_requests, SUM(total_cost)::float8 as total_cost FROM http_analytics WHERE uri LIKE '/ai/%' AND timestamp >= $1 AND timestamp <= $2 AND model = $3",
             time_range_start,
             time_range_end,
             model
         )
         .fetch_one(db)
         .await?
-        .total_requests
-        .unwrap_or(0)
     } else {
         sqlx::query_as!(
             TotalRequestsRow,
-            "SELECT COUNT(*) as total_requests FROM http_analytics WHERE uri LIKE '/ai/%' AND timestamp >= $1 AND timestamp <= $2",
+            "SELECT COUNT(*) as total_requests, SUM(total_cost)::float8 as total_cost FROM http_analytics WHERE uri LIKE '/ai/%' AND timestamp >= $1 AND timestamp <= $2",
             time_range_start,
             time_range_end
         )
         .fetch_one(db)
         .await?
-        .total_requests
-        .unwrap_or(0)
     };
-    Ok(total_requests)
+    Ok((row.total_requests.unwrap_or(0), row.total_cost))
 }
 
 /// Get time series data with configurable granularity
@@ -271,6 +280,250 @@ async fn get_time_series_ten_minutes(
     Ok(filled_time_series)
 }
 
+/// Get failed requests broken down by status code, kind of failure, deployment and user
+#[instrument(skip(db), err)]
+pub async fn get_error_breakdown(
+    db: &PgPool,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    model_filter: Option<&str>,
+    user_limit: i64,
+) -> Result<ErrorBreakdownResponse> {
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+            COUNT(*) as "total_requests!",
+            COUNT(*) FILTER (WHERE status_code >= 400) as "total_errors!"
+        FROM http_analytics
+        WHERE uri LIKE '/ai/%'
+            AND timestamp >= $1
+            AND timestamp <= $2
+            AND ($3::text IS NULL OR model = $3)
+        "#,
+        start_date,
+        end_date,
+        model_filter
+    )
+    .fetch_one(db)
+    .await?;
+
+    let statuses = sqlx::query!(
+        r#"
+        SELECT status_code as "status_code!", COUNT(*) as "count!"
+        FROM http_analytics
+        WHERE uri LIKE '/ai/%'
+            AND timestamp >= $1
+            AND timestamp <= $2
+            AND ($3::text IS NULL OR model = $3)
+            AND status_code >= 400
+        GROUP BY status_code
+        ORDER BY 2 DESC, 1
+        "#,
+        start_date,
+        end_date,
+        model_filter
+    )
+    .fetch_all(db)
+    .await?;
+
+    let models = sqlx::query!(
+        r#"
+        SELECT
+            model as "model!",
+            COUNT(*) as "requests!",
+            COUNT(*) FILTER (WHERE status_code >= 400) as "errors!"
+        FROM http_analytics
+        WHERE uri LIKE '/ai/%'
+            AND timestamp >= $1
+            AND timestamp <= $2
+            AND ($3::text IS NULL OR model = $3)
+            AND model IS NOT NULL
+        GROUP BY model
+        HAVING COUNT(*) FILTER (WHERE status_code >= 400) > 0
+        ORDER BY 3 DESC, 1
+        "#,
+        start_date,
+        end_date,
+        model_filter
+    )
+    .fetch_all(db)
+    .await?;
+
+    let users = sqlx::query!(
+        r#"
+        SELECT
+            user_id,
+            MAX(user_email) as user_email,
+            COUNT(*) as "requests!",
+            COUNT(*) FILTER (WHERE status_code >= 400) as "errors!"
+        FROM http_analytics
+        WHERE uri LIKE '/ai/%'
+            AND timestamp >= $1
+            AND timestamp <= $2
+            AND ($3::text IS NULL OR model = $3)
+        GROUP BY user_id
+        HAVING COUNT(*) FILTER (WHERE status_code >= 400) > 0
+        ORDER BY 4 DESC, 2
+        LIMIT $4
+        "#,
+        start_date,
+        end_date,
+        model_filter,
+        user_limit
+    )
+    .fetch_all(db)
+    .await?;
+
+    let by_status: Vec<ErrorStatusBreakdown> = statuses
+        .into_iter()
+        .filter_map(|row| {
+            Some(ErrorStatusBreakdown {
+                status_code: row.status_code,
+                error_type: ErrorType::from_status(row.status_code)?,
+                count: row.count,
+                percentage: percentage(row.count, totals.total_errors),
+            })
+        })
+        .collect();
+
+    let mut type_counts: Vec<(ErrorType, i64)> = Vec::new();
+    for status in &by_status {
+        match type_counts.iter_mut().find(|(error_type, _)| *error_type == status.error_type) {
+            Some((_, count)) => *count += status.count,
+            None => type_counts.push((status.error_type, status.count)),
+        }
+    }
+    type_counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    Ok(ErrorBreakdownResponse {
+        start_date,
+        end_date,
+        total_requests: totals.total_requests,
+        total_errors: totals.total_errors,
+        error_rate: percentage(totals.total_errors, totals.total_requests),
+        by_status,
+        by_type: type_counts
+            .into_iter()
+            .map(|(error_type, count)| ErrorTypeBreakdown {
+                error_type,
+                count,
+                percentage: percentage(count, totals.total_errors),
+            })
+            .collect(),
+        by_model: models
+            .into_iter()
+            .map(|row| ModelErrorBreakdown {
+                model: row.model,
+                requests: row.requests,
+                errors: row.errors,
+                error_rate: percentage(row.errors, row.requests),
+            })
+            .collect(),
+        by_user: users
+            .into_iter()
+            .map(|row| UserErrorBreakdown {
+                user_id: row.user_id,
+                user_email: row.user_email,
+                requests: row.requests,
+                errors: row.errors,
+                error_rate: percentage(row.errors, row.requests),
+            })
+            .collect(),
+    })
+}
+
+/// Usage in one bucket of a usage time series
+#[derive(FromRow)]
+struct UsageBucketRow {
+    pub bucket: DateTime<Utc>,
+    pub requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost: Option<f64>,
+}
+
+/// Origin buckets are aligned to: a Monday at midnight UTC, so daily buckets start at midnight
+/// and weekly buckets on Mondays
+fn bucket_origin() -> DateTime<Utc> {
+    DateTime::from_timestamp(946_857_600, 0).expect("2000-01-03 is a valid timestamp")
+}
+
+/// Start of the bucket `timestamp` falls in
+fn bucket_start(timestamp: DateTime<Utc>, bucket: Duration) -> DateTime<Utc> {
+    let origin = bucket_origin();
+    let offset = (timestamp - origin).num_seconds().div_euclid(bucket.num_seconds());
+    origin + Duration::seconds(offset * bucket.num_seconds())
+}
+
+/// Get usage in fixed-width buckets, including empty buckets
+#[instrument(skip(db), err)]
+pub async fn get_usage_time_series(
+    db: &PgPool,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    bucket: Duration,
+    metric: UsageMetric,
+    model_filter: Option<&str>,
+) -> Result<UsageTimeSeriesResponse> {
+    let query = sqlx::query_as!(
+        UsageBucketRow,
+        r#"
+        SELECT
+            date_bin($3::bigint * INTERVAL '1 second', timestamp, $4) as "bucket!",
+            COUNT(*) as "requests!",
+            COALESCE(SUM(prompt_tokens), 0)::bigint as "input_tokens!",
+            COALESCE(SUM(completion_tokens), 0)::bigint as "output_tokens!",
+            SUM(total_cost)::float8 as cost
+        FROM http_analytics
+        WHERE uri LIKE '/ai/%'
+            AND timestamp >= $1
+            AND timestamp <= $2
+            AND ($5::text IS NULL OR model = $5)
+        GROUP BY 1
+        ORDER BY 1
+        "#,
+        start_date,
+        end_date,
+        bucket.num_seconds(),
+        bucket_origin(),
+        model_filter
+    )
+    .fetch_all(db);
+    let rows = timed("analytics.usage_time_series", query).await?;
+
+    let values: HashMap<DateTime<Utc>, f64> = rows
+        .into_iter()
+        .map(|row| {
+            let value = match metric {
+                UsageMetric::Requests => row.requests as f64,
+                UsageMetric::Tokens => (row.input_tokens + row.output_tokens) as f64,
+                UsageMetric::InputTokens => row.input_tokens as f64,
+                UsageMetric::OutputTokens => row.output_tokens as f64,
+                UsageMetric::Cost => row.cost.unwrap_or(0.0),
+            };
+            (row.bucket, value)
+        })
+        .collect();
+
+    let mut points = Vec::new();
+    let mut current = bucket_start(start_date, bucket);
+    while current <= end_date {
+        points.push(UsageTimeSeriesPoint {
+            timestamp: current,
+            value: values.get(&current).copied().unwrap_or(0.0),
+        });
+        current += bucket;
+    }
+
+    Ok(UsageTimeSeriesResponse {
+        metric,
+        bucket_seconds: bucket.num_seconds(),
+        start_date,
+        end_date,
+        points,
+    })
+}
+
 /// Fill in missing hourly intervals with zero values
 fn fill_missing_intervals(
     mut time_series: Vec<TimeSeriesPoint>,
@@ -400,7 +653,7 @@ async fn get_status_codes(
 async fn get_model_usage(db: &PgPool, time_range_start: DateTime<Utc>, time_range_end: DateTime<Utc>) -> Result<Vec<ModelUsageRow>> {
     let rows = sqlx::query_as!(
         ModelUsageRow,
-        "SELECT model as model_name, COUNT(*) as model_count, COALESCE(AVG(duration_ms), 0)::float8 as model_avg_latency_ms FROM http_analytics WHERE uri LIKE '/ai/%' AND timestamp >= $1 AND timestamp <= $2 AND model IS NOT NULL GROUP BY model ORDER BY model_count DESC",
+        "SELECT model as model_name, COUNT(*) as model_count, COALESCE(AVG(duration_ms), 0)::float8 as model_avg_latency_ms, SUM(total_cost)::float8 as model_total_cost FROM http_analytics WHERE uri LIKE '/ai/%' AND timestamp >= $1 AND timestamp <= $2 AND model IS NOT NULL GROUP BY model ORDER BY model_count DESC",
         time_range_start,
         time_range_end
     )
@@ -419,21 +672,39 @@ pub async fn get_requests_aggregate(
     model_filter: Option<&str>,
 ) -> Result<RequestsAggregateResponse> {
     // Execute all queries concurrently
-    let (total_requests, time_series, status_code_rows, model_rows) = if model_filter.is_some() {
+    let ((total_requests, total_cost), time_series, status_code_rows, model_rows) = if model_filter.is_some() {
         // For single model view, don't fetch model breakdown
         let (total_requests, time_series, status_code_rows) = tokio::try_join!(
-            get_total_requests(db, time_range_start, time_range_end, model_filter),
-            get_time_series(db, time_range_start, time_range_end, model_filter, TimeGranularity::Hour),
-            get_status_codes(db, time_range_start, time_range_end, model_filter),
+            timed(
+                "analytics.total_requests",
+                get_total_requests(db, time_range_start, time_range_end, model_filter)
+            ),
+            timed(
+                "analytics.time_series",
+                get_time_series(db, time_range_start, time_range_end, model_filter, TimeGranularity::Hour)
+            ),
+            timed(
+                "analytics.status_codes",
+                get_status_codes(db, time_range_start, time_range_end, model_filter)
+            ),
         )?;
         (total_requests, time_series, status_code_rows, Vec::new())
     } else {
         // For all models view, fetch everything
         let (total_requests, time_series, status_code_rows, model_rows) = tokio::try_join!(
-            get_total_requests(db, time_range_start, time_range_end, model_filter),
-            get_time_series(db, time_range_start, time_range_end, model_filter, TimeGranularity::Hour),
-            get_status_codes(db, time_range_start, time_range_end, model_filter),
-            get_model_usage(db, time_range_start, time_range_end),
+            timed(
+                "analytics.total_requests",
+                get_total_requests(db, time_range_start, time_range_end, model_filter)
+            ),
+            timed(
+                "analytics.time_series",
+                get_time_series(db, time_range_start, time_range_end, model_filter, TimeGranularity::Hour)
+            ),
+            timed(
+                "analytics.status_codes",
+                get_status_codes(db, time_range_start, time_range_end, model_filter)
+            ),
+            timed("analytics.model_usage", get_model_usage(db, time_range_start, time_range_end)),
         )?;
         (total_requests, time_series, status_code_rows, model_rows)
     };
@@ -469,6 +740,7 @@ pub async fn get_requests_aggregate(
                         0.0
                     },
                     avg_latency_ms: row.model_avg_latency_ms.unwrap_or(0.0),
+                    total_cost: row.model_total_cost,
                 }),
                 _ => None,
             })
@@ -480,6 +752,7 @@ pub async fn get_requests_aggregate(
 
     Ok(RequestsAggregateResponse {
         total_requests,
+        total_cost,
         model: model_filter.map(|m| m.to_string()),
         status_codes,
         models,
@@ -487,6 +760,32 @@ pub async fn get_requests_aggregate(
     })
 }
 
+/// Get the cost of each of the requests with the given `(instance_id, correlation_id)` keys.
+///
+/// Requests that weren't priced, or have no analytics row, are left out.
+#[instrument(skip(db, keys), fields(count = keys.len()), err)]
+pub async fn get_request_costs(db: &PgPool, keys: &[(uuid::Uuid, i64)]) -> Result<HashMap<(uuid::Uuid, i64), f64>> {
+    let (instance_ids, correlation_ids): (Vec<uuid::Uuid>, Vec<i64>) = keys.iter().copied().unzip();
+    let rows = sqlx::query!(
+        r#"
+        SELECT ha.instance_id, ha.correlation_id, ha.total_cost::float8 as "total_cost!"
+        FROM http_analytics ha
+        JOIN UNNEST($1::uuid[], $2::bigint[]) AS k(instance_id, correlation_id)
+            ON ha.instance_id = k.instance_id AND ha.correlation_id = k.correlation_id
+        WHERE ha.total_cost IS NOT NULL
+        "#,
+        &instance_ids,
+        &correlation_ids
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ((row.instance_id, row.correlation_id), row.total_cost))
+        .collect())
+}
+
 /// Get aggregated metrics for a specific model
 #[instrument(skip(db), err)]
 pub async fn get_model_metrics(db: &PgPool, model_alias: &str) -> Result<ModelMetrics> {
@@ -638,93 +937,1137 @@ pub async fn get_model_user_usage(
     })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::{TimeZone, Utc};
-    use sqlx::PgPool;
+/// Group and model spend from analytics query
+#[derive(FromRow)]
+struct GroupModelUsageRow {
+    pub group_id: Option<uuid::Uuid>,
+    pub group_name: Option<String>,
+    pub model: Option<String>,
+    pub request_count: Option<f64>,
+    pub input_tokens: Option<f64>,
+    pub output_tokens: Option<f64>,
+    pub total_cost: Option<f64>,
+}
 
-    #[test]
-    fn test_fill_missing_intervals_empty_input() {
-        let time_series = vec![];
-        let start_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
-        let end_time = start_time + Duration::hours(24);
-        let result = fill_missing_intervals(time_series, start_time, end_time);
+/// Get spend grouped by group and model, for chargeback reporting
+///
+/// Each request, with its tokens and cost, is split evenly between the groups its user belongs to; users with no
+/// explicit group membership are charged to the Everyone group. Requests without a known user
+/// are reported with no group.
+#[instrument(skip(db), err)]
+pub async fn get_group_model_usage(
+    db: &PgPool,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    model_filter: Option<&str>,
+) -> Result<GroupUsageResponse> {
+    let rows = sqlx::query_as!(
+        GroupModelUsageRow,
+        r#"
+        WITH memberships AS (
+            SELECT
+                u.id as user_id,
+                COALESCE(ug.group_id, '00000000-0000-0000-0000-000000000000'::uuid) as group_id,
+                COUNT(*) OVER (PARTITION BY u.id) as group_count
+            FROM users u
+            LEFT JOIN user_groups ug ON ug.user_id = u.id
+        )
+        SELECT
+            m.group_id as "group_id?",
+            g.name as "group_name?",
+            ha.model,
+            SUM(1.0 / COALESCE(m.group_count, 1))::float8 as request_count,
+            COALESCE(SUM(ha.prompt_tokens::float8 / COALESCE(m.group_count, 1)), 0)::float8 as input_tokens,
+            COALESCE(SUM(ha.completion_tokens::float8 / COALESCE(m.group_count, 1)), 0)::float8 as output_tokens,
+            SUM(ha.total_cost / COALESCE(m.group_count, 1))::float8 as total_cost
+        FROM http_analytics ha
+        LEFT JOIN memberships m ON m.user_id = ha.user_id
+        LEFT JOIN groups g ON g.id = m.group_id
+        WHERE ha.uri LIKE '/ai/%'
+            AND ha.timestamp >= $1
+            AND ha.timestamp <= $2
+            AND ha.model IS NOT NULL
+            AND ($3::text IS NULL OR ha.model = $3)
+        GROUP BY m.group_id, g.name, ha.model
+        ORDER BY total_cost DESC NULLS LAST, g.name, ha.model
+        "#,
+        start_date,
+        end_date,
+        model_filter
+    )
+    .fetch_all(db)
+    .await?;
 
-        // Even with empty input, should fill intervals with zero values
-        assert!(!result.is_empty());
-        // All points should have zero values
-        assert!(result.iter().all(|p| p.requests == 0));
-        assert!(result.iter().all(|p| p.input_tokens == 0));
-        assert!(result.iter().all(|p| p.output_tokens == 0));
-        assert!(result.iter().all(|p| p.avg_latency_ms.is_none()));
-    }
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+            COUNT(*) as total_requests,
+            SUM(total_cost)::float8 as total_cost
+        FROM http_analytics
+        WHERE uri LIKE '/ai/%'
+            AND timestamp >= $1
+            AND timestamp <= $2
+            AND model IS NOT NULL
+            AND ($3::text IS NULL OR model = $3)
+        "#,
+        start_date,
+        end_date,
+        model_filter
+    )
+    .fetch_one(db)
+    .await?;
 
-    #[test]
-    fn test_fill_missing_intervals_single_point() {
-        let start_time = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
-        let end_time = start_time + Duration::hours(24);
-        let time_series = vec![TimeSeriesPoint {
-            timestamp: start_time,
-            duration_minutes: 60,
-            requests: 5,
-            input_tokens: 100,
-            output_tokens: 50,
-            avg_latency_ms: Some(200.0),
-            p95_latency_ms: Some(300.0),
-            p99_latency_ms: Some(400.0),
-        }];
+    let groups = rows
+        .into_iter()
+        .filter_map(|row| {
+            Some(GroupModelUsage {
+                group_id: row.group_id,
+                group_name: row.group_name,
+                model: row.model?,
+                request_count: row.request_count.unwrap_or(0.0),
+                input_tokens: row.input_tokens.unwrap_or(0.0),
+                output_tokens: row.output_tokens.unwrap_or(0.0),
+                total_cost: row.total_cost,
+            })
+        })
+        .collect();
 
-        let result = fill_missing_intervals(time_series, start_time, end_time);
+    Ok(GroupUsageResponse {
+        start_date,
+        end_date,
+        total_requests: totals.total_requests.unwrap_or(0),
+        total_cost: totals.total_cost,
+        groups,
+    })
+}
 
-        // Should have data from start_time to current hour
-        assert!(!result.is_empty());
-        assert_eq!(result[0].timestamp, start_time);
-        assert_eq!(result[0].requests, 5);
-    }
+/// Get spend grouped by the value of a user attribute and model, for chargeback reporting
+///
+/// Requests are charged to the value of the attribute their user has when the report is run.
+/// Requests without a known user, or whose user doesn't have the attribute, are reported with no
+/// value.
+#[instrument(skip(db), err)]
+pub async fn get_attribute_model_usage(
+    db: &PgPool,
+    attribute: &str,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    model_filter: Option<&str>,
+) -> Result<AttributeUsageResponse> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            u.attributes ->> $4 as "value?",
+            ha.model,
+            COUNT(*) as "request_count!",
+            COALESCE(SUM(ha.prompt_tokens), 0)::bigint as "input_tokens!",
+            COALESCE(SUM(ha.completion_tokens), 0)::bigint as "output_tokens!",
+            SUM(ha.total_cost)::float8 as total_cost
+        FROM http_analytics ha
+        LEFT JOIN users u ON u.id = ha.user_id
+        WHERE ha.uri LIKE '/ai/%'
+            AND ha.timestamp >= $1
+            AND ha.timestamp <= $2
+            AND ha.model IS NOT NULL
+            AND ($3::text IS NULL OR ha.model = $3)
+        GROUP BY 1, ha.model
+        ORDER BY total_cost DESC NULLS LAST, 1, ha.model
+        "#,
+        start_date,
+        end_date,
+        model_filter,
+        attribute
+    )
+    .fetch_all(db)
+    .await?;
 
-    #[test]
-    fn test_fill_missing_intervals_gaps() {
-        let start_time = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
-        let point1_time = start_time;
-        let point2_time = start_time + Duration::hours(3); // Skip 2 hours
+    // Each request is charged to exactly one value, so the values add up to the totals
+    let values: Vec<AttributeModelUsage> = rows
+        .into_iter()
+        .filter_map(|row| {
+            Some(AttributeModelUsage {
+                value: row.value,
+                model: row.model?,
+                request_count: row.request_count,
+                input_tokens: row.input_tokens,
+                output_tokens: row.output_tokens,
+                total_cost: row.total_cost,
+            })
+        })
+        .collect();
 
-        let time_series = vec![
-            TimeSeriesPoint {
-                timestamp: point1_time,
-                duration_minutes: 60,
-                requests: 5,
-                input_tokens: 100,
-                output_tokens: 50,
-                avg_latency_ms: Some(200.0),
-                p95_latency_ms: Some(300.0),
-                p99_latency_ms: Some(400.0),
-            },
-            TimeSeriesPoint {
-                timestamp: point2_time,
-                duration_minutes: 60,
-                requests: 3,
-                input_tokens: 60,
-                output_tokens: 30,
-                avg_latency_ms: Some(150.0),
-                p95_latency_ms: Some(250.0),
-                p99_latency_ms: Some(350.0),
-            },
-        ];
+    Ok(AttributeUsageResponse {
+        attribute: attribute.to_string(),
+        start_date,
+        end_date,
+        total_requests: values.iter().map(|usage| usage.request_count).sum(),
+        total_cost: values.iter().filter_map(|usage| usage.total_cost).reduce(|a, b| a + b),
+        values,
+    })
+}
 
-        let end_time = start_time + Duration::hours(24);
-        let result = fill_missing_intervals(time_series, start_time, end_time);
+/// Request volume, errors and latency for a deployment or endpoint
+#[derive(FromRow)]
+struct UtilizationRow {
+    pub model: Option<String>,
+    pub endpoint_id: Option<uuid::Uuid>,
+    pub endpoint_name: Option<String>,
+    pub request_count: i64,
+    pub error_count: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_cost: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+}
 
-        // Should fill in the gaps with zero values
-        let first_gap = result.iter().find(|p| p.timestamp == start_time + Duration::hours(1));
-        assert!(first_gap.is_some());
-        let gap_point = first_gap.unwrap();
-        assert_eq!(gap_point.requests, 0);
-        assert_eq!(gap_point.input_tokens, 0);
-        assert_eq!(gap_point.output_tokens, 0);
-        assert_eq!(gap_point.avg_latency_ms, None);
-        assert_eq!(gap_point.p95_latency_ms, None);
-        assert_eq!(gap_point.p99_latency_ms, None);
+/// Get request counts, tokens, error rates and latency per deployment and per endpoint
+#[instrument(skip(db), err)]
+pub async fn get_model_endpoint_usage(db: &PgPool, start_date: DateTime<Utc>, end_date: DateTime<Utc>) -> Result<ModelUsageResponse> {
+    let models = sqlx::query_as!(
+        UtilizationRow,
+        r#"
+        SELECT
+            ha.model,
+            ie.id as "endpoint_id?",
+            ie.name as "endpoint_name?",
+            COUNT(*) as "request_count!",
+            COUNT(*) FILTER (WHERE ha.status_code >= 400) as "error_count!",
+            COALESCE(SUM(ha.prompt_tokens), 0)::bigint as "input_tokens!",
+            COALESCE(SUM(ha.completion_tokens), 0)::bigint as "output_tokens!",
+            SUM(ha.total_cost)::float8 as total_cost,
+            AVG(ha.duration_ms)::float8 as avg_latency_ms,
+            PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY ha.duration_ms)::float8 as p95_latency_ms
+        FROM http_analytics ha
+        LEFT JOIN deployed_models dm ON dm.alias = ha.model AND NOT dm.deleted
+        LEFT JOIN inference_endpoints ie ON ie.id = dm.hosted_on
+        WHERE ha.uri LIKE '/ai/%'
+            AND ha.timestamp >= $1
+            AND ha.timestamp <= $2
+            AND ha.model IS NOT NULL
+        GROUP BY ha.model, ie.id, ie.name
+        ORDER BY 4 DESC, ha.model
+        "#,
+        start_date,
+        end_date
+    )
+    .fetch_all(db)
+    .await?;
+
+    let endpoints = sqlx::query_as!(
+        UtilizationRow,
+        r#"
+        SELECT
+            NULL::text as model,
+            ie.id as "endpoint_id?",
+            ie.name as "endpoint_name?",
+            COUNT(*) as "request_count!",
+            COUNT(*) FILTER (WHERE ha.status_code >= 400) as "error_count!",
+            COALESCE(SUM(ha.prompt_tokens), 0)::bigint as "input_tokens!",
+            COALESCE(SUM(ha.completion_tokens), 0)::bigint as "output_tokens!",
+            SUM(ha.total_cost)::float8 as total_cost,
+            AVG(ha.duration_ms)::float8 as avg_latency_ms,
+            PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY ha.duration_ms)::float8 as p95_latency_ms
+        FROM http_analytics ha
+        LEFT JOIN deployed_models dm ON dm.alias = ha.model AND NOT dm.deleted
+        LEFT JOIN inference_endpoints ie ON ie.id = dm.hosted_on
+        WHERE ha.uri LIKE '/ai/%'
+            AND ha.timestamp >= $1
+            AND ha.timestamp <= $2
+            AND ha.model IS NOT NULL
+        GROUP BY ie.id, ie.name
+        ORDER BY 4 DESC, ie.name
+        "#,
+        start_date,
+        end_date
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(ModelUsageResponse {
+        start_date,
+        end_date,
+        total_requests: endpoints.iter().map(|row| row.request_count).sum(),
+        models: models
+            .into_iter()
+            .filter_map(|row| {
+                Some(DeploymentUsage {
+                    model: row.model?,
+                    endpoint_id: row.endpoint_id,
+                    endpoint_name: row.endpoint_name,
+                    request_count: row.request_count,
+                    error_count: row.error_count,
+                    error_rate: percentage(row.error_count, row.request_count),
+                    input_tokens: row.input_tokens,
+                    output_tokens: row.output_tokens,
+                    total_cost: row.total_cost,
+                    avg_latency_ms: row.avg_latency_ms.unwrap_or(0.0),
+                    p95_latency_ms: row.p95_latency_ms.unwrap_or(0.0),
+                })
+            })
+            .collect(),
+        endpoints: endpoints
+            .into_iter()
+            .map(|row| EndpointUsage {
+                endpoint_id: row.endpoint_id,
+                endpoint_name: row.endpoint_name,
+                request_count: row.request_count,
+                error_count: row.error_count,
+                error_rate: percentage(row.error_count, row.request_count),
+                input_tokens: row.input_tokens,
+                output_tokens: row.output_tokens,
+                total_cost: row.total_cost,
+                avg_latency_ms: row.avg_latency_ms.unwrap_or(0.0),
+                p95_latency_ms: row.p95_latency_ms.unwrap_or(0.0),
+            })
+            .collect(),
+    })
+}
+
+/// Usage of one conversation from the analytics table
+#[derive(FromRow)]
+struct ConversationUsageRow {
+    pub conversation_id: String,
+    pub user_email: Option<String>,
+    pub turns: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub models: Option<Vec<String>>,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_cost: Option<f64>,
+}
+
+/// Get usage per conversation, returning the `limit` conversations with the most turns
+#[instrument(skip(db), err)]
+pub async fn get_conversation_usage(
+    db: &PgPool,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    model_filter: Option<&str>,
+    limit: i64,
+) -> Result<ConversationUsageResponse> {
+    let rows = sqlx::query_as!(
+        ConversationUsageRow,
+        r#"
+        SELECT
+            conversation_id as "conversation_id!",
+            MAX(user_email) as user_email,
+            COUNT(*) as "turns!",
+            MIN(timestamp) as "first_seen!",
+            MAX(timestamp) as "last_seen!",
+            ARRAY_AGG(DISTINCT model) FILTER (WHERE model IS NOT NULL) as models,
+            COALESCE(SUM(prompt_tokens), 0)::bigint as "input_tokens!",
+            COALESCE(SUM(completion_tokens), 0)::bigint as "output_tokens!",
+            SUM(total_cost)::float8 as total_cost
+        FROM http_analytics
+        WHERE conversation_id IS NOT NULL
+            AND timestamp >= $1
+            AND timestamp <= $2
+            AND ($3::text IS NULL OR model = $3)
+        GROUP BY conversation_id
+        ORDER BY 3 DESC, 5 DESC
+        LIMIT $4
+        "#,
+        start_date,
+        end_date,
+        model_filter,
+        limit
+    )
+    .fetch_all(db)
+    .await?;
+
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+            COUNT(DISTINCT conversation_id) as "total_conversations!",
+            COUNT(*) as "total_turns!"
+        FROM http_analytics
+        WHERE conversation_id IS NOT NULL
+            AND timestamp >= $1
+            AND timestamp <= $2
+            AND ($3::text IS NULL OR model = $3)
+        "#,
+        start_date,
+        end_date,
+        model_filter
+    )
+    .fetch_one(db)
+    .await?;
+
+    let conversations = rows
+        .into_iter()
+        .map(|row| ConversationUsage {
+            conversation_id: row.conversation_id,
+            user_email: row.user_email,
+            turns: row.turns,
+            first_seen: row.first_seen,
+            last_seen: row.last_seen,
+            models: row.models.unwrap_or_default(),
+            input_tokens: row.input_tokens,
+            output_tokens: row.output_tokens,
+            total_cost: row.total_cost,
+        })
+        .collect();
+
+    Ok(ConversationUsageResponse {
+        start_date,
+        end_date,
+        total_conversations: totals.total_conversations,
+        total_turns: totals.total_turns,
+        avg_turns: if totals.total_conversations == 0 {
+            0.0
+        } else {
+            totals.total_turns as f64 / totals.total_conversations as f64
+        },
+        conversations,
+    })
+}
+
+/// Usage of one tag value from the analytics table
+#[derive(FromRow)]
+struct TagValueUsageRow {
+    pub value: String,
+    pub requests: i64,
+    pub users: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_cost: Option<f64>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Get usage per value of the tag `key`, returning the `limit` values with the most requests
+#[instrument(skip(db), err)]
+pub async fn get_tag_usage(
+    db: &PgPool,
+    key: &str,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    model_filter: Option<&str>,
+    limit: i64,
+) -> Result<TagUsageResponse> {
+    let rows = sqlx::query_as!(
+        TagValueUsageRow,
+        r#"
+        SELECT
+            tags->>$1 as "value!",
+            COUNT(*) as "requests!",
+            COUNT(DISTINCT user_id) as "users!",
+            COALESCE(SUM(prompt_tokens), 0)::bigint as "input_tokens!",
+            COALESCE(SUM(completion_tokens), 0)::bigint as "output_tokens!",
+            SUM(total_cost)::float8 as total_cost,
+            MAX(timestamp) as "last_seen!"
+        FROM http_analytics
+        WHERE tags->>$1 IS NOT NULL
+            AND timestamp >= $2
+            AND timestamp <= $3
+            AND ($4::text IS NULL OR model = $4)
+        GROUP BY 1
+        ORDER BY 2 DESC, 1
+        LIMIT $5
+        "#,
+        key,
+        start_date,
+        end_date,
+        model_filter,
+        limit
+    )
+    .fetch_all(db)
+    .await?;
+
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+            COUNT(*) FILTER (WHERE tags->>$1 IS NOT NULL) as "tagged!",
+            COUNT(*) FILTER (WHERE tags->>$1 IS NULL) as "untagged!"
+        FROM http_analytics
+        WHERE timestamp >= $2
+            AND timestamp <= $3
+            AND ($4::text IS NULL OR model = $4)
+        "#,
+        key,
+        start_date,
+        end_date,
+        model_filter
+    )
+    .fetch_one(db)
+    .await?;
+
+    let values = rows
+        .into_iter()
+        .map(|row| TagValueUsage {
+            value: row.value,
+            requests: row.requests,
+            users: row.users,
+            input_tokens: row.input_tokens,
+            output_tokens: row.output_tokens,
+            total_cost: row.total_cost,
+            last_seen: row.last_seen,
+        })
+        .collect();
+
+    Ok(TagUsageResponse {
+        key: key.to_string(),
+        start_date,
+        end_date,
+        tagged_requests: totals.tagged,
+        untagged_requests: totals.untagged,
+        values,
+    })
+}
+
+/// Daily embedding usage for a model or group
+#[derive(FromRow)]
+struct EmbeddingUsageRow {
+    pub date: Option<DateTime<Utc>>,
+    pub model: Option<String>,
+    pub group_id: Option<uuid::Uuid>,
+    pub group_name: Option<String>,
+    pub requests: Option<i64>,
+    pub vectors: Option<i64>,
+    pub input_tokens: Option<i64>,
+    pub avg_dimensions: Option<f64>,
+}
+
+impl From<EmbeddingUsageRow> for EmbeddingUsagePoint {
+    fn from(row: EmbeddingUsageRow) -> Self {
+        Self {
+            date: row.date.unwrap_or_default(),
+            model: row.model,
+            group_id: row.group_id,
+            group_name: row.group_name,
+            requests: row.requests.unwrap_or(0),
+            vectors: row.vectors.unwrap_or(0),
+            input_tokens: row.input_tokens.unwrap_or(0),
+            avg_dimensions: row.avg_dimensions,
+        }
+    }
+}
+
+/// Build an embedding usage response, counting each request once for the totals
+async fn embedding_usage_response(
+    db: &PgPool,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    model_filter: Option<&str>,
+    rows: Vec<EmbeddingUsageRow>,
+) -> Result<EmbeddingUsageResponse> {
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+            COUNT(*) as total_requests,
+            COALESCE(SUM(embedding_inputs), 0)::bigint as total_vectors
+        FROM http_analytics
+        WHERE embedding_inputs IS NOT NULL
+            AND timestamp >= $1
+            AND timestamp <= $2
+            AND ($3::text IS NULL OR model = $3)
+        "#,
+        start_date,
+        end_date,
+        model_filter
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok(EmbeddingUsageResponse {
+        start_date,
+        end_date,
+        total_requests: totals.total_requests.unwrap_or(0),
+        total_vectors: totals.total_vectors.unwrap_or(0),
+        daily: rows.into_iter().map(EmbeddingUsagePoint::from).collect(),
+    })
+}
+
+/// Get embedding vectors per day for each model
+#[instrument(skip(db), err)]
+pub async fn get_embedding_usage_by_model(
+    db: &PgPool,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    model_filter: Option<&str>,
+) -> Result<EmbeddingUsageResponse> {
+    let rows = sqlx::query_as!(
+        EmbeddingUsageRow,
+        r#"
+        SELECT
+            date_trunc('day', timestamp) as date,
+            model,
+            NULL::uuid as group_id,
+            NULL::text as group_name,
+            COUNT(*) as requests,
+            COALESCE(SUM(embedding_inputs), 0)::bigint as vectors,
+            COALESCE(SUM(prompt_tokens), 0)::bigint as input_tokens,
+            AVG(embedding_dimensions)::float8 as avg_dimensions
+        FROM http_analytics
+        WHERE embedding_inputs IS NOT NULL
+            AND timestamp >= $1
+            AND timestamp <= $2
+            AND ($3::text IS NULL OR model = $3)
+        GROUP BY date_trunc('day', timestamp), model
+        ORDER BY date, model
+        "#,
+        start_date,
+        end_date,
+        model_filter
+    )
+    .fetch_all(db)
+    .await?;
+
+    embedding_usage_response(db, start_date, end_date, model_filter, rows).await
+}
+
+/// Get embedding vectors per day for each group
+///
+/// A request is attributed to every group its user belongs to, including the Everyone group.
+/// Requests without a known user are not attributed to any group.
+#[instrument(skip(db), err)]
+pub async fn get_embedding_usage_by_group(
+    db: &PgPool,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    model_filter: Option<&str>,
+) -> Result<EmbeddingUsageResponse> {
+    let rows = sqlx::query_as!(
+        EmbeddingUsageRow,
+        r#"
+        WITH memberships AS (
+            SELECT user_id, group_id FROM user_groups
+            UNION ALL
+            SELECT id as user_id, '00000000-0000-0000-0000-000000000000'::uuid as group_id FROM users
+        )
+        SELECT
+            date_trunc('day', ha.timestamp) as date,
+            NULL::text as model,
+            g.id as "group_id?",
+            g.name as "group_name?",
+            COUNT(*) as requests,
+            COALESCE(SUM(ha.embedding_inputs), 0)::bigint as vectors,
+            COALESCE(SUM(ha.prompt_tokens), 0)::bigint as input_tokens,
+            AVG(ha.embedding_dimensions)::float8 as avg_dimensions
+        FROM http_analytics ha
+        JOIN memberships m ON m.user_id = ha.user_id
+        JOIN groups g ON g.id = m.group_id
+        WHERE ha.embedding_inputs IS NOT NULL
+            AND ha.timestamp >= $1
+            AND ha.timestamp <= $2
+            AND ($3::text IS NULL OR ha.model = $3)
+        GROUP BY date_trunc('day', ha.timestamp), g.id, g.name
+        ORDER BY date, g.name
+        "#,
+        start_date,
+        end_date,
+        model_filter
+    )
+    .fetch_all(db)
+    .await?;
+
+    embedding_usage_response(db, start_date, end_date, model_filter, rows).await
+}
+
+/// PII classification counts for a group
+#[derive(FromRow)]
+struct GroupPiiRow {
+    pub group_id: Option<uuid::Uuid>,
+    pub group_name: Option<String>,
+    pub classified_requests: Option<i64>,
+    pub requests_with_pii: Option<i64>,
+    pub email: Option<i64>,
+    pub phone: Option<i64>,
+    pub financial: Option<i64>,
+}
+
+/// Requests to an alias by variant
+#[derive(FromRow)]
+struct VariantMetricsRow {
+    pub variant: String,
+    pub requests: Option<i64>,
+    pub server_errors: Option<i64>,
+    pub avg_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+}
+
+/// Compare the given variants (`stable`, `canary` or `shadow`) of an alias since `since`
+#[instrument(skip(db), err)]
+pub async fn get_variant_metrics(
+    db: &PgPool,
+    model_alias: &str,
+    since: DateTime<Utc>,
+    variants: &[&str],
+) -> Result<Vec<CanaryVariantMetrics>> {
+    let rows = sqlx::query_as!(
+        VariantMetricsRow,
+        r#"
+        SELECT
+            variant as "variant!",
+            COUNT(*) as requests,
+            COUNT(*) FILTER (WHERE status_code >= 500) as server_errors,
+            AVG(duration_ms)::float8 as avg_latency_ms,
+            PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY duration_ms)::float8 as p95_latency_ms
+        FROM http_analytics
+        WHERE uri LIKE '/ai/%' AND model = $1 AND timestamp >= $2 AND variant IS NOT NULL
+        GROUP BY variant
+        "#,
+        model_alias,
+        since
+    )
+    .fetch_all(db)
+    .await?;
+
+    // Always report every variant, so a canary or shadow that has had no traffic yet shows up as such
+    let metrics = variants
+        .iter()
+        .map(|&variant| match rows.iter().find(|row| row.variant == variant) {
+            Some(row) => {
+                let requests = row.requests.unwrap_or(0);
+                CanaryVariantMetrics {
+                    variant: variant.to_string(),
+                    requests,
+                    error_rate: percentage(row.server_errors.unwrap_or(0), requests),
+                    avg_latency_ms: row.avg_latency_ms,
+                    p95_latency_ms: row.p95_latency_ms,
+                }
+            }
+            None => CanaryVariantMetrics {
+                variant: variant.to_string(),
+                requests: 0,
+                error_rate: 0.0,
+                avg_latency_ms: None,
+                p95_latency_ms: None,
+            },
+        })
+        .collect();
+
+    Ok(metrics)
+}
+
+/// Requests to an alias in one hour of the week
+#[derive(FromRow)]
+struct HourOfWeekRow {
+    pub day: i32,
+    pub hour: i32,
+    pub requests: i64,
+}
+
+/// Count the requests to an alias since `since` by day of the week and hour (in UTC)
+#[instrument(skip(db), err)]
+pub async fn get_hour_of_week_traffic(db: &PgPool, model_alias: &str, since: DateTime<Utc>) -> Result<Vec<(DayOfWeek, u32, i64)>> {
+    let rows = sqlx::query_as!(
+        HourOfWeekRow,
+        r#"
+        SELECT
+            EXTRACT(ISODOW FROM timestamp AT TIME ZONE 'UTC')::int4 as "day!",
+            EXTRACT(HOUR FROM timestamp AT TIME ZONE 'UTC')::int4 as "hour!",
+            COUNT(*) as "requests!"
+        FROM http_analytics
+        WHERE uri LIKE '/ai/%' AND model = $1 AND timestamp >= $2
+        GROUP BY 1, 2
+        "#,
+        model_alias,
+        since
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| Some((DayOfWeek::from_iso(row.day)?, u32::try_from(row.hour).ok()?, row.requests)))
+        .collect())
+}
+
+/// Stream the logged requests matching an export's filters, oldest first
+///
+/// Rows are fetched from the database as the stream is polled, so exports of any size are never
+/// held in memory at once.
+pub fn stream_requests_for_export<'a>(
+    db: &'a PgPool,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+    user_id: Option<UserId>,
+    model: Option<&'a str>,
+) -> BoxStream<'a, std::result::Result<RequestExportRecord, sqlx::Error>> {
+    sqlx::query_as!(
+        RequestExportRecord,
+        r#"
+        SELECT
+            ha.id,
+            ha.timestamp,
+            ha.method,
+            ha.uri,
+            ha.model,
+            ha.status_code,
+            ha.duration_ms,
+            ha.duration_to_first_byte_ms,
+            ha.prompt_tokens,
+            ha.completion_tokens,
+            ha.total_tokens,
+            ha.usage_estimated,
+            ha.total_cost::float8 as total_cost,
+            ha.user_id,
+            ha.user_email,
+            ha.access_source,
+            ha.response_type,
+            ha.variant,
+            u.attributes as "user_attributes?"
+        FROM http_analytics ha
+        LEFT JOIN users u ON u.id = ha.user_id
+        WHERE ($1::timestamptz IS NULL OR ha.timestamp >= $1)
+          AND ($2::timestamptz IS NULL OR ha.timestamp < $2)
+          AND ($3::uuid IS NULL OR ha.user_id = $3)
+          AND ($4::text IS NULL OR ha.model = $4)
+        ORDER BY ha.timestamp, ha.id
+        "#,
+        start_date,
+        end_date,
+        user_id,
+        model
+    )
+    .fetch(db)
+}
+
+fn percentage(count: i64, total: i64) -> f64 {
+    if total > 0 {
+        (count as f64 * 100.0) / total as f64
+    } else {
+        0.0
+    }
+}
+
+/// Get PII classification rates for each group's requests
+///
+/// Only classified requests are counted. A request is attributed to every group its user
+/// belongs to, including the Everyone group.
+#[instrument(skip(db), err)]
+pub async fn get_pii_stats_by_group(db: &PgPool, start_date: DateTime<Utc>, end_date: DateTime<Utc>) -> Result<PiiStatsResponse> {
+    let rows = sqlx::query_as!(
+        GroupPiiRow,
+        r#"
+        WITH memberships AS (
+            SELECT user_id, group_id FROM user_groups
+            UNION ALL
+            SELECT id as user_id, '00000000-0000-0000-0000-000000000000'::uuid as group_id FROM users
+        )
+        SELECT
+            g.id as "group_id?",
+            g.name as "group_name?",
+            COUNT(*) as classified_requests,
+            COUNT(*) FILTER (WHERE cardinality(ha.pii_categories) > 0) as requests_with_pii,
+            COUNT(*) FILTER (WHERE 'email' = ANY(ha.pii_categories)) as email,
+            COUNT(*) FILTER (WHERE 'phone' = ANY(ha.pii_categories)) as phone,
+            COUNT(*) FILTER (WHERE 'financial' = ANY(ha.pii_categories)) as financial
+        FROM http_analytics ha
+        JOIN memberships m ON m.user_id = ha.user_id
+        JOIN groups g ON g.id = m.group_id
+        WHERE ha.pii_categories IS NOT NULL
+            AND ha.timestamp >= $1
+            AND ha.timestamp <= $2
+        GROUP BY g.id, g.name
+        ORDER BY g.name
+        "#,
+        start_date,
+        end_date
+    )
+    .fetch_all(db)
+    .await?;
+
+    let groups = rows
+        .into_iter()
+        .filter_map(|row| {
+            let total = row.classified_requests.unwrap_or(0);
+            let requests_with_pii = row.requests_with_pii.unwrap_or(0);
+            let categories = PiiCategory::ALL
+                .iter()
+                .map(|category| {
+                    let count = match category {
+                        PiiCategory::Email => row.email,
+                        PiiCategory::Phone => row.phone,
+                        PiiCategory::Financial => row.financial,
+                    }
+                    .unwrap_or(0);
+                    PiiCategoryBreakdown {
+                        category: category.to_string(),
+                        count,
+                        percentage: percentage(count, total),
+                    }
+                })
+                .collect();
+
+            Some(GroupPiiStats {
+                group_id: row.group_id?,
+                group_name: row.group_name?,
+                classified_requests: total,
+                requests_with_pii,
+                pii_percentage: percentage(requests_with_pii, total),
+                categories,
+            })
+        })
+        .collect();
+
+    Ok(PiiStatsResponse {
+        start_date,
+        end_date,
+        groups,
+    })
+}
+
+/// Weekly active users for a group (or overall, when `group_id` is NULL)
+#[derive(FromRow)]
+struct WeeklyActiveRow {
+    pub group_id: Option<uuid::Uuid>,
+    pub week_start: Option<DateTime<Utc>>,
+    pub active_users: Option<i64>,
+}
+
+/// Membership and monthly activity for a group
+#[derive(FromRow)]
+struct GroupAdoptionRow {
+    pub group_id: Option<uuid::Uuid>,
+    pub group_name: Option<String>,
+    pub member_count: Option<i64>,
+    pub monthly_active_users: Option<i64>,
+}
+
+/// Get adoption metrics (active users, new users, per-group trends) as of a point in time
+///
+/// Group membership includes the implicit Everyone group. The system user is excluded.
+#[instrument(skip(db), err)]
+pub async fn get_adoption_metrics(db: &PgPool, as_of: DateTime<Utc>, weeks: i32) -> Result<AdoptionResponse> {
+    let month_ago = as_of - Duration::days(30);
+
+    let summary = sqlx::query!(
+        r#"
+        WITH active AS (
+            SELECT user_id, MAX(timestamp) as last_request_at
+            FROM http_analytics
+            WHERE user_id IS NOT NULL AND timestamp > $2 AND timestamp <= $1
+            GROUP BY user_id
+        )
+        SELECT
+            (SELECT COUNT(*) FROM users WHERE id != '00000000-0000-0000-0000-000000000000') as "total_users!",
+            (SELECT COUNT(*) FROM active WHERE last_request_at > $1 - interval '1 day') as "daily_active_users!",
+            (SELECT COUNT(*) FROM active WHERE last_request_at > $1 - interval '7 days') as "weekly_active_users!",
+            (SELECT COUNT(*) FROM active) as "monthly_active_users!",
+            (
+                SELECT COUNT(*) FROM users
+                WHERE id != '00000000-0000-0000-0000-000000000000' AND created_at > $2 AND created_at <= $1
+            ) as "new_users!",
+            (
+                SELECT COUNT(*) FROM users u
+                WHERE u.id != '00000000-0000-0000-0000-000000000000'
+                    AND u.created_at <= $1
+                    AND NOT EXISTS (SELECT 1 FROM http_analytics ha WHERE ha.user_id = u.id AND ha.timestamp <= $1)
+            ) as "zero_usage_users!"
+        "#,
+        as_of,
+        month_ago
+    )
+    .fetch_one(db)
+    .await?;
+
+    let group_rows = sqlx::query_as!(
+        GroupAdoptionRow,
+        r#"
+        WITH memberships AS (
+            SELECT user_id, group_id FROM user_groups
+            UNION ALL
+            SELECT id as user_id, '00000000-0000-0000-0000-000000000000'::uuid as group_id
+            FROM users WHERE id != '00000000-0000-0000-0000-000000000000'
+        ),
+        active AS (
+            SELECT DISTINCT user_id FROM http_analytics
+            WHERE user_id IS NOT NULL AND timestamp > $2 AND timestamp <= $1
+        )
+        SELECT
+            g.id as "group_id?",
+            g.name as "group_name?",
+            COUNT(m.user_id) as member_count,
+            COUNT(a.user_id) as monthly_active_users
+        FROM groups g
+        LEFT JOIN memberships m ON m.group_id = g.id
+        LEFT JOIN active a ON a.user_id = m.user_id
+        GROUP BY g.id, g.name
+        ORDER BY g.name
+        "#,
+        as_of,
+        month_ago
+    )
+    .fetch_all(db)
+    .await?;
+
+    // Weekly active users overall (group_id NULL) and per group, for the trailing `weeks` weeks
+    let weekly_rows = sqlx::query_as!(
+        WeeklyActiveRow,
+        r#"
+        WITH weeks AS (
+            SELECT generate_series(
+                date_trunc('week', $1::timestamptz) - ($2::int - 1) * interval '1 week',
+                date_trunc('week', $1::timestamptz),
+                interval '1 week'
+            ) as week_start
+        ),
+        weekly_users AS (
+            SELECT DISTINCT date_trunc('week', timestamp) as week_start, user_id
+            FROM http_analytics
+            WHERE user_id IS NOT NULL
+                AND timestamp >= date_trunc('week', $1::timestamptz) - ($2::int - 1) * interval '1 week'
+                AND timestamp <= $1
+        ),
+        memberships AS (
+            SELECT user_id, group_id FROM user_groups
+            UNION ALL
+            SELECT id as user_id, '00000000-0000-0000-0000-000000000000'::uuid as group_id
+            FROM users WHERE id != '00000000-0000-0000-0000-000000000000'
+        )
+        SELECT NULL::uuid as group_id, w.week_start, COUNT(wu.user_id) as active_users
+        FROM weeks w
+        LEFT JOIN weekly_users wu ON wu.week_start = w.week_start
+        GROUP BY w.week_start
+        UNION ALL
+        SELECT g.id as group_id, w.week_start, COUNT(m.user_id) as active_users
+        FROM groups g
+        CROSS JOIN weeks w
+        LEFT JOIN weekly_users wu ON wu.week_start = w.week_start
+        LEFT JOIN memberships m ON m.user_id = wu.user_id AND m.group_id = g.id
+        GROUP BY g.id, w.week_start
+        ORDER BY week_start
+        "#,
+        as_of,
+        weeks
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut weekly_by_group: HashMap<Option<uuid::Uuid>, Vec<AdoptionTrendPoint>> = HashMap::new();
+    for row in weekly_rows {
+        if let Some(week_start) = row.week_start {
+            weekly_by_group.entry(row.group_id).or_default().push(AdoptionTrendPoint {
+                week_start,
+                active_users: row.active_users.unwrap_or(0),
+            });
+        }
+    }
+
+    let groups = group_rows
+        .into_iter()
+        .filter_map(|row| {
+            let group_id = row.group_id?;
+            let member_count = row.member_count.unwrap_or(0);
+            let monthly_active_users = row.monthly_active_users.unwrap_or(0);
+            Some(GroupAdoption {
+                group_id,
+                group_name: row.group_name?,
+                member_count,
+                monthly_active_users,
+                adoption_percentage: if member_count > 0 {
+                    (monthly_active_users as f64 * 100.0) / member_count as f64
+                } else {
+                    0.0
+                },
+                weekly_active: weekly_by_group.remove(&Some(group_id)).unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    Ok(AdoptionResponse {
+        as_of,
+        total_users: summary.total_users,
+        daily_active_users: summary.daily_active_users,
+        weekly_active_users: summary.weekly_active_users,
+        monthly_active_users: summary.monthly_active_users,
+        new_users: summary.new_users,
+        zero_usage_users: summary.zero_usage_users,
+        weekly_active: weekly_by_group.remove(&None).unwrap_or_default(),
+        groups,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use sqlx::PgPool;
+
+    #[test]
+    fn test_fill_missing_intervals_empty_input() {
+        let time_series = vec![];
+        let start_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let end_time = start_time + Duration::hours(24);
+        let result = fill_missing_intervals(time_series, start_time, end_time);
+
+        // Even with empty input, should fill intervals with zero values
+        assert!(!result.is_empty());
+        // All points should have zero values
+        assert!(result.iter().all(|p| p.requests == 0));
+        assert!(result.iter().all(|p| p.input_tokens == 0));
+        assert!(result.iter().all(|p| p.output_tokens == 0));
+        assert!(result.iter().all(|p| p.avg_latency_ms.is_none()));
+    }
+
+    #[test]
+    fn test_fill_missing_intervals_single_point() {
+        let start_time = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        let end_time = start_time + Duration::hours(24);
+        let time_series = vec![TimeSeriesPoint {
+            timestamp: start_time,
+            duration_minutes: 60,
+            requests: 5,
+            input_tokens: 100,
+            output_tokens: 50,
+            avg_latency_ms: Some(200.0),
+            p95_latency_ms: Some(300.0),
+            p99_latency_ms: Some(400.0),
+        }];
+
+        let result = fill_missing_intervals(time_series, start_time, end_time);
+
+        // Should have data from start_time to current hour
+        assert!(!result.is_empty());
+        assert_eq!(result[0].timestamp, start_time);
+        assert_eq!(result[0].requests, 5);
+    }
+
+    #[test]
+    fn test_fill_missing_intervals_gaps() {
+        let start_time = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        let point1_time = start_time;
+        let point2_time = start_time + Duration::hours(3); // Skip 2 hours
+
+        let time_series = vec![
+            TimeSeriesPoint {
+                timestamp: point1_time,
+                duration_minutes: 60,
+                requests: 5,
+                input_tokens: 100,
+                output_tokens: 50,
+                avg_latency_ms: Some(200.0),
+                p95_latency_ms: Some(300.0),
+                p99_latency_ms: Some(400.0),
+            },
+            TimeSeriesPoint {
+                timestamp: point2_time,
+                duration_minutes: 60,
+                requests: 3,
+                input_tokens: 60,
+                output_tokens: 30,
+                avg_latency_ms: Some(150.0),
+                p95_latency_ms: Some(250.0),
+                p99_latency_ms: Some(350.0),
+            },
+        ];
+
+        let end_time = start_time + Duration::hours(24);
+        let result = fill_missing_intervals(time_series, start_time, end_time);
+
+        // Should fill in the gaps with zero values
+        let first_gap = result.iter().find(|p| p.timestamp == start_time + Duration::hours(1));
+        assert!(first_gap.is_some());
+        let gap_point = first_gap.unwrap();
+        assert_eq!(gap_point.requests, 0);
+        assert_eq!(gap_point.input_tokens, 0);
+        assert_eq!(gap_point.output_tokens, 0);
+        assert_eq!(gap_point.avg_latency_ms, None);
+        assert_eq!(gap_point.p95_latency_ms, None);
+        assert_eq!(gap_point.p99_latency_ms, None);
     }
 
     #[test]
@@ -836,7 +2179,7 @@ mod tests {
         insert_test_analytics_data(&pool, one_hour_ago, "claude-3", 200, 150.0, 75, 35).await;
         insert_test_analytics_data(&pool, two_hours_ago, "gpt-4", 400, 200.0, 100, 50).await;
 
-        let result = get_total_requests(&pool, two_hours_ago, now, None).await.unwrap();
+        let (result, _) = get_total_requests(&pool, two_hours_ago, now, None).await.unwrap();
         assert_eq!(result, 3);
     }
 
@@ -850,13 +2193,13 @@ mod tests {
         insert_test_analytics_data(&pool, one_hour_ago, "claude-3", 200, 150.0, 75, 35).await;
         insert_test_analytics_data(&pool, one_hour_ago, "gpt-4", 400, 200.0, 100, 50).await;
 
-        let result = get_total_requests(&pool, one_hour_ago, now, Some("gpt-4")).await.unwrap();
+        let (result, _) = get_total_requests(&pool, one_hour_ago, now, Some("gpt-4")).await.unwrap();
         assert_eq!(result, 2);
 
-        let result = get_total_requests(&pool, one_hour_ago, now, Some("claude-3")).await.unwrap();
+        let (result, _) = get_total_requests(&pool, one_hour_ago, now, Some("claude-3")).await.unwrap();
         assert_eq!(result, 1);
 
-        let result = get_total_requests(&pool, one_hour_ago, now, Some("nonexistent")).await.unwrap();
+        let (result, _) = get_total_requests(&pool, one_hour_ago, now, Some("nonexistent")).await.unwrap();
         assert_eq!(result, 0);
     }
 
@@ -1029,6 +2372,49 @@ mod tests {
         assert!(result.time_series.iter().all(|p| p.requests == 0));
     }
 
+    #[sqlx::test]
+    async fn test_request_costs(pool: PgPool) {
+        let now = Utc::now();
+        let instance_id = uuid::Uuid::new_v4();
+        // (model, input price, output price); the last request was made to an unpriced model
+        let requests = [
+            ("gpt-4", Some("0.01"), Some("0.03")),
+            ("gpt-4", Some("0.01"), Some("0.03")),
+            ("claude-3", None, None),
+        ];
+        for (correlation_id, (model, input_price, output_price)) in requests.into_iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO http_analytics (
+                     instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms, model,
+                     prompt_tokens, completion_tokens, total_tokens, input_price_per_token, output_price_per_token
+                 ) VALUES ($1, $2, $3, '/ai/v1/chat/completions', 'POST', 200, 100, $4, 100, 50, 150, $5::numeric, $6::numeric)",
+            )
+            .bind(instance_id)
+            .bind(correlation_id as i64)
+            .bind(now - Duration::minutes(5))
+            .bind(model)
+            .bind(input_price)
+            .bind(output_price)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        // Each priced request costs 100 * 0.01 + 50 * 0.03
+        let costs = get_request_costs(&pool, &[(instance_id, 0), (instance_id, 2), (uuid::Uuid::new_v4(), 1)])
+            .await
+            .unwrap();
+        assert_eq!(costs.len(), 1);
+        assert!((costs[&(instance_id, 0)] - 2.5).abs() < 1e-9);
+
+        let aggregate = get_requests_aggregate(&pool, now - Duration::hours(1), now, None).await.unwrap();
+        assert!((aggregate.total_cost.unwrap() - 5.0).abs() < 1e-9);
+        let models = aggregate.models.unwrap();
+        let cost = |name: &str| models.iter().find(|model| model.model == name).unwrap().total_cost;
+        assert!((cost("gpt-4").unwrap() - 5.0).abs() < 1e-9);
+        assert_eq!(cost("claude-3"), None);
+    }
+
     #[sqlx::test]
     async fn test_percentage_calculations_precision(pool: PgPool) {
         let base_time = Utc::now() - Duration::hours(1);
@@ -1060,4 +2446,515 @@ mod tests {
         let claude3 = models.iter().find(|m| m.model == "claude-3").unwrap();
         assert_eq!(claude3.percentage, 30.0);
     }
+
+    /// Insert an embeddings request made by `user_id`
+    async fn insert_embedding_analytics(
+        pool: &PgPool,
+        timestamp: DateTime<Utc>,
+        model: &str,
+        user_id: Option<uuid::Uuid>,
+        inputs: i32,
+        dimensions: i32,
+    ) {
+        sqlx::query!(
+            r#"
+            INSERT INTO http_analytics (
+                instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms,
+                model, prompt_tokens, completion_tokens, total_tokens, user_id,
+                embedding_inputs, embedding_dimensions
+            ) VALUES ($1, 1, $2, '/ai/v1/embeddings', 'POST', 200, 50, $3, 10, 0, 10, $4, $5, $6)
+            "#,
+            uuid::Uuid::new_v4(),
+            timestamp,
+            model,
+            user_id,
+            inputs,
+            dimensions
+        )
+        .execute(pool)
+        .await
+        .expect("Failed to insert embedding analytics data");
+    }
+
+    #[sqlx::test]
+    async fn test_get_embedding_usage_by_model(pool: PgPool) {
+        let day1 = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        let day2 = day1 + Duration::days(1);
+
+        insert_embedding_analytics(&pool, day1, "embed-small", None, 4, 512).await;
+        insert_embedding_analytics(&pool, day1 + Duration::hours(2), "embed-small", None, 6, 512).await;
+        insert_embedding_analytics(&pool, day2, "embed-small", None, 1, 512).await;
+        insert_embedding_analytics(&pool, day2, "embed-large", None, 2, 3072).await;
+        // Non-embedding traffic is ignored
+        insert_test_analytics_data(&pool, day1, "gpt-4", 200, 100.0, 50, 25).await;
+
+        let start = day1 - Duration::hours(10);
+        let end = day2 + Duration::hours(10);
+        let usage = get_embedding_usage_by_model(&pool, start, end, None).await.unwrap();
+
+        assert_eq!(usage.total_requests, 4);
+        assert_eq!(usage.total_vectors, 13);
+        assert_eq!(usage.daily.len(), 3);
+
+        let first = &usage.daily[0];
+        assert_eq!(first.date, Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(first.model.as_deref(), Some("embed-small"));
+        assert_eq!(first.requests, 2);
+        assert_eq!(first.vectors, 10);
+        assert_eq!(first.avg_dimensions, Some(512.0));
+
+        let filtered = get_embedding_usage_by_model(&pool, start, end, Some("embed-large")).await.unwrap();
+        assert_eq!(filtered.total_vectors, 2);
+        assert_eq!(filtered.daily.len(), 1);
+        assert_eq!(filtered.daily[0].avg_dimensions, Some(3072.0));
+    }
+
+    #[sqlx::test]
+    async fn test_get_embedding_usage_by_group(pool: PgPool) {
+        use crate::api::models::users::Role;
+        use crate::test_utils::{add_user_to_group, create_test_group, create_test_user};
+
+        let group = create_test_group(&pool).await;
+        let member = create_test_user(&pool, Role::StandardUser).await;
+        let outsider = create_test_user(&pool, Role::StandardUser).await;
+        add_user_to_group(&pool, member.id, group.id).await;
+
+        let day = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        insert_embedding_analytics(&pool, day, "embed-small", Some(member.id), 5, 512).await;
+        insert_embedding_analytics(&pool, day, "embed-small", Some(outsider.id), 3, 512).await;
+        insert_embedding_analytics(&pool, day, "embed-small", None, 7, 512).await;
+
+        let usage = get_embedding_usage_by_group(&pool, day - Duration::hours(1), day + Duration::hours(1), None)
+            .await
+            .unwrap();
+
+        // Totals count each request once
+        assert_eq!(usage.total_requests, 3);
+        assert_eq!(usage.total_vectors, 15);
+
+        let group_usage = usage.daily.iter().find(|p| p.group_id == Some(group.id)).unwrap();
+        assert_eq!(group_usage.vectors, 5);
+        assert_eq!(group_usage.group_name.as_deref(), Some(group.name.as_str()));
+
+        // Everyone covers all known users, but not unauthenticated traffic
+        let everyone = usage.daily.iter().find(|p| p.group_id == Some(uuid::Uuid::nil())).unwrap();
+        assert_eq!(everyone.requests, 2);
+        assert_eq!(everyone.vectors, 8);
+    }
+
+    #[sqlx::test]
+    async fn test_get_group_model_usage_splits_cost_between_groups(pool: PgPool) {
+        use crate::api::models::users::Role;
+        use crate::test_utils::{add_user_to_group, create_test_group, create_test_user};
+
+        let engineering = create_test_group(&pool).await;
+        let research = create_test_group(&pool).await;
+        let shared = create_test_user(&pool, Role::StandardUser).await;
+        let engineer = create_test_user(&pool, Role::StandardUser).await;
+        let loner = create_test_user(&pool, Role::StandardUser).await;
+        add_user_to_group(&pool, shared.id, engineering.id).await;
+        add_user_to_group(&pool, shared.id, research.id).await;
+        add_user_to_group(&pool, engineer.id, engineering.id).await;
+
+        let now = Utc::now();
+        for (user_id, model) in [
+            (Some(shared.id), "gpt-4"),
+            (Some(engineer.id), "gpt-4"),
+            (Some(loner.id), "claude-3"),
+            (None, "gpt-4"),
+        ] {
+            sqlx::query!(
+                r#"
+                INSERT INTO http_analytics (
+                    instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms, model,
+                    prompt_tokens, completion_tokens, total_tokens, user_id, input_price_per_token, output_price_per_token
+                ) VALUES ($1, 1, $2, '/ai/v1/chat/completions', 'POST', 200, 100, $3, 1000, 1000, 2000, $4, 0.001, 0.001)
+                "#,
+                uuid::Uuid::new_v4(),
+                now - Duration::minutes(5),
+                model,
+                user_id
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let usage = get_group_model_usage(&pool, now - Duration::hours(1), now, None).await.unwrap();
+
+        // Each request costs 2.0
+        assert_eq!(usage.total_requests, 4);
+        assert!((usage.total_cost.unwrap() - 8.0).abs() < 1e-6);
+        let allocated: f64 = usage.groups.iter().filter_map(|g| g.total_cost).sum();
+        assert!((allocated - 8.0).abs() < 1e-6);
+        // Requests and tokens are split the same way, so they add up to the totals too
+        let requests: f64 = usage.groups.iter().map(|g| g.request_count).sum();
+        assert!((requests - usage.total_requests as f64).abs() < 1e-6);
+        let input_tokens: f64 = usage.groups.iter().map(|g| g.input_tokens).sum();
+        assert!((input_tokens - 4000.0).abs() < 1e-6);
+
+        let cost_for = |group_id: Option<uuid::Uuid>, model: &str| {
+            usage
+                .groups
+                .iter()
+                .find(|g| g.group_id == group_id && g.model == model)
+                .and_then(|g| g.total_cost)
+                .unwrap()
+        };
+        // Engineer pays 2.0, shared user's 2.0 is split between engineering and research
+        assert!((cost_for(Some(engineering.id), "gpt-4") - 3.0).abs() < 1e-6);
+        assert!((cost_for(Some(research.id), "gpt-4") - 1.0).abs() < 1e-6);
+        let research_usage = usage.groups.iter().find(|g| g.group_id == Some(research.id)).unwrap();
+        assert!((research_usage.request_count - 0.5).abs() < 1e-6);
+        assert!((research_usage.output_tokens - 500.0).abs() < 1e-6);
+        // Users without a group are charged to Everyone
+        assert!((cost_for(Some(uuid::Uuid::nil()), "claude-3") - 2.0).abs() < 1e-6);
+        // Unknown users are reported without a group
+        assert!((cost_for(None, "gpt-4") - 2.0).abs() < 1e-6);
+
+        let filtered = get_group_model_usage(&pool, now - Duration::hours(1), now, Some("claude-3"))
+            .await
+            .unwrap();
+        assert_eq!(filtered.total_requests, 1);
+        assert_eq!(filtered.groups.len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_get_attribute_model_usage(pool: PgPool) {
+        use crate::api::models::users::Role;
+        use crate::test_utils::create_test_user;
+
+        let finance = create_test_user(&pool, Role::StandardUser).await;
+        let research = create_test_user(&pool, Role::StandardUser).await;
+        let unassigned = create_test_user(&pool, Role::StandardUser).await;
+        for (user_id, cost_center) in [(finance.id, "CC-100"), (research.id, "CC-200")] {
+            sqlx::query!(
+                "UPDATE users SET attributes = jsonb_build_object('cost_center', $2::text) WHERE id = $1",
+                user_id,
+                cost_center
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let now = Utc::now();
+        for (user_id, model) in [
+            (Some(finance.id), "gpt-4"),
+            (Some(finance.id), "gpt-4"),
+            (Some(research.id), "gpt-4"),
+            (Some(research.id), "claude-3"),
+            (Some(unassigned.id), "gpt-4"),
+            (None, "gpt-4"),
+        ] {
+            sqlx::query!(
+                r#"
+                INSERT INTO http_analytics (
+                    instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms, model,
+                    prompt_tokens, completion_tokens, total_tokens, user_id, input_price_per_token, output_price_per_token
+                ) VALUES ($1, 1, $2, '/ai/v1/chat/completions', 'POST', 200, 100, $3, 1000, 1000, 2000, $4, 0.001, 0.001)
+                "#,
+                uuid::Uuid::new_v4(),
+                now - Duration::minutes(5),
+                model,
+                user_id
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let usage = get_attribute_model_usage(&pool, "cost_center", now - Duration::hours(1), now, None)
+            .await
+            .unwrap();
+
+        // Each request costs 2.0
+        assert_eq!(usage.attribute, "cost_center");
+        assert_eq!(usage.total_requests, 6);
+        assert!((usage.total_cost.unwrap() - 12.0).abs() < 1e-6);
+        let cost_for = |value: Option<&str>, model: &str| {
+            usage
+                .values
+                .iter()
+                .find(|usage| usage.value.as_deref() == value && usage.model == model)
+                .and_then(|usage| usage.total_cost)
+                .unwrap()
+        };
+        assert!((cost_for(Some("CC-100"), "gpt-4") - 4.0).abs() < 1e-6);
+        assert!((cost_for(Some("CC-200"), "gpt-4") - 2.0).abs() < 1e-6);
+        assert!((cost_for(Some("CC-200"), "claude-3") - 2.0).abs() < 1e-6);
+        // Users without the attribute and unknown users are left unallocated
+        assert!((cost_for(None, "gpt-4") - 4.0).abs() < 1e-6);
+
+        let filtered = get_attribute_model_usage(&pool, "cost_center", now - Duration::hours(1), now, Some("claude-3"))
+            .await
+            .unwrap();
+        assert_eq!(filtered.total_requests, 1);
+        assert_eq!(filtered.values.len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_get_model_endpoint_usage(pool: PgPool) {
+        use crate::api::models::users::Role;
+        use crate::test_utils::{create_test_app, create_test_deployment, create_test_user, get_test_endpoint_id};
+
+        // The test app seeds the endpoint the deployments are hosted on
+        let _app = create_test_app(pool.clone(), false).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        create_test_deployment(&pool, user.id, "gpt-4-0613", "gpt-4").await;
+        create_test_deployment(&pool, user.id, "claude-3-opus", "claude-3").await;
+        let endpoint_id = get_test_endpoint_id(&pool).await;
+
+        let now = Utc::now();
+        for (model, status, duration) in [
+            ("gpt-4", 200, 100.0),
+            ("gpt-4", 200, 300.0),
+            ("gpt-4", 500, 200.0),
+            ("claude-3", 429, 50.0),
+            ("retired", 200, 10.0),
+        ] {
+            insert_test_analytics_data(&pool, now - Duration::minutes(5), model, status, duration, 10, 5).await;
+        }
+
+        let usage = get_model_endpoint_usage(&pool, now - Duration::hours(1), now).await.unwrap();
+        assert_eq!(usage.total_requests, 5);
+
+        let gpt4 = &usage.models[0];
+        assert_eq!(gpt4.model, "gpt-4");
+        assert_eq!(gpt4.endpoint_id, Some(endpoint_id));
+        assert_eq!(gpt4.request_count, 3);
+        assert_eq!(gpt4.error_count, 1);
+        assert!((gpt4.error_rate - 100.0 / 3.0).abs() < 1e-6);
+        assert_eq!(gpt4.input_tokens, 30);
+        assert_eq!(gpt4.avg_latency_ms, 200.0);
+        assert_eq!(gpt4.p95_latency_ms, 290.0);
+
+        // Aliases without a deployment are reported without an endpoint
+        let retired = usage.models.iter().find(|m| m.model == "retired").unwrap();
+        assert_eq!(retired.endpoint_id, None);
+
+        let endpoint = usage.endpoints.iter().find(|e| e.endpoint_id == Some(endpoint_id)).unwrap();
+        assert_eq!(endpoint.request_count, 4);
+        assert_eq!(endpoint.error_count, 2);
+        assert_eq!(endpoint.error_rate, 50.0);
+        let unknown = usage.endpoints.iter().find(|e| e.endpoint_id.is_none()).unwrap();
+        assert_eq!(unknown.request_count, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_get_usage_time_series(pool: PgPool) {
+        let start = Utc.with_ymd_and_hms(2024, 3, 4, 10, 20, 0).unwrap();
+        let end = start + Duration::hours(3);
+        for (minutes, model) in [(5, "gpt-4"), (15, "gpt-4"), (50, "claude-3"), (130, "gpt-4")] {
+            insert_test_analytics_data(&pool, start + Duration::minutes(minutes), model, 200, 100.0, 10, 5).await;
+        }
+
+        // Buckets start on the hour, with empty hours included
+        let series = get_usage_time_series(&pool, start, end, Duration::hours(1), UsageMetric::Tokens, None)
+            .await
+            .unwrap();
+        assert_eq!(series.bucket_seconds, 3600);
+        let points: Vec<_> = series.points.iter().map(|p| (p.timestamp.hour(), p.value)).collect();
+        assert_eq!(points, vec![(10, 30.0), (11, 15.0), (12, 15.0), (13, 0.0)]);
+
+        let series = get_usage_time_series(&pool, start, end, Duration::minutes(90), UsageMetric::Requests, Some("gpt-4"))
+            .await
+            .unwrap();
+        let points: Vec<_> = series.points.iter().map(|p| (p.timestamp, p.value)).collect();
+        assert_eq!(
+            points,
+            vec![
+                (Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap(), 1.0),
+                (Utc.with_ymd_and_hms(2024, 3, 4, 10, 30, 0).unwrap(), 1.0),
+                (Utc.with_ymd_and_hms(2024, 3, 4, 12, 0, 0).unwrap(), 1.0),
+            ]
+        );
+
+        // Daily buckets start at midnight
+        let series = get_usage_time_series(&pool, start, end, Duration::days(1), UsageMetric::InputTokens, None)
+            .await
+            .unwrap();
+        assert_eq!(series.points.len(), 1);
+        assert_eq!(series.points[0].timestamp, Utc.with_ymd_and_hms(2024, 3, 4, 0, 0, 0).unwrap());
+        assert_eq!(series.points[0].value, 40.0);
+    }
+
+    #[sqlx::test]
+    async fn test_get_error_breakdown(pool: PgPool) {
+        use crate::api::models::users::Role;
+        use crate::test_utils::create_test_user;
+
+        let alice = create_test_user(&pool, Role::StandardUser).await;
+        let bob = create_test_user(&pool, Role::StandardUser).await;
+        let now = Utc::now();
+        for (user_id, model, status_code) in [
+            (alice.id, "gpt-4", 200),
+            (alice.id, "gpt-4", 504),
+            (alice.id, "gpt-4", 502),
+            (alice.id, "gpt-4", 503),
+            (bob.id, "claude-3", 429),
+            (bob.id, "claude-3", 429),
+            (bob.id, "claude-3", 200),
+            (bob.id, "gpt-4", 200),
+        ] {
+            sqlx::query!(
+                r#"
+                INSERT INTO http_analytics (
+                    instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms, model, user_id
+                ) VALUES ($1, 1, $2, '/ai/v1/chat/completions', 'POST', $3, 100, $4, $5)
+                "#,
+                uuid::Uuid::new_v4(),
+                now - Duration::minutes(5),
+                status_code,
+                model,
+                user_id
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let errors = get_error_breakdown(&pool, now - Duration::hours(1), now, None, 20).await.unwrap();
+        assert_eq!(errors.total_requests, 8);
+        assert_eq!(errors.total_errors, 5);
+        assert_eq!(errors.error_rate, 62.5);
+
+        assert_eq!(errors.by_status[0].status_code, 429);
+        assert_eq!(errors.by_status[0].count, 2);
+        assert_eq!(errors.by_status[0].percentage, 40.0);
+        let types: Vec<_> = errors.by_type.iter().map(|t| (t.error_type, t.count)).collect();
+        assert_eq!(types[0], (ErrorType::RateLimited, 2));
+        assert!(types.contains(&(ErrorType::Timeout, 1)));
+        assert!(types.contains(&(ErrorType::Connect, 1)));
+        assert!(types.contains(&(ErrorType::ServerError, 1)));
+
+        let models: Vec<_> = errors.by_model.iter().map(|m| (m.model.as_str(), m.requests, m.errors)).collect();
+        assert_eq!(models, vec![("gpt-4", 5, 3), ("claude-3", 3, 2)]);
+        assert_eq!(errors.by_user[0].user_id, Some(alice.id));
+        assert_eq!(errors.by_user[0].error_rate, 75.0);
+
+        let filtered = get_error_breakdown(&pool, now - Duration::hours(1), now, Some("claude-3"), 1)
+            .await
+            .unwrap();
+        assert_eq!(filtered.total_errors, 2);
+        assert_eq!(filtered.by_user.len(), 1);
+        assert_eq!(filtered.by_user[0].user_id, Some(bob.id));
+    }
+
+    #[sqlx::test]
+    async fn test_get_pii_stats_by_group(pool: PgPool) {
+        use crate::api::models::users::Role;
+        use crate::test_utils::{add_user_to_group, create_test_group, create_test_user};
+
+        let group = create_test_group(&pool).await;
+        let member = create_test_user(&pool, Role::StandardUser).await;
+        let outsider = create_test_user(&pool, Role::StandardUser).await;
+        add_user_to_group(&pool, member.id, group.id).await;
+
+        let now = Utc::now();
+        let requests: [(uuid::Uuid, Option<Vec<String>>); 5] = [
+            (member.id, Some(vec!["email".to_string()])),
+            (member.id, Some(vec!["email".to_string(), "financial".to_string()])),
+            (member.id, Some(vec![])),
+            (member.id, Some(vec![])),
+            // Not classified, so excluded from the rates
+            (outsider.id, None),
+        ];
+        for (user_id, categories) in requests {
+            sqlx::query!(
+                r#"
+                INSERT INTO http_analytics (
+                    instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms,
+                    model, prompt_tokens, completion_tokens, total_tokens, user_id, pii_categories
+                ) VALUES ($1, 1, $2, '/ai/v1/chat/completions', 'POST', 200, 100, 'gpt-4', 10, 10, 20, $3, $4)
+                "#,
+                uuid::Uuid::new_v4(),
+                now - Duration::minutes(5),
+                user_id,
+                categories.as_deref()
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let stats = get_pii_stats_by_group(&pool, now - Duration::hours(1), now).await.unwrap();
+
+        let group_stats = stats.groups.iter().find(|g| g.group_id == group.id).unwrap();
+        assert_eq!(group_stats.classified_requests, 4);
+        assert_eq!(group_stats.requests_with_pii, 2);
+        assert_eq!(group_stats.pii_percentage, 50.0);
+
+        let email = group_stats.categories.iter().find(|c| c.category == "email").unwrap();
+        assert_eq!(email.count, 2);
+        assert_eq!(email.percentage, 50.0);
+        let financial = group_stats.categories.iter().find(|c| c.category == "financial").unwrap();
+        assert_eq!(financial.count, 1);
+        let phone = group_stats.categories.iter().find(|c| c.category == "phone").unwrap();
+        assert_eq!(phone.count, 0);
+
+        // The outsider's unclassified request doesn't appear in Everyone's stats
+        let everyone = stats.groups.iter().find(|g| g.group_id == uuid::Uuid::nil()).unwrap();
+        assert_eq!(everyone.classified_requests, 4);
+    }
+
+    #[sqlx::test]
+    async fn test_get_adoption_metrics(pool: PgPool) {
+        use crate::api::models::users::Role;
+        use crate::test_utils::{add_user_to_group, create_test_group, create_test_user};
+
+        let group = create_test_group(&pool).await;
+        let daily = create_test_user(&pool, Role::StandardUser).await;
+        let monthly = create_test_user(&pool, Role::StandardUser).await;
+        let idle = create_test_user(&pool, Role::StandardUser).await;
+        add_user_to_group(&pool, daily.id, group.id).await;
+        add_user_to_group(&pool, idle.id, group.id).await;
+
+        let as_of = Utc::now();
+        for (user_id, age) in [
+            (daily.id, Duration::hours(2)),
+            (daily.id, Duration::days(8)),
+            (monthly.id, Duration::days(20)),
+        ] {
+            sqlx::query!(
+                r#"
+                INSERT INTO http_analytics (
+                    instance_id, correlation_id, timestamp, uri, method, status_code, duration_ms,
+                    model, prompt_tokens, completion_tokens, total_tokens, user_id
+                ) VALUES ($1, 1, $2, '/ai/v1/chat/completions', 'POST', 200, 100, 'gpt-4', 10, 10, 20, $3)
+                "#,
+                uuid::Uuid::new_v4(),
+                as_of - age,
+                user_id
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let adoption = get_adoption_metrics(&pool, as_of, 8).await.unwrap();
+
+        assert_eq!(adoption.total_users, 3);
+        assert_eq!(adoption.daily_active_users, 1);
+        assert_eq!(adoption.weekly_active_users, 1);
+        assert_eq!(adoption.monthly_active_users, 2);
+        assert_eq!(adoption.new_users, 3);
+        assert_eq!(adoption.zero_usage_users, 1);
+
+        assert_eq!(adoption.weekly_active.len(), 8);
+        assert_eq!(adoption.weekly_active.last().unwrap().active_users, 1);
+        let total_weekly: i64 = adoption.weekly_active.iter().map(|p| p.active_users).sum();
+        assert_eq!(total_weekly, 3);
+
+        let group_adoption = adoption.groups.iter().find(|g| g.group_id == group.id).unwrap();
+        assert_eq!(group_adoption.member_count, 2);
+        assert_eq!(group_adoption.monthly_active_users, 1);
+        assert_eq!(group_adoption.adoption_percentage, 50.0);
+        assert_eq!(group_adoption.weekly_active.len(), 8);
+        assert_eq!(group_adoption.weekly_active.last().unwrap().active_users, 1);
+
+        let everyone = adoption.groups.iter().find(|g| g.group_id == uuid::Uuid::nil()).unwrap();
+        assert_eq!(everyone.member_count, 3);
+        assert_eq!(everyone.monthly_active_users, 2);
+    }
 }
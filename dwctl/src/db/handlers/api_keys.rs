@@ -1,10 +1,10 @@
 use std::collections::HashMap;
 
-use crate::crypto::generate_api_key;
+use crate::crypto::{decrypt_secret, encrypt_secret, generate_api_key};
 use crate::db::errors::DbError;
 use crate::db::errors::Result;
 use crate::db::handlers::repository::Repository;
-use crate::db::models::api_keys::{ApiKeyCreateDBRequest, ApiKeyDBResponse, ApiKeyUpdateDBRequest};
+use crate::db::models::api_keys::{ApiKeyAccessRequestDBResponse, ApiKeyCreateDBRequest, ApiKeyDBResponse, ApiKeyUpdateDBRequest};
 use crate::types::{ApiKeyId, DeploymentId, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -42,20 +42,23 @@ struct ApiKey {
     pub burst_size: Option<i32>,
 }
 
-impl From<(Vec<DeploymentId>, ApiKey)> for ApiKeyDBResponse {
-    fn from((model_access, api_key): (Vec<DeploymentId>, ApiKey)) -> Self {
-        Self {
+/// Decrypts the key's secret, if it's stored encrypted
+impl TryFrom<(Vec<DeploymentId>, ApiKey)> for ApiKeyDBResponse {
+    type Error = DbError;
+
+    fn try_from((model_access, api_key): (Vec<DeploymentId>, ApiKey)) -> Result<Self> {
+        Ok(Self {
             id: api_key.id,
             name: api_key.name,
             description: api_key.description,
-            secret: api_key.secret,
+            secret: decrypt_secret(api_key.secret)?,
             user_id: api_key.user_id,
             created_at: api_key.created_at,
             last_used: api_key.last_used,
             model_access,
             requests_per_second: api_key.requests_per_second,
             burst_size: api_key.burst_size,
-        }
+        })
     }
 }
 
@@ -78,12 +81,13 @@ impl<'c> Repository for ApiKeys<'c> {
         let api_key = sqlx::query_as!(
             ApiKey,
             r#"
-            INSERT INTO api_keys (name, description, secret, user_id, requests_per_second, burst_size)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING *
+            INSERT INTO api_keys (name, description, secret, secret_hash, user_id, requests_per_second, burst_size)
+            VALUES ($1, $2, $3, api_key_secret_hash($4), $5, $6, $7)
+            RETURNING id, name, description, secret, user_id, created_at, last_used, requests_per_second, burst_size
             "#,
             request.name,
             request.description,
+            encrypt_secret(&secret)?,
             secret,
             request.user_id,
             request.requests_per_second,
@@ -92,7 +96,7 @@ impl<'c> Repository for ApiKeys<'c> {
         .fetch_one(&mut *self.db)
         .await?;
 
-        Ok(ApiKeyDBResponse::from((self.get_api_key_deployments(api_key.id).await?, api_key)))
+        ApiKeyDBResponse::try_from((self.get_api_key_deployments(api_key.id).await?, api_key))
     }
 
     async fn get_by_id(&mut self, id: Self::Id) -> Result<Option<Self::Response>> {
@@ -105,7 +109,10 @@ impl<'c> Repository for ApiKeys<'c> {
             .await?;
 
         match api_key {
-            Some(key) => Ok(Some(ApiKeyDBResponse::from((self.get_api_key_deployments(key.id).await?, key)))),
+            Some(key) => Ok(Some(ApiKeyDBResponse::try_from((
+                self.get_api_key_deployments(key.id).await?,
+                key,
+            ))?)),
             None => Ok(None),
         }
     }
@@ -122,7 +129,7 @@ impl<'c> Repository for ApiKeys<'c> {
         let mut responses = HashMap::new();
         for key in api_keys {
             let deployments = self.get_api_key_deployments(key.id).await?;
-            responses.insert(key.id, ApiKeyDBResponse::from((deployments, key)));
+            responses.insert(key.id, ApiKeyDBResponse::try_from((deployments, key))?);
         }
         Ok(responses)
     }
@@ -153,7 +160,7 @@ impl<'c> Repository for ApiKeys<'c> {
         for key in api_keys {
             let deployments = self.get_api_key_deployments(key.id).await?;
 
-            responses.push(ApiKeyDBResponse::from((deployments, key)));
+            responses.push(ApiKeyDBResponse::try_from((deployments, key))?);
         }
         Ok(responses)
     }
@@ -187,7 +194,7 @@ impl<'c> Repository for ApiKeys<'c> {
                     ELSE burst_size
                 END
             WHERE id = $1
-            RETURNING *
+            RETURNING id, name, description, secret, user_id, created_at, last_used, requests_per_second, burst_size
             "#,
             id,
             request.name,
@@ -199,7 +206,7 @@ impl<'c> Repository for ApiKeys<'c> {
         .await?
         .ok_or_else(|| DbError::NotFound)?;
 
-        Ok(ApiKeyDBResponse::from((self.get_api_key_deployments(api_key.id).await?, api_key)))
+        ApiKeyDBResponse::try_from((self.get_api_key_deployments(api_key.id).await?, api_key))
     }
 }
 
@@ -208,16 +215,42 @@ impl<'c> ApiKeys<'c> {
         Self { db }
     }
 
+    /// Look up an API key by its secret, as presented by clients of the AI proxy
+    pub async fn get_by_secret(&mut self, secret: &str) -> Result<Option<ApiKeyDBResponse>> {
+        let api_key = sqlx::query_as!(
+            ApiKey,
+            "SELECT id, name, description, secret, user_id, created_at, last_used, requests_per_second, burst_size FROM api_keys WHERE secret_hash = api_key_secret_hash($1)",
+            secret
+        )
+            .fetch_optional(&mut *self.db)
+            .await?;
+
+        match api_key {
+            Some(api_key) => Ok(Some(ApiKeyDBResponse::try_from((
+                self.get_api_key_deployments(api_key.id).await?,
+                api_key,
+            ))?)),
+            None => Ok(None),
+        }
+    }
+
     /// Get specific deployment IDs that an API key has access to
     async fn get_api_key_deployments(&mut self, api_key_id: ApiKeyId) -> Result<Vec<DeploymentId>> {
         let deployment_ids = sqlx::query_scalar!(
             r#"
             SELECT DISTINCT dg.deployment_id
-            FROM user_groups ug
-            INNER JOIN deployment_groups dg ON ug.group_id = dg.group_id
+            FROM effective_user_groups ug
+            INNER JOIN effective_deployment_groups dg ON ug.group_id = dg.group_id
             INNER JOIN api_keys ak ON ug.user_id = ak.user_id
             WHERE ak.id = $1
-            
+            AND (
+                NOT EXISTS (SELECT 1 FROM deployment_access_policies p WHERE p.deployment_id = dg.deployment_id AND p.requires_key_approval)
+                OR EXISTS (
+                    SELECT 1 FROM api_key_access_requests r
+                    WHERE r.api_key_id = ak.id AND r.deployment_id = dg.deployment_id AND r.status = 'approved'
+                )
+            )
+
             UNION
             
             SELECT DISTINCT dg.deployment_id
@@ -225,6 +258,13 @@ impl<'c> ApiKeys<'c> {
             INNER JOIN api_keys ak ON dg.group_id = '00000000-0000-0000-0000-000000000000'
             WHERE ak.id = $1
             AND ak.user_id != '00000000-0000-0000-0000-000000000000'  -- Exclude system user
+            AND (
+                NOT EXISTS (SELECT 1 FROM deployment_access_policies p WHERE p.deployment_id = dg.deployment_id AND p.requires_key_approval)
+                OR EXISTS (
+                    SELECT 1 FROM api_key_access_requests r
+                    WHERE r.api_key_id = ak.id AND r.deployment_id = dg.deployment_id AND r.status = 'approved'
+                )
+            )
             "#,
             api_key_id
         )
@@ -234,7 +274,10 @@ impl<'c> ApiKeys<'c> {
         Ok(deployment_ids.into_iter().flatten().collect())
     }
 
-    /// Get all API keys that can access the specified deployment with full response data
+    /// Get all API keys that can access the specified deployment with full response data.
+    ///
+    /// If the deployment requires key approval, keys with group access also need an approved
+    /// access request.
     pub async fn get_api_keys_for_deployment(&mut self, deployment_id: DeploymentId) -> Result<Vec<ApiKeyDBResponse>> {
         let api_keys = sqlx::query_as!(
             ApiKey,
@@ -265,9 +308,16 @@ impl<'c> ApiKeys<'c> {
                 ak.requests_per_second,
                 ak.burst_size
             FROM api_keys ak
-            INNER JOIN user_groups ug ON ak.user_id = ug.user_id
-            INNER JOIN deployment_groups dg ON ug.group_id = dg.group_id
+            INNER JOIN effective_user_groups ug ON ak.user_id = ug.user_id
+            INNER JOIN effective_deployment_groups dg ON ug.group_id = dg.group_id
             WHERE dg.deployment_id = $1
+            AND (
+                NOT EXISTS (SELECT 1 FROM deployment_access_policies p WHERE p.deployment_id = $1 AND p.requires_key_approval)
+                OR EXISTS (
+                    SELECT 1 FROM api_key_access_requests r
+                    WHERE r.api_key_id = ak.id AND r.deployment_id = $1 AND r.status = 'approved'
+                )
+            )
 
             UNION
 
@@ -285,6 +335,13 @@ impl<'c> ApiKeys<'c> {
             INNER JOIN deployment_groups dg ON dg.group_id = '00000000-0000-0000-0000-000000000000'
             WHERE dg.deployment_id = $1
             AND ak.user_id != '00000000-0000-0000-0000-000000000000'  -- Exclude system user (already covered above)
+            AND (
+                NOT EXISTS (SELECT 1 FROM deployment_access_policies p WHERE p.deployment_id = $1 AND p.requires_key_approval)
+                OR EXISTS (
+                    SELECT 1 FROM api_key_access_requests r
+                    WHERE r.api_key_id = ak.id AND r.deployment_id = $1 AND r.status = 'approved'
+                )
+            )
             "#,
             deployment_id,
             Uuid::nil() // System user ID
@@ -299,9 +356,16 @@ impl<'c> ApiKeys<'c> {
                 r#"
                 SELECT ak.id as api_key_id, dg.deployment_id
                 FROM api_keys ak
-                INNER JOIN user_groups ug ON ak.user_id = ug.user_id
-                INNER JOIN deployment_groups dg ON ug.group_id = dg.group_id
+                INNER JOIN effective_user_groups ug ON ak.user_id = ug.user_id
+                INNER JOIN effective_deployment_groups dg ON ug.group_id = dg.group_id
                 WHERE ak.id = ANY($1)
+                AND (
+                    NOT EXISTS (SELECT 1 FROM deployment_access_policies p WHERE p.deployment_id = dg.deployment_id AND p.requires_key_approval)
+                    OR EXISTS (
+                        SELECT 1 FROM api_key_access_requests r
+                        WHERE r.api_key_id = ak.id AND r.deployment_id = dg.deployment_id AND r.status = 'approved'
+                    )
+                )
 
                 UNION
 
@@ -310,6 +374,13 @@ impl<'c> ApiKeys<'c> {
                 INNER JOIN deployment_groups dg ON dg.group_id = '00000000-0000-0000-0000-000000000000'
                 WHERE ak.id = ANY($1)
                 AND ak.user_id != '00000000-0000-0000-0000-000000000000'
+                AND (
+                    NOT EXISTS (SELECT 1 FROM deployment_access_policies p WHERE p.deployment_id = dg.deployment_id AND p.requires_key_approval)
+                    OR EXISTS (
+                        SELECT 1 FROM api_key_access_requests r
+                        WHERE r.api_key_id = ak.id AND r.deployment_id = dg.deployment_id AND r.status = 'approved'
+                    )
+                )
                 "#,
                 &api_key_ids
             )
@@ -331,11 +402,93 @@ impl<'c> ApiKeys<'c> {
         let mut results = Vec::new();
         for api_key in api_keys {
             let deployments = deployment_access.get(&api_key.id).cloned().unwrap_or_default();
-            results.push(ApiKeyDBResponse::from((deployments, api_key)));
+            results.push(ApiKeyDBResponse::try_from((deployments, api_key))?);
         }
 
         Ok(results)
     }
+
+    /// Request access to a deployment requiring key approval for an API key.
+    ///
+    /// A denied request is reopened. Returns `None` if a request is already pending or approved.
+    pub async fn request_access(
+        &mut self,
+        api_key_id: ApiKeyId,
+        deployment_id: DeploymentId,
+        reason: Option<&str>,
+    ) -> Result<Option<ApiKeyAccessRequestDBResponse>> {
+        let request = sqlx::query_as!(
+            ApiKeyAccessRequestDBResponse,
+            r#"
+            INSERT INTO api_key_access_requests (api_key_id, deployment_id, reason)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (api_key_id, deployment_id) DO UPDATE SET
+                status = 'pending',
+                reason = EXCLUDED.reason,
+                requested_at = NOW(),
+                decided_by = NULL,
+                decided_at = NULL,
+                decision_note = NULL
+            WHERE api_key_access_requests.status = 'denied'
+            RETURNING *
+            "#,
+            api_key_id,
+            deployment_id,
+            reason
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(request)
+    }
+
+    /// List access requests, most recent first, optionally only those of an API key or with a
+    /// given status
+    pub async fn list_access_requests(
+        &mut self,
+        api_key_id: Option<ApiKeyId>,
+        status: Option<&str>,
+    ) -> Result<Vec<ApiKeyAccessRequestDBResponse>> {
+        let requests = sqlx::query_as!(
+            ApiKeyAccessRequestDBResponse,
+            r#"
+            SELECT * FROM api_key_access_requests
+            WHERE ($1::uuid IS NULL OR api_key_id = $1)
+            AND ($2::text IS NULL OR status = $2)
+            ORDER BY requested_at DESC
+            "#,
+            api_key_id,
+            status
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(requests)
+    }
+
+    /// Approve or deny an access request. Returns `None` if there is no such request.
+    pub async fn decide_access_request(
+        &mut self,
+        id: Uuid,
+        status: &str,
+        decided_by: UserId,
+        note: Option<&str>,
+    ) -> Result<Option<ApiKeyAccessRequestDBResponse>> {
+        let request = sqlx::query_as!(
+            ApiKeyAccessRequestDBResponse,
+            r#"
+            UPDATE api_key_access_requests
+            SET status = $2, decided_by = $3, decided_at = NOW(), decision_note = $4
+            WHERE id = $1
+            RETURNING *
+            "#,
+            id,
+            status,
+            decided_by,
+            note
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(request)
+    }
 }
 
 #[cfg(test)]
@@ -369,6 +522,7 @@ mod tests {
                     display_name: None,
                     avatar_url: None,
                     roles: vec![Role::StandardUser],
+                    attributes: Default::default(),
                 });
 
                 userid = user_repo.create(&user_create).await.unwrap().id;
@@ -408,6 +562,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
 
             user = user_repo.create(&user_create).await.unwrap();
@@ -469,6 +624,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
 
             user = user_repo.create(&user_create).await.unwrap();
@@ -510,6 +666,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
 
             user = user_repo.create(&user_create).await.unwrap();
@@ -575,6 +732,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::PlatformManager],
+                attributes: Default::default(),
             });
             admin_user = user_repo.create(&admin_create).await.unwrap();
 
@@ -585,6 +743,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
             user = user_repo.create(&user_create).await.unwrap();
         }
@@ -596,6 +755,8 @@ mod tests {
                 name: "Test Group".to_string(),
                 description: Some("Test group for API key access".to_string()),
                 created_by: admin_user.id,
+                parent_id: None,
+                is_default: false,
             };
             group = group_repo.create(&group_create).await.unwrap();
             group_tx.commit().await.unwrap();
@@ -684,6 +845,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::PlatformManager],
+                attributes: Default::default(),
             });
             admin_user = user_repo.create(&admin_create).await.unwrap();
 
@@ -694,6 +856,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
             user = user_repo.create(&user_create).await.unwrap();
         }
@@ -711,6 +874,8 @@ mod tests {
                 name: "Test Group".to_string(),
                 description: Some("Test group for access removal".to_string()),
                 created_by: admin_user.id,
+                parent_id: None,
+                is_default: false,
             };
             group = group_repo.create(&group_create).await.unwrap();
             group_tx.commit().await.unwrap();
@@ -801,6 +966,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::PlatformManager],
+                attributes: Default::default(),
             });
             admin_user = user_repo.create(&admin_create).await.unwrap();
 
@@ -811,6 +977,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
             user = user_repo.create(&user_create).await.unwrap();
         }
@@ -829,6 +996,8 @@ mod tests {
                 name: "Test Group".to_string(),
                 description: Some("Test group for deployment removal".to_string()),
                 created_by: admin_user.id,
+                parent_id: None,
+                is_default: false,
             };
             group = group_repo.create(&group_create).await.unwrap();
             group_tx.commit().await.unwrap();
@@ -922,6 +1091,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::PlatformManager],
+                attributes: Default::default(),
             });
             admin_user = user_repo.create(&admin_create).await.unwrap();
 
@@ -932,6 +1102,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
             user1 = user_repo.create(&user1_create).await.unwrap();
 
@@ -941,6 +1112,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
             user2 = user_repo.create(&user2_create).await.unwrap();
         }
@@ -958,6 +1130,8 @@ mod tests {
                 name: "Test Group 1".to_string(),
                 description: Some("First test group".to_string()),
                 created_by: admin_user.id,
+                parent_id: None,
+                is_default: false,
             };
             group1 = group_repo.create(&group1_create).await.unwrap();
 
@@ -965,6 +1139,8 @@ mod tests {
                 name: "Test Group 2".to_string(),
                 description: Some("Second test group".to_string()),
                 created_by: admin_user.id,
+                parent_id: None,
+                is_default: false,
             };
             group2 = group_repo.create(&group2_create).await.unwrap();
             group_tx.commit().await.unwrap();
@@ -1073,6 +1249,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::PlatformManager],
+                attributes: Default::default(),
             });
             admin_user = user_repo.create(&admin_create).await.unwrap();
 
@@ -1083,6 +1260,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
             user = user_repo.create(&user_create).await.unwrap();
         }
@@ -1100,6 +1278,8 @@ mod tests {
                     name: "Multi Deployment Group".to_string(),
                     description: Some("Group with multiple deployments".to_string()),
                     created_by: admin_user.id,
+                    parent_id: None,
+                    is_default: false,
                 };
                 group = group_repo.create(&group_create).await.unwrap();
                 group_tx.commit().await.unwrap();
@@ -1214,6 +1394,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::PlatformManager],
+                attributes: Default::default(),
             });
             admin_user = user_repo.create(&admin_create).await.unwrap();
 
@@ -1224,6 +1405,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
             user = user_repo.create(&user_create).await.unwrap();
         }
@@ -1248,6 +1430,8 @@ mod tests {
                 name: "Test Group".to_string(),
                 description: Some("Test group for dynamic access".to_string()),
                 created_by: admin_user.id,
+                parent_id: None,
+                is_default: false,
             };
             group = group_repo.create(&group_create).await.unwrap();
 
@@ -1343,6 +1527,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::PlatformManager],
+                attributes: Default::default(),
             });
             admin_user = user_repo.create(&admin_create).await.unwrap();
 
@@ -1353,6 +1538,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
             user = user_repo.create(&user_create).await.unwrap();
         }
@@ -1368,6 +1554,8 @@ mod tests {
             admin_email: "admin@example.org".to_string(),
             admin_password: None,
             secret_key: None,
+            secret_encryption: Default::default(),
+            secret_managers: Default::default(),
             model_sources: vec![crate::config::ModelSource {
                 name: "test".to_string(),
                 url: "http://localhost:8080".parse().unwrap(),
@@ -1381,7 +1569,28 @@ mod tests {
             },
             auth: Default::default(),
             enable_metrics: false,
+            metrics: Default::default(),
             enable_request_logging: false,
+            enable_pii_classification: false,
+            endpoint_validation: Default::default(),
+            endpoint_sync: Default::default(),
+            request_mirroring: Default::default(),
+            request_webhook: Default::default(),
+            body_storage: Default::default(),
+            request_log_retention: Default::default(),
+            event_stream: Default::default(),
+            sandbox: Default::default(),
+            routing: Default::default(),
+            load_testing: Default::default(),
+            slos: Default::default(),
+            probes: Default::default(),
+            probe_alerts: Default::default(),
+            moderation: Default::default(),
+            request_limits: Default::default(),
+            admission: Default::default(),
+            federation: Default::default(),
+            logging: Default::default(),
+            error_tracking: Default::default(),
         };
         crate::seed_database(&config.model_sources, &pool).await.unwrap();
 
@@ -1466,6 +1675,7 @@ mod tests {
                 display_name: None,
                 avatar_url: None,
                 roles: vec![Role::StandardUser],
+                attributes: Default::default(),
             });
 
             user = user_repo.create(&user_create).await.unwrap();
@@ -1581,6 +1791,7 @@ mod tests {
                     display_name: None,
                     avatar_url: None,
                     roles: vec![Role::StandardUser],
+                    attributes: Default::default(),
                 }))
                 .await
                 .unwrap();
@@ -1592,6 +1803,7 @@ mod tests {
                     display_name: None,
                     avatar_url: None,
                     roles: vec![Role::StandardUser],
+                    attributes: Default::default(),
                 }))
                 .await
                 .unwrap();
@@ -1666,6 +1878,7 @@ mod tests {
             display_name: None,
             avatar_url: None,
             roles: vec![Role::StandardUser],
+            attributes: Default::default(),
         });
         let user = user_repo.create(&user_create).await.unwrap();
 
@@ -1737,6 +1950,7 @@ mod tests {
             display_name: None,
             avatar_url: None,
             roles: vec![Role::StandardUser],
+            attributes: Default::default(),
         });
         let user = user_repo.create(&user_create).await.unwrap();
 
@@ -1815,6 +2029,7 @@ mod tests {
             display_name: None,
             avatar_url: None,
             roles: vec![Role::StandardUser],
+            attributes: Default::default(),
         });
         let user = user_repo.create(&user_create).await.unwrap();
 
@@ -1857,6 +2072,7 @@ mod tests {
             display_name: None,
             avatar_url: None,
             roles: vec![Role::PlatformManager],
+            attributes: Default::default(),
         });
         let admin_user = user_repo.create(&admin_create).await.unwrap();
 
@@ -1867,6 +2083,7 @@ mod tests {
             display_name: None,
             avatar_url: None,
             roles: vec![Role::StandardUser],
+            attributes: Default::default(),
         });
         let user = user_repo.create(&user_create).await.unwrap();
         tx.commit().await.unwrap();
@@ -1894,6 +2111,8 @@ mod tests {
             name: "Bulk Test Group".to_string(),
             description: Some("Group for bulk API key testing".to_string()),
             created_by: admin_user.id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.unwrap();
 
@@ -1958,6 +2177,7 @@ mod tests {
             display_name: None,
             avatar_url: None,
             roles: vec![Role::StandardUser],
+            attributes: Default::default(),
         });
         let user1 = user_repo.create(&user1_create).await.unwrap();
 
@@ -1967,6 +2187,7 @@ mod tests {
             display_name: None,
             avatar_url: None,
             roles: vec![Role::StandardUser],
+            attributes: Default::default(),
         });
         let user2 = user_repo.create(&user2_create).await.unwrap();
 
@@ -1,16 +1,24 @@
+use crate::api::models::{deployments::ModelSortField, pagination::ListCursor, sorting::SortOrder};
 use crate::db::{
     errors::{DbError, Result},
-    handlers::repository::Repository,
+    handlers::{
+        pagination::{push_after_cursor, push_order_by},
+        repository::Repository,
+    },
     models::deployments::{
-        DeploymentCreateDBRequest, DeploymentDBResponse, DeploymentUpdateDBRequest, FlatPricingFields, ModelPricing, ModelStatus, ModelType,
+        DeploymentCanaryDBResponse, DeploymentCreateDBRequest, DeploymentDBResponse, DeploymentDeprecationDBResponse,
+        DeploymentFallbackCreateDBRequest, DeploymentFallbackDBResponse, DeploymentLoggingPolicyDBResponse,
+        DeploymentLoggingPolicyUpdateDBRequest, DeploymentScheduleDBResponse, DeploymentScheduleUpdateDBRequest,
+        DeploymentShadowDBResponse, DeploymentTrafficSplitCreateDBRequest, DeploymentTrafficSplitDBResponse, DeploymentUpdateDBRequest,
+        FlatPricingFields, ModelPricing, ModelStatus, ModelType,
     },
 };
 use crate::types::{DeploymentId, InferenceEndpointId, UserId};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::PgConnection;
 use sqlx::{query_builder::QueryBuilder, FromRow};
+use sqlx::{Connection, PgConnection};
 
 /// Filter options for listing deployments
 #[derive(Debug, Clone)]
@@ -22,6 +30,9 @@ pub struct DeploymentFilter {
     pub deleted: Option<bool>, // None = show all, Some(false) = show non-deleted only, Some(true) = show deleted only
     pub accessible_to: Option<UserId>, // None = show all deployments, Some(user_id) = show only deployments accessible to that user
     pub aliases: Option<Vec<String>>,
+    pub enabled: Option<bool>, // None = show all, Some(true) = routable deployments only (the deployment and its endpoint are enabled)
+    pub sort: (ModelSortField, SortOrder),
+    pub after: Option<ListCursor<ModelSortField>>,
 }
 
 impl DeploymentFilter {
@@ -34,6 +45,9 @@ impl DeploymentFilter {
             deleted: None,       // Default: show all models
             accessible_to: None, // Default: show all deployments
             aliases: None,
+            enabled: None,
+            sort: (ModelSortField::CreatedAt, SortOrder::Desc), // Default: newest first
+            after: None,
         }
     }
 
@@ -61,6 +75,24 @@ impl DeploymentFilter {
         self.aliases = Some(aliases);
         self
     }
+
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = Some(enabled);
+        self
+    }
+
+    pub fn with_sort(mut self, field: ModelSortField, order: SortOrder) -> Self {
+        self.sort = (field, order);
+        self
+    }
+
+    /// Start after a cursor instead of skipping, in the sort the cursor was issued for
+    pub fn with_cursor(mut self, cursor: ListCursor<ModelSortField>) -> Self {
+        self.skip = 0;
+        self.sort = (cursor.sort, cursor.order);
+        self.after = Some(cursor);
+        self
+    }
 }
 
 /// Result of checking user access to a deployment
@@ -86,6 +118,7 @@ struct DeployedModel {
     pub status: String,
     pub last_sync: Option<DateTime<Utc>>,
     pub deleted: bool,
+    pub enabled: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub requests_per_second: Option<f32>,
@@ -99,6 +132,10 @@ struct DeployedModel {
     pub downstream_output_price_per_token: Option<Decimal>,
     pub downstream_hourly_rate: Option<Decimal>,
     pub downstream_input_token_cost_ratio: Option<Decimal>,
+    pub max_context_tokens: Option<i32>,
+    pub supports_tools: Option<bool>,
+    pub supports_vision: Option<bool>,
+    pub tags: Vec<String>,
 }
 
 pub struct Deployments<'c> {
@@ -130,11 +167,16 @@ impl From<(Option<ModelType>, DeployedModel)> for DeploymentDBResponse {
             status: ModelStatus::from_db_string(&m.status),
             last_sync: m.last_sync,
             deleted: m.deleted,
+            enabled: m.enabled,
             created_at: m.created_at,
             updated_at: m.updated_at,
             requests_per_second: m.requests_per_second,
             burst_size: m.burst_size,
             pricing,
+            max_context_tokens: m.max_context_tokens,
+            supports_tools: m.supports_tools,
+            supports_vision: m.supports_vision,
+            tags: m.tags,
         }
     }
 }
@@ -176,9 +218,10 @@ impl<'c> Repository for Deployments<'c> {
                 model_name, alias, description, type, capabilities, created_by, hosted_on, created_at, updated_at,
                 requests_per_second, burst_size, upstream_input_price_per_token, upstream_output_price_per_token,
                 downstream_pricing_mode, downstream_input_price_per_token, downstream_output_price_per_token,
-                downstream_hourly_rate, downstream_input_token_cost_ratio
+                downstream_hourly_rate, downstream_input_token_cost_ratio, max_context_tokens, supports_tools, supports_vision,
+                tags
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22)
             RETURNING *
             "#,
             request.model_name.trim(),
@@ -198,7 +241,11 @@ impl<'c> Repository for Deployments<'c> {
             flat_pricing.downstream_input_price_per_token,
             flat_pricing.downstream_output_price_per_token,
             flat_pricing.downstream_hourly_rate,
-            flat_pricing.downstream_input_token_cost_ratio
+            flat_pricing.downstream_input_token_cost_ratio,
+            request.max_context_tokens,
+            request.supports_tools,
+            request.supports_vision,
+            &request.tags
         )
         .fetch_one(&mut *self.db)
         .await?;
@@ -216,7 +263,7 @@ impl<'c> Repository for Deployments<'c> {
     async fn get_by_id(&mut self, id: Self::Id) -> Result<Option<Self::Response>> {
         let model = sqlx::query_as!(
             DeployedModel,
-            "SELECT id, model_name, alias, description, type, capabilities, created_by, hosted_on, status, last_sync, deleted, created_at, updated_at, requests_per_second, burst_size, upstream_input_price_per_token, upstream_output_price_per_token, downstream_pricing_mode, downstream_input_price_per_token, downstream_output_price_per_token, downstream_hourly_rate, downstream_input_token_cost_ratio FROM deployed_models WHERE id = $1",
+            "SELECT id, model_name, alias, description, type, capabilities, created_by, hosted_on, status, last_sync, deleted, enabled, created_at, updated_at, requests_per_second, burst_size, upstream_input_price_per_token, upstream_output_price_per_token, downstream_pricing_mode, downstream_input_price_per_token, downstream_output_price_per_token, downstream_hourly_rate, downstream_input_token_cost_ratio, max_context_tokens, supports_tools, supports_vision, tags FROM deployed_models WHERE id = $1",
             id
         )
             .fetch_optional(&mut *self.db)
@@ -240,7 +287,7 @@ impl<'c> Repository for Deployments<'c> {
 
         let deployments = sqlx::query_as!(
             DeployedModel,
-            "SELECT id, model_name, alias, description, type, capabilities, created_by, hosted_on, status, last_sync, deleted, created_at, updated_at, requests_per_second, burst_size, upstream_input_price_per_token, upstream_output_price_per_token, downstream_pricing_mode, downstream_input_price_per_token, downstream_output_price_per_token, downstream_hourly_rate, downstream_input_token_cost_ratio FROM deployed_models WHERE id = ANY($1)",
+            "SELECT id, model_name, alias, description, type, capabilities, created_by, hosted_on, status, last_sync, deleted, enabled, created_at, updated_at, requests_per_second, burst_size, upstream_input_price_per_token, upstream_output_price_per_token, downstream_pricing_mode, downstream_input_price_per_token, downstream_output_price_per_token, downstream_hourly_rate, downstream_input_token_cost_ratio, max_context_tokens, supports_tools, supports_vision, tags FROM deployed_models WHERE id = ANY($1)",
             ids.as_slice()
         )
             .fetch_all(&mut *self.db)
@@ -382,6 +429,23 @@ impl<'c> Repository for Deployments<'c> {
                 ELSE downstream_input_token_cost_ratio
             END,
 
+            enabled    = COALESCE($32, enabled),
+
+            -- Three-state updates for metadata
+            max_context_tokens = CASE
+                WHEN $33 THEN $34
+                ELSE max_context_tokens
+            END,
+            supports_tools = CASE
+                WHEN $35 THEN $36
+                ELSE supports_tools
+            END,
+            supports_vision = CASE
+                WHEN $37 THEN $38
+                ELSE supports_vision
+            END,
+            tags = COALESCE($39, tags),
+
             updated_at = NOW()
         WHERE id = $1
         RETURNING *
@@ -423,7 +487,16 @@ impl<'c> Repository for Deployments<'c> {
             pricing_params.should_update_downstream_hourly, // $28
             pricing_params.downstream_hourly,               // $29
             pricing_params.should_update_downstream_ratio,  // $30
-            pricing_params.downstream_ratio                 // $31
+            pricing_params.downstream_ratio,                // $31
+            request.enabled,                                // $32
+            // For metadata
+            request.max_context_tokens.is_some() as bool,                         // $33
+            request.max_context_tokens.as_ref().and_then(|inner| inner.as_ref()), // $34
+            request.supports_tools.is_some() as bool,                             // $35
+            request.supports_tools.as_ref().and_then(|inner| inner.as_ref()),     // $36
+            request.supports_vision.is_some() as bool,                            // $37
+            request.supports_vision.as_ref().and_then(|inner| inner.as_ref()),    // $38
+            request.tags.as_deref()                                               // $39
         )
         .fetch_one(&mut *self.db)
         .await?;
@@ -462,6 +535,15 @@ impl<'c> Repository for Deployments<'c> {
             query.push_bind(deleted);
         }
 
+        // Add enabled filter if specified. Deployments on a disabled endpoint aren't routable either.
+        if let Some(enabled) = filter.enabled {
+            if enabled {
+                query.push(" AND enabled AND hosted_on IN (SELECT id FROM inference_endpoints WHERE enabled)");
+            } else {
+                query.push(" AND (NOT enabled OR hosted_on IN (SELECT id FROM inference_endpoints WHERE NOT enabled))");
+            }
+        }
+
         // Add aliases filter if specified
         if let Some(ref aliases) = filter.aliases {
             if !aliases.is_empty() {
@@ -474,8 +556,8 @@ impl<'c> Repository for Deployments<'c> {
         // Add accessibility filter if specified
         if let Some(user_id) = filter.accessible_to {
             query.push(" AND id IN (");
-            query.push("SELECT dg.deployment_id FROM deployment_groups dg WHERE dg.group_id IN (");
-            query.push("SELECT ug.group_id FROM user_groups ug WHERE ug.user_id = ");
+            query.push("SELECT dg.deployment_id FROM effective_deployment_groups dg WHERE dg.group_id IN (");
+            query.push("SELECT ug.group_id FROM effective_user_groups ug WHERE ug.user_id = ");
             query.push_bind(user_id);
             query.push(" UNION SELECT '00000000-0000-0000-0000-000000000000'::uuid WHERE ");
             query.push_bind(user_id);
@@ -483,8 +565,14 @@ impl<'c> Repository for Deployments<'c> {
             query.push("))");
         }
 
-        // Add ordering and pagination
-        query.push(" ORDER BY created_at DESC LIMIT ");
+        if let Some(ref cursor) = filter.after {
+            push_after_cursor(&mut query, cursor, "id");
+        }
+
+        // Add ordering and pagination. Sort columns come from a fixed list, and the ID breaks ties
+        // so pages are stable
+        push_order_by(&mut query, filter.sort, "id");
+        query.push(" LIMIT ");
         query.push_bind(filter.limit);
         query.push(" OFFSET ");
         query.push_bind(filter.skip);
@@ -522,9 +610,9 @@ impl<'c> Deployments<'c> {
                 d.alias as deployment_alias, 
                 ak.secret as system_api_key
             FROM users u
-            JOIN deployment_groups dg ON (
+            JOIN effective_deployment_groups dg ON (
                 dg.group_id IN (
-                    SELECT ug.group_id FROM user_groups ug WHERE ug.user_id = u.id
+                    SELECT ug.group_id FROM effective_user_groups ug WHERE ug.user_id = u.id
                     UNION 
                     SELECT '00000000-0000-0000-0000-000000000000'::uuid 
                     WHERE u.id != '00000000-0000-0000-0000-000000000000'
@@ -533,6 +621,15 @@ impl<'c> Deployments<'c> {
             JOIN deployed_models d ON dg.deployment_id = d.id
             JOIN api_keys ak ON ak.id = '00000000-0000-0000-0000-000000000000'::uuid
             WHERE u.email = $1 AND d.alias = $2
+            -- Deployments requiring key approval need one of the user's keys to have been approved
+            AND (
+                NOT EXISTS (SELECT 1 FROM deployment_access_policies p WHERE p.deployment_id = d.id AND p.requires_key_approval)
+                OR EXISTS (
+                    SELECT 1 FROM api_key_access_requests r
+                    JOIN api_keys uk ON uk.id = r.api_key_id
+                    WHERE uk.user_id = u.id AND r.deployment_id = d.id AND r.status = 'approved'
+                )
+            )
             LIMIT 1
             "#,
             user_email,
@@ -541,8 +638,573 @@ impl<'c> Deployments<'c> {
         .fetch_optional(&mut *self.db)
         .await?;
 
+        result
+            .map(|access| {
+                Ok(DeploymentAccessInfo {
+                    system_api_key: crate::crypto::decrypt_secret(access.system_api_key)?,
+                    ..access
+                })
+            })
+            .transpose()
+    }
+
+    /// Get the fallbacks of a set of deployments, each ordered by priority
+    pub async fn get_fallbacks_bulk(
+        &mut self,
+        deployment_ids: &[DeploymentId],
+    ) -> Result<std::collections::HashMap<DeploymentId, Vec<DeploymentFallbackDBResponse>>> {
+        if deployment_ids.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+
+        let fallbacks = sqlx::query_as!(
+            DeploymentFallbackDBResponse,
+            r#"
+            SELECT deployment_id, priority, endpoint_id, model_name
+            FROM deployment_fallbacks
+            WHERE deployment_id = ANY($1)
+            ORDER BY deployment_id, priority
+            "#,
+            deployment_ids
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+
+        let mut result: std::collections::HashMap<DeploymentId, Vec<DeploymentFallbackDBResponse>> = std::collections::HashMap::new();
+        for fallback in fallbacks {
+            result.entry(fallback.deployment_id).or_default().push(fallback);
+        }
         Ok(result)
     }
+
+    /// Replace the fallbacks of a deployment. Priorities are assigned in the given order, starting at 1.
+    pub async fn set_fallbacks(
+        &mut self,
+        deployment_id: DeploymentId,
+        fallbacks: &[DeploymentFallbackCreateDBRequest],
+    ) -> Result<Vec<DeploymentFallbackDBResponse>> {
+        if fallbacks.iter().any(|f| f.model_name.trim().is_empty()) {
+            return Err(DbError::InvalidModelField { field: "model_name" });
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query!("DELETE FROM deployment_fallbacks WHERE deployment_id = $1", deployment_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut created = Vec::with_capacity(fallbacks.len());
+        for (i, fallback) in fallbacks.iter().enumerate() {
+            let row = sqlx::query_as!(
+                DeploymentFallbackDBResponse,
+                r#"
+                INSERT INTO deployment_fallbacks (deployment_id, priority, endpoint_id, model_name)
+                VALUES ($1, $2, $3, $4)
+                RETURNING deployment_id, priority, endpoint_id, model_name
+                "#,
+                deployment_id,
+                i as i32 + 1,
+                fallback.endpoint_id,
+                fallback.model_name.trim()
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            created.push(row);
+        }
+
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    /// Give several deployments new aliases at once. Aliases can be swapped between the
+    /// deployments, as none of the new aliases is checked for uniqueness until all are set.
+    pub async fn set_aliases(&mut self, aliases: &[(DeploymentId, String)]) -> Result<()> {
+        if aliases.iter().any(|(_, alias)| alias.trim().is_empty()) {
+            return Err(DbError::InvalidModelField { field: "alias" });
+        }
+        let ids: Vec<DeploymentId> = aliases.iter().map(|(id, _)| *id).collect();
+        let new_aliases: Vec<String> = aliases.iter().map(|(_, alias)| alias.trim().to_string()).collect();
+
+        let mut tx = self.db.begin().await?;
+
+        // Move the deployments out of the way first, under their (unique) IDs
+        sqlx::query!("UPDATE deployed_models SET alias = id::text WHERE id = ANY($1)", &ids)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            r#"
+            UPDATE deployed_models d
+            SET alias = new.alias, updated_at = NOW()
+            FROM UNNEST($1::uuid[], $2::text[]) AS new(id, alias)
+            WHERE d.id = new.id
+            "#,
+            &ids,
+            &new_aliases
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get the weighted targets of a set of deployments, each ordered by position
+    pub async fn get_traffic_splits_bulk(
+        &mut self,
+        deployment_ids: &[DeploymentId],
+    ) -> Result<std::collections::HashMap<DeploymentId, Vec<DeploymentTrafficSplitDBResponse>>> {
+        if deployment_ids.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+
+        let splits = sqlx::query_as!(
+            DeploymentTrafficSplitDBResponse,
+            r#"
+            SELECT deployment_id, position, endpoint_id, model_name, weight
+            FROM deployment_traffic_splits
+            WHERE deployment_id = ANY($1)
+            ORDER BY deployment_id, position
+            "#,
+            deployment_ids
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+
+        let mut result: std::collections::HashMap<DeploymentId, Vec<DeploymentTrafficSplitDBResponse>> = std::collections::HashMap::new();
+        for split in splits {
+            result.entry(split.deployment_id).or_default().push(split);
+        }
+        Ok(result)
+    }
+
+    /// Replace the weighted targets of a deployment. An empty list sends all traffic back to the
+    /// deployment's own endpoint.
+    pub async fn set_traffic_splits(
+        &mut self,
+        deployment_id: DeploymentId,
+        splits: &[DeploymentTrafficSplitCreateDBRequest],
+    ) -> Result<Vec<DeploymentTrafficSplitDBResponse>> {
+        if splits.iter().any(|s| s.model_name.trim().is_empty()) {
+            return Err(DbError::InvalidModelField { field: "model_name" });
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query!("DELETE FROM deployment_traffic_splits WHERE deployment_id = $1", deployment_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut created = Vec::with_capacity(splits.len());
+        for (i, split) in splits.iter().enumerate() {
+            let row = sqlx::query_as!(
+                DeploymentTrafficSplitDBResponse,
+                r#"
+                INSERT INTO deployment_traffic_splits (deployment_id, position, endpoint_id, model_name, weight)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING deployment_id, position, endpoint_id, model_name, weight
+                "#,
+                deployment_id,
+                i as i32 + 1,
+                split.endpoint_id,
+                split.model_name.trim(),
+                split.weight
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            created.push(row);
+        }
+
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    /// Get the canary configuration of a deployment, if it is a canary
+    pub async fn get_canary(&mut self, deployment_id: DeploymentId) -> Result<Option<DeploymentCanaryDBResponse>> {
+        let canary = sqlx::query_as!(
+            DeploymentCanaryDBResponse,
+            "SELECT deployment_id, stable_deployment_id, percent, started_at FROM deployment_canaries WHERE deployment_id = $1",
+            deployment_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(canary)
+    }
+
+    /// Get the canary taking traffic from a deployment's alias, if there is one
+    pub async fn get_canary_of(&mut self, stable_deployment_id: DeploymentId) -> Result<Option<DeploymentCanaryDBResponse>> {
+        let canary = sqlx::query_as!(
+            DeploymentCanaryDBResponse,
+            "SELECT deployment_id, stable_deployment_id, percent, started_at FROM deployment_canaries WHERE stable_deployment_id = $1",
+            stable_deployment_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(canary)
+    }
+
+    /// Get the canaries among a set of deployments whose stable deployment is also in the set
+    pub async fn get_canaries_bulk(&mut self, deployment_ids: &[DeploymentId]) -> Result<Vec<DeploymentCanaryDBResponse>> {
+        if deployment_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let canaries = sqlx::query_as!(
+            DeploymentCanaryDBResponse,
+            r#"
+            SELECT deployment_id, stable_deployment_id, percent, started_at
+            FROM deployment_canaries
+            WHERE deployment_id = ANY($1) AND stable_deployment_id = ANY($1)
+            "#,
+            deployment_ids
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(canaries)
+    }
+
+    /// Get the deployments among a set whose circuit breaker is open after repeated probe failures
+    pub async fn get_open_circuits_bulk(&mut self, deployment_ids: &[DeploymentId]) -> Result<std::collections::HashSet<DeploymentId>> {
+        if deployment_ids.is_empty() {
+            return Ok(std::collections::HashSet::new());
+        }
+
+        let open = sqlx::query_scalar!(
+            r#"
+            SELECT deployment_id
+            FROM deployment_circuit_breakers
+            WHERE deployment_id = ANY($1)
+            "#,
+            deployment_ids
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(open.into_iter().collect())
+    }
+
+    /// Make a deployment a canary of another, or change its percentage. The comparison window
+    /// (`started_at`) restarts only when the stable deployment changes.
+    pub async fn set_canary(
+        &mut self,
+        deployment_id: DeploymentId,
+        stable_deployment_id: DeploymentId,
+        percent: i32,
+    ) -> Result<DeploymentCanaryDBResponse> {
+        let canary = sqlx::query_as!(
+            DeploymentCanaryDBResponse,
+            r#"
+            INSERT INTO deployment_canaries (deployment_id, stable_deployment_id, percent)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (deployment_id) DO UPDATE SET
+                percent = EXCLUDED.percent,
+                started_at = CASE
+                    WHEN deployment_canaries.stable_deployment_id = EXCLUDED.stable_deployment_id THEN deployment_canaries.started_at
+                    ELSE NOW()
+                END,
+                stable_deployment_id = EXCLUDED.stable_deployment_id
+            RETURNING deployment_id, stable_deployment_id, percent, started_at
+            "#,
+            deployment_id,
+            stable_deployment_id,
+            percent
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(canary)
+    }
+
+    /// Stop a deployment being a canary. Returns whether it was one.
+    pub async fn delete_canary(&mut self, deployment_id: DeploymentId) -> Result<bool> {
+        let result = sqlx::query!("DELETE FROM deployment_canaries WHERE deployment_id = $1", deployment_id)
+            .execute(&mut *self.db)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the shadow configuration of a deployment, if it is a shadow
+    pub async fn get_shadow(&mut self, deployment_id: DeploymentId) -> Result<Option<DeploymentShadowDBResponse>> {
+        let shadow = sqlx::query_as!(
+            DeploymentShadowDBResponse,
+            "SELECT deployment_id, source_deployment_id, percent, started_at FROM deployment_shadows WHERE deployment_id = $1",
+            deployment_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(shadow)
+    }
+
+    /// Get the shadow receiving copies of a deployment's requests, if there is one
+    pub async fn get_shadow_of(&mut self, source_deployment_id: DeploymentId) -> Result<Option<DeploymentShadowDBResponse>> {
+        let shadow = sqlx::query_as!(
+            DeploymentShadowDBResponse,
+            "SELECT deployment_id, source_deployment_id, percent, started_at FROM deployment_shadows WHERE source_deployment_id = $1",
+            source_deployment_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(shadow)
+    }
+
+    /// Get the shadows among a set of deployments whose source deployment is also in the set
+    pub async fn get_shadows_bulk(&mut self, deployment_ids: &[DeploymentId]) -> Result<Vec<DeploymentShadowDBResponse>> {
+        if deployment_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let shadows = sqlx::query_as!(
+            DeploymentShadowDBResponse,
+            r#"
+            SELECT deployment_id, source_deployment_id, percent, started_at
+            FROM deployment_shadows
+            WHERE deployment_id = ANY($1) AND source_deployment_id = ANY($1)
+            "#,
+            deployment_ids
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(shadows)
+    }
+
+    /// Make a deployment a shadow of another, or change its percentage. The comparison window
+    /// (`started_at`) restarts only when the source deployment changes.
+    pub async fn set_shadow(
+        &mut self,
+        deployment_id: DeploymentId,
+        source_deployment_id: DeploymentId,
+        percent: i32,
+    ) -> Result<DeploymentShadowDBResponse> {
+        let shadow = sqlx::query_as!(
+            DeploymentShadowDBResponse,
+            r#"
+            INSERT INTO deployment_shadows (deployment_id, source_deployment_id, percent)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (deployment_id) DO UPDATE SET
+                percent = EXCLUDED.percent,
+                started_at = CASE
+                    WHEN deployment_shadows.source_deployment_id = EXCLUDED.source_deployment_id THEN deployment_shadows.started_at
+                    ELSE NOW()
+                END,
+                source_deployment_id = EXCLUDED.source_deployment_id
+            RETURNING deployment_id, source_deployment_id, percent, started_at
+            "#,
+            deployment_id,
+            source_deployment_id,
+            percent
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(shadow)
+    }
+
+    /// Stop a deployment being a shadow. Returns whether it was one.
+    pub async fn delete_shadow(&mut self, deployment_id: DeploymentId) -> Result<bool> {
+        let result = sqlx::query!("DELETE FROM deployment_shadows WHERE deployment_id = $1", deployment_id)
+            .execute(&mut *self.db)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the deprecation of a deployment, if it is deprecated
+    pub async fn get_deprecation(&mut self, deployment_id: DeploymentId) -> Result<Option<DeploymentDeprecationDBResponse>> {
+        Ok(self.get_deprecations_bulk(&[deployment_id]).await?.pop())
+    }
+
+    /// Get the deprecations of a set of deployments
+    pub async fn get_deprecations_bulk(&mut self, deployment_ids: &[DeploymentId]) -> Result<Vec<DeploymentDeprecationDBResponse>> {
+        if deployment_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let deprecations = sqlx::query_as!(
+            DeploymentDeprecationDBResponse,
+            r#"
+            SELECT d.deployment_id, d.sunset_at, d.replacement_deployment_id, r.alias as "replacement_alias?", d.deprecated_at
+            FROM deployment_deprecations d
+            LEFT JOIN deployed_models r ON r.id = d.replacement_deployment_id AND NOT r.deleted
+            WHERE d.deployment_id = ANY($1)
+            "#,
+            deployment_ids
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(deprecations)
+    }
+
+    /// Deprecate a deployment, or change its sunset date or replacement. `deprecated_at` is kept
+    /// from the first time it was deprecated.
+    pub async fn set_deprecation(
+        &mut self,
+        deployment_id: DeploymentId,
+        sunset_at: Option<DateTime<Utc>>,
+        replacement_deployment_id: Option<DeploymentId>,
+    ) -> Result<DeploymentDeprecationDBResponse> {
+        sqlx::query!(
+            r#"
+            INSERT INTO deployment_deprecations (deployment_id, sunset_at, replacement_deployment_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (deployment_id) DO UPDATE SET
+                sunset_at = EXCLUDED.sunset_at,
+                replacement_deployment_id = EXCLUDED.replacement_deployment_id
+            "#,
+            deployment_id,
+            sunset_at,
+            replacement_deployment_id
+        )
+        .execute(&mut *self.db)
+        .await?;
+        self.get_deprecation(deployment_id).await?.ok_or(DbError::NotFound)
+    }
+
+    /// Stop a deployment being deprecated. Returns whether it was.
+    pub async fn delete_deprecation(&mut self, deployment_id: DeploymentId) -> Result<bool> {
+        let result = sqlx::query!("DELETE FROM deployment_deprecations WHERE deployment_id = $1", deployment_id)
+            .execute(&mut *self.db)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Whether API keys need to be approved individually to access a deployment
+    pub async fn requires_key_approval(&mut self, deployment_id: DeploymentId) -> Result<bool> {
+        let required = sqlx::query_scalar!(
+            "SELECT requires_key_approval FROM deployment_access_policies WHERE deployment_id = $1",
+            deployment_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(required.unwrap_or(false))
+    }
+
+    /// Set whether API keys need to be approved individually to access a deployment
+    pub async fn set_requires_key_approval(&mut self, deployment_id: DeploymentId, required: bool) -> Result<bool> {
+        let required = sqlx::query_scalar!(
+            r#"
+            INSERT INTO deployment_access_policies (deployment_id, requires_key_approval)
+            VALUES ($1, $2)
+            ON CONFLICT (deployment_id) DO UPDATE SET
+                requires_key_approval = EXCLUDED.requires_key_approval,
+                updated_at = NOW()
+            RETURNING requires_key_approval
+            "#,
+            deployment_id,
+            required
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(required)
+    }
+
+    /// Get the logging policy of a deployment, if one has been set
+    pub async fn get_logging_policy(&mut self, deployment_id: DeploymentId) -> Result<Option<DeploymentLoggingPolicyDBResponse>> {
+        let policy = sqlx::query_as!(
+            DeploymentLoggingPolicyDBResponse,
+            "SELECT deployment_id, mode, redacted_fields, body_sample_percent FROM deployment_logging_policies WHERE deployment_id = $1",
+            deployment_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(policy)
+    }
+
+    /// Get the logging policies of several deployments, keyed by deployment
+    pub async fn get_logging_policies_bulk(
+        &mut self,
+        deployment_ids: &[DeploymentId],
+    ) -> Result<std::collections::HashMap<DeploymentId, DeploymentLoggingPolicyDBResponse>> {
+        if deployment_ids.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+
+        let policies = sqlx::query_as!(
+            DeploymentLoggingPolicyDBResponse,
+            "SELECT deployment_id, mode, redacted_fields, body_sample_percent FROM deployment_logging_policies WHERE deployment_id = ANY($1)",
+            deployment_ids
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(policies.into_iter().map(|policy| (policy.deployment_id, policy)).collect())
+    }
+
+    /// Set the logging policy of a deployment, replacing any existing one
+    pub async fn set_logging_policy(
+        &mut self,
+        deployment_id: DeploymentId,
+        policy: &DeploymentLoggingPolicyUpdateDBRequest,
+    ) -> Result<DeploymentLoggingPolicyDBResponse> {
+        let policy = sqlx::query_as!(
+            DeploymentLoggingPolicyDBResponse,
+            r#"
+            INSERT INTO deployment_logging_policies (deployment_id, mode, redacted_fields, body_sample_percent)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (deployment_id) DO UPDATE SET
+                mode = EXCLUDED.mode,
+                redacted_fields = EXCLUDED.redacted_fields,
+                body_sample_percent = EXCLUDED.body_sample_percent,
+                updated_at = NOW()
+            RETURNING deployment_id, mode, redacted_fields, body_sample_percent
+            "#,
+            deployment_id,
+            policy.mode,
+            &policy.redacted_fields,
+            policy.body_sample_percent
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(policy)
+    }
+
+    /// Get the schedule hints of a deployment, if they have been set
+    pub async fn get_schedule(&mut self, deployment_id: DeploymentId) -> Result<Option<DeploymentScheduleDBResponse>> {
+        let schedule = sqlx::query_as!(
+            DeploymentScheduleDBResponse,
+            "SELECT deployment_id, busy_windows, lead_time_minutes FROM deployment_schedule_hints WHERE deployment_id = $1",
+            deployment_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(schedule)
+    }
+
+    /// Get the schedule hints of every deployment that has any, skipping deleted deployments
+    pub async fn list_schedules(&mut self) -> Result<Vec<DeploymentScheduleDBResponse>> {
+        let schedules = sqlx::query_as!(
+            DeploymentScheduleDBResponse,
+            r#"
+            SELECT h.deployment_id, h.busy_windows, h.lead_time_minutes
+            FROM deployment_schedule_hints h
+            JOIN deployed_models d ON d.id = h.deployment_id
+            WHERE NOT d.deleted AND jsonb_array_length(h.busy_windows) > 0
+            ORDER BY d.alias
+            "#
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(schedules)
+    }
+
+    /// Set the schedule hints of a deployment, replacing any existing ones
+    pub async fn set_schedule(
+        &mut self,
+        deployment_id: DeploymentId,
+        schedule: &DeploymentScheduleUpdateDBRequest,
+    ) -> Result<DeploymentScheduleDBResponse> {
+        let schedule = sqlx::query_as!(
+            DeploymentScheduleDBResponse,
+            r#"
+            INSERT INTO deployment_schedule_hints (deployment_id, busy_windows, lead_time_minutes)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (deployment_id) DO UPDATE SET
+                busy_windows = EXCLUDED.busy_windows,
+                lead_time_minutes = EXCLUDED.lead_time_minutes,
+                updated_at = NOW()
+            RETURNING deployment_id, busy_windows, lead_time_minutes
+            "#,
+            deployment_id,
+            &schedule.busy_windows,
+            schedule.lead_time_minutes
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(schedule)
+    }
 }
 
 #[cfg(test)]
@@ -575,6 +1237,7 @@ mod tests {
             display_name: None,
             avatar_url: None,
             roles: vec![Role::StandardUser],
+            attributes: Default::default(),
         });
         user_repo.create(&user_create).await.unwrap().into()
     }
@@ -922,6 +1585,63 @@ mod tests {
         assert!(models.iter().all(|m| m.hosted_on == endpoint_id));
     }
 
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_list_with_enabled_filter(pool: PgPool) {
+        let base_url = url::Url::parse("http://localhost:8080").unwrap();
+        let sources = vec![crate::config::ModelSource {
+            name: "test".to_string(),
+            url: base_url.clone(),
+            api_key: None,
+            sync_interval: std::time::Duration::from_secs(3600),
+        }];
+        crate::seed_database(&sources, &pool).await.unwrap();
+
+        let mut pool_conn = pool.acquire().await.unwrap();
+        let mut repo = Deployments::new(&mut pool_conn);
+        let user = create_test_user(&pool).await;
+        let endpoint_id = get_test_endpoint_id(&pool).await;
+
+        let enabled = repo
+            .create(
+                &DeploymentCreateDBRequest::builder()
+                    .created_by(user.id)
+                    .model_name("enabled-model".to_string())
+                    .alias("enabled-deployment".to_string())
+                    .hosted_on(endpoint_id)
+                    .build(),
+            )
+            .await
+            .unwrap();
+        let disabled = repo
+            .create(
+                &DeploymentCreateDBRequest::builder()
+                    .created_by(user.id)
+                    .model_name("disabled-model".to_string())
+                    .alias("disabled-deployment".to_string())
+                    .hosted_on(endpoint_id)
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert!(disabled.enabled, "deployments are enabled by default");
+
+        let disabled = repo
+            .update(disabled.id, &DeploymentUpdateDBRequest::builder().enabled(false).build())
+            .await
+            .unwrap();
+        assert!(!disabled.enabled);
+        assert_eq!(disabled.alias, "disabled-deployment");
+
+        let models = repo.list(&DeploymentFilter::new(0, 10).with_enabled(true)).await.unwrap();
+        assert!(models.iter().any(|m| m.id == enabled.id));
+        assert!(!models.iter().any(|m| m.id == disabled.id));
+
+        // Without the filter, disabled deployments are still listed
+        let models = repo.list(&DeploymentFilter::new(0, 10)).await.unwrap();
+        assert!(models.iter().any(|m| m.id == disabled.id));
+    }
+
     #[sqlx::test]
     #[test_log::test]
     async fn test_list_with_status_filter(pool: PgPool) {
@@ -1047,6 +1767,8 @@ mod tests {
             name: "Test Group".to_string(),
             description: Some("Test group for access control".to_string()),
             created_by: user1.id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.unwrap();
         group_repo.add_user_to_group(user1.id, group.id).await.unwrap();
@@ -1119,6 +1841,8 @@ mod tests {
             name: "Combined Filter Group".to_string(),
             description: Some("Test group for combined filters".to_string()),
             created_by: user.id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.unwrap();
         group_repo.add_user_to_group(user.id, group.id).await.unwrap();
@@ -1523,6 +2247,8 @@ mod tests {
             name: "Access Test Group".to_string(),
             description: Some("Test group for access control".to_string()),
             created_by: user.id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.unwrap();
 
@@ -2152,6 +2878,10 @@ mod tests {
             model_filter: None,
             auth_header_name: None,
             auth_header_prefix: None,
+            tls_ca_pem: None,
+            tls_skip_verify: false,
+            tls_min_version: None,
+            sync_interval_seconds: None,
             created_by: user.id,
         };
         let endpoint = endpoints_repo.create(&endpoint_create).await.unwrap();
@@ -2198,6 +2928,10 @@ mod tests {
             model_filter: None,
             auth_header_name: None,
             auth_header_prefix: None,
+            tls_ca_pem: None,
+            tls_skip_verify: false,
+            tls_min_version: None,
+            sync_interval_seconds: None,
             created_by: user.id,
         };
         let endpoint = endpoints_repo.create(&endpoint_create).await.unwrap();
@@ -1,12 +1,19 @@
+use crate::api::models::{groups::GroupSortField, pagination::ListCursor, sorting::SortOrder};
 use crate::db::{
     errors::{DbError, Result},
-    handlers::repository::Repository,
-    models::groups::{GroupCreateDBRequest, GroupDBResponse, GroupUpdateDBRequest},
+    handlers::{
+        pagination::{push_after_cursor, push_order_by},
+        repository::Repository,
+    },
+    models::groups::{
+        GroupCreateDBRequest, GroupDBResponse, GroupLogRetentionDBResponse, GroupModerationDBResponse, GroupModerationUpdateDBRequest,
+        GroupRequestLimitsDBResponse, GroupRequestLimitsUpdateDBRequest, GroupUpdateDBRequest,
+    },
 };
 use crate::types::{DeploymentId, GroupId, Operation, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, PgConnection};
+use sqlx::{query_builder::QueryBuilder, FromRow, PgConnection};
 use uuid::Uuid;
 
 /// Filter for listing groups
@@ -14,11 +21,31 @@ use uuid::Uuid;
 pub struct GroupFilter {
     pub skip: i64,
     pub limit: i64,
+    pub sort: (GroupSortField, SortOrder),
+    pub after: Option<ListCursor<GroupSortField>>,
 }
 
 impl GroupFilter {
     pub fn new(skip: i64, limit: i64) -> Self {
-        Self { skip, limit }
+        Self {
+            skip,
+            limit,
+            sort: (GroupSortField::Name, SortOrder::Asc),
+            after: None,
+        }
+    }
+
+    pub fn with_sort(mut self, field: GroupSortField, order: SortOrder) -> Self {
+        self.sort = (field, order);
+        self
+    }
+
+    /// Start after a cursor instead of skipping, in the sort the cursor was issued for
+    pub fn with_cursor(mut self, cursor: ListCursor<GroupSortField>) -> Self {
+        self.skip = 0;
+        self.sort = (cursor.sort, cursor.order);
+        self.after = Some(cursor);
+        self
     }
 }
 
@@ -32,6 +59,9 @@ struct Group {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub source: String,
+    pub priority: String,
+    pub parent_id: Option<GroupId>,
+    pub is_default: bool,
 }
 
 pub struct Groups<'c> {
@@ -48,6 +78,9 @@ impl From<Group> for GroupDBResponse {
             created_at: group.created_at,
             updated_at: group.updated_at,
             source: group.source,
+            priority: group.priority,
+            parent_id: group.parent_id,
+            is_default: group.is_default,
         }
     }
 }
@@ -64,19 +97,25 @@ impl<'c> Repository for Groups<'c> {
         let created_at = Utc::now();
         let updated_at = created_at;
 
+        if let Some(parent_id) = request.parent_id {
+            self.check_parent(None, parent_id).await?;
+        }
+
         // all groups created via handler/api are native, sso groups use the sync function instead
         let group = sqlx::query_as!(
             Group,
             r#"
-            INSERT INTO groups (name, description, created_by, created_at, updated_at, source)
-            VALUES ($1, $2, $3, $4, $5, 'native')
+            INSERT INTO groups (name, description, created_by, created_at, updated_at, source, parent_id, is_default)
+            VALUES ($1, $2, $3, $4, $5, 'native', $6, $7)
             RETURNING *
             "#,
             request.name,
             request.description,
             request.created_by,
             created_at,
-            updated_at
+            updated_at,
+            request.parent_id,
+            request.is_default
         )
         .fetch_one(&mut *self.db)
         .await?;
@@ -89,15 +128,7 @@ impl<'c> Repository for Groups<'c> {
             .fetch_optional(&mut *self.db)
             .await?;
 
-        Ok(group.map(|g| GroupDBResponse {
-            id: g.id,
-            name: g.name,
-            description: g.description,
-            created_by: g.created_by,
-            created_at: g.created_at,
-            updated_at: g.updated_at,
-            source: g.source,
-        }))
+        Ok(group.map(GroupDBResponse::from))
     }
 
     async fn delete(&mut self, id: Self::Id) -> Result<bool> {
@@ -127,6 +158,10 @@ impl<'c> Repository for Groups<'c> {
             });
         }
 
+        if let Some(Some(parent_id)) = request.parent_id {
+            self.check_parent(Some(id), parent_id).await?;
+        }
+
         // Atomic update with conditional field updates
         let group = sqlx::query_as!(
             Group,
@@ -134,13 +169,20 @@ impl<'c> Repository for Groups<'c> {
             UPDATE groups SET
                 name = COALESCE($2, name),
                 description = COALESCE($3, description),
+                priority = COALESCE($4, priority),
+                parent_id = CASE WHEN $5 THEN $6 ELSE parent_id END,
+                is_default = COALESCE($7, is_default),
                 updated_at = NOW()
             WHERE id = $1
             RETURNING *
             "#,
             id,
             request.name,
-            request.description
+            request.description,
+            request.priority,
+            request.parent_id.is_some(),
+            request.parent_id.flatten(),
+            request.is_default
         )
         .fetch_optional(&mut *self.db)
         .await?
@@ -150,14 +192,20 @@ impl<'c> Repository for Groups<'c> {
     }
 
     async fn list(&mut self, filter: &Self::Filter) -> Result<Vec<Self::Response>> {
-        let groups = sqlx::query_as!(
-            Group,
-            "SELECT * FROM groups ORDER BY name LIMIT $1 OFFSET $2",
-            filter.limit,
-            filter.skip
-        )
-        .fetch_all(&mut *self.db)
-        .await?;
+        let mut query = QueryBuilder::new("SELECT * FROM groups WHERE TRUE");
+
+        if let Some(ref cursor) = filter.after {
+            push_after_cursor(&mut query, cursor, "id");
+        }
+
+        // Sort columns come from a fixed list, and the ID breaks ties so pages are stable
+        push_order_by(&mut query, filter.sort, "id");
+        query.push(" LIMIT ");
+        query.push_bind(filter.limit);
+        query.push(" OFFSET ");
+        query.push_bind(filter.skip);
+
+        let groups = query.build_query_as::<Group>().fetch_all(&mut *self.db).await?;
 
         Ok(groups.into_iter().map(GroupDBResponse::from).collect())
     }
@@ -186,6 +234,44 @@ impl<'c> Groups<'c> {
         Self { db }
     }
 
+    /// Reject parents that would put the Everyone group in the hierarchy or create a cycle, i.e.
+    /// the group itself or one of its descendants.
+    async fn check_parent(&mut self, group_id: Option<GroupId>, parent_id: GroupId) -> Result<()> {
+        if parent_id == Uuid::nil() {
+            return Err(DbError::CheckViolation {
+                constraint: Some("groups_everyone_not_nested".to_string()),
+                table: Some("groups".to_string()),
+                message: "The Everyone group cannot be a parent group".to_string(),
+            });
+        }
+
+        let Some(group_id) = group_id else {
+            return Ok(());
+        };
+
+        let creates_cycle = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM group_ancestry WHERE ancestor_id = $1 AND descendant_id = $2
+            ) as "exists!"
+            "#,
+            group_id,
+            parent_id
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+
+        if creates_cycle {
+            return Err(DbError::CheckViolation {
+                constraint: Some("groups_parent_cycle".to_string()),
+                table: Some("groups".to_string()),
+                message: format!("Group {parent_id} is {group_id} or one of its descendants"),
+            });
+        }
+
+        Ok(())
+    }
+
     pub async fn add_user_to_group(&mut self, user_id: UserId, group_id: GroupId) -> Result<()> {
         match sqlx::query!(
             "INSERT INTO user_groups (user_id, group_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
@@ -204,6 +290,27 @@ impl<'c> Groups<'c> {
         }
     }
 
+    /// Add a newly provisioned user to the groups marked as default, and to the native groups
+    /// named in `configured` (names without a matching group are skipped). Returns the groups joined.
+    pub async fn add_user_to_default_groups(&mut self, user_id: UserId, configured: &[String]) -> Result<Vec<GroupId>> {
+        let group_ids = sqlx::query_scalar!(
+            r#"
+            INSERT INTO user_groups (user_id, group_id)
+            SELECT $1, id FROM groups
+            WHERE id != '00000000-0000-0000-0000-000000000000'
+              AND (is_default OR (source = 'native' AND name = ANY($2)))
+            ON CONFLICT DO NOTHING
+            RETURNING group_id
+            "#,
+            user_id,
+            configured
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+
+        Ok(group_ids)
+    }
+
     pub async fn remove_user_from_group(&mut self, user_id: UserId, group_id: GroupId) -> Result<()> {
         let result = sqlx::query!("DELETE FROM user_groups WHERE user_id = $1 AND group_id = $2", user_id, group_id)
             .execute(&mut *self.db)
@@ -255,6 +362,148 @@ impl<'c> Groups<'c> {
         }
     }
 
+    /// Get the content moderation policy of a group, if one has been set
+    pub async fn get_moderation_policy(&mut self, group_id: GroupId) -> Result<Option<GroupModerationDBResponse>> {
+        let policy = sqlx::query_as!(
+            GroupModerationDBResponse,
+            "SELECT mode, blocked_patterns FROM group_moderation_policies WHERE group_id = $1",
+            group_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(policy)
+    }
+
+    /// Set the content moderation policy of a group, replacing any existing one
+    pub async fn set_moderation_policy(
+        &mut self,
+        group_id: GroupId,
+        policy: &GroupModerationUpdateDBRequest,
+    ) -> Result<GroupModerationDBResponse> {
+        let policy = sqlx::query_as!(
+            GroupModerationDBResponse,
+            r#"
+            INSERT INTO group_moderation_policies (group_id, mode, blocked_patterns)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (group_id) DO UPDATE SET
+                mode = EXCLUDED.mode,
+                blocked_patterns = EXCLUDED.blocked_patterns,
+                updated_at = NOW()
+            RETURNING mode, blocked_patterns
+            "#,
+            group_id,
+            policy.mode,
+            &policy.blocked_patterns
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(policy)
+    }
+
+    /// Get the request limits of a group, if they have been set
+    pub async fn get_request_limits(&mut self, group_id: GroupId) -> Result<Option<GroupRequestLimitsDBResponse>> {
+        let limits = sqlx::query_as!(
+            GroupRequestLimitsDBResponse,
+            "SELECT max_body_bytes, max_messages, max_tokens FROM group_request_limits WHERE group_id = $1",
+            group_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(limits)
+    }
+
+    /// Set the request limits of a group, replacing any existing ones
+    pub async fn set_request_limits(
+        &mut self,
+        group_id: GroupId,
+        limits: &GroupRequestLimitsUpdateDBRequest,
+    ) -> Result<GroupRequestLimitsDBResponse> {
+        let limits = sqlx::query_as!(
+            GroupRequestLimitsDBResponse,
+            r#"
+            INSERT INTO group_request_limits (group_id, max_body_bytes, max_messages, max_tokens)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (group_id) DO UPDATE SET
+                max_body_bytes = EXCLUDED.max_body_bytes,
+                max_messages = EXCLUDED.max_messages,
+                max_tokens = EXCLUDED.max_tokens,
+                updated_at = NOW()
+            RETURNING max_body_bytes, max_messages, max_tokens
+            "#,
+            group_id,
+            limits.max_body_bytes,
+            limits.max_messages,
+            limits.max_tokens
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(limits)
+    }
+
+    /// Get the request log retention override of a group, if it has been set
+    pub async fn get_log_retention(&mut self, group_id: GroupId) -> Result<Option<GroupLogRetentionDBResponse>> {
+        let retention = sqlx::query_as!(
+            GroupLogRetentionDBResponse,
+            "SELECT retention_days, kind FROM group_log_retention WHERE group_id = $1",
+            group_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(retention)
+    }
+
+    /// Get the request log retention overrides that apply to a member of a group (other than
+    /// Everyone): those of the group and of the groups nested under it, whose membership its
+    /// members inherit
+    pub async fn get_member_log_retention(&mut self, group_id: GroupId) -> Result<Vec<GroupLogRetentionDBResponse>> {
+        let retention = sqlx::query_as!(
+            GroupLogRetentionDBResponse,
+            r#"
+            SELECT lr.retention_days, lr.kind
+            FROM group_ancestry ga
+            JOIN group_log_retention lr ON lr.group_id = ga.descendant_id
+            WHERE ga.ancestor_id = $1 AND lr.group_id <> '00000000-0000-0000-0000-000000000000'
+            "#,
+            group_id
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(retention)
+    }
+
+    /// Set the request log retention override of a group, or remove it with `None`
+    pub async fn set_log_retention(
+        &mut self,
+        group_id: GroupId,
+        retention: Option<GroupLogRetentionDBResponse>,
+    ) -> Result<Option<GroupLogRetentionDBResponse>> {
+        match &retention {
+            Some(retention) => {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO group_log_retention (group_id, retention_days, kind)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (group_id) DO UPDATE SET
+                        retention_days = EXCLUDED.retention_days,
+                        kind = EXCLUDED.kind,
+                        updated_at = NOW()
+                    "#,
+                    group_id,
+                    retention.retention_days,
+                    retention.kind
+                )
+                .execute(&mut *self.db)
+                .await?;
+            }
+            None => {
+                sqlx::query!("DELETE FROM group_log_retention WHERE group_id = $1", group_id)
+                    .execute(&mut *self.db)
+                    .await?;
+            }
+        }
+        Ok(retention)
+    }
+
     // Deployment-group management methods
 
     pub async fn add_deployment_to_group(&mut self, deployment_id: DeploymentId, group_id: GroupId, granted_by: UserId) -> Result<()> {
@@ -517,7 +766,10 @@ mod tests {
     use crate::{
         db::{
             handlers::{users::UserFilter, Deployments, Users},
-            models::{api_keys::ApiKeyCreateDBRequest, deployments::DeploymentCreateDBRequest},
+            models::{
+                api_keys::{ApiKeyCreateDBRequest, ApiKeyDBResponse},
+                deployments::DeploymentCreateDBRequest,
+            },
         },
         seed_database,
     };
@@ -533,6 +785,12 @@ mod tests {
             created_at: original_response.created_at,
             updated_at: chrono::Utc::now(),
             source: "native".to_string(),
+            priority: update_request
+                .priority
+                .clone()
+                .unwrap_or_else(|| original_response.priority.clone()),
+            parent_id: update_request.parent_id.unwrap_or(original_response.parent_id),
+            is_default: update_request.is_default.unwrap_or(original_response.is_default),
         }
     }
 
@@ -581,6 +839,8 @@ mod tests {
                     name: "Test Group".to_string(),
                     description: Some("Test group for deployment access".to_string()),
                     created_by: user_id,
+                    parent_id: None,
+                    is_default: false,
                 };
                 group = group_repo.create(&group_create).await.expect("Failed to create test group");
             }
@@ -648,6 +908,8 @@ mod tests {
                     name: "Test Group".to_string(),
                     description: Some("Test group for deployment access".to_string()),
                     created_by: user_id,
+                    parent_id: None,
+                    is_default: false,
                 };
                 group = group_repo.create(&group_create).await.expect("Failed to create test group");
             }
@@ -724,6 +986,8 @@ mod tests {
                 name: format!("Test Group {i}"),
                 description: Some(format!("Test group {i} for deployment access")),
                 created_by: user_id,
+                parent_id: None,
+                is_default: false,
             };
             let group = group_repo.create(&group_create).await.expect("Failed to create test group");
             group_ids.push(group.id);
@@ -794,6 +1058,8 @@ mod tests {
             name: "Test Group".to_string(),
             description: Some("Test group for multiple deployments".to_string()),
             created_by: user_id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -868,6 +1134,8 @@ mod tests {
             name: "Test Group".to_string(),
             description: Some("Test group for CASCADE delete".to_string()),
             created_by: user_id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -947,6 +1215,8 @@ mod tests {
                     name: "Test Group CASCADE".to_string(),
                     description: Some("Test group for CASCADE delete".to_string()),
                     created_by: user_id,
+                    parent_id: None,
+                    is_default: false,
                 };
                 group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -1004,6 +1274,8 @@ mod tests {
             name: "API Key CASCADE Group".to_string(),
             description: Some("Test group for API key CASCADE delete".to_string()),
             created_by: user_id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -1120,6 +1392,8 @@ mod tests {
             name: "Deployment CASCADE Group".to_string(),
             description: Some("Test group for deployment CASCADE delete".to_string()),
             created_by: user_id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -1204,6 +1478,8 @@ mod tests {
                 name: format!("Test Group {i}"),
                 description: Some(format!("Test group {i} for bulk testing")),
                 created_by: user_id,
+                parent_id: None,
+                is_default: false,
             };
             let group = group_repo.create(&group_create).await.expect("Failed to create test group");
             group_ids.push(group.id);
@@ -1468,6 +1744,8 @@ mod tests {
             name: "Regular Group".to_string(),
             description: Some("A normal group".to_string()),
             created_by: user_id,
+            parent_id: None,
+            is_default: false,
         };
         let regular_group = group_repo
             .create(&regular_group_create)
@@ -1580,6 +1858,8 @@ mod tests {
                 name: "Original Group".to_string(),
                 description: Some("Original description".to_string()),
                 created_by: user_id,
+                parent_id: None,
+                is_default: false,
             };
             group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -1587,6 +1867,9 @@ mod tests {
             let update_request = GroupUpdateDBRequest {
                 name: Some("Updated Group Name".to_string()),
                 description: Some("Updated description".to_string()),
+                priority: None,
+                parent_id: None,
+                is_default: None,
             };
 
             let updated_group = group_repo.update(group.id, &update_request).await.expect("Failed to update group");
@@ -1624,6 +1907,8 @@ mod tests {
             name: "Original Group".to_string(),
             description: Some("Original description".to_string()),
             created_by: user_id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -1631,6 +1916,9 @@ mod tests {
         let update_request = GroupUpdateDBRequest {
             name: Some("Updated Name Only".to_string()),
             description: None,
+            priority: None,
+            parent_id: None,
+            is_default: None,
         };
 
         let updated_group = group_repo.update(group.id, &update_request).await.expect("Failed to update group");
@@ -1654,6 +1942,8 @@ mod tests {
             name: "Original Group".to_string(),
             description: Some("Original description".to_string()),
             created_by: user_id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -1661,6 +1951,9 @@ mod tests {
         let update_request = GroupUpdateDBRequest {
             name: None,
             description: Some("Updated description only".to_string()),
+            priority: None,
+            parent_id: None,
+            is_default: None,
         };
 
         let updated_group = group_repo.update(group.id, &update_request).await.expect("Failed to update group");
@@ -1684,6 +1977,8 @@ mod tests {
             name: "Test Group".to_string(),
             description: Some("Has description".to_string()),
             created_by: user_id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -1691,6 +1986,9 @@ mod tests {
         let update_request = GroupUpdateDBRequest {
             name: None,
             description: Some("".to_string()),
+            priority: None,
+            parent_id: None,
+            is_default: None,
         };
 
         let updated_group = group_repo.update(group.id, &update_request).await.expect("Failed to update group");
@@ -1713,6 +2011,8 @@ mod tests {
             name: "Original Group".to_string(),
             description: Some("Original description".to_string()),
             created_by: user_id,
+            parent_id: None,
+            is_default: false,
         };
         let group = group_repo.create(&group_create).await.expect("Failed to create test group");
 
@@ -1720,6 +2020,9 @@ mod tests {
         let update_request = GroupUpdateDBRequest {
             name: None,
             description: None,
+            priority: None,
+            parent_id: None,
+            is_default: None,
         };
 
         let updated_group = group_repo.update(group.id, &update_request).await.expect("Failed to update group");
@@ -1742,6 +2045,9 @@ mod tests {
         let update_request = GroupUpdateDBRequest {
             name: Some("Updated Name".to_string()),
             description: Some("Updated description".to_string()),
+            priority: None,
+            parent_id: None,
+            is_default: None,
         };
 
         // Attempt to update nonexistent group should fail
@@ -1767,6 +2073,9 @@ mod tests {
         let update_request = GroupUpdateDBRequest {
             name: Some("Hacked Everyone".to_string()),
             description: Some("Trying to hack".to_string()),
+            priority: None,
+            parent_id: None,
+            is_default: None,
         };
 
         // Attempt to update Everyone group should fail
@@ -1811,12 +2120,18 @@ mod tests {
             created_at: original_time,
             updated_at: original_time,
             source: "native".to_string(),
+            priority: "normal".to_string(),
+            parent_id: None,
+            is_default: false,
         };
 
         // Test ApplyUpdate trait directly
         let update_request = GroupUpdateDBRequest {
             name: Some("Applied Name".to_string()),
             description: Some("Applied description".to_string()),
+            priority: None,
+            parent_id: None,
+            is_default: None,
         };
 
         let updated = mock_coalesce_update(&update_request, &group);
@@ -1844,12 +2159,18 @@ mod tests {
             created_at: original_time,
             updated_at: original_time,
             source: "native".to_string(),
+            priority: "normal".to_string(),
+            parent_id: None,
+            is_default: false,
         };
 
         // Test ApplyUpdate with only name
         let update_request = GroupUpdateDBRequest {
             name: Some("Applied Name Only".to_string()),
             description: None,
+            priority: None,
+            parent_id: None,
+            is_default: None,
         };
 
         let updated = mock_coalesce_update(&update_request, &group);
@@ -1863,6 +2184,9 @@ mod tests {
         let update_request2 = GroupUpdateDBRequest {
             name: None,
             description: Some("Applied description only".to_string()),
+            priority: None,
+            parent_id: None,
+            is_default: None,
         };
 
         let updated2 = mock_coalesce_update(&update_request2, &group);
@@ -1887,12 +2211,18 @@ mod tests {
             created_at: original_time,
             updated_at: original_time,
             source: "native".to_string(),
+            priority: "normal".to_string(),
+            parent_id: None,
+            is_default: false,
         };
 
         // Test ApplyUpdate with no changes
         let update_request = GroupUpdateDBRequest {
             name: None,
             description: None,
+            priority: None,
+            parent_id: None,
+            is_default: None,
         };
 
         let updated = mock_coalesce_update(&update_request, &group);
@@ -1917,12 +2247,18 @@ mod tests {
             created_at: original_time,
             updated_at: original_time,
             source: "native".to_string(),
+            priority: "normal".to_string(),
+            parent_id: None,
+            is_default: false,
         };
 
         // Test clearing description with empty string
         let update_request = GroupUpdateDBRequest {
             name: None,
             description: Some("".to_string()),
+            priority: None,
+            parent_id: None,
+            is_default: None,
         };
 
         let updated = mock_coalesce_update(&update_request, &group);
@@ -1932,4 +2268,157 @@ mod tests {
         assert_eq!(updated.description, Some("".to_string()));
         assert!(updated.updated_at > group.updated_at);
     }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_group_parent_rejects_cycles(pool: PgPool) {
+        let user_id = setup_test_environment(&pool).await;
+
+        let mut conn = pool.acquire().await.unwrap();
+        let mut group_repo = Groups::new(&mut conn);
+
+        let create = |name: &str, parent_id: Option<GroupId>| GroupCreateDBRequest {
+            name: name.to_string(),
+            description: None,
+            created_by: user_id,
+            parent_id,
+            is_default: false,
+        };
+        let root = group_repo.create(&create("Root", None)).await.unwrap();
+        let child = group_repo.create(&create("Child", Some(root.id))).await.unwrap();
+        let grandchild = group_repo.create(&create("Grandchild", Some(child.id))).await.unwrap();
+        assert_eq!(grandchild.parent_id, Some(child.id));
+
+        let reparent = |parent_id: Option<GroupId>| GroupUpdateDBRequest {
+            name: None,
+            description: None,
+            priority: None,
+            parent_id: Some(parent_id),
+            is_default: None,
+        };
+
+        // A group can't be nested under itself or its descendants
+        for parent in [root.id, child.id, grandchild.id] {
+            let result = group_repo.update(root.id, &reparent(Some(parent))).await;
+            assert!(
+                matches!(result, Err(DbError::CheckViolation { .. })),
+                "nesting root under {parent} should fail, got {result:?}"
+            );
+        }
+
+        // The Everyone group stays outside of the hierarchy
+        let result = group_repo.create(&create("Under Everyone", Some(Uuid::nil()))).await;
+        assert!(matches!(result, Err(DbError::CheckViolation { .. })));
+
+        // Moving a subtree elsewhere and back to the top level is fine
+        let other = group_repo.create(&create("Other", None)).await.unwrap();
+        let moved = group_repo.update(child.id, &reparent(Some(other.id))).await.unwrap();
+        assert_eq!(moved.parent_id, Some(other.id));
+        let updated = group_repo.update(root.id, &reparent(Some(grandchild.id))).await.unwrap();
+        assert_eq!(updated.parent_id, Some(grandchild.id));
+        let top_level = group_repo.update(root.id, &reparent(None)).await.unwrap();
+        assert_eq!(top_level.parent_id, None);
+
+        // Deleting a parent moves its children to the top level
+        group_repo.delete(other.id).await.unwrap();
+        let orphan = group_repo.get_by_id(child.id).await.unwrap().unwrap();
+        assert_eq!(orphan.parent_id, None);
+    }
+
+    async fn create_member_api_key(pool: &PgPool, group_id: GroupId, username: &str) -> ApiKeyDBResponse {
+        let member_id = UserId::new_v4();
+        sqlx::query("INSERT INTO users (id, username, email, auth_source) VALUES ($1, $2, $3, 'test')")
+            .bind(member_id)
+            .bind(username)
+            .bind(format!("{username}@example.com"))
+            .execute(pool)
+            .await
+            .unwrap();
+
+        let mut conn = pool.acquire().await.unwrap();
+        Groups::new(&mut conn).add_user_to_group(member_id, group_id).await.unwrap();
+        ApiKeys::new(&mut conn)
+            .create(&ApiKeyCreateDBRequest {
+                user_id: member_id,
+                name: format!("{username} key"),
+                description: None,
+                requests_per_second: None,
+                burst_size: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_nested_groups_inherit_membership_and_deployment_access(pool: PgPool) {
+        let user_id = setup_test_environment(&pool).await;
+        let test_endpoint_id = get_test_endpoint_id(&pool).await;
+
+        let mut conn = pool.acquire().await.unwrap();
+
+        let mut deployment_repo = Deployments::new(&mut conn);
+        let mut deployments = Vec::new();
+        for alias in ["parent-model", "child-model", "sibling-model"] {
+            let mut deployment_create = DeploymentCreateDBRequest::builder()
+                .created_by(user_id)
+                .model_name(alias.to_string())
+                .alias(alias.to_string())
+                .build();
+            deployment_create.hosted_on = test_endpoint_id;
+            deployments.push(deployment_repo.create(&deployment_create).await.unwrap());
+        }
+        let (parent_model, child_model, sibling_model) = (&deployments[0], &deployments[1], &deployments[2]);
+
+        let mut group_repo = Groups::new(&mut conn);
+        let create = |name: &str, parent_id: Option<GroupId>| GroupCreateDBRequest {
+            name: name.to_string(),
+            description: None,
+            created_by: user_id,
+            parent_id,
+            is_default: false,
+        };
+        let parent = group_repo.create(&create("Parent", None)).await.unwrap();
+        let child = group_repo.create(&create("Child", Some(parent.id))).await.unwrap();
+        let sibling = group_repo.create(&create("Sibling", Some(parent.id))).await.unwrap();
+
+        group_repo
+            .add_deployment_to_group(parent_model.id, parent.id, user_id)
+            .await
+            .unwrap();
+        group_repo.add_deployment_to_group(child_model.id, child.id, user_id).await.unwrap();
+        group_repo
+            .add_deployment_to_group(sibling_model.id, sibling.id, user_id)
+            .await
+            .unwrap();
+
+        // Members of the child group inherit the parent's deployments, but not the sibling's
+        let child_key = create_member_api_key(&pool, child.id, "child_member").await;
+        assert!(child_key.model_access.contains(&parent_model.id));
+        assert!(child_key.model_access.contains(&child_model.id));
+        assert!(!child_key.model_access.contains(&sibling_model.id));
+
+        // Members of the parent group are also members of both subgroups
+        let parent_key = create_member_api_key(&pool, parent.id, "parent_member").await;
+        for deployment in &deployments {
+            assert!(parent_key.model_access.contains(&deployment.id));
+        }
+
+        let mut api_key_repo = ApiKeys::new(&mut conn);
+        let keys = api_key_repo.get_api_keys_for_deployment(sibling_model.id).await.unwrap();
+        assert!(keys.iter().any(|k| k.id == parent_key.id));
+        assert!(!keys.iter().any(|k| k.id == child_key.id));
+
+        let mut deployment_repo = Deployments::new(&mut conn);
+        let access = deployment_repo
+            .check_user_access("parent-model", "child_member@example.com")
+            .await
+            .unwrap();
+        assert!(access.is_some());
+        let access = deployment_repo
+            .check_user_access("sibling-model", "child_member@example.com")
+            .await
+            .unwrap();
+        assert!(access.is_none());
+    }
 }
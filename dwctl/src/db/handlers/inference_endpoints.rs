@@ -1,23 +1,49 @@
+use crate::api::models::{inference_endpoints::EndpointSortField, pagination::ListCursor, sorting::SortOrder};
+use crate::crypto::{decrypt_secret, encrypt_secret};
 use crate::db::errors::{DbError, Result};
+use crate::db::handlers::pagination::{push_after_cursor, push_order_by};
 use crate::db::handlers::repository::Repository;
+use crate::db::models::endpoint_history::{EndpointConfig, EndpointConfigVersionDBResponse};
 use crate::db::models::inference_endpoints::{
-    InferenceEndpointCreateDBRequest, InferenceEndpointDBResponse, InferenceEndpointUpdateDBRequest,
+    EndpointHeaderRulesDBResponse, EndpointHeaderRulesUpdateDBRequest, EndpointPendingModelDBResponse, EndpointRateLimitsDBResponse,
+    EndpointRateLimitsUpdateDBRequest, EndpointRedactionDBResponse, EndpointRedactionUpdateDBRequest, EndpointSyncStrategyDBResponse,
+    EndpointSyncStrategyUpdateDBRequest, InferenceEndpointCreateDBRequest, InferenceEndpointDBResponse, InferenceEndpointUpdateDBRequest,
 };
 use crate::types::{InferenceEndpointId, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, PgConnection};
+use sqlx::{query_builder::QueryBuilder, FromRow, PgConnection};
 
 /// Filter for listing inference endpoints
 #[derive(Debug, Clone)]
 pub struct InferenceEndpointFilter {
     pub skip: i64,
     pub limit: i64,
+    pub sort: (EndpointSortField, SortOrder),
+    pub after: Option<ListCursor<EndpointSortField>>,
 }
 
 impl InferenceEndpointFilter {
     pub fn new(skip: i64, limit: i64) -> Self {
-        Self { skip, limit }
+        Self {
+            skip,
+            limit,
+            sort: (EndpointSortField::CreatedAt, SortOrder::Desc), // Default: newest first
+            after: None,
+        }
+    }
+
+    pub fn with_sort(mut self, field: EndpointSortField, order: SortOrder) -> Self {
+        self.sort = (field, order);
+        self
+    }
+
+    /// Start after a cursor instead of skipping, in the sort the cursor was issued for
+    pub fn with_cursor(mut self, cursor: ListCursor<EndpointSortField>) -> Self {
+        self.skip = 0;
+        self.sort = (cursor.sort, cursor.order);
+        self.after = Some(cursor);
+        self
     }
 }
 
@@ -32,6 +58,13 @@ struct InferenceEndpoint {
     pub model_filter: Option<Vec<String>>,
     pub auth_header_name: String,
     pub auth_header_prefix: String,
+    pub tls_ca_pem: Option<String>,
+    pub tls_skip_verify: bool,
+    pub tls_min_version: Option<String>,
+    pub sync_interval_seconds: Option<i32>,
+    pub last_sync_at: Option<DateTime<Utc>>,
+    pub last_sync_error: Option<String>,
+    pub enabled: bool,
     pub created_by: UserId,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -46,10 +79,17 @@ impl TryFrom<InferenceEndpoint> for InferenceEndpointDBResponse {
             name: src.name,
             description: src.description,
             url: src.url.parse()?, // url::Url from String
-            api_key: src.api_key,
+            api_key: src.api_key.map(decrypt_secret).transpose()?,
             model_filter: src.model_filter,
             auth_header_name: src.auth_header_name,
             auth_header_prefix: src.auth_header_prefix,
+            tls_ca_pem: src.tls_ca_pem,
+            tls_skip_verify: src.tls_skip_verify,
+            tls_min_version: src.tls_min_version,
+            sync_interval_seconds: src.sync_interval_seconds,
+            last_sync_at: src.last_sync_at,
+            last_sync_error: src.last_sync_error,
+            enabled: src.enabled,
             created_by: src.created_by,
             created_at: src.created_at,
             updated_at: src.updated_at,
@@ -76,17 +116,21 @@ impl<'c> Repository for InferenceEndpoints<'c> {
         let endpoint = sqlx::query_as!(
             InferenceEndpoint,
             r#"
-            INSERT INTO inference_endpoints (name, description, url, api_key, model_filter, auth_header_name, auth_header_prefix, created_by, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, COALESCE($6, 'Authorization'), COALESCE($7, 'Bearer '), $8, $9, $10)
+            INSERT INTO inference_endpoints (name, description, url, api_key, model_filter, auth_header_name, auth_header_prefix, tls_ca_pem, tls_skip_verify, tls_min_version, sync_interval_seconds, created_by, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, COALESCE($6, 'Authorization'), COALESCE($7, 'Bearer '), $8, $9, $10, $11, $12, $13, $14)
             RETURNING *
             "#,
             request.name,
             request.description,
             request.url.as_str(),
-            request.api_key,
+            request.api_key.as_deref().map(encrypt_secret).transpose()?,
             request.model_filter.as_deref(),
             request.auth_header_name,
             request.auth_header_prefix,
+            request.tls_ca_pem,
+            request.tls_skip_verify,
+            request.tls_min_version,
+            request.sync_interval_seconds,
             request.created_by,
             created_at,
             updated_at
@@ -128,6 +172,13 @@ impl<'c> Repository for InferenceEndpoints<'c> {
                 model_filter: row.model_filter,
                 auth_header_name: row.auth_header_name,
                 auth_header_prefix: row.auth_header_prefix,
+                tls_ca_pem: row.tls_ca_pem,
+                tls_skip_verify: row.tls_skip_verify,
+                tls_min_version: row.tls_min_version,
+                sync_interval_seconds: row.sync_interval_seconds,
+                last_sync_at: row.last_sync_at,
+                last_sync_error: row.last_sync_error,
+                enabled: row.enabled,
                 created_by: row.created_by,
                 created_at: row.created_at,
                 updated_at: row.updated_at,
@@ -172,6 +223,11 @@ impl<'c> Repository for InferenceEndpoints<'c> {
                 END,
                 auth_header_name = COALESCE($7, auth_header_name),
                 auth_header_prefix = COALESCE($8, auth_header_prefix),
+                tls_ca_pem = CASE WHEN $9 THEN $10 ELSE tls_ca_pem END,
+                tls_skip_verify = COALESCE($11, tls_skip_verify),
+                tls_min_version = CASE WHEN $12 THEN $13 ELSE tls_min_version END,
+                sync_interval_seconds = CASE WHEN $14 THEN $15 ELSE sync_interval_seconds END,
+                enabled = COALESCE($16, enabled),
                 updated_at = NOW()
             WHERE id = $1
             RETURNING *
@@ -180,10 +236,23 @@ impl<'c> Repository for InferenceEndpoints<'c> {
             request.name,
             request.description.as_deref(),
             request.url.as_ref().map(|u| u.as_str()),
-            request.api_key.as_ref().and_then(|opt| opt.as_deref()),
+            request
+                .api_key
+                .as_ref()
+                .and_then(|opt| opt.as_deref())
+                .map(encrypt_secret)
+                .transpose()?,
             request.model_filter.as_ref().and_then(|opt| opt.as_ref().map(|v| v.as_slice())),
             request.auth_header_name,
-            request.auth_header_prefix
+            request.auth_header_prefix,
+            request.tls_ca_pem.is_some(),
+            request.tls_ca_pem.clone().flatten(),
+            request.tls_skip_verify,
+            request.tls_min_version.is_some(),
+            request.tls_min_version.clone().flatten(),
+            request.sync_interval_seconds.is_some(),
+            request.sync_interval_seconds.flatten(),
+            request.enabled
         )
         .fetch_optional(&mut *self.db)
         .await?
@@ -193,14 +262,20 @@ impl<'c> Repository for InferenceEndpoints<'c> {
     }
 
     async fn list(&mut self, filter: &Self::Filter) -> Result<Vec<Self::Response>> {
-        let endpoints = sqlx::query_as!(
-            InferenceEndpoint,
-            "SELECT * FROM inference_endpoints ORDER BY created_at DESC LIMIT $1 OFFSET $2",
-            filter.limit,
-            filter.skip
-        )
-        .fetch_all(&mut *self.db)
-        .await?;
+        let mut query = QueryBuilder::new("SELECT * FROM inference_endpoints WHERE TRUE");
+
+        if let Some(ref cursor) = filter.after {
+            push_after_cursor(&mut query, cursor, "id");
+        }
+
+        // Sort columns come from a fixed list, and the ID breaks ties so pages are stable
+        push_order_by(&mut query, filter.sort, "id");
+        query.push(" LIMIT ");
+        query.push_bind(filter.limit);
+        query.push(" OFFSET ");
+        query.push_bind(filter.skip);
+
+        let endpoints = query.build_query_as::<InferenceEndpoint>().fetch_all(&mut *self.db).await?;
 
         endpoints.into_iter().map(|e| Ok(e.try_into()?)).collect()
     }
@@ -216,6 +291,467 @@ impl<'c> InferenceEndpoints<'c> {
         // Use a deterministic UUID for tests
         uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap()
     }
+
+    /// Record the outcome of a synchronization of an endpoint, `error` being why it failed
+    pub async fn record_sync(&mut self, endpoint_id: InferenceEndpointId, error: Option<&str>) -> Result<()> {
+        sqlx::query!(
+            "UPDATE inference_endpoints SET last_sync_at = NOW(), last_sync_error = $2 WHERE id = $1",
+            endpoint_id,
+            error
+        )
+        .execute(&mut *self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Enabled endpoints with a sync interval that haven't been synchronized within it, least
+    /// recently synchronized first
+    pub async fn list_due_for_sync(&mut self) -> Result<Vec<InferenceEndpointId>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM inference_endpoints
+            WHERE enabled AND sync_interval_seconds IS NOT NULL
+              AND (last_sync_at IS NULL OR last_sync_at <= NOW() - make_interval(secs => sync_interval_seconds))
+            ORDER BY last_sync_at ASC NULLS FIRST
+            "#
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(ids)
+    }
+
+    /// The current configuration of an endpoint, or `None` if it doesn't exist
+    pub async fn get_config(&mut self, endpoint_id: InferenceEndpointId) -> Result<Option<EndpointConfig>> {
+        let Some(endpoint) = self.get_by_id(endpoint_id).await? else {
+            return Ok(None);
+        };
+        let aliases = sqlx::query!(
+            "SELECT model_name, alias FROM deployed_models WHERE hosted_on = $1 AND NOT deleted",
+            endpoint_id
+        )
+        .fetch_all(&mut *self.db)
+        .await?
+        .into_iter()
+        .map(|row| (row.model_name, row.alias))
+        .collect();
+        Ok(Some(EndpointConfig::new(&endpoint, aliases)))
+    }
+
+    /// Record the current configuration of an endpoint as a new version, if it changed since the
+    /// latest one. Returns the new version, if any.
+    pub async fn record_version(
+        &mut self,
+        endpoint_id: InferenceEndpointId,
+        changed_by: UserId,
+    ) -> Result<Option<EndpointConfigVersionDBResponse>> {
+        let Some(config) = self.get_config(endpoint_id).await? else {
+            return Ok(None);
+        };
+
+        let latest = self.list_versions(endpoint_id, 1).await?.into_iter().next();
+        if latest.is_some_and(|latest| latest.config.0 == config) {
+            return Ok(None);
+        }
+
+        let version = sqlx::query_as!(
+            EndpointConfigVersionDBResponse,
+            r#"
+            INSERT INTO endpoint_config_versions (endpoint_id, version, config, changed_by)
+            VALUES ($1, (SELECT COALESCE(MAX(version), 0) + 1 FROM endpoint_config_versions WHERE endpoint_id = $1), $2, $3)
+            RETURNING endpoint_id, version, config as "config: sqlx::types::Json<EndpointConfig>", changed_by, created_at
+            "#,
+            endpoint_id,
+            sqlx::types::Json(&config) as _,
+            changed_by
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(Some(version))
+    }
+
+    /// The latest `limit` versions of an endpoint's configuration, newest first
+    pub async fn list_versions(&mut self, endpoint_id: InferenceEndpointId, limit: i64) -> Result<Vec<EndpointConfigVersionDBResponse>> {
+        let versions = sqlx::query_as!(
+            EndpointConfigVersionDBResponse,
+            r#"
+            SELECT endpoint_id, version, config as "config: sqlx::types::Json<EndpointConfig>", changed_by, created_at
+            FROM endpoint_config_versions
+            WHERE endpoint_id = $1
+            ORDER BY version DESC
+            LIMIT $2
+            "#,
+            endpoint_id,
+            limit
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(versions)
+    }
+
+    /// Get a version of an endpoint's configuration
+    pub async fn get_version(&mut self, endpoint_id: InferenceEndpointId, version: i32) -> Result<Option<EndpointConfigVersionDBResponse>> {
+        let version = sqlx::query_as!(
+            EndpointConfigVersionDBResponse,
+            r#"
+            SELECT endpoint_id, version, config as "config: sqlx::types::Json<EndpointConfig>", changed_by, created_at
+            FROM endpoint_config_versions
+            WHERE endpoint_id = $1 AND version = $2
+            "#,
+            endpoint_id,
+            version
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(version)
+    }
+
+    /// Set every versioned setting of an endpoint to those of `config`. Aliases are left to the
+    /// caller, as they belong to the endpoint's deployments.
+    pub async fn restore_config(
+        &mut self,
+        endpoint_id: InferenceEndpointId,
+        config: &EndpointConfig,
+    ) -> Result<InferenceEndpointDBResponse> {
+        let endpoint = sqlx::query_as!(
+            InferenceEndpoint,
+            r#"
+            UPDATE inference_endpoints SET
+                name = $2,
+                description = $3,
+                url = $4,
+                model_filter = $5,
+                auth_header_name = $6,
+                auth_header_prefix = $7,
+                tls_ca_pem = $8,
+                tls_skip_verify = $9,
+                tls_min_version = $10,
+                sync_interval_seconds = $11,
+                enabled = $12,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+            endpoint_id,
+            config.name,
+            config.description,
+            config.url,
+            config.model_filter.as_deref(),
+            config.auth_header_name,
+            config.auth_header_prefix,
+            config.tls_ca_pem,
+            config.tls_skip_verify,
+            config.tls_min_version,
+            config.sync_interval_seconds,
+            config.enabled
+        )
+        .fetch_optional(&mut *self.db)
+        .await?
+        .ok_or(DbError::NotFound)?;
+
+        Ok(endpoint.try_into()?)
+    }
+
+    /// Get the redaction policy of an endpoint, if one has been set
+    pub async fn get_redaction_policy(&mut self, endpoint_id: InferenceEndpointId) -> Result<Option<EndpointRedactionDBResponse>> {
+        let policy = sqlx::query_as!(
+            EndpointRedactionDBResponse,
+            "SELECT endpoint_id, entities, patterns, log_original FROM endpoint_redaction_policies WHERE endpoint_id = $1",
+            endpoint_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(policy)
+    }
+
+    /// Get the redaction policies of several endpoints, keyed by endpoint
+    pub async fn get_redaction_policies_bulk(
+        &mut self,
+        endpoint_ids: &[InferenceEndpointId],
+    ) -> Result<std::collections::HashMap<InferenceEndpointId, EndpointRedactionDBResponse>> {
+        if endpoint_ids.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+
+        let policies = sqlx::query_as!(
+            EndpointRedactionDBResponse,
+            "SELECT endpoint_id, entities, patterns, log_original FROM endpoint_redaction_policies WHERE endpoint_id = ANY($1)",
+            endpoint_ids
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(policies.into_iter().map(|p| (p.endpoint_id, p)).collect())
+    }
+
+    /// Set the redaction policy of an endpoint, replacing any existing one
+    pub async fn set_redaction_policy(
+        &mut self,
+        endpoint_id: InferenceEndpointId,
+        policy: &EndpointRedactionUpdateDBRequest,
+    ) -> Result<EndpointRedactionDBResponse> {
+        let policy = sqlx::query_as!(
+            EndpointRedactionDBResponse,
+            r#"
+            INSERT INTO endpoint_redaction_policies (endpoint_id, entities, patterns, log_original)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (endpoint_id) DO UPDATE SET
+                entities = EXCLUDED.entities,
+                patterns = EXCLUDED.patterns,
+                log_original = EXCLUDED.log_original,
+                updated_at = NOW()
+            RETURNING endpoint_id, entities, patterns, log_original
+            "#,
+            endpoint_id,
+            &policy.entities,
+            &policy.patterns,
+            policy.log_original
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(policy)
+    }
+
+    /// Get the header rules of an endpoint, if they have been set
+    pub async fn get_header_rules(&mut self, endpoint_id: InferenceEndpointId) -> Result<Option<EndpointHeaderRulesDBResponse>> {
+        let rules = sqlx::query_as!(
+            EndpointHeaderRulesDBResponse,
+            "SELECT endpoint_id, inject, passthrough FROM endpoint_header_rules WHERE endpoint_id = $1",
+            endpoint_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(rules)
+    }
+
+    /// Get the header rules of several endpoints, keyed by endpoint
+    pub async fn get_header_rules_bulk(
+        &mut self,
+        endpoint_ids: &[InferenceEndpointId],
+    ) -> Result<std::collections::HashMap<InferenceEndpointId, EndpointHeaderRulesDBResponse>> {
+        if endpoint_ids.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+
+        let rules = sqlx::query_as!(
+            EndpointHeaderRulesDBResponse,
+            "SELECT endpoint_id, inject, passthrough FROM endpoint_header_rules WHERE endpoint_id = ANY($1)",
+            endpoint_ids
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(rules.into_iter().map(|r| (r.endpoint_id, r)).collect())
+    }
+
+    /// Set the header rules of an endpoint, replacing any existing ones
+    pub async fn set_header_rules(
+        &mut self,
+        endpoint_id: InferenceEndpointId,
+        rules: &EndpointHeaderRulesUpdateDBRequest,
+    ) -> Result<EndpointHeaderRulesDBResponse> {
+        let rules = sqlx::query_as!(
+            EndpointHeaderRulesDBResponse,
+            r#"
+            INSERT INTO endpoint_header_rules (endpoint_id, inject, passthrough)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (endpoint_id) DO UPDATE SET
+                inject = EXCLUDED.inject,
+                passthrough = EXCLUDED.passthrough,
+                updated_at = NOW()
+            RETURNING endpoint_id, inject, passthrough
+            "#,
+            endpoint_id,
+            &rules.inject,
+            rules.passthrough.as_deref()
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(rules)
+    }
+
+    /// Get the upstream rate limits of an endpoint, if they have been set
+    pub async fn get_rate_limits(&mut self, endpoint_id: InferenceEndpointId) -> Result<Option<EndpointRateLimitsDBResponse>> {
+        let limits = sqlx::query_as!(
+            EndpointRateLimitsDBResponse,
+            "SELECT endpoint_id, requests_per_second, burst_size, max_concurrent FROM endpoint_rate_limits WHERE endpoint_id = $1",
+            endpoint_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(limits)
+    }
+
+    /// Get the upstream rate limits of several endpoints, keyed by endpoint
+    pub async fn get_rate_limits_bulk(
+        &mut self,
+        endpoint_ids: &[InferenceEndpointId],
+    ) -> Result<std::collections::HashMap<InferenceEndpointId, EndpointRateLimitsDBResponse>> {
+        if endpoint_ids.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+
+        let limits = sqlx::query_as!(
+            EndpointRateLimitsDBResponse,
+            "SELECT endpoint_id, requests_per_second, burst_size, max_concurrent FROM endpoint_rate_limits WHERE endpoint_id = ANY($1)",
+            endpoint_ids
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(limits.into_iter().map(|l| (l.endpoint_id, l)).collect())
+    }
+
+    /// Set the upstream rate limits of an endpoint, replacing any existing ones
+    pub async fn set_rate_limits(
+        &mut self,
+        endpoint_id: InferenceEndpointId,
+        limits: &EndpointRateLimitsUpdateDBRequest,
+    ) -> Result<EndpointRateLimitsDBResponse> {
+        let limits = sqlx::query_as!(
+            EndpointRateLimitsDBResponse,
+            r#"
+            INSERT INTO endpoint_rate_limits (endpoint_id, requests_per_second, burst_size, max_concurrent)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (endpoint_id) DO UPDATE SET
+                requests_per_second = EXCLUDED.requests_per_second,
+                burst_size = EXCLUDED.burst_size,
+                max_concurrent = EXCLUDED.max_concurrent,
+                updated_at = NOW()
+            RETURNING endpoint_id, requests_per_second, burst_size, max_concurrent
+            "#,
+            endpoint_id,
+            limits.requests_per_second,
+            limits.burst_size,
+            limits.max_concurrent
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(limits)
+    }
+
+    /// Get the sync strategy of an endpoint, if one has been set
+    pub async fn get_sync_strategy(&mut self, endpoint_id: InferenceEndpointId) -> Result<Option<EndpointSyncStrategyDBResponse>> {
+        let strategy = sqlx::query_as!(
+            EndpointSyncStrategyDBResponse,
+            "SELECT removed_models, new_models FROM endpoint_sync_strategies WHERE endpoint_id = $1",
+            endpoint_id
+        )
+        .fetch_optional(&mut *self.db)
+        .await?;
+        Ok(strategy)
+    }
+
+    /// Set the sync strategy of an endpoint, replacing any existing one
+    pub async fn set_sync_strategy(
+        &mut self,
+        endpoint_id: InferenceEndpointId,
+        strategy: &EndpointSyncStrategyUpdateDBRequest,
+    ) -> Result<EndpointSyncStrategyDBResponse> {
+        let strategy = sqlx::query_as!(
+            EndpointSyncStrategyDBResponse,
+            r#"
+            INSERT INTO endpoint_sync_strategies (endpoint_id, removed_models, new_models)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (endpoint_id) DO UPDATE SET
+                removed_models = EXCLUDED.removed_models,
+                new_models = EXCLUDED.new_models,
+                updated_at = NOW()
+            RETURNING removed_models, new_models
+            "#,
+            endpoint_id,
+            strategy.removed_models,
+            strategy.new_models
+        )
+        .fetch_one(&mut *self.db)
+        .await?;
+        Ok(strategy)
+    }
+
+    /// List the models of an endpoint awaiting approval, and with `include_rejected` those rejected
+    pub async fn list_pending_models(
+        &mut self,
+        endpoint_id: InferenceEndpointId,
+        include_rejected: bool,
+    ) -> Result<Vec<EndpointPendingModelDBResponse>> {
+        let models = sqlx::query_as!(
+            EndpointPendingModelDBResponse,
+            r#"
+            SELECT model_name, status, discovered_at, rejected_at, rejected_by
+            FROM endpoint_pending_models
+            WHERE endpoint_id = $1 AND (status = 'pending' OR $2)
+            ORDER BY discovered_at, model_name
+            "#,
+            endpoint_id,
+            include_rejected
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(models)
+    }
+
+    /// Record the models a sync found awaiting approval. Models no longer awaiting approval (gone
+    /// upstream, or deployed meanwhile) leave the queue; rejected models stay rejected. Returns
+    /// the names of the models awaiting approval.
+    pub async fn sync_pending_models(&mut self, endpoint_id: InferenceEndpointId, model_names: &[String]) -> Result<Vec<String>> {
+        sqlx::query!(
+            "DELETE FROM endpoint_pending_models WHERE endpoint_id = $1 AND status = 'pending' AND NOT (model_name = ANY($2))",
+            endpoint_id,
+            model_names
+        )
+        .execute(&mut *self.db)
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO endpoint_pending_models (endpoint_id, model_name)
+            SELECT $1, model_name FROM UNNEST($2::text[]) AS model_name
+            ON CONFLICT (endpoint_id, model_name) DO NOTHING
+            "#,
+            endpoint_id,
+            model_names
+        )
+        .execute(&mut *self.db)
+        .await?;
+        let pending = sqlx::query_scalar!(
+            "SELECT model_name FROM endpoint_pending_models WHERE endpoint_id = $1 AND status = 'pending' ORDER BY discovered_at, model_name",
+            endpoint_id
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(pending)
+    }
+
+    /// Take models out of the queue to deploy them, returning the names that were queued
+    pub async fn approve_pending_models(&mut self, endpoint_id: InferenceEndpointId, model_names: &[String]) -> Result<Vec<String>> {
+        let approved = sqlx::query_scalar!(
+            "DELETE FROM endpoint_pending_models WHERE endpoint_id = $1 AND model_name = ANY($2) RETURNING model_name",
+            endpoint_id,
+            model_names
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(approved)
+    }
+
+    /// Reject queued models, so later syncs don't queue them again
+    pub async fn reject_pending_models(
+        &mut self,
+        endpoint_id: InferenceEndpointId,
+        model_names: &[String],
+        rejected_by: UserId,
+    ) -> Result<Vec<EndpointPendingModelDBResponse>> {
+        let rejected = sqlx::query_as!(
+            EndpointPendingModelDBResponse,
+            r#"
+            UPDATE endpoint_pending_models
+            SET status = 'rejected', rejected_at = NOW(), rejected_by = $3
+            WHERE endpoint_id = $1 AND model_name = ANY($2)
+            RETURNING model_name, status, discovered_at, rejected_at, rejected_by
+            "#,
+            endpoint_id,
+            model_names,
+            rejected_by
+        )
+        .fetch_all(&mut *self.db)
+        .await?;
+        Ok(rejected)
+    }
 }
 
 #[cfg(test)]
@@ -242,6 +778,7 @@ mod tests {
             display_name: None,
             avatar_url: None,
             roles: vec![Role::StandardUser],
+            attributes: Default::default(),
         });
         user_repo.create(&user_create).await.unwrap().into()
     }
@@ -255,6 +792,10 @@ mod tests {
             model_filter: Some(vec!["gpt-4".to_string(), "gpt-3.5-turbo".to_string()]),
             auth_header_name: None,
             auth_header_prefix: None,
+            tls_ca_pem: None,
+            tls_skip_verify: false,
+            tls_min_version: None,
+            sync_interval_seconds: None,
             created_by,
         }
     }
@@ -404,6 +945,11 @@ mod tests {
             model_filter: Some(Some(vec!["claude-3".to_string(), "gpt-4-turbo".to_string()])),
             auth_header_name: None,
             auth_header_prefix: None,
+            tls_ca_pem: None,
+            tls_skip_verify: None,
+            tls_min_version: None,
+            sync_interval_seconds: None,
+            enabled: None,
         };
 
         // Apply update
@@ -448,6 +994,11 @@ mod tests {
             model_filter: None,
             auth_header_name: None,
             auth_header_prefix: None,
+            tls_ca_pem: None,
+            tls_skip_verify: None,
+            tls_min_version: None,
+            sync_interval_seconds: None,
+            enabled: None,
         };
 
         // Apply update
@@ -466,6 +1017,47 @@ mod tests {
         assert!(updated_endpoint.updated_at > created_endpoint.updated_at);
     }
 
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_update_tls_settings(pool: PgPool) {
+        let user = create_test_user(&pool).await;
+        let mut conn = pool.acquire().await.unwrap();
+        let mut repo = InferenceEndpoints::new(&mut conn);
+
+        let mut endpoint_request = create_test_endpoint_request(user.id, "tls-endpoint");
+        endpoint_request.tls_ca_pem = Some("-----BEGIN CERTIFICATE-----".to_string());
+        endpoint_request.tls_skip_verify = true;
+        let created = repo.create(&endpoint_request).await.unwrap();
+        assert_eq!(created.tls_ca_pem.as_deref(), Some("-----BEGIN CERTIFICATE-----"));
+        assert!(created.tls_skip_verify);
+        assert_eq!(created.tls_min_version, None);
+
+        // Unset fields are kept, and the CA bundle can be cleared
+        let updated = repo
+            .update(
+                created.id,
+                &InferenceEndpointUpdateDBRequest {
+                    name: None,
+                    description: None,
+                    url: None,
+                    api_key: None,
+                    model_filter: None,
+                    auth_header_name: None,
+                    auth_header_prefix: None,
+                    tls_ca_pem: Some(None),
+                    tls_skip_verify: None,
+                    tls_min_version: Some(Some("1.3".to_string())),
+                    sync_interval_seconds: None,
+                    enabled: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.tls_ca_pem, None);
+        assert!(updated.tls_skip_verify);
+        assert_eq!(updated.tls_min_version.as_deref(), Some("1.3"));
+    }
+
     /// Mock function that simulates COALESCE behavior for updates
     fn mock_coalesce_update(
         update_request: InferenceEndpointUpdateDBRequest,
@@ -512,6 +1104,13 @@ mod tests {
             model_filter: Some(vec!["gpt-3.5".to_string()]),
             auth_header_name: "Authorization".to_string(),
             auth_header_prefix: "Bearer ".to_string(),
+            tls_ca_pem: None,
+            tls_skip_verify: false,
+            tls_min_version: None,
+            sync_interval_seconds: None,
+            last_sync_at: None,
+            last_sync_error: None,
+            enabled: true,
             created_by: uuid::Uuid::new_v4(),
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
@@ -526,6 +1125,11 @@ mod tests {
             model_filter: Some(Some(vec!["claude-3".to_string(), "gpt-4".to_string()])),
             auth_header_name: None,
             auth_header_prefix: None,
+            tls_ca_pem: None,
+            tls_skip_verify: None,
+            tls_min_version: None,
+            sync_interval_seconds: None,
+            enabled: None,
         };
 
         let updated_response = mock_coalesce_update(update_request, original_response.clone());
@@ -561,6 +1165,13 @@ mod tests {
             model_filter: Some(vec!["gpt-3.5".to_string()]),
             auth_header_name: "Authorization".to_string(),
             auth_header_prefix: "Bearer ".to_string(),
+            tls_ca_pem: None,
+            tls_skip_verify: false,
+            tls_min_version: None,
+            sync_interval_seconds: None,
+            last_sync_at: None,
+            last_sync_error: None,
+            enabled: true,
             created_by: uuid::Uuid::new_v4(),
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now() - chrono::Duration::seconds(1),
@@ -575,6 +1186,11 @@ mod tests {
             model_filter: None,
             auth_header_name: None,
             auth_header_prefix: None,
+            tls_ca_pem: None,
+            tls_skip_verify: None,
+            tls_min_version: None,
+            sync_interval_seconds: None,
+            enabled: None,
         };
 
         let updated_response = mock_coalesce_update(update_request, original_response.clone());
@@ -608,6 +1224,11 @@ mod tests {
             model_filter: None,
             auth_header_name: None,
             auth_header_prefix: None,
+            tls_ca_pem: None,
+            tls_skip_verify: None,
+            tls_min_version: None,
+            sync_interval_seconds: None,
+            enabled: None,
         };
 
         let result = repo.update(fake_id, &update_request).await;
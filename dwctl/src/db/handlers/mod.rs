@@ -3,6 +3,7 @@ pub mod api_keys;
 pub mod deployments;
 pub mod groups;
 pub mod inference_endpoints;
+pub mod pagination;
 pub mod password_reset_tokens;
 pub mod repository;
 pub mod users;
@@ -0,0 +1,53 @@
+//! Keyset pagination over lists sorted by a column, with the row ID as tiebreaker.
+//!
+//! Lists are ordered by `<column> <order> NULLS LAST, <id> <order>`, and a page after a cursor
+//! holds the rows that come after the cursor's position in that order. Unlike offsets, cursors
+//! keep their place when rows are inserted or deleted before them.
+
+use sqlx::{Encode, Postgres, QueryBuilder, Type};
+
+use crate::api::models::{
+    pagination::ListCursor,
+    sorting::{SortField, SortOrder},
+};
+
+/// Append the ordering of a list sorted by `field`, with the `id_column` as tiebreaker
+pub fn push_order_by(query: &mut QueryBuilder<'_, Postgres>, (field, order): (impl SortField, SortOrder), id_column: &str) {
+    query.push(format!(
+        " ORDER BY {} {} NULLS LAST, {id_column} {}",
+        field.column(),
+        order.as_sql(),
+        order.as_sql()
+    ));
+}
+
+/// Append a condition matching the rows after `cursor`, to a query ending in a `WHERE` clause
+pub fn push_after_cursor<'a, F, K>(query: &mut QueryBuilder<'a, Postgres>, cursor: &ListCursor<F, K>, id_column: &str)
+where
+    F: SortField,
+    K: Clone + Send + Encode<'a, Postgres> + Type<Postgres> + 'a,
+{
+    let column = cursor.sort.column();
+    let column_type = cursor.sort.column_type();
+    let after = match cursor.order {
+        SortOrder::Asc => ">",
+        SortOrder::Desc => "<",
+    };
+
+    // Nulls sort last in either direction, so they come after every value
+    match &cursor.value {
+        Some(value) => {
+            query.push(format!(" AND ({column} {after} CAST("));
+            query.push_bind(value.clone());
+            query.push(format!(" AS {column_type}) OR ({column} = CAST("));
+            query.push_bind(value.clone());
+            query.push(format!(" AS {column_type}) AND {id_column} {after} "));
+            query.push_bind(cursor.id.clone());
+            query.push(format!(") OR {column} IS NULL)"));
+        }
+        None => {
+            query.push(format!(" AND {column} IS NULL AND {id_column} {after} "));
+            query.push_bind(cursor.id.clone());
+        }
+    }
+}
@@ -1,27 +1,94 @@
-use crate::types::UserId;
+use crate::types::{DeploymentId, GroupId, UserId};
 use crate::{
-    api::models::users::Role,
+    api::models::{
+        pagination::ListCursor,
+        sorting::SortOrder,
+        users::{Role, UserSortField},
+    },
     db::{
         errors::{DbError, Result},
-        handlers::repository::Repository,
-        models::users::{UserCreateDBRequest, UserDBResponse, UserUpdateDBRequest},
+        handlers::{
+            pagination::{push_after_cursor, push_order_by},
+            repository::Repository,
+        },
+        models::users::{UserCreateDBRequest, UserDBResponse, UserPurgeDBResponse, UserUpdateDBRequest},
     },
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{Connection, FromRow, PgConnection};
+use sqlx::{query_builder::QueryBuilder, types::Json, Connection, FromRow, PgConnection};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
+/// Request log tables, in the `outlet` schema, holding the requests a user made. The archive
+/// tables only exist once the retention policy has archived rows.
+const REQUEST_LOG_TABLES: [&str; 4] = ["http_requests", "http_responses", "http_requests_archive", "http_responses_archive"];
+
 /// Filter for listing users
 #[derive(Debug, Clone)]
 pub struct UserFilter {
     pub skip: i64,
     pub limit: i64,
+    pub search: Option<String>, // Case-insensitive substring of the email, username or display name
+    pub role: Option<Role>,
+    pub group_id: Option<GroupId>, // Direct members of the group only
+    pub is_admin: Option<bool>,
+    pub attributes: Option<BTreeMap<String, String>>, // Users with all of these attributes
+    pub sort: (UserSortField, SortOrder),
+    pub after: Option<ListCursor<UserSortField>>,
 }
 
 impl UserFilter {
     pub fn new(skip: i64, limit: i64) -> Self {
-        Self { skip, limit }
+        Self {
+            skip,
+            limit,
+            search: None,
+            role: None,
+            group_id: None,
+            is_admin: None,
+            attributes: None,
+            sort: (UserSortField::CreatedAt, SortOrder::Desc), // Default: newest first
+            after: None,
+        }
+    }
+
+    pub fn with_search(mut self, search: String) -> Self {
+        self.search = Some(search);
+        self
+    }
+
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn with_group(mut self, group_id: GroupId) -> Self {
+        self.group_id = Some(group_id);
+        self
+    }
+
+    pub fn with_is_admin(mut self, is_admin: bool) -> Self {
+        self.is_admin = Some(is_admin);
+        self
+    }
+
+    pub fn with_attributes(mut self, attributes: BTreeMap<String, String>) -> Self {
+        self.attributes = Some(attributes);
+        self
+    }
+
+    pub fn with_sort(mut self, field: UserSortField, order: SortOrder) -> Self {
+        self.sort = (field, order);
+        self
+    }
+
+    /// Start after a cursor instead of skipping, in the sort the cursor was issued for
+    pub fn with_cursor(mut self, cursor: ListCursor<UserSortField>) -> Self {
+        self.skip = 0;
+        self.sort = (cursor.sort, cursor.order);
+        self.after = Some(cursor);
+        self
     }
 }
 
@@ -39,6 +106,8 @@ struct User {
     pub last_login: Option<DateTime<Utc>>,
     pub is_admin: bool,
     pub password_hash: Option<String>,
+    pub default_model_id: Option<DeploymentId>,
+    pub attributes: serde_json::Value,
 }
 
 pub struct Users<'c> {
@@ -55,10 +124,14 @@ impl From<(Vec<Role>, User)> for UserDBResponse {
             avatar_url: user.avatar_url,
             created_at: user.created_at,
             updated_at: user.updated_at,
+            last_login: user.last_login,
             auth_source: user.auth_source,
             is_admin: user.is_admin,
             roles,
             password_hash: user.password_hash,
+            default_model_id: user.default_model_id,
+            // The column is constrained to an object of strings
+            attributes: serde_json::from_value(user.attributes).unwrap_or_default(),
         }
     }
 }
@@ -80,8 +153,8 @@ impl<'c> Repository for Users<'c> {
         let user = sqlx::query_as!(
             User,
             r#"
-            INSERT INTO users (id, username, email, display_name, avatar_url, auth_source, is_admin, password_hash)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            INSERT INTO users (id, username, email, display_name, avatar_url, auth_source, is_admin, password_hash, attributes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
             RETURNING *
             "#,
             user_id,
@@ -91,7 +164,8 @@ impl<'c> Repository for Users<'c> {
             request.avatar_url,
             request.auth_source,
             request.is_admin,
-            request.password_hash
+            request.password_hash,
+            Json(&request.attributes) as _
         )
         .fetch_one(&mut *tx)
         .await?;
@@ -167,14 +241,54 @@ impl<'c> Repository for Users<'c> {
         Ok(result)
     }
     async fn list(&mut self, filter: &Self::Filter) -> Result<Vec<Self::Response>> {
-        let users = sqlx::query_as!(
-            User,
-            "SELECT * FROM users WHERE id != '00000000-0000-0000-0000-000000000000' ORDER BY created_at DESC LIMIT $1 OFFSET $2",
-            filter.limit,
-            filter.skip
-        )
-        .fetch_all(&mut *self.db)
-        .await?;
+        let mut query = QueryBuilder::new("SELECT * FROM users WHERE id != '00000000-0000-0000-0000-000000000000'");
+
+        if let Some(ref search) = filter.search {
+            // Escape LIKE wildcards so the search term matches literally
+            let pattern = format!("%{}%", search.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+            query.push(" AND (email ILIKE ");
+            query.push_bind(pattern.clone());
+            query.push(" OR username ILIKE ");
+            query.push_bind(pattern.clone());
+            query.push(" OR display_name ILIKE ");
+            query.push_bind(pattern);
+            query.push(")");
+        }
+
+        if let Some(ref role) = filter.role {
+            query.push(" AND id IN (SELECT user_id FROM user_roles WHERE role = ");
+            query.push_bind(role.clone());
+            query.push(")");
+        }
+
+        if let Some(group_id) = filter.group_id {
+            query.push(" AND id IN (SELECT user_id FROM user_groups WHERE group_id = ");
+            query.push_bind(group_id);
+            query.push(")");
+        }
+
+        if let Some(is_admin) = filter.is_admin {
+            query.push(" AND is_admin = ");
+            query.push_bind(is_admin);
+        }
+
+        if let Some(ref attributes) = filter.attributes {
+            query.push(" AND attributes @> ");
+            query.push_bind(Json(attributes));
+        }
+
+        if let Some(ref cursor) = filter.after {
+            push_after_cursor(&mut query, cursor, "id");
+        }
+
+        // Sort columns come from a fixed list, and the ID breaks ties so pages are stable
+        push_order_by(&mut query, filter.sort, "id");
+        query.push(" LIMIT ");
+        query.push_bind(filter.limit);
+        query.push(" OFFSET ");
+        query.push_bind(filter.skip);
+
+        let users = query.build_query_as::<User>().fetch_all(&mut *self.db).await?;
 
         let mut tx = self.db.begin().await?;
 
@@ -214,6 +328,11 @@ impl<'c> Repository for Users<'c> {
                 display_name = COALESCE($2, display_name),
                 avatar_url = COALESCE($3, avatar_url),
                 password_hash = COALESCE($4, password_hash),
+                default_model_id = CASE
+                    WHEN $5 THEN $6
+                    ELSE default_model_id
+                END,
+                attributes = COALESCE($7, attributes),
                 updated_at = NOW()
             WHERE id = $1
             RETURNING *
@@ -222,6 +341,9 @@ impl<'c> Repository for Users<'c> {
                 request.display_name,
                 request.avatar_url,
                 request.password_hash,
+                request.default_model_id.is_some(),
+                request.default_model_id.flatten(),
+                request.attributes.as_ref().map(Json) as _,
             )
             .fetch_optional(&mut *tx)
             .await?
@@ -267,6 +389,105 @@ impl<'c> Users<'c> {
         Self { db }
     }
 
+    /// Delete a user together with their personal data: their API keys, the request log entries
+    /// of the requests they made, and the user details on the analytics of those requests. The
+    /// analytics rows themselves are kept, so token usage and spend totals still add up.
+    ///
+    /// Returns None if the user doesn't exist. Bodies kept in object storage and requests exported
+    /// to request log archives aren't touched; their keys are returned for the caller to delete or
+    /// rewrite once the purge has committed.
+    pub async fn purge(&mut self, id: UserId) -> Result<Option<UserPurgeDBResponse>> {
+        let mut tx = self.db.begin().await?;
+
+        let Some(email) = sqlx::query_scalar!("SELECT email FROM users WHERE id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *tx)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let mut purged = UserPurgeDBResponse {
+            api_keys_deleted: sqlx::query!("DELETE FROM api_keys WHERE user_id = $1", id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected(),
+            ..Default::default()
+        };
+
+        // The request log tables aren't managed by our migrations, so they're queried dynamically
+        for table in REQUEST_LOG_TABLES {
+            let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+                .bind(format!("outlet.{table}"))
+                .fetch_one(&mut *tx)
+                .await?;
+            if !exists {
+                continue;
+            }
+
+            let stored_body_keys: Vec<Option<String>> = sqlx::query_scalar(&format!(
+                "DELETE FROM outlet.{table} t
+                 USING http_analytics a
+                 WHERE (a.user_id = $1 OR a.user_email = $2)
+                   AND t.instance_id = a.instance_id AND t.correlation_id = a.correlation_id
+                 RETURNING t.body -> 'stored_body' ->> 'key'"
+            ))
+            .bind(id)
+            .bind(&email)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            let rows = stored_body_keys.len() as u64;
+            if table.starts_with("http_requests") {
+                purged.requests_deleted += rows;
+            } else {
+                purged.responses_deleted += rows;
+            }
+            purged.stored_body_keys.extend(stored_body_keys.into_iter().flatten());
+        }
+
+        // Exported requests are only tied to the user through their analytics, so they're found
+        // before those are anonymized. Archives cover the days of the logged requests' timestamps,
+        // which can be a little off the analytics ones, hence the margin.
+        let archived = sqlx::query!(
+            r#"
+            SELECT ar.object_key, a.instance_id, a.correlation_id
+            FROM http_analytics a
+            JOIN request_log_archives ar
+                ON a.timestamp >= ar.range_start - INTERVAL '1 hour' AND a.timestamp < ar.range_end + INTERVAL '1 hour'
+            WHERE a.user_id = $1 OR a.user_email = $2
+            "#,
+            id,
+            email
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for row in archived {
+            purged
+                .archived_requests
+                .entry(row.object_key)
+                .or_default()
+                .insert((row.instance_id, row.correlation_id));
+        }
+
+        purged.analytics_anonymized = sqlx::query!(
+            r#"
+            UPDATE http_analytics
+            SET user_id = NULL, user_email = NULL, conversation_id = NULL, tags = NULL
+            WHERE user_id = $1 OR user_email = $2
+            "#,
+            id,
+            email
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        sqlx::query!("DELETE FROM users WHERE id = $1", id).execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(Some(purged))
+    }
+
     pub async fn get_user_by_email(&mut self, email: &str) -> Result<Option<UserDBResponse>> {
         let user = sqlx::query_as!(
             User,
@@ -310,6 +531,7 @@ mod tests {
             display_name: Some("Test User".to_string()),
             avatar_url: None,
             roles: vec![Role::StandardUser],
+            attributes: Default::default(),
         });
 
         let result = repo.create(&user_create).await;
@@ -334,6 +556,7 @@ mod tests {
             display_name: None,
             avatar_url: None,
             roles: vec![Role::StandardUser],
+            attributes: Default::default(),
         });
 
         let created_user = repo.create(&user_create).await.unwrap();
@@ -371,6 +594,7 @@ mod tests {
             display_name: None,
             avatar_url: None,
             roles: vec![Role::StandardUser, Role::PlatformManager],
+            attributes: Default::default(),
         });
 
         let created_user = repo.create(&user_create).await.unwrap();
@@ -384,6 +608,8 @@ mod tests {
             avatar_url: None,
             roles: Some(vec![Role::RequestViewer]), // Intentionally omitting StandardUser
             password_hash: None,
+            default_model_id: None,
+            attributes: None,
         };
 
         let updated_user = repo.update(created_user.id, &update_request).await.unwrap();
@@ -400,6 +626,8 @@ mod tests {
             avatar_url: None,
             roles: Some(vec![]), // Empty roles
             password_hash: None,
+            default_model_id: None,
+            attributes: None,
         };
 
         let updated_user = repo.update(created_user.id, &update_request).await.unwrap();
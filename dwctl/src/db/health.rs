@@ -0,0 +1,279 @@
+//! Health of the database connection pool and of the queries on hot paths.
+//!
+//! The pool is sampled every `metrics.database.sample_interval`: how many of its connections
+//! are idle and in use, and how long it took to acquire one. Queries on hot paths run through
+//! [`timed`] under a name, and are counted as slow when they take longer than
+//! `metrics.database.slow_query_threshold`. Both are exported on `/internal/metrics` and
+//! summarised on `/internal/status`, to spot a saturated pool or a degrading query before
+//! requests start timing out.
+
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, LazyLock, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use prometheus::{core::Collector, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry};
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::config::DatabaseMetricsConfig;
+
+/// Slow query counts, shared by every call to [`timed`]
+static SLOW_QUERIES: LazyLock<SlowQueries> = LazyLock::new(|| SlowQueries::new().expect("slow query metrics are statically valid"));
+
+struct SlowQueries {
+    /// Queries taking at least this many microseconds are slow
+    threshold_micros: AtomicU64,
+    /// Slow runs of each named query
+    counts: IntCounterVec,
+}
+
+impl SlowQueries {
+    fn new() -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            threshold_micros: AtomicU64::new(DatabaseMetricsConfig::default().slow_query_threshold.as_micros() as u64),
+            counts: IntCounterVec::new(
+                Opts::new("dwctl_db_slow_queries_total", "Runs of named queries slower than the threshold"),
+                &["query"],
+            )?,
+        })
+    }
+
+    fn threshold(&self) -> Duration {
+        Duration::from_micros(self.threshold_micros.load(Ordering::Relaxed))
+    }
+
+    fn observe(&self, query: &str, elapsed: Duration) {
+        if elapsed >= self.threshold() {
+            self.counts.with_label_values(&[query]).inc();
+            warn!(query, elapsed_ms = elapsed.as_millis() as u64, "Slow database query");
+        }
+    }
+
+    /// Slow runs so far, by query
+    fn counts(&self) -> BTreeMap<String, u64> {
+        let mut counts = BTreeMap::new();
+        for family in self.counts.collect() {
+            for metric in family.get_metric() {
+                if let Some(label) = metric.get_label().iter().find(|label| label.get_name() == "query") {
+                    counts.insert(label.get_value().to_string(), metric.get_counter().get_value() as u64);
+                }
+            }
+        }
+        counts
+    }
+}
+
+/// Run `future`, a database query, counting it as slow under `query` when it takes longer than
+/// the threshold. Names are `<area>.<query>`, e.g. `admission.tier_for_key`.
+pub async fn timed<F: Future>(query: &'static str, future: F) -> F::Output {
+    let start = Instant::now();
+    let output = future.await;
+    SLOW_QUERIES.observe(query, start.elapsed());
+    output
+}
+
+/// Prometheus instruments describing the connection pool
+#[derive(Clone, Debug)]
+struct PoolMetrics {
+    /// Open connections, by state: idle or in_use
+    connections: IntGaugeVec,
+    /// Most connections the pool opens
+    max_connections: IntGauge,
+    /// Time taken to acquire a connection when the pool is sampled
+    acquire_seconds: Histogram,
+    /// Samples that failed to acquire a connection
+    acquire_errors: IntCounter,
+}
+
+impl PoolMetrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            connections: IntGaugeVec::new(
+                Opts::new("dwctl_db_pool_connections", "Open database connections by state"),
+                &["state"],
+            )?,
+            max_connections: IntGauge::new("dwctl_db_pool_max_connections", "Most connections the database pool opens")?,
+            acquire_seconds: Histogram::with_opts(
+                HistogramOpts::new(
+                    "dwctl_db_pool_acquire_duration_seconds",
+                    "Time taken to acquire a database connection, sampled",
+                )
+                .buckets(vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 30.0]),
+            )?,
+            acquire_errors: IntCounter::new(
+                "dwctl_db_pool_acquire_errors_total",
+                "Samples that failed to acquire a database connection",
+            )?,
+        })
+    }
+
+    fn register(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        registry.register(Box::new(self.connections.clone()))?;
+        registry.register(Box::new(self.max_connections.clone()))?;
+        registry.register(Box::new(self.acquire_seconds.clone()))?;
+        registry.register(Box::new(self.acquire_errors.clone()))?;
+        Ok(())
+    }
+}
+
+/// Connection pool usage, as reported on `/internal/status`
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStatus {
+    pub max_connections: u32,
+    pub connections: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    /// Time taken to acquire a connection at the last sample, if it succeeded
+    pub last_acquire_ms: Option<f64>,
+    pub acquire_errors: u64,
+}
+
+/// Database health, as reported on `/internal/status`
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseStatus {
+    pub pool: PoolStatus,
+    pub slow_query_threshold_ms: u64,
+    /// Slow runs of each named query since startup
+    pub slow_queries: BTreeMap<String, u64>,
+}
+
+/// Sampler of the connection pool, and source of the database status
+#[derive(Clone, Debug)]
+pub struct DatabaseHealth {
+    pool: PgPool,
+    sample_interval: Duration,
+    metrics: PoolMetrics,
+    last_acquire: Arc<Mutex<Option<Duration>>>,
+}
+
+impl DatabaseHealth {
+    /// Watch `pool`, counting named queries as slow from `config.slow_query_threshold` on
+    pub fn new(pool: PgPool, config: &DatabaseMetricsConfig) -> Self {
+        SLOW_QUERIES
+            .threshold_micros
+            .store(config.slow_query_threshold.as_micros() as u64, Ordering::Relaxed);
+        Self {
+            pool,
+            sample_interval: config.sample_interval,
+            metrics: PoolMetrics::new().expect("pool metrics are statically valid"),
+            last_acquire: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Register the pool and slow query metrics with `registry`
+    pub fn register_metrics(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        self.metrics.register(registry)?;
+        registry.register(Box::new(SLOW_QUERIES.counts.clone()))
+    }
+
+    /// Sample the pool every `sample_interval` in the background
+    pub fn spawn_sampler(&self) {
+        let health = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(health.sample_interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                health.sample().await;
+            }
+        });
+    }
+
+    async fn sample(&self) {
+        let start = Instant::now();
+        let acquired = self.pool.acquire().await;
+        let elapsed = start.elapsed();
+        match acquired {
+            Ok(conn) => {
+                self.metrics.acquire_seconds.observe(elapsed.as_secs_f64());
+                *self.last_acquire.lock().expect("last acquire lock poisoned") = Some(elapsed);
+                drop(conn);
+            }
+            Err(e) => {
+                self.metrics.acquire_errors.inc();
+                *self.last_acquire.lock().expect("last acquire lock poisoned") = None;
+                warn!("Failed to acquire a database connection: {}", e);
+            }
+        }
+
+        let (connections, idle) = self.connections();
+        self.metrics.connections.with_label_values(&["idle"]).set(idle.into());
+        self.metrics
+            .connections
+            .with_label_values(&["in_use"])
+            .set((connections - idle).into());
+        self.metrics.max_connections.set(self.pool.options().get_max_connections().into());
+    }
+
+    /// Open and idle connections
+    fn connections(&self) -> (u32, u32) {
+        let connections = self.pool.size();
+        let idle = u32::try_from(self.pool.num_idle()).unwrap_or(u32::MAX).min(connections);
+        (connections, idle)
+    }
+
+    /// The pool's usage now, and the slow queries so far
+    pub fn status(&self) -> DatabaseStatus {
+        let (connections, idle) = self.connections();
+        let last_acquire = *self.last_acquire.lock().expect("last acquire lock poisoned");
+        DatabaseStatus {
+            pool: PoolStatus {
+                max_connections: self.pool.options().get_max_connections(),
+                connections,
+                idle,
+                in_use: connections - idle,
+                last_acquire_ms: last_acquire.map(|elapsed| elapsed.as_secs_f64() * 1000.0),
+                acquire_errors: self.metrics.acquire_errors.get(),
+            },
+            slow_query_threshold_ms: SLOW_QUERIES.threshold().as_millis() as u64,
+            slow_queries: SLOW_QUERIES.counts(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_pool_sample_and_status(pool: PgPool) {
+        let health = DatabaseHealth::new(pool.clone(), &DatabaseMetricsConfig::default());
+        let registry = Registry::new();
+        health.register_metrics(&registry).unwrap();
+
+        let _held = pool.acquire().await.unwrap();
+        health.sample().await;
+
+        assert!(health.metrics.connections.with_label_values(&["in_use"]).get() >= 1);
+        assert_eq!(health.metrics.acquire_seconds.get_sample_count(), 1);
+        assert_eq!(
+            health.metrics.max_connections.get(),
+            i64::from(pool.options().get_max_connections())
+        );
+
+        let status = health.status();
+        assert!(status.pool.in_use >= 1);
+        assert!(status.pool.last_acquire_ms.is_some());
+        assert_eq!(status.pool.acquire_errors, 0);
+        assert_eq!(status.slow_query_threshold_ms, 500);
+    }
+
+    #[test]
+    fn test_slow_queries_are_counted_by_name() {
+        SLOW_QUERIES.observe("test.fast", Duration::from_millis(1));
+        SLOW_QUERIES.observe("test.slow", Duration::from_secs(2));
+        SLOW_QUERIES.observe("test.slow", Duration::from_secs(3));
+
+        let counts = SLOW_QUERIES.counts();
+        assert!(!counts.contains_key("test.fast"));
+        assert_eq!(counts["test.slow"], 2);
+    }
+}
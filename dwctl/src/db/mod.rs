@@ -1,4 +1,5 @@
 pub mod embedded;
 pub mod errors;
 pub mod handlers;
+pub mod health;
 pub mod models;
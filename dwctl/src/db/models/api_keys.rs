@@ -1,6 +1,7 @@
 use crate::api::models::api_keys::ApiKeyCreate;
 use crate::types::{ApiKeyId, DeploymentId, UserId};
 use chrono::{DateTime, Utc};
+use uuid::Uuid;
 
 /// Database request for creating a new API key
 #[derive(Debug, Clone)]
@@ -47,3 +48,19 @@ pub struct ApiKeyDBResponse {
     pub requests_per_second: Option<f32>,
     pub burst_size: Option<i32>,
 }
+
+/// Database response for a request to grant an API key access to a deployment requiring key
+/// approval
+#[derive(Debug, Clone)]
+pub struct ApiKeyAccessRequestDBResponse {
+    pub id: Uuid,
+    pub api_key_id: ApiKeyId,
+    pub deployment_id: DeploymentId,
+    /// `pending`, `approved` or `denied`
+    pub status: String,
+    pub reason: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    pub decided_by: Option<UserId>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub decision_note: Option<String>,
+}
@@ -366,6 +366,11 @@ pub struct DeploymentCreateDBRequest {
     pub burst_size: Option<i32>,
     // Clean structured pricing
     pub pricing: Option<ModelPricing>,
+    pub max_context_tokens: Option<i32>,
+    pub supports_tools: Option<bool>,
+    pub supports_vision: Option<bool>,
+    #[builder(default)]
+    pub tags: Vec<String>,
 }
 
 impl DeploymentCreateDBRequest {
@@ -384,6 +389,10 @@ impl DeploymentCreateDBRequest {
             .maybe_requests_per_second(create.requests_per_second)
             .maybe_burst_size(create.burst_size)
             .maybe_pricing(combined_pricing)
+            .maybe_max_context_tokens(create.max_context_tokens)
+            .maybe_supports_tools(create.supports_tools)
+            .maybe_supports_vision(create.supports_vision)
+            .tags(create.tags)
             .build()
     }
 }
@@ -399,10 +408,15 @@ pub struct DeploymentUpdateDBRequest {
     pub status: Option<ModelStatus>,
     pub last_sync: Option<Option<DateTime<Utc>>>,
     pub deleted: Option<bool>,
+    pub enabled: Option<bool>,
     pub requests_per_second: Option<Option<f32>>,
     pub burst_size: Option<Option<i32>>,
     // Pricing updates using double-option pattern
     pub pricing: Option<ModelPricingUpdate>,
+    pub max_context_tokens: Option<Option<i32>>,
+    pub supports_tools: Option<Option<bool>>,
+    pub supports_vision: Option<Option<bool>>,
+    pub tags: Option<Vec<String>>,
 }
 
 impl From<DeployedModelUpdate> for DeploymentUpdateDBRequest {
@@ -421,9 +435,14 @@ impl From<DeployedModelUpdate> for DeploymentUpdateDBRequest {
             .maybe_description(update.description)
             .maybe_model_type(update.model_type)
             .maybe_capabilities(update.capabilities)
+            .maybe_enabled(update.enabled)
             .maybe_requests_per_second(update.requests_per_second)
             .maybe_burst_size(update.burst_size)
             .maybe_pricing(pricing_update)
+            .maybe_max_context_tokens(update.max_context_tokens)
+            .maybe_supports_tools(update.supports_tools)
+            .maybe_supports_vision(update.supports_vision)
+            .maybe_tags(update.tags)
             .build()
     }
 }
@@ -460,10 +479,130 @@ pub struct DeploymentDBResponse {
     pub status: ModelStatus,
     pub last_sync: Option<DateTime<Utc>>,
     pub deleted: bool,
+    /// Disabled deployments are excluded from routing but otherwise preserved
+    pub enabled: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub requests_per_second: Option<f32>,
     pub burst_size: Option<i32>,
     // Clean structured pricing
     pub pricing: Option<ModelPricing>,
+    /// Maximum context window in tokens (null = unknown)
+    pub max_context_tokens: Option<i32>,
+    /// Whether the model supports tool calling (null = unknown)
+    pub supports_tools: Option<bool>,
+    /// Whether the model accepts image inputs (null = unknown)
+    pub supports_vision: Option<bool>,
+    /// Admin-managed labels, e.g. "vision" or "eu-hosted"
+    pub tags: Vec<String>,
+}
+
+/// Database request for one fallback of a deployment. Fallbacks are given in priority order.
+#[derive(Debug, Clone)]
+pub struct DeploymentFallbackCreateDBRequest {
+    pub endpoint_id: InferenceEndpointId,
+    pub model_name: String,
+}
+
+/// Database response for a fallback of a deployment
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeploymentFallbackDBResponse {
+    pub deployment_id: DeploymentId,
+    /// Position in the fallback order, starting at 1 (the primary endpoint is implicitly 0)
+    pub priority: i32,
+    pub endpoint_id: InferenceEndpointId,
+    /// Name of the model on the fallback endpoint
+    pub model_name: String,
+}
+
+/// Database request for one weighted target of a deployment
+#[derive(Debug, Clone)]
+pub struct DeploymentTrafficSplitCreateDBRequest {
+    pub endpoint_id: InferenceEndpointId,
+    pub model_name: String,
+    pub weight: i32,
+}
+
+/// Database response for a weighted target of a deployment
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeploymentTrafficSplitDBResponse {
+    pub deployment_id: DeploymentId,
+    /// Position in the list of targets, starting at 1
+    pub position: i32,
+    pub endpoint_id: InferenceEndpointId,
+    /// Name of the model on the target endpoint
+    pub model_name: String,
+    /// Share of traffic, relative to the weights of the deployment's other targets
+    pub weight: i32,
+}
+
+/// Database response for a canary deployment
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeploymentCanaryDBResponse {
+    /// The canary deployment
+    pub deployment_id: DeploymentId,
+    /// The deployment whose alias the canary takes traffic from
+    pub stable_deployment_id: DeploymentId,
+    /// Percentage of the stable alias' requests sent to the canary
+    pub percent: i32,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Database response for a shadow deployment
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeploymentShadowDBResponse {
+    /// The shadow deployment
+    pub deployment_id: DeploymentId,
+    /// The deployment whose alias' requests are mirrored to the shadow
+    pub source_deployment_id: DeploymentId,
+    /// Percentage of the source alias' requests mirrored to the shadow
+    pub percent: i32,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Database response for a deprecated deployment
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeploymentDeprecationDBResponse {
+    pub deployment_id: DeploymentId,
+    /// When the deployment stops serving requests (never, if null)
+    pub sunset_at: Option<DateTime<Utc>>,
+    /// The deployment clients should move to
+    pub replacement_deployment_id: Option<DeploymentId>,
+    /// Current alias of the replacement (null if there is none, or it has been deleted)
+    pub replacement_alias: Option<String>,
+    pub deprecated_at: DateTime<Utc>,
+}
+
+/// Database request for setting the schedule hints of a deployment
+#[derive(Debug, Clone)]
+pub struct DeploymentScheduleUpdateDBRequest {
+    /// Array of busy windows (`days`, `start_hour` and `end_hour`, in UTC)
+    pub busy_windows: serde_json::Value,
+    pub lead_time_minutes: i32,
+}
+
+/// Database response for the schedule hints of a deployment
+#[derive(Debug, Clone)]
+pub struct DeploymentScheduleDBResponse {
+    pub deployment_id: DeploymentId,
+    pub busy_windows: serde_json::Value,
+    pub lead_time_minutes: i32,
+}
+
+/// Database request for setting the logging policy of a deployment
+#[derive(Debug, Clone)]
+pub struct DeploymentLoggingPolicyUpdateDBRequest {
+    /// `full`, `metadata_only`, `hash_only` or `redact_fields`
+    pub mode: String,
+    pub redacted_fields: Vec<String>,
+    pub body_sample_percent: i32,
+}
+
+/// Database response for the logging policy of a deployment
+#[derive(Debug, Clone)]
+pub struct DeploymentLoggingPolicyDBResponse {
+    pub deployment_id: DeploymentId,
+    pub mode: String,
+    pub redacted_fields: Vec<String>,
+    pub body_sample_percent: i32,
 }
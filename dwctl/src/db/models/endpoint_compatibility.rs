@@ -0,0 +1,49 @@
+use crate::types::{InferenceEndpointId, UserId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A stored run of the OpenAI compatibility suite against an inference endpoint.
+///
+/// Each run is kept, so admins can see when a backend gained or lost support for a feature.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct EndpointCompatibilityReport {
+    /// Unique identifier for this report
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    /// ID of the endpoint that was tested
+    #[schema(value_type = String, format = "uuid")]
+    pub endpoint_id: InferenceEndpointId,
+    /// Model the suite was run with
+    pub model: String,
+    /// Number of checks that passed
+    pub passed: i32,
+    /// Number of checks in the suite
+    pub total: i32,
+    /// Fraction of checks that passed, between 0 and 1
+    pub score: f64,
+    /// Outcome of each check
+    #[schema(value_type = Vec<CompatibilityCheckResult>)]
+    pub checks: sqlx::types::Json<Vec<CompatibilityCheckResult>>,
+    /// User who ran the suite
+    #[schema(value_type = String, format = "uuid")]
+    pub created_by: UserId,
+    /// When the suite was run
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Outcome of a single compatibility check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CompatibilityCheckResult {
+    /// Feature being checked, e.g. `streaming` or `tool_calls`
+    pub name: String,
+    /// Whether the endpoint behaved like the OpenAI API
+    pub passed: bool,
+    /// What went wrong (if the check failed)
+    pub detail: Option<String>,
+    /// Time taken by the check's request, in milliseconds
+    pub latency_ms: u64,
+}
@@ -0,0 +1,125 @@
+use crate::db::models::inference_endpoints::InferenceEndpointDBResponse;
+use crate::types::{InferenceEndpointId, UserId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use std::collections::{BTreeMap, BTreeSet};
+use utoipa::ToSchema;
+
+/// The versioned configuration of an inference endpoint: its settings (except its API key) and
+/// the alias of each of its deployments.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EndpointConfig {
+    pub name: String,
+    pub description: Option<String>,
+    pub url: String,
+    pub model_filter: Option<Vec<String>>,
+    pub auth_header_name: String,
+    pub auth_header_prefix: String,
+    pub tls_ca_pem: Option<String>,
+    pub tls_skip_verify: bool,
+    pub tls_min_version: Option<String>,
+    pub sync_interval_seconds: Option<i32>,
+    pub enabled: bool,
+    /// Alias of each of the endpoint's deployments, by model name
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl EndpointConfig {
+    pub fn new(endpoint: &InferenceEndpointDBResponse, aliases: BTreeMap<String, String>) -> Self {
+        Self {
+            name: endpoint.name.clone(),
+            description: endpoint.description.clone(),
+            url: endpoint.url.to_string(),
+            model_filter: endpoint.model_filter.clone(),
+            auth_header_name: endpoint.auth_header_name.clone(),
+            auth_header_prefix: endpoint.auth_header_prefix.clone(),
+            tls_ca_pem: endpoint.tls_ca_pem.clone(),
+            tls_skip_verify: endpoint.tls_skip_verify,
+            tls_min_version: endpoint.tls_min_version.clone(),
+            sync_interval_seconds: endpoint.sync_interval_seconds,
+            enabled: endpoint.enabled,
+            aliases,
+        }
+    }
+
+    /// The settings that differ from `previous`, with alias changes named `aliases.<model name>`
+    pub fn changes_since(&self, previous: &EndpointConfig) -> Vec<String> {
+        let (Value::Object(current), Value::Object(previous_fields)) = (
+            serde_json::to_value(self).unwrap_or_default(),
+            serde_json::to_value(previous).unwrap_or_default(),
+        ) else {
+            return Vec::new();
+        };
+
+        let mut changes: Vec<String> = current
+            .iter()
+            .filter(|(field, value)| *field != "aliases" && previous_fields.get(*field) != Some(*value))
+            .map(|(field, _)| field.clone())
+            .collect();
+        changes.sort();
+        let models: BTreeSet<&String> = self.aliases.keys().chain(previous.aliases.keys()).collect();
+        changes.extend(
+            models
+                .into_iter()
+                .filter(|model| self.aliases.get(*model) != previous.aliases.get(*model))
+                .map(|model| format!("aliases.{model}")),
+        );
+        changes
+    }
+}
+
+/// A stored version of an endpoint's configuration
+#[derive(Debug, Clone, FromRow)]
+pub struct EndpointConfigVersionDBResponse {
+    pub endpoint_id: InferenceEndpointId,
+    pub version: i32,
+    pub config: sqlx::types::Json<EndpointConfig>,
+    pub changed_by: Option<UserId>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> EndpointConfig {
+        EndpointConfig {
+            name: "endpoint".to_string(),
+            description: None,
+            url: "https://api.example.com/v1".to_string(),
+            model_filter: None,
+            auth_header_name: "Authorization".to_string(),
+            auth_header_prefix: "Bearer ".to_string(),
+            tls_ca_pem: None,
+            tls_skip_verify: false,
+            tls_min_version: None,
+            sync_interval_seconds: None,
+            enabled: true,
+            aliases: BTreeMap::from([("gpt-4".to_string(), "gpt-4".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_changes_since() {
+        let previous = config();
+        assert!(config().changes_since(&previous).is_empty());
+
+        let mut current = config();
+        current.url = "https://other.example.com/v1".to_string();
+        current.enabled = false;
+        current.aliases.insert("gpt-4".to_string(), "smart".to_string());
+        current.aliases.insert("gpt-3.5".to_string(), "fast".to_string());
+        assert_eq!(
+            current.changes_since(&previous),
+            vec!["enabled", "url", "aliases.gpt-3.5", "aliases.gpt-4"]
+        );
+
+        // Deployments that are gone count as changed aliases too
+        assert_eq!(
+            previous.changes_since(&current),
+            vec!["enabled", "url", "aliases.gpt-3.5", "aliases.gpt-4"]
+        );
+    }
+}
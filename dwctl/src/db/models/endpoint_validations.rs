@@ -0,0 +1,44 @@
+use crate::types::InferenceEndpointId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A stored result from a scheduled re-validation of an inference endpoint.
+///
+/// One row is written per endpoint per validation run, so the history can be
+/// used to tell when an endpoint started failing.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct EndpointValidationResult {
+    /// Unique identifier for this result
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    /// ID of the endpoint that was validated
+    #[schema(value_type = String, format = "uuid")]
+    pub endpoint_id: InferenceEndpointId,
+    /// When the validation was performed
+    #[schema(value_type = String, format = "date-time")]
+    pub validated_at: DateTime<Utc>,
+    /// Whether the endpoint responded with a usable model list
+    pub success: bool,
+    /// HTTP status code returned by the endpoint (if a response was received)
+    pub status_code: Option<i32>,
+    /// Error message (if failed)
+    pub error_message: Option<String>,
+    /// Number of models returned by the endpoint (if successful)
+    pub model_count: Option<i32>,
+    /// Whether the endpoint rejected the stored credentials (HTTP 401/403)
+    pub credentials_expired: bool,
+}
+
+/// In-memory representation of a validation outcome before it's stored.
+#[derive(Debug, Clone)]
+pub struct EndpointValidationCreateDBRequest {
+    pub endpoint_id: InferenceEndpointId,
+    pub success: bool,
+    pub status_code: Option<i32>,
+    pub error_message: Option<String>,
+    pub model_count: Option<i32>,
+    pub credentials_expired: bool,
+}
@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Another dwctl instance whose summary is aggregated with this one's
+#[derive(Debug, Clone, FromRow)]
+pub struct FederationPeer {
+    pub id: Uuid,
+    /// Unique display name, e.g. the peer's region
+    pub name: String,
+    /// Base URL of the peer instance
+    pub url: String,
+    /// The federation token configured on the peer
+    pub token: String,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+}
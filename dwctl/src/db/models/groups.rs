@@ -8,6 +8,8 @@ pub struct GroupCreateDBRequest {
     pub name: String,
     pub description: Option<String>,
     pub created_by: UserId,
+    pub parent_id: Option<GroupId>,
+    pub is_default: bool,
 }
 
 impl GroupCreateDBRequest {
@@ -16,6 +18,8 @@ impl GroupCreateDBRequest {
             name: create.name,
             description: create.description,
             created_by,
+            parent_id: create.parent_id,
+            is_default: create.is_default,
         }
     }
 }
@@ -25,6 +29,11 @@ impl GroupCreateDBRequest {
 pub struct GroupUpdateDBRequest {
     pub name: Option<String>,
     pub description: Option<String>,
+    /// One of `high`, `normal` or `low`
+    pub priority: Option<String>,
+    /// None = no change, Some(None) = move to the top level
+    pub parent_id: Option<Option<GroupId>>,
+    pub is_default: Option<bool>,
 }
 
 impl From<GroupUpdate> for GroupUpdateDBRequest {
@@ -32,6 +41,9 @@ impl From<GroupUpdate> for GroupUpdateDBRequest {
         Self {
             name: update.name,
             description: update.description,
+            priority: update.priority.map(|tier| tier.as_str().to_string()),
+            parent_id: update.parent_id,
+            is_default: update.is_default,
         }
     }
 }
@@ -46,4 +58,49 @@ pub struct GroupDBResponse {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub source: String,
+    /// Admission priority tier: `high`, `normal` or `low`
+    pub priority: String,
+    /// Group whose members and deployments this group inherits
+    pub parent_id: Option<GroupId>,
+    /// Whether newly provisioned users join this group
+    pub is_default: bool,
+}
+
+/// Database request for setting the content moderation policy of a group
+#[derive(Debug, Clone)]
+pub struct GroupModerationUpdateDBRequest {
+    /// One of `off`, `annotate` or `block`
+    pub mode: String,
+    pub blocked_patterns: Vec<String>,
+}
+
+/// Database response for the content moderation policy of a group
+#[derive(Debug, Clone)]
+pub struct GroupModerationDBResponse {
+    pub mode: String,
+    pub blocked_patterns: Vec<String>,
+}
+
+/// Database request for setting the request limits of a group
+#[derive(Debug, Clone)]
+pub struct GroupRequestLimitsUpdateDBRequest {
+    pub max_body_bytes: Option<i64>,
+    pub max_messages: Option<i32>,
+    pub max_tokens: Option<i32>,
+}
+
+/// Database response for the request limits of a group
+#[derive(Debug, Clone)]
+pub struct GroupRequestLimitsDBResponse {
+    pub max_body_bytes: Option<i64>,
+    pub max_messages: Option<i32>,
+    pub max_tokens: Option<i32>,
+}
+
+/// Database request and response for the request log retention override of a group
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupLogRetentionDBResponse {
+    pub retention_days: i32,
+    /// `maximum` or `minimum`
+    pub kind: String,
 }
@@ -13,6 +13,10 @@ pub struct InferenceEndpointCreateDBRequest {
     pub model_filter: Option<Vec<String>>,
     pub auth_header_name: Option<String>,
     pub auth_header_prefix: Option<String>,
+    pub tls_ca_pem: Option<String>,
+    pub tls_skip_verify: bool,
+    pub tls_min_version: Option<String>,
+    pub sync_interval_seconds: Option<i32>,
 }
 
 /// Database request for updating an inference endpoint
@@ -25,6 +29,11 @@ pub struct InferenceEndpointUpdateDBRequest {
     pub model_filter: Option<Option<Vec<String>>>,
     pub auth_header_name: Option<String>,
     pub auth_header_prefix: Option<String>,
+    pub tls_ca_pem: Option<Option<String>>,
+    pub tls_skip_verify: Option<bool>,
+    pub tls_min_version: Option<Option<String>>,
+    pub sync_interval_seconds: Option<Option<i32>>,
+    pub enabled: Option<bool>,
 }
 
 /// Database response for an inference endpoint
@@ -38,7 +47,100 @@ pub struct InferenceEndpointDBResponse {
     pub model_filter: Option<Vec<String>>,
     pub auth_header_name: String,
     pub auth_header_prefix: String,
+    /// PEM bundle of CA certificates trusted in addition to the built-in roots
+    pub tls_ca_pem: Option<String>,
+    /// Whether any certificate is accepted
+    pub tls_skip_verify: bool,
+    /// Minimum TLS version (`1.2` or `1.3`)
+    pub tls_min_version: Option<String>,
+    /// Seconds between automatic synchronizations, or `None` to only sync on demand
+    pub sync_interval_seconds: Option<i32>,
+    /// When the endpoint was last synchronized, successfully or not
+    pub last_sync_at: Option<DateTime<Utc>>,
+    /// Why the last synchronization failed, if it did
+    pub last_sync_error: Option<String>,
+    /// Whether the endpoint's deployments are routable
+    pub enabled: bool,
     pub created_by: UserId,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+/// Database request for setting the redaction policy of an inference endpoint
+#[derive(Debug, Clone)]
+pub struct EndpointRedactionUpdateDBRequest {
+    /// PII categories to mask (`email`, `phone`, `financial`)
+    pub entities: Vec<String>,
+    pub patterns: Vec<String>,
+    pub log_original: bool,
+}
+
+/// Database response for the redaction policy of an inference endpoint
+#[derive(Debug, Clone)]
+pub struct EndpointRedactionDBResponse {
+    pub endpoint_id: InferenceEndpointId,
+    pub entities: Vec<String>,
+    pub patterns: Vec<String>,
+    pub log_original: bool,
+}
+
+/// Database request for setting the header rules of an inference endpoint
+#[derive(Debug, Clone)]
+pub struct EndpointHeaderRulesUpdateDBRequest {
+    /// Object of lowercase header names to the values injected into forwarded requests
+    pub inject: serde_json::Value,
+    /// Lowercase names of the client headers to forward, or `None` to forward all of them
+    pub passthrough: Option<Vec<String>>,
+}
+
+/// Database response for the header rules of an inference endpoint
+#[derive(Debug, Clone)]
+pub struct EndpointHeaderRulesDBResponse {
+    pub endpoint_id: InferenceEndpointId,
+    pub inject: serde_json::Value,
+    pub passthrough: Option<Vec<String>>,
+}
+
+/// Database request for setting the upstream rate limits of an inference endpoint
+#[derive(Debug, Clone)]
+pub struct EndpointRateLimitsUpdateDBRequest {
+    pub requests_per_second: Option<f32>,
+    pub burst_size: Option<i32>,
+    pub max_concurrent: Option<i32>,
+}
+
+/// Database response for the upstream rate limits of an inference endpoint
+#[derive(Debug, Clone)]
+pub struct EndpointRateLimitsDBResponse {
+    pub endpoint_id: InferenceEndpointId,
+    pub requests_per_second: Option<f32>,
+    pub burst_size: Option<i32>,
+    pub max_concurrent: Option<i32>,
+}
+
+/// Database request for setting the sync strategy of an inference endpoint
+#[derive(Debug, Clone)]
+pub struct EndpointSyncStrategyUpdateDBRequest {
+    /// `disable`, `delete` or `keep`
+    pub removed_models: String,
+    /// `auto_add` or `queue`
+    pub new_models: String,
+}
+
+/// Database response for the sync strategy of an inference endpoint
+#[derive(Debug, Clone)]
+pub struct EndpointSyncStrategyDBResponse {
+    pub removed_models: String,
+    pub new_models: String,
+}
+
+/// Database response for a model found upstream that awaits approval
+#[derive(Debug, Clone)]
+pub struct EndpointPendingModelDBResponse {
+    pub model_name: String,
+    /// `pending` or `rejected`
+    pub status: String,
+    pub discovered_at: DateTime<Utc>,
+    pub rejected_at: Option<DateTime<Utc>>,
+    pub rejected_by: Option<UserId>,
+}
@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A load test run against a deployed model.
+///
+/// Load tests send synthetic traffic through the AI proxy, so quotas and rate
+/// limits apply exactly as they would to real users.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LoadTest {
+    /// Unique identifier for the load test
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    /// Deployment (model) the traffic is sent to
+    #[schema(value_type = String, format = "uuid")]
+    pub deployment_id: Uuid,
+    /// User who started the load test
+    #[schema(value_type = String, format = "uuid")]
+    pub created_by: Uuid,
+    /// One of `running`, `completed`, `stopped` or `failed`
+    pub status: String,
+    /// Why the load test stopped before sending all requests (if it did)
+    pub stop_reason: Option<String>,
+    /// Whether a stop has been requested for a running load test
+    pub stop_requested: bool,
+    /// Number of requests to send
+    pub total_requests: i32,
+    /// Maximum number of requests in flight at once
+    pub concurrency: i32,
+    /// Maximum rate at which requests are started
+    pub requests_per_second: i32,
+    /// The load test is stopped after this many seconds
+    pub max_duration_seconds: i32,
+    /// Custom request body (the model field is always set to the deployment's alias)
+    pub request_body: Option<serde_json::Value>,
+    /// Results so far; updated while the load test runs
+    #[schema(value_type = Option<LoadTestReport>)]
+    pub report: Option<sqlx::types::Json<LoadTestReport>>,
+    /// When the load test started
+    #[schema(value_type = String, format = "date-time")]
+    pub started_at: DateTime<Utc>,
+    /// When the load test finished
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Results of a load test
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LoadTestReport {
+    /// Number of requests that have completed (successfully or not)
+    pub completed_requests: u64,
+    /// Requests that returned a 2xx response
+    pub successful_requests: u64,
+    /// Requests rejected with HTTP 429
+    pub rate_limited_requests: u64,
+    /// Requests that failed for any other reason (non-2xx status or no response)
+    pub failed_requests: u64,
+    /// Number of responses per HTTP status code; requests without a response are counted as "error"
+    pub status_codes: BTreeMap<String, u64>,
+    /// Time since the load test started, in milliseconds
+    pub elapsed_ms: u64,
+    /// Completed requests per second
+    pub throughput_rps: f64,
+    /// Latency of successful requests
+    pub latency_ms: Option<LatencySummary>,
+    /// Prompt tokens reported by successful responses
+    pub prompt_tokens: u64,
+    /// Completion tokens reported by successful responses
+    pub completion_tokens: u64,
+}
+
+/// Latency distribution, in milliseconds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LatencySummary {
+    pub mean: f64,
+    pub p50: u64,
+    pub p95: u64,
+    pub p99: u64,
+    pub max: u64,
+}
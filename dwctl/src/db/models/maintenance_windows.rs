@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A period during which failures of an endpoint's or a deployment's probes don't trigger alerts
+/// or count against SLOs
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct MaintenanceWindow {
+    /// Unique identifier for the window
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub name: String,
+    /// Endpoint under maintenance, covering every deployment hosted on it
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub endpoint_id: Option<Uuid>,
+    /// Deployment under maintenance
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub deployment_id: Option<Uuid>,
+    #[schema(value_type = String, format = "date-time")]
+    pub starts_at: DateTime<Utc>,
+    #[schema(value_type = String, format = "date-time")]
+    pub ends_at: DateTime<Utc>,
+    /// Why the maintenance is happening
+    pub reason: Option<String>,
+    /// User who scheduled the window
+    #[schema(value_type = String, format = "uuid")]
+    pub created_by: Uuid,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = String, format = "date-time")]
+    pub updated_at: DateTime<Utc>,
+}
@@ -1,7 +1,17 @@
 pub mod api_keys;
 pub mod deployments;
+pub mod endpoint_compatibility;
+pub mod endpoint_history;
+pub mod endpoint_validations;
+pub mod federation;
 pub mod groups;
 pub mod inference_endpoints;
+pub mod load_tests;
+pub mod maintenance_windows;
+pub mod notification_channels;
 pub mod password_reset_tokens;
 pub mod probes;
+pub mod regression_suites;
+pub mod slos;
+pub mod token_backfills;
 pub mod users;
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Somewhere probe alerts are sent
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct NotificationChannel {
+    /// Unique identifier for the channel
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    pub name: String,
+    /// `webhook`, `slack` or `email`
+    pub channel_type: String,
+    /// Webhook URL for webhook and Slack channels, email address for email channels
+    pub destination: String,
+    /// Whether alerts are sent to the channel
+    pub enabled: bool,
+    /// When an alert was last sent to the channel
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub last_delivery_at: Option<DateTime<Utc>>,
+    /// Why the last alert couldn't be delivered, if it couldn't
+    pub last_delivery_error: Option<String>,
+    /// User who created the channel
+    #[schema(value_type = String, format = "uuid")]
+    pub created_by: Uuid,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = String, format = "date-time")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Alerting state of a probe
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct ProbeAlertState {
+    pub probe_id: Uuid,
+    /// `failing` once the probe has failed `failure_threshold` times in a row, `ok` otherwise
+    pub status: String,
+    pub consecutive_failures: i32,
+    /// Status channels were last told about
+    pub notified_status: String,
+    pub notified_at: Option<DateTime<Utc>>,
+}
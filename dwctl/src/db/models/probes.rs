@@ -21,7 +21,7 @@ pub struct Probe {
     pub deployment_id: Uuid,
     /// How often to execute the probe, in seconds
     pub interval_seconds: i32,
-    /// Whether the probe is currently active and should be scheduled
+    /// Whether the probe is currently active and should be scheduled (never, for load probes)
     pub active: bool,
     /// HTTP method to use for the probe request (e.g., GET, POST)
     pub http_method: String,
@@ -29,6 +29,37 @@ pub struct Probe {
     pub request_path: Option<String>,
     /// JSON body to send with the probe request
     pub request_body: Option<serde_json::Value>,
+    /// What the probe checks: `http` for the response status, `embeddings` to also validate
+    /// the returned vectors, `tool_call` to also validate the returned tool calls,
+    /// `conversation` to play a scripted multi-turn chat, `semantic_similarity` to compare the reply
+    /// to a golden answer, `load` to measure throughput and latency under concurrent requests
+    pub probe_type: String,
+    /// Number of dimensions each embedding must have (embeddings probes only)
+    pub expected_dimensions: Option<i32>,
+    /// Responses slower than this many milliseconds count as failures
+    pub max_latency_ms: Option<i32>,
+    /// Turns of a conversation probe, each a `content` sent as the user and the strings
+    /// (`expect_contains`) the reply must contain
+    #[schema(value_type = Option<Vec<crate::api::models::probes::ConversationTurn>>)]
+    pub conversation: Option<serde_json::Value>,
+    /// Maximum random delay before each execution, in seconds (defaults to `probes.jitter`)
+    pub jitter_seconds: Option<i32>,
+    /// Timeout for each request, in seconds (defaults to `probes.request_timeout`)
+    pub timeout_seconds: Option<i32>,
+    /// Extra checks on the response, each recorded with the result
+    #[schema(value_type = Option<Vec<crate::api::models::probes::ProbeAssertion>>)]
+    pub assertions: Option<serde_json::Value>,
+    /// Answer the reply is compared to (semantic similarity probes only)
+    pub golden_answer: Option<String>,
+    /// Deployment that embeds the reply and the golden answer (semantic similarity probes only)
+    #[schema(value_type = Option<String>, format = "uuid")]
+    pub embeddings_deployment_id: Option<Uuid>,
+    /// Minimum cosine similarity between the reply and the golden answer
+    pub similarity_threshold: Option<f64>,
+    /// Requests kept in flight (load probes only)
+    pub load_concurrency: Option<i32>,
+    /// Seconds to send requests for (load probes only)
+    pub load_duration_seconds: Option<i32>,
     /// When the probe was created
     #[schema(value_type = String, format = "date-time")]
     pub created_at: DateTime<Utc>,
@@ -64,6 +95,27 @@ pub struct ProbeResult {
     pub response_data: Option<serde_json::Value>,
     /// Additional metadata about the execution
     pub metadata: Option<serde_json::Value>,
+    /// Outcome of each of the probe's assertions, if the response could be checked
+    #[schema(value_type = Option<Vec<AssertionResult>>)]
+    pub assertion_results: Option<serde_json::Value>,
+    /// Whether the result was taken during a maintenance window of its deployment or endpoint, in
+    /// which case it doesn't trigger alerts or count against SLOs
+    pub in_maintenance: bool,
+    /// Milliseconds until the first token of a streamed completion arrived
+    pub time_to_first_token_ms: Option<i32>,
+    /// Tokens per second generated after the first, for streamed completions
+    pub tokens_per_second: Option<f64>,
+}
+
+/// Outcome of one of a probe's assertions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AssertionResult {
+    /// The assertion that was checked
+    pub assertion: crate::api::models::probes::ProbeAssertion,
+    /// Whether the response satisfied it
+    pub passed: bool,
+    /// Why the assertion failed
+    pub message: Option<String>,
 }
 
 /// In-memory representation of a probe execution before it's stored.
@@ -86,4 +138,10 @@ pub struct ProbeExecution {
     pub response_data: Option<serde_json::Value>,
     /// Additional metadata about the execution
     pub metadata: Option<serde_json::Value>,
+    /// Outcome of each of the probe's assertions
+    pub assertion_results: Option<Vec<AssertionResult>>,
+    /// Milliseconds until the first token arrived, if the completion was streamed
+    pub time_to_first_token_ms: Option<i32>,
+    /// Tokens per second generated after the first, if the completion was streamed
+    pub tokens_per_second: Option<f64>,
 }
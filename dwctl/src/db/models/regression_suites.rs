@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A suite of golden prompts attached to a deployment.
+///
+/// Each run sends every case to the deployment through the AI proxy and checks the
+/// responses against the case's assertions, so a backend upgrade that degrades output
+/// quality shows up as a drop in score.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct RegressionSuite {
+    /// Unique identifier for the suite
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    /// Deployment (model) the suite runs against
+    #[schema(value_type = String, format = "uuid")]
+    pub deployment_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    /// Prompts to send and the assertions their responses must satisfy
+    #[schema(value_type = Vec<RegressionCase>)]
+    pub cases: sqlx::types::Json<Vec<RegressionCase>>,
+    /// Run the suite automatically this often; on demand only if not set
+    pub schedule_interval_seconds: Option<i32>,
+    /// User who created the suite
+    #[schema(value_type = String, format = "uuid")]
+    pub created_by: Uuid,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = String, format = "date-time")]
+    pub updated_at: DateTime<Utc>,
+    /// When the suite last ran (manually or on its schedule)
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub last_run_at: Option<DateTime<Utc>>,
+}
+
+/// A single golden prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RegressionCase {
+    /// Name shown in run results
+    pub name: String,
+    /// Chat completion request body. The model field is always set to the deployment's alias.
+    pub request: serde_json::Value,
+    /// Properties the response must have for the case to pass
+    pub assertions: Vec<Assertion>,
+}
+
+/// A property of a response. Content assertions apply to the first choice's message content.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Assertion {
+    /// The content matches a regular expression
+    Regex { pattern: String },
+    /// The content is JSON that satisfies a JSON schema. Supports `type`, `properties`,
+    /// `required`, `items`, `enum` and `const`.
+    JsonSchema { schema: serde_json::Value },
+    /// The request completed within this many milliseconds
+    MaxLatencyMs { max_ms: u64 },
+}
+
+/// A stored run of a regression suite
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct RegressionRun {
+    /// Unique identifier for the run
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub suite_id: Uuid,
+    /// `manual` or `scheduled`
+    pub trigger: String,
+    /// Number of cases that passed
+    pub passed: i32,
+    /// Number of cases in the suite when it ran
+    pub total: i32,
+    /// Fraction of cases that passed, between 0 and 1
+    pub score: f64,
+    /// Outcome of each case
+    #[schema(value_type = Vec<RegressionCaseResult>)]
+    pub results: sqlx::types::Json<Vec<RegressionCaseResult>>,
+    #[schema(value_type = String, format = "date-time")]
+    pub started_at: DateTime<Utc>,
+    #[schema(value_type = String, format = "date-time")]
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Outcome of a single case
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RegressionCaseResult {
+    pub name: String,
+    /// Whether the request succeeded and every assertion held
+    pub passed: bool,
+    /// HTTP status of the response, if one was received
+    pub status_code: Option<u16>,
+    /// Time taken by the request, in milliseconds
+    pub latency_ms: u64,
+    /// Why the case failed, one entry per failed assertion
+    pub failures: Vec<String>,
+}
@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A service level objective for a deployment, measured on its probe results.
+///
+/// The SLO is met while at least `target_percent` of the probe results in the last
+/// `window_days` are good. The rest of the window's results make up the error budget, and the
+/// latest evaluation of how much of it is left is kept on the SLO.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DeploymentSlo {
+    /// Unique identifier for the SLO
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    /// Deployment (model) whose probe results the SLO is measured on
+    #[schema(value_type = String, format = "uuid")]
+    pub deployment_id: Uuid,
+    pub name: String,
+    /// `availability` (good results succeeded) or `latency` (good results succeeded within
+    /// `latency_threshold_ms`)
+    pub objective: String,
+    /// Share of probe results in the window that must be good, e.g. 99.9
+    pub target_percent: f64,
+    /// Probe results slower than this are bad (latency SLOs only)
+    pub latency_threshold_ms: Option<i32>,
+    /// Length of the rolling window, in days
+    pub window_days: i32,
+    /// `ok`, `at_risk` or `exhausted`, as of the last evaluation
+    pub status: String,
+    /// Probe results in the window at the last evaluation
+    pub total_results: i64,
+    /// Good probe results in the window at the last evaluation
+    pub good_results: i64,
+    /// Share of the error budget left at the last evaluation; negative once overspent
+    pub error_budget_remaining_percent: f64,
+    /// Rate the error budget was spent at over the recent burn window, where 1 spends it exactly
+    /// over the SLO window
+    pub burn_rate: f64,
+    /// When the SLO was last evaluated
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub evaluated_at: Option<DateTime<Utc>>,
+    /// User who created the SLO
+    #[schema(value_type = String, format = "uuid")]
+    pub created_by: Uuid,
+    #[schema(value_type = String, format = "date-time")]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = String, format = "date-time")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A change in the error budget status of an SLO
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SloAlert {
+    /// Unique identifier for the alert
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    #[schema(value_type = String, format = "uuid")]
+    pub slo_id: Uuid,
+    /// Status the SLO changed to: `ok` when it recovered, otherwise `at_risk` or `exhausted`
+    pub status: String,
+    /// Status the SLO changed from
+    pub previous_status: String,
+    pub error_budget_remaining_percent: f64,
+    pub burn_rate: f64,
+    /// Human-readable description of the change
+    pub message: String,
+    #[schema(value_type = String, format = "date-time")]
+    pub fired_at: DateTime<Utc>,
+}
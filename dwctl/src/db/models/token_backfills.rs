@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A run filling in the token counts of logged requests recorded without them.
+///
+/// The bodies kept in the request log are parsed again: usage reported by the upstream is taken
+/// as is, and otherwise tokens are estimated from the text of the request and response.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TokenBackfill {
+    /// Unique identifier for the backfill
+    #[schema(value_type = String, format = "uuid")]
+    pub id: Uuid,
+    /// User who started the backfill
+    #[schema(value_type = String, format = "uuid")]
+    pub created_by: Uuid,
+    /// One of `running`, `completed`, `stopped` or `failed`
+    pub status: String,
+    /// Why the backfill stopped before processing every request (if it did)
+    pub stop_reason: Option<String>,
+    /// Whether a stop has been requested for a running backfill
+    pub stop_requested: bool,
+    /// Only requests logged at or after this time are backfilled
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub range_start: Option<DateTime<Utc>>,
+    /// Only requests logged at or before this time are backfilled
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub range_end: Option<DateTime<Utc>>,
+    /// Requests without token counts when the backfill started
+    pub total_requests: i64,
+    /// Requests looked at so far
+    pub processed_requests: i64,
+    /// Requests whose token counts have been filled in
+    pub updated_requests: i64,
+    /// Requests left as they were, because their bodies are no longer in the log
+    pub skipped_requests: i64,
+    #[schema(value_type = String, format = "date-time")]
+    pub started_at: DateTime<Utc>,
+    /// When progress was last saved
+    #[schema(value_type = String, format = "date-time")]
+    pub updated_at: DateTime<Utc>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
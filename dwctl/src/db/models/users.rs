@@ -1,6 +1,8 @@
 use crate::api::models::users::{Role, UserCreate, UserUpdate};
-use crate::types::UserId;
+use crate::types::{DeploymentId, UserId};
 use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashSet};
+use uuid::Uuid;
 
 /// Database request for creating a new user
 #[derive(Debug, Clone)]
@@ -13,6 +15,7 @@ pub struct UserCreateDBRequest {
     pub roles: Vec<Role>,
     pub auth_source: String,
     pub password_hash: Option<String>,
+    pub attributes: BTreeMap<String, String>,
 }
 
 impl From<UserCreate> for UserCreateDBRequest {
@@ -26,6 +29,7 @@ impl From<UserCreate> for UserCreateDBRequest {
             roles: api.roles,
             auth_source: "proxy-header".to_string(), // Default auth source
             password_hash: None,                     // No password for vouch users
+            attributes: api.attributes,
         }
     }
 }
@@ -37,6 +41,10 @@ pub struct UserUpdateDBRequest {
     pub avatar_url: Option<String>,
     pub roles: Option<Vec<Role>>,
     pub password_hash: Option<String>,
+    /// None = no change, Some(None) = clear the default model
+    pub default_model_id: Option<Option<DeploymentId>>,
+    /// None = no change, Some(attributes) = replace all attributes
+    pub attributes: Option<BTreeMap<String, String>>,
 }
 
 impl UserUpdateDBRequest {
@@ -46,6 +54,8 @@ impl UserUpdateDBRequest {
             avatar_url: update.avatar_url,
             roles: update.roles,
             password_hash: None, // Regular updates don't include password changes
+            default_model_id: update.default_model_id,
+            attributes: update.attributes,
         }
     }
 }
@@ -60,8 +70,28 @@ pub struct UserDBResponse {
     pub avatar_url: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub last_login: Option<DateTime<Utc>>,
     pub auth_source: String,
     pub is_admin: bool,
     pub roles: Vec<Role>,
     pub password_hash: Option<String>,
+    pub default_model_id: Option<DeploymentId>,
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// Database response for a user deleted together with their personal data
+#[derive(Debug, Clone, Default)]
+pub struct UserPurgeDBResponse {
+    pub api_keys_deleted: u64,
+    /// Logged requests deleted, including archived ones
+    pub requests_deleted: u64,
+    /// Logged responses deleted, including archived ones
+    pub responses_deleted: u64,
+    /// Analytics rows whose user details were cleared
+    pub analytics_anonymized: u64,
+    /// Object storage keys of the deleted requests' and responses' bodies
+    pub stored_body_keys: Vec<String>,
+    /// Request log archives that may hold the user's exported requests, with the (instance ID,
+    /// correlation ID) of the requests to remove from each
+    pub archived_requests: BTreeMap<String, HashSet<(Uuid, i64)>>,
 }
@@ -0,0 +1,197 @@
+//! Deprecation of deployments.
+//!
+//! Admins can deprecate a deployment, optionally with a sunset date and a replacement deployment.
+//! `sync::onwards_config` records the deprecation of each alias in the [`RoutingTable`], and the
+//! [`enforce_deprecation`] middleware, which wraps the AI proxy, acts on requests to those aliases:
+//! until the sunset date they are served as usual, with `Deprecation` and `Sunset` headers
+//! (RFC 9745 and RFC 8594) and a human-readable `Warning` in the response. After it, they are
+//! refused with a 410 pointing to the replacement's alias. Deprecations are also listed with the
+//! models in onwards' `/v1/models`.
+
+use crate::routing::RoutingTable;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::watch;
+
+/// Header carrying when the model was deprecated, as `@<unix timestamp>` (RFC 9745)
+pub const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+/// Header carrying when the model stops serving requests, as an HTTP date (RFC 8594)
+pub const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+
+/// The deprecation of an alias
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Deprecation {
+    pub deprecated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sunset_at: Option<DateTime<Utc>>,
+    /// Alias clients should move to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+}
+
+impl Deprecation {
+    /// Whether the sunset date has passed, so requests are refused
+    pub fn is_retired(&self, now: DateTime<Utc>) -> bool {
+        self.sunset_at.is_some_and(|sunset_at| sunset_at <= now)
+    }
+
+    /// What clients of `alias` are told, in the warning or the error
+    pub fn message(&self, alias: &str, now: DateTime<Utc>) -> String {
+        let mut message = match self.sunset_at {
+            Some(sunset_at) if self.is_retired(now) => {
+                format!("The model '{alias}' was retired on {}", sunset_at.format("%Y-%m-%d"))
+            }
+            Some(sunset_at) => format!(
+                "The model '{alias}' is deprecated and will be retired on {}",
+                sunset_at.format("%Y-%m-%d")
+            ),
+            None => format!("The model '{alias}' is deprecated"),
+        };
+        if let Some(replacement) = &self.replacement {
+            message.push_str(&format!(", please use '{replacement}' instead"));
+        }
+        message
+    }
+}
+
+/// Middleware warning about requests to deprecated aliases, and refusing those to retired ones
+pub async fn enforce_deprecation(State(table): State<watch::Receiver<RoutingTable>>, request: Request, next: Next) -> Response {
+    if !table.borrow().has_deprecations() {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => return error_response(StatusCode::BAD_REQUEST, "Failed to read request body"),
+    };
+    let deprecation = onwards::extract_model_from_request(&parts.headers, &body)
+        .ok()
+        .and_then(|model| Some((table.borrow().deprecation(&model)?.clone(), model)));
+    let request = Request::from_parts(parts, Body::from(body));
+    let Some((deprecation, model)) = deprecation else {
+        return next.run(request).await;
+    };
+
+    let now = Utc::now();
+    if deprecation.is_retired(now) {
+        return error_response(StatusCode::GONE, &deprecation.message(&model, now));
+    }
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&format!("@{}", deprecation.deprecated_at.timestamp())) {
+        headers.insert(DEPRECATION_HEADER, value);
+    }
+    if let Some(sunset_at) = deprecation.sunset_at {
+        if let Ok(value) = HeaderValue::from_str(&sunset_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()) {
+            headers.insert(SUNSET_HEADER, value);
+        }
+    }
+    let warning = format!("299 - \"{}\"", deprecation.message(&model, now).replace('"', "'"));
+    if let Ok(value) = HeaderValue::from_str(&warning) {
+        headers.insert(header::WARNING, value);
+    }
+    response
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({"error": {"message": message, "type": "invalid_request_error", "param": "model", "code": "model_retired"}})),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware::from_fn_with_state, routing::post, Router};
+    use axum_test::TestServer;
+    use chrono::Duration;
+    use serde_json::Value;
+
+    fn server(deprecations: Vec<(&str, Deprecation)>) -> TestServer {
+        let mut table = RoutingTable::default();
+        for (alias, deprecation) in deprecations {
+            table.set_deprecation(alias.to_string(), deprecation);
+        }
+        let (_, receiver) = watch::channel(table);
+        let app = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|| async { Json(json!({"object": "chat.completion"})) }),
+            )
+            .layer(from_fn_with_state(receiver, enforce_deprecation));
+        TestServer::new(app).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_models_are_served_with_a_warning() {
+        let deprecated_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let sunset_at = Utc::now() + Duration::days(30);
+        let server = server(vec![(
+            "old-model",
+            Deprecation {
+                deprecated_at,
+                sunset_at: Some(sunset_at),
+                replacement: Some("new-model".to_string()),
+            },
+        )]);
+
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "old-model", "messages": []}))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header(DEPRECATION_HEADER), "@1700000000");
+        assert_eq!(
+            response.header(SUNSET_HEADER),
+            sunset_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+        );
+        let warning = response.header(header::WARNING);
+        let warning = warning.to_str().unwrap();
+        assert!(warning.starts_with("299 - \"The model 'old-model' is deprecated and will be retired on "));
+        assert!(warning.ends_with(", please use 'new-model' instead\""));
+
+        // Other models are left alone
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "new-model", "messages": []}))
+            .await;
+        response.assert_status_ok();
+        assert!(response.maybe_header(DEPRECATION_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retired_models_are_refused() {
+        let sunset_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let server = server(vec![(
+            "old-model",
+            Deprecation {
+                deprecated_at: sunset_at - Duration::days(30),
+                sunset_at: Some(sunset_at),
+                replacement: Some("new-model".to_string()),
+            },
+        )]);
+
+        let response = server
+            .post("/v1/chat/completions")
+            .json(&json!({"model": "old-model", "messages": []}))
+            .await;
+        response.assert_status(StatusCode::GONE);
+        assert_eq!(
+            response.json::<Value>()["error"]["message"],
+            "The model 'old-model' was retired on 2023-11-14, please use 'new-model' instead"
+        );
+    }
+}
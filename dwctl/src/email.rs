@@ -76,6 +76,12 @@ impl EmailService {
         self.send_email(to_email, to_name, subject, &body).await
     }
 
+    /// Send a plain-text notification, such as a probe alert
+    pub async fn send_notification_email(&self, to_email: &str, subject: &str, message: &str) -> Result<(), Error> {
+        let body = self.create_notification_body(subject, message);
+        self.send_email(to_email, None, subject, &body).await
+    }
+
     async fn send_email(&self, to_email: &str, to_name: Option<&str>, subject: &str, body: &str) -> Result<(), Error> {
         // Create from mailbox
         let from = format!("{} <{}>", self.from_name, self.from_email)
@@ -123,6 +129,38 @@ impl EmailService {
         Ok(())
     }
 
+    fn create_notification_body(&self, subject: &str, message: &str) -> String {
+        let escape = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+        let subject = escape(subject);
+        let message = escape(message);
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <title>{subject}</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; line-height: 1.6; color: #333; }}
+        .container {{ max-width: 600px; margin: 0 auto; padding: 20px; }}
+        .footer {{ margin-top: 30px; font-size: 12px; color: #666; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <h2>{subject}</h2>
+
+        <p>{message}</p>
+
+        <div class="footer">
+            <p>This is an automated message, please do not reply to this email.</p>
+        </div>
+    </div>
+</body>
+</html>"#
+        )
+    }
+
     fn create_password_reset_body(&self, to_name: Option<&str>, reset_link: &str) -> String {
         let greeting = if let Some(name) = to_name {
             format!("Hello {name},")
@@ -204,4 +242,15 @@ mod tests {
         assert!(body.contains("Hello,"));
         assert!(body.contains("https://example.com/reset?token=abc123"));
     }
+
+    #[tokio::test]
+    async fn test_notification_email_body_is_escaped() {
+        let config = create_test_config();
+        let email_service = EmailService::new(&config).unwrap();
+
+        let body = email_service.create_notification_body("Probe failing", "Probe '<b>chat</b>' failed 3 times");
+
+        assert!(body.contains("<h2>Probe failing</h2>"));
+        assert!(body.contains("Probe '&lt;b&gt;chat&lt;/b&gt;' failed 3 times"));
+    }
 }
@@ -0,0 +1,101 @@
+//! Reporting of panics and errors to Sentry.
+//!
+//! With `error_tracking.dsn` set, panics and `error` logs are reported to the project, along
+//! with the breadcrumbs of preceding `info` and `warn` logs. Each HTTP request gets its own
+//! scope carrying the request (without sensitive headers) and, once authenticated, the user,
+//! and the fields of the enclosing spans are attached to the reports. AI proxy requests that
+//! fail with a server error are reported too, since onwards answers them without logging.
+
+use axum::{extract::Request, http::StatusCode, middleware::Next, response::Response};
+use sentry::integrations::tracing::SentryLayer;
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::{api::models::users::CurrentUser, config::ErrorTrackingConfig};
+
+/// Start reporting to the configured project, if any. Reports are sent until the returned guard
+/// is dropped, which flushes the pending ones.
+pub fn init(config: &ErrorTrackingConfig) -> Option<sentry::ClientInitGuard> {
+    let dsn = config.dsn.as_ref()?;
+    let guard = sentry::init((
+        dsn.as_str(),
+        sentry::ClientOptions {
+            release: sentry::release_name!(),
+            environment: config.environment.clone().map(Into::into),
+            sample_rate: config.sample_rate,
+            ..Default::default()
+        },
+    ));
+    Some(guard)
+}
+
+/// Tracing layer reporting `error` events, with `info` and `warn` events as breadcrumbs
+pub fn layer<S>() -> SentryLayer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    sentry::integrations::tracing::layer().enable_span_attributes()
+}
+
+/// Attach `user` to the reports of the current request
+pub fn set_user(user: &CurrentUser) {
+    sentry::configure_scope(|scope| {
+        scope.set_user(Some(sentry::User {
+            id: Some(user.id.to_string()),
+            ..Default::default()
+        }))
+    });
+}
+
+/// Middleware reporting AI proxy requests that fail with a server error. 503s are left out, as
+/// dwctl answers with them on purpose, e.g. when admission queues are full.
+pub async fn report_proxy_errors(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let response = next.run(request).await;
+    let status = response.status();
+    if status.is_server_error() && status != StatusCode::SERVICE_UNAVAILABLE {
+        tracing::error!(%method, %path, status = status.as_u16(), "AI proxy request failed");
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware::from_fn, routing::post, Router};
+    use tower::ServiceExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_report_proxy_errors() {
+        let router = Router::new()
+            .route("/chat/completions", post(|| async { StatusCode::BAD_GATEWAY }))
+            .route("/embeddings", post(|| async { StatusCode::SERVICE_UNAVAILABLE }))
+            .route("/responses", post(|| async { StatusCode::OK }))
+            .layer(from_fn(report_proxy_errors));
+
+        let events = sentry::test::with_captured_events(|| {
+            let subscriber = tracing_subscriber::registry().with(layer());
+            let _default = tracing::subscriber::set_default(subscriber);
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            runtime.block_on(async {
+                for path in ["/chat/completions", "/embeddings", "/responses"] {
+                    let request = Request::post(path).body(Body::empty()).unwrap();
+                    router.clone().oneshot(request).await.unwrap();
+                }
+            });
+        });
+
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.level, sentry::Level::Error);
+        assert_eq!(event.message.as_deref(), Some("AI proxy request failed"));
+        let fields = &event.contexts["Rust Tracing Fields"];
+        let sentry::protocol::Context::Other(fields) = fields else {
+            panic!("unexpected context {fields:?}");
+        };
+        assert_eq!(fields["path"], "/chat/completions");
+        assert_eq!(fields["status"], 502);
+    }
+}
@@ -103,7 +103,13 @@ impl Error {
                     }
                 }
                 DbError::ForeignKeyViolation { .. } => "Invalid reference to related resource".to_string(),
-                DbError::CheckViolation { .. } => "Invalid data provided".to_string(),
+                DbError::CheckViolation { constraint, table, .. } => match (table.as_deref(), constraint.as_deref()) {
+                    (Some("groups"), Some("groups_parent_cycle" | "groups_parent_not_self")) => {
+                        "A group cannot be nested under itself or one of its subgroups".to_string()
+                    }
+                    (Some("groups"), Some("groups_everyone_not_nested")) => "The Everyone group cannot be nested".to_string(),
+                    _ => "Invalid data provided".to_string(),
+                },
                 DbError::ProtectedEntity {
                     operation,
                     entity_type,
@@ -0,0 +1,464 @@
+//! Endpoint credentials kept in an external secret manager.
+//!
+//! An inference endpoint's API key can be a reference to a secret rather than the key itself, so
+//! the credential never lives in the gateway's database: `vault://<path>#<field>` reads a field
+//! of a HashiCorp Vault KV secret, and an AWS Secrets Manager ARN reads a secret's string value,
+//! or with `#<field>` a field of it as JSON. References are resolved with [`resolve`] where the
+//! key is used (by the AI proxy's targets, model syncs and endpoint checks), and the values are
+//! cached. [`refresh`] fetches every secret referred to so far again, including ones that
+//! couldn't be fetched the first time; `sync::onwards_config` calls it on the configured interval
+//! and reloads the proxy when a value changed or was fetched for the first time, so rotated
+//! credentials and secret managers recovering from an outage are picked up without a restart.
+
+use crate::config::{AwsSecretsManagerConfig, SecretManagersConfig, VaultConfig};
+use crate::request_logging::storage::{hmac, signing_key};
+use anyhow::{anyhow, bail, Context};
+use chrono::Utc;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tracing::warn;
+use url::Url;
+
+const VAULT_SCHEME: &str = "vault://";
+const AWS_ARN_PREFIX: &str = "arn:aws:secretsmanager:";
+
+/// The secret managers configured at startup
+static EXTERNAL_SECRETS: OnceLock<ExternalSecrets> = OnceLock::new();
+
+/// Where a secret is kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretReference {
+    Vault {
+        path: String,
+        field: String,
+    },
+    AwsSecretsManager {
+        arn: String,
+        region: String,
+        field: Option<String>,
+    },
+}
+
+impl SecretReference {
+    /// The reference `value` is, or `None` if it's a plain credential
+    pub fn parse(value: &str) -> anyhow::Result<Option<Self>> {
+        if let Some(reference) = value.strip_prefix(VAULT_SCHEME) {
+            let Some((path, field)) = reference.split_once('#') else {
+                bail!("Vault references must name a field, as in vault://secret/openai#api_key");
+            };
+            if path.trim_matches('/').is_empty() || field.is_empty() {
+                bail!("Vault references must name a path and a field, as in vault://secret/openai#api_key");
+            }
+            return Ok(Some(Self::Vault {
+                path: path.trim_matches('/').to_string(),
+                field: field.to_string(),
+            }));
+        }
+        if value.starts_with(AWS_ARN_PREFIX) {
+            let (arn, field) = match value.split_once('#') {
+                Some((arn, field)) => (arn, Some(field.to_string())),
+                None => (value, None),
+            };
+            // arn:aws:secretsmanager:<region>:<account>:secret:<name>
+            let parts: Vec<&str> = arn.split(':').collect();
+            if parts.len() < 7 || parts[3].is_empty() || parts[5] != "secret" {
+                bail!("Invalid AWS Secrets Manager ARN: {arn}");
+            }
+            return Ok(Some(Self::AwsSecretsManager {
+                arn: arn.to_string(),
+                region: parts[3].to_string(),
+                field: field.filter(|field| !field.is_empty()),
+            }));
+        }
+        Ok(None)
+    }
+}
+
+/// Clients of the configured secret managers, and the values fetched from them so far
+pub struct ExternalSecrets {
+    client: reqwest::Client,
+    config: SecretManagersConfig,
+    /// Every reference resolved so far, whether or not its secret could be fetched
+    references: Mutex<HashSet<String>>,
+    cache: Mutex<HashMap<String, String>>,
+}
+
+impl ExternalSecrets {
+    pub fn new(config: SecretManagersConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(config.request_timeout).build()?,
+            config,
+            references: Mutex::new(HashSet::new()),
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The secret `value` refers to, or `value` itself if it isn't a reference
+    pub async fn resolve(&self, value: &str) -> anyhow::Result<String> {
+        let Some(reference) = SecretReference::parse(value)? else {
+            return Ok(value.to_string());
+        };
+        if let Some(secret) = self.cache.lock().unwrap().get(value) {
+            return Ok(secret.clone());
+        }
+        self.references.lock().unwrap().insert(value.to_string());
+        let secret = self.fetch(&reference).await?;
+        self.cache.lock().unwrap().insert(value.to_string(), secret.clone());
+        Ok(secret)
+    }
+
+    /// Fetch every referenced secret again, including those that couldn't be fetched before.
+    /// Secrets that can't be fetched keep their cached value, if any. Returns whether any value
+    /// changed or was fetched for the first time.
+    pub async fn refresh(&self) -> bool {
+        let references: Vec<String> = self.references.lock().unwrap().iter().cloned().collect();
+        let mut changed = false;
+        for value in references {
+            let fetched = match SecretReference::parse(&value) {
+                Ok(Some(reference)) => self.fetch(&reference).await,
+                _ => continue,
+            };
+            match fetched {
+                Ok(fetched) => {
+                    let previous = self.cache.lock().unwrap().insert(value, fetched.clone());
+                    changed |= previous.is_none_or(|previous| previous != fetched);
+                }
+                Err(e) => warn!("Failed to refresh secret {}: {:#}", value, e),
+            }
+        }
+        changed
+    }
+
+    /// Whether any secret has been referred to, so there's something to refresh
+    pub fn in_use(&self) -> bool {
+        !self.references.lock().unwrap().is_empty()
+    }
+
+    async fn fetch(&self, reference: &SecretReference) -> anyhow::Result<String> {
+        match reference {
+            SecretReference::Vault { path, field } => {
+                let vault = self
+                    .config
+                    .vault
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("Endpoint API key refers to Vault, but secret_managers.vault isn't configured"))?;
+                self.fetch_from_vault(vault, path, field).await
+            }
+            SecretReference::AwsSecretsManager { arn, region, field } => {
+                let aws =
+                    self.config.aws.as_ref().ok_or_else(|| {
+                        anyhow!("Endpoint API key refers to AWS Secrets Manager, but secret_managers.aws isn't configured")
+                    })?;
+                self.fetch_from_aws(aws, arn, region, field.as_deref()).await
+            }
+        }
+    }
+
+    async fn fetch_from_vault(&self, vault: &VaultConfig, path: &str, field: &str) -> anyhow::Result<String> {
+        let token = match (&vault.token, &vault.token_file) {
+            (_, Some(path)) => std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read the Vault token from {}", path.display()))?
+                .trim()
+                .to_string(),
+            (Some(token), None) => token.clone(),
+            (None, None) => bail!("secret_managers.vault needs a token or a token_file"),
+        };
+        // KV version 2 serves secrets under `<mount>/data/<path>`
+        let api_path = match (vault.kv_version, path.split_once('/')) {
+            (2, Some((mount, rest))) => format!("{mount}/data/{rest}"),
+            _ => path.to_string(),
+        };
+
+        let mut request = self
+            .client
+            .get(vault.address.join(&format!("v1/{api_path}"))?)
+            .header("X-Vault-Token", token);
+        if let Some(namespace) = &vault.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            bail!("Vault returned {} for {path}", response.status());
+        }
+        let body: Value = response.json().await?;
+        let data = if vault.kv_version == 2 {
+            &body["data"]["data"]
+        } else {
+            &body["data"]
+        };
+        string_field(data, field).ok_or_else(|| anyhow!("Vault secret {path} has no string field '{field}'"))
+    }
+
+    async fn fetch_from_aws(&self, aws: &AwsSecretsManagerConfig, arn: &str, region: &str, field: Option<&str>) -> anyhow::Result<String> {
+        let endpoint: Url = match &aws.endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => format!("https://secretsmanager.{region}.amazonaws.com/").parse()?,
+        };
+        let host = endpoint.host_str().ok_or_else(|| anyhow!("Secrets Manager endpoint has no host"))?;
+        let host = match endpoint.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_string(),
+        };
+        let body = serde_json::to_vec(&json!({ "SecretId": arn }))?;
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        // Signature Version 4, as for the S3 body store
+        let target = "secretsmanager.GetSecretValue";
+        let content_type = "application/x-amz-json-1.1";
+        let mut canonical_headers = format!("content-type:{content_type}\nhost:{host}\nx-amz-date:{amz_date}\n");
+        let mut signed_headers = "content-type;host;x-amz-date".to_string();
+        if let Some(session_token) = &aws.session_token {
+            canonical_headers.push_str(&format!("x-amz-security-token:{session_token}\n"));
+            signed_headers.push_str(";x-amz-security-token");
+        }
+        canonical_headers.push_str(&format!("x-amz-target:{target}\n"));
+        signed_headers.push_str(";x-amz-target");
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let canonical_request = format!("POST\n/\n\n{canonical_headers}\n{signed_headers}\n{payload_hash}");
+        let scope = format!("{date}/{region}/secretsmanager/aws4_request");
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&aws.secret_access_key, &date, region, "secretsmanager");
+        let signature = hex::encode(hmac(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            aws.access_key_id
+        );
+
+        let mut request = self
+            .client
+            .post(endpoint)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-target", target)
+            .header(reqwest::header::AUTHORIZATION, authorization);
+        if let Some(session_token) = &aws.session_token {
+            request = request.header("x-amz-security-token", session_token);
+        }
+        let response = request.body(body).send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!("Secrets Manager returned {status} for {arn}: {body}");
+        }
+        let body: Value = response.json().await?;
+        let secret = body["SecretString"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Secret {arn} has no string value"))?;
+        match field {
+            None => Ok(secret.to_string()),
+            Some(field) => {
+                let secret: Value = serde_json::from_str(secret).with_context(|| format!("Secret {arn} isn't JSON"))?;
+                string_field(&secret, field).ok_or_else(|| anyhow!("Secret {arn} has no string field '{field}'"))
+            }
+        }
+    }
+}
+
+fn string_field(data: &Value, field: &str) -> Option<String> {
+    data.get(field).and_then(Value::as_str).map(str::to_string)
+}
+
+/// Set up the secret managers endpoint API keys can refer to. Called once at startup.
+pub fn init(config: &SecretManagersConfig) -> anyhow::Result<()> {
+    EXTERNAL_SECRETS
+        .set(ExternalSecrets::new(config.clone())?)
+        .map_err(|_| anyhow!("External secrets are already initialized"))
+}
+
+/// The secret an endpoint API key refers to, or the key itself if it isn't a reference
+pub async fn resolve(value: &str) -> anyhow::Result<String> {
+    match EXTERNAL_SECRETS.get() {
+        Some(secrets) => secrets.resolve(value).await,
+        None if SecretReference::parse(value)?.is_some() => bail!("Endpoint API key refers to a secret manager, but none is configured"),
+        None => Ok(value.to_string()),
+    }
+}
+
+/// Fetch the referenced secrets again, returning whether any changed
+pub async fn refresh() -> bool {
+    match EXTERNAL_SECRETS.get() {
+        Some(secrets) if secrets.in_use() => secrets.refresh().await,
+        _ => false,
+    }
+}
+
+/// How often referenced secrets are fetched again, if any are in use
+pub fn refresh_interval() -> Option<Duration> {
+    EXTERNAL_SECRETS.get().map(|secrets| secrets.config.refresh_interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::State,
+        http::{HeaderMap, StatusCode},
+        routing::{get, post},
+        Json, Router,
+    };
+    use std::sync::Arc;
+
+    type Secret = Arc<Mutex<String>>;
+
+    async fn spawn(app: Router) -> Url {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{addr}/").parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_references() {
+        assert_eq!(SecretReference::parse("sk-plain").unwrap(), None);
+        assert_eq!(
+            SecretReference::parse("vault://secret/openai#key").unwrap(),
+            Some(SecretReference::Vault {
+                path: "secret/openai".to_string(),
+                field: "key".to_string()
+            })
+        );
+        assert!(SecretReference::parse("vault://secret/openai").is_err());
+        assert_eq!(
+            SecretReference::parse("arn:aws:secretsmanager:eu-west-1:123456789012:secret:openai-AbCdEf#api_key").unwrap(),
+            Some(SecretReference::AwsSecretsManager {
+                arn: "arn:aws:secretsmanager:eu-west-1:123456789012:secret:openai-AbCdEf".to_string(),
+                region: "eu-west-1".to_string(),
+                field: Some("api_key".to_string())
+            })
+        );
+        assert!(SecretReference::parse("arn:aws:secretsmanager:eu-west-1").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_and_refresh_vault_secrets() {
+        async fn read_secret(State(secret): State<Secret>, headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+            if headers.get("x-vault-token").is_none_or(|token| token != "vault-token") {
+                return Err(StatusCode::FORBIDDEN);
+            }
+            let key = secret.lock().unwrap().clone();
+            Ok(Json(json!({"data": {"data": {"key": key}, "metadata": {"version": 1}}})))
+        }
+        let secret = Secret::new(Mutex::new("sk-first".to_string()));
+        let address = spawn(
+            Router::new()
+                .route("/v1/secret/data/openai", get(read_secret))
+                .with_state(secret.clone()),
+        )
+        .await;
+
+        let secrets = ExternalSecrets::new(SecretManagersConfig {
+            vault: Some(VaultConfig {
+                address,
+                token: Some("vault-token".to_string()),
+                token_file: None,
+                namespace: None,
+                kv_version: 2,
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(secrets.resolve("sk-plain").await.unwrap(), "sk-plain");
+        assert!(!secrets.in_use());
+        assert_eq!(secrets.resolve("vault://secret/openai#key").await.unwrap(), "sk-first");
+        assert!(secrets.resolve("vault://secret/openai#missing").await.is_err());
+
+        // Values are cached until refreshed
+        *secret.lock().unwrap() = "sk-second".to_string();
+        assert_eq!(secrets.resolve("vault://secret/openai#key").await.unwrap(), "sk-first");
+        assert!(secrets.refresh().await);
+        assert_eq!(secrets.resolve("vault://secret/openai#key").await.unwrap(), "sk-second");
+        assert!(!secrets.refresh().await);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_aws_secrets() {
+        // Secrets Manager requests are JSON, but not `application/json`, so the mock parses the body itself
+        async fn get_secret_value(headers: HeaderMap, body: axum::body::Bytes) -> Result<Json<Value>, StatusCode> {
+            let body: Value = serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+            let signed = headers
+                .get("authorization")
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| {
+                    value.starts_with("AWS4-HMAC-SHA256 Credential=AKID/") && value.contains("/eu-west-1/secretsmanager/")
+                });
+            if !signed
+                || headers
+                    .get("x-amz-target")
+                    .is_none_or(|target| target != "secretsmanager.GetSecretValue")
+            {
+                return Err(StatusCode::FORBIDDEN);
+            }
+            Ok(Json(json!({
+                "ARN": body["SecretId"],
+                "SecretString": "{\"api_key\": \"sk-from-aws\"}"
+            })))
+        }
+        let endpoint = spawn(Router::new().route("/", post(get_secret_value))).await;
+
+        let secrets = ExternalSecrets::new(SecretManagersConfig {
+            aws: Some(AwsSecretsManagerConfig {
+                access_key_id: "AKID".to_string(),
+                secret_access_key: "secret".to_string(),
+                session_token: None,
+                endpoint: Some(endpoint),
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+        let arn = "arn:aws:secretsmanager:eu-west-1:123456789012:secret:openai-AbCdEf";
+        assert_eq!(secrets.resolve(&format!("{arn}#api_key")).await.unwrap(), "sk-from-aws");
+        assert_eq!(secrets.resolve(arn).await.unwrap(), "{\"api_key\": \"sk-from-aws\"}");
+
+        // References to unconfigured secret managers fail
+        assert!(secrets.resolve("vault://secret/openai#key").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_retries_unfetched_secrets() {
+        type MaybeSecret = Arc<Mutex<Option<String>>>;
+        async fn read_secret(State(secret): State<MaybeSecret>) -> Result<Json<Value>, StatusCode> {
+            let key = secret.lock().unwrap().clone().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+            Ok(Json(json!({"data": {"key": key}})))
+        }
+        let secret = MaybeSecret::default();
+        let address = spawn(
+            Router::new()
+                .route("/v1/secret/openai", get(read_secret))
+                .with_state(secret.clone()),
+        )
+        .await;
+
+        let secrets = ExternalSecrets::new(SecretManagersConfig {
+            vault: Some(VaultConfig {
+                address,
+                token: Some("vault-token".to_string()),
+                token_file: None,
+                namespace: None,
+                kv_version: 1,
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+
+        // A secret that can't be fetched at first is still refreshed
+        assert!(secrets.resolve("vault://secret/openai#key").await.is_err());
+        assert!(secrets.in_use());
+        assert!(!secrets.refresh().await);
+
+        // and counts as changed once it can be
+        *secret.lock().unwrap() = Some("sk-late".to_string());
+        assert!(secrets.refresh().await);
+        assert_eq!(secrets.resolve("vault://secret/openai#key").await.unwrap(), "sk-late");
+        assert!(!secrets.refresh().await);
+    }
+}
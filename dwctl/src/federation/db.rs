@@ -0,0 +1,96 @@
+//! Database access layer for federation peers and the local instance summary.
+
+use crate::api::models::federation::InstanceHealth;
+use crate::db::models::federation::FederationPeer;
+use crate::errors::Error as AppError;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Everything needed to register a peer
+pub struct NewFederationPeer {
+    pub name: String,
+    pub url: String,
+    pub token: String,
+    pub created_by: Uuid,
+}
+
+/// Database access layer for federation peers.
+pub struct FederationPeerManager;
+
+impl FederationPeerManager {
+    pub async fn create(pool: &PgPool, peer: NewFederationPeer) -> Result<FederationPeer, AppError> {
+        let result = sqlx::query_as::<_, FederationPeer>(
+            r#"
+            INSERT INTO federation_peers (name, url, token, created_by)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(&peer.name)
+        .bind(&peer.url)
+        .bind(&peer.token)
+        .bind(peer.created_by)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => AppError::Conflict {
+                message: format!("A peer named '{}' already exists", peer.name),
+                conflicts: None,
+            },
+            e => anyhow::anyhow!("Failed to create federation peer: {}", e).into(),
+        })?;
+
+        Ok(result)
+    }
+
+    /// List peers by name
+    pub async fn list(pool: &PgPool) -> Result<Vec<FederationPeer>, AppError> {
+        let peers = sqlx::query_as::<_, FederationPeer>("SELECT * FROM federation_peers ORDER BY name")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to list federation peers: {}", e))?;
+
+        Ok(peers)
+    }
+
+    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM federation_peers WHERE id = $1", id)
+            .execute(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to delete federation peer: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                resource: "Federation peer".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Count the enabled deployments of this instance and those that are unhealthy
+    pub async fn local_health(pool: &PgPool) -> Result<InstanceHealth, AppError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM deployed_models WHERE deleted = false AND enabled = true) as "deployments!",
+                (SELECT COUNT(*) FROM deployment_circuit_breakers) as "open_circuits!",
+                (
+                    SELECT COUNT(*)
+                    FROM probes p
+                    WHERE p.active
+                      AND (SELECT success FROM probe_results WHERE probe_id = p.id ORDER BY executed_at DESC LIMIT 1) = false
+                ) as "failing_probes!"
+            "#
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch instance health: {}", e))?;
+
+        Ok(InstanceHealth {
+            deployments: row.deployments,
+            open_circuits: row.open_circuits,
+            failing_probes: row.failing_probes,
+        })
+    }
+}
@@ -0,0 +1,103 @@
+//! Federation of several dwctl instances.
+//!
+//! Organizations running an instance per region can register the other instances as peers of
+//! one of them and get a single view of analytics and health across all of them, without
+//! merging databases. Every instance that sets `federation.token` serves a read-only
+//! [`InstanceSummary`] at [`SUMMARY_PATH`] to callers presenting that token; [`overview`] fetches
+//! the summaries of all peers concurrently and adds them up with the local one. Peers that can't
+//! be reached are reported as such rather than failing the whole overview.
+
+pub mod db;
+
+use crate::api::models::federation::{FederationOverview, FederationSummaryQuery, FederationTotals, InstanceOverview, InstanceSummary};
+use crate::db::handlers::analytics::get_requests_aggregate;
+use crate::db::models::federation::FederationPeer;
+use crate::errors::Error;
+use crate::AppState;
+use chrono::{DateTime, Utc};
+use db::FederationPeerManager;
+use futures::future::join_all;
+use tracing::warn;
+
+/// Path at which instances serve their summary to peers
+pub const SUMMARY_PATH: &str = "/federation/v1/summary";
+
+/// Summarize this instance, with request analytics between `start` and `end`
+pub async fn local_summary(state: &AppState, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<InstanceSummary, Error> {
+    let health = FederationPeerManager::local_health(&state.db).await?;
+    let requests = match state.outlet_db {
+        Some(_) => Some(get_requests_aggregate(&state.db, start, end, None).await?),
+        None => None,
+    };
+    Ok(InstanceSummary { health, requests })
+}
+
+/// Fetch the summary of a peer, or a description of why it couldn't be fetched
+async fn fetch_summary(
+    client: &reqwest::Client,
+    peer: &FederationPeer,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<InstanceSummary, String> {
+    let query = FederationSummaryQuery {
+        timestamp_after: Some(start),
+        timestamp_before: Some(end),
+    };
+    let response = client
+        .get(format!("{}{}", peer.url.trim_end_matches('/'), SUMMARY_PATH))
+        .bearer_auth(&peer.token)
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {e}"))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Peer responded with status {status}"));
+    }
+    response.json().await.map_err(|e| format!("Invalid summary: {e}"))
+}
+
+/// Summaries of this instance and every registered peer between `start` and `end`, with their
+/// totals
+pub async fn overview(state: &AppState, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<FederationOverview, Error> {
+    let peers = FederationPeerManager::list(&state.db).await?;
+    let client = reqwest::Client::builder()
+        .timeout(state.config.federation.request_timeout)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Failed to build federation client: {}", e))?;
+
+    let (local, remote) = tokio::join!(
+        local_summary(state, start, end),
+        join_all(peers.iter().map(|peer| fetch_summary(&client, peer, start, end)))
+    );
+
+    let mut instances = vec![InstanceOverview {
+        name: "local".to_string(),
+        peer_id: None,
+        summary: Some(local?),
+        error: None,
+    }];
+    for (peer, result) in peers.into_iter().zip(remote) {
+        let (summary, error) = match result {
+            Ok(summary) => (Some(summary), None),
+            Err(e) => {
+                warn!("Failed to fetch the summary of federation peer '{}': {}", peer.name, e);
+                (None, Some(e))
+            }
+        };
+        instances.push(InstanceOverview {
+            name: peer.name,
+            peer_id: Some(peer.id),
+            summary,
+            error,
+        });
+    }
+
+    Ok(FederationOverview {
+        timestamp_after: start,
+        timestamp_before: end,
+        totals: FederationTotals::new(instances.iter().map(|instance| instance.summary.as_ref())),
+        instances,
+    })
+}
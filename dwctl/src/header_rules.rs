@@ -0,0 +1,244 @@
+//! Header injection and passthrough rules for requests forwarded to endpoints.
+//!
+//! Some upstreams need extra headers on every request, such as organization IDs or routing
+//! hints, and some shouldn't see the headers clients send. Admins give an endpoint header rules
+//! (`PUT /endpoints/{id}/headers`): headers to inject, and optionally an allowlist of the client
+//! headers to pass through. `sync::onwards_config` compiles them into [`HeaderRules`] for every
+//! target served by the endpoint, including fallbacks, weighted targets, canaries and shadows,
+//! and the [`apply_header_rules`] middleware applies them just before requests are forwarded.
+//!
+//! Without an allowlist, client headers are forwarded as before. With one, only the allowlisted
+//! headers and those the proxy needs itself ([`REQUIRED_HEADERS`]) are kept. Injected headers
+//! replace any client header of the same name. Headers the proxy sets or strips on the way
+//! upstream ([`RESERVED_HEADERS`]) can't be injected.
+
+use crate::routing::RoutingTable;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashSet;
+use tokio::sync::watch;
+use tracing::debug;
+
+/// Client headers kept whatever the passthrough allowlist: those describing the body and
+/// accepted response, and those the proxy reads to authenticate and route the request
+pub const REQUIRED_HEADERS: [&str; 9] = [
+    "host",
+    "content-type",
+    "content-length",
+    "accept",
+    "accept-encoding",
+    "user-agent",
+    "authorization",
+    "x-api-key",
+    "model-override",
+];
+
+/// Headers that can't be injected, as the proxy sets them itself or strips them before
+/// forwarding requests
+pub const RESERVED_HEADERS: [&str; 18] = [
+    "host",
+    "content-type",
+    "content-length",
+    "transfer-encoding",
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "upgrade",
+    "authorization",
+    "x-api-key",
+    "api-key",
+    "cookie",
+    "origin",
+    "referer",
+    "x-forwarded-proto",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HeaderRuleError {
+    #[error("invalid header name '{0}'")]
+    InvalidName(String),
+    #[error("invalid value for header '{0}'")]
+    InvalidValue(String),
+    #[error("header '{0}' is set or removed by the proxy and can't be injected")]
+    Reserved(String),
+}
+
+/// Compiled header rules of an endpoint
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeaderRules {
+    inject: Vec<(HeaderName, HeaderValue)>,
+    passthrough: Option<HashSet<HeaderName>>,
+}
+
+fn header_name(name: &str) -> Result<HeaderName, HeaderRuleError> {
+    HeaderName::from_bytes(name.as_bytes()).map_err(|_| HeaderRuleError::InvalidName(name.to_string()))
+}
+
+impl HeaderRules {
+    pub fn new<'a>(inject: impl IntoIterator<Item = (&'a str, &'a str)>, passthrough: Option<&[String]>) -> Result<Self, HeaderRuleError> {
+        let inject = inject
+            .into_iter()
+            .map(|(name, value)| {
+                let header = header_name(name)?;
+                if RESERVED_HEADERS.contains(&header.as_str()) {
+                    return Err(HeaderRuleError::Reserved(header.to_string()));
+                }
+                let value = HeaderValue::from_str(value).map_err(|_| HeaderRuleError::InvalidValue(header.to_string()))?;
+                Ok((header, value))
+            })
+            .collect::<Result<_, _>>()?;
+        let passthrough = passthrough
+            .map(|names| names.iter().map(|name| header_name(name)).collect::<Result<_, _>>())
+            .transpose()?;
+        Ok(Self { inject, passthrough })
+    }
+
+    /// Whether the rules change requests at all
+    pub fn is_empty(&self) -> bool {
+        self.inject.is_empty() && self.passthrough.is_none()
+    }
+
+    /// Drop the client headers that aren't allowed through, then add the injected ones
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        if let Some(passthrough) = &self.passthrough {
+            let dropped: Vec<HeaderName> = headers
+                .keys()
+                .filter(|name| !passthrough.contains(*name) && !REQUIRED_HEADERS.contains(&name.as_str()))
+                .cloned()
+                .collect();
+            for name in dropped {
+                headers.remove(name);
+            }
+        }
+        for (name, value) in &self.inject {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+}
+
+/// Middleware applying the header rules of the target a request is for, if any
+pub async fn apply_header_rules(State(table): State<watch::Receiver<RoutingTable>>, request: Request, next: Next) -> Response {
+    if !table.borrow().has_header_rules() {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Failed to read request body").into_response(),
+    };
+
+    if let Ok(model) = onwards::extract_model_from_request(&parts.headers, &body) {
+        if let Some(rules) = table.borrow().header_rules(&model) {
+            rules.apply(&mut parts.headers);
+            debug!("Applied header rules to request for '{}'", model);
+        }
+    }
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware::from_fn_with_state, routing::post, Json, Router};
+    use axum_test::TestServer;
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_apply() {
+        let passthrough = vec!["x-trace-id".to_string()];
+        let rules = HeaderRules::new([("openai-organization", "org-123")], Some(&passthrough)).unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+        headers.insert("authorization", HeaderValue::from_static("Bearer sk-test"));
+        headers.insert("x-trace-id", HeaderValue::from_static("abc"));
+        headers.insert("x-client-secret", HeaderValue::from_static("hush"));
+        headers.insert("openai-organization", HeaderValue::from_static("org-client"));
+        rules.apply(&mut headers);
+
+        assert_eq!(headers["content-type"], "application/json");
+        assert_eq!(headers["authorization"], "Bearer sk-test");
+        assert_eq!(headers["x-trace-id"], "abc");
+        assert!(!headers.contains_key("x-client-secret"));
+        // Injected headers replace those sent by the client
+        assert_eq!(headers["openai-organization"], "org-123");
+
+        // Without an allowlist, all client headers are kept
+        let rules = HeaderRules::new([("x-route", "eu")], None).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-client-secret", HeaderValue::from_static("hush"));
+        rules.apply(&mut headers);
+        assert_eq!(headers["x-client-secret"], "hush");
+        assert_eq!(headers["x-route"], "eu");
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        assert_eq!(
+            HeaderRules::new([("bad header", "value")], None),
+            Err(HeaderRuleError::InvalidName("bad header".to_string()))
+        );
+        assert_eq!(
+            HeaderRules::new([("x-route", "line\nbreak")], None),
+            Err(HeaderRuleError::InvalidValue("x-route".to_string()))
+        );
+        assert_eq!(
+            HeaderRules::new([("Authorization", "Bearer other")], None),
+            Err(HeaderRuleError::Reserved("authorization".to_string()))
+        );
+        assert!(HeaderRules::new([], Some(&["not valid".to_string()])).is_err());
+        assert!(HeaderRules::new([], None).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_apply_header_rules_middleware() {
+        let mut table = RoutingTable::default();
+        table.set_header_rules(
+            "alias::fallback-1".to_string(),
+            HeaderRules::new([("x-route", "eu")], Some(&[])).unwrap(),
+        );
+        let (_sender, receiver) = watch::channel(table);
+
+        // Echo back the headers the upstream receives
+        let upstream = Router::new()
+            .route(
+                "/chat/completions",
+                post(|headers: HeaderMap| async move {
+                    let headers: BTreeMap<String, String> = headers
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), value.to_str().unwrap().to_string()))
+                        .collect();
+                    Json(headers)
+                }),
+            )
+            .layer(from_fn_with_state(receiver, apply_header_rules));
+        let server = TestServer::new(upstream).unwrap();
+
+        let request = |model: &str| {
+            server
+                .post("/chat/completions")
+                .add_header("x-client-secret", "hush")
+                .json(&json!({"model": model, "messages": []}))
+        };
+
+        // Only requests for targets on endpoints with rules are changed
+        let headers: Value = request("alias").await.json();
+        assert_eq!(headers["x-client-secret"], "hush");
+        assert!(headers.get("x-route").is_none());
+
+        let headers: Value = request("alias::fallback-1").await.json();
+        assert!(headers.get("x-client-secret").is_none());
+        assert_eq!(headers["x-route"], "eu");
+        assert_eq!(headers["content-type"], "application/json");
+    }
+}
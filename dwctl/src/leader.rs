@@ -0,0 +1,298 @@
+//! Fencing for leader-run background jobs.
+//!
+//! Leadership is an advisory lock held on a dedicated connection (see `leader_election_task`).
+//! If that connection is dropped, Postgres releases the lock straight away and another replica
+//! can become leader, but the old leader only notices at its next check. In that window both
+//! replicas would run the leader's jobs.
+//!
+//! To prevent this, each new leader takes a fencing token: a counter per lock, incremented in
+//! the database whenever leadership changes hands. Jobs call [`LeaderFence::is_current`] before
+//! doing any work and stop once a newer token has been issued.
+//!
+//! The fence also exports this replica's leadership, and the runs of the jobs it guards, as
+//! Prometheus metrics, so a split brain (two replicas reporting themselves leader) or a stalled
+//! job shows up without reading the logs.
+
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry};
+use sqlx::{PgConnection, PgPool};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Leader election lock ID: 0x44574354_50524F42 (DWCT_PROB in hex for "dwctl probes")
+pub const LEADER_LOCK_ID: i64 = 0x4457_4354_5052_4F42_i64;
+
+/// Marker for "no token held"; issued tokens start at 1
+const NO_TOKEN: i64 = 0;
+
+/// Prometheus instruments describing this replica's leadership and the jobs it runs as leader
+#[derive(Clone, Debug)]
+struct LeaderMetrics {
+    /// 1 while this replica holds the newest fencing token, 0 otherwise
+    is_leader: IntGauge,
+    /// The fencing token this replica holds, 0 for none
+    fencing_token: IntGauge,
+    /// Changes in leadership by transition: gained, lost (the lock's connection died) or
+    /// fenced (another replica took a newer token)
+    transitions: IntCounterVec,
+    /// Seconds each run of a leader-run job took, by job and outcome
+    job_duration_seconds: HistogramVec,
+    /// When each leader-run job last completed successfully, as a Unix timestamp
+    job_last_success: IntGaugeVec,
+}
+
+impl LeaderMetrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            is_leader: IntGauge::new("dwctl_leader_is_leader", "Whether this replica is the leader")?,
+            fencing_token: IntGauge::new("dwctl_leader_fencing_token", "Leader fencing token held by this replica")?,
+            transitions: IntCounterVec::new(
+                Opts::new("dwctl_leader_transitions_total", "Changes in this replica's leadership"),
+                &["transition"],
+            )?,
+            job_duration_seconds: HistogramVec::new(
+                HistogramOpts::new("dwctl_leader_job_duration_seconds", "Time runs of leader-run jobs took")
+                    .buckets(vec![0.1, 0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0]),
+                &["job", "outcome"],
+            )?,
+            job_last_success: IntGaugeVec::new(
+                Opts::new(
+                    "dwctl_leader_job_last_success_timestamp_seconds",
+                    "When each leader-run job last completed successfully",
+                ),
+                &["job"],
+            )?,
+        })
+    }
+
+    fn register(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        registry.register(Box::new(self.is_leader.clone()))?;
+        registry.register(Box::new(self.fencing_token.clone()))?;
+        registry.register(Box::new(self.transitions.clone()))?;
+        registry.register(Box::new(self.job_duration_seconds.clone()))?;
+        registry.register(Box::new(self.job_last_success.clone()))?;
+        Ok(())
+    }
+
+    fn set_token(&self, token: i64) {
+        self.is_leader.set((token != NO_TOKEN).into());
+        self.fencing_token.set(token);
+    }
+}
+
+/// This replica's fencing token for a leader lock, shared with the jobs it guards
+#[derive(Clone, Debug)]
+pub struct LeaderFence {
+    pool: PgPool,
+    lock_id: i64,
+    token: Arc<AtomicI64>,
+    metrics: LeaderMetrics,
+}
+
+impl LeaderFence {
+    pub fn new(pool: PgPool, lock_id: i64) -> Self {
+        Self {
+            pool,
+            lock_id,
+            token: Arc::new(AtomicI64::new(NO_TOKEN)),
+            metrics: LeaderMetrics::new().expect("leader metrics are statically valid"),
+        }
+    }
+
+    /// Register the leadership and job metrics with `registry`
+    pub fn register_metrics(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        self.metrics.register(registry)
+    }
+
+    /// Record a run of the leader-run `job`
+    pub fn observe_job(&self, job: &str, duration: Duration, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.metrics
+            .job_duration_seconds
+            .with_label_values(&[job, outcome])
+            .observe(duration.as_secs_f64());
+        if success {
+            self.metrics
+                .job_last_success
+                .with_label_values(&[job])
+                .set(chrono::Utc::now().timestamp());
+        }
+    }
+
+    /// Take a new fencing token, invalidating the tokens of any previous leaders.
+    ///
+    /// Call this right after acquiring the lock, before starting any jobs.
+    pub async fn acquire(&self, conn: &mut PgConnection) -> anyhow::Result<i64> {
+        let token = sqlx::query_scalar!(
+            r#"
+            INSERT INTO leader_fencing_tokens (lock_id, token)
+            VALUES ($1, 1)
+            ON CONFLICT (lock_id) DO UPDATE SET
+                token = leader_fencing_tokens.token + 1,
+                acquired_at = NOW()
+            RETURNING token
+            "#,
+            self.lock_id
+        )
+        .fetch_one(conn)
+        .await?;
+
+        self.token.store(token, Ordering::SeqCst);
+        self.metrics.set_token(token);
+        self.metrics.transitions.with_label_values(&["gained"]).inc();
+        tracing::info!("Acquired leader fencing token {}", token);
+        Ok(token)
+    }
+
+    /// Give up the token (called when losing leadership)
+    pub fn release(&self) {
+        if self.token.swap(NO_TOKEN, Ordering::SeqCst) != NO_TOKEN {
+            self.metrics.set_token(NO_TOKEN);
+            self.metrics.transitions.with_label_values(&["lost"]).inc();
+        }
+    }
+
+    /// The token this replica holds, if any
+    pub fn token(&self) -> Option<i64> {
+        match self.token.load(Ordering::SeqCst) {
+            NO_TOKEN => None,
+            token => Some(token),
+        }
+    }
+
+    /// Whether this replica still holds the newest token.
+    ///
+    /// Errs on the side of not running: if the token can't be checked, it's treated as stale.
+    pub async fn is_current(&self) -> bool {
+        let Some(token) = self.token() else {
+            return false;
+        };
+
+        match sqlx::query_scalar!("SELECT token FROM leader_fencing_tokens WHERE lock_id = $1", self.lock_id)
+            .fetch_optional(&self.pool)
+            .await
+        {
+            Ok(Some(latest)) if latest == token => true,
+            Ok(latest) => {
+                // Report each newer token once, however many jobs find it
+                if self.metrics.is_leader.get() == 1 {
+                    self.metrics.is_leader.set(0);
+                    self.metrics.transitions.with_label_values(&["fenced"]).inc();
+                }
+                tracing::warn!(
+                    "Leader fencing token {} is stale (latest is {:?}); another replica has taken over",
+                    token,
+                    latest
+                );
+                false
+            }
+            Err(e) => {
+                tracing::error!("Failed to check leader fencing token: {}", e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_new_leader_fences_out_previous_leader(pool: PgPool) {
+        let replica_a = LeaderFence::new(pool.clone(), LEADER_LOCK_ID);
+        let replica_b = LeaderFence::new(pool.clone(), LEADER_LOCK_ID);
+        assert!(!replica_a.is_current().await, "no token before acquiring");
+
+        let mut conn = pool.acquire().await.unwrap();
+        let first = replica_a.acquire(&mut conn).await.unwrap();
+        assert!(replica_a.is_current().await);
+
+        let second = replica_b.acquire(&mut conn).await.unwrap();
+        assert!(second > first);
+        assert!(replica_b.is_current().await);
+        assert!(!replica_a.is_current().await);
+        assert!(!replica_a.is_current().await);
+
+        // The fenced out replica stops reporting itself leader, counting the change once
+        assert_eq!(replica_a.metrics.is_leader.get(), 0);
+        assert_eq!(replica_a.metrics.transitions.with_label_values(&["fenced"]).get(), 1);
+        assert_eq!(replica_b.metrics.is_leader.get(), 1);
+        assert_eq!(replica_b.metrics.fencing_token.get(), second);
+
+        replica_b.release();
+        assert_eq!(replica_b.token(), None);
+        assert!(!replica_b.is_current().await);
+        assert_eq!(replica_b.metrics.is_leader.get(), 0);
+        assert_eq!(replica_b.metrics.transitions.with_label_values(&["lost"]).get(), 1);
+
+        let registry = Registry::new();
+        replica_b.register_metrics(&registry).unwrap();
+        replica_b.observe_job("endpoint_sync", Duration::from_millis(250), true);
+        let families = registry.gather();
+        let last_success = families
+            .iter()
+            .find(|m| m.get_name() == "dwctl_leader_job_last_success_timestamp_seconds")
+            .unwrap();
+        assert!(last_success.get_metric()[0].get_gauge().get_value() > 0.0);
+    }
+
+    #[sqlx::test]
+    async fn test_split_brain_after_leader_connection_drops(pool: PgPool) {
+        let try_lock = "SELECT pg_try_advisory_lock($1)";
+
+        // Replica A becomes leader, holding the lock on a dedicated connection
+        let replica_a = LeaderFence::new(pool.clone(), LEADER_LOCK_ID);
+        let mut conn_a = pool.acquire().await.unwrap();
+        let locked: bool = sqlx::query_scalar(try_lock)
+            .bind(LEADER_LOCK_ID)
+            .fetch_one(&mut *conn_a)
+            .await
+            .unwrap();
+        assert!(locked);
+        replica_a.acquire(&mut conn_a).await.unwrap();
+        let pid_a: i32 = sqlx::query_scalar("SELECT pg_backend_pid()").fetch_one(&mut *conn_a).await.unwrap();
+
+        // Replica B can't take the lock while A's connection is alive
+        let replica_b = LeaderFence::new(pool.clone(), LEADER_LOCK_ID);
+        let mut conn_b = pool.acquire().await.unwrap();
+        let locked: bool = sqlx::query_scalar(try_lock)
+            .bind(LEADER_LOCK_ID)
+            .fetch_one(&mut *conn_b)
+            .await
+            .unwrap();
+        assert!(!locked);
+
+        // A's connection drops, releasing the lock, but A hasn't noticed yet
+        let terminated: bool = sqlx::query_scalar("SELECT pg_terminate_backend($1)")
+            .bind(pid_a)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(terminated);
+        assert!(replica_a.token().is_some());
+
+        // B takes over
+        let mut locked = false;
+        for _ in 0..50 {
+            locked = sqlx::query_scalar(try_lock)
+                .bind(LEADER_LOCK_ID)
+                .fetch_one(&mut *conn_b)
+                .await
+                .unwrap();
+            if locked {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(locked, "lock should be released when the leader's connection drops");
+        replica_b.acquire(&mut conn_b).await.unwrap();
+
+        // A still believes it's leader, but its jobs see the stale token and stand down
+        assert!(replica_a.token().is_some());
+        assert!(!replica_a.is_current().await);
+        assert!(replica_b.is_current().await);
+
+        conn_a.close_on_drop();
+    }
+}
@@ -0,0 +1,181 @@
+//! Database access layer for load tests.
+//!
+//! Load tests are recorded as soon as they start and their report is updated while
+//! they run, so any replica can serve the status of a load test and forward stop
+//! requests to the replica running it through the `stop_requested` flag.
+
+use crate::db::models::load_tests::{LoadTest, LoadTestReport};
+use crate::errors::Error as AppError;
+use sqlx::types::Json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Everything needed to start a load test
+pub struct NewLoadTest {
+    pub deployment_id: Uuid,
+    pub created_by: Uuid,
+    pub total_requests: i32,
+    pub concurrency: i32,
+    pub requests_per_second: i32,
+    pub max_duration_seconds: i32,
+    pub request_body: Option<serde_json::Value>,
+}
+
+/// Deployment details needed to send traffic to it through the AI proxy
+pub struct LoadTestTarget {
+    pub alias: String,
+    pub model_type: Option<String>,
+    pub system_api_key: String,
+}
+
+/// Database access layer for load tests.
+pub struct LoadTestManager;
+
+impl LoadTestManager {
+    /// Fetch the alias and type of a deployment, along with the system API key used to call it
+    pub async fn get_target(pool: &PgPool, deployment_id: Uuid) -> Result<LoadTestTarget, AppError> {
+        let target = sqlx::query_as!(
+            LoadTestTarget,
+            r#"
+            SELECT
+                d.alias,
+                d.type as model_type,
+                ak.secret as system_api_key
+            FROM deployed_models d
+            CROSS JOIN api_keys ak
+            WHERE d.id = $1 AND d.deleted = false AND ak.id = '00000000-0000-0000-0000-000000000000'::uuid
+            "#,
+            deployment_id
+        )
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch load test target: {}", e))?
+        .ok_or_else(|| AppError::NotFound {
+            resource: "Deployment".to_string(),
+            id: deployment_id.to_string(),
+        })?;
+
+        Ok(LoadTestTarget {
+            system_api_key: crate::crypto::decrypt_secret(target.system_api_key)?,
+            ..target
+        })
+    }
+
+    /// Record a new running load test.
+    ///
+    /// Only one load test may run at a time. Load tests still marked as running well past
+    /// their maximum duration were interrupted (e.g. by a restart) and are marked as failed
+    /// first, so they don't block new load tests forever.
+    pub async fn create(pool: &PgPool, load_test: NewLoadTest) -> Result<LoadTest, AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE load_tests
+            SET status = 'failed', stop_reason = 'Interrupted before finishing', finished_at = NOW()
+            WHERE status = 'running'
+              AND started_at + make_interval(secs => max_duration_seconds) + INTERVAL '5 minutes' < NOW()
+            "#
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to clean up interrupted load tests: {}", e))?;
+
+        let result = sqlx::query_as::<_, LoadTest>(
+            r#"
+            INSERT INTO load_tests (deployment_id, created_by, total_requests, concurrency, requests_per_second, max_duration_seconds, request_body)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(load_test.deployment_id)
+        .bind(load_test.created_by)
+        .bind(load_test.total_requests)
+        .bind(load_test.concurrency)
+        .bind(load_test.requests_per_second)
+        .bind(load_test.max_duration_seconds)
+        .bind(&load_test.request_body)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => AppError::Conflict {
+                message: "Another load test is already running".to_string(),
+                conflicts: None,
+            },
+            e => anyhow::anyhow!("Failed to create load test: {}", e).into(),
+        })?;
+
+        Ok(result)
+    }
+
+    /// Get a load test by ID
+    pub async fn get(pool: &PgPool, id: Uuid) -> Result<LoadTest, AppError> {
+        let load_test = sqlx::query_as::<_, LoadTest>("SELECT * FROM load_tests WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch load test: {}", e))?
+            .ok_or_else(|| AppError::NotFound {
+                resource: "Load test".to_string(),
+                id: id.to_string(),
+            })?;
+
+        Ok(load_test)
+    }
+
+    /// List load tests, most recent first
+    pub async fn list(pool: &PgPool, limit: i64) -> Result<Vec<LoadTest>, AppError> {
+        let load_tests = sqlx::query_as::<_, LoadTest>("SELECT * FROM load_tests ORDER BY started_at DESC LIMIT $1")
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to list load tests: {}", e))?;
+
+        Ok(load_tests)
+    }
+
+    /// Ask a running load test to stop. Load tests that already finished are returned unchanged.
+    pub async fn request_stop(pool: &PgPool, id: Uuid) -> Result<LoadTest, AppError> {
+        sqlx::query!(
+            "UPDATE load_tests SET stop_requested = true WHERE id = $1 AND status = 'running'",
+            id
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to stop load test: {}", e))?;
+
+        Self::get(pool, id).await
+    }
+
+    /// Save the report of a running load test. Returns whether a stop has been requested.
+    pub async fn update_progress(pool: &PgPool, id: Uuid, report: &LoadTestReport) -> Result<bool, AppError> {
+        let stop_requested = sqlx::query_scalar!(
+            "UPDATE load_tests SET report = $2 WHERE id = $1 RETURNING stop_requested",
+            id,
+            Json(report) as _
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to update load test progress: {}", e))?;
+
+        Ok(stop_requested)
+    }
+
+    /// Record the final status and report of a load test
+    pub async fn finish(pool: &PgPool, id: Uuid, status: &str, stop_reason: Option<&str>, report: &LoadTestReport) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE load_tests
+            SET status = $2, stop_reason = $3, report = $4, finished_at = NOW()
+            WHERE id = $1
+            "#,
+            id,
+            status,
+            stop_reason,
+            Json(report) as _
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to finish load test: {}", e))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,426 @@
+//! Sends the synthetic traffic of a load test and aggregates the results.
+//!
+//! Requests are started no faster than the configured rate and never exceed the configured
+//! concurrency. The run stops early when it reaches its maximum duration, when an admin asks it
+//! to stop, or when too many requests fail - so a load test can't keep hammering a broken
+//! deployment.
+//!
+//! Load probes (see [`crate::probes::executor`]) send the same traffic without recording a load
+//! test, so they can't be stopped by an admin but are bounded by the same limits.
+
+use crate::db::models::load_tests::{LatencySummary, LoadTestReport};
+use crate::load_tests::db::LoadTestManager;
+use reqwest::Client;
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tokio::time::{interval, sleep_until, MissedTickBehavior};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// How often the report of a running load test is saved and stop requests are checked
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The error rate isn't checked until this many requests have completed
+const MIN_REQUESTS_FOR_ERROR_RATE: u64 = 20;
+
+/// Upper bound on how long a single request may take
+const MAX_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What to send and how hard
+#[derive(Debug, Clone)]
+pub struct LoadTestPlan {
+    /// Full URL requests are sent to
+    pub url: String,
+    pub api_key: String,
+    pub body: serde_json::Value,
+    pub total_requests: u32,
+    pub concurrency: u32,
+    pub requests_per_second: u32,
+    pub max_duration: Duration,
+    /// Fraction of failed requests (excluding 429s) at which the load test is stopped
+    pub max_error_rate: f64,
+}
+
+/// How a load test ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Completed,
+    StoppedByUser,
+    ReachedMaxDuration,
+    ErrorRateExceeded(f64),
+}
+
+impl Outcome {
+    /// Status recorded for the load test
+    pub fn status(&self) -> &'static str {
+        match self {
+            Outcome::Completed => "completed",
+            Outcome::StoppedByUser | Outcome::ReachedMaxDuration => "stopped",
+            Outcome::ErrorRateExceeded(_) => "failed",
+        }
+    }
+
+    pub fn stop_reason(&self, plan: &LoadTestPlan) -> Option<String> {
+        match self {
+            Outcome::Completed => None,
+            Outcome::StoppedByUser => Some("Stopped by an admin".to_string()),
+            Outcome::ReachedMaxDuration => Some(format!("Reached the maximum duration of {}s", plan.max_duration.as_secs())),
+            Outcome::ErrorRateExceeded(rate) => Some(format!(
+                "Error rate of {:.0}% exceeded the limit of {:.0}%",
+                rate * 100.0,
+                plan.max_error_rate * 100.0
+            )),
+        }
+    }
+}
+
+/// Result of a single request
+struct RequestResult {
+    /// None if no response was received
+    status: Option<u16>,
+    latency: Duration,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+/// Running totals for a load test
+#[derive(Default)]
+struct Stats {
+    report: LoadTestReport,
+    latencies_ms: Vec<u64>,
+}
+
+impl Stats {
+    fn record(&mut self, result: RequestResult) {
+        let report = &mut self.report;
+        report.completed_requests += 1;
+
+        let status = result.status.map(|s| s.to_string()).unwrap_or_else(|| "error".to_string());
+        *report.status_codes.entry(status).or_default() += 1;
+
+        match result.status {
+            Some(status) if (200..300).contains(&status) => {
+                report.successful_requests += 1;
+                report.prompt_tokens += result.prompt_tokens;
+                report.completion_tokens += result.completion_tokens;
+                self.latencies_ms.push(result.latency.as_millis() as u64);
+            }
+            Some(429) => report.rate_limited_requests += 1,
+            _ => report.failed_requests += 1,
+        }
+    }
+
+    /// Fraction of failed requests, ignoring rate limiting (which is the system working as intended)
+    fn error_rate(&self) -> Option<f64> {
+        let counted = self.report.completed_requests - self.report.rate_limited_requests;
+        (counted >= MIN_REQUESTS_FOR_ERROR_RATE).then(|| self.report.failed_requests as f64 / counted as f64)
+    }
+
+    fn report(&self, elapsed: Duration) -> LoadTestReport {
+        let mut report = self.report.clone();
+        report.elapsed_ms = elapsed.as_millis() as u64;
+        report.throughput_rps = if elapsed.is_zero() {
+            0.0
+        } else {
+            report.completed_requests as f64 / elapsed.as_secs_f64()
+        };
+        report.latency_ms = latency_summary(&self.latencies_ms);
+        report
+    }
+}
+
+fn latency_summary(latencies_ms: &[u64]) -> Option<LatencySummary> {
+    if latencies_ms.is_empty() {
+        return None;
+    }
+    let mut sorted = latencies_ms.to_vec();
+    sorted.sort_unstable();
+    let percentile = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+
+    Some(LatencySummary {
+        mean: sorted.iter().sum::<u64>() as f64 / sorted.len() as f64,
+        p50: percentile(0.50),
+        p95: percentile(0.95),
+        p99: percentile(0.99),
+        max: sorted[sorted.len() - 1],
+    })
+}
+
+async fn send_request(client: Client, plan: &LoadTestPlan) -> RequestResult {
+    let start = Instant::now();
+    let response = client.post(&plan.url).bearer_auth(&plan.api_key).json(&plan.body).send().await;
+
+    let Ok(response) = response else {
+        return RequestResult {
+            status: None,
+            latency: start.elapsed(),
+            prompt_tokens: 0,
+            completion_tokens: 0,
+        };
+    };
+    let status = response.status().as_u16();
+    // Reading the body is part of the request's latency
+    let body = response.json::<serde_json::Value>().await.ok();
+    let latency = start.elapsed();
+
+    let usage = |field: &str| body.as_ref().and_then(|b| b["usage"][field].as_u64()).unwrap_or_default();
+    RequestResult {
+        status: Some(status),
+        latency,
+        prompt_tokens: usage("prompt_tokens"),
+        completion_tokens: usage("completion_tokens"),
+    }
+}
+
+/// Run a load test to the end, saving its progress and final report
+pub async fn run(pool: PgPool, load_test_id: Uuid, plan: LoadTestPlan) -> Outcome {
+    info!("Starting load test {} against {}", load_test_id, plan.url);
+    let started = Instant::now();
+    let (outcome, stats) = drive(Some((&pool, load_test_id)), &plan, started).await;
+
+    let report = stats.report(started.elapsed());
+    let stop_reason = outcome.stop_reason(&plan);
+    if let Err(e) = LoadTestManager::finish(&pool, load_test_id, outcome.status(), stop_reason.as_deref(), &report).await {
+        warn!("Failed to save the report of load test {}: {}", load_test_id, e);
+    }
+    info!(
+        "Load test {} {} after {} requests",
+        load_test_id,
+        outcome.status(),
+        report.completed_requests
+    );
+    outcome
+}
+
+/// Run a plan without recording it as a load test, as load probes do, returning the final report
+pub async fn run_untracked(plan: &LoadTestPlan) -> (Outcome, LoadTestReport) {
+    let started = Instant::now();
+    let (outcome, stats) = drive(None, plan, started).await;
+    (outcome, stats.report(started.elapsed()))
+}
+
+/// Send the plan's requests. A tracked load test saves its progress and can be stopped by an admin.
+async fn drive(tracked: Option<(&PgPool, Uuid)>, plan: &LoadTestPlan, started: Instant) -> (Outcome, Stats) {
+    let mut stats = Stats::default();
+    let client = Client::builder()
+        .timeout(plan.max_duration.min(MAX_REQUEST_TIMEOUT))
+        .build()
+        .expect("Failed to build HTTP client");
+
+    let deadline = tokio::time::Instant::from_std(started + plan.max_duration);
+    let mut pacing = interval(Duration::from_secs_f64(1.0 / plan.requests_per_second as f64));
+    pacing.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut progress = interval(PROGRESS_INTERVAL);
+    progress.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let mut in_flight = JoinSet::new();
+    let mut sent = 0;
+
+    let outcome = loop {
+        if sent == plan.total_requests && in_flight.is_empty() {
+            break Outcome::Completed;
+        }
+
+        let can_send = sent < plan.total_requests && in_flight.len() < plan.concurrency as usize;
+        tokio::select! {
+            biased;
+            _ = sleep_until(deadline) => break Outcome::ReachedMaxDuration,
+            _ = progress.tick(), if tracked.is_some() => {
+                let Some((pool, load_test_id)) = tracked else { continue };
+                match LoadTestManager::update_progress(pool, load_test_id, &stats.report(started.elapsed())).await {
+                    Ok(true) => break Outcome::StoppedByUser,
+                    Ok(false) => {}
+                    Err(e) => warn!("Failed to save progress of load test {}: {}", load_test_id, e),
+                }
+            }
+            Some(result) = in_flight.join_next(), if !in_flight.is_empty() => {
+                if let Ok(result) = result {
+                    stats.record(result);
+                }
+                if let Some(rate) = stats.error_rate().filter(|rate| *rate > plan.max_error_rate) {
+                    break Outcome::ErrorRateExceeded(rate);
+                }
+            }
+            _ = pacing.tick(), if can_send => {
+                let client = client.clone();
+                let plan = plan.clone();
+                in_flight.spawn(async move { send_request(client, &plan).await });
+                sent += 1;
+            }
+        }
+    };
+
+    // Requests still in flight when stopping early are abandoned rather than counted
+    in_flight.abort_all();
+    (outcome, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::load_tests::LoadTest;
+    use crate::load_tests::db::NewLoadTest;
+    use axum::{http::StatusCode, response::IntoResponse, routing::post, Json, Router};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    /// Serve a fake chat completions endpoint, failing every request after the first `succeed`
+    async fn mock_upstream(succeed: u64, delay: Duration) -> String {
+        let count = Arc::new(AtomicU64::new(0));
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move || {
+                let count = count.clone();
+                async move {
+                    tokio::time::sleep(delay).await;
+                    if count.fetch_add(1, Ordering::SeqCst) < succeed {
+                        Json(json!({"usage": {"prompt_tokens": 3, "completion_tokens": 5}})).into_response()
+                    } else {
+                        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}/v1/chat/completions")
+    }
+
+    async fn start(pool: &PgPool, url: String, total_requests: u32) -> (LoadTest, LoadTestPlan) {
+        let endpoint_id = sqlx::query_scalar!(
+            "INSERT INTO inference_endpoints (name, url, created_by) VALUES ($1, $2, $3) RETURNING id",
+            "load-test-endpoint",
+            "http://localhost:8080",
+            Uuid::nil()
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        let deployment_id = sqlx::query_scalar!(
+            "INSERT INTO deployed_models (model_name, alias, hosted_on, created_by) VALUES ($1, $2, $3, $4) RETURNING id",
+            "load-test-model",
+            "load-test-alias",
+            endpoint_id,
+            Uuid::nil()
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        let load_test = LoadTestManager::create(
+            pool,
+            NewLoadTest {
+                deployment_id,
+                created_by: Uuid::nil(),
+                total_requests: total_requests as i32,
+                concurrency: 4,
+                requests_per_second: 1000,
+                max_duration_seconds: 60,
+                request_body: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let plan = LoadTestPlan {
+            url,
+            api_key: "test".to_string(),
+            body: json!({"model": "load-test-alias"}),
+            total_requests,
+            concurrency: 4,
+            requests_per_second: 1000,
+            max_duration: Duration::from_secs(60),
+            max_error_rate: 0.5,
+        };
+        (load_test, plan)
+    }
+
+    #[test]
+    fn test_latency_summary() {
+        let latencies: Vec<u64> = (1..=100).collect();
+        let summary = latency_summary(&latencies).unwrap();
+        assert_eq!(summary.mean, 50.5);
+        assert_eq!(summary.p50, 51);
+        assert_eq!(summary.p95, 95);
+        assert_eq!(summary.p99, 99);
+        assert_eq!(summary.max, 100);
+        assert!(latency_summary(&[]).is_none());
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_load_test_completes_and_reports(pool: PgPool) {
+        let url = mock_upstream(u64::MAX, Duration::ZERO).await;
+        let (load_test, plan) = start(&pool, url, 30).await;
+
+        let outcome = run(pool.clone(), load_test.id, plan).await;
+        assert_eq!(outcome, Outcome::Completed);
+
+        let load_test = LoadTestManager::get(&pool, load_test.id).await.unwrap();
+        assert_eq!(load_test.status, "completed");
+        assert!(load_test.finished_at.is_some());
+        let report = load_test.report.unwrap().0;
+        assert_eq!(report.completed_requests, 30);
+        assert_eq!(report.successful_requests, 30);
+        assert_eq!(report.status_codes.get("200"), Some(&30));
+        assert_eq!(report.prompt_tokens, 90);
+        assert_eq!(report.completion_tokens, 150);
+        assert!(report.latency_ms.is_some());
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_load_test_stops_when_error_rate_is_exceeded(pool: PgPool) {
+        let url = mock_upstream(5, Duration::ZERO).await;
+        let (load_test, plan) = start(&pool, url, 1000).await;
+
+        let outcome = run(pool.clone(), load_test.id, plan).await;
+        assert!(matches!(outcome, Outcome::ErrorRateExceeded(rate) if rate > 0.5));
+
+        let load_test = LoadTestManager::get(&pool, load_test.id).await.unwrap();
+        assert_eq!(load_test.status, "failed");
+        assert!(load_test.stop_reason.unwrap().contains("Error rate"));
+        let report = load_test.report.unwrap().0;
+        assert!(report.completed_requests < 1000);
+        assert_eq!(report.successful_requests, 5);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_load_test_can_be_stopped(pool: PgPool) {
+        let url = mock_upstream(u64::MAX, Duration::from_millis(100)).await;
+        let (load_test, plan) = start(&pool, url, 1000).await;
+
+        let handle = tokio::spawn(run(pool.clone(), load_test.id, plan));
+        LoadTestManager::request_stop(&pool, load_test.id).await.unwrap();
+        assert_eq!(handle.await.unwrap(), Outcome::StoppedByUser);
+
+        let load_test = LoadTestManager::get(&pool, load_test.id).await.unwrap();
+        assert_eq!(load_test.status, "stopped");
+        assert!(load_test.report.unwrap().0.completed_requests < 1000);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_only_one_load_test_runs_at_a_time(pool: PgPool) {
+        let (load_test, _) = start(&pool, String::new(), 10).await;
+
+        let second = LoadTestManager::create(
+            &pool,
+            NewLoadTest {
+                deployment_id: load_test.deployment_id,
+                created_by: load_test.created_by,
+                total_requests: 10,
+                concurrency: 1,
+                requests_per_second: 1,
+                max_duration_seconds: 60,
+                request_body: None,
+            },
+        )
+        .await;
+        assert!(matches!(second, Err(crate::errors::Error::Conflict { .. })));
+    }
+}
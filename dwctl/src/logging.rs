@@ -0,0 +1,112 @@
+//! Log output and runtime-adjustable log levels.
+//!
+//! Logs go to stdout as text or, with `logging.format: json`, as one JSON object per line that
+//! carries the fields of the enclosing spans, such as the request ID and user of admin API
+//! requests. The levels start from `RUST_LOG` or `logging.level`, and admins can replace them
+//! at runtime (see `api::handlers::logging`), e.g. to turn on debug logs for one module while
+//! chasing an issue, without a restart.
+
+use std::sync::{Mutex, OnceLock};
+
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+
+use crate::config::{LogFormat, LoggingConfig};
+
+/// The log levels of the subscriber installed by [`init`]
+static LOG_LEVELS: OnceLock<LogLevels> = OnceLock::new();
+
+/// Handle for changing the levels of a running subscriber
+pub struct LogLevels {
+    handle: reload::Handle<EnvFilter, Registry>,
+    /// The directives in effect, as given
+    directives: Mutex<String>,
+}
+
+impl LogLevels {
+    /// A filter layer logging at `directives`, and the handle changing its levels
+    pub fn new(directives: &str) -> anyhow::Result<(reload::Layer<EnvFilter, Registry>, Self)> {
+        let (layer, handle) = reload::Layer::new(EnvFilter::try_new(directives)?);
+        let levels = Self {
+            handle,
+            directives: Mutex::new(directives.to_string()),
+        };
+        Ok((layer, levels))
+    }
+
+    /// The directives in effect, e.g. `info,dwctl::auth=debug`
+    pub fn directives(&self) -> String {
+        self.directives.lock().expect("log levels lock poisoned").clone()
+    }
+
+    /// Replace the levels with `directives`, leaving them as they were if they're invalid
+    pub fn set(&self, directives: &str) -> anyhow::Result<()> {
+        let filter = EnvFilter::try_new(directives)?;
+        let mut current = self.directives.lock().expect("log levels lock poisoned");
+        self.handle.reload(filter)?;
+        *current = directives.to_string();
+        Ok(())
+    }
+}
+
+/// Install the global subscriber logging as `config` says, or at `RUST_LOG` when it's set, and
+/// reporting errors to Sentry when `error_tracking` is on
+pub fn init(config: &LoggingConfig, error_tracking: bool) -> anyhow::Result<()> {
+    let directives = std::env::var(EnvFilter::DEFAULT_ENV).unwrap_or_else(|_| config.level.clone());
+    let (filter, levels) = LogLevels::new(&directives)?;
+    let registry = tracing_subscriber::registry()
+        .with(filter)
+        .with(error_tracking.then(crate::error_tracking::layer));
+    match config.format {
+        LogFormat::Text => registry.with(fmt::layer()).try_init()?,
+        LogFormat::Json => registry
+            .with(
+                fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(false)
+                    .with_span_list(true),
+            )
+            .try_init()?,
+    }
+    let _ = LOG_LEVELS.set(levels);
+    Ok(())
+}
+
+/// The levels of the installed subscriber, or `None` if dwctl didn't install it (as in tests)
+pub fn levels() -> Option<&'static LogLevels> {
+    LOG_LEVELS.get()
+}
+
+/// Install levels for tests, whose subscriber is installed by `test_log` instead of [`init`]
+#[cfg(test)]
+pub(crate) fn install_for_tests() -> &'static LogLevels {
+    LOG_LEVELS.get_or_init(|| {
+        let (filter, levels) = LogLevels::new("info").expect("valid directives");
+        // The handle can only change the levels while the layer is alive
+        std::mem::forget(filter);
+        levels
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_levels_reload() {
+        let (filter, levels) = LogLevels::new("warn").unwrap();
+        let subscriber = tracing_subscriber::registry().with(filter);
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(!tracing::enabled!(tracing::Level::INFO));
+
+            levels.set("warn,dwctl::logging=debug").unwrap();
+            assert!(tracing::enabled!(tracing::Level::DEBUG));
+            assert_eq!(levels.directives(), "warn,dwctl::logging=debug");
+
+            // Invalid directives leave the levels as they were
+            assert!(levels.set("dwctl::logging=loud").is_err());
+            assert_eq!(levels.directives(), "warn,dwctl::logging=debug");
+            assert!(tracing::enabled!(tracing::Level::DEBUG));
+        });
+    }
+}
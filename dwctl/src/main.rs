@@ -1,17 +1,41 @@
+mod admission;
+mod anthropic;
 mod api;
 mod auth;
+mod azure;
 mod config;
 mod crypto;
 mod db;
+mod deprecation;
 mod email;
+mod error_tracking;
 mod errors;
+mod external_secrets;
+mod federation;
+mod header_rules;
+mod leader;
+mod load_tests;
+mod logging;
 mod metrics;
+mod model_metadata;
+mod moderation;
 mod openapi;
 mod probes;
+mod rate_limits;
+mod redaction;
+mod regression_suites;
+mod request_export;
+mod request_limits;
 mod request_logging;
+mod routing;
+mod sandbox;
+mod slos;
+mod state_archive;
 mod static_assets;
 mod sync;
+mod tls;
 mod types;
+mod upstream_limits;
 
 #[cfg(test)]
 mod test_utils;
@@ -23,16 +47,22 @@ use crate::{
     db::models::users::UserCreateDBRequest,
     metrics::GenAiMetrics,
     openapi::ApiDoc,
-    request_logging::serializers::{parse_ai_request, AnalyticsResponseSerializer},
+    request_logging::{
+        mirror::RequestMirror,
+        policy as logging_policy,
+        serializers::{parse_ai_request, AnalyticsResponseSerializer},
+        storage::BodyStorage,
+        webhook::RequestWebhook,
+    },
 };
 use auth::middleware::admin_ai_proxy_middleware;
 use axum::http::HeaderValue;
 use axum::{
     body::Body,
     http::{Request, Response, StatusCode, Uri},
-    middleware::from_fn_with_state,
+    middleware::{from_fn, from_fn_with_state},
     response::{Html, IntoResponse},
-    routing::{delete, get, patch, post},
+    routing::{delete, get, patch, post, put},
     Router, ServiceExt,
 };
 use axum_prometheus::PrometheusMetricLayer;
@@ -62,9 +92,23 @@ pub struct AppState {
     pub db: PgPool,
     pub config: Config,
     pub outlet_db: Option<PgPool>,
+    /// Object storage holding captured bodies, when they aren't kept in `outlet_db`
+    pub body_storage: Option<BodyStorage>,
     pub metrics_recorder: Option<GenAiMetrics>,
     #[builder(default = false)]
     pub is_leader: bool,
+    pub admission: Option<admission::Admission>,
+    /// Request log purge job, whose metrics are registered alongside the GenAI metrics
+    pub log_retention: Option<request_logging::retention::RequestLogRetention>,
+    /// Event stream publisher, whose metrics are registered alongside the GenAI metrics
+    pub event_stream: Option<request_logging::events::EventStream>,
+    /// Probe scheduler, whose metrics are registered alongside the GenAI metrics
+    pub probe_scheduler: Option<probes::ProbeScheduler>,
+    /// Leader fencing token, whose leadership and job metrics are registered alongside the
+    /// GenAI metrics
+    pub leader_fence: Option<leader::LeaderFence>,
+    /// Routing table of the onwards router, used to redact logged requests
+    pub routing_table: Option<tokio::sync::watch::Receiver<routing::RoutingTable>>,
 }
 
 /// Create the initial admin user if it doesn't exist
@@ -107,6 +151,7 @@ pub async fn create_initial_admin_user(email: &str, password: Option<&str>, db:
         roles: vec![Role::PlatformManager],
         auth_source: "system".to_string(),
         password_hash,
+        attributes: Default::default(),
     };
 
     let created_user = user_repo
@@ -125,12 +170,15 @@ pub async fn create_initial_admin_user(email: &str, password: Option<&str>, db:
 /// the endpoints. At some point, we may want to expand this to other tasks as well.
 ///
 /// PostgreSQL advisory locks are session-based, so we need to maintain a dedicated connection
-/// for the entire duration we want to hold the lock.
-#[instrument(skip(pool, config, lock_id, on_gain_leadership, on_lose_leadership))]
+/// for the entire duration we want to hold the lock. Each time we gain the lock we also take a
+/// new fencing token, which the leader-run jobs check so they stop if another replica takes over
+/// before we notice our connection has gone.
+#[instrument(skip(pool, config, fence, lock_id, on_gain_leadership, on_lose_leadership))]
 async fn leader_election_task<F1, F2, Fut1, Fut2>(
     pool: PgPool,
     config: config::Config,
     is_leader: Arc<AtomicBool>,
+    fence: leader::LeaderFence,
     lock_id: i64,
     on_gain_leadership: F1,
     on_lose_leadership: F2,
@@ -159,7 +207,15 @@ async fn leader_election_task<F1, F2, Fut1, Fut2>(
                         .await
                     {
                         Ok(true) => {
-                            // Successfully acquired lock!
+                            // Successfully acquired lock! Fence out any previous leader before
+                            // starting the jobs.
+                            if let Err(e) = fence.acquire(&mut conn).await {
+                                tracing::error!("Failed to acquire leader fencing token: {}", e);
+                                // Closing the connection releases the lock, so we retry from scratch
+                                let _ = conn.close().await;
+                                continue;
+                            }
+
                             info!("Gained leadership");
                             is_leader.store(true, Ordering::Relaxed);
                             leader_conn = Some(conn); // Keep connection alive
@@ -195,6 +251,7 @@ async fn leader_election_task<F1, F2, Fut1, Fut2>(
                         tracing::warn!("Lost leadership (connection died): {}", e);
                         info!("Lost leadership");
                         is_leader.store(false, Ordering::Relaxed);
+                        fence.release();
                         leader_conn = None;
 
                         if let Err(e) = on_lose_leadership(pool.clone(), config.clone()).await {
@@ -206,6 +263,7 @@ async fn leader_election_task<F1, F2, Fut1, Fut2>(
                 // We think we're leader but have no connection, this can't happen
                 tracing::error!("Inconsistent state: is_leader=true but no connection");
                 is_leader.store(false, Ordering::Relaxed);
+                fence.release();
             }
         }
     }
@@ -232,15 +290,18 @@ pub async fn seed_database(sources: &[config::ModelSource], db: &PgPool) -> Resu
     // Seed endpoints from model sources
     let system_user_id = Uuid::nil();
     for source in sources {
-        // Insert endpoint if it doesn't already exist (first-time seeding only)
+        // Insert endpoint if it doesn't already exist (first-time seeding only). Seeded endpoints
+        // are synchronized by the sync scheduler on the source's interval.
+        let sync_interval_seconds = i32::try_from(source.sync_interval.as_secs()).unwrap_or(i32::MAX).max(1);
         sqlx::query!(
-            "INSERT INTO inference_endpoints (name, description, url, created_by)
-             VALUES ($1, $2, $3, $4)
+            "INSERT INTO inference_endpoints (name, description, url, created_by, sync_interval_seconds)
+             VALUES ($1, $2, $3, $4, $5)
              ON CONFLICT (name) DO NOTHING",
             source.name,
             None::<String>, // System-created endpoints don't have descriptions
             source.url.as_str(),
             system_user_id,
+            sync_interval_seconds,
         )
         .execute(&mut *tx)
         .await?;
@@ -249,9 +310,14 @@ pub async fn seed_database(sources: &[config::ModelSource], db: &PgPool) -> Resu
     // Update the system API key secret with a new secure value
     let system_api_key_id = Uuid::nil();
     let new_secret = crypto::generate_api_key();
-    sqlx::query!("UPDATE api_keys SET secret = $1 WHERE id = $2", new_secret, system_api_key_id)
-        .execute(&mut *tx)
-        .await?;
+    sqlx::query!(
+        "UPDATE api_keys SET secret = $1, secret_hash = api_key_secret_hash($2) WHERE id = $3",
+        crypto::encrypt_secret(&new_secret)?,
+        new_secret,
+        system_api_key_id
+    )
+    .execute(&mut *tx)
+    .await?;
 
     // Mark database as seeded to prevent future overwrites
     sqlx::query!(
@@ -349,29 +415,109 @@ pub async fn setup_app(
     debug!("Setting up application");
     // Seed database with initial configuration (only runs once)
     seed_database(&config.model_sources, &pool).await?;
+    if config.sandbox.enabled {
+        sandbox::register_endpoint(&config, &pool).await?;
+    }
 
     // Start onwards integration
+    let change_notifier = sync::routing_changes::RoutingChangeNotifier::new(pool.clone(), &config.routing.change_webhook);
     let (onwards_config_sync, initial_targets, onwards_stream, drop_guard) =
-        sync::onwards_config::OnwardsConfigSync::new(pool.clone()).await?;
-
-    // Build the onwards router
-    let onwards_app_state = onwards::AppState::new(initial_targets.clone());
-    let onwards_router = onwards::build_router(onwards_app_state);
+        sync::onwards_config::OnwardsConfigSync::new(pool.clone(), change_notifier).await?;
+
+    // Build the onwards router, applying the header rules and masking PII in requests for
+    // endpoints with header rules or a redaction policy, adding the API version to requests for
+    // Azure OpenAI targets, translating Messages API requests for targets that aren't Anthropic
+    // endpoints, retrying failed requests against fallback endpoints, mirroring requests to
+    // shadow deployments and, if enabled, queueing requests by group priority when saturated,
+    // checking requests against group moderation policies and refusing requests over their
+    // groups' size limits or their endpoint's rate limits before forwarding them with the TLS
+    // settings of their endpoint. Listed models are given the metadata of their deployment, and
+    // requests to deprecated models are warned about, or refused once the models are retired.
+    let onwards_app_state = onwards::AppState::with_client(
+        initial_targets.clone(),
+        tls::UpstreamClient::new(onwards_config_sync.routing_table()),
+    );
+    // Requests mirrored to shadow deployments come back through the proxy as the system user
+    let system_api_key = sqlx::query_scalar!("SELECT secret FROM api_keys WHERE id = $1", Uuid::nil())
+        .fetch_one(&pool)
+        .await?;
+    let system_api_key = crypto::decrypt_secret(system_api_key)?;
+    let shadowing = routing::Shadowing::new(format!("http://localhost:{}/ai/v1", config.port), system_api_key);
+    let fallback_routing =
+        routing::FallbackRouting::new(onwards_config_sync.routing_table(), config.routing.fallback_timeout).with_shadowing(shadowing);
+    let mut onwards_router = onwards::build_router(onwards_app_state)
+        .layer(from_fn_with_state(
+            upstream_limits::UpstreamLimits::new(onwards_config_sync.routing_table()).shared_through(pool.clone()),
+            upstream_limits::enforce_upstream_limits,
+        ))
+        .layer(from_fn_with_state(
+            onwards_config_sync.routing_table(),
+            header_rules::apply_header_rules,
+        ))
+        .layer(from_fn_with_state(onwards_config_sync.routing_table(), redaction::redact))
+        .layer(from_fn_with_state(onwards_config_sync.routing_table(), azure::add_api_version))
+        .layer(from_fn_with_state(
+            onwards_config_sync.routing_table(),
+            anthropic::translate_messages,
+        ))
+        .layer(from_fn_with_state(fallback_routing, routing::fallback_routing))
+        .layer(from_fn_with_state(
+            onwards_config_sync.routing_table(),
+            model_metadata::add_model_metadata,
+        ))
+        .layer(from_fn_with_state(
+            onwards_config_sync.routing_table(),
+            deprecation::enforce_deprecation,
+        ));
+    let admission = if config.admission.enabled {
+        let admission = admission::Admission::new(pool.clone(), config.admission.clone())
+            .map_err(|e| anyhow::anyhow!("Failed to create admission metrics: {}", e))?;
+        onwards_router = onwards_router.layer(from_fn_with_state(admission.clone(), admission::admit));
+        Some(admission)
+    } else {
+        None
+    };
+    if config.moderation.enabled {
+        let moderation = moderation::Moderation::new(pool.clone(), config.moderation.clone());
+        onwards_router = onwards_router.layer(from_fn_with_state(moderation, moderation::moderate));
+    }
+    if config.request_limits.enabled {
+        let request_limits = request_limits::RequestLimits::new(pool.clone());
+        onwards_router = onwards_router.layer(from_fn_with_state(request_limits, request_limits::enforce_limits));
+    }
+    // Accept keys sent the Anthropic SDK's way, ahead of everything that authenticates requests
+    onwards_router = onwards_router.layer(from_fn(anthropic::accept_api_key));
+    if config.error_tracking.dsn.is_some() {
+        onwards_router = onwards_router.layer(from_fn(error_tracking::report_proxy_errors));
+    }
 
     // Start target updates (infallible task, handle internally)
     tokio::spawn(async move {
         let _ = initial_targets.receive_updates(onwards_stream).await;
     });
 
-    // Leader election lock ID: 0x44574354_50524F42 (DWCT_PROB in hex for "dwctl probes")
-    const LEADER_LOCK_ID: i64 = 0x4457_4354_5052_4F42_i64;
-
-    let probe_scheduler = probes::ProbeScheduler::new(pool.clone(), config.clone());
+    // Fencing token shared by the leader-run jobs, so they stop if another replica takes over
+    let fence = leader::LeaderFence::new(pool.clone(), leader::LEADER_LOCK_ID);
+
+    let probe_scheduler = probes::ProbeScheduler::new(pool.clone(), config.clone(), fence.clone())
+        .map_err(|e| anyhow::anyhow!("Failed to create probe scheduler: {}", e))?;
+    let validation_scheduler =
+        sync::endpoint_validation::EndpointValidationScheduler::new(pool.clone(), config.endpoint_validation.clone(), fence.clone());
+    let sync_scheduler = sync::scheduled_sync::EndpointSyncScheduler::new(pool.clone(), config.endpoint_sync.clone(), fence.clone());
+    let regression_scheduler = regression_suites::RegressionSuiteScheduler::new(pool.clone(), config.clone(), fence.clone());
+    let slo_evaluator = slos::SloEvaluator::new(pool.clone(), config.slos.clone(), fence.clone());
+    let log_retention =
+        request_logging::retention::RequestLogRetention::new(pool.clone(), config.request_log_retention.clone(), fence.clone())
+            .map_err(|e| anyhow::anyhow!("Failed to create request log retention: {}", e))?;
+    let event_stream = request_logging::events::EventStream::new(pool.clone(), config.event_stream.clone(), fence.clone())
+        .map_err(|e| anyhow::anyhow!("Failed to create event stream publisher: {}", e))?;
+    let leader_fence = fence.clone();
     let is_leader: bool;
 
     if skip_leader_election {
         // Skip leader election - just become leader immediately
         is_leader = true;
+        fence.acquire(&mut *pool.acquire().await?).await?;
         probe_scheduler.initialize().await?;
 
         // Start the scheduler daemon in the background
@@ -382,6 +528,13 @@ pub async fn setup_app(
             daemon_scheduler.run_daemon(use_listen_notify, 300).await; // Fallback sync every 5 minutes
         });
 
+        validation_scheduler.start().await;
+        sync_scheduler.start().await;
+        regression_scheduler.start().await;
+        slo_evaluator.start().await;
+        log_retention.start().await;
+        event_stream.start().await;
+
         info!("Skipping leader election - running as leader with probe scheduler");
     } else {
         // Normal leader election
@@ -405,6 +558,18 @@ pub async fn setup_app(
         let leader_election_pool = pool.clone();
         let leader_election_scheduler_gain = probe_scheduler.clone();
         let leader_election_scheduler_lose = probe_scheduler.clone();
+        let leader_election_validation_gain = validation_scheduler.clone();
+        let leader_election_validation_lose = validation_scheduler.clone();
+        let leader_election_sync_gain = sync_scheduler.clone();
+        let leader_election_sync_lose = sync_scheduler.clone();
+        let leader_election_regression_gain = regression_scheduler.clone();
+        let leader_election_regression_lose = regression_scheduler.clone();
+        let leader_election_slo_gain = slo_evaluator.clone();
+        let leader_election_slo_lose = slo_evaluator.clone();
+        let leader_election_retention_gain = log_retention.clone();
+        let leader_election_retention_lose = log_retention.clone();
+        let leader_election_events_gain = event_stream.clone();
+        let leader_election_events_lose = event_stream.clone();
         let leader_election_config = config.clone();
         let leader_election_flag = is_leader_flag.clone();
         tokio::spawn(async move {
@@ -412,10 +577,17 @@ pub async fn setup_app(
                 leader_election_pool,
                 leader_election_config,
                 leader_election_flag,
-                LEADER_LOCK_ID,
+                fence,
+                leader::LEADER_LOCK_ID,
                 move |_pool, _config| {
                     // This closure is run when a replica becomes the leader
                     let scheduler = leader_election_scheduler_gain.clone();
+                    let validation_scheduler = leader_election_validation_gain.clone();
+                    let sync_scheduler = leader_election_sync_gain.clone();
+                    let regression_scheduler = leader_election_regression_gain.clone();
+                    let slo_evaluator = leader_election_slo_gain.clone();
+                    let log_retention = leader_election_retention_gain.clone();
+                    let event_stream = leader_election_events_gain.clone();
                     async move {
                         // Wait for the server to be fully up before starting probes
                         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
@@ -434,13 +606,43 @@ pub async fn setup_app(
                             daemon_scheduler.run_daemon(use_listen_notify, 300).await;
                         });
 
+                        // Start the scheduled endpoint re-validation
+                        validation_scheduler.start().await;
+
+                        // Start synchronizing endpoints on their sync interval
+                        sync_scheduler.start().await;
+
+                        // Start running scheduled regression suites
+                        regression_scheduler.start().await;
+
+                        // Start evaluating SLOs and firing error budget alerts
+                        slo_evaluator.start().await;
+
+                        // Start purging request logs past the retention window
+                        log_retention.start().await;
+
+                        // Start publishing request analytics to the event stream
+                        event_stream.start().await;
+
                         Ok(())
                     }
                 },
                 move |_pool, _config| {
                     // This closure is run when a replica stops being the leader
                     let scheduler = leader_election_scheduler_lose.clone();
+                    let validation_scheduler = leader_election_validation_lose.clone();
+                    let sync_scheduler = leader_election_sync_lose.clone();
+                    let regression_scheduler = leader_election_regression_lose.clone();
+                    let slo_evaluator = leader_election_slo_lose.clone();
+                    let log_retention = leader_election_retention_lose.clone();
+                    let event_stream = leader_election_events_lose.clone();
                     async move {
+                        validation_scheduler.stop().await;
+                        sync_scheduler.stop().await;
+                        regression_scheduler.stop().await;
+                        slo_evaluator.stop().await;
+                        log_retention.stop().await;
+                        event_stream.stop().await;
                         scheduler
                             .stop_all()
                             .await
@@ -452,7 +654,17 @@ pub async fn setup_app(
         });
     }
 
-    let mut app_state = AppState::builder().db(pool).config(config).is_leader(is_leader).build();
+    let mut app_state = AppState::builder()
+        .db(pool)
+        .config(config)
+        .is_leader(is_leader)
+        .maybe_admission(admission)
+        .log_retention(log_retention)
+        .event_stream(event_stream)
+        .probe_scheduler(probe_scheduler)
+        .leader_fence(leader_fence)
+        .routing_table(onwards_config_sync.routing_table())
+        .build();
     let router = build_router(&mut app_state, onwards_router).await?;
 
     Ok((router, onwards_config_sync, drop_guard))
@@ -490,29 +702,109 @@ pub async fn build_router(state: &mut AppState, onwards_router: Router) -> anyho
             .await
             .expect("Failed to run outlet migrations");
 
+        // Searching bodies still works without the indexes, only slower
+        if let Err(e) = request_logging::search::create_search_indexes(&outlet_pool).await {
+            tracing::warn!("Failed to create request log search indexes: {}", e);
+        }
+
         // Initialize GenAI metrics BEFORE creating analytics serializer if metrics enabled
         if state.config.enable_metrics {
             let gen_ai_registry = prometheus::Registry::new();
-            let gen_ai_metrics =
-                GenAiMetrics::new(&gen_ai_registry).map_err(|e| anyhow::anyhow!("Failed to create GenAI metrics: {}", e))?;
+            let gen_ai_metrics = GenAiMetrics::with_config(&gen_ai_registry, &state.config.metrics)
+                .map_err(|e| anyhow::anyhow!("Failed to create GenAI metrics: {}", e))?
+                .with_group_lookup(state.db.clone());
+            if let Some(admission) = &state.admission {
+                admission
+                    .register_metrics(&gen_ai_registry)
+                    .map_err(|e| anyhow::anyhow!("Failed to register admission metrics: {}", e))?;
+            }
+            if let Some(log_retention) = &state.log_retention {
+                log_retention
+                    .register_metrics(&gen_ai_registry)
+                    .map_err(|e| anyhow::anyhow!("Failed to register request log retention metrics: {}", e))?;
+            }
+            if let Some(event_stream) = &state.event_stream {
+                event_stream
+                    .register_metrics(&gen_ai_registry)
+                    .map_err(|e| anyhow::anyhow!("Failed to register event stream metrics: {}", e))?;
+            }
+            if let Some(probe_scheduler) = &state.probe_scheduler {
+                probe_scheduler
+                    .register_metrics(&gen_ai_registry)
+                    .map_err(|e| anyhow::anyhow!("Failed to register probe metrics: {}", e))?;
+            }
+            if let Some(leader_fence) = &state.leader_fence {
+                leader_fence
+                    .register_metrics(&gen_ai_registry)
+                    .map_err(|e| anyhow::anyhow!("Failed to register leader metrics: {}", e))?;
+            }
             state.metrics_recorder = Some(gen_ai_metrics);
         }
 
-        let analytics_serializer = AnalyticsResponseSerializer::new(
+        let mut analytics_serializer = AnalyticsResponseSerializer::new(
             state.db.clone(),
             uuid::Uuid::new_v4(),
             state.config.clone(),
             state.metrics_recorder.clone(),
         );
 
+        // Forward completed request records to an external endpoint if configured
+        let mirroring = &state.config.request_mirroring;
+        if let (true, Some(url)) = (mirroring.enabled, mirroring.url.clone()) {
+            let registry = state.metrics_recorder.as_ref().map(|m| m.registry());
+            let mirror = RequestMirror::spawn(url, mirroring, registry)
+                .map_err(|e| anyhow::anyhow!("Failed to create request mirroring metrics: {}", e))?;
+            analytics_serializer = analytics_serializer.with_mirror(mirror);
+        }
+
+        // Send per-request events for completed requests matching the webhook filter if configured
+        let webhook = &state.config.request_webhook;
+        if let (true, Some(url)) = (webhook.enabled, webhook.url.clone()) {
+            let registry = state.metrics_recorder.as_ref().map(|m| m.registry());
+            let webhook = RequestWebhook::spawn(url, webhook, registry)
+                .map_err(|e| anyhow::anyhow!("Failed to create request webhook metrics: {}", e))?;
+            analytics_serializer = analytics_serializer.with_webhook(webhook);
+        }
+
+        // Keep captured bodies in object storage rather than the outlet tables, if configured
+        let body_storage = BodyStorage::from_config(&state.config.body_storage)?;
+        if let Some(body_storage) = &body_storage {
+            analytics_serializer = analytics_serializer.with_body_storage(body_storage.clone());
+        }
+        if let Some(routing_table) = &state.routing_table {
+            analytics_serializer = analytics_serializer.with_routing_table(routing_table.clone());
+        }
+        let request_body_storage = body_storage.clone();
+        let routing_table = state.routing_table.clone();
+        let request_serializer = move |request_data: &outlet::RequestData| {
+            // Log the redacted request when the policies of its endpoints don't allow the original
+            let redacted = routing_table
+                .as_ref()
+                .and_then(|table| redaction::logged_request(&table.borrow(), request_data));
+            let request = parse_ai_request(redacted.as_ref().unwrap_or(request_data));
+            // Then keep only what the deployment's logging policy allows
+            let policy = routing_table
+                .as_ref()
+                .and_then(|table| logging_policy::policy_for(&table.borrow(), request_data));
+            let request = match policy {
+                Some(policy) => policy.apply_to_request(request)?,
+                None => request?,
+            };
+            Ok(match &request_body_storage {
+                Some(body_storage) => body_storage.offload_request(request),
+                None => request,
+            })
+        };
+
         let postgres_handler = PostgresHandler::<AiRequest, AiResponse>::from_pool(outlet_pool.clone())
             .await
             .expect("Failed to create PostgresHandler for request logging")
             .with_path_prefix("/ai/")
-            .with_request_serializer(parse_ai_request)
+            .with_request_serializer(request_serializer)
             .with_response_serializer(analytics_serializer.create_serializer());
 
         state.outlet_db = Some(outlet_pool.clone());
+        state.body_storage = body_storage;
 
         let outlet_config = RequestLoggerConfig {
             capture_request_body: true,
@@ -559,6 +851,12 @@ pub async fn build_router(state: &mut AppState, onwards_router: Router) -> anyho
             "/users/{user_id}/api-keys/{id}",
             delete(api::handlers::api_keys::delete_user_api_key),
         )
+        .route(
+            "/users/{user_id}/api-keys/{id}/access-requests",
+            get(api::handlers::api_keys::list_api_key_access_requests).post(api::handlers::api_keys::request_api_key_access),
+        )
+        .route("/access-requests", get(api::handlers::api_keys::list_access_requests))
+        .route("/access-requests/{id}", patch(api::handlers::api_keys::decide_access_request))
         // User-group relationships
         .route("/users/{user_id}/groups", get(api::handlers::groups::get_user_groups))
         .route("/users/{user_id}/groups/{group_id}", post(api::handlers::groups::add_group_to_user))
@@ -573,6 +871,12 @@ pub async fn build_router(state: &mut AppState, onwards_router: Router) -> anyho
             "/endpoints/validate",
             post(api::handlers::inference_endpoints::validate_inference_endpoint),
         )
+        .route("/endpoints/export", get(api::handlers::inference_endpoints::export_endpoints))
+        .route("/endpoints/import", post(api::handlers::inference_endpoints::import_endpoints))
+        .route(
+            "/endpoints/validation-report",
+            get(api::handlers::inference_endpoints::get_validation_report),
+        )
         .route("/endpoints/{id}", get(api::handlers::inference_endpoints::get_inference_endpoint))
         .route(
             "/endpoints/{id}",
@@ -586,18 +890,139 @@ pub async fn build_router(state: &mut AppState, onwards_router: Router) -> anyho
             "/endpoints/{id}/synchronize",
             post(api::handlers::inference_endpoints::synchronize_endpoint),
         )
+        .route(
+            "/endpoints/{id}/compatibility",
+            get(api::handlers::inference_endpoints::list_compatibility_reports),
+        )
+        .route(
+            "/endpoints/{id}/compatibility",
+            post(api::handlers::inference_endpoints::run_compatibility_suite),
+        )
+        .route(
+            "/endpoints/{id}/redaction",
+            get(api::handlers::inference_endpoints::get_endpoint_redaction),
+        )
+        .route(
+            "/endpoints/{id}/redaction",
+            put(api::handlers::inference_endpoints::set_endpoint_redaction),
+        )
+        .route(
+            "/endpoints/{id}/headers",
+            get(api::handlers::inference_endpoints::get_endpoint_headers),
+        )
+        .route(
+            "/endpoints/{id}/headers",
+            put(api::handlers::inference_endpoints::set_endpoint_headers),
+        )
+        .route(
+            "/endpoints/{id}/rate-limits",
+            get(api::handlers::inference_endpoints::get_endpoint_rate_limits),
+        )
+        .route(
+            "/endpoints/{id}/rate-limits",
+            put(api::handlers::inference_endpoints::set_endpoint_rate_limits),
+        )
+        .route(
+            "/endpoints/{id}/sync-strategy",
+            get(api::handlers::inference_endpoints::get_endpoint_sync_strategy)
+                .put(api::handlers::inference_endpoints::set_endpoint_sync_strategy),
+        )
+        .route(
+            "/endpoints/{id}/pending-models",
+            get(api::handlers::inference_endpoints::list_endpoint_pending_models),
+        )
+        .route(
+            "/endpoints/{id}/pending-models/approve",
+            post(api::handlers::inference_endpoints::approve_endpoint_pending_models),
+        )
+        .route(
+            "/endpoints/{id}/pending-models/reject",
+            post(api::handlers::inference_endpoints::reject_endpoint_pending_models),
+        )
+        .route(
+            "/endpoints/{id}/history",
+            get(api::handlers::inference_endpoints::get_endpoint_history),
+        )
+        .route(
+            "/endpoints/{id}/aliases",
+            get(api::handlers::inference_endpoints::get_endpoint_aliases).put(api::handlers::inference_endpoints::set_endpoint_aliases),
+        )
+        .route(
+            "/endpoints/{id}/rollback/{version}",
+            post(api::handlers::inference_endpoints::rollback_inference_endpoint),
+        )
         // Models endpoints
         .route("/models", get(api::handlers::deployments::list_deployed_models))
         .route("/models", post(api::handlers::deployments::create_deployed_model))
         .route("/models/{id}", get(api::handlers::deployments::get_deployed_model))
         .route("/models/{id}", patch(api::handlers::deployments::update_deployed_model))
         .route("/models/{id}", delete(api::handlers::deployments::delete_deployed_model))
+        .route("/models/{id}/fallbacks", get(api::handlers::deployments::get_deployment_fallbacks))
+        .route("/models/{id}/fallbacks", put(api::handlers::deployments::set_deployment_fallbacks))
+        .route(
+            "/models/{id}/traffic-split",
+            get(api::handlers::deployments::get_deployment_traffic_split),
+        )
+        .route(
+            "/models/{id}/traffic-split",
+            put(api::handlers::deployments::set_deployment_traffic_split),
+        )
+        .route("/models/{id}/canary", get(api::handlers::deployments::get_deployment_canary))
+        .route("/models/{id}/canary", put(api::handlers::deployments::set_deployment_canary))
+        .route("/models/{id}/canary", delete(api::handlers::deployments::delete_deployment_canary))
+        .route(
+            "/models/{id}/deprecation",
+            get(api::handlers::deployments::get_deployment_deprecation),
+        )
+        .route(
+            "/models/{id}/deprecation",
+            put(api::handlers::deployments::set_deployment_deprecation),
+        )
+        .route(
+            "/models/{id}/deprecation",
+            delete(api::handlers::deployments::delete_deployment_deprecation),
+        )
+        .route("/models/{id}/shadow", get(api::handlers::deployments::get_deployment_shadow))
+        .route("/models/{id}/shadow", put(api::handlers::deployments::set_deployment_shadow))
+        .route("/models/{id}/shadow", delete(api::handlers::deployments::delete_deployment_shadow))
+        .route("/models/{id}/schedule", get(api::handlers::deployments::get_deployment_schedule))
+        .route("/models/{id}/schedule", put(api::handlers::deployments::set_deployment_schedule))
+        .route(
+            "/models/{id}/schedule/observed",
+            get(api::handlers::deployments::get_observed_deployment_schedule),
+        )
+        .route("/schedule-hints", get(api::handlers::deployments::list_schedule_hints))
+        .route(
+            "/models/{id}/access-policy",
+            get(api::handlers::deployments::get_deployment_access_policy).put(api::handlers::deployments::set_deployment_access_policy),
+        )
+        .route(
+            "/models/{id}/logging-policy",
+            get(api::handlers::deployments::get_deployment_logging_policy).put(api::handlers::deployments::set_deployment_logging_policy),
+        )
+        .route(
+            "/models/{id}/rate-limits/simulate",
+            post(api::handlers::deployments::simulate_deployment_rate_limits),
+        )
+        .route("/models/{id}/test", post(api::handlers::deployments::test_deployment))
         // Groups management
         .route("/groups", get(api::handlers::groups::list_groups))
         .route("/groups", post(api::handlers::groups::create_group))
         .route("/groups/{id}", get(api::handlers::groups::get_group))
         .route("/groups/{id}", patch(api::handlers::groups::update_group))
         .route("/groups/{id}", delete(api::handlers::groups::delete_group))
+        .route("/groups/{group_id}/moderation", get(api::handlers::groups::get_group_moderation))
+        .route("/groups/{group_id}/moderation", put(api::handlers::groups::set_group_moderation))
+        .route("/groups/{group_id}/limits", get(api::handlers::groups::get_group_limits))
+        .route("/groups/{group_id}/limits", put(api::handlers::groups::set_group_limits))
+        .route(
+            "/groups/{group_id}/log-retention",
+            get(api::handlers::groups::get_group_log_retention),
+        )
+        .route(
+            "/groups/{group_id}/log-retention",
+            put(api::handlers::groups::set_group_log_retention),
+        )
         // Group-user relationships
         .route("/groups/{group_id}/users", get(api::handlers::groups::get_group_users))
         .route("/groups/{group_id}/users/{user_id}", post(api::handlers::groups::add_user_to_group))
@@ -617,8 +1042,55 @@ pub async fn build_router(state: &mut AppState, onwards_router: Router) -> anyho
         )
         .route("/models/{deployment_id}/groups", get(api::handlers::groups::get_deployment_groups))
         .route("/requests", get(api::handlers::requests::list_requests))
+        .route("/requests/export", get(api::handlers::requests::export_requests))
+        .route("/requests/archives", get(api::handlers::requests::list_request_archives))
+        .route("/requests/{id}", get(api::handlers::requests::get_request))
         .route("/requests/aggregate", get(api::handlers::requests::aggregate_requests))
         .route("/requests/aggregate-by-user", get(api::handlers::requests::aggregate_by_user))
+        .route("/requests/timeseries", get(api::handlers::requests::usage_time_series))
+        .route("/requests/errors", get(api::handlers::requests::error_breakdown))
+        .route("/requests/aggregate-by-model", get(api::handlers::requests::aggregate_by_model))
+        .route("/requests/aggregate-by-group", get(api::handlers::requests::aggregate_by_group))
+        .route(
+            "/requests/aggregate-by-attribute",
+            get(api::handlers::requests::aggregate_by_attribute),
+        )
+        .route(
+            "/requests/aggregate-by-conversation",
+            get(api::handlers::requests::aggregate_by_conversation),
+        )
+        .route("/requests/aggregate-by-tag", get(api::handlers::requests::aggregate_by_tag))
+        .route(
+            "/requests/token-backfills",
+            get(api::handlers::requests::list_token_backfills).post(api::handlers::requests::create_token_backfill),
+        )
+        .route("/requests/token-backfills/{id}", get(api::handlers::requests::get_token_backfill))
+        .route(
+            "/requests/token-backfills/{id}/stop",
+            post(api::handlers::requests::stop_token_backfill),
+        )
+        .route("/requests/pii-by-group", get(api::handlers::requests::pii_stats_by_group))
+        .route(
+            "/requests/embeddings/aggregate-by-model",
+            get(api::handlers::requests::aggregate_embeddings_by_model),
+        )
+        .route(
+            "/requests/embeddings/aggregate-by-group",
+            get(api::handlers::requests::aggregate_embeddings_by_group),
+        )
+        // Adoption metrics
+        .route("/adoption", get(api::handlers::adoption::get_adoption))
+        // Load testing
+        .route("/loadtest", get(api::handlers::load_tests::list_load_tests))
+        .route("/loadtest", post(api::handlers::load_tests::create_load_test))
+        .route("/loadtest/{id}", get(api::handlers::load_tests::get_load_test))
+        .route("/loadtest/{id}/stop", post(api::handlers::load_tests::stop_load_test))
+        .route("/federation/peers", get(api::handlers::federation::list_peers))
+        .route("/federation/peers", post(api::handlers::federation::create_peer))
+        .route("/federation/peers/{id}", delete(api::handlers::federation::delete_peer))
+        .route("/federation/overview", get(api::handlers::federation::get_overview))
+        .route("/log-levels", get(api::handlers::logging::get_log_levels))
+        .route("/log-levels", put(api::handlers::logging::update_log_levels))
         // Probes management
         .route("/probes", get(api::handlers::probes::list_probes))
         .route("/probes", post(api::handlers::probes::create_probe))
@@ -631,13 +1103,97 @@ pub async fn build_router(state: &mut AppState, onwards_router: Router) -> anyho
         .route("/probes/{id}/execute", post(api::handlers::probes::execute_probe))
         .route("/probes/{id}/results", get(api::handlers::probes::get_probe_results))
         .route("/probes/{id}/statistics", get(api::handlers::probes::get_statistics))
+        // Regression suites management
+        .route("/regression-suites", get(api::handlers::regression_suites::list_regression_suites))
+        .route(
+            "/regression-suites",
+            post(api::handlers::regression_suites::create_regression_suite),
+        )
+        .route(
+            "/regression-suites/{id}",
+            get(api::handlers::regression_suites::get_regression_suite),
+        )
+        .route(
+            "/regression-suites/{id}",
+            patch(api::handlers::regression_suites::update_regression_suite),
+        )
+        .route(
+            "/regression-suites/{id}",
+            delete(api::handlers::regression_suites::delete_regression_suite),
+        )
+        .route(
+            "/regression-suites/{id}/run",
+            post(api::handlers::regression_suites::run_regression_suite),
+        )
+        .route(
+            "/regression-suites/{id}/runs",
+            get(api::handlers::regression_suites::list_regression_runs),
+        )
+        // Deployment SLOs and their error budget alerts
+        .route("/slos", get(api::handlers::slos::list_slos))
+        .route("/slos", post(api::handlers::slos::create_slo))
+        .route("/slos/{id}", get(api::handlers::slos::get_slo))
+        .route("/slos/{id}", patch(api::handlers::slos::update_slo))
+        .route("/slos/{id}", delete(api::handlers::slos::delete_slo))
+        .route("/slos/{id}/alerts", get(api::handlers::slos::list_slo_alerts))
+        // Where probe failure and recovery alerts are sent
+        .route(
+            "/notification-channels",
+            get(api::handlers::notification_channels::list_notification_channels),
+        )
+        .route(
+            "/notification-channels",
+            post(api::handlers::notification_channels::create_notification_channel),
+        )
+        .route(
+            "/notification-channels/{id}",
+            get(api::handlers::notification_channels::get_notification_channel),
+        )
+        .route(
+            "/notification-channels/{id}",
+            patch(api::handlers::notification_channels::update_notification_channel),
+        )
+        .route(
+            "/notification-channels/{id}",
+            delete(api::handlers::notification_channels::delete_notification_channel),
+        )
+        // Maintenance windows, during which probe failures don't alert or count against SLOs
+        .route(
+            "/maintenance-windows",
+            get(api::handlers::maintenance_windows::list_maintenance_windows),
+        )
+        .route(
+            "/maintenance-windows",
+            post(api::handlers::maintenance_windows::create_maintenance_window),
+        )
+        .route(
+            "/maintenance-windows/{id}",
+            get(api::handlers::maintenance_windows::get_maintenance_window),
+        )
+        .route(
+            "/maintenance-windows/{id}",
+            patch(api::handlers::maintenance_windows::update_maintenance_window),
+        )
+        .route(
+            "/maintenance-windows/{id}",
+            delete(api::handlers::maintenance_windows::delete_maintenance_window),
+        )
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(|request: &Request<_>| {
+                    let request_id = request
+                        .headers()
+                        .get("x-request-id")
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string)
+                        .unwrap_or_else(|| Uuid::new_v4().to_string());
                     tracing::info_span!(
                         "request",
                         method = %request.method(),
                         uri = %request.uri(),
+                        request_id = %request_id,
+                        // Recorded once the caller is authenticated
+                        user_id = tracing::field::Empty,
                     )
                 })
                 .on_response(|response: &Response<_>, latency: Duration, _span: &Span| {
@@ -654,7 +1210,7 @@ pub async fn build_router(state: &mut AppState, onwards_router: Router) -> anyho
     let fallback = get(serve_embedded_asset).fallback(get(spa_fallback));
 
     // Build the app with admin API and onwards proxy nested. serve the (restricted) openai spec.
-    let router = Router::new()
+    let mut router = Router::new()
         .route("/healthz", get(|| async { "OK" }))
         .route(
             "/openai-openapi.yaml",
@@ -664,12 +1220,25 @@ pub async fn build_router(state: &mut AppState, onwards_router: Router) -> anyho
             }),
         )
         .merge(auth_routes)
+        .route(
+            "/ai/v1/bootstrap",
+            get(api::handlers::bootstrap::bootstrap).with_state(state.clone()),
+        )
+        .route(
+            federation::SUMMARY_PATH,
+            get(api::handlers::federation::get_summary).with_state(state.clone()),
+        )
         .nest("/ai/v1", onwards_router)
         .nest("/admin/api/v1", api_routes)
         .merge(RapiDoc::with_openapi("/api-docs/openapi.json", ApiDoc::openapi()).path("/admin/docs"))
         .merge(RapiDoc::new("/openai-openapi.yaml").path("/ai/docs"))
         .fallback_service(fallback);
 
+    // Serve the mock backend if the developer sandbox is enabled
+    if state.config.sandbox.enabled {
+        router = router.nest("/sandbox/v1", sandbox::router(state.config.sandbox.clone()));
+    }
+
     // Create CORS layer from config
     let cors_layer = create_cors_layer(&state.config)?;
 
@@ -680,6 +1249,13 @@ pub async fn build_router(state: &mut AppState, onwards_router: Router) -> anyho
         router.layer(cors_layer)
     };
 
+    // Give each request its own error tracking scope, carrying the request
+    if state.config.error_tracking.dsn.is_some() {
+        router = router
+            .layer(sentry::integrations::tower::SentryHttpLayer::new())
+            .layer(sentry::integrations::tower::NewSentryLayer::<Request<axum::body::Body>>::new_from_top());
+    }
+
     // Add Prometheus metrics if enabled
     if state.config.enable_metrics {
         let (prometheus_layer, metric_handle) = PrometheusMetricLayer::pair();
@@ -692,25 +1268,55 @@ pub async fn build_router(state: &mut AppState, onwards_router: Router) -> anyho
             prometheus::Registry::new()
         };
 
-        // Add metrics endpoint that combines both axum-prometheus and GenAI metrics
+        // Render both axum-prometheus and GenAI metrics, leaving out any families disabled in the
+        // config
+        let metrics_config = state.config.metrics.clone();
+        let render_registry = gen_ai_registry.clone();
+        let render = Arc::new(move || {
+            use prometheus::{Encoder, TextEncoder};
+
+            // Get axum-prometheus metrics
+            let mut axum_metrics = metrics::filter_text(&metric_handle.render(), &metrics_config);
+
+            // Get GenAI metrics
+            let encoder = TextEncoder::new();
+            let gen_ai_families = metrics::filter_families(render_registry.gather(), &metrics_config);
+            let mut gen_ai_buffer = vec![];
+            encoder.encode(&gen_ai_families, &mut gen_ai_buffer).unwrap();
+
+            // Combine both
+            axum_metrics.push_str(&String::from_utf8_lossy(&gen_ai_buffer));
+            axum_metrics
+        });
+
+        // Sample the database pool, and count slow named queries
+        let database_health = db::health::DatabaseHealth::new(state.db.clone(), &state.config.metrics.database);
+        database_health
+            .register_metrics(&gen_ai_registry)
+            .map_err(|e| anyhow::anyhow!("Failed to register database metrics: {}", e))?;
+        database_health.spawn_sampler();
+
+        // Also push them to an OpenTelemetry collector, if configured
+        let otlp = &state.config.metrics.otlp;
+        if let (true, Some(endpoint)) = (otlp.enabled, &otlp.endpoint) {
+            let render = render.clone();
+            metrics::OtlpExporter::spawn(endpoint.clone(), otlp, &gen_ai_registry, move || render())
+                .map_err(|e| anyhow::anyhow!("Failed to start OTLP metrics exporter: {}", e))?;
+        }
+
         router = router
             .route(
                 "/internal/metrics",
-                get(|| async move {
-                    use prometheus::{Encoder, TextEncoder};
-
-                    // Get axum-prometheus metrics
-                    let mut axum_metrics = metric_handle.render();
-
-                    // Get GenAI metrics
-                    let encoder = TextEncoder::new();
-                    let gen_ai_families = gen_ai_registry.gather();
-                    let mut gen_ai_buffer = vec![];
-                    encoder.encode(&gen_ai_families, &mut gen_ai_buffer).unwrap();
-
-                    // Combine both
-                    axum_metrics.push_str(&String::from_utf8_lossy(&gen_ai_buffer));
-                    axum_metrics
+                get(move || {
+                    let render = render.clone();
+                    async move { render() }
+                }),
+            )
+            .route(
+                "/internal/status",
+                get(move || {
+                    let status = serde_json::json!({ "database": database_health.status() });
+                    async move { axum::Json(status) }
                 }),
             )
             .layer(prometheus_layer);
@@ -721,20 +1327,19 @@ pub async fn build_router(state: &mut AppState, onwards_router: Router) -> anyho
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize tracing with environment filter
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info,onwards_pilot::sync=warn")),
-        )
-        .init();
-
     // Parse CLI args
     let args = Args::parse();
-    debug!("{:?}", args);
 
     // Load configuration
     let config = Config::load(&args)?;
+
+    // Report panics and errors to Sentry, if configured. Pending reports are flushed when the
+    // guard is dropped on exit.
+    let error_tracking_guard = error_tracking::init(&config.error_tracking);
+
+    // Initialize tracing in the configured format, at RUST_LOG or the configured levels
+    logging::init(&config.logging, error_tracking_guard.is_some())?;
+    debug!("{:?}", args);
     debug!("Starting control layer with configuration: {:#?}", config);
 
     // Database connection - handle both embedded and external
@@ -771,6 +1376,37 @@ async fn main() -> anyhow::Result<()> {
     // Run migrations
     sqlx::migrate!("./migrations").run(&pool).await?;
 
+    // Secrets are encrypted as they're stored, and decrypted as they're read, once a key is set
+    if let Some(cipher) = crypto::SecretCipher::from_config(&config.secret_encryption)? {
+        crypto::init_secret_encryption(cipher)?;
+    }
+    // Endpoint API keys can refer to secrets kept in Vault or AWS Secrets Manager
+    external_secrets::init(&config.secret_managers)?;
+
+    // Maintenance commands run against the migrated database instead of starting the server
+    match &args.command {
+        Some(config::Command::ExportState { output }) => {
+            let archive = state_archive::export_state(&pool).await?;
+            std::fs::write(output, serde_json::to_vec(&archive)?)?;
+            info!("Exported gateway state to {}", output.display());
+            return Ok(());
+        }
+        Some(config::Command::ImportState { input }) => {
+            let archive: state_archive::StateArchive = serde_json::from_slice(&std::fs::read(input)?)?;
+            let rows = state_archive::import_state(&pool, &archive).await?;
+            info!("Imported {} rows of gateway state from {}", rows, input.display());
+            return Ok(());
+        }
+        Some(config::Command::EncryptSecrets) => {
+            let cipher = crypto::SecretCipher::from_config(&config.secret_encryption)?
+                .ok_or_else(|| anyhow::anyhow!("Set secret_encryption.key or secret_encryption.key_file to encrypt secrets"))?;
+            let rows = crypto::encrypt_stored_secrets(&pool, &cipher).await?;
+            info!("Encrypted {} stored secrets", rows);
+            return Ok(());
+        }
+        None => {}
+    }
+
     // create admin user if it doesn't exist
     create_initial_admin_user(&config.admin_email, config.admin_password.as_deref(), &pool)
         .await
@@ -1046,6 +1682,13 @@ mod test {
                 .expect("Should be able to count endpoints");
         assert_eq!(endpoint_count, Some(2), "Should have created 2 endpoints");
 
+        // Verify endpoints are synchronized on the source's interval
+        let sync_interval = sqlx::query_scalar!("SELECT sync_interval_seconds FROM inference_endpoints WHERE name = 'test-endpoint-1'")
+            .fetch_one(&pool)
+            .await
+            .expect("Should be able to get endpoint sync interval");
+        assert_eq!(sync_interval, Some(10), "Seeded endpoint should sync on the source's interval");
+
         // Verify API key was updated
         let updated_secret = sqlx::query_scalar!("SELECT secret FROM api_keys WHERE id = $1", system_api_key_id)
             .fetch_one(&pool)
@@ -1327,5 +1970,13 @@ mod test {
         let metrics_content = metrics_response.text();
         // Should contain Prometheus metrics format
         assert!(metrics_content.contains("# HELP") || metrics_content.contains("# TYPE"));
+        assert!(metrics_content.contains("dwctl_db_pool_max_connections"));
+
+        // Status endpoint should summarise the database pool
+        let status_response = server.get("/internal/status").await;
+        assert_eq!(status_response.status_code().as_u16(), 200);
+        let status: serde_json::Value = status_response.json();
+        assert!(status["database"]["pool"]["max_connections"].as_u64().unwrap() > 0);
+        assert_eq!(status["database"]["slow_query_threshold_ms"], 500);
     }
 }
@@ -0,0 +1,79 @@
+//! Filtering of the metric families exposed on `/internal/metrics`
+
+use prometheus::proto::MetricFamily;
+
+use crate::config::MetricsConfig;
+
+/// Suffixes of the sample names of histogram and summary families
+pub(super) const SAMPLE_SUFFIXES: &[&str] = &["_bucket", "_sum", "_count"];
+
+/// Drop the families `config` disables from gathered metric families
+pub fn filter_families(families: Vec<MetricFamily>, config: &MetricsConfig) -> Vec<MetricFamily> {
+    families
+        .into_iter()
+        .filter(|family| config.family_enabled(family.get_name()))
+        .collect()
+}
+
+/// Drop the families `config` disables from metrics already rendered in the Prometheus text
+/// format, as the HTTP metrics are
+pub fn filter_text(text: &str, config: &MetricsConfig) -> String {
+    if config.disabled_families.is_empty() {
+        return text.to_string();
+    }
+    text.lines()
+        .filter(|line| line_enabled(line, config))
+        .flat_map(|line| [line, "\n"])
+        .collect()
+}
+
+fn line_enabled(line: &str, config: &MetricsConfig) -> bool {
+    // `# HELP <name> ...` and `# TYPE <name> ...` lines belong to the family they describe
+    let name = match line.strip_prefix('#') {
+        Some(comment) => match comment.split_whitespace().collect::<Vec<_>>()[..] {
+            ["HELP" | "TYPE", name, ..] => name,
+            _ => return true,
+        },
+        None => line.split(['{', ' ']).next().unwrap_or_default(),
+    };
+    if name.is_empty() {
+        return true;
+    }
+    config.family_enabled(name)
+        && SAMPLE_SUFFIXES
+            .iter()
+            .filter_map(|suffix| name.strip_suffix(suffix))
+            .all(|family| config.family_enabled(family))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_text() {
+        let text = "\
+# HELP axum_http_requests_total Total number of HTTP requests
+# TYPE axum_http_requests_total counter
+axum_http_requests_total{method=\"GET\",status=\"200\"} 3
+# TYPE axum_http_requests_duration_seconds histogram
+axum_http_requests_duration_seconds_bucket{le=\"0.005\"} 1
+axum_http_requests_duration_seconds_sum 0.002
+axum_http_requests_duration_seconds_count 1
+";
+        let config = MetricsConfig {
+            disabled_families: vec!["axum_http_requests_duration_seconds".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            filter_text(text, &config),
+            "\
+# HELP axum_http_requests_total Total number of HTTP requests
+# TYPE axum_http_requests_total counter
+axum_http_requests_total{method=\"GET\",status=\"200\"} 3
+"
+        );
+
+        assert_eq!(filter_text(text, &MetricsConfig::default()), text);
+    }
+}
@@ -5,23 +5,191 @@
 //! - gen_ai.server.time_to_first_token
 //! - gen_ai.server.time_per_output_token
 //! - gen_ai.client.token.usage
+//!
+//! Families disabled in [`MetricsConfig`] aren't registered at all, and the label dimensions it
+//! drops are left off every family, so large installs can bound the cardinality of the metrics.
+//! The opt-in user and group labels are kept to a fixed number of values each.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use prometheus::{HistogramOpts, HistogramVec, Registry};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    config::{LabelOverflow, MetricsConfig},
+    metrics::MetricsRecorder,
+    request_logging::serializers::HttpAnalyticsRow,
+};
+
+/// Labels the GenAI metrics carry, any of which can be dropped by `metrics.dropped_labels`
+pub const GEN_AI_LABELS: &[&str] = &[
+    "gen_ai_operation_name",
+    "gen_ai_provider_name",
+    "gen_ai_request_model",
+    "gen_ai_response_model",
+    "gen_ai_token_type",
+    "server_address",
+    "server_port",
+    "error_type",
+];
+
+/// Label carrying the requesting user, when `metrics.user_labels` is enabled
+const USER_LABEL: &str = "user_id";
+
+/// Label carrying the groups of the requesting user, when `metrics.group_labels` is enabled
+const GROUP_LABEL: &str = "group";
+
+/// Label of the users or groups beyond the limit, with [`LabelOverflow::Other`]
+const OTHER: &str = "other";
+
+/// How long a user's groups are cached for the group label
+const GROUP_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// A histogram and the labels it was registered with
+#[derive(Clone)]
+struct Family {
+    histogram: HistogramVec,
+    labels: Vec<&'static str>,
+}
+
+impl Family {
+    /// Register a histogram with those of `labels` that `config` keeps, or nothing if `config`
+    /// disables the family
+    fn register(
+        registry: &Registry,
+        config: &MetricsConfig,
+        opts: HistogramOpts,
+        labels: &[&'static str],
+    ) -> Result<Option<Self>, prometheus::Error> {
+        if !config.family_enabled(&opts.common_opts.name) {
+            return Ok(None);
+        }
+        let mut labels: Vec<&'static str> = labels
+            .iter()
+            .copied()
+            .filter(|label| !config.dropped_labels.iter().any(|dropped| dropped == label))
+            .collect();
+        if config.user_labels {
+            labels.push(USER_LABEL);
+        }
+        if config.group_labels {
+            labels.push(GROUP_LABEL);
+        }
+
+        let histogram = HistogramVec::new(opts, &labels)?;
+        registry.register(Box::new(histogram.clone()))?;
+        Ok(Some(Self { histogram, labels }))
+    }
+
+    /// Observe `value`, taking the value of each of the family's labels from `labels` by name
+    fn observe(&self, value: f64, labels: &[(&str, &str)]) {
+        let values: Vec<&str> = self
+            .labels
+            .iter()
+            .map(|label| labels.iter().find(|(name, _)| name == label).map_or("", |(_, value)| *value))
+            .collect();
+        self.histogram.with_label_values(&values).observe(value);
+    }
+}
+
+/// Keeps the values of a label within a limit
+struct LabelLimiter {
+    overflow: LabelOverflow,
+    max: usize,
+    /// Values given their own label so far, with [`LabelOverflow::Other`]
+    seen: Mutex<HashSet<String>>,
+}
+
+impl LabelLimiter {
+    fn new(overflow: LabelOverflow, max: usize) -> Self {
+        Self {
+            overflow,
+            max,
+            seen: Mutex::default(),
+        }
+    }
+
+    /// Label to record `value` under
+    fn label(&self, value: String) -> String {
+        match self.overflow {
+            LabelOverflow::Other => {
+                let mut seen = self.seen.lock().expect("label limiter lock poisoned");
+                if seen.contains(&value) || seen.len() < self.max {
+                    seen.insert(value.clone());
+                    value
+                } else {
+                    OTHER.to_string()
+                }
+            }
+            LabelOverflow::Hash => {
+                let digest = Sha256::digest(value.as_bytes());
+                let hash = u64::from_be_bytes(digest[..8].try_into().expect("digest is 32 bytes"));
+                format!("bucket-{}", hash % self.max as u64)
+            }
+        }
+    }
+}
+
+/// Looks up, and caches, the groups of the users making requests
+#[derive(Clone)]
+struct GroupLookup {
+    pool: PgPool,
+    cache: Arc<Mutex<HashMap<Uuid, (Instant, String)>>>,
+}
 
-use crate::{metrics::MetricsRecorder, request_logging::serializers::HttpAnalyticsRow};
+impl GroupLookup {
+    /// Names of the groups `user_id` belongs to, comma-separated in name order
+    async fn groups(&self, user_id: Uuid) -> String {
+        if let Some((fetched_at, groups)) = self.cache.lock().expect("group cache lock poisoned").get(&user_id) {
+            if fetched_at.elapsed() < GROUP_CACHE_TTL {
+                return groups.clone();
+            }
+        }
+
+        let groups = match sqlx::query_scalar!(
+            "SELECT g.name FROM user_groups ug JOIN groups g ON g.id = ug.group_id WHERE ug.user_id = $1 ORDER BY g.name",
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        {
+            Ok(names) => names.join(","),
+            Err(e) => {
+                warn!("Failed to look up groups of user {} for metrics: {}", user_id, e);
+                return String::new();
+            }
+        };
+        self.cache
+            .lock()
+            .expect("group cache lock poisoned")
+            .insert(user_id, (Instant::now(), groups.clone()));
+        groups
+    }
+}
 
 /// GenAI metrics instruments using Prometheus
 #[derive(Clone)]
 pub struct GenAiMetrics {
     /// Total request duration (required)
-    request_duration: HistogramVec,
+    request_duration: Option<Family>,
     /// Time until first byte received (recommended, streaming only)
-    time_to_first_token: HistogramVec,
+    time_to_first_token: Option<Family>,
     /// Average time per output token during decode (recommended)
-    time_per_output_token: HistogramVec,
+    time_per_output_token: Option<Family>,
     /// Token usage - input and output (recommended)
-    token_usage: HistogramVec,
+    token_usage: Option<Family>,
+    /// Limits the user label, when user labels are enabled
+    user_labels: Option<Arc<LabelLimiter>>,
+    /// Limits the group label, when group labels are enabled
+    group_labels: Option<Arc<LabelLimiter>>,
+    /// Source of the group label; without it requests carry an empty group label
+    group_lookup: Option<GroupLookup>,
     /// Reference to the Prometheus registry
     registry: Registry,
 }
@@ -29,12 +197,19 @@ pub struct GenAiMetrics {
 impl GenAiMetrics {
     /// Create new GenAI metrics instruments and register with Prometheus
     pub fn new(registry: &Registry) -> Result<Self, prometheus::Error> {
+        Self::with_config(registry, &MetricsConfig::default())
+    }
+
+    /// Create the GenAI metrics instruments `config` enables and register them with Prometheus
+    pub fn with_config(registry: &Registry, config: &MetricsConfig) -> Result<Self, prometheus::Error> {
         // Request duration histogram (required)
         // Buckets from OTel spec: 0.01s to 81.92s (exponential with factor 2)
         let duration_buckets = vec![
             0.01, 0.02, 0.04, 0.08, 0.16, 0.32, 0.64, 1.28, 2.56, 5.12, 10.24, 20.48, 40.96, 81.92,
         ];
-        let request_duration = HistogramVec::new(
+        let request_duration = Family::register(
+            registry,
+            config,
             HistogramOpts::new("gen_ai_server_request_duration_seconds", "GenAI operation duration").buckets(duration_buckets),
             &[
                 "gen_ai_operation_name",
@@ -46,14 +221,15 @@ impl GenAiMetrics {
                 "error_type",
             ],
         )?;
-        registry.register(Box::new(request_duration.clone()))?;
 
         // Time to first token histogram (recommended)
         // Buckets from OTel spec: 0.001s to 10.0s
         let ttft_buckets = vec![
             0.001, 0.005, 0.01, 0.02, 0.04, 0.06, 0.08, 0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0, 7.5, 10.0,
         ];
-        let time_to_first_token = HistogramVec::new(
+        let time_to_first_token = Family::register(
+            registry,
+            config,
             HistogramOpts::new(
                 "gen_ai_server_time_to_first_token_seconds",
                 "Time to generate first token for successful responses",
@@ -68,12 +244,13 @@ impl GenAiMetrics {
                 "server_port",
             ],
         )?;
-        registry.register(Box::new(time_to_first_token.clone()))?;
 
         // Time per output token histogram (recommended)
         // Buckets from OTel spec: 0.01s to 2.5s (exponential with factor 2)
         let tpot_buckets = vec![0.01, 0.025, 0.05, 0.075, 0.1, 0.15, 0.2, 0.3, 0.4, 0.5, 0.75, 1.0, 2.5];
-        let time_per_output_token = HistogramVec::new(
+        let time_per_output_token = Family::register(
+            registry,
+            config,
             HistogramOpts::new(
                 "gen_ai_server_time_per_output_token_seconds",
                 "Time per output token generated after the first token",
@@ -88,14 +265,15 @@ impl GenAiMetrics {
                 "server_port",
             ],
         )?;
-        registry.register(Box::new(time_per_output_token.clone()))?;
 
         // Token usage histogram (recommended)
         // Buckets from OTel spec: 1 to 67108864 tokens (exponential with factor 4)
         let token_buckets = vec![
             1.0, 4.0, 16.0, 64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0, 16777216.0, 67108864.0,
         ];
-        let token_usage = HistogramVec::new(
+        let token_usage = Family::register(
+            registry,
+            config,
             HistogramOpts::new("gen_ai_client_token_usage", "Number of tokens used in prompt and completion").buckets(token_buckets),
             &[
                 "gen_ai_operation_name",
@@ -107,40 +285,83 @@ impl GenAiMetrics {
                 "server_port",
             ],
         )?;
-        registry.register(Box::new(token_usage.clone()))?;
 
         Ok(Self {
             request_duration,
             time_to_first_token,
             time_per_output_token,
             token_usage,
+            user_labels: config
+                .user_labels
+                .then(|| Arc::new(LabelLimiter::new(config.label_overflow, config.max_user_labels))),
+            group_labels: config
+                .group_labels
+                .then(|| Arc::new(LabelLimiter::new(config.label_overflow, config.max_group_labels))),
+            group_lookup: None,
             registry: registry.clone(),
         })
     }
 
+    /// Look up the groups for the group label in `pool`
+    pub fn with_group_lookup(mut self, pool: PgPool) -> Self {
+        self.group_lookup = Some(GroupLookup {
+            pool,
+            cache: Arc::default(),
+        });
+        self
+    }
+
     /// Get reference to the Prometheus registry
     pub fn registry(&self) -> &Registry {
         &self.registry
     }
 
     /// Record request duration metric
-    pub fn record_request_duration(&self, duration_seconds: f64, labels: &[&str]) {
-        self.request_duration.with_label_values(labels).observe(duration_seconds);
+    pub fn record_request_duration(&self, duration_seconds: f64, labels: &[(&str, &str)]) {
+        if let Some(family) = &self.request_duration {
+            family.observe(duration_seconds, labels);
+        }
     }
 
     /// Record time to first token (only for streaming requests)
-    pub fn record_time_to_first_token(&self, ttfb_seconds: f64, labels: &[&str]) {
-        self.time_to_first_token.with_label_values(labels).observe(ttfb_seconds);
+    pub fn record_time_to_first_token(&self, ttfb_seconds: f64, labels: &[(&str, &str)]) {
+        if let Some(family) = &self.time_to_first_token {
+            family.observe(ttfb_seconds, labels);
+        }
     }
 
     /// Record time per output token (only when output tokens > 0)
-    pub fn record_time_per_output_token(&self, time_per_token_seconds: f64, labels: &[&str]) {
-        self.time_per_output_token.with_label_values(labels).observe(time_per_token_seconds);
+    pub fn record_time_per_output_token(&self, time_per_token_seconds: f64, labels: &[(&str, &str)]) {
+        if let Some(family) = &self.time_per_output_token {
+            family.observe(time_per_token_seconds, labels);
+        }
     }
 
     /// Record token usage (called twice per request: once for input, once for output)
-    pub fn record_token_usage(&self, token_count: f64, labels: &[&str]) {
-        self.token_usage.with_label_values(labels).observe(token_count);
+    pub fn record_token_usage(&self, token_count: f64, labels: &[(&str, &str)]) {
+        if let Some(family) = &self.token_usage {
+            family.observe(token_count, labels);
+        }
+    }
+
+    /// User label of a request, empty if it has no user
+    fn user_label(&self, user_id: Option<Uuid>) -> String {
+        match (&self.user_labels, user_id) {
+            (Some(limiter), Some(user_id)) => limiter.label(user_id.to_string()),
+            _ => String::new(),
+        }
+    }
+
+    /// Group label of a request, empty if its user is in no groups
+    async fn group_label(&self, user_id: Option<Uuid>) -> String {
+        let (Some(limiter), Some(lookup), Some(user_id)) = (&self.group_labels, &self.group_lookup, user_id) else {
+            return String::new();
+        };
+        let groups = lookup.groups(user_id).await;
+        if groups.is_empty() {
+            return groups;
+        }
+        limiter.label(groups)
     }
 }
 
@@ -149,7 +370,7 @@ impl MetricsRecorder for GenAiMetrics {
     async fn record_from_analytics(&self, row: &HttpAnalyticsRow) {
         // Extract operation from response_type
         let operation = match row.response_type.as_str() {
-            "chat_completion" | "chat_completion_stream" => "chat",
+            "chat_completion" | "chat_completion_stream" | "messages" | "messages_stream" => "chat",
             "completion" => "text_completion",
             "embeddings" | "base64_embeddings" => "embeddings",
             _ => "",
@@ -172,24 +393,29 @@ impl MetricsRecorder for GenAiMetrics {
         let provider_name = row.provider_name.as_deref().unwrap_or("");
         let request_model = row.request_model.as_deref().unwrap_or("");
         let response_model = row.response_model.as_deref().unwrap_or("");
+        let user = self.user_label(row.user_id);
+        let group = self.group_label(row.user_id).await;
+
+        // Each family takes the labels it was registered with from these
+        let labels = [
+            ("gen_ai_operation_name", operation),
+            ("gen_ai_provider_name", provider_name),
+            ("gen_ai_request_model", request_model),
+            ("gen_ai_response_model", response_model),
+            ("server_address", server_address.as_str()),
+            ("server_port", server_port.as_str()),
+            ("error_type", error_type.as_str()),
+            (USER_LABEL, user.as_str()),
+            (GROUP_LABEL, group.as_str()),
+        ];
 
         // Record request duration (always)
-        let duration_labels = vec![
-            operation,
-            provider_name,
-            request_model,
-            response_model,
-            server_address,
-            server_port,
-            &error_type,
-        ];
-        self.record_request_duration(row.duration_ms as f64 / 1000.0, &duration_labels);
+        self.record_request_duration(row.duration_ms as f64 / 1000.0, &labels);
 
         // Record time to first token (only for streaming)
         if is_streaming {
             if let Some(ttfb_ms) = row.duration_to_first_byte_ms {
-                let ttft_labels = vec![operation, provider_name, request_model, response_model, server_address, server_port];
-                self.record_time_to_first_token(ttfb_ms as f64 / 1000.0, &ttft_labels);
+                self.record_time_to_first_token(ttfb_ms as f64 / 1000.0, &labels);
             }
         }
 
@@ -198,36 +424,19 @@ impl MetricsRecorder for GenAiMetrics {
             if let Some(ttfb_ms) = row.duration_to_first_byte_ms {
                 let time_after_first_token = (row.duration_ms - ttfb_ms) as f64 / 1000.0;
                 let time_per_token = time_after_first_token / row.completion_tokens as f64;
-                let tpot_labels = vec![operation, provider_name, request_model, response_model, server_address, server_port];
-                self.record_time_per_output_token(time_per_token, &tpot_labels);
+                self.record_time_per_output_token(time_per_token, &labels);
             }
         }
 
         // Record token usage (input tokens)
         if row.prompt_tokens > 0 {
-            let input_labels = vec![
-                operation,
-                provider_name,
-                request_model,
-                response_model,
-                "input",
-                server_address,
-                server_port,
-            ];
+            let input_labels = [labels.as_slice(), &[("gen_ai_token_type", "input")]].concat();
             self.record_token_usage(row.prompt_tokens as f64, &input_labels);
         }
 
         // Record token usage (output tokens)
         if row.completion_tokens > 0 {
-            let output_labels = vec![
-                operation,
-                provider_name,
-                request_model,
-                response_model,
-                "output",
-                server_address,
-                server_port,
-            ];
+            let output_labels = [labels.as_slice(), &[("gen_ai_token_type", "output")]].concat();
             self.record_token_usage(row.completion_tokens as f64, &output_labels);
         }
     }
@@ -236,6 +445,7 @@ impl MetricsRecorder for GenAiMetrics {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::MetricsConfig;
     use crate::request_logging::serializers::HttpAnalyticsRow;
     use uuid::Uuid;
 
@@ -265,6 +475,7 @@ mod tests {
             prompt_tokens: 10,
             completion_tokens: 50,
             total_tokens: 60,
+            usage_estimated: false,
             response_type: "chat_completion_stream".to_string(),
             user_id: None,
             user_email: None,
@@ -274,6 +485,14 @@ mod tests {
             server_address: "api.openai.com".to_string(),
             server_port: 443,
             provider_name: Some("openai".to_string()),
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
         };
 
         // Call the function under test
@@ -374,6 +593,7 @@ mod tests {
             prompt_tokens: 20,
             completion_tokens: 100,
             total_tokens: 120,
+            usage_estimated: false,
             response_type: "chat_completion".to_string(), // NOT streaming
             user_id: None,
             user_email: None,
@@ -383,6 +603,14 @@ mod tests {
             server_address: "api.anthropic.com".to_string(),
             server_port: 443,
             provider_name: Some("anthropic".to_string()),
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
         };
 
         metrics.record_from_analytics(&row).await;
@@ -431,6 +659,7 @@ mod tests {
             prompt_tokens: 100,
             completion_tokens: 0, // Embeddings don't have completion tokens
             total_tokens: 100,
+            usage_estimated: false,
             response_type: "embeddings".to_string(),
             user_id: None,
             user_email: None,
@@ -440,6 +669,14 @@ mod tests {
             server_address: "api.openai.com".to_string(),
             server_port: 443,
             provider_name: Some("openai".to_string()),
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
         };
 
         metrics.record_from_analytics(&row).await;
@@ -499,6 +736,7 @@ mod tests {
             prompt_tokens: 0, // No tokens on error
             completion_tokens: 0,
             total_tokens: 0,
+            usage_estimated: false,
             response_type: "chat_completion".to_string(),
             user_id: None,
             user_email: None,
@@ -508,6 +746,14 @@ mod tests {
             server_address: "api.openai.com".to_string(),
             server_port: 443,
             provider_name: Some("openai".to_string()),
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
         };
 
         metrics.record_from_analytics(&row).await;
@@ -553,6 +799,7 @@ mod tests {
             prompt_tokens: 10,
             completion_tokens: 20,
             total_tokens: 30,
+            usage_estimated: false,
             response_type: "completion".to_string(),
             user_id: None,
             user_email: None,
@@ -562,6 +809,14 @@ mod tests {
             server_address: "localhost".to_string(),
             server_port: 8080,
             provider_name: None, // Missing provider
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
         };
 
         metrics.record_from_analytics(&row).await;
@@ -610,6 +865,7 @@ mod tests {
             prompt_tokens: 50,
             completion_tokens: 0, // No output tokens
             total_tokens: 50,
+            usage_estimated: false,
             response_type: "chat_completion".to_string(),
             user_id: None,
             user_email: None,
@@ -619,6 +875,14 @@ mod tests {
             server_address: "api.openai.com".to_string(),
             server_port: 443,
             provider_name: Some("openai".to_string()),
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
         };
 
         metrics.record_from_analytics(&row).await;
@@ -664,6 +928,7 @@ mod tests {
             prompt_tokens: 0,
             completion_tokens: 0,
             total_tokens: 0,
+            usage_estimated: false,
             response_type: "other".to_string(),
             user_id: None,
             user_email: None,
@@ -673,6 +938,14 @@ mod tests {
             server_address: "api.example.com".to_string(),
             server_port: 443,
             provider_name: Some("custom".to_string()),
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
         };
 
         metrics.record_from_analytics(&row).await;
@@ -713,6 +986,7 @@ mod tests {
                 prompt_tokens: 0,
                 completion_tokens: 0,
                 total_tokens: 0,
+                usage_estimated: false,
                 response_type: "chat_completion".to_string(),
                 user_id: None,
                 user_email: None,
@@ -722,6 +996,14 @@ mod tests {
                 server_address: "api.openai.com".to_string(),
                 server_port: 443,
                 provider_name: Some("openai".to_string()),
+                embedding_inputs: None,
+                embedding_dimensions: None,
+                pii_categories: None,
+                moderation_decision: None,
+                moderation_categories: None,
+                variant: None,
+                conversation_id: None,
+                tags: None,
             };
 
             metrics.record_from_analytics(&row).await;
@@ -760,6 +1042,7 @@ mod tests {
             prompt_tokens: 50,
             completion_tokens: 0,
             total_tokens: 50,
+            usage_estimated: false,
             response_type: "base64_embeddings".to_string(),
             user_id: None,
             user_email: None,
@@ -769,6 +1052,14 @@ mod tests {
             server_address: "api.openai.com".to_string(),
             server_port: 443,
             provider_name: Some("openai".to_string()),
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
         };
 
         metrics.record_from_analytics(&row).await;
@@ -787,4 +1078,169 @@ mod tests {
             "base64_embeddings should map to embeddings operation"
         );
     }
+
+    #[tokio::test]
+    async fn test_metrics_config_limits_cardinality() {
+        let registry = Registry::new();
+        let config = MetricsConfig {
+            disabled_families: vec!["gen_ai_server_time_*".to_string()],
+            dropped_labels: vec!["server_address".to_string(), "server_port".to_string()],
+            user_labels: true,
+            max_user_labels: 1,
+            ..Default::default()
+        };
+        let metrics = GenAiMetrics::with_config(&registry, &config).expect("Failed to create metrics");
+
+        let mut row = HttpAnalyticsRow {
+            instance_id: Uuid::new_v4(),
+            correlation_id: 666,
+            timestamp: chrono::Utc::now(),
+            method: "POST".to_string(),
+            uri: "/v1/chat/completions".to_string(),
+            request_model: Some("gpt-4".to_string()),
+            response_model: Some("gpt-4".to_string()),
+            status_code: 200,
+            duration_ms: 1000,
+            duration_to_first_byte_ms: Some(100),
+            prompt_tokens: 10,
+            completion_tokens: 20,
+            total_tokens: 30,
+            usage_estimated: false,
+            response_type: "chat_completion_stream".to_string(),
+            user_id: None,
+            user_email: None,
+            access_source: "api_key".to_string(),
+            input_price_per_token: None,
+            output_price_per_token: None,
+            server_address: "api.openai.com".to_string(),
+            server_port: 443,
+            provider_name: Some("openai".to_string()),
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
+        };
+        let first_user = Uuid::new_v4();
+        for user_id in [first_user, Uuid::new_v4(), Uuid::new_v4(), first_user] {
+            row.user_id = Some(user_id);
+            metrics.record_from_analytics(&row).await;
+        }
+
+        let metric_families = registry.gather();
+        let names: Vec<&str> = metric_families.iter().map(|m| m.get_name()).collect();
+        assert_eq!(names, vec!["gen_ai_client_token_usage", "gen_ai_server_request_duration_seconds"]);
+
+        let duration_metric = &metric_families[1];
+        let mut users: Vec<(String, u64)> = duration_metric
+            .get_metric()
+            .iter()
+            .map(|m| {
+                assert_eq!(find_label(m.get_label(), "server_address"), None);
+                (find_label(m.get_label(), "user_id").unwrap(), m.get_histogram().get_sample_count())
+            })
+            .collect();
+        // Only the first user gets their own label; everyone after them shares one
+        let mut expected = vec![(first_user.to_string(), 2), ("other".to_string(), 2)];
+        users.sort();
+        expected.sort();
+        assert_eq!(users, expected);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_group_labels_with_hash_overflow(pool: sqlx::PgPool) {
+        use crate::api::models::users::Role;
+        use crate::test_utils::{add_user_to_group, create_test_group, create_test_user};
+
+        let registry = Registry::new();
+        let config = MetricsConfig {
+            disabled_families: vec!["gen_ai_server_time_*".to_string(), "gen_ai_client_token_usage".to_string()],
+            user_labels: true,
+            max_user_labels: 4,
+            group_labels: true,
+            max_group_labels: 4,
+            label_overflow: LabelOverflow::Hash,
+            ..Default::default()
+        };
+        let metrics = GenAiMetrics::with_config(&registry, &config)
+            .expect("Failed to create metrics")
+            .with_group_lookup(pool.clone());
+
+        let grouped_user = create_test_user(&pool, Role::StandardUser).await;
+        let ungrouped_user = create_test_user(&pool, Role::StandardUser).await;
+        let group = create_test_group(&pool).await;
+        add_user_to_group(&pool, grouped_user.id, group.id).await;
+
+        let mut row = HttpAnalyticsRow {
+            instance_id: Uuid::new_v4(),
+            correlation_id: 777,
+            timestamp: chrono::Utc::now(),
+            method: "POST".to_string(),
+            uri: "/v1/chat/completions".to_string(),
+            request_model: Some("gpt-4".to_string()),
+            response_model: Some("gpt-4".to_string()),
+            status_code: 200,
+            duration_ms: 1000,
+            duration_to_first_byte_ms: None,
+            prompt_tokens: 10,
+            completion_tokens: 20,
+            total_tokens: 30,
+            usage_estimated: false,
+            response_type: "chat_completion".to_string(),
+            user_id: None,
+            user_email: None,
+            access_source: "api_key".to_string(),
+            input_price_per_token: None,
+            output_price_per_token: None,
+            server_address: "api.openai.com".to_string(),
+            server_port: 443,
+            provider_name: Some("openai".to_string()),
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
+        };
+        for user_id in [grouped_user.id, grouped_user.id, ungrouped_user.id] {
+            row.user_id = Some(user_id);
+            metrics.record_from_analytics(&row).await;
+        }
+
+        let metric_families = registry.gather();
+        let duration_metric = metric_families
+            .iter()
+            .find(|m| m.get_name() == "gen_ai_server_request_duration_seconds")
+            .expect("Should have request duration metric");
+        let series: Vec<(String, String, u64)> = duration_metric
+            .get_metric()
+            .iter()
+            .map(|m| {
+                (
+                    find_label(m.get_label(), "user_id").unwrap(),
+                    find_label(m.get_label(), "group").unwrap(),
+                    m.get_histogram().get_sample_count(),
+                )
+            })
+            .collect();
+        assert_eq!(series.len(), 2);
+
+        // Users and groups are bucketed, the same way on every replica, and users in no
+        // groups get an empty group label
+        let limiter = LabelLimiter::new(LabelOverflow::Hash, 4);
+        let grouped = series.iter().find(|(_, group, _)| !group.is_empty()).unwrap();
+        assert_eq!(grouped.0, limiter.label(grouped_user.id.to_string()));
+        assert_eq!(grouped.1, limiter.label(group.name.clone()));
+        assert!(grouped.1.starts_with("bucket-"));
+        assert_eq!(grouped.2, 2);
+        let ungrouped = series.iter().find(|(_, group, _)| group.is_empty()).unwrap();
+        assert_eq!(ungrouped.0, limiter.label(ungrouped_user.id.to_string()));
+        assert_eq!(ungrouped.2, 1);
+    }
 }
@@ -3,8 +3,12 @@
 //! This module implements the OpenTelemetry Semantic Conventions for Generative AI,
 //! providing standardized metrics for monitoring AI model requests through the proxy.
 
+mod exposition;
 mod gen_ai;
+mod otlp;
 mod recorder;
 
-pub use gen_ai::GenAiMetrics;
+pub use exposition::{filter_families, filter_text};
+pub use gen_ai::{GenAiMetrics, GEN_AI_LABELS};
+pub use otlp::OtlpExporter;
 pub use recorder::MetricsRecorder;
@@ -0,0 +1,500 @@
+//! Push export of the metrics to an OpenTelemetry collector over OTLP/HTTP.
+//!
+//! Every `metrics.otlp.interval` the exporter renders the same metrics `/internal/metrics`
+//! serves, converts them from the Prometheus text format into an OTLP
+//! `ExportMetricsServiceRequest` and POSTs it as JSON to the configured endpoint. Counters and
+//! histograms are cumulative, as Prometheus keeps them, so a failed push isn't retried: the
+//! next one carries everything it would have.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use prometheus::{IntCounterVec, Opts, Registry};
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+use url::Url;
+
+use super::exposition::SAMPLE_SUFFIXES;
+use crate::config::OtlpMetricsConfig;
+
+/// OTLP `AGGREGATION_TEMPORALITY_CUMULATIVE`
+const CUMULATIVE: u8 = 2;
+
+/// Pushes the rendered metrics to an OTLP/HTTP endpoint
+pub struct OtlpExporter {
+    client: reqwest::Client,
+    endpoint: Url,
+    headers: Vec<(String, String)>,
+    interval: Duration,
+    request_timeout: Duration,
+    service_name: String,
+    /// Renders the metrics in the Prometheus text format
+    render: Box<dyn Fn() -> String + Send + Sync>,
+    /// Pushes by outcome: success or failure
+    exports: IntCounterVec,
+}
+
+impl OtlpExporter {
+    /// Start pushing the output of `render` to `endpoint`, registering the exporter's own
+    /// metrics with `registry`
+    pub fn spawn(
+        endpoint: Url,
+        config: &OtlpMetricsConfig,
+        registry: &Registry,
+        render: impl Fn() -> String + Send + Sync + 'static,
+    ) -> Result<(), prometheus::Error> {
+        let exports = IntCounterVec::new(
+            Opts::new("dwctl_otlp_metric_exports_total", "Pushes of the metrics to the OTLP endpoint"),
+            &["outcome"],
+        )?;
+        registry.register(Box::new(exports.clone()))?;
+
+        let exporter = Self {
+            client: reqwest::Client::new(),
+            endpoint,
+            headers: config.headers.clone().into_iter().collect(),
+            interval: config.interval,
+            request_timeout: config.request_timeout,
+            service_name: config.service_name.clone(),
+            render: Box::new(render),
+            exports,
+        };
+        tokio::spawn(exporter.run());
+        Ok(())
+    }
+
+    async fn run(self) {
+        let start_time = SystemTime::now();
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick completes immediately, and there's nothing worth pushing yet
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            let families = parse_text(&(self.render)());
+            let request = export_request(&families, &self.service_name, start_time, SystemTime::now());
+            match self.send(&request).await {
+                Ok(()) => {
+                    self.exports.with_label_values(&["success"]).inc();
+                    debug!(families = families.len(), "Pushed metrics to OTLP endpoint");
+                }
+                Err(e) => {
+                    self.exports.with_label_values(&["failure"]).inc();
+                    warn!("Failed to push metrics to OTLP endpoint: {:#}", e);
+                }
+            }
+        }
+    }
+
+    async fn send(&self, request: &Value) -> anyhow::Result<()> {
+        let mut builder = self.client.post(self.endpoint.clone()).timeout(self.request_timeout).json(request);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+
+        let response = builder.send().await?;
+        if !response.status().is_success() {
+            anyhow::bail!("OTLP endpoint returned {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+/// A metric family parsed from the Prometheus text format
+#[derive(Debug, Default)]
+struct TextFamily {
+    name: String,
+    help: String,
+    kind: String,
+    samples: Vec<Sample>,
+}
+
+impl TextFamily {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: "untyped".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Whether a sample named `name` belongs to this family, e.g. `<family>_bucket` for a
+    /// histogram
+    fn owns(&self, name: &str) -> bool {
+        name == self.name || SAMPLE_SUFFIXES.iter().any(|suffix| name.strip_suffix(suffix) == Some(&self.name))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+    name: String,
+    labels: Vec<(String, String)>,
+    value: f64,
+}
+
+/// Parse metrics rendered in the Prometheus text format into their families
+fn parse_text(text: &str) -> Vec<TextFamily> {
+    let mut families: Vec<TextFamily> = Vec::new();
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(comment) = line.strip_prefix('#') {
+            let mut parts = comment.trim_start().splitn(3, ' ');
+            let (Some(keyword @ ("HELP" | "TYPE")), Some(name)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            if families.last().is_none_or(|family| family.name != name) {
+                families.push(TextFamily::new(name));
+            }
+            let family = families.last_mut().expect("family was just pushed");
+            let rest = parts.next().unwrap_or_default().trim();
+            if keyword == "HELP" {
+                family.help = rest.replace("\\n", "\n").replace("\\\\", "\\");
+            } else {
+                family.kind = rest.to_string();
+            }
+            continue;
+        }
+
+        let Some(sample) = parse_sample(line) else {
+            debug!("Skipping unparseable metrics line: {}", line);
+            continue;
+        };
+        if families.last().is_none_or(|family| !family.owns(&sample.name)) {
+            families.push(TextFamily::new(&sample.name));
+        }
+        families.last_mut().expect("family was just pushed").samples.push(sample);
+    }
+
+    families
+}
+
+/// Parse a sample line: `name{label="value",...} value [timestamp]`
+fn parse_sample(line: &str) -> Option<Sample> {
+    let name_end = line.find(['{', ' ']).unwrap_or(line.len());
+    let name = line[..name_end].to_string();
+    let mut rest = &line[name_end..];
+
+    let mut labels = Vec::new();
+    if let Some(body) = rest.strip_prefix('{') {
+        let mut chars = body.char_indices();
+        loop {
+            // Label name, up to `=`, or the end of the labels
+            let mut label = String::new();
+            let end = loop {
+                match chars.next()? {
+                    (i, '}') => break Some(i),
+                    (_, '=') => break None,
+                    (_, ',') | (_, ' ') => {}
+                    (_, c) => label.push(c),
+                }
+            };
+            if let Some(i) = end {
+                rest = &body[i + 1..];
+                break;
+            }
+
+            // Quoted label value, with `\\`, `\"` and `\n` escapes
+            if chars.next()?.1 != '"' {
+                return None;
+            }
+            let mut value = String::new();
+            loop {
+                match chars.next()?.1 {
+                    '"' => break,
+                    '\\' => match chars.next()?.1 {
+                        'n' => value.push('\n'),
+                        c => value.push(c),
+                    },
+                    c => value.push(c),
+                }
+            }
+            labels.push((label, value));
+        }
+    }
+
+    let value = parse_value(rest.split_whitespace().next()?)?;
+    Some(Sample { name, labels, value })
+}
+
+/// Parse a sample value or `le` bound, including `+Inf`, `-Inf` and `NaN`
+fn parse_value(value: &str) -> Option<f64> {
+    match value {
+        "+Inf" => Some(f64::INFINITY),
+        "-Inf" => Some(f64::NEG_INFINITY),
+        _ => value.parse().ok(),
+    }
+}
+
+/// Build an OTLP `ExportMetricsServiceRequest` in its JSON encoding
+fn export_request(families: &[TextFamily], service_name: &str, start_time: SystemTime, time: SystemTime) -> Value {
+    let start_time = unix_nanos(start_time);
+    let time = unix_nanos(time);
+    let point = |labels: &[(String, String)]| {
+        json!({
+            "attributes": attributes(labels),
+            "startTimeUnixNano": start_time,
+            "timeUnixNano": time,
+        })
+    };
+
+    let metrics: Vec<Value> = families
+        .iter()
+        .filter_map(|family| {
+            let data = match family.kind.as_str() {
+                "counter" => json!({
+                    "sum": {
+                        "dataPoints": number_points(family, point),
+                        "aggregationTemporality": CUMULATIVE,
+                        "isMonotonic": true,
+                    }
+                }),
+                "gauge" | "untyped" => json!({ "gauge": { "dataPoints": number_points(family, point) } }),
+                "histogram" => json!({
+                    "histogram": {
+                        "dataPoints": histogram_points(family, point),
+                        "aggregationTemporality": CUMULATIVE,
+                    }
+                }),
+                // None of our families are summaries
+                _ => return None,
+            };
+            let mut metric = json!({ "name": family.name, "description": family.help });
+            metric.as_object_mut()?.extend(data.as_object()?.clone());
+            Some(metric)
+        })
+        .collect();
+
+    json!({
+        "resourceMetrics": [{
+            "resource": { "attributes": attributes(&[("service.name".to_string(), service_name.to_string())]) },
+            "scopeMetrics": [{
+                "scope": { "name": "dwctl", "version": env!("CARGO_PKG_VERSION") },
+                "metrics": metrics,
+            }],
+        }],
+    })
+}
+
+fn number_points(family: &TextFamily, point: impl Fn(&[(String, String)]) -> Value) -> Vec<Value> {
+    family
+        .samples
+        .iter()
+        .map(|sample| {
+            let mut point = point(&sample.labels);
+            point["asDouble"] = json!(sample.value);
+            point
+        })
+        .collect()
+}
+
+fn histogram_points(family: &TextFamily, point: impl Fn(&[(String, String)]) -> Value) -> Vec<Value> {
+    /// The samples of one histogram series: cumulative buckets by bound, sum and count
+    #[derive(Default)]
+    struct Series {
+        buckets: Vec<(f64, f64)>,
+        sum: f64,
+        count: f64,
+    }
+
+    let mut series: Vec<(Vec<(String, String)>, Series)> = Vec::new();
+    for sample in &family.samples {
+        let bound = sample
+            .labels
+            .iter()
+            .find(|(name, _)| name == "le")
+            .and_then(|(_, le)| parse_value(le));
+        let labels: Vec<(String, String)> = sample.labels.iter().filter(|(name, _)| name != "le").cloned().collect();
+        let index = match series.iter().position(|(existing, _)| *existing == labels) {
+            Some(index) => index,
+            None => {
+                series.push((labels, Series::default()));
+                series.len() - 1
+            }
+        };
+        let entry = &mut series[index].1;
+        match &sample.name[family.name.len()..] {
+            "_bucket" => entry.buckets.extend(bound.map(|bound| (bound, sample.value))),
+            "_sum" => entry.sum = sample.value,
+            "_count" => entry.count = sample.value,
+            _ => {}
+        }
+    }
+
+    series
+        .into_iter()
+        .map(|(labels, mut series)| {
+            series.buckets.sort_by(|a, b| a.0.total_cmp(&b.0));
+            // OTLP counts each bucket on its own, with an implicit last bucket above the
+            // highest bound, where Prometheus buckets are cumulative and end with `+Inf`
+            let mut explicit_bounds = Vec::new();
+            let mut bucket_counts = Vec::new();
+            let mut below = 0.0;
+            for (bound, cumulative) in series.buckets.iter().filter(|(bound, _)| bound.is_finite()) {
+                explicit_bounds.push(*bound);
+                bucket_counts.push(((cumulative - below) as u64).to_string());
+                below = *cumulative;
+            }
+            bucket_counts.push(((series.count - below) as u64).to_string());
+
+            let mut point = point(&labels);
+            point["count"] = json!((series.count as u64).to_string());
+            point["sum"] = json!(series.sum);
+            point["bucketCounts"] = json!(bucket_counts);
+            point["explicitBounds"] = json!(explicit_bounds);
+            point
+        })
+        .collect()
+}
+
+fn attributes(labels: &[(String, String)]) -> Vec<Value> {
+    labels
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } }))
+        .collect()
+}
+
+/// Nanoseconds since the epoch, as a string as OTLP JSON encodes 64-bit integers
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+    use std::sync::{Arc, Mutex};
+
+    /// Pushes received by the fake collector, with their `x-api-key` header
+    type Received = Arc<Mutex<Vec<(Option<String>, Value)>>>;
+
+    fn find_label<'a>(metric: &'a prometheus::proto::Metric, name: &str) -> Option<&'a str> {
+        metric.get_label().iter().find(|l| l.get_name() == name).map(|l| l.get_value())
+    }
+
+    const TEXT: &str = "\
+# HELP axum_http_requests_total The number of HTTP requests.
+# TYPE axum_http_requests_total counter
+axum_http_requests_total{method=\"GET\",endpoint=\"/healthz\",status=\"200\"} 3
+# TYPE axum_http_requests_pending gauge
+axum_http_requests_pending{method=\"GET\",endpoint=\"/say \\\"hi\\\"\"} 1
+# HELP gen_ai_client_token_usage Number of tokens used in prompt and completion
+# TYPE gen_ai_client_token_usage histogram
+gen_ai_client_token_usage_bucket{gen_ai_token_type=\"input\",le=\"1\"} 0
+gen_ai_client_token_usage_bucket{gen_ai_token_type=\"input\",le=\"16\"} 2
+gen_ai_client_token_usage_bucket{gen_ai_token_type=\"input\",le=\"+Inf\"} 3
+gen_ai_client_token_usage_sum{gen_ai_token_type=\"input\"} 40
+gen_ai_client_token_usage_count{gen_ai_token_type=\"input\"} 3
+";
+
+    #[test]
+    fn test_parse_text() {
+        let families = parse_text(TEXT);
+        let names: Vec<&str> = families.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "axum_http_requests_total",
+                "axum_http_requests_pending",
+                "gen_ai_client_token_usage"
+            ]
+        );
+        assert_eq!(families[0].kind, "counter");
+        assert_eq!(families[0].help, "The number of HTTP requests.");
+        assert_eq!(
+            families[1].samples,
+            vec![Sample {
+                name: "axum_http_requests_pending".to_string(),
+                labels: vec![
+                    ("method".to_string(), "GET".to_string()),
+                    ("endpoint".to_string(), "/say \"hi\"".to_string()),
+                ],
+                value: 1.0,
+            }]
+        );
+        assert_eq!(families[2].samples.len(), 5);
+    }
+
+    #[test]
+    fn test_export_request() {
+        let request = export_request(&parse_text(TEXT), "dwctl-test", UNIX_EPOCH, UNIX_EPOCH + Duration::from_secs(60));
+        let resource = &request["resourceMetrics"][0];
+        assert_eq!(resource["resource"]["attributes"][0]["value"]["stringValue"], "dwctl-test");
+        let metrics = resource["scopeMetrics"][0]["metrics"].as_array().unwrap();
+
+        let counter = &metrics[0]["sum"];
+        assert_eq!(counter["isMonotonic"], true);
+        assert_eq!(counter["aggregationTemporality"], 2);
+        assert_eq!(counter["dataPoints"][0]["asDouble"], 3.0);
+        assert_eq!(counter["dataPoints"][0]["timeUnixNano"], "60000000000");
+        assert_eq!(counter["dataPoints"][0]["attributes"].as_array().unwrap().len(), 3);
+
+        assert_eq!(metrics[1]["gauge"]["dataPoints"][0]["asDouble"], 1.0);
+
+        // Cumulative Prometheus buckets become per-bucket counts
+        let histogram = &metrics[2]["histogram"]["dataPoints"][0];
+        assert_eq!(
+            histogram["attributes"],
+            json!([{ "key": "gen_ai_token_type", "value": { "stringValue": "input" } }])
+        );
+        assert_eq!(histogram["explicitBounds"], json!([1.0, 16.0]));
+        assert_eq!(histogram["bucketCounts"], json!(["0", "2", "1"]));
+        assert_eq!(histogram["count"], "3");
+        assert_eq!(histogram["sum"], 40.0);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_are_pushed() {
+        let received: Received = Arc::default();
+        let app = Router::new()
+            .route(
+                "/v1/metrics",
+                post(
+                    |State(received): State<Received>, headers: axum::http::HeaderMap, Json(body): Json<Value>| async move {
+                        let api_key = headers.get("x-api-key").map(|v| v.to_str().unwrap().to_string());
+                        received.lock().unwrap().push((api_key, body));
+                        StatusCode::OK
+                    },
+                ),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let config = OtlpMetricsConfig {
+            enabled: true,
+            headers: [("x-api-key".to_string(), "secret".to_string())].into(),
+            interval: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let registry = Registry::new();
+        let endpoint: Url = format!("http://{addr}/v1/metrics").parse().unwrap();
+        OtlpExporter::spawn(endpoint, &config, &registry, || TEXT.to_string()).unwrap();
+
+        // Wait for the exporter to count a successful push
+        let pushed = || {
+            registry
+                .gather()
+                .iter()
+                .filter(|m| m.get_name() == "dwctl_otlp_metric_exports_total")
+                .flat_map(|m| m.get_metric())
+                .any(|m| find_label(m, "outcome") == Some("success") && m.get_counter().get_value() >= 1.0)
+        };
+        for _ in 0..100 {
+            if pushed() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(pushed(), "timed out waiting for a push");
+
+        let (api_key, body) = received.lock().unwrap().first().cloned().unwrap();
+        assert_eq!(api_key.as_deref(), Some("secret"));
+        assert_eq!(
+            body["resourceMetrics"][0]["scopeMetrics"][0]["metrics"].as_array().unwrap().len(),
+            3
+        );
+    }
+}
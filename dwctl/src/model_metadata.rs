@@ -0,0 +1,226 @@
+//! Deployment metadata in the AI proxy's model list.
+//!
+//! Admins can describe a deployment's context window, whether it supports tool calling and
+//! image inputs, and tag it with labels such as "code" or "eu-hosted", alongside its
+//! customer-facing pricing. `sync::onwards_config` records this
+//! metadata for each alias in the [`RoutingTable`], and the [`add_model_metadata`] middleware
+//! adds it to the models listed by onwards' `/v1/models`, as extra fields of each OpenAI model
+//! object. Fields are left out when unknown, so clients that don't look for them see the usual
+//! OpenAI response. Deprecated models are marked with a `deprecation` object. The internal targets
+//! behind fallbacks and traffic splits are dropped from the list, since they can't be requested
+//! directly.
+
+use crate::db::models::deployments::{DeploymentDBResponse, TokenPricing};
+use crate::routing::RoutingTable;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::watch;
+use tracing::warn;
+
+/// Path of onwards' model list, within the AI proxy router
+const MODELS_PATH: &str = "/v1/models";
+
+/// What clients can know about a model before sending it requests
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_context_tokens: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_tools: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_vision: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Customer-facing price per token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<TokenPricing>,
+}
+
+impl ModelMetadata {
+    /// The metadata of a deployment, or `None` if nothing is known about it
+    pub fn from_deployment(deployment: &DeploymentDBResponse) -> Option<Self> {
+        let pricing = deployment
+            .pricing
+            .as_ref()
+            .and_then(|pricing| pricing.upstream.clone())
+            .filter(|pricing| pricing.input_price_per_token.is_some() || pricing.output_price_per_token.is_some());
+        let metadata = Self {
+            max_context_tokens: deployment.max_context_tokens,
+            supports_tools: deployment.supports_tools,
+            supports_vision: deployment.supports_vision,
+            tags: deployment.tags.clone(),
+            pricing,
+        };
+        (metadata != Self::default()).then_some(metadata)
+    }
+}
+
+/// Middleware adding the metadata of each alias to the model list, and hiding internal targets
+pub async fn add_model_metadata(State(table): State<watch::Receiver<RoutingTable>>, request: Request, next: Next) -> Response {
+    if request.method() != Method::GET || request.uri().path() != MODELS_PATH {
+        return next.run(request).await;
+    }
+
+    let response = next.run(request).await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Failed to read the model list: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let Ok(mut models) = serde_json::from_slice::<Value>(&body) else {
+        return Response::from_parts(parts, Body::from(body));
+    };
+
+    if let Some(data) = models.get_mut("data").and_then(Value::as_array_mut) {
+        let table = table.borrow();
+        data.retain(|model| !model["id"].as_str().is_some_and(|id| table.is_internal(id)));
+        for model in data.iter_mut() {
+            let metadata = model["id"].as_str().and_then(|id| table.model_metadata(id));
+            let metadata = metadata.and_then(|metadata| serde_json::to_value(metadata).ok());
+            if let (Some(fields), Some(Value::Object(metadata))) = (model.as_object_mut(), metadata) {
+                fields.extend(metadata);
+            }
+            let deprecation = model["id"].as_str().and_then(|id| table.deprecation(id));
+            let deprecation = deprecation.and_then(|deprecation| serde_json::to_value(deprecation).ok());
+            if let (Some(fields), Some(deprecation)) = (model.as_object_mut(), deprecation) {
+                fields.insert("deprecation".to_string(), deprecation);
+            }
+        }
+    }
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    (parts, Json(models)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::deployments::{ModelPricing, ModelStatus};
+    use crate::deprecation::Deprecation;
+    use crate::routing::fallback_alias;
+    use axum::{middleware::from_fn_with_state, routing::get, Router};
+    use axum_test::TestServer;
+    use chrono::{DateTime, Utc};
+    use rust_decimal::Decimal;
+    use serde_json::json;
+    use std::str::FromStr;
+    use uuid::Uuid;
+
+    fn deployment() -> DeploymentDBResponse {
+        DeploymentDBResponse {
+            id: Uuid::new_v4(),
+            model_name: "gpt-4".to_string(),
+            alias: "gpt-4".to_string(),
+            description: None,
+            model_type: None,
+            capabilities: None,
+            created_by: Uuid::nil(),
+            hosted_on: Uuid::new_v4(),
+            status: ModelStatus::Active,
+            last_sync: None,
+            deleted: false,
+            enabled: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            requests_per_second: None,
+            burst_size: None,
+            pricing: None,
+            max_context_tokens: None,
+            supports_tools: None,
+            supports_vision: None,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_metadata_from_deployment() {
+        assert_eq!(ModelMetadata::from_deployment(&deployment()), None);
+
+        // Provider pricing isn't shown to clients
+        let mut with_pricing = deployment();
+        with_pricing.pricing = Some(ModelPricing {
+            upstream: Some(TokenPricing {
+                input_price_per_token: Some(Decimal::from_str("0.00001").unwrap()),
+                output_price_per_token: None,
+            }),
+            downstream: None,
+        });
+        with_pricing.supports_tools = Some(false);
+        let metadata = ModelMetadata::from_deployment(&with_pricing).unwrap();
+        assert_eq!(metadata.supports_tools, Some(false));
+        assert_eq!(
+            metadata.pricing.unwrap().input_price_per_token,
+            Some(Decimal::from_str("0.00001").unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_model_list_includes_metadata() {
+        let mut table = RoutingTable::default();
+        table.set_fallbacks("gpt-4".to_string(), vec![fallback_alias("gpt-4", 1)]);
+        table.set_model_metadata(
+            "gpt-4".to_string(),
+            ModelMetadata {
+                max_context_tokens: Some(128_000),
+                supports_tools: Some(true),
+                tags: vec!["code".to_string()],
+                ..Default::default()
+            },
+        );
+        table.set_deprecation(
+            "embeddings".to_string(),
+            Deprecation {
+                deprecated_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+                sunset_at: None,
+                replacement: Some("gpt-4".to_string()),
+            },
+        );
+        let (_, receiver) = watch::channel(table);
+        let app = Router::new()
+            .route(
+                MODELS_PATH,
+                get(|| async {
+                    Json(json!({
+                        "object": "list",
+                        "data": [
+                            {"id": "gpt-4", "object": "model", "created": 0, "owned_by": "None"},
+                            {"id": "gpt-4::fallback-1", "object": "model", "created": 0, "owned_by": "None"},
+                            {"id": "embeddings", "object": "model", "created": 0, "owned_by": "None"}
+                        ]
+                    }))
+                }),
+            )
+            .layer(from_fn_with_state(receiver, add_model_metadata));
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get(MODELS_PATH).await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<Value>()["data"],
+            json!([
+                {
+                    "id": "gpt-4", "object": "model", "created": 0, "owned_by": "None",
+                    "max_context_tokens": 128000, "supports_tools": true, "tags": ["code"]
+                },
+                {
+                    "id": "embeddings", "object": "model", "created": 0, "owned_by": "None",
+                    "deprecation": {"deprecated_at": "2023-11-14T22:13:20Z", "replacement": "gpt-4"}
+                }
+            ])
+        );
+    }
+}
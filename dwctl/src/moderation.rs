@@ -0,0 +1,548 @@
+//! Content moderation of AI requests, driven by group policies.
+//!
+//! Admins give a group a moderation policy (`PUT /groups/{id}/moderation`) with a mode and a
+//! list of blocked patterns. When moderation is enabled, the [`moderate`] middleware looks up the
+//! policies that apply to the caller's API key, checks the text of the request against their
+//! patterns and, if one is configured, an OpenAI-compatible moderation endpoint, and then:
+//!
+//! - `block`: refuses flagged requests with a structured `content_policy_violation` error
+//! - `annotate`: forwards flagged requests, marking the response as flagged
+//!
+//! The decision is returned in the `X-Doubleword-Moderation` and
+//! `X-Doubleword-Moderation-Categories` response headers, which the request logger records
+//! alongside the request. Requests made with the system API key (probes, load tests and the
+//! playground) are not moderated.
+
+use crate::api::models::groups::ModerationMode;
+use crate::config::ModerationConfig;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use regex::Regex;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::BTreeSet;
+use tracing::{debug, error, warn};
+
+/// Response header carrying the moderation decision: `allowed`, `flagged` or `blocked`
+pub const MODERATION_HEADER: &str = "x-doubleword-moderation";
+
+/// Response header carrying the comma-separated categories a request was flagged for
+pub const MODERATION_CATEGORIES_HEADER: &str = "x-doubleword-moderation-categories";
+
+/// Category reported when a request matches one of a policy's blocked patterns
+const BLOCKED_PATTERN_CATEGORY: &str = "blocked_pattern";
+
+/// Outcome of moderating a request
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    Allowed,
+    /// Flagged by an `annotate` policy; the request is forwarded
+    Flagged(Vec<String>),
+    /// Flagged by a `block` policy; the request is refused
+    Blocked(Vec<String>),
+}
+
+impl Decision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Decision::Allowed => "allowed",
+            Decision::Flagged(_) => "flagged",
+            Decision::Blocked(_) => "blocked",
+        }
+    }
+
+    pub fn categories(&self) -> &[String] {
+        match self {
+            Decision::Allowed => &[],
+            Decision::Flagged(categories) | Decision::Blocked(categories) => categories,
+        }
+    }
+}
+
+/// A group policy that applies to a request
+#[derive(Debug, Clone)]
+pub struct ActivePolicy {
+    pub mode: ModerationMode,
+    pub blocked_patterns: Vec<String>,
+}
+
+/// State for the [`moderate`] middleware
+#[derive(Debug, Clone)]
+pub struct Moderation {
+    pool: PgPool,
+    config: ModerationConfig,
+    client: reqwest::Client,
+}
+
+impl Moderation {
+    pub fn new(pool: PgPool, config: ModerationConfig) -> Self {
+        Self {
+            pool,
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Policies of the groups of the API key's owner, including the Everyone group
+    async fn policies_for_key(&self, api_key: &str) -> anyhow::Result<Vec<ActivePolicy>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT p.mode, p.blocked_patterns
+            FROM group_moderation_policies p
+            JOIN api_keys ak ON ak.secret_hash = api_key_secret_hash($1)
+            WHERE p.mode <> 'off'
+              AND ak.user_id <> '00000000-0000-0000-0000-000000000000'
+              AND (
+                  p.group_id = '00000000-0000-0000-0000-000000000000'
+                  OR p.group_id IN (SELECT group_id FROM effective_user_groups WHERE user_id = ak.user_id)
+              )
+            "#,
+            api_key
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ActivePolicy {
+                mode: ModerationMode::parse(&row.mode),
+                blocked_patterns: row.blocked_patterns,
+            })
+            .collect())
+    }
+
+    /// Decide what to do with a request's text under the given policies.
+    ///
+    /// Fails only if the moderation endpoint can't be reached and `fail_open` is off.
+    pub async fn decide(&self, policies: &[ActivePolicy], text: &str) -> anyhow::Result<Decision> {
+        let mut categories = BTreeSet::new();
+        let mut strictest = ModerationMode::Off;
+
+        for policy in policies {
+            let matched = policy
+                .blocked_patterns
+                .iter()
+                .filter_map(|pattern| Regex::new(pattern).ok())
+                .any(|regex| regex.is_match(text));
+            if matched {
+                categories.insert(BLOCKED_PATTERN_CATEGORY.to_string());
+                strictest = strictest.max(policy.mode);
+            }
+        }
+
+        if let Some(endpoint) = &self.config.endpoint {
+            match self.check_endpoint(endpoint, text).await {
+                Ok(flagged) if !flagged.is_empty() => {
+                    categories.extend(flagged);
+                    strictest = policies.iter().map(|p| p.mode).max().unwrap_or(ModerationMode::Off);
+                }
+                Ok(_) => {}
+                Err(e) if self.config.fail_open => {
+                    warn!("Moderation endpoint failed, forwarding request unmoderated: {:#}", e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let categories = categories.into_iter().collect();
+        Ok(match strictest {
+            ModerationMode::Block => Decision::Blocked(categories),
+            ModerationMode::Annotate => Decision::Flagged(categories),
+            ModerationMode::Off => Decision::Allowed,
+        })
+    }
+
+    /// Ask the moderation endpoint about `text`, returning the categories it was flagged for
+    async fn check_endpoint(&self, endpoint: &url::Url, text: &str) -> anyhow::Result<Vec<String>> {
+        let mut body = json!({"input": text});
+        if let Some(model) = &self.config.model {
+            body["model"] = model.clone().into();
+        }
+
+        let mut request = self.client.post(endpoint.clone()).timeout(self.config.timeout).json(&body);
+        if let Some(api_key) = &self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            anyhow::bail!("moderation endpoint returned {}", response.status());
+        }
+        let response: Value = response.json().await?;
+        let results = response
+            .get("results")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow::anyhow!("moderation endpoint response has no results"))?;
+
+        let mut flagged = BTreeSet::new();
+        for result in results.iter().filter(|r| r["flagged"].as_bool().unwrap_or(false)) {
+            let before = flagged.len();
+            if let Some(categories) = result.get("categories").and_then(Value::as_object) {
+                flagged.extend(categories.iter().filter(|(_, v)| v.as_bool() == Some(true)).map(|(k, _)| k.clone()));
+            }
+            if flagged.len() == before {
+                flagged.insert("flagged".to_string());
+            }
+        }
+        Ok(flagged.into_iter().collect())
+    }
+}
+
+/// The text of a chat completion, completion, embeddings or Messages API request
+pub fn request_text(body: &Value) -> String {
+    let mut parts = Vec::new();
+
+    match body.get("system") {
+        Some(Value::String(system)) => parts.push(system.as_str()),
+        Some(Value::Array(system)) => parts.extend(system.iter().filter_map(|block| block.get("text")?.as_str())),
+        _ => {}
+    }
+    if let Some(messages) = body.get("messages").and_then(Value::as_array) {
+        for message in messages {
+            match message.get("content") {
+                Some(Value::String(content)) => parts.push(content.as_str()),
+                Some(Value::Array(content)) => parts.extend(content.iter().filter_map(|part| part.get("text")?.as_str())),
+                _ => {}
+            }
+        }
+    }
+    for field in ["prompt", "input"] {
+        match body.get(field) {
+            Some(Value::String(text)) => parts.push(text.as_str()),
+            Some(Value::Array(texts)) => parts.extend(texts.iter().filter_map(Value::as_str)),
+            _ => {}
+        }
+    }
+
+    parts.join("\n")
+}
+
+/// Middleware that checks AI requests against the moderation policies of the caller's groups
+pub async fn moderate(State(moderation): State<Moderation>, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let Some(api_key) = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string)
+    else {
+        // Let onwards reject unauthenticated requests
+        return next.run(request).await;
+    };
+
+    let policies = match moderation.policies_for_key(&api_key).await {
+        Ok(policies) => policies,
+        Err(e) if moderation.config.fail_open => {
+            error!("Failed to load moderation policies, forwarding request unmoderated: {:#}", e);
+            return next.run(request).await;
+        }
+        Err(e) => {
+            error!("Failed to load moderation policies: {:#}", e);
+            return unavailable_response();
+        }
+    };
+    if policies.is_empty() {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => return error_response(StatusCode::BAD_REQUEST, "Failed to read request body", None),
+    };
+    let text = serde_json::from_slice::<Value>(&body)
+        .map(|value| request_text(&value))
+        .unwrap_or_default();
+
+    let decision = if text.is_empty() {
+        Decision::Allowed
+    } else {
+        match moderation.decide(&policies, &text).await {
+            Ok(decision) => decision,
+            Err(e) => {
+                error!("Moderation endpoint failed: {:#}", e);
+                return unavailable_response();
+            }
+        }
+    };
+    debug!("Moderation decision: {:?}", decision);
+
+    let mut response = match &decision {
+        Decision::Blocked(categories) => error_response(
+            StatusCode::BAD_REQUEST,
+            "This request was blocked by your organization's content policy",
+            Some(categories),
+        ),
+        _ => next.run(Request::from_parts(parts, Body::from(body))).await,
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(MODERATION_HEADER, HeaderValue::from_static(decision.as_str()));
+    if let Ok(categories) = HeaderValue::from_str(&decision.categories().join(",")) {
+        if !categories.is_empty() {
+            headers.insert(MODERATION_CATEGORIES_HEADER, categories);
+        }
+    }
+    response
+}
+
+fn unavailable_response() -> Response {
+    let mut response = error_response(StatusCode::SERVICE_UNAVAILABLE, "Content moderation is currently unavailable", None);
+    response
+        .headers_mut()
+        .insert(MODERATION_HEADER, HeaderValue::from_static("blocked"));
+    response
+}
+
+fn error_response(status: StatusCode, message: &str, categories: Option<&[String]>) -> Response {
+    let (error_type, code) = match categories {
+        Some(_) => ("invalid_request_error", Value::from("content_policy_violation")),
+        None if status.is_server_error() => ("server_error", Value::Null),
+        None => ("invalid_request_error", Value::Null),
+    };
+    let mut error = json!({"message": message, "type": error_type, "param": null, "code": code});
+    if let Some(categories) = categories {
+        error["categories"] = json!(categories);
+    }
+    (status, Json(json!({ "error": error }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::users::Role;
+    use crate::db::handlers::Groups;
+    use crate::db::models::groups::GroupModerationUpdateDBRequest;
+    use crate::test_utils::*;
+    use axum::{middleware::from_fn_with_state, routing::post, Router};
+    use axum_test::TestServer;
+    use std::time::Duration;
+
+    fn config(endpoint: Option<url::Url>, fail_open: bool) -> ModerationConfig {
+        ModerationConfig {
+            enabled: true,
+            endpoint,
+            api_key: Some("moderation-key".to_string()),
+            model: None,
+            timeout: Duration::from_secs(5),
+            fail_open,
+        }
+    }
+
+    fn policy(mode: ModerationMode, patterns: &[&str]) -> ActivePolicy {
+        ActivePolicy {
+            mode,
+            blocked_patterns: patterns.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    /// A moderation endpoint that flags any input mentioning "weapon" for violence
+    async fn mock_moderation_endpoint() -> url::Url {
+        let app = Router::new().route(
+            "/v1/moderations",
+            post(|Json(body): Json<Value>| async move {
+                let flagged = body["input"].as_str().unwrap_or_default().contains("weapon");
+                Json(json!({"results": [{"flagged": flagged, "categories": {"violence": flagged, "hate": false}}]}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}/v1/moderations").parse().unwrap()
+    }
+
+    #[test]
+    fn test_request_text() {
+        let chat = json!({
+            "model": "gpt-4",
+            "messages": [
+                {"role": "system", "content": "Be helpful"},
+                {"role": "user", "content": [{"type": "text", "text": "Describe this"}, {"type": "image_url", "image_url": {"url": "https://example.com/a.png"}}]}
+            ]
+        });
+        assert_eq!(request_text(&chat), "Be helpful\nDescribe this");
+
+        assert_eq!(
+            request_text(&json!({"model": "m", "prompt": "Once upon a time"})),
+            "Once upon a time"
+        );
+        assert_eq!(request_text(&json!({"model": "m", "input": ["a", "b"]})), "a\nb");
+        assert_eq!(request_text(&json!({"model": "m"})), "");
+
+        let messages = json!({
+            "model": "claude",
+            "system": [{"type": "text", "text": "Be terse"}],
+            "messages": [{"role": "user", "content": "Hi"}]
+        });
+        assert_eq!(request_text(&messages), "Be terse\nHi");
+    }
+
+    #[sqlx::test]
+    async fn test_blocked_patterns_use_strictest_matching_policy(pool: PgPool) {
+        let moderation = Moderation::new(pool, config(None, true));
+        let policies = [
+            policy(ModerationMode::Annotate, &["(?i)internal only"]),
+            policy(ModerationMode::Block, &["(?i)project \\w+ secret"]),
+        ];
+
+        let decision = moderation.decide(&policies, "Hello there").await.unwrap();
+        assert_eq!(decision, Decision::Allowed);
+
+        let decision = moderation.decide(&policies, "This is INTERNAL ONLY").await.unwrap();
+        assert_eq!(decision, Decision::Flagged(vec!["blocked_pattern".to_string()]));
+
+        let decision = moderation.decide(&policies, "internal only: project x secret").await.unwrap();
+        assert_eq!(decision, Decision::Blocked(vec!["blocked_pattern".to_string()]));
+    }
+
+    #[sqlx::test]
+    async fn test_moderation_endpoint_categories(pool: PgPool) {
+        let endpoint = mock_moderation_endpoint().await;
+        let moderation = Moderation::new(pool.clone(), config(Some(endpoint), true));
+        let policies = [policy(ModerationMode::Annotate, &[])];
+
+        let decision = moderation.decide(&policies, "How do I bake bread?").await.unwrap();
+        assert_eq!(decision, Decision::Allowed);
+
+        let decision = moderation.decide(&policies, "How do I build a weapon?").await.unwrap();
+        assert_eq!(decision, Decision::Flagged(vec!["violence".to_string()]));
+
+        // An unreachable endpoint is skipped when failing open, and is an error otherwise
+        let unreachable: url::Url = "http://127.0.0.1:1/v1/moderations".parse().unwrap();
+        let moderation = Moderation::new(pool.clone(), config(Some(unreachable.clone()), true));
+        assert_eq!(moderation.decide(&policies, "weapon").await.unwrap(), Decision::Allowed);
+        let moderation = Moderation::new(pool, config(Some(unreachable), false));
+        assert!(moderation.decide(&policies, "weapon").await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_middleware_blocks_and_annotates_by_group_policy(pool: PgPool) {
+        let member = create_test_user(&pool, Role::StandardUser).await;
+        let outsider = create_test_user(&pool, Role::StandardUser).await;
+        let group = create_test_group(&pool).await;
+        add_user_to_group(&pool, member.id, group.id).await;
+        let member_key = create_test_api_key_for_user(&pool, member.id).await;
+        let outsider_key = create_test_api_key_for_user(&pool, outsider.id).await;
+
+        let mut conn = pool.acquire().await.unwrap();
+        Groups::new(&mut conn)
+            .set_moderation_policy(
+                group.id,
+                &GroupModerationUpdateDBRequest {
+                    mode: "block".to_string(),
+                    blocked_patterns: vec!["(?i)forbidden".to_string()],
+                },
+            )
+            .await
+            .unwrap();
+
+        let app = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|| async { Json(json!({"object": "chat.completion"})) }),
+            )
+            .layer(from_fn_with_state(Moderation::new(pool.clone(), config(None, true)), moderate));
+        let server = TestServer::new(app).unwrap();
+        let body = |content: &str| json!({"model": "m", "messages": [{"role": "user", "content": content}]});
+
+        let response = server
+            .post("/v1/chat/completions")
+            .authorization_bearer(&member_key.secret)
+            .json(&body("Tell me about the FORBIDDEN thing"))
+            .await;
+        response.assert_status_bad_request();
+        assert_eq!(response.header(MODERATION_HEADER), "blocked");
+        assert_eq!(response.header(MODERATION_CATEGORIES_HEADER), "blocked_pattern");
+        let error: Value = response.json();
+        assert_eq!(error["error"]["code"], "content_policy_violation");
+        assert_eq!(error["error"]["categories"], json!(["blocked_pattern"]));
+
+        let response = server
+            .post("/v1/chat/completions")
+            .authorization_bearer(&member_key.secret)
+            .json(&body("Hello"))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header(MODERATION_HEADER), "allowed");
+
+        // Users outside the group aren't moderated at all
+        let response = server
+            .post("/v1/chat/completions")
+            .authorization_bearer(&outsider_key.secret)
+            .json(&body("Tell me about the forbidden thing"))
+            .await;
+        response.assert_status_ok();
+        assert!(response.headers().get(MODERATION_HEADER).is_none());
+
+        // Annotating forwards the request, marking it as flagged
+        Groups::new(&mut conn)
+            .set_moderation_policy(
+                group.id,
+                &GroupModerationUpdateDBRequest {
+                    mode: "annotate".to_string(),
+                    blocked_patterns: vec!["(?i)forbidden".to_string()],
+                },
+            )
+            .await
+            .unwrap();
+        let response = server
+            .post("/v1/chat/completions")
+            .authorization_bearer(&member_key.secret)
+            .json(&body("Tell me about the forbidden thing"))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header(MODERATION_HEADER), "flagged");
+    }
+
+    #[sqlx::test]
+    async fn test_child_group_policy_applies_to_parent_group_members(pool: PgPool) {
+        let member = create_test_user(&pool, Role::StandardUser).await;
+        let parent = create_test_group(&pool).await;
+        let child = create_test_group(&pool).await;
+        add_user_to_group(&pool, member.id, parent.id).await;
+        sqlx::query("UPDATE groups SET parent_id = $1 WHERE id = $2")
+            .bind(parent.id)
+            .bind(child.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let member_key = create_test_api_key_for_user(&pool, member.id).await;
+
+        let mut conn = pool.acquire().await.unwrap();
+        Groups::new(&mut conn)
+            .set_moderation_policy(
+                child.id,
+                &GroupModerationUpdateDBRequest {
+                    mode: "block".to_string(),
+                    blocked_patterns: vec!["(?i)forbidden".to_string()],
+                },
+            )
+            .await
+            .unwrap();
+
+        let app = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|| async { Json(json!({"object": "chat.completion"})) }),
+            )
+            .layer(from_fn_with_state(Moderation::new(pool.clone(), config(None, true)), moderate));
+        let server = TestServer::new(app).unwrap();
+
+        // Members of the parent group are members of the child group, so its policy applies
+        let response = server
+            .post("/v1/chat/completions")
+            .authorization_bearer(&member_key.secret)
+            .json(&json!({"model": "m", "messages": [{"role": "user", "content": "The forbidden thing"}]}))
+            .await;
+        response.assert_status_bad_request();
+        assert_eq!(response.header(MODERATION_HEADER), "blocked");
+    }
+}
@@ -3,7 +3,7 @@ use utoipa::{
     Modify, OpenApi,
 };
 
-use crate::{api, sync};
+use crate::{api, db, sync};
 
 struct SecurityAddon;
 
@@ -39,23 +39,76 @@ impl Modify for SecurityAddon {
         api::handlers::api_keys::create_user_api_key,
         api::handlers::api_keys::get_user_api_key,
         api::handlers::api_keys::delete_user_api_key,
+        api::handlers::api_keys::request_api_key_access,
+        api::handlers::api_keys::list_api_key_access_requests,
+        api::handlers::api_keys::list_access_requests,
+        api::handlers::api_keys::decide_access_request,
         api::handlers::inference_endpoints::list_inference_endpoints,
         api::handlers::inference_endpoints::get_inference_endpoint,
         api::handlers::inference_endpoints::create_inference_endpoint,
         api::handlers::inference_endpoints::update_inference_endpoint,
         api::handlers::inference_endpoints::delete_inference_endpoint,
         api::handlers::inference_endpoints::validate_inference_endpoint,
+        api::handlers::inference_endpoints::get_validation_report,
         api::handlers::inference_endpoints::synchronize_endpoint,
+        api::handlers::inference_endpoints::run_compatibility_suite,
+        api::handlers::inference_endpoints::list_compatibility_reports,
+        api::handlers::inference_endpoints::get_endpoint_redaction,
+        api::handlers::inference_endpoints::set_endpoint_redaction,
+        api::handlers::inference_endpoints::get_endpoint_headers,
+        api::handlers::inference_endpoints::set_endpoint_headers,
+        api::handlers::inference_endpoints::get_endpoint_rate_limits,
+        api::handlers::inference_endpoints::set_endpoint_rate_limits,
+        api::handlers::inference_endpoints::get_endpoint_sync_strategy,
+        api::handlers::inference_endpoints::set_endpoint_sync_strategy,
+        api::handlers::inference_endpoints::list_endpoint_pending_models,
+        api::handlers::inference_endpoints::approve_endpoint_pending_models,
+        api::handlers::inference_endpoints::reject_endpoint_pending_models,
+        api::handlers::inference_endpoints::get_endpoint_history,
+        api::handlers::inference_endpoints::rollback_inference_endpoint,
+        api::handlers::inference_endpoints::get_endpoint_aliases,
+        api::handlers::inference_endpoints::set_endpoint_aliases,
+        api::handlers::inference_endpoints::export_endpoints,
+        api::handlers::inference_endpoints::import_endpoints,
         api::handlers::deployments::list_deployed_models,
         api::handlers::deployments::create_deployed_model,
         api::handlers::deployments::get_deployed_model,
         api::handlers::deployments::update_deployed_model,
         api::handlers::deployments::delete_deployed_model,
+        api::handlers::deployments::get_deployment_fallbacks,
+        api::handlers::deployments::set_deployment_fallbacks,
+        api::handlers::deployments::get_deployment_traffic_split,
+        api::handlers::deployments::set_deployment_traffic_split,
+        api::handlers::deployments::get_deployment_canary,
+        api::handlers::deployments::set_deployment_canary,
+        api::handlers::deployments::delete_deployment_canary,
+        api::handlers::deployments::get_deployment_shadow,
+        api::handlers::deployments::set_deployment_shadow,
+        api::handlers::deployments::delete_deployment_shadow,
+        api::handlers::deployments::get_deployment_deprecation,
+        api::handlers::deployments::set_deployment_deprecation,
+        api::handlers::deployments::delete_deployment_deprecation,
+        api::handlers::deployments::get_deployment_schedule,
+        api::handlers::deployments::set_deployment_schedule,
+        api::handlers::deployments::get_observed_deployment_schedule,
+        api::handlers::deployments::list_schedule_hints,
+        api::handlers::deployments::get_deployment_access_policy,
+        api::handlers::deployments::set_deployment_access_policy,
+        api::handlers::deployments::get_deployment_logging_policy,
+        api::handlers::deployments::set_deployment_logging_policy,
+        api::handlers::deployments::simulate_deployment_rate_limits,
+        api::handlers::deployments::test_deployment,
         api::handlers::groups::list_groups,
         api::handlers::groups::create_group,
         api::handlers::groups::get_group,
         api::handlers::groups::update_group,
         api::handlers::groups::delete_group,
+        api::handlers::groups::get_group_moderation,
+        api::handlers::groups::set_group_moderation,
+        api::handlers::groups::get_group_limits,
+        api::handlers::groups::set_group_limits,
+        api::handlers::groups::get_group_log_retention,
+        api::handlers::groups::set_group_log_retention,
         api::handlers::groups::add_user_to_group,
         api::handlers::groups::remove_user_from_group,
         api::handlers::groups::add_group_to_user,
@@ -79,28 +132,93 @@ impl Modify for SecurityAddon {
             api::models::users::UserResponse,
             api::models::users::CurrentUser,
             api::models::users::ListUsersQuery,
+            api::models::users::DeleteUserQuery,
+            api::models::users::UserDeletionReport,
+            api::models::users::UserSortField,
+            api::models::sorting::SortOrder,
             api::models::api_keys::ApiKeyCreate,
             api::models::api_keys::ApiKeyUpdate,
             api::models::api_keys::ListApiKeysQuery,
             api::models::api_keys::ApiKeyResponse,
             api::models::api_keys::ApiKeyInfoResponse,
+            api::models::api_keys::AccessRequestStatus,
+            api::models::api_keys::ApiKeyAccessRequestCreate,
+            api::models::api_keys::AccessRequestDecision,
+            api::models::api_keys::ListAccessRequestsQuery,
+            api::models::api_keys::ApiKeyAccessRequestResponse,
             api::models::deployments::DeployedModelCreate,
             api::models::deployments::DeployedModelUpdate,
             api::models::deployments::DeployedModelUpdateRequest,
             api::models::deployments::DeployedModelResponse,
+            api::models::deployments::DeploymentFallback,
+            api::models::deployments::DeploymentFallbacks,
+            api::models::deployments::DeploymentTrafficSplit,
+            api::models::deployments::DeploymentTrafficSplits,
+            api::models::deployments::DeploymentCanary,
+            api::models::deployments::DeploymentCanaryUpdate,
+            api::models::deployments::DeploymentShadow,
+            api::models::deployments::DeploymentShadowUpdate,
+            api::models::deployments::DeploymentDeprecation,
+            api::models::deployments::DeploymentDeprecationUpdate,
+            api::models::deployments::DayOfWeek,
+            api::models::deployments::BusyWindow,
+            api::models::deployments::DeploymentSchedule,
+            api::models::deployments::ScheduleHint,
+            api::models::deployments::DeploymentAccessPolicy,
+            api::models::deployments::DeploymentLoggingPolicy,
+            api::models::deployments::LoggingMode,
+            api::models::deployments::HourlyTraffic,
+            api::models::deployments::ObservedSchedule,
+            api::models::deployments::CanaryVariantMetrics,
+            api::models::deployments::RateLimitSimulationRequest,
+            api::models::deployments::RateLimitSimulation,
+            api::models::deployments::SimulatedRateLimit,
+            api::models::deployments::DeploymentTestRequest,
+            api::models::deployments::DeploymentTestResponse,
             api::models::groups::GroupCreate,
             api::models::groups::GroupUpdate,
             api::models::groups::GroupResponse,
             api::models::groups::ListGroupsQuery,
+            api::models::groups::GroupSortField,
+            api::models::groups::GroupModerationPolicy,
+            api::models::groups::ModerationMode,
+            api::models::groups::GroupRequestLimits,
+            api::models::groups::GroupLogRetention,
+            api::models::groups::LogRetentionKind,
             api::models::deployments::ListModelsQuery,
+            api::models::deployments::ModelSortField,
             api::models::inference_endpoints::InferenceEndpointCreate,
             api::models::inference_endpoints::InferenceEndpointUpdate,
             api::models::inference_endpoints::InferenceEndpointValidate,
             api::models::inference_endpoints::InferenceEndpointValidateResponse,
+            api::models::inference_endpoints::EndpointCompatibilityRun,
+            api::models::inference_endpoints::EndpointRedactionPolicy,
+            api::models::inference_endpoints::RedactionEntity,
+            api::models::inference_endpoints::EndpointHeaderRules,
+            api::models::inference_endpoints::EndpointRateLimits,
+            api::models::inference_endpoints::EndpointSyncStrategy,
+            api::models::inference_endpoints::RemovedModelAction,
+            api::models::inference_endpoints::NewModelAction,
+            api::models::inference_endpoints::EndpointPendingModel,
+            api::models::inference_endpoints::PendingModelsDecision,
+            api::models::inference_endpoints::EndpointConfigVersion,
+            api::models::inference_endpoints::EndpointAliases,
+            api::models::inference_endpoints::EndpointManifest,
+            api::models::inference_endpoints::EndpointManifestEntry,
+            api::models::inference_endpoints::EndpointImportAction,
+            api::models::inference_endpoints::EndpointSettingChange,
+            api::models::inference_endpoints::EndpointImportChange,
+            api::models::inference_endpoints::EndpointImportResult,
+            api::models::inference_endpoints::EndpointValidationReport,
+            api::models::inference_endpoints::EndpointValidationStatus,
             api::models::inference_endpoints::InferenceEndpointResponse,
             api::models::inference_endpoints::ListEndpointsQuery,
+            api::models::inference_endpoints::EndpointSortField,
             api::models::inference_endpoints::OpenAIModel,
             api::models::inference_endpoints::OpenAIModelsResponse,
+            db::models::endpoint_compatibility::EndpointCompatibilityReport,
+            db::models::endpoint_compatibility::CompatibilityCheckResult,
+            db::models::endpoint_history::EndpointConfig,
             sync::endpoint_sync::EndpointSyncResponse,
         )
     ),
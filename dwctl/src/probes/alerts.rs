@@ -0,0 +1,474 @@
+//! Alerts sent to notification channels when probes start failing or recover.
+//!
+//! Every probe result updates the probe's alerting state: it's `failing` once its last
+//! `failure_threshold` results all failed, and `ok` again after its next success. Channels are only
+//! told about a status that differs from the one they were last told about, so an outage is one
+//! alert rather than one per failed result, and about each probe at most once per `cooldown`. A
+//! change held back by the cooldown is sent with the first result after it, unless the probe has
+//! changed back by then.
+
+use crate::api::models::notification_channels::ChannelType;
+use crate::config::{Config, ProbeAlertsConfig};
+use crate::db::models::notification_channels::{NotificationChannel, ProbeAlertState};
+use crate::db::models::probes::ProbeResult;
+use crate::email::EmailService;
+use crate::errors::Error as AppError;
+use crate::probes::channels::ChannelManager;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Upper bound on the delay between delivery attempts for a single alert
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// A probe starting to fail or recovering, as sent to webhook channels
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeAlert {
+    /// `probe.failing` or `probe.recovered`
+    pub event: &'static str,
+    pub probe_id: Uuid,
+    pub probe_name: String,
+    pub deployment_id: Uuid,
+    /// Alias of the probed deployment
+    pub model: String,
+    pub consecutive_failures: i32,
+    /// Error of the result that triggered the alert, if it failed
+    pub error_message: Option<String>,
+    /// Human-readable description, as sent to Slack and email channels
+    pub message: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl ProbeAlert {
+    fn subject(&self) -> String {
+        match self.event {
+            "probe.failing" => format!("Probe failing: {}", self.probe_name),
+            _ => format!("Probe recovered: {}", self.probe_name),
+        }
+    }
+}
+
+/// Work out a probe's alerting state after a result. Returns the new state and whether channels
+/// should be told about it.
+pub fn advance(state: &ProbeAlertState, success: bool, now: DateTime<Utc>, config: &ProbeAlertsConfig) -> (ProbeAlertState, bool) {
+    let consecutive_failures = if success { 0 } else { state.consecutive_failures.saturating_add(1) };
+    let status = if i64::from(consecutive_failures) >= i64::from(config.failure_threshold.max(1)) {
+        "failing"
+    } else if success {
+        "ok"
+    } else {
+        // Failures short of the threshold don't change the status either way
+        state.status.as_str()
+    };
+
+    let cooldown = chrono::Duration::from_std(config.cooldown).unwrap_or(chrono::Duration::MAX);
+    let cooled_down = state.notified_at.is_none_or(|notified_at| now - notified_at >= cooldown);
+    let notify = status != state.notified_status && cooled_down;
+
+    let next = ProbeAlertState {
+        probe_id: state.probe_id,
+        status: status.to_string(),
+        consecutive_failures,
+        notified_status: if notify {
+            status.to_string()
+        } else {
+            state.notified_status.clone()
+        },
+        notified_at: if notify { Some(now) } else { state.notified_at },
+    };
+    (next, notify)
+}
+
+/// Update a probe's alerting state with a result, returning the new state if channels should
+/// be told about it
+async fn record_result(pool: &PgPool, result: &ProbeResult, config: &ProbeAlertsConfig) -> Result<Option<ProbeAlertState>, AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to begin transaction: {}", e))?;
+
+    sqlx::query!(
+        "INSERT INTO probe_alert_states (probe_id) VALUES ($1) ON CONFLICT (probe_id) DO NOTHING",
+        result.probe_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to initialize probe alert state: {}", e))?;
+
+    // Lock the state so concurrent results (e.g. a manual run) can't both send the same alert
+    let state = sqlx::query_as::<_, ProbeAlertState>(
+        r#"
+        SELECT probe_id, status, consecutive_failures, notified_status, notified_at
+        FROM probe_alert_states
+        WHERE probe_id = $1
+        FOR UPDATE
+        "#,
+    )
+    .bind(result.probe_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to fetch probe alert state: {}", e))?;
+
+    let (next, notify) = advance(&state, result.success, Utc::now(), config);
+    sqlx::query!(
+        r#"
+        UPDATE probe_alert_states
+        SET status = $2, consecutive_failures = $3, notified_status = $4, notified_at = $5, updated_at = NOW()
+        WHERE probe_id = $1
+        "#,
+        next.probe_id,
+        next.status,
+        next.consecutive_failures,
+        next.notified_status,
+        next.notified_at
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to update probe alert state: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to commit transaction: {}", e))?;
+    Ok(notify.then_some(next))
+}
+
+/// Describe a probe's new status
+async fn build_alert(pool: &PgPool, result: &ProbeResult, state: &ProbeAlertState) -> Result<ProbeAlert, AppError> {
+    let probe = sqlx::query!(
+        r#"
+        SELECT p.name, p.deployment_id, d.alias
+        FROM probes p
+        JOIN deployed_models d ON d.id = p.deployment_id
+        WHERE p.id = $1
+        "#,
+        result.probe_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to fetch probe for alert: {}", e))?;
+
+    let (event, message) = if state.status == "failing" {
+        let mut message = format!(
+            "Probe '{}' of model '{}' is failing: {} consecutive failures",
+            probe.name, probe.alias, state.consecutive_failures
+        );
+        if let Some(error_message) = &result.error_message {
+            message.push_str(&format!(" (last error: {error_message})"));
+        }
+        ("probe.failing", message)
+    } else {
+        (
+            "probe.recovered",
+            format!("Probe '{}' of model '{}' has recovered", probe.name, probe.alias),
+        )
+    };
+
+    Ok(ProbeAlert {
+        event,
+        probe_id: result.probe_id,
+        probe_name: probe.name,
+        deployment_id: probe.deployment_id,
+        model: probe.alias,
+        consecutive_failures: state.consecutive_failures,
+        error_message: result.error_message.clone(),
+        message,
+        occurred_at: result.executed_at,
+    })
+}
+
+/// Send an alert to a single channel
+async fn send(client: &reqwest::Client, config: &Config, channel: &NotificationChannel, alert: &ProbeAlert) -> anyhow::Result<()> {
+    let request = match ChannelType::parse(&channel.channel_type) {
+        Some(ChannelType::Webhook) => client.post(&channel.destination).json(alert),
+        Some(ChannelType::Slack) => client
+            .post(&channel.destination)
+            .json(&serde_json::json!({ "text": alert.message })),
+        Some(ChannelType::Email) => {
+            let email = EmailService::new(config)?;
+            email
+                .send_notification_email(&channel.destination, &alert.subject(), &alert.message)
+                .await?;
+            return Ok(());
+        }
+        None => anyhow::bail!("unknown channel type '{}'", channel.channel_type),
+    };
+
+    let response = request.timeout(config.probe_alerts.request_timeout).send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("{} returned {}", channel.channel_type, response.status());
+    }
+    Ok(())
+}
+
+/// Send an alert to a channel, retrying with exponential backoff, and record the outcome on the
+/// channel. Gives up after `max_retries` retries.
+async fn deliver(pool: &PgPool, client: &reqwest::Client, config: &Config, channel: &NotificationChannel, alert: &ProbeAlert) {
+    let max_retries = config.probe_alerts.max_retries;
+    let mut delay = Duration::from_millis(500);
+
+    let mut outcome = Ok(());
+    for attempt in 0..=max_retries {
+        outcome = send(client, config, channel, alert).await;
+        match &outcome {
+            Ok(()) => break,
+            Err(e) if attempt < max_retries => {
+                warn!(
+                    channel_id = %channel.id,
+                    attempt = attempt + 1,
+                    "Failed to send probe alert, retrying in {}ms: {:#}",
+                    delay.as_millis(),
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+            Err(e) => error!(channel_id = %channel.id, "Giving up on sending probe alert: {:#}", e),
+        }
+    }
+
+    let error = outcome.err().map(|e| format!("{e:#}"));
+    if let Err(e) = ChannelManager::record_delivery(pool, channel.id, error.as_deref()).await {
+        error!(channel_id = %channel.id, "Failed to record probe alert delivery: {}", e);
+    }
+}
+
+/// Update a probe's alerting state with a result and, if its status changed, send an alert to
+/// every enabled notification channel. Returns the alert sent, if any.
+pub async fn handle_result(pool: &PgPool, config: &Config, result: &ProbeResult) -> Result<Option<ProbeAlert>, AppError> {
+    let Some(state) = record_result(pool, result, &config.probe_alerts).await? else {
+        return Ok(None);
+    };
+    let alert = build_alert(pool, result, &state).await?;
+
+    if state.status == "failing" {
+        warn!(probe_id = %alert.probe_id, "{}", alert.message);
+    } else {
+        info!(probe_id = %alert.probe_id, "{}", alert.message);
+    }
+
+    let channels = ChannelManager::list(pool, true).await?;
+    if !channels.is_empty() {
+        let pool = pool.clone();
+        let config = config.clone();
+        let alert = alert.clone();
+        tokio::spawn(async move {
+            let client = reqwest::Client::new();
+            let deliveries = channels.iter().map(|channel| deliver(&pool, &client, &config, channel, &alert));
+            futures::future::join_all(deliveries).await;
+        });
+    }
+
+    Ok(Some(alert))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::users::Role;
+    use crate::probes::channels::NewChannel;
+    use crate::test_utils::{create_test_admin_user, create_test_config};
+    use axum::{extract::State, http::StatusCode, routing::post, Router};
+    use std::sync::Arc;
+
+    fn state(status: &str, consecutive_failures: i32, notified_status: &str, notified_at: Option<DateTime<Utc>>) -> ProbeAlertState {
+        ProbeAlertState {
+            probe_id: Uuid::nil(),
+            status: status.to_string(),
+            consecutive_failures,
+            notified_status: notified_status.to_string(),
+            notified_at,
+        }
+    }
+
+    #[test]
+    fn test_advance() {
+        let config = ProbeAlertsConfig::default();
+        let now = Utc::now();
+
+        // Failures short of the threshold don't alert
+        let (next, notify) = advance(&state("ok", 1, "ok", None), false, now, &config);
+        assert_eq!((next.status.as_str(), next.consecutive_failures, notify), ("ok", 2, false));
+
+        let (next, notify) = advance(&next, false, now, &config);
+        assert_eq!(
+            (next.status.as_str(), next.notified_status.as_str(), notify),
+            ("failing", "failing", true)
+        );
+        assert_eq!(next.notified_at, Some(now));
+
+        // Further failures are the same outage
+        let (next, notify) = advance(&next, false, now, &config);
+        assert_eq!((next.consecutive_failures, notify), (4, false));
+
+        // Recovering within the cooldown is held back...
+        let soon = now + chrono::Duration::minutes(5);
+        let (next, notify) = advance(&next, true, soon, &config);
+        assert_eq!(
+            (next.status.as_str(), next.notified_status.as_str(), notify),
+            ("ok", "failing", false)
+        );
+
+        // ...and sent with the first result after it
+        let later = now + chrono::Duration::minutes(20);
+        let (next, notify) = advance(&next, true, later, &config);
+        assert_eq!((next.notified_status.as_str(), notify), ("ok", true));
+        assert_eq!(next.notified_at, Some(later));
+    }
+
+    type Received = Arc<std::sync::Mutex<Vec<serde_json::Value>>>;
+
+    async fn spawn_receiver(received: Received) -> String {
+        async fn ingest(State(received): State<Received>, axum::Json(body): axum::Json<serde_json::Value>) -> StatusCode {
+            received.lock().unwrap().push(body);
+            StatusCode::OK
+        }
+        let app = Router::new()
+            .route("/hooks", post(ingest))
+            .route("/broken", post(|| async { StatusCode::INTERNAL_SERVER_ERROR }))
+            .with_state(received);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    /// A deployment with a probe, returning the probe's ID
+    async fn setup_probe(pool: &PgPool, user_id: Uuid) -> Uuid {
+        let endpoint_id = sqlx::query_scalar!(
+            "INSERT INTO inference_endpoints (name, url, created_by) VALUES ('alerts-endpoint', 'http://localhost:8080', $1) RETURNING id",
+            user_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        let deployment_id = sqlx::query_scalar!(
+            "INSERT INTO deployed_models (model_name, alias, hosted_on, created_by) VALUES ('alerts-model', 'alerts-model', $1, $2) RETURNING id",
+            endpoint_id,
+            user_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query_scalar!(
+            "INSERT INTO probes (name, deployment_id, interval_seconds) VALUES ('chat', $1, 60) RETURNING id",
+            deployment_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    fn probe_result(probe_id: Uuid, success: bool) -> ProbeResult {
+        ProbeResult {
+            id: Uuid::new_v4(),
+            probe_id,
+            executed_at: Utc::now(),
+            success,
+            response_time_ms: Some(10),
+            status_code: Some(if success { 200 } else { 503 }),
+            error_message: (!success).then(|| "HTTP 503".to_string()),
+            response_data: None,
+            metadata: None,
+            assertion_results: None,
+            time_to_first_token_ms: None,
+            tokens_per_second: None,
+            in_maintenance: false,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_handle_result_alerts_channels_on_status_changes(pool: PgPool) {
+        let user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let probe_id = setup_probe(&pool, user.id).await;
+
+        let received = Received::default();
+        let base_url = spawn_receiver(received.clone()).await;
+        let channel = |name: &str, channel_type: &str, path: &str, enabled: bool| NewChannel {
+            name: name.to_string(),
+            channel_type: channel_type.to_string(),
+            destination: format!("{base_url}{path}"),
+            enabled,
+            created_by: user.id,
+        };
+        let webhook = ChannelManager::create(&pool, channel("ops", "webhook", "/hooks", true))
+            .await
+            .unwrap();
+        ChannelManager::create(&pool, channel("slack", "slack", "/hooks", true))
+            .await
+            .unwrap();
+        ChannelManager::create(&pool, channel("muted", "webhook", "/hooks", false))
+            .await
+            .unwrap();
+        let broken = ChannelManager::create(&pool, channel("broken", "webhook", "/broken", true))
+            .await
+            .unwrap();
+
+        let mut config = create_test_config();
+        config.probe_alerts.cooldown = Duration::ZERO;
+        config.probe_alerts.max_retries = 0;
+
+        for _ in 0..2 {
+            assert!(handle_result(&pool, &config, &probe_result(probe_id, false))
+                .await
+                .unwrap()
+                .is_none());
+        }
+        let alert = handle_result(&pool, &config, &probe_result(probe_id, false))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(alert.event, "probe.failing");
+        assert_eq!(alert.consecutive_failures, 3);
+        assert_eq!(
+            alert.message,
+            "Probe 'chat' of model 'alerts-model' is failing: 3 consecutive failures (last error: HTTP 503)"
+        );
+
+        // The outage carries on without further alerts
+        assert!(handle_result(&pool, &config, &probe_result(probe_id, false))
+            .await
+            .unwrap()
+            .is_none());
+
+        let alert = handle_result(&pool, &config, &probe_result(probe_id, true)).await.unwrap().unwrap();
+        assert_eq!(alert.event, "probe.recovered");
+
+        // A new outage straight after is held back by the cooldown
+        config.probe_alerts.cooldown = Duration::from_secs(3600);
+        for _ in 0..3 {
+            assert!(handle_result(&pool, &config, &probe_result(probe_id, false))
+                .await
+                .unwrap()
+                .is_none());
+        }
+
+        // Two alerts to each of the enabled, working channels. Deliveries are recorded after the
+        // receiver has answered, so wait for the records too.
+        let delivered = || async {
+            let webhook = ChannelManager::get(&pool, webhook.id).await.unwrap();
+            let broken = ChannelManager::get(&pool, broken.id).await.unwrap();
+            received.lock().unwrap().len() == 4 && webhook.last_delivery_at.is_some() && broken.last_delivery_error.is_some()
+        };
+        for _ in 0..250 {
+            if delivered().await {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.len(), 4);
+        let mut events: Vec<_> = received.iter().filter_map(|body| body["event"].as_str()).collect();
+        events.sort();
+        assert_eq!(events, vec!["probe.failing", "probe.recovered"]);
+        assert_eq!(received.iter().filter(|body| body["text"].is_string()).count(), 2);
+
+        let webhook = ChannelManager::get(&pool, webhook.id).await.unwrap();
+        assert!(webhook.last_delivery_at.is_some());
+        assert!(webhook.last_delivery_error.is_none());
+        let broken = ChannelManager::get(&pool, broken.id).await.unwrap();
+        assert!(broken.last_delivery_error.unwrap().contains("500"));
+    }
+}
@@ -0,0 +1,219 @@
+//! Custom assertions on probe responses.
+//!
+//! Assertions are checked after a probe's built-in checks pass, against the response body and the
+//! time it took. Every assertion is evaluated, not just up to the first failure, so the stored
+//! result shows all the ways a response fell short.
+
+use crate::api::models::probes::ProbeAssertion;
+use crate::db::models::probes::AssertionResult;
+use regex::Regex;
+use serde_json::Value;
+
+/// A step of a JSON path
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Parse a JSON path like `$.choices[0].message['content']`
+fn parse_path(path: &str) -> Result<Vec<Segment>, String> {
+    let rest = path
+        .strip_prefix('$')
+        .ok_or_else(|| format!("JSON path '{path}' must start with '$'"))?;
+    let invalid = || format!("Invalid JSON path '{path}'");
+
+    let mut segments = Vec::new();
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                let mut key = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == '.' || c == '[' {
+                        break;
+                    }
+                    key.push(c);
+                    chars.next();
+                }
+                if key.is_empty() {
+                    return Err(invalid());
+                }
+                segments.push(Segment::Key(key));
+            }
+            '[' => {
+                let mut inner = String::new();
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    inner.push(c);
+                }
+                let quoted = inner
+                    .strip_prefix('\'')
+                    .and_then(|key| key.strip_suffix('\''))
+                    .or_else(|| inner.strip_prefix('"').and_then(|key| key.strip_suffix('"')));
+                match quoted {
+                    Some(key) => segments.push(Segment::Key(key.to_string())),
+                    None => segments.push(Segment::Index(inner.parse().map_err(|_| invalid())?)),
+                }
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(segments)
+}
+
+/// Check that an assertion can be evaluated: its JSON path parses or its regex compiles
+pub fn validate(assertion: &ProbeAssertion) -> Result<(), String> {
+    match assertion {
+        ProbeAssertion::JsonPathExists { path } => parse_path(path).map(|_| ()),
+        ProbeAssertion::Regex { pattern } => Regex::new(pattern).map(|_| ()).map_err(|e| format!("Invalid regex: {e}")),
+        ProbeAssertion::MaxLatencyMs { .. } | ProbeAssertion::MinTokens { .. } => Ok(()),
+    }
+}
+
+/// The generated text of a chat or text completion
+fn content(response: &Value) -> Option<&str> {
+    response
+        .pointer("/choices/0/message/content")
+        .or_else(|| response.pointer("/choices/0/text"))
+        .and_then(Value::as_str)
+}
+
+/// Check a single assertion against a response and how long it took, in milliseconds
+fn check(assertion: &ProbeAssertion, response: &Value, latency_ms: u64) -> Result<(), String> {
+    match assertion {
+        ProbeAssertion::JsonPathExists { path } => {
+            let mut value = response;
+            for segment in parse_path(path)? {
+                let next = match &segment {
+                    Segment::Key(key) => value.get(key),
+                    Segment::Index(index) => value.get(index),
+                };
+                value = next.ok_or_else(|| format!("Response has nothing at {path}"))?;
+            }
+            Ok(())
+        }
+        ProbeAssertion::Regex { pattern } => {
+            let regex = Regex::new(pattern).map_err(|e| format!("Invalid regex '{pattern}': {e}"))?;
+            let content = content(response).ok_or("Response has no content")?;
+            if regex.is_match(content) {
+                Ok(())
+            } else {
+                Err(format!("Content does not match /{pattern}/"))
+            }
+        }
+        ProbeAssertion::MaxLatencyMs { max_ms } => {
+            if latency_ms <= *max_ms {
+                Ok(())
+            } else {
+                Err(format!("Took {latency_ms}ms, more than the maximum of {max_ms}ms"))
+            }
+        }
+        ProbeAssertion::MinTokens { min_tokens } => {
+            let tokens = response
+                .pointer("/usage/completion_tokens")
+                .and_then(Value::as_u64)
+                .ok_or("Response does not report usage.completion_tokens")?;
+            if tokens >= *min_tokens {
+                Ok(())
+            } else {
+                Err(format!("Generated {tokens} tokens, fewer than the minimum of {min_tokens}"))
+            }
+        }
+    }
+}
+
+/// Evaluate every assertion against a response
+pub fn evaluate(assertions: &[ProbeAssertion], response: &Value, latency_ms: u64) -> Vec<AssertionResult> {
+    assertions
+        .iter()
+        .map(|assertion| {
+            let outcome = check(assertion, response, latency_ms);
+            AssertionResult {
+                assertion: assertion.clone(),
+                passed: outcome.is_ok(),
+                message: outcome.err(),
+            }
+        })
+        .collect()
+}
+
+/// Why a response failed its assertions, if any failed
+pub fn failure_message(results: &[AssertionResult]) -> Option<String> {
+    let failures: Vec<&str> = results.iter().filter_map(|result| result.message.as_deref()).collect();
+    if failures.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "{} of {} assertions failed: {}",
+        failures.len(),
+        results.len(),
+        failures.join("; ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(
+            parse_path("$.choices[0]['message'].content").unwrap(),
+            vec![
+                Segment::Key("choices".to_string()),
+                Segment::Index(0),
+                Segment::Key("message".to_string()),
+                Segment::Key("content".to_string()),
+            ]
+        );
+        assert_eq!(parse_path("$").unwrap(), vec![]);
+        for invalid in ["choices", "$..usage", "$.choices[first]", "$usage"] {
+            assert!(parse_path(invalid).is_err(), "{invalid} should not parse");
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let response = json!({
+            "choices": [{"message": {"role": "assistant", "content": "The answer is 42"}}],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5}
+        });
+        let assertions = vec![
+            ProbeAssertion::JsonPathExists {
+                path: "$.choices[0].message.role".to_string(),
+            },
+            ProbeAssertion::JsonPathExists {
+                path: "$.choices[1]".to_string(),
+            },
+            ProbeAssertion::Regex {
+                pattern: r"\b42\b".to_string(),
+            },
+            ProbeAssertion::MaxLatencyMs { max_ms: 100 },
+            ProbeAssertion::MinTokens { min_tokens: 8 },
+        ];
+
+        let results = evaluate(&assertions, &response, 250);
+        let passed: Vec<bool> = results.iter().map(|result| result.passed).collect();
+        assert_eq!(passed, vec![true, false, true, false, false]);
+        assert_eq!(results[0].message, None);
+        assert_eq!(results[1].message.as_deref(), Some("Response has nothing at $.choices[1]"));
+        assert_eq!(
+            failure_message(&results).unwrap(),
+            "3 of 5 assertions failed: Response has nothing at $.choices[1]; Took 250ms, more than the maximum of 100ms; \
+             Generated 5 tokens, fewer than the minimum of 8"
+        );
+
+        assert!(failure_message(&evaluate(&assertions[..1], &response, 250)).is_none());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&ProbeAssertion::Regex { pattern: "(".to_string() }).is_err());
+        assert!(validate(&ProbeAssertion::JsonPathExists { path: "usage".to_string() }).is_err());
+        assert!(validate(&ProbeAssertion::MinTokens { min_tokens: 1 }).is_ok());
+    }
+}
@@ -0,0 +1,130 @@
+//! Database access layer for notification channels.
+
+use crate::db::models::notification_channels::NotificationChannel;
+use crate::errors::Error as AppError;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Everything needed to create a notification channel
+pub struct NewChannel {
+    pub name: String,
+    pub channel_type: String,
+    pub destination: String,
+    pub enabled: bool,
+    pub created_by: Uuid,
+}
+
+/// Changes to a notification channel; `None` fields are left unchanged
+#[derive(Default)]
+pub struct ChannelChanges {
+    pub name: Option<String>,
+    pub destination: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Database access layer for notification channels.
+pub struct ChannelManager;
+
+impl ChannelManager {
+    pub async fn create(pool: &PgPool, channel: NewChannel) -> Result<NotificationChannel, AppError> {
+        let result = sqlx::query_as::<_, NotificationChannel>(
+            r#"
+            INSERT INTO notification_channels (name, channel_type, destination, enabled, created_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(&channel.name)
+        .bind(&channel.channel_type)
+        .bind(&channel.destination)
+        .bind(channel.enabled)
+        .bind(channel.created_by)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create notification channel: {}", e))?;
+
+        Ok(result)
+    }
+
+    pub async fn get(pool: &PgPool, id: Uuid) -> Result<NotificationChannel, AppError> {
+        let channel = sqlx::query_as::<_, NotificationChannel>("SELECT * FROM notification_channels WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch notification channel: {}", e))?
+            .ok_or_else(|| AppError::NotFound {
+                resource: "Notification channel".to_string(),
+                id: id.to_string(),
+            })?;
+
+        Ok(channel)
+    }
+
+    /// List notification channels, optionally only the enabled ones
+    pub async fn list(pool: &PgPool, enabled_only: bool) -> Result<Vec<NotificationChannel>, AppError> {
+        let channels = sqlx::query_as::<_, NotificationChannel>(
+            "SELECT * FROM notification_channels WHERE (NOT $1 OR enabled) ORDER BY created_at DESC",
+        )
+        .bind(enabled_only)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to list notification channels: {}", e))?;
+
+        Ok(channels)
+    }
+
+    pub async fn update(pool: &PgPool, id: Uuid, changes: ChannelChanges) -> Result<NotificationChannel, AppError> {
+        let channel = sqlx::query_as::<_, NotificationChannel>(
+            r#"
+            UPDATE notification_channels SET
+                name = COALESCE($2, name),
+                destination = COALESCE($3, destination),
+                enabled = COALESCE($4, enabled),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(&changes.name)
+        .bind(&changes.destination)
+        .bind(changes.enabled)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to update notification channel: {}", e))?
+        .ok_or_else(|| AppError::NotFound {
+            resource: "Notification channel".to_string(),
+            id: id.to_string(),
+        })?;
+
+        Ok(channel)
+    }
+
+    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM notification_channels WHERE id = $1", id)
+            .execute(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to delete notification channel: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                resource: "Notification channel".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Record the outcome of delivering an alert to a channel
+    pub async fn record_delivery(pool: &PgPool, id: Uuid, error: Option<&str>) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE notification_channels SET last_delivery_at = NOW(), last_delivery_error = $2 WHERE id = $1",
+            id,
+            error
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to record notification delivery: {}", e))?;
+        Ok(())
+    }
+}
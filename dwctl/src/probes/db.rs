@@ -6,14 +6,41 @@
 //!
 //! Background scheduling is handled separately by the `ProbeScheduler`.
 
-use crate::api::models::probes::{CreateProbe, ProbeStatistics, UpdateProbeRequest};
+use crate::api::models::probes::{CreateProbe, Health, HealthStatus, ProbeStatistics, ProbeType, TestProbeRequest, UpdateProbeRequest};
 use crate::db::models::probes::{Probe, ProbeExecution, ProbeResult};
 use crate::errors::Error as AppError;
-use crate::probes::executor::{ProbeExecutionContext, ProbeExecutor};
+use crate::probes::executor::{LoadSettings, ProbeExecutionContext, ProbeExecutor};
 use chrono::{DateTime, Utc};
 use sqlx::PgPool;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
+/// How many of a deployment's most recent probe results its health is derived from
+const HEALTH_WINDOW: i64 = 5;
+
+/// A probe's own request timeout, or the configured default
+fn request_timeout(timeout_seconds: Option<i32>, config: &crate::config::Config) -> std::time::Duration {
+    timeout_seconds
+        .filter(|&seconds| seconds > 0)
+        .map(|seconds| std::time::Duration::from_secs(seconds as u64))
+        .unwrap_or(config.probes.request_timeout)
+}
+
+/// Concurrency and duration of a load probe, refused if they exceed the load testing limits
+fn load_settings(
+    probe_type: ProbeType,
+    concurrency: Option<i32>,
+    duration_seconds: Option<i32>,
+    config: &crate::config::Config,
+) -> Result<Option<LoadSettings>, AppError> {
+    if probe_type != ProbeType::Load {
+        return Ok(None);
+    }
+    LoadSettings::new(concurrency, duration_seconds, &config.load_testing)
+        .map(Some)
+        .map_err(|message| AppError::BadRequest { message })
+}
+
 /// Database access layer for probes.
 ///
 /// This provides pure database operations for probes. Background scheduling
@@ -22,12 +49,15 @@ use uuid::Uuid;
 pub struct ProbeManager;
 
 impl ProbeManager {
-    /// Create a new probe
+    /// Create a new probe, active unless it's a load probe (which only runs on demand)
     pub async fn create_probe(pool: &PgPool, probe: CreateProbe) -> Result<Probe, AppError> {
         let result = sqlx::query_as::<_, Probe>(
             r#"
-            INSERT INTO probes (name, deployment_id, interval_seconds, active, http_method, request_path, request_body)
-            VALUES ($1, $2, $3, true, $4, $5, $6)
+            INSERT INTO probes (name, deployment_id, interval_seconds, active, http_method, request_path, request_body,
+                                probe_type, expected_dimensions, max_latency_ms, conversation, jitter_seconds, timeout_seconds,
+                                assertions, golden_answer, embeddings_deployment_id, similarity_threshold, load_concurrency,
+                                load_duration_seconds)
+            VALUES ($1, $2, $3, $7 <> 'load', $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
             RETURNING *
             "#,
         )
@@ -37,6 +67,18 @@ impl ProbeManager {
         .bind(&probe.http_method)
         .bind(&probe.request_path)
         .bind(&probe.request_body)
+        .bind(probe.probe_type.as_str())
+        .bind(probe.expected_dimensions)
+        .bind(probe.max_latency_ms)
+        .bind(probe.conversation.map(sqlx::types::Json))
+        .bind(probe.jitter_seconds)
+        .bind(probe.timeout_seconds)
+        .bind(probe.assertions.map(sqlx::types::Json))
+        .bind(&probe.golden_answer)
+        .bind(probe.embeddings_deployment_id)
+        .bind(probe.similarity_threshold)
+        .bind(probe.load_concurrency)
+        .bind(probe.load_duration_seconds)
         .fetch_one(pool)
         .await
         .map_err(|e| anyhow::anyhow!("Failed to create probe: {}", e))?;
@@ -63,6 +105,25 @@ impl ProbeManager {
         Ok(probe)
     }
 
+    /// Whether a probe is paused because the endpoint of its deployment is disabled
+    pub async fn is_paused(pool: &PgPool, id: Uuid) -> Result<bool, AppError> {
+        let paused = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT NOT e.enabled
+            FROM probes p
+            JOIN deployed_models d ON d.id = p.deployment_id
+            JOIN inference_endpoints e ON e.id = d.hosted_on
+            WHERE p.id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to check whether probe is paused: {}", e))?;
+
+        Ok(paused.unwrap_or(false))
+    }
+
     /// List all probes
     pub async fn list_probes(pool: &PgPool) -> Result<Vec<Probe>, AppError> {
         let probes = sqlx::query_as::<_, Probe>(
@@ -168,6 +229,93 @@ impl ProbeManager {
         Ok(result)
     }
 
+    /// Get the health of multiple deployments (bulk operation), from the most recent results of
+    /// each of their probes other than load probes. Deployments without results are left out.
+    pub async fn get_deployment_health(
+        pool: &PgPool,
+        deployment_ids: &[Uuid],
+    ) -> Result<std::collections::HashMap<Uuid, Health>, AppError> {
+        if deployment_ids.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                p.deployment_id,
+                BOOL_AND(NOT pr.success) as "all_failed!",
+                BOOL_OR(NOT pr.success) as "any_failed!",
+                MAX(pr.executed_at) as "last_checked!"
+            FROM probes p
+            CROSS JOIN LATERAL (
+                SELECT success, executed_at
+                FROM probe_results
+                WHERE probe_id = p.id
+                ORDER BY executed_at DESC
+                LIMIT $2
+            ) pr
+            WHERE p.deployment_id = ANY($1) AND p.probe_type <> 'load'
+            GROUP BY p.deployment_id
+            "#,
+            deployment_ids,
+            HEALTH_WINDOW
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch deployment health: {}", e))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let status = if row.all_failed {
+                    HealthStatus::Down
+                } else if row.any_failed {
+                    HealthStatus::Degraded
+                } else {
+                    HealthStatus::Healthy
+                };
+                (
+                    row.deployment_id,
+                    Health {
+                        status,
+                        last_checked: row.last_checked,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    /// Get the health of multiple endpoints (bulk operation), combined from that of the
+    /// deployments they host. Endpoints without probed deployments are left out.
+    pub async fn get_endpoint_health(pool: &PgPool, endpoint_ids: &[Uuid]) -> Result<std::collections::HashMap<Uuid, Health>, AppError> {
+        if endpoint_ids.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+
+        let deployments = sqlx::query!(
+            "SELECT id, hosted_on FROM deployed_models WHERE hosted_on = ANY($1) AND deleted = false",
+            endpoint_ids
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch endpoint deployments: {}", e))?;
+
+        let deployment_ids: Vec<Uuid> = deployments.iter().map(|row| row.id).collect();
+        let deployment_health = Self::get_deployment_health(pool, &deployment_ids).await?;
+
+        let mut by_endpoint: std::collections::HashMap<Uuid, Vec<Health>> = std::collections::HashMap::new();
+        for row in deployments {
+            if let Some(health) = deployment_health.get(&row.id) {
+                by_endpoint.entry(row.hosted_on).or_default().push(*health);
+            }
+        }
+
+        Ok(by_endpoint
+            .into_iter()
+            .filter_map(|(endpoint_id, healths)| Health::combine(healths).map(|health| (endpoint_id, health)))
+            .collect())
+    }
+
     /// Calculate uptime percentage for a probe over a time period
     async fn calculate_uptime_percentage(pool: &PgPool, probe_id: Uuid, duration: chrono::Duration) -> Result<f64, AppError> {
         let since = chrono::Utc::now() - duration;
@@ -197,8 +345,14 @@ impl ProbeManager {
         Ok((successful as f64 / total as f64) * 100.0)
     }
 
-    /// Activate a probe
+    /// Activate a probe. Load probes only run on demand, so can't be activated.
     pub async fn activate_probe(pool: &PgPool, id: Uuid) -> Result<Probe, AppError> {
+        if Self::get_probe(pool, id).await?.probe_type == ProbeType::Load.as_str() {
+            return Err(AppError::BadRequest {
+                message: "Load probes only run on demand and can't be activated".to_string(),
+            });
+        }
+
         let probe = sqlx::query_as::<_, Probe>(
             r#"
             UPDATE probes SET active = true WHERE id = $1 RETURNING *
@@ -224,10 +378,13 @@ impl ProbeManager {
         .await
         .map_err(|e| anyhow::anyhow!("Failed to deactivate probe: {}", e))?;
 
+        // Without a probe nothing would close the circuit again
+        Self::close_circuit(pool, id).await?;
+
         Ok(probe)
     }
 
-    /// Update a probe's configuration
+    /// Update a probe's configuration. Turning it into a load probe deactivates it.
     pub async fn update_probe(pool: &PgPool, id: Uuid, update: UpdateProbeRequest) -> Result<Probe, AppError> {
         let updated_probe = sqlx::query_as::<_, Probe>(
             r#"
@@ -235,7 +392,20 @@ impl ProbeManager {
             SET interval_seconds = COALESCE($2, interval_seconds),
                 http_method = COALESCE($3, http_method),
                 request_path = COALESCE($4, request_path),
-                request_body = COALESCE($5, request_body)
+                request_body = COALESCE($5, request_body),
+                probe_type = COALESCE($6, probe_type),
+                expected_dimensions = COALESCE($7, expected_dimensions),
+                max_latency_ms = COALESCE($8, max_latency_ms),
+                conversation = COALESCE($9, conversation),
+                jitter_seconds = COALESCE($10, jitter_seconds),
+                timeout_seconds = COALESCE($11, timeout_seconds),
+                assertions = COALESCE($12, assertions),
+                golden_answer = COALESCE($13, golden_answer),
+                embeddings_deployment_id = COALESCE($14, embeddings_deployment_id),
+                similarity_threshold = COALESCE($15, similarity_threshold),
+                load_concurrency = COALESCE($16, load_concurrency),
+                load_duration_seconds = COALESCE($17, load_duration_seconds),
+                active = active AND COALESCE($6, probe_type) <> 'load'
             WHERE id = $1
             RETURNING *
             "#,
@@ -245,6 +415,18 @@ impl ProbeManager {
         .bind(update.http_method)
         .bind(update.request_path)
         .bind(update.request_body)
+        .bind(update.probe_type.map(|probe_type| probe_type.as_str()))
+        .bind(update.expected_dimensions)
+        .bind(update.max_latency_ms)
+        .bind(update.conversation.map(sqlx::types::Json))
+        .bind(update.jitter_seconds)
+        .bind(update.timeout_seconds)
+        .bind(update.assertions.map(sqlx::types::Json))
+        .bind(update.golden_answer)
+        .bind(update.embeddings_deployment_id)
+        .bind(update.similarity_threshold)
+        .bind(update.load_concurrency)
+        .bind(update.load_duration_seconds)
         .fetch_one(pool)
         .await
         .map_err(|e| anyhow::anyhow!("Failed to update probe: {}", e))?;
@@ -254,6 +436,8 @@ impl ProbeManager {
 
     /// Delete a probe
     pub async fn delete_probe(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+        Self::close_circuit(pool, id).await?;
+
         sqlx::query!(
             r#"
             DELETE FROM probes WHERE id = $1
@@ -272,9 +456,7 @@ impl ProbeManager {
         pool: &PgPool,
         deployment_id: Uuid,
         config: &crate::config::Config,
-        http_method: Option<String>,
-        request_path: Option<String>,
-        request_body: Option<serde_json::Value>,
+        request: TestProbeRequest,
     ) -> Result<ProbeResult, AppError> {
         // Fetch deployment details - use alias to route through control layer
         let context = sqlx::query!(
@@ -295,7 +477,11 @@ impl ProbeManager {
 
         let model_name = context.alias;
         let model_type_str = context.model_type;
-        let system_api_key = context.system_api_key;
+        let system_api_key = crate::crypto::decrypt_secret(context.system_api_key)?;
+        let embeddings_model = match request.embeddings_deployment_id {
+            Some(id) => Some(Self::get_alias(pool, id).await?),
+            None => None,
+        };
 
         // Route through control layer's normal AI proxy (not admin path)
         let endpoint_url = format!("http://localhost:{}/ai", config.port);
@@ -315,15 +501,28 @@ impl ProbeManager {
             None => crate::db::models::deployments::ModelType::detect_from_name(&model_name),
         };
 
+        let probe_type = request.probe_type.unwrap_or_default();
+        let load = load_settings(probe_type, request.load_concurrency, request.load_duration_seconds, config)?;
+
         let execution_context = ProbeExecutionContext {
             probe_id: Uuid::nil(), // Use nil UUID for test probes
             model_name,
             model_type,
             endpoint_url,
             api_key,
-            http_method: http_method.unwrap_or_else(|| "POST".to_string()),
-            request_path,
-            request_body,
+            http_method: request.http_method.unwrap_or_else(|| "POST".to_string()),
+            request_path: request.request_path,
+            request_body: request.request_body,
+            probe_type,
+            expected_dimensions: request.expected_dimensions,
+            max_latency_ms: request.max_latency_ms,
+            conversation: request.conversation,
+            assertions: request.assertions.unwrap_or_default(),
+            golden_answer: request.golden_answer,
+            embeddings_model,
+            similarity_threshold: request.similarity_threshold,
+            timeout: request_timeout(request.timeout_seconds, config),
+            load,
         };
 
         let executor = ProbeExecutor::new();
@@ -343,9 +542,25 @@ impl ProbeManager {
             error_message: execution.error_message,
             response_data: execution.response_data,
             metadata: execution.metadata,
+            assertion_results: execution.assertion_results.map(|results| serde_json::json!(results)),
+            in_maintenance: false,
+            time_to_first_token_ms: execution.time_to_first_token_ms,
+            tokens_per_second: execution.tokens_per_second,
         })
     }
 
+    /// Alias of a deployment, by which probes address it through the AI proxy
+    async fn get_alias(pool: &PgPool, deployment_id: Uuid) -> Result<String, AppError> {
+        sqlx::query_scalar!("SELECT alias FROM deployed_models WHERE id = $1", deployment_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch deployment: {}", e))?
+            .ok_or_else(|| AppError::NotFound {
+                resource: "Deployment".to_string(),
+                id: deployment_id.to_string(),
+            })
+    }
+
     /// Execute a probe and store the result
     pub async fn execute_probe(pool: &PgPool, id: Uuid, config: &crate::config::Config) -> Result<ProbeResult, AppError> {
         // Note: We allow executing inactive probes manually via "Run Now"
@@ -359,11 +574,23 @@ impl ProbeManager {
                 p.http_method,
                 p.request_path,
                 p.request_body,
+                p.probe_type,
+                p.expected_dimensions,
+                p.max_latency_ms,
+                p.conversation,
+                p.timeout_seconds,
+                p.assertions,
+                p.golden_answer,
+                p.similarity_threshold,
+                p.load_concurrency,
+                p.load_duration_seconds,
                 d.alias,
                 d.type as model_type,
+                e.alias as "embeddings_alias?",
                 ak.secret as system_api_key
             FROM probes p
             JOIN deployed_models d ON p.deployment_id = d.id
+            LEFT JOIN deployed_models e ON p.embeddings_deployment_id = e.id
             CROSS JOIN api_keys ak
             WHERE p.id = $1 AND ak.id = '00000000-0000-0000-0000-000000000000'::uuid
             "#,
@@ -379,7 +606,7 @@ impl ProbeManager {
         let request_body = context.request_body;
         let model_name = context.alias;
         let model_type_str = context.model_type;
-        let system_api_key = context.system_api_key;
+        let system_api_key = crate::crypto::decrypt_secret(context.system_api_key)?;
 
         // Route through control layer's normal AI proxy (not admin path)
         let endpoint_url = format!("http://localhost:{}/ai", config.port);
@@ -399,6 +626,9 @@ impl ProbeManager {
             None => crate::db::models::deployments::ModelType::detect_from_name(&model_name),
         };
 
+        let probe_type = ProbeType::parse(&context.probe_type).unwrap_or_default();
+        let load = load_settings(probe_type, context.load_concurrency, context.load_duration_seconds, config)?;
+
         let execution_context = ProbeExecutionContext {
             probe_id,
             model_name,
@@ -408,6 +638,23 @@ impl ProbeManager {
             http_method,
             request_path,
             request_body,
+            probe_type,
+            expected_dimensions: context.expected_dimensions,
+            max_latency_ms: context.max_latency_ms,
+            // Conversations are validated when stored; an unreadable one falls back to the default
+            conversation: context
+                .conversation
+                .and_then(|conversation| serde_json::from_value(conversation).ok()),
+            // Likewise assertions, which are skipped if unreadable
+            assertions: context
+                .assertions
+                .and_then(|assertions| serde_json::from_value(assertions).ok())
+                .unwrap_or_default(),
+            golden_answer: context.golden_answer,
+            embeddings_model: context.embeddings_alias,
+            similarity_threshold: context.similarity_threshold,
+            timeout: request_timeout(context.timeout_seconds, config),
+            load,
         };
 
         let executor = ProbeExecutor::new();
@@ -415,16 +662,103 @@ impl ProbeManager {
 
         let result = Self::store_result(pool, execution).await?;
 
+        // A deployment struggling under a load probe's artificial load isn't down
+        if probe_type == ProbeType::Load {
+            return Ok(result);
+        }
+
+        let circuit_breaker = &config.routing.circuit_breaker;
+        if circuit_breaker.enabled {
+            if let Err(e) = Self::update_circuit_breaker(pool, probe_id, result.success, circuit_breaker.failure_threshold).await {
+                error!("Failed to update circuit breaker for probe {}: {}", probe_id, e);
+            }
+        }
+
+        // Probes keep running during maintenance, but failures then are expected
+        if config.probe_alerts.enabled && !result.in_maintenance {
+            if let Err(e) = crate::probes::alerts::handle_result(pool, config, &result).await {
+                error!("Failed to update alerting state for probe {}: {}", probe_id, e);
+            }
+        }
+
         Ok(result)
     }
 
-    /// Store a probe execution result
+    /// Open or close the circuit breaker of a probe's deployment after a probe result.
+    ///
+    /// The circuit opens once the probe's last `failure_threshold` results have all failed, and
+    /// closes again on its next success. Opening or closing it reloads the proxy configuration.
+    pub async fn update_circuit_breaker(pool: &PgPool, probe_id: Uuid, success: bool, failure_threshold: u32) -> Result<(), AppError> {
+        if success {
+            if Self::close_circuit(pool, probe_id).await? {
+                info!("Probe {} succeeded, closing its deployment's circuit breaker", probe_id);
+            }
+            return Ok(());
+        }
+
+        let opened = sqlx::query!(
+            r#"
+            INSERT INTO deployment_circuit_breakers (deployment_id)
+            SELECT p.deployment_id
+            FROM probes p
+            WHERE p.id = $1
+              AND (
+                  SELECT COUNT(*) FILTER (WHERE NOT recent.success)
+                  FROM (
+                      SELECT success FROM probe_results WHERE probe_id = $1 ORDER BY executed_at DESC LIMIT $2
+                  ) recent
+              ) = $2
+            ON CONFLICT (deployment_id) DO NOTHING
+            "#,
+            probe_id,
+            i64::from(failure_threshold.max(1))
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to open circuit breaker: {}", e))?
+        .rows_affected()
+            > 0;
+        if opened {
+            warn!(
+                "Probe {} failed {} times in a row, opening its deployment's circuit breaker",
+                probe_id, failure_threshold
+            );
+        }
+        Ok(())
+    }
+
+    /// Close the circuit breaker of a probe's deployment, returning whether it was open
+    async fn close_circuit(pool: &PgPool, probe_id: Uuid) -> Result<bool, AppError> {
+        let closed = sqlx::query!(
+            r#"
+            DELETE FROM deployment_circuit_breakers
+            WHERE deployment_id = (SELECT deployment_id FROM probes WHERE id = $1)
+            "#,
+            probe_id
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to close circuit breaker: {}", e))?
+        .rows_affected()
+            > 0;
+        Ok(closed)
+    }
+
+    /// Store a probe execution result, marking it as in maintenance if a maintenance window of the
+    /// probe's deployment or of the endpoint hosting it is in progress
     async fn store_result(pool: &PgPool, execution: ProbeExecution) -> Result<ProbeResult, AppError> {
         let result = sqlx::query_as::<_, ProbeResult>(
             r#"
             INSERT INTO probe_results
-            (probe_id, success, response_time_ms, status_code, error_message, response_data, metadata)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            (probe_id, success, response_time_ms, status_code, error_message, response_data, metadata, assertion_results,
+             time_to_first_token_ms, tokens_per_second, in_maintenance)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, EXISTS(
+                SELECT 1
+                FROM probes p
+                JOIN deployed_models d ON d.id = p.deployment_id
+                JOIN maintenance_windows w ON w.deployment_id = d.id OR w.endpoint_id = d.hosted_on
+                WHERE p.id = $1 AND w.starts_at <= NOW() AND w.ends_at > NOW()
+            ))
             RETURNING *
             "#,
         )
@@ -435,6 +769,9 @@ impl ProbeManager {
         .bind(execution.error_message)
         .bind(execution.response_data)
         .bind(execution.metadata)
+        .bind(execution.assertion_results.map(sqlx::types::Json))
+        .bind(execution.time_to_first_token_ms)
+        .bind(execution.tokens_per_second)
         .fetch_one(pool)
         .await
         .map_err(|e| anyhow::anyhow!("Failed to store probe result: {}", e))?;
@@ -506,7 +843,7 @@ impl ProbeManager {
     /// Calculate aggregated statistics for a probe over a time period.
     ///
     /// Computes success rates, response time percentiles, and execution counts
-    /// from stored probe results.
+    /// from stored probe results, along with streaming timings where results have them.
     pub async fn get_statistics(
         pool: &PgPool,
         probe_id: Uuid,
@@ -525,6 +862,10 @@ impl ProbeManager {
             p50: Option<f64>,
             p95: Option<f64>,
             p99: Option<f64>,
+            avg_ttft: Option<f64>,
+            p50_ttft: Option<f64>,
+            p95_ttft: Option<f64>,
+            avg_tokens_per_second: Option<f64>,
             last_execution: Option<DateTime<Utc>>,
             last_success: Option<DateTime<Utc>>,
             last_failure: Option<DateTime<Utc>>,
@@ -544,6 +885,10 @@ impl ProbeManager {
                         (PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p50,
                         (PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p95,
                         (PERCENTILE_CONT(0.99) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p99,
+                        (AVG(time_to_first_token_ms) FILTER (WHERE success = true))::float8 as avg_ttft,
+                        (PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY time_to_first_token_ms) FILTER (WHERE success = true))::float8 as p50_ttft,
+                        (PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY time_to_first_token_ms) FILTER (WHERE success = true))::float8 as p95_ttft,
+                        (AVG(tokens_per_second) FILTER (WHERE success = true))::float8 as avg_tokens_per_second,
                         MAX(executed_at) as last_execution,
                         MAX(executed_at) FILTER (WHERE success = true) as last_success,
                         MAX(executed_at) FILTER (WHERE success = false) as last_failure
@@ -568,6 +913,10 @@ impl ProbeManager {
                         (PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p50,
                         (PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p95,
                         (PERCENTILE_CONT(0.99) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p99,
+                        (AVG(time_to_first_token_ms) FILTER (WHERE success = true))::float8 as avg_ttft,
+                        (PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY time_to_first_token_ms) FILTER (WHERE success = true))::float8 as p50_ttft,
+                        (PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY time_to_first_token_ms) FILTER (WHERE success = true))::float8 as p95_ttft,
+                        (AVG(tokens_per_second) FILTER (WHERE success = true))::float8 as avg_tokens_per_second,
                         MAX(executed_at) as last_execution,
                         MAX(executed_at) FILTER (WHERE success = true) as last_success,
                         MAX(executed_at) FILTER (WHERE success = false) as last_failure
@@ -593,6 +942,10 @@ impl ProbeManager {
                         (PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p50,
                         (PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p95,
                         (PERCENTILE_CONT(0.99) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p99,
+                        (AVG(time_to_first_token_ms) FILTER (WHERE success = true))::float8 as avg_ttft,
+                        (PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY time_to_first_token_ms) FILTER (WHERE success = true))::float8 as p50_ttft,
+                        (PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY time_to_first_token_ms) FILTER (WHERE success = true))::float8 as p95_ttft,
+                        (AVG(tokens_per_second) FILTER (WHERE success = true))::float8 as avg_tokens_per_second,
                         MAX(executed_at) as last_execution,
                         MAX(executed_at) FILTER (WHERE success = true) as last_success,
                         MAX(executed_at) FILTER (WHERE success = false) as last_failure
@@ -618,6 +971,10 @@ impl ProbeManager {
                         (PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p50,
                         (PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p95,
                         (PERCENTILE_CONT(0.99) WITHIN GROUP (ORDER BY response_time_ms) FILTER (WHERE success = true))::float8 as p99,
+                        (AVG(time_to_first_token_ms) FILTER (WHERE success = true))::float8 as avg_ttft,
+                        (PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY time_to_first_token_ms) FILTER (WHERE success = true))::float8 as p50_ttft,
+                        (PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY time_to_first_token_ms) FILTER (WHERE success = true))::float8 as p95_ttft,
+                        (AVG(tokens_per_second) FILTER (WHERE success = true))::float8 as avg_tokens_per_second,
                         MAX(executed_at) as last_execution,
                         MAX(executed_at) FILTER (WHERE success = true) as last_success,
                         MAX(executed_at) FILTER (WHERE success = false) as last_failure
@@ -653,6 +1010,10 @@ impl ProbeManager {
             p50_response_time_ms: row.p50,
             p95_response_time_ms: row.p95,
             p99_response_time_ms: row.p99,
+            avg_time_to_first_token_ms: row.avg_ttft,
+            p50_time_to_first_token_ms: row.p50_ttft,
+            p95_time_to_first_token_ms: row.p95_ttft,
+            avg_tokens_per_second: row.avg_tokens_per_second,
             last_execution: row.last_execution,
             last_success: row.last_success,
             last_failure: row.last_failure,
@@ -707,6 +1068,18 @@ mod tests {
             http_method: "POST".to_string(),
             request_path: None,
             request_body: None,
+            probe_type: ProbeType::Http,
+            expected_dimensions: None,
+            max_latency_ms: None,
+            conversation: None,
+            jitter_seconds: None,
+            timeout_seconds: None,
+            assertions: None,
+            golden_answer: None,
+            embeddings_deployment_id: None,
+            similarity_threshold: None,
+            load_concurrency: None,
+            load_duration_seconds: None,
         };
 
         let created = ProbeManager::create_probe(&pool, probe_create).await.unwrap();
@@ -736,6 +1109,18 @@ mod tests {
                     http_method: "POST".to_string(),
                     request_path: None,
                     request_body: None,
+                    probe_type: ProbeType::Http,
+                    expected_dimensions: None,
+                    max_latency_ms: None,
+                    conversation: None,
+                    jitter_seconds: None,
+                    timeout_seconds: None,
+                    assertions: None,
+                    golden_answer: None,
+                    embeddings_deployment_id: None,
+                    similarity_threshold: None,
+                    load_concurrency: None,
+                    load_duration_seconds: None,
                 },
             )
             .await
@@ -762,6 +1147,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -776,6 +1173,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -802,6 +1211,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -831,6 +1252,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -845,6 +1278,18 @@ mod tests {
                 http_method: None,
                 request_path: None,
                 request_body: None,
+                probe_type: None,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -860,6 +1305,18 @@ mod tests {
                 http_method: None,
                 request_path: None,
                 request_body: None,
+                probe_type: None,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -880,6 +1337,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -905,6 +1374,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -919,6 +1400,275 @@ mod tests {
         assert_eq!(*interval, Some(60));
     }
 
+    #[sqlx::test]
+    async fn test_get_deployment_and_endpoint_health(pool: PgPool) {
+        let flaky = setup_test_deployment(&pool).await;
+        let endpoint_id = sqlx::query_scalar!("SELECT hosted_on FROM deployed_models WHERE id = $1", flaky)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let broken = sqlx::query_scalar!(
+            "INSERT INTO deployed_models (model_name, alias, type, hosted_on, created_by) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            "broken-model",
+            "broken-model",
+            "chat" as _,
+            endpoint_id,
+            Uuid::nil()
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let unprobed = setup_test_deployment(&pool).await;
+
+        let mut probes = std::collections::HashMap::new();
+        for deployment_id in [flaky, broken] {
+            let probe = ProbeManager::create_probe(
+                &pool,
+                CreateProbe {
+                    name: format!("Test Probe {}", deployment_id),
+                    deployment_id,
+                    interval_seconds: 60,
+                    http_method: "POST".to_string(),
+                    request_path: None,
+                    request_body: None,
+                    probe_type: ProbeType::Http,
+                    expected_dimensions: None,
+                    max_latency_ms: None,
+                    conversation: None,
+                    jitter_seconds: None,
+                    timeout_seconds: None,
+                    assertions: None,
+                    golden_answer: None,
+                    embeddings_deployment_id: None,
+                    similarity_threshold: None,
+                    load_concurrency: None,
+                    load_duration_seconds: None,
+                },
+            )
+            .await
+            .unwrap();
+            probes.insert(deployment_id, probe.id);
+        }
+        let record = |deployment_id: Uuid, success: bool| {
+            let pool = pool.clone();
+            let probe_id = probes[&deployment_id];
+            async move {
+                ProbeManager::store_result(
+                    &pool,
+                    ProbeExecution {
+                        probe_id,
+                        success,
+                        response_time_ms: 100,
+                        status_code: Some(if success { 200 } else { 500 }),
+                        error_message: None,
+                        response_data: None,
+                        metadata: None,
+                        assertion_results: None,
+                        time_to_first_token_ms: None,
+                        tokens_per_second: None,
+                    },
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        // A failure older than the window is forgotten
+        record(flaky, false).await;
+        for _ in 0..HEALTH_WINDOW {
+            record(flaky, true).await;
+        }
+        record(broken, false).await;
+        record(broken, false).await;
+
+        let health = ProbeManager::get_deployment_health(&pool, &[flaky, broken, unprobed])
+            .await
+            .unwrap();
+        assert_eq!(health[&flaky].status, HealthStatus::Healthy);
+        assert_eq!(health[&broken].status, HealthStatus::Down);
+        assert!(!health.contains_key(&unprobed));
+
+        let latest = record(flaky, false).await;
+        let health = ProbeManager::get_deployment_health(&pool, &[flaky]).await.unwrap();
+        assert_eq!(health[&flaky].status, HealthStatus::Degraded);
+        assert_eq!(health[&flaky].last_checked, latest.executed_at);
+
+        // The endpoint combines its deployments: one degraded and one down
+        let health = ProbeManager::get_endpoint_health(&pool, &[endpoint_id]).await.unwrap();
+        assert_eq!(health[&endpoint_id].status, HealthStatus::Degraded);
+        assert_eq!(health[&endpoint_id].last_checked, latest.executed_at);
+    }
+
+    #[sqlx::test]
+    async fn test_circuit_breaker_opens_after_consecutive_failures(pool: PgPool) {
+        use sqlx::postgres::PgListener;
+        use tokio::time::{timeout, Duration};
+
+        let deployment_id = setup_test_deployment(&pool).await;
+        let probe = ProbeManager::create_probe(
+            &pool,
+            CreateProbe {
+                name: "Test Probe".to_string(),
+                deployment_id,
+                interval_seconds: 60,
+                http_method: "POST".to_string(),
+                request_path: None,
+                request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let record = |success: bool| {
+            let pool = pool.clone();
+            async move {
+                let execution = ProbeExecution {
+                    probe_id: probe.id,
+                    success,
+                    response_time_ms: 10,
+                    status_code: Some(if success { 200 } else { 503 }),
+                    error_message: None,
+                    response_data: None,
+                    metadata: None,
+                    assertion_results: None,
+                    time_to_first_token_ms: None,
+                    tokens_per_second: None,
+                };
+                ProbeManager::store_result(&pool, execution).await.unwrap();
+                ProbeManager::update_circuit_breaker(&pool, probe.id, success, 3).await.unwrap();
+            }
+        };
+        let is_open = || async {
+            let mut conn = pool.acquire().await.unwrap();
+            let open = crate::db::handlers::Deployments::new(&mut conn)
+                .get_open_circuits_bulk(&[deployment_id])
+                .await
+                .unwrap();
+            open.contains(&deployment_id)
+        };
+
+        let mut listener = PgListener::connect_with(&pool).await.unwrap();
+        listener.listen("auth_config_changed").await.unwrap();
+
+        // A success resets the run of failures
+        record(false).await;
+        record(false).await;
+        record(true).await;
+        record(false).await;
+        record(false).await;
+        assert!(!is_open().await);
+
+        record(false).await;
+        assert!(is_open().await);
+        timeout(Duration::from_secs(2), listener.recv()).await.unwrap().unwrap();
+
+        // Further failures leave it open without reloading the proxy again
+        record(false).await;
+        assert!(is_open().await);
+        assert!(timeout(Duration::from_millis(200), listener.recv()).await.is_err());
+
+        record(true).await;
+        assert!(!is_open().await);
+        timeout(Duration::from_secs(2), listener.recv()).await.unwrap().unwrap();
+
+        // Deactivating the probe closes the circuit, as nothing else would
+        for _ in 0..3 {
+            record(false).await;
+        }
+        assert!(is_open().await);
+        ProbeManager::deactivate_probe(&pool, probe.id).await.unwrap();
+        assert!(!is_open().await);
+    }
+
+    #[sqlx::test]
+    async fn test_results_in_maintenance_windows(pool: PgPool) {
+        let deployment_id = setup_test_deployment(&pool).await;
+        let probe = ProbeManager::create_probe(
+            &pool,
+            CreateProbe {
+                name: "Test Probe".to_string(),
+                deployment_id,
+                interval_seconds: 60,
+                http_method: "POST".to_string(),
+                request_path: None,
+                request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
+            },
+        )
+        .await
+        .unwrap();
+        let store = || {
+            ProbeManager::store_result(
+                &pool,
+                ProbeExecution {
+                    probe_id: probe.id,
+                    success: false,
+                    response_time_ms: 10,
+                    status_code: Some(503),
+                    error_message: None,
+                    response_data: None,
+                    metadata: None,
+                    assertion_results: None,
+                    time_to_first_token_ms: None,
+                    tokens_per_second: None,
+                },
+            )
+        };
+
+        // Windows that have ended or haven't started don't count
+        sqlx::query!(
+            r#"
+            INSERT INTO maintenance_windows (name, deployment_id, starts_at, ends_at, created_by)
+            VALUES ('past', $1, NOW() - INTERVAL '2 hours', NOW() - INTERVAL '1 hour', $2),
+                   ('future', $1, NOW() + INTERVAL '1 hour', NOW() + INTERVAL '2 hours', $2)
+            "#,
+            deployment_id,
+            Uuid::nil()
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(!store().await.unwrap().in_maintenance);
+
+        // A window of the endpoint covers its deployments
+        sqlx::query!(
+            r#"
+            INSERT INTO maintenance_windows (name, endpoint_id, starts_at, ends_at, created_by)
+            SELECT 'now', hosted_on, NOW() - INTERVAL '1 minute', NOW() + INTERVAL '1 hour', $2
+            FROM deployed_models WHERE id = $1
+            "#,
+            deployment_id,
+            Uuid::nil()
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(store().await.unwrap().in_maintenance);
+    }
+
     #[sqlx::test]
     async fn test_get_statistics_empty(pool: PgPool) {
         let deployment_id = setup_test_deployment(&pool).await;
@@ -932,6 +1682,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -945,6 +1707,66 @@ mod tests {
         assert_eq!(stats.success_rate, 0.0);
     }
 
+    #[sqlx::test]
+    async fn test_get_statistics_streaming_timings(pool: PgPool) {
+        let deployment_id = setup_test_deployment(&pool).await;
+        let probe = ProbeManager::create_probe(
+            &pool,
+            CreateProbe {
+                name: "Test Probe".to_string(),
+                deployment_id,
+                interval_seconds: 60,
+                http_method: "POST".to_string(),
+                request_path: None,
+                request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        // Failures don't count towards the timings
+        for (success, time_to_first_token_ms, tokens_per_second) in
+            [(true, 100, 10.0), (true, 200, 20.0), (true, 300, 30.0), (false, 5000, 1.0)]
+        {
+            let result = ProbeManager::store_result(
+                &pool,
+                ProbeExecution {
+                    probe_id: probe.id,
+                    success,
+                    response_time_ms: 1000,
+                    status_code: Some(200),
+                    error_message: None,
+                    response_data: None,
+                    metadata: None,
+                    assertion_results: None,
+                    time_to_first_token_ms: Some(time_to_first_token_ms),
+                    tokens_per_second: Some(tokens_per_second),
+                },
+            )
+            .await
+            .unwrap();
+            assert_eq!(result.time_to_first_token_ms, Some(time_to_first_token_ms));
+        }
+
+        let stats = ProbeManager::get_statistics(&pool, probe.id, None, None).await.unwrap();
+        assert_eq!(stats.avg_time_to_first_token_ms, Some(200.0));
+        assert_eq!(stats.p50_time_to_first_token_ms, Some(200.0));
+        assert_eq!(stats.p95_time_to_first_token_ms, Some(290.0));
+        assert_eq!(stats.avg_tokens_per_second, Some(20.0));
+    }
+
     #[sqlx::test]
     async fn test_get_probe_results_empty(pool: PgPool) {
         let deployment_id = setup_test_deployment(&pool).await;
@@ -958,6 +1780,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -988,6 +1822,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -1013,6 +1859,18 @@ mod tests {
                 http_method: None,
                 request_path: None,
                 request_body: None,
+                probe_type: None,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
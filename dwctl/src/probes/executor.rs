@@ -3,15 +3,107 @@
 //! This module provides the `ProbeExecutor` which handles the actual HTTP requests
 //! to monitored endpoints. It constructs appropriate payloads for different endpoint
 //! types (chat completions vs embeddings) and measures response times.
+//!
+//! HTTP probes of chat deployments without a custom request body stream their completion, and
+//! record the time to the first token (the latency users actually feel) and the rate of the tokens
+//! that follow, besides the total latency. Any response streamed as server-sent events is timed
+//! this way, so a custom request body can opt in by setting `stream`. The streamed chunks are
+//! merged into a regular chat completion before the response is checked.
+//!
+//! Embeddings probes call `/v1/embeddings` whatever the deployment's model type and also check
+//! the returned vectors: a response only counts as a success if every input got a non-empty
+//! numeric vector and all vectors have the same (and, if configured, the expected) number of
+//! dimensions.
+//!
+//! Tool call probes send a chat completion offering a tool schema and check that the model calls
+//! one of the offered tools with a JSON object of arguments that has every required parameter, to
+//! catch upstream regressions in function calling (e.g. a dropped tool parser) before users do.
+//! The default payload forces a call to a `get_weather` tool; a custom request body can offer its
+//! own tools instead.
+//!
+//! Conversation probes play a scripted multi-turn chat, sending the whole history with each user
+//! message, and fail on the first turn whose reply doesn't contain the expected content. This
+//! catches upstreams that answer single prompts fine but mishandle context (e.g. a broken chat
+//! template or truncated history). Without a script, the model is asked to remember a code word
+//! and then to recall it. Fields of a custom request body other than `messages` are sent with
+//! every turn.
+//!
+//! Semantic similarity probes send a chat completion (the question is the request body) and embed
+//! the reply and the probe's golden answer with a separate embeddings deployment. They fail when
+//! the cosine similarity of the two is below the threshold, catching regressions in answer quality
+//! (e.g. a wrong checkpoint or broken quantization) that still return well-formed responses.
+//!
+//! Load probes run on demand only. They keep a number of requests in flight for a bounded duration,
+//! sending the same request as an HTTP probe through the load test runner (see
+//! [`crate::load_tests::runner`]), and record the throughput and latency distribution. Their
+//! concurrency and duration are capped by the `load_testing` configuration, which must be enabled,
+//! and they stop early if too many requests fail.
+//!
+//! Any probe can also fail responses slower than its latency limit; for conversation probes the
+//! limit applies to the whole conversation. Once a response passes these checks, the probe's custom
+//! assertions (see [`crate::probes::assertions`]) are evaluated against it.
 
+use crate::api::models::probes::{ConversationTurn, ProbeAssertion, ProbeType};
+use crate::config::LoadTestingConfig;
 use crate::db::models::deployments::ModelType;
-use crate::db::models::probes::ProbeExecution;
+use crate::db::models::probes::{AssertionResult, ProbeExecution};
+use crate::load_tests::runner::{self, LoadTestPlan, Outcome};
+use crate::probes::assertions;
 use anyhow::Result;
 use reqwest::Client;
 use serde_json::json;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+/// Minimum similarity to the golden answer when a semantic similarity probe doesn't set one
+pub const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/// Requests a load probe keeps in flight when it doesn't set its own concurrency
+pub const DEFAULT_LOAD_CONCURRENCY: i32 = 4;
+
+/// Seconds a load probe sends requests for when it doesn't set its own duration
+pub const DEFAULT_LOAD_DURATION_SECONDS: i32 = 10;
+
+/// How hard a load probe pushes its deployment, within the server's load testing limits
+#[derive(Debug, Clone)]
+pub struct LoadSettings {
+    pub concurrency: u32,
+    pub duration: Duration,
+    /// Most requests sent, from `load_testing.max_requests`
+    pub max_requests: u32,
+    /// Fastest rate requests are started at, from `load_testing.max_requests_per_second`
+    pub max_requests_per_second: u32,
+    /// Fraction of failed requests at which the probe stops, from `load_testing.max_error_rate`
+    pub max_error_rate: f64,
+}
+
+impl LoadSettings {
+    /// Settings of a load probe, or why it can't run: load testing is disabled, or the probe's
+    /// concurrency or duration exceed the configured limits
+    pub fn new(concurrency: Option<i32>, duration_seconds: Option<i32>, limits: &LoadTestingConfig) -> Result<Self, String> {
+        if !limits.enabled {
+            return Err("Load probes need load testing to be enabled on this server".to_string());
+        }
+        let max_duration_seconds = i32::try_from(limits.max_duration.as_secs()).unwrap_or(i32::MAX);
+        let max_concurrency = i32::try_from(limits.max_concurrency).unwrap_or(i32::MAX);
+        let concurrency = concurrency.unwrap_or(DEFAULT_LOAD_CONCURRENCY.min(max_concurrency));
+        let duration_seconds = duration_seconds.unwrap_or(DEFAULT_LOAD_DURATION_SECONDS.min(max_duration_seconds));
+        if !(1..=max_concurrency).contains(&concurrency) {
+            return Err(format!("load_concurrency must be between 1 and {}", max_concurrency));
+        }
+        if !(1..=max_duration_seconds).contains(&duration_seconds) {
+            return Err(format!("load_duration_seconds must be between 1 and {}", max_duration_seconds));
+        }
+        Ok(Self {
+            concurrency: concurrency as u32,
+            duration: Duration::from_secs(duration_seconds as u64),
+            max_requests: limits.max_requests,
+            max_requests_per_second: limits.max_requests_per_second,
+            max_error_rate: limits.max_error_rate,
+        })
+    }
+}
+
 /// Data needed to execute a probe, fetched from database
 pub struct ProbeExecutionContext {
     pub probe_id: Uuid,
@@ -22,6 +114,19 @@ pub struct ProbeExecutionContext {
     pub http_method: String,
     pub request_path: Option<String>,
     pub request_body: Option<serde_json::Value>,
+    pub probe_type: ProbeType,
+    pub expected_dimensions: Option<i32>,
+    pub max_latency_ms: Option<i32>,
+    pub conversation: Option<Vec<ConversationTurn>>,
+    pub assertions: Vec<ProbeAssertion>,
+    pub golden_answer: Option<String>,
+    /// Name of the deployment that embeds the reply and the golden answer
+    pub embeddings_model: Option<String>,
+    pub similarity_threshold: Option<f64>,
+    /// Timeout for each request
+    pub timeout: Duration,
+    /// Concurrency and duration of a load probe
+    pub load: Option<LoadSettings>,
 }
 
 /// Executes health check requests against API endpoints.
@@ -39,7 +144,7 @@ impl ProbeExecutor {
     }
 
     /// Get default URL and payload for a model type
-    fn get_default_config(model_type: &ModelType, model_name: &str, endpoint_url: &str) -> (String, serde_json::Value) {
+    pub(crate) fn get_default_config(model_type: &ModelType, model_name: &str, endpoint_url: &str) -> (String, serde_json::Value) {
         match model_type {
             ModelType::Chat => (
                 format!("{}/v1/chat/completions", endpoint_url.trim_end_matches('/')),
@@ -67,6 +172,45 @@ impl ProbeExecutor {
         }
     }
 
+    /// Get default URL and payload for a tool call probe
+    pub(crate) fn get_tool_call_config(model_name: &str, endpoint_url: &str) -> (String, serde_json::Value) {
+        (
+            format!("{}/v1/chat/completions", endpoint_url.trim_end_matches('/')),
+            json!({
+                "model": model_name,
+                "messages": [{"role": "user", "content": "What is the weather in Paris? This is a health check probe."}],
+                "tools": [{
+                    "type": "function",
+                    "function": {
+                        "name": "get_weather",
+                        "description": "Get the current weather in a city",
+                        "parameters": {
+                            "type": "object",
+                            "properties": {"city": {"type": "string", "description": "Name of the city"}},
+                            "required": ["city"]
+                        }
+                    }
+                }],
+                "tool_choice": {"type": "function", "function": {"name": "get_weather"}},
+                "max_tokens": 100
+            }),
+        )
+    }
+
+    /// Default turns of a conversation probe: the second reply must recall the first message
+    pub(crate) fn get_default_conversation() -> Vec<ConversationTurn> {
+        vec![
+            ConversationTurn {
+                content: "Remember the code word 'pineapple'. This is a health check probe, reply with just OK.".to_string(),
+                expect_contains: vec![],
+            },
+            ConversationTurn {
+                content: "What was the code word? Reply with just the word.".to_string(),
+                expect_contains: vec!["pineapple".to_string()],
+            },
+        ]
+    }
+
     /// Execute a probe against its configured endpoint.
     ///
     /// Constructs an appropriate test payload based on the model type,
@@ -74,10 +218,23 @@ impl ProbeExecutor {
     /// `ProbeExecution` regardless of success or failure to ensure
     /// all execution attempts are captured.
     pub async fn execute(&self, context: ProbeExecutionContext) -> Result<ProbeExecution> {
+        if context.probe_type == ProbeType::Conversation {
+            return Ok(self.execute_conversation(context).await);
+        }
+        if context.probe_type == ProbeType::Load {
+            return Ok(execute_load(context).await);
+        }
+
         let start = Instant::now();
 
         // Get default config based on model type, then override with custom values if provided
-        let (default_url, default_payload) = Self::get_default_config(&context.model_type, &context.model_name, &context.endpoint_url);
+        let (default_url, default_payload) = match context.probe_type {
+            ProbeType::Http | ProbeType::Load => Self::get_default_config(&context.model_type, &context.model_name, &context.endpoint_url),
+            ProbeType::SemanticSimilarity => Self::get_default_config(&ModelType::Chat, &context.model_name, &context.endpoint_url),
+            ProbeType::Embeddings => Self::get_default_config(&ModelType::Embeddings, &context.model_name, &context.endpoint_url),
+            ProbeType::ToolCall => Self::get_tool_call_config(&context.model_name, &context.endpoint_url),
+            ProbeType::Conversation => unreachable!("conversation probes are executed turn by turn"),
+        };
 
         let full_url = context
             .request_path
@@ -85,7 +242,12 @@ impl ProbeExecutor {
             .map(|path| format!("{}{}", context.endpoint_url.trim_end_matches('/'), path))
             .unwrap_or(default_url);
 
-        let payload = context.request_body.clone().unwrap_or(default_payload);
+        let mut payload = context.request_body.clone().unwrap_or(default_payload);
+        // Stream the default chat completion, to time the first token
+        if context.probe_type == ProbeType::Http && context.model_type == ModelType::Chat && context.request_body.is_none() {
+            payload["stream"] = json!(true);
+            payload["stream_options"] = json!({"include_usage": true});
+        }
 
         // Build and send request with the configured HTTP method
         let mut request = match context.http_method.to_uppercase().as_str() {
@@ -100,6 +262,9 @@ impl ProbeExecutor {
         if let Some(api_key) = &context.api_key {
             request = request.header("Authorization", format!("Bearer {}", api_key));
         }
+        request = request.timeout(context.timeout);
+        // Probes must reach deployments whose circuit breaker is open, to notice when they recover
+        request = request.header(crate::routing::PROBE_HEADER, "true");
 
         let response = request.send().await;
         let elapsed = start.elapsed().as_millis() as i32;
@@ -109,6 +274,31 @@ impl ProbeExecutor {
             Ok(resp) => {
                 let status_code = resp.status().as_u16() as i32;
 
+                if is_event_stream(&resp) {
+                    return Ok(match read_stream(resp, start).await {
+                        Ok(streamed) => {
+                            let mut execution = self
+                                .check(&context, &payload, status_code, streamed.elapsed, streamed.response_data)
+                                .await;
+                            execution.time_to_first_token_ms = streamed.time_to_first_token_ms;
+                            execution.tokens_per_second = streamed.tokens_per_second;
+                            execution
+                        }
+                        Err(e) => ProbeExecution {
+                            probe_id: context.probe_id,
+                            success: false,
+                            response_time_ms: start.elapsed().as_millis() as i32,
+                            status_code: Some(status_code),
+                            error_message: Some(format!("HTTP {} - {}", status_code, e)),
+                            response_data: None,
+                            metadata: None,
+                            assertion_results: None,
+                            time_to_first_token_ms: None,
+                            tokens_per_second: None,
+                        },
+                    });
+                }
+
                 // Get response body as text first
                 let body_text = match resp.text().await {
                     Ok(text) => text,
@@ -121,50 +311,16 @@ impl ProbeExecutor {
                             error_message: Some(format!("HTTP {} - Failed to read response body: {}", status_code, e)),
                             response_data: None,
                             metadata: None,
+                            assertion_results: None,
+                            time_to_first_token_ms: None,
+                            tokens_per_second: None,
                         });
                     }
                 };
 
                 // Try to parse as JSON
                 match serde_json::from_str::<serde_json::Value>(&body_text) {
-                    Ok(response_data) => {
-                        // Check if the response contains an error, even if HTTP status is 200
-                        // Some OpenAI-compatible APIs (vLLM) return HTTP 200 with error details in the body
-                        let is_error_response = response_data.get("object").and_then(|o| o.as_str()) == Some("error")
-                            || response_data
-                                .get("code")
-                                .and_then(|c| c.as_i64())
-                                .map(|c| c >= 400)
-                                .unwrap_or(false);
-
-                        if (200..300).contains(&status_code) && !is_error_response {
-                            Ok(ProbeExecution {
-                                probe_id: context.probe_id,
-                                success: true,
-                                response_time_ms: elapsed,
-                                status_code: Some(status_code),
-                                error_message: None,
-                                response_data: Some(response_data),
-                                metadata: None,
-                            })
-                        } else {
-                            let error_msg = response_data
-                                .get("message")
-                                .or_else(|| response_data.get("error"))
-                                .and_then(|e| e.as_str())
-                                .unwrap_or("Unknown error");
-
-                            Ok(ProbeExecution {
-                                probe_id: context.probe_id,
-                                success: false,
-                                response_time_ms: elapsed,
-                                status_code: Some(status_code),
-                                error_message: Some(format!("HTTP {} - {}", status_code, error_msg)),
-                                response_data: Some(response_data),
-                                metadata: None,
-                            })
-                        }
-                    }
+                    Ok(response_data) => Ok(self.check(&context, &payload, status_code, elapsed, response_data).await),
                     Err(e) => Ok(ProbeExecution {
                         probe_id: context.probe_id,
                         success: false,
@@ -176,6 +332,9 @@ impl ProbeExecutor {
                         )),
                         response_data: None,
                         metadata: None,
+                        assertion_results: None,
+                        time_to_first_token_ms: None,
+                        tokens_per_second: None,
                     }),
                 }
             }
@@ -187,9 +346,553 @@ impl ProbeExecutor {
                 error_message: Some(e.to_string()),
                 response_data: None,
                 metadata: None,
+                assertion_results: None,
+                time_to_first_token_ms: None,
+                tokens_per_second: None,
             }),
         }
     }
+
+    /// Check a response that parsed as JSON against the probe's expectations and assertions
+    async fn check(
+        &self,
+        context: &ProbeExecutionContext,
+        payload: &serde_json::Value,
+        status_code: i32,
+        elapsed: i32,
+        response_data: serde_json::Value,
+    ) -> ProbeExecution {
+        let (metadata, error_message, assertion_results) = match response_error(status_code, &response_data) {
+            None => {
+                let (mut metadata, mut error_message) = check_response(context, payload, elapsed, &response_data);
+                if context.probe_type == ProbeType::SemanticSimilarity && error_message.is_none() {
+                    (metadata, error_message) = self.check_similarity(context, &response_data).await;
+                }
+                let assertion_results = check_assertions(&context.assertions, &response_data, elapsed, &mut error_message);
+                (metadata, error_message, assertion_results)
+            }
+            Some(error_message) => (None, Some(error_message), None),
+        };
+        ProbeExecution {
+            probe_id: context.probe_id,
+            success: error_message.is_none(),
+            response_time_ms: elapsed,
+            status_code: Some(status_code),
+            error_message,
+            response_data: Some(response_data),
+            metadata,
+            assertion_results,
+            time_to_first_token_ms: None,
+            tokens_per_second: None,
+        }
+    }
+
+    /// Play a conversation probe's turns, stopping at the first that fails
+    async fn execute_conversation(&self, context: ProbeExecutionContext) -> ProbeExecution {
+        let start = Instant::now();
+        let (default_url, default_payload) = Self::get_default_config(&ModelType::Chat, &context.model_name, &context.endpoint_url);
+        let url = context
+            .request_path
+            .as_ref()
+            .map(|path| format!("{}{}", context.endpoint_url.trim_end_matches('/'), path))
+            .unwrap_or(default_url);
+        let turns = context.conversation.clone().unwrap_or_else(Self::get_default_conversation);
+
+        let mut execution = ProbeExecution {
+            probe_id: context.probe_id,
+            success: false,
+            response_time_ms: 0,
+            status_code: None,
+            error_message: None,
+            response_data: None,
+            metadata: None,
+            assertion_results: None,
+            time_to_first_token_ms: None,
+            tokens_per_second: None,
+        };
+        let mut payload = context.request_body.clone().unwrap_or(default_payload);
+        let Some(fields) = payload.as_object_mut() else {
+            execution.error_message = Some("Request body of a conversation probe must be a JSON object".to_string());
+            return execution;
+        };
+
+        let mut messages = Vec::new();
+        for (index, turn) in turns.iter().enumerate() {
+            messages.push(json!({"role": "user", "content": turn.content}));
+            fields.insert("messages".to_string(), json!(messages));
+
+            let mut request = self.client.post(&url).timeout(context.timeout).json(&fields);
+            if let Some(api_key) = &context.api_key {
+                request = request.header("Authorization", format!("Bearer {}", api_key));
+            }
+            request = request.header(crate::routing::PROBE_HEADER, "true");
+
+            let reply = match request.send().await {
+                Ok(response) => {
+                    let status_code = response.status().as_u16() as i32;
+                    execution.status_code = Some(status_code);
+                    match response.json::<serde_json::Value>().await {
+                        Ok(response_data) => {
+                            let reply = match response_error(status_code, &response_data) {
+                                Some(error) => Err(error),
+                                None => check_reply(turn, &response_data),
+                            };
+                            execution.response_data = Some(response_data);
+                            reply
+                        }
+                        Err(e) => Err(format!("HTTP {} - Failed to parse response as JSON: {}", status_code, e)),
+                    }
+                }
+                Err(e) => Err(e.to_string()),
+            };
+            execution.response_time_ms = start.elapsed().as_millis() as i32;
+
+            match reply {
+                Ok(content) => messages.push(json!({"role": "assistant", "content": content})),
+                Err(e) => {
+                    execution.error_message = Some(format!("Turn {} of {}: {}", index + 1, turns.len(), e));
+                    return execution;
+                }
+            }
+        }
+
+        execution.metadata = Some(json!({"probe_type": "conversation", "turns": turns.len()}));
+        execution.error_message = context
+            .max_latency_ms
+            .filter(|&max| execution.response_time_ms > max)
+            .map(|max| format!("Conversation took {}ms, over the {}ms limit", execution.response_time_ms, max));
+        if let Some(response_data) = &execution.response_data {
+            execution.assertion_results = check_assertions(
+                &context.assertions,
+                response_data,
+                execution.response_time_ms,
+                &mut execution.error_message,
+            );
+        }
+        execution.success = execution.error_message.is_none();
+        execution
+    }
+
+    /// Compare the reply in a chat completion to the probe's golden answer. Returns the metadata to
+    /// record with the result and, if the reply isn't similar enough, why.
+    async fn check_similarity(
+        &self,
+        context: &ProbeExecutionContext,
+        response_data: &serde_json::Value,
+    ) -> (Option<serde_json::Value>, Option<String>) {
+        match self.similarity(context, response_data).await {
+            Ok(similarity) => {
+                let threshold = context.similarity_threshold.unwrap_or(DEFAULT_SIMILARITY_THRESHOLD);
+                let metadata = json!({"probe_type": "semantic_similarity", "similarity": similarity, "threshold": threshold});
+                let error = (similarity < threshold).then(|| {
+                    format!(
+                        "Reply has similarity {:.3} to the golden answer, below the {} threshold",
+                        similarity, threshold
+                    )
+                });
+                (Some(metadata), error)
+            }
+            Err(e) => (None, Some(e)),
+        }
+    }
+
+    /// Cosine similarity of the embeddings of a chat completion's reply and the golden answer
+    async fn similarity(&self, context: &ProbeExecutionContext, response_data: &serde_json::Value) -> Result<f64, String> {
+        let reply = response_data
+            .pointer("/choices/0/message/content")
+            .and_then(|content| content.as_str())
+            .ok_or("Response has no reply")?;
+        let golden_answer = context.golden_answer.as_deref().ok_or("Probe has no golden answer")?;
+        let embeddings_model = context.embeddings_model.as_deref().ok_or("Probe has no embeddings deployment")?;
+
+        let (url, _) = Self::get_default_config(&ModelType::Embeddings, embeddings_model, &context.endpoint_url);
+        let mut request = self
+            .client
+            .post(&url)
+            .timeout(context.timeout)
+            .json(&json!({"model": embeddings_model, "input": [reply, golden_answer]}));
+        if let Some(api_key) = &context.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        request = request.header(crate::routing::PROBE_HEADER, "true");
+
+        let response = request.send().await.map_err(|e| format!("Embeddings request failed: {}", e))?;
+        let status_code = response.status().as_u16() as i32;
+        let embeddings = response
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| format!("Failed to parse embeddings response as JSON: {}", e))?;
+        if let Some(error) = response_error(status_code, &embeddings) {
+            return Err(format!("Embeddings request failed: {}", error));
+        }
+
+        let (vectors, _) = embedding_shape(&embeddings)?;
+        if vectors != 2 {
+            return Err(format!("Expected 2 embeddings, got {}", vectors));
+        }
+        let vector = |index: usize| -> Vec<f64> {
+            embeddings["data"][index]["embedding"]
+                .as_array()
+                .map(|vector| vector.iter().filter_map(|value| value.as_f64()).collect())
+                .unwrap_or_default()
+        };
+        cosine_similarity(&vector(0), &vector(1)).ok_or_else(|| "Embeddings have no direction".to_string())
+    }
+}
+
+/// Cosine similarity of two vectors of the same length, if neither is all zeros
+fn cosine_similarity(a: &[f64], b: &[f64]) -> Option<f64> {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |vector: &[f64]| vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let norms = norm(a) * norm(b);
+    (norms > 0.0).then(|| dot / norms)
+}
+
+/// Run a load probe through the load test runner and summarize its report. The response time
+/// recorded is the median latency of the successful requests.
+async fn execute_load(context: ProbeExecutionContext) -> ProbeExecution {
+    let mut execution = ProbeExecution {
+        probe_id: context.probe_id,
+        success: false,
+        response_time_ms: 0,
+        status_code: None,
+        error_message: None,
+        response_data: None,
+        metadata: None,
+        assertion_results: None,
+        time_to_first_token_ms: None,
+        tokens_per_second: None,
+    };
+    let Some(load) = context.load else {
+        execution.error_message = Some("Load probe has no load settings".to_string());
+        return execution;
+    };
+
+    let (default_url, default_payload) = ProbeExecutor::get_default_config(&context.model_type, &context.model_name, &context.endpoint_url);
+    let plan = LoadTestPlan {
+        url: context
+            .request_path
+            .as_ref()
+            .map(|path| format!("{}{}", context.endpoint_url.trim_end_matches('/'), path))
+            .unwrap_or(default_url),
+        api_key: context.api_key.unwrap_or_default(),
+        body: context.request_body.unwrap_or(default_payload),
+        total_requests: load.max_requests,
+        concurrency: load.concurrency,
+        requests_per_second: load.max_requests_per_second,
+        max_duration: load.duration,
+        max_error_rate: load.max_error_rate,
+    };
+    let (outcome, report) = runner::run_untracked(&plan).await;
+
+    let latency = report.latency_ms.as_ref();
+    execution.response_time_ms = latency.map(|latency| latency.p50 as i32).unwrap_or_default();
+    execution.error_message = match &outcome {
+        Outcome::ErrorRateExceeded(_) => outcome.stop_reason(&plan),
+        _ if report.successful_requests == 0 => Some(format!("None of the {} requests succeeded", report.completed_requests)),
+        _ => context
+            .max_latency_ms
+            .zip(latency)
+            .filter(|(max, latency)| latency.p95 > *max as u64)
+            .map(|(max, latency)| format!("95th percentile latency was {}ms, over the {}ms limit", latency.p95, max)),
+    };
+    execution.success = execution.error_message.is_none();
+    execution.metadata = Some(json!({
+        "probe_type": "load",
+        "concurrency": load.concurrency,
+        "duration_seconds": load.duration.as_secs(),
+        "report": report,
+    }));
+    execution
+}
+
+/// Whether a response is a stream of server-sent events, as streamed chat completions are
+fn is_event_stream(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/event-stream"))
+}
+
+/// A chat completion read from a stream
+struct StreamedCompletion {
+    /// The chunks merged into a non-streamed completion, so the usual checks and assertions apply
+    response_data: serde_json::Value,
+    /// Milliseconds from sending the request to the end of the stream
+    elapsed: i32,
+    /// Milliseconds from sending the request to the first token
+    time_to_first_token_ms: Option<i32>,
+    /// Tokens per second after the first
+    tokens_per_second: Option<f64>,
+}
+
+/// Read a streamed chat completion, timing its tokens. The number of tokens is taken from the usage
+/// chunk if the upstream sends one, and is otherwise the number of chunks carrying content.
+async fn read_stream(mut response: reqwest::Response, start: Instant) -> Result<StreamedCompletion, String> {
+    let mut buffer = Vec::new();
+    let mut first_token = None;
+    let mut content_chunks = 0;
+    let mut content = String::new();
+    let mut completion = json!({"object": "chat.completion"});
+    let mut finish_reason = serde_json::Value::Null;
+
+    while let Some(bytes) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read response stream: {}", e))?
+    {
+        buffer.extend_from_slice(&bytes);
+        while let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                continue;
+            };
+            if data == "[DONE]" {
+                continue;
+            }
+            let event: serde_json::Value =
+                serde_json::from_str(data).map_err(|e| format!("Failed to parse stream event as JSON: {}. Event: {}", e, data))?;
+            if let Some(error) = event.get("error") {
+                return Err(format!("Stream failed: {}", error));
+            }
+
+            for field in ["id", "model", "created"] {
+                if let Some(value) = event.get(field) {
+                    completion[field] = value.clone();
+                }
+            }
+            if let Some(usage) = event.get("usage").filter(|usage| !usage.is_null()) {
+                completion["usage"] = usage.clone();
+            }
+            let Some(choice) = event.pointer("/choices/0") else {
+                continue;
+            };
+            // Reasoning models stream their reasoning before the reply; either is a first token
+            let has_token = ["content", "reasoning_content"]
+                .iter()
+                .any(|field| choice["delta"][field].as_str().is_some_and(|text| !text.is_empty()));
+            if has_token {
+                first_token.get_or_insert_with(|| start.elapsed());
+                content_chunks += 1;
+            }
+            if let Some(text) = choice.pointer("/delta/content").and_then(|text| text.as_str()) {
+                content.push_str(text);
+            }
+            if let Some(reason) = choice.get("finish_reason").filter(|reason| !reason.is_null()) {
+                finish_reason = reason.clone();
+            }
+        }
+    }
+
+    let elapsed = start.elapsed();
+    completion["choices"] = json!([{
+        "index": 0,
+        "message": {"role": "assistant", "content": content},
+        "finish_reason": finish_reason,
+    }]);
+    let tokens = completion
+        .pointer("/usage/completion_tokens")
+        .and_then(|tokens| tokens.as_u64())
+        .unwrap_or(content_chunks);
+    let tokens_per_second = first_token.and_then(|first_token| {
+        let generating = elapsed.saturating_sub(first_token).as_secs_f64();
+        (tokens > 1 && generating > 0.0).then(|| (tokens - 1) as f64 / generating)
+    });
+
+    Ok(StreamedCompletion {
+        response_data: completion,
+        elapsed: elapsed.as_millis() as i32,
+        time_to_first_token_ms: first_token.map(|first_token| first_token.as_millis() as i32),
+        tokens_per_second,
+    })
+}
+
+/// Why a response is an error, if it is. Some OpenAI-compatible APIs (vLLM) return HTTP 200 with
+/// error details in the body, so the body is checked as well as the status.
+fn response_error(status_code: i32, response_data: &serde_json::Value) -> Option<String> {
+    let is_error_response = response_data.get("object").and_then(|o| o.as_str()) == Some("error")
+        || response_data
+            .get("code")
+            .and_then(|c| c.as_i64())
+            .map(|c| c >= 400)
+            .unwrap_or(false);
+    if (200..300).contains(&status_code) && !is_error_response {
+        return None;
+    }
+
+    let error_msg = response_data
+        .get("message")
+        .or_else(|| response_data.get("error"))
+        .and_then(|e| e.as_str())
+        .unwrap_or("Unknown error");
+    Some(format!("HTTP {} - {}", status_code, error_msg))
+}
+
+/// The assistant's reply in a chat completion, checking it contains what the turn expects
+fn check_reply(turn: &ConversationTurn, response_data: &serde_json::Value) -> Result<String, String> {
+    let content = response_data
+        .pointer("/choices/0/message/content")
+        .and_then(|content| content.as_str())
+        .ok_or("Response has no reply")?;
+
+    let reply = content.to_lowercase();
+    if let Some(missing) = turn
+        .expect_contains
+        .iter()
+        .find(|expected| !reply.contains(&expected.to_lowercase()))
+    {
+        return Err(format!("Reply does not contain '{}': {}", missing, content));
+    }
+    Ok(content.to_string())
+}
+
+/// Check a successful response against the probe's expectations. Returns the metadata to record
+/// with the result and, if a check failed, why.
+fn check_response(
+    context: &ProbeExecutionContext,
+    payload: &serde_json::Value,
+    elapsed: i32,
+    response_data: &serde_json::Value,
+) -> (Option<serde_json::Value>, Option<String>) {
+    let mut metadata = None;
+    let mut error = None;
+
+    match context.probe_type {
+        ProbeType::Http => {}
+        ProbeType::Embeddings => match embedding_shape(response_data) {
+            Ok((vectors, dimensions)) => {
+                metadata = Some(json!({"probe_type": "embeddings", "vectors": vectors, "dimensions": dimensions}));
+                if let Some(expected) = context.expected_dimensions.filter(|&expected| expected as usize != dimensions) {
+                    error = Some(format!("Expected {} dimensions, got {}", expected, dimensions));
+                }
+            }
+            Err(e) => error = Some(e),
+        },
+        ProbeType::ToolCall => match called_tools(payload, response_data) {
+            Ok(tools) => metadata = Some(json!({"probe_type": "tool_call", "tools": tools})),
+            Err(e) => error = Some(e),
+        },
+        // Checked turn by turn in `execute_conversation`
+        ProbeType::Conversation => {}
+        // Compared in `check_similarity`, which needs another request
+        ProbeType::SemanticSimilarity => {}
+        // Summarized in `execute_load`
+        ProbeType::Load => {}
+    }
+
+    if error.is_none() {
+        if let Some(max_latency_ms) = context.max_latency_ms.filter(|&max| elapsed > max) {
+            error = Some(format!("Response took {}ms, over the {}ms limit", elapsed, max_latency_ms));
+        }
+    }
+
+    (metadata, error)
+}
+
+/// Evaluate a probe's assertions against a response that passed its built-in checks, recording why
+/// the response failed if any assertion didn't hold
+fn check_assertions(
+    probe_assertions: &[ProbeAssertion],
+    response_data: &serde_json::Value,
+    elapsed: i32,
+    error: &mut Option<String>,
+) -> Option<Vec<AssertionResult>> {
+    if error.is_some() || probe_assertions.is_empty() {
+        return None;
+    }
+    let results = assertions::evaluate(probe_assertions, response_data, elapsed.max(0) as u64);
+    *error = assertions::failure_message(&results);
+    Some(results)
+}
+
+/// The number of vectors in an embeddings response and their dimensionality
+fn embedding_shape(response_data: &serde_json::Value) -> Result<(usize, usize), String> {
+    let data = response_data
+        .get("data")
+        .and_then(|data| data.as_array())
+        .filter(|data| !data.is_empty())
+        .ok_or("Response has no embeddings")?;
+
+    let mut dimensions = None;
+    for (index, item) in data.iter().enumerate() {
+        let vector = item
+            .get("embedding")
+            .and_then(|embedding| embedding.as_array())
+            .filter(|vector| !vector.is_empty() && vector.iter().all(|value| value.is_number()))
+            .ok_or_else(|| format!("Embedding {} is not a non-empty numeric vector", index))?;
+        match dimensions {
+            None => dimensions = Some(vector.len()),
+            Some(dimensions) if dimensions != vector.len() => {
+                return Err(format!(
+                    "Embedding {} has {} dimensions, but embedding 0 has {}",
+                    index,
+                    vector.len(),
+                    dimensions
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok((data.len(), dimensions.unwrap_or_default()))
+}
+
+/// The names of the tools called in a chat completion, checking that each call is to a tool offered
+/// in the request and has a JSON object of arguments with all of the tool's required parameters
+fn called_tools(payload: &serde_json::Value, response_data: &serde_json::Value) -> Result<Vec<String>, String> {
+    let calls = response_data
+        .pointer("/choices/0/message/tool_calls")
+        .and_then(|calls| calls.as_array())
+        .filter(|calls| !calls.is_empty())
+        .ok_or("Response has no tool calls")?;
+    let offered = payload
+        .get("tools")
+        .and_then(|tools| tools.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    calls
+        .iter()
+        .enumerate()
+        .map(|(index, call)| {
+            let name = call
+                .pointer("/function/name")
+                .and_then(|name| name.as_str())
+                .ok_or_else(|| format!("Tool call {} has no function name", index))?;
+            let tool = offered
+                .iter()
+                .find(|tool| tool.pointer("/function/name").and_then(|name| name.as_str()) == Some(name))
+                .ok_or_else(|| format!("Tool call {} is to unknown tool {}", index, name))?;
+
+            // Arguments are a JSON-encoded string in OpenAI-compatible responses
+            let arguments = match call.pointer("/function/arguments") {
+                Some(serde_json::Value::String(arguments)) => serde_json::from_str(arguments)
+                    .map_err(|e| format!("Tool call {} has arguments that are not valid JSON: {}", index, e))?,
+                Some(arguments) => arguments.clone(),
+                None => serde_json::Value::Null,
+            };
+            let arguments = arguments
+                .as_object()
+                .ok_or_else(|| format!("Tool call {} has arguments that are not a JSON object", index))?;
+
+            let required = tool
+                .pointer("/function/parameters/required")
+                .and_then(|required| required.as_array())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            if let Some(missing) = required
+                .iter()
+                .filter_map(|parameter| parameter.as_str())
+                .find(|parameter| !arguments.contains_key(*parameter))
+            {
+                return Err(format!("Tool call {} is missing required argument {}", index, missing));
+            }
+
+            Ok(name.to_string())
+        })
+        .collect()
 }
 
 impl Default for ProbeExecutor {
@@ -197,3 +900,438 @@ impl Default for ProbeExecutor {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use futures::StreamExt;
+    use serde_json::Value;
+
+    /// An upstream whose embeddings endpoint returns `response`
+    async fn mock_upstream(response: Value) -> String {
+        let app = Router::new().route(
+            "/v1/embeddings",
+            post(move |Json(body): Json<Value>| async move {
+                assert_eq!(body["model"], "embedder");
+                Json(response)
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    fn context(endpoint_url: String, expected_dimensions: Option<i32>, max_latency_ms: Option<i32>) -> ProbeExecutionContext {
+        ProbeExecutionContext {
+            probe_id: Uuid::new_v4(),
+            model_name: "embedder".to_string(),
+            // Embeddings probes call /v1/embeddings whatever the deployment's model type
+            model_type: ModelType::Chat,
+            endpoint_url,
+            api_key: None,
+            http_method: "POST".to_string(),
+            request_path: None,
+            request_body: None,
+            probe_type: ProbeType::Embeddings,
+            expected_dimensions,
+            max_latency_ms,
+            conversation: None,
+            assertions: Vec::new(),
+            golden_answer: None,
+            embeddings_model: None,
+            similarity_threshold: None,
+            timeout: Duration::from_secs(10),
+            load: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_probe() {
+        let url =
+            mock_upstream(json!({"object": "list", "data": [{"object": "embedding", "index": 0, "embedding": [0.1, -0.2, 0.3]}]})).await;
+        let executor = ProbeExecutor::new();
+
+        let execution = executor.execute(context(url.clone(), Some(3), Some(10_000))).await.unwrap();
+        assert!(execution.success, "{:?}", execution.error_message);
+        assert_eq!(
+            execution.metadata,
+            Some(json!({"probe_type": "embeddings", "vectors": 1, "dimensions": 3}))
+        );
+
+        let execution = executor.execute(context(url, Some(1024), None)).await.unwrap();
+        assert!(!execution.success);
+        assert_eq!(execution.error_message.as_deref(), Some("Expected 1024 dimensions, got 3"));
+        assert_eq!(execution.metadata.unwrap()["dimensions"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_probe_rejects_malformed_vectors() {
+        let url = mock_upstream(json!({"data": [{"embedding": [0.1, 0.2]}, {"embedding": [0.1]}]})).await;
+        let execution = ProbeExecutor::new().execute(context(url, None, None)).await.unwrap();
+        assert!(!execution.success);
+        assert_eq!(
+            execution.error_message.as_deref(),
+            Some("Embedding 1 has 1 dimensions, but embedding 0 has 2")
+        );
+
+        assert!(embedding_shape(&json!({"data": []})).is_err());
+        assert!(embedding_shape(&json!({"data": [{"embedding": "base64=="}]})).is_err());
+        assert!(embedding_shape(&json!({"data": [{"embedding": ["a"]}]})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_assertions() {
+        let url = mock_upstream(json!({"data": [{"embedding": [0.1, 0.2]}]})).await;
+        let mut assertions_context = context(url, Some(2), None);
+        assertions_context.assertions = vec![
+            ProbeAssertion::JsonPathExists {
+                path: "$.data[0].embedding".to_string(),
+            },
+            ProbeAssertion::JsonPathExists {
+                path: "$.usage".to_string(),
+            },
+        ];
+        let execution = ProbeExecutor::new().execute(assertions_context).await.unwrap();
+        assert!(!execution.success);
+        assert_eq!(
+            execution.error_message.as_deref(),
+            Some("1 of 2 assertions failed: Response has nothing at $.usage")
+        );
+        let results = execution.assertion_results.unwrap();
+        assert!(results[0].passed);
+        assert!(!results[1].passed);
+
+        // Assertions aren't evaluated for responses that fail the built-in checks
+        let mut error = Some("Expected 3 dimensions, got 2".to_string());
+        let results = check_assertions(&[ProbeAssertion::MaxLatencyMs { max_ms: 1 }], &json!({}), 10, &mut error);
+        assert!(results.is_none());
+        assert_eq!(error.as_deref(), Some("Expected 3 dimensions, got 2"));
+    }
+
+    #[test]
+    fn test_latency_limit() {
+        let mut context = context(String::new(), None, Some(100));
+        context.probe_type = ProbeType::Http;
+        let response = json!({"choices": []});
+
+        assert_eq!(check_response(&context, &json!({}), 50, &response), (None, None));
+        assert_eq!(
+            check_response(&context, &json!({}), 250, &response).1.as_deref(),
+            Some("Response took 250ms, over the 100ms limit")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let app = Router::new().route(
+            "/v1/embeddings",
+            post(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Json(json!({"data": []}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut context = context(format!("http://{addr}"), None, None);
+        context.timeout = Duration::from_millis(100);
+        let execution = ProbeExecutor::new().execute(context).await.unwrap();
+        assert!(!execution.success);
+        assert!(execution.status_code.is_none());
+        assert!(execution.response_time_ms < 5000);
+    }
+
+    /// An upstream whose chat completions endpoint answers with `tool_calls`
+    async fn mock_tool_calling_upstream(tool_calls: Value) -> String {
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move |Json(body): Json<Value>| async move {
+                assert_eq!(body["tools"][0]["function"]["name"], "get_weather");
+                Json(json!({
+                    "object": "chat.completion",
+                    "choices": [{"index": 0, "message": {"role": "assistant", "content": null, "tool_calls": tool_calls}, "finish_reason": "tool_calls"}]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    fn tool_call(name: &str, arguments: &str) -> Value {
+        json!({"id": "call_1", "type": "function", "function": {"name": name, "arguments": arguments}})
+    }
+
+    async fn execute_tool_call_probe(tool_calls: Value) -> ProbeExecution {
+        let mut context = context(mock_tool_calling_upstream(tool_calls).await, None, None);
+        context.probe_type = ProbeType::ToolCall;
+        ProbeExecutor::new().execute(context).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_probe() {
+        let execution = execute_tool_call_probe(json!([tool_call("get_weather", r#"{"city": "Paris"}"#)])).await;
+        assert!(execution.success, "{:?}", execution.error_message);
+        assert_eq!(
+            execution.metadata,
+            Some(json!({"probe_type": "tool_call", "tools": ["get_weather"]}))
+        );
+
+        let failures = [
+            (json!(null), "Response has no tool calls"),
+            (json!([]), "Response has no tool calls"),
+            (json!([tool_call("get_time", "{}")]), "Tool call 0 is to unknown tool get_time"),
+            (
+                json!([tool_call("get_weather", r#"{"city": "Par"#)]),
+                "Tool call 0 has arguments that are not valid JSON",
+            ),
+            (
+                json!([tool_call("get_weather", r#""Paris""#)]),
+                "Tool call 0 has arguments that are not a JSON object",
+            ),
+            (
+                json!([tool_call("get_weather", r#"{"town": "Paris"}"#)]),
+                "Tool call 0 is missing required argument city",
+            ),
+        ];
+        for (tool_calls, error) in failures {
+            let execution = execute_tool_call_probe(tool_calls).await;
+            assert!(!execution.success);
+            let message = execution.error_message.unwrap();
+            assert!(message.starts_with(error), "{message}");
+        }
+    }
+
+    /// An upstream that replies with the code word once it has been told it, unless it only
+    /// looks at the latest message
+    async fn mock_chat_upstream(keeps_context: bool) -> String {
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move |Json(body): Json<Value>| async move {
+                let messages = body["messages"].as_array().unwrap().clone();
+                let seen = if keeps_context {
+                    &messages[..]
+                } else {
+                    &messages[messages.len() - 1..]
+                };
+                let told = seen
+                    .iter()
+                    .any(|message| message["content"].as_str().unwrap().contains("pineapple"));
+                let reply = if told { "Pineapple" } else { "I don't know" };
+                Json(json!({"object": "chat.completion", "choices": [{"index": 0, "message": {"role": "assistant", "content": reply}}]}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_conversation_probe() {
+        let mut conversation_context = context(mock_chat_upstream(true).await, None, None);
+        conversation_context.probe_type = ProbeType::Conversation;
+        let execution = ProbeExecutor::new().execute(conversation_context).await.unwrap();
+        assert!(execution.success, "{:?}", execution.error_message);
+        assert_eq!(execution.metadata, Some(json!({"probe_type": "conversation", "turns": 2})));
+        assert_eq!(execution.response_data.unwrap()["choices"][0]["message"]["content"], "Pineapple");
+
+        // An upstream that drops the history answers the first turn but not the second
+        let mut conversation_context = context(mock_chat_upstream(false).await, None, None);
+        conversation_context.probe_type = ProbeType::Conversation;
+        let execution = ProbeExecutor::new().execute(conversation_context).await.unwrap();
+        assert!(!execution.success);
+        assert_eq!(
+            execution.error_message.as_deref(),
+            Some("Turn 2 of 2: Reply does not contain 'pineapple': I don't know")
+        );
+    }
+
+    /// An upstream that answers every chat completion with a fact about Paris, and embeds text
+    /// mentioning Paris and other text as orthogonal vectors
+    async fn mock_similarity_upstream() -> String {
+        let app = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|| async {
+                    Json(json!({"choices": [{"index": 0, "message": {"role": "assistant", "content": "Paris is the capital of France."}}]}))
+                }),
+            )
+            .route(
+                "/v1/embeddings",
+                post(|Json(body): Json<Value>| async move {
+                    assert_eq!(body["model"], "embedder");
+                    let data: Vec<Value> = body["input"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .enumerate()
+                        .map(|(index, input)| {
+                            let embedding = if input.as_str().unwrap().contains("Paris") {
+                                [1.0, 0.0]
+                            } else {
+                                [0.0, 1.0]
+                            };
+                            json!({"index": index, "embedding": embedding})
+                        })
+                        .collect();
+                    Json(json!({"data": data}))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_semantic_similarity_probe() {
+        let url = mock_similarity_upstream().await;
+        let similarity_context = |golden_answer: &str| {
+            let mut similarity_context = context(url.clone(), None, None);
+            similarity_context.model_name = "chat".to_string();
+            similarity_context.probe_type = ProbeType::SemanticSimilarity;
+            similarity_context.request_body =
+                Some(json!({"model": "chat", "messages": [{"role": "user", "content": "Capital of France?"}]}));
+            similarity_context.golden_answer = Some(golden_answer.to_string());
+            similarity_context.embeddings_model = Some("embedder".to_string());
+            similarity_context
+        };
+
+        let execution = ProbeExecutor::new()
+            .execute(similarity_context("The capital of France is Paris"))
+            .await
+            .unwrap();
+        assert!(execution.success, "{:?}", execution.error_message);
+        assert_eq!(
+            execution.metadata,
+            Some(json!({"probe_type": "semantic_similarity", "similarity": 1.0, "threshold": DEFAULT_SIMILARITY_THRESHOLD}))
+        );
+
+        let execution = ProbeExecutor::new().execute(similarity_context("Lyon")).await.unwrap();
+        assert!(!execution.success);
+        assert_eq!(
+            execution.error_message.as_deref(),
+            Some("Reply has similarity 0.000 to the golden answer, below the 0.8 threshold")
+        );
+
+        assert_eq!(
+            cosine_similarity(&[1.0, 1.0], &[1.0, 0.0]).map(|s| (s * 1000.0).round()),
+            Some(707.0)
+        );
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), None);
+    }
+
+    /// An upstream that streams a three token chat completion, the first token after 100ms and
+    /// the others 50ms apart
+    async fn mock_streaming_upstream() -> String {
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(|Json(body): Json<Value>| async move {
+                assert_eq!(body["stream"], true);
+                let chunk = |delta: Value, finish_reason: Value| {
+                    json!({"id": "chatcmpl-1", "model": "chat", "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}]})
+                };
+                let events = vec![
+                    (0, chunk(json!({"role": "assistant"}), Value::Null).to_string()),
+                    (100, chunk(json!({"content": "Hello"}), Value::Null).to_string()),
+                    (50, chunk(json!({"content": " there"}), Value::Null).to_string()),
+                    (50, chunk(json!({"content": " friend"}), json!("stop")).to_string()),
+                    (0, json!({"id": "chatcmpl-1", "choices": [], "usage": {"prompt_tokens": 5, "completion_tokens": 3}}).to_string()),
+                    (0, "[DONE]".to_string()),
+                ];
+                let stream = futures::stream::iter(events).then(|(delay, event)| async move {
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    Ok::<_, std::convert::Infallible>(format!("data: {event}\n\n"))
+                });
+                (
+                    [(axum::http::header::CONTENT_TYPE, "text/event-stream")],
+                    axum::body::Body::from_stream(stream),
+                )
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_streamed_chat_probe() {
+        let mut streaming_context = context(mock_streaming_upstream().await, None, None);
+        streaming_context.model_name = "chat".to_string();
+        streaming_context.probe_type = ProbeType::Http;
+        streaming_context.assertions = vec![ProbeAssertion::Regex {
+            pattern: "^Hello there".to_string(),
+        }];
+
+        let execution = ProbeExecutor::new().execute(streaming_context).await.unwrap();
+        assert!(execution.success, "{:?}", execution.error_message);
+        let response_data = execution.response_data.unwrap();
+        assert_eq!(response_data["choices"][0]["message"]["content"], "Hello there friend");
+        assert_eq!(response_data["choices"][0]["finish_reason"], "stop");
+        assert_eq!(response_data["usage"]["completion_tokens"], 3);
+
+        let time_to_first_token_ms = execution.time_to_first_token_ms.unwrap();
+        assert!(time_to_first_token_ms >= 100, "{time_to_first_token_ms}");
+        assert!(execution.response_time_ms >= time_to_first_token_ms + 100);
+        // Two tokens followed the first over about 100ms
+        let tokens_per_second = execution.tokens_per_second.unwrap();
+        assert!(tokens_per_second > 5.0 && tokens_per_second <= 20.0, "{tokens_per_second}");
+    }
+
+    #[tokio::test]
+    async fn test_load_probe() {
+        let load_context = |url: String, max_latency_ms: Option<i32>| {
+            let mut load_context = context(url, None, max_latency_ms);
+            load_context.probe_type = ProbeType::Load;
+            load_context.load = Some(LoadSettings {
+                concurrency: 4,
+                duration: Duration::from_secs(5),
+                max_requests: 20,
+                max_requests_per_second: 100,
+                max_error_rate: 0.5,
+            });
+            load_context
+        };
+
+        let execution = ProbeExecutor::new()
+            .execute(load_context(mock_chat_upstream(true).await, None))
+            .await
+            .unwrap();
+        assert!(execution.success, "{:?}", execution.error_message);
+        let metadata = execution.metadata.unwrap();
+        assert_eq!(metadata["probe_type"], "load");
+        assert_eq!(metadata["concurrency"], 4);
+        assert_eq!(metadata["report"]["successful_requests"], 20);
+        assert!(metadata["report"]["latency_ms"]["p95"].is_u64());
+
+        // Every request fails against an upstream without a chat completions endpoint
+        let execution = ProbeExecutor::new()
+            .execute(load_context(mock_upstream(json!({})).await, None))
+            .await
+            .unwrap();
+        assert!(!execution.success);
+        assert!(
+            execution.error_message.as_deref().unwrap().starts_with("Error rate of 100%"),
+            "{:?}",
+            execution.error_message
+        );
+
+        let limits = LoadTestingConfig {
+            enabled: true,
+            max_concurrency: 8,
+            max_duration: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let settings = LoadSettings::new(None, None, &limits).unwrap();
+        assert_eq!(settings.concurrency, DEFAULT_LOAD_CONCURRENCY as u32);
+        assert!(LoadSettings::new(Some(9), None, &limits).is_err());
+        assert!(LoadSettings::new(None, Some(61), &limits).is_err());
+        assert!(LoadSettings::new(None, None, &LoadTestingConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Database access layer for maintenance windows.
+//!
+//! Windows are only read when a probe result is stored: the result is marked `in_maintenance` if
+//! a window of its deployment, or of the endpoint hosting it, covers that moment. Marking results
+//! rather than looking windows up later keeps SLO accounting stable if a window is edited or
+//! deleted afterwards.
+
+use crate::api::models::maintenance_windows::MaintenanceWindowsQuery;
+use crate::db::models::maintenance_windows::MaintenanceWindow;
+use crate::errors::Error as AppError;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Everything needed to schedule a maintenance window
+pub struct NewMaintenanceWindow {
+    pub name: String,
+    pub endpoint_id: Option<Uuid>,
+    pub deployment_id: Option<Uuid>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub reason: Option<String>,
+    pub created_by: Uuid,
+}
+
+/// Changes to a maintenance window; `None` fields are left unchanged
+#[derive(Default)]
+pub struct MaintenanceWindowChanges {
+    pub name: Option<String>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+/// Database access layer for maintenance windows.
+pub struct MaintenanceManager;
+
+impl MaintenanceManager {
+    pub async fn create(pool: &PgPool, window: NewMaintenanceWindow) -> Result<MaintenanceWindow, AppError> {
+        let (resource, id, exists) = match (window.endpoint_id, window.deployment_id) {
+            (Some(endpoint_id), _) => (
+                "Endpoint",
+                endpoint_id,
+                sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM inference_endpoints WHERE id = $1)", endpoint_id)
+                    .fetch_one(pool)
+                    .await,
+            ),
+            (None, Some(deployment_id)) => (
+                "Deployment",
+                deployment_id,
+                sqlx::query_scalar!(
+                    "SELECT EXISTS(SELECT 1 FROM deployed_models WHERE id = $1 AND deleted = false)",
+                    deployment_id
+                )
+                .fetch_one(pool)
+                .await,
+            ),
+            (None, None) => {
+                return Err(AppError::BadRequest {
+                    message: "A maintenance window needs an endpoint_id or a deployment_id".to_string(),
+                });
+            }
+        };
+        let exists = exists.map_err(|e| anyhow::anyhow!("Failed to check maintenance window target: {}", e))?;
+        if exists != Some(true) {
+            return Err(AppError::NotFound {
+                resource: resource.to_string(),
+                id: id.to_string(),
+            });
+        }
+
+        let result = sqlx::query_as::<_, MaintenanceWindow>(
+            r#"
+            INSERT INTO maintenance_windows (name, endpoint_id, deployment_id, starts_at, ends_at, reason, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(&window.name)
+        .bind(window.endpoint_id)
+        .bind(window.deployment_id)
+        .bind(window.starts_at)
+        .bind(window.ends_at)
+        .bind(&window.reason)
+        .bind(window.created_by)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create maintenance window: {}", e))?;
+
+        Ok(result)
+    }
+
+    pub async fn get(pool: &PgPool, id: Uuid) -> Result<MaintenanceWindow, AppError> {
+        let window = sqlx::query_as::<_, MaintenanceWindow>("SELECT * FROM maintenance_windows WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch maintenance window: {}", e))?
+            .ok_or_else(|| AppError::NotFound {
+                resource: "Maintenance window".to_string(),
+                id: id.to_string(),
+            })?;
+
+        Ok(window)
+    }
+
+    /// List maintenance windows, soonest first
+    pub async fn list(pool: &PgPool, query: &MaintenanceWindowsQuery) -> Result<Vec<MaintenanceWindow>, AppError> {
+        let windows = sqlx::query_as::<_, MaintenanceWindow>(
+            r#"
+            SELECT * FROM maintenance_windows
+            WHERE ($1::uuid IS NULL OR endpoint_id = $1)
+              AND ($2::uuid IS NULL OR deployment_id = $2)
+              AND (NOT $3 OR ends_at > NOW())
+            ORDER BY starts_at
+            "#,
+        )
+        .bind(query.endpoint_id)
+        .bind(query.deployment_id)
+        .bind(query.upcoming)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to list maintenance windows: {}", e))?;
+
+        Ok(windows)
+    }
+
+    pub async fn update(pool: &PgPool, id: Uuid, changes: MaintenanceWindowChanges) -> Result<MaintenanceWindow, AppError> {
+        let window = sqlx::query_as::<_, MaintenanceWindow>(
+            r#"
+            UPDATE maintenance_windows SET
+                name = COALESCE($2, name),
+                starts_at = COALESCE($3, starts_at),
+                ends_at = COALESCE($4, ends_at),
+                reason = COALESCE($5, reason),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(&changes.name)
+        .bind(changes.starts_at)
+        .bind(changes.ends_at)
+        .bind(&changes.reason)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to update maintenance window: {}", e))?
+        .ok_or_else(|| AppError::NotFound {
+            resource: "Maintenance window".to_string(),
+            id: id.to_string(),
+        })?;
+
+        Ok(window)
+    }
+
+    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM maintenance_windows WHERE id = $1", id)
+            .execute(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to delete maintenance window: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                resource: "Maintenance window".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
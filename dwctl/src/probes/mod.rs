@@ -1,5 +1,9 @@
+pub mod alerts;
+pub mod assertions;
+pub mod channels;
 pub mod db;
 pub mod executor;
+pub mod maintenance;
 pub mod scheduler;
 
 pub use scheduler::ProbeScheduler;
@@ -3,12 +3,30 @@
 //! This module provides the `ProbeScheduler` which runs as a background daemon
 //! on the leader replica. It periodically polls the database for active probes
 //! and manages background tasks that execute each probe at its configured interval.
+//!
+//! To keep probes from hitting upstreams in synchronized bursts, each execution starts after a
+//! random delay of up to the probe's jitter (`probes.jitter` unless the probe sets its own), and
+//! all probes share a limit on executions in flight (`probes.max_concurrent_executions`). Each
+//! probe runs its own executions one at a time, so the limit is global rather than per probe.
+//!
+//! Probes keep running through maintenance windows (see [`crate::probes::maintenance`]), so their
+//! history has no gaps; results taken during one are marked and don't alert or count against SLOs.
+//!
+//! Scheduled executions are also exported as Prometheus metrics, by probe: outcomes, response
+//! times and, for streamed completions, the time to the first token and the rate of the rest.
+//! The number of probes scheduled and of executions queued for the shared limit show whether the
+//! scheduler is keeping up.
 
+use crate::db::models::probes::ProbeResult;
+use crate::leader::LeaderFence;
 use crate::probes::db::ProbeManager;
+use prometheus::{GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry};
+use rand::Rng;
 use sqlx::PgPool;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{RwLock, Semaphore};
 use tokio::task::JoinHandle;
 use uuid::Uuid;
 
@@ -20,17 +38,130 @@ use uuid::Uuid;
 pub struct ProbeScheduler {
     pool: PgPool,
     config: crate::config::Config,
+    fence: LeaderFence,
     schedulers: Arc<RwLock<HashMap<Uuid, JoinHandle<()>>>>,
+    /// Permits for executions in flight, shared by all probes
+    executions: Arc<Semaphore>,
+    metrics: ProbeMetrics,
+}
+
+/// Prometheus instruments describing scheduled probe executions
+#[derive(Clone)]
+struct ProbeMetrics {
+    /// Executions by probe and outcome: success or failure
+    executions: IntCounterVec,
+    /// Seconds each execution took to complete, by probe
+    response_time_seconds: HistogramVec,
+    /// Seconds until the first token of streamed completions, by probe
+    time_to_first_token_seconds: HistogramVec,
+    /// Tokens per second after the first in the latest streamed completion, by probe
+    tokens_per_second: GaugeVec,
+    /// Probes with a running scheduler
+    scheduled: IntGauge,
+    /// Executions waiting for one in flight to finish, under `probes.max_concurrent_executions`
+    queued: IntGauge,
+}
+
+impl ProbeMetrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        let buckets = vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+        Ok(Self {
+            executions: IntCounterVec::new(
+                Opts::new("dwctl_probe_executions_total", "Scheduled probe executions"),
+                &["probe", "outcome"],
+            )?,
+            response_time_seconds: HistogramVec::new(
+                HistogramOpts::new(
+                    "dwctl_probe_response_time_seconds",
+                    "Time scheduled probe executions took to complete",
+                )
+                .buckets(buckets.clone()),
+                &["probe"],
+            )?,
+            time_to_first_token_seconds: HistogramVec::new(
+                HistogramOpts::new(
+                    "dwctl_probe_time_to_first_token_seconds",
+                    "Time until the first token of completions streamed by scheduled probes",
+                )
+                .buckets(buckets),
+                &["probe"],
+            )?,
+            tokens_per_second: GaugeVec::new(
+                Opts::new(
+                    "dwctl_probe_tokens_per_second",
+                    "Tokens per second after the first in the latest completion streamed by a scheduled probe",
+                ),
+                &["probe"],
+            )?,
+            scheduled: IntGauge::new("dwctl_probe_schedulers", "Probes with a running scheduler")?,
+            queued: IntGauge::new(
+                "dwctl_probe_executions_queued",
+                "Scheduled probe executions waiting for the limit on executions in flight",
+            )?,
+        })
+    }
+
+    fn register(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        registry.register(Box::new(self.executions.clone()))?;
+        registry.register(Box::new(self.response_time_seconds.clone()))?;
+        registry.register(Box::new(self.time_to_first_token_seconds.clone()))?;
+        registry.register(Box::new(self.tokens_per_second.clone()))?;
+        registry.register(Box::new(self.scheduled.clone()))?;
+        registry.register(Box::new(self.queued.clone()))?;
+        Ok(())
+    }
+
+    fn observe(&self, probe: &str, result: &ProbeResult) {
+        let outcome = if result.success { "success" } else { "failure" };
+        self.executions.with_label_values(&[probe, outcome]).inc();
+        if let Some(response_time_ms) = result.response_time_ms {
+            self.response_time_seconds
+                .with_label_values(&[probe])
+                .observe(response_time_ms as f64 / 1000.0);
+        }
+        if let Some(time_to_first_token_ms) = result.time_to_first_token_ms {
+            self.time_to_first_token_seconds
+                .with_label_values(&[probe])
+                .observe(time_to_first_token_ms as f64 / 1000.0);
+        }
+        if let Some(tokens_per_second) = result.tokens_per_second {
+            self.tokens_per_second.with_label_values(&[probe]).set(tokens_per_second);
+        }
+    }
+}
+
+/// Random delay before an execution of a probe: up to its jitter, capped at its interval
+fn jitter_delay(jitter_seconds: Option<i32>, interval_seconds: i32, default_jitter: Duration) -> Duration {
+    let jitter = jitter_seconds
+        .map(|seconds| Duration::from_secs(seconds.max(0) as u64))
+        .unwrap_or(default_jitter)
+        .min(Duration::from_secs(interval_seconds.max(0) as u64));
+    if jitter.is_zero() {
+        return Duration::ZERO;
+    }
+    rand::thread_rng().gen_range(Duration::ZERO..=jitter)
 }
 
 impl ProbeScheduler {
     /// Create a new ProbeScheduler instance
-    pub fn new(pool: PgPool, config: crate::config::Config) -> Self {
-        Self {
+    pub fn new(pool: PgPool, config: crate::config::Config, fence: LeaderFence) -> Result<Self, prometheus::Error> {
+        let permits = match config.probes.max_concurrent_executions {
+            0 => Semaphore::MAX_PERMITS,
+            max => max as usize,
+        };
+        Ok(Self {
             pool,
             config,
+            fence,
             schedulers: Arc::new(RwLock::new(HashMap::new())),
-        }
+            executions: Arc::new(Semaphore::new(permits)),
+            metrics: ProbeMetrics::new()?,
+        })
+    }
+
+    /// Register the scheduler's metrics with `registry`
+    pub fn register_metrics(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        self.metrics.register(registry)
     }
 
     /// Initialize schedulers for all active probes in the database.
@@ -60,6 +191,9 @@ impl ProbeScheduler {
 
         let pool = self.pool.clone();
         let config = self.config.clone();
+        let fence = self.fence.clone();
+        let executions = self.executions.clone();
+        let metrics = self.metrics.clone();
 
         // Spawn the scheduler task
         let handle = tokio::spawn(async move {
@@ -132,9 +266,37 @@ impl ProbeScheduler {
                     break;
                 }
 
-                // Execute the probe
+                // Spread executions of probes that share an interval
+                tokio::time::sleep(jitter_delay(probe.jitter_seconds, probe.interval_seconds, config.probes.jitter)).await;
+
+                // If another replica has taken over as leader, leave the probe to them
+                if !fence.is_current().await {
+                    tracing::warn!("Leadership is stale, stopping scheduler for probe {}", probe.name);
+                    break;
+                }
+
+                // Skip executions while the deployment's endpoint is disabled, without stopping the
+                // scheduler, so the probe resumes when the endpoint is enabled again
+                match ProbeManager::is_paused(&pool, probe_id).await {
+                    Ok(true) => {
+                        tracing::debug!("Probe {} is paused while its endpoint is disabled", probe.name);
+                        tokio::time::sleep(tokio::time::Duration::from_secs(probe.interval_seconds as u64)).await;
+                        continue;
+                    }
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!("Error checking whether probe {} is paused: {}", probe.name, e),
+                }
+
+                // Execute the probe, once fewer than the maximum executions are in flight
+                metrics.queued.inc();
+                let permit = executions.acquire().await;
+                metrics.queued.dec();
+                let Ok(permit) = permit else {
+                    break;
+                };
                 match ProbeManager::execute_probe(&pool, probe_id, &config).await {
                     Ok(result) => {
+                        metrics.observe(&probe.name, &result);
                         if result.success {
                             tracing::debug!(
                                 "Probe {} executed successfully in {}ms",
@@ -149,6 +311,7 @@ impl ProbeScheduler {
                         tracing::error!("Error executing probe {}: {}", probe.name, e);
                     }
                 }
+                drop(permit);
 
                 // Sleep for the configured interval
                 tokio::time::sleep(tokio::time::Duration::from_secs(probe.interval_seconds as u64)).await;
@@ -160,6 +323,7 @@ impl ProbeScheduler {
         // Store the handle
         let mut schedulers = self.schedulers.write().await;
         schedulers.insert(probe_id, handle);
+        self.metrics.scheduled.set(schedulers.len() as i64);
 
         tracing::info!("Started scheduler for probe {}", probe_id);
 
@@ -172,6 +336,7 @@ impl ProbeScheduler {
 
         if let Some(handle) = schedulers.remove(&probe_id) {
             handle.abort();
+            self.metrics.scheduled.set(schedulers.len() as i64);
             tracing::info!("Stopped scheduler for probe {}", probe_id);
         }
 
@@ -187,6 +352,7 @@ impl ProbeScheduler {
             handle.abort();
             tracing::debug!("Stopped scheduler for probe {}", probe_id);
         }
+        self.metrics.scheduled.set(0);
 
         if count > 0 {
             tracing::info!("Stopped {} probe schedulers", count);
@@ -363,7 +529,7 @@ impl ProbeScheduler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::api::models::probes::CreateProbe;
+    use crate::api::models::probes::{CreateProbe, ProbeType};
     use crate::probes::db::ProbeManager;
     use sqlx::PgPool;
 
@@ -399,7 +565,74 @@ mod tests {
     }
 
     fn create_test_config() -> crate::config::Config {
-        crate::test_utils::create_test_config()
+        let mut config = crate::test_utils::create_test_config();
+        config.probes.jitter = Duration::ZERO;
+        config
+    }
+
+    /// A fence holding the current leader token
+    async fn create_test_fence(pool: &PgPool) -> LeaderFence {
+        let fence = LeaderFence::new(pool.clone(), crate::leader::LEADER_LOCK_ID);
+        fence.acquire(&mut pool.acquire().await.unwrap()).await.unwrap();
+        fence
+    }
+
+    #[test]
+    fn test_jitter_delay() {
+        let default_jitter = Duration::from_secs(10);
+        for _ in 0..100 {
+            assert!(jitter_delay(None, 60, default_jitter) <= default_jitter);
+            assert!(jitter_delay(Some(30), 60, default_jitter) <= Duration::from_secs(30));
+            // Capped at the probe's interval
+            assert!(jitter_delay(Some(300), 5, default_jitter) <= Duration::from_secs(5));
+        }
+        assert_eq!(jitter_delay(Some(0), 60, default_jitter), Duration::ZERO);
+        assert_eq!(jitter_delay(None, 60, Duration::ZERO), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_probe_metrics() {
+        let registry = Registry::new();
+        let metrics = ProbeMetrics::new().unwrap();
+        metrics.register(&registry).unwrap();
+
+        let result = ProbeResult {
+            id: Uuid::new_v4(),
+            probe_id: Uuid::new_v4(),
+            executed_at: chrono::Utc::now(),
+            success: true,
+            response_time_ms: Some(1500),
+            status_code: Some(200),
+            error_message: None,
+            response_data: None,
+            metadata: None,
+            assertion_results: None,
+            in_maintenance: false,
+            time_to_first_token_ms: Some(250),
+            tokens_per_second: Some(42.0),
+        };
+        metrics.observe("chat", &result);
+        metrics.observe("chat", &ProbeResult { success: false, ..result });
+
+        assert_eq!(metrics.executions.with_label_values(&["chat", "success"]).get(), 1);
+        assert_eq!(metrics.executions.with_label_values(&["chat", "failure"]).get(), 1);
+        let time_to_first_token = metrics.time_to_first_token_seconds.with_label_values(&["chat"]);
+        assert_eq!(time_to_first_token.get_sample_count(), 2);
+        assert_eq!(time_to_first_token.get_sample_sum(), 0.5);
+        assert_eq!(metrics.tokens_per_second.with_label_values(&["chat"]).get(), 42.0);
+        assert_eq!(registry.gather().len(), 6);
+    }
+
+    #[sqlx::test]
+    async fn test_execution_limit(pool: PgPool) {
+        let mut config = create_test_config();
+        config.probes.max_concurrent_executions = 2;
+        let scheduler = ProbeScheduler::new(pool.clone(), config.clone(), create_test_fence(&pool).await).unwrap();
+        assert_eq!(scheduler.executions.available_permits(), 2);
+
+        config.probes.max_concurrent_executions = 0;
+        let scheduler = ProbeScheduler::new(pool.clone(), config, create_test_fence(&pool).await).unwrap();
+        assert_eq!(scheduler.executions.available_permits(), Semaphore::MAX_PERMITS);
     }
 
     #[sqlx::test]
@@ -418,6 +651,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -432,13 +677,25 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
         .unwrap();
 
         let config = create_test_config();
-        let scheduler = ProbeScheduler::new(pool, config);
+        let scheduler = ProbeScheduler::new(pool.clone(), config, create_test_fence(&pool).await).unwrap();
 
         scheduler.initialize().await.unwrap();
 
@@ -452,7 +709,7 @@ mod tests {
         let deployment_id = setup_test_deployment(&pool).await;
 
         let config = create_test_config();
-        let scheduler = ProbeScheduler::new(pool.clone(), config);
+        let scheduler = ProbeScheduler::new(pool.clone(), config, create_test_fence(&pool).await).unwrap();
 
         // Initially no schedulers
         scheduler.initialize().await.unwrap();
@@ -469,6 +726,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -494,13 +763,25 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
         .unwrap();
 
         let config = create_test_config();
-        let scheduler = ProbeScheduler::new(pool.clone(), config);
+        let scheduler = ProbeScheduler::new(pool.clone(), config, create_test_fence(&pool).await).unwrap();
 
         scheduler.initialize().await.unwrap();
         assert_eq!(scheduler.schedulers.read().await.len(), 1);
@@ -527,6 +808,18 @@ mod tests {
                     http_method: "POST".to_string(),
                     request_path: None,
                     request_body: None,
+                    probe_type: ProbeType::Http,
+                    expected_dimensions: None,
+                    max_latency_ms: None,
+                    conversation: None,
+                    jitter_seconds: None,
+                    timeout_seconds: None,
+                    assertions: None,
+                    golden_answer: None,
+                    embeddings_deployment_id: None,
+                    similarity_threshold: None,
+                    load_concurrency: None,
+                    load_duration_seconds: None,
                 },
             )
             .await
@@ -534,14 +827,16 @@ mod tests {
         }
 
         let config = create_test_config();
-        let scheduler = ProbeScheduler::new(pool, config);
+        let scheduler = ProbeScheduler::new(pool.clone(), config, create_test_fence(&pool).await).unwrap();
 
         scheduler.initialize().await.unwrap();
         assert_eq!(scheduler.schedulers.read().await.len(), 3);
+        assert_eq!(scheduler.metrics.scheduled.get(), 3);
 
         // Stop all
         scheduler.stop_all().await.unwrap();
         assert_eq!(scheduler.schedulers.read().await.len(), 0);
+        assert_eq!(scheduler.metrics.scheduled.get(), 0);
     }
 
     #[sqlx::test]
@@ -558,6 +853,18 @@ mod tests {
                 http_method: "POST".to_string(),
                 request_path: None,
                 request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
             },
         )
         .await
@@ -566,11 +873,113 @@ mod tests {
         ProbeManager::deactivate_probe(&pool, probe.id).await.unwrap();
 
         let config = create_test_config();
-        let scheduler = ProbeScheduler::new(pool, config);
+        let scheduler = ProbeScheduler::new(pool.clone(), config, create_test_fence(&pool).await).unwrap();
 
         scheduler.initialize().await.unwrap();
 
         // Should not have any schedulers
         assert_eq!(scheduler.schedulers.read().await.len(), 0);
     }
+
+    #[sqlx::test]
+    async fn test_stale_leader_does_not_execute_probes(pool: PgPool) {
+        let deployment_id = setup_test_deployment(&pool).await;
+        let probe = ProbeManager::create_probe(
+            &pool,
+            CreateProbe {
+                name: "Fenced Probe".to_string(),
+                deployment_id,
+                interval_seconds: 60,
+                http_method: "POST".to_string(),
+                request_path: None,
+                request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        // The old leader's token is superseded by a new leader's before it gets to run
+        let stale_fence = create_test_fence(&pool).await;
+        let current_fence = create_test_fence(&pool).await;
+
+        let stale_scheduler = ProbeScheduler::new(pool.clone(), create_test_config(), stale_fence).unwrap();
+        stale_scheduler.initialize().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        assert!(ProbeManager::get_recent_results(&pool, probe.id, 10).await.unwrap().is_empty());
+        stale_scheduler.stop_all().await.unwrap();
+
+        // The current leader does run it
+        let scheduler = ProbeScheduler::new(pool.clone(), create_test_config(), current_fence).unwrap();
+        scheduler.initialize().await.unwrap();
+        let mut results = Vec::new();
+        for _ in 0..50 {
+            results = ProbeManager::get_recent_results(&pool, probe.id, 10).await.unwrap();
+            if !results.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(results.len(), 1);
+        scheduler.stop_all().await.unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_probes_paused_while_endpoint_disabled(pool: PgPool) {
+        let deployment_id = setup_test_deployment(&pool).await;
+        let probe = ProbeManager::create_probe(
+            &pool,
+            CreateProbe {
+                name: "Paused Probe".to_string(),
+                deployment_id,
+                interval_seconds: 60,
+                http_method: "POST".to_string(),
+                request_path: None,
+                request_body: None,
+                probe_type: ProbeType::Http,
+                expected_dimensions: None,
+                max_latency_ms: None,
+                conversation: None,
+                jitter_seconds: None,
+                timeout_seconds: None,
+                assertions: None,
+                golden_answer: None,
+                embeddings_deployment_id: None,
+                similarity_threshold: None,
+                load_concurrency: None,
+                load_duration_seconds: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!ProbeManager::is_paused(&pool, probe.id).await.unwrap());
+
+        sqlx::query!(
+            "UPDATE inference_endpoints SET enabled = false WHERE id = (SELECT hosted_on FROM deployed_models WHERE id = $1)",
+            deployment_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(ProbeManager::is_paused(&pool, probe.id).await.unwrap());
+
+        // The probe keeps its scheduler, but doesn't execute
+        let scheduler = ProbeScheduler::new(pool.clone(), create_test_config(), create_test_fence(&pool).await).unwrap();
+        scheduler.initialize().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        assert!(scheduler.is_scheduler_running(probe.id).await);
+        assert!(ProbeManager::get_recent_results(&pool, probe.id, 10).await.unwrap().is_empty());
+        scheduler.stop_all().await.unwrap();
+    }
 }
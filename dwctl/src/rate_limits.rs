@@ -0,0 +1,219 @@
+//! Simulation of the proxy's rate limits against a hypothetical traffic profile.
+//!
+//! onwards enforces up to two request rate limits on each request: the limit of the deployment
+//! and the limit of the API key. Each behaves as a bucket holding up to `burst_size` requests
+//! (the rate itself when no burst size is set) that refills at `requests_per_second`, rounded
+//! down to a whole number of at least one. [`simulate`] replays a profile's requests through
+//! both buckets, so admins can see which limit would trip, and when, before changing a policy.
+
+/// Allowance for floating point error when a bucket refills exactly one request at a time
+const EPSILON: f64 = 1e-9;
+
+/// A request rate limit as enforced by the proxy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests_per_second: u32,
+    pub burst_size: u32,
+}
+
+impl RateLimit {
+    /// The limit the proxy enforces for a configured rate and burst size, if any. Mirrors the
+    /// conversion in `sync::onwards_config`: limits without a positive rate aren't enforced.
+    pub fn from_config(requests_per_second: Option<f32>, burst_size: Option<i32>) -> Option<Self> {
+        let requests_per_second = requests_per_second.filter(|rps| *rps > 0.0)?;
+        let requests_per_second = (requests_per_second.max(1.0) as u32).max(1);
+        Some(Self {
+            requests_per_second,
+            burst_size: burst_size.map(|burst| burst.max(1) as u32).unwrap_or(requests_per_second),
+        })
+    }
+}
+
+/// Hypothetical traffic: `initial_burst` requests at once, then a steady `requests_per_second`
+/// for `duration_seconds`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrafficProfile {
+    pub requests_per_second: f64,
+    pub duration_seconds: u32,
+    pub initial_burst: u32,
+}
+
+impl TrafficProfile {
+    fn steady_requests(&self) -> u64 {
+        (self.requests_per_second * f64::from(self.duration_seconds)).floor() as u64
+    }
+
+    pub fn total_requests(&self) -> u64 {
+        u64::from(self.initial_burst) + self.steady_requests()
+    }
+
+    /// Seconds from the start of the traffic at which each request is sent
+    fn arrivals(&self) -> impl Iterator<Item = f64> + '_ {
+        let burst = std::iter::repeat_n(0.0, self.initial_burst as usize);
+        let steady = (0..self.steady_requests()).map(|i| i as f64 / self.requests_per_second);
+        burst.chain(steady)
+    }
+}
+
+/// How often a limit refused requests
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LimitOutcome {
+    pub limited_requests: u64,
+    /// Seconds into the traffic at which the limit first refused a request
+    pub first_limited_after_seconds: Option<f64>,
+}
+
+impl LimitOutcome {
+    fn record(&mut self, at: f64) {
+        self.limited_requests += 1;
+        self.first_limited_after_seconds.get_or_insert(at);
+    }
+}
+
+/// Result of replaying a traffic profile through the proxy's rate limits
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SimulationOutcome {
+    pub total_requests: u64,
+    pub allowed_requests: u64,
+    pub api_key: LimitOutcome,
+    pub model: LimitOutcome,
+}
+
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    last: f64,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: f64::from(limit.burst_size),
+            last: 0.0,
+        }
+    }
+
+    fn try_take(&mut self, at: f64) -> bool {
+        let refilled = (at - self.last) * f64::from(self.limit.requests_per_second);
+        self.tokens = (self.tokens + refilled).min(f64::from(self.limit.burst_size));
+        self.last = at;
+        if self.tokens + EPSILON >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Replay `profile` through the API key's and the deployment's limits, starting with both
+/// buckets full. The key's limit is checked first, and a request refused by one limit doesn't
+/// count against the other.
+pub fn simulate(profile: &TrafficProfile, api_key: Option<RateLimit>, model: Option<RateLimit>) -> SimulationOutcome {
+    let mut api_key_bucket = api_key.map(Bucket::new);
+    let mut model_bucket = model.map(Bucket::new);
+    let mut outcome = SimulationOutcome {
+        total_requests: profile.total_requests(),
+        ..Default::default()
+    };
+
+    for at in profile.arrivals() {
+        if api_key_bucket.as_mut().is_some_and(|bucket| !bucket.try_take(at)) {
+            outcome.api_key.record(at);
+        } else if model_bucket.as_mut().is_some_and(|bucket| !bucket.try_take(at)) {
+            outcome.model.record(at);
+        } else {
+            outcome.allowed_requests += 1;
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(requests_per_second: f64, duration_seconds: u32, initial_burst: u32) -> TrafficProfile {
+        TrafficProfile {
+            requests_per_second,
+            duration_seconds,
+            initial_burst,
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_from_config() {
+        assert_eq!(RateLimit::from_config(None, Some(10)), None);
+        assert_eq!(RateLimit::from_config(Some(0.0), None), None);
+        assert_eq!(
+            RateLimit::from_config(Some(2.7), None),
+            Some(RateLimit {
+                requests_per_second: 2,
+                burst_size: 2
+            })
+        );
+        assert_eq!(
+            RateLimit::from_config(Some(0.5), Some(0)),
+            Some(RateLimit {
+                requests_per_second: 1,
+                burst_size: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_traffic_within_limits_is_allowed() {
+        let limit = RateLimit {
+            requests_per_second: 10,
+            burst_size: 10,
+        };
+        let outcome = simulate(&profile(10.0, 60, 0), Some(limit), Some(limit));
+        assert_eq!(outcome.total_requests, 600);
+        assert_eq!(outcome.allowed_requests, 600);
+        assert_eq!(outcome.api_key, LimitOutcome::default());
+        assert_eq!(outcome.model, LimitOutcome::default());
+
+        let outcome = simulate(&profile(100.0, 10, 50), None, None);
+        assert_eq!(outcome.allowed_requests, 1050);
+    }
+
+    #[test]
+    fn test_burst_drains_before_the_limit_trips() {
+        // 20 req/s against a limit of 10 req/s with room for 30: the bucket drains by 10 a second
+        let model = RateLimit {
+            requests_per_second: 10,
+            burst_size: 30,
+        };
+        let outcome = simulate(&profile(20.0, 10, 0), None, Some(model));
+        let first = outcome.model.first_limited_after_seconds.unwrap();
+        assert!((2.9..3.1).contains(&first), "first limited after {first}s");
+        // Roughly the burst plus the refill rate gets through
+        assert!(
+            (128..=131).contains(&outcome.allowed_requests),
+            "allowed {}",
+            outcome.allowed_requests
+        );
+        assert_eq!(outcome.model.limited_requests, 200 - outcome.allowed_requests);
+        assert_eq!(outcome.api_key.limited_requests, 0);
+    }
+
+    #[test]
+    fn test_api_key_limit_is_checked_first() {
+        let api_key = RateLimit {
+            requests_per_second: 1,
+            burst_size: 5,
+        };
+        let model = RateLimit {
+            requests_per_second: 1,
+            burst_size: 10,
+        };
+        let outcome = simulate(&profile(1.0, 10, 10), Some(api_key), Some(model));
+        assert_eq!(outcome.api_key.first_limited_after_seconds, Some(0.0));
+        // Half of the initial burst, and the first steady request, exceed the key's burst size
+        assert_eq!(outcome.api_key.limited_requests, 6);
+        // Requests refused by the key's limit don't drain the model's
+        assert_eq!(outcome.model.limited_requests, 0);
+        assert_eq!(outcome.allowed_requests, 14);
+    }
+}
@@ -0,0 +1,315 @@
+//! Redaction of personally identifiable information in requests sent to external providers.
+//!
+//! Admins give an endpoint a redaction policy (`PUT /endpoints/{id}/redaction`) listing the PII
+//! entities (emails, phone numbers, card numbers and IBANs) and regular expressions to mask.
+//! `sync::onwards_config` compiles the policies into [`RedactionRules`] for every target served
+//! by such an endpoint, including fallbacks, weighted targets and canaries, and the [`redact`]
+//! middleware replaces the matches in the prompts of requests for those targets with
+//! placeholders like `[REDACTED_EMAIL]` just before they're forwarded.
+//!
+//! The request logger captures requests before they're redacted. Unless the policies of an
+//! alias' endpoints allow the original to be logged, [`logged_request`] hands it the redacted
+//! request instead.
+
+use crate::request_logging::pii::{self, PiiCategory};
+use crate::routing::RoutingTable;
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use regex::Regex;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::ops::Range;
+use tokio::sync::watch;
+use tracing::debug;
+
+/// Placeholder for matches of a policy's patterns
+pub const PATTERN_PLACEHOLDER: &str = "[REDACTED]";
+
+/// Request fields holding prompts: chat and Messages API requests, completions and embeddings
+const REDACTED_FIELDS: [&str; 4] = ["system", "messages", "prompt", "input"];
+
+/// Keys within prompts whose values are structure (or encoded media) rather than text
+const SKIPPED_KEYS: [&str; 4] = ["role", "type", "image_url", "source"];
+
+fn placeholder(category: PiiCategory) -> &'static str {
+    match category {
+        PiiCategory::Email => "[REDACTED_EMAIL]",
+        PiiCategory::Phone => "[REDACTED_PHONE]",
+        PiiCategory::Financial => "[REDACTED_FINANCIAL]",
+    }
+}
+
+/// Compiled redaction policy of an endpoint
+#[derive(Debug, Clone)]
+pub struct RedactionRules {
+    entities: BTreeSet<PiiCategory>,
+    patterns: Vec<Regex>,
+    log_original: bool,
+}
+
+impl PartialEq for RedactionRules {
+    fn eq(&self, other: &Self) -> bool {
+        self.entities == other.entities
+            && self.log_original == other.log_original
+            && self.patterns.iter().map(Regex::as_str).eq(other.patterns.iter().map(Regex::as_str))
+    }
+}
+
+impl RedactionRules {
+    pub fn new(entities: impl IntoIterator<Item = PiiCategory>, patterns: &[String], log_original: bool) -> Result<Self, regex::Error> {
+        Ok(Self {
+            entities: entities.into_iter().collect(),
+            patterns: patterns.iter().map(|p| Regex::new(p)).collect::<Result<_, _>>()?,
+            log_original,
+        })
+    }
+
+    /// Whether the request log may keep the original request
+    pub fn log_original(&self) -> bool {
+        self.log_original
+    }
+
+    /// Whether the rules mask anything at all
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.patterns.is_empty()
+    }
+
+    /// Mask the entities and pattern matches in `text`. Overlapping matches are merged under
+    /// the placeholder of the one that starts first.
+    pub fn redact_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut matches: Vec<(Range<usize>, &str)> = Vec::new();
+        if !self.entities.is_empty() {
+            matches.extend(
+                pii::find_text(text)
+                    .into_iter()
+                    .filter(|(_, category)| self.entities.contains(category))
+                    .map(|(range, category)| (range, placeholder(category))),
+            );
+        }
+        for pattern in &self.patterns {
+            matches.extend(
+                pattern
+                    .find_iter(text)
+                    .filter(|m| !m.is_empty())
+                    .map(|m| (m.range(), PATTERN_PLACEHOLDER)),
+            );
+        }
+        if matches.is_empty() {
+            return Cow::Borrowed(text);
+        }
+
+        matches.sort_by_key(|(range, _)| (range.start, std::cmp::Reverse(range.end)));
+        let mut redacted = String::with_capacity(text.len());
+        let mut end = 0;
+        for (range, placeholder) in matches {
+            if range.start < end {
+                end = end.max(range.end);
+                continue;
+            }
+            redacted.push_str(&text[end..range.start]);
+            redacted.push_str(placeholder);
+            end = range.end;
+        }
+        redacted.push_str(&text[end..]);
+        Cow::Owned(redacted)
+    }
+
+    /// Mask the prompts of a JSON request body, returning whether anything was masked
+    pub fn redact_request(&self, body: &mut Value) -> bool {
+        let mut redacted = false;
+        if let Value::Object(map) = body {
+            for field in REDACTED_FIELDS {
+                if let Some(value) = map.get_mut(field) {
+                    self.redact_value(value, &mut redacted);
+                }
+            }
+        }
+        redacted
+    }
+
+    fn redact_value(&self, value: &mut Value, redacted: &mut bool) {
+        match value {
+            Value::String(text) => {
+                if let Cow::Owned(masked) = self.redact_text(text) {
+                    *text = masked;
+                    *redacted = true;
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|v| self.redact_value(v, redacted)),
+            Value::Object(map) => map
+                .iter_mut()
+                .filter(|(key, _)| !SKIPPED_KEYS.contains(&key.as_str()))
+                .for_each(|(_, v)| self.redact_value(v, redacted)),
+            _ => {}
+        }
+    }
+
+    /// The redacted version of a serialized request, or `None` if nothing had to be masked
+    /// (including bodies that aren't JSON)
+    pub fn redact_body(&self, body: &[u8]) -> Option<Bytes> {
+        let mut value: Value = serde_json::from_slice(body).ok()?;
+        self.redact_request(&mut value)
+            .then(|| Bytes::from(serde_json::to_vec(&value).unwrap_or_default()))
+    }
+}
+
+/// Middleware that masks PII in requests for targets served by endpoints with a redaction policy
+pub async fn redact(State(table): State<watch::Receiver<RoutingTable>>, request: Request, next: Next) -> Response {
+    if !table.borrow().has_redaction() {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Failed to read request body").into_response(),
+    };
+
+    let redacted = onwards::extract_model_from_request(&parts.headers, &body).ok().and_then(|model| {
+        let table = table.borrow();
+        let redacted = table.redaction(&model)?.redact_body(&body)?;
+        debug!("Redacted PII from request for '{}'", model);
+        Some(redacted)
+    });
+    let body = match redacted {
+        Some(redacted) => {
+            parts.headers.insert(header::CONTENT_LENGTH, HeaderValue::from(redacted.len()));
+            redacted
+        }
+        None => body,
+    };
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
+
+/// The request to record in the request log in place of `request_data`, if it has to be redacted
+pub fn logged_request(table: &RoutingTable, request_data: &outlet::RequestData) -> Option<outlet::RequestData> {
+    if !table.has_redaction() {
+        return None;
+    }
+    let body = request_data.body.as_ref()?;
+    let model = serde_json::from_slice::<Value>(body).ok()?.get("model")?.as_str()?.to_string();
+    let redacted = table.log_redaction(&model)?.redact_body(body)?;
+    Some(outlet::RequestData {
+        body: Some(redacted),
+        ..request_data.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware::from_fn_with_state, routing::post, Json, Router};
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    fn rules(entities: &[PiiCategory], patterns: &[&str], log_original: bool) -> RedactionRules {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        RedactionRules::new(entities.iter().copied(), &patterns, log_original).unwrap()
+    }
+
+    #[test]
+    fn test_redact_text() {
+        let rules = rules(&[PiiCategory::Email, PiiCategory::Phone], &[r"ACME-\d+"], false);
+        assert_eq!(
+            rules.redact_text("Mail jane@example.com or call +44 20 7946 0958 about ACME-1234."),
+            "Mail [REDACTED_EMAIL] or call [REDACTED_PHONE] about [REDACTED]."
+        );
+        // Categories not in the policy are left alone
+        assert_eq!(rules.redact_text("Card 4111 1111 1111 1111"), "Card 4111 1111 1111 1111");
+        assert!(matches!(rules.redact_text("Nothing to see"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_overlapping_matches_are_merged() {
+        let rules = rules(&[PiiCategory::Email], &["example"], false);
+        assert_eq!(rules.redact_text("to bob@example.com now"), "to [REDACTED_EMAIL] now");
+        let rules = self::rules(&[], &["ab", "bc"], false);
+        assert_eq!(rules.redact_text("xabcx"), "x[REDACTED]x");
+    }
+
+    #[test]
+    fn test_redact_request_masks_prompts_only() {
+        let rules = rules(&[PiiCategory::Email], &["user"], false);
+        let mut request = json!({
+            "model": "user-model",
+            "user": "alice@example.com",
+            "system": "Reply to alice@example.com",
+            "messages": [
+                {"role": "user", "content": [
+                    {"type": "text", "text": "I'm bob@example.com"},
+                    {"type": "image_url", "image_url": {"url": "https://example.com/user@2x.png"}}
+                ]},
+                {"role": "assistant", "content": "Hello user"}
+            ]
+        });
+
+        assert!(rules.redact_request(&mut request));
+        assert_eq!(request["model"], "user-model");
+        assert_eq!(request["user"], "alice@example.com");
+        assert_eq!(request["system"], "Reply to [REDACTED_EMAIL]");
+        assert_eq!(request["messages"][0]["role"], "user");
+        assert_eq!(request["messages"][0]["content"][0]["text"], "I'm [REDACTED_EMAIL]");
+        assert_eq!(
+            request["messages"][0]["content"][1]["image_url"]["url"],
+            "https://example.com/user@2x.png"
+        );
+        assert_eq!(request["messages"][1]["content"], "Hello [REDACTED]");
+
+        let mut request = json!({"model": "m", "input": ["plain text"]});
+        assert!(!rules.redact_request(&mut request));
+    }
+
+    fn table() -> RoutingTable {
+        let mut table = RoutingTable::default();
+        table.set_fallbacks("alias".to_string(), vec!["alias::fallback-1".to_string()]);
+        table.set_redaction("alias::fallback-1".to_string(), rules(&[PiiCategory::Email], &[], false));
+        table.set_redaction("logged".to_string(), rules(&[PiiCategory::Email], &[], true));
+        table
+    }
+
+    #[tokio::test]
+    async fn test_redact_middleware() {
+        let (_sender, receiver) = watch::channel(table());
+        let upstream = Router::new()
+            .route("/chat/completions", post(|Json(body): Json<Value>| async move { Json(body) }))
+            .layer(from_fn_with_state(receiver, redact));
+        let server = TestServer::new(upstream).unwrap();
+
+        let request = |model: &str| json!({"model": model, "messages": [{"role": "user", "content": "I'm bob@example.com"}]});
+
+        // Only requests for targets on endpoints with a policy are redacted
+        let body: Value = server.post("/chat/completions").json(&request("alias")).await.json();
+        assert_eq!(body["messages"][0]["content"], "I'm bob@example.com");
+        let body: Value = server.post("/chat/completions").json(&request("alias::fallback-1")).await.json();
+        assert_eq!(body["messages"][0]["content"], "I'm [REDACTED_EMAIL]");
+    }
+
+    #[test]
+    fn test_logged_request() {
+        let table = table();
+        let request_data = |model: &str| outlet::RequestData {
+            correlation_id: 1,
+            timestamp: std::time::SystemTime::now(),
+            method: axum::http::Method::POST,
+            uri: "/ai/v1/chat/completions".parse().unwrap(),
+            headers: Default::default(),
+            body: Some(Bytes::from(
+                json!({"model": model, "messages": [{"role": "user", "content": "I'm bob@example.com"}]}).to_string(),
+            )),
+        };
+
+        // The log is redacted when any endpoint the alias may be routed to doesn't allow the original
+        let logged = logged_request(&table, &request_data("alias")).unwrap();
+        let body: Value = serde_json::from_slice(logged.body.as_ref().unwrap()).unwrap();
+        assert_eq!(body["messages"][0]["content"], "I'm [REDACTED_EMAIL]");
+
+        assert!(logged_request(&table, &request_data("logged")).is_none());
+        assert!(logged_request(&table, &request_data("other")).is_none());
+    }
+}
@@ -0,0 +1,247 @@
+//! Database access layer for regression suites and their runs.
+
+use crate::db::models::regression_suites::{RegressionCase, RegressionCaseResult, RegressionRun, RegressionSuite};
+use crate::errors::Error as AppError;
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Everything needed to create a regression suite
+pub struct NewRegressionSuite {
+    pub deployment_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub cases: Vec<RegressionCase>,
+    pub schedule_interval_seconds: Option<i32>,
+    pub created_by: Uuid,
+}
+
+/// Changes to a regression suite; `None` fields are left unchanged
+#[derive(Default)]
+pub struct RegressionSuiteChanges {
+    pub name: Option<String>,
+    pub description: Option<Option<String>>,
+    pub cases: Option<Vec<RegressionCase>>,
+    pub schedule_interval_seconds: Option<Option<i32>>,
+}
+
+/// Deployment details needed to send a suite's prompts through the AI proxy
+pub struct RegressionTarget {
+    pub alias: String,
+    pub system_api_key: String,
+}
+
+/// Database access layer for regression suites.
+pub struct RegressionSuiteManager;
+
+impl RegressionSuiteManager {
+    pub async fn create(pool: &PgPool, suite: NewRegressionSuite) -> Result<RegressionSuite, AppError> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM deployed_models WHERE id = $1 AND deleted = false)",
+            suite.deployment_id
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to check deployment: {}", e))?;
+        if exists != Some(true) {
+            return Err(AppError::NotFound {
+                resource: "Deployment".to_string(),
+                id: suite.deployment_id.to_string(),
+            });
+        }
+
+        let result = sqlx::query_as::<_, RegressionSuite>(
+            r#"
+            INSERT INTO regression_suites (deployment_id, name, description, cases, schedule_interval_seconds, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(suite.deployment_id)
+        .bind(&suite.name)
+        .bind(&suite.description)
+        .bind(Json(&suite.cases))
+        .bind(suite.schedule_interval_seconds)
+        .bind(suite.created_by)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create regression suite: {}", e))?;
+
+        Ok(result)
+    }
+
+    pub async fn get(pool: &PgPool, id: Uuid) -> Result<RegressionSuite, AppError> {
+        let suite = sqlx::query_as::<_, RegressionSuite>("SELECT * FROM regression_suites WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch regression suite: {}", e))?
+            .ok_or_else(|| AppError::NotFound {
+                resource: "Regression suite".to_string(),
+                id: id.to_string(),
+            })?;
+
+        Ok(suite)
+    }
+
+    /// List regression suites, optionally only those attached to one deployment
+    pub async fn list(pool: &PgPool, deployment_id: Option<Uuid>) -> Result<Vec<RegressionSuite>, AppError> {
+        let suites = sqlx::query_as::<_, RegressionSuite>(
+            "SELECT * FROM regression_suites WHERE ($1::uuid IS NULL OR deployment_id = $1) ORDER BY created_at DESC",
+        )
+        .bind(deployment_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to list regression suites: {}", e))?;
+
+        Ok(suites)
+    }
+
+    pub async fn update(pool: &PgPool, id: Uuid, changes: RegressionSuiteChanges) -> Result<RegressionSuite, AppError> {
+        let suite = sqlx::query_as::<_, RegressionSuite>(
+            r#"
+            UPDATE regression_suites SET
+                name = COALESCE($2, name),
+                description = CASE WHEN $3 THEN $4 ELSE description END,
+                cases = COALESCE($5, cases),
+                schedule_interval_seconds = CASE WHEN $6 THEN $7 ELSE schedule_interval_seconds END,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(&changes.name)
+        .bind(changes.description.is_some())
+        .bind(changes.description.flatten())
+        .bind(changes.cases.as_ref().map(Json))
+        .bind(changes.schedule_interval_seconds.is_some())
+        .bind(changes.schedule_interval_seconds.flatten())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to update regression suite: {}", e))?
+        .ok_or_else(|| AppError::NotFound {
+            resource: "Regression suite".to_string(),
+            id: id.to_string(),
+        })?;
+
+        Ok(suite)
+    }
+
+    /// Delete a regression suite along with its run history
+    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM regression_suites WHERE id = $1", id)
+            .execute(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to delete regression suite: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                resource: "Regression suite".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Fetch the alias of a suite's deployment, along with the system API key used to call it
+    pub async fn get_target(pool: &PgPool, deployment_id: Uuid) -> Result<RegressionTarget, AppError> {
+        let target = sqlx::query_as!(
+            RegressionTarget,
+            r#"
+            SELECT d.alias, ak.secret as system_api_key
+            FROM deployed_models d
+            CROSS JOIN api_keys ak
+            WHERE d.id = $1 AND d.deleted = false AND ak.id = '00000000-0000-0000-0000-000000000000'::uuid
+            "#,
+            deployment_id
+        )
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch regression suite target: {}", e))?
+        .ok_or_else(|| AppError::NotFound {
+            resource: "Deployment".to_string(),
+            id: deployment_id.to_string(),
+        })?;
+
+        Ok(RegressionTarget {
+            system_api_key: crate::crypto::decrypt_secret(target.system_api_key)?,
+            ..target
+        })
+    }
+
+    /// Scheduled suites of live deployments whose interval has elapsed since their last run
+    pub async fn due_for_run(pool: &PgPool) -> Result<Vec<RegressionSuite>, AppError> {
+        let suites = sqlx::query_as::<_, RegressionSuite>(
+            r#"
+            SELECT s.* FROM regression_suites s
+            JOIN deployed_models d ON d.id = s.deployment_id
+            WHERE s.schedule_interval_seconds IS NOT NULL
+              AND d.deleted = false
+              AND (s.last_run_at IS NULL OR s.last_run_at + make_interval(secs => s.schedule_interval_seconds) <= NOW())
+            ORDER BY s.last_run_at ASC NULLS FIRST
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch due regression suites: {}", e))?;
+
+        Ok(suites)
+    }
+
+    /// Store the results of a run and mark the suite as run
+    pub async fn record_run(
+        pool: &PgPool,
+        suite_id: Uuid,
+        trigger: &str,
+        started_at: DateTime<Utc>,
+        results: &[RegressionCaseResult],
+    ) -> Result<RegressionRun, AppError> {
+        let passed = results.iter().filter(|r| r.passed).count() as i32;
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to begin transaction: {}", e))?;
+
+        let run = sqlx::query_as::<_, RegressionRun>(
+            r#"
+            INSERT INTO regression_runs (suite_id, trigger, passed, total, results, started_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(suite_id)
+        .bind(trigger)
+        .bind(passed)
+        .bind(results.len() as i32)
+        .bind(Json(results))
+        .bind(started_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to record regression run: {}", e))?;
+
+        sqlx::query!("UPDATE regression_suites SET last_run_at = $2 WHERE id = $1", suite_id, started_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to update regression suite: {}", e))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to commit transaction: {}", e))?;
+        Ok(run)
+    }
+
+    /// List the runs of a suite, most recent first
+    pub async fn list_runs(pool: &PgPool, suite_id: Uuid, limit: i64) -> Result<Vec<RegressionRun>, AppError> {
+        let runs =
+            sqlx::query_as::<_, RegressionRun>("SELECT * FROM regression_runs WHERE suite_id = $1 ORDER BY started_at DESC LIMIT $2")
+                .bind(suite_id)
+                .bind(limit)
+                .fetch_all(pool)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to list regression runs: {}", e))?;
+
+        Ok(runs)
+    }
+}
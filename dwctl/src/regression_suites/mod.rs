@@ -0,0 +1,5 @@
+pub mod db;
+pub mod runner;
+pub mod scheduler;
+
+pub use scheduler::RegressionSuiteScheduler;
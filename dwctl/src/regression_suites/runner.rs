@@ -0,0 +1,372 @@
+//! Runs the golden prompts of a regression suite and checks their responses.
+//!
+//! Cases are sent one at a time through the AI proxy, so latency assertions aren't skewed by
+//! the suite competing with itself. A case passes when its request succeeds and every
+//! assertion holds; the run's score is the fraction of cases that passed.
+
+use crate::db::models::regression_suites::{Assertion, RegressionCase, RegressionCaseResult, RegressionRun, RegressionSuite};
+use crate::errors::Error as AppError;
+use crate::regression_suites::db::RegressionSuiteManager;
+use regex::Regex;
+use reqwest::Client;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Upper bound on how long a single case may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Check that a suite's cases can be run: each request is a JSON object and every regex compiles.
+pub fn validate_cases(cases: &[RegressionCase]) -> Result<(), String> {
+    if cases.is_empty() {
+        return Err("A regression suite needs at least one case".to_string());
+    }
+    for case in cases {
+        if !case.request.is_object() {
+            return Err(format!("Request of case '{}' must be a JSON object", case.name));
+        }
+        for assertion in &case.assertions {
+            if let Assertion::Regex { pattern } = assertion {
+                Regex::new(pattern).map_err(|e| format!("Invalid regex in case '{}': {}", case.name, e))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run every case of a suite against its deployment and store the results.
+///
+/// `ai_base_url` is the base URL of the AI proxy, e.g. `http://localhost:3001/ai`.
+pub async fn run_suite(pool: &PgPool, ai_base_url: &str, suite: &RegressionSuite, trigger: &str) -> Result<RegressionRun, AppError> {
+    let target = RegressionSuiteManager::get_target(pool, suite.deployment_id).await?;
+    let started_at = chrono::Utc::now();
+
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Failed to build HTTP client: {}", e))?;
+    let url = format!("{}/v1/chat/completions", ai_base_url.trim_end_matches('/'));
+
+    let mut results = Vec::with_capacity(suite.cases.len());
+    for case in suite.cases.iter() {
+        results.push(run_case(&client, &url, &target.system_api_key, &target.alias, case).await);
+    }
+
+    let run = RegressionSuiteManager::record_run(pool, suite.id, trigger, started_at, &results).await?;
+    info!(
+        suite_id = %suite.id,
+        "Regression suite '{}' passed {}/{} cases",
+        suite.name,
+        run.passed,
+        run.total
+    );
+    Ok(run)
+}
+
+async fn run_case(client: &Client, url: &str, api_key: &str, alias: &str, case: &RegressionCase) -> RegressionCaseResult {
+    let mut body = case.request.clone();
+    if let Some(fields) = body.as_object_mut() {
+        fields.insert("model".to_string(), alias.into());
+        fields.insert("stream".to_string(), false.into());
+    }
+
+    let start = Instant::now();
+    let response = client.post(url).bearer_auth(api_key).json(&body).send().await;
+    let (status_code, outcome) = match response {
+        Ok(response) => {
+            let status = response.status();
+            let outcome = if status.is_success() {
+                response
+                    .json::<Value>()
+                    .await
+                    .map_err(|e| format!("Response was not valid JSON: {e}"))
+            } else {
+                Err(format!("Request failed with status {status}"))
+            };
+            (Some(status.as_u16()), outcome)
+        }
+        Err(e) => (None, Err(format!("Request failed: {e}"))),
+    };
+    let latency = start.elapsed();
+
+    let failures = match outcome {
+        Ok(body) => {
+            let content = body
+                .pointer("/choices/0/message/content")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            case.assertions
+                .iter()
+                .filter_map(|assertion| evaluate(assertion, content, latency).err())
+                .collect()
+        }
+        Err(failure) => vec![failure],
+    };
+
+    RegressionCaseResult {
+        name: case.name.clone(),
+        passed: failures.is_empty(),
+        status_code,
+        latency_ms: latency.as_millis() as u64,
+        failures,
+    }
+}
+
+/// Check a single assertion against a response's content and latency
+pub fn evaluate(assertion: &Assertion, content: &str, latency: Duration) -> Result<(), String> {
+    match assertion {
+        Assertion::Regex { pattern } => {
+            let regex = Regex::new(pattern).map_err(|e| format!("Invalid regex '{pattern}': {e}"))?;
+            if regex.is_match(content) {
+                Ok(())
+            } else {
+                Err(format!("Content does not match /{pattern}/"))
+            }
+        }
+        Assertion::JsonSchema { schema } => {
+            let value: Value = serde_json::from_str(content.trim()).map_err(|e| format!("Content is not valid JSON: {e}"))?;
+            let mut errors = Vec::new();
+            check_schema(schema, &value, "$", &mut errors);
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(format!("Content does not match the JSON schema: {}", errors.join("; ")))
+            }
+        }
+        Assertion::MaxLatencyMs { max_ms } => {
+            let latency_ms = latency.as_millis() as u64;
+            if latency_ms <= *max_ms {
+                Ok(())
+            } else {
+                Err(format!("Took {latency_ms}ms, more than the maximum of {max_ms}ms"))
+            }
+        }
+    }
+}
+
+/// Validate a value against the subset of JSON schema used for response assertions
+fn check_schema(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+            errors.push(format!("{path} should be of type {}", types.join(" or ")));
+            return;
+        }
+    }
+
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            errors.push(format!("{path} should be {expected}"));
+        }
+    }
+
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            errors.push(format!("{path} should be one of {}", Value::Array(allowed.clone())));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    errors.push(format!("{path} is missing required property '{key}'"));
+                }
+            }
+        }
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (key, property_schema) in properties {
+                if let Some(property) = object.get(key) {
+                    check_schema(property_schema, property, &format!("{path}.{key}"), errors);
+                }
+            }
+        }
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            check_schema(items, item, &format!("{path}[{i}]"), errors);
+        }
+    }
+}
+
+fn has_type(value: &Value, t: &str) -> bool {
+    match t {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regression_suites::db::NewRegressionSuite;
+    use axum::{http::StatusCode, response::IntoResponse, routing::post, Json, Router};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    /// Serve a fake AI proxy that answers every chat completion with `content`, or fails
+    /// requests whose last message is "fail"
+    async fn mock_proxy(content: &'static str) -> String {
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move |Json(body): Json<Value>| async move {
+                if body.pointer("/messages/0/content") == Some(&json!("fail")) {
+                    return StatusCode::BAD_GATEWAY.into_response();
+                }
+                assert_eq!(body["model"], "regression-alias");
+                Json(json!({"choices": [{"message": {"role": "assistant", "content": content}}]})).into_response()
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    fn case(name: &str, prompt: &str, assertions: Vec<Assertion>) -> RegressionCase {
+        RegressionCase {
+            name: name.to_string(),
+            request: json!({"messages": [{"role": "user", "content": prompt}]}),
+            assertions,
+        }
+    }
+
+    #[test]
+    fn test_regex_and_latency_assertions() {
+        let regex = Assertion::Regex {
+            pattern: r"^\d+ apples$".to_string(),
+        };
+        assert!(evaluate(&regex, "12 apples", Duration::ZERO).is_ok());
+        assert!(evaluate(&regex, "twelve apples", Duration::ZERO).is_err());
+
+        let latency = Assertion::MaxLatencyMs { max_ms: 100 };
+        assert!(evaluate(&latency, "", Duration::from_millis(100)).is_ok());
+        assert!(evaluate(&latency, "", Duration::from_millis(101)).is_err());
+    }
+
+    #[test]
+    fn test_json_schema_assertion() {
+        let schema = Assertion::JsonSchema {
+            schema: json!({
+                "type": "object",
+                "required": ["answer", "tags"],
+                "properties": {
+                    "answer": {"type": "integer"},
+                    "tags": {"type": "array", "items": {"enum": ["a", "b"]}},
+                    "kind": {"const": "fruit"}
+                }
+            }),
+        };
+        assert!(evaluate(&schema, r#"{"answer": 4, "tags": ["a"], "kind": "fruit"}"#, Duration::ZERO).is_ok());
+
+        for content in [
+            "not json",
+            r#"[1, 2]"#,
+            r#"{"answer": 4}"#,
+            r#"{"answer": "4", "tags": []}"#,
+            r#"{"answer": 4, "tags": ["c"]}"#,
+            r#"{"answer": 4, "tags": [], "kind": "vegetable"}"#,
+        ] {
+            assert!(evaluate(&schema, content, Duration::ZERO).is_err(), "{content} should fail");
+        }
+    }
+
+    #[test]
+    fn test_validate_cases() {
+        assert!(validate_cases(&[]).is_err());
+        assert!(validate_cases(&[case("ok", "hi", vec![Assertion::Regex { pattern: "h.".to_string() }])]).is_ok());
+        assert!(validate_cases(&[case("bad", "hi", vec![Assertion::Regex { pattern: "(".to_string() }])]).is_err());
+
+        let mut not_object = case("bad", "hi", vec![]);
+        not_object.request = json!("hi");
+        assert!(validate_cases(&[not_object]).is_err());
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_run_suite_records_scores(pool: PgPool) {
+        let endpoint_id = sqlx::query_scalar!(
+            "INSERT INTO inference_endpoints (name, url, created_by) VALUES ($1, $2, $3) RETURNING id",
+            "regression-endpoint",
+            "http://localhost:8080",
+            Uuid::nil()
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let deployment_id = sqlx::query_scalar!(
+            "INSERT INTO deployed_models (model_name, alias, hosted_on, created_by) VALUES ($1, $2, $3, $4) RETURNING id",
+            "regression-model",
+            "regression-alias",
+            endpoint_id,
+            Uuid::nil()
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let suite = RegressionSuiteManager::create(
+            &pool,
+            NewRegressionSuite {
+                deployment_id,
+                name: "golden".to_string(),
+                description: None,
+                cases: vec![
+                    case(
+                        "matches",
+                        "hi",
+                        vec![Assertion::Regex {
+                            pattern: "Paris".to_string(),
+                        }],
+                    ),
+                    case(
+                        "json",
+                        "hi",
+                        vec![Assertion::JsonSchema {
+                            schema: json!({"type": "object"}),
+                        }],
+                    ),
+                    case("upstream error", "fail", vec![]),
+                ],
+                schedule_interval_seconds: Some(3600),
+                created_by: Uuid::nil(),
+            },
+        )
+        .await
+        .unwrap();
+
+        // Never run, so the scheduled suite is due
+        assert_eq!(RegressionSuiteManager::due_for_run(&pool).await.unwrap().len(), 1);
+
+        let ai_base_url = mock_proxy("The capital of France is Paris").await;
+        let run = run_suite(&pool, &ai_base_url, &suite, "manual").await.unwrap();
+        assert_eq!(run.trigger, "manual");
+        assert_eq!((run.passed, run.total), (1, 3));
+        assert!((run.score - 1.0 / 3.0).abs() < 1e-9);
+
+        let results = &run.results.0;
+        assert!(results[0].passed);
+        assert_eq!(results[0].status_code, Some(200));
+        assert!(!results[1].passed);
+        assert!(results[1].failures[0].contains("not valid JSON"));
+        assert_eq!(results[2].status_code, Some(502));
+
+        // Just ran, so it isn't due again until its interval elapses
+        assert!(RegressionSuiteManager::due_for_run(&pool).await.unwrap().is_empty());
+        let runs = RegressionSuiteManager::list_runs(&pool, suite.id, 10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].id, run.id);
+    }
+}
@@ -0,0 +1,93 @@
+//! Runs regression suites on their schedules.
+//!
+//! Like the probe scheduler, this only runs on the leader replica, so each scheduled run
+//! happens once however many replicas there are. Due suites are picked up from the database
+//! on every tick, which keeps schedule changes made through the API effective without any
+//! signalling between replicas.
+
+use crate::config::Config;
+use crate::leader::LeaderFence;
+use crate::regression_suites::db::RegressionSuiteManager;
+use crate::regression_suites::runner;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// How often to look for suites that are due to run
+const TICK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct RegressionSuiteScheduler {
+    pool: PgPool,
+    config: Config,
+    fence: LeaderFence,
+    handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl RegressionSuiteScheduler {
+    pub fn new(pool: PgPool, config: Config, fence: LeaderFence) -> Self {
+        Self {
+            pool,
+            config,
+            fence,
+            handle: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Start running scheduled suites, if not already running.
+    pub async fn start(&self) {
+        let mut handle = self.handle.lock().await;
+        if handle.is_some() {
+            return;
+        }
+
+        let pool = self.pool.clone();
+        let fence = self.fence.clone();
+        let ai_base_url = format!("http://localhost:{}/ai", self.config.port);
+        *handle = Some(tokio::spawn(async move {
+            loop {
+                if !run_due_suites(&pool, &fence, &ai_base_url).await {
+                    tracing::warn!("Leadership is stale, stopping regression suite scheduler");
+                    break;
+                }
+                tokio::time::sleep(TICK_INTERVAL).await;
+            }
+        }));
+
+        tracing::info!("Started regression suite scheduler");
+    }
+
+    /// Stop running scheduled suites (called when losing leadership).
+    pub async fn stop(&self) {
+        if let Some(handle) = self.handle.lock().await.take() {
+            handle.abort();
+            tracing::info!("Stopped regression suite scheduler");
+        }
+    }
+}
+
+/// Run every suite that's due. Returns false if leadership turned out to be stale, in which
+/// case the remaining suites are left for the new leader.
+async fn run_due_suites(pool: &PgPool, fence: &LeaderFence, ai_base_url: &str) -> bool {
+    let suites = match RegressionSuiteManager::due_for_run(pool).await {
+        Ok(suites) => suites,
+        Err(e) => {
+            tracing::error!("Failed to fetch due regression suites: {}", e);
+            return true;
+        }
+    };
+
+    for suite in suites {
+        // Suite runs can be long, so check before each one rather than once per tick
+        if !fence.is_current().await {
+            return false;
+        }
+        if let Err(e) = runner::run_suite(pool, ai_base_url, &suite, "scheduled").await {
+            tracing::error!(suite_id = %suite.id, "Scheduled regression suite run failed: {}", e);
+        }
+    }
+
+    true
+}
@@ -0,0 +1,197 @@
+//! Encoding of request log exports.
+//!
+//! Exports are streamed as they're read from the database, so each format is encoded record by
+//! record into the chunks of the download. CSV and JSON Lines produce a chunk per record. Parquet
+//! (available when built with the `parquet` feature) buffers records into row groups of
+//! [`PARQUET_ROW_GROUP_ROWS`], producing a chunk per row group and the file footer at the end.
+
+use crate::api::models::requests::{ExportFormat, RequestExportRecord};
+use bytes::Bytes;
+
+/// Records per row group of Parquet exports, buffered in memory before being written
+#[cfg(feature = "parquet")]
+const PARQUET_ROW_GROUP_ROWS: usize = 8192;
+
+/// Encodes the records of an export in one of the [`ExportFormat`]s
+pub enum ExportEncoder {
+    Csv,
+    Jsonl,
+    #[cfg(feature = "parquet")]
+    Parquet(Box<parquet_encoder::ParquetEncoder>),
+}
+
+impl ExportEncoder {
+    /// An encoder for `format`. Fails if dwctl was built without support for it.
+    pub fn new(format: ExportFormat) -> anyhow::Result<Self> {
+        match format {
+            ExportFormat::Csv => Ok(Self::Csv),
+            ExportFormat::Jsonl => Ok(Self::Jsonl),
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => Ok(Self::Parquet(Box::new(parquet_encoder::ParquetEncoder::new()?))),
+            #[cfg(not(feature = "parquet"))]
+            ExportFormat::Parquet => anyhow::bail!("dwctl was built without support for Parquet exports"),
+        }
+    }
+
+    /// Content type and file extension of the export
+    pub fn content_type(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::Csv => ("text/csv", "csv"),
+            Self::Jsonl => ("application/x-ndjson", "jsonl"),
+            #[cfg(feature = "parquet")]
+            Self::Parquet(_) => ("application/vnd.apache.parquet", "parquet"),
+        }
+    }
+
+    /// The beginning of the export, before any record
+    pub fn start(&self) -> Option<Bytes> {
+        match self {
+            Self::Csv => Some(Bytes::from_static(RequestExportRecord::CSV_HEADER.as_bytes())),
+            _ => None,
+        }
+    }
+
+    /// Encode a record, returning the bytes that are ready to be sent, if any
+    pub fn encode(&mut self, record: RequestExportRecord) -> anyhow::Result<Option<Bytes>> {
+        match self {
+            Self::Csv => Ok(Some(Bytes::from(record.to_csv_row()))),
+            Self::Jsonl => {
+                let mut line = serde_json::to_vec(&record)?;
+                line.push(b'\n');
+                Ok(Some(Bytes::from(line)))
+            }
+            #[cfg(feature = "parquet")]
+            Self::Parquet(encoder) => encoder.push(record),
+        }
+    }
+
+    /// The end of the export, after every record
+    pub fn finish(self) -> anyhow::Result<Option<Bytes>> {
+        match self {
+            #[cfg(feature = "parquet")]
+            Self::Parquet(encoder) => encoder.finish().map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_encoder {
+    use super::PARQUET_ROW_GROUP_ROWS;
+    use crate::api::models::requests::RequestExportRecord;
+    use arrow_array::{ArrayRef, BooleanArray, Float64Array, Int32Array, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray};
+    use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+    use bytes::Bytes;
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+    use std::sync::Arc;
+
+    /// Writes records as a Parquet file with the columns of CSV exports, flushing a row group at
+    /// a time
+    pub struct ParquetEncoder {
+        writer: ArrowWriter<Vec<u8>>,
+        schema: SchemaRef,
+        records: Vec<RequestExportRecord>,
+    }
+
+    impl ParquetEncoder {
+        pub fn new() -> anyhow::Result<Self> {
+            let string = |name| Field::new(name, DataType::Utf8, true);
+            let int64 = |name| Field::new(name, DataType::Int64, true);
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("timestamp", DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), false),
+                Field::new("method", DataType::Utf8, false),
+                Field::new("uri", DataType::Utf8, false),
+                string("model"),
+                Field::new("status_code", DataType::Int32, true),
+                int64("duration_ms"),
+                int64("duration_to_first_byte_ms"),
+                int64("prompt_tokens"),
+                int64("completion_tokens"),
+                int64("total_tokens"),
+                Field::new("usage_estimated", DataType::Boolean, false),
+                Field::new("total_cost", DataType::Float64, true),
+                string("user_id"),
+                string("user_email"),
+                string("access_source"),
+                string("response_type"),
+                string("variant"),
+                // As JSON, since attributes vary between users
+                string("user_attributes"),
+            ]));
+            let properties = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .set_max_row_group_row_count(Some(PARQUET_ROW_GROUP_ROWS))
+                .build();
+            Ok(Self {
+                writer: ArrowWriter::try_new(Vec::new(), schema.clone(), Some(properties))?,
+                schema,
+                records: Vec::with_capacity(PARQUET_ROW_GROUP_ROWS),
+            })
+        }
+
+        /// Buffer a record, returning the encoded row group once it's full
+        pub fn push(&mut self, record: RequestExportRecord) -> anyhow::Result<Option<Bytes>> {
+            self.records.push(record);
+            if self.records.len() < PARQUET_ROW_GROUP_ROWS {
+                return Ok(None);
+            }
+            self.flush().map(Some)
+        }
+
+        /// Write the remaining records and the file footer
+        pub fn finish(mut self) -> anyhow::Result<Bytes> {
+            let mut rest = self.flush()?.to_vec();
+            rest.extend(self.writer.into_inner()?);
+            Ok(Bytes::from(rest))
+        }
+
+        /// Write the buffered records as a row group, returning the bytes written
+        fn flush(&mut self) -> anyhow::Result<Bytes> {
+            if !self.records.is_empty() {
+                let batch = self.record_batch()?;
+                self.records.clear();
+                self.writer.write(&batch)?;
+                self.writer.flush()?;
+            }
+            Ok(Bytes::from(std::mem::take(self.writer.inner_mut())))
+        }
+
+        fn record_batch(&self) -> anyhow::Result<RecordBatch> {
+            let records = &self.records;
+            let strings = |field: fn(&RequestExportRecord) -> Option<String>| -> ArrayRef {
+                Arc::new(records.iter().map(field).collect::<StringArray>())
+            };
+            let int64s = |field: fn(&RequestExportRecord) -> Option<i64>| -> ArrayRef {
+                Arc::new(records.iter().map(field).collect::<Int64Array>())
+            };
+            let columns: Vec<ArrayRef> = vec![
+                Arc::new(Int64Array::from_iter_values(records.iter().map(|r| r.id))),
+                Arc::new(
+                    TimestampMicrosecondArray::from_iter_values(records.iter().map(|r| r.timestamp.timestamp_micros()))
+                        .with_timezone("UTC"),
+                ),
+                Arc::new(records.iter().map(|r| Some(r.method.as_str())).collect::<StringArray>()),
+                Arc::new(records.iter().map(|r| Some(r.uri.as_str())).collect::<StringArray>()),
+                strings(|r| r.model.clone()),
+                Arc::new(records.iter().map(|r| r.status_code).collect::<Int32Array>()),
+                int64s(|r| r.duration_ms),
+                int64s(|r| r.duration_to_first_byte_ms),
+                int64s(|r| r.prompt_tokens),
+                int64s(|r| r.completion_tokens),
+                int64s(|r| r.total_tokens),
+                Arc::new(BooleanArray::from(records.iter().map(|r| r.usage_estimated).collect::<Vec<_>>())),
+                Arc::new(records.iter().map(|r| r.total_cost).collect::<Float64Array>()),
+                strings(|r| r.user_id.map(|id| id.to_string())),
+                strings(|r| r.user_email.clone()),
+                strings(|r| r.access_source.clone()),
+                strings(|r| r.response_type.clone()),
+                strings(|r| r.variant.clone()),
+                strings(|r| r.user_attributes.as_ref().map(|attributes| attributes.to_string())),
+            ];
+            Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+        }
+    }
+}
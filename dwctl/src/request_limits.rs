@@ -0,0 +1,272 @@
+//! Per-group caps on the size of AI requests.
+//!
+//! Admins give a group request limits (`PUT /groups/{id}/limits`): a maximum body size, number
+//! of messages and `max_tokens`. When request limits are enabled, the [`enforce_limits`]
+//! middleware looks up the limits that apply to the caller's API key, taking the lowest of each
+//! across their groups (including Everyone), and refuses requests that exceed any of them with a
+//! `400` naming the limit, so runaway contexts never reach the upstream. Requests made with the
+//! system API key (probes, load tests and the playground) are not limited.
+
+use crate::db::health::timed;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tracing::{debug, error};
+
+/// Error code of requests refused for exceeding a limit
+const LIMIT_EXCEEDED_CODE: &str = "request_limit_exceeded";
+
+/// Request fields holding the number of tokens to generate: chat completions and completions,
+/// newer chat completions and the Responses API (the Messages API uses `max_tokens`)
+const MAX_TOKENS_FIELDS: [&str; 3] = ["max_tokens", "max_completion_tokens", "max_output_tokens"];
+
+/// The lowest limits of the groups that apply to a request
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ActiveLimits {
+    pub max_body_bytes: Option<usize>,
+    pub max_messages: Option<usize>,
+    pub max_tokens: Option<u64>,
+}
+
+impl ActiveLimits {
+    pub fn is_empty(&self) -> bool {
+        self.max_body_bytes.is_none() && self.max_messages.is_none() && self.max_tokens.is_none()
+    }
+
+    /// The first limit `body` exceeds, as the offending field and a message for the caller
+    pub fn check(&self, body: &Value) -> Option<(&'static str, String)> {
+        if let Some(max_messages) = self.max_messages {
+            let messages = body.get("messages").and_then(Value::as_array).map_or(0, Vec::len);
+            if messages > max_messages {
+                return Some((
+                    "messages",
+                    format!("Request has {messages} messages, more than the limit of {max_messages} set for your group"),
+                ));
+            }
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            for field in MAX_TOKENS_FIELDS {
+                let Some(requested) = body.get(field).and_then(Value::as_u64) else {
+                    continue;
+                };
+                if requested > max_tokens {
+                    return Some((
+                        field,
+                        format!("{field} of {requested} exceeds the limit of {max_tokens} set for your group"),
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// State for the [`enforce_limits`] middleware
+#[derive(Debug, Clone)]
+pub struct RequestLimits {
+    pool: PgPool,
+}
+
+impl RequestLimits {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Lowest limits of the groups of the API key's owner, including the Everyone group
+    async fn limits_for_key(&self, api_key: &str) -> anyhow::Result<ActiveLimits> {
+        let query = sqlx::query!(
+            r#"
+            SELECT
+                MIN(l.max_body_bytes) AS max_body_bytes,
+                MIN(l.max_messages) AS max_messages,
+                MIN(l.max_tokens) AS max_tokens
+            FROM group_request_limits l
+            JOIN api_keys ak ON ak.secret_hash = api_key_secret_hash($1)
+            WHERE ak.user_id <> '00000000-0000-0000-0000-000000000000'
+              AND (
+                  l.group_id = '00000000-0000-0000-0000-000000000000'
+                  OR l.group_id IN (SELECT group_id FROM effective_user_groups WHERE user_id = ak.user_id)
+              )
+            "#,
+            api_key
+        )
+        .fetch_one(&self.pool);
+        let row = timed("request_limits.limits_for_key", query).await?;
+
+        Ok(ActiveLimits {
+            max_body_bytes: row.max_body_bytes.and_then(|n| usize::try_from(n).ok()),
+            max_messages: row.max_messages.and_then(|n| usize::try_from(n).ok()),
+            max_tokens: row.max_tokens.and_then(|n| u64::try_from(n).ok()),
+        })
+    }
+}
+
+/// Middleware that refuses AI requests exceeding the request limits of the caller's groups
+pub async fn enforce_limits(State(limits): State<RequestLimits>, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let Some(api_key) = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string)
+    else {
+        // Let onwards reject unauthenticated requests
+        return next.run(request).await;
+    };
+
+    let limits = match limits.limits_for_key(&api_key).await {
+        Ok(limits) => limits,
+        Err(e) => {
+            error!("Failed to load request limits, forwarding request unlimited: {:#}", e);
+            return next.run(request).await;
+        }
+    };
+    if limits.is_empty() {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let max_body_bytes = limits.max_body_bytes.unwrap_or(usize::MAX);
+    let body = match to_bytes(body, max_body_bytes).await {
+        Ok(body) => body,
+        Err(_) => {
+            debug!("Refused request over the body size limit of {} bytes", max_body_bytes);
+            return limit_exceeded_response(
+                None,
+                &format!("Request body exceeds the limit of {max_body_bytes} bytes set for your group"),
+            );
+        }
+    };
+
+    if let Ok(value) = serde_json::from_slice::<Value>(&body) {
+        if let Some((param, message)) = limits.check(&value) {
+            debug!("Refused request over its group limits: {}", message);
+            return limit_exceeded_response(Some(param), &message);
+        }
+    }
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
+
+fn limit_exceeded_response(param: Option<&str>, message: &str) -> Response {
+    let error = json!({"message": message, "type": "invalid_request_error", "param": param, "code": LIMIT_EXCEEDED_CODE});
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::users::Role;
+    use crate::db::handlers::Groups;
+    use crate::db::models::groups::GroupRequestLimitsUpdateDBRequest;
+    use crate::test_utils::*;
+    use axum::{middleware::from_fn_with_state, routing::post, Router};
+    use axum_test::TestServer;
+
+    #[test]
+    fn test_check() {
+        let limits = ActiveLimits {
+            max_body_bytes: None,
+            max_messages: Some(2),
+            max_tokens: Some(1000),
+        };
+        let message = json!({"role": "user", "content": "Hi"});
+
+        assert_eq!(limits.check(&json!({"messages": [message, message], "max_tokens": 1000})), None);
+        assert_eq!(limits.check(&json!({"prompt": "Hi"})), None);
+
+        let (param, message) = limits.check(&json!({"messages": [message, message, message]})).unwrap();
+        assert_eq!(param, "messages");
+        assert_eq!(message, "Request has 3 messages, more than the limit of 2 set for your group");
+
+        let (param, message) = limits.check(&json!({"messages": [], "max_completion_tokens": 4096})).unwrap();
+        assert_eq!(param, "max_completion_tokens");
+        assert_eq!(
+            message,
+            "max_completion_tokens of 4096 exceeds the limit of 1000 set for your group"
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_middleware_enforces_lowest_group_limits(pool: PgPool) {
+        let member = create_test_user(&pool, Role::StandardUser).await;
+        let outsider = create_test_user(&pool, Role::StandardUser).await;
+        let strict = create_test_group(&pool).await;
+        let lenient = create_test_group(&pool).await;
+        add_user_to_group(&pool, member.id, strict.id).await;
+        add_user_to_group(&pool, member.id, lenient.id).await;
+        let member_key = create_test_api_key_for_user(&pool, member.id).await;
+        let outsider_key = create_test_api_key_for_user(&pool, outsider.id).await;
+
+        let mut conn = pool.acquire().await.unwrap();
+        let limits = |max_body_bytes, max_tokens| GroupRequestLimitsUpdateDBRequest {
+            max_body_bytes,
+            max_messages: None,
+            max_tokens,
+        };
+        Groups::new(&mut conn)
+            .set_request_limits(strict.id, &limits(None, Some(100)))
+            .await
+            .unwrap();
+        Groups::new(&mut conn)
+            .set_request_limits(lenient.id, &limits(Some(200), Some(1000)))
+            .await
+            .unwrap();
+
+        let app = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|| async { Json(json!({"object": "chat.completion"})) }),
+            )
+            .layer(from_fn_with_state(RequestLimits::new(pool.clone()), enforce_limits));
+        let server = TestServer::new(app).unwrap();
+        let body = |content: &str, max_tokens: u64| json!({"model": "m", "messages": [{"role": "user", "content": content}], "max_tokens": max_tokens});
+
+        let response = server
+            .post("/v1/chat/completions")
+            .authorization_bearer(&member_key.secret)
+            .json(&body("Hello", 100))
+            .await;
+        response.assert_status_ok();
+
+        // The strict group's max_tokens and the lenient group's body size both apply
+        let response = server
+            .post("/v1/chat/completions")
+            .authorization_bearer(&member_key.secret)
+            .json(&body("Hello", 500))
+            .await;
+        response.assert_status_bad_request();
+        let error: Value = response.json();
+        assert_eq!(error["error"]["code"], LIMIT_EXCEEDED_CODE);
+        assert_eq!(error["error"]["param"], "max_tokens");
+
+        let response = server
+            .post("/v1/chat/completions")
+            .authorization_bearer(&member_key.secret)
+            .json(&body(&"a".repeat(500), 100))
+            .await;
+        response.assert_status_bad_request();
+        let error: Value = response.json();
+        assert_eq!(
+            error["error"]["message"],
+            "Request body exceeds the limit of 200 bytes set for your group"
+        );
+
+        // Users outside the groups aren't limited
+        let response = server
+            .post("/v1/chat/completions")
+            .authorization_bearer(&outsider_key.secret)
+            .json(&body(&"a".repeat(500), 500))
+            .await;
+        response.assert_status_ok();
+    }
+}
@@ -0,0 +1,339 @@
+//! Backfills the token counts of logged requests whose analytics were recorded without them.
+//!
+//! Analytics written before usage was extracted for an endpoint, or for upstreams that don't
+//! report usage, have no token counts, so they're missing from usage and cost reporting. An admin
+//! can start a backfill (`POST /requests/token-backfills`), which walks the successful AI requests
+//! without token counts in batches, parses their logged bodies again (fetching bodies kept in
+//! object storage) and fills in the counts with [`logged_token_usage`]. Since the cost of a request
+//! is computed from its token counts and the prices recorded with it, costs are filled in too.
+//! Counts estimated from the bodies rather than reported in the response are flagged with
+//! `usage_estimated`.
+//!
+//! Backfills are recorded as soon as they start and their progress is saved after every batch,
+//! so any replica can serve the progress of a backfill and forward stop requests to the replica
+//! running it through the `stop_requested` flag. Only one backfill may run at a time.
+
+use crate::db::models::token_backfills::TokenBackfill;
+use crate::errors::Error as AppError;
+use crate::request_logging::serializers::{logged_token_usage, LoggedTokenUsage};
+use crate::request_logging::storage::BodyStorage;
+use crate::request_logging::{AiRequest, AiResponse};
+use chrono::{DateTime, Utc};
+use outlet_postgres::{RequestFilter, RequestRepository};
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Number of requests processed between progress updates
+const BATCH_SIZE: i64 = 100;
+
+/// Requests processed so far by a backfill
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BackfillProgress {
+    pub processed: i64,
+    pub updated: i64,
+    pub skipped: i64,
+}
+
+/// A request whose analytics are missing token counts
+struct Candidate {
+    id: i64,
+    instance_id: Uuid,
+    correlation_id: i64,
+}
+
+/// Database access layer for token backfills.
+pub struct TokenBackfillManager;
+
+impl TokenBackfillManager {
+    /// Record a new running backfill of the requests logged in the given range.
+    ///
+    /// Backfills still marked as running without progress for several minutes were interrupted
+    /// (e.g. by a restart) and are marked as failed first, so they don't block new backfills forever.
+    pub async fn create(
+        pool: &PgPool,
+        created_by: Uuid,
+        range_start: Option<DateTime<Utc>>,
+        range_end: Option<DateTime<Utc>>,
+    ) -> Result<TokenBackfill, AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE token_backfills
+            SET status = 'failed', stop_reason = 'Interrupted before finishing', finished_at = NOW()
+            WHERE status = 'running' AND updated_at + INTERVAL '5 minutes' < NOW()
+            "#
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to clean up interrupted token backfills: {}", e))?;
+
+        let total_requests = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM http_analytics
+            WHERE uri LIKE '/ai/%'
+              AND status_code BETWEEN 200 AND 299
+              AND COALESCE(total_tokens, 0) = 0
+              AND ($1::timestamptz IS NULL OR timestamp >= $1)
+              AND ($2::timestamptz IS NULL OR timestamp <= $2)
+            "#,
+            range_start,
+            range_end
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to count requests to backfill: {}", e))?;
+
+        let backfill = sqlx::query_as::<_, TokenBackfill>(
+            r#"
+            INSERT INTO token_backfills (created_by, range_start, range_end, total_requests)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(created_by)
+        .bind(range_start)
+        .bind(range_end)
+        .bind(total_requests)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => AppError::Conflict {
+                message: "Another token backfill is already running".to_string(),
+                conflicts: None,
+            },
+            e => anyhow::anyhow!("Failed to create token backfill: {}", e).into(),
+        })?;
+
+        Ok(backfill)
+    }
+
+    /// Get a backfill by ID
+    pub async fn get(pool: &PgPool, id: Uuid) -> Result<TokenBackfill, AppError> {
+        let backfill = sqlx::query_as::<_, TokenBackfill>("SELECT * FROM token_backfills WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch token backfill: {}", e))?
+            .ok_or_else(|| AppError::NotFound {
+                resource: "Token backfill".to_string(),
+                id: id.to_string(),
+            })?;
+
+        Ok(backfill)
+    }
+
+    /// List backfills, most recent first
+    pub async fn list(pool: &PgPool, limit: i64) -> Result<Vec<TokenBackfill>, AppError> {
+        let backfills = sqlx::query_as::<_, TokenBackfill>("SELECT * FROM token_backfills ORDER BY started_at DESC LIMIT $1")
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to list token backfills: {}", e))?;
+
+        Ok(backfills)
+    }
+
+    /// Ask a running backfill to stop. Backfills that already finished are returned unchanged.
+    pub async fn request_stop(pool: &PgPool, id: Uuid) -> Result<TokenBackfill, AppError> {
+        sqlx::query!(
+            "UPDATE token_backfills SET stop_requested = true WHERE id = $1 AND status = 'running'",
+            id
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to stop token backfill: {}", e))?;
+
+        Self::get(pool, id).await
+    }
+
+    /// Save the progress of a running backfill. Returns whether a stop has been requested.
+    pub async fn update_progress(pool: &PgPool, id: Uuid, progress: BackfillProgress) -> Result<bool, AppError> {
+        let stop_requested = sqlx::query_scalar!(
+            r#"
+            UPDATE token_backfills
+            SET processed_requests = $2, updated_requests = $3, skipped_requests = $4, updated_at = NOW()
+            WHERE id = $1
+            RETURNING stop_requested
+            "#,
+            id,
+            progress.processed,
+            progress.updated,
+            progress.skipped
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to update token backfill progress: {}", e))?;
+
+        Ok(stop_requested)
+    }
+
+    /// Record the final status and progress of a backfill
+    pub async fn finish(
+        pool: &PgPool,
+        id: Uuid,
+        status: &str,
+        stop_reason: Option<&str>,
+        progress: BackfillProgress,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE token_backfills
+            SET status = $2, stop_reason = $3, processed_requests = $4, updated_requests = $5, skipped_requests = $6,
+                updated_at = NOW(), finished_at = NOW()
+            WHERE id = $1
+            "#,
+            id,
+            status,
+            stop_reason,
+            progress.processed,
+            progress.updated,
+            progress.skipped
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to finish token backfill: {}", e))?;
+
+        Ok(())
+    }
+
+    /// The next batch of requests without token counts after the request with analytics ID `after`
+    async fn next_batch(pool: &PgPool, backfill: &TokenBackfill, after: i64) -> Result<Vec<Candidate>, AppError> {
+        let candidates = sqlx::query_as!(
+            Candidate,
+            r#"
+            SELECT id, instance_id, correlation_id
+            FROM http_analytics
+            WHERE id > $1
+              AND uri LIKE '/ai/%'
+              AND status_code BETWEEN 200 AND 299
+              AND COALESCE(total_tokens, 0) = 0
+              AND ($2::timestamptz IS NULL OR timestamp >= $2)
+              AND ($3::timestamptz IS NULL OR timestamp <= $3)
+            ORDER BY id
+            LIMIT $4
+            "#,
+            after,
+            backfill.range_start,
+            backfill.range_end,
+            BATCH_SIZE
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch requests to backfill: {}", e))?;
+
+        Ok(candidates)
+    }
+
+    /// Fill in the token counts of a request. Counts recorded in the meantime are kept.
+    async fn set_tokens(pool: &PgPool, id: i64, usage: LoggedTokenUsage) -> Result<bool, AppError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE http_analytics
+            SET prompt_tokens = $2, completion_tokens = $3, total_tokens = $2::bigint + $3::bigint, usage_estimated = $4
+            WHERE id = $1 AND COALESCE(total_tokens, 0) = 0
+            "#,
+            id,
+            usage.prompt_tokens,
+            usage.completion_tokens,
+            usage.estimated
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to update token counts: {}", e))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// The token usage of a logged request, or `None` if its bodies are no longer in the log
+async fn usage_of(
+    repository: &RequestRepository<AiRequest, AiResponse>,
+    body_storage: Option<&BodyStorage>,
+    candidate: &Candidate,
+) -> Result<Option<LoggedTokenUsage>, AppError> {
+    let pairs = repository
+        .query(RequestFilter {
+            instance_id: Some(candidate.instance_id),
+            correlation_id: Some(candidate.correlation_id),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch logged request: {}", e))?;
+    let Some(pair) = pairs.into_iter().next() else {
+        return Ok(None);
+    };
+
+    let mut request = pair.request.body.and_then(Result::ok);
+    let mut response = pair.response.and_then(|response| response.body).and_then(Result::ok);
+    if let Some(body_storage) = body_storage {
+        if let Some(AiRequest::Stored { stored_body }) = &request {
+            request = body_storage.load(stored_body).await.ok();
+        }
+        if let Some(AiResponse::Stored { stored_body }) = &response {
+            response = body_storage.load(stored_body).await.ok();
+        }
+    }
+
+    Ok(response.and_then(|response| logged_token_usage(request.as_ref(), &response)))
+}
+
+/// Walk the requests to backfill until there are none left or a stop is requested. Returns
+/// whether the backfill was stopped.
+async fn backfill(
+    db: &PgPool,
+    repository: &RequestRepository<AiRequest, AiResponse>,
+    body_storage: Option<&BodyStorage>,
+    backfill: &TokenBackfill,
+    progress: &mut BackfillProgress,
+) -> Result<bool, AppError> {
+    let mut after = 0;
+    loop {
+        let batch = TokenBackfillManager::next_batch(db, backfill, after).await?;
+        let Some(last) = batch.last() else {
+            return Ok(false);
+        };
+        after = last.id;
+
+        for candidate in &batch {
+            match usage_of(repository, body_storage, candidate).await? {
+                Some(usage) if TokenBackfillManager::set_tokens(db, candidate.id, usage).await? => progress.updated += 1,
+                _ => progress.skipped += 1,
+            }
+            progress.processed += 1;
+        }
+
+        if TokenBackfillManager::update_progress(db, backfill.id, *progress).await? {
+            return Ok(true);
+        }
+    }
+}
+
+/// Run a backfill to completion and record how it ended
+pub async fn run(db: PgPool, outlet_db: PgPool, body_storage: Option<BodyStorage>, token_backfill: TokenBackfill) {
+    let repository: RequestRepository<AiRequest, AiResponse> = RequestRepository::new(outlet_db);
+    let mut progress = BackfillProgress::default();
+    info!(backfill_id = %token_backfill.id, total = token_backfill.total_requests, "Starting token backfill");
+
+    let result = backfill(&db, &repository, body_storage.as_ref(), &token_backfill, &mut progress).await;
+    let (status, stop_reason) = match &result {
+        Ok(false) => ("completed", None),
+        Ok(true) => ("stopped", Some("Stopped by user".to_string())),
+        Err(e) => {
+            warn!(backfill_id = %token_backfill.id, error = %e, "Token backfill failed");
+            ("failed", Some(e.user_message()))
+        }
+    };
+    info!(
+        backfill_id = %token_backfill.id,
+        status,
+        processed = progress.processed,
+        updated = progress.updated,
+        skipped = progress.skipped,
+        "Token backfill finished"
+    );
+
+    if let Err(e) = TokenBackfillManager::finish(&db, token_backfill.id, status, stop_reason.as_deref(), progress).await {
+        warn!(backfill_id = %token_backfill.id, error = %e, "Failed to record token backfill result");
+    }
+}
@@ -0,0 +1,142 @@
+//! Threading of logged requests into conversations.
+//!
+//! Clients can say which conversation a request belongs to with the [`CONVERSATION_HEADER`]
+//! header. Otherwise, chat requests (chat completions and the Messages API) are threaded by the
+//! opening of the conversation: as every turn resends the history, the system prompt and first
+//! user message are the same for each turn of a conversation, so a digest of them and the
+//! caller's credentials gives a stable ID. Derived IDs are prefixed with `derived-`; identical
+//! conversations started by the same caller share one.
+
+use super::search::push_filter;
+use super::serializers::Auth;
+use crate::api::models::requests::RequestCursor;
+use outlet::RequestData;
+use outlet_postgres::RequestFilter;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, QueryBuilder};
+use uuid::Uuid;
+
+/// Request header carrying a client-supplied conversation ID
+pub const CONVERSATION_HEADER: &str = "x-doubleword-conversation-id";
+
+/// Longest client-supplied conversation ID that is recorded
+const MAX_CONVERSATION_ID_LEN: usize = 128;
+
+/// Prefix of conversation IDs derived from the request
+const DERIVED_PREFIX: &str = "derived-";
+
+/// The conversation a request belongs to, from its header or derived from its messages
+pub fn conversation_id(request_data: &RequestData, auth: &Auth) -> Option<String> {
+    let supplied = request_data
+        .headers
+        .get(CONVERSATION_HEADER)
+        .and_then(|values| values.first())
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_CONVERSATION_ID_LEN);
+    if let Some(id) = supplied {
+        return Some(id.to_string());
+    }
+
+    let caller = match auth {
+        Auth::ApiKey { bearer_token } => bearer_token,
+        Auth::Playground { user_email } => user_email,
+        Auth::None => return None,
+    };
+    let body: Value = serde_json::from_slice(request_data.body.as_ref()?).ok()?;
+    let messages = body.get("messages")?.as_array()?;
+    let first_user = messages
+        .iter()
+        .position(|message| message.get("role").and_then(Value::as_str) == Some("user"))?;
+    let opening = json!([body.get("system"), &messages[..=first_user]]);
+
+    let mut digest = Sha256::new();
+    digest.update(caller.as_bytes());
+    digest.update(b"\n");
+    digest.update(opening.to_string().as_bytes());
+    Some(format!("{DERIVED_PREFIX}{}", hex::encode(&digest.finalize()[..16])))
+}
+
+/// Find the logged requests in a conversation.
+///
+/// Applies the same filters, ordering and pagination as [`outlet_postgres::RequestRepository`]
+/// queries, and returns the `(instance_id, correlation_id)` keys of the matching requests in order.
+/// Takes the main database pool, as conversations are recorded in the analytics table.
+pub async fn conversation_requests(
+    pool: &PgPool,
+    conversation_id: &str,
+    filter: &RequestFilter,
+    after: Option<&RequestCursor>,
+) -> Result<Vec<(Uuid, i64)>, sqlx::Error> {
+    let mut query = QueryBuilder::new(
+        "SELECT r.instance_id, r.correlation_id
+         FROM http_analytics a
+         JOIN outlet.http_requests r ON (r.instance_id = a.instance_id AND r.correlation_id = a.correlation_id)
+         LEFT JOIN outlet.http_responses res ON (r.instance_id = res.instance_id AND r.correlation_id = res.correlation_id)
+         WHERE a.conversation_id = ",
+    );
+    query.push_bind(conversation_id);
+    push_filter(&mut query, filter, after);
+    query.build_query_as().fetch_all(pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use std::collections::HashMap;
+
+    fn request_data(headers: &[(&str, &str)], body: Value) -> RequestData {
+        RequestData {
+            correlation_id: 1,
+            timestamp: std::time::SystemTime::now(),
+            method: axum::http::Method::POST,
+            uri: "/ai/v1/chat/completions".parse().unwrap(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), vec![Bytes::from(value.to_string())]))
+                .collect::<HashMap<_, _>>(),
+            body: Some(Bytes::from(body.to_string())),
+        }
+    }
+
+    fn key(token: &str) -> Auth {
+        Auth::ApiKey {
+            bearer_token: token.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_conversation_id() {
+        let turn = |messages: Value| json!({"model": "m", "messages": messages});
+        let first = turn(json!([
+            {"role": "system", "content": "Be brief"},
+            {"role": "user", "content": "Hi"}
+        ]));
+        let second = turn(json!([
+            {"role": "system", "content": "Be brief"},
+            {"role": "user", "content": "Hi"},
+            {"role": "assistant", "content": "Hello"},
+            {"role": "user", "content": "How are you?"}
+        ]));
+
+        // Supplied IDs take precedence
+        let supplied = request_data(&[(CONVERSATION_HEADER, " ticket-42 ")], first.clone());
+        assert_eq!(conversation_id(&supplied, &key("a")).as_deref(), Some("ticket-42"));
+
+        // Turns of a conversation share a derived ID, which differs between callers
+        let id = conversation_id(&request_data(&[], first.clone()), &key("a")).unwrap();
+        assert!(id.starts_with(DERIVED_PREFIX));
+        assert_eq!(conversation_id(&request_data(&[], second), &key("a")), Some(id.clone()));
+        assert_ne!(conversation_id(&request_data(&[], first.clone()), &key("b")), Some(id.clone()));
+
+        let other = turn(json!([{"role": "system", "content": "Be brief"}, {"role": "user", "content": "Bye"}]));
+        assert_ne!(conversation_id(&request_data(&[], other), &key("a")), Some(id));
+
+        // Requests without messages aren't threaded
+        let embedding = request_data(&[], json!({"model": "m", "input": "Hi"}));
+        assert_eq!(conversation_id(&embedding, &key("a")), None);
+        assert_eq!(conversation_id(&request_data(&[], first), &Auth::None), None);
+    }
+}
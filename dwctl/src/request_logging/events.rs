@@ -0,0 +1,554 @@
+//! Publishing of request analytics records to an event stream (Kafka or NATS JetStream).
+//!
+//! For teams with existing streaming pipelines, the leader replica publishes every row written to
+//! `http_analytics` as a JSON event, keyed by `<instance_id>:<correlation_id>`. The publisher
+//! tails the table by ID and records how far it has got per destination in
+//! `event_stream_cursors`, only moving the cursor past a record once the platform has
+//! acknowledged it. Delivery is at least once: after a restart or a change of leader, records
+//! published since the cursor was last saved are published again, and consumers should
+//! deduplicate on the key (NATS does this for them within its duplicate window, since the key is
+//! sent as the `Nats-Msg-Id` header).
+//!
+//! A record that still can't be published after `max_retries` retries is sent to the dead-letter
+//! destination, with the error in an `error` header, so one bad record doesn't hold up the stream.
+//! If that fails too, the publisher stops and tries again from the same record on its next run.
+//!
+//! A destination publishes records written from the time it was first used; history isn't
+//! replayed. Records are only picked up once they're a few seconds old, so rows committed out of
+//! ID order aren't skipped.
+
+use crate::config::EventStreamConfig;
+use crate::leader::LeaderFence;
+use async_trait::async_trait;
+use bytes::Bytes;
+use prometheus::{IntCounterVec, Opts, Registry};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Records are only published once they're this old, so concurrent inserts that commit out of
+/// ID order aren't skipped
+const SETTLE_DELAY_SECONDS: f64 = 5.0;
+
+/// Upper bound on the delay between attempts to publish a single record
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// A request analytics record as published to the event stream
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalyticsEvent {
+    pub id: i64,
+    pub instance_id: Uuid,
+    pub correlation_id: i64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub method: String,
+    pub uri: String,
+    pub model: Option<String>,
+    pub status_code: Option<i32>,
+    pub duration_ms: Option<i64>,
+    pub duration_to_first_byte_ms: Option<i64>,
+    pub prompt_tokens: Option<i64>,
+    pub completion_tokens: Option<i64>,
+    pub total_tokens: Option<i64>,
+    pub response_type: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub user_email: Option<String>,
+    pub access_source: Option<String>,
+    pub total_cost: Option<f64>,
+    pub conversation_id: Option<String>,
+    pub tags: Option<serde_json::Value>,
+    pub variant: Option<String>,
+}
+
+impl AnalyticsEvent {
+    /// Message key, unique per request
+    fn key(&self) -> String {
+        format!("{}:{}", self.instance_id, self.correlation_id)
+    }
+}
+
+/// A streaming platform destination records are published to
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    /// Identifies the destination the publisher's cursor belongs to
+    fn destination(&self) -> String;
+
+    /// Publish a record, returning once the platform has acknowledged it
+    async fn publish(&self, key: &str, payload: Bytes) -> anyhow::Result<()>;
+
+    /// Publish a record that couldn't be published to the dead-letter destination
+    async fn dead_letter(&self, key: &str, payload: Bytes, error: &str) -> anyhow::Result<()>;
+}
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use super::*;
+    use crate::config::KafkaSinkConfig;
+    use rdkafka::message::{Header, OwnedHeaders};
+    use rdkafka::producer::{FutureProducer, FutureRecord};
+    use rdkafka::ClientConfig;
+
+    pub struct KafkaSink {
+        producer: FutureProducer,
+        config: KafkaSinkConfig,
+        timeout: Duration,
+    }
+
+    impl KafkaSink {
+        pub fn new(config: &KafkaSinkConfig, timeout: Duration) -> anyhow::Result<Self> {
+            let mut client = ClientConfig::new();
+            client
+                .set("bootstrap.servers", &config.brokers)
+                .set("acks", "all")
+                .set("enable.idempotence", "true");
+            for (key, value) in &config.properties {
+                client.set(key, value);
+            }
+            Ok(Self {
+                producer: client.create()?,
+                config: config.clone(),
+                timeout,
+            })
+        }
+
+        async fn send(&self, topic: &str, key: &str, payload: &[u8], headers: Option<OwnedHeaders>) -> anyhow::Result<()> {
+            let mut record = FutureRecord::to(topic).key(key).payload(payload);
+            if let Some(headers) = headers {
+                record = record.headers(headers);
+            }
+            self.producer
+                .send(record, self.timeout)
+                .await
+                .map_err(|(e, _)| anyhow::anyhow!("Kafka rejected the record: {}", e))?;
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl EventSink for KafkaSink {
+        fn destination(&self) -> String {
+            format!("kafka:{}", self.config.topic)
+        }
+
+        async fn publish(&self, key: &str, payload: Bytes) -> anyhow::Result<()> {
+            self.send(&self.config.topic, key, &payload, None).await
+        }
+
+        async fn dead_letter(&self, key: &str, payload: Bytes, error: &str) -> anyhow::Result<()> {
+            let headers = OwnedHeaders::new().insert(Header {
+                key: "error",
+                value: Some(error),
+            });
+            self.send(&self.config.dead_letter_topic, key, &payload, Some(headers)).await
+        }
+    }
+}
+
+#[cfg(feature = "nats")]
+mod nats {
+    use super::*;
+    use crate::config::NatsSinkConfig;
+    use async_nats::{jetstream, HeaderMap};
+
+    pub struct NatsSink {
+        context: jetstream::Context,
+        config: NatsSinkConfig,
+    }
+
+    impl NatsSink {
+        pub async fn connect(config: &NatsSinkConfig, timeout: Duration) -> anyhow::Result<Self> {
+            let mut options = async_nats::ConnectOptions::new();
+            if let Some(token) = &config.token {
+                options = options.token(token.clone());
+            }
+            let client = options.connect(config.url.as_str()).await?;
+            let mut context = jetstream::new(client);
+            context.set_timeout(timeout);
+            Ok(Self {
+                context,
+                config: config.clone(),
+            })
+        }
+
+        async fn send(&self, subject: &str, headers: HeaderMap, payload: Bytes) -> anyhow::Result<()> {
+            self.context
+                .publish_with_headers(subject.to_string(), headers, payload)
+                .await?
+                .await
+                .map_err(|e| anyhow::anyhow!("JetStream didn't acknowledge the record: {}", e))?;
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl EventSink for NatsSink {
+        fn destination(&self) -> String {
+            format!("nats:{}", self.config.subject)
+        }
+
+        async fn publish(&self, key: &str, payload: Bytes) -> anyhow::Result<()> {
+            let mut headers = HeaderMap::new();
+            headers.insert("Nats-Msg-Id", key);
+            self.send(&self.config.subject, headers, payload).await
+        }
+
+        async fn dead_letter(&self, key: &str, payload: Bytes, error: &str) -> anyhow::Result<()> {
+            let mut headers = HeaderMap::new();
+            headers.insert("Nats-Msg-Id", key);
+            headers.insert("error", error);
+            self.send(&self.config.dead_letter_subject, headers, payload).await
+        }
+    }
+}
+
+/// Connect to the configured destination
+async fn connect(config: &EventStreamConfig) -> anyhow::Result<Arc<dyn EventSink>> {
+    match &config.sink {
+        #[cfg(feature = "kafka")]
+        Some(crate::config::EventSinkConfig::Kafka(kafka)) => Ok(Arc::new(kafka::KafkaSink::new(kafka, config.publish_timeout)?)),
+        #[cfg(feature = "nats")]
+        Some(crate::config::EventSinkConfig::Nats(nats)) => Ok(Arc::new(nats::NatsSink::connect(nats, config.publish_timeout).await?)),
+        #[allow(unreachable_patterns)]
+        Some(_) => anyhow::bail!("dwctl was built without support for the configured event stream sink"),
+        None => anyhow::bail!("No event stream sink is configured"),
+    }
+}
+
+/// Prometheus instruments describing the publisher
+#[derive(Clone)]
+struct EventStreamMetrics {
+    /// Records by outcome: published or dead_lettered
+    records: IntCounterVec,
+}
+
+impl EventStreamMetrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            records: IntCounterVec::new(
+                Opts::new(
+                    "dwctl_event_stream_records_total",
+                    "Request analytics records handled by the event stream publisher",
+                ),
+                &["outcome"],
+            )?,
+        })
+    }
+}
+
+/// Records handled by a run of the publisher
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PublishedRecords {
+    pub published: u64,
+    pub dead_lettered: u64,
+}
+
+/// ID of the last record handled for `destination`, starting new destinations at the latest record
+async fn cursor(pool: &PgPool, destination: &str) -> sqlx::Result<i64> {
+    sqlx::query!(
+        r#"
+        INSERT INTO event_stream_cursors (destination, last_id)
+        SELECT $1, COALESCE(MAX(id), 0) FROM http_analytics
+        ON CONFLICT (destination) DO NOTHING
+        "#,
+        destination
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query_scalar!("SELECT last_id FROM event_stream_cursors WHERE destination = $1", destination)
+        .fetch_one(pool)
+        .await
+}
+
+async fn save_cursor(pool: &PgPool, destination: &str, last_id: i64) -> sqlx::Result<()> {
+    sqlx::query!(
+        "UPDATE event_stream_cursors SET last_id = $2, updated_at = NOW() WHERE destination = $1",
+        destination,
+        last_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn next_batch(pool: &PgPool, after: i64, limit: i64) -> sqlx::Result<Vec<AnalyticsEvent>> {
+    sqlx::query_as!(
+        AnalyticsEvent,
+        r#"
+        SELECT
+            id, instance_id, correlation_id, timestamp, method, uri, model, status_code, duration_ms,
+            duration_to_first_byte_ms, prompt_tokens, completion_tokens, total_tokens, response_type,
+            user_id, user_email, access_source, total_cost::float8 as total_cost, conversation_id, tags, variant
+        FROM http_analytics
+        WHERE id > $1 AND created_at < NOW() - make_interval(secs => $3)
+        ORDER BY id
+        LIMIT $2
+        "#,
+        after,
+        limit,
+        SETTLE_DELAY_SECONDS
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Publish a record, retrying with exponential backoff. Returns the last error if every attempt
+/// failed.
+async fn publish_with_retries(sink: &dyn EventSink, key: &str, payload: &Bytes, max_retries: u32) -> Result<(), anyhow::Error> {
+    let mut delay = Duration::from_millis(500);
+    let mut attempt = 0;
+    loop {
+        match sink.publish(key, payload.clone()).await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < max_retries => {
+                tracing::warn!(key, attempt = attempt + 1, "Failed to publish analytics record, retrying: {:#}", e);
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Publish the records written since the destination's cursor.
+///
+/// Stops early (leaving the rest to the next run) if leadership is lost, or if a record can be
+/// neither published nor dead-lettered.
+pub async fn publish_pending(
+    pool: &PgPool,
+    sink: &dyn EventSink,
+    config: &EventStreamConfig,
+    fence: &LeaderFence,
+) -> anyhow::Result<PublishedRecords> {
+    let destination = sink.destination();
+    let mut last_id = cursor(pool, &destination).await?;
+    let mut handled = PublishedRecords::default();
+
+    while fence.is_current().await {
+        let batch = next_batch(pool, last_id, config.batch_size).await?;
+        if batch.is_empty() {
+            break;
+        }
+
+        for event in &batch {
+            let key = event.key();
+            let payload = Bytes::from(serde_json::to_vec(event)?);
+            match publish_with_retries(sink, &key, &payload, config.max_retries).await {
+                Ok(()) => handled.published += 1,
+                Err(e) => {
+                    let error = format!("{e:#}");
+                    tracing::error!(
+                        key,
+                        "Giving up on publishing analytics record, sending it to the dead-letter destination: {}",
+                        error
+                    );
+                    if let Err(e) = sink.dead_letter(&key, payload, &error).await {
+                        save_cursor(pool, &destination, last_id).await?;
+                        return Err(e.context(format!("Failed to dead-letter analytics record {key}")));
+                    }
+                    handled.dead_lettered += 1;
+                }
+            }
+            last_id = event.id;
+        }
+
+        save_cursor(pool, &destination, last_id).await?;
+    }
+
+    Ok(handled)
+}
+
+/// Leader-run publisher of request analytics records to the configured event stream
+#[derive(Clone)]
+pub struct EventStream {
+    pool: PgPool,
+    config: EventStreamConfig,
+    fence: LeaderFence,
+    metrics: EventStreamMetrics,
+    handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl EventStream {
+    pub fn new(pool: PgPool, config: EventStreamConfig, fence: LeaderFence) -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            pool,
+            config,
+            fence,
+            metrics: EventStreamMetrics::new()?,
+            handle: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Register the publisher's metrics with `registry`
+    pub fn register_metrics(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        registry.register(Box::new(self.metrics.records.clone()))
+    }
+
+    /// Start the publishing loop, if enabled and not already running.
+    pub async fn start(&self) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let mut handle = self.handle.lock().await;
+        if handle.is_some() {
+            return;
+        }
+
+        let pool = self.pool.clone();
+        let config = self.config.clone();
+        let fence = self.fence.clone();
+        let metrics = self.metrics.clone();
+        *handle = Some(tokio::spawn(async move {
+            let mut sink = None;
+            loop {
+                if !fence.is_current().await {
+                    tracing::warn!("Leadership is stale, stopping event stream publisher");
+                    break;
+                }
+                if sink.is_none() {
+                    match connect(&config).await {
+                        Ok(connected) => sink = Some(connected),
+                        Err(e) => tracing::error!("Failed to connect to the event stream: {:#}", e),
+                    }
+                }
+                if let Some(sink) = &sink {
+                    match publish_pending(&pool, sink.as_ref(), &config, &fence).await {
+                        Ok(handled) => {
+                            metrics.records.with_label_values(&["published"]).inc_by(handled.published);
+                            metrics.records.with_label_values(&["dead_lettered"]).inc_by(handled.dead_lettered);
+                        }
+                        Err(e) => tracing::error!("Failed to publish analytics records: {:#}", e),
+                    }
+                }
+                tokio::time::sleep(config.interval).await;
+            }
+        }));
+
+        tracing::info!("Started event stream publisher");
+    }
+
+    /// Stop the publishing loop (called when losing leadership).
+    pub async fn stop(&self) {
+        if let Some(handle) = self.handle.lock().await.take() {
+            handle.abort();
+            tracing::info!("Stopped event stream publisher");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Mutex as StdMutex;
+
+    /// Records published to it, failing for the given keys
+    #[derive(Default)]
+    struct TestSink {
+        published: StdMutex<Vec<serde_json::Value>>,
+        dead_lettered: StdMutex<Vec<(String, String)>>,
+        failing: HashSet<String>,
+        dead_letter_fails: bool,
+    }
+
+    #[async_trait]
+    impl EventSink for TestSink {
+        fn destination(&self) -> String {
+            "test:analytics".to_string()
+        }
+
+        async fn publish(&self, key: &str, payload: Bytes) -> anyhow::Result<()> {
+            if self.failing.contains(key) {
+                anyhow::bail!("broker unavailable");
+            }
+            self.published.lock().unwrap().push(serde_json::from_slice(&payload)?);
+            Ok(())
+        }
+
+        async fn dead_letter(&self, key: &str, _payload: Bytes, error: &str) -> anyhow::Result<()> {
+            if self.dead_letter_fails {
+                anyhow::bail!("dead-letter topic unavailable");
+            }
+            self.dead_lettered.lock().unwrap().push((key.to_string(), error.to_string()));
+            Ok(())
+        }
+    }
+
+    async fn insert_record(pool: &PgPool, instance_id: Uuid, correlation_id: i64) {
+        sqlx::query(
+            "INSERT INTO http_analytics (instance_id, correlation_id, timestamp, uri, method, status_code, model, total_tokens, created_at)
+             VALUES ($1, $2, NOW(), '/ai/v1/chat/completions', 'POST', 200, 'gpt-4', 30, NOW() - INTERVAL '1 minute')",
+        )
+        .bind(instance_id)
+        .bind(correlation_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    fn test_config() -> EventStreamConfig {
+        EventStreamConfig {
+            enabled: true,
+            batch_size: 2,
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_publish_pending(pool: PgPool) {
+        let fence = LeaderFence::new(pool.clone(), crate::leader::LEADER_LOCK_ID);
+        fence.acquire(&mut pool.acquire().await.unwrap()).await.unwrap();
+        let instance_id = Uuid::new_v4();
+
+        // Records written before a destination is first used aren't replayed
+        insert_record(&pool, instance_id, 0).await;
+        let sink = TestSink::default();
+        assert_eq!(
+            publish_pending(&pool, &sink, &test_config(), &fence).await.unwrap(),
+            PublishedRecords::default()
+        );
+
+        for correlation_id in 1..=3 {
+            insert_record(&pool, instance_id, correlation_id).await;
+        }
+        let sink = TestSink {
+            failing: HashSet::from([format!("{instance_id}:2")]),
+            ..Default::default()
+        };
+        let handled = publish_pending(&pool, &sink, &test_config(), &fence).await.unwrap();
+        assert_eq!(
+            handled,
+            PublishedRecords {
+                published: 2,
+                dead_lettered: 1
+            }
+        );
+        let published = sink.published.lock().unwrap().clone();
+        assert_eq!(published[0]["correlation_id"], 1);
+        assert_eq!(published[0]["model"], "gpt-4");
+        assert_eq!(published[0]["total_tokens"], 30);
+        assert_eq!(published[1]["correlation_id"], 3);
+        let dead_lettered = sink.dead_lettered.lock().unwrap().clone();
+        assert_eq!(dead_lettered, vec![(format!("{instance_id}:2"), "broker unavailable".to_string())]);
+
+        // Records aren't published twice, and a record that can't be dead-lettered is retried
+        insert_record(&pool, instance_id, 4).await;
+        let sink = TestSink {
+            failing: HashSet::from([format!("{instance_id}:4")]),
+            dead_letter_fails: true,
+            ..Default::default()
+        };
+        assert!(publish_pending(&pool, &sink, &test_config(), &fence).await.is_err());
+
+        let sink = TestSink::default();
+        let handled = publish_pending(&pool, &sink, &test_config(), &fence).await.unwrap();
+        assert_eq!(handled.published, 1);
+        assert_eq!(sink.published.lock().unwrap()[0]["correlation_id"], 4);
+    }
+}
@@ -0,0 +1,414 @@
+//! Near-real-time forwarding of completed request records to an external webhook.
+//!
+//! Records are pushed onto a bounded in-memory buffer by the analytics serializer and a
+//! background task POSTs them in batches to the configured endpoint. The proxy path never
+//! waits on delivery, since records are queued from the analytics task after the response has
+//! been sent. When the buffer is full (for example while the receiver is down and batches are
+//! being retried) that task waits up to `enqueue_timeout` for space, and only then drops the
+//! record. Batches that still fail after `max_retries` are dropped too. Either way the records
+//! are counted and remain in the request log, from which they can be exported.
+
+use crate::config::RequestMirroringConfig;
+use crate::request_logging::serializers::HttpAnalyticsRow;
+use prometheus::{Gauge, IntCounterVec, IntGauge, Opts, Registry};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{debug, error, warn};
+use url::Url;
+
+/// Upper bound on the delay between delivery attempts for a single batch
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Body POSTed to the mirroring endpoint
+#[derive(Debug, Serialize)]
+struct MirrorBatch<'a> {
+    records: &'a [HttpAnalyticsRow],
+}
+
+/// Prometheus instruments describing the health of the forwarder
+#[derive(Clone)]
+struct MirrorMetrics {
+    /// Records by outcome: delivered, dropped (buffer full) or failed (retries exhausted)
+    records: IntCounterVec,
+    /// Seconds between a request completing and its batch being delivered
+    lag_seconds: Gauge,
+    /// Records currently waiting in the buffer
+    buffered: IntGauge,
+}
+
+impl MirrorMetrics {
+    fn new(registry: Option<&Registry>) -> Result<Self, prometheus::Error> {
+        let records = IntCounterVec::new(
+            Opts::new(
+                "dwctl_request_mirror_records_total",
+                "Request records handled by the mirroring forwarder",
+            ),
+            &["outcome"],
+        )?;
+        let lag_seconds = Gauge::new(
+            "dwctl_request_mirror_lag_seconds",
+            "Age of the oldest record in the most recently delivered mirroring batch",
+        )?;
+        let buffered = IntGauge::new("dwctl_request_mirror_buffered", "Request records waiting to be mirrored")?;
+
+        if let Some(registry) = registry {
+            registry.register(Box::new(records.clone()))?;
+            registry.register(Box::new(lag_seconds.clone()))?;
+            registry.register(Box::new(buffered.clone()))?;
+        }
+
+        Ok(Self {
+            records,
+            lag_seconds,
+            buffered,
+        })
+    }
+}
+
+/// Handle used to queue completed request records for mirroring.
+///
+/// Cloning is cheap; the background task exits and flushes any remaining records once every
+/// handle has been dropped.
+#[derive(Clone)]
+pub struct RequestMirror {
+    sender: mpsc::Sender<HttpAnalyticsRow>,
+    enqueue_timeout: Duration,
+    metrics: MirrorMetrics,
+}
+
+impl RequestMirror {
+    /// Start the forwarding task, registering its metrics with `registry` if provided.
+    pub fn spawn(url: Url, config: &RequestMirroringConfig, registry: Option<&Registry>) -> Result<Self, prometheus::Error> {
+        let metrics = MirrorMetrics::new(registry)?;
+        let (sender, receiver) = mpsc::channel(config.buffer_size.max(1));
+
+        let forwarder = Forwarder {
+            client: reqwest::Client::new(),
+            url,
+            authorization: config.authorization.clone(),
+            batch_size: config.batch_size.max(1),
+            flush_interval: config.flush_interval,
+            max_retries: config.max_retries,
+            request_timeout: config.request_timeout,
+            metrics: metrics.clone(),
+        };
+        tokio::spawn(forwarder.run(receiver));
+
+        Ok(Self {
+            sender,
+            enqueue_timeout: config.enqueue_timeout,
+            metrics,
+        })
+    }
+
+    /// Queue a record for delivery, waiting up to `enqueue_timeout` for space if the buffer is
+    /// full. Drops the record if there's still no space by then.
+    pub async fn mirror(&self, row: HttpAnalyticsRow) {
+        // Counted before sending, since the forwarder may take the record out straight away
+        self.metrics.buffered.inc();
+        match self.sender.send_timeout(row, self.enqueue_timeout).await {
+            Ok(()) => {}
+            Err(mpsc::error::SendTimeoutError::Timeout(row)) => {
+                self.metrics.buffered.dec();
+                self.metrics.records.with_label_values(&["dropped"]).inc();
+                warn!(
+                    correlation_id = row.correlation_id,
+                    "Request mirroring buffer still full after {:?}, dropping record", self.enqueue_timeout
+                );
+            }
+            Err(mpsc::error::SendTimeoutError::Closed(row)) => {
+                self.metrics.buffered.dec();
+                self.metrics.records.with_label_values(&["dropped"]).inc();
+                warn!(
+                    correlation_id = row.correlation_id,
+                    "Request mirroring task has stopped, dropping record"
+                );
+            }
+        }
+    }
+}
+
+struct Forwarder {
+    client: reqwest::Client,
+    url: Url,
+    authorization: Option<String>,
+    batch_size: usize,
+    flush_interval: Duration,
+    max_retries: u32,
+    request_timeout: Duration,
+    metrics: MirrorMetrics,
+}
+
+impl Forwarder {
+    async fn run(self, mut receiver: mpsc::Receiver<HttpAnalyticsRow>) {
+        let mut batch = Vec::with_capacity(self.batch_size);
+
+        // Block until a record arrives, then keep collecting until the batch is full or the
+        // first record has waited `flush_interval`.
+        while let Some(row) = receiver.recv().await {
+            batch.push(row);
+            let deadline = Instant::now() + self.flush_interval;
+            while batch.len() < self.batch_size {
+                match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                    Ok(Some(row)) => batch.push(row),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+
+            self.metrics.buffered.sub(batch.len() as i64);
+            self.deliver(&batch).await;
+            batch.clear();
+        }
+
+        debug!("Request mirroring task stopped");
+    }
+
+    /// Send a batch, retrying with exponential backoff. Gives up after `max_retries` retries,
+    /// leaving the records to be recovered from the request log.
+    async fn deliver(&self, batch: &[HttpAnalyticsRow]) {
+        let mut delay = Duration::from_millis(500);
+
+        for attempt in 0..=self.max_retries {
+            match self.send(batch).await {
+                Ok(()) => {
+                    self.metrics.records.with_label_values(&["delivered"]).inc_by(batch.len() as u64);
+                    if let Some(oldest) = batch.iter().map(completed_at).min() {
+                        let lag = (chrono::Utc::now() - oldest).to_std().unwrap_or_default();
+                        self.metrics.lag_seconds.set(lag.as_secs_f64());
+                    }
+                    return;
+                }
+                Err(e) if attempt < self.max_retries => {
+                    warn!(
+                        attempt = attempt + 1,
+                        records = batch.len(),
+                        "Failed to mirror request records, retrying in {}ms: {:#}",
+                        delay.as_millis(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                }
+                Err(e) => {
+                    error!(records = batch.len(), "Giving up on mirroring request records: {:#}", e);
+                }
+            }
+        }
+
+        self.metrics.records.with_label_values(&["failed"]).inc_by(batch.len() as u64);
+    }
+
+    async fn send(&self, batch: &[HttpAnalyticsRow]) -> anyhow::Result<()> {
+        let mut request = self
+            .client
+            .post(self.url.clone())
+            .timeout(self.request_timeout)
+            .json(&MirrorBatch { records: batch });
+        if let Some(authorization) = &self.authorization {
+            request = request.header(reqwest::header::AUTHORIZATION, authorization);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            anyhow::bail!("mirroring endpoint returned {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+/// When the request finished, used to measure end-to-end mirroring lag
+fn completed_at(row: &HttpAnalyticsRow) -> chrono::DateTime<chrono::Utc> {
+    row.timestamp + chrono::Duration::milliseconds(row.duration_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+    use uuid::Uuid;
+
+    #[derive(Clone, Default)]
+    struct Receiver {
+        batches: Arc<Mutex<Vec<serde_json::Value>>>,
+        /// Number of requests to reject before accepting
+        failures: Arc<AtomicUsize>,
+    }
+
+    async fn ingest(State(receiver): State<Receiver>, Json(body): Json<serde_json::Value>) -> StatusCode {
+        if receiver
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+        {
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+        receiver.batches.lock().unwrap().push(body);
+        StatusCode::OK
+    }
+
+    async fn spawn_receiver(receiver: Receiver) -> Url {
+        let app = Router::new().route("/ingest", post(ingest)).with_state(receiver);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{addr}/ingest").parse().unwrap()
+    }
+
+    fn test_row(correlation_id: i64) -> HttpAnalyticsRow {
+        HttpAnalyticsRow {
+            instance_id: Uuid::new_v4(),
+            correlation_id,
+            timestamp: chrono::Utc::now() - chrono::Duration::seconds(1),
+            method: "POST".to_string(),
+            uri: "/ai/v1/chat/completions".to_string(),
+            request_model: Some("gpt-4".to_string()),
+            response_model: Some("gpt-4-0613".to_string()),
+            status_code: 200,
+            duration_ms: 100,
+            duration_to_first_byte_ms: Some(20),
+            prompt_tokens: 10,
+            completion_tokens: 20,
+            total_tokens: 30,
+            usage_estimated: false,
+            response_type: "chat_completion".to_string(),
+            user_id: None,
+            user_email: Some("user@example.com".to_string()),
+            access_source: "api_key".to_string(),
+            input_price_per_token: None,
+            output_price_per_token: None,
+            server_address: "localhost".to_string(),
+            server_port: 3001,
+            provider_name: None,
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
+        }
+    }
+
+    fn test_config() -> RequestMirroringConfig {
+        RequestMirroringConfig {
+            enabled: true,
+            batch_size: 10,
+            flush_interval: Duration::from_millis(50),
+            buffer_size: 100,
+            max_retries: 3,
+            ..Default::default()
+        }
+    }
+
+    async fn wait_for_records(receiver: &Receiver, expected: usize) -> Vec<serde_json::Value> {
+        for _ in 0..100 {
+            let records: Vec<_> = receiver
+                .batches
+                .lock()
+                .unwrap()
+                .iter()
+                .flat_map(|b| b["records"].as_array().unwrap().clone())
+                .collect();
+            if records.len() >= expected {
+                return records;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("timed out waiting for {expected} mirrored records");
+    }
+
+    #[tokio::test]
+    async fn test_records_are_delivered_in_batches() {
+        let receiver = Receiver::default();
+        let url = spawn_receiver(receiver.clone()).await;
+        let registry = Registry::new();
+        let mirror = RequestMirror::spawn(url, &test_config(), Some(&registry)).unwrap();
+
+        for id in 0..25 {
+            mirror.mirror(test_row(id)).await;
+        }
+
+        let records = wait_for_records(&receiver, 25).await;
+        assert_eq!(records.len(), 25);
+        assert_eq!(records[0]["correlation_id"], 0);
+        assert_eq!(records[0]["request_model"], "gpt-4");
+
+        // Batches never exceed batch_size
+        let batches = receiver.batches.lock().unwrap();
+        assert!(batches.len() >= 3);
+        assert!(batches.iter().all(|b| b["records"].as_array().unwrap().len() <= 10));
+        drop(batches);
+
+        assert_eq!(mirror.metrics.records.with_label_values(&["delivered"]).get(), 25);
+        assert!(mirror.metrics.lag_seconds.get() > 0.0);
+        assert!(registry.gather().iter().any(|m| m.get_name() == "dwctl_request_mirror_lag_seconds"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_batches_are_retried() {
+        let receiver = Receiver::default();
+        receiver.failures.store(2, Ordering::SeqCst);
+        let url = spawn_receiver(receiver.clone()).await;
+        let mirror = RequestMirror::spawn(url, &test_config(), None).unwrap();
+
+        mirror.mirror(test_row(1)).await;
+
+        let records = wait_for_records(&receiver, 1).await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(mirror.metrics.records.with_label_values(&["delivered"]).get(), 1);
+        assert_eq!(mirror.metrics.records.with_label_values(&["failed"]).get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_buffer_waits_for_space() {
+        let receiver = Receiver::default();
+        receiver.failures.store(1, Ordering::SeqCst);
+        let url = spawn_receiver(receiver.clone()).await;
+        let config = RequestMirroringConfig {
+            batch_size: 1,
+            buffer_size: 1,
+            ..test_config()
+        };
+        let mirror = RequestMirror::spawn(url, &config, None).unwrap();
+
+        // The first batch is retried after a delay, during which the buffer fills up
+        for id in 0..5 {
+            mirror.mirror(test_row(id)).await;
+        }
+
+        let records = wait_for_records(&receiver, 5).await;
+        assert_eq!(records.len(), 5);
+        assert_eq!(mirror.metrics.records.with_label_values(&["dropped"]).get(), 0);
+        assert_eq!(mirror.metrics.buffered.get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_records_are_dropped_when_buffer_stays_full() {
+        // Nothing is listening here, so the first batch is stuck retrying
+        let config = RequestMirroringConfig {
+            batch_size: 1,
+            buffer_size: 2,
+            enqueue_timeout: Duration::from_millis(10),
+            ..test_config()
+        };
+        let mirror = RequestMirror::spawn("http://127.0.0.1:1/ingest".parse().unwrap(), &config, None).unwrap();
+
+        mirror.mirror(test_row(1)).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        for id in 2..10 {
+            mirror.mirror(test_row(id)).await;
+        }
+
+        assert!(mirror.metrics.records.with_label_values(&["dropped"]).get() >= 6);
+        assert!(mirror.metrics.buffered.get() <= 2);
+    }
+}
@@ -1,5 +1,16 @@
+pub mod backfill;
+pub mod conversations;
+pub mod events;
+pub mod mirror;
 pub mod models;
+pub mod pii;
+pub mod policy;
+pub mod retention;
+pub mod search;
 pub mod serializers;
+pub mod storage;
+pub mod tags;
 mod utils;
+pub mod webhook;
 
 pub use models::{AiRequest, AiResponse};
@@ -3,7 +3,7 @@ use async_openai::types::{
     CreateCompletionRequest, CreateCompletionResponse, CreateEmbeddingRequest, CreateEmbeddingResponse,
 };
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 use thiserror::Error;
 
 /// Errors that can occur during SSE parsing
@@ -14,7 +14,7 @@ pub enum SseParseError {
     InvalidFormat,
 }
 
-/// AI request types covering common OpenAI-compatible endpoints
+/// AI request types covering common OpenAI-compatible endpoints and the Anthropic Messages API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 #[allow(clippy::large_enum_variant)]
@@ -22,6 +22,11 @@ pub enum AiRequest {
     ChatCompletions(CreateChatCompletionRequest),
     Completions(CreateCompletionRequest),
     Embeddings(CreateEmbeddingRequest),
+    Messages(MessagesRequest),
+    /// Body kept in object storage, see `request_logging::storage`
+    Stored {
+        stored_body: StoredBody,
+    },
     Other(Value),
 }
 
@@ -42,5 +47,95 @@ pub enum AiResponse {
     Completions(CreateCompletionResponse),
     Embeddings(CreateEmbeddingResponse),
     Base64Embeddings(CreateBase64EmbeddingResponse),
+    Messages(MessagesResponse),
+    MessagesStream(Vec<MessagesStreamEvent>),
+    /// Body kept in object storage, see `request_logging::storage`
+    Stored {
+        stored_body: StoredBody,
+    },
     Other(Value),
 }
+
+/// Pointer to a captured body kept in object storage rather than in the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredBody {
+    /// Key of the object holding the body
+    pub key: String,
+    pub size_bytes: usize,
+}
+
+/// A request to the Anthropic Messages API. Only the fields the request logger reads are typed;
+/// the rest are kept as sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagesRequest {
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
+
+/// A response from the Anthropic Messages API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagesResponse {
+    pub id: String,
+    pub model: String,
+    pub usage: MessagesUsage,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
+
+/// Token usage of a Messages API response. Prompt caching splits input tokens between
+/// `input_tokens` and the two cache counts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessagesUsage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens: Option<u64>,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_input_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_input_tokens: Option<u64>,
+    #[serde(flatten)]
+    pub rest: Map<String, Value>,
+}
+
+impl MessagesUsage {
+    /// Input tokens, including those written to and read from the prompt cache, if reported
+    pub fn total_input_tokens(&self) -> Option<u64> {
+        self.input_tokens
+            .map(|input| input + self.cache_creation_input_tokens.unwrap_or_default() + self.cache_read_input_tokens.unwrap_or_default())
+    }
+}
+
+/// An event of a streamed Messages API response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MessagesStreamEvent {
+    /// Opens the message, with the model and the input token count
+    MessageStart {
+        message: MessagesResponse,
+    },
+    /// Closes the message, with the cumulative output token count
+    MessageDelta {
+        delta: Value,
+        usage: MessagesUsage,
+    },
+    ContentBlockStart {
+        index: u64,
+        content_block: Value,
+    },
+    ContentBlockDelta {
+        index: u64,
+        delta: Value,
+    },
+    ContentBlockStop {
+        index: u64,
+    },
+    MessageStop,
+    Ping,
+    Error {
+        error: Value,
+    },
+}
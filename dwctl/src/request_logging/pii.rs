@@ -0,0 +1,318 @@
+//! Coarse PII classification of logged prompts.
+//!
+//! Classifies the text of AI requests into broad PII categories so that data-governance
+//! reviews can see how often sensitive data is being sent to models. Only the categories are
+//! recorded; the matched values themselves are never stored or logged.
+//!
+//! The same detection is used to mask PII in requests sent to endpoints with a redaction policy
+//! (see [`crate::redaction`]).
+
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Range;
+
+/// Coarse category of personally identifiable information found in a prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PiiCategory {
+    Email,
+    Phone,
+    /// Payment card numbers and IBANs
+    Financial,
+}
+
+impl PiiCategory {
+    pub const ALL: [PiiCategory; 3] = [PiiCategory::Email, PiiCategory::Phone, PiiCategory::Financial];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PiiCategory::Email => "email",
+            PiiCategory::Phone => "phone",
+            PiiCategory::Financial => "financial",
+        }
+    }
+
+    pub fn parse(category: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == category)
+    }
+}
+
+impl fmt::Display for PiiCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Classify every string in a JSON request body. An empty set means no PII was found.
+pub fn classify_json(value: &Value) -> BTreeSet<PiiCategory> {
+    let mut categories = BTreeSet::new();
+    visit_strings(value, &mut |s| categories.extend(classify_text(s)));
+    categories
+}
+
+fn visit_strings(value: &Value, f: &mut impl FnMut(&str)) {
+    match value {
+        Value::String(s) => f(s),
+        Value::Array(values) => values.iter().for_each(|v| visit_strings(v, f)),
+        Value::Object(map) => map.values().for_each(|v| visit_strings(v, f)),
+        _ => {}
+    }
+}
+
+/// Classify a piece of free text.
+pub fn classify_text(text: &str) -> BTreeSet<PiiCategory> {
+    find_text(text).into_iter().map(|(_, category)| category).collect()
+}
+
+/// Find the PII in a piece of free text, as byte ranges of `text` with their category. The
+/// ranges aren't sorted, and may overlap.
+pub fn find_text(text: &str) -> Vec<(Range<usize>, PiiCategory)> {
+    let mut found = Vec::new();
+
+    if text.contains('@') {
+        for token in token_ranges(text, is_email_delimiter) {
+            if is_email(&text[token.clone()]) {
+                let email = text[token.clone()].trim_end_matches(['.', '!', '?']);
+                found.push((token.start..token.start + email.len(), PiiCategory::Email));
+            }
+        }
+    }
+
+    for run in number_runs(text) {
+        let digits: String = text[run.clone()].chars().filter(|c| c.is_ascii_digit()).collect();
+        if (13..=19).contains(&digits.len()) && luhn_valid(&digits) {
+            found.push((run, PiiCategory::Financial));
+        } else if (10..=15).contains(&digits.len()) && looks_like_phone(&text[run.clone()]) {
+            found.push((run, PiiCategory::Phone));
+        }
+    }
+
+    for token in token_ranges(text, |c| !c.is_ascii_alphanumeric()) {
+        if is_iban(&text[token.clone()]) {
+            found.push((token, PiiCategory::Financial));
+        }
+    }
+
+    found
+}
+
+/// Byte ranges of the non-empty runs of `text` between delimiters
+fn token_ranges(text: &str, is_delimiter: impl Fn(char) -> bool) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (is_delimiter(c), start) {
+            (true, Some(s)) => {
+                ranges.push(s..i);
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        ranges.push(s..text.len());
+    }
+    ranges
+}
+
+fn is_email_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '"' | '\'' | ',' | ';' | ':' | '<' | '>' | '(' | ')' | '[' | ']' | '{' | '}')
+}
+
+fn is_email(token: &str) -> bool {
+    let token = token.trim_end_matches(['.', '!', '?']);
+    let Some((local, domain)) = token.split_once('@') else {
+        return false;
+    };
+    if local.is_empty() || domain.contains('@') {
+        return false;
+    }
+    if !local
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-'))
+    {
+        return false;
+    }
+
+    let labels: Vec<&str> = domain.split('.').collect();
+    let Some(tld) = labels.last() else {
+        return false;
+    };
+    labels.len() >= 2
+        && labels
+            .iter()
+            .all(|l| !l.is_empty() && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Find standalone runs of digits joined by single separators, e.g. `+44 (20) 7946-0958` or
+/// `4111 1111 1111 1111`.
+fn number_runs(text: &str) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let bytes = text.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if !(bytes[i].is_ascii_digit() || bytes[i] == b'+' || bytes[i] == b'(') {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let mut end = i;
+        let mut previous_was_separator = false;
+        while i < bytes.len() {
+            let b = bytes[i];
+            if b.is_ascii_digit() {
+                end = i + 1;
+                previous_was_separator = false;
+            } else if matches!(b, b' ' | b'-' | b'.' | b'(' | b')') || (b == b'+' && i == start) {
+                // Two separators in a row end the run, except around parentheses
+                if previous_was_separator && !matches!(b, b'(' | b')') && !matches!(bytes[i - 1], b'(' | b')') {
+                    break;
+                }
+                previous_was_separator = true;
+            } else {
+                break;
+            }
+            i += 1;
+        }
+
+        // Digits that are part of a longer word (e.g. inside an IBAN or ID) aren't a number
+        let attached_before = start > 0 && bytes[start - 1].is_ascii_alphanumeric();
+        let attached_after = end < bytes.len() && bytes[end].is_ascii_alphanumeric();
+        if end > start && !attached_before && !attached_after {
+            runs.push(start..end);
+        }
+        i = i.max(start + 1);
+    }
+
+    runs
+}
+
+/// Phone numbers are made of digit groups; after the first (country code) each has 2+ digits.
+fn looks_like_phone(run: &str) -> bool {
+    run.split(|c: char| !c.is_ascii_digit())
+        .filter(|g| !g.is_empty())
+        .skip(1)
+        .all(|g| g.len() >= 2)
+}
+
+fn luhn_valid(digits: &str) -> bool {
+    let sum: u32 = digits
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// IBANs: two letters, two check digits, then up to 30 alphanumerics, validated with mod-97.
+fn is_iban(token: &str) -> bool {
+    let bytes = token.as_bytes();
+    if !(15..=34).contains(&bytes.len())
+        || !bytes[..2].iter().all(u8::is_ascii_uppercase)
+        || !bytes[2..4].iter().all(u8::is_ascii_digit)
+        || !bytes.iter().all(|b| b.is_ascii_digit() || b.is_ascii_uppercase())
+    {
+        return false;
+    }
+
+    let rearranged = bytes[4..].iter().chain(&bytes[..4]);
+    let remainder = rearranged.fold(0u32, |acc, &b| {
+        if b.is_ascii_digit() {
+            (acc * 10 + (b - b'0') as u32) % 97
+        } else {
+            (acc * 100 + (b - b'A' + 10) as u32) % 97
+        }
+    });
+    remainder == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn categories(text: &str) -> Vec<PiiCategory> {
+        classify_text(text).into_iter().collect()
+    }
+
+    #[test]
+    fn test_no_pii() {
+        assert!(categories("What is the capital of France?").is_empty());
+        assert!(categories("Meeting on 2024-01-15 at 10:30, room 101").is_empty());
+        assert!(categories("Use the @decorator syntax in Python").is_empty());
+        assert!(categories("Count 1 2 3 4 5 6 7 8 9 10 11").is_empty());
+    }
+
+    #[test]
+    fn test_email() {
+        assert_eq!(categories("Contact me at jane.doe+work@example.co.uk."), vec![PiiCategory::Email]);
+        assert_eq!(categories("<bob@example.com>"), vec![PiiCategory::Email]);
+        assert!(categories("user@localhost").is_empty());
+    }
+
+    #[test]
+    fn test_phone() {
+        assert_eq!(categories("Call +44 20 7946 0958 tomorrow"), vec![PiiCategory::Phone]);
+        assert_eq!(categories("My number is (555) 123-4567 ext"), vec![PiiCategory::Phone]);
+        assert_eq!(categories("+1 (555) 123-4567"), vec![PiiCategory::Phone]);
+    }
+
+    #[test]
+    fn test_financial() {
+        assert_eq!(categories("Card: 4111 1111 1111 1111"), vec![PiiCategory::Financial]);
+        assert_eq!(categories("4111-1111-1111-1111"), vec![PiiCategory::Financial]);
+        assert_eq!(categories("Pay to GB82WEST12345698765432 please"), vec![PiiCategory::Financial]);
+        // Fails the IBAN checksum
+        assert!(categories("GB00WEST12345698765432").is_empty());
+    }
+
+    #[test]
+    fn test_find_text_ranges() {
+        let text = "Mail bob@example.com. Call +44 20 7946 0958, card 4111-1111-1111-1111";
+        let found: Vec<_> = find_text(text)
+            .into_iter()
+            .map(|(range, category)| (&text[range], category))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("bob@example.com", PiiCategory::Email),
+                ("+44 20 7946 0958", PiiCategory::Phone),
+                ("4111-1111-1111-1111", PiiCategory::Financial),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classify_json_walks_all_strings() {
+        let request = json!({
+            "model": "gpt-4",
+            "messages": [
+                {"role": "system", "content": "You are helpful"},
+                {"role": "user", "content": [{"type": "text", "text": "Email alice@example.com"}]},
+                {"role": "user", "content": "Card 4111111111111111"}
+            ]
+        });
+
+        let found: Vec<_> = classify_json(&request).into_iter().collect();
+        assert_eq!(found, vec![PiiCategory::Email, PiiCategory::Financial]);
+    }
+}
@@ -0,0 +1,262 @@
+//! Per-deployment policies for what the request log keeps of bodies.
+//!
+//! Not every deployment is allowed to store prompts. Admins give a deployment a logging policy
+//! (`PUT /models/{id}/logging-policy`), which `sync::onwards_config` publishes in the routing
+//! table under the deployment's alias. The request and response serializers then apply the
+//! policy of the alias named in each request to the parsed bodies just before they're written to
+//! the outlet schema, after usage has been extracted for analytics:
+//!
+//! - metadata only: bodies are dropped, keeping the method, URI, headers, status and timings
+//! - hash only: bodies are replaced with `{"sha256": "<hex digest>"}` of the body as it would
+//!   otherwise have been logged, so a known body can still be matched against the log
+//! - redacted fields: the values at the listed dot-separated paths (e.g. `messages.content` or
+//!   `choices.message.content`) are replaced with `[REDACTED]`; arrays along a path are
+//!   traversed element by element, so paths apply to each message and each streamed chunk
+//!
+//! Bodies that can't be parsed are dropped under any policy other than full logging, except that
+//! hash-only policies hash them as captured.
+//!
+//! A policy can also sample body capture, keeping bodies (under its mode) for only a percentage of
+//! requests and logging the rest as metadata only. Whether a request is sampled is decided from a
+//! digest of its correlation ID and timestamp, so its request and response bodies are kept or
+//! dropped together.
+
+use crate::redaction::PATTERN_PLACEHOLDER;
+use crate::request_logging::models::{AiRequest, AiResponse};
+use crate::routing::RoutingTable;
+use outlet::RequestData;
+use outlet_postgres::SerializationError;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+/// What the request log keeps of the bodies of a deployment's requests, other than everything
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoggingPolicy {
+    MetadataOnly,
+    HashOnly,
+    RedactFields(Vec<Vec<String>>),
+}
+
+impl LoggingPolicy {
+    /// Compile a stored policy, or `None` if bodies are logged in full
+    pub fn new(mode: &str, redacted_fields: &[String]) -> Option<Self> {
+        match mode {
+            "metadata_only" => Some(Self::MetadataOnly),
+            "hash_only" => Some(Self::HashOnly),
+            "redact_fields" => Some(Self::RedactFields(
+                redacted_fields
+                    .iter()
+                    .map(|field| field.split('.').map(str::to_string).collect())
+                    .collect(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// The request body to log in place of `request`
+    pub fn apply_to_request(&self, request: Result<AiRequest, SerializationError>) -> Result<AiRequest, SerializationError> {
+        Ok(AiRequest::Other(self.apply(request)?))
+    }
+
+    /// The response body to log in place of `response`
+    pub fn apply_to_response(&self, response: Result<AiResponse, SerializationError>) -> Result<AiResponse, SerializationError> {
+        Ok(AiResponse::Other(self.apply(response)?))
+    }
+
+    fn apply<T: Serialize>(&self, body: Result<T, SerializationError>) -> Result<Value, SerializationError> {
+        let body = match body {
+            Ok(body) => serde_json::to_value(body).map_err(|e| SerializationError {
+                fallback_data: String::new(),
+                error: Box::new(e),
+            })?,
+            Err(e) => {
+                return Ok(match self {
+                    Self::HashOnly => sha256(e.fallback_data.as_bytes()),
+                    _ => Value::Null,
+                });
+            }
+        };
+        if body.is_null() {
+            return Ok(body);
+        }
+
+        Ok(match self {
+            Self::MetadataOnly => Value::Null,
+            Self::HashOnly => sha256(&serde_json::to_vec(&body).unwrap_or_default()),
+            Self::RedactFields(paths) => {
+                let mut body = body;
+                for path in paths {
+                    redact_path(&mut body, path);
+                }
+                body
+            }
+        })
+    }
+}
+
+fn sha256(bytes: &[u8]) -> Value {
+    json!({ "sha256": hex::encode(Sha256::digest(bytes)) })
+}
+
+/// Replace the values at `path` with the placeholder, traversing arrays along the way
+fn redact_path(value: &mut Value, path: &[String]) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| redact_path(item, path)),
+        Value::Object(map) => {
+            let Some((key, rest)) = path.split_first() else {
+                return;
+            };
+            let Some(field) = map.get_mut(key) else {
+                return;
+            };
+            if rest.is_empty() {
+                *field = Value::String(PATTERN_PLACEHOLDER.to_string());
+            } else {
+                redact_path(field, rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether the bodies of a request fall within a sample of `percent` of requests
+fn sampled(request_data: &RequestData, percent: u32) -> bool {
+    let timestamp = request_data
+        .timestamp
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let mut digest = Sha256::new();
+    digest.update(request_data.correlation_id.to_le_bytes());
+    digest.update(timestamp.to_le_bytes());
+    let bucket = u64::from_le_bytes(digest.finalize()[..8].try_into().expect("digest is 32 bytes")) % 100;
+    bucket < u64::from(percent)
+}
+
+/// The logging policy of the deployment a request is addressed to, if it has one. Requests
+/// outside the deployment's body sample are logged as metadata only.
+pub fn policy_for(table: &RoutingTable, request_data: &RequestData) -> Option<LoggingPolicy> {
+    if !table.has_logging_policies() {
+        return None;
+    }
+    let body = request_data.body.as_ref()?;
+    let request: Value = serde_json::from_slice(body).ok()?;
+    let alias = request.get("model")?.as_str()?;
+    match table.body_sample_percent(alias) {
+        Some(percent) if !sampled(request_data, percent) => Some(LoggingPolicy::MetadataOnly),
+        _ => table.logging_policy(alias).cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_logging::serializers::parse_ai_request;
+    use bytes::Bytes;
+
+    fn request_data(body: Value) -> RequestData {
+        RequestData {
+            correlation_id: 1,
+            timestamp: std::time::SystemTime::now(),
+            method: axum::http::Method::POST,
+            uri: "/ai/v1/chat/completions".parse().unwrap(),
+            headers: Default::default(),
+            body: Some(Bytes::from(body.to_string())),
+        }
+    }
+
+    fn logged(policy: &LoggingPolicy, body: Value) -> Value {
+        let request = parse_ai_request(&request_data(body));
+        serde_json::to_value(policy.apply_to_request(request).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_policies() {
+        let body = json!({
+            "model": "private",
+            "user": "alice",
+            "messages": [
+                {"role": "system", "content": "You are a doctor"},
+                {"role": "user", "content": "I have a rash"}
+            ]
+        });
+
+        assert_eq!(logged(&LoggingPolicy::MetadataOnly, body.clone()), Value::Null);
+
+        let hashed = logged(&LoggingPolicy::HashOnly, body.clone());
+        assert_eq!(hashed["sha256"].as_str().unwrap().len(), 64);
+        assert_eq!(hashed, logged(&LoggingPolicy::HashOnly, body.clone()));
+
+        let policy = LoggingPolicy::new("redact_fields", &["messages.content".to_string(), "user".to_string()]).unwrap();
+        let redacted = logged(&policy, body);
+        assert_eq!(redacted["model"], "private");
+        assert_eq!(redacted["user"], "[REDACTED]");
+        assert_eq!(redacted["messages"][0]["role"], "system");
+        assert_eq!(redacted["messages"][0]["content"], "[REDACTED]");
+        assert_eq!(redacted["messages"][1]["content"], "[REDACTED]");
+
+        assert_eq!(LoggingPolicy::new("full", &[]), None);
+    }
+
+    #[test]
+    fn test_unparseable_bodies_are_not_logged() {
+        let request = Err(SerializationError {
+            fallback_data: "base64:bm90IGpzb24=".to_string(),
+            error: "not JSON".into(),
+        });
+        let policy = LoggingPolicy::new("redact_fields", &["messages".to_string()]).unwrap();
+        assert!(matches!(policy.apply_to_request(request), Ok(AiRequest::Other(Value::Null))));
+    }
+
+    #[test]
+    fn test_policy_for() {
+        let mut table = RoutingTable::default();
+        assert_eq!(policy_for(&table, &request_data(json!({"model": "private"}))), None);
+
+        table.set_logging_policy("private".to_string(), LoggingPolicy::MetadataOnly);
+        assert_eq!(
+            policy_for(&table, &request_data(json!({"model": "private"}))),
+            Some(LoggingPolicy::MetadataOnly)
+        );
+        assert_eq!(policy_for(&table, &request_data(json!({"model": "public"}))), None);
+    }
+
+    #[test]
+    fn test_body_sampling() {
+        let mut table = RoutingTable::default();
+        table.set_body_sample_percent("sampled".to_string(), 100);
+        assert!(!table.has_logging_policies());
+
+        table.set_body_sample_percent("sampled".to_string(), 25);
+        table.set_logging_policy("sampled".to_string(), LoggingPolicy::HashOnly);
+        let requests: Vec<_> = (0..1000)
+            .map(|correlation_id| RequestData {
+                correlation_id,
+                ..request_data(json!({"model": "sampled"}))
+            })
+            .collect();
+
+        // Sampled requests keep the deployment's policy and the rest are logged as metadata only
+        let kept = requests
+            .iter()
+            .filter(|request| policy_for(&table, request) == Some(LoggingPolicy::HashOnly))
+            .count();
+        assert!((150..350).contains(&kept), "kept {kept} of 1000 bodies");
+        assert!(requests.iter().all(|request| matches!(
+            policy_for(&table, request),
+            Some(LoggingPolicy::HashOnly | LoggingPolicy::MetadataOnly)
+        )));
+
+        // The decision is the same each time a request is looked at
+        assert!(requests
+            .iter()
+            .all(|request| policy_for(&table, request) == policy_for(&table, request)));
+
+        table.set_body_sample_percent("none".to_string(), 0);
+        assert_eq!(
+            policy_for(&table, &request_data(json!({"model": "none"}))),
+            Some(LoggingPolicy::MetadataOnly)
+        );
+    }
+}
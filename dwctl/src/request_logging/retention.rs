@@ -0,0 +1,971 @@
+//! Retention of the request log.
+//!
+//! Logged requests and responses accumulate in the `outlet` schema indefinitely. When a retention
+//! policy is enabled, the leader replica periodically purges the rows older than the retention
+//! window, either deleting them or moving them to archive tables (`http_requests_archive` and
+//! `http_responses_archive`). Rows are purged in batches so no single statement holds locks on
+//! the tables for long, and the job stops between batches if another replica takes over as
+//! leader. In dry-run mode the expired rows are only counted.
+//!
+//! Groups can override the retention window for their members' requests (`PUT
+//! /groups/{id}/log-retention`), e.g. to keep one team's requests for a year and another's for 30
+//! days. The override of the Everyone group replaces the default window. Other overrides are
+//! either maximums (privacy limits) or minimums (legal holds): a request is kept for the shortest
+//! maximum among its user's groups, or the default without any, extended to their longest
+//! minimum, see [`effective_retention`].
+//!
+//! The export action keeps the log in object storage instead. The expired requests of each whole
+//! day past the retention window are uploaded as JSON Lines objects of up to `batch_size`
+//! requests (one request with its response per line, under `<prefix>request-log/YYYY/MM/DD/`),
+//! each recorded in the `request_log_archives` manifest, and only then deleted. Objects are named
+//! after the first request they hold, so an export interrupted before deleting an object's
+//! requests uploads the same object again, and never loses them. Purging a user rewrites the objects holding their
+//! requests without them, see [`redact_archive`].
+
+use crate::api::models::groups::LogRetentionKind;
+use crate::api::models::requests::RequestLogArchive;
+use crate::config::{RequestLogRetentionConfig, RetentionAction};
+use crate::leader::LeaderFence;
+use crate::request_logging::storage::{BodyStorage, S3Store};
+use bytes::Bytes;
+use chrono::{DateTime, Duration, Utc};
+use prometheus::{IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry};
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Request log tables purged by the retention policy, in the `outlet` schema
+const TABLES: [&str; 2] = ["http_requests", "http_responses"];
+
+impl RetentionAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RetentionAction::Delete => "delete",
+            RetentionAction::Archive => "archive",
+            RetentionAction::Export => "export",
+        }
+    }
+}
+
+/// Prometheus instruments describing the purge job
+#[derive(Clone)]
+struct RetentionMetrics {
+    /// Rows purged, by table and action
+    purged: IntCounterVec,
+    /// Rows past the retention window found by the latest dry run, by table
+    expired: IntGaugeVec,
+    /// Unix time of the latest completed purge
+    last_run: IntGauge,
+}
+
+impl RetentionMetrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            purged: IntCounterVec::new(
+                Opts::new(
+                    "dwctl_request_log_purged_rows_total",
+                    "Request log rows purged by the retention policy",
+                ),
+                &["table", "action"],
+            )?,
+            expired: IntGaugeVec::new(
+                Opts::new(
+                    "dwctl_request_log_expired_rows",
+                    "Request log rows past the retention window, as counted by the latest dry run",
+                ),
+                &["table"],
+            )?,
+            last_run: IntGauge::new(
+                "dwctl_request_log_last_purge_timestamp_seconds",
+                "Unix time of the latest completed request log purge",
+            )?,
+        })
+    }
+
+    fn register(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        registry.register(Box::new(self.purged.clone()))?;
+        registry.register(Box::new(self.expired.clone()))?;
+        registry.register(Box::new(self.last_run.clone()))?;
+        Ok(())
+    }
+}
+
+/// Rows purged (or, in a dry run, that would be purged) from a table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PurgedRows {
+    pub table: &'static str,
+    pub rows: u64,
+}
+
+/// When request log rows expire. Requests are kept for the default retention window, or for the
+/// retention resolved from the group overrides of their user, see [`effective_retention`].
+/// Each user's retention is resolved once per run, and joined to the rows by their analytics.
+struct Expiry {
+    /// Time the retention windows end at, e.g. now
+    now: DateTime<Utc>,
+    /// Retention window of requests whose user has no group overrides, in seconds
+    default_secs: f64,
+    /// End of the shortest retention window, no row newer than which has expired
+    latest: DateTime<Utc>,
+    /// Users with group overrides, and the retention of their requests in seconds
+    user_ids: Vec<Uuid>,
+    user_secs: Vec<f64>,
+}
+
+impl Expiry {
+    /// The expiry of rows past the retention windows ending at `cutoff` + the default window
+    async fn load(pool: &PgPool, config: &RequestLogRetentionConfig, cutoff: DateTime<Utc>) -> Result<Self, sqlx::Error> {
+        let everyone =
+            sqlx::query_scalar!("SELECT retention_days FROM group_log_retention WHERE group_id = '00000000-0000-0000-0000-000000000000'")
+                .fetch_optional(pool)
+                .await?;
+        let rows = sqlx::query!(
+            r#"
+            SELECT ug.user_id as "user_id!", lr.retention_days, lr.kind
+            FROM effective_user_groups ug
+            JOIN group_log_retention lr ON lr.group_id = ug.group_id
+            WHERE lr.group_id <> '00000000-0000-0000-0000-000000000000'
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let default = everyone.map_or(config.retention, days);
+        let mut overrides: BTreeMap<Uuid, Vec<(i32, LogRetentionKind)>> = BTreeMap::new();
+        for row in rows {
+            overrides
+                .entry(row.user_id)
+                .or_default()
+                .push((row.retention_days, LogRetentionKind::parse(&row.kind)));
+        }
+        let (user_ids, user_secs): (Vec<_>, Vec<_>) = overrides
+            .into_iter()
+            .map(|(user_id, overrides)| (user_id, resolve_retention(default, overrides).as_secs_f64()))
+            .unzip();
+
+        let now = cutoff + Duration::milliseconds(config.retention.as_millis() as i64);
+        let default_secs = default.as_secs_f64();
+        let shortest_secs = user_secs.iter().copied().fold(default_secs, f64::min);
+        Ok(Self {
+            now,
+            default_secs,
+            latest: now - Duration::milliseconds((shortest_secs * 1000.0) as i64),
+            user_ids,
+            user_secs,
+        })
+    }
+
+    /// Joins of the request log row `alias` to the retention of its user, for [`Expiry::condition`]
+    fn joins(alias: &str) -> String {
+        format!(
+            "LEFT JOIN http_analytics ea ON (ea.instance_id = {alias}.instance_id AND ea.correlation_id = {alias}.correlation_id)
+             LEFT JOIN unnest($4::uuid[], $5::float8[]) AS er(user_id, retention_secs) ON er.user_id = ea.user_id"
+        )
+    }
+
+    /// SQL condition on the request log row `alias`, joined with [`Expiry::joins`], that holds
+    /// once it has expired. Their parameters are `$1` to `$5`, see [`Expiry::bind`].
+    fn condition(alias: &str) -> String {
+        format!("{alias}.timestamp < $3 AND {alias}.timestamp < $1 - make_interval(secs => COALESCE(er.retention_secs, $2))")
+    }
+
+    fn bind<'q, O>(
+        &self,
+        query: sqlx::query::QueryAs<'q, sqlx::Postgres, O, sqlx::postgres::PgArguments>,
+    ) -> sqlx::query::QueryAs<'q, sqlx::Postgres, O, sqlx::postgres::PgArguments> {
+        query
+            .bind(self.now)
+            .bind(self.default_secs)
+            .bind(self.latest)
+            .bind(self.user_ids.clone())
+            .bind(self.user_secs.clone())
+    }
+}
+
+/// Seconds in a day, the unit of group retention overrides
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+fn days(days: i32) -> std::time::Duration {
+    std::time::Duration::from_secs(u64::try_from(days).unwrap_or_default() * DAY_SECS)
+}
+
+/// Retention of the requests of a user with the given group overrides, in days, given the
+/// retention of users without any.
+fn resolve_retention(default: std::time::Duration, overrides: impl IntoIterator<Item = (i32, LogRetentionKind)>) -> std::time::Duration {
+    let (mut maximum, mut minimum) = (None::<i32>, None::<i32>);
+    for (override_days, kind) in overrides {
+        match kind {
+            LogRetentionKind::Maximum => maximum = Some(maximum.map_or(override_days, |max| max.min(override_days))),
+            LogRetentionKind::Minimum => minimum = Some(minimum.map_or(override_days, |min| min.max(override_days))),
+        }
+    }
+    let retention = maximum.map_or(default, days);
+    minimum.map_or(retention, |minimum| retention.max(days(minimum)))
+}
+
+/// How long the requests of a user are kept, given the override of the Everyone group and those
+/// of the user's other groups (in days), or `None` if the retention policy is disabled and
+/// they're kept indefinitely.
+///
+/// This is the strictest applicable policy: the Everyone override replaces the default window,
+/// the shortest maximum of the user's groups replaces that, and the longest minimum extends the
+/// result, so a legal hold is never cut short by a privacy limit or the default.
+pub fn effective_retention(
+    config: &RequestLogRetentionConfig,
+    everyone: Option<i32>,
+    overrides: impl IntoIterator<Item = (i32, LogRetentionKind)>,
+) -> Option<std::time::Duration> {
+    if !config.enabled {
+        return None;
+    }
+    Some(resolve_retention(everyone.map_or(config.retention, days), overrides))
+}
+
+/// Purge the request log rows past their retention window, `cutoff` for requests without group
+/// overrides, exporting them to `archive` first if the policy's action is export.
+///
+/// Returns `None` if the request log tables don't exist, or if leadership was lost part way
+/// through (in which case the remaining rows are left to the new leader).
+pub async fn purge_expired(
+    pool: &PgPool,
+    config: &RequestLogRetentionConfig,
+    archive: Option<&BodyStorage>,
+    fence: &LeaderFence,
+    cutoff: DateTime<Utc>,
+) -> anyhow::Result<Option<Vec<PurgedRows>>> {
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass('outlet.http_requests') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        tracing::debug!("Request log tables don't exist, nothing to purge");
+        return Ok(None);
+    }
+
+    if config.action == RetentionAction::Export && !config.dry_run {
+        let archive = archive.ok_or_else(|| anyhow::anyhow!("No bucket is configured to export the request log to"))?;
+        return export_expired(pool, config, archive, fence, cutoff).await;
+    }
+
+    let expiry = Expiry::load(pool, config, cutoff).await?;
+    let (joins, expired) = (Expiry::joins("t"), Expiry::condition("t"));
+    let mut purged = Vec::with_capacity(TABLES.len());
+    for table in TABLES {
+        if config.dry_run {
+            let (rows,): (i64,) = expiry
+                .bind(sqlx::query_as(&format!(
+                    "SELECT COUNT(*) FROM outlet.{table} t {joins} WHERE {expired}"
+                )))
+                .fetch_one(pool)
+                .await?;
+            purged.push(PurgedRows { table, rows: rows as u64 });
+            continue;
+        }
+
+        let statement = match config.action {
+            RetentionAction::Delete | RetentionAction::Export => {
+                format!(
+                    "DELETE FROM outlet.{table} WHERE id IN (SELECT t.id FROM outlet.{table} t {joins} WHERE {expired} ORDER BY t.id LIMIT $6)"
+                )
+            }
+            RetentionAction::Archive => {
+                sqlx::query(&format!(
+                    "CREATE TABLE IF NOT EXISTS outlet.{table}_archive (LIKE outlet.{table} INCLUDING DEFAULTS)"
+                ))
+                .execute(pool)
+                .await?;
+                format!(
+                    "WITH moved AS (
+                         DELETE FROM outlet.{table}
+                         WHERE id IN (SELECT t.id FROM outlet.{table} t {joins} WHERE {expired} ORDER BY t.id LIMIT $6)
+                         RETURNING *
+                     )
+                     INSERT INTO outlet.{table}_archive SELECT * FROM moved"
+                )
+            }
+        };
+
+        let mut rows = 0;
+        loop {
+            // Leave the rest to the new leader if another replica has taken over
+            if !fence.is_current().await {
+                return Ok(None);
+            }
+            let batch = sqlx::query(&statement)
+                .bind(expiry.now)
+                .bind(expiry.default_secs)
+                .bind(expiry.latest)
+                .bind(&expiry.user_ids)
+                .bind(&expiry.user_secs)
+                .bind(config.batch_size)
+                .execute(pool)
+                .await?
+                .rows_affected();
+            rows += batch;
+            if batch < config.batch_size as u64 {
+                break;
+            }
+        }
+        purged.push(PurgedRows { table, rows });
+    }
+    Ok(Some(purged))
+}
+
+/// Export the expired requests of whole days before `cutoff` to `archive`, deleting each object's
+/// requests once it has been uploaded.
+async fn export_expired(
+    pool: &PgPool,
+    config: &RequestLogRetentionConfig,
+    archive: &BodyStorage,
+    fence: &LeaderFence,
+    cutoff: DateTime<Utc>,
+) -> anyhow::Result<Option<Vec<PurgedRows>>> {
+    // Only days entirely past the retention window are exported, so each is exported once. With
+    // group overrides, requests held for longer are exported from their day once they expire.
+    let cutoff = cutoff.date_naive().and_time(chrono::NaiveTime::MIN).and_utc();
+    let expiry = Expiry::load(pool, config, cutoff).await?;
+    let (joins, expired) = (Expiry::joins("r"), Expiry::condition("r"));
+    let (mut requests, mut responses) = (0, 0);
+
+    loop {
+        let (oldest,): (Option<DateTime<Utc>>,) = expiry
+            .bind(sqlx::query_as(&format!(
+                "SELECT MIN(r.timestamp) FROM outlet.http_requests r {joins} WHERE {expired}"
+            )))
+            .fetch_one(pool)
+            .await?;
+        let Some(oldest) = oldest else { break };
+        let day = oldest.date_naive().and_time(chrono::NaiveTime::MIN).and_utc();
+        let next_day = day + Duration::days(1);
+
+        // Upload the day in objects of up to `batch_size` requests, deleting each object's
+        // requests and their responses once it's recorded
+        let mut last_id = 0;
+        loop {
+            if !fence.is_current().await {
+                return Ok(None);
+            }
+            let lines: Vec<(i64, String)> = expiry
+                .bind(sqlx::query_as(&format!(
+                    "SELECT r.id, jsonb_build_object('request', to_jsonb(r), 'response', to_jsonb(res))::text
+                     FROM outlet.http_requests r
+                     LEFT JOIN outlet.http_responses res ON (r.instance_id = res.instance_id AND r.correlation_id = res.correlation_id)
+                     {joins}
+                     WHERE r.timestamp >= $6 AND r.timestamp < $7 AND r.id > $8 AND {expired}
+                     ORDER BY r.id
+                     LIMIT $9"
+                )))
+                .bind(day)
+                .bind(next_day)
+                .bind(last_id)
+                .bind(config.batch_size)
+                .fetch_all(pool)
+                .await?;
+            let (Some((first_id, _)), Some((end_id, _))) = (lines.first(), lines.last()) else {
+                break;
+            };
+
+            let name = format!("request-log/{}/{first_id}.jsonl", day.format("%Y/%m/%d"));
+            last_id = *end_id;
+            let count = lines.len();
+            let mut ids = Vec::with_capacity(count);
+            let mut body = Vec::new();
+            for (id, line) in lines {
+                ids.push(id);
+                body.extend_from_slice(line.as_bytes());
+                body.push(b'\n');
+            }
+            let size = body.len();
+            let key = archive.put(&name, Bytes::from(body)).await?;
+            sqlx::query!(
+                "INSERT INTO request_log_archives (range_start, range_end, object_key, request_count, size_bytes)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (object_key) DO UPDATE SET
+                     request_count = EXCLUDED.request_count,
+                     size_bytes = EXCLUDED.size_bytes,
+                     archived_at = NOW()",
+                day,
+                next_day,
+                key,
+                count as i64,
+                size as i64
+            )
+            .execute(pool)
+            .await?;
+
+            let (batch_requests, batch_responses): (i64, i64) = sqlx::query_as(
+                "WITH deleted AS (
+                     DELETE FROM outlet.http_requests
+                     WHERE id = ANY($1)
+                     RETURNING instance_id, correlation_id
+                 ),
+                 deleted_responses AS (
+                     DELETE FROM outlet.http_responses res
+                     USING deleted
+                     WHERE res.instance_id = deleted.instance_id AND res.correlation_id = deleted.correlation_id
+                     RETURNING 1
+                 )
+                 SELECT (SELECT COUNT(*) FROM deleted), (SELECT COUNT(*) FROM deleted_responses)",
+            )
+            .bind(&ids)
+            .fetch_one(pool)
+            .await?;
+            requests += batch_requests as u64;
+            responses += batch_responses as u64;
+
+            if (count as i64) < config.batch_size {
+                break;
+            }
+        }
+    }
+
+    Ok(Some(vec![
+        PurgedRows {
+            table: "http_requests",
+            rows: requests,
+        },
+        PurgedRows {
+            table: "http_responses",
+            rows: responses,
+        },
+    ]))
+}
+
+/// The exported ranges of the request log overlapping `start`..`end`, oldest first
+pub async fn list_archives(
+    pool: &PgPool,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<RequestLogArchive>, sqlx::Error> {
+    sqlx::query_as!(
+        RequestLogArchive,
+        "SELECT id, range_start, range_end, object_key, format, request_count, size_bytes, archived_at
+         FROM request_log_archives
+         WHERE ($1::timestamptz IS NULL OR range_end > $1) AND ($2::timestamptz IS NULL OR range_start < $2)
+         ORDER BY range_start, object_key",
+        start,
+        end
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// The bucket the export action writes to, if one is configured
+pub fn export_storage(config: &RequestLogRetentionConfig) -> anyhow::Result<Option<BodyStorage>> {
+    match &config.export {
+        Some(s3) => Ok(Some(BodyStorage::new(Arc::new(S3Store::new(s3.clone())?), s3.prefix.clone()))),
+        None => Ok(None),
+    }
+}
+
+/// Rewrite the exported object `object_key` without `requests`, given by (instance ID,
+/// correlation ID), and update its manifest entry to match. An object left empty is deleted
+/// along with its entry. Returns the number of requests removed.
+pub async fn redact_archive(
+    pool: &PgPool,
+    archive: &BodyStorage,
+    object_key: &str,
+    requests: &HashSet<(Uuid, i64)>,
+) -> anyhow::Result<u64> {
+    let object = archive.get(object_key).await?;
+    let mut kept = Vec::with_capacity(object.len());
+    let (mut removed, mut count) = (0, 0);
+    for line in object.split(|b| *b == b'\n').filter(|line| !line.is_empty()) {
+        let line_request = serde_json::from_slice::<serde_json::Value>(line).ok().and_then(|line| {
+            let request = line.get("request")?;
+            let instance_id = request.get("instance_id")?.as_str()?.parse().ok()?;
+            Some((instance_id, request.get("correlation_id")?.as_i64()?))
+        });
+        if line_request.is_some_and(|request| requests.contains(&request)) {
+            removed += 1;
+            continue;
+        }
+        kept.extend_from_slice(line);
+        kept.push(b'\n');
+        count += 1;
+    }
+    if removed == 0 {
+        return Ok(0);
+    }
+
+    if count == 0 {
+        archive.delete(object_key).await?;
+        sqlx::query!("DELETE FROM request_log_archives WHERE object_key = $1", object_key)
+            .execute(pool)
+            .await?;
+    } else {
+        let size = kept.len();
+        archive.replace(object_key, Bytes::from(kept)).await?;
+        sqlx::query!(
+            "UPDATE request_log_archives SET request_count = $2, size_bytes = $3 WHERE object_key = $1",
+            object_key,
+            count as i64,
+            size as i64
+        )
+        .execute(pool)
+        .await?;
+    }
+    Ok(removed)
+}
+
+/// Background task that applies the request log retention policy on a fixed interval.
+///
+/// Like the probe scheduler, this only runs on the leader replica.
+#[derive(Clone)]
+pub struct RequestLogRetention {
+    pool: PgPool,
+    config: RequestLogRetentionConfig,
+    archive: Option<BodyStorage>,
+    fence: LeaderFence,
+    metrics: RetentionMetrics,
+    handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl RequestLogRetention {
+    pub fn new(pool: PgPool, config: RequestLogRetentionConfig, fence: LeaderFence) -> anyhow::Result<Self> {
+        let archive = export_storage(&config)?;
+        Ok(Self {
+            pool,
+            config,
+            archive,
+            fence,
+            metrics: RetentionMetrics::new()?,
+            handle: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Register the purge job's metrics with `registry`
+    pub fn register_metrics(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        self.metrics.register(registry)
+    }
+
+    /// Start the purge loop, if enabled and not already running.
+    pub async fn start(&self) {
+        if !self.config.enabled {
+            tracing::info!("Request log retention is disabled");
+            return;
+        }
+
+        let mut handle = self.handle.lock().await;
+        if handle.is_some() {
+            return;
+        }
+
+        let pool = self.pool.clone();
+        let config = self.config.clone();
+        let archive = self.archive.clone();
+        let fence = self.fence.clone();
+        let metrics = self.metrics.clone();
+        *handle = Some(tokio::spawn(async move {
+            loop {
+                if !fence.is_current().await {
+                    tracing::warn!("Leadership is stale, stopping request log retention");
+                    break;
+                }
+                let cutoff = Utc::now() - config.retention;
+                match purge_expired(&pool, &config, archive.as_ref(), &fence, cutoff).await {
+                    Ok(Some(purged)) => {
+                        for PurgedRows { table, rows } in purged {
+                            if config.dry_run {
+                                metrics.expired.with_label_values(&[table]).set(rows as i64);
+                                tracing::info!("Dry run: {} rows of {} are older than {}", rows, table, cutoff);
+                            } else {
+                                metrics.purged.with_label_values(&[table, config.action.as_str()]).inc_by(rows);
+                                tracing::info!("Purged {} rows of {} older than {}", rows, table, cutoff);
+                            }
+                        }
+                        metrics.last_run.set(Utc::now().timestamp());
+                    }
+                    Ok(None) => {}
+                    Err(e) => tracing::error!("Failed to purge expired request logs: {}", e),
+                }
+                tokio::time::sleep(config.interval).await;
+            }
+        }));
+
+        tracing::info!(
+            "Started request log retention (keeping {}, every {}s)",
+            humantime::format_duration(self.config.retention),
+            self.config.interval.as_secs()
+        );
+    }
+
+    /// Stop the purge loop (called when losing leadership).
+    pub async fn stop(&self) {
+        if let Some(handle) = self.handle.lock().await.take() {
+            handle.abort();
+            tracing::info!("Stopped request log retention");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_logging::storage::BodyStore;
+    use sqlx::Executor;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Default)]
+    struct MemoryStore(std::sync::Mutex<HashMap<String, Bytes>>);
+
+    #[async_trait::async_trait]
+    impl BodyStore for MemoryStore {
+        async fn put(&self, key: &str, body: Bytes) -> anyhow::Result<()> {
+            self.0.lock().unwrap().insert(key.to_string(), body);
+            Ok(())
+        }
+
+        async fn get(&self, key: &str) -> anyhow::Result<Bytes> {
+            self.0
+                .lock()
+                .unwrap()
+                .get(key)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("no such key"))
+        }
+
+        async fn delete(&self, key: &str) -> anyhow::Result<()> {
+            self.0.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    async fn setup_request_log(pool: &PgPool) -> LeaderFence {
+        pool.execute("CREATE SCHEMA outlet").await.unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+        conn.execute("SET search_path = 'outlet'").await.unwrap();
+        outlet_postgres::migrator().run(&mut *conn).await.unwrap();
+        conn.execute("SET search_path = 'public'").await.unwrap();
+
+        for (correlation_id, days_ago) in [(1, 120), (2, 100), (3, 95), (4, 10)] {
+            sqlx::query(
+                "INSERT INTO outlet.http_requests (instance_id, correlation_id, timestamp, method, uri, headers)
+                 VALUES ($1, $2, NOW() - make_interval(days => $3), 'POST', '/ai/v1/chat/completions', '{}')",
+            )
+            .bind(uuid::Uuid::nil())
+            .bind(correlation_id as i64)
+            .bind(days_ago)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO outlet.http_responses
+                     (instance_id, correlation_id, timestamp, status_code, headers, duration_ms, duration_to_first_byte_ms)
+                 VALUES ($1, $2, NOW() - make_interval(days => $3), 200, '{}', 100, 10)",
+            )
+            .bind(uuid::Uuid::nil())
+            .bind(correlation_id as i64)
+            .bind(days_ago)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        }
+
+        let fence = LeaderFence::new(pool.clone(), 1);
+        fence.acquire(&mut conn).await.unwrap();
+        fence
+    }
+
+    async fn count(pool: &PgPool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM outlet.{table}"))
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_purge_expired(pool: PgPool) {
+        let fence = setup_request_log(&pool).await;
+        let cutoff = Utc::now() - chrono::Duration::days(90);
+        let expired = |rows| {
+            Some(vec![
+                PurgedRows {
+                    table: "http_requests",
+                    rows,
+                },
+                PurgedRows {
+                    table: "http_responses",
+                    rows,
+                },
+            ])
+        };
+
+        // A dry run only counts the expired rows
+        let config = RequestLogRetentionConfig {
+            enabled: true,
+            batch_size: 2,
+            dry_run: true,
+            ..Default::default()
+        };
+        assert_eq!(purge_expired(&pool, &config, None, &fence, cutoff).await.unwrap(), expired(3));
+        assert_eq!(count(&pool, "http_requests").await, 4);
+
+        // Expired rows are deleted across several batches
+        let config = RequestLogRetentionConfig { dry_run: false, ..config };
+        assert_eq!(purge_expired(&pool, &config, None, &fence, cutoff).await.unwrap(), expired(3));
+        assert_eq!(count(&pool, "http_requests").await, 1);
+        assert_eq!(count(&pool, "http_responses").await, 1);
+
+        // A former leader leaves the purge to the new one
+        let stale = LeaderFence::new(pool.clone(), 1);
+        stale.acquire(&mut pool.acquire().await.unwrap()).await.unwrap();
+        let cutoff = Utc::now();
+        assert_eq!(purge_expired(&pool, &config, None, &fence, cutoff).await.unwrap(), None);
+        assert_eq!(count(&pool, "http_requests").await, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_purge_expired_with_group_overrides(pool: PgPool) {
+        use crate::api::models::users::Role;
+        use crate::test_utils::{add_user_to_group, create_test_group, create_test_user};
+
+        let fence = setup_request_log(&pool).await;
+        let cutoff = Utc::now() - chrono::Duration::days(90);
+        let config = RequestLogRetentionConfig {
+            enabled: true,
+            ..Default::default()
+        };
+
+        // Legal must keep its members' requests for a year, a short-lived team may only keep
+        // theirs for a week
+        let (legal, short) = (create_test_group(&pool).await, create_test_group(&pool).await);
+        let (lawyer, intern) = (
+            create_test_user(&pool, Role::StandardUser).await,
+            create_test_user(&pool, Role::StandardUser).await,
+        );
+        add_user_to_group(&pool, lawyer.id, legal.id).await;
+        add_user_to_group(&pool, lawyer.id, short.id).await;
+        add_user_to_group(&pool, intern.id, short.id).await;
+        let set_retention = |group: Uuid, days: i32, kind: &'static str| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query(
+                    "INSERT INTO group_log_retention (group_id, retention_days, kind) VALUES ($1, $2, $3)
+                     ON CONFLICT (group_id) DO UPDATE SET retention_days = EXCLUDED.retention_days, kind = EXCLUDED.kind",
+                )
+                .bind(group)
+                .bind(days)
+                .bind(kind)
+                .execute(&pool)
+                .await
+                .unwrap();
+            }
+        };
+        set_retention(legal.id, 365, "minimum").await;
+        set_retention(short.id, 7, "maximum").await;
+        // Requests 1 and 2 (120 and 100 days old) are the lawyer's, 4 (10 days old) the
+        // intern's, and 3 (95 days old) nobody's
+        for (correlation_id, user) in [(1_i64, &lawyer), (2, &lawyer), (4, &intern)] {
+            sqlx::query(
+                "INSERT INTO http_analytics (instance_id, correlation_id, timestamp, method, uri, user_id)
+                 VALUES ($1, $2, NOW(), 'POST', '/ai/v1/chat/completions', $3)",
+            )
+            .bind(Uuid::nil())
+            .bind(correlation_id)
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let logged = || async {
+            sqlx::query_scalar::<_, i64>("SELECT correlation_id FROM outlet.http_requests ORDER BY correlation_id")
+                .fetch_all(&pool)
+                .await
+                .unwrap()
+        };
+
+        // The legal hold wins over the lawyer's other group, and the intern's requests are kept
+        // for a week only
+        let dry_run = RequestLogRetentionConfig {
+            dry_run: true,
+            ..config.clone()
+        };
+        let purged = purge_expired(&pool, &dry_run, None, &fence, cutoff).await.unwrap().unwrap();
+        assert_eq!(purged.iter().map(|purged| purged.rows).collect::<Vec<_>>(), vec![2, 2]);
+        purge_expired(&pool, &config, None, &fence, cutoff).await.unwrap();
+        assert_eq!(logged().await, vec![1, 2]);
+
+        // An override of the Everyone group replaces the default, without capping other groups
+        set_retention(Uuid::nil(), 30, "maximum").await;
+        purge_expired(&pool, &config, None, &fence, cutoff).await.unwrap();
+        assert_eq!(logged().await, vec![1, 2]);
+
+        set_retention(legal.id, 110, "minimum").await;
+        purge_expired(&pool, &config, None, &fence, cutoff).await.unwrap();
+        assert_eq!(logged().await, vec![2]);
+
+        // Exports hold expired requests back the same way
+        let store = Arc::new(MemoryStore::default());
+        let archive = BodyStorage::new(store.clone(), "archive/".to_string());
+        let export = RequestLogRetentionConfig {
+            action: RetentionAction::Export,
+            ..config.clone()
+        };
+        purge_expired(&pool, &export, Some(&archive), &fence, Utc::now() - chrono::Duration::days(85))
+            .await
+            .unwrap();
+        assert_eq!(logged().await, vec![2]);
+        assert!(store.0.lock().unwrap().is_empty());
+        purge_expired(&pool, &export, Some(&archive), &fence, Utc::now() - chrono::Duration::days(70))
+            .await
+            .unwrap();
+        assert!(logged().await.is_empty());
+        assert_eq!(store.0.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_effective_retention() {
+        use LogRetentionKind::{Maximum, Minimum};
+
+        let config = RequestLogRetentionConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        assert_eq!(effective_retention(&config, None, []), Some(config.retention));
+        assert_eq!(effective_retention(&config, Some(30), []), Some(days(30)));
+        // The shortest maximum replaces the default, even if it's longer
+        assert_eq!(
+            effective_retention(&config, Some(30), [(365, Maximum), (180, Maximum)]),
+            Some(days(180))
+        );
+        // The longest minimum extends it, and wins over maximums
+        assert_eq!(
+            effective_retention(&config, Some(30), [(365, Minimum), (60, Minimum)]),
+            Some(days(365))
+        );
+        assert_eq!(effective_retention(&config, None, [(7, Maximum), (365, Minimum)]), Some(days(365)));
+        assert_eq!(effective_retention(&config, None, [(7, Minimum)]), Some(config.retention));
+        assert_eq!(effective_retention(&RequestLogRetentionConfig::default(), Some(30), []), None);
+    }
+
+    #[sqlx::test]
+    async fn test_purge_expired_archives(pool: PgPool) {
+        let fence = setup_request_log(&pool).await;
+        let config = RequestLogRetentionConfig {
+            enabled: true,
+            action: RetentionAction::Archive,
+            ..Default::default()
+        };
+        let cutoff = Utc::now() - chrono::Duration::days(90);
+
+        purge_expired(&pool, &config, None, &fence, cutoff).await.unwrap();
+        assert_eq!(count(&pool, "http_requests").await, 1);
+        assert_eq!(count(&pool, "http_requests_archive").await, 3);
+        assert_eq!(count(&pool, "http_responses_archive").await, 3);
+    }
+
+    #[sqlx::test]
+    async fn test_purge_expired_exports(pool: PgPool) {
+        let fence = setup_request_log(&pool).await;
+        let store = Arc::new(MemoryStore::default());
+        let archive = BodyStorage::new(store.clone(), "archive/".to_string());
+        let config = RequestLogRetentionConfig {
+            enabled: true,
+            action: RetentionAction::Export,
+            batch_size: 2,
+            ..Default::default()
+        };
+        let cutoff = Utc::now() - chrono::Duration::days(90);
+
+        let purged = purge_expired(&pool, &config, Some(&archive), &fence, cutoff)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(purged.iter().map(|purged| purged.rows).collect::<Vec<_>>(), vec![3, 3]);
+        assert_eq!(count(&pool, "http_requests").await, 1);
+        assert_eq!(count(&pool, "http_responses").await, 1);
+
+        // Each expired day is one object here, listed in the manifest
+        let archives = list_archives(&pool, None, None).await.unwrap();
+        assert_eq!(archives.len(), 3);
+        assert_eq!(archives.iter().map(|archive| archive.request_count).sum::<i64>(), 3);
+        let objects = store.0.lock().unwrap().clone();
+        for archive in &archives {
+            assert!(archive
+                .object_key
+                .starts_with(&format!("archive/request-log/{}/", archive.range_start.format("%Y/%m/%d"))));
+            let lines: Vec<serde_json::Value> = objects[&archive.object_key]
+                .split(|b| *b == b'\n')
+                .filter(|line| !line.is_empty())
+                .map(|line| serde_json::from_slice(line).unwrap())
+                .collect();
+            assert_eq!(lines.len(), 1);
+            assert_eq!(lines[0]["request"]["uri"], "/ai/v1/chat/completions");
+            assert_eq!(lines[0]["response"]["status_code"], 200);
+        }
+
+        let since = Utc::now() - chrono::Duration::days(101);
+        assert_eq!(list_archives(&pool, Some(since), None).await.unwrap().len(), 2);
+
+        // Without a bucket nothing is deleted
+        assert!(purge_expired(&pool, &config, None, &fence, Utc::now()).await.is_err());
+        assert_eq!(count(&pool, "http_requests").await, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_purge_without_request_log(pool: PgPool) {
+        let fence = LeaderFence::new(pool.clone(), 1);
+        let config = RequestLogRetentionConfig::default();
+        assert_eq!(purge_expired(&pool, &config, None, &fence, Utc::now()).await.unwrap(), None);
+    }
+
+    #[sqlx::test]
+    async fn test_redact_archive(pool: PgPool) {
+        let fence = setup_request_log(&pool).await;
+        let store = Arc::new(MemoryStore::default());
+        let archive = BodyStorage::new(store.clone(), "archive/".to_string());
+        let config = RequestLogRetentionConfig {
+            enabled: true,
+            action: RetentionAction::Export,
+            ..Default::default()
+        };
+        let requests: Vec<(Uuid, i64)> = sqlx::query_as("SELECT instance_id, correlation_id FROM outlet.http_requests ORDER BY timestamp")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+
+        // Export everything into one object per day, then put two days' requests in one object
+        purge_expired(&pool, &config, Some(&archive), &fence, Utc::now() + chrono::Duration::days(1))
+            .await
+            .unwrap()
+            .unwrap();
+        let archives = list_archives(&pool, None, None).await.unwrap();
+        let (first, second) = (&archives[0].object_key, &archives[1].object_key);
+        {
+            let mut objects = store.0.lock().unwrap();
+            let merged = [objects[first].clone(), objects.remove(second).unwrap()].concat();
+            objects.insert(first.clone(), Bytes::from(merged));
+        }
+        sqlx::query("UPDATE request_log_archives SET request_count = 2 WHERE object_key = $1")
+            .bind(first)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Removing one request rewrites the object without it
+        let removed = redact_archive(&pool, &archive, first, &HashSet::from([requests[1]])).await.unwrap();
+        assert_eq!(removed, 1);
+        let object = store.0.lock().unwrap()[first].clone();
+        let line: serde_json::Value = serde_json::from_slice(object.strip_suffix(b"\n").unwrap()).unwrap();
+        assert_eq!(line["request"]["correlation_id"], requests[0].1);
+        let entry = &list_archives(&pool, None, None).await.unwrap()[0];
+        assert_eq!((entry.request_count, entry.size_bytes), (1, object.len() as i64));
+
+        // Requests the object doesn't hold leave it alone
+        let removed = redact_archive(&pool, &archive, first, &HashSet::from([requests[2]])).await.unwrap();
+        assert_eq!(removed, 0);
+
+        // Removing the last request deletes the object and its manifest entry
+        let removed = redact_archive(&pool, &archive, first, &HashSet::from([requests[0]])).await.unwrap();
+        assert_eq!(removed, 1);
+        assert!(!store.0.lock().unwrap().contains_key(first));
+        assert!(list_archives(&pool, None, None)
+            .await
+            .unwrap()
+            .iter()
+            .all(|entry| &entry.object_key != first));
+    }
+}
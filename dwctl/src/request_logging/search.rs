@@ -0,0 +1,128 @@
+//! Full-text search over logged request and response bodies.
+//!
+//! Bodies are indexed with GIN indexes over `to_tsvector('simple', body)`, which covers every
+//! string value in the stored JSON (message contents, prompts, completions and raw bodies that
+//! failed to parse) without stemming, so identifiers and names match as written. Searches use
+//! `websearch_to_tsquery` syntax: words are ANDed, `"quoted phrases"` match in order, `or`
+//! separates alternatives and `-word` excludes a word. A request matches if either its request
+//! body or its response body matches the search on its own.
+//!
+//! Bodies kept in object storage rather than the outlet tables aren't searchable.
+
+use outlet_postgres::RequestFilter;
+use sqlx::{Executor, PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{api::models::requests::RequestCursor, db::handlers::pagination::push_after_cursor};
+
+/// Create the body search indexes on the request log tables, if they don't exist yet.
+///
+/// Indexes are built concurrently so that logging isn't blocked while a large log is indexed.
+pub async fn create_search_indexes(pool: &PgPool) -> Result<(), sqlx::Error> {
+    for table in ["http_requests", "http_responses"] {
+        pool.execute(
+            format!("CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_{table}_body_search ON {table} USING GIN (to_tsvector('simple', body))")
+                .as_str(),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Find the logged requests whose request or response body matches `search`.
+///
+/// Applies the same filters, ordering and pagination as [`outlet_postgres::RequestRepository`]
+/// queries, starting after `after` if given, and returns the `(instance_id, correlation_id)` keys
+/// of the matching requests in order.
+pub async fn search_requests(
+    pool: &PgPool,
+    search: &str,
+    filter: &RequestFilter,
+    after: Option<&RequestCursor>,
+) -> Result<Vec<(Uuid, i64)>, sqlx::Error> {
+    let mut query = QueryBuilder::new("WITH search AS (SELECT websearch_to_tsquery('simple', ");
+    query.push_bind(search);
+    query.push(
+        ") AS q),
+         matches AS (
+             SELECT instance_id, correlation_id FROM http_requests, search WHERE to_tsvector('simple', body) @@ search.q
+             UNION
+             SELECT instance_id, correlation_id FROM http_responses, search WHERE to_tsvector('simple', body) @@ search.q
+         )
+         SELECT r.instance_id, r.correlation_id
+         FROM matches m
+         JOIN http_requests r ON (r.instance_id = m.instance_id AND r.correlation_id = m.correlation_id)
+         LEFT JOIN http_responses res ON (r.instance_id = res.instance_id AND r.correlation_id = res.correlation_id)
+         WHERE TRUE",
+    );
+
+    push_filter(&mut query, filter, after);
+    query.build_query_as().fetch_all(pool).await
+}
+
+/// Find the logged requests after a cursor.
+///
+/// [`outlet_postgres::RequestRepository`] queries only page by offset, so pages after a cursor are
+/// listed here with the same filters and ordering, returning the `(instance_id, correlation_id)`
+/// keys of the requests in order.
+pub async fn requests_after(pool: &PgPool, filter: &RequestFilter, after: &RequestCursor) -> Result<Vec<(Uuid, i64)>, sqlx::Error> {
+    let mut query = QueryBuilder::new(
+        "SELECT r.instance_id, r.correlation_id
+         FROM http_requests r
+         LEFT JOIN http_responses res ON (r.instance_id = res.instance_id AND r.correlation_id = res.correlation_id)
+         WHERE TRUE",
+    );
+
+    push_filter(&mut query, filter, Some(after));
+    query.build_query_as().fetch_all(pool).await
+}
+
+/// Append the conditions, ordering and pagination of `filter` to a query over requests `r`
+/// left joined to their responses `res`, ending in a `WHERE` clause, keeping only the requests
+/// after `after` if given. Requests with the same timestamp are ordered by ID, so that cursors
+/// don't skip or repeat them.
+pub(super) fn push_filter<'a>(query: &mut QueryBuilder<'a, Postgres>, filter: &'a RequestFilter, after: Option<&RequestCursor>) {
+    if let Some(method) = &filter.method {
+        query.push(" AND r.method = ").push_bind(method);
+    }
+    if let Some(uri_pattern) = &filter.uri_pattern {
+        query.push(" AND r.uri ILIKE ").push_bind(uri_pattern);
+    }
+    if let Some(status_code) = filter.status_code {
+        query.push(" AND res.status_code = ").push_bind(status_code);
+    }
+    if let Some(min_status) = filter.status_code_min {
+        query.push(" AND res.status_code >= ").push_bind(min_status);
+    }
+    if let Some(max_status) = filter.status_code_max {
+        query.push(" AND res.status_code <= ").push_bind(max_status);
+    }
+    if let Some(timestamp_after) = filter.timestamp_after {
+        query.push(" AND r.timestamp >= ").push_bind(timestamp_after);
+    }
+    if let Some(timestamp_before) = filter.timestamp_before {
+        query.push(" AND r.timestamp <= ").push_bind(timestamp_before);
+    }
+    if let Some(min_duration) = filter.min_duration_ms {
+        query.push(" AND res.duration_ms >= ").push_bind(min_duration);
+    }
+    if let Some(max_duration) = filter.max_duration_ms {
+        query.push(" AND res.duration_ms <= ").push_bind(max_duration);
+    }
+
+    if let Some(after) = after {
+        push_after_cursor(query, after, "r.id");
+    }
+
+    query.push(if filter.order_by_timestamp_desc {
+        " ORDER BY r.timestamp DESC, r.id DESC"
+    } else {
+        " ORDER BY r.timestamp ASC, r.id ASC"
+    });
+    if let Some(limit) = filter.limit {
+        query.push(" LIMIT ").push_bind(limit);
+    }
+    if let Some(offset) = filter.offset {
+        query.push(" OFFSET ").push_bind(offset);
+    }
+}
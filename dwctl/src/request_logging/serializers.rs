@@ -1,11 +1,22 @@
 use crate::config::Config;
-use crate::request_logging::models::{AiRequest, AiResponse, ChatCompletionChunk};
+use crate::request_logging::conversations;
+use crate::request_logging::mirror::RequestMirror;
+use crate::request_logging::models::{AiRequest, AiResponse, ChatCompletionChunk, MessagesStreamEvent};
+use crate::request_logging::pii;
+use crate::request_logging::policy;
+use crate::request_logging::storage::BodyStorage;
+use crate::request_logging::tags;
+use crate::request_logging::webhook::RequestWebhook;
+use crate::routing::RoutingTable;
 use outlet::{RequestData, ResponseData};
 use outlet_postgres::SerializationError;
+use serde::Serialize;
 use serde_json::Value;
 use sqlx::PgPool;
 use std::fmt;
 use std::str;
+use tiktoken_rs::CoreBPE;
+use tokio::sync::watch;
 use tracing::{error, instrument, warn};
 use uuid::Uuid;
 
@@ -36,7 +47,7 @@ impl fmt::Display for AccessSource {
 pub enum Auth {
     /// Playground access via SSO proxy (X-Doubleword-User header)
     Playground { user_email: String },
-    /// API key access (Authorization: Bearer <key>)
+    /// API key access (Authorization: Bearer <key>, or x-api-key: <key>)
     ApiKey { bearer_token: String },
     /// No authentication found
     None,
@@ -53,7 +64,7 @@ impl fmt::Debug for Auth {
 }
 
 /// Complete row structure for http_analytics table
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct HttpAnalyticsRow {
     pub instance_id: Uuid,
     pub correlation_id: i64,
@@ -68,6 +79,8 @@ pub struct HttpAnalyticsRow {
     pub prompt_tokens: i64,
     pub completion_tokens: i64,
     pub total_tokens: i64,
+    /// Whether the token counts were estimated rather than reported by the upstream
+    pub usage_estimated: bool,
     pub response_type: String,
     pub user_id: Option<Uuid>,
     pub user_email: Option<String>,
@@ -77,6 +90,14 @@ pub struct HttpAnalyticsRow {
     pub server_address: String,
     pub server_port: u16,
     pub provider_name: Option<String>,
+    pub embedding_inputs: Option<i32>,
+    pub embedding_dimensions: Option<i32>,
+    pub pii_categories: Option<Vec<String>>,
+    pub moderation_decision: Option<String>,
+    pub moderation_categories: Option<Vec<String>>,
+    pub variant: Option<String>,
+    pub conversation_id: Option<String>,
+    pub tags: Option<serde_json::Value>,
 }
 
 /// Usage metrics extracted from AI responses (subset of HttpAnalyticsRow)
@@ -95,9 +116,18 @@ pub struct UsageMetrics {
     pub prompt_tokens: i64,
     pub completion_tokens: i64,
     pub total_tokens: i64,
+    pub usage_estimated: bool,
     pub response_type: String,
     pub server_address: String,
     pub server_port: u16,
+    pub embedding_inputs: Option<i32>,
+    pub embedding_dimensions: Option<i32>,
+    pub pii_categories: Option<Vec<String>>,
+    pub moderation_decision: Option<String>,
+    pub moderation_categories: Option<Vec<String>>,
+    pub variant: Option<String>,
+    pub conversation_id: Option<String>,
+    pub tags: Option<serde_json::Value>,
 }
 
 /// Parses HTTP request body data into structured AI request types.
@@ -124,7 +154,14 @@ pub fn parse_ai_request(request_data: &RequestData) -> Result<AiRequest, Seriali
         return Ok(AiRequest::Other(Value::Null));
     }
 
-    match serde_json::from_str(&body_str) {
+    // Messages API requests are told apart by their path, as their bodies can also parse as
+    // chat completions
+    let parsed = if request_data.uri.path().ends_with(crate::anthropic::MESSAGES_PATH) {
+        serde_json::from_str(&body_str).map(AiRequest::Messages)
+    } else {
+        serde_json::from_str(&body_str)
+    };
+    match parsed {
         Ok(request) => Ok(request),
         Err(e) => {
             // Always base64 encode unparseable content to avoid PostgreSQL issues
@@ -173,6 +210,7 @@ pub fn parse_ai_response(request_data: &RequestData, response_data: &ResponseDat
     let result = match parse_ai_request(request_data) {
         Ok(AiRequest::ChatCompletions(chat_req)) if chat_req.stream.unwrap_or(false) => utils::parse_streaming_response(&body_str),
         Ok(AiRequest::Completions(completion_req)) if completion_req.stream.unwrap_or(false) => utils::parse_streaming_response(&body_str),
+        Ok(AiRequest::Messages(messages_req)) if messages_req.stream.unwrap_or(false) => utils::parse_messages_stream(&body_str),
         _ => utils::parse_non_streaming_response(&body_str),
     };
 
@@ -209,11 +247,19 @@ impl UsageMetrics {
             Ok(AiRequest::ChatCompletions(req)) => Some(req.model),
             Ok(AiRequest::Completions(req)) => Some(req.model),
             Ok(AiRequest::Embeddings(req)) => Some(req.model),
+            Ok(AiRequest::Messages(req)) => Some(req.model),
             _ => None,
         };
 
         // Extract token metrics and response model from response
-        let response_metrics = TokenMetrics::from(parsed_response);
+        let mut response_metrics = TokenMetrics::from(parsed_response);
+        let usage_estimated = match parsed_response {
+            AiResponse::ChatCompletionsStream(chunks) => {
+                response_metrics.estimate_stream_usage_if_missing(request_model.as_deref(), request_data, chunks)
+            }
+            _ => false,
+        };
+        let embedding_metrics = EmbeddingMetrics::from(parsed_response);
 
         Self {
             instance_id,
@@ -229,13 +275,44 @@ impl UsageMetrics {
             prompt_tokens: response_metrics.prompt_tokens,
             completion_tokens: response_metrics.completion_tokens,
             total_tokens: response_metrics.total_tokens,
+            usage_estimated,
             response_type: response_metrics.response_type,
             server_address: config.host.clone(),
             server_port: config.port,
+            embedding_inputs: embedding_metrics.inputs,
+            embedding_dimensions: embedding_metrics.dimensions,
+            pii_categories: config.enable_pii_classification.then(|| classify_request_pii(request_data)),
+            moderation_decision: header_value(response_data, crate::moderation::MODERATION_HEADER),
+            moderation_categories: header_value(response_data, crate::moderation::MODERATION_CATEGORIES_HEADER)
+                .map(|categories| categories.split(',').map(str::to_string).collect()),
+            variant: header_value(response_data, crate::routing::VARIANT_HEADER),
+            conversation_id: conversations::conversation_id(request_data, &Auth::from_request(request_data, config)),
+            tags: tags::request_tags(request_data),
         }
     }
 }
 
+/// The first value of a response header, if present and valid UTF-8
+fn header_value(response_data: &ResponseData, name: &str) -> Option<String> {
+    let value = response_data.headers.get(name)?.first()?;
+    std::str::from_utf8(value).ok().map(str::to_string)
+}
+
+/// Classify the request body into coarse PII categories, without keeping any matched values.
+///
+/// Bodies that aren't JSON are classified as plain text.
+fn classify_request_pii(request_data: &RequestData) -> Vec<String> {
+    let Some(body) = request_data.body.as_ref().filter(|b| !b.is_empty()) else {
+        return Vec::new();
+    };
+
+    let categories = match serde_json::from_slice::<Value>(body) {
+        Ok(value) => pii::classify_json(&value),
+        Err(_) => pii::classify_text(&String::from_utf8_lossy(body)),
+    };
+    categories.into_iter().map(|c| c.to_string()).collect()
+}
+
 impl Auth {
     /// Extract authentication from request headers
     pub fn from_request(request_data: &RequestData, config: &Config) -> Self {
@@ -254,6 +331,11 @@ impl Auth {
             }
         }
 
+        // Check for API key in the header the Anthropic SDK sends it in
+        if let Some(api_key) = Self::get_header_value(request_data, crate::anthropic::API_KEY_HEADER) {
+            return Auth::ApiKey { bearer_token: api_key };
+        }
+
         Auth::None
     }
 
@@ -329,7 +411,7 @@ pub async fn store_analytics_record(pool: &PgPool, metrics: &UsageMetrics, auth:
         Auth::ApiKey { bearer_token } => {
             // Try to get user ID and email from API key
             match sqlx::query!(
-                "SELECT u.id, u.email FROM api_keys ak JOIN users u ON ak.user_id = u.id WHERE ak.secret = $1",
+                "SELECT u.id, u.email FROM api_keys ak JOIN users u ON ak.user_id = u.id WHERE ak.secret_hash = api_key_secret_hash($1)",
                 bearer_token
             )
             .fetch_optional(pool)
@@ -401,6 +483,7 @@ pub async fn store_analytics_record(pool: &PgPool, metrics: &UsageMetrics, auth:
         prompt_tokens: metrics.prompt_tokens,
         completion_tokens: metrics.completion_tokens,
         total_tokens: metrics.total_tokens,
+        usage_estimated: metrics.usage_estimated,
         response_type: metrics.response_type.clone(),
         user_id,
         user_email: user_email.clone(),
@@ -410,6 +493,14 @@ pub async fn store_analytics_record(pool: &PgPool, metrics: &UsageMetrics, auth:
         server_address: metrics.server_address.clone(),
         server_port: metrics.server_port,
         provider_name,
+        embedding_inputs: metrics.embedding_inputs,
+        embedding_dimensions: metrics.embedding_dimensions,
+        pii_categories: metrics.pii_categories.clone(),
+        moderation_decision: metrics.moderation_decision.clone(),
+        moderation_categories: metrics.moderation_categories.clone(),
+        variant: metrics.variant.clone(),
+        conversation_id: metrics.conversation_id.clone(),
+        tags: metrics.tags.clone(),
     };
 
     // Insert the analytics record using the row data
@@ -419,9 +510,10 @@ pub async fn store_analytics_record(pool: &PgPool, metrics: &UsageMetrics, auth:
             instance_id, correlation_id, timestamp, method, uri, model,
             status_code, duration_ms, duration_to_first_byte_ms, prompt_tokens, completion_tokens,
             total_tokens, response_type, user_id, user_email, access_source,
-            input_price_per_token, output_price_per_token
+            input_price_per_token, output_price_per_token, embedding_inputs, embedding_dimensions, pii_categories,
+            moderation_decision, moderation_categories, variant, conversation_id, tags, usage_estimated
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27)
         ON CONFLICT (instance_id, correlation_id)
         DO UPDATE SET
             status_code = EXCLUDED.status_code,
@@ -435,7 +527,16 @@ pub async fn store_analytics_record(pool: &PgPool, metrics: &UsageMetrics, auth:
             user_email = EXCLUDED.user_email,
             access_source = EXCLUDED.access_source,
             input_price_per_token = EXCLUDED.input_price_per_token,
-            output_price_per_token = EXCLUDED.output_price_per_token
+            output_price_per_token = EXCLUDED.output_price_per_token,
+            embedding_inputs = EXCLUDED.embedding_inputs,
+            embedding_dimensions = EXCLUDED.embedding_dimensions,
+            pii_categories = EXCLUDED.pii_categories,
+            moderation_decision = EXCLUDED.moderation_decision,
+            moderation_categories = EXCLUDED.moderation_categories,
+            variant = EXCLUDED.variant,
+            conversation_id = EXCLUDED.conversation_id,
+            tags = EXCLUDED.tags,
+            usage_estimated = EXCLUDED.usage_estimated
         "#,
         row.instance_id,
         row.correlation_id,
@@ -454,7 +555,16 @@ pub async fn store_analytics_record(pool: &PgPool, metrics: &UsageMetrics, auth:
         row.user_email,
         row.access_source,
         row.input_price_per_token,
-        row.output_price_per_token
+        row.output_price_per_token,
+        row.embedding_inputs,
+        row.embedding_dimensions,
+        row.pii_categories.as_deref(),
+        row.moderation_decision,
+        row.moderation_categories.as_deref(),
+        row.variant,
+        row.conversation_id,
+        row.tags,
+        row.usage_estimated
     )
     .execute(pool)
     .await?;
@@ -472,6 +582,158 @@ struct TokenMetrics {
     response_model: Option<String>,
 }
 
+impl TokenMetrics {
+    /// Estimate token counts for a streamed chat completion whose backend didn't send a usage
+    /// frame (e.g. because the client didn't set `stream_options.include_usage`), so streamed
+    /// requests aren't recorded as free. Returns whether the counts were estimated.
+    fn estimate_stream_usage_if_missing(
+        &mut self,
+        request_model: Option<&str>,
+        request_data: &RequestData,
+        chunks: &[ChatCompletionChunk],
+    ) -> bool {
+        let mut streamed_any = false;
+        for chunk in chunks {
+            if let ChatCompletionChunk::Normal(chunk) = chunk {
+                if chunk.usage.is_some() {
+                    return false;
+                }
+                streamed_any = true;
+            }
+        }
+        if !streamed_any {
+            return false;
+        }
+
+        let prompt = request_data
+            .body
+            .as_ref()
+            .and_then(|body| serde_json::from_slice::<Value>(body).ok())
+            .map(|body| crate::moderation::request_text(&body))
+            .unwrap_or_default();
+
+        let tokenizer = tokenizer_for([self.response_model.as_deref(), request_model]);
+        self.prompt_tokens = estimate_tokens(tokenizer, &prompt);
+        self.completion_tokens = estimate_tokens(tokenizer, &streamed_completion_text(chunks));
+        self.total_tokens = self.prompt_tokens + self.completion_tokens;
+        true
+    }
+}
+
+/// The tokenizer of the first of `models` known to use an OpenAI tokenizer, if any.
+///
+/// Provider prefixes such as `openai/` are ignored, so `openai/gpt-4o` uses the tokenizer of `gpt-4o`.
+fn tokenizer_for<'a>(models: impl IntoIterator<Item = Option<&'a str>>) -> Option<&'static CoreBPE> {
+    models.into_iter().flatten().find_map(|model| {
+        let name = model.rsplit('/').next().unwrap_or(model);
+        tiktoken_rs::bpe_for_model(name).ok()
+    })
+}
+
+/// Approximate number of tokens in `text`.
+///
+/// Counted exactly with the model's tokenizer where it's known. Other models behind the proxy use
+/// many different tokenizers, so they fall back to the rule of thumb of four characters per token.
+fn estimate_tokens(tokenizer: Option<&CoreBPE>, text: &str) -> i64 {
+    match tokenizer {
+        Some(tokenizer) => tokenizer.encode_ordinary(text).len() as i64,
+        None => text.chars().count().div_ceil(4) as i64,
+    }
+}
+
+/// Text generated across all choices of a streamed chat completion, including tool call arguments
+fn streamed_completion_text(chunks: &[ChatCompletionChunk]) -> String {
+    let mut text = String::new();
+    for chunk in chunks {
+        let ChatCompletionChunk::Normal(chunk) = chunk else { continue };
+        for choice in &chunk.choices {
+            let delta = &choice.delta;
+            text.extend(delta.content.as_deref());
+            text.extend(delta.refusal.as_deref());
+            for call in delta.tool_calls.iter().flatten() {
+                if let Some(function) = &call.function {
+                    text.extend(function.name.as_deref());
+                    text.extend(function.arguments.as_deref());
+                }
+            }
+        }
+    }
+    text
+}
+
+/// Text generated by a logged response, for estimating its output tokens
+fn response_text(response: &AiResponse) -> String {
+    let mut text = String::new();
+    match response {
+        AiResponse::ChatCompletions(response) => {
+            for choice in &response.choices {
+                let message = &choice.message;
+                text.extend(message.content.as_deref());
+                text.extend(message.refusal.as_deref());
+                for call in message.tool_calls.iter().flatten() {
+                    text.push_str(&call.function.name);
+                    text.push_str(&call.function.arguments);
+                }
+            }
+        }
+        AiResponse::ChatCompletionsStream(chunks) => text = streamed_completion_text(chunks),
+        AiResponse::Completions(response) => text.extend(response.choices.iter().map(|choice| choice.text.as_str())),
+        AiResponse::Messages(response) => {
+            let blocks = response.rest.get("content").and_then(Value::as_array);
+            text.extend(blocks.into_iter().flatten().filter_map(|block| block.get("text")?.as_str()));
+        }
+        AiResponse::MessagesStream(events) => {
+            for event in events {
+                if let MessagesStreamEvent::ContentBlockDelta { delta, .. } = event {
+                    text.extend(delta.get("text").and_then(Value::as_str));
+                    text.extend(delta.get("partial_json").and_then(Value::as_str));
+                }
+            }
+        }
+        _ => {}
+    }
+    text
+}
+
+/// Token usage of a logged request and its response
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoggedTokenUsage {
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    /// Whether the counts were estimated rather than reported in the response
+    pub estimated: bool,
+}
+
+/// Token usage of a logged request and its response.
+///
+/// Used to fill in analytics recorded without token counts. Usage reported in the response is
+/// taken as is; otherwise tokens are estimated from the text of the request and the response.
+/// Returns `None` if there's no text to estimate from.
+pub fn logged_token_usage(request: Option<&AiRequest>, response: &AiResponse) -> Option<LoggedTokenUsage> {
+    let metrics = TokenMetrics::from(response);
+    if metrics.total_tokens > 0 {
+        return Some(LoggedTokenUsage {
+            prompt_tokens: metrics.prompt_tokens,
+            completion_tokens: metrics.completion_tokens,
+            estimated: false,
+        });
+    }
+
+    let request = request.and_then(|request| serde_json::to_value(request).ok());
+    let prompt = request.as_ref().map(crate::moderation::request_text).unwrap_or_default();
+    let completion = response_text(response);
+    if prompt.is_empty() && completion.is_empty() {
+        return None;
+    }
+    let request_model = request.as_ref().and_then(|request| request.get("model")?.as_str());
+    let tokenizer = tokenizer_for([metrics.response_model.as_deref(), request_model]);
+    Some(LoggedTokenUsage {
+        prompt_tokens: estimate_tokens(tokenizer, &prompt),
+        completion_tokens: estimate_tokens(tokenizer, &completion),
+        estimated: true,
+    })
+}
+
 impl From<&AiResponse> for TokenMetrics {
     fn from(response: &AiResponse) -> Self {
         match response {
@@ -575,7 +837,48 @@ impl From<&AiResponse> for TokenMetrics {
                     response_model: Some(response.model.clone()),
                 }
             }
-            AiResponse::Other(_) => Self {
+            AiResponse::Messages(response) => {
+                let prompt_tokens = response.usage.total_input_tokens().unwrap_or_default() as i64;
+                let completion_tokens = response.usage.output_tokens as i64;
+                Self {
+                    prompt_tokens,
+                    completion_tokens,
+                    total_tokens: prompt_tokens + completion_tokens,
+                    response_type: "messages".to_string(),
+                    response_model: Some(response.model.clone()),
+                }
+            }
+            AiResponse::MessagesStream(events) => {
+                // Input tokens are reported when the message starts, and may be restated when it
+                // ends along with the final output token count
+                let mut prompt_tokens = 0;
+                let mut completion_tokens = 0;
+                let mut response_model = None;
+                for event in events {
+                    match event {
+                        MessagesStreamEvent::MessageStart { message } => {
+                            prompt_tokens = message.usage.total_input_tokens().unwrap_or_default() as i64;
+                            response_model = Some(message.model.clone());
+                        }
+                        MessagesStreamEvent::MessageDelta { usage, .. } => {
+                            if let Some(input_tokens) = usage.total_input_tokens() {
+                                prompt_tokens = input_tokens as i64;
+                            }
+                            completion_tokens = usage.output_tokens as i64;
+                        }
+                        _ => {}
+                    }
+                }
+                Self {
+                    prompt_tokens,
+                    completion_tokens,
+                    total_tokens: prompt_tokens + completion_tokens,
+                    response_type: "messages_stream".to_string(),
+                    response_model,
+                }
+            }
+            // Metrics are extracted before bodies are moved to object storage
+            AiResponse::Stored { .. } | AiResponse::Other(_) => Self {
                 prompt_tokens: 0,
                 completion_tokens: 0,
                 total_tokens: 0,
@@ -586,6 +889,36 @@ impl From<&AiResponse> for TokenMetrics {
     }
 }
 
+/// Helper struct for extracting embedding workload size from responses
+#[derive(Debug, Clone, Default)]
+struct EmbeddingMetrics {
+    /// Number of vectors returned
+    inputs: Option<i32>,
+    /// Length of each returned vector
+    dimensions: Option<i32>,
+}
+
+impl From<&AiResponse> for EmbeddingMetrics {
+    fn from(response: &AiResponse) -> Self {
+        match response {
+            AiResponse::Embeddings(response) => Self {
+                inputs: Some(response.data.len() as i32),
+                dimensions: response.data.first().map(|e| e.embedding.len() as i32),
+            },
+            AiResponse::Base64Embeddings(response) => Self {
+                inputs: Some(response.data.len() as i32),
+                // Vectors are little-endian f32s, so four bytes per dimension
+                dimensions: response.data.first().and_then(|e| {
+                    base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &e.embedding.0)
+                        .ok()
+                        .map(|bytes| (bytes.len() / 4) as i32)
+                }),
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
 pub struct AnalyticsResponseSerializer<M = crate::metrics::GenAiMetrics>
 where
     M: crate::metrics::MetricsRecorder + Clone + 'static,
@@ -594,6 +927,10 @@ where
     instance_id: Uuid,
     config: Config,
     metrics_recorder: Option<M>,
+    mirror: Option<RequestMirror>,
+    webhook: Option<RequestWebhook>,
+    body_storage: Option<BodyStorage>,
+    routing_table: Option<watch::Receiver<RoutingTable>>,
 }
 
 impl<M> AnalyticsResponseSerializer<M>
@@ -613,9 +950,37 @@ where
             instance_id,
             config,
             metrics_recorder,
+            mirror: None,
+            webhook: None,
+            body_storage: None,
+            routing_table: None,
         }
     }
 
+    /// Forwards every stored analytics row to the given request mirror.
+    pub fn with_mirror(mut self, mirror: RequestMirror) -> Self {
+        self.mirror = Some(mirror);
+        self
+    }
+
+    /// Sends an event to the given request webhook for every stored analytics row matching its filter.
+    pub fn with_webhook(mut self, webhook: RequestWebhook) -> Self {
+        self.webhook = Some(webhook);
+        self
+    }
+
+    /// Moves response bodies into object storage once their analytics have been extracted.
+    pub fn with_body_storage(mut self, body_storage: BodyStorage) -> Self {
+        self.body_storage = Some(body_storage);
+        self
+    }
+
+    /// Applies the logging policies published in the routing table to response bodies.
+    pub fn with_routing_table(mut self, routing_table: watch::Receiver<RoutingTable>) -> Self {
+        self.routing_table = Some(routing_table);
+        self
+    }
+
     /// Creates a serializer function that parses responses and stores analytics data.
     ///
     /// # Returns
@@ -626,8 +991,22 @@ where
     /// - Logs errors if analytics storage fails
     pub fn create_serializer(self) -> impl Fn(&RequestData, &ResponseData) -> Result<AiResponse, SerializationError> + Send + Sync {
         move |request_data: &RequestData, response_data: &ResponseData| {
+            // The logging policy of the deployment, applied once analytics have been extracted
+            let policy = self
+                .routing_table
+                .as_ref()
+                .and_then(|table| policy::policy_for(&table.borrow(), request_data));
+
             // The full response that gets written to the outlet-postgres database
-            let parsed_response = parse_ai_response(request_data, response_data)?;
+            let parsed_response = match parse_ai_response(request_data, response_data) {
+                Ok(response) => response,
+                Err(e) => {
+                    return match policy {
+                        Some(policy) => policy.apply_to_response(Err(e)),
+                        None => Err(e),
+                    }
+                }
+            };
 
             // Basic metrics
             let metrics = UsageMetrics::extract(self.instance_id, request_data, response_data, &parsed_response, &self.config);
@@ -638,6 +1017,8 @@ where
             // Clone data for async processing
             let pool_clone = self.pool.clone();
             let metrics_recorder_clone = self.metrics_recorder.clone();
+            let mirror_clone = self.mirror.clone();
+            let webhook_clone = self.webhook.clone();
 
             // The write to the analytics table and metrics recording
             tokio::spawn(async move {
@@ -648,6 +1029,14 @@ where
                         if let Some(ref recorder) = metrics_recorder_clone {
                             recorder.record_from_analytics(&complete_row).await;
                         }
+                        // Queue a per-request event, if configured
+                        if let Some(ref webhook) = webhook_clone {
+                            webhook.notify(&complete_row);
+                        }
+                        // Queue for near-real-time forwarding, if configured
+                        if let Some(ref mirror) = mirror_clone {
+                            mirror.mirror(complete_row).await;
+                        }
                     }
                     Err(e) => {
                         error!(
@@ -659,14 +1048,21 @@ where
                 }
             });
 
-            Ok(parsed_response)
+            let logged_response = match policy {
+                Some(policy) => policy.apply_to_response(Ok(parsed_response))?,
+                None => parsed_response,
+            };
+            match &self.body_storage {
+                Some(body_storage) => Ok(body_storage.offload_response(logged_response)),
+                None => Ok(logged_response),
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_ai_request, parse_ai_response, UsageMetrics};
+    use super::{parse_ai_request, parse_ai_response, Auth, UsageMetrics};
     use crate::request_logging::models::{AiRequest, AiResponse};
     use async_openai::types::{
         CreateBase64EmbeddingResponse, CreateChatCompletionResponse, CreateChatCompletionStreamResponse, CreateCompletionResponse,
@@ -939,6 +1335,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_messages_stream_usage() {
+        let request_json = r#"{"model": "claude", "max_tokens": 64, "messages": [{"role": "user", "content": "hello"}], "stream": true}"#;
+        let mut headers = HashMap::new();
+        headers.insert("x-api-key".to_string(), vec![Bytes::from("sk-test")]);
+        let request_data = RequestData {
+            correlation_id: 123,
+            timestamp: SystemTime::now(),
+            method: Method::POST,
+            uri: "/ai/v1/messages".parse::<Uri>().unwrap(),
+            headers,
+            body: Some(Bytes::from(request_json)),
+        };
+
+        let sse_response = concat!(
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"type\":\"message\",\"role\":\"assistant\",\"model\":\"claude-sonnet-4\",\"content\":[],\"usage\":{\"input_tokens\":10,\"cache_read_input_tokens\":5,\"output_tokens\":1}}}\n\n",
+            "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: ping\ndata: {\"type\":\"ping\"}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n",
+            "event: content_block_stop\ndata: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            "event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":7}}\n\n",
+            "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        );
+        let response_data = ResponseData {
+            correlation_id: 123,
+            timestamp: SystemTime::now(),
+            status: StatusCode::OK,
+            headers: HashMap::new(),
+            body: Some(Bytes::from(sse_response)),
+            duration: Duration::from_millis(100),
+            duration_to_first_byte: Duration::from_millis(50),
+        };
+
+        assert!(matches!(parse_ai_request(&request_data), Ok(AiRequest::Messages(_))));
+        let parsed_response = parse_ai_response(&request_data, &response_data).unwrap();
+        let AiResponse::MessagesStream(events) = &parsed_response else {
+            panic!("Expected AiResponse::MessagesStream");
+        };
+        assert_eq!(events.len(), 7);
+
+        let config = crate::test_utils::create_test_config();
+        let metrics = UsageMetrics::extract(Uuid::new_v4(), &request_data, &response_data, &parsed_response, &config);
+        assert_eq!(metrics.request_model, Some("claude".to_string()));
+        assert_eq!(metrics.response_model, Some("claude-sonnet-4".to_string()));
+        assert_eq!(metrics.prompt_tokens, 15);
+        assert_eq!(metrics.completion_tokens, 7);
+        assert_eq!(metrics.total_tokens, 22);
+        assert_eq!(metrics.response_type, "messages_stream");
+
+        // Keys sent the Anthropic SDK's way are attributed like bearer tokens
+        match Auth::from_request(&request_data, &config) {
+            Auth::ApiKey { bearer_token } => assert_eq!(bearer_token, "sk-test"),
+            _ => panic!("Expected Auth::ApiKey"),
+        }
+    }
+
     #[test]
     fn test_parse_ai_response_embeddings() {
         let request_data = RequestData {
@@ -1177,6 +1629,73 @@ mod tests {
         assert_eq!(metrics.completion_tokens, 12);
         assert_eq!(metrics.total_tokens, 20);
         assert_eq!(metrics.response_type, "chat_completion_stream");
+        assert!(!metrics.usage_estimated);
+    }
+
+    #[test]
+    fn test_analytics_metrics_estimate_streaming_tokens_without_usage() {
+        let json_body = r#"{"model": "gpt-4", "messages": [{"role": "user", "content": "Write a haiku about rust"}], "stream": true}"#;
+        let request_data = RequestData {
+            correlation_id: 12345,
+            timestamp: SystemTime::now(),
+            method: Method::POST,
+            uri: "/v1/chat/completions".parse::<Uri>().unwrap(),
+            headers: HashMap::new(),
+            body: Some(Bytes::from(json_body)),
+        };
+        let response_data = ResponseData {
+            correlation_id: 12345,
+            timestamp: SystemTime::now(),
+            status: StatusCode::OK,
+            headers: HashMap::new(),
+            body: None,
+            duration: Duration::from_millis(300),
+            duration_to_first_byte: Duration::from_millis(50),
+        };
+
+        // A backend that streams content but never sends a usage frame
+        let chunk = |content: &str| -> crate::request_logging::models::ChatCompletionChunk {
+            serde_json::from_value(serde_json::json!({
+                "id": "chatcmpl-123",
+                "object": "chat.completion.chunk",
+                "created": 1677652288,
+                "model": "gpt-4",
+                "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}]
+            }))
+            .unwrap()
+        };
+        let parsed_response = AiResponse::ChatCompletionsStream(vec![
+            chunk("Red flakes on iron, "),
+            chunk("patient oxygen at work"),
+            crate::request_logging::models::ChatCompletionChunk::Done,
+        ]);
+
+        let metrics = UsageMetrics::extract(
+            Uuid::new_v4(),
+            &request_data,
+            &response_data,
+            &parsed_response,
+            &crate::test_utils::create_test_config(),
+        );
+
+        // Counted with the tokenizer of gpt-4, and flagged as estimated
+        assert_eq!(metrics.prompt_tokens, 6);
+        assert_eq!(metrics.completion_tokens, 9);
+        assert_eq!(metrics.total_tokens, 15);
+        assert!(metrics.usage_estimated);
+        assert_eq!(metrics.response_model.as_deref(), Some("gpt-4"));
+
+        // Streams that never produced a chunk aren't charged for the prompt
+        let parsed_response = AiResponse::ChatCompletionsStream(vec![crate::request_logging::models::ChatCompletionChunk::Done]);
+        let metrics = UsageMetrics::extract(
+            Uuid::new_v4(),
+            &request_data,
+            &response_data,
+            &parsed_response,
+            &crate::test_utils::create_test_config(),
+        );
+        assert_eq!(metrics.total_tokens, 0);
+        assert!(!metrics.usage_estimated);
     }
 
     #[test]
@@ -1228,6 +1747,184 @@ mod tests {
         assert_eq!(metrics.response_type, "embeddings");
     }
 
+    #[test]
+    fn test_analytics_metrics_extract_embedding_inputs_and_dimensions() {
+        let request_data = RequestData {
+            correlation_id: 12345,
+            timestamp: SystemTime::now(),
+            method: Method::POST,
+            uri: "/v1/embeddings".parse::<Uri>().unwrap(),
+            headers: HashMap::new(),
+            body: None,
+        };
+
+        let response_data = ResponseData {
+            correlation_id: 12345,
+            timestamp: SystemTime::now(),
+            status: StatusCode::OK,
+            headers: HashMap::new(),
+            body: None,
+            duration: Duration::from_millis(150),
+            duration_to_first_byte: Duration::from_millis(50),
+        };
+
+        let embedding = |index| async_openai::types::Embedding {
+            index,
+            object: "embedding".to_string(),
+            embedding: vec![0.1, 0.2, 0.3],
+        };
+        let parsed_response = AiResponse::Embeddings(CreateEmbeddingResponse {
+            object: "list".to_string(),
+            data: vec![embedding(0), embedding(1)],
+            model: "text-embedding-3-small".to_string(),
+            usage: EmbeddingUsage {
+                prompt_tokens: 8,
+                total_tokens: 8,
+            },
+        });
+
+        let config = crate::test_utils::create_test_config();
+        let metrics = UsageMetrics::extract(Uuid::new_v4(), &request_data, &response_data, &parsed_response, &config);
+        assert_eq!(metrics.embedding_inputs, Some(2));
+        assert_eq!(metrics.embedding_dimensions, Some(3));
+
+        // Base64 vectors are little-endian f32s
+        let bytes: Vec<u8> = [0.1f32, 0.2, 0.3, 0.4].iter().flat_map(|f| f.to_le_bytes()).collect();
+        let parsed_response = AiResponse::Base64Embeddings(CreateBase64EmbeddingResponse {
+            object: "list".to_string(),
+            data: vec![async_openai::types::Base64Embedding {
+                index: 0,
+                object: "embedding".to_string(),
+                embedding: async_openai::types::Base64EmbeddingVector(base64::Engine::encode(
+                    &base64::engine::general_purpose::STANDARD,
+                    bytes,
+                )),
+            }],
+            model: "text-embedding-3-small".to_string(),
+            usage: EmbeddingUsage {
+                prompt_tokens: 4,
+                total_tokens: 4,
+            },
+        });
+
+        let metrics = UsageMetrics::extract(Uuid::new_v4(), &request_data, &response_data, &parsed_response, &config);
+        assert_eq!(metrics.embedding_inputs, Some(1));
+        assert_eq!(metrics.embedding_dimensions, Some(4));
+
+        // Non-embedding responses leave the fields empty
+        let parsed_response = AiResponse::Other(serde_json::Value::Null);
+        let metrics = UsageMetrics::extract(Uuid::new_v4(), &request_data, &response_data, &parsed_response, &config);
+        assert_eq!(metrics.embedding_inputs, None);
+        assert_eq!(metrics.embedding_dimensions, None);
+    }
+
+    #[test]
+    fn test_analytics_metrics_extract_pii_categories() {
+        let json_body = r#"{"model": "gpt-4", "messages": [{"role": "user", "content": "Email me at jane@example.com"}]}"#;
+        let request_data = RequestData {
+            correlation_id: 12345,
+            timestamp: SystemTime::now(),
+            method: Method::POST,
+            uri: "/v1/chat/completions".parse::<Uri>().unwrap(),
+            headers: HashMap::new(),
+            body: Some(Bytes::from(json_body)),
+        };
+
+        let response_data = ResponseData {
+            correlation_id: 12345,
+            timestamp: SystemTime::now(),
+            status: StatusCode::OK,
+            headers: HashMap::new(),
+            body: None,
+            duration: Duration::from_millis(150),
+            duration_to_first_byte: Duration::from_millis(50),
+        };
+        let parsed_response = AiResponse::Other(serde_json::Value::Null);
+
+        // Classification is opt-in
+        let mut config = crate::test_utils::create_test_config();
+        let metrics = UsageMetrics::extract(Uuid::new_v4(), &request_data, &response_data, &parsed_response, &config);
+        assert_eq!(metrics.pii_categories, None);
+
+        config.enable_pii_classification = true;
+        let metrics = UsageMetrics::extract(Uuid::new_v4(), &request_data, &response_data, &parsed_response, &config);
+        assert_eq!(metrics.pii_categories, Some(vec!["email".to_string()]));
+    }
+
+    #[test]
+    fn test_analytics_metrics_extract_moderation_decision() {
+        let request_data = RequestData {
+            correlation_id: 12345,
+            timestamp: SystemTime::now(),
+            method: Method::POST,
+            uri: "/v1/chat/completions".parse::<Uri>().unwrap(),
+            headers: HashMap::new(),
+            body: None,
+        };
+        let mut response_data = ResponseData {
+            correlation_id: 12345,
+            timestamp: SystemTime::now(),
+            status: StatusCode::BAD_REQUEST,
+            headers: HashMap::new(),
+            body: None,
+            duration: Duration::from_millis(5),
+            duration_to_first_byte: Duration::from_millis(5),
+        };
+        let parsed_response = AiResponse::Other(serde_json::Value::Null);
+        let config = crate::test_utils::create_test_config();
+
+        // Unmoderated requests have no decision
+        let metrics = UsageMetrics::extract(Uuid::new_v4(), &request_data, &response_data, &parsed_response, &config);
+        assert_eq!(metrics.moderation_decision, None);
+        assert_eq!(metrics.moderation_categories, None);
+
+        response_data
+            .headers
+            .insert("x-doubleword-moderation".to_string(), vec![Bytes::from("blocked")]);
+        response_data.headers.insert(
+            "x-doubleword-moderation-categories".to_string(),
+            vec![Bytes::from("blocked_pattern,violence")],
+        );
+        let metrics = UsageMetrics::extract(Uuid::new_v4(), &request_data, &response_data, &parsed_response, &config);
+        assert_eq!(metrics.moderation_decision.as_deref(), Some("blocked"));
+        assert_eq!(
+            metrics.moderation_categories,
+            Some(vec!["blocked_pattern".to_string(), "violence".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_analytics_metrics_extract_canary_variant() {
+        let request_data = RequestData {
+            correlation_id: 12345,
+            timestamp: SystemTime::now(),
+            method: Method::POST,
+            uri: "/v1/chat/completions".parse::<Uri>().unwrap(),
+            headers: HashMap::new(),
+            body: None,
+        };
+        let mut response_data = ResponseData {
+            correlation_id: 12345,
+            timestamp: SystemTime::now(),
+            status: StatusCode::OK,
+            headers: HashMap::new(),
+            body: None,
+            duration: Duration::from_millis(5),
+            duration_to_first_byte: Duration::from_millis(5),
+        };
+        let parsed_response = AiResponse::Other(serde_json::Value::Null);
+        let config = crate::test_utils::create_test_config();
+
+        let metrics = UsageMetrics::extract(Uuid::new_v4(), &request_data, &response_data, &parsed_response, &config);
+        assert_eq!(metrics.variant, None);
+
+        response_data
+            .headers
+            .insert("x-doubleword-variant".to_string(), vec![Bytes::from("canary")]);
+        let metrics = UsageMetrics::extract(Uuid::new_v4(), &request_data, &response_data, &parsed_response, &config);
+        assert_eq!(metrics.variant.as_deref(), Some("canary"));
+    }
+
     #[test]
     fn test_analytics_metrics_extract_completions_tokens() {
         let instance_id = Uuid::new_v4();
@@ -1423,4 +2120,56 @@ mod tests {
         assert_eq!(super::map_url_to_otel_provider("https://API.OPENAI.COM/v1/chat"), Some("openai"));
         assert_eq!(super::map_url_to_otel_provider("HTTPS://API.ANTHROPIC.COM/"), Some("anthropic"));
     }
+
+    #[test]
+    fn test_logged_token_usage() {
+        let request: crate::request_logging::AiRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "12345678"}]
+        }))
+        .unwrap();
+        let mut response = serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1,
+            "model": "gpt-4",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hello there"},
+                "finish_reason": "stop",
+                "logprobs": null
+            }]
+        });
+
+        let usage = |prompt_tokens, completion_tokens, estimated| {
+            Some(super::LoggedTokenUsage {
+                prompt_tokens,
+                completion_tokens,
+                estimated,
+            })
+        };
+
+        // Without reported usage, tokens are counted with the model's tokenizer
+        let estimated: crate::request_logging::AiResponse = serde_json::from_value(response.clone()).unwrap();
+        assert_eq!(super::logged_token_usage(Some(&request), &estimated), usage(3, 2, true));
+        assert_eq!(super::logged_token_usage(None, &estimated), usage(0, 2, true));
+
+        // Models without a known tokenizer fall back to roughly four characters per token
+        response["model"] = serde_json::json!("my-org/llama-3-8b");
+        let request: crate::request_logging::AiRequest = serde_json::from_value(serde_json::json!({
+            "model": "llama-3-8b",
+            "messages": [{"role": "user", "content": "12345678"}]
+        }))
+        .unwrap();
+        let estimated: crate::request_logging::AiResponse = serde_json::from_value(response.clone()).unwrap();
+        assert_eq!(super::logged_token_usage(Some(&request), &estimated), usage(2, 3, true));
+
+        // Reported usage is taken as is
+        response["usage"] = serde_json::json!({"prompt_tokens": 20, "completion_tokens": 7, "total_tokens": 27});
+        let reported: crate::request_logging::AiResponse = serde_json::from_value(response).unwrap();
+        assert_eq!(super::logged_token_usage(Some(&request), &reported), usage(20, 7, false));
+
+        let empty = crate::request_logging::AiResponse::Other(serde_json::Value::Null);
+        assert_eq!(super::logged_token_usage(None, &empty), None);
+    }
 }
@@ -0,0 +1,439 @@
+//! Storage of captured request and response bodies outside of PostgreSQL.
+//!
+//! By default outlet-postgres keeps every captured body in the request logging tables, which
+//! dominate the size of the database on high-volume deployments. When a [`BodyStore`] is
+//! configured the serializers upload each parsed body to it and log an `AiRequest::Stored` /
+//! `AiResponse::Stored` pointer in its place. The requests API resolves the pointers again with
+//! [`BodyStorage::load`], so callers see the same bodies either way.
+//!
+//! A pointer is only logged once its upload has succeeded. The serializers are synchronous and
+//! run on outlet's background logging task, so the upload blocks that task (not the proxied
+//! request) and is retried a few times; if it still fails, the body is logged inline as if no
+//! store were configured, rather than leaving a pointer to an object that doesn't exist.
+//!
+//! Bodies that failed to parse are still logged in full, as the base64 fallback outlet-postgres
+//! stores for them.
+
+use crate::config::{BodyStorageConfig, S3StorageConfig};
+use crate::request_logging::models::{AiRequest, AiResponse, StoredBody};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tracing::{error, warn};
+use url::Url;
+use uuid::Uuid;
+
+/// Attempts made to upload a captured body before logging it inline instead
+const UPLOAD_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry of a failed upload, doubled for each further retry
+const UPLOAD_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// A place captured bodies can be written to and read back from
+#[async_trait]
+pub trait BodyStore: fmt::Debug + Send + Sync {
+    async fn put(&self, key: &str, body: Bytes) -> anyhow::Result<()>;
+
+    async fn get(&self, key: &str) -> anyhow::Result<Bytes>;
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+}
+
+/// Shared handle to the configured [`BodyStore`]
+#[derive(Debug, Clone)]
+pub struct BodyStorage {
+    store: Arc<dyn BodyStore>,
+    prefix: String,
+}
+
+impl BodyStorage {
+    /// The storage configured for this installation, or `None` if bodies stay in PostgreSQL
+    pub fn from_config(config: &BodyStorageConfig) -> anyhow::Result<Option<Self>> {
+        match config {
+            BodyStorageConfig::Postgres => Ok(None),
+            BodyStorageConfig::S3(s3) => Ok(Some(Self::new(Arc::new(S3Store::new(s3.as_ref().clone())?), s3.prefix.clone()))),
+        }
+    }
+
+    pub fn new(store: Arc<dyn BodyStore>, prefix: String) -> Self {
+        Self { store, prefix }
+    }
+
+    /// Upload a parsed request body, returning the pointer to log in its place
+    pub fn offload_request(&self, request: AiRequest) -> AiRequest {
+        if matches!(request, AiRequest::Other(Value::Null) | AiRequest::Stored { .. }) {
+            return request;
+        }
+        match self.offload("request", &request) {
+            Some(stored_body) => AiRequest::Stored { stored_body },
+            None => request,
+        }
+    }
+
+    /// Upload a parsed response body, returning the pointer to log in its place
+    pub fn offload_response(&self, response: AiResponse) -> AiResponse {
+        if matches!(response, AiResponse::Other(Value::Null) | AiResponse::Stored { .. }) {
+            return response;
+        }
+        match self.offload("response", &response) {
+            Some(stored_body) => AiResponse::Stored { stored_body },
+            None => response,
+        }
+    }
+
+    /// Upload `body`, returning its pointer, or `None` (so the body is logged as usual) if it
+    /// can't be serialized or uploaded.
+    ///
+    /// Blocks the calling thread until the upload is done, so it must be called on a worker of a
+    /// multi-threaded runtime; anywhere else the body is left inline.
+    fn offload<T: Serialize>(&self, kind: &str, body: &T) -> Option<StoredBody> {
+        let handle = Handle::try_current().ok()?;
+        if handle.runtime_flavor() != RuntimeFlavor::MultiThread {
+            return None;
+        }
+        let bytes = Bytes::from(serde_json::to_vec(body).ok()?);
+        let key = format!("{}{}/{}-{kind}.json", self.prefix, Utc::now().format("%Y/%m/%d"), Uuid::new_v4());
+        let size_bytes = bytes.len();
+
+        match tokio::task::block_in_place(|| handle.block_on(self.upload(&key, bytes))) {
+            Ok(()) => Some(StoredBody { key, size_bytes }),
+            Err(e) => {
+                error!(key = %key, error = %e, "Failed to upload captured body, logging it inline");
+                None
+            }
+        }
+    }
+
+    /// Upload `bytes` under `key`, retrying with backoff up to [`UPLOAD_ATTEMPTS`] times
+    async fn upload(&self, key: &str, bytes: Bytes) -> anyhow::Result<()> {
+        let mut delay = UPLOAD_RETRY_DELAY;
+        for attempt in 1.. {
+            match self.store.put(key, bytes.clone()).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < UPLOAD_ATTEMPTS => {
+                    warn!(key = %key, attempt, error = %e, "Failed to upload captured body, retrying");
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!("the last attempt returns")
+    }
+
+    /// Upload `body` under `name`, relative to the storage prefix, returning its key
+    pub async fn put(&self, name: &str, body: Bytes) -> anyhow::Result<String> {
+        let key = format!("{}{name}", self.prefix);
+        self.store.put(&key, body).await?;
+        Ok(key)
+    }
+
+    /// Download the object `key`, as returned by [`BodyStorage::put`]
+    pub async fn get(&self, key: &str) -> anyhow::Result<Bytes> {
+        self.store.get(key).await
+    }
+
+    /// Overwrite the object `key`, as returned by [`BodyStorage::put`]
+    pub async fn replace(&self, key: &str, body: Bytes) -> anyhow::Result<()> {
+        self.store.put(key, body).await
+    }
+
+    /// Download and parse a body uploaded by [`BodyStorage::offload_request`] or
+    /// [`BodyStorage::offload_response`]
+    pub async fn load<T: DeserializeOwned>(&self, stored: &StoredBody) -> anyhow::Result<T> {
+        let bytes = self.store.get(&stored.key).await?;
+        serde_json::from_slice(&bytes).with_context(|| format!("Stored body {} is not valid JSON", stored.key))
+    }
+
+    /// Delete the object holding a stored body
+    pub async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.store.delete(key).await
+    }
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// [`BodyStore`] backed by an S3-compatible bucket, with requests signed using AWS Signature
+/// Version 4
+#[derive(Debug)]
+pub struct S3Store {
+    client: reqwest::Client,
+    config: S3StorageConfig,
+    endpoint: Url,
+}
+
+impl S3Store {
+    pub fn new(config: S3StorageConfig) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder().timeout(config.request_timeout).build()?;
+        let endpoint = match &config.endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => format!("https://s3.{}.amazonaws.com", config.region).parse()?,
+        };
+        Ok(Self { client, config, endpoint })
+    }
+
+    /// URL of the object `key`, as (url, host header, canonical path)
+    fn object_url(&self, key: &str) -> anyhow::Result<(Url, String, String)> {
+        let host = self.endpoint.host_str().ok_or_else(|| anyhow!("S3 endpoint has no host"))?;
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_string(),
+        };
+        let key_path: Vec<String> = key.split('/').map(uri_encode).collect();
+        let (host, path) = if self.config.path_style {
+            (host, format!("/{}/{}", uri_encode(&self.config.bucket), key_path.join("/")))
+        } else {
+            (format!("{}.{host}", self.config.bucket), format!("/{}", key_path.join("/")))
+        };
+        let url = format!("{}://{host}{path}", self.endpoint.scheme()).parse()?;
+        Ok((url, host, path))
+    }
+
+    async fn send(&self, method: reqwest::Method, key: &str, body: Bytes) -> anyhow::Result<reqwest::Response> {
+        let (url, host, path) = self.object_url(key)?;
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let now = Utc::now();
+        let authorization = self.authorization(method.as_str(), &host, &path, &payload_hash, now);
+
+        let response = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+            .header("x-amz-content-sha256", payload_hash)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .body(body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("S3 request for {key} failed: {status} - {body}"));
+        }
+        Ok(response)
+    }
+
+    /// `Authorization` header value for a request without query parameters
+    fn authorization(&self, method: &str, host: &str, path: &str, payload_hash: &str, now: DateTime<Utc>) -> String {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{date}/{}/s3/aws4_request", self.config.region);
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+
+        let canonical_request = format!(
+            "{method}\n{path}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{signed_headers}\n{payload_hash}"
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&self.config.secret_access_key, &date, &self.config.region, "s3");
+        let signature = hex::encode(hmac(&key, string_to_sign.as_bytes()));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            self.config.access_key_id
+        )
+    }
+}
+
+#[async_trait]
+impl BodyStore for S3Store {
+    async fn put(&self, key: &str, body: Bytes) -> anyhow::Result<()> {
+        self.send(reqwest::Method::PUT, key, body).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Bytes> {
+        let response = self.send(reqwest::Method::GET, key, Bytes::new()).await?;
+        Ok(response.bytes().await?)
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.send(reqwest::Method::DELETE, key, Bytes::new()).await?;
+        Ok(())
+    }
+}
+
+pub(crate) fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Derive the SigV4 signing key for `date` (YYYYMMDD), `region` and `service`
+pub(crate) fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac(format!("AWS4{secret}").as_bytes(), date.as_bytes());
+    let key = hmac(&key, region.as_bytes());
+    let key = hmac(&key, service.as_bytes());
+    hmac(&key, b"aws4_request")
+}
+
+/// Percent-encode everything but unreserved characters, as SigV4 canonical paths require
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::{Path, State},
+        http::{HeaderMap, StatusCode},
+        routing::put,
+        Router,
+    };
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    type Objects = Arc<Mutex<HashMap<String, Bytes>>>;
+
+    /// A fake S3 server, addressed path-style, that records the objects uploaded to it. The
+    /// first `failing_puts` uploads are answered with a 500.
+    async fn spawn_bucket(objects: Objects, failing_puts: Arc<AtomicUsize>) -> Url {
+        type Bucket = (Objects, Arc<AtomicUsize>);
+
+        async fn put_object(
+            State((objects, failing_puts)): State<Bucket>,
+            Path(key): Path<String>,
+            headers: HeaderMap,
+            body: Bytes,
+        ) -> StatusCode {
+            if !headers.contains_key("authorization") || !headers.contains_key("x-amz-content-sha256") {
+                return StatusCode::FORBIDDEN;
+            }
+            if failing_puts
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+            objects.lock().unwrap().insert(key, body);
+            StatusCode::OK
+        }
+        async fn get_object(State((objects, _)): State<Bucket>, Path(key): Path<String>) -> Result<Bytes, StatusCode> {
+            objects.lock().unwrap().get(&key).cloned().ok_or(StatusCode::NOT_FOUND)
+        }
+        async fn delete_object(State((objects, _)): State<Bucket>, Path(key): Path<String>) -> StatusCode {
+            objects.lock().unwrap().remove(&key);
+            StatusCode::NO_CONTENT
+        }
+
+        let app = Router::new()
+            .route("/bodies/{*key}", put(put_object).get(get_object).delete(delete_object))
+            .with_state((objects, failing_puts));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{addr}").parse().unwrap()
+    }
+
+    fn s3_config(endpoint: Url) -> S3StorageConfig {
+        S3StorageConfig {
+            endpoint: Some(endpoint),
+            bucket: "bodies".to_string(),
+            access_key_id: "minio".to_string(),
+            secret_access_key: "minio-secret".to_string(),
+            path_style: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_signing_key() {
+        // Example from the AWS Signature Version 4 documentation
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex::encode(key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+    }
+
+    #[test]
+    fn test_object_urls() {
+        let mut config = s3_config("http://minio:9000".parse().unwrap());
+        let store = S3Store::new(config.clone()).unwrap();
+        let (url, host, path) = store.object_url("bodies/2025/01/01/a b.json").unwrap();
+        assert_eq!(url.as_str(), "http://minio:9000/bodies/bodies/2025/01/01/a%20b.json");
+        assert_eq!(host, "minio:9000");
+        assert_eq!(path, "/bodies/bodies/2025/01/01/a%20b.json");
+
+        config.endpoint = None;
+        config.path_style = false;
+        config.region = "eu-west-2".to_string();
+        let store = S3Store::new(config).unwrap();
+        let (url, host, _) = store.object_url("key.json").unwrap();
+        assert_eq!(url.as_str(), "https://bodies.s3.eu-west-2.amazonaws.com/key.json");
+        assert_eq!(host, "bodies.s3.eu-west-2.amazonaws.com");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_offloaded_bodies_round_trip() {
+        let objects = Objects::default();
+        let endpoint = spawn_bucket(objects.clone(), Arc::default()).await;
+        let storage = BodyStorage::new(Arc::new(S3Store::new(s3_config(endpoint)).unwrap()), "captured/".to_string());
+
+        // Empty bodies aren't worth uploading
+        assert!(matches!(
+            storage.offload_response(AiResponse::Other(Value::Null)),
+            AiResponse::Other(Value::Null)
+        ));
+
+        let body = AiRequest::Other(serde_json::json!({"model": "gpt-4", "input": "hello"}));
+        let AiRequest::Stored { stored_body: stored } = storage.offload_request(body.clone()) else {
+            panic!("expected the body to be offloaded");
+        };
+        assert!(stored.key.starts_with("captured/"));
+        assert!(stored.key.ends_with("-request.json"));
+        assert_eq!(objects.lock().unwrap()[&stored.key].len(), stored.size_bytes);
+
+        let loaded: AiRequest = storage.load(&stored).await.unwrap();
+        assert_eq!(serde_json::to_value(loaded).unwrap(), serde_json::to_value(body).unwrap());
+
+        let missing = StoredBody {
+            key: "captured/missing.json".to_string(),
+            size_bytes: 0,
+        };
+        assert!(storage.load::<AiRequest>(&missing).await.is_err());
+
+        storage.delete(&stored.key).await.unwrap();
+        assert!(objects.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_failed_uploads_are_retried_then_logged_inline() {
+        let objects = Objects::default();
+        let failing_puts = Arc::new(AtomicUsize::new(UPLOAD_ATTEMPTS as usize - 1));
+        let endpoint = spawn_bucket(objects.clone(), failing_puts.clone()).await;
+        let storage = BodyStorage::new(Arc::new(S3Store::new(s3_config(endpoint)).unwrap()), "captured/".to_string());
+        let body = AiResponse::Other(serde_json::json!({"id": "resp-1", "output": "hello"}));
+
+        // Transient failures are retried until the upload succeeds
+        let AiResponse::Stored { stored_body: stored } = storage.offload_response(body.clone()) else {
+            panic!("expected the body to be offloaded after retrying");
+        };
+        assert_eq!(failing_puts.load(Ordering::SeqCst), 0);
+        assert!(objects.lock().unwrap().contains_key(&stored.key));
+
+        // When every attempt fails the body is logged inline rather than lost
+        failing_puts.store(UPLOAD_ATTEMPTS as usize, Ordering::SeqCst);
+        let logged = storage.offload_response(body.clone());
+        assert_eq!(failing_puts.load(Ordering::SeqCst), 0);
+        assert_eq!(serde_json::to_value(logged).unwrap(), serde_json::to_value(body).unwrap());
+        assert_eq!(objects.lock().unwrap().len(), 1);
+    }
+}
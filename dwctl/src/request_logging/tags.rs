@@ -0,0 +1,143 @@
+//! Custom metadata tags on logged requests.
+//!
+//! Clients tag AI requests with the [`TAGS_HEADER`] header, a comma-separated list of `key=value`
+//! pairs such as `feature=search, project=atlas`. Tags are recorded with the request's analytics
+//! as a JSON object, so requests can be filtered and usage aggregated by tag. Malformed pairs are
+//! skipped rather than failing the request, and when a key is repeated the last value wins.
+
+use super::search::push_filter;
+use crate::api::models::requests::RequestCursor;
+use outlet::RequestData;
+use outlet_postgres::RequestFilter;
+use serde_json::{Map, Value};
+use sqlx::{PgPool, QueryBuilder};
+use uuid::Uuid;
+
+/// Request header carrying the request's tags
+pub const TAGS_HEADER: &str = "x-doubleword-tags";
+
+/// Most tags recorded for a request
+const MAX_TAGS: usize = 16;
+
+/// Longest tag key or value that is recorded
+const MAX_TAG_LEN: usize = 64;
+
+/// Parse a `key=value` list, returning `None` for the first malformed pair
+fn parse_pairs(list: &str) -> impl Iterator<Item = Option<(&str, &str)>> {
+    list.split(',').filter(|pair| !pair.trim().is_empty()).map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        let (key, value) = (key.trim(), value.trim());
+        let valid = |part: &str| !part.is_empty() && part.len() <= MAX_TAG_LEN;
+        (valid(key) && valid(value)).then_some((key, value))
+    })
+}
+
+/// The tags a request was made with, as a JSON object of keys to values
+pub fn request_tags(request_data: &RequestData) -> Option<Value> {
+    let tags: Map<String, Value> = request_data
+        .headers
+        .get(TAGS_HEADER)
+        .into_iter()
+        .flatten()
+        .filter_map(|value| std::str::from_utf8(value).ok())
+        .flat_map(parse_pairs)
+        .flatten()
+        .take(MAX_TAGS)
+        .map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+        .collect();
+    (!tags.is_empty()).then_some(Value::Object(tags))
+}
+
+/// Parse a tag filter in the header's `key=value` format, failing on malformed pairs
+pub fn parse_tag_filter(filter: &str) -> Result<Value, String> {
+    let tags = parse_pairs(filter)
+        .map(|pair| pair.map(|(key, value)| (key.to_string(), Value::String(value.to_string()))))
+        .collect::<Option<Map<_, _>>>()
+        .ok_or_else(|| format!("tags must be a comma-separated list of key=value pairs, got '{filter}'"))?;
+    if tags.is_empty() {
+        return Err("tags must include at least one key=value pair".to_string());
+    }
+    Ok(Value::Object(tags))
+}
+
+/// Find the logged requests carrying all of `tags`, optionally only those in a conversation.
+///
+/// Applies the same filters, ordering and pagination as [`outlet_postgres::RequestRepository`]
+/// queries, and returns the `(instance_id, correlation_id)` keys of the matching requests in order.
+/// Takes the main database pool, as tags are recorded in the analytics table.
+pub async fn tagged_requests(
+    pool: &PgPool,
+    tags: &Value,
+    conversation_id: Option<&str>,
+    filter: &RequestFilter,
+    after: Option<&RequestCursor>,
+) -> Result<Vec<(Uuid, i64)>, sqlx::Error> {
+    let mut query = QueryBuilder::new(
+        "SELECT r.instance_id, r.correlation_id
+         FROM http_analytics a
+         JOIN outlet.http_requests r ON (r.instance_id = a.instance_id AND r.correlation_id = a.correlation_id)
+         LEFT JOIN outlet.http_responses res ON (r.instance_id = res.instance_id AND r.correlation_id = res.correlation_id)
+         WHERE a.tags @> ",
+    );
+    query.push_bind(tags);
+    if let Some(conversation_id) = conversation_id {
+        query.push(" AND a.conversation_id = ").push_bind(conversation_id);
+    }
+    push_filter(&mut query, filter, after);
+    query.build_query_as().fetch_all(pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn request_data(tags: &[&str]) -> RequestData {
+        RequestData {
+            correlation_id: 1,
+            timestamp: std::time::SystemTime::now(),
+            method: axum::http::Method::POST,
+            uri: "/ai/v1/chat/completions".parse().unwrap(),
+            headers: HashMap::from([(
+                TAGS_HEADER.to_string(),
+                tags.iter().map(|value| Bytes::from(value.to_string())).collect(),
+            )]),
+            body: None,
+        }
+    }
+
+    #[test]
+    fn test_request_tags() {
+        assert_eq!(
+            request_tags(&request_data(&["feature=search, project = atlas"])),
+            Some(json!({"feature": "search", "project": "atlas"}))
+        );
+
+        // Header values are combined, malformed pairs are skipped and the last value of a key wins
+        let long = "x".repeat(MAX_TAG_LEN + 1);
+        let tags = request_tags(&request_data(&[
+            "feature=search,broken,=empty",
+            &format!("feature=chat,{long}=1,team=a=b"),
+        ]));
+        assert_eq!(tags, Some(json!({"feature": "chat", "team": "a=b"})));
+
+        assert_eq!(request_tags(&request_data(&["", "no-tags"])), None);
+        let many: Vec<_> = (0..MAX_TAGS + 4).map(|i| format!("k{i}=v")).collect();
+        let tags = request_tags(&request_data(&[&many.join(",")])).unwrap();
+        assert_eq!(tags.as_object().unwrap().len(), MAX_TAGS);
+    }
+
+    #[test]
+    fn test_parse_tag_filter() {
+        assert_eq!(parse_tag_filter("feature=search").unwrap(), json!({"feature": "search"}));
+        assert_eq!(
+            parse_tag_filter("feature=search,project=atlas").unwrap(),
+            json!({"feature": "search", "project": "atlas"})
+        );
+        assert!(parse_tag_filter("feature").is_err());
+        assert!(parse_tag_filter("feature=search,=x").is_err());
+        assert!(parse_tag_filter(" ").is_err());
+    }
+}
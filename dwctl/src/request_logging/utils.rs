@@ -2,7 +2,7 @@ use crate::request_logging::models::AiResponse;
 use outlet_postgres::SerializationError;
 use std::io::Read as _;
 
-use super::models::{ChatCompletionChunk, SseParseError};
+use super::models::{ChatCompletionChunk, MessagesStreamEvent, SseParseError};
 
 /// Parse a Server-Sent Events string into a vector of data chunks
 ///
@@ -69,6 +69,23 @@ pub(crate) fn parse_streaming_response(body_str: &str) -> Result<AiResponse, Box
         .or_else(|_| serde_json::from_str(body_str).map_err(|e| Box::new(e) as Box<dyn std::error::Error>))
 }
 
+/// Parses a streamed Messages API response body, trying SSE first then JSON fallback
+///
+/// # Errors
+/// Returns error if both SSE parsing and JSON deserialization fail
+pub(crate) fn parse_messages_stream(body_str: &str) -> Result<AiResponse, Box<dyn std::error::Error>> {
+    parse_sse_chunks(body_str)
+        .map(|chunks| {
+            let events = chunks
+                .iter()
+                .filter_map(|chunk| serde_json::from_str::<MessagesStreamEvent>(chunk).ok())
+                .collect();
+            AiResponse::MessagesStream(events)
+        })
+        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+        .or_else(|_| serde_json::from_str(body_str).map_err(|e| Box::new(e) as Box<dyn std::error::Error>))
+}
+
 /// Parses non-streaming response body, expecting JSON format only
 ///
 /// # Errors
@@ -0,0 +1,380 @@
+//! Per-request webhook events for completed AI requests.
+//!
+//! Unlike request mirroring, which forwards every record in batches to an analytics pipeline,
+//! the request webhook POSTs one small event per completed request matching the configured filter
+//! (model, user, outcome and token count), carrying the request's metadata and usage. That suits
+//! downstream systems like quota services or a SIEM that react to individual requests without
+//! database access.
+//!
+//! Events are queued on a bounded in-memory buffer by the analytics serializer once the request's
+//! analytics have been stored, and a background task delivers them with limited concurrency. The
+//! proxy path never waits on delivery: when the buffer is full new events are dropped and counted.
+
+use crate::config::{RequestWebhookConfig, RequestWebhookFilter, RequestWebhookStatus};
+use crate::request_logging::serializers::HttpAnalyticsRow;
+use prometheus::{IntCounterVec, Opts, Registry};
+use rust_decimal::prelude::ToPrimitive;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+use tracing::{debug, error, warn};
+use url::Url;
+use uuid::Uuid;
+
+/// Upper bound on the delay between delivery attempts for a single event
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Token usage of a request
+#[derive(Debug, Clone, Serialize)]
+pub struct EventUsage {
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    /// Whether the token counts were estimated rather than reported by the upstream
+    pub estimated: bool,
+}
+
+/// Body POSTed to the webhook for each completed request
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestCompletedEvent {
+    /// Always `request.completed`
+    #[serde(rename = "type")]
+    pub event_type: &'static str,
+    pub instance_id: Uuid,
+    pub correlation_id: i64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub method: String,
+    pub uri: String,
+    /// Model alias named in the request
+    pub model: Option<String>,
+    /// Model reported by the upstream
+    pub response_model: Option<String>,
+    pub provider: Option<String>,
+    pub status_code: i32,
+    pub duration_ms: i64,
+    pub duration_to_first_byte_ms: Option<i64>,
+    pub user_id: Option<Uuid>,
+    pub user_email: Option<String>,
+    pub access_source: String,
+    pub usage: EventUsage,
+    /// Cost of the request at the prices in effect when it was made, if the model is priced
+    pub cost: Option<f64>,
+    pub conversation_id: Option<String>,
+    pub tags: Option<serde_json::Value>,
+    pub variant: Option<String>,
+}
+
+impl From<&HttpAnalyticsRow> for RequestCompletedEvent {
+    fn from(row: &HttpAnalyticsRow) -> Self {
+        let cost = match (row.input_price_per_token, row.output_price_per_token) {
+            (Some(input), Some(output)) => (input * rust_decimal::Decimal::from(row.prompt_tokens)
+                + output * rust_decimal::Decimal::from(row.completion_tokens))
+            .to_f64(),
+            _ => None,
+        };
+        Self {
+            event_type: "request.completed",
+            instance_id: row.instance_id,
+            correlation_id: row.correlation_id,
+            timestamp: row.timestamp,
+            method: row.method.clone(),
+            uri: row.uri.clone(),
+            model: row.request_model.clone(),
+            response_model: row.response_model.clone(),
+            provider: row.provider_name.clone(),
+            status_code: row.status_code,
+            duration_ms: row.duration_ms,
+            duration_to_first_byte_ms: row.duration_to_first_byte_ms,
+            user_id: row.user_id,
+            user_email: row.user_email.clone(),
+            access_source: row.access_source.clone(),
+            usage: EventUsage {
+                prompt_tokens: row.prompt_tokens,
+                completion_tokens: row.completion_tokens,
+                total_tokens: row.total_tokens,
+                estimated: row.usage_estimated,
+            },
+            cost,
+            conversation_id: row.conversation_id.clone(),
+            tags: row.tags.clone(),
+            variant: row.variant.clone(),
+        }
+    }
+}
+
+/// Whether a completed request produces an event under `filter`
+fn matches(filter: &RequestWebhookFilter, row: &HttpAnalyticsRow) -> bool {
+    let model_matches = filter.models.is_empty()
+        || row
+            .request_model
+            .as_ref()
+            .is_some_and(|model| filter.models.iter().any(|m| m == model));
+    let user_matches = filter.users.is_empty()
+        || filter.users.iter().any(|user| {
+            row.user_id.is_some_and(|id| id.to_string() == *user)
+                || row.user_email.as_ref().is_some_and(|email| email.eq_ignore_ascii_case(user))
+        });
+    let status_matches = match filter.status {
+        RequestWebhookStatus::All => true,
+        RequestWebhookStatus::Success => (200..300).contains(&row.status_code),
+        RequestWebhookStatus::Error => row.status_code >= 400,
+    };
+    model_matches && user_matches && status_matches && row.total_tokens >= filter.min_total_tokens
+}
+
+/// Handle used to queue events for completed requests.
+///
+/// Cloning is cheap; the background task exits once every handle has been dropped and the
+/// remaining events have been delivered.
+#[derive(Clone)]
+pub struct RequestWebhook {
+    sender: mpsc::Sender<RequestCompletedEvent>,
+    filter: Arc<RequestWebhookFilter>,
+    /// Events by outcome: delivered, dropped (buffer full) or failed (retries exhausted)
+    events: IntCounterVec,
+}
+
+impl RequestWebhook {
+    /// Start the delivery task, registering its metrics with `registry` if provided.
+    pub fn spawn(url: Url, config: &RequestWebhookConfig, registry: Option<&Registry>) -> Result<Self, prometheus::Error> {
+        let events = IntCounterVec::new(
+            Opts::new("dwctl_request_webhook_events_total", "Request webhook events by delivery outcome"),
+            &["outcome"],
+        )?;
+        if let Some(registry) = registry {
+            registry.register(Box::new(events.clone()))?;
+        }
+
+        let (sender, receiver) = mpsc::channel(config.buffer_size.max(1));
+        let deliverer = Deliverer {
+            client: reqwest::Client::new(),
+            url,
+            authorization: config.authorization.clone(),
+            max_retries: config.max_retries,
+            request_timeout: config.request_timeout,
+            events: events.clone(),
+        };
+        tokio::spawn(Arc::new(deliverer).run(receiver, config.max_concurrency.max(1)));
+
+        Ok(Self {
+            sender,
+            filter: Arc::new(config.filter.clone()),
+            events,
+        })
+    }
+
+    /// Queue an event for a completed request without waiting, if it matches the filter. Drops
+    /// the event if the buffer is full.
+    pub fn notify(&self, row: &HttpAnalyticsRow) {
+        if !matches(&self.filter, row) {
+            return;
+        }
+        if let Err(e) = self.sender.try_send(RequestCompletedEvent::from(row)) {
+            self.events.with_label_values(&["dropped"]).inc();
+            debug!(correlation_id = row.correlation_id, error = %e, "Dropping request webhook event");
+        }
+    }
+}
+
+struct Deliverer {
+    client: reqwest::Client,
+    url: Url,
+    authorization: Option<String>,
+    max_retries: u32,
+    request_timeout: Duration,
+    events: IntCounterVec,
+}
+
+impl Deliverer {
+    async fn run(self: Arc<Self>, mut receiver: mpsc::Receiver<RequestCompletedEvent>, max_concurrency: usize) {
+        let permits = Arc::new(Semaphore::new(max_concurrency));
+        while let Some(event) = receiver.recv().await {
+            let Ok(permit) = permits.clone().acquire_owned().await else {
+                break;
+            };
+            let deliverer = self.clone();
+            tokio::spawn(async move {
+                deliverer.deliver(&event).await;
+                drop(permit);
+            });
+        }
+
+        debug!("Request webhook task stopped");
+    }
+
+    /// Send an event, retrying with exponential backoff. Gives up after `max_retries` retries.
+    async fn deliver(&self, event: &RequestCompletedEvent) {
+        let mut delay = Duration::from_millis(500);
+
+        for attempt in 0..=self.max_retries {
+            match self.send(event).await {
+                Ok(()) => {
+                    self.events.with_label_values(&["delivered"]).inc();
+                    return;
+                }
+                Err(e) if attempt < self.max_retries => {
+                    warn!(
+                        attempt = attempt + 1,
+                        correlation_id = event.correlation_id,
+                        "Failed to deliver request webhook event, retrying in {}ms: {:#}",
+                        delay.as_millis(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                }
+                Err(e) => {
+                    error!(correlation_id = event.correlation_id, "Giving up on request webhook event: {:#}", e);
+                }
+            }
+        }
+
+        self.events.with_label_values(&["failed"]).inc();
+    }
+
+    async fn send(&self, event: &RequestCompletedEvent) -> anyhow::Result<()> {
+        let mut request = self.client.post(self.url.clone()).timeout(self.request_timeout).json(event);
+        if let Some(authorization) = &self.authorization {
+            request = request.header(reqwest::header::AUTHORIZATION, authorization);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            anyhow::bail!("webhook endpoint returned {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+    use std::sync::Mutex;
+
+    type Received = Arc<Mutex<Vec<serde_json::Value>>>;
+
+    async fn ingest(State(received): State<Received>, Json(body): Json<serde_json::Value>) -> StatusCode {
+        received.lock().unwrap().push(body);
+        StatusCode::OK
+    }
+
+    async fn spawn_receiver(received: Received) -> Url {
+        let app = Router::new().route("/events", post(ingest)).with_state(received);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{addr}/events").parse().unwrap()
+    }
+
+    fn test_row(correlation_id: i64, model: &str, status_code: i32, total_tokens: i64) -> HttpAnalyticsRow {
+        HttpAnalyticsRow {
+            instance_id: Uuid::new_v4(),
+            correlation_id,
+            timestamp: chrono::Utc::now(),
+            method: "POST".to_string(),
+            uri: "/ai/v1/chat/completions".to_string(),
+            request_model: Some(model.to_string()),
+            response_model: None,
+            status_code,
+            duration_ms: 100,
+            duration_to_first_byte_ms: Some(20),
+            prompt_tokens: total_tokens / 2,
+            completion_tokens: total_tokens - total_tokens / 2,
+            total_tokens,
+            usage_estimated: false,
+            response_type: "chat_completion".to_string(),
+            user_id: None,
+            user_email: Some("Alice@example.com".to_string()),
+            access_source: "api_key".to_string(),
+            input_price_per_token: Some(rust_decimal::Decimal::new(1, 3)),
+            output_price_per_token: Some(rust_decimal::Decimal::new(2, 3)),
+            server_address: "localhost".to_string(),
+            server_port: 3001,
+            provider_name: Some("openai".to_string()),
+            embedding_inputs: None,
+            embedding_dimensions: None,
+            pii_categories: None,
+            moderation_decision: None,
+            moderation_categories: None,
+            variant: None,
+            conversation_id: None,
+            tags: None,
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let all = RequestWebhookFilter::default();
+        assert!(matches(&all, &test_row(1, "gpt-4", 500, 0)));
+
+        let filter = RequestWebhookFilter {
+            models: vec!["gpt-4".to_string()],
+            users: vec!["alice@example.com".to_string()],
+            status: RequestWebhookStatus::Success,
+            min_total_tokens: 100,
+        };
+        assert!(matches(&filter, &test_row(1, "gpt-4", 200, 100)));
+        assert!(!matches(&filter, &test_row(1, "claude-3", 200, 100)));
+        assert!(!matches(&filter, &test_row(1, "gpt-4", 429, 100)));
+        assert!(!matches(&filter, &test_row(1, "gpt-4", 200, 99)));
+
+        let mut row = test_row(1, "gpt-4", 200, 100);
+        row.user_email = Some("bob@example.com".to_string());
+        assert!(!matches(&filter, &row));
+        let user_id = Uuid::new_v4();
+        row.user_id = Some(user_id);
+        let by_id = RequestWebhookFilter {
+            users: vec![user_id.to_string()],
+            ..filter
+        };
+        assert!(matches(&by_id, &row));
+
+        let errors = RequestWebhookFilter {
+            status: RequestWebhookStatus::Error,
+            ..Default::default()
+        };
+        assert!(matches(&errors, &test_row(1, "gpt-4", 503, 0)));
+        assert!(!matches(&errors, &test_row(1, "gpt-4", 200, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_matching_requests_are_delivered() {
+        let received = Received::default();
+        let url = spawn_receiver(received.clone()).await;
+        let config = RequestWebhookConfig {
+            enabled: true,
+            filter: RequestWebhookFilter {
+                models: vec!["gpt-4".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let webhook = RequestWebhook::spawn(url, &config, None).unwrap();
+
+        webhook.notify(&test_row(1, "gpt-4", 200, 30));
+        webhook.notify(&test_row(2, "claude-3", 200, 30));
+        webhook.notify(&test_row(3, "gpt-4", 500, 0));
+
+        for _ in 0..100 {
+            if received.lock().unwrap().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let mut events = received.lock().unwrap().clone();
+        events.sort_by_key(|event| event["correlation_id"].as_i64());
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["type"], "request.completed");
+        assert_eq!(events[0]["model"], "gpt-4");
+        assert_eq!(events[0]["usage"]["total_tokens"], 30);
+        assert_eq!(events[0]["usage"]["prompt_tokens"], 15);
+        assert!((events[0]["cost"].as_f64().unwrap() - 0.045).abs() < 1e-9);
+        assert_eq!(events[1]["correlation_id"], 3);
+        assert_eq!(events[1]["status_code"], 500);
+        assert_eq!(webhook.events.with_label_values(&["delivered"]).get(), 2);
+    }
+}
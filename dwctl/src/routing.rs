@@ -0,0 +1,948 @@
+//! Routing of a single deployment alias across several inference endpoints.
+//!
+//! onwards maps every alias to exactly one upstream. To give an alias fallbacks or a weighted
+//! traffic split, `sync::onwards_config` registers an internal target per extra endpoint and
+//! publishes a [`RoutingTable`] listing the internal targets behind each alias. The
+//! [`fallback_routing`] middleware wraps the onwards router and redirects requests by rewriting
+//! their `model` field: each request first goes to a target picked by weight (or the alias' own
+//! target if it has no split), and when that returns a 5xx or 429, or doesn't respond within the
+//! attempt timeout, the request is replayed against each fallback in turn.
+//!
+//! An alias can also have a canary: another deployment that takes a percentage of its requests
+//! in place of the alias' own target or split. Responses for such aliases carry a
+//! [`VARIANT_HEADER`] saying which variant the request was routed to, which request logging
+//! records so the two can be compared.
+//!
+//! An alias can also have a shadow: another deployment that receives a copy of a percentage of
+//! its requests without affecting what users see. [`Shadowing`] sends each copy back through the
+//! AI proxy in the background, marked with a [`SHADOW_HEADER`] token that routes it to the
+//! shadow, so the shadow's response is logged (as the `shadow` variant) and then discarded.
+//!
+//! When the circuit breaker of an alias is open (its probe has failed repeatedly), requests skip
+//! the alias' own targets and go straight to its fallbacks, or are refused with a 503 if it has
+//! none. Probe requests, marked with [`PROBE_HEADER`], are routed as usual so the probe can see
+//! the deployment recover.
+
+use crate::deprecation::Deprecation;
+use crate::header_rules::HeaderRules;
+use crate::model_metadata::ModelMetadata;
+use crate::redaction::RedactionRules;
+use crate::request_logging::policy::LoggingPolicy;
+use crate::tls::EndpointTls;
+use crate::types::InferenceEndpointId;
+use crate::upstream_limits::UpstreamLimit;
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use rand::Rng;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{debug, warn};
+
+/// Separator between an alias and the priority of one of its fallbacks in internal target names
+const FALLBACK_SEPARATOR: &str = "::fallback-";
+
+/// Separator between an alias and the position of one of its weighted targets in internal target names
+const SPLIT_SEPARATOR: &str = "::split-";
+
+/// Suffix of the internal target serving an alias' canary
+const CANARY_SUFFIX: &str = "::canary";
+
+/// Suffix of the internal target serving an alias' shadow
+const SHADOW_SUFFIX: &str = "::shadow";
+
+/// Header that onwards also reads the model from, so it's rewritten alongside the body
+const MODEL_OVERRIDE_HEADER: &str = "model-override";
+
+/// Response header naming the variant (`stable`, `canary` or `shadow`) of an alias with a canary
+/// or shadow that a request was routed to
+pub const VARIANT_HEADER: &str = "x-doubleword-variant";
+
+/// Request header marking probe requests, which are routed to an alias' own targets even while
+/// its circuit breaker is open
+pub const PROBE_HEADER: &str = "x-doubleword-probe";
+
+/// Request header carrying the token that marks copies of requests mirrored to an alias' shadow
+pub const SHADOW_HEADER: &str = "x-doubleword-shadow";
+
+/// How long a mirrored request may take before it's abandoned
+const SHADOW_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Name of the internal onwards target serving the fallback of `alias` with the given priority
+pub fn fallback_alias(alias: &str, priority: i32) -> String {
+    format!("{alias}{FALLBACK_SEPARATOR}{priority}")
+}
+
+/// Name of the internal onwards target serving the weighted target of `alias` at the given position
+pub fn split_alias(alias: &str, position: i32) -> String {
+    format!("{alias}{SPLIT_SEPARATOR}{position}")
+}
+
+/// Name of the internal onwards target serving the canary of `alias`
+pub fn canary_alias(alias: &str) -> String {
+    format!("{alias}{CANARY_SUFFIX}")
+}
+
+/// Name of the internal onwards target serving the shadow of `alias`
+pub fn shadow_alias(alias: &str) -> String {
+    format!("{alias}{SHADOW_SUFFIX}")
+}
+
+/// Which internal targets back each deployment alias
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoutingTable {
+    fallbacks: HashMap<String, Vec<String>>,
+    splits: HashMap<String, Vec<(String, u32)>>,
+    canaries: HashMap<String, (String, u32)>,
+    shadows: HashMap<String, (String, u32)>,
+    open_circuits: HashSet<String>,
+    internal: HashSet<String>,
+    api_versions: HashMap<String, String>,
+    anthropic: HashSet<String>,
+    redaction: HashMap<String, RedactionRules>,
+    header_rules: HashMap<String, HeaderRules>,
+    logging_policies: HashMap<String, LoggingPolicy>,
+    body_sample_percents: HashMap<String, u32>,
+    /// TLS settings of endpoints by URL, longest URL first
+    endpoint_tls: Vec<(String, EndpointTls)>,
+    upstream_limits: HashMap<String, (InferenceEndpointId, UpstreamLimit)>,
+    model_metadata: HashMap<String, ModelMetadata>,
+    deprecations: HashMap<String, Deprecation>,
+}
+
+impl RoutingTable {
+    /// Set the internal targets to try, in order, after the primary target of `alias`
+    pub fn set_fallbacks(&mut self, alias: String, targets: Vec<String>) {
+        if targets.is_empty() {
+            return;
+        }
+        self.internal.extend(targets.iter().cloned());
+        self.fallbacks.insert(alias, targets);
+    }
+
+    pub fn fallbacks(&self, alias: &str) -> &[String] {
+        self.fallbacks.get(alias).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Split the traffic of `alias` across internal targets in proportion to their weights
+    pub fn set_split(&mut self, alias: String, mut targets: Vec<(String, u32)>) {
+        targets.retain(|(_, weight)| *weight > 0);
+        if targets.is_empty() {
+            return;
+        }
+        self.internal.extend(targets.iter().map(|(target, _)| target.clone()));
+        self.splits.insert(alias, targets);
+    }
+
+    /// Internal targets `alias` splits its traffic across, with their weights
+    pub fn split(&self, alias: &str) -> &[(String, u32)] {
+        self.splits.get(alias).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Pick the target for a request to `alias`, or `None` if it has no split and its own target
+    /// should be used
+    pub fn pick_target(&self, alias: &str, rng: &mut impl Rng) -> Option<&str> {
+        let targets = self.splits.get(alias)?;
+        let total: u64 = targets.iter().map(|(_, weight)| u64::from(*weight)).sum();
+        let mut roll = rng.gen_range(0..total);
+        for (target, weight) in targets {
+            let weight = u64::from(*weight);
+            if roll < weight {
+                return Some(target);
+            }
+            roll -= weight;
+        }
+        unreachable!("the roll is always below the total weight")
+    }
+
+    /// Send `percent` of the traffic of `alias` to the internal canary target instead
+    pub fn set_canary(&mut self, alias: String, target: String, percent: u32) {
+        let percent = percent.min(100);
+        if percent == 0 {
+            return;
+        }
+        self.internal.insert(target.clone());
+        self.canaries.insert(alias, (target, percent));
+    }
+
+    /// The canary target of `alias` and the percentage of its traffic it takes
+    pub fn canary(&self, alias: &str) -> Option<(&str, u32)> {
+        self.canaries.get(alias).map(|(target, percent)| (target.as_str(), *percent))
+    }
+
+    /// Decide whether a request to `alias` goes to its canary. Returns `None` if the alias has no
+    /// canary, otherwise the variant picked and, for the canary, the target to use.
+    pub fn pick_variant(&self, alias: &str, rng: &mut impl Rng) -> Option<(Variant, Option<&str>)> {
+        let (target, percent) = self.canary(alias)?;
+        if rng.gen_range(0..100) < percent {
+            Some((Variant::Canary, Some(target)))
+        } else {
+            Some((Variant::Stable, None))
+        }
+    }
+
+    /// Mirror `percent` of the traffic of `alias` to the internal shadow target
+    pub fn set_shadow(&mut self, alias: String, target: String, percent: u32) {
+        let percent = percent.min(100);
+        if percent == 0 {
+            return;
+        }
+        self.internal.insert(target.clone());
+        self.shadows.insert(alias, (target, percent));
+    }
+
+    /// The shadow target of `alias` and the percentage of its traffic mirrored to it
+    pub fn shadow(&self, alias: &str) -> Option<(&str, u32)> {
+        self.shadows.get(alias).map(|(target, percent)| (target.as_str(), *percent))
+    }
+
+    /// Decide whether a request to `alias` is mirrored to its shadow
+    pub fn pick_shadow(&self, alias: &str, rng: &mut impl Rng) -> bool {
+        self.shadow(alias).is_some_and(|(_, percent)| rng.gen_range(0..100) < percent)
+    }
+
+    /// Take the own targets of `alias` out of rotation after repeated probe failures
+    pub fn open_circuit(&mut self, alias: String) {
+        self.open_circuits.insert(alias);
+    }
+
+    pub fn is_circuit_open(&self, alias: &str) -> bool {
+        self.open_circuits.contains(alias)
+    }
+
+    /// Send requests for the Azure OpenAI target `target` with API version `version`
+    pub fn set_api_version(&mut self, target: String, version: String) {
+        self.api_versions.insert(target, version);
+    }
+
+    pub fn api_version(&self, target: &str) -> Option<&str> {
+        self.api_versions.get(target).map(String::as_str)
+    }
+
+    pub fn has_api_versions(&self) -> bool {
+        !self.api_versions.is_empty()
+    }
+
+    /// Mark `target` as served by an Anthropic endpoint, which takes Messages API requests as-is
+    pub fn set_anthropic(&mut self, target: String) {
+        self.anthropic.insert(target);
+    }
+
+    pub fn is_anthropic(&self, target: &str) -> bool {
+        self.anthropic.contains(target)
+    }
+
+    /// Mask PII in requests for `target` according to the redaction policy of its endpoint
+    pub fn set_redaction(&mut self, target: String, rules: RedactionRules) {
+        if rules.is_empty() {
+            return;
+        }
+        self.redaction.insert(target, rules);
+    }
+
+    pub fn redaction(&self, target: &str) -> Option<&RedactionRules> {
+        self.redaction.get(target)
+    }
+
+    pub fn has_redaction(&self) -> bool {
+        !self.redaction.is_empty()
+    }
+
+    /// Rules to apply to the logged copy of requests for `alias`: those of the first of the
+    /// targets it may be routed to whose policy doesn't allow logging the original
+    pub fn log_redaction(&self, alias: &str) -> Option<&RedactionRules> {
+        let targets = std::iter::once(alias)
+            .chain(self.split(alias).iter().map(|(target, _)| target.as_str()))
+            .chain(self.canary(alias).map(|(target, _)| target))
+            .chain(self.shadow(alias).map(|(target, _)| target))
+            .chain(self.fallbacks(alias).iter().map(String::as_str));
+        targets
+            .filter_map(|target| self.redaction(target))
+            .find(|rules| !rules.log_original())
+    }
+
+    /// Limit what the request log keeps of the bodies of requests for `alias`
+    pub fn set_logging_policy(&mut self, alias: String, policy: LoggingPolicy) {
+        self.logging_policies.insert(alias, policy);
+    }
+
+    pub fn logging_policy(&self, alias: &str) -> Option<&LoggingPolicy> {
+        self.logging_policies.get(alias)
+    }
+
+    /// Keep the bodies of only `percent` of the requests for `alias`
+    pub fn set_body_sample_percent(&mut self, alias: String, percent: u32) {
+        if percent < 100 {
+            self.body_sample_percents.insert(alias, percent);
+        }
+    }
+
+    /// The percentage of the requests for `alias` whose bodies are kept, if not all of them
+    pub fn body_sample_percent(&self, alias: &str) -> Option<u32> {
+        self.body_sample_percents.get(alias).copied()
+    }
+
+    pub fn has_logging_policies(&self) -> bool {
+        !self.logging_policies.is_empty() || !self.body_sample_percents.is_empty()
+    }
+
+    /// Make connections to the endpoint at `url` with its TLS settings
+    pub fn set_endpoint_tls(&mut self, url: String, tls: EndpointTls) {
+        self.endpoint_tls.retain(|(existing, _)| *existing != url);
+        self.endpoint_tls.push((url, tls));
+        self.endpoint_tls.sort_by_key(|(url, _)| std::cmp::Reverse(url.len()));
+    }
+
+    /// The URL and TLS settings of the endpoint serving `uri`, the one with the longest URL if
+    /// several could, unless it has none
+    pub fn endpoint_tls(&self, uri: &str) -> Option<(&str, &EndpointTls)> {
+        self.endpoint_tls
+            .iter()
+            .find(|(url, _)| uri.starts_with(url.as_str()))
+            .filter(|(_, tls)| !tls.is_default())
+            .map(|(url, tls)| (url.as_str(), tls))
+    }
+
+    /// Inject and filter the headers of requests for `target` according to the rules of its endpoint
+    pub fn set_header_rules(&mut self, target: String, rules: HeaderRules) {
+        if rules.is_empty() {
+            return;
+        }
+        self.header_rules.insert(target, rules);
+    }
+
+    pub fn header_rules(&self, target: &str) -> Option<&HeaderRules> {
+        self.header_rules.get(target)
+    }
+
+    pub fn has_header_rules(&self) -> bool {
+        !self.header_rules.is_empty()
+    }
+
+    /// Limit the requests for `target` forwarded to its endpoint, sharing the limit with the
+    /// endpoint's other targets
+    pub fn set_upstream_limit(&mut self, target: String, endpoint_id: InferenceEndpointId, limit: UpstreamLimit) {
+        self.upstream_limits.insert(target, (endpoint_id, limit));
+    }
+
+    /// The endpoint serving `target` and its limits, if it has any
+    pub fn upstream_limit(&self, target: &str) -> Option<(InferenceEndpointId, UpstreamLimit)> {
+        self.upstream_limits.get(target).copied()
+    }
+
+    pub fn has_upstream_limits(&self) -> bool {
+        !self.upstream_limits.is_empty()
+    }
+
+    /// List `alias` in the model list with the given metadata
+    pub fn set_model_metadata(&mut self, alias: String, metadata: ModelMetadata) {
+        self.model_metadata.insert(alias, metadata);
+    }
+
+    pub fn model_metadata(&self, alias: &str) -> Option<&ModelMetadata> {
+        self.model_metadata.get(alias)
+    }
+
+    /// Mark `alias` as deprecated, warning its clients until it's retired
+    pub fn set_deprecation(&mut self, alias: String, deprecation: Deprecation) {
+        self.deprecations.insert(alias, deprecation);
+    }
+
+    pub fn deprecation(&self, alias: &str) -> Option<&Deprecation> {
+        self.deprecations.get(alias)
+    }
+
+    pub fn has_deprecations(&self) -> bool {
+        !self.deprecations.is_empty()
+    }
+
+    /// Internal targets can only be reached through their alias
+    pub fn is_internal(&self, alias: &str) -> bool {
+        self.internal.contains(alias)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fallbacks.is_empty()
+            && self.splits.is_empty()
+            && self.canaries.is_empty()
+            && self.shadows.is_empty()
+            && self.open_circuits.is_empty()
+    }
+}
+
+/// Which side of a canary rollout or shadow evaluation a request was routed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Stable,
+    Canary,
+    Shadow,
+}
+
+impl Variant {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Variant::Stable => "stable",
+            Variant::Canary => "canary",
+            Variant::Shadow => "shadow",
+        }
+    }
+}
+
+/// Mirrors requests to the shadow of their alias, by sending a copy back through the AI proxy
+#[derive(Debug, Clone)]
+pub struct Shadowing {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    /// Random per-process token that marks mirrored requests, so clients can't address a shadow
+    token: String,
+}
+
+impl Shadowing {
+    /// Send copies to `base_url` (the AI proxy's own `/ai/v1`), authenticated with `api_key` so
+    /// they aren't attributed to the user who made the original request
+    pub fn new(base_url: String, api_key: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url,
+            api_key,
+            token: format!("{:032x}", rand::thread_rng().gen::<u128>()),
+        }
+    }
+
+    fn is_mirrored(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get(SHADOW_HEADER)
+            .is_some_and(|value| value.as_bytes() == self.token.as_bytes())
+    }
+
+    /// Send a copy of a request in the background, discarding the response
+    fn mirror(&self, model: &str, parts: &Parts, body: Bytes) {
+        let path = parts
+            .uri
+            .path_and_query()
+            .map(|path| path.as_str())
+            .unwrap_or_else(|| parts.uri.path());
+        let mut request = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .bearer_auth(&self.api_key)
+            .header(SHADOW_HEADER, &self.token)
+            .timeout(SHADOW_TIMEOUT)
+            .body(body);
+        if let Some(content_type) = parts.headers.get(header::CONTENT_TYPE) {
+            request = request.header(header::CONTENT_TYPE, content_type.as_bytes());
+        }
+
+        let model = model.to_string();
+        tokio::spawn(async move {
+            match request.send().await {
+                // Read the whole response so it's logged, then drop it
+                Ok(response) => {
+                    let status = response.status();
+                    let _ = response.bytes().await;
+                    debug!("Shadow of '{}' responded with status {}", model, status);
+                }
+                Err(e) => warn!("Failed to mirror request for '{}' to its shadow: {}", model, e),
+            }
+        });
+    }
+}
+
+/// State for the [`fallback_routing`] middleware
+#[derive(Debug, Clone)]
+pub struct FallbackRouting {
+    table: watch::Receiver<RoutingTable>,
+    attempt_timeout: Duration,
+    shadowing: Option<Shadowing>,
+}
+
+impl FallbackRouting {
+    pub fn new(table: watch::Receiver<RoutingTable>, attempt_timeout: Duration) -> Self {
+        Self {
+            table,
+            attempt_timeout,
+            shadowing: None,
+        }
+    }
+
+    /// Mirror requests to the shadows of their aliases
+    pub fn with_shadowing(mut self, shadowing: Shadowing) -> Self {
+        self.shadowing = Some(shadowing);
+        self
+    }
+}
+
+/// Middleware that spreads AI requests across an alias' weighted targets, retries failed
+/// requests against its fallback endpoints and mirrors requests to its shadow
+pub async fn fallback_routing(State(routing): State<FallbackRouting>, request: Request, next: Next) -> Response {
+    if routing.table.borrow().is_empty() {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => return error_response(StatusCode::BAD_REQUEST, "Failed to read request body"),
+    };
+
+    let Ok(model) = onwards::extract_model_from_request(&parts.headers, &body) else {
+        // Let onwards produce its usual error for requests without a model
+        return next.run(Request::from_parts(parts, Body::from(body))).await;
+    };
+
+    if routing
+        .shadowing
+        .as_ref()
+        .is_some_and(|shadowing| shadowing.is_mirrored(&parts.headers))
+    {
+        return send_to_shadow(&routing, &model, parts, body, next).await;
+    }
+
+    let is_probe = parts.headers.contains_key(PROBE_HEADER);
+    let (first, fallbacks, mut variant) = {
+        let table = routing.table.borrow();
+        if table.is_internal(&model) {
+            return error_response(StatusCode::NOT_FOUND, &format!("The model '{model}' does not exist"));
+        }
+        if table.is_circuit_open(&model) && !is_probe {
+            // Skip the alias' own targets (and its canary) until its probe succeeds again
+            let Some((first, fallbacks)) = table.fallbacks(&model).split_first() else {
+                return error_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    &format!("The model '{model}' is currently unavailable, please retry later"),
+                );
+            };
+            debug!("Circuit breaker for '{}' is open, sending request to its fallbacks", model);
+            (Some(first.clone()), fallbacks.to_vec(), None)
+        } else {
+            let mut rng = rand::thread_rng();
+            let (variant, first) = match table.pick_variant(&model, &mut rng) {
+                Some((Variant::Canary, canary)) => (Some(Variant::Canary), canary.map(str::to_string)),
+                variant => (
+                    variant.map(|(variant, _)| variant),
+                    table.pick_target(&model, &mut rng).map(str::to_string),
+                ),
+            };
+            (first, table.fallbacks(&model).to_vec(), variant)
+        }
+    };
+
+    if let Some(shadowing) = &routing.shadowing {
+        if !is_probe && routing.table.borrow().pick_shadow(&model, &mut rand::thread_rng()) {
+            shadowing.mirror(&model, &parts, body.clone());
+            // Tag the response the user gets, to compare with the shadow's
+            variant.get_or_insert(Variant::Stable);
+        }
+    }
+
+    let mut response = if first.is_none() && fallbacks.is_empty() {
+        next.run(Request::from_parts(parts, Body::from(body))).await
+    } else {
+        send_with_fallbacks(&routing, &model, parts, body, first, fallbacks, next).await
+    };
+    if let Some(variant) = variant {
+        response
+            .headers_mut()
+            .insert(VARIANT_HEADER, HeaderValue::from_static(variant.as_str()));
+    }
+    response
+}
+
+/// Send a mirrored request to the shadow of `model`, without fallbacks
+async fn send_to_shadow(routing: &FallbackRouting, model: &str, mut parts: Parts, body: Bytes, next: Next) -> Response {
+    parts.headers.remove(SHADOW_HEADER);
+    let Some((target, _)) = routing
+        .table
+        .borrow()
+        .shadow(model)
+        .map(|(target, percent)| (target.to_string(), percent))
+    else {
+        return error_response(StatusCode::NOT_FOUND, &format!("The model '{model}' has no shadow"));
+    };
+
+    let mut response = send_with_fallbacks(routing, model, parts, body, Some(target), Vec::new(), next).await;
+    response
+        .headers_mut()
+        .insert(VARIANT_HEADER, HeaderValue::from_static(Variant::Shadow.as_str()));
+    response
+}
+
+/// Send a request to `first` (or the alias' own target, if `None`), then to each fallback in turn
+/// until one succeeds
+async fn send_with_fallbacks(
+    routing: &FallbackRouting,
+    model: &str,
+    parts: Parts,
+    body: Bytes,
+    first: Option<String>,
+    fallbacks: Vec<String>,
+    next: Next,
+) -> Response {
+    // `None` sends the request to the alias' own target, unchanged
+    let attempts: Vec<Option<&String>> = std::iter::once(first.as_ref()).chain(fallbacks.iter().map(Some)).collect();
+    let attempt_count = attempts.len();
+    for (i, target) in attempts.into_iter().enumerate() {
+        let is_last = i + 1 == attempt_count;
+
+        let mut attempt = Request::from_parts(parts.clone(), Body::empty());
+        match target {
+            None => *attempt.body_mut() = Body::from(body.clone()),
+            Some(target) => {
+                let Some(rewritten) = rewrite_model(&body, target) else {
+                    return error_response(StatusCode::BAD_REQUEST, "Request body is not a JSON object");
+                };
+                let headers = attempt.headers_mut();
+                headers.insert(header::CONTENT_LENGTH, HeaderValue::from(rewritten.len()));
+                if headers.contains_key(MODEL_OVERRIDE_HEADER) {
+                    if let Ok(value) = HeaderValue::from_str(target) {
+                        headers.insert(MODEL_OVERRIDE_HEADER, value);
+                    }
+                }
+                *attempt.body_mut() = Body::from(rewritten);
+            }
+        }
+
+        match tokio::time::timeout(routing.attempt_timeout, next.clone().run(attempt)).await {
+            Ok(response) if is_last || !is_retryable(response.status()) => {
+                if i > 0 {
+                    debug!("Request for '{}' served by fallback {}", model, i);
+                }
+                return response;
+            }
+            Ok(response) => {
+                warn!(
+                    "Attempt {} for '{}' failed with status {}, trying next fallback",
+                    i,
+                    model,
+                    response.status()
+                );
+            }
+            Err(_) if is_last => {
+                return error_response(StatusCode::GATEWAY_TIMEOUT, "All endpoints for this model timed out");
+            }
+            Err(_) => {
+                warn!(
+                    "Attempt {} for '{}' timed out after {:?}, trying next fallback",
+                    i, model, routing.attempt_timeout
+                );
+            }
+        }
+    }
+
+    unreachable!("the last attempt always returns")
+}
+
+/// Server errors and rate limiting are worth retrying elsewhere; client errors are not
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn rewrite_model(body: &Bytes, target: &str) -> Option<Bytes> {
+    let mut value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    value.as_object_mut()?.insert("model".to_string(), target.into());
+    serde_json::to_vec(&value).ok().map(Bytes::from)
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({"error": {"message": message, "type": "invalid_request_error", "param": null, "code": null}})),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware::from_fn_with_state, routing::post, Router};
+    use axum_test::TestServer;
+    use serde_json::Value;
+    use std::sync::{Arc, Mutex};
+
+    /// A fake proxy that fails for some targets and records which targets were called
+    fn server(table: RoutingTable, failing: &'static [(&'static str, u16)], delay: Duration) -> (TestServer, Arc<Mutex<Vec<String>>>) {
+        let (upstream, calls) = upstream(failing, delay);
+
+        // The receiver keeps the last value after the sender is dropped
+        let (_, receiver) = watch::channel(table);
+        let routing = FallbackRouting::new(receiver, Duration::from_millis(200));
+        let app = upstream.layer(from_fn_with_state(routing, fallback_routing));
+        (TestServer::new(app).unwrap(), calls)
+    }
+
+    /// The fake proxy served over HTTP, so requests mirrored to shadows come back through it
+    async fn shadow_server(table: RoutingTable) -> (String, Arc<Mutex<Vec<String>>>) {
+        let (upstream, calls) = upstream(&[], Duration::ZERO);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let (_, receiver) = watch::channel(table);
+        let shadowing = Shadowing::new(base_url.clone(), "system-key".to_string());
+        let routing = FallbackRouting::new(receiver, Duration::from_millis(200)).with_shadowing(shadowing);
+        let app = upstream.layer(from_fn_with_state(routing, fallback_routing));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (base_url, calls)
+    }
+
+    fn upstream(failing: &'static [(&'static str, u16)], delay: Duration) -> (Router, Arc<Mutex<Vec<String>>>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        let upstream = Router::new().route(
+            "/chat/completions",
+            post(move |Json(body): Json<Value>| {
+                let calls = recorded.clone();
+                async move {
+                    let model = body["model"].as_str().unwrap().to_string();
+                    calls.lock().unwrap().push(model.clone());
+                    if model.ends_with("slow") {
+                        tokio::time::sleep(delay).await;
+                    }
+                    match failing.iter().find(|(m, _)| *m == model) {
+                        Some((_, status)) => StatusCode::from_u16(*status).unwrap().into_response(),
+                        None => Json(json!({"served_by": model})).into_response(),
+                    }
+                }
+            }),
+        );
+        (upstream, calls)
+    }
+
+    fn table() -> RoutingTable {
+        let mut table = RoutingTable::default();
+        table.set_fallbacks("gpt".to_string(), vec![fallback_alias("gpt", 1), fallback_alias("gpt", 2)]);
+        table
+    }
+
+    #[tokio::test]
+    async fn test_primary_success_is_not_retried() {
+        let (server, calls) = server(table(), &[], Duration::ZERO);
+        let response = server.post("/chat/completions").json(&json!({"model": "gpt"})).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<Value>()["served_by"], "gpt");
+        assert_eq!(*calls.lock().unwrap(), vec!["gpt"]);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_in_order_on_server_errors_and_rate_limits() {
+        let (server, calls) = server(table(), &[("gpt", 503), ("gpt::fallback-1", 429)], Duration::ZERO);
+        let response = server.post("/chat/completions").json(&json!({"model": "gpt"})).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<Value>()["served_by"], "gpt::fallback-2");
+        assert_eq!(*calls.lock().unwrap(), vec!["gpt", "gpt::fallback-1", "gpt::fallback-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let (server, calls) = server(table(), &[("gpt", 400)], Duration::ZERO);
+        let response = server.post("/chat/completions").json(&json!({"model": "gpt"})).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert_eq!(calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_last_failure_is_returned() {
+        let (server, _) = server(
+            table(),
+            &[("gpt", 500), ("gpt::fallback-1", 502), ("gpt::fallback-2", 503)],
+            Duration::ZERO,
+        );
+        let response = server.post("/chat/completions").json(&json!({"model": "gpt"})).await;
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_falls_back() {
+        let mut table = RoutingTable::default();
+        table.set_fallbacks("slow".to_string(), vec![fallback_alias("slow", 1)]);
+        let (server, calls) = server(table, &[], Duration::from_secs(5));
+
+        let response = server.post("/chat/completions").json(&json!({"model": "slow"})).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<Value>()["served_by"], "slow::fallback-1");
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_internal_targets_cannot_be_requested_directly() {
+        let (server, calls) = server(table(), &[], Duration::ZERO);
+        let response = server.post("/chat/completions").json(&json!({"model": "gpt::fallback-1"})).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        assert!(calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pick_target_follows_weights() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut table = RoutingTable::default();
+        table.set_split(
+            "llama".to_string(),
+            vec![
+                (split_alias("llama", 1), 80),
+                (split_alias("llama", 2), 20),
+                (split_alias("llama", 3), 0),
+            ],
+        );
+        assert!(table.is_internal("llama::split-1"));
+        assert!(!table.is_internal("llama::split-3"));
+        assert_eq!(table.pick_target("other", &mut StdRng::seed_from_u64(0)), None);
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut counts = HashMap::new();
+        for _ in 0..10_000 {
+            *counts.entry(table.pick_target("llama", &mut rng).unwrap().to_string()).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 2);
+        let share = counts["llama::split-1"] as f64 / 10_000.0;
+        assert!((0.77..0.83).contains(&share), "share was {share}");
+    }
+
+    #[tokio::test]
+    async fn test_split_targets_fall_back() {
+        let mut table = RoutingTable::default();
+        table.set_split("llama".to_string(), vec![(split_alias("llama", 1), 1)]);
+        table.set_fallbacks("llama".to_string(), vec![fallback_alias("llama", 1)]);
+        let (server, calls) = server(table, &[("llama::split-1", 502)], Duration::ZERO);
+
+        let response = server.post("/chat/completions").json(&json!({"model": "llama"})).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<Value>()["served_by"], "llama::fallback-1");
+        // The alias' own target isn't used once it has a split
+        assert_eq!(*calls.lock().unwrap(), vec!["llama::split-1", "llama::fallback-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_aliases_without_fallbacks_pass_through() {
+        let (server, calls) = server(table(), &[("other", 503)], Duration::ZERO);
+        let response = server.post("/chat/completions").json(&json!({"model": "other"})).await;
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(*calls.lock().unwrap(), vec!["other"]);
+    }
+
+    #[tokio::test]
+    async fn test_canary_requests_are_tagged_with_their_variant() {
+        let mut table = RoutingTable::default();
+        table.set_canary("gpt".to_string(), canary_alias("gpt"), 100);
+        table.set_fallbacks("gpt".to_string(), vec![fallback_alias("gpt", 1)]);
+        let (server, calls) = server(table, &[], Duration::ZERO);
+
+        let response = server.post("/chat/completions").json(&json!({"model": "gpt"})).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<Value>()["served_by"], "gpt::canary");
+        assert_eq!(response.header(VARIANT_HEADER), "canary");
+
+        // The canary target is internal, and aliases without a canary aren't tagged
+        let response = server.post("/chat/completions").json(&json!({"model": "gpt::canary"})).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        let response = server.post("/chat/completions").json(&json!({"model": "other"})).await;
+        assert!(response.maybe_header(VARIANT_HEADER).is_none());
+        assert_eq!(*calls.lock().unwrap(), vec!["gpt::canary", "other"]);
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_skips_to_fallbacks_or_refuses() {
+        let mut table = table();
+        table.open_circuit("gpt".to_string());
+        table.open_circuit("solo".to_string());
+        let (server, calls) = server(table, &[], Duration::ZERO);
+
+        let response = server.post("/chat/completions").json(&json!({"model": "gpt"})).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<Value>()["served_by"], "gpt::fallback-1");
+
+        let response = server.post("/chat/completions").json(&json!({"model": "solo"})).await;
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+
+        // Probes still reach the alias' own target
+        let response = server
+            .post("/chat/completions")
+            .add_header(PROBE_HEADER, "true")
+            .json(&json!({"model": "solo"}))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(*calls.lock().unwrap(), vec!["gpt::fallback-1", "solo"]);
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_mirrored_to_shadow() {
+        let mut table = RoutingTable::default();
+        table.set_shadow("gpt".to_string(), shadow_alias("gpt"), 100);
+        let (base_url, calls) = shadow_server(table).await;
+        let client = reqwest::Client::new();
+
+        // The user only sees the alias' own response
+        let response = client
+            .post(format!("{base_url}/chat/completions"))
+            .json(&json!({"model": "gpt"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[VARIANT_HEADER], "stable");
+        assert_eq!(response.json::<Value>().await.unwrap()["served_by"], "gpt");
+
+        // The copy arrives in the background
+        for _ in 0..100 {
+            if calls.lock().unwrap().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(*calls.lock().unwrap(), vec!["gpt", "gpt::shadow"]);
+
+        // Probes aren't mirrored, and clients can't mark their own requests as mirrored
+        let response = client
+            .post(format!("{base_url}/chat/completions"))
+            .header(PROBE_HEADER, "1")
+            .header(SHADOW_HEADER, "guess")
+            .json(&json!({"model": "gpt"}))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.headers().get(VARIANT_HEADER).is_none());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(*calls.lock().unwrap(), vec!["gpt", "gpt::shadow", "gpt"]);
+
+        // The shadow target is internal
+        let response = client
+            .post(format!("{base_url}/chat/completions"))
+            .json(&json!({"model": "gpt::shadow"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_pick_variant_follows_percentage() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut table = RoutingTable::default();
+        table.set_split("llama".to_string(), vec![(split_alias("llama", 1), 1)]);
+        table.set_canary("llama".to_string(), canary_alias("llama"), 10);
+        assert_eq!(table.canary("llama"), Some(("llama::canary", 10)));
+        assert_eq!(table.pick_variant("other", &mut StdRng::seed_from_u64(0)), None);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut canary = 0;
+        for _ in 0..10_000 {
+            match table.pick_variant("llama", &mut rng).unwrap() {
+                (Variant::Canary, target) => {
+                    assert_eq!(target, Some("llama::canary"));
+                    canary += 1;
+                }
+                (Variant::Stable, target) => assert_eq!(target, None),
+                (Variant::Shadow, _) => unreachable!("shadows aren't picked as variants"),
+            }
+        }
+        let share = canary as f64 / 10_000.0;
+        assert!((0.08..0.12).contains(&share), "share was {share}");
+    }
+}
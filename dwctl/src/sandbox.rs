@@ -0,0 +1,506 @@
+//! Built-in mock inference backend.
+//!
+//! When `sandbox.enabled` is set, an OpenAI-compatible backend is served at `/sandbox/v1` and
+//! registered as an inference endpoint, so that new users and CI environments can exercise the
+//! whole gateway (auth, proxying, request logging, analytics) without a real model server.
+//!
+//! Responses are synthetic: models whose name contains `echo` repeat the last user message, and
+//! all other models return lorem ipsum. Streaming responses are sent one word at a time with a
+//! configurable delay, and every response carries usage numbers (one token per word).
+
+use crate::config::{Config, SandboxConfig};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{
+        sse::{Event, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use futures::{stream, StreamExt};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::convert::Infallible;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+const LOREM: &str = "lorem ipsum dolor sit amet consectetur adipiscing elit sed do eiusmod tempor incididunt ut labore et dolore \
+                     magna aliqua ut enim ad minim veniam quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo \
+                     consequat duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur";
+
+/// Number of words generated for lorem ipsum models when the request doesn't set `max_tokens`
+const DEFAULT_COMPLETION_TOKENS: usize = 64;
+
+/// Default number of dimensions of sandbox embeddings
+const DEFAULT_EMBEDDING_DIMENSIONS: usize = 16;
+
+/// URL under which the sandbox backend is reachable from this process
+pub fn sandbox_url(config: &Config) -> url::Url {
+    format!("http://127.0.0.1:{}/sandbox/v1", config.port)
+        .parse()
+        .expect("sandbox URL is valid")
+}
+
+/// Register the sandbox as an inference endpoint, if it isn't already
+///
+/// Unlike `seed_database`, this runs on every startup, so the sandbox can be switched on for an
+/// existing deployment. An existing endpoint with the same name is left untouched.
+#[instrument(skip_all, err)]
+pub async fn register_endpoint(config: &Config, db: &PgPool) -> Result<(), sqlx::Error> {
+    let url = sandbox_url(config);
+    let result = sqlx::query!(
+        "INSERT INTO inference_endpoints (name, description, url, created_by)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (name) DO NOTHING",
+        config.sandbox.endpoint_name,
+        "Built-in mock backend returning synthetic responses",
+        url.as_str(),
+        Uuid::nil(),
+    )
+    .execute(db)
+    .await?;
+
+    if result.rows_affected() > 0 {
+        info!(
+            "Registered sandbox endpoint '{}'; synchronize it to create deployments",
+            config.sandbox.endpoint_name
+        );
+    }
+    Ok(())
+}
+
+/// Router for the mock backend, to be nested at `/sandbox/v1`
+pub fn router(config: SandboxConfig) -> Router {
+    Router::new()
+        .route("/models", get(list_models))
+        .route("/chat/completions", post(chat_completions))
+        .route("/completions", post(completions))
+        .route("/embeddings", post(embeddings))
+        .with_state(config)
+}
+
+async fn list_models(State(config): State<SandboxConfig>) -> Json<Value> {
+    let data: Vec<Value> = config
+        .models
+        .iter()
+        .map(|model| json!({"id": model, "object": "model", "created": 0, "owned_by": "sandbox"}))
+        .collect();
+    Json(json!({"object": "list", "data": data}))
+}
+
+async fn chat_completions(State(config): State<SandboxConfig>, Json(request): Json<Value>) -> Response {
+    let Some(model) = requested_model(&config, &request) else {
+        return unknown_model(&request);
+    };
+
+    let messages = request["messages"].as_array().cloned().unwrap_or_default();
+    let prompt_tokens: usize = messages.iter().map(|m| count_tokens(&message_text(m))).sum();
+    let last_user_message = messages
+        .iter()
+        .rev()
+        .find(|m| m["role"] == "user")
+        .map(message_text)
+        .unwrap_or_default();
+    let completion = generate(&model, &last_user_message, max_tokens(&request));
+    let usage = completion.usage(prompt_tokens);
+
+    let id = format!("chatcmpl-{}", Uuid::new_v4().simple());
+    let created = chrono::Utc::now().timestamp();
+
+    if !is_streaming(&request) {
+        return Json(json!({
+            "id": id,
+            "object": "chat.completion",
+            "created": created,
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": completion.words.join(" ")},
+                "finish_reason": completion.finish_reason,
+            }],
+            "usage": usage,
+        }))
+        .into_response();
+    }
+
+    let chunk = |delta: Value, finish_reason: Option<&str>| {
+        json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": model,
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}],
+        })
+    };
+
+    let mut chunks = vec![chunk(json!({"role": "assistant", "content": ""}), None)];
+    chunks.extend(completion.stream_pieces().map(|piece| chunk(json!({"content": piece}), None)));
+    chunks.push(chunk(json!({}), Some(completion.finish_reason)));
+    if include_usage(&request) {
+        chunks.push(json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": model,
+            "choices": [],
+            "usage": usage,
+        }));
+    }
+
+    stream_chunks(chunks, &config)
+}
+
+async fn completions(State(config): State<SandboxConfig>, Json(request): Json<Value>) -> Response {
+    let Some(model) = requested_model(&config, &request) else {
+        return unknown_model(&request);
+    };
+
+    let prompt = match &request["prompt"] {
+        Value::Array(prompts) => prompts.iter().filter_map(Value::as_str).collect::<Vec<_>>().join("\n"),
+        other => other.as_str().unwrap_or_default().to_string(),
+    };
+    let completion = generate(&model, &prompt, max_tokens(&request));
+    let usage = completion.usage(count_tokens(&prompt));
+
+    let id = format!("cmpl-{}", Uuid::new_v4().simple());
+    let created = chrono::Utc::now().timestamp();
+
+    if !is_streaming(&request) {
+        return Json(json!({
+            "id": id,
+            "object": "text_completion",
+            "created": created,
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "text": completion.words.join(" "),
+                "logprobs": null,
+                "finish_reason": completion.finish_reason,
+            }],
+            "usage": usage,
+        }))
+        .into_response();
+    }
+
+    let chunk = |text: &str, finish_reason: Option<&str>| {
+        json!({
+            "id": id,
+            "object": "text_completion",
+            "created": created,
+            "model": model,
+            "choices": [{"index": 0, "text": text, "logprobs": null, "finish_reason": finish_reason}],
+        })
+    };
+
+    let mut chunks: Vec<Value> = completion.stream_pieces().map(|piece| chunk(&piece, None)).collect();
+    chunks.push(chunk("", Some(completion.finish_reason)));
+    if include_usage(&request) {
+        chunks.push(json!({
+            "id": id,
+            "object": "text_completion",
+            "created": created,
+            "model": model,
+            "choices": [],
+            "usage": usage,
+        }));
+    }
+
+    stream_chunks(chunks, &config)
+}
+
+async fn embeddings(State(config): State<SandboxConfig>, Json(request): Json<Value>) -> Response {
+    let Some(model) = requested_model(&config, &request) else {
+        return unknown_model(&request);
+    };
+
+    let inputs: Vec<String> = match &request["input"] {
+        Value::Array(inputs) => inputs
+            .iter()
+            .map(|i| i.as_str().map_or_else(|| i.to_string(), str::to_string))
+            .collect(),
+        Value::String(input) => vec![input.clone()],
+        _ => return error_response(StatusCode::BAD_REQUEST, "'input' must be a string or an array"),
+    };
+    let dimensions = request["dimensions"]
+        .as_u64()
+        .map_or(DEFAULT_EMBEDDING_DIMENSIONS, |d| d.clamp(1, 4096) as usize);
+
+    let data: Vec<Value> = inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| json!({"object": "embedding", "index": index, "embedding": embed(input, dimensions)}))
+        .collect();
+    let prompt_tokens: usize = inputs.iter().map(|i| count_tokens(i)).sum();
+
+    Json(json!({
+        "object": "list",
+        "data": data,
+        "model": model,
+        "usage": {"prompt_tokens": prompt_tokens, "total_tokens": prompt_tokens},
+    }))
+    .into_response()
+}
+
+/// A synthetic completion, as a list of words
+struct Completion {
+    words: Vec<String>,
+    finish_reason: &'static str,
+}
+
+impl Completion {
+    fn usage(&self, prompt_tokens: usize) -> Value {
+        json!({
+            "prompt_tokens": prompt_tokens,
+            "completion_tokens": self.words.len(),
+            "total_tokens": prompt_tokens + self.words.len(),
+        })
+    }
+
+    /// The words of the completion as streamed, with the separating spaces attached
+    fn stream_pieces(&self) -> impl Iterator<Item = String> + '_ {
+        self.words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| if i == 0 { word.clone() } else { format!(" {word}") })
+    }
+}
+
+fn generate(model: &str, input: &str, max_tokens: Option<usize>) -> Completion {
+    if model.contains("echo") {
+        let words: Vec<String> = input.split_whitespace().map(str::to_string).collect();
+        let limit = max_tokens.unwrap_or(words.len());
+        let finish_reason = if words.len() > limit { "length" } else { "stop" };
+        Completion {
+            words: words.into_iter().take(limit).collect(),
+            finish_reason,
+        }
+    } else {
+        // Lorem ipsum models write until they run out of tokens
+        let words = LOREM
+            .split_whitespace()
+            .cycle()
+            .take(max_tokens.unwrap_or(DEFAULT_COMPLETION_TOKENS))
+            .map(str::to_string)
+            .collect();
+        Completion {
+            words,
+            finish_reason: if max_tokens.is_some() { "length" } else { "stop" },
+        }
+    }
+}
+
+/// Deterministic unit vector derived from the input, so identical inputs embed identically
+fn embed(input: &str, dimensions: usize) -> Vec<f32> {
+    let raw: Vec<f32> = (0..dimensions)
+        .map(|i| {
+            let mut hasher = DefaultHasher::new();
+            (input, i).hash(&mut hasher);
+            (hasher.finish() % 2000) as f32 / 1000.0 - 1.0
+        })
+        .collect();
+    let norm = raw.iter().map(|x| x * x).sum::<f32>().sqrt().max(f32::EPSILON);
+    raw.into_iter().map(|x| x / norm).collect()
+}
+
+fn stream_chunks(chunks: Vec<Value>, config: &SandboxConfig) -> Response {
+    let delay = config.token_delay;
+    let events = chunks
+        .into_iter()
+        .map(|chunk| Event::default().data(chunk.to_string()))
+        .chain(std::iter::once(Event::default().data("[DONE]")));
+    let stream = stream::iter(events).then(move |event| async move {
+        tokio::time::sleep(delay).await;
+        Ok::<_, Infallible>(event)
+    });
+    Sse::new(stream).into_response()
+}
+
+fn requested_model(config: &SandboxConfig, request: &Value) -> Option<String> {
+    let model = request["model"].as_str()?;
+    config.models.iter().any(|m| m == model).then(|| model.to_string())
+}
+
+fn unknown_model(request: &Value) -> Response {
+    let model = request["model"].as_str().unwrap_or_default();
+    error_response(StatusCode::NOT_FOUND, &format!("The model '{model}' does not exist in the sandbox"))
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({"error": {"message": message, "type": "invalid_request_error", "param": null, "code": null}})),
+    )
+        .into_response()
+}
+
+fn is_streaming(request: &Value) -> bool {
+    request["stream"].as_bool().unwrap_or(false)
+}
+
+fn include_usage(request: &Value) -> bool {
+    request["stream_options"]["include_usage"].as_bool().unwrap_or(false)
+}
+
+fn max_tokens(request: &Value) -> Option<usize> {
+    request["max_completion_tokens"]
+        .as_u64()
+        .or_else(|| request["max_tokens"].as_u64())
+        .map(|n| n as usize)
+}
+
+/// Text content of a chat message, joining the text parts of multi-part content
+fn message_text(message: &Value) -> String {
+    match &message["content"] {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts.iter().filter_map(|p| p["text"].as_str()).collect::<Vec<_>>().join(" "),
+        _ => String::new(),
+    }
+}
+
+fn count_tokens(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_test::TestServer;
+    use std::time::Duration;
+
+    fn server() -> TestServer {
+        let config = SandboxConfig {
+            token_delay: Duration::ZERO,
+            ..Default::default()
+        };
+        TestServer::new(Router::new().nest("/sandbox/v1", router(config))).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_models() {
+        let response = server().get("/sandbox/v1/models").await;
+        response.assert_status_ok();
+        let body: Value = response.json();
+        let ids: Vec<&str> = body["data"].as_array().unwrap().iter().map(|m| m["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["sandbox-echo", "sandbox-lorem"]);
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_echo() {
+        let response = server()
+            .post("/sandbox/v1/chat/completions")
+            .json(&json!({
+                "model": "sandbox-echo",
+                "messages": [
+                    {"role": "system", "content": "Be brief"},
+                    {"role": "user", "content": "hello there sandbox"}
+                ]
+            }))
+            .await;
+        response.assert_status_ok();
+        let body: Value = response.json();
+        assert_eq!(body["choices"][0]["message"]["content"], "hello there sandbox");
+        assert_eq!(body["choices"][0]["finish_reason"], "stop");
+        assert_eq!(body["usage"]["prompt_tokens"], 5);
+        assert_eq!(body["usage"]["completion_tokens"], 3);
+        assert_eq!(body["usage"]["total_tokens"], 8);
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_lorem_respects_max_tokens() {
+        let response = server()
+            .post("/sandbox/v1/chat/completions")
+            .json(&json!({
+                "model": "sandbox-lorem",
+                "max_tokens": 5,
+                "messages": [{"role": "user", "content": "write something"}]
+            }))
+            .await;
+        let body: Value = response.json();
+        assert_eq!(body["choices"][0]["message"]["content"], "lorem ipsum dolor sit amet");
+        assert_eq!(body["choices"][0]["finish_reason"], "length");
+        assert_eq!(body["usage"]["completion_tokens"], 5);
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_streaming() {
+        let response = server()
+            .post("/sandbox/v1/chat/completions")
+            .json(&json!({
+                "model": "sandbox-echo",
+                "stream": true,
+                "stream_options": {"include_usage": true},
+                "messages": [{"role": "user", "content": "one two three"}]
+            }))
+            .await;
+        response.assert_status_ok();
+        assert!(response.header("content-type").to_str().unwrap().starts_with("text/event-stream"));
+
+        let text = response.text();
+        let events: Vec<&str> = text.lines().filter_map(|line| line.strip_prefix("data: ")).collect();
+        assert_eq!(events.last(), Some(&"[DONE]"));
+
+        let chunks: Vec<Value> = events[..events.len() - 1]
+            .iter()
+            .map(|e| serde_json::from_str(e).unwrap())
+            .collect();
+        let content: String = chunks.iter().filter_map(|c| c["choices"][0]["delta"]["content"].as_str()).collect();
+        assert_eq!(content, "one two three");
+
+        let usage = &chunks.last().unwrap()["usage"];
+        assert_eq!(usage["prompt_tokens"], 3);
+        assert_eq!(usage["completion_tokens"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_completions_and_embeddings() {
+        let server = server();
+
+        let response = server
+            .post("/sandbox/v1/completions")
+            .json(&json!({"model": "sandbox-echo", "prompt": "say this back"}))
+            .await;
+        let body: Value = response.json();
+        assert_eq!(body["choices"][0]["text"], "say this back");
+        assert_eq!(body["usage"]["total_tokens"], 6);
+
+        let response = server
+            .post("/sandbox/v1/embeddings")
+            .json(&json!({"model": "sandbox-lorem", "input": ["a", "b", "a"], "dimensions": 4}))
+            .await;
+        let body: Value = response.json();
+        let data = body["data"].as_array().unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[0]["embedding"].as_array().unwrap().len(), 4);
+        assert_eq!(data[0]["embedding"], data[2]["embedding"]);
+        assert_ne!(data[0]["embedding"], data[1]["embedding"]);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_model() {
+        let response = server()
+            .post("/sandbox/v1/chat/completions")
+            .json(&json!({"model": "gpt-4", "messages": []}))
+            .await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        let body: Value = response.json();
+        assert!(body["error"]["message"].as_str().unwrap().contains("gpt-4"));
+    }
+
+    #[sqlx::test]
+    async fn test_register_endpoint_is_idempotent(pool: PgPool) {
+        let mut config = Config::default();
+        config.sandbox.enabled = true;
+
+        register_endpoint(&config, &pool).await.unwrap();
+        register_endpoint(&config, &pool).await.unwrap();
+
+        let urls = sqlx::query_scalar!("SELECT url FROM inference_endpoints WHERE name = 'sandbox'")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(urls, vec![format!("http://127.0.0.1:{}/sandbox/v1", config.port)]);
+    }
+}
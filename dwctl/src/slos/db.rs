@@ -0,0 +1,268 @@
+//! Database access layer for deployment SLOs and their alerts.
+
+use crate::db::models::slos::{DeploymentSlo, SloAlert};
+use crate::errors::Error as AppError;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Everything needed to create an SLO
+pub struct NewSlo {
+    pub deployment_id: Uuid,
+    pub name: String,
+    pub objective: String,
+    pub target_percent: f64,
+    pub latency_threshold_ms: Option<i32>,
+    pub window_days: i32,
+    pub created_by: Uuid,
+}
+
+/// Changes to an SLO; `None` fields are left unchanged
+#[derive(Default)]
+pub struct SloChanges {
+    pub name: Option<String>,
+    pub target_percent: Option<f64>,
+    pub latency_threshold_ms: Option<i32>,
+    pub window_days: Option<i32>,
+}
+
+/// Probe results of a deployment over an SLO's window and over the recent burn window
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResultCounts {
+    pub total: i64,
+    pub bad: i64,
+    pub recent_total: i64,
+    pub recent_bad: i64,
+}
+
+/// The outcome of evaluating an SLO
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    pub status: &'static str,
+    pub total_results: i64,
+    pub good_results: i64,
+    pub error_budget_remaining_percent: f64,
+    pub burn_rate: f64,
+}
+
+/// Database access layer for SLOs.
+pub struct SloManager;
+
+impl SloManager {
+    pub async fn create(pool: &PgPool, slo: NewSlo) -> Result<DeploymentSlo, AppError> {
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM deployed_models WHERE id = $1 AND deleted = false)",
+            slo.deployment_id
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to check deployment: {}", e))?;
+        if exists != Some(true) {
+            return Err(AppError::NotFound {
+                resource: "Deployment".to_string(),
+                id: slo.deployment_id.to_string(),
+            });
+        }
+
+        let result = sqlx::query_as::<_, DeploymentSlo>(
+            r#"
+            INSERT INTO deployment_slos (deployment_id, name, objective, target_percent, latency_threshold_ms, window_days, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(slo.deployment_id)
+        .bind(&slo.name)
+        .bind(&slo.objective)
+        .bind(slo.target_percent)
+        .bind(slo.latency_threshold_ms)
+        .bind(slo.window_days)
+        .bind(slo.created_by)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create SLO: {}", e))?;
+
+        Ok(result)
+    }
+
+    pub async fn get(pool: &PgPool, id: Uuid) -> Result<DeploymentSlo, AppError> {
+        let slo = sqlx::query_as::<_, DeploymentSlo>("SELECT * FROM deployment_slos WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch SLO: {}", e))?
+            .ok_or_else(|| AppError::NotFound {
+                resource: "SLO".to_string(),
+                id: id.to_string(),
+            })?;
+
+        Ok(slo)
+    }
+
+    /// List SLOs, optionally only those of one deployment
+    pub async fn list(pool: &PgPool, deployment_id: Option<Uuid>) -> Result<Vec<DeploymentSlo>, AppError> {
+        let slos = sqlx::query_as::<_, DeploymentSlo>(
+            "SELECT * FROM deployment_slos WHERE ($1::uuid IS NULL OR deployment_id = $1) ORDER BY created_at DESC",
+        )
+        .bind(deployment_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to list SLOs: {}", e))?;
+
+        Ok(slos)
+    }
+
+    pub async fn update(pool: &PgPool, id: Uuid, changes: SloChanges) -> Result<DeploymentSlo, AppError> {
+        let slo = sqlx::query_as::<_, DeploymentSlo>(
+            r#"
+            UPDATE deployment_slos SET
+                name = COALESCE($2, name),
+                target_percent = COALESCE($3, target_percent),
+                latency_threshold_ms = COALESCE($4, latency_threshold_ms),
+                window_days = COALESCE($5, window_days),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(&changes.name)
+        .bind(changes.target_percent)
+        .bind(changes.latency_threshold_ms)
+        .bind(changes.window_days)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to update SLO: {}", e))?
+        .ok_or_else(|| AppError::NotFound {
+            resource: "SLO".to_string(),
+            id: id.to_string(),
+        })?;
+
+        Ok(slo)
+    }
+
+    /// Delete an SLO along with its alerts
+    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!("DELETE FROM deployment_slos WHERE id = $1", id)
+            .execute(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to delete SLO: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                resource: "SLO".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Count the probe results of an SLO's deployment over its window and over the last
+    /// `burn_window`. Results are bad if the probe failed or, for latency SLOs, was too slow.
+    /// Results taken during maintenance windows, or by load probes under artificial load, aren't
+    /// counted.
+    pub async fn count_results(pool: &PgPool, slo: &DeploymentSlo, burn_window: Duration) -> Result<ResultCounts, AppError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) as "total!",
+                COUNT(*) FILTER (WHERE NOT good) as "bad!",
+                COUNT(*) FILTER (WHERE recent) as "recent_total!",
+                COUNT(*) FILTER (WHERE recent AND NOT good) as "recent_bad!"
+            FROM (
+                SELECT
+                    r.success AND ($2::int IS NULL OR COALESCE(r.response_time_ms <= $2, false)) as good,
+                    r.executed_at >= NOW() - make_interval(secs => $4) as recent
+                FROM probe_results r
+                JOIN probes p ON p.id = r.probe_id
+                WHERE p.deployment_id = $1 AND r.executed_at >= NOW() - make_interval(days => $3) AND NOT r.in_maintenance
+                  AND p.probe_type <> 'load'
+            ) results
+            "#,
+            slo.deployment_id,
+            slo.latency_threshold_ms,
+            slo.window_days,
+            burn_window.as_secs_f64()
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to count probe results for SLO: {}", e))?;
+
+        Ok(ResultCounts {
+            total: row.total,
+            bad: row.bad,
+            recent_total: row.recent_total,
+            recent_bad: row.recent_bad,
+        })
+    }
+
+    /// Store an evaluation of an SLO, and an alert if its status changed. Returns the alert.
+    pub async fn record_evaluation(
+        pool: &PgPool,
+        slo: &DeploymentSlo,
+        evaluation: &Evaluation,
+        message: &str,
+    ) -> Result<Option<SloAlert>, AppError> {
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to begin transaction: {}", e))?;
+
+        sqlx::query!(
+            r#"
+            UPDATE deployment_slos
+            SET status = $2, total_results = $3, good_results = $4, error_budget_remaining_percent = $5, burn_rate = $6,
+                evaluated_at = NOW()
+            WHERE id = $1
+            "#,
+            slo.id,
+            evaluation.status,
+            evaluation.total_results,
+            evaluation.good_results,
+            evaluation.error_budget_remaining_percent,
+            evaluation.burn_rate
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to record SLO evaluation: {}", e))?;
+
+        let alert = if evaluation.status != slo.status {
+            let alert = sqlx::query_as::<_, SloAlert>(
+                r#"
+                INSERT INTO slo_alerts (slo_id, status, previous_status, error_budget_remaining_percent, burn_rate, message)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING *
+                "#,
+            )
+            .bind(slo.id)
+            .bind(evaluation.status)
+            .bind(&slo.status)
+            .bind(evaluation.error_budget_remaining_percent)
+            .bind(evaluation.burn_rate)
+            .bind(message)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to record SLO alert: {}", e))?;
+            Some(alert)
+        } else {
+            None
+        };
+
+        tx.commit()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to commit transaction: {}", e))?;
+        Ok(alert)
+    }
+
+    /// List the alerts of an SLO, most recent first
+    pub async fn list_alerts(pool: &PgPool, slo_id: Uuid, limit: i64) -> Result<Vec<SloAlert>, AppError> {
+        let alerts = sqlx::query_as::<_, SloAlert>("SELECT * FROM slo_alerts WHERE slo_id = $1 ORDER BY fired_at DESC LIMIT $2")
+            .bind(slo_id)
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to list SLO alerts: {}", e))?;
+
+        Ok(alerts)
+    }
+}
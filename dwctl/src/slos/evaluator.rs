@@ -0,0 +1,438 @@
+//! Evaluates deployment SLOs and fires alerts when their error budgets are at risk.
+//!
+//! An SLO's error budget is the share of probe results in its window that may be bad while the
+//! SLO is still met: 1% of them for a 99% target. On every tick the leader replica counts each
+//! SLO's good and bad probe results, works out how much of the budget is left and how fast it's
+//! being spent over the recent burn window, and classifies the SLO as:
+//!
+//! - `exhausted` once the budget is spent
+//! - `at_risk` when less than `at_risk_budget_percent` of it is left, or it's being burnt at
+//!   `fast_burn_rate` or faster, so it would run out well before the window ends
+//! - `ok` otherwise
+//!
+//! Whenever an SLO's status changes, an alert is recorded (and listed under
+//! `/slos/{id}/alerts`), logged and, if configured, POSTed to the alert webhook.
+
+use crate::config::{SloAlertWebhookConfig, SloConfig};
+use crate::db::models::slos::{DeploymentSlo, SloAlert};
+use crate::leader::LeaderFence;
+use crate::slos::db::{Evaluation, ResultCounts, SloManager};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use url::Url;
+
+/// Upper bound on the delay between delivery attempts for a single alert
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Work out an SLO's error budget and status from its probe results
+pub fn assess(slo: &DeploymentSlo, counts: ResultCounts, config: &SloConfig) -> Evaluation {
+    let allowed_error_rate = 1.0 - slo.target_percent / 100.0;
+
+    // Without results there's nothing to spend the budget on
+    let error_budget_remaining_percent = if counts.total == 0 {
+        100.0
+    } else {
+        let error_rate = counts.bad as f64 / counts.total as f64;
+        (1.0 - error_rate / allowed_error_rate) * 100.0
+    };
+    let burn_rate = if counts.recent_total == 0 {
+        0.0
+    } else {
+        (counts.recent_bad as f64 / counts.recent_total as f64) / allowed_error_rate
+    };
+
+    let status = if error_budget_remaining_percent <= 0.0 {
+        "exhausted"
+    } else if error_budget_remaining_percent < config.at_risk_budget_percent || burn_rate >= config.fast_burn_rate {
+        "at_risk"
+    } else {
+        "ok"
+    };
+
+    Evaluation {
+        status,
+        total_results: counts.total,
+        good_results: counts.total - counts.bad,
+        error_budget_remaining_percent,
+        burn_rate,
+    }
+}
+
+/// Describe a change in an SLO's status
+fn alert_message(slo: &DeploymentSlo, evaluation: &Evaluation) -> String {
+    let state = match evaluation.status {
+        "exhausted" => "has exhausted its error budget",
+        "at_risk" => "is at risk of exhausting its error budget",
+        _ => "has recovered",
+    };
+    format!(
+        "SLO '{}' ({}% {} over {} days) {}: {:.1}% of the budget left, burn rate {:.1}",
+        slo.name,
+        slo.target_percent,
+        slo.objective,
+        slo.window_days,
+        state,
+        evaluation.error_budget_remaining_percent,
+        evaluation.burn_rate
+    )
+}
+
+/// Body POSTed to the alert webhook
+#[derive(Debug, Serialize)]
+struct SloAlertNotification<'a> {
+    /// `slo.at_risk`, `slo.exhausted` or `slo.recovered`
+    event: String,
+    slo: &'a DeploymentSlo,
+    alert: &'a SloAlert,
+}
+
+/// Sends SLO alerts to the configured webhook
+#[derive(Debug, Clone)]
+struct AlertWebhook {
+    client: reqwest::Client,
+    url: Url,
+    authorization: Option<String>,
+    max_retries: u32,
+    request_timeout: Duration,
+}
+
+impl AlertWebhook {
+    /// Build a webhook, or `None` if alerts aren't sent anywhere
+    fn new(config: &SloAlertWebhookConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        Some(Self {
+            client: reqwest::Client::new(),
+            url: config.url.clone()?,
+            authorization: config.authorization.clone(),
+            max_retries: config.max_retries,
+            request_timeout: config.request_timeout,
+        })
+    }
+
+    /// Send an alert, retrying with exponential backoff. Gives up after `max_retries` retries.
+    async fn deliver(&self, slo: &DeploymentSlo, alert: &SloAlert) {
+        let event = match alert.status.as_str() {
+            "ok" => "slo.recovered".to_string(),
+            status => format!("slo.{}", status),
+        };
+        let notification = SloAlertNotification { event, slo, alert };
+        let mut delay = Duration::from_millis(500);
+
+        for attempt in 0..=self.max_retries {
+            match self.send(&notification).await {
+                Ok(()) => return,
+                Err(e) if attempt < self.max_retries => {
+                    warn!(
+                        attempt = attempt + 1,
+                        "Failed to send SLO alert, retrying in {}ms: {:#}",
+                        delay.as_millis(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                }
+                Err(e) => error!(slo_id = %slo.id, "Giving up on sending SLO alert: {:#}", e),
+            }
+        }
+    }
+
+    async fn send(&self, notification: &SloAlertNotification<'_>) -> anyhow::Result<()> {
+        let mut request = self.client.post(self.url.clone()).timeout(self.request_timeout).json(notification);
+        if let Some(authorization) = &self.authorization {
+            request = request.header(reqwest::header::AUTHORIZATION, authorization);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            anyhow::bail!("SLO alert webhook returned {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+/// Evaluate every SLO, recording the evaluations and firing alerts for status changes. Returns
+/// the alerts fired.
+async fn evaluate_all(pool: &PgPool, config: &SloConfig, webhook: Option<&AlertWebhook>) -> anyhow::Result<Vec<SloAlert>> {
+    let mut alerts = Vec::new();
+    for slo in SloManager::list(pool, None).await? {
+        let counts = SloManager::count_results(pool, &slo, config.burn_window).await?;
+        let evaluation = assess(&slo, counts, config);
+        let message = alert_message(&slo, &evaluation);
+        let Some(alert) = SloManager::record_evaluation(pool, &slo, &evaluation, &message).await? else {
+            continue;
+        };
+
+        if alert.status == "ok" {
+            info!(slo_id = %slo.id, deployment_id = %slo.deployment_id, "{}", message);
+        } else {
+            warn!(slo_id = %slo.id, deployment_id = %slo.deployment_id, "{}", message);
+        }
+        if let Some(webhook) = webhook.cloned() {
+            let alert = alert.clone();
+            tokio::spawn(async move { webhook.deliver(&slo, &alert).await });
+        }
+        alerts.push(alert);
+    }
+    Ok(alerts)
+}
+
+/// Background task that evaluates SLOs on a fixed interval.
+///
+/// Like the probe scheduler, this only runs on the leader replica, so each status change
+/// fires a single alert however many replicas there are.
+#[derive(Clone)]
+pub struct SloEvaluator {
+    pool: PgPool,
+    config: SloConfig,
+    fence: LeaderFence,
+    handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl SloEvaluator {
+    pub fn new(pool: PgPool, config: SloConfig, fence: LeaderFence) -> Self {
+        Self {
+            pool,
+            config,
+            fence,
+            handle: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Start the evaluation loop, if enabled and not already running.
+    pub async fn start(&self) {
+        if !self.config.enabled {
+            info!("SLO evaluation is disabled");
+            return;
+        }
+
+        let mut handle = self.handle.lock().await;
+        if handle.is_some() {
+            return;
+        }
+
+        let pool = self.pool.clone();
+        let config = self.config.clone();
+        let fence = self.fence.clone();
+        let webhook = AlertWebhook::new(&config.alert_webhook);
+        *handle = Some(tokio::spawn(async move {
+            loop {
+                if !fence.is_current().await {
+                    warn!("Leadership is stale, stopping SLO evaluation");
+                    break;
+                }
+                let started = Instant::now();
+                let result = evaluate_all(&pool, &config, webhook.as_ref()).await;
+                fence.observe_job("slo_evaluation", started.elapsed(), result.is_ok());
+                if let Err(e) = result {
+                    error!("Failed to evaluate SLOs: {}", e);
+                }
+                tokio::time::sleep(config.interval).await;
+            }
+        }));
+
+        info!("Started SLO evaluation (every {}s)", self.config.interval.as_secs());
+    }
+
+    /// Stop the evaluation loop (called when losing leadership).
+    pub async fn stop(&self) {
+        if let Some(handle) = self.handle.lock().await.take() {
+            handle.abort();
+            info!("Stopped SLO evaluation");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::users::Role;
+    use crate::slos::db::NewSlo;
+    use crate::test_utils::create_test_admin_user;
+    use axum::{extract::State, http::StatusCode, routing::post, Router};
+
+    fn slo(target_percent: f64) -> DeploymentSlo {
+        DeploymentSlo {
+            id: uuid::Uuid::new_v4(),
+            deployment_id: uuid::Uuid::new_v4(),
+            name: "availability".to_string(),
+            objective: "availability".to_string(),
+            target_percent,
+            latency_threshold_ms: None,
+            window_days: 30,
+            status: "ok".to_string(),
+            total_results: 0,
+            good_results: 0,
+            error_budget_remaining_percent: 100.0,
+            burn_rate: 0.0,
+            evaluated_at: None,
+            created_by: uuid::Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn counts(total: i64, bad: i64, recent_total: i64, recent_bad: i64) -> ResultCounts {
+        ResultCounts {
+            total,
+            bad,
+            recent_total,
+            recent_bad,
+        }
+    }
+
+    #[test]
+    fn test_assess() {
+        let config = SloConfig::default();
+        let slo = slo(99.0);
+
+        let evaluation = assess(&slo, counts(0, 0, 0, 0), &config);
+        assert_eq!((evaluation.status, evaluation.error_budget_remaining_percent), ("ok", 100.0));
+
+        // 5 bad results of 1000 spend half of a 1% budget
+        let evaluation = assess(&slo, counts(1000, 5, 10, 0), &config);
+        assert_eq!(evaluation.status, "ok");
+        assert_eq!(evaluation.good_results, 995);
+        assert!((evaluation.error_budget_remaining_percent - 50.0).abs() < 1e-9);
+
+        // 8 bad results leave 20% of the budget
+        assert_eq!(assess(&slo, counts(1000, 8, 10, 0), &config).status, "at_risk");
+
+        // Plenty of budget left, but all of the last hour's results failed
+        let evaluation = assess(&slo, counts(1000, 2, 10, 10), &config);
+        assert_eq!(evaluation.status, "at_risk");
+        assert!((evaluation.burn_rate - 100.0).abs() < 1e-9);
+
+        let evaluation = assess(&slo, counts(1000, 20, 10, 0), &config);
+        assert_eq!(evaluation.status, "exhausted");
+        assert!((evaluation.error_budget_remaining_percent + 100.0).abs() < 1e-9);
+    }
+
+    /// A deployment with a probe
+    async fn setup_probe(pool: &PgPool, user_id: uuid::Uuid) -> (uuid::Uuid, uuid::Uuid) {
+        let endpoint_id = sqlx::query_scalar!(
+            "INSERT INTO inference_endpoints (name, url, created_by) VALUES ('slo-endpoint', 'http://localhost:8080', $1) RETURNING id",
+            user_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        let deployment_id = sqlx::query_scalar!(
+            "INSERT INTO deployed_models (model_name, alias, hosted_on, created_by) VALUES ('slo-model', 'slo-model', $1, $2) RETURNING id",
+            endpoint_id,
+            user_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        let probe_id = sqlx::query_scalar!(
+            "INSERT INTO probes (name, deployment_id, interval_seconds) VALUES ('slo-probe', $1, 60) RETURNING id",
+            deployment_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        (deployment_id, probe_id)
+    }
+
+    async fn add_results(pool: &PgPool, probe_id: uuid::Uuid, success: bool, response_time_ms: i32, count: i32) {
+        sqlx::query!(
+            "INSERT INTO probe_results (probe_id, success, response_time_ms) SELECT $1, $2, $3 FROM generate_series(1, $4)",
+            probe_id,
+            success,
+            response_time_ms,
+            count
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    type Received = Arc<std::sync::Mutex<Vec<serde_json::Value>>>;
+
+    async fn spawn_webhook(received: Received) -> Url {
+        async fn ingest(State(received): State<Received>, axum::Json(body): axum::Json<serde_json::Value>) -> StatusCode {
+            received.lock().unwrap().push(body);
+            StatusCode::OK
+        }
+        let app = Router::new().route("/alerts", post(ingest)).with_state(received);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}/alerts").parse().unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_evaluate_all_fires_alerts_on_status_changes(pool: PgPool) {
+        let user = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let (deployment_id, probe_id) = setup_probe(&pool, user.id).await;
+        let new_slo = |objective: &str, latency_threshold_ms| NewSlo {
+            deployment_id,
+            name: format!("{objective} SLO"),
+            objective: objective.to_string(),
+            target_percent: 90.0,
+            latency_threshold_ms,
+            window_days: 30,
+            created_by: user.id,
+        };
+        let availability = SloManager::create(&pool, new_slo("availability", None)).await.unwrap();
+        let latency = SloManager::create(&pool, new_slo("latency", Some(500))).await.unwrap();
+
+        let received = Received::default();
+        let webhook = AlertWebhook::new(&SloAlertWebhookConfig {
+            enabled: true,
+            url: Some(spawn_webhook(received.clone()).await),
+            ..Default::default()
+        });
+        let config = SloConfig::default();
+
+        // All good: nothing to report
+        add_results(&pool, probe_id, true, 100, 95).await;
+        assert!(evaluate_all(&pool, &config, webhook.as_ref()).await.unwrap().is_empty());
+
+        // Neither are failures during maintenance
+        sqlx::query!(
+            "INSERT INTO probe_results (probe_id, success, in_maintenance) SELECT $1, false, true FROM generate_series(1, 50)",
+            probe_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(evaluate_all(&pool, &config, webhook.as_ref()).await.unwrap().is_empty());
+
+        // Slow successes only count against the latency SLO, whose budget they exhaust
+        add_results(&pool, probe_id, true, 2000, 5).await;
+        add_results(&pool, probe_id, false, 100, 9).await;
+        let alerts = evaluate_all(&pool, &config, webhook.as_ref()).await.unwrap();
+        assert_eq!(alerts.len(), 2);
+
+        let availability = SloManager::get(&pool, availability.id).await.unwrap();
+        assert_eq!(availability.status, "at_risk");
+        assert_eq!((availability.total_results, availability.good_results), (109, 100));
+        let latency = SloManager::get(&pool, latency.id).await.unwrap();
+        assert_eq!(latency.status, "exhausted");
+        assert_eq!(latency.good_results, 95);
+
+        // The status doesn't change again, so no new alerts are fired
+        assert!(evaluate_all(&pool, &config, webhook.as_ref()).await.unwrap().is_empty());
+        let alerts = SloManager::list_alerts(&pool, latency.id, 10).await.unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!((alerts[0].previous_status.as_str(), alerts[0].status.as_str()), ("ok", "exhausted"));
+
+        for _ in 0..50 {
+            if received.lock().unwrap().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let mut events: Vec<_> = received.lock().unwrap().iter().map(|body| body["event"].to_string()).collect();
+        events.sort();
+        assert_eq!(events, vec!["\"slo.at_risk\"", "\"slo.exhausted\""]);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod db;
+pub mod evaluator;
+
+pub use evaluator::SloEvaluator;
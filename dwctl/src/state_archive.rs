@@ -0,0 +1,357 @@
+//! Export and import of the gateway's state, for disaster recovery and environment cloning.
+//!
+//! `dwctl export-state` writes a single JSON archive of everything an administrator configures:
+//! users, groups, endpoints, deployments with their pricing, routing and SLOs, API keys, probes,
+//! notification channels, maintenance windows and the per-group and per-endpoint policies and
+//! limits. Request logs, analytics and the results of probes, validations, load tests and
+//! regression runs are left out. `dwctl import-state` replaces the state of another database
+//! with the archive's, in one transaction, e.g. to restore into a standby environment or to
+//! clone production into staging.
+//!
+//! Archives are tied to the schema they were exported from: they can only be imported into a
+//! database migrated to the same version. Rows are exported as JSON objects keyed by column, so
+//! the archive contains secrets (API keys and endpoint credentials) and should be stored
+//! accordingly.
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// Version of the archive layout, bumped when it changes incompatibly
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// Tables holding the gateway's state, in an order where every table comes after the tables it
+/// references
+pub const STATE_TABLES: [&str; 33] = [
+    "users",
+    "user_roles",
+    "groups",
+    "user_groups",
+    "group_moderation_policies",
+    "group_request_limits",
+    "group_log_retention",
+    "inference_endpoints",
+    "endpoint_header_rules",
+    "endpoint_redaction_policies",
+    "endpoint_rate_limits",
+    "endpoint_sync_strategies",
+    "endpoint_pending_models",
+    "deployed_models",
+    "deployment_slos",
+    "deployment_groups",
+    "deployment_fallbacks",
+    "deployment_traffic_splits",
+    "deployment_canaries",
+    "deployment_circuit_breakers",
+    "deployment_shadows",
+    "deployment_schedule_hints",
+    "deployment_access_policies",
+    "deployment_logging_policies",
+    "deployment_deprecations",
+    "api_keys",
+    "api_key_access_requests",
+    "probes",
+    "notification_channels",
+    "maintenance_windows",
+    "regression_suites",
+    "federation_peers",
+    "system_config",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateArchive {
+    pub format_version: u32,
+    /// Latest migration applied to the exporting database
+    pub schema_version: i64,
+    pub exported_at: DateTime<Utc>,
+    /// Rows of each table, as JSON objects keyed by column
+    pub tables: BTreeMap<String, Vec<Value>>,
+}
+
+/// Latest migration applied to the database
+async fn schema_version(pool: &PgPool) -> anyhow::Result<i64> {
+    let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success")
+        .fetch_one(pool)
+        .await?;
+    version.context("database has no migrations applied")
+}
+
+/// Read the state of the gateway into an archive
+pub async fn export_state(pool: &PgPool) -> anyhow::Result<StateArchive> {
+    // Read every table from the same snapshot, so the archive is consistent
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+
+    let mut tables = BTreeMap::new();
+    for table in STATE_TABLES {
+        let rows: Value = sqlx::query_scalar(&format!("SELECT COALESCE(jsonb_agg(t), '[]'::jsonb) FROM {table} t"))
+            .fetch_one(&mut *tx)
+            .await
+            .with_context(|| format!("failed to export {table}"))?;
+        let rows = match rows {
+            Value::Array(rows) => rows,
+            _ => unreachable!("jsonb_agg returns an array"),
+        };
+        tables.insert(table.to_string(), rows);
+    }
+    tx.commit().await?;
+
+    Ok(StateArchive {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        schema_version: schema_version(pool).await?,
+        exported_at: Utc::now(),
+        tables,
+    })
+}
+
+/// Columns of `table`, and those of its primary key
+async fn table_columns(tx: &mut sqlx::PgConnection, table: &str) -> anyhow::Result<(Vec<String>, Vec<String>)> {
+    let columns = sqlx::query_as::<_, (String, bool)>(
+        "SELECT a.attname::text, COALESCE(a.attnum = ANY(i.indkey), false)
+         FROM pg_attribute a
+         LEFT JOIN pg_index i ON i.indrelid = a.attrelid AND i.indisprimary
+         WHERE a.attrelid = $1::regclass AND a.attnum > 0 AND NOT a.attisdropped
+         ORDER BY a.attnum",
+    )
+    .bind(table)
+    .fetch_all(tx)
+    .await?;
+    let key = columns
+        .iter()
+        .filter(|(_, key)| *key)
+        .map(|(column, _)| column.clone())
+        .collect::<Vec<_>>();
+    if key.is_empty() {
+        anyhow::bail!("{table} has no primary key");
+    }
+    Ok((columns.into_iter().map(|(column, _)| column).collect(), key))
+}
+
+/// Replace the state of the gateway with the archive's.
+///
+/// Rows of the state tables that aren't in the archive are deleted, along with the rows of other
+/// tables that reference them (such as the results of a deleted probe). The archive's rows are
+/// then inserted, or update the rows with the same key in place, so that request logs, analytics
+/// and results referencing state that survives the import are kept. Returns the number of rows
+/// imported.
+pub async fn import_state(pool: &PgPool, archive: &StateArchive) -> anyhow::Result<u64> {
+    if archive.format_version != ARCHIVE_FORMAT_VERSION {
+        anyhow::bail!(
+            "unsupported archive format version {} (expected {})",
+            archive.format_version,
+            ARCHIVE_FORMAT_VERSION
+        );
+    }
+    let current = schema_version(pool).await?;
+    if archive.schema_version != current {
+        anyhow::bail!(
+            "archive was exported from schema version {}, but the database is at version {}",
+            archive.schema_version,
+            current
+        );
+    }
+    if let Some(table) = archive.tables.keys().find(|table| !STATE_TABLES.contains(&table.as_str())) {
+        anyhow::bail!("archive contains unknown table '{table}'");
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut columns = BTreeMap::new();
+    for table in STATE_TABLES {
+        columns.insert(table, table_columns(&mut tx, table).await?);
+    }
+    let rows = |table: &str| Value::Array(archive.tables.get(table).cloned().unwrap_or_default());
+
+    // Delete what the archive doesn't have, referencing tables first
+    for table in STATE_TABLES.iter().rev() {
+        let (_, key) = &columns[table];
+        let matches = key.iter().map(|column| format!("a.{column} = t.{column}")).collect::<Vec<_>>();
+        let result = sqlx::query(&format!(
+            "DELETE FROM {table} t WHERE NOT EXISTS (SELECT 1 FROM jsonb_populate_recordset(NULL::{table}, $1) a WHERE {})",
+            matches.join(" AND ")
+        ))
+        .bind(rows(table))
+        .execute(&mut *tx)
+        .await
+        .with_context(|| format!("failed to prune {table}"))?;
+        if result.rows_affected() > 0 {
+            info!("Deleted {} rows from {}", result.rows_affected(), table);
+        }
+    }
+
+    let mut imported = 0;
+    for table in STATE_TABLES {
+        if archive.tables.get(table).is_none_or(|rows| rows.is_empty()) {
+            continue;
+        }
+        let (all, key) = &columns[table];
+        let updates = all
+            .iter()
+            .filter(|column| !key.contains(column))
+            .map(|column| format!("{column} = EXCLUDED.{column}"))
+            .collect::<Vec<_>>();
+        let on_conflict = if updates.is_empty() {
+            "DO NOTHING".to_string()
+        } else {
+            format!("DO UPDATE SET {}", updates.join(", "))
+        };
+        let result = sqlx::query(&format!(
+            "INSERT INTO {table} SELECT * FROM jsonb_populate_recordset(NULL::{table}, $1) ON CONFLICT ({}) {on_conflict}",
+            key.join(", ")
+        ))
+        .bind(rows(table))
+        .execute(&mut *tx)
+        .await
+        .with_context(|| format!("failed to import {table}"))?;
+        info!("Imported {} rows into {}", result.rows_affected(), table);
+        imported += result.rows_affected();
+    }
+    tx.commit().await?;
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::users::Role;
+    use crate::test_utils::*;
+
+    async fn count(pool: &PgPool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}"))
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_export_and_import_round_trip(pool: PgPool) {
+        // Seeds the test endpoint
+        let _app = create_test_app(pool.clone(), false).await;
+        let admin = create_test_admin_user(&pool, Role::PlatformManager).await;
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let group = create_test_group(&pool).await;
+        add_user_to_group(&pool, user.id, group.id).await;
+        let deployment = create_test_deployment(&pool, admin.id, "llama", "llama").await;
+        add_deployment_to_group(&pool, deployment.id, group.id, admin.id).await;
+        let api_key = create_test_api_key_for_user(&pool, user.id).await;
+        sqlx::query(
+            "INSERT INTO deployment_slos (deployment_id, name, objective, target_percent, created_by)
+             VALUES ($1, 'availability', 'availability', 99.5, $2)",
+        )
+        .bind(deployment.id)
+        .bind(admin.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO notification_channels (name, channel_type, destination, created_by)
+             VALUES ('on-call', 'webhook', 'https://alerts.example.com/hook', $1)",
+        )
+        .bind(admin.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO maintenance_windows (name, deployment_id, starts_at, ends_at, created_by)
+             VALUES ('upgrade', $1, NOW(), NOW() + INTERVAL '1 hour', $2)",
+        )
+        .bind(deployment.id)
+        .bind(admin.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let archive = export_state(&pool).await.unwrap();
+        assert_eq!(archive.format_version, ARCHIVE_FORMAT_VERSION);
+        let counts: Vec<i64> = futures::future::join_all(STATE_TABLES.iter().map(|table| count(&pool, table))).await;
+
+        // Archives survive serialization
+        let archive: StateArchive = serde_json::from_slice(&serde_json::to_vec(&archive).unwrap()).unwrap();
+
+        // Changes made after the export are undone by the import
+        sqlx::query("DELETE FROM groups WHERE id = $1")
+            .bind(group.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM deployment_slos").execute(&pool).await.unwrap();
+        sqlx::query("DELETE FROM notification_channels").execute(&pool).await.unwrap();
+        sqlx::query("DELETE FROM maintenance_windows").execute(&pool).await.unwrap();
+        create_test_user(&pool, Role::StandardUser).await;
+
+        import_state(&pool, &archive).await.unwrap();
+        let restored: Vec<i64> = futures::future::join_all(STATE_TABLES.iter().map(|table| count(&pool, table))).await;
+        assert_eq!(restored, counts);
+
+        let secret: String = sqlx::query_scalar("SELECT secret FROM api_keys WHERE id = $1")
+            .bind(api_key.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(secret, api_key.secret);
+        let granted: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM deployment_groups WHERE group_id = $1 AND deployment_id = $2")
+            .bind(group.id)
+            .bind(deployment.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(granted, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_import_keeps_request_analytics(pool: PgPool) {
+        let user = create_test_user(&pool, Role::StandardUser).await;
+        let removed = create_test_user(&pool, Role::StandardUser).await;
+        let archive = export_state(&pool).await.unwrap();
+
+        for user_id in [user.id, removed.id] {
+            sqlx::query(
+                "INSERT INTO http_analytics (instance_id, correlation_id, timestamp, method, uri, user_id)
+                 VALUES (gen_random_uuid(), 1, NOW(), 'POST', '/ai/v1/chat/completions', $1)",
+            )
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        // Deleted after the export, so restored by the import
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(removed.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        import_state(&pool, &archive).await.unwrap();
+        assert_eq!(count(&pool, "http_analytics").await, 2);
+        let attributed: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM http_analytics WHERE user_id = $1")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(attributed, 1);
+        let restored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE id = $1")
+            .bind(removed.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(restored, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_import_rejects_other_schema_versions(pool: PgPool) {
+        let mut archive = export_state(&pool).await.unwrap();
+        archive.schema_version -= 1;
+        let error = import_state(&pool, &archive).await.unwrap_err();
+        assert!(error.to_string().contains("schema version"));
+
+        let mut archive = export_state(&pool).await.unwrap();
+        archive.tables.insert("http_analytics".to_string(), vec![]);
+        assert!(import_state(&pool, &archive).await.is_err());
+    }
+}
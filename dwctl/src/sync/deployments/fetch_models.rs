@@ -1,5 +1,7 @@
-use crate::api::models::inference_endpoints::{AnthropicModelsResponse, OpenAIModelsResponse};
+use crate::api::models::inference_endpoints::{AnthropicModelsResponse, AzureDeploymentsResponse, OpenAIModelsResponse};
 use crate::db::models::inference_endpoints::InferenceEndpointDBResponse;
+use crate::external_secrets;
+use crate::tls::EndpointTls;
 use anyhow::anyhow;
 use async_trait::async_trait;
 use reqwest::Client;
@@ -14,25 +16,44 @@ pub struct SyncConfig {
     pub auth_header_name: String,
     pub auth_header_prefix: String,
     pub(crate) request_timeout: Duration,
+    pub tls: EndpointTls,
 }
 
 impl SyncConfig {
     /// Default timeout for API requests
     const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
-    /// Create a SyncConfig from an endpoint DB response
+    /// Create a SyncConfig from an endpoint DB response, resolving an API key kept in a secret
+    /// manager
     #[instrument]
-    pub fn from_endpoint(source: &InferenceEndpointDBResponse) -> Self {
-        Self {
-            openai_api_key: source.api_key.clone(),
+    pub async fn from_endpoint(source: &InferenceEndpointDBResponse) -> anyhow::Result<Self> {
+        let openai_api_key = match &source.api_key {
+            Some(api_key) => Some(external_secrets::resolve(api_key).await?),
+            None => None,
+        };
+        Ok(Self {
+            openai_api_key,
             openai_base_url: source.url.clone(),
             auth_header_name: source.auth_header_name.clone(),
             auth_header_prefix: source.auth_header_prefix.clone(),
             request_timeout: Self::DEFAULT_REQUEST_TIMEOUT,
-        }
+            tls: EndpointTls::from_endpoint(source),
+        })
     }
 }
 
+/// Error returned when a models API responds with a non-success status code.
+///
+/// Kept as a typed error (rather than a formatted string) so callers can tell
+/// authentication failures apart from other kinds of failure.
+#[derive(Debug, thiserror::Error)]
+#[error("{provider} API error: {status} - {body}")]
+pub struct ModelsApiError {
+    pub provider: &'static str,
+    pub status: reqwest::StatusCode,
+    pub body: String,
+}
+
 /// A trait for fetching models in openai compatible format.
 /// In practise, this is used for fetching models over http from downstream openai compatible
 /// endpoints, using the `reqwest` library. See `FetchModelsReqwest` for more info.
@@ -53,7 +74,13 @@ pub struct FetchModelsReqwest {
 
 impl FetchModelsReqwest {
     pub fn new(config: SyncConfig) -> Self {
-        let client = Client::builder()
+        // TLS settings are validated when they're stored, so this only falls back if they were
+        // changed behind our back. The fallback still verifies certificates.
+        let builder = config.tls.client_builder().unwrap_or_else(|e| {
+            tracing::error!("Ignoring invalid TLS settings of {}: {}", config.openai_base_url, e);
+            Client::builder()
+        });
+        let client = builder
             .timeout(config.request_timeout)
             .build()
             .expect("Failed to create HTTP client");
@@ -94,13 +121,17 @@ fn ensure_slash(url: &Url) -> Url {
 pub enum ModelFormat {
     OpenAI,
     Anthropic,
+    Azure,
 }
 
 impl From<&Url> for ModelFormat {
     fn from(value: &Url) -> Self {
-        if value.as_str().starts_with("https://api.anthropic.com") {
+        if crate::anthropic::is_anthropic_url(value) {
             return Self::Anthropic;
         }
+        if crate::azure::is_azure_url(value) {
+            return Self::Azure;
+        }
         Self::OpenAI
     }
 }
@@ -112,9 +143,13 @@ impl FetchModels for FetchModelsReqwest {
         let fmt = (&self.base_url).into();
         debug!("Featching models in format: {:?}", fmt);
 
-        let url = ensure_slash(&self.base_url)
-            .join("models")
-            .map_err(|e| anyhow::anyhow!("Failed to construct models URL: {}", e))?;
+        // Azure serves models under their deployment names, so list the deployments instead
+        let url = match fmt {
+            ModelFormat::Azure => crate::azure::deployments_url(&self.base_url),
+            _ => ensure_slash(&self.base_url)
+                .join("models")
+                .map_err(|e| anyhow::anyhow!("Failed to construct models URL: {}", e))?,
+        };
 
         debug!("Fetching models from URL: {}", url);
 
@@ -133,7 +168,12 @@ impl FetchModels for FetchModelsReqwest {
                     let body = response.text().await.unwrap_or_default();
                     tracing::error!("Failed to make request to openAI API for models");
                     tracing::error!("Url was: {}", url);
-                    return Err(anyhow!("OpenAI API error: {} - {}", status, body));
+                    return Err(ModelsApiError {
+                        provider: "OpenAI",
+                        status,
+                        body,
+                    }
+                    .into());
                 }
 
                 // Get the response body as text first for logging
@@ -166,7 +206,12 @@ impl FetchModels for FetchModelsReqwest {
                     let body = response.text().await.unwrap_or_default();
                     tracing::error!("Failed to make request to anthropic API for models");
                     tracing::error!("Url was: {}", url);
-                    return Err(anyhow!("Anthropic API error {}: {}", status, body));
+                    return Err(ModelsApiError {
+                        provider: "Anthropic",
+                        status,
+                        body,
+                    }
+                    .into());
                 }
 
                 // Get the response body as text first for logging
@@ -185,6 +230,42 @@ impl FetchModels for FetchModelsReqwest {
                     }
                 }
             }
+            ModelFormat::Azure => {
+                if let Some(api_key) = &self.openai_api_key {
+                    request = request.header(&self.auth_header_name, format!("{}{}", self.auth_header_prefix, api_key));
+                };
+
+                let response = request.timeout(self.request_timeout).send().await?;
+
+                if !response.status().is_success() {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    tracing::error!("Failed to make request to Azure OpenAI API for deployments");
+                    tracing::error!("Url was: {}", url);
+                    return Err(ModelsApiError {
+                        provider: "Azure OpenAI",
+                        status,
+                        body,
+                    }
+                    .into());
+                }
+
+                // Get the response body as text first for logging
+                let body_text = response.text().await?;
+                tracing::debug!("Deployments API response body: {}", body_text);
+
+                // Try to parse the JSON
+                match serde_json::from_str::<AzureDeploymentsResponse>(&body_text) {
+                    Ok(parsed) => Ok(parsed.into()),
+                    Err(e) => {
+                        tracing::error!("Failed to make request to Azure OpenAI API for deployments");
+                        tracing::error!("Url was: {}", url);
+                        tracing::error!("Failed to parse deployments response as JSON. Error: {}", e);
+                        tracing::error!("Response body was: {}", body_text);
+                        Err(anyhow!("error decoding response body: {}", e))
+                    }
+                }
+            }
         }
     }
 }
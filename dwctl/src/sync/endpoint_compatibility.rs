@@ -0,0 +1,520 @@
+//! OpenAI compatibility suite for inference endpoints.
+//!
+//! Many backends advertise an "OpenAI-compatible" API but only implement part of it. The suite
+//! sends a handful of small requests straight to an endpoint - bypassing the proxy - and checks
+//! that streaming, tool calls, JSON mode, stop sequences and error responses behave like the
+//! OpenAI API. Each run is stored in `endpoint_compatibility_reports` with a score, so admins can
+//! see which features each backend genuinely supports.
+
+use crate::db::models::endpoint_compatibility::{CompatibilityCheckResult, EndpointCompatibilityReport};
+use crate::db::models::inference_endpoints::InferenceEndpointDBResponse;
+use crate::errors::{Error, Result};
+use crate::external_secrets;
+use crate::sync::endpoint_validation::validate_endpoint_connection;
+use crate::tls::EndpointTls;
+use crate::types::{InferenceEndpointId, UserId};
+use serde_json::{json, Value};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Upper bound on how long a single check may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(60);
+
+type CheckOutcome = std::result::Result<(), String>;
+
+/// Sends the suite's requests to a single endpoint and model
+struct Suite {
+    client: reqwest::Client,
+    chat_url: String,
+    model: String,
+    auth: Option<(String, String)>,
+}
+
+impl Suite {
+    fn new(endpoint: &InferenceEndpointDBResponse, model: String) -> Result<Self> {
+        let client = EndpointTls::from_endpoint(endpoint)
+            .client_builder()
+            .map_err(|message| Error::BadRequest { message })?
+            .timeout(CHECK_TIMEOUT)
+            .build()
+            .map_err(|e| Error::Other(e.into()))?;
+        let auth = endpoint
+            .api_key
+            .as_ref()
+            .map(|key| (endpoint.auth_header_name.clone(), format!("{}{}", endpoint.auth_header_prefix, key)));
+
+        Ok(Self {
+            client,
+            chat_url: format!("{}/chat/completions", endpoint.url.as_str().trim_end_matches('/')),
+            model,
+            auth,
+        })
+    }
+
+    async fn send(&self, body: &Value) -> std::result::Result<reqwest::Response, String> {
+        let mut request = self.client.post(&self.chat_url).json(body);
+        if let Some((name, value)) = &self.auth {
+            request = request.header(name, value);
+        }
+        request.send().await.map_err(|e| format!("Request failed: {e}"))
+    }
+
+    /// Send a short chat completion with `extra` merged into the body, returning the parsed response
+    async fn chat(&self, extra: Value) -> std::result::Result<Value, String> {
+        let mut body = json!({
+            "model": self.model,
+            "messages": [{"role": "user", "content": "Say hello in one short sentence."}],
+            "max_tokens": 50,
+        });
+        if let (Some(body), Value::Object(extra)) = (body.as_object_mut(), extra) {
+            body.extend(extra);
+        }
+
+        let response = self.send(&body).await?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| format!("Failed to read response body: {e}"))?;
+        if !status.is_success() {
+            return Err(format!("HTTP {status}: {}", truncate(&text)));
+        }
+        serde_json::from_str(&text).map_err(|_| format!("Response is not JSON: {}", truncate(&text)))
+    }
+
+    async fn run(&self) -> Vec<CompatibilityCheckResult> {
+        vec![
+            timed("chat_completion", self.check_chat_completion()).await,
+            timed("streaming", self.check_streaming()).await,
+            timed("tool_calls", self.check_tool_calls()).await,
+            timed("json_mode", self.check_json_mode()).await,
+            timed("stop_sequences", self.check_stop_sequences()).await,
+            timed("error_shape", self.check_error_shape()).await,
+        ]
+    }
+
+    async fn check_chat_completion(&self) -> CheckOutcome {
+        let response = self.chat(json!({})).await?;
+        message_content(&response)?;
+        if response["object"] != "chat.completion" {
+            return Err(format!("Expected object 'chat.completion', got {}", response["object"]));
+        }
+        if !response["usage"]["prompt_tokens"].is_u64() || !response["usage"]["completion_tokens"].is_u64() {
+            return Err("Response has no token usage".to_string());
+        }
+        Ok(())
+    }
+
+    async fn check_streaming(&self) -> CheckOutcome {
+        let body = json!({
+            "model": self.model,
+            "messages": [{"role": "user", "content": "Say hello in one short sentence."}],
+            "max_tokens": 50,
+            "stream": true,
+        });
+        let response = self.send(&body).await?;
+        let status = response.status();
+        let is_event_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        let text = response.text().await.map_err(|e| format!("Failed to read stream: {e}"))?;
+        if !status.is_success() {
+            return Err(format!("HTTP {status}: {}", truncate(&text)));
+        }
+        if !is_event_stream {
+            return Err("Response is not a text/event-stream".to_string());
+        }
+
+        let events: Vec<&str> = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(str::trim)
+            .filter(|data| !data.is_empty())
+            .collect();
+        let Some((last, chunks)) = events.split_last() else {
+            return Err("Stream contained no events".to_string());
+        };
+        if *last != "[DONE]" {
+            return Err("Stream did not end with [DONE]".to_string());
+        }
+
+        let mut saw_delta = false;
+        for chunk in chunks {
+            let chunk: Value = serde_json::from_str(chunk).map_err(|_| format!("Stream chunk is not JSON: {}", truncate(chunk)))?;
+            if chunk["object"] != "chat.completion.chunk" {
+                return Err(format!("Expected object 'chat.completion.chunk', got {}", chunk["object"]));
+            }
+            saw_delta |= chunk["choices"][0]["delta"].is_object();
+        }
+        if !saw_delta {
+            return Err("No chunk contained a delta".to_string());
+        }
+        Ok(())
+    }
+
+    async fn check_tool_calls(&self) -> CheckOutcome {
+        let response = self
+            .chat(json!({
+                "messages": [{"role": "user", "content": "What is the weather in Paris?"}],
+                "tools": [{
+                    "type": "function",
+                    "function": {
+                        "name": "get_weather",
+                        "description": "Get the current weather in a city",
+                        "parameters": {
+                            "type": "object",
+                            "properties": {"city": {"type": "string"}},
+                            "required": ["city"]
+                        }
+                    }
+                }],
+                "tool_choice": {"type": "function", "function": {"name": "get_weather"}},
+            }))
+            .await?;
+
+        let call = &response["choices"][0]["message"]["tool_calls"][0];
+        if call.is_null() {
+            return Err("Response contains no tool calls".to_string());
+        }
+        if call["function"]["name"] != "get_weather" {
+            return Err(format!("Expected a call to get_weather, got {}", call["function"]["name"]));
+        }
+        let arguments = call["function"]["arguments"]
+            .as_str()
+            .ok_or("Tool call arguments are not a string")?;
+        match serde_json::from_str::<Value>(arguments) {
+            Ok(Value::Object(_)) => Ok(()),
+            _ => Err(format!("Tool call arguments are not a JSON object: {}", truncate(arguments))),
+        }
+    }
+
+    async fn check_json_mode(&self) -> CheckOutcome {
+        let response = self
+            .chat(json!({
+                "messages": [{"role": "user", "content": "Return a JSON object with a single key \"greeting\" set to \"hello\"."}],
+                "response_format": {"type": "json_object"},
+            }))
+            .await?;
+
+        let content = message_content(&response)?;
+        match serde_json::from_str::<Value>(content) {
+            Ok(Value::Object(_)) => Ok(()),
+            _ => Err(format!("Content is not a JSON object: {}", truncate(content))),
+        }
+    }
+
+    async fn check_stop_sequences(&self) -> CheckOutcome {
+        let response = self
+            .chat(json!({
+                "messages": [{"role": "user", "content": "Count from 1 to 20, separated by spaces. Output only the numbers."}],
+                "max_tokens": 100,
+                "stop": ["7"],
+            }))
+            .await?;
+
+        let content = message_content(&response)?;
+        if content.contains('7') {
+            return Err(format!("Generation continued past the stop sequence: {}", truncate(content)));
+        }
+        if response["choices"][0]["finish_reason"] != "stop" {
+            return Err(format!(
+                "Expected finish_reason 'stop', got {}",
+                response["choices"][0]["finish_reason"]
+            ));
+        }
+        Ok(())
+    }
+
+    async fn check_error_shape(&self) -> CheckOutcome {
+        let response = self
+            .send(&json!({"model": self.model, "messages": "not a list of messages"}))
+            .await?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| format!("Failed to read response body: {e}"))?;
+        if !status.is_client_error() {
+            return Err(format!("Expected a 4xx response to an invalid request, got HTTP {status}"));
+        }
+        let body: Value = serde_json::from_str(&text).map_err(|_| format!("Error response is not JSON: {}", truncate(&text)))?;
+        if !body["error"]["message"].is_string() {
+            return Err(format!("Error response has no error.message: {}", truncate(&text)));
+        }
+        Ok(())
+    }
+}
+
+async fn timed(name: &str, check: impl Future<Output = CheckOutcome>) -> CompatibilityCheckResult {
+    let start = Instant::now();
+    let outcome = check.await;
+    CompatibilityCheckResult {
+        name: name.to_string(),
+        passed: outcome.is_ok(),
+        detail: outcome.err(),
+        latency_ms: start.elapsed().as_millis() as u64,
+    }
+}
+
+fn message_content(response: &Value) -> std::result::Result<&str, String> {
+    response["choices"][0]["message"]["content"]
+        .as_str()
+        .ok_or_else(|| "Response has no choices[0].message.content".to_string())
+}
+
+/// Keep stored failure details short, even when an endpoint returns a whole HTML page
+fn truncate(text: &str) -> String {
+    const MAX_CHARS: usize = 200;
+    match text.char_indices().nth(MAX_CHARS) {
+        Some((i, _)) => format!("{}...", &text[..i]),
+        None => text.to_string(),
+    }
+}
+
+/// Run the compatibility suite against an endpoint and store the report.
+///
+/// If no model is given, the first model listed by the endpoint is used.
+pub async fn run_compatibility_suite(
+    pool: &PgPool,
+    endpoint: &InferenceEndpointDBResponse,
+    model: Option<String>,
+    created_by: UserId,
+) -> Result<EndpointCompatibilityReport> {
+    let model = match model {
+        Some(model) => model,
+        None => {
+            let models = validate_endpoint_connection(
+                &endpoint.url,
+                endpoint.api_key.as_deref(),
+                Some(endpoint.auth_header_name.clone()),
+                Some(endpoint.auth_header_prefix.clone()),
+                &EndpointTls::from_endpoint(endpoint),
+            )
+            .await?;
+            models.data.into_iter().next().map(|m| m.id).ok_or_else(|| Error::BadRequest {
+                message: "No models found at this endpoint".to_string(),
+            })?
+        }
+    };
+
+    tracing::info!(
+        "Running compatibility suite against endpoint '{}' with model '{}'",
+        endpoint.name,
+        model
+    );
+    // The checks send requests with the key itself, so one kept in a secret manager is resolved
+    let mut resolved = endpoint.clone();
+    if let Some(api_key) = &endpoint.api_key {
+        resolved.api_key = Some(external_secrets::resolve(api_key).await.map_err(|e| Error::BadRequest {
+            message: format!("Failed to resolve the endpoint's API key: {e:#}"),
+        })?);
+    }
+    let checks = Suite::new(&resolved, model.clone())?.run().await;
+    let passed = checks.iter().filter(|c| c.passed).count() as i32;
+    tracing::info!(
+        "Endpoint '{}' passed {} of {} compatibility checks",
+        endpoint.name,
+        passed,
+        checks.len()
+    );
+
+    let report = sqlx::query_as!(
+        EndpointCompatibilityReport,
+        r#"
+        INSERT INTO endpoint_compatibility_reports (endpoint_id, model, passed, total, checks, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, endpoint_id, model, passed, total, score as "score!",
+            checks as "checks: Json<Vec<CompatibilityCheckResult>>", created_by, created_at
+        "#,
+        endpoint.id,
+        model,
+        passed,
+        checks.len() as i32,
+        Json(&checks) as _,
+        created_by
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| Error::Database(e.into()))?;
+
+    Ok(report)
+}
+
+/// List the stored compatibility reports for an endpoint, most recent first
+pub async fn list_compatibility_reports(
+    pool: &PgPool,
+    endpoint_id: InferenceEndpointId,
+    limit: i64,
+) -> Result<Vec<EndpointCompatibilityReport>> {
+    let reports = sqlx::query_as!(
+        EndpointCompatibilityReport,
+        r#"
+        SELECT id, endpoint_id, model, passed, total, score as "score!",
+            checks as "checks: Json<Vec<CompatibilityCheckResult>>", created_by, created_at
+        FROM endpoint_compatibility_reports
+        WHERE endpoint_id = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+        endpoint_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| Error::Database(e.into()))?;
+
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::{header, StatusCode},
+        response::IntoResponse,
+        routing::{get, post},
+        Json as AxumJson, Router,
+    };
+    use chrono::Utc;
+
+    /// A fake OpenAI-compatible backend. When `compliant` is false it ignores streaming, tools,
+    /// JSON mode and stop sequences, and returns errors as plain text.
+    async fn mock_endpoint(compliant: bool) -> InferenceEndpointDBResponse {
+        let app = Router::new()
+            .route(
+                "/v1/models",
+                get(|| async {
+                    AxumJson(json!({"object": "list", "data": [{"id": "mock-model", "object": "model", "owned_by": "test"}]}))
+                }),
+            )
+            .route(
+                "/v1/chat/completions",
+                post(move |AxumJson(body): AxumJson<Value>| async move { mock_chat(compliant, body) }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        InferenceEndpointDBResponse {
+            id: uuid::Uuid::new_v4(),
+            name: "mock".to_string(),
+            description: None,
+            url: format!("http://{addr}/v1").parse().unwrap(),
+            api_key: Some("secret".to_string()),
+            model_filter: None,
+            auth_header_name: "Authorization".to_string(),
+            auth_header_prefix: "Bearer ".to_string(),
+            tls_ca_pem: None,
+            tls_skip_verify: false,
+            tls_min_version: None,
+            sync_interval_seconds: None,
+            last_sync_at: None,
+            last_sync_error: None,
+            enabled: true,
+            created_by: uuid::Uuid::nil(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn mock_chat(compliant: bool, body: Value) -> axum::response::Response {
+        if !body["messages"].is_array() {
+            return if compliant {
+                (
+                    StatusCode::BAD_REQUEST,
+                    AxumJson(json!({"error": {"message": "messages must be a list"}})),
+                )
+                    .into_response()
+            } else {
+                (StatusCode::INTERNAL_SERVER_ERROR, "oops").into_response()
+            };
+        }
+
+        let completion = |message: Value, finish_reason: &str| {
+            AxumJson(json!({
+                "object": "chat.completion",
+                "choices": [{"index": 0, "message": message, "finish_reason": finish_reason}],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 5, "total_tokens": 10}
+            }))
+            .into_response()
+        };
+
+        if !compliant {
+            return completion(json!({"role": "assistant", "content": "1 2 3 4 5 6 7 8"}), "length");
+        }
+        if body["stream"] == true {
+            let chunk = json!({"object": "chat.completion.chunk", "choices": [{"index": 0, "delta": {"content": "Hello"}}]});
+            let stream = format!("data: {chunk}\n\ndata: [DONE]\n\n");
+            return ([(header::CONTENT_TYPE, "text/event-stream")], stream).into_response();
+        }
+        if body["tools"].is_array() {
+            let call =
+                json!({"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}});
+            return completion(json!({"role": "assistant", "content": null, "tool_calls": [call]}), "tool_calls");
+        }
+        if body["response_format"]["type"] == "json_object" {
+            return completion(json!({"role": "assistant", "content": "{\"greeting\": \"hello\"}"}), "stop");
+        }
+        if body["stop"].is_array() {
+            return completion(json!({"role": "assistant", "content": "1 2 3 4 5 6 "}), "stop");
+        }
+        completion(json!({"role": "assistant", "content": "Hello!"}), "stop")
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short"), "short");
+        let long = "é".repeat(300);
+        assert_eq!(truncate(&long).chars().count(), 203);
+    }
+
+    #[tokio::test]
+    async fn test_compliant_endpoint_passes_every_check() {
+        let endpoint = mock_endpoint(true).await;
+        let checks = Suite::new(&endpoint, "mock-model".to_string()).unwrap().run().await;
+
+        assert_eq!(checks.len(), 6);
+        for check in &checks {
+            assert!(check.passed, "{} failed: {:?}", check.name, check.detail);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partial_endpoint_fails_unsupported_features() {
+        let endpoint = mock_endpoint(false).await;
+        let checks = Suite::new(&endpoint, "mock-model".to_string()).unwrap().run().await;
+
+        let passed: Vec<&str> = checks.iter().filter(|c| c.passed).map(|c| c.name.as_str()).collect();
+        assert_eq!(passed, vec!["chat_completion"]);
+        let streaming = checks.iter().find(|c| c.name == "streaming").unwrap();
+        assert_eq!(streaming.detail.as_deref(), Some("Response is not a text/event-stream"));
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_reports_are_stored_per_endpoint(pool: PgPool) {
+        let mut endpoint = mock_endpoint(false).await;
+        endpoint.id = sqlx::query_scalar!(
+            "INSERT INTO inference_endpoints (name, url, created_by) VALUES ($1, $2, $3) RETURNING id",
+            "compat-endpoint",
+            endpoint.url.as_str(),
+            uuid::Uuid::nil()
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        // The model defaults to the first one the endpoint lists
+        let first = run_compatibility_suite(&pool, &endpoint, None, uuid::Uuid::nil()).await.unwrap();
+        assert_eq!(first.model, "mock-model");
+        assert_eq!((first.passed, first.total), (1, 6));
+        assert!((first.score - 1.0 / 6.0).abs() < 1e-9);
+
+        let second = run_compatibility_suite(&pool, &endpoint, Some("other-model".to_string()), uuid::Uuid::nil())
+            .await
+            .unwrap();
+
+        let reports = list_compatibility_reports(&pool, endpoint.id, 10).await.unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].id, second.id);
+        assert_eq!(reports[1].checks.0, first.checks.0);
+    }
+}
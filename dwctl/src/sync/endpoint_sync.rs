@@ -1,4 +1,4 @@
-use crate::api::models::inference_endpoints::OpenAIModel;
+use crate::api::models::inference_endpoints::{EndpointSyncStrategy, NewModelAction, OpenAIModel, RemovedModelAction};
 use crate::db::handlers::deployments::DeploymentFilter;
 use crate::db::handlers::repository::Repository;
 use crate::db::handlers::{Deployments, InferenceEndpoints};
@@ -38,8 +38,10 @@ pub struct EndpointSyncResponse {
     pub models_reactivated: usize,
     /// Number of models deactivated
     pub models_deactivated: usize,
-    /// Number of models deleted (filtered out)
+    /// Number of models deleted (filtered out, or removed upstream when the sync strategy deletes them)
     pub models_deleted: usize,
+    /// Models awaiting approval, when the endpoint's sync strategy queues new models
+    pub queued_models: Vec<String>,
     /// Total number of models fetched from endpoint
     pub total_models_fetched: usize,
     /// Number of models after applying filter
@@ -48,11 +50,23 @@ pub struct EndpointSyncResponse {
     pub synced_at: chrono::DateTime<chrono::Utc>,
 }
 
-/// Synchronize deployments for a specific inference endpoint
+/// Synchronize deployments for a specific inference endpoint, recording the outcome on the endpoint
 #[instrument]
 pub async fn synchronize_endpoint(endpoint_id: InferenceEndpointId, pool: PgPool) -> Result<EndpointSyncResponse> {
+    let result = sync_endpoint(endpoint_id, &pool).await;
+
+    let error = result.as_ref().err().map(|e| format!("{e:#}"));
+    let mut conn = pool.acquire().await?;
+    if let Err(e) = InferenceEndpoints::new(&mut conn).record_sync(endpoint_id, error.as_deref()).await {
+        warn!("Failed to record the synchronization of endpoint {}: {}", endpoint_id, e);
+    }
+    result
+}
+
+async fn sync_endpoint(endpoint_id: InferenceEndpointId, pool: &PgPool) -> Result<EndpointSyncResponse> {
     let mut tx = pool.begin().await?;
     let endpoint_info;
+    let strategy;
     // Automatically synchronize the endpoint after creating
     {
         let mut endpoints_repo = InferenceEndpoints::new(&mut tx);
@@ -62,19 +76,31 @@ pub async fn synchronize_endpoint(endpoint_id: InferenceEndpointId, pool: PgPool
             .get_by_id(endpoint_id)
             .await?
             .ok_or_else(|| anyhow::anyhow!("Endpoint not found: {}", endpoint_id))?;
+        strategy = endpoints_repo
+            .get_sync_strategy(endpoint_id)
+            .await?
+            .map(EndpointSyncStrategy::from)
+            .unwrap_or_default();
     }
 
     // Create sync config from endpoint
-    let sync_config = SyncConfig::from_endpoint(&endpoint_info);
+    let sync_config = SyncConfig::from_endpoint(&endpoint_info).await?;
 
     // Create fetcher
     let fetcher = FetchModelsReqwest::new(sync_config);
 
     // Perform the sync
-    let sync_result;
+    let mut sync_result;
     {
         let mut deployments_repo = Deployments::new(&mut tx);
-        sync_result = sync_endpoint_models(endpoint_info, &mut deployments_repo, fetcher).await
+        sync_result = sync_endpoint_models(endpoint_info, &mut deployments_repo, fetcher, strategy).await
+    }
+
+    // New models get default aliases, so record them as a change made by the system user
+    if let Ok(response) = &mut sync_result {
+        let mut endpoints_repo = InferenceEndpoints::new(&mut tx);
+        endpoints_repo.record_version(endpoint_id, uuid::Uuid::nil()).await?;
+        response.queued_models = endpoints_repo.sync_pending_models(endpoint_id, &response.queued_models).await?;
     }
 
     tx.commit()
@@ -83,12 +109,16 @@ pub async fn synchronize_endpoint(endpoint_id: InferenceEndpointId, pool: PgPool
     sync_result
 }
 
-/// Synchronizes models for an endpoint by fetching and comparing with existing deployments
+/// Synchronizes models for an endpoint by fetching and comparing with existing deployments.
+///
+/// New models are deployed or, if the strategy queues them, returned in `queued_models` for the
+/// caller to record; deployments of models removed upstream are disabled, deleted or kept.
 #[instrument(skip(deployments_repo, fetch_models))]
 pub async fn sync_endpoint_models<D, F>(
     endpoint_info: InferenceEndpointDBResponse,
     deployments_repo: &mut D,
     fetch_models: F,
+    strategy: EndpointSyncStrategy,
 ) -> Result<EndpointSyncResponse>
 where
     D: Repository<
@@ -113,6 +143,7 @@ where
     let mut models_reactivated = 0;
     let mut models_deactivated = 0;
     let mut models_deleted = 0;
+    let mut queued_models = Vec::new();
     let sync_time = Utc::now();
 
     // Filter models based on endpoint's model_filter if specified
@@ -139,9 +170,13 @@ where
     // Use system user ID (nil UUID) for creating deployments
     let system_user_id = Uuid::nil();
 
-    // Create new models that don't exist yet.
+    // Create new models that don't exist yet, or queue them for approval
     for model in &models_to_sync {
         if !existing_model_names.contains(&model.id) {
+            if strategy.new_models == NewModelAction::Queue {
+                queued_models.push(model.id.clone());
+                continue;
+            }
             match create_deployment(deployments_repo, model, &endpoint_info, system_user_id).await {
                 Ok(_) => {
                     debug!("Created new deployment for model: {}", model.id);
@@ -215,6 +250,25 @@ where
                 }
             }
 
+            // Model is missing from API and the strategy deletes such models
+            (_, false) if strategy.removed_models == RemovedModelAction::Delete => {
+                if let Err(e) = deployments_repo.delete(existing_model.id).await {
+                    warn!("Failed to delete model {}: {}", existing_model.model_name, e);
+                } else {
+                    debug!("Deleted model {} (missing from API)", existing_model.model_name);
+                    models_deleted += 1;
+                    changes_made += 1;
+                }
+            }
+
+            // Model is active but missing from API and the strategy keeps such models - update sync time
+            (ModelStatus::Active, false) if strategy.removed_models == RemovedModelAction::Keep => {
+                let update = DeploymentUpdateDBRequest::status_update(None, sync_time);
+                if let Err(e) = deployments_repo.update(existing_model.id, &update).await {
+                    warn!("Failed to update sync time for kept model {}: {}", existing_model.model_name, e);
+                }
+            }
+
             // Model is active but missing from API - mark inactive
             (ModelStatus::Active, false) => {
                 let update = DeploymentUpdateDBRequest::status_update(Some(ModelStatus::Inactive), sync_time);
@@ -249,6 +303,7 @@ where
         models_reactivated,
         models_deactivated,
         models_deleted,
+        queued_models,
         total_models_fetched: fetched_models.data.len(),
         filtered_models_count: models_to_sync.len(),
         synced_at: sync_time,
@@ -374,6 +429,7 @@ where
         models_reactivated: 0,
         models_deactivated: 0,
         models_deleted: 0,
+        queued_models: Vec::new(),
         total_models_fetched: fetched_models.data.len(),
         filtered_models_count: models_to_sync.len(),
         synced_at: sync_time,
@@ -674,6 +730,7 @@ where
         models_reactivated: 0,
         models_deactivated: 0,
         models_deleted,
+        queued_models: Vec::new(),
         total_models_fetched: models_to_deploy.len(),
         filtered_models_count: models_to_deploy.len(),
         synced_at: Utc::now(),
@@ -683,7 +740,7 @@ where
 #[cfg(test)]
 mod tests {
     use crate::{
-        api::models::inference_endpoints::{OpenAIModel, OpenAIModelsResponse},
+        api::models::inference_endpoints::{EndpointSyncStrategy, NewModelAction, OpenAIModel, OpenAIModelsResponse, RemovedModelAction},
         db::{
             errors::Result,
             handlers::{deployments::DeploymentFilter, InferenceEndpoints, Repository},
@@ -730,9 +787,14 @@ mod tests {
                 status: mock.status,
                 last_sync: mock.last_sync,
                 deleted: false,
+                enabled: true,
                 requests_per_second: None,
                 burst_size: None,
                 pricing: None,
+                max_context_tokens: None,
+                supports_tools: None,
+                supports_vision: None,
+                tags: Vec::new(),
             }
         }
     }
@@ -788,6 +850,9 @@ mod tests {
             if let Some(deleted) = &request.deleted {
                 response.deleted = *deleted;
             }
+            if let Some(enabled) = &request.enabled {
+                response.enabled = *enabled;
+            }
             response.updated_at = chrono::Utc::now();
             response
         }
@@ -913,6 +978,13 @@ mod tests {
             model_filter: None, // No filter by default - sync all models
             auth_header_name: "Authorization".to_string(),
             auth_header_prefix: "Bearer ".to_string(),
+            tls_ca_pem: None,
+            tls_skip_verify: false,
+            tls_min_version: None,
+            sync_interval_seconds: None,
+            last_sync_at: None,
+            last_sync_error: None,
+            enabled: true,
             created_by: uuid::Uuid::nil(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -974,7 +1046,9 @@ mod tests {
         let endpoint_info = create_test_endpoint();
 
         // Run sync - should add 2 new models
-        let result = sync_endpoint_models(endpoint_info, &mut repo, fetch_models).await.unwrap();
+        let result = sync_endpoint_models(endpoint_info, &mut repo, fetch_models, EndpointSyncStrategy::default())
+            .await
+            .unwrap();
         assert_eq!(result.changes_made, 2);
         assert_eq!(result.new_models_created, 2);
         assert_eq!(result.models_reactivated, 0);
@@ -1004,7 +1078,9 @@ mod tests {
         let endpoint_info = create_test_endpoint();
 
         // Run sync - should mark 2 models inactive
-        let result = sync_endpoint_models(endpoint_info, &mut repo, fetch_models).await.unwrap();
+        let result = sync_endpoint_models(endpoint_info, &mut repo, fetch_models, EndpointSyncStrategy::default())
+            .await
+            .unwrap();
         assert_eq!(result.changes_made, 2);
         assert_eq!(result.new_models_created, 0);
         assert_eq!(result.models_reactivated, 0);
@@ -1040,7 +1116,9 @@ mod tests {
         let endpoint_info = create_test_endpoint();
 
         // Run sync - should add 1 new model and mark 1 model inactive
-        let result = sync_endpoint_models(endpoint_info, &mut repo, fetch_models).await.unwrap();
+        let result = sync_endpoint_models(endpoint_info, &mut repo, fetch_models, EndpointSyncStrategy::default())
+            .await
+            .unwrap();
         assert_eq!(result.changes_made, 2); // 1 added + 1 marked inactive
         assert_eq!(result.new_models_created, 1);
         assert_eq!(result.models_deactivated, 1);
@@ -1065,6 +1143,46 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_sync_models_with_strategy() {
+        let mut repo = MockDeploymentsRepo::new();
+        let fetch_models = MockFetchModels::new();
+        repo.add_deployment("removed-model".to_string(), "removed-model".to_string()).await;
+        repo.add_deployment("kept-model".to_string(), "kept-model".to_string()).await;
+        fetch_models.set_models(vec![create_test_model("kept-model"), create_test_model("new-model")]);
+
+        // Queued models aren't deployed, and removed models are deleted
+        let strategy = EndpointSyncStrategy {
+            removed_models: RemovedModelAction::Delete,
+            new_models: NewModelAction::Queue,
+        };
+        let result = sync_endpoint_models(create_test_endpoint(), &mut repo, fetch_models, strategy)
+            .await
+            .unwrap();
+        assert_eq!(result.new_models_created, 0);
+        assert_eq!(result.queued_models, vec!["new-model".to_string()]);
+        assert_eq!(result.models_deleted, 1);
+        assert_eq!(result.models_deactivated, 0);
+        let deployments = repo.list(&DeploymentFilter::new(0, 10)).await.unwrap();
+        assert_eq!(deployments.len(), 1);
+        assert_eq!(deployments[0].model_name, "kept-model");
+
+        // Kept models stay active while missing upstream
+        let fetch_models = MockFetchModels::new();
+        fetch_models.set_models(vec![]);
+        let strategy = EndpointSyncStrategy {
+            removed_models: RemovedModelAction::Keep,
+            new_models: NewModelAction::AutoAdd,
+        };
+        let result = sync_endpoint_models(create_test_endpoint(), &mut repo, fetch_models, strategy)
+            .await
+            .unwrap();
+        assert_eq!(result.changes_made, 0);
+        let deployments = repo.list(&DeploymentFilter::new(0, 10)).await.unwrap();
+        assert_eq!(deployments[0].status, ModelStatus::Active);
+        assert!(deployments[0].last_sync.is_some());
+    }
+
     #[tokio::test]
     async fn test_sync_models_no_changes() {
         let mut repo = MockDeploymentsRepo::new();
@@ -1081,7 +1199,9 @@ mod tests {
         let endpoint_info = create_test_endpoint();
 
         // Run sync - no changes should occur
-        let result = sync_endpoint_models(endpoint_info, &mut repo, fetch_models).await.unwrap();
+        let result = sync_endpoint_models(endpoint_info, &mut repo, fetch_models, EndpointSyncStrategy::default())
+            .await
+            .unwrap();
         assert_eq!(result.changes_made, 0);
         assert_eq!(result.new_models_created, 0);
         assert_eq!(result.models_reactivated, 0);
@@ -0,0 +1,539 @@
+//! Scheduled re-validation of inference endpoints.
+//!
+//! Endpoint credentials can expire or be rotated upstream without anyone noticing until users
+//! start seeing errors. The `EndpointValidationScheduler` runs on the leader replica and
+//! periodically fetches the model list from every endpoint, storing the outcome in
+//! `endpoint_validation_results`. Endpoints that start rejecting their stored credentials are
+//! reported loudly, and the latest outcome for every endpoint is exposed through
+//! `GET /endpoints/validation-report`.
+
+use crate::api::models::inference_endpoints::{EndpointValidationStatus, OpenAIModelsResponse};
+use crate::config::EndpointValidationConfig;
+use crate::db::handlers::{inference_endpoints::InferenceEndpointFilter, InferenceEndpoints, Repository};
+use crate::db::models::endpoint_validations::{EndpointValidationCreateDBRequest, EndpointValidationResult};
+use crate::db::models::inference_endpoints::InferenceEndpointDBResponse;
+use crate::errors::{Error, Result};
+use crate::external_secrets;
+use crate::leader::LeaderFence;
+use crate::sync::deployments::fetch_models::{FetchModels, FetchModelsReqwest, ModelsApiError, SyncConfig};
+use crate::tls::EndpointTls;
+use crate::types::InferenceEndpointId;
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Validate an endpoint connection by fetching its model list.
+///
+/// Fails if the endpoint can't be reached, rejects the credentials, or returns no models.
+pub async fn validate_endpoint_connection(
+    url: &url::Url,
+    api_key: Option<&str>,
+    auth_header_name: Option<String>,
+    auth_header_prefix: Option<String>,
+    tls: &EndpointTls,
+) -> Result<OpenAIModelsResponse> {
+    // Keys kept in a secret manager are checked as they'll be used
+    let api_key = match api_key {
+        Some(api_key) => Some(external_secrets::resolve(api_key).await.map_err(|e| Error::BadRequest {
+            message: format!("Failed to resolve the endpoint's API key: {e:#}"),
+        })?),
+        None => None,
+    };
+    let auth_header_name = auth_header_name.unwrap_or_else(|| "Authorization".to_string());
+    let auth_header_prefix = auth_header_prefix.unwrap_or_else(|| "Bearer ".to_string());
+
+    tracing::debug!(
+        "Creating SyncConfig for validation: url={}, auth_header_name={}, auth_header_prefix={:?}, has_api_key={}",
+        url,
+        auth_header_name,
+        auth_header_prefix,
+        api_key.is_some()
+    );
+
+    let sync_config = SyncConfig {
+        openai_api_key: api_key,
+        openai_base_url: url.clone(),
+        auth_header_name,
+        auth_header_prefix,
+        request_timeout: Duration::from_secs(10),
+        tls: tls.clone(),
+    };
+
+    // Use the existing FetchModelsReqwest implementation
+    let fetcher = FetchModelsReqwest::new(sync_config);
+
+    tracing::debug!("Fetching models from endpoint...");
+    let models_response = fetcher.fetch().await.map_err(|e| {
+        tracing::error!("Failed to fetch models: {:#}", e);
+        e
+    })?;
+
+    tracing::debug!(
+        "Received models response: object={}, model_count={}",
+        models_response.object,
+        models_response.data.len()
+    );
+
+    if models_response.object != "list" {
+        return Err(Error::BadRequest {
+            message: "Invalid response format - expected 'list' object".to_string(),
+        });
+    }
+    if models_response.data.is_empty() {
+        return Err(Error::BadRequest {
+            message: "No models found at this endpoint".to_string(),
+        });
+    }
+
+    // The OpenAIModelsResponse is already in the right format
+    Ok(models_response)
+}
+
+/// Validate a single stored endpoint and record the outcome.
+pub async fn validate_endpoint(pool: &PgPool, endpoint: &InferenceEndpointDBResponse) -> Result<EndpointValidationResult> {
+    let outcome = validate_endpoint_connection(
+        &endpoint.url,
+        endpoint.api_key.as_deref(),
+        Some(endpoint.auth_header_name.clone()),
+        Some(endpoint.auth_header_prefix.clone()),
+        &EndpointTls::from_endpoint(endpoint),
+    )
+    .await;
+
+    let request = match outcome {
+        Ok(models) => EndpointValidationCreateDBRequest {
+            endpoint_id: endpoint.id,
+            success: true,
+            status_code: Some(StatusCode::OK.as_u16() as i32),
+            error_message: None,
+            model_count: Some(models.data.len() as i32),
+            credentials_expired: false,
+        },
+        Err(e) => {
+            let status = upstream_status(&e);
+            EndpointValidationCreateDBRequest {
+                endpoint_id: endpoint.id,
+                success: false,
+                status_code: status.map(|s| s.as_u16() as i32),
+                error_message: Some(failure_message(&e)),
+                model_count: None,
+                credentials_expired: matches!(status, Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)),
+            }
+        }
+    };
+
+    let previous = get_latest_result(pool, endpoint.id).await?;
+    let result = store_result(pool, &request).await?;
+    report_transition(endpoint, previous.as_ref(), &result);
+
+    Ok(result)
+}
+
+/// Validate every enabled inference endpoint, recording one result per endpoint.
+pub async fn validate_all_endpoints(pool: &PgPool) -> Result<Vec<EndpointValidationResult>> {
+    const PAGE_SIZE: i64 = 100;
+
+    let mut endpoints = Vec::new();
+    loop {
+        let mut conn = pool.acquire().await.map_err(|e| Error::Database(e.into()))?;
+        let page = InferenceEndpoints::new(&mut conn)
+            .list(&InferenceEndpointFilter::new(endpoints.len() as i64, PAGE_SIZE))
+            .await?;
+        let done = (page.len() as i64) < PAGE_SIZE;
+        endpoints.extend(page);
+        if done {
+            break;
+        }
+    }
+
+    endpoints.retain(|endpoint| endpoint.enabled);
+    tracing::info!("Re-validating {} inference endpoints", endpoints.len());
+
+    let mut results = Vec::with_capacity(endpoints.len());
+    for endpoint in &endpoints {
+        match validate_endpoint(pool, endpoint).await {
+            Ok(result) => results.push(result),
+            Err(e) => tracing::error!("Failed to record validation result for endpoint {}: {}", endpoint.id, e),
+        }
+    }
+
+    let failed = results.iter().filter(|r| !r.success).count();
+    tracing::info!(
+        "Endpoint re-validation finished: {} succeeded, {} failed",
+        results.len() - failed,
+        failed
+    );
+
+    Ok(results)
+}
+
+/// Get the latest validation outcome for every endpoint, including endpoints never validated.
+pub async fn get_validation_report(pool: &PgPool) -> Result<Vec<EndpointValidationStatus>> {
+    let statuses = sqlx::query_as!(
+        EndpointValidationStatus,
+        r#"
+        SELECT
+            e.id as endpoint_id,
+            e.name as endpoint_name,
+            e.url,
+            r.validated_at as "last_validated_at?",
+            s.last_success_at as "last_success_at?",
+            r.success as "success?",
+            r.status_code as "status_code?",
+            r.error_message as "error_message?",
+            r.model_count as "model_count?",
+            COALESCE(r.credentials_expired, false) as "credentials_expired!"
+        FROM inference_endpoints e
+        LEFT JOIN LATERAL (
+            SELECT validated_at, success, status_code, error_message, model_count, credentials_expired
+            FROM endpoint_validation_results
+            WHERE endpoint_id = e.id
+            ORDER BY validated_at DESC
+            LIMIT 1
+        ) r ON true
+        LEFT JOIN LATERAL (
+            SELECT MAX(validated_at) as last_success_at
+            FROM endpoint_validation_results
+            WHERE endpoint_id = e.id AND success = true
+        ) s ON true
+        ORDER BY e.name
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| Error::Database(e.into()))?;
+
+    Ok(statuses)
+}
+
+async fn get_latest_result(pool: &PgPool, endpoint_id: InferenceEndpointId) -> Result<Option<EndpointValidationResult>> {
+    let result = sqlx::query_as!(
+        EndpointValidationResult,
+        r#"
+        SELECT * FROM endpoint_validation_results
+        WHERE endpoint_id = $1
+        ORDER BY validated_at DESC
+        LIMIT 1
+        "#,
+        endpoint_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| Error::Database(e.into()))?;
+
+    Ok(result)
+}
+
+async fn store_result(pool: &PgPool, request: &EndpointValidationCreateDBRequest) -> Result<EndpointValidationResult> {
+    let result = sqlx::query_as!(
+        EndpointValidationResult,
+        r#"
+        INSERT INTO endpoint_validation_results
+        (endpoint_id, success, status_code, error_message, model_count, credentials_expired)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#,
+        request.endpoint_id,
+        request.success,
+        request.status_code,
+        request.error_message,
+        request.model_count,
+        request.credentials_expired
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| Error::Database(e.into()))?;
+
+    Ok(result)
+}
+
+/// Time of the most recent validation of any endpoint, used to avoid re-running on every restart.
+async fn last_run_at(pool: &PgPool) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+    let last = sqlx::query_scalar!("SELECT MAX(validated_at) FROM endpoint_validation_results")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| Error::Database(e.into()))?;
+
+    Ok(last)
+}
+
+/// Extract the HTTP status returned by the endpoint, if it responded at all.
+fn upstream_status(error: &Error) -> Option<StatusCode> {
+    match error {
+        Error::Other(e) => e.downcast_ref::<ModelsApiError>().map(|e| e.status),
+        _ => None,
+    }
+}
+
+fn failure_message(error: &Error) -> String {
+    match error {
+        Error::Other(e) => format!("{e:#}"),
+        other => other.user_message(),
+    }
+}
+
+/// Raise notifications when an endpoint changes state, rather than on every run.
+fn report_transition(
+    endpoint: &InferenceEndpointDBResponse,
+    previous: Option<&EndpointValidationResult>,
+    current: &EndpointValidationResult,
+) {
+    let was_expired = previous.is_some_and(|p| p.credentials_expired);
+    let was_failing = previous.is_some_and(|p| !p.success);
+
+    if current.credentials_expired && !was_expired {
+        tracing::error!(
+            endpoint_id = %endpoint.id,
+            endpoint_name = %endpoint.name,
+            status_code = ?current.status_code,
+            "Credentials for endpoint '{}' have been rejected by {} - the API key has likely expired or been revoked",
+            endpoint.name,
+            endpoint.url
+        );
+    } else if !current.success && !was_failing {
+        tracing::warn!(
+            endpoint_id = %endpoint.id,
+            endpoint_name = %endpoint.name,
+            "Endpoint '{}' failed validation: {}",
+            endpoint.name,
+            current.error_message.as_deref().unwrap_or("unknown error")
+        );
+    } else if current.success && was_failing {
+        tracing::info!(
+            endpoint_id = %endpoint.id,
+            endpoint_name = %endpoint.name,
+            "Endpoint '{}' is validating successfully again",
+            endpoint.name
+        );
+    }
+}
+
+/// Background task that re-validates all endpoints on a fixed interval.
+///
+/// Like the probe scheduler, this only runs on the leader replica.
+#[derive(Clone)]
+pub struct EndpointValidationScheduler {
+    pool: PgPool,
+    config: EndpointValidationConfig,
+    fence: LeaderFence,
+    handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl EndpointValidationScheduler {
+    pub fn new(pool: PgPool, config: EndpointValidationConfig, fence: LeaderFence) -> Self {
+        Self {
+            pool,
+            config,
+            fence,
+            handle: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Start the re-validation loop, if enabled and not already running.
+    pub async fn start(&self) {
+        if !self.config.enabled {
+            tracing::info!("Scheduled endpoint validation is disabled");
+            return;
+        }
+
+        let mut handle = self.handle.lock().await;
+        if handle.is_some() {
+            return;
+        }
+
+        let pool = self.pool.clone();
+        let fence = self.fence.clone();
+        let interval = self.config.interval;
+        *handle = Some(tokio::spawn(async move {
+            // Wait out the remainder of the interval if a recent run exists, so restarts
+            // and leadership changes don't trigger a fresh round of validations.
+            match last_run_at(&pool).await {
+                Ok(Some(last)) => {
+                    let elapsed = (chrono::Utc::now() - last).to_std().unwrap_or_default();
+                    if elapsed < interval {
+                        let wait = interval - elapsed;
+                        tracing::info!(
+                            "Endpoints last validated {}s ago, next run in {}s",
+                            elapsed.as_secs(),
+                            wait.as_secs()
+                        );
+                        tokio::time::sleep(wait).await;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to check last endpoint validation run: {}", e),
+            }
+
+            loop {
+                // If another replica has taken over as leader, leave validation to them
+                if !fence.is_current().await {
+                    tracing::warn!("Leadership is stale, stopping scheduled endpoint validation");
+                    break;
+                }
+                let started = Instant::now();
+                let result = validate_all_endpoints(&pool).await;
+                fence.observe_job("endpoint_validation", started.elapsed(), result.is_ok());
+                if let Err(e) = result {
+                    tracing::error!("Scheduled endpoint validation failed: {}", e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        }));
+
+        tracing::info!("Started scheduled endpoint validation (every {}s)", interval.as_secs());
+    }
+
+    /// Stop the re-validation loop (called when losing leadership).
+    pub async fn stop(&self) {
+        if let Some(handle) = self.handle.lock().await.take() {
+            handle.abort();
+            tracing::info!("Stopped scheduled endpoint validation");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::inference_endpoints::InferenceEndpointCreateDBRequest;
+    use crate::test_utils::get_system_user;
+    use axum::{http::StatusCode as AxumStatusCode, routing::get, Json, Router};
+
+    /// Serve a fake `/v1/models` endpoint on a random local port
+    async fn spawn_models_server(status: AxumStatusCode) -> url::Url {
+        let app = Router::new().route(
+            "/v1/models",
+            get(move || async move {
+                (
+                    status,
+                    Json(serde_json::json!({
+                        "object": "list",
+                        "data": [{"id": "test-model", "object": "model", "created": 0, "owned_by": "test"}]
+                    })),
+                )
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{addr}/v1").parse().unwrap()
+    }
+
+    async fn create_endpoint(pool: &PgPool, name: &str, url: url::Url) -> InferenceEndpointDBResponse {
+        let mut conn = pool.acquire().await.unwrap();
+        let system_user = get_system_user(&mut conn).await;
+        InferenceEndpoints::new(&mut conn)
+            .create(&InferenceEndpointCreateDBRequest {
+                created_by: system_user.id,
+                name: name.to_string(),
+                description: None,
+                url,
+                api_key: Some("sk-test".to_string()),
+                model_filter: None,
+                auth_header_name: None,
+                auth_header_prefix: None,
+                tls_ca_pem: None,
+                tls_skip_verify: false,
+                tls_min_version: None,
+                sync_interval_seconds: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_validate_endpoint_records_success(pool: PgPool) {
+        let url = spawn_models_server(AxumStatusCode::OK).await;
+        let endpoint = create_endpoint(&pool, "healthy", url).await;
+
+        let result = validate_endpoint(&pool, &endpoint).await.unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.model_count, Some(1));
+        assert!(!result.credentials_expired);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_validate_endpoint_flags_expired_credentials(pool: PgPool) {
+        let url = spawn_models_server(AxumStatusCode::UNAUTHORIZED).await;
+        let endpoint = create_endpoint(&pool, "expired", url).await;
+
+        let result = validate_endpoint(&pool, &endpoint).await.unwrap();
+
+        assert!(!result.success);
+        assert_eq!(result.status_code, Some(401));
+        assert!(result.credentials_expired);
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_validation_report_includes_latest_result_per_endpoint(pool: PgPool) {
+        let healthy_url = spawn_models_server(AxumStatusCode::OK).await;
+        let expired_url = spawn_models_server(AxumStatusCode::FORBIDDEN).await;
+        let healthy = create_endpoint(&pool, "healthy", healthy_url).await;
+        let expired = create_endpoint(&pool, "expired", expired_url).await;
+        let unchecked = create_endpoint(&pool, "unchecked", "http://localhost:1".parse().unwrap()).await;
+
+        validate_endpoint(&pool, &healthy).await.unwrap();
+        validate_endpoint(&pool, &expired).await.unwrap();
+        validate_endpoint(&pool, &expired).await.unwrap();
+
+        let report = get_validation_report(&pool).await.unwrap();
+
+        let healthy_status = report.iter().find(|s| s.endpoint_id == healthy.id).unwrap();
+        assert_eq!(healthy_status.success, Some(true));
+        assert!(healthy_status.last_success_at.is_some());
+
+        let expired_status = report.iter().find(|s| s.endpoint_id == expired.id).unwrap();
+        assert_eq!(expired_status.success, Some(false));
+        assert!(expired_status.credentials_expired);
+        assert!(expired_status.last_success_at.is_none());
+
+        let unchecked_status = report.iter().find(|s| s.endpoint_id == unchecked.id).unwrap();
+        assert!(unchecked_status.last_validated_at.is_none());
+        assert!(unchecked_status.success.is_none());
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_scheduler_stands_down_when_leadership_is_stale(pool: PgPool) {
+        let url = spawn_models_server(AxumStatusCode::OK).await;
+        create_endpoint(&pool, "fenced", url).await;
+
+        // A replica whose token has been superseded by a newer leader
+        let stale_fence = LeaderFence::new(pool.clone(), crate::leader::LEADER_LOCK_ID);
+        let current_fence = LeaderFence::new(pool.clone(), crate::leader::LEADER_LOCK_ID);
+        stale_fence.acquire(&mut pool.acquire().await.unwrap()).await.unwrap();
+        current_fence.acquire(&mut pool.acquire().await.unwrap()).await.unwrap();
+
+        let config = EndpointValidationConfig {
+            enabled: true,
+            interval: std::time::Duration::from_secs(3600),
+        };
+        let stale = EndpointValidationScheduler::new(pool.clone(), config.clone(), stale_fence);
+        stale.start().await;
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        assert_eq!(last_run_at(&pool).await.unwrap(), None);
+        stale.stop().await;
+
+        let current = EndpointValidationScheduler::new(pool.clone(), config, current_fence);
+        current.start().await;
+        let mut last = None;
+        for _ in 0..50 {
+            last = last_run_at(&pool).await.unwrap();
+            if last.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert!(last.is_some(), "current leader should validate endpoints");
+        current.stop().await;
+    }
+}
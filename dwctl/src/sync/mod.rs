@@ -1,3 +1,7 @@
 pub mod deployments;
+pub mod endpoint_compatibility;
 pub mod endpoint_sync;
+pub mod endpoint_validation;
 pub mod onwards_config;
+pub mod routing_changes;
+pub mod scheduled_sync;
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, num::NonZeroU32};
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroU32,
+};
 
 use onwards::target::{Auth, ConfigFile, KeyDefinition, RateLimitParameters, TargetSpec, Targets, WatchTargetsStream};
 use sqlx::{postgres::PgListener, PgPool};
@@ -8,11 +11,25 @@ use tracing::{debug, error, info, instrument};
 use url::Url;
 
 use crate::{
+    anthropic, azure,
     db::{
         handlers::{api_keys::ApiKeys, deployments::DeploymentFilter, Deployments, InferenceEndpoints, Repository as _},
-        models::{api_keys::ApiKeyDBResponse, deployments::DeploymentDBResponse},
+        models::{
+            api_keys::ApiKeyDBResponse,
+            deployments::{DeploymentDBResponse, DeploymentFallbackDBResponse, DeploymentTrafficSplitDBResponse},
+        },
     },
+    deprecation::Deprecation,
+    external_secrets,
+    header_rules::HeaderRules,
+    model_metadata::ModelMetadata,
+    redaction::RedactionRules,
+    request_logging::{pii::PiiCategory, policy::LoggingPolicy},
+    routing::{canary_alias, fallback_alias, shadow_alias, split_alias, RoutingTable},
+    sync::routing_changes::{RoutingChangeNotifier, RoutingSnapshot},
+    tls::EndpointTls,
     types::{DeploymentId, InferenceEndpointId},
+    upstream_limits::UpstreamLimit,
 };
 
 /// Manages the integration between onwards-pilot and the onwards proxy
@@ -20,18 +37,31 @@ use crate::{
 pub struct OnwardsConfigSync {
     db: PgPool,
     sender: watch::Sender<Targets>,
+    routing_sender: watch::Sender<RoutingTable>,
+    change_notifier: Option<RoutingChangeNotifier>,
     shutdown_token: CancellationToken,
 }
 
 impl OnwardsConfigSync {
     /// Creates a new OnwardsConfigSync and returns it along with initial targets, a WatchTargetsStream, and a drop guard for shutdown
-    #[instrument(skip(db))]
-    pub async fn new(db: PgPool) -> Result<(Self, Targets, WatchTargetsStream, DropGuard), anyhow::Error> {
+    ///
+    /// Routing table changes are sent through `change_notifier`, if given, including any made while no replica was running.
+    #[instrument(skip(db, change_notifier))]
+    pub async fn new(
+        db: PgPool,
+        change_notifier: Option<RoutingChangeNotifier>,
+    ) -> Result<(Self, Targets, WatchTargetsStream, DropGuard), anyhow::Error> {
         // Load initial configuration
-        let initial_targets = load_targets_from_db(&db).await?;
+        let (initial_targets, initial_routing, initial_snapshot) = load_targets_from_db(&db).await?;
+        if let Some(notifier) = &change_notifier {
+            if let Err(e) = notifier.record(&initial_snapshot).await {
+                error!("Failed to record routing table changes: {:#}", e);
+            }
+        }
 
-        // Create watch channel with initial state
+        // Create watch channels with initial state
         let (sender, receiver) = watch::channel(initial_targets.clone());
+        let (routing_sender, _) = watch::channel(initial_routing);
 
         // Create shutdown token and drop guard
         let shutdown_token = CancellationToken::new();
@@ -40,6 +70,8 @@ impl OnwardsConfigSync {
         let integration = Self {
             db,
             sender,
+            routing_sender,
+            change_notifier,
             shutdown_token,
         };
         let stream = WatchTargetsStream::new(receiver);
@@ -47,6 +79,11 @@ impl OnwardsConfigSync {
         Ok((integration, initial_targets, stream, drop_guard))
     }
 
+    /// Subscribes to the routing table for aliases served by more than one endpoint
+    pub fn routing_table(&self) -> watch::Receiver<RoutingTable> {
+        self.routing_sender.subscribe()
+    }
+
     /// Starts the background task that listens for database changes and updates the configuration
     #[instrument(skip(self))]
     pub async fn start(self) -> Result<(), anyhow::Error> {
@@ -61,6 +98,12 @@ impl OnwardsConfigSync {
         let mut last_reload_time = std::time::Instant::now();
         const MIN_RELOAD_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
 
+        // Endpoint API keys kept in a secret manager are fetched again periodically, so rotated
+        // credentials are picked up
+        let mut secret_refresh = tokio::time::interval(external_secrets::refresh_interval().unwrap_or(std::time::Duration::from_secs(300)));
+        secret_refresh.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        secret_refresh.tick().await;
+
         // Listen for notifications with graceful shutdown
         loop {
             tokio::select! {
@@ -70,6 +113,16 @@ impl OnwardsConfigSync {
                     break;
                 }
 
+                // Reload the targets if a referenced secret changed
+                _ = secret_refresh.tick() => {
+                    if external_secrets::refresh().await {
+                        info!("Endpoint secrets changed, reloading onwards configuration");
+                        if !self.reload().await? {
+                            break;
+                        }
+                    }
+                }
+
                 // Handle database notifications
                 notification_result = listener.recv() => {
                     match notification_result {
@@ -86,33 +139,8 @@ impl OnwardsConfigSync {
 
                             // Reload configuration from database
                             last_reload_time = std::time::Instant::now();
-                            match load_targets_from_db(&self.db).await {
-                                Ok(new_targets) => {
-                                    info!("Loaded {} targets from database", new_targets.targets.len());
-                                    for entry in new_targets.targets.iter() {
-                                        let alias = entry.key();
-                                        let target = entry.value();
-                                        debug!("Target '{}': {} keys configured", alias,
-                                              target.keys.as_ref().map(|k| k.len()).unwrap_or(0));
-                                    }
-
-                                    // Send update through watch channel
-                                    if let Err(e) = self.sender.send(new_targets) {
-                                        error!("Failed to send targets update: {}", e);
-                                        // If all receivers are dropped, we can exit
-                                        break;
-                                    }
-                                    info!("Updated onwards configuration successfully");
-                                }
-                                Err(e) => {
-                                    error!("Failed to load targets from database: {}", e);
-                                    // Return error if database operations fail consistently
-                                    if e.to_string().contains("closed pool") || e.to_string().contains("connection closed") {
-                                        error!("Database pool closed, exiting sync task");
-                                        return Err(e);
-                                    }
-                                    // Continue listening for other types of errors
-                                }
+                            if !self.reload().await? {
+                                break;
                             }
                         }
                         Err(e) => {
@@ -146,30 +174,147 @@ impl OnwardsConfigSync {
         info!("Onwards configuration listener stopped gracefully");
         Ok(())
     }
+    /// Reloads the targets and routing table from the database and publishes them. Returns
+    /// whether anyone is still listening for updates.
+    async fn reload(&self) -> Result<bool, anyhow::Error> {
+        match load_targets_from_db(&self.db).await {
+            Ok((new_targets, new_routing, new_snapshot)) => {
+                info!("Loaded {} targets from database", new_targets.targets.len());
+                for entry in new_targets.targets.iter() {
+                    let alias = entry.key();
+                    let target = entry.value();
+                    debug!(
+                        "Target '{}': {} keys configured",
+                        alias,
+                        target.keys.as_ref().map(|k| k.len()).unwrap_or(0)
+                    );
+                }
+
+                // Send update through watch channel
+                if let Err(e) = self.sender.send(new_targets) {
+                    error!("Failed to send targets update: {}", e);
+                    // If all receivers are dropped, we can exit
+                    return Ok(false);
+                }
+
+                // Publish the routing table after the targets it refers to
+                self.routing_sender.send_replace(new_routing);
+                info!("Updated onwards configuration successfully");
+
+                if let Some(notifier) = &self.change_notifier {
+                    if let Err(e) = notifier.record(&new_snapshot).await {
+                        error!("Failed to record routing table changes: {:#}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to load targets from database: {}", e);
+                // Return error if database operations fail consistently
+                if e.to_string().contains("closed pool") || e.to_string().contains("connection closed") {
+                    error!("Database pool closed, exiting sync task");
+                    return Err(e);
+                }
+                // Continue listening for other types of errors
+            }
+        }
+        Ok(true)
+    }
 }
 
-/// Loads the current targets configuration from the database
+/// Loads the current targets configuration and routing table from the database, along with a
+/// summary of the routing for change notifications
 #[tracing::instrument(skip(db))]
-async fn load_targets_from_db(db: &PgPool) -> Result<Targets, anyhow::Error> {
+async fn load_targets_from_db(db: &PgPool) -> Result<(Targets, RoutingTable, RoutingSnapshot), anyhow::Error> {
     debug!("Loading onwards targets from database");
 
     let mut tx = db.begin().await?;
     let models;
+    let mut routes;
+    let logging_policies;
+    let deprecations;
     {
         let mut deployments_repo = Deployments::new(&mut tx);
 
-        // Fetch all deployments
-        models = deployments_repo.list(&DeploymentFilter::new(0, i64::MAX)).await?;
+        // Fetch all deployments, skipping those that have been disabled or are on a disabled endpoint
+        models = deployments_repo
+            .list(&DeploymentFilter::new(0, i64::MAX).with_enabled(true))
+            .await?;
+
+        let deployment_ids: Vec<DeploymentId> = models.iter().map(|m| m.id).collect();
+        let canaries = deployments_repo
+            .get_canaries_bulk(&deployment_ids)
+            .await?
+            .into_iter()
+            .filter_map(|canary| {
+                let model = models.iter().find(|m| m.id == canary.deployment_id)?;
+                let route = CanaryRoute {
+                    endpoint_id: model.hosted_on,
+                    model_name: model.model_name.clone(),
+                    percent: canary.percent,
+                };
+                Some((canary.stable_deployment_id, route))
+            })
+            .collect();
+        let shadows = deployments_repo
+            .get_shadows_bulk(&deployment_ids)
+            .await?
+            .into_iter()
+            .filter_map(|shadow| {
+                let model = models.iter().find(|m| m.id == shadow.deployment_id)?;
+                let route = CanaryRoute {
+                    endpoint_id: model.hosted_on,
+                    model_name: model.model_name.clone(),
+                    percent: shadow.percent,
+                };
+                Some((shadow.source_deployment_id, route))
+            })
+            .collect();
+        routes = DeploymentRoutes {
+            fallbacks: deployments_repo.get_fallbacks_bulk(&deployment_ids).await?,
+            splits: deployments_repo.get_traffic_splits_bulk(&deployment_ids).await?,
+            canaries,
+            shadows,
+            open_circuits: deployments_repo.get_open_circuits_bulk(&deployment_ids).await?,
+        };
+        logging_policies = deployments_repo.get_logging_policies_bulk(&deployment_ids).await?;
+        deprecations = deployments_repo.get_deprecations_bulk(&deployment_ids).await?;
     }
 
     let endpoints;
+    let redaction_policies;
+    let header_rules;
+    let rate_limits;
     {
         let mut endpoints_repo = InferenceEndpoints::new(&mut tx);
-        // Fetch all endpoints to create a mapping
-        endpoints = endpoints_repo.get_bulk(models.iter().map(|m| m.hosted_on).collect()).await?;
+        // Fetch all endpoints (primary, fallback and weighted) to create a mapping
+        let endpoint_ids: Vec<InferenceEndpointId> = models
+            .iter()
+            .map(|m| m.hosted_on)
+            .chain(routes.fallbacks.values().flatten().map(|f| f.endpoint_id))
+            .chain(routes.splits.values().flatten().map(|s| s.endpoint_id))
+            .collect();
+        redaction_policies = endpoints_repo.get_redaction_policies_bulk(&endpoint_ids).await?;
+        header_rules = endpoints_repo.get_header_rules_bulk(&endpoint_ids).await?;
+        rate_limits = endpoints_repo.get_rate_limits_bulk(&endpoint_ids).await?;
+        endpoints = endpoints_repo.get_bulk(endpoint_ids).await?;
+    }
+    // Deployments on disabled endpoints were skipped above; so are fallbacks and weighted targets
+    for fallbacks in routes.fallbacks.values_mut() {
+        fallbacks.retain(|fallback| endpoints.get(&fallback.endpoint_id).is_none_or(|endpoint| endpoint.enabled));
+    }
+    for splits in routes.splits.values_mut() {
+        splits.retain(|split| endpoints.get(&split.endpoint_id).is_none_or(|endpoint| endpoint.enabled));
     }
     let endpoint_urls: HashMap<InferenceEndpointId, String> = endpoints.iter().map(|(k, v)| (*k, v.url.to_string())).collect();
-    let endpoint_api_keys: HashMap<InferenceEndpointId, Option<String>> = endpoints.iter().map(|(k, v)| (*k, v.api_key.clone())).collect();
+    let endpoint_tls: Vec<(String, EndpointTls)> = endpoints
+        .values()
+        .map(|endpoint| (endpoint.url.to_string(), EndpointTls::from_endpoint(endpoint)))
+        .collect();
+    // Keys kept in a secret manager are resolved once the transaction is done with
+    let stored_api_keys: Vec<(InferenceEndpointId, String, Option<String>)> = endpoints
+        .iter()
+        .map(|(id, endpoint)| (*id, endpoint.name.clone(), endpoint.api_key.clone()))
+        .collect();
     let endpoint_auth_header_names: HashMap<InferenceEndpointId, String> =
         endpoints.iter().map(|(k, v)| (*k, v.auth_header_name.clone())).collect();
     let endpoint_auth_header_prefixes: HashMap<InferenceEndpointId, String> =
@@ -195,8 +340,32 @@ async fn load_targets_from_db(db: &PgPool) -> Result<Targets, anyhow::Error> {
     tx.commit().await?;
     debug!("Loaded {} deployments from database", models.len());
 
+    // An endpoint whose key can't be resolved is served without one, and upstream will refuse its
+    // requests until a secret refresh fetches it
+    let mut endpoint_api_keys: HashMap<InferenceEndpointId, Option<String>> = HashMap::new();
+    for (id, name, api_key) in stored_api_keys {
+        let api_key = match api_key {
+            Some(api_key) => match external_secrets::resolve(&api_key).await {
+                Ok(api_key) => Some(api_key),
+                Err(e) => {
+                    error!("Failed to resolve the API key of endpoint {}: {:#}", name, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        endpoint_api_keys.insert(id, api_key);
+    }
+
+    let deployment_aliases: HashMap<DeploymentId, String> = models.iter().map(|m| (m.id, m.alias.clone())).collect();
+    let alias_endpoints: HashMap<String, InferenceEndpointId> = models.iter().map(|m| (m.alias.clone(), m.hosted_on)).collect();
+    let model_metadata: Vec<(String, ModelMetadata)> = models
+        .iter()
+        .filter_map(|m| Some((m.alias.clone(), ModelMetadata::from_deployment(m)?)))
+        .collect();
+
     // Convert to ConfigFile format
-    let config = convert_to_config_file(
+    let mut config = convert_to_config_file(
         models,
         &deployment_api_keys,
         &endpoint_urls,
@@ -204,9 +373,88 @@ async fn load_targets_from_db(db: &PgPool) -> Result<Targets, anyhow::Error> {
         &endpoint_auth_header_names,
         &endpoint_auth_header_prefixes,
     );
+    let mut routing = add_routing_targets(
+        &mut config,
+        &deployment_aliases,
+        &routes,
+        &endpoint_urls,
+        &endpoint_api_keys,
+        &endpoint_auth_header_names,
+        &endpoint_auth_header_prefixes,
+    );
+    for (target, version) in azure::take_api_versions(&mut config) {
+        routing.set_api_version(target, version);
+    }
+    for target in anthropic::anthropic_targets(&config) {
+        routing.set_anthropic(target);
+    }
+    for (url, tls) in endpoint_tls {
+        routing.set_endpoint_tls(url, tls);
+    }
+    for (alias, metadata) in model_metadata {
+        routing.set_model_metadata(alias, metadata);
+    }
+    if !redaction_policies.is_empty() || !header_rules.is_empty() || !rate_limits.is_empty() {
+        for (target, endpoint_id) in target_endpoints(&config, &alias_endpoints, &deployment_aliases, &routes) {
+            if let Some(policy) = redaction_policies.get(&endpoint_id) {
+                let entities = policy.entities.iter().filter_map(|entity| PiiCategory::parse(entity));
+                match RedactionRules::new(entities, &policy.patterns, policy.log_original) {
+                    Ok(rules) => routing.set_redaction(target.clone(), rules),
+                    Err(e) => error!(
+                        "Redaction policy of endpoint {} has an invalid pattern, skipping: {}",
+                        endpoint_id, e
+                    ),
+                }
+            }
+            if let Some(rules) = header_rules.get(&endpoint_id) {
+                let inject = rules
+                    .inject
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(name, value)| Some((name.as_str(), value.as_str()?)));
+                match HeaderRules::new(inject, rules.passthrough.as_deref()) {
+                    Ok(rules) => routing.set_header_rules(target.clone(), rules),
+                    Err(e) => error!("Header rules of endpoint {} are invalid, skipping: {}", endpoint_id, e),
+                }
+            }
+            let limit = rate_limits
+                .get(&endpoint_id)
+                .and_then(|limits| UpstreamLimit::from_config(limits.requests_per_second, limits.burst_size, limits.max_concurrent));
+            if let Some(limit) = limit {
+                routing.set_upstream_limit(target, endpoint_id, limit);
+            }
+        }
+    }
+
+    for (deployment_id, policy) in logging_policies {
+        let Some(alias) = deployment_aliases.get(&deployment_id) else {
+            continue;
+        };
+        routing.set_body_sample_percent(alias.clone(), policy.body_sample_percent.clamp(0, 100) as u32);
+        if let Some(policy) = LoggingPolicy::new(&policy.mode, &policy.redacted_fields) {
+            routing.set_logging_policy(alias.clone(), policy);
+        }
+    }
+
+    for deprecation in deprecations {
+        let Some(alias) = deployment_aliases.get(&deprecation.deployment_id) else {
+            continue;
+        };
+        routing.set_deprecation(
+            alias.clone(),
+            Deprecation {
+                deprecated_at: deprecation.deprecated_at,
+                sunset_at: deprecation.sunset_at,
+                replacement: deprecation.replacement_alias,
+            },
+        );
+    }
+
+    let snapshot = RoutingSnapshot::new(&config, &routing);
 
     // Convert ConfigFile to Targets
-    Targets::from_config(config)
+    Ok((Targets::from_config(config)?, routing, snapshot))
 }
 
 /// Converts database models to the ConfigFile format expected by onwards
@@ -280,9 +528,14 @@ fn convert_to_config_file(
     let targets = models
         .into_iter()
         .filter_map(|model| {
-            // Get API keys for this deployment
+            // Get API keys for this deployment. Targets without keys are public, so a deployment
+            // whose keys couldn't be loaded is left accessible to no key, rather than to everyone.
             let api_keys = deployment_api_keys.get(&model.id);
-            let keys = api_keys.map(|keys| keys.iter().map(|k| k.secret.clone().into()).collect());
+            let keys = Some(
+                api_keys
+                    .map(|keys| keys.iter().map(|k| k.secret.clone().into()).collect())
+                    .unwrap_or_default(),
+            );
 
             // Determine the URL for this model
             let url = match endpoint_urls.get(&model.hosted_on) {
@@ -304,6 +557,12 @@ fn convert_to_config_file(
                     return None;
                 }
             };
+            // Azure OpenAI addresses models by deployment name in the path
+            let url = if azure::is_azure_url(&url) {
+                azure::deployment_url(&url, &model.model_name)
+            } else {
+                url
+            };
 
             // Get the API key for this endpoint (for downstream authentication)
             let endpoint_api_key = endpoint_api_keys.get(&model.hosted_on).and_then(|k| k.as_ref());
@@ -354,16 +613,213 @@ fn convert_to_config_file(
     ConfigFile { targets, auth }
 }
 
+/// Fallbacks and weighted targets configured for each deployment
+#[derive(Debug, Default)]
+struct DeploymentRoutes {
+    fallbacks: HashMap<DeploymentId, Vec<DeploymentFallbackDBResponse>>,
+    splits: HashMap<DeploymentId, Vec<DeploymentTrafficSplitDBResponse>>,
+    /// Canary of each stable deployment
+    canaries: HashMap<DeploymentId, CanaryRoute>,
+    /// Shadow of each source deployment
+    shadows: HashMap<DeploymentId, CanaryRoute>,
+    /// Deployments taken out of rotation after repeated probe failures
+    open_circuits: HashSet<DeploymentId>,
+}
+
+/// Where a canary deployment serves its share of a stable deployment's traffic, or a shadow
+/// deployment serves the copies of a source deployment's traffic it receives
+#[derive(Debug, Clone)]
+struct CanaryRoute {
+    endpoint_id: InferenceEndpointId,
+    model_name: String,
+    percent: i32,
+}
+
+/// Adds an internal target for every fallback, weighted target, canary and shadow of every
+/// deployment, and returns the routing table that points each alias at them.
+///
+/// Internal targets inherit the keys and rate limit of the alias' primary target (so anyone who
+/// can use an alias reaches its canary, whatever the canary deployment's own groups), and a
+/// deployment whose primary target was skipped gets no fallbacks, split, canary or shadow either.
+#[tracing::instrument(skip_all)]
+fn add_routing_targets(
+    config: &mut ConfigFile,
+    deployment_aliases: &HashMap<DeploymentId, String>,
+    routes: &DeploymentRoutes,
+    endpoint_urls: &HashMap<InferenceEndpointId, String>,
+    endpoint_api_keys: &HashMap<InferenceEndpointId, Option<String>>,
+    endpoint_auth_header_names: &HashMap<InferenceEndpointId, String>,
+    endpoint_auth_header_prefixes: &HashMap<InferenceEndpointId, String>,
+) -> RoutingTable {
+    let mut routing = RoutingTable::default();
+
+    // Build the target serving `model_name` on `endpoint_id` in place of the primary target
+    let internal_target = |primary: &TargetSpec, endpoint_id: &InferenceEndpointId, model_name: &str| {
+        let mut url = endpoint_urls.get(endpoint_id).and_then(|url| Url::parse(url).ok())?;
+        if azure::is_azure_url(&url) {
+            url = azure::deployment_url(&url, model_name);
+        }
+        Some(TargetSpec {
+            url,
+            onwards_key: endpoint_api_keys.get(endpoint_id).cloned().flatten(),
+            onwards_model: Some(model_name.to_string()),
+            upstream_auth_header_name: endpoint_auth_header_names
+                .get(endpoint_id)
+                .filter(|name| *name != "Authorization")
+                .cloned(),
+            upstream_auth_header_prefix: endpoint_auth_header_prefixes
+                .get(endpoint_id)
+                .filter(|prefix| *prefix != "Bearer ")
+                .cloned(),
+            ..primary.clone()
+        })
+    };
+
+    for (deployment_id, deployment_fallbacks) in &routes.fallbacks {
+        let Some(alias) = deployment_aliases.get(deployment_id) else {
+            continue;
+        };
+        let Some(primary) = config.targets.get(alias).cloned() else {
+            continue;
+        };
+
+        let mut chain = Vec::with_capacity(deployment_fallbacks.len());
+        for fallback in deployment_fallbacks {
+            let Some(target_spec) = internal_target(&primary, &fallback.endpoint_id, &fallback.model_name) else {
+                error!(
+                    "Fallback {} of '{}' references a missing or invalid endpoint {}, skipping",
+                    fallback.priority, alias, fallback.endpoint_id
+                );
+                continue;
+            };
+
+            let target_alias = fallback_alias(alias, fallback.priority);
+            config.targets.insert(target_alias.clone(), target_spec);
+            chain.push(target_alias);
+        }
+
+        debug!("Alias '{}' configured with {} fallback(s)", alias, chain.len());
+        routing.set_fallbacks(alias.clone(), chain);
+    }
+
+    for (deployment_id, deployment_splits) in &routes.splits {
+        let Some(alias) = deployment_aliases.get(deployment_id) else {
+            continue;
+        };
+        let Some(primary) = config.targets.get(alias).cloned() else {
+            continue;
+        };
+
+        let mut targets = Vec::with_capacity(deployment_splits.len());
+        for split in deployment_splits {
+            let Some(target_spec) = internal_target(&primary, &split.endpoint_id, &split.model_name) else {
+                error!(
+                    "Weighted target {} of '{}' references a missing or invalid endpoint {}, skipping",
+                    split.position, alias, split.endpoint_id
+                );
+                continue;
+            };
+
+            let target_alias = split_alias(alias, split.position);
+            config.targets.insert(target_alias.clone(), target_spec);
+            targets.push((target_alias, u32::try_from(split.weight).unwrap_or(0)));
+        }
+
+        debug!("Alias '{}' split across {} weighted target(s)", alias, targets.len());
+        routing.set_split(alias.clone(), targets);
+    }
+
+    for (deployment_id, canary) in &routes.canaries {
+        let Some(alias) = deployment_aliases.get(deployment_id) else {
+            continue;
+        };
+        let Some(primary) = config.targets.get(alias).cloned() else {
+            continue;
+        };
+        let Some(target_spec) = internal_target(&primary, &canary.endpoint_id, &canary.model_name) else {
+            error!(
+                "Canary of '{}' references a missing or invalid endpoint {}, skipping",
+                alias, canary.endpoint_id
+            );
+            continue;
+        };
+
+        let target_alias = canary_alias(alias);
+        config.targets.insert(target_alias.clone(), target_spec);
+        debug!("Alias '{}' sends {}% of traffic to its canary", alias, canary.percent);
+        routing.set_canary(alias.clone(), target_alias, u32::try_from(canary.percent).unwrap_or(0));
+    }
+
+    for (deployment_id, shadow) in &routes.shadows {
+        let Some(alias) = deployment_aliases.get(deployment_id) else {
+            continue;
+        };
+        let Some(primary) = config.targets.get(alias).cloned() else {
+            continue;
+        };
+        let Some(target_spec) = internal_target(&primary, &shadow.endpoint_id, &shadow.model_name) else {
+            error!(
+                "Shadow of '{}' references a missing or invalid endpoint {}, skipping",
+                alias, shadow.endpoint_id
+            );
+            continue;
+        };
+
+        let target_alias = shadow_alias(alias);
+        config.targets.insert(target_alias.clone(), target_spec);
+        debug!("Alias '{}' mirrors {}% of traffic to its shadow", alias, shadow.percent);
+        routing.set_shadow(alias.clone(), target_alias, u32::try_from(shadow.percent).unwrap_or(0));
+    }
+
+    for deployment_id in &routes.open_circuits {
+        let Some(alias) = deployment_aliases.get(deployment_id) else {
+            continue;
+        };
+        debug!("Alias '{}' has an open circuit breaker", alias);
+        routing.open_circuit(alias.clone());
+    }
+
+    routing
+}
+
+/// The endpoint serving each target in `config`: aliases are served by the endpoint of their
+/// deployment, and internal targets by that of their fallback, weighted target, canary or shadow
+fn target_endpoints(
+    config: &ConfigFile,
+    alias_endpoints: &HashMap<String, InferenceEndpointId>,
+    deployment_aliases: &HashMap<DeploymentId, String>,
+    routes: &DeploymentRoutes,
+) -> Vec<(String, InferenceEndpointId)> {
+    let mut targets: Vec<(String, InferenceEndpointId)> = alias_endpoints.iter().map(|(alias, id)| (alias.clone(), *id)).collect();
+    for (deployment_id, alias) in deployment_aliases {
+        for fallback in routes.fallbacks.get(deployment_id).into_iter().flatten() {
+            targets.push((fallback_alias(alias, fallback.priority), fallback.endpoint_id));
+        }
+        for split in routes.splits.get(deployment_id).into_iter().flatten() {
+            targets.push((split_alias(alias, split.position), split.endpoint_id));
+        }
+        if let Some(canary) = routes.canaries.get(deployment_id) {
+            targets.push((canary_alias(alias), canary.endpoint_id));
+        }
+        if let Some(shadow) = routes.shadows.get(deployment_id) {
+            targets.push((shadow_alias(alias), shadow.endpoint_id));
+        }
+    }
+    targets.retain(|(target, _)| config.targets.contains_key(target));
+    targets
+}
+
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
 
     use chrono::Utc;
     use uuid::Uuid;
 
     use crate::{
-        db::models::deployments::{DeploymentDBResponse, ModelStatus},
-        sync::onwards_config::convert_to_config_file,
+        azure,
+        db::models::deployments::{DeploymentDBResponse, DeploymentFallbackDBResponse, DeploymentTrafficSplitDBResponse, ModelStatus},
+        sync::onwards_config::{add_routing_targets, convert_to_config_file, target_endpoints, CanaryRoute, DeploymentRoutes},
     };
 
     // Helper function to create a test deployed model
@@ -380,11 +836,16 @@ mod tests {
             status: ModelStatus::Active,
             last_sync: None,
             deleted: false,
+            enabled: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
             requests_per_second: None,
             burst_size: None,
             pricing: None,
+            max_context_tokens: None,
+            supports_tools: None,
+            supports_vision: None,
+            tags: Vec::new(),
         }
     }
 
@@ -520,4 +981,380 @@ mod tests {
         assert!(config.targets.contains_key("valid-alias"));
         assert!(!config.targets.contains_key("invalid-alias"));
     }
+
+    #[test]
+    fn test_add_fallback_targets() {
+        let primary_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap();
+        let fallback_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000002").unwrap();
+        let missing_endpoint = Uuid::parse_str("99999999-9999-9999-9999-999999999999").unwrap();
+
+        let mut model = create_test_model("gpt-4", "gpt4-alias", primary_endpoint);
+        model.requests_per_second = Some(10.0);
+        let deployment_aliases = HashMap::from([(model.id, model.alias.clone())]);
+
+        let endpoint_urls = HashMap::from([
+            (primary_endpoint, "https://api.openai.com".to_string()),
+            (fallback_endpoint, "https://backup.example.com/v1".to_string()),
+        ]);
+        let endpoint_api_keys = HashMap::from([(fallback_endpoint, Some("sk-backup".to_string()))]);
+        let endpoint_auth_header_names = HashMap::from([(fallback_endpoint, "X-Api-Key".to_string())]);
+        let endpoint_auth_header_prefixes = HashMap::from([(fallback_endpoint, "Bearer ".to_string())]);
+
+        let fallback = |priority, endpoint_id, model_name: &str| DeploymentFallbackDBResponse {
+            deployment_id: model.id,
+            priority,
+            endpoint_id,
+            model_name: model_name.to_string(),
+        };
+        let routes = DeploymentRoutes {
+            fallbacks: HashMap::from([(
+                model.id,
+                vec![
+                    fallback(1, missing_endpoint, "ignored"),
+                    fallback(2, fallback_endpoint, "gpt-4-backup"),
+                ],
+            )]),
+            ..Default::default()
+        };
+
+        let mut config = convert_to_config_file(
+            vec![model],
+            &HashMap::new(),
+            &endpoint_urls,
+            &endpoint_api_keys,
+            &endpoint_auth_header_names,
+            &endpoint_auth_header_prefixes,
+        );
+        let routing = add_routing_targets(
+            &mut config,
+            &deployment_aliases,
+            &routes,
+            &endpoint_urls,
+            &endpoint_api_keys,
+            &endpoint_auth_header_names,
+            &endpoint_auth_header_prefixes,
+        );
+
+        // The fallback with a missing endpoint is skipped
+        assert_eq!(config.targets.len(), 2);
+        assert_eq!(routing.fallbacks("gpt4-alias"), ["gpt4-alias::fallback-2".to_string()]);
+        assert!(routing.is_internal("gpt4-alias::fallback-2"));
+
+        let target = &config.targets["gpt4-alias::fallback-2"];
+        assert_eq!(target.url.as_str(), "https://backup.example.com/v1");
+        assert_eq!(target.onwards_model, Some("gpt-4-backup".to_string()));
+        assert_eq!(target.onwards_key, Some("sk-backup".to_string()));
+        assert_eq!(target.upstream_auth_header_name, Some("X-Api-Key".to_string()));
+        assert_eq!(target.upstream_auth_header_prefix, None);
+        // Rate limits are inherited from the primary target
+        assert!(target.rate_limit.is_some());
+    }
+
+    #[test]
+    fn test_add_split_targets() {
+        let primary_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap();
+        let other_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000002").unwrap();
+
+        let model = create_test_model("llama", "llama-alias", primary_endpoint);
+        let deployment_aliases = HashMap::from([(model.id, model.alias.clone())]);
+        let endpoint_urls = HashMap::from([
+            (primary_endpoint, "https://cluster-a.example.com/v1".to_string()),
+            (other_endpoint, "https://cluster-b.example.com/v1".to_string()),
+        ]);
+
+        let split = |position, endpoint_id, model_name: &str, weight| DeploymentTrafficSplitDBResponse {
+            deployment_id: model.id,
+            position,
+            endpoint_id,
+            model_name: model_name.to_string(),
+            weight,
+        };
+        let routes = DeploymentRoutes {
+            splits: HashMap::from([(
+                model.id,
+                vec![split(1, primary_endpoint, "llama", 80), split(2, other_endpoint, "llama-b", 20)],
+            )]),
+            ..Default::default()
+        };
+
+        let mut config = convert_to_config_file(
+            vec![model],
+            &HashMap::new(),
+            &endpoint_urls,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        let routing = add_routing_targets(
+            &mut config,
+            &deployment_aliases,
+            &routes,
+            &endpoint_urls,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+
+        assert_eq!(config.targets.len(), 3);
+        assert!(routing.is_internal("llama-alias::split-1"));
+        assert!(routing.is_internal("llama-alias::split-2"));
+        assert!(routing.fallbacks("llama-alias").is_empty());
+
+        let target = &config.targets["llama-alias::split-2"];
+        assert_eq!(target.url.as_str(), "https://cluster-b.example.com/v1");
+        assert_eq!(target.onwards_model, Some("llama-b".to_string()));
+    }
+
+    #[test]
+    fn test_add_canary_target() {
+        let stable_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap();
+        let canary_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000002").unwrap();
+
+        let model = create_test_model("llama", "llama-alias", stable_endpoint);
+        let deployment_aliases = HashMap::from([(model.id, model.alias.clone())]);
+        let endpoint_urls = HashMap::from([
+            (stable_endpoint, "https://stable.example.com/v1".to_string()),
+            (canary_endpoint, "https://canary.example.com/v1".to_string()),
+        ]);
+        let routes = DeploymentRoutes {
+            canaries: HashMap::from([(
+                model.id,
+                CanaryRoute {
+                    endpoint_id: canary_endpoint,
+                    model_name: "llama-next".to_string(),
+                    percent: 5,
+                },
+            )]),
+            ..Default::default()
+        };
+
+        let mut config = convert_to_config_file(
+            vec![model],
+            &HashMap::new(),
+            &endpoint_urls,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        let routing = add_routing_targets(
+            &mut config,
+            &deployment_aliases,
+            &routes,
+            &endpoint_urls,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+
+        assert_eq!(config.targets.len(), 2);
+        assert_eq!(routing.canary("llama-alias"), Some(("llama-alias::canary", 5)));
+        assert!(routing.is_internal("llama-alias::canary"));
+
+        let target = &config.targets["llama-alias::canary"];
+        assert_eq!(target.url.as_str(), "https://canary.example.com/v1");
+        assert_eq!(target.onwards_model, Some("llama-next".to_string()));
+    }
+
+    #[test]
+    fn test_add_shadow_target() {
+        let source_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap();
+        let shadow_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000002").unwrap();
+
+        let model = create_test_model("llama", "llama-alias", source_endpoint);
+        let deployment_aliases = HashMap::from([(model.id, model.alias.clone())]);
+        let endpoint_urls = HashMap::from([
+            (source_endpoint, "https://source.example.com/v1".to_string()),
+            (shadow_endpoint, "https://shadow.example.com/v1".to_string()),
+        ]);
+        let routes = DeploymentRoutes {
+            shadows: HashMap::from([(
+                model.id,
+                CanaryRoute {
+                    endpoint_id: shadow_endpoint,
+                    model_name: "llama-next".to_string(),
+                    percent: 20,
+                },
+            )]),
+            ..Default::default()
+        };
+
+        let mut config = convert_to_config_file(
+            vec![model],
+            &HashMap::new(),
+            &endpoint_urls,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        let routing = add_routing_targets(
+            &mut config,
+            &deployment_aliases,
+            &routes,
+            &endpoint_urls,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+
+        assert_eq!(config.targets.len(), 2);
+        assert_eq!(routing.shadow("llama-alias"), Some(("llama-alias::shadow", 20)));
+        assert!(routing.is_internal("llama-alias::shadow"));
+        assert!(routing.canary("llama-alias").is_none());
+
+        let target = &config.targets["llama-alias::shadow"];
+        assert_eq!(target.url.as_str(), "https://shadow.example.com/v1");
+        assert_eq!(target.onwards_model, Some("llama-next".to_string()));
+    }
+
+    #[test]
+    fn test_target_endpoints() {
+        let primary_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap();
+        let external_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000002").unwrap();
+
+        let model = create_test_model("llama", "llama-alias", primary_endpoint);
+        let deployment_aliases = HashMap::from([(model.id, model.alias.clone())]);
+        let alias_endpoints = HashMap::from([(model.alias.clone(), primary_endpoint)]);
+        let endpoint_urls = HashMap::from([
+            (primary_endpoint, "https://local.example.com/v1".to_string()),
+            (external_endpoint, "https://api.provider.com/v1".to_string()),
+        ]);
+        let routes = DeploymentRoutes {
+            fallbacks: HashMap::from([(
+                model.id,
+                vec![DeploymentFallbackDBResponse {
+                    deployment_id: model.id,
+                    priority: 1,
+                    endpoint_id: external_endpoint,
+                    model_name: "llama-hosted".to_string(),
+                }],
+            )]),
+            ..Default::default()
+        };
+
+        let mut config = convert_to_config_file(
+            vec![model],
+            &HashMap::new(),
+            &endpoint_urls,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        add_routing_targets(
+            &mut config,
+            &deployment_aliases,
+            &routes,
+            &endpoint_urls,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+
+        let mut targets = target_endpoints(&config, &alias_endpoints, &deployment_aliases, &routes);
+        targets.sort();
+        assert_eq!(
+            targets,
+            vec![
+                ("llama-alias".to_string(), primary_endpoint),
+                ("llama-alias::fallback-1".to_string(), external_endpoint),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_open_circuits_are_added_by_alias() {
+        let endpoint_id = Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap();
+        let broken = create_test_model("llama", "llama-alias", endpoint_id);
+        let healthy = create_test_model("gpt", "gpt-alias", endpoint_id);
+        let deployment_aliases = HashMap::from([(broken.id, broken.alias.clone()), (healthy.id, healthy.alias.clone())]);
+        let routes = DeploymentRoutes {
+            open_circuits: HashSet::from([broken.id, Uuid::new_v4()]),
+            ..Default::default()
+        };
+
+        let mut config = convert_to_config_file(
+            vec![broken, healthy],
+            &HashMap::new(),
+            &HashMap::from([(endpoint_id, "https://api.example.com/v1".to_string())]),
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        let routing = add_routing_targets(
+            &mut config,
+            &deployment_aliases,
+            &routes,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+
+        // The alias keeps its target, for probes
+        assert_eq!(config.targets.len(), 2);
+        assert!(routing.is_circuit_open("llama-alias"));
+        assert!(!routing.is_circuit_open("gpt-alias"));
+        assert!(!routing.is_empty());
+    }
+
+    #[test]
+    fn test_azure_targets_point_at_their_deployment() {
+        let azure_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap();
+        let openai_endpoint = Uuid::parse_str("00000000-0000-0000-0000-000000000002").unwrap();
+        let model = create_test_model("gpt-4o-prod", "gpt4o-alias", azure_endpoint);
+        let deployment_aliases = HashMap::from([(model.id, model.alias.clone())]);
+        let routes = DeploymentRoutes {
+            fallbacks: HashMap::from([(
+                model.id,
+                vec![DeploymentFallbackDBResponse {
+                    deployment_id: model.id,
+                    priority: 1,
+                    endpoint_id: azure_endpoint,
+                    model_name: "gpt-4o-backup".to_string(),
+                }],
+            )]),
+            ..Default::default()
+        };
+        let endpoint_urls = HashMap::from([
+            (
+                azure_endpoint,
+                "https://my-resource.openai.azure.com/?api-version=2025-01-01-preview".to_string(),
+            ),
+            (openai_endpoint, "https://api.openai.com/v1".to_string()),
+        ]);
+
+        let mut config = convert_to_config_file(
+            vec![model, create_test_model("gpt-4o", "openai-alias", openai_endpoint)],
+            &HashMap::new(),
+            &endpoint_urls,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        let mut routing = add_routing_targets(
+            &mut config,
+            &deployment_aliases,
+            &routes,
+            &endpoint_urls,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        for (target, version) in azure::take_api_versions(&mut config) {
+            routing.set_api_version(target, version);
+        }
+
+        assert_eq!(
+            config.targets["gpt4o-alias"].url.as_str(),
+            "https://my-resource.openai.azure.com/openai/deployments/gpt-4o-prod"
+        );
+        assert_eq!(
+            config.targets["gpt4o-alias::fallback-1"].url.as_str(),
+            "https://my-resource.openai.azure.com/openai/deployments/gpt-4o-backup"
+        );
+        assert_eq!(config.targets["openai-alias"].url.as_str(), "https://api.openai.com/v1");
+
+        assert_eq!(routing.api_version("gpt4o-alias"), Some("2025-01-01-preview"));
+        assert_eq!(routing.api_version("gpt4o-alias::fallback-1"), Some("2025-01-01-preview"));
+        assert_eq!(routing.api_version("openai-alias"), None);
+    }
 }
@@ -0,0 +1,390 @@
+//! Change notifications for the routing table.
+//!
+//! Whenever the onwards configuration is reloaded, the public routing table (which endpoint and
+//! model serve each alias, along with its fallbacks and weighted targets) is compared with the
+//! last one recorded in `routing_table_snapshot`. If it changed, the diff is POSTed to the
+//! configured webhook, giving platform teams an external record of what traffic moved where.
+//!
+//! Every replica reloads its configuration on the same database notifications, so the
+//! comparison happens under a row lock: the first replica to see a change records it and sends
+//! the notification, and the others find nothing left to report.
+
+use crate::config::RoutingChangeWebhookConfig;
+use crate::routing::RoutingTable;
+use chrono::{DateTime, Utc};
+use onwards::target::{ConfigFile, TargetSpec};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use url::Url;
+
+/// Upper bound on the delay between delivery attempts for a single notification
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Where a request is sent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteTarget {
+    pub url: String,
+    pub model: Option<String>,
+    /// Share of the alias' traffic, for weighted targets (a percentage, for canaries and shadows)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u32>,
+}
+
+/// How requests to a single alias are routed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AliasRoute {
+    /// The alias' own target
+    pub target: RouteTarget,
+    /// Targets tried in order when a request fails
+    #[serde(default)]
+    pub fallbacks: Vec<RouteTarget>,
+    /// Targets traffic is split across instead of the alias' own target
+    #[serde(default)]
+    pub split: Vec<RouteTarget>,
+    /// Canary taking a percentage of the alias' traffic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canary: Option<RouteTarget>,
+    /// Shadow receiving copies of a percentage of the alias' traffic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow: Option<RouteTarget>,
+}
+
+/// The routing of every public alias
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RoutingSnapshot(BTreeMap<String, AliasRoute>);
+
+impl RoutingSnapshot {
+    /// Summarize the targets of an onwards configuration, hiding the internal targets behind
+    /// fallbacks, splits, canaries and shadows inside the alias they serve
+    pub fn new(config: &ConfigFile, routing: &RoutingTable) -> Self {
+        let target = |alias: &str, weight: Option<u32>| {
+            config.targets.get(alias).map(|spec: &TargetSpec| RouteTarget {
+                url: spec.url.to_string(),
+                model: spec.onwards_model.clone(),
+                weight,
+            })
+        };
+
+        let routes = config
+            .targets
+            .keys()
+            .filter(|alias| !routing.is_internal(alias))
+            .filter_map(|alias| {
+                let route = AliasRoute {
+                    target: target(alias, None)?,
+                    fallbacks: routing.fallbacks(alias).iter().filter_map(|t| target(t, None)).collect(),
+                    split: routing
+                        .split(alias)
+                        .iter()
+                        .filter_map(|(t, weight)| target(t, Some(*weight)))
+                        .collect(),
+                    canary: routing.canary(alias).and_then(|(t, percent)| target(t, Some(percent))),
+                    shadow: routing.shadow(alias).and_then(|(t, percent)| target(t, Some(percent))),
+                };
+                Some((alias.clone(), route))
+            })
+            .collect();
+
+        Self(routes)
+    }
+}
+
+/// Routing of an alias before and after a change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteChange {
+    pub before: AliasRoute,
+    pub after: AliasRoute,
+}
+
+/// Differences between two routing tables
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoutingDiff {
+    pub added: BTreeMap<String, AliasRoute>,
+    pub removed: BTreeMap<String, AliasRoute>,
+    pub changed: BTreeMap<String, RouteChange>,
+}
+
+impl RoutingDiff {
+    pub fn between(before: &RoutingSnapshot, after: &RoutingSnapshot) -> Self {
+        let mut diff = Self::default();
+        for (alias, route) in &after.0 {
+            match before.0.get(alias) {
+                None => {
+                    diff.added.insert(alias.clone(), route.clone());
+                }
+                Some(previous) if previous != route => {
+                    diff.changed.insert(
+                        alias.clone(),
+                        RouteChange {
+                            before: previous.clone(),
+                            after: route.clone(),
+                        },
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+        for (alias, route) in &before.0 {
+            if !after.0.contains_key(alias) {
+                diff.removed.insert(alias.clone(), route.clone());
+            }
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Body POSTed to the webhook
+#[derive(Debug, Serialize)]
+struct RoutingChangeNotification<'a> {
+    event: &'static str,
+    changed_at: DateTime<Utc>,
+    /// When the routing table the diff is against was recorded
+    previous_recorded_at: DateTime<Utc>,
+    diff: &'a RoutingDiff,
+}
+
+/// Records routing table changes and sends them to the configured webhook
+#[derive(Debug, Clone)]
+pub struct RoutingChangeNotifier {
+    pool: PgPool,
+    client: reqwest::Client,
+    url: Url,
+    authorization: Option<String>,
+    max_retries: u32,
+    request_timeout: Duration,
+}
+
+impl RoutingChangeNotifier {
+    /// Build a notifier, or `None` if change notifications are disabled
+    pub fn new(pool: PgPool, config: &RoutingChangeWebhookConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        Some(Self {
+            pool,
+            client: reqwest::Client::new(),
+            url: config.url.clone()?,
+            authorization: config.authorization.clone(),
+            max_retries: config.max_retries,
+            request_timeout: config.request_timeout,
+        })
+    }
+
+    /// Compare `snapshot` with the last recorded routing table and, if it changed, record it and
+    /// send the diff in the background. Returns the diff that was sent, if any.
+    pub async fn record(&self, snapshot: &RoutingSnapshot) -> anyhow::Result<Option<RoutingDiff>> {
+        let mut tx = self.pool.begin().await?;
+        let previous = sqlx::query!(
+            r#"SELECT snapshot as "snapshot: Json<RoutingSnapshot>", recorded_at FROM routing_table_snapshot WHERE id FOR UPDATE"#
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let diff = RoutingDiff::between(&previous.snapshot, snapshot);
+        if diff.is_empty() {
+            return Ok(None);
+        }
+
+        let changed_at = sqlx::query_scalar!(
+            "UPDATE routing_table_snapshot SET snapshot = $1, recorded_at = NOW() WHERE id RETURNING recorded_at",
+            Json(snapshot) as _
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        info!(
+            added = diff.added.len(),
+            removed = diff.removed.len(),
+            changed = diff.changed.len(),
+            "Routing table changed, sending change notification"
+        );
+        let notifier = self.clone();
+        let sent = diff.clone();
+        let previous_recorded_at = previous.recorded_at;
+        tokio::spawn(async move {
+            notifier
+                .deliver(&RoutingChangeNotification {
+                    event: "routing_table.changed",
+                    changed_at,
+                    previous_recorded_at,
+                    diff: &sent,
+                })
+                .await
+        });
+
+        Ok(Some(diff))
+    }
+
+    /// Send a notification, retrying with exponential backoff. Gives up after `max_retries` retries.
+    async fn deliver(&self, notification: &RoutingChangeNotification<'_>) {
+        let mut delay = Duration::from_millis(500);
+
+        for attempt in 0..=self.max_retries {
+            match self.send(notification).await {
+                Ok(()) => return,
+                Err(e) if attempt < self.max_retries => {
+                    warn!(
+                        attempt = attempt + 1,
+                        "Failed to send routing change notification, retrying in {}ms: {:#}",
+                        delay.as_millis(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                }
+                Err(e) => {
+                    error!("Giving up on sending routing change notification: {:#}", e);
+                }
+            }
+        }
+    }
+
+    async fn send(&self, notification: &RoutingChangeNotification<'_>) -> anyhow::Result<()> {
+        let mut request = self.client.post(self.url.clone()).timeout(self.request_timeout).json(notification);
+        if let Some(authorization) = &self.authorization {
+            request = request.header(reqwest::header::AUTHORIZATION, authorization);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            anyhow::bail!("routing change webhook returned {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::{fallback_alias, split_alias};
+    use axum::{extract::State, http::StatusCode, routing::post, Router};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    type Received = Arc<Mutex<Vec<serde_json::Value>>>;
+
+    async fn spawn_webhook(received: Received) -> Url {
+        async fn ingest(State(received): State<Received>, axum::Json(body): axum::Json<serde_json::Value>) -> StatusCode {
+            received.lock().unwrap().push(body);
+            StatusCode::OK
+        }
+        let app = Router::new().route("/routing", post(ingest)).with_state(received);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}/routing").parse().unwrap()
+    }
+
+    fn target_spec(url: &str, model: &str) -> TargetSpec {
+        TargetSpec {
+            url: url.parse().unwrap(),
+            keys: None,
+            onwards_key: Some("sk-secret".to_string()),
+            onwards_model: Some(model.to_string()),
+            rate_limit: None,
+            upstream_auth_header_name: None,
+            upstream_auth_header_prefix: None,
+        }
+    }
+
+    /// `chat` served by endpoint A with a fallback on B and a split across A and B
+    fn snapshot(split_weight: u32, with_embeddings: bool) -> RoutingSnapshot {
+        let mut targets = HashMap::from([
+            ("chat".to_string(), target_spec("https://a.example.com/v1", "llama")),
+            (fallback_alias("chat", 1), target_spec("https://b.example.com/v1", "llama-b")),
+            (split_alias("chat", 0), target_spec("https://a.example.com/v1", "llama")),
+            (split_alias("chat", 1), target_spec("https://b.example.com/v1", "llama-b")),
+        ]);
+        if with_embeddings {
+            targets.insert("embed".to_string(), target_spec("https://a.example.com/v1", "bge"));
+        }
+
+        let mut routing = RoutingTable::default();
+        routing.set_fallbacks("chat".to_string(), vec![fallback_alias("chat", 1)]);
+        routing.set_split(
+            "chat".to_string(),
+            vec![(split_alias("chat", 0), 1), (split_alias("chat", 1), split_weight)],
+        );
+
+        RoutingSnapshot::new(&ConfigFile { targets, auth: None }, &routing)
+    }
+
+    #[test]
+    fn test_snapshot_hides_internal_targets() {
+        let snapshot = snapshot(1, true);
+        assert_eq!(snapshot.0.keys().collect::<Vec<_>>(), ["chat", "embed"]);
+
+        let chat = &snapshot.0["chat"];
+        assert_eq!(chat.target.model.as_deref(), Some("llama"));
+        assert_eq!(chat.fallbacks.len(), 1);
+        assert_eq!(chat.fallbacks[0].url, "https://b.example.com/v1");
+        assert_eq!(chat.split.iter().map(|t| t.weight).collect::<Vec<_>>(), [Some(1), Some(1)]);
+
+        // Upstream credentials never leave the gateway
+        assert!(!serde_json::to_string(&snapshot).unwrap().contains("sk-secret"));
+    }
+
+    #[test]
+    fn test_diff_between_snapshots() {
+        assert!(RoutingDiff::between(&snapshot(1, true), &snapshot(1, true)).is_empty());
+
+        let diff = RoutingDiff::between(&snapshot(1, false), &snapshot(3, true));
+        assert_eq!(diff.added.keys().collect::<Vec<_>>(), ["embed"]);
+        assert!(diff.removed.is_empty());
+        let change = &diff.changed["chat"];
+        assert_eq!(change.before.split[1].weight, Some(1));
+        assert_eq!(change.after.split[1].weight, Some(3));
+
+        let diff = RoutingDiff::between(&snapshot(1, true), &snapshot(1, false));
+        assert_eq!(diff.removed.keys().collect::<Vec<_>>(), ["embed"]);
+        assert!(diff.added.is_empty() && diff.changed.is_empty());
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_each_change_is_notified_once(pool: PgPool) {
+        let received = Received::default();
+        let config = RoutingChangeWebhookConfig {
+            enabled: true,
+            url: Some(spawn_webhook(received.clone()).await),
+            authorization: None,
+            max_retries: 0,
+            request_timeout: Duration::from_secs(5),
+        };
+        // Two replicas seeing the same reloads
+        let replica_a = RoutingChangeNotifier::new(pool.clone(), &config).unwrap();
+        let replica_b = RoutingChangeNotifier::new(pool.clone(), &config).unwrap();
+
+        let diff = replica_a.record(&snapshot(1, false)).await.unwrap().unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert!(replica_b.record(&snapshot(1, false)).await.unwrap().is_none());
+
+        let diff = replica_b.record(&snapshot(2, false)).await.unwrap().unwrap();
+        assert_eq!(diff.changed.keys().collect::<Vec<_>>(), ["chat"]);
+        assert!(replica_a.record(&snapshot(2, false)).await.unwrap().is_none());
+
+        for _ in 0..50 {
+            if received.lock().unwrap().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        // Notifications are sent in the background, so may arrive in either order
+        let mut received = received.lock().unwrap().clone();
+        received.sort_by_key(|n| n["changed_at"].as_str().unwrap().to_string());
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0]["event"], "routing_table.changed");
+        assert!(received[0]["diff"]["added"]["chat"].is_object());
+        assert_eq!(received[1]["diff"]["changed"]["chat"]["after"]["split"][1]["weight"], 2);
+    }
+}
@@ -0,0 +1,203 @@
+//! Scheduled synchronization of inference endpoints.
+//!
+//! Endpoints only synchronize their model list on demand unless they have a sync interval
+//! (`sync_interval_seconds`), as endpoints seeded from the configured model sources do. The
+//! `EndpointSyncScheduler` runs on the leader replica and periodically synchronizes every endpoint
+//! whose interval has passed since its last synchronization. The outcome of the latest
+//! synchronization, scheduled or on demand, is stored on the endpoint and returned with it.
+
+use crate::config::EndpointSyncConfig;
+use crate::db::handlers::InferenceEndpoints;
+use crate::errors::{Error, Result};
+use crate::leader::LeaderFence;
+use crate::sync::endpoint_sync;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Synchronize every endpoint that is due, returning how many were synchronized successfully.
+pub async fn sync_due_endpoints(pool: &PgPool) -> Result<usize> {
+    let due = {
+        let mut conn = pool.acquire().await.map_err(|e| Error::Database(e.into()))?;
+        InferenceEndpoints::new(&mut conn).list_due_for_sync().await?
+    };
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    tracing::debug!("Synchronizing {} endpoints due for scheduled sync", due.len());
+    let mut synced = 0;
+    for endpoint_id in due {
+        match endpoint_sync::synchronize_endpoint(endpoint_id, pool.clone()).await {
+            Ok(result) => {
+                synced += 1;
+                tracing::info!("Scheduled sync of endpoint {}: {} changes made", endpoint_id, result.changes_made);
+            }
+            Err(e) => tracing::warn!("Scheduled sync of endpoint {} failed: {:#}", endpoint_id, e),
+        }
+    }
+    Ok(synced)
+}
+
+/// Background task that synchronizes endpoints on their sync interval.
+///
+/// Like the probe scheduler, this only runs on the leader replica.
+#[derive(Clone)]
+pub struct EndpointSyncScheduler {
+    pool: PgPool,
+    config: EndpointSyncConfig,
+    fence: LeaderFence,
+    handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl EndpointSyncScheduler {
+    pub fn new(pool: PgPool, config: EndpointSyncConfig, fence: LeaderFence) -> Self {
+        Self {
+            pool,
+            config,
+            fence,
+            handle: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Start the sync loop, if enabled and not already running.
+    pub async fn start(&self) {
+        if !self.config.enabled {
+            tracing::info!("Scheduled endpoint sync is disabled");
+            return;
+        }
+
+        let mut handle = self.handle.lock().await;
+        if handle.is_some() {
+            return;
+        }
+
+        let pool = self.pool.clone();
+        let fence = self.fence.clone();
+        let check_interval = self.config.check_interval;
+        *handle = Some(tokio::spawn(async move {
+            loop {
+                // If another replica has taken over as leader, leave syncing to them
+                if !fence.is_current().await {
+                    tracing::warn!("Leadership is stale, stopping scheduled endpoint sync");
+                    break;
+                }
+                let started = Instant::now();
+                let result = sync_due_endpoints(&pool).await;
+                fence.observe_job("endpoint_sync", started.elapsed(), result.is_ok());
+                if let Err(e) = result {
+                    tracing::error!("Scheduled endpoint sync failed: {}", e);
+                }
+                tokio::time::sleep(check_interval).await;
+            }
+        }));
+
+        tracing::info!("Started scheduled endpoint sync (checking every {}s)", check_interval.as_secs());
+    }
+
+    /// Stop the sync loop (called when losing leadership).
+    pub async fn stop(&self) {
+        if let Some(handle) = self.handle.lock().await.take() {
+            handle.abort();
+            tracing::info!("Stopped scheduled endpoint sync");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::handlers::{deployments::DeploymentFilter, Deployments, Repository};
+    use crate::db::models::inference_endpoints::{InferenceEndpointCreateDBRequest, InferenceEndpointDBResponse};
+    use crate::test_utils::get_system_user;
+    use axum::{routing::get, Json, Router};
+
+    /// Serve a fake `/v1/models` endpoint listing one model on a random local port
+    async fn spawn_models_server() -> url::Url {
+        let app = Router::new().route(
+            "/v1/models",
+            get(|| async {
+                Json(serde_json::json!({
+                    "object": "list",
+                    "data": [{"id": "scheduled-model", "object": "model", "created": 0, "owned_by": "test"}]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{addr}/v1").parse().unwrap()
+    }
+
+    async fn create_endpoint(pool: &PgPool, name: &str, url: url::Url, sync_interval_seconds: Option<i32>) -> InferenceEndpointDBResponse {
+        let mut conn = pool.acquire().await.unwrap();
+        let system_user = get_system_user(&mut conn).await;
+        InferenceEndpoints::new(&mut conn)
+            .create(&InferenceEndpointCreateDBRequest {
+                created_by: system_user.id,
+                name: name.to_string(),
+                description: None,
+                url,
+                api_key: None,
+                model_filter: None,
+                auth_header_name: None,
+                auth_header_prefix: None,
+                tls_ca_pem: None,
+                tls_skip_verify: false,
+                tls_min_version: None,
+                sync_interval_seconds,
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn get_endpoint(pool: &PgPool, endpoint: &InferenceEndpointDBResponse) -> InferenceEndpointDBResponse {
+        let mut conn = pool.acquire().await.unwrap();
+        InferenceEndpoints::new(&mut conn).get_by_id(endpoint.id).await.unwrap().unwrap()
+    }
+
+    #[sqlx::test]
+    #[test_log::test]
+    async fn test_sync_due_endpoints(pool: PgPool) {
+        let url = spawn_models_server().await;
+        let scheduled = create_endpoint(&pool, "scheduled", url.clone(), Some(3600)).await;
+        let on_demand = create_endpoint(&pool, "on-demand", url, None).await;
+        let unreachable = create_endpoint(&pool, "unreachable", "http://localhost:1/v1".parse().unwrap(), Some(3600)).await;
+
+        assert_eq!(sync_due_endpoints(&pool).await.unwrap(), 1);
+
+        let endpoint = get_endpoint(&pool, &scheduled).await;
+        assert!(endpoint.last_sync_at.is_some());
+        assert_eq!(endpoint.last_sync_error, None);
+        let mut conn = pool.acquire().await.unwrap();
+        let deployments = Deployments::new(&mut conn)
+            .list(&DeploymentFilter::new(0, 100).with_endpoint(scheduled.id))
+            .await
+            .unwrap();
+        assert_eq!(deployments.len(), 1);
+        assert_eq!(deployments[0].model_name, "scheduled-model");
+
+        // Endpoints without an interval are left alone
+        assert!(get_endpoint(&pool, &on_demand).await.last_sync_at.is_none());
+
+        // Failures are recorded, and the endpoint isn't retried until its interval has passed
+        let endpoint = get_endpoint(&pool, &unreachable).await;
+        assert!(endpoint.last_sync_at.is_some());
+        assert!(endpoint.last_sync_error.is_some());
+        assert_eq!(sync_due_endpoints(&pool).await.unwrap(), 0);
+
+        // Once the interval has passed, the endpoint is synchronized again
+        sqlx::query!(
+            "UPDATE inference_endpoints SET last_sync_at = NOW() - INTERVAL '2 hours' WHERE id = $1",
+            scheduled.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(sync_due_endpoints(&pool).await.unwrap(), 1);
+    }
+}
@@ -24,6 +24,21 @@ use sqlx::{PgConnection, PgPool};
 use tokio_util::sync::DropGuard;
 use uuid::Uuid;
 
+/// A self-signed CA certificate for TLS settings, only ever parsed
+pub const TEST_CA_PEM: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIBhjCCAS2gAwIBAgIUN8Cqm1uDynvvLJ200EjpmSVsuvAwCgYIKoZIzj0EAwIw
+GDEWMBQGA1UEAwwNZHdjdGwgdGVzdCBDQTAgFw0yNjEwMTcxNTEzMzlaGA8yMTI2
+MDkyMzE1MTMzOVowGDEWMBQGA1UEAwwNZHdjdGwgdGVzdCBDQTBZMBMGByqGSM49
+AgEGCCqGSM49AwEHA0IABB78GKtKIf7qQQa4tY7RAEdlaInwAehYu6cBzLL5Eg8E
+U4qMT2QOnMt84GSiwSJKk20J9SmnEu1acEL8Mao9CvujUzBRMB0GA1UdDgQWBBS0
+Lyx+SwVJ6yJTpQGzWBeaHbjbtzAfBgNVHSMEGDAWgBS0Lyx+SwVJ6yJTpQGzWBea
+HbjbtzAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0cAMEQCIFHwx9IdVTNC
+OAWFoPvbCo3GF0yeSE7msSlMrB1B6bTeAiAG3IXIYLjj4eod1rQuqirkjOFvV71K
+Ap71quep7wLSKA==
+-----END CERTIFICATE-----
+";
+
 pub async fn create_test_app(pool: PgPool, enable_sync: bool) -> (TestServer, Option<DropGuard>) {
     let config = create_test_config();
     let (router, onwards_config_sync, drop_guard) = crate::setup_app(pool, config, true).await.expect("Failed to setup test app");
@@ -55,6 +70,8 @@ pub fn create_test_config() -> crate::config::Config {
         admin_email: "admin@test.com".to_string(),
         admin_password: None,
         secret_key: Some("test-secret-key-for-testing-only".to_string()),
+        secret_encryption: Default::default(),
+        secret_managers: Default::default(),
         model_sources: vec![crate::config::ModelSource {
             name: "test".to_string(),
             url: "http://localhost:8081".parse().unwrap(),
@@ -72,9 +89,37 @@ pub fn create_test_config() -> crate::config::Config {
                 ..Default::default()
             },
             security: SecurityConfig::default(),
+            default_groups: Vec::new(),
         },
         enable_metrics: false,
+        metrics: Default::default(),
         enable_request_logging: false,
+        enable_pii_classification: false,
+        endpoint_validation: crate::config::EndpointValidationConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        endpoint_sync: crate::config::EndpointSyncConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        request_mirroring: Default::default(),
+        request_webhook: Default::default(),
+        body_storage: Default::default(),
+        request_log_retention: Default::default(),
+        event_stream: Default::default(),
+        sandbox: Default::default(),
+        routing: Default::default(),
+        load_testing: Default::default(),
+        slos: Default::default(),
+        probes: Default::default(),
+        probe_alerts: Default::default(),
+        moderation: Default::default(),
+        request_limits: Default::default(),
+        admission: Default::default(),
+        federation: Default::default(),
+        logging: Default::default(),
+        error_tracking: Default::default(),
     }
 }
 
@@ -96,6 +141,7 @@ pub async fn create_test_user(pool: &PgPool, role: Role) -> UserResponse {
         roles,
         auth_source: "test".to_string(),
         password_hash: None,
+        attributes: Default::default(),
     };
 
     let user = users_repo.create(&user_create).await.expect("Failed to create test user");
@@ -120,6 +166,7 @@ pub async fn create_test_admin_user(pool: &PgPool, role: Role) -> UserResponse {
         roles,
         auth_source: "test".to_string(),
         password_hash: None,
+        attributes: Default::default(),
     };
 
     let user = users_repo.create(&user_create).await.expect("Failed to create test admin user");
@@ -142,6 +189,7 @@ pub async fn create_test_user_with_roles(pool: &PgPool, roles: Vec<Role>) -> Use
         roles,
         auth_source: "test".to_string(),
         password_hash: None,
+        attributes: Default::default(),
     };
 
     let user = users_repo
@@ -164,6 +212,8 @@ pub async fn create_test_group(pool: &PgPool) -> GroupDBResponse {
         name: format!("test_group_{}", Uuid::new_v4().simple()),
         description: Some("Test group".to_string()),
         created_by: system_user.id,
+        parent_id: None,
+        is_default: false,
     };
 
     group_repo.create(&group_create).await.expect("Failed to create test group")
@@ -203,7 +253,9 @@ pub async fn get_system_user(pool: &mut PgConnection) -> UserResponse {
         updated_at: user.updated_at,
         last_login: None,
         auth_source: user.auth_source,
+        default_model_id: None,
         groups: None, // Groups not included in test users by default
+        attributes: Default::default(),
     }
 }
 
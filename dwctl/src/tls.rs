@@ -0,0 +1,247 @@
+//! TLS settings of connections to inference endpoints.
+//!
+//! Endpoints in private networks often serve certificates signed by an internal CA, and lab
+//! environments sometimes serve self-signed ones. An endpoint can be given a PEM bundle of CA
+//! certificates to trust in addition to the built-in roots, a minimum TLS version, and, for lab
+//! environments only, to skip certificate verification altogether.
+//!
+//! [`EndpointTls::client_builder`] applies the settings to the clients that fetch an endpoint's
+//! models. `sync::onwards_config` publishes them in the [`RoutingTable`] by endpoint URL, and the
+//! [`UpstreamClient`] the AI proxy forwards requests with sends those to an endpoint with TLS
+//! settings through a client configured the same way. Requests to other endpoints go through
+//! onwards' own client, as before.
+
+use crate::db::models::inference_endpoints::InferenceEndpointDBResponse;
+use crate::routing::RoutingTable;
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    response::Response,
+};
+use onwards::client::{HttpClient, HyperClient};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tracing::error;
+use utoipa::ToSchema;
+
+/// Minimum TLS version negotiated with an endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum TlsVersion {
+    #[serde(rename = "1.2")]
+    Tls12,
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+impl TlsVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TlsVersion::Tls12 => "1.2",
+            TlsVersion::Tls13 => "1.3",
+        }
+    }
+
+    pub fn parse(version: &str) -> Option<Self> {
+        match version {
+            "1.2" => Some(TlsVersion::Tls12),
+            "1.3" => Some(TlsVersion::Tls13),
+            _ => None,
+        }
+    }
+}
+
+/// TLS settings of an endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EndpointTls {
+    /// PEM bundle of CA certificates to trust in addition to the built-in roots
+    pub ca_pem: Option<String>,
+    /// Accept any certificate, whoever signed it and whichever host it's for
+    pub skip_verify: bool,
+    pub min_version: Option<TlsVersion>,
+}
+
+impl EndpointTls {
+    pub fn from_endpoint(endpoint: &InferenceEndpointDBResponse) -> Self {
+        Self {
+            ca_pem: endpoint.tls_ca_pem.clone(),
+            skip_verify: endpoint.tls_skip_verify,
+            min_version: endpoint.tls_min_version.as_deref().and_then(TlsVersion::parse),
+        }
+    }
+
+    /// Whether connections to the endpoint are made as to any other
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Parse a PEM bundle of CA certificates, refusing it if it holds none
+    pub fn parse_ca_pem(pem: &str) -> Result<Vec<reqwest::Certificate>, String> {
+        let certificates =
+            reqwest::Certificate::from_pem_bundle(pem.as_bytes()).map_err(|e| format!("Invalid CA certificate bundle: {e}"))?;
+        if certificates.is_empty() {
+            return Err("CA certificate bundle holds no PEM certificates".to_string());
+        }
+        Ok(certificates)
+    }
+
+    /// A client builder making connections with these settings
+    pub fn client_builder(&self) -> Result<reqwest::ClientBuilder, String> {
+        if self.is_default() {
+            return Ok(reqwest::Client::builder());
+        }
+        // The TLS version and trusted roots are only applied consistently with rustls
+        let mut builder = reqwest::Client::builder()
+            .use_rustls_tls()
+            .danger_accept_invalid_certs(self.skip_verify);
+        if let Some(pem) = &self.ca_pem {
+            for certificate in Self::parse_ca_pem(pem)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if let Some(version) = self.min_version {
+            builder = builder.min_tls_version(match version {
+                TlsVersion::Tls12 => reqwest::tls::Version::TLS_1_2,
+                TlsVersion::Tls13 => reqwest::tls::Version::TLS_1_3,
+            });
+        }
+        Ok(builder)
+    }
+
+    /// Check the settings can be applied, so that bad certificates are refused when they're stored
+    pub fn validate(&self) -> Result<(), String> {
+        self.client_builder()?
+            .build()
+            .map(|_| ())
+            .map_err(|e| format!("Invalid TLS settings: {e}"))
+    }
+}
+
+/// The client the AI proxy forwards requests with, applying the TLS settings of the endpoint
+/// each request is forwarded to
+#[derive(Debug, Clone)]
+pub struct UpstreamClient {
+    default: HyperClient,
+    routing: watch::Receiver<RoutingTable>,
+    /// Clients of endpoints with TLS settings, by endpoint URL, with the settings they were built with
+    clients: Arc<Mutex<HashMap<String, (EndpointTls, reqwest::Client)>>>,
+}
+
+impl UpstreamClient {
+    pub fn new(routing: watch::Receiver<RoutingTable>) -> Self {
+        Self {
+            default: onwards::client::create_hyper_client(),
+            routing,
+            clients: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The client for requests to `uri`, if it's served by an endpoint with TLS settings
+    fn client_for(&self, uri: &str) -> Option<reqwest::Client> {
+        let routing = self.routing.borrow();
+        let (url, tls) = routing.endpoint_tls(uri)?;
+
+        let mut clients = self.clients.lock().expect("upstream clients lock poisoned");
+        if let Some((built_with, client)) = clients.get(url) {
+            if built_with == tls {
+                return Some(client.clone());
+            }
+        }
+        match tls.client_builder().and_then(|builder| builder.build().map_err(|e| e.to_string())) {
+            Ok(client) => {
+                clients.insert(url.to_string(), (tls.clone(), client.clone()));
+                Some(client)
+            }
+            Err(e) => {
+                // Settings are validated when they're stored, so this shouldn't happen. Falling
+                // back to the default client still verifies certificates against the built-in roots.
+                error!("Failed to build client for endpoint {}: {}", url, e);
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl HttpClient for UpstreamClient {
+    async fn request(&self, req: Request) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(client) = self.client_for(&req.uri().to_string()) else {
+            return HttpClient::request(&self.default, req).await;
+        };
+
+        // onwards has already buffered the body, so this doesn't hold up streaming
+        let (parts, body) = req.into_parts();
+        let body = to_bytes(body, usize::MAX).await?;
+        let request = reqwest::Request::try_from(axum::http::Request::from_parts(parts, reqwest::Body::from(body)))?;
+        let response = client.execute(request).await?;
+
+        let mut builder = Response::builder().status(response.status()).version(response.version());
+        if let Some(headers) = builder.headers_mut() {
+            headers.extend(response.headers().clone());
+        }
+        Ok(builder.body(Body::from_stream(response.bytes_stream()))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TEST_CA_PEM;
+
+    #[test]
+    fn test_parse_ca_pem() {
+        assert_eq!(EndpointTls::parse_ca_pem(TEST_CA_PEM).unwrap().len(), 1);
+        let bundle = format!("{TEST_CA_PEM}\n{TEST_CA_PEM}");
+        assert_eq!(EndpointTls::parse_ca_pem(&bundle).unwrap().len(), 2);
+
+        assert!(EndpointTls::parse_ca_pem("").is_err());
+        assert!(EndpointTls::parse_ca_pem("not a certificate").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        for tls in [
+            EndpointTls::default(),
+            EndpointTls {
+                ca_pem: Some(TEST_CA_PEM.to_string()),
+                skip_verify: false,
+                min_version: Some(TlsVersion::Tls13),
+            },
+            EndpointTls {
+                ca_pem: None,
+                skip_verify: true,
+                min_version: Some(TlsVersion::Tls12),
+            },
+        ] {
+            tls.validate().unwrap();
+        }
+
+        // PEM framing around something that isn't a certificate
+        let tls = EndpointTls {
+            ca_pem: Some("-----BEGIN CERTIFICATE-----\nbm9wZQ==\n-----END CERTIFICATE-----\n".to_string()),
+            ..Default::default()
+        };
+        assert!(tls.validate().is_err());
+    }
+
+    #[test]
+    fn test_upstream_client_picks_endpoint_by_url() {
+        let tls = EndpointTls {
+            skip_verify: true,
+            ..Default::default()
+        };
+        let mut routing = RoutingTable::default();
+        routing.set_endpoint_tls("https://lab.internal/".to_string(), tls.clone());
+        routing.set_endpoint_tls("https://lab.internal/v2/".to_string(), EndpointTls::default());
+        let (_sender, receiver) = watch::channel(routing);
+        let client = UpstreamClient::new(receiver);
+
+        assert!(client.client_for("https://lab.internal/v1/chat/completions").is_some());
+        assert!(client.client_for("https://api.openai.com/v1/chat/completions").is_none());
+        // Endpoints without settings use the default client, even under another endpoint's URL
+        assert!(client.client_for("https://lab.internal/v2/chat/completions").is_none());
+        assert_eq!(client.clients.lock().unwrap().len(), 1);
+    }
+}
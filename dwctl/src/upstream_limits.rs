@@ -0,0 +1,416 @@
+//! Rate and concurrency limits on the requests forwarded to each inference endpoint.
+//!
+//! Providers rate limit a whole organization's API key, so a burst from one team against a
+//! shared endpoint can get every deployment on it refused upstream. Admins give an endpoint
+//! upstream limits (`PUT /endpoints/{id}/rate-limits`): a request rate, with an optional burst
+//! size, and a cap on the requests in flight. `sync::onwards_config` records the limits of the
+//! endpoint serving every target, including fallbacks, weighted targets, canaries and shadows,
+//! and the [`enforce_upstream_limits`] middleware checks them just before requests are
+//! forwarded. All targets served by an endpoint share its limits.
+//!
+//! The rate behaves as a bucket holding up to `burst_size` requests (the rate itself, rounded
+//! up, when no burst size is set) that refills continuously. A request counts against the
+//! concurrency cap until its response body has been fully sent, so streaming completions hold
+//! their slot throughout. Requests over either limit are refused with a `429` without reaching
+//! the endpoint, which the fallback routing retries against the alias' fallbacks, if any.
+//!
+//! The limits are an endpoint's total across replicas. Rather than coordinating every request,
+//! each replica records a heartbeat in `replica_heartbeats`, counts the replicas heard from
+//! recently, and enforces its even share of the limits: the rate and burst divided by the
+//! number of replicas, and the concurrency cap too, though never below one request. This
+//! assumes the load balancer spreads requests evenly; a replica receiving more than its share
+//! is refused sooner. Until the first heartbeat, or if the database can't be reached, the last
+//! count is used (one replica at startup).
+
+use crate::routing::RoutingTable;
+use crate::types::InferenceEndpointId;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use serde_json::json;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc, Mutex,
+};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// How often each replica records its heartbeat and counts the others
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Replicas not heard from for this long are no longer counted
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Limits on the requests forwarded to an endpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpstreamLimit {
+    /// Requests per second and the number that may be sent at once, if the rate is limited
+    pub rate: Option<(f64, f64)>,
+    pub max_concurrent: Option<u32>,
+}
+
+impl UpstreamLimit {
+    /// The limit for a configured rate, burst size and concurrency cap, or `None` if nothing is
+    /// limited. Non-positive values aren't enforced.
+    pub fn from_config(requests_per_second: Option<f32>, burst_size: Option<i32>, max_concurrent: Option<i32>) -> Option<Self> {
+        let rate = requests_per_second.filter(|rps| *rps > 0.0).map(|rps| {
+            let rps = f64::from(rps);
+            let burst = burst_size.filter(|burst| *burst > 0).map(f64::from).unwrap_or(rps.ceil());
+            (rps, burst.max(1.0))
+        });
+        let max_concurrent = max_concurrent.filter(|max| *max > 0).map(|max| max as u32);
+        if rate.is_none() && max_concurrent.is_none() {
+            return None;
+        }
+        Some(Self { rate, max_concurrent })
+    }
+
+    /// This replica's share of the limit when `replicas` replicas forward requests
+    fn share(&self, replicas: u32) -> Self {
+        let replicas = replicas.max(1);
+        Self {
+            rate: self
+                .rate
+                .map(|(rps, burst)| (rps / f64::from(replicas), (burst / f64::from(replicas)).max(1.0))),
+            max_concurrent: self.max_concurrent.map(|max| (max / replicas).max(1)),
+        }
+    }
+}
+
+/// Why a request wasn't forwarded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Refusal {
+    /// The endpoint's request rate was used up; a request may be sent after the given delay
+    Rate(Duration),
+    /// The endpoint already had its maximum number of requests in flight
+    Concurrency,
+}
+
+impl Refusal {
+    fn code(&self) -> &'static str {
+        match self {
+            Refusal::Rate(_) => "upstream_rate_limited",
+            Refusal::Concurrency => "upstream_concurrency_limited",
+        }
+    }
+
+    /// Whole seconds clients are asked to wait before retrying
+    fn retry_after_secs(&self) -> u64 {
+        match self {
+            Refusal::Rate(wait) => wait.as_secs_f64().ceil().max(1.0) as u64,
+            Refusal::Concurrency => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct EndpointState {
+    tokens: f64,
+    refilled_at: Instant,
+    in_flight: u32,
+}
+
+/// Usage of each endpoint's limits, kept across reloads of the routing table
+#[derive(Debug, Default)]
+struct Usage {
+    endpoints: Mutex<HashMap<InferenceEndpointId, EndpointState>>,
+}
+
+impl Usage {
+    /// Count a request to `endpoint_id` against `limit` at `now`, if it's within it
+    fn acquire(self: &Arc<Self>, endpoint_id: InferenceEndpointId, limit: &UpstreamLimit, now: Instant) -> Result<InFlight, Refusal> {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let state = endpoints.entry(endpoint_id).or_insert_with(|| EndpointState {
+            tokens: limit.rate.map(|(_, burst)| burst).unwrap_or_default(),
+            refilled_at: now,
+            in_flight: 0,
+        });
+
+        if limit.max_concurrent.is_some_and(|max| state.in_flight >= max) {
+            return Err(Refusal::Concurrency);
+        }
+        if let Some((rps, burst)) = limit.rate {
+            let elapsed = now.saturating_duration_since(state.refilled_at).as_secs_f64();
+            state.tokens = (state.tokens + elapsed * rps).min(burst);
+            state.refilled_at = now;
+            if state.tokens < 1.0 {
+                return Err(Refusal::Rate(Duration::from_secs_f64((1.0 - state.tokens) / rps)));
+            }
+            state.tokens -= 1.0;
+        }
+        state.in_flight += 1;
+        Ok(InFlight {
+            usage: self.clone(),
+            endpoint_id,
+        })
+    }
+}
+
+/// A request forwarded to an endpoint, no longer in flight when dropped
+struct InFlight {
+    usage: Arc<Usage>,
+    endpoint_id: InferenceEndpointId,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Some(state) = self.usage.endpoints.lock().unwrap().get_mut(&self.endpoint_id) {
+            state.in_flight = state.in_flight.saturating_sub(1);
+        }
+    }
+}
+
+/// Record this replica's heartbeat, forget replicas that have stopped, and count those left
+async fn heartbeat(pool: &PgPool, replica_id: Uuid) -> Result<u32, sqlx::Error> {
+    let timeout = HEARTBEAT_TIMEOUT.as_secs_f64();
+    sqlx::query!(
+        "INSERT INTO replica_heartbeats (id) VALUES ($1) ON CONFLICT (id) DO UPDATE SET last_seen_at = NOW()",
+        replica_id
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM replica_heartbeats WHERE last_seen_at < NOW() - make_interval(secs => $1)",
+        timeout
+    )
+    .execute(pool)
+    .await?;
+    let replicas = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM replica_heartbeats WHERE last_seen_at >= NOW() - make_interval(secs => $1)"#,
+        timeout
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(u32::try_from(replicas).unwrap_or(u32::MAX).max(1))
+}
+
+/// State for the [`enforce_upstream_limits`] middleware
+#[derive(Debug, Clone)]
+pub struct UpstreamLimits {
+    table: watch::Receiver<RoutingTable>,
+    usage: Arc<Usage>,
+    /// Replicas the limits are shared between
+    replicas: Arc<AtomicU32>,
+}
+
+impl UpstreamLimits {
+    pub fn new(table: watch::Receiver<RoutingTable>) -> Self {
+        Self {
+            table,
+            usage: Arc::default(),
+            replicas: Arc::new(AtomicU32::new(1)),
+        }
+    }
+
+    /// Share the limits with the other replicas using `pool`, counting them in the background
+    pub fn shared_through(self, pool: PgPool) -> Self {
+        let replicas = self.replicas.clone();
+        let replica_id = Uuid::new_v4();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(HEARTBEAT_INTERVAL);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            while !pool.is_closed() {
+                ticker.tick().await;
+                match heartbeat(&pool, replica_id).await {
+                    Ok(count) => replicas.store(count, Ordering::Relaxed),
+                    Err(e) => warn!("Failed to count the replicas sharing upstream limits: {}", e),
+                }
+            }
+        });
+        self
+    }
+}
+
+/// Middleware refusing requests for targets whose endpoint is at its rate or concurrency limit
+pub async fn enforce_upstream_limits(State(limits): State<UpstreamLimits>, request: Request, next: Next) -> Response {
+    if !limits.table.borrow().has_upstream_limits() {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Failed to read request body").into_response(),
+    };
+    let request = Request::from_parts(parts, Body::from(body.clone()));
+
+    let Ok(model) = onwards::extract_model_from_request(request.headers(), &body) else {
+        return next.run(request).await;
+    };
+    let Some((endpoint_id, limit)) = limits.table.borrow().upstream_limit(&model) else {
+        return next.run(request).await;
+    };
+
+    let limit = limit.share(limits.replicas.load(Ordering::Relaxed));
+    let in_flight = match limits.usage.acquire(endpoint_id, &limit, Instant::now()) {
+        Ok(in_flight) => in_flight,
+        Err(refusal) => {
+            debug!(
+                "Refused request for '{}' at the limits of endpoint {}: {:?}",
+                model, endpoint_id, refusal
+            );
+            return refused_response(refusal);
+        }
+    };
+
+    // Keep the request in flight until the response body has been sent
+    let (parts, body) = next.run(request).await.into_parts();
+    let body = body.into_data_stream().map(move |chunk| {
+        let _in_flight = &in_flight;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+fn refused_response(refusal: Refusal) -> Response {
+    let message = match refusal {
+        Refusal::Rate(_) => "The upstream endpoint for this model is at its request rate limit, please retry later",
+        Refusal::Concurrency => "The upstream endpoint for this model is at its concurrent request limit, please retry later",
+    };
+    let error = json!({"message": message, "type": "rate_limit_error", "param": null, "code": refusal.code()});
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(json!({ "error": error }))).into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(refusal.retry_after_secs()));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware::from_fn_with_state, routing::post, Router};
+    use axum_test::TestServer;
+    use serde_json::Value;
+
+    #[test]
+    fn test_upstream_limit_from_config() {
+        assert_eq!(UpstreamLimit::from_config(None, Some(10), None), None);
+        assert_eq!(UpstreamLimit::from_config(Some(0.0), None, Some(0)), None);
+        assert_eq!(
+            UpstreamLimit::from_config(Some(2.5), None, None),
+            Some(UpstreamLimit {
+                rate: Some((2.5, 3.0)),
+                max_concurrent: None
+            })
+        );
+        assert_eq!(
+            UpstreamLimit::from_config(Some(0.5), Some(4), Some(8)),
+            Some(UpstreamLimit {
+                rate: Some((0.5, 4.0)),
+                max_concurrent: Some(8)
+            })
+        );
+    }
+
+    #[test]
+    fn test_limits_are_shared_between_replicas() {
+        let limit = UpstreamLimit::from_config(Some(10.0), Some(20), Some(8)).unwrap();
+        assert_eq!(limit.share(1), limit);
+        assert_eq!(
+            limit.share(4),
+            UpstreamLimit {
+                rate: Some((2.5, 5.0)),
+                max_concurrent: Some(2)
+            }
+        );
+        // Every replica can still send a request
+        assert_eq!(
+            limit.share(32),
+            UpstreamLimit {
+                rate: Some((0.3125, 1.0)),
+                max_concurrent: Some(1)
+            }
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_heartbeats_count_live_replicas(pool: PgPool) {
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(heartbeat(&pool, first).await.unwrap(), 1);
+        assert_eq!(heartbeat(&pool, second).await.unwrap(), 2);
+        assert_eq!(heartbeat(&pool, first).await.unwrap(), 2);
+
+        // A replica that stopped is forgotten
+        sqlx::query("UPDATE replica_heartbeats SET last_seen_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+            .bind(second)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(heartbeat(&pool, first).await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_rate_refills_over_time() {
+        let usage = Arc::new(Usage::default());
+        let endpoint = Uuid::new_v4();
+        let limit = UpstreamLimit::from_config(Some(2.0), Some(3), None).unwrap();
+        let start = Instant::now();
+
+        // The burst goes through at once, then requests wait for the bucket to refill
+        for _ in 0..3 {
+            usage.acquire(endpoint, &limit, start).unwrap();
+        }
+        match usage.acquire(endpoint, &limit, start) {
+            Err(Refusal::Rate(wait)) => assert_eq!(wait, Duration::from_millis(500)),
+            other => panic!("expected the rate limit to trip, got {:?}", other.err()),
+        }
+        assert!(usage.acquire(endpoint, &limit, start + Duration::from_millis(500)).is_ok());
+        assert!(usage.acquire(endpoint, &limit, start + Duration::from_millis(600)).is_err());
+
+        // Other endpoints have their own bucket
+        assert!(usage.acquire(Uuid::new_v4(), &limit, start).is_ok());
+    }
+
+    #[test]
+    fn test_concurrency_is_released_when_requests_finish() {
+        let usage = Arc::new(Usage::default());
+        let endpoint = Uuid::new_v4();
+        let limit = UpstreamLimit::from_config(None, None, Some(2)).unwrap();
+        let now = Instant::now();
+
+        let first = usage.acquire(endpoint, &limit, now).unwrap();
+        let _second = usage.acquire(endpoint, &limit, now).unwrap();
+        assert_eq!(usage.acquire(endpoint, &limit, now).err(), Some(Refusal::Concurrency));
+
+        drop(first);
+        assert!(usage.acquire(endpoint, &limit, now).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_enforce_upstream_limits_middleware() {
+        let endpoint = Uuid::new_v4();
+        let mut table = RoutingTable::default();
+        let limit = UpstreamLimit::from_config(Some(1.0), Some(2), None).unwrap();
+        table.set_upstream_limit("alias".to_string(), endpoint, limit);
+        table.set_upstream_limit("other::fallback-1".to_string(), endpoint, limit);
+        let (_sender, receiver) = watch::channel(table);
+
+        let upstream = Router::new()
+            .route("/chat/completions", post(|| async { Json(json!({"ok": true})) }))
+            .layer(from_fn_with_state(UpstreamLimits::new(receiver), enforce_upstream_limits));
+        let server = TestServer::new(upstream).unwrap();
+        let request = |model: &str| server.post("/chat/completions").json(&json!({"model": model, "messages": []}));
+
+        // Targets served by the same endpoint share its bucket
+        request("alias").await.assert_status_ok();
+        request("other::fallback-1").await.assert_status_ok();
+        let response = request("alias").await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.header(header::RETRY_AFTER), "1");
+        let body: Value = response.json();
+        assert_eq!(body["error"]["code"], "upstream_rate_limited");
+
+        // Targets on endpoints without limits aren't affected
+        request("unlimited").await.assert_status_ok();
+    }
+}